@@ -0,0 +1,491 @@
+//! Approval policy: which tool calls ask the user before they run
+//!
+//! A call is checked against, in order: the tools the current profile
+//! (HOMEGPT_PROFILE) always asks about, then `approval.rules` on the call's
+//! arguments (the first rule that matches decides), then `approval.tools`.
+//! Anything else runs without asking. Calls that asked, and calls a rule
+//! allowed, are appended to `~/.homegpt/approvals.jsonl`.
+//!
+//! With `files.on_violation = "ask"`, a file tool call outside the
+//! [`FilePolicy`] asks before any of these are checked.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Utc;
+use serde_json::{json, Value};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use tracing::warn;
+
+use super::file_policy::{Access, FilePolicy};
+use super::get_state_dir;
+use super::tools::extract_tool_detail;
+use crate::config::{ApprovalRuleConfig, Config};
+
+const AUDIT_FILE: &str = "approvals.jsonl";
+
+/// What the policy decided for one tool call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Approval {
+    /// Run it; with the rule that allowed it, if one did
+    Allow(Option<String>),
+    /// Ask the user first, and why
+    Ask(String),
+}
+
+impl Approval {
+    pub fn asks(&self) -> bool {
+        matches!(self, Self::Ask(_))
+    }
+
+    pub fn reason(&self) -> Option<&str> {
+        match self {
+            Self::Allow(reason) => reason.as_deref(),
+            Self::Ask(reason) => Some(reason),
+        }
+    }
+}
+
+/// How a decision ended, for the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApprovalOutcome {
+    /// Ran without asking
+    Allowed,
+    Approved,
+    Denied,
+    /// Asked, with the answer given elsewhere (the desktop app)
+    Requested,
+    /// Asked remotely and nobody answered in time
+    TimedOut,
+}
+
+impl ApprovalOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Allowed => "allowed",
+            Self::Approved => "approved",
+            Self::Denied => "denied",
+            Self::Requested => "requested",
+            Self::TimedOut => "timed_out",
+        }
+    }
+}
+
+/// Someone who answers approval requests while the turn waits, such as
+/// [`super::RemoteApprover`]
+#[async_trait]
+pub trait Approver: Send + Sync {
+    /// `Approved`, `Denied` or `TimedOut` for calling `tool` with
+    /// `arguments` (JSON), asked because of `reason`
+    async fn approve(&self, tool: &str, arguments: &str, reason: &str) -> ApprovalOutcome;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Ask,
+    Allow,
+}
+
+/// One of `approval.rules`
+#[derive(Debug, Clone)]
+struct Rule {
+    /// 1-based, as listed in config
+    number: usize,
+    tools: Vec<String>,
+    action: Action,
+    argument: String,
+    /// As written, for messages
+    path_names: Vec<String>,
+    paths: Vec<PathBuf>,
+    outside_workspace: bool,
+    contains: Vec<String>,
+}
+
+impl Rule {
+    fn from_config(number: usize, rule: &ApprovalRuleConfig, workspace: &Path) -> Result<Self> {
+        let action = match rule.action.as_str() {
+            "ask" => Action::Ask,
+            "allow" => Action::Allow,
+            other => anyhow::bail!(
+                "Invalid approval.rules #{}: action '{}' (expected ask or allow)",
+                number,
+                other
+            ),
+        };
+        if rule.tools.is_empty() {
+            anyhow::bail!("Invalid approval.rules #{}: no tools", number);
+        }
+        let paths = rule
+            .paths
+            .iter()
+            .map(|p| normalize(&workspace.join(shellexpand::tilde(p).as_ref())))
+            .collect();
+        Ok(Self {
+            number,
+            tools: rule.tools.clone(),
+            action,
+            argument: rule.argument.clone(),
+            path_names: rule.paths.clone(),
+            paths,
+            outside_workspace: rule.outside_workspace,
+            contains: rule.contains.clone(),
+        })
+    }
+
+    fn applies_to(&self, tool: &str) -> bool {
+        self.tools.iter().any(|t| t == tool || t == "*")
+    }
+
+    fn matches(&self, tool: &str, args: &Value, workspace: &Path) -> bool {
+        if !self.applies_to(tool) {
+            return false;
+        }
+        let value = args.get(&self.argument).and_then(Value::as_str);
+        if !self.contains.is_empty()
+            && !value.is_some_and(|v| self.contains.iter().any(|c| v.contains(c.as_str())))
+        {
+            return false;
+        }
+        if self.paths.is_empty() && !self.outside_workspace {
+            return true;
+        }
+        let Some(path) = value.map(resolve) else {
+            return false;
+        };
+        (self.paths.is_empty() || self.paths.iter().any(|p| path.starts_with(p)))
+            && !(self.outside_workspace && path.starts_with(workspace))
+    }
+
+    fn describe(&self) -> String {
+        let mut conditions = Vec::new();
+        if !self.path_names.is_empty() {
+            conditions.push(format!(
+                "{} under {}",
+                self.argument,
+                self.path_names.join(" or ")
+            ));
+        }
+        if self.outside_workspace {
+            conditions.push(format!("{} outside the workspace", self.argument));
+        }
+        if !self.contains.is_empty() {
+            let quoted: Vec<String> = self.contains.iter().map(|c| format!("{:?}", c)).collect();
+            conditions.push(format!(
+                "{} contains {}",
+                self.argument,
+                quoted.join(" or ")
+            ));
+        }
+        if conditions.is_empty() {
+            conditions.push("every call".to_string());
+        }
+        format!("approval rule {}: {}", self.number, conditions.join(", "))
+    }
+}
+
+/// Decides which tool calls need the user's approval
+#[derive(Debug, Clone, Default)]
+pub struct ApprovalPolicy {
+    tools: Vec<String>,
+    profile: Option<String>,
+    /// Tools the profile always asks about; "*" = every tool
+    profile_tools: Vec<String>,
+    rules: Vec<Rule>,
+    /// `None` = file tools aren't checked
+    files: Option<FilePolicy>,
+    workspace: PathBuf,
+    /// `None` = don't record decisions
+    audit_path: Option<PathBuf>,
+}
+
+impl ApprovalPolicy {
+    /// Policy for the current profile (HOMEGPT_PROFILE)
+    pub fn from_config(config: &Config) -> Result<Self> {
+        Self::for_profile(config, Config::profile().as_deref())
+    }
+
+    pub fn for_profile(config: &Config, profile: Option<&str>) -> Result<Self> {
+        let approval = &config.approval;
+        let workspace = normalize(&config.workspace_path());
+        let rules = approval
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| Rule::from_config(i + 1, rule, &workspace))
+            .collect::<Result<_>>()?;
+        let profile_tools = profile
+            .and_then(|p| approval.profiles.get(p))
+            .cloned()
+            .unwrap_or_default();
+        let mut tools = approval.tools.clone();
+        tools.extend(config.tools.require_approval.iter().cloned());
+        let audit_path = if approval.audit {
+            Some(get_state_dir()?.join(AUDIT_FILE))
+        } else {
+            None
+        };
+
+        Ok(Self {
+            tools,
+            profile: profile.map(String::from),
+            profile_tools,
+            rules,
+            files: Some(FilePolicy::from_config(config)?),
+            workspace,
+            audit_path,
+        })
+    }
+
+    /// Record decisions in `path` instead (`None` = not at all)
+    pub fn with_audit_path(mut self, path: Option<PathBuf>) -> Self {
+        self.audit_path = path;
+        self
+    }
+
+    /// Whether calling `tool` with `arguments` (JSON) needs approval
+    pub fn check(&self, tool: &str, arguments: &str) -> Approval {
+        if let Some(reason) = self
+            .files
+            .as_ref()
+            .filter(|files| files.asks())
+            .and_then(|files| files.violation(tool, arguments))
+        {
+            return Approval::Ask(reason);
+        }
+        if let Some(ref profile) = self.profile {
+            if self.profile_tools.iter().any(|t| t == "*") {
+                return Approval::Ask(format!("the {} profile asks before every tool", profile));
+            }
+            if self.profile_tools.iter().any(|t| t == tool) {
+                return Approval::Ask(format!("the {} profile asks before {}", profile, tool));
+            }
+        }
+
+        let args: Value = serde_json::from_str(arguments).unwrap_or(Value::Null);
+        if let Some(rule) = self
+            .rules
+            .iter()
+            .find(|rule| rule.matches(tool, &args, &self.workspace))
+        {
+            return match rule.action {
+                Action::Ask => Approval::Ask(rule.describe()),
+                Action::Allow => Approval::Allow(Some(rule.describe())),
+            };
+        }
+
+        if self.tools.iter().any(|t| t == tool) {
+            return Approval::Ask(format!("{} is in approval.tools", tool));
+        }
+        Approval::Allow(None)
+    }
+
+    /// Why the file policy doesn't allow this call, if it's a file tool's
+    pub fn file_violation(&self, tool: &str, arguments: &str) -> Option<String> {
+        self.files.as_ref()?.violation(tool, arguments)
+    }
+
+    /// Whether file tool calls outside the file policy ask rather than fail
+    pub fn asks_on_file_violation(&self) -> bool {
+        self.files.as_ref().is_some_and(FilePolicy::asks)
+    }
+
+    /// Whether some calls to `tool` ask first, for tool listings
+    pub fn may_ask(&self, tool: &str) -> bool {
+        (self.asks_on_file_violation() && Access::of_tool(tool).is_some())
+            || self.profile_tools.iter().any(|t| t == "*" || t == tool)
+            || self.tools.iter().any(|t| t == tool)
+            || self
+                .rules
+                .iter()
+                .any(|rule| rule.action == Action::Ask && rule.applies_to(tool))
+    }
+
+    /// Append a decision to the audit log. Calls no rule or list mentions
+    /// aren't recorded.
+    pub fn record(
+        &self,
+        session: &str,
+        tool: &str,
+        arguments: &str,
+        approval: &Approval,
+        outcome: ApprovalOutcome,
+    ) {
+        let Some(ref path) = self.audit_path else {
+            return;
+        };
+        if *approval == Approval::Allow(None) {
+            return;
+        }
+
+        let entry = json!({
+            "timestamp": Utc::now().to_rfc3339(),
+            "session": session,
+            "profile": self.profile,
+            "tool": tool,
+            "detail": extract_tool_detail(tool, arguments),
+            "reason": approval.reason(),
+            "outcome": outcome.as_str(),
+        });
+        let written = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| OpenOptions::new().create(true).append(true).open(path))
+            .and_then(|mut file| writeln!(file, "{}", entry));
+        if let Err(e) = written {
+            warn!("Failed to write {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Absolute form of a tool's path argument, the way the file tools open it
+pub(super) fn resolve(path: &str) -> PathBuf {
+    let path = PathBuf::from(shellexpand::tilde(path).as_ref());
+    let path = if path.is_relative() {
+        std::env::current_dir().unwrap_or_default().join(path)
+    } else {
+        path
+    };
+    normalize(&path)
+}
+
+/// Drop `.` and resolve `..` without touching the filesystem, so
+/// "memory/food/../../.ssh" doesn't count as inside memory/food
+pub(super) fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use tempfile::TempDir;
+
+    fn rule(tools: &[&str], action: &str) -> ApprovalRuleConfig {
+        ApprovalRuleConfig {
+            tools: tools.iter().map(|t| t.to_string()).collect(),
+            action: action.to_string(),
+            argument: "path".to_string(),
+            paths: Vec::new(),
+            outside_workspace: false,
+            contains: Vec::new(),
+        }
+    }
+
+    fn policy(workspace: &Path, profile: Option<&str>) -> ApprovalPolicy {
+        let mut config = Config::default();
+        config.memory.workspace = workspace.display().to_string();
+        config.approval.tools = vec!["write_file".to_string()];
+        config.approval.profiles = BTreeMap::from([("kids".to_string(), vec!["*".to_string()])]);
+
+        let mut food = rule(&["write_file", "edit_file"], "allow");
+        food.paths = vec!["memory/food".to_string()];
+        let mut outside = rule(&["write_file", "edit_file"], "ask");
+        outside.outside_workspace = true;
+        let mut sudo = rule(&["bash"], "ask");
+        sudo.argument = "command".to_string();
+        sudo.contains = vec!["sudo".to_string()];
+        config.approval.rules = vec![food, outside, sudo];
+
+        ApprovalPolicy::for_profile(&config, profile)
+            .unwrap()
+            .with_audit_path(None)
+    }
+
+    fn args(key: &str, value: &Path) -> String {
+        json!({ key: value }).to_string()
+    }
+
+    #[test]
+    fn test_rules_decide_in_order() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+        let policy = policy(workspace, None);
+
+        let food = workspace.join("memory/food/tacos.md");
+        assert_eq!(
+            policy.check("write_file", &args("path", &food)),
+            Approval::Allow(Some("approval rule 1: path under memory/food".to_string()))
+        );
+        let escape = workspace.join("memory/food/../../../.ssh/config");
+        assert_eq!(
+            policy.check("write_file", &args("path", &escape)),
+            Approval::Ask("approval rule 2: path outside the workspace".to_string())
+        );
+        // Inside the workspace but not under memory/food: the tool list
+        assert_eq!(
+            policy.check("write_file", &args("path", &workspace.join("MEMORY.md"))),
+            Approval::Ask("write_file is in approval.tools".to_string())
+        );
+        assert!(policy.check("bash", r#"{"command": "sudo reboot"}"#).asks());
+        assert_eq!(
+            policy.check("bash", r#"{"command": "ls"}"#),
+            Approval::Allow(None)
+        );
+        assert!(policy.may_ask("edit_file"));
+        assert!(!policy.may_ask("read_file"));
+    }
+
+    #[test]
+    fn test_profile_asks_first() {
+        let temp = TempDir::new().unwrap();
+        let policy = policy(temp.path(), Some("kids"));
+        let food = temp.path().join("memory/food/tacos.md");
+
+        assert_eq!(
+            policy.check("write_file", &args("path", &food)),
+            Approval::Ask("the kids profile asks before every tool".to_string())
+        );
+        assert!(policy.may_ask("read_file"));
+    }
+
+    #[test]
+    fn test_audit_log() {
+        let temp = TempDir::new().unwrap();
+        let log = temp.path().join(AUDIT_FILE);
+        let policy = policy(temp.path(), None).with_audit_path(Some(log.clone()));
+
+        let ls = r#"{"command": "ls"}"#;
+        policy.record(
+            "s1",
+            "bash",
+            ls,
+            &policy.check("bash", ls),
+            ApprovalOutcome::Allowed,
+        );
+        let sudo = r#"{"command": "sudo reboot"}"#;
+        let approval = policy.check("bash", sudo);
+        policy.record("s1", "bash", sudo, &approval, ApprovalOutcome::Denied);
+
+        let content = fs::read_to_string(&log).unwrap();
+        let lines: Vec<Value> = content
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["detail"], "sudo reboot");
+        assert_eq!(lines[0]["outcome"], "denied");
+        assert_eq!(
+            lines[0]["reason"],
+            "approval rule 3: command contains \"sudo\""
+        );
+    }
+
+    #[test]
+    fn test_invalid_rule() {
+        let mut config = Config::default();
+        config.approval.rules = vec![rule(&["bash"], "maybe")];
+        let err = ApprovalPolicy::for_profile(&config, None).unwrap_err();
+        assert!(err.to_string().contains("action 'maybe'"));
+    }
+}
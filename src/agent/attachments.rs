@@ -0,0 +1,176 @@
+//! File attachments for chat turns (CLI `/attach`, desktop drag-and-drop).
+//!
+//! Images are sent as image parts; PDF and DOCX text is extracted like
+//! memory documents, and other files are read as text. Text attachments are
+//! appended to the user message as fenced blocks.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+
+use super::providers::ImageAttachment;
+use crate::memory::documents;
+
+/// Longest text attachment sent with a message; the rest is cut off
+const MAX_TEXT_CHARS: usize = 60_000;
+
+/// Separates the typed message from appended text attachments
+const ATTACHED_FILES_HEADER: &str = "\n\n---\n\n**Attached files:**\n";
+
+/// A file attached to the next message
+#[derive(Debug, Clone)]
+pub enum Attachment {
+    Text {
+        name: String,
+        path: PathBuf,
+        content: String,
+    },
+    Image {
+        name: String,
+        data: ImageAttachment,
+    },
+}
+
+impl Attachment {
+    /// Read a file as an image, document text or plain text based on its extension
+    pub fn load(path: &Path) -> Result<Self> {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.display().to_string());
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase());
+
+        if let Some(media_type) = ext.as_deref().and_then(image_media_type) {
+            let bytes = std::fs::read(path)
+                .with_context(|| format!("Failed to read image {}", path.display()))?;
+            return Ok(Self::Image {
+                name,
+                data: ImageAttachment {
+                    data: STANDARD.encode(&bytes),
+                    media_type: media_type.to_string(),
+                },
+            });
+        }
+
+        let content = documents::read_text(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        if content.trim().is_empty() {
+            anyhow::bail!(
+                "No text found in {} (scanned documents need OCR first)",
+                name
+            );
+        }
+
+        Ok(Self::Text {
+            name,
+            path: path.to_path_buf(),
+            content,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Text { name, .. } | Self::Image { name, .. } => name,
+        }
+    }
+
+    /// One-line description, e.g. "[text] quote.pdf (4120 bytes)"
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Text { name, content, .. } => {
+                format!("[text] {} ({} bytes)", name, content.len())
+            }
+            Self::Image { name, data } => format!(
+                "[image] {} ({}, {} bytes encoded)",
+                name,
+                data.media_type,
+                data.data.len()
+            ),
+        }
+    }
+}
+
+fn image_media_type(ext: &str) -> Option<&'static str> {
+    match ext {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
+
+/// Append text attachments to `message` and split out the images
+pub fn attach_to_message(
+    message: &str,
+    attachments: Vec<Attachment>,
+) -> (String, Vec<ImageAttachment>) {
+    let mut message = message.to_string();
+    let mut texts = Vec::new();
+    let mut images = Vec::new();
+    for attachment in attachments {
+        match attachment {
+            Attachment::Text { name, content, .. } => texts.push((name, content)),
+            Attachment::Image { data, .. } => images.push(data),
+        }
+    }
+
+    if !texts.is_empty() {
+        message.push_str(ATTACHED_FILES_HEADER);
+        for (name, content) in &texts {
+            let content = match content.char_indices().nth(MAX_TEXT_CHARS) {
+                Some((end, _)) => format!("{}\n[... truncated]", &content[..end]),
+                None => content.clone(),
+            };
+            message.push_str(&format!("\n### {}\n```\n{}\n```\n", name, content));
+        }
+    }
+    (message, images)
+}
+
+/// Split a sent message into the typed text and the appended attachments
+/// (empty if there are none)
+pub fn split_attachments(message: &str) -> (&str, &str) {
+    match message.find(ATTACHED_FILES_HEADER) {
+        Some(i) => message.split_at(i),
+        None => (message, ""),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attach_to_message() {
+        let attachments = vec![
+            Attachment::Text {
+                name: "quote.csv".to_string(),
+                path: PathBuf::from("/tmp/quote.csv"),
+                content: "item,price\nroof,4200".to_string(),
+            },
+            Attachment::Image {
+                name: "leak.jpg".to_string(),
+                data: ImageAttachment {
+                    data: "AAAA".to_string(),
+                    media_type: "image/jpeg".to_string(),
+                },
+            },
+        ];
+
+        let (message, images) = attach_to_message("Is this a fair price?", attachments);
+        assert!(message.starts_with("Is this a fair price?"));
+        assert!(message.contains("### quote.csv\n```\nitem,price\nroof,4200\n```"));
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].media_type, "image/jpeg");
+
+        let (typed, attached) = split_attachments(&message);
+        assert_eq!(typed, "Is this a fair price?");
+        assert!(attached.contains("### quote.csv"));
+        assert_eq!(split_attachments("hi"), ("hi", ""));
+    }
+}
@@ -0,0 +1,578 @@
+//! Model capabilities: what a model can do, and working around what it can't
+//!
+//! With `agent.probe_capabilities`, the first turn on a model sends it a few
+//! tiny requests to find out whether it calls tools, sees images and answers
+//! in plain JSON, and asks the provider for its context window. The results
+//! are kept in `~/.homegpt/capabilities.json` and checked again after a
+//! month. A model that can't call tools or see images is sent requests
+//! through a [`DegradedProvider`]: tools are described in the system prompt
+//! and called with ReAct-style `Action:` lines, and images are replaced by a
+//! note asking the model to tell the user it can't see them. Claude CLI
+//! sessions, replays and recordings aren't probed.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use tracing::warn;
+
+use super::providers::{
+    ApiError, ChatProvider, LLMResponse, LLMResponseContent, Message, Role, StreamChunk,
+    StreamResult, ToolCall, ToolSchema,
+};
+use super::replay::RECORD_ENV;
+use super::session::get_state_dir;
+
+/// Days before a model's capabilities are checked again
+const RECHECK_DAYS: i64 = 30;
+
+/// An 8x8 red PNG, for the vision probe
+const PROBE_IMAGE: &str = "iVBORw0KGgoAAAANSUhEUgAAAAgAAAAICAIAAABLbSncAAAAEUlEQVR42mP4z8CAFTEMLQkAKP8/wc53yE8AAAAASUVORK5CYII=";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Capabilities {
+    /// Calls tools through the API
+    pub tools: bool,
+    /// Sees attached images
+    pub vision: bool,
+    /// Answers with bare JSON when asked
+    pub json: bool,
+    /// Context window in tokens, if the provider says
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_window: Option<usize>,
+    pub checked: DateTime<Utc>,
+}
+
+impl Capabilities {
+    /// Whether requests to the model need working around
+    pub fn degraded(&self) -> bool {
+        !self.tools || !self.vision
+    }
+
+    /// What the model can't do, for `homegpt doctor`
+    pub fn limits(&self) -> Vec<String> {
+        let mut limits = Vec::new();
+        if !self.tools {
+            limits.push("no tool calling (tools used through the prompt)".to_string());
+        }
+        if !self.vision {
+            limits.push("no images".to_string());
+        }
+        if !self.json {
+            limits.push("unreliable JSON".to_string());
+        }
+        if let Some(window) = self.context_window {
+            limits.push(format!("{}-token context", window));
+        }
+        limits
+    }
+}
+
+fn cache_path() -> Result<PathBuf> {
+    Ok(get_state_dir()?.join("capabilities.json"))
+}
+
+fn load_cache() -> BTreeMap<String, Capabilities> {
+    let Ok(path) = cache_path() else {
+        return BTreeMap::new();
+    };
+    match fs::read_to_string(&path) {
+        Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+            warn!("Ignoring unreadable {}: {}", path.display(), e);
+            BTreeMap::new()
+        }),
+        Err(_) => BTreeMap::new(),
+    }
+}
+
+/// `model`'s capabilities from an earlier probe, unless they're due to be
+/// checked again
+pub fn cached_capabilities(model: &str) -> Option<Capabilities> {
+    load_cache()
+        .remove(model)
+        .filter(|c| Utc::now() - c.checked < Duration::days(RECHECK_DAYS))
+}
+
+/// Remember `model`'s capabilities
+pub fn save_capabilities(model: &str, capabilities: &Capabilities) -> Result<()> {
+    let mut cache = load_cache();
+    cache.insert(model.to_string(), capabilities.clone());
+    let path = cache_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(&cache)?)?;
+    Ok(())
+}
+
+/// Whether `model` may be sent probe requests: not Claude CLI, whose session
+/// would keep them, and not while recording or replaying traffic
+pub fn probeable(model: &str) -> bool {
+    let recording = std::env::var(RECORD_ENV).is_ok_and(|path| !path.is_empty());
+    !recording && !model.starts_with("claude-cli/") && !model.starts_with("replay/")
+}
+
+/// Find out what `provider`'s model can do. Fails if the model can't be
+/// reached, so a network problem isn't remembered as a missing capability.
+pub async fn probe(provider: &dyn ChatProvider) -> Result<Capabilities> {
+    let json = probe_json(provider).await?;
+    let tools = probe_tools(provider).await?;
+    let vision = probe_vision(provider).await?;
+    Ok(Capabilities {
+        tools,
+        vision,
+        json,
+        context_window: provider.context_window().await,
+        checked: Utc::now(),
+    })
+}
+
+async fn probe_json(provider: &dyn ChatProvider) -> Result<bool> {
+    let prompt = r#"Reply with only this JSON object, with the sum filled in: {"sum": <2 + 3>}"#;
+    let response = provider.chat(&[user(prompt)], None).await?;
+    Ok(match response.content {
+        LLMResponseContent::Text(text) => json_object(&text).is_some(),
+        LLMResponseContent::ToolCalls(_) => false,
+    })
+}
+
+async fn probe_tools(provider: &dyn ChatProvider) -> Result<bool> {
+    let tools = [ToolSchema {
+        name: "clock".to_string(),
+        description: "Get the current time".to_string(),
+        parameters: json!({ "type": "object", "properties": {} }),
+    }];
+    let prompt = "What time is it? Use the clock tool.";
+    match provider.chat(&[user(prompt)], Some(&tools)).await {
+        Ok(response) => Ok(matches!(response.content, LLMResponseContent::ToolCalls(_))),
+        Err(e) => rejected(e),
+    }
+}
+
+async fn probe_vision(provider: &dyn ChatProvider) -> Result<bool> {
+    let mut message = user("What colour is this image? Answer with one word.");
+    message.images.push(super::providers::ImageAttachment {
+        data: PROBE_IMAGE.to_string(),
+        media_type: "image/png".to_string(),
+    });
+    match provider.chat(&[message], None).await {
+        Ok(response) => Ok(match response.content {
+            LLMResponseContent::Text(text) => text.to_lowercase().contains("red"),
+            LLMResponseContent::ToolCalls(_) => false,
+        }),
+        Err(e) => rejected(e),
+    }
+}
+
+/// A probe request failed: the model rejected it (so can't do what it
+/// asked), unless the API was rate limited or down
+fn rejected(error: anyhow::Error) -> Result<bool> {
+    match error.downcast_ref::<ApiError>() {
+        Some(e) if e.status() == 429 || e.status() >= 500 => Err(error),
+        _ => Ok(false),
+    }
+}
+
+fn user(content: &str) -> Message {
+    Message {
+        role: Role::User,
+        content: content.to_string(),
+        tool_calls: None,
+        tool_call_id: None,
+        images: Vec::new(),
+    }
+}
+
+/// `text` as a JSON object, allowing a Markdown code fence around it
+fn json_object(text: &str) -> Option<Value> {
+    let text = text.trim();
+    let text = text
+        .strip_prefix("```json")
+        .or_else(|| text.strip_prefix("```"))
+        .and_then(|t| t.strip_suffix("```"))
+        .unwrap_or(text);
+    serde_json::from_str::<Value>(text.trim())
+        .ok()
+        .filter(Value::is_object)
+}
+
+/// A provider for a model that can't call tools or see images
+pub struct DegradedProvider {
+    inner: Box<dyn ChatProvider>,
+    capabilities: Capabilities,
+}
+
+impl DegradedProvider {
+    pub fn new(inner: Box<dyn ChatProvider>, capabilities: Capabilities) -> Self {
+        Self {
+            inner,
+            capabilities,
+        }
+    }
+
+    /// Whether tools go through the prompt for this request
+    fn react(&self, tools: Option<&[ToolSchema]>) -> bool {
+        !self.capabilities.tools && tools.is_some_and(|t| !t.is_empty())
+    }
+
+    /// `messages` as the model can take them
+    fn prepare(&self, messages: &[Message], tools: Option<&[ToolSchema]>) -> Vec<Message> {
+        let mut messages = messages.to_vec();
+        if !self.capabilities.vision {
+            if messages.last().is_some_and(|m| !m.images.is_empty()) {
+                warn!("The chat model can't see images; set agent.vision_model for one that can");
+            }
+            strip_images(&mut messages);
+        }
+        match tools {
+            Some(tools) if self.react(Some(tools)) => to_react(&messages, tools),
+            _ => messages,
+        }
+    }
+}
+
+/// Replace images with a note for the model to pass on
+fn strip_images(messages: &mut [Message]) {
+    for message in messages.iter_mut().filter(|m| !m.images.is_empty()) {
+        let count = message.images.len();
+        message.images.clear();
+        message.content.push_str(&format!(
+            "\n\n[{} image{} attached here, but you can't see images. Tell the user, and that \
+             setting agent.vision_model to a model that can see would fix it.]",
+            count,
+            if count == 1 { "" } else { "s" }
+        ));
+    }
+}
+
+/// How to call tools without tool calling, for the system prompt
+fn react_instructions(tools: &[ToolSchema]) -> String {
+    let mut text = String::from(
+        "## Using tools\n\n\
+         To use a tool, reply with only these two lines, then wait for the Observation:\n\n\
+         Action: <tool name>\n\
+         Action Input: <arguments, as a JSON object>\n\n\
+         When you have what you need, reply to the user normally, without an Action.\n\n\
+         Tools:\n",
+    );
+    for tool in tools {
+        text.push_str(&format!(
+            "- {}: {} Arguments: {}\n",
+            tool.name, tool.description, tool.parameters
+        ));
+    }
+    text
+}
+
+/// `messages` with tool use written out as ReAct text: calls as `Action:`
+/// lines and results as `Observation:` messages from the user
+fn to_react(messages: &[Message], tools: &[ToolSchema]) -> Vec<Message> {
+    let mut react: Vec<Message> = Vec::with_capacity(messages.len() + 1);
+    let instructions = react_instructions(tools);
+    if !messages.iter().any(|m| m.role == Role::System) {
+        react.push(Message {
+            role: Role::System,
+            content: instructions.clone(),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+        });
+    }
+    let mut instructed = false;
+    for message in messages {
+        let mut message = message.clone();
+        match message.role {
+            Role::System if !instructed => {
+                message.content = format!("{}\n\n{}", message.content, instructions);
+                instructed = true;
+            }
+            Role::Assistant => {
+                for call in message.tool_calls.take().unwrap_or_default() {
+                    if !message.content.is_empty() {
+                        message.content.push('\n');
+                    }
+                    message.content.push_str(&format!(
+                        "Action: {}\nAction Input: {}",
+                        call.name, call.arguments
+                    ));
+                }
+            }
+            Role::Tool => {
+                let observation = format!("Observation: {}", message.content);
+                // Results of calls made together go in one message
+                match react.last_mut() {
+                    Some(last)
+                        if last.role == Role::User && last.content.starts_with("Observation:") =>
+                    {
+                        last.content.push_str("\n\n");
+                        last.content.push_str(&observation);
+                    }
+                    _ => react.push(Message {
+                        role: Role::User,
+                        content: observation,
+                        tool_calls: None,
+                        tool_call_id: None,
+                        images: Vec::new(),
+                    }),
+                }
+                continue;
+            }
+            _ => {}
+        }
+        react.push(message);
+    }
+    react
+}
+
+/// The tool calls written as `Action:` lines in `text`
+fn parse_actions(text: &str, tools: &[ToolSchema]) -> Vec<ToolCall> {
+    // Models sometimes go on to imagine the result; that's ignored
+    let text = text.split("\nObservation:").next().unwrap_or_default();
+    let mut calls = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("Action:") {
+        rest = &rest[start + "Action:".len()..];
+        let (line, after) = rest.split_once('\n').unwrap_or((rest, ""));
+        let name = line.trim().trim_matches('`');
+        rest = after;
+        if !tools.iter().any(|t| t.name == name) {
+            continue;
+        }
+        let mut arguments = json!({});
+        if let Some(input) = rest.trim_start().strip_prefix("Action Input:") {
+            if let Some(brace) = input.find('{') {
+                let mut values =
+                    serde_json::Deserializer::from_str(&input[brace..]).into_iter::<Value>();
+                if let Some(Ok(value)) = values.next() {
+                    arguments = value;
+                }
+            }
+            rest = input;
+        }
+        calls.push(ToolCall {
+            id: format!("call_{}", uuid::Uuid::new_v4().simple()),
+            name: name.to_string(),
+            arguments: arguments.to_string(),
+        });
+    }
+    calls
+}
+
+#[async_trait]
+impl ChatProvider for DegradedProvider {
+    async fn chat(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<LLMResponse> {
+        let prepared = self.prepare(messages, tools);
+        if !self.react(tools) {
+            return self.inner.chat(&prepared, tools).await;
+        }
+        let mut response = self.inner.chat(&prepared, None).await?;
+        if let LLMResponseContent::Text(ref text) = response.content {
+            let calls = parse_actions(text, tools.unwrap_or_default());
+            if !calls.is_empty() {
+                response.content = LLMResponseContent::ToolCalls(calls);
+            }
+        }
+        Ok(response)
+    }
+
+    async fn summarize(&self, text: &str) -> Result<String> {
+        self.inner.summarize(text).await
+    }
+
+    fn is_local(&self) -> bool {
+        self.inner.is_local()
+    }
+
+    async fn context_window(&self) -> Option<usize> {
+        match self.capabilities.context_window {
+            Some(window) => Some(window),
+            None => self.inner.context_window().await,
+        }
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<StreamResult> {
+        if !self.react(tools) {
+            let prepared = self.prepare(messages, tools);
+            return self.inner.chat_stream(&prepared, tools).await;
+        }
+        // Actions can't be told from the reply until it's all there
+        let chunk = match self.chat(messages, tools).await?.content {
+            LLMResponseContent::Text(text) => StreamChunk {
+                delta: text,
+                done: true,
+                tool_calls: None,
+            },
+            LLMResponseContent::ToolCalls(calls) => StreamChunk {
+                delta: String::new(),
+                done: true,
+                tool_calls: Some(calls),
+            },
+        };
+        Ok(Box::pin(futures::stream::once(async move { Ok(chunk) })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::{ImageAttachment, MockChatProvider};
+
+    fn tools() -> Vec<ToolSchema> {
+        vec![ToolSchema {
+            name: "timer_set".to_string(),
+            description: "Set a kitchen timer.".to_string(),
+            parameters: json!({ "type": "object", "properties": { "minutes": { "type": "integer" } } }),
+        }]
+    }
+
+    fn text(content: &str) -> LLMResponse {
+        LLMResponse::text(content.to_string())
+    }
+
+    #[test]
+    fn test_parse_actions() {
+        let reply = "Thought: they want a timer.\n\
+                     Action: timer_set\n\
+                     Action Input: {\"minutes\": 10}\n\
+                     Observation: Timer set for 10 minutes";
+        let calls = parse_actions(reply, &tools());
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].name, "timer_set");
+        assert_eq!(calls[0].arguments, r#"{"minutes":10}"#);
+
+        // Unknown tools and plain replies aren't calls
+        assert!(parse_actions("Action: launch_rocket\nAction Input: {}", &tools()).is_empty());
+        assert!(parse_actions("The timer is set.", &tools()).is_empty());
+        let bare = parse_actions("Action: `timer_set`", &tools());
+        assert_eq!(bare[0].arguments, "{}");
+    }
+
+    #[test]
+    fn test_to_react() {
+        let call = ToolCall {
+            id: "call_1".to_string(),
+            name: "timer_set".to_string(),
+            arguments: r#"{"minutes":10}"#.to_string(),
+        };
+        let messages = vec![
+            Message {
+                role: Role::System,
+                ..user("You are HomeGPT.")
+            },
+            user("Set a 10 minute timer"),
+            Message {
+                role: Role::Assistant,
+                tool_calls: Some(vec![call.clone(), call]),
+                ..user("")
+            },
+            Message {
+                role: Role::Tool,
+                tool_call_id: Some("call_1".to_string()),
+                ..user("Timer set")
+            },
+            Message {
+                role: Role::Tool,
+                tool_call_id: Some("call_1".to_string()),
+                ..user("Timer set")
+            },
+        ];
+        let react = to_react(&messages, &tools());
+        assert_eq!(react.len(), 4);
+        assert!(react[0]
+            .content
+            .starts_with("You are HomeGPT.\n\n## Using tools"));
+        assert!(react[0]
+            .content
+            .contains("- timer_set: Set a kitchen timer."));
+        assert_eq!(
+            react[2].content,
+            "Action: timer_set\nAction Input: {\"minutes\":10}\n\
+             Action: timer_set\nAction Input: {\"minutes\":10}"
+        );
+        assert!(react[2].tool_calls.is_none());
+        assert_eq!(react[3].role, Role::User);
+        assert_eq!(
+            react[3].content,
+            "Observation: Timer set\n\nObservation: Timer set"
+        );
+    }
+
+    #[test]
+    fn test_json_object() {
+        assert!(json_object(r#"{"sum": 5}"#).is_some());
+        assert!(json_object("```json\n{\"sum\": 5}\n```").is_some());
+        assert!(json_object("The sum is 5").is_none());
+        assert!(json_object("[5]").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_probe() {
+        let provider =
+            MockChatProvider::new([text(r#"{"sum": 5}"#), text("It's 3pm."), text("Red.")]);
+        let capabilities = probe(&provider).await.unwrap();
+        assert!(capabilities.json);
+        assert!(!capabilities.tools);
+        assert!(capabilities.vision);
+        assert!(capabilities.degraded());
+    }
+
+    #[tokio::test]
+    async fn test_degraded_provider() {
+        let mock =
+            MockChatProvider::new([text("Action: timer_set\nAction Input: {\"minutes\": 5}")]);
+        let capabilities = Capabilities {
+            tools: false,
+            vision: false,
+            json: true,
+            context_window: Some(8192),
+            checked: Utc::now(),
+        };
+        let provider = DegradedProvider::new(Box::new(mock), capabilities);
+        let mut message = user("Set a timer for this");
+        message.images.push(ImageAttachment {
+            data: PROBE_IMAGE.to_string(),
+            media_type: "image/png".to_string(),
+        });
+
+        let response = provider.chat(&[message], Some(&tools())).await.unwrap();
+        match response.content {
+            LLMResponseContent::ToolCalls(calls) => assert_eq!(calls[0].name, "timer_set"),
+            _ => panic!("expected tool calls"),
+        }
+        assert_eq!(provider.context_window().await, Some(8192));
+    }
+
+    #[test]
+    fn test_strip_images() {
+        let mut message = user("What's this?");
+        message.images.push(ImageAttachment {
+            data: PROBE_IMAGE.to_string(),
+            media_type: "image/png".to_string(),
+        });
+        let mut messages = vec![message];
+        strip_images(&mut messages);
+        assert!(messages[0].images.is_empty());
+        assert!(messages[0]
+            .content
+            .starts_with("What's this?\n\n[1 image attached"));
+    }
+
+    #[test]
+    fn test_probeable() {
+        assert!(probeable("ollama/llama3.2"));
+        assert!(!probeable("replay/session.jsonl"));
+        assert!(!probeable("claude-cli/opus"));
+    }
+}
@@ -0,0 +1,416 @@
+//! Context packing: fit retrieved memory chunks into a token budget.
+//!
+//! memory_search retrieves more candidates than it returns, then packs them
+//! highest score first until `memory.context_budget_tokens` is used up,
+//! taking at most `memory.max_chunks_per_file` from any one file so a long
+//! note can't crowd out the others. Chunks that don't fit are dropped, or
+//! with `memory.summarize_overflow` condensed by the summary model and
+//! packed in that shorter form. Every decision is logged, so the turn's
+//! trace shows what went into the prompt and why the rest didn't.
+
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::collections::HashMap;
+use tracing::{debug, info};
+
+use super::providers::{create_provider, ChatProvider, Message, Role};
+use super::structured;
+use super::tokens::Tokenizer;
+use crate::config::Config;
+use crate::memory::VerifiedChunk;
+
+/// Words asked for per overflow summary
+const SUMMARY_WORDS: usize = 40;
+
+/// A chunk as it goes into the context
+#[derive(Debug, Clone)]
+pub struct PackedChunk {
+    pub chunk: VerifiedChunk,
+    /// The chunk's content, or its summary or truncation
+    pub text: String,
+    pub tokens: usize,
+    pub summarized: bool,
+    pub truncated: bool,
+}
+
+/// Why a retrieved chunk was left out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dropped {
+    /// Its file already has `max_chunks_per_file` chunks in
+    FileCap,
+    /// It didn't fit, even summarized
+    OverBudget,
+}
+
+impl std::fmt::Display for Dropped {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::FileCap => write!(f, "per-file cap"),
+            Self::OverBudget => write!(f, "over budget"),
+        }
+    }
+}
+
+/// Result of packing, best chunk first
+#[derive(Debug, Default)]
+pub struct Packing {
+    pub chunks: Vec<PackedChunk>,
+    /// (file, hash prefix, tokens, reason)
+    pub dropped: Vec<(String, String, usize, Dropped)>,
+    pub tokens: usize,
+    /// The budget packed into (0 = unlimited)
+    pub budget: usize,
+    /// Chunks that didn't fit whole, to summarize if enabled
+    overflow: Vec<VerifiedChunk>,
+}
+
+impl Packing {
+    fn log(&self, retrieved: usize, budget: usize) {
+        let count = |reason: Dropped| self.dropped.iter().filter(|d| d.3 == reason).count();
+        info!(
+            "Packed {} of {} retrieved chunks into {}/{} tokens ({} summarized, {} truncated, {} over the per-file cap, {} over budget)",
+            self.chunks.len(),
+            retrieved,
+            self.tokens,
+            budget,
+            self.chunks.iter().filter(|c| c.summarized).count(),
+            self.chunks.iter().filter(|c| c.truncated).count(),
+            count(Dropped::FileCap),
+            count(Dropped::OverBudget),
+        );
+        for packed in &self.chunks {
+            let how = if packed.summarized {
+                "summarized"
+            } else if packed.truncated {
+                "truncated"
+            } else {
+                "whole"
+            };
+            debug!(
+                "  packed {} {} (score {:.3}, {} tokens, {})",
+                packed.chunk.hash_prefix, packed.chunk.file, packed.chunk.score, packed.tokens, how
+            );
+        }
+        for (file, hash, tokens, reason) in &self.dropped {
+            debug!(
+                "  dropped {} {} ({} tokens, {})",
+                hash, file, tokens, reason
+            );
+        }
+    }
+}
+
+/// Summaries of overflow chunks, by their number in the prompt
+#[derive(Debug, Deserialize, JsonSchema)]
+struct OverflowSummaries {
+    summaries: Vec<OverflowSummary>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct OverflowSummary {
+    /// Number of the excerpt
+    excerpt: usize,
+    /// The excerpt's facts in a sentence or two
+    summary: String,
+}
+
+pub struct ContextPacker {
+    /// Token budget for all packed chunks (0 = unlimited)
+    budget: usize,
+    /// Chunks per file (0 = unlimited)
+    max_per_file: usize,
+    tokenizer: Tokenizer,
+    /// Condenses chunks that don't fit whole, if `memory.summarize_overflow`
+    summarizer: Option<Box<dyn ChatProvider>>,
+}
+
+impl ContextPacker {
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let memory = &config.memory;
+        let summarizer = if memory.summarize_overflow {
+            let model = config
+                .agent
+                .summary_model
+                .as_deref()
+                .unwrap_or(&config.agent.default_model);
+            Some(create_provider(model, config)?)
+        } else {
+            None
+        };
+        Ok(Self {
+            budget: memory.context_budget_tokens,
+            max_per_file: memory.max_chunks_per_file,
+            tokenizer: Tokenizer::for_model(&config.agent.default_model),
+            summarizer,
+        })
+    }
+
+    pub fn new(budget: usize, max_per_file: usize) -> Self {
+        Self {
+            budget,
+            max_per_file,
+            tokenizer: Tokenizer::default(),
+            summarizer: None,
+        }
+    }
+
+    /// Pack up to `limit` of `chunks`, summarizing overflow when enabled
+    pub async fn pack(&self, chunks: Vec<VerifiedChunk>, limit: usize) -> Result<Packing> {
+        let retrieved = chunks.len();
+        let mut packing = self.select(chunks, limit);
+
+        let overflow = std::mem::take(&mut packing.overflow);
+        if let Some(ref summarizer) = self.summarizer {
+            let wanted = limit
+                .saturating_sub(packing.chunks.len())
+                .min(overflow.len());
+            if wanted > 0 && self.budget > packing.tokens {
+                let (summarize, rest) = overflow.split_at(wanted);
+                match self.summarize(summarizer.as_ref(), summarize).await {
+                    Ok(summaries) => {
+                        for (chunk, summary) in summarize.iter().zip(summaries) {
+                            self.place_summary(&mut packing, chunk.clone(), summary);
+                        }
+                    }
+                    Err(e) => {
+                        tracing::warn!("Summarizing overflow chunks failed: {}", e);
+                        self.drop_all(&mut packing, summarize);
+                    }
+                }
+                self.drop_all(&mut packing, rest);
+            } else {
+                self.drop_all(&mut packing, &overflow);
+            }
+        } else {
+            self.drop_all(&mut packing, &overflow);
+        }
+
+        packing
+            .chunks
+            .sort_by(|a, b| b.chunk.score.total_cmp(&a.chunk.score));
+        packing.log(retrieved, self.budget);
+        Ok(packing)
+    }
+
+    /// Score-ordered greedy selection. Chunks that don't fit whole are kept
+    /// in `overflow`, except that the best chunk is truncated rather than
+    /// leave nothing at all.
+    fn select(&self, mut chunks: Vec<VerifiedChunk>, limit: usize) -> Packing {
+        chunks.sort_by(|a, b| b.score.total_cmp(&a.score));
+        let mut packing = Packing {
+            budget: self.budget,
+            ..Default::default()
+        };
+        let mut per_file: HashMap<String, usize> = HashMap::new();
+
+        for chunk in chunks {
+            if packing.chunks.len() >= limit {
+                break;
+            }
+            let tokens = self.tokenizer.count(&chunk.content);
+            let in_file = per_file.get(&chunk.file).copied().unwrap_or(0);
+            if self.max_per_file > 0 && in_file >= self.max_per_file {
+                packing
+                    .dropped
+                    .push((chunk.file, chunk.hash_prefix, tokens, Dropped::FileCap));
+                continue;
+            }
+
+            if self.budget == 0 || packing.tokens + tokens <= self.budget {
+                *per_file.entry(chunk.file.clone()).or_default() += 1;
+                packing.tokens += tokens;
+                packing.chunks.push(PackedChunk {
+                    text: chunk.content.clone(),
+                    chunk,
+                    tokens,
+                    summarized: false,
+                    truncated: false,
+                });
+            } else if packing.chunks.is_empty() && self.summarizer.is_none() {
+                let text = truncate_to(&chunk.content, tokens, self.budget);
+                let tokens = self.tokenizer.count(&text);
+                *per_file.entry(chunk.file.clone()).or_default() += 1;
+                packing.tokens += tokens;
+                packing.chunks.push(PackedChunk {
+                    text,
+                    chunk,
+                    tokens,
+                    summarized: false,
+                    truncated: true,
+                });
+            } else {
+                packing.overflow.push(chunk);
+            }
+        }
+        packing
+    }
+
+    /// Pack a summary if it fits and its file still has room
+    fn place_summary(&self, packing: &mut Packing, chunk: VerifiedChunk, summary: String) {
+        let tokens = self.tokenizer.count(&summary);
+        let in_file = packing
+            .chunks
+            .iter()
+            .filter(|c| c.chunk.file == chunk.file)
+            .count();
+        if summary.trim().is_empty() || packing.tokens + tokens > self.budget {
+            let tokens = self.tokenizer.count(&chunk.content);
+            packing
+                .dropped
+                .push((chunk.file, chunk.hash_prefix, tokens, Dropped::OverBudget));
+        } else if self.max_per_file > 0 && in_file >= self.max_per_file {
+            packing
+                .dropped
+                .push((chunk.file, chunk.hash_prefix, tokens, Dropped::FileCap));
+        } else {
+            packing.tokens += tokens;
+            packing.chunks.push(PackedChunk {
+                text: summary,
+                chunk,
+                tokens,
+                summarized: true,
+                truncated: false,
+            });
+        }
+    }
+
+    fn drop_all(&self, packing: &mut Packing, chunks: &[VerifiedChunk]) {
+        for chunk in chunks {
+            packing.dropped.push((
+                chunk.file.clone(),
+                chunk.hash_prefix.clone(),
+                self.tokenizer.count(&chunk.content),
+                Dropped::OverBudget,
+            ));
+        }
+    }
+
+    /// One summary per chunk, in order (empty where the model gave none)
+    async fn summarize(
+        &self,
+        summarizer: &dyn ChatProvider,
+        chunks: &[VerifiedChunk],
+    ) -> Result<Vec<String>> {
+        let excerpts: Vec<String> = chunks
+            .iter()
+            .enumerate()
+            .map(|(i, chunk)| format!("[{}] ({})\n{}", i + 1, chunk.file, chunk.content))
+            .collect();
+        let request = [Message {
+            role: Role::User,
+            content: format!(
+                "Condense each excerpt from a household's notes to at most {} words, keeping \
+                 names, dates, numbers and other specifics. Don't add anything that isn't in \
+                 the excerpt.\n\n{}",
+                SUMMARY_WORDS,
+                excerpts.join("\n\n")
+            ),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+        }];
+        let reply: OverflowSummaries = structured::chat_structured(summarizer, &request).await?;
+
+        let mut summaries = vec![String::new(); chunks.len()];
+        for s in reply.summaries {
+            if let Some(slot) = s.excerpt.checked_sub(1).and_then(|i| summaries.get_mut(i)) {
+                *slot = s.summary.trim().to_string();
+            }
+        }
+        Ok(summaries)
+    }
+}
+
+/// Cut `text` (`tokens` long) to roughly `budget` tokens, at a word boundary
+fn truncate_to(text: &str, tokens: usize, budget: usize) -> String {
+    let chars = text.chars().count() * budget / tokens.max(1);
+    let cut: String = text.chars().take(chars.saturating_sub(1)).collect();
+    let cut = match cut.rfind(char::is_whitespace) {
+        Some(end) if end > 0 => &cut[..end],
+        _ => cut.as_str(),
+    };
+    format!("{}…", cut.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::{Confidence, Provenance};
+
+    fn chunk(file: &str, hash: &str, score: f64, words: usize) -> VerifiedChunk {
+        VerifiedChunk {
+            file: file.to_string(),
+            line_start: 1,
+            line_end: 5,
+            content: vec!["word"; words].join(" "),
+            score,
+            verified: true,
+            hash_prefix: hash.to_string(),
+            hash: hash.to_string(),
+            provenance: Provenance::Unknown,
+            confidence: Confidence::Medium,
+            last_verified: None,
+            stale: false,
+        }
+    }
+
+    fn hashes(packing: &Packing) -> Vec<&str> {
+        packing
+            .chunks
+            .iter()
+            .map(|c| c.chunk.hash_prefix.as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_pack_per_file_cap_and_budget() -> Result<()> {
+        let chunks = vec![
+            chunk("a.md", "a3", 0.7, 10),
+            chunk("a.md", "a1", 0.9, 10),
+            chunk("a.md", "a2", 0.8, 10),
+            chunk("b.md", "b1", 0.6, 500),
+            chunk("c.md", "c1", 0.5, 10),
+        ];
+        let packing = ContextPacker::new(100, 2).pack(chunks, 5).await?;
+
+        // a3 is over the file cap, b1 over budget; c1 still gets in
+        assert_eq!(hashes(&packing), vec!["a1", "a2", "c1"]);
+        assert_eq!(packing.tokens, 30);
+        let dropped: Vec<(&str, Dropped)> = packing
+            .dropped
+            .iter()
+            .map(|d| (d.1.as_str(), d.3))
+            .collect();
+        assert_eq!(
+            dropped,
+            vec![("a3", Dropped::FileCap), ("b1", Dropped::OverBudget)]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pack_limit_and_unlimited() -> Result<()> {
+        let chunks = vec![
+            chunk("a.md", "a1", 0.9, 300),
+            chunk("a.md", "a2", 0.8, 300),
+            chunk("b.md", "b1", 0.7, 300),
+        ];
+        let packing = ContextPacker::new(0, 0).pack(chunks, 2).await?;
+        assert_eq!(hashes(&packing), vec!["a1", "a2"]);
+        assert!(packing.dropped.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pack_truncates_oversized_best_chunk() -> Result<()> {
+        let packing = ContextPacker::new(50, 2)
+            .pack(vec![chunk("a.md", "a1", 0.9, 400)], 5)
+            .await?;
+        let packed = &packing.chunks[0];
+        assert!(packed.truncated);
+        assert!(packed.tokens <= 50);
+        assert!(packed.text.ends_with('…'));
+        Ok(())
+    }
+}
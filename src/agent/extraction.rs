@@ -0,0 +1,103 @@
+//! Fact extraction: durable facts from a finished conversation, proposed for
+//! review before they're remembered.
+//!
+//! With `memory.extract_facts` on, the part of a session not yet read is
+//! sent to the summary model once the session ends, and the facts it finds
+//! wait for `homegpt memory review`.
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use super::providers::{Message, Role};
+
+/// Characters of one message kept in the transcript
+const MAX_MESSAGE_CHARS: usize = 2000;
+
+/// Characters of transcript sent; older messages are dropped first
+const MAX_TRANSCRIPT_CHARS: usize = 24_000;
+
+/// Facts found by the model
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ExtractedFacts {
+    /// Durable facts about the household, one short self-contained sentence
+    /// each. Empty if there are none.
+    pub facts: Vec<String>,
+}
+
+pub(crate) fn extraction_prompt(transcript: &str, today: &str) -> String {
+    format!(
+        "Below is a conversation with a household assistant (today is {}). List the \
+         durable facts in it worth remembering for later conversations: plans and \
+         dates, changes to routines, preferences, allergies, names and relationships. \
+         Write each as one short sentence that makes sense on its own, with names \
+         and absolute dates (\"Leo's piano recital moved to October 21\"). Leave out \
+         small talk, questions, one-off requests, anything the assistant said that the \
+         user didn't confirm, and secrets such as passwords. Return no facts if there \
+         are none.\n\nConversation:\n{}",
+        today, transcript
+    )
+}
+
+/// Transcript of the user and assistant messages in `messages`, keeping the
+/// newest that fit
+pub(crate) fn transcript(messages: &[Message]) -> String {
+    let mut lines = Vec::new();
+    let mut total = 0;
+    for message in messages.iter().rev() {
+        let speaker = match message.role {
+            Role::User => "User",
+            Role::Assistant => "Assistant",
+            _ => continue,
+        };
+        let content = message.content.trim();
+        if content.is_empty() {
+            continue;
+        }
+        let mut line = format!("{}: ", speaker);
+        line.extend(content.chars().take(MAX_MESSAGE_CHARS));
+        if content.chars().count() > MAX_MESSAGE_CHARS {
+            line.push_str("...");
+        }
+        total += line.len();
+        if total > MAX_TRANSCRIPT_CHARS && !lines.is_empty() {
+            break;
+        }
+        lines.push(line);
+    }
+    lines.reverse();
+    lines.join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: Role, content: &str) -> Message {
+        Message {
+            role,
+            content: content.to_string(),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_transcript() {
+        let long = "la ".repeat(1000);
+        let messages = vec![
+            message(Role::System, "You are helpful."),
+            message(Role::User, "Leo's recital moved to the 21st."),
+            message(Role::Tool, "ok"),
+            message(Role::Assistant, "Noted!"),
+            message(Role::User, &long),
+        ];
+
+        let text = transcript(&messages);
+        assert!(text.starts_with(
+            "User: Leo's recital moved to the 21st.\n\nAssistant: Noted!\n\nUser: la la"
+        ));
+        assert!(text.ends_with("..."));
+        assert!(!text.contains("helpful"));
+    }
+}
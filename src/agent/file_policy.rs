@@ -0,0 +1,272 @@
+//! File policy: which paths the file tools may read and write
+//!
+//! `files.read` and `files.write` are globs (or plain directories) the two
+//! kinds of access are allowed under, relative to the workspace unless they
+//! start with `/` or `~`. `files.deny` wins over both. By default the tools
+//! stay inside the workspace, so having `read_file` doesn't mean the model
+//! can read `~/.ssh`. Paths are checked with `..` and symlinks resolved.
+//!
+//! A call outside the policy isn't run and is logged; with
+//! `on_violation = "ask"` it's asked about like any other approval instead.
+
+use anyhow::{Context, Result};
+use glob::{MatchOptions, Pattern};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+use super::approval::{normalize, resolve};
+use super::tools::resolve_memory_path;
+use crate::config::Config;
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// What a file tool does with its `path` argument
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+impl Access {
+    /// The access `tool` needs; `None` for tools that don't take a file
+    pub fn of_tool(tool: &str) -> Option<Self> {
+        match tool {
+            "read_file" | "memory_get" | "finance_import" => Some(Self::Read),
+            "write_file" | "edit_file" | "patch_file" => Some(Self::Write),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Write => "write",
+        }
+    }
+}
+
+/// One of `files.read`, `files.write` or `files.deny`
+#[derive(Debug, Clone)]
+enum Glob {
+    /// A plain path: it and everything under it
+    Under(PathBuf),
+    Pattern(Pattern),
+}
+
+impl Glob {
+    fn new(written: &str, workspace: &Path) -> Result<Self> {
+        let expanded = shellexpand::tilde(written).to_string();
+        if !expanded.contains(['*', '?', '[']) {
+            return Ok(Self::Under(canonical(&normalize(
+                &workspace.join(expanded),
+            ))));
+        }
+        let absolute = if Path::new(&expanded).is_absolute() {
+            expanded
+        } else {
+            format!(
+                "{}/{}",
+                Pattern::escape(&workspace.to_string_lossy()),
+                expanded
+            )
+        };
+        Pattern::new(&absolute)
+            .map(Self::Pattern)
+            .with_context(|| format!("Invalid files glob '{}'", written))
+    }
+
+    fn matches(&self, path: &Path) -> bool {
+        match self {
+            Self::Under(base) => path.starts_with(base),
+            Self::Pattern(pattern) => pattern.matches_path_with(path, MATCH_OPTIONS),
+        }
+    }
+}
+
+/// Decides which paths the file tools may touch
+#[derive(Debug, Clone)]
+pub struct FilePolicy {
+    read: Vec<(String, Glob)>,
+    write: Vec<(String, Glob)>,
+    deny: Vec<(String, Glob)>,
+    /// As configured, for the tools that resolve paths against it
+    workspace: PathBuf,
+    /// Ask for approval instead of refusing
+    ask: bool,
+}
+
+impl FilePolicy {
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let files = &config.files;
+        let ask = match files.on_violation.as_str() {
+            "deny" => false,
+            "ask" => true,
+            other => anyhow::bail!(
+                "Invalid files.on_violation '{}' (expected deny or ask)",
+                other
+            ),
+        };
+        let workspace = normalize(&config.workspace_path());
+        // Globs are matched against resolved paths, so a workspace behind
+        // a symlink still matches its own files
+        let resolved = canonical(&workspace);
+        let globs = |written: &[String]| {
+            written
+                .iter()
+                .map(|w| Ok((w.clone(), Glob::new(w, &resolved)?)))
+                .collect::<Result<Vec<_>>>()
+        };
+
+        Ok(Self {
+            read: globs(&files.read)?,
+            write: globs(&files.write)?,
+            deny: globs(&files.deny)?,
+            workspace,
+            ask,
+        })
+    }
+
+    /// Whether calls outside the policy are asked about rather than refused
+    pub fn asks(&self) -> bool {
+        self.ask
+    }
+
+    /// Why calling `tool` with `arguments` (JSON) is outside the policy, if
+    /// it is. A file tool call without a path to check is outside it too.
+    pub fn violation(&self, tool: &str, arguments: &str) -> Option<String> {
+        let access = Access::of_tool(tool)?;
+        let args: Value = serde_json::from_str(arguments).unwrap_or_default();
+        let Some(written) = args.get("path").and_then(Value::as_str) else {
+            return Some(format!("{} was called without a path to check", tool));
+        };
+        let path = if tool == "memory_get" {
+            resolve(&resolve_memory_path(&self.workspace, written).to_string_lossy())
+        } else {
+            resolve(written)
+        };
+        let path = canonical(&path);
+
+        if let Some((glob, _)) = self.deny.iter().find(|(_, g)| g.matches(&path)) {
+            return Some(format!(
+                "{} matches files.deny \"{}\"",
+                path.display(),
+                glob
+            ));
+        }
+        let allowed = match access {
+            Access::Read => &self.read,
+            Access::Write => &self.write,
+        };
+        if allowed.iter().any(|(_, g)| g.matches(&path)) {
+            None
+        } else {
+            Some(format!(
+                "{} is outside the paths file tools may {} (files.{})",
+                path.display(),
+                access.as_str(),
+                access.as_str()
+            ))
+        }
+    }
+}
+
+/// `path` (absolute, without `..`) with symlinks resolved, so a link in the
+/// workspace to ~/.ssh is checked as ~/.ssh. The part that doesn't exist
+/// yet, such as a file about to be written, is kept as it is.
+fn canonical(path: &Path) -> PathBuf {
+    let mut existing = path;
+    let mut missing = Vec::new();
+    loop {
+        if let Ok(resolved) = existing.canonicalize() {
+            return missing
+                .iter()
+                .rev()
+                .fold(resolved, |resolved, part| resolved.join(part));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name);
+                existing = parent;
+            }
+            _ => return path.to_path_buf(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn args(path: &Path) -> String {
+        json!({ "path": path }).to_string()
+    }
+
+    #[test]
+    fn test_file_policy() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path().join("workspace");
+        std::fs::create_dir_all(workspace.join("memory")).unwrap();
+        let outside = temp.path().join("secrets");
+        std::fs::create_dir_all(&outside).unwrap();
+
+        let mut config = Config::default();
+        config.memory.workspace = workspace.display().to_string();
+        config.files.write = vec!["memory/**".to_string()];
+        config.files.deny = vec!["**/.env".to_string()];
+        let policy = FilePolicy::from_config(&config).unwrap();
+
+        let notes = workspace.join("memory/notes.md");
+        assert_eq!(policy.violation("read_file", &args(&notes)), None);
+        assert_eq!(policy.violation("write_file", &args(&notes)), None);
+        assert_eq!(
+            policy.violation("memory_get", r#"{"path": "MEMORY.md"}"#),
+            None
+        );
+        assert!(policy
+            .violation("write_file", &args(&workspace.join("SOUL.md")))
+            .unwrap()
+            .contains("files.write"));
+        assert!(policy
+            .violation("read_file", &args(&workspace.join("memory/.env")))
+            .unwrap()
+            .contains("files.deny \"**/.env\""));
+
+        // Outside the workspace, by `..` or through a symlink
+        let escape = workspace.join("memory/../../secrets/key");
+        assert!(policy.violation("read_file", &args(&escape)).is_some());
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&outside, workspace.join("memory/link")).unwrap();
+            let linked = workspace.join("memory/link/key");
+            assert!(policy.violation("read_file", &args(&linked)).is_some());
+        }
+
+        // Nothing to check is refused rather than let through
+        for arguments in ["not json", "{}", r#"{"path": 42}"#] {
+            assert!(policy
+                .violation("write_file", arguments)
+                .unwrap()
+                .contains("without a path"));
+        }
+
+        // Other tools aren't checked
+        assert_eq!(policy.violation("bash", r#"{"command": "ls"}"#), None);
+        assert!(!policy.asks());
+
+        config.files.read = vec![outside.display().to_string()];
+        config.files.on_violation = "ask".to_string();
+        let policy = FilePolicy::from_config(&config).unwrap();
+        assert_eq!(policy.violation("read_file", &args(&escape)), None);
+        assert!(policy.violation("read_file", &args(&notes)).is_some());
+        assert!(policy.asks());
+
+        config.files.on_violation = "maybe".to_string();
+        assert!(FilePolicy::from_config(&config).is_err());
+    }
+}
@@ -0,0 +1,569 @@
+//! Google Gemini chat, through Gemini's own API (`generateContent`)
+//!
+//! Gemini differs from the OpenAI-style APIs in ways the rest of the agent
+//! shouldn't see: the system prompt is a separate `systemInstruction`, the
+//! assistant's role is "model", tool arguments are JSON objects rather than
+//! strings, tool results are `functionResponse` parts that name the tool
+//! rather than a call ID, and tool schemas are an OpenAPI subset. Calls
+//! don't always come with IDs, so they're given one here; a call's thought
+//! signature is sent back with it on the next request, as Gemini 3 needs.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::StreamExt;
+use reqwest::Client;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tracing::debug;
+
+use super::providers::{
+    ChatProvider, LLMResponse, LLMResponseContent, Message, Role, StreamChunk, StreamResult,
+    ToolCall, ToolSchema, Usage,
+};
+
+/// JSON Schema keywords Gemini's function declarations reject
+const UNSUPPORTED_SCHEMA_KEYS: [&str; 6] = [
+    "$schema",
+    "$id",
+    "$defs",
+    "definitions",
+    "additionalProperties",
+    "examples",
+];
+
+pub struct GeminiProvider {
+    client: Client,
+    api_key: String,
+    base_url: String,
+    model: String,
+    max_tokens: usize,
+    /// Thought signatures of the calls Gemini made, by call ID
+    signatures: Arc<Mutex<HashMap<String, String>>>,
+}
+
+impl GeminiProvider {
+    pub fn new(api_key: &str, base_url: &str, model: &str, max_tokens: usize) -> Result<Self> {
+        Ok(Self {
+            client: Client::new(),
+            api_key: api_key.to_string(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model: model.to_string(),
+            max_tokens,
+            signatures: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    fn format_tools(&self, tools: &[ToolSchema]) -> Value {
+        let declarations: Vec<Value> = tools
+            .iter()
+            .map(|t| {
+                json!({
+                    "name": t.name,
+                    "description": t.description,
+                    "parameters": gemini_schema(&t.parameters)
+                })
+            })
+            .collect();
+        json!([{ "functionDeclarations": declarations }])
+    }
+
+    fn request_body(&self, messages: &[Message], tools: Option<&[ToolSchema]>) -> Value {
+        let signatures = self.signatures.lock().unwrap();
+        let (system, contents) = format_contents(messages, &signatures);
+        let mut body = json!({
+            "contents": contents,
+            "generationConfig": { "maxOutputTokens": self.max_tokens }
+        });
+        if let Some(system) = system {
+            body["systemInstruction"] = json!({ "parts": [{ "text": system }] });
+        }
+        if let Some(tools) = tools {
+            if !tools.is_empty() {
+                body["tools"] = self.format_tools(tools);
+            }
+        }
+        body
+    }
+
+    async fn send(&self, method: &str, body: &Value) -> Result<reqwest::Response> {
+        let response = self
+            .client
+            .post(format!(
+                "{}/models/{}:{}",
+                self.base_url, self.model, method
+            ))
+            .header("x-goog-api-key", &self.api_key)
+            .json(body)
+            .send()
+            .await?;
+        if response.status().is_success() {
+            return Ok(response);
+        }
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        let message = body["error"]["message"].as_str().unwrap_or("no details");
+        anyhow::bail!("Gemini API error ({}): {}", status, message)
+    }
+}
+
+/// `schema` with what Gemini rejects taken out. A nullable type, which
+/// JSON Schema writes as `["string", "null"]`, becomes `nullable`.
+fn gemini_schema(schema: &Value) -> Value {
+    match schema {
+        Value::Object(object) => {
+            let mut cleaned = Map::new();
+            for (key, value) in object {
+                if UNSUPPORTED_SCHEMA_KEYS.contains(&key.as_str()) {
+                    continue;
+                }
+                if key == "type" {
+                    if let Some(types) = value.as_array() {
+                        let mut types = types.iter().filter(|t| t.as_str() != Some("null"));
+                        if let Some(first) = types.next() {
+                            cleaned.insert(key.clone(), first.clone());
+                        }
+                        if types.count() + 1 < value.as_array().map_or(0, Vec::len) {
+                            cleaned.insert("nullable".to_string(), json!(true));
+                        }
+                        continue;
+                    }
+                }
+                cleaned.insert(key.clone(), gemini_schema(value));
+            }
+            Value::Object(cleaned)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(gemini_schema).collect()),
+        other => other.clone(),
+    }
+}
+
+/// The system prompt, and `messages` as Gemini contents
+fn format_contents(
+    messages: &[Message],
+    signatures: &HashMap<String, String>,
+) -> (Option<String>, Vec<Value>) {
+    let mut system: Vec<&str> = Vec::new();
+    let mut contents: Vec<Value> = Vec::new();
+    // Tool results name the tool; the agent only has the call ID
+    let mut tool_names: HashMap<&str, &str> = HashMap::new();
+
+    for m in messages {
+        match m.role {
+            Role::System => system.push(&m.content),
+            Role::User => {
+                let mut parts: Vec<Value> = m
+                    .images
+                    .iter()
+                    .map(|img| json!({ "inlineData": { "mimeType": img.media_type, "data": img.data } }))
+                    .collect();
+                if !m.content.is_empty() || parts.is_empty() {
+                    parts.push(json!({ "text": m.content }));
+                }
+                contents.push(json!({ "role": "user", "parts": parts }));
+            }
+            Role::Assistant => {
+                let mut parts = Vec::new();
+                if !m.content.is_empty() {
+                    parts.push(json!({ "text": m.content }));
+                }
+                for call in m.tool_calls.iter().flatten() {
+                    tool_names.insert(&call.id, &call.name);
+                    let args: Value =
+                        serde_json::from_str(&call.arguments).unwrap_or_else(|_| json!({}));
+                    let mut part = json!({ "functionCall": { "name": call.name, "args": args } });
+                    if let Some(signature) = signatures.get(&call.id) {
+                        part["thoughtSignature"] = json!(signature);
+                    }
+                    parts.push(part);
+                }
+                if parts.is_empty() {
+                    parts.push(json!({ "text": "" }));
+                }
+                contents.push(json!({ "role": "model", "parts": parts }));
+            }
+            Role::Tool => {
+                let id = m.tool_call_id.as_deref().unwrap_or_default();
+                let name = tool_names.get(id).copied().unwrap_or("tool");
+                let part = json!({
+                    "functionResponse": {
+                        "name": name,
+                        "response": { "content": m.content }
+                    }
+                });
+                // One turn answers all of a turn's calls
+                let last = contents.last_mut().filter(|last| {
+                    last["parts"].as_array().is_some_and(|parts| {
+                        parts.iter().all(|p| p.get("functionResponse").is_some())
+                    })
+                });
+                match last {
+                    Some(last) => last["parts"].as_array_mut().unwrap().push(part),
+                    None => contents.push(json!({ "role": "user", "parts": [part] })),
+                }
+            }
+        }
+    }
+
+    let system = (!system.is_empty()).then(|| system.join("\n\n"));
+    (system, contents)
+}
+
+/// The text and tool calls in a response (or streamed chunk) from Gemini.
+/// Calls get an ID if they came without, and their thought signatures are
+/// kept in `signatures`.
+fn parse_parts(
+    response: &Value,
+    signatures: &Mutex<HashMap<String, String>>,
+) -> (String, Vec<ToolCall>) {
+    let mut text = String::new();
+    let mut calls = Vec::new();
+    let parts = response["candidates"][0]["content"]["parts"].as_array();
+    for part in parts.into_iter().flatten() {
+        // Thought summaries aren't part of the reply
+        if part["thought"].as_bool() == Some(true) {
+            continue;
+        }
+        if let Some(t) = part["text"].as_str() {
+            text.push_str(t);
+        }
+        if let Some(call) = part.get("functionCall") {
+            let id = call["id"]
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("call_{}", uuid::Uuid::new_v4().simple()));
+            if let Some(signature) = part["thoughtSignature"].as_str() {
+                signatures
+                    .lock()
+                    .unwrap()
+                    .insert(id.clone(), signature.to_string());
+            }
+            calls.push(ToolCall {
+                id,
+                name: call["name"].as_str().unwrap_or_default().to_string(),
+                arguments: call.get("args").unwrap_or(&json!({})).to_string(),
+            });
+        }
+    }
+    (text, calls)
+}
+
+fn parse_usage(response: &Value) -> Option<Usage> {
+    let usage = response.get("usageMetadata")?;
+    let count = |key: &str| usage[key].as_u64().unwrap_or(0);
+    Some(Usage {
+        input_tokens: count("promptTokenCount"),
+        // Thinking is billed as output
+        output_tokens: count("candidatesTokenCount") + count("thoughtsTokenCount"),
+        cache_read_tokens: count("cachedContentTokenCount"),
+        ..Default::default()
+    })
+}
+
+/// Why Gemini gave nothing back, if it said
+fn blocked_reason(response: &Value) -> Option<String> {
+    if let Some(reason) = response["promptFeedback"]["blockReason"].as_str() {
+        return Some(format!("request blocked ({})", reason));
+    }
+    match response["candidates"][0]["finishReason"].as_str() {
+        Some(reason @ ("SAFETY" | "RECITATION" | "PROHIBITED_CONTENT" | "BLOCKLIST" | "SPII")) => {
+            Some(format!("reply blocked ({})", reason))
+        }
+        _ => None,
+    }
+}
+
+#[async_trait]
+impl ChatProvider for GeminiProvider {
+    async fn chat(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<LLMResponse> {
+        let body = self.request_body(messages, tools);
+        debug!("Gemini request: {}", serde_json::to_string_pretty(&body)?);
+
+        let response: Value = self.send("generateContent", &body).await?.json().await?;
+        debug!(
+            "Gemini response: {}",
+            serde_json::to_string_pretty(&response)?
+        );
+
+        let usage = parse_usage(&response);
+        let (text, calls) = parse_parts(&response, &self.signatures);
+        if !calls.is_empty() {
+            return Ok(LLMResponse {
+                content: LLMResponseContent::ToolCalls(calls),
+                usage,
+            });
+        }
+        if text.is_empty() {
+            if let Some(reason) = blocked_reason(&response) {
+                anyhow::bail!("Gemini {}", reason);
+            }
+        }
+        Ok(LLMResponse {
+            content: LLMResponseContent::Text(text),
+            usage,
+        })
+    }
+
+    async fn summarize(&self, text: &str) -> Result<String> {
+        let messages = vec![Message {
+            role: Role::User,
+            content: format!(
+                "Summarize the following conversation concisely, preserving key information and context:\n\n{}",
+                text
+            ),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+        }];
+
+        match self.chat(&messages, None).await?.content {
+            LLMResponseContent::Text(summary) => Ok(summary),
+            _ => anyhow::bail!("Unexpected response type"),
+        }
+    }
+
+    async fn context_window(&self) -> Option<usize> {
+        let response = self
+            .client
+            .get(format!("{}/models/{}", self.base_url, self.model))
+            .header("x-goog-api-key", &self.api_key)
+            .send()
+            .await
+            .ok()?;
+        let model: Value = response.error_for_status().ok()?.json().await.ok()?;
+        Some(model["inputTokenLimit"].as_u64()? as usize)
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<StreamResult> {
+        let body = self.request_body(messages, tools);
+        debug!(
+            "Gemini streaming request: {}",
+            serde_json::to_string_pretty(&body)?
+        );
+
+        let response = self.send("streamGenerateContent?alt=sse", &body).await?;
+        let signatures = Arc::clone(&self.signatures);
+
+        // Server-sent events, each a whole response with the next piece of
+        // text; calls arrive whole. The stream just ends, without [DONE].
+        let stream = async_stream::stream! {
+            let mut byte_stream = response.bytes_stream();
+            let mut buffer = String::new();
+            let mut tool_calls: Vec<ToolCall> = Vec::new();
+            let mut failed = false;
+
+            'read: while let Some(chunk) = byte_stream.next().await {
+                let bytes = match chunk {
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        yield Err(anyhow::anyhow!("Stream error: {}", e));
+                        failed = true;
+                        break;
+                    }
+                };
+                buffer.push_str(&String::from_utf8_lossy(&bytes));
+
+                while let Some(pos) = buffer.find('\n') {
+                    let line = buffer[..pos].trim().to_string();
+                    buffer.drain(..=pos);
+                    let Some(data) = line.strip_prefix("data:") else {
+                        continue;
+                    };
+                    let Ok(json) = serde_json::from_str::<Value>(data.trim()) else {
+                        continue;
+                    };
+                    if let Some(error) = json.get("error") {
+                        yield Err(anyhow::anyhow!("Gemini error: {}", error["message"]));
+                        failed = true;
+                        break 'read;
+                    }
+                    if let Some(reason) = blocked_reason(&json) {
+                        yield Err(anyhow::anyhow!("Gemini {}", reason));
+                        failed = true;
+                        break 'read;
+                    }
+
+                    let (text, calls) = parse_parts(&json, &signatures);
+                    tool_calls.extend(calls);
+                    if !text.is_empty() {
+                        yield Ok(StreamChunk {
+                            delta: text,
+                            done: false,
+                            tool_calls: None,
+                        });
+                    }
+                }
+            }
+
+            if !failed {
+                yield Ok(StreamChunk {
+                    delta: String::new(),
+                    done: true,
+                    tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+                });
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::ImageAttachment;
+
+    fn message(role: Role, content: &str) -> Message {
+        Message {
+            role,
+            content: content.to_string(),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_format_contents() {
+        let mut user = message(Role::User, "What's on the calendar?");
+        user.images.push(ImageAttachment {
+            data: "iVBORw0KGgo=".to_string(),
+            media_type: "image/png".to_string(),
+        });
+        let mut assistant = message(Role::Assistant, "");
+        assistant.tool_calls = Some(vec![
+            ToolCall {
+                id: "call_1".to_string(),
+                name: "calendar_list".to_string(),
+                arguments: r#"{"days":7}"#.to_string(),
+            },
+            ToolCall {
+                id: "call_2".to_string(),
+                name: "timer_list".to_string(),
+                arguments: "{}".to_string(),
+            },
+        ]);
+        let mut first = message(Role::Tool, "Dentist on Tuesday");
+        first.tool_call_id = Some("call_1".to_string());
+        let mut second = message(Role::Tool, "No timers");
+        second.tool_call_id = Some("call_2".to_string());
+        let messages = vec![
+            message(Role::System, "You are HomeGPT."),
+            user,
+            assistant,
+            first,
+            second,
+            message(Role::Assistant, "The dentist is on Tuesday."),
+        ];
+        let signatures = HashMap::from([("call_1".to_string(), "sig".to_string())]);
+
+        let (system, contents) = format_contents(&messages, &signatures);
+        assert_eq!(system.as_deref(), Some("You are HomeGPT."));
+        assert_eq!(contents.len(), 4);
+        assert_eq!(
+            contents[0]["parts"][0]["inlineData"]["mimeType"],
+            "image/png"
+        );
+        assert_eq!(contents[0]["parts"][1]["text"], "What's on the calendar?");
+
+        assert_eq!(contents[1]["role"], "model");
+        let call = &contents[1]["parts"][0];
+        assert_eq!(call["functionCall"]["args"], json!({ "days": 7 }));
+        assert_eq!(call["thoughtSignature"], "sig");
+        assert!(contents[1]["parts"][1].get("thoughtSignature").is_none());
+
+        // Both results in one turn, by tool name
+        assert_eq!(contents[2]["role"], "user");
+        let results = contents[2]["parts"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["functionResponse"]["name"], "calendar_list");
+        assert_eq!(
+            results[1]["functionResponse"]["response"]["content"],
+            "No timers"
+        );
+        assert_eq!(
+            contents[3]["parts"][0]["text"],
+            "The dentist is on Tuesday."
+        );
+    }
+
+    #[test]
+    fn test_gemini_schema() {
+        let schema = json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "type": "object",
+            "additionalProperties": false,
+            "properties": {
+                "query": { "type": "string", "description": "What to find" },
+                "limit": { "type": ["integer", "null"] },
+                "tags": { "type": "array", "items": { "type": "string", "additionalProperties": false } }
+            },
+            "required": ["query"]
+        });
+        assert_eq!(
+            gemini_schema(&schema),
+            json!({
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "What to find" },
+                    "limit": { "type": "integer", "nullable": true },
+                    "tags": { "type": "array", "items": { "type": "string" } }
+                },
+                "required": ["query"]
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let response = json!({
+            "candidates": [{
+                "content": {
+                    "role": "model",
+                    "parts": [
+                        { "text": "Thinking about timers", "thought": true },
+                        { "text": "Setting it now." },
+                        {
+                            "functionCall": { "name": "timer_set", "args": { "minutes": 10 } },
+                            "thoughtSignature": "abc"
+                        }
+                    ]
+                },
+                "finishReason": "STOP"
+            }],
+            "usageMetadata": {
+                "promptTokenCount": 120,
+                "candidatesTokenCount": 15,
+                "thoughtsTokenCount": 30,
+                "cachedContentTokenCount": 100
+            }
+        });
+        let signatures = Mutex::new(HashMap::new());
+        let (text, calls) = parse_parts(&response, &signatures);
+        assert_eq!(text, "Setting it now.");
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].name, "timer_set");
+        assert_eq!(calls[0].arguments, r#"{"minutes":10}"#);
+        assert_eq!(signatures.lock().unwrap()[&calls[0].id], "abc");
+
+        let usage = parse_usage(&response).unwrap();
+        assert_eq!(usage.input_tokens, 120);
+        assert_eq!(usage.output_tokens, 45);
+        assert_eq!(usage.cache_read_tokens, 100);
+        assert!(blocked_reason(&response).is_none());
+
+        let blocked = json!({ "promptFeedback": { "blockReason": "SAFETY" } });
+        assert_eq!(
+            blocked_reason(&blocked).as_deref(),
+            Some("request blocked (SAFETY)")
+        );
+    }
+}
@@ -0,0 +1,203 @@
+//! Grounded answers: reply strictly from verified memory, or abstain.
+//!
+//! `Agent::answer_grounded` retrieves verified chunks for a question and
+//! asks the model to answer from them alone, naming the chunks it used.
+//! When no chunk is verified at `memory.answer_min_confidence` or better, or
+//! the model finds the answer isn't in them, the result abstains instead, so
+//! a voice or chat frontend can say "I'm not sure" rather than guess.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::locale;
+use crate::memory::{Confidence, VerifiedChunk};
+
+/// Chunks retrieved per question
+pub const RETRIEVAL_LIMIT: usize = 8;
+
+/// Result of `Agent::answer_grounded`
+#[derive(Debug, Clone, Serialize)]
+pub struct GroundedAnswer {
+    /// The answer, or a short "not sure" line when abstaining
+    pub answer: String,
+    /// Chunks the answer came from
+    pub citations: Vec<Citation>,
+    /// Highest confidence among the cited chunks (`None` when abstaining)
+    pub confidence: Confidence,
+    pub abstained: bool,
+}
+
+impl GroundedAnswer {
+    pub fn abstain() -> Self {
+        Self {
+            answer: locale::current().text("answer.not_sure").to_string(),
+            citations: Vec::new(),
+            confidence: Confidence::None,
+            abstained: true,
+        }
+    }
+}
+
+/// A verified chunk an answer cites
+#[derive(Debug, Clone, Serialize)]
+pub struct Citation {
+    /// Hash prefix, as in `[VERIFIED:<hash>]`
+    pub hash: String,
+    pub file: String,
+    pub line_start: i32,
+    pub line_end: i32,
+    pub confidence: Confidence,
+}
+
+/// The model's reply
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GroundedReply {
+    /// False if the excerpts don't contain the answer
+    pub answerable: bool,
+    /// The answer, using only the excerpts
+    pub answer: String,
+    /// Ids of the excerpts the answer uses
+    pub sources: Vec<String>,
+}
+
+/// Chunks that can back an answer: verified, at `min` confidence or better
+pub fn usable(chunks: Vec<VerifiedChunk>, min: Confidence) -> Vec<VerifiedChunk> {
+    chunks
+        .into_iter()
+        .filter(|c| c.verified && c.confidence > Confidence::None && c.confidence >= min)
+        .collect()
+}
+
+pub fn prompt(question: &str, chunks: &[VerifiedChunk]) -> String {
+    let excerpts: Vec<String> = chunks
+        .iter()
+        .map(|c| {
+            format!(
+                "<excerpt id=\"{}\" file=\"{}\">\n{}\n</excerpt>",
+                c.hash_prefix,
+                c.file,
+                c.content.trim()
+            )
+        })
+        .collect();
+    format!(
+        "Answer the question using only these excerpts from the family's notes. Don't use \
+         anything you know otherwise, and don't guess. If the excerpts don't answer it, set \
+         answerable to false. Keep the answer short enough to be read aloud, in the language \
+         of the question, and list the ids of the excerpts you used in sources.\n\n{}\n\n\
+         Question: {}",
+        excerpts.join("\n\n"),
+        question.trim()
+    )
+}
+
+/// Turn the model's reply into a result. An answer that cites none of
+/// `chunks` isn't grounded, so it abstains.
+pub fn resolve(reply: GroundedReply, chunks: &[VerifiedChunk]) -> GroundedAnswer {
+    let answer = reply.answer.trim();
+    if !reply.answerable || answer.is_empty() {
+        return GroundedAnswer::abstain();
+    }
+
+    let mut citations: Vec<Citation> = Vec::new();
+    for source in &reply.sources {
+        let source = source
+            .trim()
+            .trim_matches(['[', ']'])
+            .trim_start_matches("VERIFIED:")
+            .to_lowercase();
+        let Some(chunk) = chunks.iter().find(|c| c.hash_prefix == source) else {
+            continue;
+        };
+        if citations.iter().any(|c| c.hash == chunk.hash_prefix) {
+            continue;
+        }
+        citations.push(Citation {
+            hash: chunk.hash_prefix.clone(),
+            file: chunk.file.clone(),
+            line_start: chunk.line_start,
+            line_end: chunk.line_end,
+            confidence: chunk.confidence,
+        });
+    }
+    if citations.is_empty() {
+        return GroundedAnswer::abstain();
+    }
+
+    let confidence = citations
+        .iter()
+        .map(|c| c.confidence)
+        .fold(Confidence::None, |best, c| if c > best { c } else { best });
+    GroundedAnswer {
+        answer: answer.to_string(),
+        citations,
+        confidence,
+        abstained: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::Provenance;
+
+    fn chunk(hash: &str, confidence: Confidence, verified: bool) -> VerifiedChunk {
+        VerifiedChunk {
+            file: "memory/house.md".to_string(),
+            line_start: 1,
+            line_end: 4,
+            content: "Acme Plumbing installed the water heater in 2021.".to_string(),
+            score: 0.9,
+            verified,
+            hash_prefix: hash.to_string(),
+            hash: format!("{}00000000", hash),
+            provenance: Provenance::UserStated,
+            confidence,
+            last_verified: None,
+            stale: false,
+        }
+    }
+
+    fn reply(answerable: bool, sources: &[&str]) -> GroundedReply {
+        GroundedReply {
+            answerable,
+            answer: "Acme Plumbing, in 2021.".to_string(),
+            sources: sources.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_usable_filters_by_confidence() {
+        let chunks = vec![
+            chunk("aaaa1111", Confidence::High, true),
+            chunk("bbbb2222", Confidence::Low, true),
+            chunk("cccc3333", Confidence::High, false),
+        ];
+        let usable = usable(chunks, Confidence::Medium);
+        assert_eq!(usable.len(), 1);
+        assert_eq!(usable[0].hash_prefix, "aaaa1111");
+        assert!(
+            super::usable(vec![chunk("d", Confidence::None, true)], Confidence::None).is_empty()
+        );
+    }
+
+    #[test]
+    fn test_resolve() {
+        let chunks = vec![
+            chunk("aaaa1111", Confidence::Medium, true),
+            chunk("bbbb2222", Confidence::High, true),
+        ];
+
+        let answer = resolve(reply(true, &["VERIFIED:AAAA1111", "bbbb2222"]), &chunks);
+        assert!(!answer.abstained);
+        assert_eq!(answer.citations.len(), 2);
+        assert_eq!(answer.confidence, Confidence::High);
+
+        // Not in the excerpts, or citing nothing that was given
+        assert!(resolve(reply(false, &["aaaa1111"]), &chunks).abstained);
+        let ungrounded = resolve(reply(true, &["ffff0000"]), &chunks);
+        assert!(ungrounded.abstained);
+        assert_eq!(ungrounded.confidence, Confidence::None);
+        assert!(ungrounded.citations.is_empty());
+    }
+}
@@ -0,0 +1,264 @@
+//! Weekly family journal: on the first heartbeat of a week, the summary
+//! model reads the week before's sessions (of every agent) and heartbeat
+//! log and writes `memory/journal/YYYY-Www.md` with what happened, the
+//! decisions made and what's still open. The file is indexed with journal
+//! provenance, so over the years it becomes a searchable family history.
+
+use anyhow::Result;
+use chrono::{
+    DateTime, Datelike, Duration, IsoWeek, NaiveDate, NaiveDateTime, NaiveTime, Utc, Weekday,
+};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::fs;
+use tracing::warn;
+
+use super::extraction;
+use super::providers::{Message, Role};
+use super::session::{get_sessions_dir_for_agent, list_all_sessions, Session};
+use crate::locale;
+
+/// Where the journal is written, one file per ISO week, in the workspace
+pub const JOURNAL_DIR: &str = "memory/journal";
+
+/// Characters of one session's transcript sent; its start is dropped first
+const MAX_SESSION_CHARS: usize = 6000;
+
+/// Characters of transcripts sent in all; later sessions are left out
+const MAX_SESSIONS_CHARS: usize = 60_000;
+
+/// A week, as the model summed it up
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct WeeklyJournal {
+    /// What happened, one short sentence each with names and dates, most
+    /// important first
+    pub happened: Vec<String>,
+    /// Decisions the family made, one short sentence each
+    pub decisions: Vec<String>,
+    /// Things still open at the end of the week, with who is to do them
+    /// when that came up
+    pub follow_ups: Vec<String>,
+}
+
+/// "2026-W42"
+pub fn week_label(week: IsoWeek) -> String {
+    format!("{}-W{:02}", week.year(), week.week())
+}
+
+/// `week`'s journal, relative to the workspace
+pub fn journal_path(week: IsoWeek) -> String {
+    format!("{}/{}.md", JOURNAL_DIR, week_label(week))
+}
+
+/// `week`'s Monday and Sunday
+fn week_days(week: IsoWeek) -> (NaiveDate, NaiveDate) {
+    let monday =
+        NaiveDate::from_isoywd_opt(week.year(), week.week(), Weekday::Mon).unwrap_or_default();
+    (monday, monday + Duration::days(6))
+}
+
+/// The user and assistant messages sent during `week` in every agent's
+/// saved sessions, oldest session first, each with a heading
+pub(crate) fn week_sessions(week: IsoWeek) -> Result<Vec<(String, Vec<Message>)>> {
+    let (monday, _) = week_days(week);
+    let start = monday.and_time(NaiveTime::MIN);
+    let end = start + Duration::days(7);
+    let local = |timestamp_ms: u64| {
+        DateTime::from_timestamp_millis(timestamp_ms as i64)
+            .map(|time| locale::home_time(&time).naive_local())
+    };
+    let sent_during = |time: Option<NaiveDateTime>| time.is_some_and(|t| t >= start && t < end);
+
+    let mut sessions = Vec::new();
+    for info in list_all_sessions()?.into_iter().rev() {
+        let created = locale::home_time(&info.created_at).naive_local();
+        if created >= end {
+            continue;
+        }
+        // Not written to since before the week began
+        let path = get_sessions_dir_for_agent(&info.agent_id)?.join(format!("{}.jsonl", info.id));
+        let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+        if modified
+            .is_some_and(|m| locale::home_time(&DateTime::<Utc>::from(m)).naive_local() < start)
+        {
+            continue;
+        }
+        let session = match Session::load_for_agent(&info.agent_id, &info.id) {
+            Ok(session) => session,
+            Err(e) => {
+                warn!("Failed to read session {} for the journal: {}", info.id, e);
+                continue;
+            }
+        };
+        let messages: Vec<Message> = session
+            .raw_messages()
+            .iter()
+            .filter(|sm| matches!(sm.message.role, Role::User | Role::Assistant))
+            .filter(|sm| sent_during(local(sm.timestamp)))
+            .map(|sm| sm.message.clone())
+            .collect();
+        if !messages.iter().any(|m| m.role == Role::User) {
+            continue;
+        }
+        let mut heading = created.format("%a %Y-%m-%d %H:%M").to_string();
+        if let Some(label) = info.label {
+            heading.push_str(&format!(" ({})", label));
+        }
+        sessions.push((heading, messages));
+    }
+    Ok(sessions)
+}
+
+/// Lines of the heartbeat task log (`- 2026-10-12 08:00 **Title** ...`)
+/// from `week`
+pub(crate) fn heartbeat_entries(log: &str, week: IsoWeek) -> Vec<&str> {
+    log.lines()
+        .filter(|line| {
+            line.strip_prefix("- ")
+                .and_then(|rest| rest.get(..10))
+                .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+                .is_some_and(|date| date.iso_week() == week)
+        })
+        .collect()
+}
+
+pub(crate) fn journal_prompt(
+    week: IsoWeek,
+    sessions: &[(String, Vec<Message>)],
+    heartbeat: &[&str],
+) -> String {
+    let (monday, sunday) = week_days(week);
+    let mut conversations = String::new();
+    let mut left_out = 0;
+    for (heading, messages) in sessions {
+        let transcript = extraction::transcript(messages);
+        let transcript = match transcript.char_indices().rev().nth(MAX_SESSION_CHARS) {
+            Some((before, ch)) => format!("...{}", &transcript[before + ch.len_utf8()..]),
+            None => transcript,
+        };
+        if conversations.len() + transcript.len() > MAX_SESSIONS_CHARS {
+            left_out += 1;
+            continue;
+        }
+        conversations.push_str(&format!("### {}\n\n{}\n\n", heading, transcript));
+    }
+    if left_out > 0 {
+        conversations.push_str(&format!("({} more conversations left out)\n\n", left_out));
+    }
+    if conversations.is_empty() {
+        conversations.push_str("(none)\n\n");
+    }
+    let heartbeat = if heartbeat.is_empty() {
+        "(none)".to_string()
+    } else {
+        heartbeat.join("\n")
+    };
+
+    format!(
+        "Below are a family's conversations with their household assistant from the \
+         week of {} to {}, and the log of the assistant's scheduled tasks that week. \
+         Write the week's entry in the family journal: what happened (events, plans, \
+         news, milestones), the decisions the family made, and the follow-ups still \
+         open at the end of the week. Be specific, with names and dates (\"Leo's piano \
+         recital moved to October 21\"), and only note what the conversations and log \
+         show. Leave out small talk, routine task runs that found nothing, and secrets \
+         such as passwords. Write in the language the family used.\n\n\
+         Conversations:\n\n{}Scheduled tasks:\n{}",
+        monday.format("%A %Y-%m-%d"),
+        sunday.format("%A %Y-%m-%d"),
+        conversations,
+        heartbeat
+    )
+}
+
+/// `journal` as the week's journal file, with the frontmatter that gives
+/// its chunks journal provenance
+pub(crate) fn journal_file(week: IsoWeek, journal: &WeeklyJournal) -> String {
+    let locale = locale::current();
+    let label = week_label(week);
+    let (monday, sunday) = week_days(week);
+    let title = locale.format(
+        "journal.title",
+        &[
+            ("week", &label),
+            ("from", &locale.short_date(monday)),
+            ("to", &locale.short_date(sunday)),
+        ],
+    );
+
+    let mut content = format!(
+        "---\ncategory: journal\nprovenance: journal\nweek: {}\n---\n\n# {}\n",
+        label, title
+    );
+    for (key, items) in [
+        ("journal.happened", &journal.happened),
+        ("journal.decisions", &journal.decisions),
+        ("journal.follow_ups", &journal.follow_ups),
+    ] {
+        let items: Vec<&str> = items
+            .iter()
+            .map(|item| item.trim())
+            .filter(|item| !item.is_empty())
+            .collect();
+        if items.is_empty() {
+            continue;
+        }
+        content.push_str(&format!("\n## {}\n\n", locale.text(key)));
+        for item in items {
+            content.push_str(&format!("- {}\n", item));
+        }
+    }
+    content
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn week() -> IsoWeek {
+        NaiveDate::from_ymd_opt(2026, 10, 14).unwrap().iso_week()
+    }
+
+    #[test]
+    fn test_journal_file() {
+        assert_eq!(journal_path(week()), "memory/journal/2026-W42.md");
+        assert_eq!(
+            week_days(week()),
+            (
+                NaiveDate::from_ymd_opt(2026, 10, 12).unwrap(),
+                NaiveDate::from_ymd_opt(2026, 10, 18).unwrap()
+            )
+        );
+
+        let journal = WeeklyJournal {
+            happened: vec!["Leo's piano recital moved to October 21.".to_string()],
+            decisions: vec![" ".to_string()],
+            follow_ups: vec!["Mia to return the library books.".to_string()],
+        };
+        let content = journal_file(week(), &journal);
+        assert!(content.starts_with(
+            "---\ncategory: journal\nprovenance: journal\nweek: 2026-W42\n---\n\n# Week 2026-W42 ("
+        ));
+        assert!(content.contains("## What happened\n\n- Leo's piano recital moved"));
+        assert!(!content.contains("## Decisions"));
+        assert!(content.ends_with("## Open follow-ups\n\n- Mia to return the library books.\n"));
+    }
+
+    #[test]
+    fn test_heartbeat_entries() {
+        let log = "# Heartbeat Log\n\n\
+                   - 2026-10-11 20:00 **Chores** (900 tokens): OK\n\
+                   - 2026-10-12 08:00 **Weather** (1200 tokens): Rain all day\n\
+                   - 2026-10-18 20:00 **Chores** (800 tokens): Trash day missed\n\
+                   - 2026-10-19 08:00 **Weather** (1100 tokens): OK\n";
+        let entries = heartbeat_entries(log, week());
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].contains("Rain all day"));
+        assert!(entries[1].contains("Trash day missed"));
+
+        let prompt = journal_prompt(week(), &[], &entries);
+        assert!(prompt.contains("Monday 2026-10-12 to Sunday 2026-10-18"));
+        assert!(prompt.contains("Conversations:\n\n(none)"));
+        assert!(prompt.ends_with("Trash day missed"));
+    }
+}
@@ -0,0 +1,297 @@
+//! Usage limits: per-profile quotas enforced by the agent
+//!
+//! `[limits.profiles.<profile>]` caps how much a profile (HOMEGPT_PROFILE,
+//! "default" without one) can use: messages per hour, tokens per day and
+//! web_fetch calls per day. Counts live in `~/.homegpt/usage.json`, so every
+//! process running as the profile shares them; days follow local time.
+//! Tokens are counted for every profile, for the desktop status panel.
+
+use anyhow::Result;
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use super::get_state_dir;
+use crate::config::{Config, ProfileLimitsConfig};
+
+const USAGE_FILE: &str = "usage.json";
+
+/// Held while usage.json is read, changed and written back
+const LOCK_FILE: &str = "usage.lock";
+
+/// What a profile has used so far
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ProfileUsage {
+    /// Day the daily counts are for
+    #[serde(default)]
+    day: Option<NaiveDate>,
+    #[serde(default)]
+    tokens: u64,
+    #[serde(default)]
+    web_fetches: u32,
+    /// When messages were sent in the last hour
+    #[serde(default)]
+    messages: Vec<NaiveDateTime>,
+}
+
+impl ProfileUsage {
+    /// Drop counts from before `now`'s day and hour
+    fn roll(&mut self, now: NaiveDateTime) {
+        if self.day != Some(now.date()) {
+            self.day = Some(now.date());
+            self.tokens = 0;
+            self.web_fetches = 0;
+        }
+        let hour_ago = now - Duration::hours(1);
+        self.messages.retain(|sent| *sent > hour_ago);
+    }
+}
+
+/// The current profile's quotas and what it has used of them
+#[derive(Debug, Clone, Default)]
+pub struct UsageLimits {
+    profile: String,
+    limits: ProfileLimitsConfig,
+    /// `None` when nothing is counted (tests, or no state directory)
+    path: Option<PathBuf>,
+}
+
+impl UsageLimits {
+    /// Limits for the current profile (HOMEGPT_PROFILE)
+    pub fn from_config(config: &Config) -> Result<Self> {
+        Self::for_profile(config, Config::profile().as_deref())
+    }
+
+    pub fn for_profile(config: &Config, profile: Option<&str>) -> Result<Self> {
+        let profile = profile.unwrap_or("default");
+        Ok(Self {
+            profile: profile.to_string(),
+            limits: config
+                .limits
+                .profiles
+                .get(profile)
+                .cloned()
+                .unwrap_or_default(),
+            path: Some(get_state_dir()?.join(USAGE_FILE)),
+        })
+    }
+
+    /// Keep the counts in `path` instead of ~/.homegpt/usage.json
+    pub fn with_path(mut self, path: PathBuf) -> Self {
+        if self.path.is_some() {
+            self.path = Some(path);
+        }
+        self
+    }
+
+    /// Count a message sent at `now`, or say why it can't be: the hourly
+    /// message limit or the daily token limit is used up
+    pub fn start_message(&self, now: NaiveDateTime) -> Option<String> {
+        if let Some(reason) = self.token_hold(now) {
+            return Some(reason);
+        }
+        let limit = self.limits.messages_per_hour?;
+        let mut held = None;
+        self.update(now, |usage| {
+            if usage.messages.len() >= limit as usize {
+                let oldest = usage.messages.iter().min().copied().unwrap_or(now);
+                held = Some(format!(
+                    "The {} profile has used its {} messages for this hour. Try again after {}.",
+                    self.profile,
+                    limit,
+                    (oldest + Duration::hours(1)).format("%H:%M")
+                ));
+            } else {
+                usage.messages.push(now);
+            }
+        });
+        held
+    }
+
+    /// Why no more tokens may be spent today, if the daily limit is used up
+    pub fn token_hold(&self, now: NaiveDateTime) -> Option<String> {
+        let limit = self.limits.tokens_per_day?;
+        let mut usage = self.load().remove(&self.profile).unwrap_or_default();
+        usage.roll(now);
+        (usage.tokens >= limit).then(|| {
+            format!(
+                "The {} profile has used its {} tokens for today. It resets at midnight.",
+                self.profile, limit
+            )
+        })
+    }
+
+    /// Count `tokens` spent at `now` against the daily limit
+    pub fn add_tokens(&self, tokens: u64, now: NaiveDateTime) {
+        if tokens > 0 {
+            self.update(now, |usage| usage.tokens += tokens);
+        }
+    }
+
+    /// Tokens the profile has spent on `now`'s day
+    pub fn tokens_today(&self, now: NaiveDateTime) -> u64 {
+        let mut usage = self.load().remove(&self.profile).unwrap_or_default();
+        usage.roll(now);
+        usage.tokens
+    }
+
+    /// Count a web_fetch call at `now`, or say why it can't run
+    pub fn start_web_fetch(&self, now: NaiveDateTime) -> Option<String> {
+        let limit = self.limits.web_fetches_per_day?;
+        let mut held = None;
+        self.update(now, |usage| {
+            if usage.web_fetches >= limit {
+                held = Some(format!(
+                    "the {} profile has used its {} web fetches for today",
+                    self.profile, limit
+                ));
+            } else {
+                usage.web_fetches += 1;
+            }
+        });
+        held
+    }
+
+    fn load(&self) -> BTreeMap<String, ProfileUsage> {
+        self.path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Apply `f` to the profile's usage as of `now` and save it, holding
+    /// `usage.lock` so other processes' counts aren't overwritten. Failing
+    /// to save is logged: a full disk shouldn't stop the assistant.
+    fn update(&self, now: NaiveDateTime, f: impl FnOnce(&mut ProfileUsage)) {
+        let Some(ref path) = self.path else {
+            return;
+        };
+        let saved = File::create(path.with_file_name(LOCK_FILE))
+            .and_then(|lock| lock.lock_exclusive().map(|_| lock))
+            .map_err(anyhow::Error::from)
+            .and_then(|lock| {
+                let mut all = self.load();
+                let usage = all.entry(self.profile.clone()).or_default();
+                usage.roll(now);
+                f(usage);
+                let saved = save(path, &all);
+                let _ = lock.unlock();
+                saved
+            });
+        if let Err(e) = saved {
+            warn!("Failed to save usage to {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Write `all` to a temp file and rename it over `path`, so readers never
+/// see a half-written file
+fn save(path: &Path, all: &BTreeMap<String, ProfileUsage>) -> Result<()> {
+    let json = serde_json::to_string_pretty(all)?;
+    let tmp_path = path.with_extension(format!(
+        "{}.{}.tmp",
+        std::process::id(),
+        uuid::Uuid::new_v4().as_simple()
+    ));
+    fs::write(&tmp_path, json)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LimitsConfig;
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn limits(dir: &tempfile::TempDir, profile: Option<&str>) -> UsageLimits {
+        let config = Config {
+            limits: LimitsConfig {
+                profiles: BTreeMap::from([(
+                    "kids".to_string(),
+                    ProfileLimitsConfig {
+                        messages_per_hour: Some(2),
+                        tokens_per_day: Some(1000),
+                        web_fetches_per_day: Some(1),
+                    },
+                )]),
+            },
+            ..Config::default()
+        };
+        UsageLimits::for_profile(&config, profile)
+            .unwrap()
+            .with_path(dir.path().join(USAGE_FILE))
+    }
+
+    #[test]
+    fn test_message_and_web_limits() {
+        let dir = tempfile::tempdir().unwrap();
+        let kids = limits(&dir, Some("kids"));
+
+        assert_eq!(kids.start_message(at("2026-10-18 14:00")), None);
+        assert_eq!(kids.start_message(at("2026-10-18 14:20")), None);
+        assert_eq!(
+            kids.start_message(at("2026-10-18 14:40")).as_deref(),
+            Some("The kids profile has used its 2 messages for this hour. Try again after 15:00.")
+        );
+        assert_eq!(kids.start_message(at("2026-10-18 15:01")), None);
+
+        assert_eq!(kids.start_web_fetch(at("2026-10-18 15:01")), None);
+        assert!(kids.start_web_fetch(at("2026-10-18 15:02")).is_some());
+        assert_eq!(kids.start_web_fetch(at("2026-10-19 08:00")), None);
+
+        // Profiles without limits only count tokens
+        let default = limits(&dir, None);
+        for _ in 0..5 {
+            assert_eq!(default.start_message(at("2026-10-18 15:05")), None);
+        }
+        assert!(!kids.load().contains_key("default"));
+        default.add_tokens(250, at("2026-10-18 15:06"));
+        assert_eq!(default.token_hold(at("2026-10-18 15:07")), None);
+        assert_eq!(default.tokens_today(at("2026-10-18 16:00")), 250);
+        assert_eq!(default.tokens_today(at("2026-10-19 08:00")), 0);
+    }
+
+    #[test]
+    fn test_token_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let kids = limits(&dir, Some("kids"));
+
+        kids.add_tokens(600, at("2026-10-18 09:00"));
+        assert_eq!(kids.token_hold(at("2026-10-18 10:00")), None);
+        kids.add_tokens(600, at("2026-10-18 10:00"));
+        assert!(kids.token_hold(at("2026-10-18 11:00")).is_some());
+        assert!(kids.start_message(at("2026-10-18 11:00")).is_some());
+        // A new day starts over
+        assert_eq!(kids.token_hold(at("2026-10-19 00:01")), None);
+    }
+
+    #[test]
+    fn test_concurrent_updates_all_count() {
+        let dir = tempfile::tempdir().unwrap();
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                let default = limits(&dir, None);
+                std::thread::spawn(move || {
+                    for _ in 0..25 {
+                        default.add_tokens(1, at("2026-10-18 09:00"));
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(limits(&dir, None).tokens_today(at("2026-10-18 10:00")), 200);
+        // Only usage.json and the lock are left behind
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+}
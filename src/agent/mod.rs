@@ -1,40 +1,105 @@
+mod approval;
+mod attachments;
+mod capabilities;
+mod context_packing;
+mod extraction;
+mod file_policy;
+mod gemini;
+mod grounded;
+mod journal;
+mod limits;
+mod planning;
+mod prompt_inspect;
 mod providers;
+mod query_expansion;
+mod recovery;
+mod redact;
+mod remote_approval;
+mod replay;
+mod retrieval_trace;
+mod safety;
 mod sanitize;
+mod self_check;
 mod session;
 mod session_store;
 mod skills;
+mod structured;
 mod system_prompt;
+mod templates;
+mod tokens;
 mod tools;
-
+mod tutoring;
+
+pub use approval::{Approval, ApprovalOutcome, ApprovalPolicy, Approver};
+pub use attachments::{attach_to_message, split_attachments, Attachment};
+pub use capabilities::{cached_capabilities, Capabilities, DegradedProvider};
+pub use grounded::{Citation, GroundedAnswer};
+pub use journal::{journal_path, week_label, WeeklyJournal, JOURNAL_DIR};
+pub use limits::UsageLimits;
+pub use planning::{PlanReport, StepOutcome, StepResult};
+pub use prompt_inspect::{PromptInspection, PromptSection};
 pub use providers::{
-    ImageAttachment, LLMProvider, LLMResponse, LLMResponseContent, Message, Role, StreamChunk,
-    StreamEvent, StreamResult, ToolCall, ToolSchema, Usage,
+    create_provider, ChatProvider, ImageAttachment, LLMResponse, LLMResponseContent, Message, Role,
+    StreamChunk, StreamEvent, StreamResult, ToolCall, ToolSchema, Usage,
+};
+pub use query_expansion::QueryExpander;
+pub use recovery::RecoveryMarker;
+pub use redact::{RedactingProvider, Redactor};
+pub use remote_approval::{
+    answer_approval, pending_approval, PendingApproval, RemoteApprover, StreamApproval,
+    StreamApprover,
+};
+pub use replay::{
+    Exchange, MockChatProvider, RecordedReply, RecordedRequest, RecordingProvider, RECORD_ENV,
 };
+pub use retrieval_trace::{find_turn, load_traces, RetrievalTrace};
+pub use safety::SafetyFilter;
 pub use sanitize::{
     wrap_external_content, wrap_memory_content, wrap_tool_output, MemorySource, SanitizeResult,
     EXTERNAL_CONTENT_END, EXTERNAL_CONTENT_START, MEMORY_CONTENT_END, MEMORY_CONTENT_START,
     TOOL_OUTPUT_END, TOOL_OUTPUT_START,
 };
 pub use session::{
-    get_last_session_id, get_last_session_id_for_agent, get_sessions_dir_for_agent, get_state_dir,
-    list_sessions, list_sessions_for_agent, search_sessions, search_sessions_for_agent, Session,
-    SessionInfo, SessionMessage, SessionSearchResult, SessionStatus, DEFAULT_AGENT_ID,
+    delete_session_for_agent, get_last_session_id, get_last_session_id_for_agent,
+    get_sessions_dir_for_agent, get_state_dir, list_all_sessions, list_sessions,
+    list_sessions_for_agent, search_sessions, search_sessions_for_agent, Session, SessionInfo,
+    SessionMessage, SessionSearchResult, SessionStatus, DEFAULT_AGENT_ID,
 };
 pub use session_store::{SessionEntry, SessionStore};
-pub use skills::{get_skills_summary, load_skills, parse_skill_command, Skill, SkillInvocation};
+pub use skills::{
+    get_skills_summary, load_agent_skills, load_skills, parse_skill_command, skill_prompt, Skill,
+    SkillInvocation,
+};
+pub use structured::chat_structured;
 pub use system_prompt::{
-    build_heartbeat_prompt, is_heartbeat_ok, is_silent_reply, HEARTBEAT_OK_TOKEN,
-    SILENT_REPLY_TOKEN,
+    build_heartbeat_prompt, is_heartbeat_ok, is_silent_reply, strip_emergency, EMERGENCY_PREFIX,
+    HEARTBEAT_OK_TOKEN, SILENT_REPLY_TOKEN,
 };
-pub use tools::{extract_tool_detail, Tool, ToolResult};
+pub use templates::{render_template, PromptVars};
+pub use tokens::Tokenizer;
+pub use tools::{extract_tool_detail, extract_tool_preview, Tool, ToolResult};
+pub use tutoring::{TutorNotes, TUTOR_NOTES_PATH};
 
 use anyhow::Result;
+use chrono::IsoWeek;
+use futures::StreamExt;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
 use std::path::PathBuf;
-use std::sync::Arc;
-use tracing::{debug, info};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::{debug, info, info_span, warn, Instrument, Span};
 
+use crate::commitments::{self, CommitmentStore};
 use crate::config::Config;
-use crate::memory::{MemoryChunk, MemoryManager};
+use crate::locale;
+use crate::memory::{audit, cited_hashes, Confidence, FileWrite, MemoryManager, VerifiedChunk};
+use crate::metrics;
+use retrieval_trace::RetrievalRecorder;
+
+/// Appended to assistant replies that were cut off by the user
+pub const INTERRUPTED_MARKER: &str = "[interrupted]";
 
 /// Soft threshold buffer before compaction (tokens)
 /// Memory flush runs when within this buffer of the hard limit
@@ -58,6 +123,46 @@ fn generate_slug(text: &str) -> String {
         .collect()
 }
 
+/// Provider for a secondary model setting (`agent.summary_model`,
+/// `agent.vision_model`), or `None` to use the chat model
+fn create_secondary_provider(
+    purpose: &str,
+    model: Option<&str>,
+    chat_model: &str,
+    config: &Config,
+) -> Option<Box<dyn ChatProvider>> {
+    let model = model?;
+    if model == chat_model {
+        return None;
+    }
+    match providers::create_provider(model, config) {
+        Ok(provider) => Some(provider),
+        Err(e) => {
+            tracing::warn!(
+                "{} model {} unavailable, using {}: {}",
+                purpose,
+                model,
+                chat_model,
+                e
+            );
+            None
+        }
+    }
+}
+
+/// Whether new agents start in dry run (`--dry-run`)
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// Start every agent created from now on in dry run
+pub fn set_dry_run_default(on: bool) {
+    DRY_RUN.store(on, Ordering::Relaxed);
+}
+
+/// Whether `--dry-run` was given
+pub fn dry_run_default() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
 #[derive(Debug, Clone)]
 pub struct AgentConfig {
     pub model: String,
@@ -68,12 +173,48 @@ pub struct AgentConfig {
 pub struct Agent {
     config: AgentConfig,
     app_config: Config,
-    provider: Box<dyn LLMProvider>,
+    provider: Box<dyn ChatProvider>,
+    /// Provider for compaction summaries when `agent.summary_model` is set
+    summary_provider: Option<Box<dyn ChatProvider>>,
+    /// Provider for requests with images when `agent.vision_model` is set
+    vision_provider: Option<Box<dyn ChatProvider>>,
     session: Session,
     memory: Arc<MemoryManager>,
     tools: Vec<Box<dyn Tool>>,
+    /// Which tool calls ask the user first
+    approval: ApprovalPolicy,
+    /// Answers approval requests while the turn waits; without one the
+    /// caller asks before running tool calls (`homegpt chat`)
+    approver: Option<Arc<dyn Approver>>,
+    /// File tool calls outside the file policy the caller got a yes for,
+    /// as (tool, arguments); each runs once
+    approved_file_calls: Mutex<Vec<(String, String)>>,
+    /// Profile set with `set_profile`, for `{{active_profile}}` in SOUL.md
+    profile: Option<String>,
+    /// The profile's quotas on messages, tokens and web fetches
+    limits: UsageLimits,
+    /// Checks replies under a kid profile
+    safety: Option<SafetyFilter>,
+    /// Checks cited claims against memory when `agent.self_check` is set
+    self_check: Option<self_check::Mode>,
+    /// Tools that change things are logged and skipped instead of run
+    dry_run: bool,
+    /// Tokens this agent may use before its LLM calls are refused
+    token_budget: Option<u64>,
+    /// Tokens the tool definitions add to every request
+    tool_tokens: usize,
     /// Cumulative token usage for this session
     cumulative_usage: Usage,
+    /// The chat model's capabilities are checked on the next turn
+    capabilities_pending: bool,
+    /// Span of the turn in progress; LLM and tool calls are logged inside it
+    turn_span: Span,
+    /// When the turn in progress started, for the turn latency metric
+    turn_started: Option<Instant>,
+    /// Memory searches of each turn, saved with the session
+    retrieval: RetrievalRecorder,
+    /// Parts of the system prompt `new_session` built, for `inspect_prompt`
+    prompt_sections: Vec<(&'static str, String)>,
 }
 
 impl Agent {
@@ -83,38 +224,162 @@ impl Agent {
         memory: MemoryManager,
     ) -> Result<Self> {
         let provider = providers::create_provider(&config.model, app_config)?;
+        let mut agent = Self::with_provider(config, app_config, memory, provider).await?;
+        agent.capabilities_pending = true;
+        Ok(agent)
+    }
+
+    /// Create an agent that talks to `provider` instead of the one
+    /// `config.model` names, such as a [`MockChatProvider`] in tests
+    pub async fn with_provider(
+        config: AgentConfig,
+        app_config: &Config,
+        memory: MemoryManager,
+        provider: Box<dyn ChatProvider>,
+    ) -> Result<Self> {
+        let summary_provider = create_secondary_provider(
+            "Summary",
+            app_config.agent.summary_model.as_deref(),
+            &config.model,
+            app_config,
+        );
+        let vision_provider = create_secondary_provider(
+            "Vision",
+            app_config.agent.vision_model.as_deref(),
+            &config.model,
+            app_config,
+        );
 
         // Wrap memory in Arc so tools can share it
         let memory = Arc::new(memory);
-        let tools = tools::create_default_tools(app_config, Some(Arc::clone(&memory)))?;
-
-        Ok(Self {
+        let retrieval = RetrievalRecorder::default();
+        let tools = tools::create_default_tools(app_config, Some(Arc::clone(&memory)), &retrieval)?;
+        let approval = ApprovalPolicy::from_config(app_config)?;
+        let limits = UsageLimits::from_config(app_config)?;
+        let safety = SafetyFilter::from_config(app_config)?;
+        let self_check = self_check::Mode::parse(&app_config.agent.self_check)?;
+
+        let mut agent = Self {
             config,
             app_config: app_config.clone(),
             provider,
+            summary_provider,
+            vision_provider,
             session: Session::new(),
             memory,
             tools,
+            approval,
+            approver: None,
+            approved_file_calls: Mutex::new(Vec::new()),
+            profile: None,
+            limits,
+            safety,
+            self_check,
+            dry_run: dry_run_default(),
+            token_budget: None,
+            tool_tokens: 0,
             cumulative_usage: Usage::default(),
-        })
+            capabilities_pending: false,
+            turn_span: Span::none(),
+            turn_started: None,
+            retrieval,
+            prompt_sections: Vec::new(),
+        };
+        agent.update_tokenizer();
+        Ok(agent)
     }
 
     pub fn model(&self) -> &str {
         &self.config.model
     }
 
-    /// Check if a tool requires user approval before execution
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Turn dry run on or off for this agent's sessions
+    pub fn set_dry_run(&mut self, on: bool) {
+        self.dry_run = on;
+    }
+
+    /// Stop calling the model once this agent has used `budget` tokens, so
+    /// a runaway tool loop can't spend more (None = no cap)
+    pub fn set_token_budget(&mut self, budget: Option<u64>) {
+        self.token_budget = budget;
+    }
+
+    /// Switch to `profile`'s approvals, quotas and reply checks (None = the
+    /// default profile), e.g. when a kid picks themselves on the kitchen
+    /// tablet. The workspace stays the same.
+    pub fn set_profile(&mut self, profile: Option<&str>) -> Result<()> {
+        let config = &self.app_config;
+        self.approval = ApprovalPolicy::for_profile(config, profile)?;
+        self.limits = UsageLimits::for_profile(config, profile)?;
+        self.safety = SafetyFilter::for_profile(config, profile)?;
+        self.profile = profile.map(str::to_string);
+        Ok(())
+    }
+
+    /// Values for the variables in SOUL.md and SKILL.md, as of now
+    pub fn prompt_vars(&self) -> PromptVars {
+        PromptVars::new(
+            &self.app_config,
+            self.profile.as_deref(),
+            self.memory.persona(),
+        )
+    }
+
+    /// Keep only the tools named in `allowed`. Call before `new_session`,
+    /// whose system prompt lists the tools.
+    pub fn restrict_tools(&mut self, allowed: &[String]) {
+        for name in allowed {
+            if !self.tools.iter().any(|t| t.name() == name) {
+                warn!("Unknown tool in allowlist: {}", name);
+            }
+        }
+        self.tools
+            .retain(|t| allowed.iter().any(|name| name == t.name()));
+        self.update_tokenizer();
+    }
+
+    /// Check if a tool may require user approval before execution
     pub fn requires_approval(&self, tool_name: &str) -> bool {
-        self.app_config
-            .tools
-            .require_approval
-            .iter()
-            .any(|t| t == tool_name)
+        self.approval.may_ask(tool_name)
+    }
+
+    /// Whether this call needs the user's approval, and why
+    pub fn check_approval(&self, tool_name: &str, arguments: &str) -> Approval {
+        self.approval.check(tool_name, arguments)
     }
 
-    /// Get the list of tools that require approval
-    pub fn approval_required_tools(&self) -> &[String] {
-        &self.app_config.tools.require_approval
+    /// Record how an approval decision ended in the audit log
+    pub fn record_approval(
+        &self,
+        tool_name: &str,
+        arguments: &str,
+        approval: &Approval,
+        outcome: ApprovalOutcome,
+    ) {
+        if outcome == ApprovalOutcome::Approved
+            && self.approval.file_violation(tool_name, arguments).is_some()
+        {
+            if let Ok(mut approved) = self.approved_file_calls.lock() {
+                approved.push((tool_name.to_string(), arguments.to_string()));
+            }
+        }
+        self.approval
+            .record(self.session.id(), tool_name, arguments, approval, outcome);
+    }
+
+    /// The approval policy, for checking calls while a turn is streaming
+    pub fn approval_policy(&self) -> &ApprovalPolicy {
+        &self.approval
+    }
+
+    /// Ask `approver` before running calls the policy asks about, blocking
+    /// the turn until it answers
+    pub fn set_approver(&mut self, approver: Arc<dyn Approver>) {
+        self.approver = Some(approver);
     }
 
     /// Set additional context that gets appended to the system prompt.
@@ -129,10 +394,162 @@ impl Agent {
         let provider = providers::create_provider(model, &self.app_config)?;
         self.config.model = model.to_string();
         self.provider = provider;
+        self.summary_provider = create_secondary_provider(
+            "Summary",
+            self.app_config.agent.summary_model.as_deref(),
+            model,
+            &self.app_config,
+        );
+        self.vision_provider = create_secondary_provider(
+            "Vision",
+            self.app_config.agent.vision_model.as_deref(),
+            model,
+            &self.app_config,
+        );
+        self.capabilities_pending = true;
+        self.update_tokenizer();
         info!("Switched to model: {}", model);
         Ok(())
     }
 
+    /// The first time the chat model is used, find out what it can do
+    /// (probing it unless that's cached), and work around what it can't
+    async fn check_capabilities(&mut self) {
+        if !std::mem::take(&mut self.capabilities_pending) {
+            return;
+        }
+        let model = self.config.model.clone();
+        let capabilities = match cached_capabilities(&model) {
+            Some(capabilities) => capabilities,
+            None if self.app_config.agent.probe_capabilities && capabilities::probeable(&model) => {
+                info!("Checking what {} can do", model);
+                match capabilities::probe(&*self.provider).await {
+                    Ok(capabilities) => {
+                        if let Err(e) = capabilities::save_capabilities(&model, &capabilities) {
+                            warn!("Failed to save capabilities of {}: {}", model, e);
+                        }
+                        capabilities
+                    }
+                    Err(e) => {
+                        warn!("Couldn't check what {} can do: {:#}", model, e);
+                        return;
+                    }
+                }
+            }
+            None => return,
+        };
+
+        if capabilities.degraded() {
+            info!("{}: {}", model, capabilities.limits().join(", "));
+            match providers::create_provider(&model, &self.app_config) {
+                Ok(provider) => {
+                    self.provider = Box::new(DegradedProvider::new(provider, capabilities.clone()))
+                }
+                Err(e) => warn!("Failed to recreate provider for {}: {}", model, e),
+            }
+        }
+        if let Some(window) = capabilities.context_window {
+            if window < self.config.context_window {
+                info!(
+                    "{} has a {}-token context window; using that instead of {}",
+                    model, window, self.config.context_window
+                );
+                self.config.context_window = window;
+            }
+        }
+    }
+
+    /// Count session and tool tokens with the current model's encoding
+    fn update_tokenizer(&mut self) {
+        self.session.set_model(&self.config.model);
+        let schemas: Vec<ToolSchema> = self.tools.iter().map(|t| t.schema()).collect();
+        self.tool_tokens = self.session.tokenizer().count_tools(&schemas);
+    }
+
+    /// One-shot request for a typed result (quiz generation, fact extraction,
+    /// ...), validated against `T`'s JSON schema. Not added to the session.
+    pub async fn chat_structured<T: JsonSchema + DeserializeOwned>(
+        &self,
+        prompt: &str,
+    ) -> Result<T> {
+        let messages = [Message {
+            role: Role::User,
+            content: prompt.to_string(),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+        }];
+        structured::chat_structured(&*self.provider, &messages).await
+    }
+
+    /// Plan-then-execute: draft a step list for `instruction` with the
+    /// session's context, run each step as its own turn with tools, then
+    /// answer the instruction from the results
+    pub async fn run_plan(&mut self, instruction: &str) -> Result<PlanReport> {
+        let mut messages = self.session.messages_for_llm();
+        messages.push(Message {
+            role: Role::User,
+            content: planning::planning_prompt(instruction),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+        });
+        let plan: planning::Plan =
+            structured::chat_structured(self.provider_for(&messages), &messages).await?;
+        let steps: Vec<String> = plan
+            .steps
+            .into_iter()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+        info!("Plan drafted with {} steps", steps.len());
+
+        let mut report = PlanReport::new();
+        if steps.is_empty() {
+            return Ok(report);
+        }
+
+        let total = steps.len().min(planning::MAX_STEPS);
+        for (i, step) in steps.into_iter().enumerate() {
+            if i >= total {
+                report.steps.push(StepResult {
+                    step,
+                    outcome: StepOutcome::Skipped,
+                    result: "over the step limit".to_string(),
+                });
+                continue;
+            }
+            let (outcome, result) =
+                match self.chat(&planning::step_prompt(i + 1, total, &step)).await {
+                    Ok(reply) => planning::parse_step_reply(&reply),
+                    Err(e) => (StepOutcome::Failed, e.to_string()),
+                };
+            debug!("Plan step {}/{} {:?}: {}", i + 1, total, outcome, step);
+            report.steps.push(StepResult {
+                step,
+                outcome,
+                result,
+            });
+        }
+
+        report.reply = self.chat(planning::FINAL_PROMPT).await?;
+        Ok(report)
+    }
+
+    /// Save a text attachment into memory so later searches find it
+    pub fn save_attachment(&self, attachment: &Attachment) -> Result<PathBuf> {
+        match attachment {
+            Attachment::Text {
+                name,
+                path,
+                content,
+            } => self.memory.save_document(name, path, content),
+            Attachment::Image { name, .. } => {
+                anyhow::bail!("{} is an image; only text can be saved to memory", name)
+            }
+        }
+    }
+
     pub fn memory_chunk_count(&self) -> usize {
         self.memory.chunk_count().unwrap_or(0)
     }
@@ -141,6 +558,11 @@ impl Agent {
         self.memory.has_embeddings()
     }
 
+    /// True while the embedding model is still loading in the background
+    pub fn embeddings_loading(&self) -> bool {
+        self.memory.embeddings_loading()
+    }
+
     /// Get context window configuration
     pub fn context_window(&self) -> usize {
         self.config.context_window
@@ -153,7 +575,7 @@ impl Agent {
 
     /// Get current context usage info
     pub fn context_usage(&self) -> (usize, usize, usize) {
-        let used = self.session.token_count();
+        let used = self.context_tokens();
         let available = self.config.context_window;
         let reserve = self.config.reserve_tokens;
         let usable = available.saturating_sub(reserve);
@@ -189,16 +611,47 @@ impl Agent {
     /// Add usage from an API response to cumulative totals
     fn add_usage(&mut self, usage: Option<Usage>) {
         if let Some(u) = usage {
+            self.limits.add_tokens(
+                u.input_tokens + u.output_tokens,
+                locale::now().naive_local(),
+            );
             self.cumulative_usage.input_tokens += u.input_tokens;
             self.cumulative_usage.output_tokens += u.output_tokens;
+            self.cumulative_usage.cache_read_tokens += u.cache_read_tokens;
+            self.cumulative_usage.cache_write_tokens += u.cache_write_tokens;
+            if let Some(cost) = u.cost {
+                *self.cumulative_usage.cost.get_or_insert(0.0) += cost;
+            }
         }
     }
 
     pub async fn new_session(&mut self) -> Result<()> {
         self.session = Session::new();
+        self.session.set_model(&self.config.model);
+
+        self.prompt_sections = self.system_prompt_sections().await?;
+        let full_context = self
+            .prompt_sections
+            .iter()
+            .map(|(_, text)| text.as_str())
+            .collect();
+        self.session.set_system_context(full_context);
 
+        info!("Created new session: {}", self.session.id());
+        Ok(())
+    }
+
+    /// The system prompt for a new session, in parts: the instructions with
+    /// the skills listed in the middle, then each workspace file
+    async fn system_prompt_sections(&self) -> Result<Vec<(&'static str, String)>> {
         // Load skills from workspace
-        let workspace_skills = skills::load_skills(self.memory.workspace()).unwrap_or_default();
+        let vars = self.prompt_vars();
+        let mut workspace_skills =
+            skills::load_agent_skills(self.memory.workspace(), self.memory.persona())
+                .unwrap_or_default();
+        for skill in &mut workspace_skills {
+            skill.description = render_template(&skill.name, &skill.description, &vars);
+        }
         let skills_prompt = skills::build_skills_prompt(&workspace_skills);
         debug!("Loaded {} skills from workspace", workspace_skills.len());
 
@@ -207,34 +660,108 @@ impl Agent {
         let system_prompt_params =
             system_prompt::SystemPromptParams::new(self.memory.workspace(), &self.config.model)
                 .with_tools(tool_names)
-                .with_skills_prompt(skills_prompt);
+                .with_skills_prompt(skills_prompt.clone());
         let system_prompt = system_prompt::build_system_prompt(system_prompt_params);
 
-        // Load memory context (SOUL.md, MEMORY.md, daily logs, HEARTBEAT.md)
-        let memory_context = self.build_memory_context().await?;
-
-        // Combine system prompt with memory context
-        let full_context = if memory_context.is_empty() {
-            system_prompt
-        } else {
-            format!(
-                "{}\n\n---\n\n# Workspace Context\n\n{}",
-                system_prompt, memory_context
-            )
-        };
+        let mut sections = Vec::new();
+        match system_prompt
+            .split_once(skills_prompt.as_str())
+            .filter(|_| !skills_prompt.is_empty())
+        {
+            Some((before, after)) => {
+                sections.push(("Instructions", before.to_string()));
+                sections.push(("Skills", skills_prompt.clone()));
+                sections.push(("Workspace and memory", after.to_string()));
+            }
+            None => sections.push(("Instructions", system_prompt.clone())),
+        }
 
-        self.session.set_system_context(full_context);
+        // Load memory context (SOUL.md, MEMORY.md, daily logs, HEARTBEAT.md)
+        let memory_context = self.memory_context_sections().await?;
+        if !memory_context.is_empty() {
+            if let Some((_, last)) = sections.last_mut() {
+                last.push_str("\n\n---\n\n# Workspace Context\n\n");
+            }
+            sections.extend(memory_context);
+        }
+        Ok(sections)
+    }
 
-        info!("Created new session: {}", self.session.id());
-        Ok(())
+    /// What the next turn sends before the user's message: the session's
+    /// system prompt by part, the tool definitions and the conversation so
+    /// far, with token counts. A resumed session's prompt is one part.
+    pub fn inspect_prompt(&self) -> PromptInspection {
+        let tokenizer = self.session.tokenizer();
+        let mut sections = Vec::new();
+        if let Some(context) = self.session.system_context() {
+            let built: String = self
+                .prompt_sections
+                .iter()
+                .map(|(_, text)| text.as_str())
+                .collect();
+            if built == context {
+                for (name, text) in &self.prompt_sections {
+                    sections.push(PromptSection::new(name, text.clone(), tokenizer));
+                }
+            } else {
+                sections.push(PromptSection::new(
+                    "System prompt",
+                    context.to_string(),
+                    tokenizer,
+                ));
+            }
+        }
+        if let Some(extra) = self.session.additional_context() {
+            sections.push(PromptSection::new(
+                "Additional context",
+                format!("\n\n---\n\n# Additional Context\n\n{}", extra),
+                tokenizer,
+            ));
+        }
+        let messages = self.session.messages();
+        PromptInspection {
+            session_id: self.session.id().to_string(),
+            model: self.config.model.clone(),
+            sections,
+            tool_tokens: self.tool_tokens,
+            history_tokens: messages.iter().map(|m| tokenizer.count_message(m)).sum(),
+            history_messages: messages.len(),
+            context_window: self.config.context_window,
+        }
     }
 
     pub async fn resume_session(&mut self, session_id: &str) -> Result<()> {
         self.session = Session::load(session_id)?;
+        self.session.set_model(&self.config.model);
         info!("Resumed session: {}", session_id);
         Ok(())
     }
 
+    /// Reopen the session a recovery marker was left for. If the app was
+    /// killed before saving, the interrupted exchange is added from the
+    /// marker, into a new session when the original was never saved.
+    pub async fn restore_session(&mut self, marker: &RecoveryMarker) -> Result<()> {
+        if let Err(e) = self.resume_session(&marker.session_id).await {
+            if marker.saved {
+                return Err(e);
+            }
+            debug!("Restoring interrupted turn into a new session: {}", e);
+            self.new_session().await?;
+        }
+        if !marker.saved {
+            self.session.add_message(Message {
+                role: Role::User,
+                content: marker.message.clone(),
+                tool_calls: None,
+                tool_call_id: None,
+                images: Vec::new(),
+            });
+            self.interrupt_turn(&marker.partial);
+        }
+        self.session.save()?;
+        Ok(())
+    }
+
     pub async fn chat(&mut self, message: &str) -> Result<String> {
         self.chat_with_images(message, Vec::new()).await
     }
@@ -244,6 +771,10 @@ impl Agent {
         message: &str,
         images: Vec<ImageAttachment>,
     ) -> Result<String> {
+        self.start_message()?;
+        self.begin_turn(message);
+        self.check_capabilities().await;
+
         // Add user message with images
         self.session.add_message(Message {
             role: Role::User,
@@ -271,13 +802,11 @@ impl Agent {
         let tool_schemas: Vec<ToolSchema> = self.tools.iter().map(|t| t.schema()).collect();
 
         // Invoke LLM
-        let response = self
-            .provider
-            .chat(&messages, Some(tool_schemas.as_slice()))
-            .await?;
+        let response = self.complete(&messages, &tool_schemas).await?;
 
         // Handle tool calls if any
         let final_response = self.handle_response(response).await?;
+        let final_response = self.filter_reply(final_response).await;
 
         // Add assistant response
         self.session.add_message(Message {
@@ -288,6 +817,7 @@ impl Agent {
             images: Vec::new(),
         });
 
+        self.end_turn();
         Ok(final_response)
     }
 
@@ -337,10 +867,7 @@ impl Agent {
                 // Continue conversation with tool results
                 let messages = self.session.messages_for_llm();
                 let tool_schemas: Vec<ToolSchema> = self.tools.iter().map(|t| t.schema()).collect();
-                let next_response = self
-                    .provider
-                    .chat(&messages, Some(tool_schemas.as_slice()))
-                    .await?;
+                let next_response = self.complete(&messages, &tool_schemas).await?;
 
                 // Recursively handle (in case of more tool calls)
                 Box::pin(self.handle_response(next_response)).await
@@ -349,9 +876,92 @@ impl Agent {
     }
 
     async fn execute_tool(&self, call: &ToolCall) -> Result<String> {
+        if let Some(reason) = self.approval.file_violation(&call.name, &call.arguments) {
+            // Under files.on_violation = "ask" the approver below asks;
+            // without one, the caller must already have had a yes
+            let asked = self.approval.asks_on_file_violation()
+                && (self.approver.is_some() || self.take_approved_file_call(call));
+            if !asked {
+                warn!("Refused {}: {}", call.name, reason);
+                return Ok(format!("Not run: {}.", reason));
+            }
+        }
+        if let Some(ref approver) = self.approver {
+            let approval = self.approval.check(&call.name, &call.arguments);
+            let outcome = match approval {
+                Approval::Ask(ref reason) => {
+                    approver.approve(&call.name, &call.arguments, reason).await
+                }
+                Approval::Allow(_) => ApprovalOutcome::Allowed,
+            };
+            self.record_approval(&call.name, &call.arguments, &approval, outcome);
+            match outcome {
+                ApprovalOutcome::Denied => {
+                    return Ok(format!("Not run: the user denied {}.", call.name))
+                }
+                ApprovalOutcome::TimedOut => {
+                    return Ok(format!("Not run: nobody approved {} in time.", call.name))
+                }
+                _ => {}
+            }
+        }
+        if call.name == "web_fetch" {
+            if let Some(reason) = self.limits.start_web_fetch(locale::now().naive_local()) {
+                return Ok(format!("Not run: {}.", reason));
+            }
+        }
+
+        let span = info_span!(
+            parent: &self.turn_span,
+            "tool_call",
+            tool = %call.name,
+            call_id = %call.id
+        );
+        let started = Instant::now();
+        let result = self.run_tool(call).instrument(span.clone()).await;
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+        // Names the model made up are counted together
+        let known = self.tools.iter().any(|t| t.name() == call.name);
+        metrics::record_tool_call(if known { &call.name } else { "unknown" }, result.is_ok());
+        span.in_scope(|| match &result {
+            Ok(_) => info!(elapsed_ms, "Tool {} finished", call.name),
+            Err(e) => warn!(elapsed_ms, "Tool {} failed: {:#}", call.name, e),
+        });
+        result
+    }
+
+    /// Whether `call` was approved with `record_approval`, forgetting it
+    fn take_approved_file_call(&self, call: &ToolCall) -> bool {
+        let Ok(mut approved) = self.approved_file_calls.lock() else {
+            return false;
+        };
+        match approved
+            .iter()
+            .position(|(name, args)| *name == call.name && *args == call.arguments)
+        {
+            Some(i) => {
+                approved.remove(i);
+                true
+            }
+            None => false,
+        }
+    }
+
+    async fn run_tool(&self, call: &ToolCall) -> Result<String> {
         for tool in &self.tools {
             if tool.name() == call.name {
-                let raw_output = tool.execute(&call.arguments).await?;
+                if self.dry_run && tool.mutates() {
+                    info!("Dry run: skipped {} {}", call.name, call.arguments);
+                    return Ok(format!(
+                        "[Dry run] {} was not run; nothing was changed. Continue as if it \
+                         had succeeded.",
+                        call.name
+                    ));
+                }
+
+                // Memory reads/writes made by the tool are audited under this session
+                let raw_output =
+                    audit::with_session(self.session.id(), tool.execute(&call.arguments)).await?;
 
                 // Apply sanitization if configured
                 if self.app_config.tools.use_content_delimiters {
@@ -380,20 +990,22 @@ impl Agent {
         anyhow::bail!("Unknown tool: {}", call.name)
     }
 
-    async fn build_memory_context(&self) -> Result<String> {
-        let mut context = String::new();
+    /// The workspace files loaded into a new session, each with its heading
+    /// and separator, in prompt order
+    async fn memory_context_sections(&self) -> Result<Vec<(&'static str, String)>> {
+        let mut sections = Vec::new();
         let use_delimiters = self.app_config.tools.use_content_delimiters;
 
         // Show welcome message on brand new workspace (first run)
         if self.memory.is_brand_new() {
-            context.push_str(FIRST_RUN_WELCOME);
-            context.push_str("\n\n---\n\n");
+            sections.push(("Welcome", format!("{}\n\n---\n\n", FIRST_RUN_WELCOME)));
             info!("First run detected - showing welcome message");
         }
 
         // Load IDENTITY.md first (OpenClaw-compatible: agent identity context)
         if let Ok(identity_content) = self.memory.read_identity_file() {
             if !identity_content.is_empty() {
+                let mut context = String::new();
                 if use_delimiters {
                     context.push_str(&sanitize::wrap_memory_content(
                         "IDENTITY.md",
@@ -405,12 +1017,14 @@ impl Agent {
                     context.push_str(&identity_content);
                 }
                 context.push_str("\n\n---\n\n");
+                sections.push(("IDENTITY.md", context));
             }
         }
 
         // Load USER.md (OpenClaw-compatible: user info)
         if let Ok(user_content) = self.memory.read_user_file() {
             if !user_content.is_empty() {
+                let mut context = String::new();
                 if use_delimiters {
                     context.push_str(&sanitize::wrap_memory_content(
                         "USER.md",
@@ -422,12 +1036,15 @@ impl Agent {
                     context.push_str(&user_content);
                 }
                 context.push_str("\n\n---\n\n");
+                sections.push(("USER.md", context));
             }
         }
 
         // Load SOUL.md (persona/tone) - this defines who the agent is
         if let Ok(soul_content) = self.memory.read_soul_file() {
+            let soul_content = render_template("SOUL.md", &soul_content, &self.prompt_vars());
             if !soul_content.is_empty() {
+                let mut context = String::new();
                 if use_delimiters {
                     context.push_str(&sanitize::wrap_memory_content(
                         "SOUL.md",
@@ -438,12 +1055,14 @@ impl Agent {
                     context.push_str(&soul_content);
                 }
                 context.push_str("\n\n---\n\n");
+                sections.push(("SOUL.md", context));
             }
         }
 
         // Load AGENTS.md (OpenClaw-compatible: list of connected agents)
         if let Ok(agents_content) = self.memory.read_agents_file() {
             if !agents_content.is_empty() {
+                let mut context = String::new();
                 if use_delimiters {
                     context.push_str(&sanitize::wrap_memory_content(
                         "AGENTS.md",
@@ -455,12 +1074,14 @@ impl Agent {
                     context.push_str(&agents_content);
                 }
                 context.push_str("\n\n---\n\n");
+                sections.push(("AGENTS.md", context));
             }
         }
 
         // Load TOOLS.md (OpenClaw-compatible: local tool notes)
         if let Ok(tools_content) = self.memory.read_tools_file() {
             if !tools_content.is_empty() {
+                let mut context = String::new();
                 if use_delimiters {
                     context.push_str(&sanitize::wrap_memory_content(
                         "TOOLS.md",
@@ -472,12 +1093,14 @@ impl Agent {
                     context.push_str(&tools_content);
                 }
                 context.push_str("\n\n---\n\n");
+                sections.push(("TOOLS.md", context));
             }
         }
 
         // Load MEMORY.md if it exists
         if let Ok(memory_content) = self.memory.read_memory_file() {
             if !memory_content.is_empty() {
+                let mut context = String::new();
                 if use_delimiters {
                     context.push_str(&sanitize::wrap_memory_content(
                         "MEMORY.md",
@@ -489,12 +1112,14 @@ impl Agent {
                     context.push_str(&memory_content);
                 }
                 context.push_str("\n\n");
+                sections.push(("MEMORY.md", context));
             }
         }
 
         // Load today's and yesterday's daily logs
         if let Ok(recent_logs) = self.memory.read_recent_daily_logs(2) {
             if !recent_logs.is_empty() {
+                let mut context = String::new();
                 if use_delimiters {
                     context.push_str(&sanitize::wrap_memory_content(
                         "memory/*.md",
@@ -506,12 +1131,14 @@ impl Agent {
                     context.push_str(&recent_logs);
                 }
                 context.push_str("\n\n");
+                sections.push(("Daily logs", context));
             }
         }
 
         // Load HEARTBEAT.md if it exists
         if let Ok(heartbeat) = self.memory.read_heartbeat_file() {
             if !heartbeat.is_empty() {
+                let mut context = String::new();
                 if use_delimiters {
                     context.push_str(&sanitize::wrap_memory_content(
                         "HEARTBEAT.md",
@@ -523,14 +1150,20 @@ impl Agent {
                     context.push_str(&heartbeat);
                 }
                 context.push('\n');
+                sections.push(("HEARTBEAT.md", context));
             }
         }
 
-        Ok(context)
+        Ok(sections)
+    }
+
+    /// Tokens the next request will send: system prompt, history and tools
+    fn context_tokens(&self) -> usize {
+        self.session.token_count() + self.tool_tokens
     }
 
     fn should_compact(&self) -> bool {
-        self.session.token_count() > (self.config.context_window - self.config.reserve_tokens)
+        self.context_tokens() > (self.config.context_window - self.config.reserve_tokens)
     }
 
     /// Check if we should run pre-compaction memory flush (soft threshold)
@@ -538,7 +1171,7 @@ impl Agent {
         let hard_limit = self.config.context_window - self.config.reserve_tokens;
         let soft_limit = hard_limit.saturating_sub(MEMORY_FLUSH_SOFT_THRESHOLD);
 
-        self.session.token_count() > soft_limit && self.session.should_memory_flush()
+        self.context_tokens() > soft_limit && self.session.should_memory_flush()
     }
 
     pub async fn compact_session(&mut self) -> Result<(usize, usize)> {
@@ -550,7 +1183,8 @@ impl Agent {
         }
 
         // Compact the session
-        self.session.compact(&*self.provider).await?;
+        let summarizer = self.summary_provider.as_deref().unwrap_or(&*self.provider);
+        self.session.compact(summarizer).await?;
 
         let after = self.session.token_count();
         info!("Session compacted: {} -> {} tokens", before, after);
@@ -564,7 +1198,7 @@ impl Agent {
         // Mark as flushed for this compaction cycle (prevents running twice)
         self.session.mark_memory_flushed();
 
-        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+        let today = locale::now().format("%Y-%m-%d").to_string();
         let flush_prompt = format!(
             "Pre-compaction memory flush. Session nearing token limit.\n\
              Store durable memories now (use memory/{}.md; create memory/ if needed).\n\
@@ -587,7 +1221,7 @@ impl Agent {
         let tool_schemas: Vec<ToolSchema> = self.tools.iter().map(|t| t.schema()).collect();
         let messages = self.session.messages_for_llm();
 
-        let response = self.provider.chat(&messages, Some(&tool_schemas)).await?;
+        let response = self.complete(&messages, &tool_schemas).await?;
 
         // Handle response (may include tool calls)
         let final_response = self.handle_response(response).await?;
@@ -643,7 +1277,7 @@ impl Agent {
             .map(|m| generate_slug(&m.content))
             .unwrap_or_else(|| "session".to_string());
 
-        let now = chrono::Local::now();
+        let now = locale::now();
         let date_str = now.format("%Y-%m-%d").to_string();
         let time_str = now.format("%H:%M:%S").to_string();
 
@@ -680,8 +1314,10 @@ impl Agent {
         let memory_dir = self.memory.workspace().join("memory");
         std::fs::create_dir_all(&memory_dir)?;
 
-        let filename = format!("{}-{}.md", date_str, slug);
-        let path = memory_dir.join(&filename);
+        // Another session may have saved under the same slug today
+        let _writes = self.memory.lock_writes().await;
+        let path =
+            MemoryManager::unused_path(&memory_dir.join(format!("{}-{}.md", date_str, slug)));
 
         debug!(
             "save_session_to_memory: writing {} bytes to {}",
@@ -691,40 +1327,289 @@ impl Agent {
         std::fs::write(&path, content)?;
         info!("Saved session to memory: {}", path.display());
 
+        audit::with_session(self.session.id(), async {
+            self.memory
+                .record_write(&path, "session_memory", "session-transcript")
+        })
+        .await;
+
         Ok(Some(path))
     }
 
+    /// Propose durable facts from the part of the session not read yet,
+    /// for `homegpt memory review`. Does nothing unless
+    /// `memory.extract_facts` is on. Returns how many facts were proposed.
+    pub async fn extract_memories(&self) -> Result<usize> {
+        if !self.app_config.memory.extract_facts {
+            return Ok(0);
+        }
+        let session_id = self.session.id();
+        let messages = self.session.user_assistant_messages();
+        // Compaction can leave fewer messages than were read; start over then
+        let read = self.memory.proposals_read(session_id)?;
+        let start = if read <= messages.len() { read } else { 0 };
+        let new = &messages[start..];
+        if !new.iter().any(|m| m.role == Role::User) {
+            return Ok(0);
+        }
+
+        let today = locale::now().format("%A %Y-%m-%d").to_string();
+        let prompt = extraction::extraction_prompt(&extraction::transcript(new), &today);
+        let request = [Message {
+            role: Role::User,
+            content: prompt,
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+        }];
+        let extractor = self.summary_provider.as_deref().unwrap_or(&*self.provider);
+        let found: extraction::ExtractedFacts =
+            structured::chat_structured(extractor, &request).await?;
+
+        let proposed = self
+            .memory
+            .propose_facts(session_id, messages.len(), &found.facts)?;
+        if !proposed.is_empty() {
+            info!(
+                "Proposed {} facts from session {} for review",
+                proposed.len(),
+                session_id
+            );
+        }
+        Ok(proposed.len())
+    }
+
+    /// Track what the assistant said it would do later in the part of the
+    /// session not read yet, for the heartbeat to check on. Does nothing
+    /// unless `memory.track_commitments` is on. Returns how many were added.
+    pub async fn track_commitments(&self) -> Result<usize> {
+        if !self.app_config.memory.track_commitments {
+            return Ok(0);
+        }
+        let store = CommitmentStore::open(&get_state_dir()?)?;
+        let session_id = self.session.id();
+        let messages = self.session.user_assistant_messages();
+        // Compaction can leave fewer messages than were read; start over then
+        let read = store.read_count(session_id)?;
+        let start = if read <= messages.len() { read } else { 0 };
+        let new = &messages[start..];
+        let replies = new
+            .iter()
+            .filter(|m| m.role == Role::Assistant)
+            .map(|m| m.content.as_str());
+        if !commitments::mentions_promise(replies) {
+            store.set_read(session_id, messages.len())?;
+            return Ok(0);
+        }
+
+        let today = locale::now().format("%A %Y-%m-%d").to_string();
+        let prompt = commitments::extraction_prompt(&extraction::transcript(new), &today);
+        let request = [Message {
+            role: Role::User,
+            content: prompt,
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+        }];
+        let extractor = self.summary_provider.as_deref().unwrap_or(&*self.provider);
+        let found: commitments::ExtractedCommitments =
+            structured::chat_structured(extractor, &request).await?;
+
+        let mut added = 0;
+        for (promise, due) in commitments::parse_extracted(found) {
+            if let Some(commitment) = store.add(&promise, due, session_id)? {
+                debug!("Tracking commitment {}", commitment.summary());
+                added += 1;
+            }
+        }
+        store.set_read(session_id, messages.len())?;
+        if added > 0 {
+            info!("Tracking {} commitments from session {}", added, session_id);
+        }
+        Ok(added)
+    }
+
+    /// Answer `question` strictly from verified memory, with the chunks it
+    /// came from, or abstain when nothing verified at
+    /// `memory.answer_min_confidence` backs an answer. The session is left
+    /// alone; frontends decide whether to speak the answer.
+    pub async fn answer_grounded(&self, question: &str) -> Result<GroundedAnswer> {
+        let min: Confidence = self.app_config.memory.answer_min_confidence.parse()?;
+        let found = self
+            .memory
+            .search_verified(question, grounded::RETRIEVAL_LIMIT)?;
+        let chunks = grounded::usable(found, min);
+        if chunks.is_empty() {
+            debug!("No verified memory at {} or above, abstaining", min);
+            return Ok(GroundedAnswer::abstain());
+        }
+
+        let request = [Message {
+            role: Role::User,
+            content: grounded::prompt(question, &chunks),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+        }];
+        let reply: grounded::GroundedReply =
+            structured::chat_structured(&*self.provider, &request).await?;
+        let answer = grounded::resolve(reply, &chunks);
+        debug!(
+            "Grounded answer: abstained {}, {} citations, confidence {}",
+            answer.abstained,
+            answer.citations.len(),
+            answer.confidence
+        );
+        Ok(answer)
+    }
+
+    /// Add notes on this tutoring session to the tutor notes file, written
+    /// by the summary model. With `memory.staging` on they wait for review
+    /// unless an auto-approve rule covers the file. `None` if nothing was
+    /// said yet.
+    pub async fn log_tutor_session(&self, student: Option<&str>) -> Result<Option<FileWrite>> {
+        let messages = self.session.user_assistant_messages();
+        if !messages.iter().any(|m| m.role == Role::User) {
+            return Ok(None);
+        }
+
+        let prompt = tutoring::notes_prompt(&extraction::transcript(&messages), student);
+        let request = [Message {
+            role: Role::User,
+            content: prompt,
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+        }];
+        let writer = self.summary_provider.as_deref().unwrap_or(&*self.provider);
+        let notes: TutorNotes = structured::chat_structured(writer, &request).await?;
+
+        let workspace = self.memory.workspace();
+        let path = locale::current().localized_path(&workspace.join(TUTOR_NOTES_PATH));
+        // Missing until the first session is logged
+        let _writes = self.memory.lock_writes().await;
+        let existing = self.memory.read_file(&path).unwrap_or_default();
+        let entry = tutoring::notes_entry(&notes, student, locale::now().naive_local());
+        let content = tutoring::append_entry(&existing, &entry);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let write = audit::with_session(self.session.id(), async {
+            self.memory
+                .write_file(&path, &content, "tutor_notes", "session-summary")
+        })
+        .await?;
+        info!(
+            "Logged tutoring session {} to {}",
+            self.session.id(),
+            path.display()
+        );
+        Ok(Some(write))
+    }
+
+    /// Write `week`'s family journal from that week's saved sessions, of
+    /// every agent, and the heartbeat task log, by the summary model. With
+    /// `memory.staging` on it waits for review unless an auto-approve rule
+    /// covers the file. `None` if nothing happened that week.
+    pub async fn write_journal(
+        &self,
+        week: IsoWeek,
+        heartbeat_log: &str,
+    ) -> Result<Option<(PathBuf, FileWrite)>> {
+        let sessions = journal::week_sessions(week)?;
+        let heartbeat = journal::heartbeat_entries(heartbeat_log, week);
+        if sessions.is_empty() && heartbeat.is_empty() {
+            return Ok(None);
+        }
+
+        let request = [Message {
+            role: Role::User,
+            content: journal::journal_prompt(week, &sessions, &heartbeat),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+        }];
+        let writer = self.summary_provider.as_deref().unwrap_or(&*self.provider);
+        let entry: WeeklyJournal = structured::chat_structured(writer, &request).await?;
+
+        let path = self.memory.workspace().join(journal_path(week));
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = journal::journal_file(week, &entry);
+        let write = self
+            .memory
+            .write_file(&path, &content, "weekly_journal", "journal")?;
+        info!(
+            "Wrote the journal for {} from {} sessions",
+            week_label(week),
+            sessions.len()
+        );
+        Ok(Some((path, write)))
+    }
+
     pub fn clear_session(&mut self) {
         self.session = Session::new();
+        self.session.set_model(&self.config.model);
     }
 
-    pub async fn search_memory(&self, query: &str) -> Result<Vec<MemoryChunk>> {
-        self.memory.search(query, 10)
+    /// Hybrid search for `/memory`, with each result's citation and
+    /// verification; no model call
+    pub async fn search_memory(&self, query: &str) -> Result<Vec<VerifiedChunk>> {
+        audit::with_session(self.session.id(), async {
+            self.memory.search_verified(query, 10)
+        })
+        .await
+    }
+
+    /// Location (`path:start-end`) of the chunk a citation points at
+    pub fn citation_source(&self, hash_prefix: &str) -> Option<String> {
+        match self.memory.cited_chunk(hash_prefix) {
+            Ok(chunk) => chunk.map(|c| c.location()),
+            Err(e) => {
+                warn!("Failed to look up citation {}: {}", hash_prefix, e);
+                None
+            }
+        }
     }
 
     pub async fn reindex_memory(&self) -> Result<(usize, usize, usize)> {
         let stats = self.memory.reindex(true)?;
 
         // Generate embeddings for new chunks (if embedding provider is configured)
-        let (_, embedded) = self.memory.generate_embeddings(50).await?;
+        let (_, embedded) = self.memory.generate_embeddings().await?;
 
         Ok((stats.files_processed, stats.chunks_indexed, embedded))
     }
 
     pub async fn save_session(&self) -> Result<PathBuf> {
+        self.save_retrieval_traces(DEFAULT_AGENT_ID);
         self.session.save()
     }
 
     /// Save session for a specific agent ID (used by HTTP server)
     pub async fn save_session_for_agent(&self, agent_id: &str) -> Result<PathBuf> {
+        self.save_retrieval_traces(agent_id);
         self.session.save_for_agent(agent_id)
     }
 
+    /// Append finished turns' retrieval traces next to the session; a
+    /// failure only loses the traces
+    fn save_retrieval_traces(&self, agent_id: &str) {
+        let saved = get_sessions_dir_for_agent(agent_id).and_then(|dir| self.retrieval.save(&dir));
+        if let Err(e) = saved {
+            warn!("Failed to save retrieval traces: {}", e);
+        }
+    }
+
+    /// The memory retrieval of each turn of this session, oldest first
+    pub fn retrieval_traces(&self, agent_id: &str) -> Result<Vec<RetrievalTrace>> {
+        let dir = get_sessions_dir_for_agent(agent_id)?;
+        self.retrieval.traces(&dir, self.session.id())
+    }
+
     pub fn session_status(&self) -> SessionStatus {
-        self.session.status_with_usage(
-            self.cumulative_usage.input_tokens,
-            self.cumulative_usage.output_tokens,
-        )
+        self.session.status_with_usage(&self.cumulative_usage)
     }
 
     /// Stream chat response - returns a stream of chunks
@@ -740,6 +1625,10 @@ impl Agent {
         message: &str,
         images: Vec<ImageAttachment>,
     ) -> Result<StreamResult> {
+        self.start_message()?;
+        self.begin_turn(message);
+        self.check_capabilities().await;
+
         // Add user message with images
         self.session.add_message(Message {
             role: Role::User,
@@ -766,14 +1655,49 @@ impl Agent {
         // Get tool schemas so the model knows the correct tool call format
         let tool_schemas: Vec<ToolSchema> = self.tools.iter().map(|t| t.schema()).collect();
 
+        // Streams don't report usage; count the request's size instead
+        self.limits
+            .add_tokens(self.context_tokens() as u64, locale::now().naive_local());
+
         // Get stream from provider with tools
-        self.provider
+        let stream = self
+            .provider_for(&messages)
             .chat_stream(&messages, Some(&tool_schemas))
-            .await
+            .instrument(self.llm_span(&messages))
+            .await?;
+        if self.safety.is_none() && self.self_check.is_none() {
+            return Ok(stream);
+        }
+
+        // The whole reply is checked before any of it is shown
+        let mut stream = stream;
+        let mut text = String::new();
+        let mut tool_calls = None;
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            text.push_str(&chunk.delta);
+            if chunk.tool_calls.is_some() {
+                tool_calls = chunk.tool_calls;
+            }
+        }
+        // Text alongside tool calls is checked with the final reply
+        if tool_calls.is_none() {
+            text = self.filter_reply(text).await;
+        }
+        Ok(Box::pin(futures::stream::once(async move {
+            Ok(StreamChunk {
+                delta: text,
+                done: true,
+                tool_calls,
+            })
+        })))
     }
 
     /// Complete a streaming chat by adding the assistant response to the session
     pub fn finish_chat_stream(&mut self, response: &str) {
+        let tokens = self.session.tokenizer().count(response);
+        self.limits
+            .add_tokens(tokens as u64, locale::now().naive_local());
         self.session.add_message(Message {
             role: Role::Assistant,
             content: response.to_string(),
@@ -781,6 +1705,7 @@ impl Agent {
             tool_call_id: None,
             images: Vec::new(),
         });
+        self.end_turn();
     }
 
     /// Execute tool calls that were accumulated during streaming
@@ -828,13 +1753,11 @@ impl Agent {
         // Get follow-up response from LLM
         let messages = self.session.messages_for_llm();
         let tool_schemas: Vec<ToolSchema> = self.tools.iter().map(|t| t.schema()).collect();
-        let response = self
-            .provider
-            .chat(&messages, Some(tool_schemas.as_slice()))
-            .await?;
+        let response = self.complete(&messages, &tool_schemas).await?;
 
         // Handle the response (may have more tool calls)
         let final_response = self.handle_response(response).await?;
+        let final_response = self.filter_reply(final_response).await;
 
         // Add final response to session
         self.session.add_message(Message {
@@ -845,14 +1768,138 @@ impl Agent {
             images: Vec::new(),
         });
 
+        self.end_turn();
         Ok(final_response)
     }
 
     /// Get a reference to the LLM provider for streaming
-    pub fn provider(&self) -> &dyn LLMProvider {
+    pub fn provider(&self) -> &dyn ChatProvider {
         &*self.provider
     }
 
+    /// `reply` after the self-check, or what the safety filter replaces it
+    /// with
+    async fn filter_reply(&self, reply: String) -> String {
+        let reply = self.check_claims(reply).await;
+        match self.safety {
+            Some(ref safety) => safety.filter(&reply, &*self.provider).await,
+            None => reply,
+        }
+    }
+
+    /// `reply`, revised or flagged if the memory it cites doesn't back it
+    async fn check_claims(&self, reply: String) -> String {
+        let Some(mode) = self.self_check else {
+            return reply;
+        };
+        let hashes = cited_hashes(&reply);
+        if hashes.is_empty() {
+            return reply;
+        }
+        let mut sources = Vec::new();
+        for hash in hashes {
+            let text = match self.memory.cited_chunk(&hash) {
+                Ok(chunk) => chunk.map(|c| c.text),
+                Err(e) => {
+                    warn!("Failed to look up citation {}: {}", hash, e);
+                    return reply;
+                }
+            };
+            sources.push(self_check::Source { hash, text });
+        }
+        let checker = self.summary_provider.as_deref().unwrap_or(&*self.provider);
+        self_check::check(checker, mode, &reply, &sources).await
+    }
+
+    /// Count a new message against the profile's limits, failing with why
+    /// if it's over them
+    fn start_message(&self) -> Result<()> {
+        match self.limits.start_message(locale::now().naive_local()) {
+            Some(reason) => anyhow::bail!(reason),
+            None => Ok(()),
+        }
+    }
+
+    /// Start the span a new turn's LLM and tool calls are logged under, and
+    /// its retrieval trace
+    fn begin_turn(&mut self, message: &str) {
+        let turn_id = uuid::Uuid::new_v4().simple().to_string();
+        self.turn_span = info_span!(
+            "turn",
+            session_id = %self.session.id(),
+            turn_id = %&turn_id[..8]
+        );
+        self.turn_started = Some(Instant::now());
+        self.retrieval
+            .begin_turn(self.session.id(), &turn_id[..8], message);
+    }
+
+    /// Record the turn's latency once its reply is in the session
+    fn end_turn(&mut self) {
+        if let Some(started) = self.turn_started.take() {
+            metrics::record_turn(started.elapsed());
+        }
+        self.retrieval.end_turn();
+    }
+
+    fn llm_span(&self, messages: &[Message]) -> Span {
+        info_span!(
+            parent: &self.turn_span,
+            "llm_call",
+            model = %self.config.model,
+            messages = messages.len()
+        )
+    }
+
+    /// Ask the model for the next response, inside an `llm_call` span
+    async fn complete(&self, messages: &[Message], tools: &[ToolSchema]) -> Result<LLMResponse> {
+        // Also stops a long tool loop once the day's tokens are spent
+        if let Some(reason) = self.limits.token_hold(locale::now().naive_local()) {
+            anyhow::bail!(reason);
+        }
+        if let Some(budget) = self.token_budget {
+            let used = self.cumulative_usage.input_tokens + self.cumulative_usage.output_tokens;
+            if used >= budget {
+                anyhow::bail!(
+                    "Stopped after {} tokens, over this session's budget of {}",
+                    used,
+                    budget
+                );
+            }
+        }
+        let span = self.llm_span(messages);
+        let started = Instant::now();
+        let response = self
+            .provider_for(messages)
+            .chat(messages, Some(tools))
+            .instrument(span.clone())
+            .await;
+        let elapsed = started.elapsed();
+        let elapsed_ms = elapsed.as_millis() as u64;
+        span.in_scope(|| match &response {
+            Ok(r) => {
+                let input_tokens = r.usage.as_ref().map_or(0, |u| u.input_tokens);
+                let output_tokens = r.usage.as_ref().map_or(0, |u| u.output_tokens);
+                metrics::record_llm_call(elapsed, input_tokens, output_tokens);
+                info!(elapsed_ms, input_tokens, output_tokens, "LLM call finished");
+            }
+            Err(e) => {
+                metrics::record_llm_failure();
+                warn!(elapsed_ms, "LLM call failed: {:#}", e);
+            }
+        });
+        response
+    }
+
+    /// Provider for a request: the vision model when `messages` include
+    /// images and `agent.vision_model` is set, otherwise the chat model
+    fn provider_for(&self, messages: &[Message]) -> &dyn ChatProvider {
+        match &self.vision_provider {
+            Some(vision) if messages.iter().any(|m| !m.images.is_empty()) => &**vision,
+            _ => &*self.provider,
+        }
+    }
+
     /// Get messages for the LLM (for streaming)
     pub fn session_messages(&self) -> Vec<Message> {
         self.session.messages_for_llm()
@@ -891,14 +1938,19 @@ impl Agent {
     pub async fn chat_stream_with_tools(
         &mut self,
         message: &str,
+        images: Vec<ImageAttachment>,
     ) -> Result<impl futures::Stream<Item = Result<StreamEvent>> + '_> {
-        // Add user message
+        self.start_message()?;
+        self.begin_turn(message);
+        self.check_capabilities().await;
+
+        // Add user message with images
         self.session.add_message(Message {
             role: Role::User,
             content: message.to_string(),
             tool_calls: None,
             tool_call_id: None,
-            images: Vec::new(),
+            images,
         });
 
         // Check if we should run pre-compaction memory flush (soft threshold)
@@ -935,10 +1987,7 @@ impl Agent {
 
                 // Try streaming first (without tools since most providers don't support tool streaming)
                 // Then check for tool calls in the response
-                let response = self
-                    .provider
-                    .chat(&messages, Some(tool_schemas.as_slice()))
-                    .await;
+                let response = self.complete(&messages, &tool_schemas).await;
 
                 match response {
                     Ok(resp) => {
@@ -947,6 +1996,7 @@ impl Agent {
 
                         match resp.content {
                             LLMResponseContent::Text(text) => {
+                                let text = self.filter_reply(text).await;
                                 // No tool calls - yield the text and we're done
                                 yield Ok(StreamEvent::Content(text.clone()));
                                 yield Ok(StreamEvent::Done);
@@ -959,6 +2009,7 @@ impl Agent {
                                     tool_call_id: None,
                                     images: Vec::new(),
                                 });
+                                self.end_turn();
                                 break;
                             }
                             LLMResponseContent::ToolCalls(calls) => {
@@ -1012,6 +2063,46 @@ impl Agent {
         }
     }
 
+    /// Rewind the session to just before an earlier user message so it can be
+    /// sent again, edited or as-is to regenerate the reply. `occurrence` counts
+    /// later user messages with the same text. Returns the removed message,
+    /// which carries any attachments.
+    pub fn rewind_to_user_message(&mut self, content: &str, occurrence: usize) -> Result<Message> {
+        self.session
+            .truncate_at_user_message(content, occurrence)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Message is no longer in the session history (it may have been compacted)"
+                )
+            })
+    }
+
+    /// Close a turn whose stream was dropped before finishing: discard tool
+    /// results from the unfinished tool round and record the partial reply,
+    /// marked as interrupted
+    pub fn interrupt_turn(&mut self, partial: &str) {
+        self.session.discard_trailing_tool_results();
+        let content = if partial.trim().is_empty() {
+            INTERRUPTED_MARKER.to_string()
+        } else {
+            format!("{}\n\n{}", partial.trim_end(), INTERRUPTED_MARKER)
+        };
+        self.session.add_message_with_metadata(
+            Message {
+                role: Role::Assistant,
+                content,
+                tool_calls: None,
+                tool_call_id: None,
+                images: Vec::new(),
+            },
+            None,
+            Some(&self.config.model),
+            None,
+            Some("interrupted"),
+        );
+        info!("Turn interrupted");
+    }
+
     /// Get tool schemas for external use
     pub fn tool_schemas(&self) -> Vec<ToolSchema> {
         self.tools.iter().map(|t| t.schema()).collect()
@@ -1019,6 +2110,7 @@ impl Agent {
 
     /// Auto-save session to disk (call after each message)
     pub fn auto_save_session(&self) -> Result<()> {
+        self.save_retrieval_traces(DEFAULT_AGENT_ID);
         self.session.auto_save()
     }
 }
@@ -0,0 +1,196 @@
+//! Plan-then-execute for multi-step instructions (e.g. heartbeat checklists).
+//!
+//! The model first drafts a step list as structured output, then each step
+//! runs as its own turn with tools, and a final turn answers the original
+//! instruction from the step results. Giving every item its own turn keeps
+//! long checklists from being half-done in a single reply.
+
+use chrono::{DateTime, FixedOffset};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::locale;
+
+/// Steps run per plan; the rest are reported as skipped
+pub const MAX_STEPS: usize = 12;
+
+/// Step replies starting with these are recorded as failed / skipped
+const FAILED_PREFIX: &str = "FAILED:";
+const SKIPPED_PREFIX: &str = "SKIPPED:";
+
+/// Step list drafted by the model
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct Plan {
+    /// Concrete actions in execution order, one per item. Empty if nothing
+    /// needs doing.
+    pub steps: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepOutcome {
+    Done,
+    Failed,
+    Skipped,
+}
+
+#[derive(Debug, Clone)]
+pub struct StepResult {
+    pub step: String,
+    pub outcome: StepOutcome,
+    /// One-line result reported by the model (or the error)
+    pub result: String,
+}
+
+/// Outcome of a plan run
+#[derive(Debug, Clone)]
+pub struct PlanReport {
+    pub started: DateTime<FixedOffset>,
+    pub steps: Vec<StepResult>,
+    /// Final answer to the instruction, written after all steps ran
+    pub reply: String,
+}
+
+impl PlanReport {
+    pub fn new() -> Self {
+        Self {
+            started: locale::now(),
+            steps: Vec::new(),
+            reply: String::new(),
+        }
+    }
+
+    pub fn count(&self, outcome: StepOutcome) -> usize {
+        self.steps.iter().filter(|s| s.outcome == outcome).count()
+    }
+
+    /// e.g. "3 steps: 2 done, 1 failed"
+    pub fn summary(&self) -> String {
+        let mut parts = vec![format!("{} done", self.count(StepOutcome::Done))];
+        for (outcome, label) in [
+            (StepOutcome::Failed, "failed"),
+            (StepOutcome::Skipped, "skipped"),
+        ] {
+            let n = self.count(outcome);
+            if n > 0 {
+                parts.push(format!("{} {}", n, label));
+            }
+        }
+        format!("{} steps: {}", self.steps.len(), parts.join(", "))
+    }
+
+    /// Run report with a checklist of steps and the final reply
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!(
+            "# Plan run {}\n\n{}\n\n## Steps\n\n",
+            self.started.format("%Y-%m-%d %H:%M"),
+            self.summary()
+        );
+        if self.steps.is_empty() {
+            out.push_str("(nothing to do)\n");
+        }
+        for step in &self.steps {
+            let mark = match step.outcome {
+                StepOutcome::Done => "x",
+                StepOutcome::Failed => " ",
+                StepOutcome::Skipped => "-",
+            };
+            out.push_str(&format!("- [{}] {}", mark, step.step));
+            if !step.result.is_empty() {
+                out.push_str(&format!(" — {}", step.result));
+            }
+            out.push('\n');
+        }
+        if !self.reply.is_empty() {
+            out.push_str(&format!("\n## Result\n\n{}\n", self.reply.trim()));
+        }
+        out
+    }
+}
+
+impl Default for PlanReport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub(crate) fn planning_prompt(instruction: &str) -> String {
+    format!(
+        "Plan how to carry out the instruction below. Don't do any of it yet. \
+         List the concrete actions in order, one per step (at most {}); a checklist \
+         item that needs doing is one step. Leave out items that are already done \
+         or not due. Return no steps if nothing needs doing.\n\n\
+         Instruction:\n{}",
+        MAX_STEPS, instruction
+    )
+}
+
+pub(crate) fn step_prompt(index: usize, total: usize, step: &str) -> String {
+    format!(
+        "Step {}/{}: {}\n\n\
+         Do this step now, using tools as needed. Then reply with a one-line result. \
+         Start the reply with \"{}\" if it could not be done, or \"{}\" if it turned \
+         out to be unnecessary.",
+        index, total, step, FAILED_PREFIX, SKIPPED_PREFIX
+    )
+}
+
+pub(crate) const FINAL_PROMPT: &str =
+    "All steps are finished. Now reply to the original instruction based on the step results.";
+
+/// Outcome and one-line result from a step reply
+pub(crate) fn parse_step_reply(reply: &str) -> (StepOutcome, String) {
+    let reply = reply.trim();
+    let (outcome, rest) = if let Some(rest) = reply.strip_prefix(FAILED_PREFIX) {
+        (StepOutcome::Failed, rest)
+    } else if let Some(rest) = reply.strip_prefix(SKIPPED_PREFIX) {
+        (StepOutcome::Skipped, rest)
+    } else {
+        (StepOutcome::Done, reply)
+    };
+    let line = rest.trim().lines().next().unwrap_or("");
+    let result: String = line.chars().take(200).collect();
+    (outcome, result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_step_reply() {
+        assert_eq!(
+            parse_step_reply("Added 2 events to the calendar.\nDetails..."),
+            (
+                StepOutcome::Done,
+                "Added 2 events to the calendar.".to_string()
+            )
+        );
+        assert_eq!(
+            parse_step_reply("FAILED: weather API unreachable"),
+            (StepOutcome::Failed, "weather API unreachable".to_string())
+        );
+        assert_eq!(parse_step_reply("SKIPPED:").0, StepOutcome::Skipped);
+    }
+
+    #[test]
+    fn test_report_markdown() {
+        let mut report = PlanReport::new();
+        report.steps.push(StepResult {
+            step: "Check tomorrow's calendar".to_string(),
+            outcome: StepOutcome::Done,
+            result: "2 events".to_string(),
+        });
+        report.steps.push(StepResult {
+            step: "Send the grocery list".to_string(),
+            outcome: StepOutcome::Failed,
+            result: "no list found".to_string(),
+        });
+        report.reply = "Dentist at 9am tomorrow.".to_string();
+
+        assert_eq!(report.summary(), "2 steps: 1 done, 1 failed");
+        let md = report.to_markdown();
+        assert!(md.contains("- [x] Check tomorrow's calendar — 2 events\n"));
+        assert!(md.contains("- [ ] Send the grocery list — no list found\n"));
+        assert!(md.ends_with("## Result\n\nDentist at 9am tomorrow.\n"));
+    }
+}
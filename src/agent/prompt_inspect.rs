@@ -0,0 +1,140 @@
+//! What the model receives before the conversation: the session's system
+//! prompt split into its parts (instructions, skills, workspace files) and
+//! the tool definitions, with token counts, for `homegpt session prompt`
+//! and the desktop Prompt panel.
+
+use serde::{Deserialize, Serialize};
+
+use super::Tokenizer;
+
+/// One part of the system prompt, in prompt order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptSection {
+    /// "Instructions", "Skills", "SOUL.md", ...
+    pub name: String,
+    pub tokens: usize,
+    /// Exactly as sent, headings and separators included
+    pub text: String,
+}
+
+impl PromptSection {
+    pub fn new(name: &str, text: String, tokenizer: Tokenizer) -> Self {
+        Self {
+            name: name.to_string(),
+            tokens: tokenizer.count(&text),
+            text,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptInspection {
+    pub session_id: String,
+    pub model: String,
+    pub sections: Vec<PromptSection>,
+    /// Tokens the tool definitions add to every request
+    pub tool_tokens: usize,
+    /// Tokens of the messages so far
+    pub history_tokens: usize,
+    pub history_messages: usize,
+    pub context_window: usize,
+}
+
+impl PromptInspection {
+    /// The system prompt the next turn sends
+    pub fn system_prompt(&self) -> String {
+        self.sections.iter().map(|s| s.text.as_str()).collect()
+    }
+
+    pub fn system_tokens(&self) -> usize {
+        self.sections.iter().map(|s| s.tokens).sum()
+    }
+
+    /// Everything the next turn sends before the user's message
+    pub fn total_tokens(&self) -> usize {
+        self.system_tokens() + self.tool_tokens + self.history_tokens
+    }
+
+    /// Token counts per section, as a table
+    pub fn format(&self) -> String {
+        let mut out = format!(
+            "Session {} ({}, {} token context window)\n\n",
+            self.session_id, self.model, self.context_window
+        );
+        let mut row = |name: &str, tokens: usize| {
+            out.push_str(&format!(
+                "  {:<28} {:>7}  {:>5.1}%\n",
+                name,
+                tokens,
+                percent(tokens, self.context_window)
+            ));
+        };
+        for section in &self.sections {
+            row(&section.name, section.tokens);
+        }
+        row("Tool definitions", self.tool_tokens);
+        row(
+            &format!("Conversation ({} messages)", self.history_messages),
+            self.history_tokens,
+        );
+        row("Total", self.total_tokens());
+        out
+    }
+
+    /// Each section's text under a heading with its token count
+    pub fn format_sections(&self) -> String {
+        self.sections
+            .iter()
+            .map(|s| format!("===== {} ({} tokens) =====\n{}\n", s.name, s.tokens, s.text))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn percent(tokens: usize, window: usize) -> f64 {
+    if window == 0 {
+        0.0
+    } else {
+        tokens as f64 * 100.0 / window as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inspection_totals() {
+        let tokenizer = Tokenizer::for_model("gpt-4o");
+        let inspection = PromptInspection {
+            session_id: "3f2a9c1e".to_string(),
+            model: "gpt-4o".to_string(),
+            sections: vec![
+                PromptSection::new(
+                    "Instructions",
+                    "You are a helpful assistant.\n".to_string(),
+                    tokenizer,
+                ),
+                PromptSection::new("SOUL.md", "Be brief.\n\n---\n\n".to_string(), tokenizer),
+            ],
+            tool_tokens: 100,
+            history_tokens: 50,
+            history_messages: 2,
+            context_window: 1000,
+        };
+        assert_eq!(
+            inspection.system_prompt(),
+            "You are a helpful assistant.\nBe brief.\n\n---\n\n"
+        );
+        assert!(inspection.system_tokens() > 0);
+        assert_eq!(inspection.total_tokens(), inspection.system_tokens() + 150);
+
+        let table = inspection.format();
+        assert!(table.contains("SOUL.md"));
+        assert!(table.contains("Conversation (2 messages)"));
+        assert!(table.contains("Total"));
+        assert!(inspection
+            .format_sections()
+            .starts_with("===== Instructions ("));
+    }
+}
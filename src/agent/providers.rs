@@ -5,13 +5,19 @@ use futures::StreamExt;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::fmt;
+use std::path::PathBuf;
 use std::pin::Pin;
 use std::process::Stdio;
 use std::sync::Mutex as StdMutex;
+use std::time::Duration;
 use tokio::io::{AsyncBufReadExt, BufReader};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
-use crate::config::Config;
+use super::gemini::GeminiProvider;
+use super::redact::{RedactingProvider, Redactor};
+use super::replay::{MockChatProvider, RecordingProvider, RECORD_ENV};
+use crate::config::{Config, GroqConfig, OpenRouterConfig};
 
 /// Image attachment for multimodal messages
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -63,6 +69,15 @@ pub struct ToolSchema {
 pub struct Usage {
     pub input_tokens: u64,
     pub output_tokens: u64,
+    /// Request cost in USD, when the provider reports it (OpenRouter)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<f64>,
+    /// Input tokens served from the provider's prompt cache (part of input_tokens)
+    #[serde(default)]
+    pub cache_read_tokens: u64,
+    /// Input tokens written to the prompt cache (part of input_tokens)
+    #[serde(default)]
+    pub cache_write_tokens: u64,
 }
 
 impl Usage {
@@ -143,12 +158,23 @@ pub enum StreamEvent {
 pub type StreamResult = Pin<Box<dyn Stream<Item = Result<StreamChunk>> + Send>>;
 
 #[async_trait]
-pub trait LLMProvider: Send + Sync {
+pub trait ChatProvider: Send + Sync {
     async fn chat(&self, messages: &[Message], tools: Option<&[ToolSchema]>)
         -> Result<LLMResponse>;
 
     async fn summarize(&self, text: &str) -> Result<String>;
 
+    /// Runs on this machine or the home network, so requests aren't
+    /// redacted
+    fn is_local(&self) -> bool {
+        false
+    }
+
+    /// The model's context window in tokens, if the provider's API says
+    async fn context_window(&self) -> Option<usize> {
+        None
+    }
+
     /// Stream chat response (default: falls back to non-streaming)
     async fn chat_stream(
         &self,
@@ -210,7 +236,26 @@ fn normalize_model_id(provider: &str, model_id: &str) -> String {
     }
 }
 
-pub fn create_provider(model: &str, config: &Config) -> Result<Box<dyn LLMProvider>> {
+/// Create the provider for `model`. With `HOMEGPT_RECORD` set, its
+/// traffic is recorded for replay (see [`RecordingProvider`]).
+pub fn create_provider(model: &str, config: &Config) -> Result<Box<dyn ChatProvider>> {
+    let mut provider = create_base_provider(model, config)?;
+    if !provider.is_local() {
+        if let Some(redactor) = Redactor::from_config(&config.redaction)? {
+            provider = Box::new(RedactingProvider::new(provider, redactor));
+        }
+    }
+    match std::env::var(RECORD_ENV) {
+        Ok(path) if !path.is_empty() && !model.starts_with("replay/") => {
+            let path = PathBuf::from(shellexpand::tilde(&path).as_ref());
+            info!("Recording LLM traffic to {}", path.display());
+            Ok(Box::new(RecordingProvider::new(provider, &path)?))
+        }
+        _ => Ok(provider),
+    }
+}
+
+fn create_base_provider(model: &str, config: &Config) -> Result<Box<dyn ChatProvider>> {
     let workspace = config.workspace_path();
 
     // Resolve aliases first (e.g., "opus" → "anthropic/claude-opus-4-5")
@@ -224,6 +269,8 @@ pub fn create_provider(model: &str, config: &Config) -> Result<Box<dyn LLMProvid
         ("openai".to_string(), model.clone())
     } else if model.starts_with("claude-") {
         ("anthropic".to_string(), model.clone())
+    } else if model.starts_with("gemini-") {
+        ("gemini".to_string(), model.clone())
     } else {
         // Default to anthropic for unknown models, or ollama if configured
         if config.providers.ollama.is_some() {
@@ -272,6 +319,53 @@ pub fn create_provider(model: &str, config: &Config) -> Result<Box<dyn LLMProvid
             )?))
         }
 
+        "openrouter" => {
+            let openrouter_config = config.providers.openrouter.as_ref().ok_or_else(|| {
+                anyhow::anyhow!(
+                    "OpenRouter provider not configured.\n\
+                    Set OPENROUTER_API_KEY env var or add to ~/.homegpt/config.toml:\n\n\
+                    [providers.openrouter]\n\
+                    api_key = \"sk-or-...\""
+                )
+            })?;
+
+            Ok(Box::new(OpenRouterProvider::new(
+                openrouter_config,
+                &model_id,
+            )?))
+        }
+
+        "gemini" => {
+            let gemini_config = config.providers.gemini.as_ref().ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Gemini provider not configured.\n\
+                    Set GEMINI_API_KEY env var or add to ~/.homegpt/config.toml:\n\n\
+                    [providers.gemini]\n\
+                    api_key = \"AIza...\""
+                )
+            })?;
+
+            Ok(Box::new(GeminiProvider::new(
+                &gemini_config.api_key,
+                &gemini_config.base_url,
+                &model_id,
+                config.agent.max_tokens,
+            )?))
+        }
+
+        "groq" => {
+            let groq_config = config.providers.groq.as_ref().ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Groq provider not configured.\n\
+                    Set GROQ_API_KEY env var or add to ~/.homegpt/config.toml:\n\n\
+                    [providers.groq]\n\
+                    api_key = \"gsk_...\""
+                )
+            })?;
+
+            Ok(Box::new(GroqProvider::new(groq_config, &model_id)?))
+        }
+
         "claude-cli" => {
             let cli_config = config.providers.claude_cli.as_ref();
             let command = cli_config.map(|c| c.command.as_str()).unwrap_or("claude");
@@ -296,6 +390,12 @@ pub fn create_provider(model: &str, config: &Config) -> Result<Box<dyn LLMProvid
             )?))
         }
 
+        "replay" => {
+            // Recorded responses, for runs without network or API keys
+            let path = PathBuf::from(shellexpand::tilde(&model_id).as_ref());
+            Ok(Box::new(MockChatProvider::from_recording(&path)?))
+        }
+
         _ => {
             // Fallback: try Claude CLI if configured
             if let Some(cli_config) = &config.providers.claude_cli {
@@ -311,8 +411,12 @@ pub fn create_provider(model: &str, config: &Config) -> Result<Box<dyn LLMProvid
                 Supported formats (OpenClaw-compatible):\n  \
                 - anthropic/claude-opus-4-5, anthropic/claude-sonnet-4-5\n  \
                 - openai/gpt-4o, openai/gpt-4o-mini\n  \
+                - openrouter/openai/gpt-4o-mini, openrouter/meta-llama/llama-3.3-70b-instruct\n  \
+                - gemini/gemini-2.5-flash, gemini/gemini-2.5-pro\n  \
+                - groq/llama-3.1-8b-instant, groq/llama-3.3-70b-versatile\n  \
                 - claude-cli/opus, claude-cli/sonnet\n  \
-                - ollama/llama3, ollama/mistral\n\n\
+                - ollama/llama3, ollama/mistral\n  \
+                - replay/~/recording.jsonl (responses recorded with HOMEGPT_RECORD)\n\n\
                 Or use aliases: opus, sonnet, haiku, gpt, gpt-mini",
                 provider,
                 model
@@ -321,12 +425,139 @@ pub fn create_provider(model: &str, config: &Config) -> Result<Box<dyn LLMProvid
     }
 }
 
+/// An error status from an OpenAI-compatible API
+#[derive(Debug)]
+pub struct ApiError {
+    /// Whose API, for the message
+    api: &'static str,
+    status: u16,
+    /// e.g. "rate_limit_exceeded", or Groq's "tool_use_failed"
+    code: Option<String>,
+    message: String,
+    /// From the `retry-after` header
+    retry_after: Option<Duration>,
+}
+
+impl ApiError {
+    /// The HTTP status
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    async fn from_response(api: &'static str, response: reqwest::Response) -> Self {
+        let status = response.status().as_u16();
+        let retry_after = response
+            .headers()
+            .get("retry-after")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<f64>().ok())
+            .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+            .map(Duration::from_secs_f64);
+        let body = response.text().await.unwrap_or_default();
+        Self::parse(api, status, &body, retry_after)
+    }
+
+    fn parse(api: &'static str, status: u16, body: &str, retry_after: Option<Duration>) -> Self {
+        let json: Value = serde_json::from_str(body).unwrap_or(Value::Null);
+        let error = &json["error"];
+        let message = error["message"]
+            .as_str()
+            .or(error.as_str())
+            .map(str::to_string)
+            .unwrap_or_else(|| body.trim().to_string());
+        let code = match &error["code"] {
+            Value::String(code) => Some(code.clone()),
+            Value::Number(code) => Some(code.to_string()),
+            _ => None,
+        };
+        Self {
+            api,
+            status,
+            code,
+            message,
+            retry_after,
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} API error ({}): {}",
+            self.api, self.status, self.message
+        )
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// Tool calls as an OpenAI-compatible stream sends them: the ID and name
+/// first, then the arguments a fragment at a time, keyed by index. Groq
+/// sends each call whole.
+#[derive(Default)]
+struct ToolCallDeltas {
+    calls: Vec<ToolCall>,
+}
+
+impl ToolCallDeltas {
+    fn push(&mut self, deltas: &Value) {
+        let Some(deltas) = deltas.as_array() else {
+            return;
+        };
+        for delta in deltas {
+            let index = delta["index"]
+                .as_u64()
+                .map_or(self.calls.len(), |index| index as usize);
+            if self.calls.len() <= index {
+                self.calls.resize_with(index + 1, || ToolCall {
+                    id: String::new(),
+                    name: String::new(),
+                    arguments: String::new(),
+                });
+            }
+            let call = &mut self.calls[index];
+            if let Some(id) = delta["id"].as_str() {
+                call.id = id.to_string();
+            }
+            let function = &delta["function"];
+            if let Some(name) = function["name"].as_str() {
+                call.name.push_str(name);
+            }
+            if let Some(arguments) = function["arguments"].as_str() {
+                call.arguments.push_str(arguments);
+            }
+        }
+    }
+
+    fn finish(self) -> Option<Vec<ToolCall>> {
+        let calls: Vec<ToolCall> = self
+            .calls
+            .into_iter()
+            .filter(|call| !call.name.is_empty())
+            .map(|mut call| {
+                if call.arguments.trim().is_empty() {
+                    call.arguments = "{}".to_string();
+                }
+                call
+            })
+            .collect();
+        (!calls.is_empty()).then_some(calls)
+    }
+}
+
 // OpenAI Provider
 pub struct OpenAIProvider {
     client: Client,
     api_key: String,
     base_url: String,
     model: String,
+    /// Whose API this is, for errors
+    api: &'static str,
+    /// Extra headers sent with every request
+    headers: Vec<(String, String)>,
+    /// Extra top-level fields merged into every request body
+    extra_body: serde_json::Map<String, Value>,
 }
 
 impl OpenAIProvider {
@@ -336,9 +567,42 @@ impl OpenAIProvider {
             api_key: api_key.to_string(),
             base_url: base_url.to_string(),
             model: model.to_string(),
+            api: "OpenAI",
+            headers: Vec::new(),
+            extra_body: serde_json::Map::new(),
         })
     }
 
+    /// Name the API in errors, for the OpenAI-compatible ones
+    pub fn with_api_name(mut self, api: &'static str) -> Self {
+        self.api = api;
+        self
+    }
+
+    /// GET `{base_url}/{path}`, for model metadata
+    async fn get_json(&self, path: &str) -> Result<Value> {
+        let response = self
+            .client
+            .get(format!("{}/{}", self.base_url, path))
+            .bearer_auth(&self.api_key)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(response.json().await?)
+    }
+
+    /// Send an extra header with every request
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Merge an extra top-level field into every request body
+    pub fn with_body_field(mut self, key: &str, value: Value) -> Self {
+        self.extra_body.insert(key.to_string(), value);
+        self
+    }
+
     fn format_tools(&self, tools: &[ToolSchema]) -> Vec<Value> {
         tools
             .iter()
@@ -422,15 +686,8 @@ impl OpenAIProvider {
             })
             .collect()
     }
-}
 
-#[async_trait]
-impl LLMProvider for OpenAIProvider {
-    async fn chat(
-        &self,
-        messages: &[Message],
-        tools: Option<&[ToolSchema]>,
-    ) -> Result<LLMResponse> {
+    fn request_body(&self, messages: &[Message], tools: Option<&[ToolSchema]>) -> Value {
         let mut body = json!({
             "model": self.model,
             "messages": self.format_messages(messages)
@@ -442,17 +699,42 @@ impl LLMProvider for OpenAIProvider {
             }
         }
 
-        debug!("OpenAI request: {}", serde_json::to_string_pretty(&body)?);
+        for (key, value) in &self.extra_body {
+            body[key] = value.clone();
+        }
+        body
+    }
 
-        let response = self
+    /// POST `body` to the chat completions endpoint; an error status comes
+    /// back as an [`ApiError`]
+    async fn send(&self, body: &Value) -> Result<reqwest::Response> {
+        let mut request = self
             .client
             .post(format!("{}/chat/completions", self.base_url))
             .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await?;
+            .header("Content-Type", "application/json");
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        let response = request.json(body).send().await?;
+        if response.status().is_success() {
+            return Ok(response);
+        }
+        Err(ApiError::from_response(self.api, response).await.into())
+    }
+}
 
+#[async_trait]
+impl ChatProvider for OpenAIProvider {
+    async fn chat(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<LLMResponse> {
+        let body = self.request_body(messages, tools);
+        debug!("OpenAI request: {}", serde_json::to_string_pretty(&body)?);
+
+        let response = self.send(&body).await?;
         let response_body: Value = response.json().await?;
         debug!(
             "OpenAI response: {}",
@@ -461,7 +743,7 @@ impl LLMProvider for OpenAIProvider {
 
         // Check for errors
         if let Some(error) = response_body.get("error") {
-            anyhow::bail!("OpenAI API error: {}", error);
+            anyhow::bail!("{} API error: {}", self.api, error);
         }
 
         let choice = response_body["choices"]
@@ -474,6 +756,12 @@ impl LLMProvider for OpenAIProvider {
         let usage = response_body.get("usage").map(|u| Usage {
             input_tokens: u["prompt_tokens"].as_u64().unwrap_or(0),
             output_tokens: u["completion_tokens"].as_u64().unwrap_or(0),
+            cost: u["cost"].as_f64(),
+            // OpenAI caches long prompt prefixes automatically and reports hits here
+            cache_read_tokens: u["prompt_tokens_details"]["cached_tokens"]
+                .as_u64()
+                .unwrap_or(0),
+            cache_write_tokens: 0,
         });
 
         // Check for tool calls
@@ -525,6 +813,246 @@ impl LLMProvider for OpenAIProvider {
             _ => anyhow::bail!("Unexpected response type"),
         }
     }
+
+    async fn chat_stream(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<StreamResult> {
+        let mut body = self.request_body(messages, tools);
+        body["stream"] = json!(true);
+        debug!(
+            "OpenAI streaming request: {}",
+            serde_json::to_string_pretty(&body)?
+        );
+
+        let response = self.send(&body).await?;
+        let api = self.api;
+
+        // Server-sent events: a JSON chunk per "data:" line, then [DONE]
+        let stream = async_stream::stream! {
+            let mut byte_stream = response.bytes_stream();
+            let mut buffer = String::new();
+            let mut tool_calls = ToolCallDeltas::default();
+            let mut failed = false;
+
+            'read: while let Some(chunk) = byte_stream.next().await {
+                let bytes = match chunk {
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        yield Err(anyhow::anyhow!("Stream error: {}", e));
+                        failed = true;
+                        break;
+                    }
+                };
+                buffer.push_str(&String::from_utf8_lossy(&bytes));
+
+                while let Some(pos) = buffer.find('\n') {
+                    let line = buffer[..pos].trim().to_string();
+                    buffer.drain(..=pos);
+                    let Some(data) = line.strip_prefix("data:") else {
+                        continue;
+                    };
+                    let data = data.trim();
+                    if data == "[DONE]" {
+                        break 'read;
+                    }
+                    let Ok(json) = serde_json::from_str::<Value>(data) else {
+                        continue;
+                    };
+                    if let Some(error) = json.get("error") {
+                        yield Err(anyhow::anyhow!("{} API error: {}", api, error));
+                        failed = true;
+                        break 'read;
+                    }
+
+                    let delta = &json["choices"][0]["delta"];
+                    if let Some(text) = delta["content"].as_str() {
+                        if !text.is_empty() {
+                            yield Ok(StreamChunk {
+                                delta: text.to_string(),
+                                done: false,
+                                tool_calls: None,
+                            });
+                        }
+                    }
+                    tool_calls.push(&delta["tool_calls"]);
+                }
+            }
+
+            if !failed {
+                yield Ok(StreamChunk {
+                    delta: String::new(),
+                    done: true,
+                    tool_calls: tool_calls.finish(),
+                });
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
+
+// OpenRouter Provider (OpenAI-compatible API in front of many upstream models)
+pub struct OpenRouterProvider {
+    inner: OpenAIProvider,
+    model: String,
+}
+
+impl OpenRouterProvider {
+    pub fn new(config: &OpenRouterConfig, model: &str) -> Result<Self> {
+        let mut inner = OpenAIProvider::new(&config.api_key, &config.base_url, model)?
+            .with_api_name("OpenRouter")
+            .with_header("X-Title", &config.app_name)
+            // Ask OpenRouter to report the request cost in the usage block
+            .with_body_field("usage", json!({ "include": true }));
+
+        if let Some(ref app_url) = config.app_url {
+            inner = inner.with_header("HTTP-Referer", app_url);
+        }
+
+        // Model routing: try the requested model first, then the fallbacks
+        if !config.fallback_models.is_empty() {
+            let models: Vec<&str> = std::iter::once(model)
+                .chain(config.fallback_models.iter().map(String::as_str))
+                .collect();
+            inner = inner.with_body_field("models", json!(models));
+        }
+
+        let mut routing = serde_json::Map::new();
+        if !config.provider_order.is_empty() {
+            routing.insert("order".to_string(), json!(config.provider_order));
+        }
+        if let Some(ref sort) = config.sort {
+            routing.insert("sort".to_string(), json!(sort));
+        }
+        if !routing.is_empty() {
+            inner = inner.with_body_field("provider", Value::Object(routing));
+        }
+
+        Ok(Self {
+            inner,
+            model: model.to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl ChatProvider for OpenRouterProvider {
+    async fn chat(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<LLMResponse> {
+        let response = self.inner.chat(messages, tools).await?;
+        if let Some(cost) = response.usage.as_ref().and_then(|u| u.cost) {
+            debug!("OpenRouter {} request cost: ${:.6}", self.model, cost);
+        }
+        Ok(response)
+    }
+
+    async fn summarize(&self, text: &str) -> Result<String> {
+        self.inner.summarize(text).await
+    }
+
+    async fn context_window(&self) -> Option<usize> {
+        let models = self.inner.get_json("models").await.ok()?;
+        let model = models["data"]
+            .as_array()?
+            .iter()
+            .find(|m| m["id"].as_str() == Some(&self.model))?;
+        Some(model["context_length"].as_u64()? as usize)
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<StreamResult> {
+        self.inner.chat_stream(messages, tools).await
+    }
+}
+
+/// Longest `retry-after` a Groq request waits out; beyond it the limit is
+/// per day, not per minute
+const GROQ_MAX_RETRY_WAIT: Duration = Duration::from_secs(20);
+
+// Groq Provider (OpenAI-compatible, fast, with tight per-minute rate limits)
+pub struct GroqProvider {
+    inner: OpenAIProvider,
+}
+
+impl GroqProvider {
+    pub fn new(config: &GroqConfig, model: &str) -> Result<Self> {
+        let inner =
+            OpenAIProvider::new(&config.api_key, &config.base_url, model)?.with_api_name("Groq");
+        Ok(Self { inner })
+    }
+
+    /// How long to wait before trying a failed request once more, if it's
+    /// worth it: a rate limit that clears within a few seconds, or a
+    /// malformed tool call (Groq rejects those rather than returning them,
+    /// and another sample usually gets it right)
+    fn retry_delay(error: &anyhow::Error) -> Option<Duration> {
+        let error = error.downcast_ref::<ApiError>()?;
+        if error.code.as_deref() == Some("tool_use_failed") {
+            return Some(Duration::ZERO);
+        }
+        if error.status != 429 {
+            return None;
+        }
+        let wait = error.retry_after.unwrap_or(Duration::from_secs(2));
+        (wait <= GROQ_MAX_RETRY_WAIT).then_some(wait)
+    }
+}
+
+#[async_trait]
+impl ChatProvider for GroqProvider {
+    async fn chat(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<LLMResponse> {
+        match self.inner.chat(messages, tools).await {
+            Err(e) => match Self::retry_delay(&e) {
+                Some(wait) => {
+                    warn!("{}; retrying in {:.1}s", e, wait.as_secs_f32());
+                    tokio::time::sleep(wait).await;
+                    self.inner.chat(messages, tools).await
+                }
+                None => Err(e),
+            },
+            response => response,
+        }
+    }
+
+    async fn summarize(&self, text: &str) -> Result<String> {
+        self.inner.summarize(text).await
+    }
+
+    async fn context_window(&self) -> Option<usize> {
+        let path = format!("models/{}", self.inner.model);
+        let model = self.inner.get_json(&path).await.ok()?;
+        Some(model["context_window"].as_u64()? as usize)
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<StreamResult> {
+        match self.inner.chat_stream(messages, tools).await {
+            Err(e) => match Self::retry_delay(&e) {
+                Some(wait) => {
+                    warn!("{}; retrying in {:.1}s", e, wait.as_secs_f32());
+                    tokio::time::sleep(wait).await;
+                    self.inner.chat_stream(messages, tools).await
+                }
+                None => Err(e),
+            },
+            stream => stream,
+        }
+    }
 }
 
 // Anthropic Provider
@@ -646,8 +1174,19 @@ impl AnthropicProvider {
     }
 }
 
+/// The system prompt as a single block marked for prompt caching. It's the
+/// same on every turn of a session, so after the first request the tools and
+/// system prompt are read from the cache at a fraction of the input price.
+fn cached_system_prompt(system: String) -> Value {
+    json!([{
+        "type": "text",
+        "text": system,
+        "cache_control": { "type": "ephemeral" }
+    }])
+}
+
 #[async_trait]
-impl LLMProvider for AnthropicProvider {
+impl ChatProvider for AnthropicProvider {
     async fn chat(
         &self,
         messages: &[Message],
@@ -662,7 +1201,7 @@ impl LLMProvider for AnthropicProvider {
         });
 
         if let Some(system) = system_prompt {
-            body["system"] = json!(system);
+            body["system"] = cached_system_prompt(system);
         }
 
         if let Some(tools) = tools {
@@ -701,10 +1240,19 @@ impl LLMProvider for AnthropicProvider {
             .as_array()
             .ok_or_else(|| anyhow::anyhow!("No content in response"))?;
 
-        // Parse usage (Anthropic uses input_tokens/output_tokens directly)
-        let usage = response_body.get("usage").map(|u| Usage {
-            input_tokens: u["input_tokens"].as_u64().unwrap_or(0),
-            output_tokens: u["output_tokens"].as_u64().unwrap_or(0),
+        // Parse usage (Anthropic reports cached input separately from input_tokens)
+        let usage = response_body.get("usage").map(|u| {
+            let cache_read_tokens = u["cache_read_input_tokens"].as_u64().unwrap_or(0);
+            let cache_write_tokens = u["cache_creation_input_tokens"].as_u64().unwrap_or(0);
+            Usage {
+                input_tokens: u["input_tokens"].as_u64().unwrap_or(0)
+                    + cache_read_tokens
+                    + cache_write_tokens,
+                output_tokens: u["output_tokens"].as_u64().unwrap_or(0),
+                cost: None,
+                cache_read_tokens,
+                cache_write_tokens,
+            }
         });
 
         // Check for tool use
@@ -772,7 +1320,7 @@ impl LLMProvider for AnthropicProvider {
         });
 
         if let Some(system) = system_prompt {
-            body["system"] = json!(system);
+            body["system"] = cached_system_prompt(system);
         }
 
         // Include tools so the model uses native tool_use instead of XML
@@ -943,28 +1491,33 @@ impl OllamaProvider {
     }
 }
 
+/// Ollama chat message; images go in a separate list of base64 strings
+fn ollama_message(m: &Message) -> Value {
+    let mut message = json!({
+        "role": match m.role {
+            Role::System => "system",
+            Role::User => "user",
+            Role::Assistant => "assistant",
+            Role::Tool => "user", // Treat tool results as user messages
+        },
+        "content": m.content
+    });
+    if !m.images.is_empty() {
+        let images: Vec<&str> = m.images.iter().map(|img| img.data.as_str()).collect();
+        message["images"] = json!(images);
+    }
+    message
+}
+
 #[async_trait]
-impl LLMProvider for OllamaProvider {
+impl ChatProvider for OllamaProvider {
     async fn chat(
         &self,
         messages: &[Message],
         _tools: Option<&[ToolSchema]>,
     ) -> Result<LLMResponse> {
         // Note: Ollama tool support is limited, so we format as plain chat
-        let formatted_messages: Vec<Value> = messages
-            .iter()
-            .map(|m| {
-                json!({
-                    "role": match m.role {
-                        Role::System => "system",
-                        Role::User => "user",
-                        Role::Assistant => "assistant",
-                        Role::Tool => "user", // Treat tool results as user messages
-                    },
-                    "content": m.content
-                })
-            })
-            .collect();
+        let formatted_messages: Vec<Value> = messages.iter().map(ollama_message).collect();
 
         let body = json!({
             "model": self.model,
@@ -998,6 +1551,7 @@ impl LLMProvider for OllamaProvider {
             Some(Usage {
                 input_tokens: response_body["prompt_eval_count"].as_u64().unwrap_or(0),
                 output_tokens: response_body["eval_count"].as_u64().unwrap_or(0),
+                ..Default::default()
             })
         } else {
             None
@@ -1027,25 +1581,34 @@ impl LLMProvider for OllamaProvider {
         }
     }
 
+    fn is_local(&self) -> bool {
+        true
+    }
+
+    /// From the model's metadata, `<architecture>.context_length`
+    async fn context_window(&self) -> Option<usize> {
+        let response = self
+            .client
+            .post(format!("{}/api/show", self.endpoint))
+            .json(&json!({ "model": self.model }))
+            .send()
+            .await
+            .ok()?;
+        let show: Value = response.json().await.ok()?;
+        show["model_info"]
+            .as_object()?
+            .iter()
+            .find(|(key, _)| key.ends_with(".context_length"))
+            .and_then(|(_, length)| length.as_u64())
+            .map(|length| length as usize)
+    }
+
     async fn chat_stream(
         &self,
         messages: &[Message],
         _tools: Option<&[ToolSchema]>,
     ) -> Result<StreamResult> {
-        let formatted_messages: Vec<Value> = messages
-            .iter()
-            .map(|m| {
-                json!({
-                    "role": match m.role {
-                        Role::System => "system",
-                        Role::User => "user",
-                        Role::Assistant => "assistant",
-                        Role::Tool => "user",
-                    },
-                    "content": m.content
-                })
-            })
-            .collect();
+        let formatted_messages: Vec<Value> = messages.iter().map(ollama_message).collect();
 
         let body = json!({
             "model": self.model,
@@ -1387,7 +1950,7 @@ fn parse_claude_cli_output(stdout: &str) -> Result<(String, Option<String>)> {
 }
 
 #[async_trait]
-impl LLMProvider for ClaudeCliProvider {
+impl ChatProvider for ClaudeCliProvider {
     async fn chat(
         &self,
         messages: &[Message],
@@ -1758,6 +2321,7 @@ mod tests {
         let usage = Usage {
             input_tokens: 100,
             output_tokens: 50,
+            ..Default::default()
         };
         assert_eq!(usage.total(), 150);
     }
@@ -1781,6 +2345,7 @@ mod tests {
         let usage = Usage {
             input_tokens: 10,
             output_tokens: 5,
+            ..Default::default()
         };
         let resp = LLMResponse::text_with_usage("hello".to_string(), usage);
         assert!(matches!(resp.content, LLMResponseContent::Text(_)));
@@ -1798,6 +2363,66 @@ mod tests {
         assert!(resp.usage.is_none());
     }
 
+    #[test]
+    fn test_openrouter_request_options() {
+        let config = OpenRouterConfig {
+            api_key: "sk-or-test".to_string(),
+            base_url: "https://openrouter.ai/api/v1".to_string(),
+            app_name: "HomeGPT".to_string(),
+            app_url: Some("https://example.com".to_string()),
+            fallback_models: vec!["openai/gpt-4o-mini".to_string()],
+            provider_order: vec!["anthropic".to_string()],
+            sort: Some("price".to_string()),
+        };
+        let provider = OpenRouterProvider::new(&config, "anthropic/claude-sonnet-4.5").unwrap();
+        let inner = &provider.inner;
+
+        assert_eq!(inner.model, "anthropic/claude-sonnet-4.5");
+        assert!(inner.headers.contains(&(
+            "HTTP-Referer".to_string(),
+            "https://example.com".to_string()
+        )));
+        assert_eq!(inner.extra_body["usage"], json!({ "include": true }));
+        assert_eq!(
+            inner.extra_body["models"],
+            json!(["anthropic/claude-sonnet-4.5", "openai/gpt-4o-mini"])
+        );
+        assert_eq!(
+            inner.extra_body["provider"],
+            json!({ "order": ["anthropic"], "sort": "price" })
+        );
+
+        // No routing options: only the cost request is added
+        let config = OpenRouterConfig {
+            fallback_models: Vec::new(),
+            provider_order: Vec::new(),
+            sort: None,
+            ..config
+        };
+        let provider = OpenRouterProvider::new(&config, "openai/gpt-4o").unwrap();
+        assert_eq!(provider.inner.extra_body.len(), 1);
+    }
+
+    #[test]
+    fn test_ollama_message_images() {
+        let mut message = Message {
+            role: Role::User,
+            content: "What's wrong with this dishwasher?".to_string(),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+        };
+        assert!(ollama_message(&message).get("images").is_none());
+
+        message.images.push(ImageAttachment {
+            data: "iVBORw0KGgo=".to_string(),
+            media_type: "image/png".to_string(),
+        });
+        let formatted = ollama_message(&message);
+        assert_eq!(formatted["role"], "user");
+        assert_eq!(formatted["images"], json!(["iVBORw0KGgo="]));
+    }
+
     #[test]
     fn test_resolve_model_alias() {
         assert_eq!(resolve_model_alias("opus"), "anthropic/claude-opus-4-5");
@@ -1810,4 +2435,61 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_tool_call_deltas() {
+        // OpenAI: ID and name first, then the arguments in pieces
+        let mut deltas = ToolCallDeltas::default();
+        deltas.push(&json!([{
+            "index": 0,
+            "id": "call_1",
+            "function": { "name": "memory_search", "arguments": "" }
+        }]));
+        deltas.push(&json!([{ "index": 0, "function": { "arguments": "{\"query\":" } }]));
+        deltas.push(&json!([{ "index": 0, "function": { "arguments": "\"dentist\"}" } }]));
+        // Groq: a second call, whole
+        deltas.push(&json!([{
+            "index": 1,
+            "id": "call_2",
+            "function": { "name": "timer_list" }
+        }]));
+        deltas.push(&Value::Null);
+
+        let calls = deltas.finish().unwrap();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].id, "call_1");
+        assert_eq!(calls[0].arguments, "{\"query\":\"dentist\"}");
+        assert_eq!(calls[1].name, "timer_list");
+        assert_eq!(calls[1].arguments, "{}");
+        assert!(ToolCallDeltas::default().finish().is_none());
+    }
+
+    #[test]
+    fn test_groq_retry_delay() {
+        let error = |status, body: &str, retry_after| {
+            anyhow::Error::from(ApiError::parse("Groq", status, body, retry_after))
+        };
+        let rate_limited =
+            r#"{"error":{"message":"Rate limit reached","code":"rate_limit_exceeded"}}"#;
+        let short = error(429, rate_limited, Some(Duration::from_secs(3)));
+        assert_eq!(
+            short.to_string(),
+            "Groq API error (429): Rate limit reached"
+        );
+        assert_eq!(
+            GroqProvider::retry_delay(&short),
+            Some(Duration::from_secs(3))
+        );
+        // A daily limit isn't waited out
+        let long = error(429, rate_limited, Some(Duration::from_secs(3600)));
+        assert_eq!(GroqProvider::retry_delay(&long), None);
+
+        let tool_use = r#"{"error":{"message":"Failed to call a function","code":"tool_use_failed","failed_generation":"<function=x>"}}"#;
+        let tool_use = error(400, tool_use, None);
+        assert_eq!(GroqProvider::retry_delay(&tool_use), Some(Duration::ZERO));
+
+        let bad_key = error(401, "Invalid API Key", None);
+        assert_eq!(bad_key.to_string(), "Groq API error (401): Invalid API Key");
+        assert_eq!(GroqProvider::retry_delay(&bad_key), None);
+        assert_eq!(GroqProvider::retry_delay(&anyhow::anyhow!("offline")), None);
+    }
 }
@@ -0,0 +1,155 @@
+//! Query rewriting and HyDE expansion for memory retrieval
+//!
+//! Short voice-style queries ("ella dentist when") often miss on both FTS
+//! and embeddings. Before searching, an LLM can produce alternative
+//! phrasings (searched with FTS) and a hypothetical answer (embedded for
+//! vector search, a.k.a. HyDE).
+
+use anyhow::Result;
+use serde::Deserialize;
+use tracing::debug;
+
+use super::providers::{create_provider, ChatProvider, LLMResponseContent, Message, Role};
+use crate::config::Config;
+use crate::memory::QueryExpansion;
+
+/// Maximum number of rewrites kept from the model response
+const MAX_REWRITES: usize = 3;
+
+pub struct QueryExpander {
+    provider: Box<dyn ChatProvider>,
+    rewrite: bool,
+    hyde: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ExpansionResponse {
+    #[serde(default)]
+    rewrites: Vec<String>,
+    #[serde(default)]
+    hypothetical: Option<String>,
+}
+
+impl QueryExpander {
+    /// Create an expander from config, or `None` if expansion is disabled
+    pub fn from_config(config: &Config) -> Result<Option<Self>> {
+        let memory = &config.memory;
+        if !memory.query_rewrite && !memory.hyde {
+            return Ok(None);
+        }
+
+        let model = memory
+            .query_expansion_model
+            .as_deref()
+            .unwrap_or(&config.agent.default_model);
+        let provider = create_provider(model, config)?;
+
+        Ok(Some(Self {
+            provider,
+            rewrite: memory.query_rewrite,
+            hyde: memory.hyde,
+        }))
+    }
+
+    /// Expand a query into rewrites and/or a hypothetical answer
+    pub async fn expand(&self, query: &str) -> Result<QueryExpansion> {
+        let messages = vec![Message {
+            role: Role::User,
+            content: self.build_prompt(query),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+        }];
+
+        let response = self.provider.chat(&messages, None).await?;
+        let text = match response.content {
+            LLMResponseContent::Text(text) => text,
+            LLMResponseContent::ToolCalls(_) => String::new(),
+        };
+
+        let expansion = parse_expansion(&text, self.rewrite, self.hyde);
+        debug!(
+            "Expanded query '{}': {} rewrites, hyde: {}",
+            query,
+            expansion.rewrites.len(),
+            expansion.hypothetical.is_some()
+        );
+        Ok(expansion)
+    }
+
+    fn build_prompt(&self, query: &str) -> String {
+        let mut wanted = Vec::new();
+        if self.rewrite {
+            wanted.push(format!(
+                "\"rewrites\": up to {} alternative search queries using synonyms and full phrasing",
+                MAX_REWRITES
+            ));
+        }
+        if self.hyde {
+            wanted.push(
+                "\"hypothetical\": one or two sentences that a note answering the query might contain (invent plausible details; it is only used for similarity search)".to_string(),
+            );
+        }
+
+        format!(
+            "You help search a household's personal notes. Expand the search query below.\n\
+             Respond with only a JSON object with these keys:\n- {}\n\nQuery: {}",
+            wanted.join("\n- "),
+            query
+        )
+    }
+}
+
+/// Parse the model response leniently (code fences or surrounding prose are ignored)
+fn parse_expansion(text: &str, rewrite: bool, hyde: bool) -> QueryExpansion {
+    let json = match (text.find('{'), text.rfind('}')) {
+        (Some(start), Some(end)) if end > start => &text[start..=end],
+        _ => return QueryExpansion::default(),
+    };
+    let parsed: ExpansionResponse = serde_json::from_str(json).unwrap_or_default();
+
+    QueryExpansion {
+        rewrites: if rewrite {
+            parsed
+                .rewrites
+                .into_iter()
+                .map(|r| r.trim().to_string())
+                .filter(|r| !r.is_empty())
+                .take(MAX_REWRITES)
+                .collect()
+        } else {
+            Vec::new()
+        },
+        hypothetical: if hyde {
+            parsed
+                .hypothetical
+                .map(|h| h.trim().to_string())
+                .filter(|h| !h.is_empty())
+        } else {
+            None
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_expansion() {
+        let text = "```json\n{\"rewrites\": [\"Ella dentist appointment date\", \" \"], \"hypothetical\": \"Ella's dentist appointment is on March 3.\"}\n```";
+        let expansion = parse_expansion(text, true, true);
+        assert_eq!(expansion.rewrites, vec!["Ella dentist appointment date"]);
+        assert_eq!(
+            expansion.hypothetical.as_deref(),
+            Some("Ella's dentist appointment is on March 3.")
+        );
+
+        // Disabled parts are dropped even if the model returns them
+        let expansion = parse_expansion(text, false, true);
+        assert!(expansion.rewrites.is_empty());
+        assert!(expansion.hypothetical.is_some());
+
+        assert!(parse_expansion("no json here", true, true).is_empty());
+    }
+}
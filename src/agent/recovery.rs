@@ -0,0 +1,120 @@
+//! Recovery marker for replies cut short
+//!
+//! While the desktop app streams a reply it keeps `recovery.json` next to
+//! the agent's sessions up to date with the session ID, the message being
+//! answered and the reply so far. A turn that completes removes the marker.
+//! Closing the app mid-reply saves the partial reply into the session and
+//! leaves the marker pointing at it; killing the app leaves the marker with
+//! the partial reply the session file never got. Either way the next start
+//! finds it and offers to restore the session.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use super::session::get_state_dir;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecoveryMarker {
+    pub session_id: String,
+    /// The user message being answered
+    pub message: String,
+    /// Reply streamed before the app stopped
+    pub partial: String,
+    /// Whether the session file already holds the message and partial reply
+    pub saved: bool,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl RecoveryMarker {
+    pub fn new(session_id: &str, message: &str) -> Self {
+        Self {
+            session_id: session_id.to_string(),
+            message: message.to_string(),
+            partial: String::new(),
+            saved: false,
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Where the marker for `agent_id` lives
+    pub fn path(agent_id: &str) -> Result<PathBuf> {
+        Ok(get_state_dir()?
+            .join("agents")
+            .join(agent_id)
+            .join("recovery.json"))
+    }
+
+    /// Read the marker at `path`, if one was left behind. An unreadable
+    /// marker is removed rather than offered.
+    pub fn load(path: &Path) -> Option<Self> {
+        let content = fs::read_to_string(path).ok()?;
+        match serde_json::from_str(&content) {
+            Ok(marker) => Some(marker),
+            Err(e) => {
+                warn!(
+                    "Ignoring unreadable recovery marker {}: {}",
+                    path.display(),
+                    e
+                );
+                Self::clear(path);
+                None
+            }
+        }
+    }
+
+    /// Write the marker atomically (temp file + rename), so a crash
+    /// mid-write leaves the previous version
+    pub fn save(&mut self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        self.updated_at = Utc::now();
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_string(self)?)?;
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+
+    pub fn clear(path: &Path) {
+        if let Err(e) = fs::remove_file(path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("Failed to remove recovery marker {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_marker_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("agents/main/recovery.json");
+        assert!(RecoveryMarker::load(&path).is_none());
+
+        let mut marker = RecoveryMarker::new("abc", "What's for dinner?");
+        marker.partial.push_str("How about");
+        marker.save(&path).unwrap();
+        assert_eq!(RecoveryMarker::load(&path), Some(marker));
+
+        RecoveryMarker::clear(&path);
+        assert!(RecoveryMarker::load(&path).is_none());
+        RecoveryMarker::clear(&path);
+    }
+
+    #[test]
+    fn test_unreadable_marker_is_dropped() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("recovery.json");
+        fs::write(&path, "{\"session_id\":").unwrap();
+        assert!(RecoveryMarker::load(&path).is_none());
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,423 @@
+//! Redaction: cloud models see stand-ins for sensitive text
+//!
+//! With `[redaction] enabled`, requests to cloud providers have each of
+//! `terms` (an address, the kids' full names) replaced by its stand-in, and
+//! text matching `patterns` (phone numbers, SSNs, emails) by numbered ones
+//! like `[PHONE_1]`. Replies, streamed or not, and tool call arguments get
+//! the real text back, so tools, sessions and memory never see stand-ins.
+//! Local models (Ollama) are sent the real text.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::StreamExt;
+use regex::{Captures, NoExpand, Regex};
+use std::cmp::Reverse;
+
+use super::providers::{
+    ChatProvider, LLMResponse, LLMResponseContent, Message, StreamChunk, StreamResult, ToolCall,
+    ToolSchema,
+};
+use crate::config::RedactionConfig;
+
+/// Built-in patterns: name in `patterns`, stand-in label, regex
+const PATTERNS: [(&str, &str, &str); 3] = [
+    ("ssn", "SSN", r"\b\d{3}-\d{2}-\d{4}\b"),
+    (
+        "phone",
+        "PHONE",
+        r"(?:\+\d{1,3}[\s.-]?)?(?:\(\d{3}\)\s?|\b\d{3}[\s.-])\d{3}[\s.-]\d{4}\b",
+    ),
+    (
+        "email",
+        "EMAIL",
+        r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}\b",
+    ),
+];
+
+/// Replaces sensitive text with stand-ins
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    /// Matcher, real text and stand-in for each term, longest first
+    terms: Vec<(Regex, String, String)>,
+    /// Matcher and stand-in label for each pattern
+    patterns: Vec<(Regex, &'static str)>,
+}
+
+impl Redactor {
+    /// Redactor for `[redaction]`, or `None` if it's off
+    pub fn from_config(config: &RedactionConfig) -> Result<Option<Self>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        let mut terms: Vec<(&str, &String)> = config
+            .terms
+            .iter()
+            .map(|(term, stand_in)| (term.trim(), stand_in))
+            .filter(|(term, _)| !term.is_empty())
+            .collect();
+        terms.sort_by_key(|(term, _)| Reverse(term.len()));
+        let terms = terms
+            .into_iter()
+            .map(|(term, stand_in)| Ok((term_matcher(term)?, term.to_string(), stand_in.clone())))
+            .collect::<Result<_>>()?;
+
+        let patterns = config
+            .patterns
+            .iter()
+            .map(|name| {
+                let (_, label, pattern) =
+                    PATTERNS.iter().find(|(n, _, _)| n == name).ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "Unknown redaction pattern '{}' (expected phone, ssn or email)",
+                            name
+                        )
+                    })?;
+                Ok((Regex::new(pattern)?, *label))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Some(Self { terms, patterns }))
+    }
+
+    /// `text` with stand-ins, noting them in `stand_ins` for the reply
+    pub fn redact(&self, text: &str, stand_ins: &mut StandIns) -> String {
+        let mut text = text.to_string();
+        for (matcher, real, stand_in) in &self.terms {
+            if matcher.is_match(&text) {
+                text = matcher.replace_all(&text, NoExpand(stand_in)).into_owned();
+                stand_ins.add(stand_in, real);
+            }
+        }
+        for (matcher, label) in &self.patterns {
+            text = matcher
+                .replace_all(&text, |caps: &Captures| stand_ins.numbered(label, &caps[0]))
+                .into_owned();
+        }
+        text
+    }
+
+    fn redact_messages(&self, messages: &[Message], stand_ins: &mut StandIns) -> Vec<Message> {
+        messages
+            .iter()
+            .map(|message| Message {
+                content: self.redact(&message.content, stand_ins),
+                tool_calls: message.tool_calls.as_ref().map(|calls| {
+                    calls
+                        .iter()
+                        .map(|call| ToolCall {
+                            arguments: self.redact(&call.arguments, stand_ins),
+                            ..call.clone()
+                        })
+                        .collect()
+                }),
+                ..message.clone()
+            })
+            .collect()
+    }
+}
+
+/// Matches `term` in any case, as whole words where it starts or ends with one
+fn term_matcher(term: &str) -> Result<Regex> {
+    let boundary = |c: Option<char>| {
+        if c.is_some_and(char::is_alphanumeric) {
+            r"\b"
+        } else {
+            ""
+        }
+    };
+    Ok(Regex::new(&format!(
+        "(?i){}{}{}",
+        boundary(term.chars().next()),
+        regex::escape(term),
+        boundary(term.chars().last())
+    ))?)
+}
+
+/// The stand-ins one request used, and the text each replaced
+#[derive(Debug, Clone, Default)]
+pub struct StandIns {
+    /// (stand-in, real text)
+    pairs: Vec<(String, String)>,
+}
+
+impl StandIns {
+    fn add(&mut self, stand_in: &str, real: &str) {
+        if !self.pairs.iter().any(|(s, _)| s == stand_in) {
+            self.pairs.push((stand_in.to_string(), real.to_string()));
+        }
+    }
+
+    /// Stand-in for `real` matched by a pattern; the same text always gets
+    /// the same one
+    fn numbered(&mut self, label: &str, real: &str) -> String {
+        let prefix = format!("[{}_", label);
+        let mut count = 0;
+        for (stand_in, text) in &self.pairs {
+            if stand_in.starts_with(&prefix) {
+                if text == real {
+                    return stand_in.clone();
+                }
+                count += 1;
+            }
+        }
+        let stand_in = format!("{}{}]", prefix, count + 1);
+        self.add(&stand_in, real);
+        stand_in
+    }
+
+    /// `text` with the real text back in place of the stand-ins
+    pub fn restore(&self, text: &str) -> String {
+        self.restore_with(text, |real| real.to_string())
+    }
+
+    /// Tool call arguments are JSON: the real text goes in escaped
+    fn restore_call(&self, call: ToolCall) -> ToolCall {
+        let arguments = self.restore_with(&call.arguments, |real| {
+            let quoted = serde_json::Value::from(real).to_string();
+            quoted[1..quoted.len() - 1].to_string()
+        });
+        ToolCall { arguments, ..call }
+    }
+
+    fn restore_with(&self, text: &str, escape: impl Fn(&str) -> String) -> String {
+        // Longest first, so "[PHONE_10]" isn't taken for "[PHONE_1]"
+        let mut pairs: Vec<&(String, String)> = self.pairs.iter().collect();
+        pairs.sort_by_key(|(stand_in, _)| Reverse(stand_in.len()));
+        let mut text = text.to_string();
+        for (stand_in, real) in pairs {
+            if text.contains(stand_in.as_str()) {
+                text = text.replace(stand_in.as_str(), &escape(real));
+            }
+        }
+        text
+    }
+
+    /// Length of the end of `text` that could be the start of a stand-in,
+    /// held back while streaming until the next chunk shows which it is
+    fn partial_len(&self, text: &str) -> usize {
+        self.pairs
+            .iter()
+            .flat_map(|(stand_in, _)| {
+                stand_in
+                    .char_indices()
+                    .skip(1)
+                    .map(move |(i, _)| &stand_in[..i])
+            })
+            .filter(|prefix| text.ends_with(prefix))
+            .map(str::len)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Wraps a cloud provider so it only sees stand-ins
+pub struct RedactingProvider {
+    inner: Box<dyn ChatProvider>,
+    redactor: Redactor,
+}
+
+impl RedactingProvider {
+    pub fn new(inner: Box<dyn ChatProvider>, redactor: Redactor) -> Self {
+        Self { inner, redactor }
+    }
+}
+
+#[async_trait]
+impl ChatProvider for RedactingProvider {
+    async fn chat(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<LLMResponse> {
+        let mut stand_ins = StandIns::default();
+        let messages = self.redactor.redact_messages(messages, &mut stand_ins);
+        let mut response = self.inner.chat(&messages, tools).await?;
+        response.content = match response.content {
+            LLMResponseContent::Text(text) => LLMResponseContent::Text(stand_ins.restore(&text)),
+            LLMResponseContent::ToolCalls(calls) => LLMResponseContent::ToolCalls(
+                calls
+                    .into_iter()
+                    .map(|call| stand_ins.restore_call(call))
+                    .collect(),
+            ),
+        };
+        Ok(response)
+    }
+
+    async fn summarize(&self, text: &str) -> Result<String> {
+        let mut stand_ins = StandIns::default();
+        let text = self.redactor.redact(text, &mut stand_ins);
+        let summary = self.inner.summarize(&text).await?;
+        Ok(stand_ins.restore(&summary))
+    }
+
+    async fn context_window(&self) -> Option<usize> {
+        self.inner.context_window().await
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<StreamResult> {
+        let mut stand_ins = StandIns::default();
+        let messages = self.redactor.redact_messages(messages, &mut stand_ins);
+        let stream = self.inner.chat_stream(&messages, tools).await?;
+        Ok(restore_stream(stream, stand_ins))
+    }
+}
+
+/// `stream` with the real text back, holding back the end of a chunk that
+/// may be the first half of a stand-in
+fn restore_stream(mut stream: StreamResult, stand_ins: StandIns) -> StreamResult {
+    Box::pin(async_stream::stream! {
+        let mut pending = String::new();
+        while let Some(chunk) = stream.next().await {
+            match chunk {
+                Ok(mut chunk) => {
+                    pending.push_str(&chunk.delta);
+                    let held = if chunk.done { 0 } else { stand_ins.partial_len(&pending) };
+                    let ready: String = pending.drain(..pending.len() - held).collect();
+                    chunk.delta = stand_ins.restore(&ready);
+                    chunk.tool_calls = chunk.tool_calls.map(|calls| {
+                        calls
+                            .into_iter()
+                            .map(|call| stand_ins.restore_call(call))
+                            .collect()
+                    });
+                    yield Ok(chunk);
+                }
+                Err(e) => {
+                    pending.clear();
+                    yield Err(e);
+                    break;
+                }
+            }
+        }
+        if !pending.is_empty() {
+            yield Ok(StreamChunk {
+                delta: stand_ins.restore(&pending),
+                done: true,
+                tool_calls: None,
+            });
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::providers::Role;
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, Mutex};
+
+    fn redactor() -> Redactor {
+        let config = RedactionConfig {
+            enabled: true,
+            terms: BTreeMap::from([
+                ("Emma Johnson".to_string(), "CHILD_A".to_string()),
+                ("42 Elm Street".to_string(), "HOME_ADDRESS".to_string()),
+            ]),
+            patterns: vec!["phone".to_string(), "ssn".to_string()],
+        };
+        Redactor::from_config(&config).unwrap().unwrap()
+    }
+
+    fn user(content: &str) -> Message {
+        Message {
+            role: Role::User,
+            content: content.to_string(),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_redact_and_restore() {
+        let mut stand_ins = StandIns::default();
+        let text = "emma johnson lives at 42 Elm Street, call 555-123-4567 or (555) 987-6543, \
+                    SSN 123-45-6789, again 555-123-4567. Emma Johnsonville stays.";
+        let redacted = redactor().redact(text, &mut stand_ins);
+        assert_eq!(
+            redacted,
+            "CHILD_A lives at HOME_ADDRESS, call [PHONE_1] or [PHONE_2], \
+             SSN [SSN_1], again [PHONE_1]. Emma Johnsonville stays."
+        );
+        assert_eq!(
+            stand_ins.restore("Calling [PHONE_2] for CHILD_A"),
+            "Calling (555) 987-6543 for Emma Johnson"
+        );
+
+        let err = RedactionConfig {
+            enabled: true,
+            patterns: vec!["iban".to_string()],
+            ..RedactionConfig::default()
+        };
+        assert!(Redactor::from_config(&err).is_err());
+    }
+
+    #[test]
+    fn test_partial_stand_in() {
+        let mut stand_ins = StandIns::default();
+        stand_ins.add("CHILD_A", "Emma Johnson");
+        assert_eq!(stand_ins.partial_len("Say hi to CHI"), 3);
+        assert_eq!(stand_ins.partial_len("Say hi to CHILD_A"), 0);
+        assert_eq!(stand_ins.partial_len("Say hi"), 0);
+    }
+
+    /// Keeps what it was sent and asks for a tool call with `arguments`
+    struct Capture {
+        sent: Arc<Mutex<Vec<String>>>,
+        arguments: String,
+    }
+
+    #[async_trait]
+    impl ChatProvider for Capture {
+        async fn chat(
+            &self,
+            messages: &[Message],
+            _tools: Option<&[ToolSchema]>,
+        ) -> Result<LLMResponse> {
+            let mut sent = self.sent.lock().unwrap();
+            sent.extend(messages.iter().map(|m| m.content.clone()));
+            Ok(LLMResponse {
+                content: LLMResponseContent::ToolCalls(vec![ToolCall {
+                    id: "1".to_string(),
+                    name: "memory_save".to_string(),
+                    arguments: self.arguments.clone(),
+                }]),
+                usage: None,
+            })
+        }
+
+        async fn summarize(&self, text: &str) -> Result<String> {
+            Ok(text.to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_provider_round_trip() {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let capture = Capture {
+            sent: Arc::clone(&sent),
+            arguments: r#"{"content": "CHILD_A: dentist, [PHONE_1]"}"#.to_string(),
+        };
+        let provider = RedactingProvider::new(Box::new(capture), redactor());
+
+        let response = provider
+            .chat(&[user("Emma Johnson's dentist is 555-123-4567")], None)
+            .await
+            .unwrap();
+        assert_eq!(
+            sent.lock().unwrap().as_slice(),
+            ["CHILD_A's dentist is [PHONE_1]"]
+        );
+        match response.content {
+            LLMResponseContent::ToolCalls(calls) => assert_eq!(
+                calls[0].arguments,
+                r#"{"content": "Emma Johnson: dentist, 555-123-4567"}"#
+            ),
+            _ => panic!("expected tool calls"),
+        }
+    }
+}
@@ -0,0 +1,362 @@
+//! Approving tool calls from a phone
+//!
+//! With `[approval.remote]` set, agents in the daemon (HTTP sessions and
+//! chat bridges) don't run a call the approval policy asks about until
+//! someone answers. The request goes out as an `approval_requested`
+//! notification: ntfy shows Approve and Deny buttons, other channels a link
+//! to a page with the same choices. Both land on the HTTP server's
+//! `/approvals/<id>` endpoints, which answer the waiting turn. No answer
+//! within `timeout` and the call isn't run.
+//!
+//! Clients attached to a streamed turn (`homegpt chat`, the desktop app)
+//! are asked in the stream instead, and answer at the same endpoints.
+//! Without either, nobody is at the daemon to answer, so such calls are
+//! refused.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{info, warn};
+
+use super::approval::{ApprovalOutcome, Approver};
+use super::tools::extract_tool_detail;
+use crate::config::{parse_duration, Config};
+use crate::notifications::{Actions, Event, Notifier};
+
+/// Requests waiting for an answer, by id. Shared by the agents that ask and
+/// the HTTP handlers that answer, which run in the same process (the daemon).
+static PENDING: Lazy<Mutex<HashMap<String, Pending>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+struct Pending {
+    /// Secret in the request's URLs
+    token: String,
+    request: PendingApproval,
+    answer: oneshot::Sender<bool>,
+}
+
+/// A tool call waiting for someone to approve it
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingApproval {
+    pub tool: String,
+    /// Short description of the arguments, e.g. the command or path
+    pub detail: Option<String>,
+    /// Why the policy asked
+    pub reason: String,
+}
+
+impl PendingApproval {
+    /// One line for the notification and the approval page
+    pub fn summary(&self) -> String {
+        match self.detail {
+            Some(ref detail) => format!("Run {}: {}? ({})", self.tool, detail, self.reason),
+            None => format!("Run {}? ({})", self.tool, self.reason),
+        }
+    }
+}
+
+/// The request `id`, if it's still waiting and `token` is its secret
+pub fn pending_approval(id: &str, token: &str) -> Option<PendingApproval> {
+    let pending = PENDING.lock().unwrap();
+    pending
+        .get(id)
+        .filter(|p| p.token == token)
+        .map(|p| p.request.clone())
+}
+
+/// Approve or deny the request `id`, returning it, or `None` if it isn't
+/// waiting (already answered, timed out) or `token` is wrong
+pub fn answer_approval(id: &str, token: &str, approved: bool) -> Option<PendingApproval> {
+    let mut pending = PENDING.lock().unwrap();
+    if pending.get(id)?.token != token {
+        return None;
+    }
+    let entry = pending.remove(id)?;
+    // The turn may have just given up; then the answer has nowhere to go
+    let _ = entry.answer.send(approved);
+    Some(entry.request)
+}
+
+/// Add `request` to the requests waiting for an answer, returning its id,
+/// its token and where the answer arrives
+fn register(request: PendingApproval) -> (String, String, oneshot::Receiver<bool>) {
+    let id = uuid::Uuid::new_v4().simple().to_string()[..12].to_string();
+    let token = uuid::Uuid::new_v4().simple().to_string();
+    let (answer, answered) = oneshot::channel();
+    PENDING.lock().unwrap().insert(
+        id.clone(),
+        Pending {
+            token: token.clone(),
+            request,
+            answer,
+        },
+    );
+    (id, token, answered)
+}
+
+/// Wait up to `timeout` for the answer to request `id`
+async fn wait_for(
+    id: &str,
+    answered: oneshot::Receiver<bool>,
+    timeout: Duration,
+) -> ApprovalOutcome {
+    let outcome = match tokio::time::timeout(timeout, answered).await {
+        Ok(Ok(true)) => ApprovalOutcome::Approved,
+        Ok(Ok(false)) => ApprovalOutcome::Denied,
+        _ => ApprovalOutcome::TimedOut,
+    };
+    PENDING.lock().unwrap().remove(id);
+    outcome
+}
+
+/// Asks through the notification channels and waits for the answer
+pub struct RemoteApprover {
+    notifier: Notifier,
+    public_url: String,
+    timeout: Duration,
+}
+
+impl RemoteApprover {
+    /// Approver for `approval.remote`, or `None` if it isn't set
+    pub fn from_config(config: &Config) -> Result<Option<Self>> {
+        let Some(ref remote) = config.approval.remote else {
+            return Ok(None);
+        };
+        let timeout = parse_duration(&remote.timeout)
+            .map_err(|e| anyhow::anyhow!("Invalid approval.remote.timeout: {}", e))?;
+        let notifier = match Notifier::from_config(config)? {
+            Some(notifier) if notifier.wants(Event::ApprovalRequested) => notifier,
+            _ => anyhow::bail!(
+                "approval.remote needs a notification channel that takes approval_requested"
+            ),
+        };
+        Ok(Some(Self {
+            notifier,
+            public_url: remote.public_url.trim_end_matches('/').to_string(),
+            timeout,
+        }))
+    }
+
+    /// The approver for agents in the daemon: this one with
+    /// `approval.remote`, otherwise [`Unattended`]
+    pub fn shared(config: &Config) -> Result<Arc<dyn Approver>> {
+        Ok(match Self::from_config(config)? {
+            Some(approver) => Arc::new(approver),
+            None => Arc::new(Unattended),
+        })
+    }
+
+    fn actions(&self, id: &str, token: &str) -> Actions {
+        let base = format!("{}/approvals/{}", self.public_url, id);
+        Actions {
+            buttons: vec![
+                (
+                    "Approve".to_string(),
+                    format!("{}/approve?token={}", base, token),
+                ),
+                ("Deny".to_string(), format!("{}/deny?token={}", base, token)),
+            ],
+            link: format!("{}?token={}", base, token),
+        }
+    }
+}
+
+#[async_trait]
+impl Approver for RemoteApprover {
+    async fn approve(&self, tool: &str, arguments: &str, reason: &str) -> ApprovalOutcome {
+        let request = PendingApproval {
+            tool: tool.to_string(),
+            detail: extract_tool_detail(tool, arguments),
+            reason: reason.to_string(),
+        };
+        let message = request.summary();
+        let (id, token, answered) = register(request);
+
+        let reached = self
+            .notifier
+            .notify_with_actions(
+                Event::ApprovalRequested,
+                &message,
+                &self.actions(&id, &token),
+            )
+            .await;
+        if reached.is_empty() {
+            warn!(
+                "Approval request {} reached no channel; not running {}",
+                id, tool
+            );
+            PENDING.lock().unwrap().remove(&id);
+            return ApprovalOutcome::Denied;
+        }
+        info!(
+            "Waiting for approval {} ({}) from {}",
+            id,
+            tool,
+            reached.join(", ")
+        );
+        wait_for(&id, answered, self.timeout).await
+    }
+}
+
+/// A request for the client attached to a streamed turn, answered at
+/// `/approvals/<id>/approve` or `/deny` with `token`
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamApproval {
+    pub id: String,
+    pub token: String,
+    /// The call's JSON arguments, for previews
+    pub arguments: String,
+    pub request: PendingApproval,
+}
+
+/// Asks the client that's streaming the turn, and waits for its answer
+pub struct StreamApprover {
+    requests: mpsc::UnboundedSender<StreamApproval>,
+    timeout: Duration,
+}
+
+impl StreamApprover {
+    /// An approver for one streamed turn, and the requests to send the client
+    pub fn new(timeout: Duration) -> (Self, mpsc::UnboundedReceiver<StreamApproval>) {
+        let (requests, received) = mpsc::unbounded_channel();
+        (Self { requests, timeout }, received)
+    }
+}
+
+#[async_trait]
+impl Approver for StreamApprover {
+    async fn approve(&self, tool: &str, arguments: &str, reason: &str) -> ApprovalOutcome {
+        let request = PendingApproval {
+            tool: tool.to_string(),
+            detail: extract_tool_detail(tool, arguments),
+            reason: reason.to_string(),
+        };
+        let (id, token, answered) = register(request.clone());
+        let sent = self.requests.send(StreamApproval {
+            id: id.clone(),
+            token,
+            arguments: arguments.to_string(),
+            request,
+        });
+        if sent.is_err() {
+            // The client has gone; its turn ended with the stream
+            PENDING.lock().unwrap().remove(&id);
+            return ApprovalOutcome::Denied;
+        }
+        wait_for(&id, answered, self.timeout).await
+    }
+}
+
+/// Refuses every call the policy asks about: the daemon without
+/// `approval.remote`, where nobody is watching to answer
+pub struct Unattended;
+
+#[async_trait]
+impl Approver for Unattended {
+    async fn approve(&self, tool: &str, _arguments: &str, reason: &str) -> ApprovalOutcome {
+        warn!(
+            "Not running {} ({}): nobody can approve it without approval.remote",
+            tool, reason
+        );
+        ApprovalOutcome::Denied
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approver(timeout: Duration) -> RemoteApprover {
+        RemoteApprover {
+            notifier: Notifier::default(),
+            public_url: "https://homegpt.example.org".to_string(),
+            timeout,
+        }
+    }
+
+    fn insert(id: &str, token: &str) -> oneshot::Receiver<bool> {
+        let (answer, answered) = oneshot::channel();
+        let request = PendingApproval {
+            tool: "bash".to_string(),
+            detail: Some("sudo reboot".to_string()),
+            reason: "approval rule 3".to_string(),
+        };
+        PENDING.lock().unwrap().insert(
+            id.to_string(),
+            Pending {
+                token: token.to_string(),
+                request,
+                answer,
+            },
+        );
+        answered
+    }
+
+    #[test]
+    fn test_actions() {
+        let actions = approver(Duration::from_secs(60)).actions("abc", "s3cret");
+        assert_eq!(
+            actions.buttons[0].1,
+            "https://homegpt.example.org/approvals/abc/approve?token=s3cret"
+        );
+        assert_eq!(
+            actions.link,
+            "https://homegpt.example.org/approvals/abc?token=s3cret"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_answer_needs_token() {
+        let answered = insert("test-answer", "right");
+        assert_eq!(pending_approval("test-answer", "wrong"), None);
+        assert_eq!(answer_approval("test-answer", "wrong", true), None);
+
+        let request = pending_approval("test-answer", "right").unwrap();
+        assert_eq!(
+            request.summary(),
+            "Run bash: sudo reboot? (approval rule 3)"
+        );
+        assert!(answer_approval("test-answer", "right", false).is_some());
+        assert!(!answered.await.unwrap());
+        // Answered once only
+        assert_eq!(answer_approval("test-answer", "right", true), None);
+    }
+
+    #[tokio::test]
+    async fn test_stream_approver_waits_for_answer() {
+        let (approver, mut requests) = StreamApprover::new(Duration::from_secs(60));
+        let asking = tokio::spawn(async move {
+            approver
+                .approve("bash", r#"{"command": "ls"}"#, "bash is in approval.tools")
+                .await
+        });
+        let asked = requests.recv().await.unwrap();
+        assert_eq!(asked.request.detail.as_deref(), Some("ls"));
+        assert_eq!(asked.arguments, r#"{"command": "ls"}"#);
+        assert!(answer_approval(&asked.id, &asked.token, true).is_some());
+        assert_eq!(asking.await.unwrap(), ApprovalOutcome::Approved);
+
+        // Nobody left to ask
+        let (approver, requests) = StreamApprover::new(Duration::from_secs(60));
+        drop(requests);
+        let outcome = approver
+            .approve("bash", "{}", "bash is in approval.tools")
+            .await;
+        assert_eq!(outcome, ApprovalOutcome::Denied);
+        let outcome = Unattended
+            .approve("bash", "{}", "bash is in approval.tools")
+            .await;
+        assert_eq!(outcome, ApprovalOutcome::Denied);
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_is_denied() {
+        let outcome = approver(Duration::from_secs(60))
+            .approve("bash", r#"{"command": "ls"}"#, "bash is in approval.tools")
+            .await;
+        assert_eq!(outcome, ApprovalOutcome::Denied);
+    }
+}
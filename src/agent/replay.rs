@@ -0,0 +1,409 @@
+//! Recording and replaying LLM traffic
+//!
+//! With `HOMEGPT_RECORD=<file>` set, every provider request and response is
+//! appended to `<file>` as JSON lines. The model `replay/<file>` then answers
+//! with the recorded responses, in order, through [`MockChatProvider`], so a
+//! session can be rerun without network access or API keys. Tools run for
+//! real during a replay; their outputs reach the mock like any other
+//! message. Recording goes through `chat`, so streamed replies arrive as a
+//! single chunk while recording and replaying.
+//!
+//! Tests can also script a [`MockChatProvider`] with responses directly and
+//! check what the agent sent with [`MockChatProvider::requests`].
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::warn;
+
+use super::providers::{
+    ChatProvider, LLMResponse, LLMResponseContent, Message, Role, ToolCall, ToolSchema, Usage,
+};
+
+/// Environment variable naming the file to record provider traffic to
+pub const RECORD_ENV: &str = "HOMEGPT_RECORD";
+
+/// One request and its response, as a line of a recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Exchange {
+    pub request: RecordedRequest,
+    pub reply: RecordedReply,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecordedRequest {
+    Chat {
+        messages: Vec<Message>,
+        /// Names of the tools offered
+        tools: Vec<String>,
+    },
+    Summarize {
+        text: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecordedReply {
+    Text {
+        text: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        usage: Option<Usage>,
+    },
+    ToolCalls {
+        calls: Vec<ToolCall>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        usage: Option<Usage>,
+    },
+    Error {
+        message: String,
+    },
+}
+
+impl RecordedReply {
+    fn from_result(result: &Result<LLMResponse>) -> Self {
+        match result {
+            Ok(response) => match &response.content {
+                LLMResponseContent::Text(text) => Self::Text {
+                    text: text.clone(),
+                    usage: response.usage.clone(),
+                },
+                LLMResponseContent::ToolCalls(calls) => Self::ToolCalls {
+                    calls: calls.clone(),
+                    usage: response.usage.clone(),
+                },
+            },
+            Err(e) => Self::Error {
+                message: format!("{:#}", e),
+            },
+        }
+    }
+
+    fn into_result(self) -> Result<LLMResponse> {
+        match self {
+            Self::Text { text, usage } => Ok(LLMResponse {
+                content: LLMResponseContent::Text(text),
+                usage,
+            }),
+            Self::ToolCalls { calls, usage } => Ok(LLMResponse {
+                content: LLMResponseContent::ToolCalls(calls),
+                usage,
+            }),
+            Self::Error { message } => Err(anyhow::anyhow!(message)),
+        }
+    }
+}
+
+/// Wraps a provider, appending every exchange to a recording
+pub struct RecordingProvider {
+    inner: Box<dyn ChatProvider>,
+    file: Mutex<File>,
+}
+
+impl RecordingProvider {
+    pub fn new(inner: Box<dyn ChatProvider>, path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open recording {}", path.display()))?;
+        Ok(Self {
+            inner,
+            file: Mutex::new(file),
+        })
+    }
+
+    fn record(&self, request: RecordedRequest, reply: RecordedReply) {
+        let line = match serde_json::to_string(&Exchange { request, reply }) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!("Failed to record LLM exchange: {}", e);
+                return;
+            }
+        };
+        let mut file = self.file.lock().unwrap_or_else(|p| p.into_inner());
+        if let Err(e) = writeln!(file, "{}", line) {
+            warn!("Failed to record LLM exchange: {}", e);
+        }
+    }
+}
+
+#[async_trait]
+impl ChatProvider for RecordingProvider {
+    async fn chat(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<LLMResponse> {
+        let result = self.inner.chat(messages, tools).await;
+        self.record(
+            RecordedRequest::Chat {
+                messages: messages.to_vec(),
+                tools: tools
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|t| t.name.clone())
+                    .collect(),
+            },
+            RecordedReply::from_result(&result),
+        );
+        result
+    }
+
+    async fn summarize(&self, text: &str) -> Result<String> {
+        let result = self.inner.summarize(text).await;
+        let reply = match &result {
+            Ok(summary) => RecordedReply::Text {
+                text: summary.clone(),
+                usage: None,
+            },
+            Err(e) => RecordedReply::Error {
+                message: format!("{:#}", e),
+            },
+        };
+        self.record(
+            RecordedRequest::Summarize {
+                text: text.to_string(),
+            },
+            reply,
+        );
+        result
+    }
+
+    async fn context_window(&self) -> Option<usize> {
+        self.inner.context_window().await
+    }
+}
+
+/// Answers with recorded or scripted responses, in order
+pub struct MockChatProvider {
+    /// Responses still to give, with the request each was recorded for
+    script: Mutex<VecDeque<(Option<RecordedRequest>, RecordedReply)>>,
+    /// Requests received so far
+    requests: Mutex<Vec<RecordedRequest>>,
+}
+
+impl MockChatProvider {
+    /// A provider giving `responses` in order, whatever it is asked
+    pub fn new(responses: impl IntoIterator<Item = LLMResponse>) -> Self {
+        Self::from_script(
+            responses
+                .into_iter()
+                .map(|response| (None, RecordedReply::from_result(&Ok(response))))
+                .collect(),
+        )
+    }
+
+    /// Replay a file written with `HOMEGPT_RECORD`. Each request must
+    /// match the recorded one (same kind, same latest user message), so a
+    /// replay that drifts from the recording fails instead of answering
+    /// out of turn.
+    pub fn from_recording(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read recording {}", path.display()))?;
+        let mut script = VecDeque::new();
+        for (n, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let exchange: Exchange = serde_json::from_str(line)
+                .with_context(|| format!("{}:{}: invalid exchange", path.display(), n + 1))?;
+            script.push_back((Some(exchange.request), exchange.reply));
+        }
+        Ok(Self::from_script(script))
+    }
+
+    fn from_script(script: VecDeque<(Option<RecordedRequest>, RecordedReply)>) -> Self {
+        Self {
+            script: Mutex::new(script),
+            requests: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Requests received so far, oldest first
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .clone()
+    }
+
+    /// Responses not yet given
+    pub fn remaining(&self) -> usize {
+        self.script.lock().unwrap_or_else(|p| p.into_inner()).len()
+    }
+
+    fn next(&self, request: RecordedRequest) -> Result<RecordedReply> {
+        let n = {
+            let mut requests = self.requests.lock().unwrap_or_else(|p| p.into_inner());
+            requests.push(request.clone());
+            requests.len()
+        };
+        let Some((expected, reply)) = self
+            .script
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .pop_front()
+        else {
+            bail!("Replay has no response left for request {}", n);
+        };
+        if let Some(expected) = expected {
+            if let Err(e) = check_request(&expected, &request) {
+                bail!("Replay diverged at request {}: {}", n, e);
+            }
+        }
+        Ok(reply)
+    }
+}
+
+/// Whether `actual` is the request `expected` was recorded for. Only the
+/// kind and the latest user message are compared: system prompts and tool
+/// outputs can carry the time and differ between runs.
+fn check_request(expected: &RecordedRequest, actual: &RecordedRequest) -> Result<()> {
+    match (expected, actual) {
+        (RecordedRequest::Chat { messages: a, .. }, RecordedRequest::Chat { messages: b, .. }) => {
+            let last_user = |messages: &[Message]| {
+                messages
+                    .iter()
+                    .rev()
+                    .find(|m| m.role == Role::User)
+                    .map(|m| m.content.clone())
+            };
+            let (a, b) = (last_user(a), last_user(b));
+            if a != b {
+                bail!(
+                    "recorded for user message {:?}, got {:?}",
+                    a.unwrap_or_default(),
+                    b.unwrap_or_default()
+                );
+            }
+            Ok(())
+        }
+        (RecordedRequest::Summarize { .. }, RecordedRequest::Summarize { .. }) => Ok(()),
+        (RecordedRequest::Chat { .. }, RecordedRequest::Summarize { .. }) => {
+            bail!("recorded a chat request, got a summary request")
+        }
+        (RecordedRequest::Summarize { .. }, RecordedRequest::Chat { .. }) => {
+            bail!("recorded a summary request, got a chat request")
+        }
+    }
+}
+
+#[async_trait]
+impl ChatProvider for MockChatProvider {
+    async fn chat(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<LLMResponse> {
+        self.next(RecordedRequest::Chat {
+            messages: messages.to_vec(),
+            tools: tools
+                .unwrap_or_default()
+                .iter()
+                .map(|t| t.name.clone())
+                .collect(),
+        })?
+        .into_result()
+    }
+
+    async fn summarize(&self, text: &str) -> Result<String> {
+        let reply = self.next(RecordedRequest::Summarize {
+            text: text.to_string(),
+        })?;
+        match reply.into_result()?.content {
+            LLMResponseContent::Text(text) => Ok(text),
+            LLMResponseContent::ToolCalls(_) => bail!("Replay gave tool calls for a summary"),
+        }
+    }
+
+    fn is_local(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn user(content: &str) -> Message {
+        Message {
+            role: Role::User,
+            content: content.to_string(),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+        }
+    }
+
+    fn text(response: &Result<LLMResponse>) -> &str {
+        match &response.as_ref().unwrap().content {
+            LLMResponseContent::Text(text) => text,
+            LLMResponseContent::ToolCalls(_) => panic!("expected text"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("session.jsonl");
+        let call = ToolCall {
+            id: "t1".to_string(),
+            name: "set_timer".to_string(),
+            arguments: r#"{"duration":"10m"}"#.to_string(),
+        };
+
+        let scripted = MockChatProvider::new([
+            LLMResponse::tool_calls(vec![call]),
+            LLMResponse::text("Timer set.".to_string()),
+        ]);
+        let recorder = RecordingProvider::new(Box::new(scripted), &path).unwrap();
+        let messages = vec![user("Set a timer for 10 minutes")];
+        recorder.chat(&messages, None).await.unwrap();
+        assert_eq!(text(&recorder.chat(&messages, None).await), "Timer set.");
+        // Running out of script is recorded as an error too
+        assert!(recorder.chat(&messages, None).await.is_err());
+
+        let replay = MockChatProvider::from_recording(&path).unwrap();
+        assert_eq!(replay.remaining(), 3);
+        let first = replay.chat(&messages, None).await.unwrap();
+        assert!(matches!(
+            first.content,
+            LLMResponseContent::ToolCalls(ref calls) if calls[0].name == "set_timer"
+        ));
+        assert_eq!(text(&replay.chat(&messages, None).await), "Timer set.");
+        assert!(replay.chat(&messages, None).await.is_err());
+        assert_eq!(replay.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_replay_detects_divergence() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("session.jsonl");
+        let recorder = RecordingProvider::new(
+            Box::new(MockChatProvider::new([LLMResponse::text(
+                "Hi!".to_string(),
+            )])),
+            &path,
+        )
+        .unwrap();
+        recorder.chat(&[user("Hello")], None).await.unwrap();
+
+        let replay = MockChatProvider::from_recording(&path).unwrap();
+        let err = replay.chat(&[user("Goodbye")], None).await.err().unwrap();
+        assert!(err.to_string().contains("diverged at request 1"));
+
+        let replay = MockChatProvider::from_recording(&path).unwrap();
+        assert!(replay.summarize("notes").await.is_err());
+    }
+}
@@ -0,0 +1,396 @@
+//! Retrieval traces: which memory went into a reply ("why did it say that?").
+//!
+//! Each turn records the memory searches it ran: the query and its
+//! expansion, every candidate chunk with its score and verification result,
+//! and what the context packer did with it. When the session is saved the
+//! turn's trace is appended to `sessions/traces/<session id>.jsonl`, one line
+//! per turn, for `homegpt session trace <id> <turn>` and the web UI's
+//! retrieval expander under each reply.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use super::context_packing::{Dropped, Packing};
+use crate::locale;
+use crate::memory::{Confidence, QueryExpansion, VerifiedChunk};
+
+/// Directory under the sessions directory
+const TRACES_DIR: &str = "traces";
+
+/// Memory retrieval during one turn
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetrievalTrace {
+    /// 1-based position among the session's traced turns
+    #[serde(default)]
+    pub turn: usize,
+    /// Short turn ID, as on the `turn` span in the logs
+    pub turn_id: String,
+    pub timestamp: String,
+    /// The user's message
+    pub message: String,
+    pub searches: Vec<SearchTrace>,
+    #[serde(skip)]
+    session_id: String,
+}
+
+/// One memory_search call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchTrace {
+    pub query: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rewrites: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hypothetical: Option<String>,
+    /// Every chunk retrieved, best score first
+    pub candidates: Vec<CandidateTrace>,
+    /// Tokens injected, and the budget they were packed into (0 = unlimited)
+    pub tokens: usize,
+    pub budget: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CandidateTrace {
+    pub hash: String,
+    pub file: String,
+    pub line_start: i32,
+    pub line_end: i32,
+    pub score: f64,
+    pub verified: bool,
+    pub confidence: Confidence,
+    #[serde(default)]
+    pub stale: bool,
+    pub outcome: Outcome,
+}
+
+/// What happened to a candidate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Injected,
+    Summarized,
+    Truncated,
+    /// Left out by `memory.max_chunks_per_file`
+    FileCap,
+    /// Left out by `memory.context_budget_tokens`
+    OverBudget,
+    /// Past the number of results asked for
+    NotUsed,
+}
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Injected => write!(f, "injected"),
+            Self::Summarized => write!(f, "summarized"),
+            Self::Truncated => write!(f, "truncated"),
+            Self::FileCap => write!(f, "per-file cap"),
+            Self::OverBudget => write!(f, "over budget"),
+            Self::NotUsed => write!(f, "not used"),
+        }
+    }
+}
+
+impl SearchTrace {
+    /// Trace a search from its candidates and how they were packed
+    pub fn new(
+        query: &str,
+        expansion: &QueryExpansion,
+        candidates: &[VerifiedChunk],
+        packing: &Packing,
+    ) -> Self {
+        let outcome = |chunk: &VerifiedChunk| {
+            if let Some(packed) = packing.chunks.iter().find(|p| p.chunk.hash == chunk.hash) {
+                return if packed.summarized {
+                    Outcome::Summarized
+                } else if packed.truncated {
+                    Outcome::Truncated
+                } else {
+                    Outcome::Injected
+                };
+            }
+            match packing.dropped.iter().find(|d| d.1 == chunk.hash_prefix) {
+                Some((_, _, _, Dropped::FileCap)) => Outcome::FileCap,
+                Some((_, _, _, Dropped::OverBudget)) => Outcome::OverBudget,
+                None => Outcome::NotUsed,
+            }
+        };
+        let mut candidates: Vec<CandidateTrace> = candidates
+            .iter()
+            .map(|chunk| CandidateTrace {
+                hash: chunk.hash_prefix.clone(),
+                file: chunk.file.clone(),
+                line_start: chunk.line_start,
+                line_end: chunk.line_end,
+                score: chunk.score,
+                verified: chunk.verified,
+                confidence: chunk.confidence,
+                stale: chunk.stale,
+                outcome: outcome(chunk),
+            })
+            .collect();
+        candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+        Self {
+            query: query.to_string(),
+            rewrites: expansion.rewrites.clone(),
+            hypothetical: expansion.hypothetical.clone(),
+            candidates,
+            tokens: packing.tokens,
+            budget: packing.budget,
+        }
+    }
+
+    fn injected(&self) -> usize {
+        self.candidates
+            .iter()
+            .filter(|c| {
+                matches!(
+                    c.outcome,
+                    Outcome::Injected | Outcome::Summarized | Outcome::Truncated
+                )
+            })
+            .count()
+    }
+}
+
+impl RetrievalTrace {
+    /// Plain-text view, for the CLI and the web UI
+    pub fn format(&self) -> String {
+        let mut out = format!(
+            "Turn {} ({}) at {}\nMessage: {}\n",
+            self.turn, self.turn_id, self.timestamp, self.message
+        );
+        if self.searches.is_empty() {
+            out.push_str("\nNo memory searches in this turn.\n");
+        }
+        for (i, search) in self.searches.iter().enumerate() {
+            out.push_str(&format!("\nSearch {}: {}\n", i + 1, search.query));
+            for rewrite in &search.rewrites {
+                out.push_str(&format!("  Rewrite: {}\n", rewrite));
+            }
+            if let Some(ref hypothetical) = search.hypothetical {
+                out.push_str(&format!("  Hypothetical: {}\n", hypothetical));
+            }
+            let budget = match search.budget {
+                0 => "no limit".to_string(),
+                budget => budget.to_string(),
+            };
+            out.push_str(&format!(
+                "  Injected {} of {} candidates, {} tokens (budget {})\n",
+                search.injected(),
+                search.candidates.len(),
+                search.tokens,
+                budget
+            ));
+            for c in &search.candidates {
+                let tag = if c.verified {
+                    format!("[VERIFIED:{}]", c.hash)
+                } else {
+                    format!("[UNVERIFIED:{}]", c.hash)
+                };
+                out.push_str(&format!(
+                    "  {:<12} {:.3}  {} {}:{}-{} (confidence {}{})\n",
+                    c.outcome.to_string(),
+                    c.score,
+                    tag,
+                    c.file,
+                    c.line_start,
+                    c.line_end,
+                    c.confidence,
+                    if c.stale { ", stale" } else { "" }
+                ));
+            }
+        }
+        out
+    }
+}
+
+/// Collects the searches of the turn in progress, shared between the agent
+/// and the memory_search tool
+#[derive(Clone, Default)]
+pub struct RetrievalRecorder {
+    state: Arc<Mutex<RecorderState>>,
+}
+
+#[derive(Default)]
+struct RecorderState {
+    current: Option<RetrievalTrace>,
+    /// Finished turns not yet written to disk
+    unsaved: Vec<RetrievalTrace>,
+}
+
+impl RetrievalRecorder {
+    pub fn begin_turn(&self, session_id: &str, turn_id: &str, message: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.current = Some(RetrievalTrace {
+            turn: 0,
+            turn_id: turn_id.to_string(),
+            timestamp: locale::now().to_rfc3339(),
+            message: message.to_string(),
+            searches: Vec::new(),
+            session_id: session_id.to_string(),
+        });
+    }
+
+    /// Add a search to the turn in progress (searches outside a turn, like
+    /// `/memory`, aren't traced)
+    pub fn record(&self, search: SearchTrace) {
+        if let Some(ref mut trace) = self.state.lock().unwrap().current {
+            trace.searches.push(search);
+        }
+    }
+
+    pub fn end_turn(&self) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(trace) = state.current.take() {
+            state.unsaved.push(trace);
+        }
+    }
+
+    /// Append finished turns to their sessions' trace files
+    pub fn save(&self, sessions_dir: &Path) -> Result<()> {
+        let unsaved = std::mem::take(&mut self.state.lock().unwrap().unsaved);
+        for mut trace in unsaved {
+            let path = trace_path(sessions_dir, &trace.session_id);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            trace.turn = count_lines(&path) + 1;
+            let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+            writeln!(file, "{}", serde_json::to_string(&trace)?)?;
+        }
+        Ok(())
+    }
+
+    /// Every traced turn of `session_id`, saved or not
+    pub fn traces(&self, sessions_dir: &Path, session_id: &str) -> Result<Vec<RetrievalTrace>> {
+        let mut traces = load_traces(sessions_dir, session_id)?;
+        let state = self.state.lock().unwrap();
+        for trace in state.unsaved.iter().filter(|t| t.session_id == session_id) {
+            let mut trace = trace.clone();
+            trace.turn = traces.len() + 1;
+            traces.push(trace);
+        }
+        Ok(traces)
+    }
+}
+
+fn trace_path(sessions_dir: &Path, session_id: &str) -> PathBuf {
+    sessions_dir
+        .join(TRACES_DIR)
+        .join(format!("{}.jsonl", session_id))
+}
+
+fn count_lines(path: &Path) -> usize {
+    fs::read_to_string(path)
+        .map(|content| content.lines().filter(|l| !l.trim().is_empty()).count())
+        .unwrap_or(0)
+}
+
+/// The saved traces of `session_id`, oldest turn first
+pub fn load_traces(sessions_dir: &Path, session_id: &str) -> Result<Vec<RetrievalTrace>> {
+    let Ok(content) = fs::read_to_string(trace_path(sessions_dir, session_id)) else {
+        return Ok(Vec::new());
+    };
+    Ok(content
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .filter_map(|line| serde_json::from_str::<RetrievalTrace>(line).ok())
+        .enumerate()
+        .map(|(i, mut trace)| {
+            trace.turn = i + 1;
+            trace
+        })
+        .collect())
+}
+
+/// The trace `turn` names: a turn number, "last", or a turn ID from the
+/// logs (or its start; IDs can be all digits, so numbers past the last turn
+/// are tried as IDs too)
+pub fn find_turn<'a>(traces: &'a [RetrievalTrace], turn: &str) -> Option<&'a RetrievalTrace> {
+    if turn == "last" {
+        return traces.last();
+    }
+    turn.parse::<usize>()
+        .ok()
+        .and_then(|n| traces.iter().find(|t| t.turn == n))
+        .or_else(|| traces.iter().find(|t| t.turn_id.starts_with(turn)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::context_packing::ContextPacker;
+    use crate::memory::Provenance;
+    use tempfile::TempDir;
+
+    fn chunk(file: &str, hash: &str, score: f64) -> VerifiedChunk {
+        VerifiedChunk {
+            file: file.to_string(),
+            line_start: 1,
+            line_end: 4,
+            content: "Ella's dentist is Dr. Park.".to_string(),
+            score,
+            verified: true,
+            hash_prefix: hash.to_string(),
+            hash: format!("{}0000", hash),
+            provenance: Provenance::Unknown,
+            confidence: Confidence::High,
+            last_verified: None,
+            stale: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_trace_outcomes_and_save() -> Result<()> {
+        let candidates = vec![
+            chunk("kids.md", "aaaa", 0.9),
+            chunk("kids.md", "bbbb", 0.8),
+            chunk("house.md", "cccc", 0.5),
+            chunk("house.md", "dddd", 0.1),
+        ];
+        let packing = ContextPacker::new(1500, 1)
+            .pack(candidates.clone(), 2)
+            .await?;
+        let search = SearchTrace::new("dentist", &QueryExpansion::default(), &candidates, &packing);
+        let outcomes: Vec<Outcome> = search.candidates.iter().map(|c| c.outcome).collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                Outcome::Injected,
+                Outcome::FileCap,
+                Outcome::Injected,
+                Outcome::NotUsed
+            ]
+        );
+
+        let dir = TempDir::new()?;
+        let recorder = RetrievalRecorder::default();
+        recorder.record(search.clone());
+        for (turn_id, message) in [("11111111", "hi"), ("22222222", "dentist?")] {
+            recorder.begin_turn("s1", turn_id, message);
+            if message == "dentist?" {
+                recorder.record(search.clone());
+            }
+            recorder.end_turn();
+        }
+        recorder.save(dir.path())?;
+        recorder.begin_turn("s1", "33333333", "thanks");
+        recorder.end_turn();
+
+        let traces = recorder.traces(dir.path(), "s1")?;
+        assert_eq!(traces.len(), 3);
+        assert!(traces[0].searches.is_empty());
+        let turn = find_turn(&traces, "2").unwrap();
+        assert_eq!(turn.turn_id, "22222222");
+        assert!(turn.format().contains("Injected 2 of 4 candidates"));
+        assert_eq!(find_turn(&traces, "3333").unwrap().turn, 3);
+        assert_eq!(find_turn(&traces, "last").unwrap().message, "thanks");
+        assert_eq!(load_traces(dir.path(), "s1")?.len(), 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,266 @@
+//! Safety filter: replies are checked before a kid sees or hears them
+//!
+//! Under a profile in `safety.profiles`, every reply is checked against
+//! `safety.words` and, with `safety.model` set, by that model. An unsuitable
+//! reply is replaced with `safety.message` or, with `action = "rephrase"`,
+//! rewritten for a child first. The check runs before the reply reaches the
+//! chat view, TTS or the session.
+
+use anyhow::Result;
+use regex::Regex;
+use tracing::{info, warn};
+
+use super::providers::{create_provider, ChatProvider, LLMResponseContent, Message, Role};
+use crate::config::Config;
+use crate::locale;
+
+const CLASSIFY_PROMPT: &str = "You check replies an assistant is about to give a child \
+under 13. Answer SAFE if the reply below suits a child, or UNSAFE if it has violence, \
+sexual content, self-harm, drugs, cruelty, scary detail or language a parent wouldn't \
+want. Answer with the one word only.";
+
+const REPHRASE_PROMPT: &str = "Rewrite the reply below for a child under 13: keep what \
+is helpful, leave out anything unsuitable, and if nothing suitable is left, kindly \
+suggest asking a grown-up. Answer with the rewritten reply only.";
+
+/// What to do with an unsuitable reply
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Block,
+    Rephrase,
+}
+
+impl Action {
+    fn parse(action: &str) -> Result<Self> {
+        match action {
+            "block" => Ok(Self::Block),
+            "rephrase" => Ok(Self::Rephrase),
+            other => anyhow::bail!(
+                "Invalid safety.action '{}' (expected block or rephrase)",
+                other
+            ),
+        }
+    }
+}
+
+/// Checks replies for one profile
+pub struct SafetyFilter {
+    /// One matcher for all of `safety.words`
+    words: Option<Regex>,
+    /// Classifies replies, and rephrases them if set
+    model: Option<Box<dyn ChatProvider>>,
+    action: Action,
+    message: Option<String>,
+}
+
+impl SafetyFilter {
+    /// Filter for the current profile (HOMEGPT_PROFILE), or `None` if its
+    /// replies aren't checked
+    pub fn from_config(config: &Config) -> Result<Option<Self>> {
+        Self::for_profile(config, Config::profile().as_deref())
+    }
+
+    /// Filter for `profile` (None = the default profile)
+    pub fn for_profile(config: &Config, profile: Option<&str>) -> Result<Option<Self>> {
+        let safety = &config.safety;
+        let action = Action::parse(&safety.action)?;
+        let Some(profile) = profile else {
+            return Ok(None);
+        };
+        if !safety
+            .profiles
+            .iter()
+            .any(|p| p.eq_ignore_ascii_case(profile))
+        {
+            return Ok(None);
+        }
+        let model = match safety.model {
+            Some(ref model) => Some(create_provider(model, config)?),
+            None => None,
+        };
+        Ok(Some(Self::new(
+            &safety.words,
+            model,
+            action,
+            safety.message.clone(),
+        )?))
+    }
+
+    fn new(
+        words: &[String],
+        model: Option<Box<dyn ChatProvider>>,
+        action: Action,
+        message: Option<String>,
+    ) -> Result<Self> {
+        let words: Vec<String> = words
+            .iter()
+            .map(|w| w.trim())
+            .filter(|w| !w.is_empty())
+            .map(regex::escape)
+            .collect();
+        let words = if words.is_empty() {
+            None
+        } else {
+            Some(Regex::new(&format!(r"(?i)\b(?:{})\b", words.join("|")))?)
+        };
+        Ok(Self {
+            words,
+            model,
+            action,
+            message,
+        })
+    }
+
+    /// `reply`, or what replaces it if it's unsuitable. Rephrasing uses
+    /// `safety.model` if set, otherwise `chat`.
+    pub async fn filter(&self, reply: &str, chat: &dyn ChatProvider) -> String {
+        if reply.trim().is_empty() {
+            return reply.to_string();
+        }
+        let Some(reason) = self.flag(reply).await else {
+            return reply.to_string();
+        };
+        info!("Safety filter caught a reply ({})", reason);
+
+        if self.action == Action::Rephrase {
+            let rephraser: &dyn ChatProvider = match self.model {
+                Some(ref model) => model.as_ref(),
+                None => chat,
+            };
+            match ask(rephraser, REPHRASE_PROMPT, reply).await {
+                // The rewrite only gets the word check: a second model call
+                // per reply would double the cost for little gain
+                Ok(rephrased)
+                    if !rephrased.trim().is_empty() && self.word(&rephrased).is_none() =>
+                {
+                    return rephrased;
+                }
+                Ok(_) => info!("Safety filter: the rephrased reply was unsuitable too"),
+                Err(e) => warn!("Safety filter couldn't rephrase a reply: {}", e),
+            }
+        }
+        self.blocked_message()
+    }
+
+    /// Why `reply` is unsuitable, if it is. A reply the model couldn't
+    /// check counts as unsuitable.
+    async fn flag(&self, reply: &str) -> Option<String> {
+        if let Some(word) = self.word(reply) {
+            return Some(format!("word '{}'", word));
+        }
+        let model = self.model.as_deref()?;
+        match ask(model, CLASSIFY_PROMPT, reply).await {
+            Ok(verdict) if is_safe(&verdict) => None,
+            Ok(_) => Some("classifier".to_string()),
+            Err(e) => Some(format!("classifier failed: {}", e)),
+        }
+    }
+
+    fn word(&self, text: &str) -> Option<String> {
+        self.words
+            .as_ref()?
+            .find(text)
+            .map(|m| m.as_str().to_lowercase())
+    }
+
+    fn blocked_message(&self) -> String {
+        match self.message {
+            Some(ref message) => message.clone(),
+            None => locale::current().text("safety.blocked").to_string(),
+        }
+    }
+}
+
+/// The classifier's answer, read loosely: "Safe." counts, "UNSAFE" doesn't
+fn is_safe(verdict: &str) -> bool {
+    verdict
+        .trim()
+        .trim_matches(|c: char| !c.is_alphanumeric())
+        .eq_ignore_ascii_case("safe")
+}
+
+/// Ask `provider` to apply `instructions` to `reply`
+async fn ask(provider: &dyn ChatProvider, instructions: &str, reply: &str) -> Result<String> {
+    let message = |role, content: String| Message {
+        role,
+        content,
+        tool_calls: None,
+        tool_call_id: None,
+        images: Vec::new(),
+    };
+    let messages = [
+        message(Role::System, instructions.to_string()),
+        message(Role::User, format!("Reply:\n\n{}", reply)),
+    ];
+    match provider.chat(&messages, None).await?.content {
+        LLMResponseContent::Text(text) => Ok(text.trim().to_string()),
+        LLMResponseContent::ToolCalls(_) => anyhow::bail!("Model answered with tool calls"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::providers::LLMResponse;
+    use crate::agent::replay::MockChatProvider;
+
+    fn safety_filter(model: Option<Vec<&str>>, action: &str) -> SafetyFilter {
+        let model = model.map(|replies| {
+            let replies = replies
+                .into_iter()
+                .map(|r| LLMResponse::text(r.to_string()));
+            Box::new(MockChatProvider::new(replies)) as Box<dyn ChatProvider>
+        });
+        let words = vec!["damn".to_string(), "blood bath".to_string()];
+        let action = Action::parse(action).unwrap();
+        SafetyFilter::new(&words, model, action, Some("Ask a grown-up!".to_string())).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_words() {
+        let chat = MockChatProvider::new([]);
+        let filter = safety_filter(None, "block");
+        assert_eq!(
+            filter.filter("Dinosaurs were big.", &chat).await,
+            "Dinosaurs were big."
+        );
+        // Whole words only
+        assert_eq!(
+            filter.filter("The dam holds water.", &chat).await,
+            "The dam holds water."
+        );
+        assert_eq!(
+            filter.filter("Then a Blood Bath began", &chat).await,
+            "Ask a grown-up!"
+        );
+        assert!(Action::parse("scold").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_classifier_and_rephrase() {
+        let chat = MockChatProvider::new([]);
+        let filter = safety_filter(Some(vec!["Safe."]), "rephrase");
+        assert_eq!(filter.filter("Sharks sleep.", &chat).await, "Sharks sleep.");
+
+        // Flagged, then rewritten
+        let filter = safety_filter(Some(vec!["UNSAFE", "Sharks eat fish."]), "rephrase");
+        assert_eq!(
+            filter.filter("A gory shark story", &chat).await,
+            "Sharks eat fish."
+        );
+
+        // A rewrite with a blocked word is blocked
+        let filter = safety_filter(Some(vec!["UNSAFE", "damn sharks"]), "rephrase");
+        assert_eq!(
+            filter.filter("A gory shark story", &chat).await,
+            "Ask a grown-up!"
+        );
+
+        // Nothing left to ask: the check failed, so the reply is blocked
+        let filter = safety_filter(Some(vec![]), "block");
+        assert_eq!(
+            filter.filter("Sharks sleep.", &chat).await,
+            "Ask a grown-up!"
+        );
+    }
+}
@@ -0,0 +1,218 @@
+//! Self-check: a reply's claims are checked against the memory it cites
+//!
+//! With `agent.self_check` set, a reply that cites `[VERIFIED:<hash>]`
+//! chunks goes to the summary model (or the chat model) along with those
+//! chunks' text before it's shown. It lists the reply's claims and whether
+//! the cited text backs each one. A reply with unsupported claims is
+//! rewritten without them (`"revise"`) or shown with a note listing them
+//! (`"flag"`). Replies without citations aren't checked.
+
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use super::providers::{ChatProvider, Message, Role};
+use super::structured;
+use crate::locale;
+use crate::memory::cited_hashes;
+
+/// What to do with a reply that has unsupported claims
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Show the reply with a note listing them
+    Flag,
+    /// Rewrite the reply without them
+    Revise,
+}
+
+impl Mode {
+    /// The mode `agent.self_check` names, or `None` for "off"
+    pub fn parse(mode: &str) -> Result<Option<Self>> {
+        match mode {
+            "off" => Ok(None),
+            "flag" => Ok(Some(Self::Flag)),
+            "revise" => Ok(Some(Self::Revise)),
+            other => anyhow::bail!(
+                "Invalid agent.self_check '{}' (expected off, flag or revise)",
+                other
+            ),
+        }
+    }
+}
+
+/// A chunk the reply cites
+#[derive(Debug, Clone)]
+pub struct Source {
+    /// Hash prefix, as cited
+    pub hash: String,
+    /// The chunk's text, or `None` if the citation points at nothing
+    pub text: Option<String>,
+}
+
+/// The checker's verdict
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CheckReply {
+    /// Each factual claim in the reply
+    pub claims: Vec<Claim>,
+    /// The reply with unsupported claims left out, citations kept
+    pub revised: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct Claim {
+    pub claim: String,
+    /// True if the cited excerpts state it
+    pub supported: bool,
+}
+
+pub fn prompt(reply: &str, sources: &[Source]) -> String {
+    let excerpts: Vec<String> = sources
+        .iter()
+        .map(|s| {
+            format!(
+                "<excerpt id=\"{}\">\n{}\n</excerpt>",
+                s.hash,
+                s.text.as_deref().unwrap_or("(no such note)").trim()
+            )
+        })
+        .collect();
+    format!(
+        "Check a reply an assistant is about to give against the notes it cites. List each \
+         factual claim in the reply and whether the excerpts state it; a claim citing an \
+         excerpt that doesn't say it is unsupported, and so is a guess dressed up as a fact. \
+         Greetings, questions and offers to help aren't claims. Then write the reply again \
+         with the unsupported claims left out, keeping everything else and its \
+         [VERIFIED:...] tags as they are.\n\n{}\n\nReply:\n\n{}",
+        excerpts.join("\n\n"),
+        reply.trim()
+    )
+}
+
+/// `reply` after the check: unchanged when every claim is supported,
+/// otherwise revised or flagged. A revision that's empty or cites chunks
+/// the reply didn't is flagged instead.
+pub fn apply(mode: Mode, reply: &str, check: CheckReply, sources: &[Source]) -> String {
+    let unsupported: Vec<&str> = check
+        .claims
+        .iter()
+        .filter(|c| !c.supported)
+        .map(|c| c.claim.trim())
+        .filter(|c| !c.is_empty())
+        .collect();
+    if unsupported.is_empty() {
+        return reply.to_string();
+    }
+    info!(
+        "Self-check found {} unsupported claim(s)",
+        unsupported.len()
+    );
+
+    let revised = check.revised.trim();
+    let grounded = cited_hashes(revised)
+        .iter()
+        .all(|h| sources.iter().any(|s| s.hash == *h));
+    if mode == Mode::Revise && !revised.is_empty() && grounded {
+        return revised.to_string();
+    }
+
+    let mut flagged = format!(
+        "{}\n\n{}",
+        reply.trim_end(),
+        locale::current().text("self_check.unsupported")
+    );
+    for claim in unsupported {
+        flagged.push_str("\n- ");
+        flagged.push_str(claim);
+    }
+    flagged
+}
+
+/// Check `reply` against `sources` with `checker`. If the check fails, the
+/// reply is shown as it is.
+pub async fn check(
+    checker: &dyn ChatProvider,
+    mode: Mode,
+    reply: &str,
+    sources: &[Source],
+) -> String {
+    let messages = [Message {
+        role: Role::User,
+        content: prompt(reply, sources),
+        tool_calls: None,
+        tool_call_id: None,
+        images: Vec::new(),
+    }];
+    match structured::chat_structured::<CheckReply>(checker, &messages).await {
+        Ok(verdict) => apply(mode, reply, verdict, sources),
+        Err(e) => {
+            warn!("Self-check failed, showing the reply unchecked: {}", e);
+            reply.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sources() -> Vec<Source> {
+        vec![Source {
+            hash: "ab12cd34".to_string(),
+            text: Some("Acme Plumbing installed the water heater in 2021.".to_string()),
+        }]
+    }
+
+    fn verdict(supported: bool, revised: &str) -> CheckReply {
+        CheckReply {
+            claims: vec![
+                Claim {
+                    claim: "Acme Plumbing installed it".to_string(),
+                    supported: true,
+                },
+                Claim {
+                    claim: "It has a 10 year warranty".to_string(),
+                    supported,
+                },
+            ],
+            revised: revised.to_string(),
+        }
+    }
+
+    const REPLY: &str = "Acme Plumbing installed it [VERIFIED:ab12cd34], and it has a 10 \
+                         year warranty.";
+
+    #[test]
+    fn test_mode_parse() {
+        assert_eq!(Mode::parse("off").unwrap(), None);
+        assert_eq!(Mode::parse("revise").unwrap(), Some(Mode::Revise));
+        assert!(Mode::parse("strict").is_err());
+    }
+
+    #[test]
+    fn test_apply_supported_unchanged() {
+        let checked = apply(Mode::Revise, REPLY, verdict(true, "other"), &sources());
+        assert_eq!(checked, REPLY);
+    }
+
+    #[test]
+    fn test_apply_revise() {
+        let revised = "Acme Plumbing installed it [VERIFIED:ab12cd34].";
+        let checked = apply(Mode::Revise, REPLY, verdict(false, revised), &sources());
+        assert_eq!(checked, revised);
+
+        // A revision citing something else is flagged instead
+        let invented = "Acme installed it [VERIFIED:ffff0000].";
+        let checked = apply(Mode::Revise, REPLY, verdict(false, invented), &sources());
+        assert!(checked.starts_with(REPLY));
+        assert!(checked.ends_with("\n- It has a 10 year warranty"));
+    }
+
+    #[test]
+    fn test_apply_flag() {
+        let checked = apply(Mode::Flag, REPLY, verdict(false, "ignored"), &sources());
+        assert!(checked.starts_with(REPLY));
+        assert!(checked.contains("\n- It has a 10 year warranty"));
+        assert!(!checked.contains("- Acme Plumbing installed it"));
+    }
+}
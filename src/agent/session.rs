@@ -9,11 +9,14 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::fs::{self, File};
-use std::io::{BufRead, BufReader, Write};
+use std::io::{BufRead, BufReader, BufWriter, Write};
 use std::path::PathBuf;
 use uuid::Uuid;
 
-use super::providers::{LLMProvider, Message, Role, ToolCall, Usage};
+use super::attachments::split_attachments;
+use super::providers::{ChatProvider, Message, Role, ToolCall, Usage};
+use super::session_store::SessionStore;
+use super::tokens::Tokenizer;
 
 /// Current session format version (matches Pi)
 pub const CURRENT_SESSION_VERSION: u32 = 1;
@@ -30,6 +33,8 @@ pub struct Session {
     /// Appended to system_context when building messages for LLM.
     additional_context: Option<String>,
     token_count: usize,
+    /// Encoding used for `token_count`, chosen from the session's model
+    tokenizer: Tokenizer,
     compaction_count: u32,
     memory_flush_compaction_count: u32,
 }
@@ -75,10 +80,15 @@ impl From<&Usage> for MessageUsage {
         Self {
             input: usage.input_tokens,
             output: usage.output_tokens,
-            cache_read: None,
-            cache_write: None,
+            cache_read: Some(usage.cache_read_tokens).filter(|&t| t > 0),
+            cache_write: Some(usage.cache_write_tokens).filter(|&t| t > 0),
             total_tokens: usage.total(),
-            cost: None, // Cost calculation not implemented
+            // Only the total is known (reported by OpenRouter)
+            cost: usage.cost.map(|total| MessageCost {
+                input: 0.0,
+                output: 0.0,
+                total,
+            }),
         }
     }
 }
@@ -123,6 +133,20 @@ pub struct SessionStatus {
     pub compaction_count: u32,
     pub api_input_tokens: u64,
     pub api_output_tokens: u64,
+    /// Input tokens served from the provider's prompt cache
+    pub api_cache_read_tokens: u64,
+    /// Input tokens written to the provider's prompt cache
+    pub api_cache_write_tokens: u64,
+}
+
+impl SessionStatus {
+    /// Share of API input tokens served from the prompt cache (0.0-1.0)
+    pub fn cache_hit_ratio(&self) -> f64 {
+        if self.api_input_tokens == 0 {
+            return 0.0;
+        }
+        self.api_cache_read_tokens as f64 / self.api_input_tokens as f64
+    }
 }
 
 impl Session {
@@ -143,6 +167,7 @@ impl Session {
             system_context: None,
             additional_context: None,
             token_count: 0,
+            tokenizer: Tokenizer::default(),
             compaction_count: 0,
             memory_flush_compaction_count: 0,
         }
@@ -168,6 +193,24 @@ impl Session {
         self.memory_flush_compaction_count = self.compaction_count + 1;
     }
 
+    /// Count tokens with the encoding for `model`
+    pub fn set_model(&mut self, model: &str) {
+        self.tokenizer = Tokenizer::for_model(model);
+        self.recalculate_tokens();
+    }
+
+    pub fn tokenizer(&self) -> Tokenizer {
+        self.tokenizer
+    }
+
+    pub fn system_context(&self) -> Option<&str> {
+        self.system_context.as_deref()
+    }
+
+    pub fn additional_context(&self) -> Option<&str> {
+        self.additional_context.as_deref()
+    }
+
     pub fn set_system_context(&mut self, context: String) {
         self.system_context = Some(context);
         self.recalculate_tokens();
@@ -177,11 +220,12 @@ impl Session {
     /// Pass `None` to clear.
     pub fn set_additional_context(&mut self, context: Option<String>) {
         self.additional_context = context;
+        self.recalculate_tokens();
     }
 
     /// Add a message without metadata
     pub fn add_message(&mut self, message: Message) {
-        let tokens = estimate_tokens(&message.content);
+        let tokens = self.tokenizer.count_message(&message);
         self.token_count += tokens;
         self.messages.push(SessionMessage::new(message));
     }
@@ -195,7 +239,7 @@ impl Session {
         usage: Option<&Usage>,
         stop_reason: Option<&str>,
     ) {
-        let tokens = estimate_tokens(&message.content);
+        let tokens = self.tokenizer.count_message(&message);
         self.token_count += tokens;
         self.messages.push(SessionMessage::with_metadata(
             message,
@@ -206,6 +250,47 @@ impl Session {
         ));
     }
 
+    /// Drop tool results at the end of the history that have no assistant
+    /// tool-call message after them (a tool round that was cut off)
+    pub fn discard_trailing_tool_results(&mut self) {
+        let before = self.messages.len();
+        while self
+            .messages
+            .last()
+            .is_some_and(|sm| sm.message.role == Role::Tool)
+        {
+            self.messages.pop();
+        }
+        if self.messages.len() != before {
+            self.recalculate_tokens();
+        }
+    }
+
+    /// Remove a user message and everything after it, returning the removed
+    /// message. `content` is matched without appended attachments, and
+    /// `occurrence` picks among user messages with the same text, 0 being the
+    /// most recent. Returns None if no such message is in the history (e.g. it
+    /// was compacted away).
+    pub fn truncate_at_user_message(
+        &mut self,
+        content: &str,
+        occurrence: usize,
+    ) -> Option<Message> {
+        let position = self
+            .messages
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, sm)| {
+                sm.message.role == Role::User && split_attachments(&sm.message.content).0 == content
+            })
+            .nth(occurrence)
+            .map(|(i, _)| i)?;
+        let removed = self.messages.drain(position..).next().map(|sm| sm.message);
+        self.recalculate_tokens();
+        removed
+    }
+
     pub fn messages_for_llm(&self) -> Vec<Message> {
         let mut messages = Vec::new();
 
@@ -247,7 +332,7 @@ impl Session {
             .collect()
     }
 
-    pub async fn compact(&mut self, provider: &dyn LLMProvider) -> Result<()> {
+    pub async fn compact(&mut self, provider: &dyn ChatProvider) -> Result<()> {
         if self.messages.len() < 4 {
             return Ok(());
         }
@@ -281,15 +366,12 @@ impl Session {
     }
 
     fn recalculate_tokens(&mut self) {
-        self.token_count = 0;
-
-        if let Some(ref context) = self.system_context {
-            self.token_count += estimate_tokens(context);
-        }
-
-        for sm in &self.messages {
-            self.token_count += estimate_tokens(&sm.message.content);
-        }
+        let tokenizer = self.tokenizer;
+        self.token_count = self
+            .messages_for_llm()
+            .iter()
+            .map(|m| tokenizer.count_message(m))
+            .sum();
     }
 
     /// Save session in Pi-compatible JSONL format
@@ -311,8 +393,11 @@ impl Session {
         Ok(path)
     }
 
+    /// Write the session to a temp file and rename it over `path`, so a
+    /// crash mid-save leaves the previous version intact
     fn save_to_path(&self, path: &PathBuf) -> Result<()> {
-        let mut file = File::create(path)?;
+        let tmp_path = path.with_extension("jsonl.tmp");
+        let mut file = BufWriter::new(File::create(&tmp_path)?);
 
         // Write Pi-compatible header
         let header = json!({
@@ -345,6 +430,8 @@ impl Session {
             writeln!(file, "{}", serde_json::to_string(&entry)?)?;
         }
 
+        file.into_inner()?.sync_all()?;
+        fs::rename(&tmp_path, path)?;
         Ok(())
     }
 
@@ -430,7 +517,12 @@ impl Session {
 
     /// Load session (supports both old and Pi formats)
     pub fn load(session_id: &str) -> Result<Self> {
-        let dir = get_sessions_dir()?;
+        Self::load_for_agent(DEFAULT_AGENT_ID, session_id)
+    }
+
+    /// Load one of `agent_id`'s saved sessions
+    pub fn load_for_agent(agent_id: &str, session_id: &str) -> Result<Self> {
+        let dir = get_sessions_dir_for_agent(agent_id)?;
         let path = dir.join(format!("{}.jsonl", session_id));
 
         if !path.exists() {
@@ -452,6 +544,7 @@ impl Session {
             system_context: None,
             additional_context: None,
             token_count: 0,
+            tokenizer: Tokenizer::default(),
             compaction_count: 0,
             memory_flush_compaction_count: 0,
         };
@@ -570,24 +663,19 @@ impl Session {
     }
 
     pub fn status(&self) -> SessionStatus {
-        SessionStatus {
-            id: self.id.clone(),
-            message_count: self.messages.len(),
-            token_count: self.token_count,
-            compaction_count: self.compaction_count,
-            api_input_tokens: 0,
-            api_output_tokens: 0,
-        }
+        self.status_with_usage(&Usage::default())
     }
 
-    pub fn status_with_usage(&self, input_tokens: u64, output_tokens: u64) -> SessionStatus {
+    pub fn status_with_usage(&self, usage: &Usage) -> SessionStatus {
         SessionStatus {
             id: self.id.clone(),
             message_count: self.messages.len(),
             token_count: self.token_count,
             compaction_count: self.compaction_count,
-            api_input_tokens: input_tokens,
-            api_output_tokens: output_tokens,
+            api_input_tokens: usage.input_tokens,
+            api_output_tokens: usage.output_tokens,
+            api_cache_read_tokens: usage.cache_read_tokens,
+            api_cache_write_tokens: usage.cache_write_tokens,
         }
     }
 
@@ -632,16 +720,18 @@ pub fn get_state_dir() -> Result<PathBuf> {
     Ok(base.home_dir().join(".homegpt"))
 }
 
-fn estimate_tokens(text: &str) -> usize {
-    text.len() / 4
-}
-
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct SessionInfo {
     pub id: String,
+    /// The agent whose sessions directory holds it
+    pub agent_id: String,
     pub created_at: DateTime<Utc>,
     pub message_count: usize,
     pub file_size: u64,
+    /// Name, pin and tags from sessions.json
+    pub label: Option<String>,
+    pub pinned: bool,
+    pub tags: Vec<String>,
 }
 
 pub fn list_sessions() -> Result<Vec<SessionInfo>> {
@@ -655,6 +745,7 @@ pub fn list_sessions_for_agent(agent_id: &str) -> Result<Vec<SessionInfo>> {
         return Ok(Vec::new());
     }
 
+    let store = SessionStore::load_for_agent(agent_id).ok();
     let mut sessions = Vec::new();
 
     for entry in fs::read_dir(&sessions_dir)? {
@@ -690,11 +781,16 @@ pub fn list_sessions_for_agent(agent_id: &str) -> Result<Vec<SessionInfo>> {
                             .map(|s| s.lines().count().saturating_sub(1))
                             .unwrap_or(0);
 
+                        let meta = store.as_ref().and_then(|s| s.find_session(filename));
                         sessions.push(SessionInfo {
                             id: filename.to_string(),
+                            agent_id: agent_id.to_string(),
                             created_at,
                             message_count,
                             file_size,
+                            label: meta.and_then(|m| m.label.clone()),
+                            pinned: meta.is_some_and(|m| m.pinned),
+                            tags: meta.map(|m| m.tags.clone()).unwrap_or_default(),
                         });
                     }
                 }
@@ -706,6 +802,42 @@ pub fn list_sessions_for_agent(agent_id: &str) -> Result<Vec<SessionInfo>> {
     Ok(sessions)
 }
 
+/// Saved sessions of every agent, newest first
+pub fn list_all_sessions() -> Result<Vec<SessionInfo>> {
+    let agents_dir = get_state_dir()?.join("agents");
+    if !agents_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut sessions = Vec::new();
+    for entry in fs::read_dir(&agents_dir)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        if let Some(agent_id) = entry.file_name().to_str() {
+            sessions.extend(list_sessions_for_agent(agent_id)?);
+        }
+    }
+
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+    Ok(sessions)
+}
+
+/// Delete a saved session: its JSONL file and its sessions.json entries
+pub fn delete_session_for_agent(agent_id: &str, session_id: &str) -> Result<()> {
+    if session_id.is_empty() || session_id.contains(['/', '\\', '.']) {
+        anyhow::bail!("Invalid session ID: {}", session_id);
+    }
+    let path = get_sessions_dir_for_agent(agent_id)?.join(format!("{}.jsonl", session_id));
+    if !path.exists() {
+        anyhow::bail!("Session not found: {}", session_id);
+    }
+    fs::remove_file(&path)?;
+    SessionStore::load_for_agent(agent_id)?.remove_session(session_id)?;
+    Ok(())
+}
+
 pub fn get_last_session_id() -> Result<Option<String>> {
     get_last_session_id_for_agent(DEFAULT_AGENT_ID)
 }
@@ -812,10 +944,91 @@ mod tests {
         let usage = Usage {
             input_tokens: 100,
             output_tokens: 50,
+            ..Default::default()
         };
         let msg_usage = MessageUsage::from(&usage);
         assert_eq!(msg_usage.input, 100);
         assert_eq!(msg_usage.output, 50);
         assert_eq!(msg_usage.total_tokens, 150);
+        assert!(msg_usage.cache_read.is_none());
+
+        let usage = Usage {
+            input_tokens: 1000,
+            output_tokens: 50,
+            cache_read_tokens: 800,
+            ..Default::default()
+        };
+        let msg_usage = MessageUsage::from(&usage);
+        assert_eq!(msg_usage.cache_read, Some(800));
+        assert!(msg_usage.cache_write.is_none());
+
+        let session = Session::new();
+        let status = session.status_with_usage(&usage);
+        assert!((status.cache_hit_ratio() - 0.8).abs() < 1e-9);
+        assert_eq!(session.status().cache_hit_ratio(), 0.0);
+    }
+
+    #[test]
+    fn test_discard_trailing_tool_results() {
+        let message = |role, content: &str| Message {
+            role,
+            content: content.to_string(),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+        };
+        let mut session = Session::new();
+        session.add_message(message(Role::User, "what's in notes.md?"));
+        session.add_message(message(Role::Tool, "notes"));
+        session.add_message(message(Role::Tool, "more notes"));
+
+        session.discard_trailing_tool_results();
+        assert_eq!(session.messages().len(), 1);
+        assert_eq!(session.messages()[0].role, Role::User);
+    }
+
+    #[test]
+    fn test_truncate_at_user_message() {
+        let message = |role, content: &str| Message {
+            role,
+            content: content.to_string(),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+        };
+        let mut session = Session::new();
+        for _ in 0..2 {
+            session.add_message(message(Role::User, "tell me a joke"));
+            session.add_message(message(Role::Assistant, "knock knock"));
+        }
+
+        assert!(session
+            .truncate_at_user_message("tell me a story", 0)
+            .is_none());
+        let removed = session.truncate_at_user_message("tell me a joke", 1);
+        assert_eq!(removed.unwrap().content, "tell me a joke");
+        assert!(session.messages().is_empty());
+    }
+
+    #[test]
+    fn test_save_replaces_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut session = Session::new();
+        let path = dir.path().join(format!("{}.jsonl", session.id()));
+        session.save_to_path(&path).unwrap();
+
+        session.add_message(Message {
+            role: Role::User,
+            content: "remind me at 5".to_string(),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+        });
+        session.save_to_path(&path).unwrap();
+
+        let loaded = Session::load_from_path(&path, session.id()).unwrap();
+        assert_eq!(loaded.messages().len(), 1);
+        let files: Vec<_> = fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(files.len(), 1);
     }
 }
@@ -4,14 +4,18 @@
 //! ~/.homegpt/agents/<agentId>/sessions/sessions.json
 
 use anyhow::Result;
+use fs2::FileExt;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::fs;
+use std::fs::{self, File};
 use std::path::PathBuf;
 use tracing::debug;
 
 use super::session::{get_sessions_dir_for_agent, DEFAULT_AGENT_ID};
 
+/// Held while sessions.json is read, changed and written back
+const LOCK_FILE: &str = "sessions.lock";
+
 /// Session entry in sessions.json (matches OpenClaw's SessionEntry)
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
@@ -55,6 +59,17 @@ pub struct SessionEntry {
     /// Timestamp when last heartbeat was sent (milliseconds since epoch)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub last_heartbeat_sent_at: Option<u64>,
+
+    /// Name shown in session lists instead of the ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+
+    /// Listed above the other sessions
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
 }
 
 impl SessionEntry {
@@ -193,15 +208,13 @@ impl SessionStore {
             .or_insert_with(|| SessionEntry::new(session_id))
     }
 
-    /// Update a session entry
+    /// Update a session entry, on top of whatever other sessions saved
+    /// since the store was loaded
     pub fn update<F>(&mut self, session_key: &str, session_id: &str, f: F) -> Result<()>
     where
         F: FnOnce(&mut SessionEntry),
     {
-        let entry = self.get_or_create(session_key, session_id);
-        f(entry);
-        entry.updated_at = chrono::Utc::now().timestamp_millis() as u64;
-        self.save()
+        self.locked(|store| store.modify(session_key, session_id, f))
     }
 
     /// Re-read the store from disk, apply a mutation, and save atomically.
@@ -212,18 +225,77 @@ impl SessionStore {
     where
         F: FnOnce(&mut SessionEntry),
     {
-        // Re-read from disk to pick up changes from other processes
-        if self.path.exists() {
-            let content = fs::read_to_string(&self.path)?;
-            self.entries = serde_json::from_str(&content).unwrap_or_default();
-        }
+        self.update(session_key, session_id, f)
+    }
+
+    /// The entry for a session, whatever key it's under
+    pub fn find_session(&self, session_id: &str) -> Option<&SessionEntry> {
+        self.entries.values().find(|e| e.session_id == session_id)
+    }
 
+    /// Update the entry for a session, adding one keyed by its ID if it
+    /// has none
+    pub fn update_session<F>(&mut self, session_id: &str, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut SessionEntry),
+    {
+        self.locked(|store| {
+            let key = store
+                .entries
+                .iter()
+                .find(|(_, e)| e.session_id == session_id)
+                .map_or_else(|| session_id.to_string(), |(key, _)| key.clone());
+            store.modify(&key, session_id, f)
+        })
+    }
+
+    /// Drop every entry for a session, returning how many there were
+    pub fn remove_session(&mut self, session_id: &str) -> Result<usize> {
+        self.locked(|store| {
+            let before = store.entries.len();
+            store.entries.retain(|_, e| e.session_id != session_id);
+            let removed = before - store.entries.len();
+            if removed > 0 {
+                store.save()?;
+            }
+            Ok(removed)
+        })
+    }
+
+    /// Apply a mutation to an entry and save
+    fn modify<F>(&mut self, session_key: &str, session_id: &str, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut SessionEntry),
+    {
         let entry = self.get_or_create(session_key, session_id);
         f(entry);
         entry.updated_at = chrono::Utc::now().timestamp_millis() as u64;
         self.save()
     }
 
+    /// Run `f` on the entries as they are on disk, holding `sessions.lock`
+    /// so sessions saving at the same time (other daemon turns, the CLI)
+    /// don't drop each other's changes
+    fn locked<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let lock = File::create(self.path.with_file_name(LOCK_FILE))?;
+        lock.lock_exclusive()?;
+        let result = self.reload().and_then(|_| f(self));
+        let _ = lock.unlock();
+        result
+    }
+
+    /// Replace the in-memory entries with what's on disk
+    fn reload(&mut self) -> Result<()> {
+        if self.path.exists() {
+            let content = fs::read_to_string(&self.path)?;
+            self.entries = serde_json::from_str(&content).unwrap_or_default();
+        }
+        Ok(())
+    }
+
     /// Get CLI session ID for a session and provider
     pub fn get_cli_session_id(&self, session_key: &str, provider: &str) -> Option<String> {
         self.get(session_key)
@@ -336,6 +408,60 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_session_metadata() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("sessions.json");
+        let mut store = SessionStore {
+            path: path.clone(),
+            entries: HashMap::new(),
+        };
+        store.get_or_create("main", "session-abc");
+        store.save().unwrap();
+
+        // Keyed sessions are updated in place, others get their own entry
+        store
+            .update_session("session-abc", |e| e.label = Some("Trip planning".into()))
+            .unwrap();
+        store
+            .update_session("session-xyz", |e| e.pinned = true)
+            .unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        let parsed: HashMap<String, SessionEntry> = serde_json::from_str(&content).unwrap();
+        assert_eq!(parsed["main"].label.as_deref(), Some("Trip planning"));
+        assert!(parsed["session-xyz"].pinned);
+        assert!(!content.contains("\"tags\""));
+
+        assert_eq!(store.remove_session("session-abc").unwrap(), 1);
+        assert!(store.find_session("session-abc").is_none());
+        assert!(store.find_session("session-xyz").is_some());
+        assert_eq!(store.remove_session("session-abc").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_concurrent_stores_keep_each_others_updates() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("sessions.json");
+        let open = || SessionStore {
+            path: path.clone(),
+            entries: HashMap::new(),
+        };
+
+        // Two sessions load the store, then save one after the other
+        let (mut first, mut second) = (open(), open());
+        first
+            .update("desktop", "session-1", |e| e.total_tokens = Some(10))
+            .unwrap();
+        second
+            .update("matrix:room", "session-2", |e| e.total_tokens = Some(20))
+            .unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        let parsed: HashMap<String, SessionEntry> = serde_json::from_str(&content).unwrap();
+        assert_eq!(parsed["desktop"].total_tokens, Some(10));
+        assert_eq!(parsed["matrix:room"].total_tokens, Some(20));
+    }
+
     #[test]
     fn test_atomic_save_produces_valid_json() {
         let tmp = tempfile::tempdir().unwrap();
@@ -12,6 +12,10 @@ use std::path::{Path, PathBuf};
 use std::process::Command;
 use tracing::{debug, warn};
 
+use super::templates::{render_template, PromptVars};
+use crate::config::AgentDefinition;
+use crate::locale;
+
 /// Skill requirements for eligibility gating
 #[derive(Debug, Clone, Default, Deserialize)]
 #[serde(default)]
@@ -211,6 +215,19 @@ pub fn load_skills(workspace: &Path) -> Result<Vec<Skill>> {
     Ok(skills)
 }
 
+/// Load the skills an agent from agents.toml lists, or all of them for an
+/// agent without a definition
+pub fn load_agent_skills(
+    workspace: &Path,
+    persona: Option<&AgentDefinition>,
+) -> Result<Vec<Skill>> {
+    let mut skills = load_skills(workspace)?;
+    if let Some(persona) = persona {
+        skills.retain(|skill| persona.allows_skill(&skill.name));
+    }
+    Ok(skills)
+}
+
 /// Get the managed skills directory (~/.homegpt/skills/)
 fn get_managed_skills_dir() -> Option<PathBuf> {
     directories::BaseDirs::new().map(|dirs| dirs.home_dir().join(".homegpt").join("skills"))
@@ -228,7 +245,8 @@ fn load_skills_from_dir(dir: &Path, source: SkillSource) -> Result<Vec<Skill>> {
             continue;
         }
 
-        let skill_file = path.join("SKILL.md");
+        // SKILL.es.md replaces SKILL.md when the language is Spanish
+        let skill_file = locale::current().localized_path(&path.join("SKILL.md"));
         if !skill_file.exists() {
             continue;
         }
@@ -480,6 +498,39 @@ pub fn parse_skill_command(input: &str, skills: &[Skill]) -> Option<SkillInvocat
     None
 }
 
+/// The message that has the agent run `skill`, with the user's request if
+/// they gave one. A skill that uses variables is sent filled in, since the
+/// file itself has them unfilled.
+pub fn skill_prompt(skill: &Skill, args: &str, vars: &PromptVars) -> String {
+    let content = fs::read_to_string(&skill.path).unwrap_or_default();
+    let rendered = render_template(&skill.name, &content, vars);
+    if rendered != content {
+        let request = if args.is_empty() {
+            String::new()
+        } else {
+            format!(" with this request: {}", args)
+        };
+        return format!(
+            "Use the {} skill{}. Follow its instructions:\n\n{}",
+            skill.name,
+            request,
+            rendered.trim()
+        );
+    }
+    if args.is_empty() {
+        format!(
+            "Use the skill at {}. Read it first, then follow its instructions.",
+            skill.path.display()
+        )
+    } else {
+        format!(
+            "Use the skill at {} with this request: {}\n\nRead the skill file first, then follow its instructions.",
+            skill.path.display(),
+            args
+        )
+    }
+}
+
 /// Build skills prompt section for the system prompt
 pub fn build_skills_prompt(skills: &[Skill]) -> String {
     // Filter to skills that should be in the prompt
@@ -0,0 +1,196 @@
+//! Structured output: ask the model for JSON matching a Rust type's schema.
+//!
+//! The type's JSON schema is offered as the only tool, so providers with tool
+//! calling return the result as tool arguments; providers without it (Claude
+//! CLI) are asked for JSON in the reply text instead. The result is validated
+//! against the schema and deserialized, and on failure the model is shown the
+//! error and asked again.
+
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use tracing::debug;
+
+use super::providers::{ChatProvider, LLMResponseContent, Message, Role, ToolSchema};
+
+/// Name of the tool the model "calls" to return its answer
+const RESPOND_TOOL: &str = "respond";
+
+/// Requests per call, including retries after invalid output
+const MAX_ATTEMPTS: usize = 3;
+
+/// Send `messages` and parse the reply as a `T`, retrying on invalid output
+pub async fn chat_structured<T: JsonSchema + DeserializeOwned>(
+    provider: &dyn ChatProvider,
+    messages: &[Message],
+) -> Result<T> {
+    let schema = schema_for::<T>();
+    let validator = jsonschema::validator_for(&schema)
+        .map_err(|e| anyhow::anyhow!("Invalid schema for {}: {}", T::schema_name(), e))?;
+    let tools = [ToolSchema {
+        name: RESPOND_TOOL.to_string(),
+        description: format!(
+            "Return the answer. Always call this tool; the arguments are the result ({}).",
+            T::schema_name()
+        ),
+        parameters: schema.clone(),
+    }];
+
+    let mut messages = messages.to_vec();
+    messages.push(user_message(format!(
+        "Answer by calling the `{}` tool. If you cannot call tools, reply with only a JSON \
+         object matching this schema:\n{}",
+        RESPOND_TOOL, schema
+    )));
+
+    let mut last_error = None;
+    for attempt in 1..=MAX_ATTEMPTS {
+        let raw = match provider.chat(&messages, Some(&tools)).await?.content {
+            LLMResponseContent::ToolCalls(calls) => calls
+                .into_iter()
+                .find(|c| c.name == RESPOND_TOOL)
+                .map(|c| c.arguments)
+                .unwrap_or_default(),
+            LLMResponseContent::Text(text) => text,
+        };
+
+        match parse_structured(&validator, &raw) {
+            Ok(value) => return Ok(value),
+            Err(e) => {
+                debug!(
+                    "Structured output attempt {}/{} invalid: {}",
+                    attempt, MAX_ATTEMPTS, e
+                );
+                messages.push(Message {
+                    role: Role::Assistant,
+                    content: raw,
+                    tool_calls: None,
+                    tool_call_id: None,
+                    images: Vec::new(),
+                });
+                messages.push(user_message(format!(
+                    "That answer was invalid: {}. Call `{}` again with corrected arguments.",
+                    e, RESPOND_TOOL
+                )));
+                last_error = Some(e);
+            }
+        }
+    }
+
+    Err(last_error
+        .unwrap_or_else(|| anyhow::anyhow!("No response"))
+        .context(format!(
+            "No valid {} after {} attempts",
+            T::schema_name(),
+            MAX_ATTEMPTS
+        )))
+}
+
+/// JSON schema for `T`, usable as tool parameters
+fn schema_for<T: JsonSchema>() -> Value {
+    let mut schema = schemars::schema_for!(T);
+    schema.remove("$schema");
+    schema.to_value()
+}
+
+fn user_message(content: String) -> Message {
+    Message {
+        role: Role::User,
+        content,
+        tool_calls: None,
+        tool_call_id: None,
+        images: Vec::new(),
+    }
+}
+
+/// Validate `raw` against the schema and deserialize it
+fn parse_structured<T: DeserializeOwned>(
+    validator: &jsonschema::Validator,
+    raw: &str,
+) -> Result<T> {
+    let value: Value = serde_json::from_str(extract_json(raw))
+        .map_err(|e| anyhow::anyhow!("not valid JSON ({})", e))?;
+    if let Err(e) = validator.validate(&value) {
+        anyhow::bail!("does not match the schema at '{}': {}", e.instance_path, e);
+    }
+    Ok(serde_json::from_value(value)?)
+}
+
+/// The JSON object in a text reply, without code fences or surrounding prose
+fn extract_json(text: &str) -> &str {
+    let text = text.trim();
+    match (text.find('{'), text.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &text[start..=end],
+        _ => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::providers::{LLMResponse, ToolCall};
+    use serde::Deserialize;
+    use std::sync::Mutex;
+
+    #[derive(Debug, Deserialize, JsonSchema, PartialEq)]
+    struct Quiz {
+        question: String,
+        answers: Vec<String>,
+    }
+
+    /// Replays canned responses in order
+    struct Replay(Mutex<Vec<LLMResponse>>);
+
+    #[async_trait::async_trait]
+    impl ChatProvider for Replay {
+        async fn chat(
+            &self,
+            _messages: &[Message],
+            _tools: Option<&[ToolSchema]>,
+        ) -> Result<LLMResponse> {
+            Ok(self.0.lock().unwrap().remove(0))
+        }
+
+        async fn summarize(&self, _text: &str) -> Result<String> {
+            Ok(String::new())
+        }
+    }
+
+    #[test]
+    fn test_parse_structured() {
+        let validator = jsonschema::validator_for(&schema_for::<Quiz>()).unwrap();
+
+        let quiz: Quiz = parse_structured(
+            &validator,
+            "Sure!\n```json\n{\"question\": \"2+2?\", \"answers\": [\"4\"]}\n```",
+        )
+        .unwrap();
+        assert_eq!(quiz.answers, vec!["4"]);
+
+        let err = parse_structured::<Quiz>(&validator, r#"{"question": "2+2?"}"#).unwrap_err();
+        assert!(err.to_string().contains("schema"));
+        assert!(parse_structured::<Quiz>(&validator, "no idea").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_chat_structured_retries() {
+        let call = |arguments: &str| {
+            LLMResponse::tool_calls(vec![ToolCall {
+                id: "1".to_string(),
+                name: RESPOND_TOOL.to_string(),
+                arguments: arguments.to_string(),
+            }])
+        };
+        let provider = Replay(Mutex::new(vec![
+            call(r#"{"question": "2+2?", "answers": "4"}"#),
+            call(r#"{"question": "2+2?", "answers": ["4"]}"#),
+        ]));
+
+        let quiz: Quiz = chat_structured(&provider, &[user_message("quiz".into())])
+            .await
+            .unwrap();
+        assert_eq!(quiz.question, "2+2?");
+        assert!(provider.0.lock().unwrap().is_empty());
+    }
+}
@@ -1,13 +1,17 @@
 //! System prompt builder for HomeGPT agent
 //!
 //! Builds the system prompt with identity, safety guardrails, workspace info,
-//! and special token handling (NO_REPLY, HEARTBEAT_OK).
+//! and special token handling (NO_REPLY, HEARTBEAT_OK, EMERGENCY:).
 
 use std::path::Path;
 
+use crate::locale;
+
 /// Special tokens for silent replies
 pub const SILENT_REPLY_TOKEN: &str = "NO_REPLY";
 pub const HEARTBEAT_OK_TOKEN: &str = "HEARTBEAT_OK";
+/// Starts a heartbeat alert that can't wait for quiet hours to end
+pub const EMERGENCY_PREFIX: &str = "EMERGENCY:";
 
 /// Build the full system prompt for the agent
 #[allow(clippy::vec_init_then_push)] // clearer with explicit pushes for multi-section content
@@ -103,6 +107,30 @@ pub fn build_system_prompt(params: SystemPromptParams) -> String {
         lines.push(String::new());
     }
 
+    // Language section (omitted for English-only households)
+    if let Some((primary, others)) = params.languages.split_first() {
+        if !others.is_empty() || primary != "English" {
+            lines.push("## Language".to_string());
+            if others.is_empty() {
+                lines.push(format!(
+                    "Reply in {} unless the user writes in another language.",
+                    primary
+                ));
+            } else {
+                lines.push(format!(
+                    "The household speaks {} and {}. Reply in the language the user writes in.",
+                    primary,
+                    others.join(" and ")
+                ));
+            }
+            lines.push(format!(
+                "Write memory files, heartbeat alerts and reminders in {}.",
+                primary
+            ));
+            lines.push(String::new());
+        }
+    }
+
     // Memory section
     lines.push("## Memory".to_string());
     lines.push("Memory files in the workspace:".to_string());
@@ -115,7 +143,8 @@ pub fn build_system_prompt(params: SystemPromptParams) -> String {
     lines.push("- memory/YYYY-MM-DD.md: Daily logs for session notes".to_string());
     lines.push(String::new());
     lines.push(
-        "To save information: use write_file or edit_file to update memory files directly. \
+        "To save information: change memory files with patch_file (a unified diff against \
+         the file as you last read it) or edit_file, and create new ones with write_file. \
          Use MEMORY.md for important persistent facts (names, preferences). \
          Sessions are auto-saved to memory/ when starting a new session."
             .to_string(),
@@ -188,6 +217,10 @@ pub fn build_system_prompt(params: SystemPromptParams) -> String {
         "If something needs attention, do NOT include \"{}\"; reply with the alert or action instead.",
         HEARTBEAT_OK_TOKEN
     ));
+    lines.push(format!(
+        "Alerts wait for quiet hours to end. If one can't wait (smoke, a leak, someone hurt), start it with \"{}\".",
+        EMERGENCY_PREFIX
+    ));
     lines.push(String::new());
 
     // Runtime info
@@ -211,16 +244,19 @@ pub struct SystemPromptParams<'a> {
     pub hostname: Option<String>,
     pub current_time: Option<String>,
     pub timezone: Option<String>,
+    /// Languages the household speaks, the configured one first
+    pub languages: Vec<String>,
     pub skills_prompt: Option<String>,
 }
 
 impl<'a> SystemPromptParams<'a> {
     pub fn new(workspace: &'a Path, model: &'a str) -> Self {
-        use chrono::Local;
-
-        let now = Local::now();
+        let now = locale::now();
         let current_time = now.format("%Y-%m-%d %H:%M:%S").to_string();
-        let timezone = now.format("%Z").to_string();
+        let timezone = match locale::timezone_name() {
+            Some(name) => format!("{}, {}", now.format("%Z"), name),
+            None => now.format("%Z").to_string(),
+        };
 
         Self {
             workspace_dir: workspace.to_str().unwrap_or("~/.homegpt/workspace"),
@@ -235,6 +271,11 @@ impl<'a> SystemPromptParams<'a> {
             } else {
                 Some(timezone)
             },
+            languages: locale::current()
+                .languages()
+                .iter()
+                .map(|language| language.name().to_string())
+                .collect(),
             skills_prompt: None,
         }
     }
@@ -257,8 +298,9 @@ fn get_tool_summary(tool_name: &str) -> &'static str {
     match tool_name {
         "bash" => "Run shell commands",
         "read_file" => "Read file contents",
-        "write_file" => "Create or overwrite files",
+        "write_file" => "Create new files",
         "edit_file" => "Make precise edits to files",
+        "patch_file" => "Change files by applying a unified diff",
         "memory_search" => "Search verified memory with hash-based anti-hallucination",
         "memory_store" => "Store a verified fact with provenance tracking",
         "memory_get" => "Fetch specific lines from memory files (use after memory_search)",
@@ -284,6 +326,14 @@ pub fn build_heartbeat_prompt(workspace_is_git: bool) -> String {
     )
 }
 
+/// The alert in a heartbeat reply marked as an emergency, if it is one
+pub fn strip_emergency(response: &str) -> Option<&str> {
+    response
+        .trim_start()
+        .strip_prefix(EMERGENCY_PREFIX)
+        .map(str::trim)
+}
+
 /// Check if a response is a heartbeat acknowledgment (nothing to do)
 pub fn is_heartbeat_ok(response: &str) -> bool {
     let trimmed = response.trim();
@@ -314,6 +364,15 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_strip_emergency() {
+        assert_eq!(
+            strip_emergency("EMERGENCY: Water on the basement floor"),
+            Some("Water on the basement floor")
+        );
+        assert_eq!(strip_emergency("The garage door is open"), None);
+    }
+
     #[test]
     fn test_is_silent_reply() {
         assert!(is_silent_reply("NO_REPLY"));
@@ -0,0 +1,155 @@
+//! Variables in SOUL.md and SKILL.md, e.g. `{{family_members}}` or
+//! `{{today}}`, filled in when the prompt is built so the files can follow
+//! the household and the day without the model looking them up.
+//!
+//! Files are rendered with minijinja, so `{% if kids %}...{% endif %}` works
+//! too. A file with an unknown variable or a syntax error is used as written.
+
+use minijinja::{Environment, UndefinedBehavior};
+use serde::Serialize;
+use tracing::warn;
+
+use crate::config::{AgentDefinition, Config};
+use crate::locale;
+
+/// Values a SOUL or SKILL file can use
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptVars {
+    /// e.g. "2026-10-18"
+    pub today: String,
+    /// e.g. "Sunday"
+    pub weekday: String,
+    /// Time the prompt was built, e.g. "14:05"
+    pub now: String,
+    /// Everyone in `presence.people` and the kiosk's people, e.g. "Ella, Sam and Mia"
+    pub family_members: String,
+    /// The kids among `presence.people`
+    pub kids: String,
+    /// Profile the session runs under, or "default"
+    pub active_profile: String,
+    /// e.g. "English"
+    pub language: String,
+    /// The agent's description in agents.toml, if it has one
+    pub agent_description: String,
+}
+
+impl PromptVars {
+    pub fn new(config: &Config, profile: Option<&str>, persona: Option<&AgentDefinition>) -> Self {
+        let now = locale::now();
+        let mut family: Vec<&str> = config
+            .presence
+            .people
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        for person in &config.desktop.people {
+            if !family
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(&person.name))
+            {
+                family.push(&person.name);
+            }
+        }
+        let kids: Vec<&str> = config
+            .presence
+            .people
+            .iter()
+            .filter(|p| p.kid)
+            .map(|p| p.name.as_str())
+            .collect();
+        let profile = profile.map(str::to_string).or_else(Config::profile);
+
+        Self {
+            today: now.format("%Y-%m-%d").to_string(),
+            weekday: now.format("%A").to_string(),
+            now: now.format("%H:%M").to_string(),
+            family_members: join_names(&family),
+            kids: join_names(&kids),
+            active_profile: profile.unwrap_or_else(|| "default".to_string()),
+            language: locale::current().language().name().to_string(),
+            agent_description: persona.map(|p| p.description.clone()).unwrap_or_default(),
+        }
+    }
+}
+
+/// `content` of the file `name` with its variables filled in
+pub fn render_template(name: &str, content: &str, vars: &PromptVars) -> String {
+    if !content.contains("{{") && !content.contains("{%") {
+        return content.to_string();
+    }
+    let mut env = Environment::new();
+    env.set_keep_trailing_newline(true);
+    env.set_undefined_behavior(UndefinedBehavior::Strict);
+    match env.render_named_str(name, content, vars) {
+        Ok(rendered) => rendered,
+        Err(e) => {
+            warn!("Using {} without filling in its variables: {}", name, e);
+            content.to_string()
+        }
+    }
+}
+
+/// "Ella", "Ella and Sam", "Ella, Sam and Mia"
+fn join_names(names: &[&str]) -> String {
+    match names {
+        [] => String::new(),
+        [one] => one.to_string(),
+        [rest @ .., last] => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars() -> PromptVars {
+        PromptVars {
+            today: "2026-10-18".to_string(),
+            weekday: "Sunday".to_string(),
+            now: "14:05".to_string(),
+            family_members: join_names(&["Ella", "Sam", "Mia"]),
+            kids: join_names(&["Mia"]),
+            active_profile: "kids".to_string(),
+            language: "English".to_string(),
+            agent_description: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let vars = vars();
+        assert_eq!(
+            render_template(
+                "SOUL.md",
+                "You help {{family_members}}. Today is {{ weekday }}.\n",
+                &vars
+            ),
+            "You help Ella, Sam and Mia. Today is Sunday.\n"
+        );
+        assert_eq!(
+            render_template(
+                "SOUL.md",
+                "{% if active_profile == \"kids\" %}Keep it short for {{kids}}.{% endif %}",
+                &vars
+            ),
+            "Keep it short for Mia."
+        );
+
+        // Unknown variables and bad syntax leave the file as written
+        let typo = "Hello {{family}}";
+        assert_eq!(render_template("SOUL.md", typo, &vars), typo);
+        let broken = "Use {{ today";
+        assert_eq!(render_template("SKILL.md", broken, &vars), broken);
+        assert_eq!(
+            render_template("SOUL.md", "No variables", &vars),
+            "No variables"
+        );
+    }
+
+    #[test]
+    fn test_join_names() {
+        assert_eq!(join_names(&[]), "");
+        assert_eq!(join_names(&["Ella"]), "Ella");
+        assert_eq!(join_names(&["Ella", "Sam"]), "Ella and Sam");
+    }
+}
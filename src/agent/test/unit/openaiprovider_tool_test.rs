@@ -1,4 +1,4 @@
-//! Tests for the default `chat_stream` fallback on `LLMProvider`.
+//! Tests for the default `chat_stream` fallback on `ChatProvider`.
 //!
 //! The default implementation delegates to `chat()` and wraps the result
 //! in a single `StreamChunk`. These tests verify that:
@@ -13,7 +13,7 @@ use futures::StreamExt;
 use super::*;
 
 /// Mock provider that returns a configured response from chat(),
-/// used to test the default chat_stream fallback on LLMProvider.
+/// used to test the default chat_stream fallback on ChatProvider.
 struct MockProvider {
     response: std::sync::Mutex<Option<LLMResponse>>,
     /// Captures whether tools were forwarded to chat()
@@ -37,7 +37,7 @@ impl MockProvider {
 }
 
 #[async_trait]
-impl LLMProvider for MockProvider {
+impl ChatProvider for MockProvider {
     async fn chat(
         &self,
         _messages: &[Message],
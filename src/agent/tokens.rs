@@ -0,0 +1,130 @@
+//! Token counting for context budgeting.
+//!
+//! Counts use OpenAI's BPE encodings: o200k for GPT-4o and newer, cl100k for
+//! everything else. Other vendors' tokenizers aren't public, but cl100k lands
+//! within a few percent for Claude and Llama-family models, which
+//! `reserve_tokens` absorbs.
+
+use once_cell::sync::Lazy;
+use tiktoken_rs::CoreBPE;
+
+use super::providers::{Message, ToolSchema};
+
+static CL100K: Lazy<CoreBPE> =
+    Lazy::new(|| tiktoken_rs::cl100k_base().expect("cl100k_base encoding is bundled"));
+static O200K: Lazy<CoreBPE> =
+    Lazy::new(|| tiktoken_rs::o200k_base().expect("o200k_base encoding is bundled"));
+
+/// Role markers and separators around each message
+const MESSAGE_OVERHEAD: usize = 4;
+
+/// Budget for one image attachment (providers bill roughly 1-2k per image)
+const IMAGE_TOKENS: usize = 1_600;
+
+/// Encoding used to count tokens for a model
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Tokenizer {
+    #[default]
+    Cl100k,
+    O200k,
+}
+
+impl Tokenizer {
+    /// Pick the encoding for a model ID, with or without provider prefix
+    /// (e.g. "openai/gpt-4o", "openrouter/openai/gpt-4o-mini", "opus")
+    pub fn for_model(model: &str) -> Self {
+        let name = model.rsplit('/').next().unwrap_or(model).to_lowercase();
+        let o200k_prefixes = ["gpt-4o", "gpt-4.1", "gpt-5", "chatgpt-4o", "o1", "o3", "o4"];
+        if o200k_prefixes.iter().any(|p| name.starts_with(p)) {
+            return Self::O200k;
+        }
+        match tiktoken_rs::tokenizer::get_tokenizer(&name) {
+            Some(tiktoken_rs::tokenizer::Tokenizer::O200kBase) => Self::O200k,
+            _ => Self::Cl100k,
+        }
+    }
+
+    fn bpe(self) -> &'static CoreBPE {
+        match self {
+            Self::Cl100k => &CL100K,
+            Self::O200k => &O200K,
+        }
+    }
+
+    pub fn count(self, text: &str) -> usize {
+        if text.is_empty() {
+            return 0;
+        }
+        self.bpe().encode_ordinary(text).len()
+    }
+
+    /// Tokens a message takes in the prompt, including tool calls and images
+    pub fn count_message(self, message: &Message) -> usize {
+        let mut tokens = MESSAGE_OVERHEAD + self.count(&message.content);
+        tokens += message.images.len() * IMAGE_TOKENS;
+        for call in message.tool_calls.iter().flatten() {
+            tokens += self.count(&call.name) + self.count(&call.arguments);
+        }
+        tokens
+    }
+
+    /// Tokens the tool definitions take in every request
+    pub fn count_tools(self, tools: &[ToolSchema]) -> usize {
+        tools
+            .iter()
+            .map(|t| {
+                self.count(&t.name)
+                    + self.count(&t.description)
+                    + self.count(&t.parameters.to_string())
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::providers::{Role, ToolCall};
+
+    #[test]
+    fn test_for_model() {
+        assert_eq!(Tokenizer::for_model("openai/gpt-4o-mini"), Tokenizer::O200k);
+        assert_eq!(
+            Tokenizer::for_model("openrouter/openai/gpt-4.1"),
+            Tokenizer::O200k
+        );
+        assert_eq!(
+            Tokenizer::for_model("openai/gpt-4-turbo"),
+            Tokenizer::Cl100k
+        );
+        assert_eq!(
+            Tokenizer::for_model("anthropic/claude-opus-4-5"),
+            Tokenizer::Cl100k
+        );
+        assert_eq!(Tokenizer::for_model("ollama/llama3"), Tokenizer::Cl100k);
+    }
+
+    #[test]
+    fn test_count() {
+        let tokenizer = Tokenizer::Cl100k;
+        assert_eq!(tokenizer.count(""), 0);
+        assert_eq!(tokenizer.count("hello world"), 2);
+        // Code and non-English text are denser than 4 chars per token
+        let cjk = "今日はいい天気ですね";
+        assert!(tokenizer.count(cjk) > cjk.len() / 4);
+
+        let message = Message {
+            role: Role::Assistant,
+            content: "hello world".to_string(),
+            tool_calls: Some(vec![ToolCall {
+                id: "1".to_string(),
+                name: "memory_search".to_string(),
+                arguments: r#"{"query": "dentist"}"#.to_string(),
+            }]),
+            tool_call_id: None,
+            images: Vec::new(),
+        };
+        let content_only = MESSAGE_OVERHEAD + 2;
+        assert!(tokenizer.count_message(&message) > content_only);
+    }
+}
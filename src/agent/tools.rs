@@ -6,11 +6,27 @@ use sha2::{Digest, Sha256};
 use std::fs;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tracing::debug;
+use tracing::{debug, warn};
 
+use super::context_packing::ContextPacker;
 use super::providers::ToolSchema;
+use super::query_expansion::QueryExpander;
+use super::retrieval_trace::{RetrievalRecorder, SearchTrace};
+use crate::announce::Announcer;
+use crate::calendar::{short_id, Calendar, When};
+use crate::commitments::{CommitmentStore, Status};
 use crate::config::Config;
-use crate::memory::MemoryManager;
+use crate::finance::{Categorizer, FinanceLedger, ImportOptions};
+use crate::heartbeat::{add_task, parse_tasks, remove_task, schedule_label, HEARTBEAT_PATH};
+use crate::locale;
+use crate::memory::{
+    apply_patch, replace_file, FileWrite, MemoryManager, QueryExpansion, SearchFilter,
+};
+use crate::notifications::Event;
+use crate::presence::Presence;
+use crate::reminders::{parse_schedule, ReminderStore};
+use crate::timers::{parse_timer_duration, TimerStore};
+use crate::weather::{WeatherService, FORECAST_DAYS};
 
 #[derive(Debug, Clone)]
 pub struct ToolResult {
@@ -22,18 +38,44 @@ pub struct ToolResult {
 pub trait Tool: Send + Sync {
     fn name(&self) -> &str;
     fn schema(&self) -> ToolSchema;
+
+    /// Whether a call changes anything: files, the index, or the world
+    /// outside (messages, calendars). Under dry run these aren't executed.
+    fn mutates(&self) -> bool {
+        true
+    }
     async fn execute(&self, arguments: &str) -> Result<String>;
 }
 
 pub fn create_default_tools(
     config: &Config,
     memory: Option<Arc<MemoryManager>>,
+    retrieval: &RetrievalRecorder,
 ) -> Result<Vec<Box<dyn Tool>>> {
     let workspace = config.workspace_path();
 
     // Use indexed memory search if MemoryManager is provided, otherwise fallback to grep-based
     let memory_search_tool: Box<dyn Tool> = if let Some(ref mem) = memory {
-        Box::new(MemorySearchToolWithIndex::new(Arc::clone(mem)))
+        let expander = match QueryExpander::from_config(config) {
+            Ok(expander) => expander,
+            Err(e) => {
+                warn!("Query expansion disabled: {}", e);
+                None
+            }
+        };
+        let packer = ContextPacker::from_config(config).unwrap_or_else(|e| {
+            warn!("Overflow summaries disabled: {}", e);
+            ContextPacker::new(
+                config.memory.context_budget_tokens,
+                config.memory.max_chunks_per_file,
+            )
+        });
+        Box::new(
+            MemorySearchToolWithIndex::new(Arc::clone(mem))
+                .with_expander(expander)
+                .with_packer(packer)
+                .with_recorder(retrieval.clone()),
+        )
     } else {
         Box::new(MemorySearchTool::new(workspace.clone()))
     };
@@ -41,8 +83,9 @@ pub fn create_default_tools(
     let mut tools: Vec<Box<dyn Tool>> = vec![
         Box::new(BashTool::new(config.tools.bash_timeout_ms)),
         Box::new(ReadFileTool::new()),
-        Box::new(WriteFileTool::new()),
-        Box::new(EditFileTool::new()),
+        Box::new(WriteFileTool::new(memory.clone())),
+        Box::new(EditFileTool::new(memory.clone())),
+        Box::new(PatchFileTool::new(memory.clone())),
         memory_search_tool,
         Box::new(MemoryGetTool::new(workspace)),
         Box::new(WebFetchTool::new(config.tools.web_fetch_max_bytes)),
@@ -51,6 +94,86 @@ pub fn create_default_tools(
     // Add verified memory store tool if MemoryManager is available
     if let Some(ref mem) = memory {
         tools.push(Box::new(MemoryStoreVerifiedTool::new(Arc::clone(mem))));
+        tools.push(Box::new(MemoryConsolidateTool::new(
+            Arc::clone(mem),
+            config.memory.consolidation_threshold,
+        )));
+        tools.push(Box::new(ForgetTool::new(Arc::clone(mem))));
+        if config.memory.entity_graph {
+            tools.push(Box::new(GraphNeighborsTool::new(Arc::clone(mem))));
+        }
+        if config.memory.time_index {
+            tools.push(Box::new(UpcomingEventsTool::new(Arc::clone(mem))));
+            tools.push(Box::new(WhatHappenedAroundTool::new(Arc::clone(mem))));
+        }
+        tools.push(Box::new(ScheduleTaskTool::new(Arc::clone(mem))));
+        tools.push(Box::new(ListTasksTool::new(Arc::clone(mem))));
+        tools.push(Box::new(RemoveTaskTool::new(Arc::clone(mem))));
+    }
+
+    tools.push(Box::new(BudgetStatusTool::new(FinanceLedger::new(
+        &config.workspace_path(),
+        &config.finance,
+    ))));
+    tools.push(Box::new(FinanceImportTool::new(
+        config.clone(),
+        memory.clone(),
+    )));
+
+    let state_dir = super::get_state_dir()?;
+    match WeatherService::from_config(config, &state_dir) {
+        Ok(Some(service)) => tools.push(Box::new(WeatherTool::new(service))),
+        Ok(None) => {}
+        Err(e) => warn!("Weather tool disabled: {}", e),
+    }
+    match Presence::from_config(config, &state_dir) {
+        Ok(Some(presence)) => tools.push(Box::new(WhoIsHomeTool::new(presence))),
+        Ok(None) => {}
+        Err(e) => warn!("Presence tool disabled: {}", e),
+    }
+    match Announcer::from_config(config, &state_dir) {
+        Ok(Some(announcer)) => tools.push(Box::new(AnnounceTool::new(announcer))),
+        Ok(None) => {}
+        Err(e) => warn!("Announce tool disabled: {}", e),
+    }
+    match Calendar::from_config(config, &state_dir) {
+        Ok(calendar) => {
+            let calendar = Arc::new(calendar);
+            tools.push(Box::new(CalendarAddEventTool::new(
+                Arc::clone(&calendar),
+                memory.clone(),
+            )));
+            tools.push(Box::new(CalendarRemoveEventTool::new(calendar, memory)));
+        }
+        Err(e) => warn!("Calendar tools disabled: {}", e),
+    }
+    match ReminderStore::open(&state_dir) {
+        Ok(store) => {
+            let store = Arc::new(store);
+            tools.push(Box::new(SetReminderTool::new(Arc::clone(&store))));
+            tools.push(Box::new(ListRemindersTool::new(Arc::clone(&store))));
+            tools.push(Box::new(CancelReminderTool::new(store)));
+        }
+        Err(e) => warn!("Reminder tools disabled: {}", e),
+    }
+    match TimerStore::open(&state_dir) {
+        Ok(store) => {
+            let store = Arc::new(store);
+            tools.push(Box::new(SetTimerTool::new(Arc::clone(&store))));
+            tools.push(Box::new(ListTimersTool::new(Arc::clone(&store))));
+            tools.push(Box::new(CancelTimerTool::new(store)));
+        }
+        Err(e) => warn!("Timer tools disabled: {}", e),
+    }
+    if config.memory.track_commitments {
+        match CommitmentStore::open(&state_dir) {
+            Ok(store) => {
+                let store = Arc::new(store);
+                tools.push(Box::new(ListCommitmentsTool::new(Arc::clone(&store))));
+                tools.push(Box::new(ResolveCommitmentTool::new(store)));
+            }
+            Err(e) => warn!("Commitment tools disabled: {}", e),
+        }
     }
 
     Ok(tools)
@@ -116,6 +239,8 @@ impl Tool for BashTool {
             tokio::process::Command::new("bash")
                 .arg("-c")
                 .arg(command)
+                // Kill the command if it times out or the turn is cancelled
+                .kill_on_drop(true)
                 .output(),
         )
         .await
@@ -163,6 +288,10 @@ impl Tool for ReadFileTool {
         "read_file"
     }
 
+    fn mutates(&self) -> bool {
+        false
+    }
+
     fn schema(&self) -> ToolSchema {
         ToolSchema {
             name: "read_file".to_string(),
@@ -222,12 +351,25 @@ impl Tool for ReadFileTool {
     }
 }
 
+/// Tool output for a change staged for review instead of written
+fn staged_message(id: i64, path: &std::path::Path) -> String {
+    format!(
+        "Staged change #{} to {} for review. It is not written until someone approves it; \
+         later edits to the file build on it.",
+        id,
+        path.display()
+    )
+}
+
 // Write File Tool
-pub struct WriteFileTool;
+pub struct WriteFileTool {
+    /// Writes through memory so changes to the workspace can be staged
+    memory: Option<Arc<MemoryManager>>,
+}
 
 impl WriteFileTool {
-    pub fn new() -> Self {
-        Self
+    pub fn new(memory: Option<Arc<MemoryManager>>) -> Self {
+        Self { memory }
     }
 }
 
@@ -240,7 +382,9 @@ impl Tool for WriteFileTool {
     fn schema(&self) -> ToolSchema {
         ToolSchema {
             name: "write_file".to_string(),
-            description: "Write content to a file (creates or overwrites)".to_string(),
+            description: "Create a new file with the given content. Fails if the file exists; \
+                          change existing files with patch_file or edit_file."
+                .to_string(),
             parameters: json!({
                 "type": "object",
                 "properties": {
@@ -272,12 +416,36 @@ impl Tool for WriteFileTool {
 
         debug!("Writing file: {}", path.display());
 
-        // Create parent directories if needed
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
+        let _writes = match self.memory {
+            Some(ref memory) => Some(memory.lock_writes().await),
+            None => None,
+        };
+        // Rewriting a whole file from memory loses what was edited by hand
+        // since the model read it
+        let exists = match self.memory {
+            Some(ref memory) => memory.read_file(&path).is_ok(),
+            None => path.exists(),
+        };
+        if exists {
+            anyhow::bail!(
+                "{} already exists; change it with patch_file or edit_file",
+                path.display()
+            );
         }
 
-        fs::write(&path, content)?;
+        if let Some(ref memory) = self.memory {
+            if let FileWrite::Staged(id) =
+                memory.write_file(&path, content, "write_file", "agent")?
+            {
+                return Ok(staged_message(id, &path));
+            }
+        } else {
+            // Create parent directories if needed
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, content)?;
+        }
 
         Ok(format!(
             "Successfully wrote {} bytes to {}",
@@ -288,11 +456,14 @@ impl Tool for WriteFileTool {
 }
 
 // Edit File Tool
-pub struct EditFileTool;
+pub struct EditFileTool {
+    /// Writes through memory so changes to the workspace can be staged
+    memory: Option<Arc<MemoryManager>>,
+}
 
 impl EditFileTool {
-    pub fn new() -> Self {
-        Self
+    pub fn new(memory: Option<Arc<MemoryManager>>) -> Self {
+        Self { memory }
     }
 }
 
@@ -344,11 +515,19 @@ impl Tool for EditFileTool {
             .ok_or_else(|| anyhow::anyhow!("Missing new_string"))?;
         let replace_all = args["replace_all"].as_bool().unwrap_or(false);
 
-        let path = shellexpand::tilde(path).to_string();
+        let path = PathBuf::from(shellexpand::tilde(path).to_string());
 
-        debug!("Editing file: {}", path);
+        debug!("Editing file: {}", path.display());
 
-        let content = fs::read_to_string(&path)?;
+        let _writes = match self.memory {
+            Some(ref memory) => Some(memory.lock_writes().await),
+            None => None,
+        };
+        // Edits build on changes still waiting for review
+        let content = match self.memory {
+            Some(ref memory) => memory.read_file(&path)?,
+            None => fs::read_to_string(&path)?,
+        };
 
         let (new_content, count) = if replace_all {
             let count = content.matches(old_string).count();
@@ -359,9 +538,104 @@ impl Tool for EditFileTool {
             return Err(anyhow::anyhow!("old_string not found in file"));
         };
 
-        fs::write(&path, &new_content)?;
+        if let Some(ref memory) = self.memory {
+            if let FileWrite::Staged(id) =
+                memory.write_file(&path, &new_content, "edit_file", "agent")?
+            {
+                return Ok(staged_message(id, &path));
+            }
+        } else {
+            fs::write(&path, &new_content)?;
+        }
+
+        Ok(format!(
+            "Replaced {} occurrence(s) in {}",
+            count,
+            path.display()
+        ))
+    }
+}
+
+// Patch File Tool
+pub struct PatchFileTool {
+    /// Writes through memory so changes to the workspace can be staged
+    memory: Option<Arc<MemoryManager>>,
+}
+
+impl PatchFileTool {
+    pub fn new(memory: Option<Arc<MemoryManager>>) -> Self {
+        Self { memory }
+    }
+}
+
+#[async_trait]
+impl Tool for PatchFileTool {
+    fn name(&self) -> &str {
+        "patch_file"
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "patch_file".to_string(),
+            description: "Change a file by applying a unified diff. Context and '-' lines must \
+                          match the file as it is now, so read it first. Hunk line numbers \
+                          and counts may be approximate."
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "The path to the file to change"
+                    },
+                    "diff": {
+                        "type": "string",
+                        "description": "Unified diff of the change: @@ hunks of ' ' (context), '-' and '+' lines"
+                    }
+                },
+                "required": ["path", "diff"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments)?;
+        let path = args["path"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing path"))?;
+        let diff = args["diff"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing diff"))?;
+
+        let path = PathBuf::from(shellexpand::tilde(path).to_string());
+
+        debug!("Patching file: {}", path.display());
+
+        let _writes = match self.memory {
+            Some(ref memory) => Some(memory.lock_writes().await),
+            None => None,
+        };
+        // Patches build on changes still waiting for review; a missing file
+        // is patched from empty
+        let content = match self.memory {
+            Some(ref memory) if path.exists() => memory.read_file(&path)?,
+            Some(ref memory) => memory.read_file(&path).unwrap_or_default(),
+            None if path.exists() => fs::read_to_string(&path)?,
+            None => String::new(),
+        };
+        let new_content = apply_patch(&content, diff)?;
+
+        if let Some(ref memory) = self.memory {
+            if let FileWrite::Staged(id) =
+                memory.write_file(&path, &new_content, "patch_file", "agent")?
+            {
+                return Ok(staged_message(id, &path));
+            }
+        } else {
+            replace_file(&path, &new_content, None)?;
+        }
 
-        Ok(format!("Replaced {} occurrence(s) in {}", count, path))
+        Ok(format!("Patched {}", path.display()))
     }
 }
 
@@ -382,6 +656,10 @@ impl Tool for MemorySearchTool {
         "memory_search"
     }
 
+    fn mutates(&self) -> bool {
+        false
+    }
+
     fn schema(&self) -> ToolSchema {
         ToolSchema {
             name: "memory_search".to_string(),
@@ -473,11 +751,41 @@ impl Tool for MemorySearchTool {
 // Memory Search Tool with Index - uses MemoryManager for hybrid FTS+vector search
 pub struct MemorySearchToolWithIndex {
     memory: Arc<MemoryManager>,
+    /// Optional query rewriting / HyDE step before search
+    expander: Option<QueryExpander>,
+    /// Fits results into the context budget
+    packer: ContextPacker,
+    /// Records each search in the turn's retrieval trace
+    recorder: Option<RetrievalRecorder>,
 }
 
+/// Candidates retrieved per result asked for, so the per-file cap and the
+/// budget have others to choose from
+const SEARCH_CANDIDATES_PER_RESULT: usize = 3;
+
 impl MemorySearchToolWithIndex {
     pub fn new(memory: Arc<MemoryManager>) -> Self {
-        Self { memory }
+        Self {
+            memory,
+            expander: None,
+            packer: ContextPacker::new(0, 0),
+            recorder: None,
+        }
+    }
+
+    pub fn with_expander(mut self, expander: Option<QueryExpander>) -> Self {
+        self.expander = expander;
+        self
+    }
+
+    pub fn with_packer(mut self, packer: ContextPacker) -> Self {
+        self.packer = packer;
+        self
+    }
+
+    pub fn with_recorder(mut self, recorder: RetrievalRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
     }
 }
 
@@ -487,6 +795,10 @@ impl Tool for MemorySearchToolWithIndex {
         "memory_search"
     }
 
+    fn mutates(&self) -> bool {
+        false
+    }
+
     fn schema(&self) -> ToolSchema {
         let description = if self.memory.has_embeddings() {
             "Search the memory index using hybrid semantic + keyword search for relevant information"
@@ -507,6 +819,10 @@ impl Tool for MemorySearchToolWithIndex {
                     "limit": {
                         "type": "integer",
                         "description": "Maximum number of results (default: 5)"
+                    },
+                    "category": {
+                        "type": "string",
+                        "description": "Only return memories whose frontmatter category matches (e.g. \"food\", \"family\", \"school\")"
                     }
                 },
                 "required": ["query"]
@@ -521,6 +837,13 @@ impl Tool for MemorySearchToolWithIndex {
             .ok_or_else(|| anyhow::anyhow!("Missing query"))?;
         let limit = args["limit"].as_u64().unwrap_or(5) as usize;
 
+        // Category filters use the same `category:` syntax as the query
+        let query = match args["category"].as_str().filter(|c| !c.trim().is_empty()) {
+            Some(category) => format!("{} category:{}", query, category.trim()),
+            None => query.to_string(),
+        };
+        let query = query.as_str();
+
         let search_type = if self.memory.has_embeddings() {
             "hybrid"
         } else {
@@ -531,26 +854,60 @@ impl Tool for MemorySearchToolWithIndex {
             search_type, query, limit
         );
 
-        let results = self.memory.search_verified(query, limit)?;
+        let expansion = match self.expander {
+            Some(ref expander) => {
+                // Expansion only uses the free-text part; filters stay on the query
+                let (text, _) = SearchFilter::parse(query);
+                expander.expand(&text).await.unwrap_or_else(|e| {
+                    warn!("Query expansion failed, searching without it: {}", e);
+                    QueryExpansion::default()
+                })
+            }
+            None => QueryExpansion::default(),
+        };
 
-        if results.is_empty() {
+        let results = self.memory.search_verified_expanded(
+            query,
+            &expansion,
+            limit * SEARCH_CANDIDATES_PER_RESULT,
+        )?;
+
+        let candidates = self.recorder.as_ref().map(|_| results.clone());
+        let packing = self.packer.pack(results, limit).await?;
+        if let (Some(recorder), Some(candidates)) = (&self.recorder, candidates) {
+            recorder.record(SearchTrace::new(query, &expansion, &candidates, &packing));
+        }
+        if packing.chunks.is_empty() {
             return Ok("No results found in verified memory.".to_string());
         }
 
         // Format results with verification status and relevance scores
-        let formatted: Vec<String> = results
+        let mut formatted: Vec<String> = packing
+            .chunks
             .iter()
             .enumerate()
-            .map(|(i, chunk)| {
-                let preview: String = chunk.content.chars().take(200).collect();
-                let preview = preview.replace('\n', " ");
+            .map(|(i, packed)| {
+                let chunk = &packed.chunk;
+                let text = if packed.summarized {
+                    format!("(summary) {}", packed.text)
+                } else {
+                    packed.text.clone()
+                };
                 let tag = if chunk.verified {
                     format!("[VERIFIED:{}]", chunk.hash_prefix)
                 } else {
                     "[UNVERIFIED]".to_string()
                 };
+                // Stale facts may have changed; the family should confirm them
+                let stale = match chunk.last_verified {
+                    Some(ref date) if chunk.stale => format!(
+                        " | STALE: last verified {}, confirm with the family before relying on it",
+                        date
+                    ),
+                    _ => String::new(),
+                };
                 format!(
-                    "{}. {} {} (lines {}-{}, score: {:.3})\n   Source: {} | Confidence: {}\n   {}{}",
+                    "{}. {} {} (lines {}-{}, score: {:.3})\n   Source: {} | Confidence: {}{}\n   {}",
                     i + 1,
                     tag,
                     chunk.file,
@@ -559,11 +916,17 @@ impl Tool for MemorySearchToolWithIndex {
                     chunk.score,
                     chunk.provenance,
                     chunk.confidence,
-                    preview,
-                    if chunk.content.len() > 200 { "..." } else { "" }
+                    stale,
+                    text.replace('\n', "\n   ")
                 )
             })
             .collect();
+        if !packing.dropped.is_empty() {
+            formatted.push(format!(
+                "({} more matches left out to fit the context budget; narrow the query or use memory_get to see more)",
+                packing.dropped.len()
+            ));
+        }
 
         Ok(formatted.join("\n\n"))
     }
@@ -621,12 +984,18 @@ impl Tool for MemoryStoreVerifiedTool {
 
         let workspace = self.memory.workspace();
         let facts_dir = workspace.join("memory").join("facts");
-        fs::create_dir_all(&facts_dir)?;
 
-        // Generate filename from timestamp
+        // Generate filename from timestamp; another session may have
+        // stored a fact this second
+        let _writes = self.memory.lock_writes().await;
         let now = Utc::now();
-        let filename = format!("{}.md", now.format("%Y%m%d-%H%M%S"));
-        let filepath = facts_dir.join(&filename);
+        let filepath = MemoryManager::unused_path(
+            &facts_dir.join(format!("{}.md", now.format("%Y%m%d-%H%M%S"))),
+        );
+        let filename = filepath
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
 
         // Compute content hash for reference
         let mut hasher = Sha256::new();
@@ -646,7 +1015,12 @@ impl Tool for MemoryStoreVerifiedTool {
             source, category, confidence, now.to_rfc3339(), fact,
         );
 
-        fs::write(&filepath, &content)?;
+        let write = self
+            .memory
+            .write_file(&filepath, &content, "memory_store", source)?;
+        if let FileWrite::Staged(id) = write {
+            return Ok(staged_message(id, &filepath));
+        }
 
         debug!(
             "Stored verified fact [{}] in memory/facts/{}",
@@ -660,156 +1034,520 @@ impl Tool for MemoryStoreVerifiedTool {
     }
 }
 
-// Memory Get Tool - efficient snippet fetching after memory_search
-pub struct MemoryGetTool {
-    workspace: PathBuf,
+// Memory Consolidate Tool - reports near-duplicate chunks (merging is left to the user)
+pub struct MemoryConsolidateTool {
+    memory: Arc<MemoryManager>,
+    default_threshold: f32,
 }
 
-impl MemoryGetTool {
-    pub fn new(workspace: PathBuf) -> Self {
-        Self { workspace }
-    }
-
-    fn resolve_path(&self, path: &str) -> PathBuf {
-        // Handle paths relative to workspace
-        if path.starts_with("memory/") || path == "MEMORY.md" || path == "HEARTBEAT.md" {
-            self.workspace.join(path)
-        } else {
-            PathBuf::from(shellexpand::tilde(path).to_string())
+impl MemoryConsolidateTool {
+    pub fn new(memory: Arc<MemoryManager>, default_threshold: f32) -> Self {
+        Self {
+            memory,
+            default_threshold,
         }
     }
 }
 
 #[async_trait]
-impl Tool for MemoryGetTool {
+impl Tool for MemoryConsolidateTool {
     fn name(&self) -> &str {
-        "memory_get"
+        "memory_consolidate"
     }
 
     fn schema(&self) -> ToolSchema {
         ToolSchema {
-            name: "memory_get".to_string(),
-            description: "Safe snippet read from MEMORY.md or memory/*.md with optional line range; use after memory_search to pull only the needed lines and keep context small.".to_string(),
+            name: "memory_consolidate".to_string(),
+            description: "Report near-duplicate memory chunks (dry run). Merging them rewrites memory files, so it's left to the user: `homegpt memory consolidate --apply`, or memory.consolidation_interval for the heartbeat.".to_string(),
             parameters: json!({
                 "type": "object",
                 "properties": {
-                    "path": {
-                        "type": "string",
-                        "description": "Path to the file (e.g., 'MEMORY.md' or 'memory/2024-01-15.md')"
-                    },
-                    "from": {
-                        "type": "integer",
-                        "description": "Starting line number (1-indexed, default: 1)"
-                    },
-                    "lines": {
-                        "type": "integer",
-                        "description": "Number of lines to read (default: 50)"
+                    "threshold": {
+                        "type": "number",
+                        "description": format!("Cosine similarity threshold (default: {})", self.default_threshold)
                     }
-                },
-                "required": ["path"]
+                }
             }),
         }
     }
 
     async fn execute(&self, arguments: &str) -> Result<String> {
         let args: Value = serde_json::from_str(arguments)?;
-        let path = args["path"]
-            .as_str()
-            .ok_or_else(|| anyhow::anyhow!("Missing path"))?;
+        let threshold = args["threshold"]
+            .as_f64()
+            .map(|t| t as f32)
+            .unwrap_or(self.default_threshold);
 
-        let from = args["from"].as_u64().unwrap_or(1).max(1) as usize;
-        let lines_count = args["lines"].as_u64().unwrap_or(50) as usize;
+        if !self.memory.has_embeddings() {
+            return Ok("Memory consolidation needs an embedding provider".to_string());
+        }
 
-        let resolved_path = self.resolve_path(path);
+        self.memory.generate_embeddings().await?;
+        // Not while another session is rewriting the files it reports on
+        let _writes = self.memory.lock_writes().await;
+        let memory = Arc::clone(&self.memory);
+        let report =
+            tokio::task::spawn_blocking(move || memory.consolidate(threshold, false)).await??;
 
-        debug!(
-            "Memory get: {} (from: {}, lines: {})",
-            resolved_path.display(),
-            from,
-            lines_count
-        );
+        debug!("memory_consolidate: {} groups", report.groups.len());
 
-        if !resolved_path.exists() {
-            return Ok(format!("File not found: {}", path));
-        }
+        Ok(report.summary())
+    }
+}
 
-        let content = fs::read_to_string(&resolved_path)?;
-        let lines: Vec<&str> = content.lines().collect();
-        let total_lines = lines.len();
+// Forget Tool - verified deletion of memory content on request
+pub struct ForgetTool {
+    memory: Arc<MemoryManager>,
+}
 
-        // Convert from 1-indexed to 0-indexed
-        let start = (from - 1).min(total_lines);
-        let end = (start + lines_count).min(total_lines);
+impl ForgetTool {
+    pub fn new(memory: Arc<MemoryManager>) -> Self {
+        Self { memory }
+    }
+}
 
-        if start >= total_lines {
-            return Ok(format!(
-                "Line {} is past end of file ({} lines)",
-                from, total_lines
-            ));
+#[async_trait]
+impl Tool for ForgetTool {
+    fn name(&self) -> &str {
+        "forget"
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "forget".to_string(),
+            description: "Permanently delete information from memory: the matching lines in the markdown files plus their indexed chunks and embeddings. Only use when the user explicitly asks to forget something. Without confirm it only lists the matches: show them to the user and call again with confirm: true once they agree.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "target": {
+                        "type": "string",
+                        "description": "Text to forget (case-insensitive), or a chunk hash prefix from a [VERIFIED:...] citation"
+                    },
+                    "confirm": {
+                        "type": "boolean",
+                        "description": "Delete the matches the user agreed to (default: false, only list them)"
+                    }
+                },
+                "required": ["target"]
+            }),
         }
+    }
 
-        let selected: Vec<String> = lines[start..end]
-            .iter()
-            .enumerate()
-            .map(|(i, line)| format!("{:4}\t{}", start + i + 1, line))
-            .collect();
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments)?;
+        let target = args["target"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing target"))?;
+        let confirm = args["confirm"].as_bool().unwrap_or(false);
 
-        let header = format!(
-            "# {} (lines {}-{} of {})\n",
-            path,
-            start + 1,
-            end,
-            total_lines
+        let _writes = self.memory.lock_writes().await;
+        let memory = Arc::clone(&self.memory);
+        let target = target.to_string();
+        let report = tokio::task::spawn_blocking(move || memory.forget(&target, confirm)).await??;
+        debug!(
+            "forget: {} matches, {} chunks removed (verified: {})",
+            report.matches.len(),
+            report.chunks_removed,
+            report.verified()
         );
-        Ok(header + &selected.join("\n"))
+
+        let mut summary = report.summary();
+        if !report.applied && !report.matches.is_empty() {
+            summary.push_str(
+                "Nothing was deleted. Ask the user, then call forget again with confirm: true.",
+            );
+        }
+        Ok(summary)
     }
 }
 
-// Web Fetch Tool
-pub struct WebFetchTool {
-    client: reqwest::Client,
-    max_bytes: usize,
+// Graph Neighbors Tool - follow entity links across memory files
+pub struct GraphNeighborsTool {
+    memory: Arc<MemoryManager>,
 }
 
-impl WebFetchTool {
-    pub fn new(max_bytes: usize) -> Self {
-        Self {
-            client: reqwest::Client::new(),
-            max_bytes,
-        }
+impl GraphNeighborsTool {
+    pub fn new(memory: Arc<MemoryManager>) -> Self {
+        Self { memory }
     }
 }
 
 #[async_trait]
-impl Tool for WebFetchTool {
+impl Tool for GraphNeighborsTool {
     fn name(&self) -> &str {
-        "web_fetch"
+        "graph_neighbors"
+    }
+
+    fn mutates(&self) -> bool {
+        false
     }
 
     fn schema(&self) -> ToolSchema {
         ToolSchema {
-            name: "web_fetch".to_string(),
-            description: "Fetch content from a URL".to_string(),
+            name: "graph_neighbors".to_string(),
+            description: "Find the people, places, appliances and vendors linked to an entity in memory, across files (e.g. water heater -> the plumber who installed it -> their phone number). Use for questions like 'who installed the water heater' or 'what has Acme Plumbing worked on'. Each link cites the file and lines it came from; read them with memory_get.".to_string(),
             parameters: json!({
                 "type": "object",
                 "properties": {
-                    "url": {
+                    "entity": {
                         "type": "string",
-                        "description": "The URL to fetch"
+                        "description": "Name to start from, e.g. 'water heater', 'Acme Plumbing', 'Dave'"
+                    },
+                    "depth": {
+                        "type": "integer",
+                        "description": "How many links to follow (1-3, default: 2)"
                     }
                 },
-                "required": ["url"]
+                "required": ["entity"]
             }),
         }
     }
 
     async fn execute(&self, arguments: &str) -> Result<String> {
         let args: Value = serde_json::from_str(arguments)?;
-        let url = args["url"]
+        let entity = args["entity"]
             .as_str()
-            .ok_or_else(|| anyhow::anyhow!("Missing url"))?;
-
-        debug!("Fetching URL: {}", url);
+            .ok_or_else(|| anyhow::anyhow!("Missing entity"))?;
+        let depth = args["depth"].as_u64().unwrap_or(2).clamp(1, 3) as usize;
+
+        let edges = self.memory.graph_neighbors(entity, depth, 40)?;
+        debug!("graph_neighbors: {} links from '{}'", edges.len(), entity);
+        if edges.is_empty() {
+            return Ok(format!(
+                "No linked entities found for '{}'. Try memory_search instead.",
+                entity
+            ));
+        }
+
+        let mut output = String::new();
+        for hop in 1..=depth {
+            let lines: Vec<String> = edges
+                .iter()
+                .filter(|e| e.depth == hop)
+                .map(|e| format!("- {}", e.summary()))
+                .collect();
+            if lines.is_empty() {
+                continue;
+            }
+            let heading = if hop == 1 {
+                "Direct links".to_string()
+            } else {
+                format!("{} links away", hop)
+            };
+            output.push_str(&format!("{}:\n{}\n", heading, lines.join("\n")));
+        }
+        Ok(output.trim_end().to_string())
+    }
+}
+
+// Upcoming Events Tool - dates written in memory over the next few days
+pub struct UpcomingEventsTool {
+    memory: Arc<MemoryManager>,
+}
+
+impl UpcomingEventsTool {
+    pub fn new(memory: Arc<MemoryManager>) -> Self {
+        Self { memory }
+    }
+}
+
+#[async_trait]
+impl Tool for UpcomingEventsTool {
+    fn name(&self) -> &str {
+        "upcoming_events"
+    }
+
+    fn mutates(&self) -> bool {
+        false
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "upcoming_events".to_string(),
+            description: "List dates written anywhere in memory that fall in the next N days: birthdays, appointments, warranty expirations, renewals, due dates. Use this instead of memory_search for 'what's coming up' or 'what's due in the next two weeks'. Dates written without a year count as every year.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "days": {
+                        "type": "integer",
+                        "description": "How many days ahead to look, including today (1-366, default: 14)"
+                    }
+                }
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments)?;
+        let days = args["days"].as_i64().unwrap_or(14).clamp(1, 366);
+
+        let today = locale::now().date_naive();
+        let end = today + chrono::Duration::days(days - 1);
+        let entries = self.memory.dates_between(today, end, false)?;
+        debug!("upcoming_events: {} dates in {} days", entries.len(), days);
+
+        if entries.is_empty() {
+            return Ok(format!(
+                "Nothing dated in memory for the next {} days.",
+                days
+            ));
+        }
+        let lines: Vec<String> = entries
+            .iter()
+            .map(|e| format!("- {}", e.summary(today)))
+            .collect();
+        Ok(format!(
+            "Dates in memory for the next {} days:\n{}",
+            days,
+            lines.join("\n")
+        ))
+    }
+}
+
+// What Happened Around Tool - dated notes and daily logs near a date
+pub struct WhatHappenedAroundTool {
+    memory: Arc<MemoryManager>,
+}
+
+impl WhatHappenedAroundTool {
+    pub fn new(memory: Arc<MemoryManager>) -> Self {
+        Self { memory }
+    }
+}
+
+#[async_trait]
+impl Tool for WhatHappenedAroundTool {
+    fn name(&self) -> &str {
+        "what_happened_around"
+    }
+
+    fn mutates(&self) -> bool {
+        false
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "what_happened_around".to_string(),
+            description: "List what memory has dated near a given day: daily logs from those days and any notes that mention a date in the window. Use for 'what happened around March 3rd' or 'when did we last service the furnace' once you know roughly when. Read the cited lines with memory_get.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "date": {
+                        "type": "string",
+                        "description": "Day to look around (YYYY-MM-DD)"
+                    },
+                    "days": {
+                        "type": "integer",
+                        "description": "Days either side to include (0-31, default: 3)"
+                    }
+                },
+                "required": ["date"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments)?;
+        let date = args["date"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing date"))?;
+        let date = chrono::NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+            .map_err(|_| anyhow::anyhow!("Invalid date '{}', expected YYYY-MM-DD", date))?;
+        let days = args["days"].as_i64().unwrap_or(3).clamp(0, 31);
+
+        let window = chrono::Duration::days(days);
+        let entries = self
+            .memory
+            .dates_between(date - window, date + window, true)?;
+        debug!(
+            "what_happened_around: {} dates near {}",
+            entries.len(),
+            date
+        );
+
+        if entries.is_empty() {
+            return Ok(format!(
+                "Nothing in memory is dated within {} days of {}.",
+                days, date
+            ));
+        }
+        let today = locale::now().date_naive();
+        let lines: Vec<String> = entries
+            .iter()
+            .map(|e| format!("- {}", e.summary(today)))
+            .collect();
+        Ok(format!(
+            "Dated in memory within {} days of {}:\n{}",
+            days,
+            date,
+            lines.join("\n")
+        ))
+    }
+}
+
+// Memory Get Tool - efficient snippet fetching after memory_search
+pub struct MemoryGetTool {
+    workspace: PathBuf,
+}
+
+impl MemoryGetTool {
+    pub fn new(workspace: PathBuf) -> Self {
+        Self { workspace }
+    }
+
+    fn resolve_path(&self, path: &str) -> PathBuf {
+        resolve_memory_path(&self.workspace, path)
+    }
+}
+
+/// The file memory_get reads for `path`: memory files relative to the
+/// workspace, anything else as given
+pub(crate) fn resolve_memory_path(workspace: &std::path::Path, path: &str) -> PathBuf {
+    if path.starts_with("memory/") || path == "MEMORY.md" || path == "HEARTBEAT.md" {
+        workspace.join(path)
+    } else {
+        PathBuf::from(shellexpand::tilde(path).to_string())
+    }
+}
+
+#[async_trait]
+impl Tool for MemoryGetTool {
+    fn name(&self) -> &str {
+        "memory_get"
+    }
+
+    fn mutates(&self) -> bool {
+        false
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "memory_get".to_string(),
+            description: "Safe snippet read from MEMORY.md or memory/*.md with optional line range; use after memory_search to pull only the needed lines and keep context small.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Path to the file (e.g., 'MEMORY.md' or 'memory/2024-01-15.md')"
+                    },
+                    "from": {
+                        "type": "integer",
+                        "description": "Starting line number (1-indexed, default: 1)"
+                    },
+                    "lines": {
+                        "type": "integer",
+                        "description": "Number of lines to read (default: 50)"
+                    }
+                },
+                "required": ["path"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments)?;
+        let path = args["path"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing path"))?;
+
+        let from = args["from"].as_u64().unwrap_or(1).max(1) as usize;
+        let lines_count = args["lines"].as_u64().unwrap_or(50) as usize;
+
+        let resolved_path = self.resolve_path(path);
+
+        debug!(
+            "Memory get: {} (from: {}, lines: {})",
+            resolved_path.display(),
+            from,
+            lines_count
+        );
+
+        if !resolved_path.exists() {
+            return Ok(format!("File not found: {}", path));
+        }
+
+        let content = fs::read_to_string(&resolved_path)?;
+        let lines: Vec<&str> = content.lines().collect();
+        let total_lines = lines.len();
+
+        // Convert from 1-indexed to 0-indexed
+        let start = (from - 1).min(total_lines);
+        let end = (start + lines_count).min(total_lines);
+
+        if start >= total_lines {
+            return Ok(format!(
+                "Line {} is past end of file ({} lines)",
+                from, total_lines
+            ));
+        }
+
+        let selected: Vec<String> = lines[start..end]
+            .iter()
+            .enumerate()
+            .map(|(i, line)| format!("{:4}\t{}", start + i + 1, line))
+            .collect();
+
+        let header = format!(
+            "# {} (lines {}-{} of {})\n",
+            path,
+            start + 1,
+            end,
+            total_lines
+        );
+        Ok(header + &selected.join("\n"))
+    }
+}
+
+// Web Fetch Tool
+pub struct WebFetchTool {
+    client: reqwest::Client,
+    max_bytes: usize,
+}
+
+impl WebFetchTool {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            max_bytes,
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for WebFetchTool {
+    fn name(&self) -> &str {
+        "web_fetch"
+    }
+
+    fn mutates(&self) -> bool {
+        false
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "web_fetch".to_string(),
+            description: "Fetch content from a URL".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "url": {
+                        "type": "string",
+                        "description": "The URL to fetch"
+                    }
+                },
+                "required": ["url"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments)?;
+        let url = args["url"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing url"))?;
+
+        debug!("Fetching URL: {}", url);
 
         let response = self
             .client
@@ -818,21 +1556,1084 @@ impl Tool for WebFetchTool {
             .send()
             .await?;
 
-        let status = response.status();
-        let body = response.text().await?;
+        let status = response.status();
+        let body = response.text().await?;
+
+        // Truncate if too long
+        let truncated = if body.len() > self.max_bytes {
+            format!(
+                "{}...\n\n[Truncated, {} bytes total]",
+                &body[..self.max_bytes],
+                body.len()
+            )
+        } else {
+            body
+        };
+
+        Ok(format!("Status: {}\n\n{}", status, truncated))
+    }
+}
+
+// Budget Status Tool - spending against finance.budgets for a month
+pub struct BudgetStatusTool {
+    ledger: FinanceLedger,
+}
+
+impl BudgetStatusTool {
+    pub fn new(ledger: FinanceLedger) -> Self {
+        Self { ledger }
+    }
+}
+
+#[async_trait]
+impl Tool for BudgetStatusTool {
+    fn name(&self) -> &str {
+        "budget_status"
+    }
+
+    fn mutates(&self) -> bool {
+        false
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "budget_status".to_string(),
+            description: "Income, spending per category and budget remaining for a month, from imported bank transactions. Use for questions like 'how much is left for groceries'.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "month": {
+                        "type": "string",
+                        "description": "Month as YYYY-MM (default: current month)"
+                    }
+                }
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments)?;
+        let month = match args["month"].as_str() {
+            Some(month) => {
+                chrono::NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d")
+                    .map_err(|_| anyhow::anyhow!("Invalid month '{}', expected YYYY-MM", month))?;
+                month.to_string()
+            }
+            None => locale::now().format("%Y-%m").to_string(),
+        };
+
+        let summary = self.ledger.summary(&month)?;
+        if summary.transactions == 0 {
+            return Ok(format!(
+                "No transactions imported for {}. Import a bank CSV export with finance_import.",
+                month
+            ));
+        }
+        Ok(summary.status_text(self.ledger.config()))
+    }
+}
+
+// Finance Import Tool - add a bank/card CSV export to the finance ledger
+pub struct FinanceImportTool {
+    config: Config,
+    memory: Option<Arc<MemoryManager>>,
+}
+
+impl FinanceImportTool {
+    pub fn new(config: Config, memory: Option<Arc<MemoryManager>>) -> Self {
+        Self { config, memory }
+    }
+}
+
+#[async_trait]
+impl Tool for FinanceImportTool {
+    fn name(&self) -> &str {
+        "finance_import"
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "finance_import".to_string(),
+            description: "Import a bank or credit card CSV export: categorize the transactions and update the monthly summaries in memory/finance/. Re-importing overlapping exports is safe.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Path to the CSV file"
+                    },
+                    "account": {
+                        "type": "string",
+                        "description": "Account name, e.g. checking or visa (default: file name)"
+                    },
+                    "invert": {
+                        "type": "boolean",
+                        "description": "Flip amount signs, for card exports that list purchases as positive (default: false)"
+                    }
+                },
+                "required": ["path"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments)?;
+        let path = args["path"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing path"))?;
+        let path = PathBuf::from(shellexpand::tilde(path).to_string());
+
+        let options = ImportOptions {
+            account: match args["account"].as_str() {
+                Some(account) => account.to_string(),
+                None => path
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+            },
+            invert: args["invert"].as_bool().unwrap_or(false),
+            date_format: None,
+        };
+
+        let ledger = FinanceLedger::new(&self.config.workspace_path(), &self.config.finance);
+        let categorizer = Categorizer::from_config(&self.config);
+        let summary = ledger.import(&path, &options, &categorizer).await?;
+        debug!(
+            "finance_import: {} added, {} duplicates",
+            summary.added, summary.duplicates
+        );
+
+        if let Some(ref memory) = self.memory {
+            for month in &summary.months {
+                let summary_path = ledger.summary_path(month);
+                if let Err(e) = memory.index_written_file(&summary_path, "finance_import") {
+                    warn!("Failed to index {}: {}", summary_path.display(), e);
+                }
+            }
+        }
+
+        Ok(summary.summary())
+    }
+}
+
+// Weather Tool - forecast for the configured location
+pub struct WeatherTool {
+    service: WeatherService,
+}
+
+impl WeatherTool {
+    pub fn new(service: WeatherService) -> Self {
+        Self { service }
+    }
+}
+
+#[async_trait]
+impl Tool for WeatherTool {
+    fn name(&self) -> &str {
+        "weather"
+    }
+
+    fn mutates(&self) -> bool {
+        false
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "weather".to_string(),
+            description: "Current weather and forecast for home, with when rain starts and stops, frost, heat and wind. Use before suggesting outdoor plans or for questions like 'will it rain this afternoon'.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "days": {
+                        "type": "integer",
+                        "description": format!("Days of forecast including today (1-{}, default 2)", FORECAST_DAYS)
+                    }
+                }
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments)?;
+        let days = args["days"]
+            .as_u64()
+            .map_or(2, |d| (d as usize).clamp(1, FORECAST_DAYS));
+        let forecast = self.service.forecast().await?;
+        Ok(forecast.to_text(days))
+    }
+}
+
+// Who Is Home Tool - presence from phones (Home Assistant / OwnTracks)
+pub struct WhoIsHomeTool {
+    presence: Presence,
+}
+
+impl WhoIsHomeTool {
+    pub fn new(presence: Presence) -> Self {
+        Self { presence }
+    }
+}
+
+#[async_trait]
+impl Tool for WhoIsHomeTool {
+    fn name(&self) -> &str {
+        "who_is_home"
+    }
+
+    fn mutates(&self) -> bool {
+        false
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "who_is_home".to_string(),
+            description: "Which family members are home or away right now, from their phones."
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {}
+            }),
+        }
+    }
+
+    async fn execute(&self, _arguments: &str) -> Result<String> {
+        let snapshot = self.presence.snapshot().await;
+        let mut text = snapshot.summary();
+        if let Some(reason) = self.presence.quiet_reason(&snapshot, locale::now().time()) {
+            text.push_str(&format!(" Spoken announcements are off: {}.", reason));
+        }
+        Ok(text)
+    }
+}
+
+// Announce Tool - speak a short message on the house speakers
+pub struct AnnounceTool {
+    announcer: Announcer,
+}
+
+impl AnnounceTool {
+    pub fn new(announcer: Announcer) -> Self {
+        Self { announcer }
+    }
+}
+
+#[async_trait]
+impl Tool for AnnounceTool {
+    fn name(&self) -> &str {
+        "announce"
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "announce".to_string(),
+            description: "Speak a short message aloud on the house speakers, e.g. 'The dryer finished' or 'Leave in 10 minutes for piano'. Held back during quiet hours and when nobody is home, unless it's an emergency.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "message": {
+                        "type": "string",
+                        "description": "One or two short sentences, written to be spoken"
+                    },
+                    "device": {
+                        "type": "string",
+                        "description": format!("Only this speaker (default: all): {}", self.announcer.device_names().join(", "))
+                    },
+                    "emergency": {
+                        "type": "boolean",
+                        "description": "Speak even during quiet hours or while the kids are asleep. Only for real emergencies: smoke, a leak, someone hurt."
+                    }
+                },
+                "required": ["message"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments)?;
+        let message = args["message"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing message"))?;
+        let event = if args["emergency"].as_bool().unwrap_or(false) {
+            Event::Emergency
+        } else {
+            Event::Alert
+        };
+        let now = locale::now().naive_local();
+        let announcement = self
+            .announcer
+            .announce(message, args["device"].as_str(), event, now)
+            .await?;
+        Ok(announcement.summary())
+    }
+}
+
+/// Rewrite memory/calendar/upcoming.md after a calendar change and reindex it
+fn refresh_upcoming(calendar: &Calendar, memory: Option<&Arc<MemoryManager>>, source: &str) {
+    match calendar.write_upcoming(locale::now()) {
+        Ok(path) => {
+            if let Some(memory) = memory {
+                if let Err(e) = memory.index_written_file(&path, source) {
+                    warn!("Failed to index {}: {}", path.display(), e);
+                }
+            }
+        }
+        Err(e) => warn!("Failed to update upcoming calendar: {}", e),
+    }
+}
+
+// Calendar Add Event Tool - create an event on the published HomeGPT calendar
+pub struct CalendarAddEventTool {
+    calendar: Arc<Calendar>,
+    memory: Option<Arc<MemoryManager>>,
+}
+
+impl CalendarAddEventTool {
+    pub fn new(calendar: Arc<Calendar>, memory: Option<Arc<MemoryManager>>) -> Self {
+        Self { calendar, memory }
+    }
+}
+
+#[async_trait]
+impl Tool for CalendarAddEventTool {
+    fn name(&self) -> &str {
+        "calendar_add_event"
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "calendar_add_event".to_string(),
+            description: "Add an event to the family's HomeGPT calendar. It shows up in memory/calendar/upcoming.md and, when publishing is on, on everyone's phones through the .ics feed.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "title": {
+                        "type": "string",
+                        "description": "What the event is, e.g. 'Piano recital'"
+                    },
+                    "start": {
+                        "type": "string",
+                        "description": "YYYY-MM-DD for an all-day event, or YYYY-MM-DD HH:MM (local time)"
+                    },
+                    "end": {
+                        "type": "string",
+                        "description": "YYYY-MM-DD (last day, inclusive) or YYYY-MM-DD HH:MM (default: no end)"
+                    },
+                    "location": {
+                        "type": "string",
+                        "description": "Where it is"
+                    },
+                    "notes": {
+                        "type": "string",
+                        "description": "Extra details for the event description"
+                    }
+                },
+                "required": ["title", "start"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments)?;
+        let title = args["title"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing title"))?;
+        let start = When::parse(
+            args["start"]
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("Missing start"))?,
+        )?;
+        let end = args["end"].as_str().map(When::parse).transpose()?;
+        let event = self.calendar.add_event(
+            title,
+            start,
+            end,
+            args["location"].as_str().map(String::from),
+            args["notes"].as_str().map(String::from),
+        )?;
+        refresh_upcoming(&self.calendar, self.memory.as_ref(), "calendar_add_event");
+        Ok(format!(
+            "Added {} (id {})",
+            event.describe(),
+            short_id(&event.uid)
+        ))
+    }
+}
+
+// Calendar Remove Event Tool - delete an event the assistant created
+pub struct CalendarRemoveEventTool {
+    calendar: Arc<Calendar>,
+    memory: Option<Arc<MemoryManager>>,
+}
+
+impl CalendarRemoveEventTool {
+    pub fn new(calendar: Arc<Calendar>, memory: Option<Arc<MemoryManager>>) -> Self {
+        Self { calendar, memory }
+    }
+}
+
+#[async_trait]
+impl Tool for CalendarRemoveEventTool {
+    fn name(&self) -> &str {
+        "calendar_remove_event"
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "calendar_remove_event".to_string(),
+            description: "Remove an event from the HomeGPT calendar. Only events added with calendar_add_event can be removed, not ones from subscribed calendars.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "event": {
+                        "type": "string",
+                        "description": "The event's id or exact title"
+                    }
+                },
+                "required": ["event"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments)?;
+        let query = args["event"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing event"))?;
+        let event = self.calendar.remove_event(query.trim())?;
+        refresh_upcoming(
+            &self.calendar,
+            self.memory.as_ref(),
+            "calendar_remove_event",
+        );
+        Ok(format!("Removed {}", event.describe()))
+    }
+}
+
+// Set Reminder Tool - a one-off or repeating reminder sent to phones
+pub struct SetReminderTool {
+    store: Arc<ReminderStore>,
+}
+
+impl SetReminderTool {
+    pub fn new(store: Arc<ReminderStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Tool for SetReminderTool {
+    fn name(&self) -> &str {
+        "set_reminder"
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "set_reminder".to_string(),
+            description: "Set a reminder that is pushed to the family's phones when it's due. Use this, not HEARTBEAT.md, for anything at a particular time.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "message": {
+                        "type": "string",
+                        "description": "What to remind about, written for the person reading it, e.g. 'Piano lesson at 4:30'"
+                    },
+                    "when": {
+                        "type": "string",
+                        "description": "When, in plain English: 'in 20 minutes', 'tomorrow at 9am', 'next Tuesday at 4', 'nov 3 at 18:30', 'every other Saturday', 'every weekday at 7am'"
+                    }
+                },
+                "required": ["message", "when"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments)?;
+        let message = args["message"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing message"))?;
+        let when = args["when"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing when"))?;
+        let schedule = parse_schedule(when, locale::now().naive_local())?;
+        let reminder = self.store.add(message.trim(), &schedule)?;
+        Ok(format!("Reminder set: {}", reminder.summary()))
+    }
+}
+
+// List Reminders Tool
+pub struct ListRemindersTool {
+    store: Arc<ReminderStore>,
+}
+
+impl ListRemindersTool {
+    pub fn new(store: Arc<ReminderStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Tool for ListRemindersTool {
+    fn name(&self) -> &str {
+        "list_reminders"
+    }
+
+    fn mutates(&self) -> bool {
+        false
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "list_reminders".to_string(),
+            description: "List pending reminders with their ids, soonest first.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {}
+            }),
+        }
+    }
+
+    async fn execute(&self, _arguments: &str) -> Result<String> {
+        let reminders = self.store.list()?;
+        if reminders.is_empty() {
+            return Ok("No reminders set.".to_string());
+        }
+        Ok(reminders
+            .iter()
+            .map(|r| r.summary())
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+}
+
+// Cancel Reminder Tool
+pub struct CancelReminderTool {
+    store: Arc<ReminderStore>,
+}
+
+impl CancelReminderTool {
+    pub fn new(store: Arc<ReminderStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Tool for CancelReminderTool {
+    fn name(&self) -> &str {
+        "cancel_reminder"
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "cancel_reminder".to_string(),
+            description: "Cancel a reminder (all future occurrences of a repeating one). Get the id from list_reminders.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "integer",
+                        "description": "Reminder id"
+                    }
+                },
+                "required": ["id"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments)?;
+        let id = args["id"]
+            .as_i64()
+            .ok_or_else(|| anyhow::anyhow!("Missing id"))?;
+        match self.store.cancel(id)? {
+            Some(reminder) => Ok(format!("Cancelled {}", reminder.summary())),
+            None => anyhow::bail!("No reminder #{}", id),
+        }
+    }
+}
+
+// List Commitments Tool - what the assistant said it would do later
+pub struct ListCommitmentsTool {
+    store: Arc<CommitmentStore>,
+}
+
+impl ListCommitmentsTool {
+    pub fn new(store: Arc<CommitmentStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Tool for ListCommitmentsTool {
+    fn name(&self) -> &str {
+        "list_commitments"
+    }
+
+    fn mutates(&self) -> bool {
+        false
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "list_commitments".to_string(),
+            description: "List what you said you would do later and haven't done yet, with ids, oldest first.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {}
+            }),
+        }
+    }
+
+    async fn execute(&self, _arguments: &str) -> Result<String> {
+        let open = self.store.list(Status::Open)?;
+        if open.is_empty() {
+            return Ok("No open commitments.".to_string());
+        }
+        Ok(open
+            .iter()
+            .map(|c| c.summary())
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+}
+
+// Resolve Commitment Tool - mark a promise kept or broken
+pub struct ResolveCommitmentTool {
+    store: Arc<CommitmentStore>,
+}
 
-        // Truncate if too long
-        let truncated = if body.len() > self.max_bytes {
-            format!(
-                "{}...\n\n[Truncated, {} bytes total]",
-                &body[..self.max_bytes],
-                body.len()
-            )
-        } else {
-            body
+impl ResolveCommitmentTool {
+    pub fn new(store: Arc<CommitmentStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Tool for ResolveCommitmentTool {
+    fn name(&self) -> &str {
+        "resolve_commitment"
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "resolve_commitment".to_string(),
+            description: "Mark something you said you would do as done (you can see it was done: in memory, a reminder, a list) or broken (its time passed and it wasn't). Get the id from list_commitments.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "integer",
+                        "description": "Commitment id"
+                    },
+                    "status": {
+                        "type": "string",
+                        "enum": ["done", "broken", "open"],
+                        "description": "done, broken, or open to keep tracking it"
+                    },
+                    "note": {
+                        "type": "string",
+                        "description": "How you checked, or what went wrong"
+                    }
+                },
+                "required": ["id", "status"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments)?;
+        let id = args["id"]
+            .as_i64()
+            .ok_or_else(|| anyhow::anyhow!("Missing id"))?;
+        let status = Status::parse(
+            args["status"]
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("Missing status"))?,
+        )?;
+        let note = args["note"]
+            .as_str()
+            .map(str::trim)
+            .filter(|n| !n.is_empty());
+        match self.store.resolve(id, status, note)? {
+            Some(commitment) => Ok(format!(
+                "Marked {} as {}",
+                commitment.summary(),
+                status.as_str()
+            )),
+            None => anyhow::bail!("No commitment #{}", id),
+        }
+    }
+}
+
+// Set Timer Tool - a kitchen timer that goes off on the second
+pub struct SetTimerTool {
+    store: Arc<TimerStore>,
+}
+
+impl SetTimerTool {
+    pub fn new(store: Arc<TimerStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Tool for SetTimerTool {
+    fn name(&self) -> &str {
+        "set_timer"
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "set_timer".to_string(),
+            description: "Start a countdown timer (up to 24 hours), e.g. a 12 minute pasta timer. When it ends it is announced on the speakers and pushed to phones. For a particular time of day, use set_reminder instead.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "duration": {
+                        "type": "string",
+                        "description": "How long: '12 minutes', '1h30m', '90 seconds'"
+                    },
+                    "label": {
+                        "type": "string",
+                        "description": "What it's for, e.g. 'pasta' (optional)"
+                    },
+                    "device": {
+                        "type": "string",
+                        "description": "Speaker to announce on, e.g. 'kitchen' (default: all)"
+                    }
+                },
+                "required": ["duration"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments)?;
+        let duration = parse_timer_duration(
+            args["duration"]
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("Missing duration"))?,
+        )?;
+        let label = args["label"].as_str().unwrap_or_default().trim();
+        let now = Utc::now();
+        let timer = self
+            .store
+            .add(label, duration, args["device"].as_str(), now)?;
+        let ends = locale::home_time(&timer.ends_at);
+        Ok(format!(
+            "Timer set: {} (ends at {})",
+            timer.summary(now),
+            ends.format("%H:%M:%S")
+        ))
+    }
+}
+
+// List Timers Tool
+pub struct ListTimersTool {
+    store: Arc<TimerStore>,
+}
+
+impl ListTimersTool {
+    pub fn new(store: Arc<TimerStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Tool for ListTimersTool {
+    fn name(&self) -> &str {
+        "list_timers"
+    }
+
+    fn mutates(&self) -> bool {
+        false
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "list_timers".to_string(),
+            description: "List running timers and how long each has left.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {}
+            }),
+        }
+    }
+
+    async fn execute(&self, _arguments: &str) -> Result<String> {
+        let timers = self.store.list()?;
+        if timers.is_empty() {
+            return Ok("No timers running.".to_string());
+        }
+        let now = Utc::now();
+        Ok(timers
+            .iter()
+            .map(|t| t.summary(now))
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+}
+
+// Cancel Timer Tool
+pub struct CancelTimerTool {
+    store: Arc<TimerStore>,
+}
+
+impl CancelTimerTool {
+    pub fn new(store: Arc<TimerStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Tool for CancelTimerTool {
+    fn name(&self) -> &str {
+        "cancel_timer"
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "cancel_timer".to_string(),
+            description: "Stop a running timer.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "timer": {
+                        "type": "string",
+                        "description": "The timer's label (e.g. 'pasta') or id from list_timers"
+                    }
+                },
+                "required": ["timer"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments)?;
+        let query = match &args["timer"] {
+            Value::Number(id) => id.to_string(),
+            value => value
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("Missing timer"))?
+                .to_string(),
         };
+        let timer = self.store.cancel(&query)?;
+        Ok(format!("Cancelled the {}", timer.name()))
+    }
+}
 
-        Ok(format!("Status: {}\n\n{}", status, truncated))
+/// HEARTBEAT.md and its content as the agent's writes left it (empty if
+/// there's no file yet)
+fn read_heartbeat(memory: &MemoryManager) -> Result<(PathBuf, String)> {
+    let path = memory.workspace().join(HEARTBEAT_PATH);
+    let content = if path.exists() {
+        memory.read_file(&path)?
+    } else {
+        memory.read_file(&path).unwrap_or_default()
+    };
+    Ok((path, content))
+}
+
+// Schedule Task Tool - a recurring heartbeat task from plain English
+pub struct ScheduleTaskTool {
+    memory: Arc<MemoryManager>,
+}
+
+impl ScheduleTaskTool {
+    pub fn new(memory: Arc<MemoryManager>) -> Self {
+        Self { memory }
+    }
+}
+
+#[async_trait]
+impl Tool for ScheduleTaskTool {
+    fn name(&self) -> &str {
+        "schedule_task"
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "schedule_task".to_string(),
+            description: "Add a recurring task for the heartbeat to HEARTBEAT.md, e.g. 'check the propane level page every Monday morning'. Use set_reminder for a one-off nudge to the family instead.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "title": {
+                        "type": "string",
+                        "description": "Short section title, e.g. 'Propane Check'"
+                    },
+                    "task": {
+                        "type": "string",
+                        "description": "What to do each time; one checklist item per line"
+                    },
+                    "schedule": {
+                        "type": "string",
+                        "description": "How often, in plain English: 'every Monday morning', 'daily at 8pm', 'every weekday at 7am', 'every other Saturday', 'every 2 hours'"
+                    }
+                },
+                "required": ["title", "task", "schedule"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments)?;
+        let title = args["title"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing title"))?;
+        let task = args["task"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing task"))?;
+        let schedule = args["schedule"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing schedule"))?;
+
+        let schedule = schedule_label(schedule, locale::now().naive_local())?;
+        let _writes = self.memory.lock_writes().await;
+        let (path, content) = read_heartbeat(&self.memory)?;
+        let content = add_task(&content, title, &schedule, task)?;
+        if let FileWrite::Staged(id) =
+            self.memory
+                .write_file(&path, &content, "schedule_task", "agent")?
+        {
+            return Ok(staged_message(id, &path));
+        }
+        Ok(format!(
+            "Scheduled '{}' ({}) in HEARTBEAT.md",
+            title.trim(),
+            schedule
+        ))
+    }
+}
+
+// List Tasks Tool
+pub struct ListTasksTool {
+    memory: Arc<MemoryManager>,
+}
+
+impl ListTasksTool {
+    pub fn new(memory: Arc<MemoryManager>) -> Self {
+        Self { memory }
+    }
+}
+
+#[async_trait]
+impl Tool for ListTasksTool {
+    fn name(&self) -> &str {
+        "list_tasks"
+    }
+
+    fn mutates(&self) -> bool {
+        false
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "list_tasks".to_string(),
+            description: "List the recurring tasks in HEARTBEAT.md with their schedules"
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {}
+            }),
+        }
+    }
+
+    async fn execute(&self, _arguments: &str) -> Result<String> {
+        let (_, content) = read_heartbeat(&self.memory)?;
+        let tasks = parse_tasks(&content);
+        if tasks.is_empty() {
+            return Ok("HEARTBEAT.md has no tasks.".to_string());
+        }
+        let mut lines = Vec::new();
+        for task in &tasks {
+            match task.schedule {
+                Some(ref schedule) => lines.push(format!("{} ({})", task.title, schedule)),
+                None => lines.push(format!("{} (no schedule)", task.title)),
+            }
+            lines.extend(task.items().map(|item| format!("  {}", item)));
+        }
+        Ok(lines.join("\n"))
+    }
+}
+
+// Remove Task Tool
+pub struct RemoveTaskTool {
+    memory: Arc<MemoryManager>,
+}
+
+impl RemoveTaskTool {
+    pub fn new(memory: Arc<MemoryManager>) -> Self {
+        Self { memory }
+    }
+}
+
+#[async_trait]
+impl Tool for RemoveTaskTool {
+    fn name(&self) -> &str {
+        "remove_task"
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchema {
+            name: "remove_task".to_string(),
+            description: "Remove a recurring task from HEARTBEAT.md by its title (see list_tasks)"
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "title": {
+                        "type": "string",
+                        "description": "The task's title, e.g. 'Propane Check'"
+                    }
+                },
+                "required": ["title"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String> {
+        let args: Value = serde_json::from_str(arguments)?;
+        let title = args["title"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing title"))?;
+
+        let _writes = self.memory.lock_writes().await;
+        let (path, content) = read_heartbeat(&self.memory)?;
+        let (content, task) = remove_task(&content, title)?;
+        if let FileWrite::Staged(id) =
+            self.memory
+                .write_file(&path, &content, "remove_task", "agent")?
+        {
+            return Ok(staged_message(id, &path));
+        }
+        Ok(format!("Removed '{}' from HEARTBEAT.md", task.title))
+    }
+}
+
+/// The change a file tool call would make, as diff-style lines for the
+/// approval prompt
+pub fn extract_tool_preview(tool_name: &str, arguments: &str) -> Option<String> {
+    let args: Value = serde_json::from_str(arguments).ok()?;
+    let prefixed = |prefix: &str, text: &str| {
+        text.lines()
+            .map(|line| format!("{}{}", prefix, line))
+            .collect::<Vec<_>>()
+    };
+
+    match tool_name {
+        "patch_file" => args["diff"].as_str().map(|s| s.trim_end().to_string()),
+        "edit_file" => {
+            let old = args["old_string"].as_str()?;
+            let new = args["new_string"].as_str()?;
+            let mut lines = prefixed("-", old);
+            lines.extend(prefixed("+", new));
+            Some(lines.join("\n"))
+        }
+        "write_file" => args["content"]
+            .as_str()
+            .map(|s| prefixed("+", s).join("\n")),
+        _ => None,
     }
 }
 
@@ -842,7 +2643,7 @@ pub fn extract_tool_detail(tool_name: &str, arguments: &str) -> Option<String> {
     let args: Value = serde_json::from_str(arguments).ok()?;
 
     match tool_name {
-        "edit_file" | "write_file" | "read_file" => args
+        "edit_file" | "write_file" | "read_file" | "patch_file" => args
             .get("path")
             .or_else(|| args.get("file_path"))
             .and_then(|v| v.as_str())
@@ -858,6 +2659,14 @@ pub fn extract_tool_detail(tool_name: &str, arguments: &str) -> Option<String> {
             .get("query")
             .and_then(|v| v.as_str())
             .map(|s| format!("\"{}\"", s)),
+        "what_happened_around" => args
+            .get("date")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        "graph_neighbors" => args
+            .get("entity")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
         "memory_store" => args
             .get("fact")
             .and_then(|v| v.as_str())
@@ -868,6 +2677,30 @@ pub fn extract_tool_detail(tool_name: &str, arguments: &str) -> Option<String> {
                     format!("\"{}\"", s)
                 }
             }),
+        "schedule_task" | "remove_task" => args
+            .get("title")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        "set_timer" => args
+            .get("duration")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        "set_reminder" => args
+            .get("when")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        "calendar_add_event" => args
+            .get("title")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        "finance_import" => args
+            .get("path")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        "budget_status" => args
+            .get("month")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
         "web_fetch" => args
             .get("url")
             .and_then(|v| v.as_str())
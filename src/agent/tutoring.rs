@@ -0,0 +1,131 @@
+//! Tutor notes: when a tutoring session ends, the summary model writes up
+//! what was covered, what the student struggled with and what they got
+//! right, and it's added to `memory/school/tutor-notes.md`.
+
+use chrono::NaiveDateTime;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::locale;
+
+/// Where tutoring sessions are logged, in the workspace
+pub const TUTOR_NOTES_PATH: &str = "memory/school/tutor-notes.md";
+
+/// A tutoring session, as the model summed it up
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TutorNotes {
+    /// The student's name if it came up, otherwise empty
+    pub student: String,
+    /// The subject, e.g. "Math" or "Reading"
+    pub subject: String,
+    /// What the session covered, in one or two sentences
+    pub summary: String,
+    /// Things the student found hard, one short phrase each
+    pub struggled: Vec<String>,
+    /// Things the student got right or did well, one short phrase each
+    pub got_right: Vec<String>,
+}
+
+pub(crate) fn notes_prompt(transcript: &str, student: Option<&str>) -> String {
+    let who = match student {
+        Some(name) => format!("The student is {}. ", name),
+        None => String::new(),
+    };
+    format!(
+        "Below is a spoken tutoring session between a homeschool student and their \
+         tutor. {}Write notes on it for the parents and the next session: the \
+         subject, what was covered, what the student struggled with and what they \
+         got right. Be specific (\"carrying in two-digit addition\", not \"math\"), \
+         and only note what happened in the session. Write in the language the \
+         session was in.\n\nSession:\n{}",
+        who, transcript
+    )
+}
+
+/// `notes` as a section of the tutor notes file
+pub(crate) fn notes_entry(
+    notes: &TutorNotes,
+    student: Option<&str>,
+    when: NaiveDateTime,
+) -> String {
+    let locale = locale::current();
+    let mut heading = vec![when.format("%Y-%m-%d %H:%M").to_string()];
+    let student = student.unwrap_or(notes.student.trim());
+    if !student.is_empty() {
+        heading.push(student.to_string());
+    }
+    if !notes.subject.trim().is_empty() {
+        heading.push(notes.subject.trim().to_string());
+    }
+
+    let mut entry = format!("## {}\n\n", heading.join(" · "));
+    if !notes.summary.trim().is_empty() {
+        entry.push_str(notes.summary.trim());
+        entry.push_str("\n\n");
+    }
+    for (key, items) in [
+        ("tutor.struggled", &notes.struggled),
+        ("tutor.got_right", &notes.got_right),
+    ] {
+        let items: Vec<&str> = items
+            .iter()
+            .map(|item| item.trim())
+            .filter(|item| !item.is_empty())
+            .collect();
+        if !items.is_empty() {
+            entry.push_str(&format!("- {}: {}\n", locale.text(key), items.join("; ")));
+        }
+    }
+    entry
+}
+
+/// `existing` tutor notes with `entry` added at the end
+pub(crate) fn append_entry(existing: &str, entry: &str) -> String {
+    let mut notes = existing.trim_end().to_string();
+    if !notes.is_empty() {
+        notes.push_str("\n\n");
+    }
+    notes.push_str(entry.trim_end());
+    notes.push('\n');
+    notes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_notes_entry() {
+        let notes = TutorNotes {
+            student: "Leo".to_string(),
+            subject: "Math".to_string(),
+            summary: "Adding fractions with unlike denominators.".to_string(),
+            struggled: vec!["finding a common denominator".to_string()],
+            got_right: vec!["simplifying 2/4".to_string(), " ".to_string()],
+        };
+        let when = NaiveDate::from_ymd_opt(2026, 10, 18)
+            .unwrap()
+            .and_hms_opt(15, 4, 0)
+            .unwrap();
+
+        assert_eq!(
+            notes_entry(&notes, None, when),
+            "## 2026-10-18 15:04 · Leo · Math\n\n\
+             Adding fractions with unlike denominators.\n\n\
+             - Struggled with: finding a common denominator\n\
+             - Got right: simplifying 2/4\n"
+        );
+        assert!(notes_entry(&notes, Some("Mia"), when).starts_with("## 2026-10-18 15:04 · Mia ·"));
+    }
+
+    #[test]
+    fn test_append_entry() {
+        let entry = "## Today\n\nFractions.\n";
+        assert_eq!(
+            append_entry("# Tutor Session Notes\n\n<!-- notes -->\n", entry),
+            "# Tutor Session Notes\n\n<!-- notes -->\n\n## Today\n\nFractions.\n"
+        );
+        assert_eq!(append_entry("", entry), entry);
+    }
+}
@@ -0,0 +1,313 @@
+//! Spoken announcements ("the dryer finished", "leave in 10 minutes for
+//! piano")
+//!
+//! Announcements go to the speakers in `announce.devices`: a local speaker
+//! through Piper or the OpenAI speech API, or a Home Assistant media_player
+//! (Sonos, Google, ...) with `tts.speak`. They are held back during quiet hours (see [`crate::quiet`])
+//! and, when presence is configured, when nobody is home or the kids are
+//! asleep. Emergencies are always spoken.
+
+mod sentences;
+mod speakers;
+
+pub use sentences::SentenceChunker;
+pub use speakers::{HomeAssistantSpeaker, OpenAiSpeaker, PiperSpeaker};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use std::path::Path;
+use tracing::{info, warn};
+
+use crate::config::Config;
+use crate::notifications::Event;
+use crate::presence::Presence;
+use crate::quiet::QuietPolicy;
+
+/// A device that can speak text aloud
+#[async_trait]
+pub trait Speaker: Send + Sync {
+    /// Name from config, e.g. "kitchen"
+    fn name(&self) -> &str;
+
+    async fn speak(&self, text: &str) -> Result<()>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Announcement {
+    /// Spoken on these devices
+    Spoken(Vec<String>),
+    /// Not spoken, with the reason
+    Held(String),
+}
+
+impl Announcement {
+    pub fn summary(&self) -> String {
+        match self {
+            Self::Spoken(devices) => format!("Announced on {}", devices.join(", ")),
+            Self::Held(reason) => format!("Not announced: {}", reason),
+        }
+    }
+}
+
+/// Sends announcements to the configured speakers
+pub struct Announcer {
+    speakers: Vec<Box<dyn Speaker>>,
+    policy: QuietPolicy,
+    presence: Option<Presence>,
+}
+
+impl Announcer {
+    pub fn new(policy: QuietPolicy, presence: Option<Presence>) -> Self {
+        Self {
+            speakers: Vec::new(),
+            policy,
+            presence,
+        }
+    }
+
+    /// Announcer for `announce.devices`, or `None` if there are none
+    pub fn from_config(config: &Config, state_dir: &Path) -> Result<Option<Self>> {
+        let announce = &config.announce;
+        if announce.devices.is_empty() {
+            return Ok(None);
+        }
+
+        let mut announcer = Self::new(
+            QuietPolicy::from_config(config)?,
+            Presence::from_config(config, state_dir)?,
+        );
+
+        for device in &announce.devices {
+            let speaker: Box<dyn Speaker> = match device.kind.as_str() {
+                "piper" => Box::new(PiperSpeaker::new(device)?),
+                "homeassistant" => {
+                    let home_assistant = config.home_assistant.as_ref().ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "Announce device {} needs [home_assistant] in config",
+                            device.name
+                        )
+                    })?;
+                    Box::new(HomeAssistantSpeaker::new(device, home_assistant)?)
+                }
+                "openai" => {
+                    let openai = config.providers.openai.as_ref().ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "Announce device {} needs [providers.openai] in config",
+                            device.name
+                        )
+                    })?;
+                    Box::new(OpenAiSpeaker::new(device, openai)?)
+                }
+                other => anyhow::bail!(
+                    "Unknown announce device kind '{}' for {}: expected piper, homeassistant or openai",
+                    other,
+                    device.name
+                ),
+            };
+            announcer.add(speaker);
+        }
+        Ok(Some(announcer))
+    }
+
+    pub fn add(&mut self, speaker: Box<dyn Speaker>) {
+        self.speakers.push(speaker);
+    }
+
+    pub fn device_names(&self) -> Vec<&str> {
+        self.speakers.iter().map(|s| s.name()).collect()
+    }
+
+    /// Why announcing `event` is held back at `now`, if it is
+    pub async fn hold_reason(&self, event: Event, now: NaiveDateTime) -> Option<String> {
+        if let Some(reason) = self.policy.speaker_hold(event, now) {
+            return Some(reason);
+        }
+        if event == Event::Emergency {
+            return None;
+        }
+        let presence = self.presence.as_ref()?;
+        let snapshot = presence.snapshot().await;
+        presence
+            .quiet_reason(&snapshot, now.time())
+            .map(String::from)
+    }
+
+    /// Speak `text` on one device (by name) or all of them, unless held back
+    pub async fn announce(
+        &self,
+        text: &str,
+        device: Option<&str>,
+        event: Event,
+        now: NaiveDateTime,
+    ) -> Result<Announcement> {
+        let targets: Vec<&dyn Speaker> = match device {
+            Some(name) => vec![self.speaker(name)?],
+            None => self.speakers.iter().map(|s| s.as_ref()).collect(),
+        };
+
+        if let Some(reason) = self.hold_reason(event, now).await {
+            info!("Announcement held ({}): {}", reason, text);
+            return Ok(Announcement::Held(reason));
+        }
+        Self::speak_on(&targets, text).await
+    }
+
+    /// Speak `text` on one device (by name, or the first one) right away.
+    /// For things the user asked to hear, like chat replies read aloud, so
+    /// quiet hours and presence don't hold it back.
+    pub async fn say(&self, text: &str, device: Option<&str>) -> Result<Announcement> {
+        let speaker = match device {
+            Some(name) => self.speaker(name)?,
+            None => self
+                .speakers
+                .first()
+                .map(|s| s.as_ref())
+                .ok_or_else(|| anyhow::anyhow!("No announce devices configured"))?,
+        };
+        Self::speak_on(&[speaker], text).await
+    }
+
+    fn speaker(&self, name: &str) -> Result<&dyn Speaker> {
+        self.speakers
+            .iter()
+            .find(|s| s.name().eq_ignore_ascii_case(name))
+            .map(|s| s.as_ref())
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Unknown announce device '{}' (have: {})",
+                    name,
+                    self.device_names().join(", ")
+                )
+            })
+    }
+
+    async fn speak_on(targets: &[&dyn Speaker], text: &str) -> Result<Announcement> {
+        let mut spoken = Vec::new();
+        let mut last_error = None;
+        for speaker in targets {
+            match speaker.speak(text).await {
+                Ok(()) => spoken.push(speaker.name().to_string()),
+                Err(e) => {
+                    warn!("Announcement on {} failed: {}", speaker.name(), e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        match (spoken.is_empty(), last_error) {
+            (true, Some(e)) => Err(e),
+            _ => Ok(Announcement::Spoken(spoken)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TimeWindow;
+    use crate::quiet::Schedule;
+    use std::sync::{Arc, Mutex};
+
+    struct FakeSpeaker {
+        name: String,
+        spoken: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl Speaker for FakeSpeaker {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        async fn speak(&self, text: &str) -> Result<()> {
+            self.spoken
+                .lock()
+                .unwrap()
+                .push(format!("{}: {}", self.name, text));
+            Ok(())
+        }
+    }
+
+    fn announcer(spoken: &Arc<Mutex<Vec<String>>>) -> Announcer {
+        let quiet = TimeWindow::parse("21:00", "07:00").unwrap();
+        let policy = QuietPolicy::new(Schedule::every_night(quiet), vec![]);
+        let mut announcer = Announcer::new(policy, None);
+        for name in ["kitchen", "upstairs"] {
+            announcer.add(Box::new(FakeSpeaker {
+                name: name.to_string(),
+                spoken: Arc::clone(spoken),
+            }));
+        }
+        announcer
+    }
+
+    fn time(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(&format!("2026-10-15 {}", s), "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_announce_targets() {
+        let spoken = Arc::new(Mutex::new(Vec::new()));
+        let announcer = announcer(&spoken);
+
+        let result = announcer
+            .announce("Dryer finished", None, Event::Alert, time("15:00"))
+            .await
+            .unwrap();
+        assert_eq!(result.summary(), "Announced on kitchen, upstairs");
+
+        announcer
+            .announce(
+                "Piano in 10 minutes",
+                Some("Kitchen"),
+                Event::Alert,
+                time("15:05"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            *spoken.lock().unwrap(),
+            vec![
+                "kitchen: Dryer finished",
+                "upstairs: Dryer finished",
+                "kitchen: Piano in 10 minutes",
+            ]
+        );
+        assert!(announcer
+            .announce("Hi", Some("garage"), Event::Alert, time("15:00"))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_quiet_hours_hold() {
+        let spoken = Arc::new(Mutex::new(Vec::new()));
+        let announcer = announcer(&spoken);
+
+        let result = announcer
+            .announce("Dryer finished", None, Event::Alert, time("22:30"))
+            .await
+            .unwrap();
+        assert_eq!(
+            result,
+            Announcement::Held("quiet hours (21:00 to 07:00)".to_string())
+        );
+        assert!(spoken.lock().unwrap().is_empty());
+
+        let result = announcer
+            .announce(
+                "Smoke alarm downstairs",
+                None,
+                Event::Emergency,
+                time("02:00"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.summary(), "Announced on kitchen, upstairs");
+
+        // A reply read aloud was asked for, so it isn't held; first device
+        let result = announcer.say("Here's the recipe", None).await.unwrap();
+        assert_eq!(result.summary(), "Announced on kitchen");
+        assert!(announcer.say("Hi", Some("garage")).await.is_err());
+    }
+}
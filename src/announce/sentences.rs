@@ -0,0 +1,157 @@
+//! Splitting streamed text into sentences, so a speaker can start on the
+//! first one while the rest is still being written
+
+/// Words that end in a period without ending the sentence
+const ABBREVIATIONS: &[&str] = &["mr", "mrs", "ms", "dr", "st", "vs", "e.g", "i.e", "approx"];
+
+/// Collects streamed text and hands back whole sentences. Lines also end a
+/// sentence (list items, headings), and fenced code blocks are skipped.
+#[derive(Debug, Default)]
+pub struct SentenceChunker {
+    buffer: String,
+    in_code: bool,
+}
+
+impl SentenceChunker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add streamed `text`; returns the sentences it completed
+    pub fn push(&mut self, text: &str) -> Vec<String> {
+        self.buffer.push_str(text);
+        let mut sentences = Vec::new();
+        while let Some(end) = self.next_boundary() {
+            let sentence: String = self.buffer.drain(..end).collect();
+            self.take(&sentence, &mut sentences);
+        }
+        sentences
+    }
+
+    /// The rest once the reply is done, if there's anything to say
+    pub fn finish(&mut self) -> Option<String> {
+        let rest = std::mem::take(&mut self.buffer);
+        let mut sentences = Vec::new();
+        self.take(&rest, &mut sentences);
+        self.in_code = false;
+        sentences.pop()
+    }
+
+    /// Byte index just past the first complete sentence or line in the buffer
+    fn next_boundary(&self) -> Option<usize> {
+        let text = &self.buffer;
+        let line_end = text.find('\n').map(|i| i + 1);
+        if self.in_code {
+            // Nothing in a code block is spoken, so only lines matter
+            return line_end;
+        }
+        let mut chars = text.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            if line_end.is_some_and(|end| i >= end) {
+                break;
+            }
+            if !matches!(c, '.' | '!' | '?') {
+                continue;
+            }
+            // Wait to see what follows before deciding
+            let &(next_index, next) = chars.peek()?;
+            if next.is_whitespace() && ends_sentence(&text[..i + c.len_utf8()]) {
+                return Some(next_index);
+            }
+        }
+        line_end
+    }
+
+    /// Queue `text` as a sentence, tracking code fences
+    fn take(&mut self, text: &str, sentences: &mut Vec<String>) {
+        let trimmed = text.trim();
+        if trimmed.starts_with("```") {
+            self.in_code = !self.in_code;
+            return;
+        }
+        if self.in_code || !trimmed.chars().any(char::is_alphanumeric) {
+            return;
+        }
+        sentences.push(trimmed.to_string());
+    }
+}
+
+/// Whether `text`, ending in `.`, `!` or `?`, ends a sentence rather than a
+/// list number ("2."), an initial ("J.") or an abbreviation ("Dr.")
+fn ends_sentence(text: &str) -> bool {
+    let Some(text) = text.strip_suffix('.') else {
+        return true;
+    };
+    let number = text.trim();
+    if number.is_empty() || number.chars().all(|c| c.is_ascii_digit()) {
+        return false;
+    }
+    let word = text
+        .rsplit(char::is_whitespace)
+        .next()
+        .unwrap_or_default()
+        .trim_start_matches(|c: char| !c.is_alphanumeric());
+    if word.chars().count() == 1 && word.chars().all(char::is_uppercase) {
+        return false;
+    }
+    !ABBREVIATIONS.contains(&word.to_lowercase().as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(parts: &[&str]) -> Vec<String> {
+        let mut chunker = SentenceChunker::new();
+        let mut sentences: Vec<String> = parts.iter().flat_map(|p| chunker.push(p)).collect();
+        sentences.extend(chunker.finish());
+        sentences
+    }
+
+    #[test]
+    fn test_sentences_across_chunks() {
+        assert_eq!(
+            chunk(&[
+                "Piano is at 4",
+                ". Leave by 3:",
+                "45! Don't forget",
+                " the music?"
+            ]),
+            vec![
+                "Piano is at 4.",
+                "Leave by 3:45!",
+                "Don't forget the music?"
+            ]
+        );
+
+        let mut chunker = SentenceChunker::new();
+        // Not yet: the period might be a decimal point
+        assert!(chunker.push("It's 3.").is_empty());
+        assert!(chunker.push("5 miles.").is_empty());
+        assert_eq!(chunker.push(" Drive safe"), vec!["It's 3.5 miles."]);
+        assert_eq!(chunker.finish().as_deref(), Some("Drive safe"));
+        assert_eq!(chunker.finish(), None);
+    }
+
+    #[test]
+    fn test_lists_and_abbreviations() {
+        assert_eq!(
+            chunk(&["Ask Dr. Lee and J. Smith. You need:\n1. Eggs\n2. Milk\n\nThat's it."]),
+            vec![
+                "Ask Dr. Lee and J. Smith.",
+                "You need:",
+                "1. Eggs",
+                "2. Milk",
+                "That's it.",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_code_blocks_skipped() {
+        assert_eq!(
+            chunk(&["Run this.\n```", "sh\nls -la. rm x.\n``", "`\nDone."]),
+            vec!["Run this.", "Done."]
+        );
+    }
+}
@@ -0,0 +1,230 @@
+//! Speaker implementations: Piper or the OpenAI speech API on a local
+//! speaker, Home Assistant media players
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde_json::json;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use super::Speaker;
+use crate::config::{AnnounceDeviceConfig, HomeAssistantConfig, OpenAIConfig};
+use crate::presence::HomeAssistant;
+
+/// Speaks with Piper and plays the WAV on this machine
+pub struct PiperSpeaker {
+    name: String,
+    command: String,
+    model: String,
+    player: String,
+}
+
+impl PiperSpeaker {
+    pub fn new(config: &AnnounceDeviceConfig) -> Result<Self> {
+        if config.model.is_empty() {
+            anyhow::bail!("Announce device {} needs a Piper model", config.name);
+        }
+        Ok(Self {
+            name: config.name.clone(),
+            command: config.piper_command.clone(),
+            model: shellexpand::tilde(&config.model).to_string(),
+            player: player(config),
+        })
+    }
+
+    async fn synthesize(&self, text: &str, wav: &std::path::Path) -> Result<()> {
+        let mut child = Command::new(&self.command)
+            .arg("--model")
+            .arg(&self.model)
+            .arg("--output_file")
+            .arg(wav)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            // Stopping a reply read aloud drops this mid-sentence
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to run {}", self.command))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes()).await?;
+        }
+        let output = child.wait_with_output().await?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "Piper failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Speaker for PiperSpeaker {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn speak(&self, text: &str) -> Result<()> {
+        let wav = temp_wav();
+        let result = match self.synthesize(text, &wav).await {
+            Ok(()) => play(&self.player, &wav).await,
+            Err(e) => Err(e),
+        };
+        std::fs::remove_file(&wav).ok();
+        result
+    }
+}
+
+/// Speaks with the OpenAI speech API and plays the WAV on this machine
+pub struct OpenAiSpeaker {
+    name: String,
+    client: reqwest::Client,
+    api_key: String,
+    base_url: String,
+    model: String,
+    voice: String,
+    player: String,
+}
+
+impl OpenAiSpeaker {
+    pub fn new(config: &AnnounceDeviceConfig, openai: &OpenAIConfig) -> Result<Self> {
+        let model = if config.model.is_empty() {
+            "tts-1".to_string()
+        } else {
+            config.model.clone()
+        };
+        Ok(Self {
+            name: config.name.clone(),
+            client: reqwest::Client::new(),
+            api_key: openai.api_key.clone(),
+            base_url: openai.base_url.trim_end_matches('/').to_string(),
+            model,
+            voice: config.voice.clone().unwrap_or_else(|| "alloy".to_string()),
+            player: player(config),
+        })
+    }
+
+    async fn synthesize(&self, text: &str, wav: &std::path::Path) -> Result<()> {
+        let response = self
+            .client
+            .post(format!("{}/audio/speech", self.base_url))
+            .bearer_auth(&self.api_key)
+            .json(&json!({
+                "model": self.model,
+                "voice": self.voice,
+                "input": text,
+                "response_format": "wav",
+            }))
+            .send()
+            .await
+            .context("Speech request failed")?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("Speech API returned {}: {}", status, body);
+        }
+        let audio = response.bytes().await?;
+        tokio::fs::write(wav, &audio).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Speaker for OpenAiSpeaker {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn speak(&self, text: &str) -> Result<()> {
+        let wav = temp_wav();
+        let result = match self.synthesize(text, &wav).await {
+            Ok(()) => play(&self.player, &wav).await,
+            Err(e) => Err(e),
+        };
+        std::fs::remove_file(&wav).ok();
+        result
+    }
+}
+
+/// The device's player command, or the platform's
+fn player(config: &AnnounceDeviceConfig) -> String {
+    let default_player = if cfg!(target_os = "macos") {
+        "afplay"
+    } else {
+        "aplay -q"
+    };
+    config
+        .player
+        .clone()
+        .unwrap_or_else(|| default_player.to_string())
+}
+
+fn temp_wav() -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("homegpt-announce-{}.wav", uuid::Uuid::new_v4()))
+}
+
+async fn play(player: &str, wav: &std::path::Path) -> Result<()> {
+    let mut parts = player.split_whitespace();
+    let program = parts
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Empty player command"))?;
+    let status = Command::new(program)
+        .args(parts)
+        .arg(wav)
+        .kill_on_drop(true)
+        .status()
+        .await
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !status.success() {
+        anyhow::bail!("{} exited with {}", program, status);
+    }
+    Ok(())
+}
+
+/// Speaks through a Home Assistant media_player with `tts.speak`
+pub struct HomeAssistantSpeaker {
+    name: String,
+    home_assistant: HomeAssistant,
+    media_player: String,
+    tts_entity: String,
+}
+
+impl HomeAssistantSpeaker {
+    pub fn new(
+        config: &AnnounceDeviceConfig,
+        home_assistant: &HomeAssistantConfig,
+    ) -> Result<Self> {
+        if config.media_player.is_empty() {
+            anyhow::bail!("Announce device {} needs a media_player", config.name);
+        }
+        Ok(Self {
+            name: config.name.clone(),
+            home_assistant: HomeAssistant::new(home_assistant),
+            media_player: config.media_player.clone(),
+            tts_entity: config.tts_entity.clone(),
+        })
+    }
+}
+
+#[async_trait]
+impl Speaker for HomeAssistantSpeaker {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn speak(&self, text: &str) -> Result<()> {
+        self.home_assistant
+            .call_service(
+                "tts",
+                "speak",
+                &json!({
+                    "entity_id": self.tts_entity,
+                    "media_player_entity_id": self.media_player,
+                    "message": text,
+                }),
+            )
+            .await
+    }
+}
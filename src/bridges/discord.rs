@@ -0,0 +1,242 @@
+//! Discord bridge: gateway websocket for incoming messages, REST for replies
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, info};
+
+use super::{ChatBridge, IncomingMessage};
+use crate::config::DiscordBridgeConfig;
+
+const GATEWAY_URL: &str = "wss://gateway.discord.gg/?v=10&encoding=json";
+const API_URL: &str = "https://discord.com/api/v10";
+
+/// GUILD_MESSAGES | DIRECT_MESSAGES | MESSAGE_CONTENT
+const INTENTS: u64 = (1 << 9) | (1 << 12) | (1 << 15);
+
+const MAX_MESSAGE_LEN: usize = 2000;
+
+pub struct DiscordBridge {
+    token: String,
+    mentions: bool,
+    client: reqwest::Client,
+    /// The bot's own user id, learned from READY
+    bot_id: Mutex<Option<String>>,
+}
+
+/// A MESSAGE_CREATE the bot should answer, with its mention stripped.
+/// Guild messages are only answered when `mentions` is set and the bot is
+/// mentioned; DMs always are.
+fn parse_message_create(data: &Value, bot_id: &str, mentions: bool) -> Option<IncomingMessage> {
+    let author = &data["author"];
+    if author["bot"].as_bool().unwrap_or(false) || author["id"] == bot_id {
+        return None;
+    }
+    let mut text = data["content"].as_str()?.to_string();
+
+    if data["guild_id"].is_string() {
+        let mention = format!("<@{}>", bot_id);
+        let nick_mention = format!("<@!{}>", bot_id);
+        if !mentions || !(text.contains(&mention) || text.contains(&nick_mention)) {
+            return None;
+        }
+        text = text.replace(&mention, "").replace(&nick_mention, "");
+    }
+
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    Some(IncomingMessage {
+        chat_id: data["channel_id"].as_str()?.to_string(),
+        user_id: author["id"].as_str()?.to_string(),
+        text: text.to_string(),
+    })
+}
+
+impl DiscordBridge {
+    pub fn new(config: &DiscordBridgeConfig) -> Self {
+        Self {
+            token: config.token.clone(),
+            mentions: config.mentions,
+            client: reqwest::Client::new(),
+            bot_id: Mutex::new(None),
+        }
+    }
+
+    fn bot_id(&self) -> String {
+        self.bot_id.lock().unwrap().clone().unwrap_or_default()
+    }
+
+    async fn request(&self, request: reqwest::RequestBuilder) -> Result<Value> {
+        let response = request
+            .header("Authorization", format!("Bot {}", self.token))
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("Discord {}: {}", status, body);
+        }
+        Ok(response.json().await?)
+    }
+}
+
+#[async_trait]
+impl ChatBridge for DiscordBridge {
+    fn name(&self) -> &str {
+        "discord"
+    }
+
+    fn max_message_len(&self) -> usize {
+        MAX_MESSAGE_LEN
+    }
+
+    async fn listen(&self, incoming: mpsc::Sender<IncomingMessage>) -> Result<()> {
+        let (socket, _) = tokio_tungstenite::connect_async(GATEWAY_URL).await?;
+        let (mut write, mut read) = socket.split();
+
+        // Hello (op 10) carries the heartbeat interval
+        let hello = loop {
+            match read.next().await {
+                Some(Ok(Message::Text(text))) => break serde_json::from_str::<Value>(&text)?,
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => return Err(e.into()),
+                None => anyhow::bail!("Discord gateway closed before Hello"),
+            }
+        };
+        let interval_ms = hello["d"]["heartbeat_interval"].as_u64().unwrap_or(41_250);
+        let mut heartbeat = tokio::time::interval(Duration::from_millis(interval_ms));
+        heartbeat.tick().await;
+
+        let identify = json!({
+            "op": 2,
+            "d": {
+                "token": self.token,
+                "intents": INTENTS,
+                "properties": { "os": std::env::consts::OS, "browser": "homegpt", "device": "homegpt" },
+            }
+        });
+        write.send(Message::text(identify.to_string())).await?;
+
+        let mut seq = Value::Null;
+        loop {
+            tokio::select! {
+                _ = heartbeat.tick() => {
+                    let beat = json!({ "op": 1, "d": seq });
+                    write.send(Message::text(beat.to_string())).await?;
+                }
+                frame = read.next() => {
+                    let text = match frame {
+                        Some(Ok(Message::Text(text))) => text,
+                        Some(Ok(Message::Close(close))) => {
+                            anyhow::bail!("Discord gateway closed: {:?}", close)
+                        }
+                        Some(Ok(_)) => continue,
+                        Some(Err(e)) => return Err(e.into()),
+                        None => anyhow::bail!("Discord gateway closed"),
+                    };
+                    let payload: Value = serde_json::from_str(&text)?;
+                    if !payload["s"].is_null() {
+                        seq = payload["s"].clone();
+                    }
+                    match payload["op"].as_u64() {
+                        // Heartbeat requested
+                        Some(1) => {
+                            let beat = json!({ "op": 1, "d": seq });
+                            write.send(Message::text(beat.to_string())).await?;
+                        }
+                        // Reconnect / invalid session: start over
+                        Some(7) | Some(9) => anyhow::bail!("Discord asked to reconnect"),
+                        Some(0) => match payload["t"].as_str() {
+                            Some("READY") => {
+                                let user = &payload["d"]["user"];
+                                info!("Discord bridge connected as {}", user["username"]);
+                                *self.bot_id.lock().unwrap() =
+                                    user["id"].as_str().map(String::from);
+                            }
+                            Some("MESSAGE_CREATE") => {
+                                if let Some(message) =
+                                    parse_message_create(&payload["d"], &self.bot_id(), self.mentions)
+                                {
+                                    incoming.send(message).await?;
+                                }
+                            }
+                            other => debug!("Discord event {:?}", other),
+                        },
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
+    async fn send(&self, chat_id: &str, text: &str) -> Result<String> {
+        let url = format!("{}/channels/{}/messages", API_URL, chat_id);
+        let response = self
+            .request(self.client.post(url).json(&json!({ "content": text })))
+            .await?;
+        Ok(response["id"].as_str().unwrap_or_default().to_string())
+    }
+
+    async fn edit(&self, chat_id: &str, message_id: &str, text: &str) -> Result<()> {
+        let url = format!("{}/channels/{}/messages/{}", API_URL, chat_id, message_id);
+        self.request(self.client.patch(url).json(&json!({ "content": text })))
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(content: &str, guild: bool, author_id: &str) -> Value {
+        let mut data = json!({
+            "channel_id": "c1",
+            "content": content,
+            "author": { "id": author_id, "username": "ella" },
+        });
+        if guild {
+            data["guild_id"] = json!("g1");
+        }
+        data
+    }
+
+    #[test]
+    fn test_parse_message_create() {
+        let dm = parse_message_create(&message("Is it raining?", false, "u1"), "b1", true);
+        assert_eq!(
+            dm,
+            Some(IncomingMessage {
+                chat_id: "c1".to_string(),
+                user_id: "u1".to_string(),
+                text: "Is it raining?".to_string(),
+            })
+        );
+
+        // Guild messages need a mention, which is stripped
+        assert_eq!(
+            parse_message_create(&message("Is it raining?", true, "u1"), "b1", true),
+            None
+        );
+        let mentioned =
+            parse_message_create(&message("<@b1> Is it raining?", true, "u1"), "b1", true);
+        assert_eq!(mentioned.unwrap().text, "Is it raining?");
+        assert_eq!(
+            parse_message_create(&message("<@b1> hi", true, "u1"), "b1", false),
+            None
+        );
+
+        // Never answer itself
+        assert_eq!(
+            parse_message_create(&message("hi", false, "b1"), "b1", true),
+            None
+        );
+    }
+}
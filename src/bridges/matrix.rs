@@ -0,0 +1,297 @@
+//! Matrix bridge over the client-server API (`/sync` long polling)
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::Url;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use super::{is_allowed, ChatBridge, IncomingMessage};
+use crate::config::MatrixBridgeConfig;
+
+/// How long the homeserver may hold a `/sync` request open
+const SYNC_TIMEOUT_MS: u64 = 30_000;
+
+/// Matrix has no hard limit; keep replies readable
+const MAX_MESSAGE_LEN: usize = 16_000;
+
+pub struct MatrixBridge {
+    homeserver: Url,
+    user_id: String,
+    access_token: String,
+    allowed_users: Vec<String>,
+    client: reqwest::Client,
+    /// Transaction ids for sends, unique per bridge start
+    txn: AtomicU64,
+}
+
+/// What one `/sync` response asks of the bridge
+#[derive(Debug, Default, PartialEq)]
+struct SyncUpdate {
+    next_batch: String,
+    messages: Vec<IncomingMessage>,
+    /// (room id, inviter)
+    invites: Vec<(String, String)>,
+}
+
+/// Pull new text messages and invites out of a `/sync` response, ignoring
+/// the bot's own messages and edits
+fn parse_sync(body: &Value, own_user_id: &str) -> SyncUpdate {
+    let mut update = SyncUpdate {
+        next_batch: body["next_batch"].as_str().unwrap_or_default().to_string(),
+        ..Default::default()
+    };
+
+    if let Some(rooms) = body["rooms"]["join"].as_object() {
+        for (room_id, room) in rooms {
+            let events = room["timeline"]["events"].as_array();
+            for event in events.into_iter().flatten() {
+                let content = &event["content"];
+                let sender = event["sender"].as_str().unwrap_or_default();
+                if event["type"] != "m.room.message"
+                    || content["msgtype"] != "m.text"
+                    || content["m.relates_to"]["rel_type"] == "m.replace"
+                    || sender == own_user_id
+                {
+                    continue;
+                }
+                if let Some(text) = content["body"].as_str() {
+                    update.messages.push(IncomingMessage {
+                        chat_id: room_id.clone(),
+                        user_id: sender.to_string(),
+                        text: text.to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    if let Some(rooms) = body["rooms"]["invite"].as_object() {
+        for (room_id, room) in rooms {
+            let events = room["invite_state"]["events"].as_array();
+            let inviter = events.into_iter().flatten().find(|event| {
+                event["type"] == "m.room.member"
+                    && event["state_key"] == own_user_id
+                    && event["content"]["membership"] == "invite"
+            });
+            if let Some(sender) = inviter.and_then(|event| event["sender"].as_str()) {
+                update.invites.push((room_id.clone(), sender.to_string()));
+            }
+        }
+    }
+
+    update
+}
+
+impl MatrixBridge {
+    pub fn new(config: &MatrixBridgeConfig) -> Result<Self> {
+        let homeserver = Url::parse(&config.homeserver)
+            .with_context(|| format!("Invalid bridges.matrix.homeserver: {}", config.homeserver))?;
+        if !matches!(homeserver.scheme(), "http" | "https") || homeserver.cannot_be_a_base() {
+            anyhow::bail!(
+                "Invalid bridges.matrix.homeserver: {} (expected an http(s) URL like \"https://matrix.example.org\")",
+                config.homeserver
+            );
+        }
+        Ok(Self {
+            homeserver,
+            user_id: config.user_id.clone(),
+            access_token: config.access_token.clone(),
+            allowed_users: config.allowed_users.clone(),
+            client: reqwest::Client::new(),
+            txn: AtomicU64::new(chrono::Utc::now().timestamp_millis() as u64),
+        })
+    }
+
+    /// `/_matrix/client/v3/<segments>`, with each segment escaped
+    fn endpoint(&self, segments: &[&str]) -> Url {
+        let mut url = self.homeserver.clone();
+        url.path_segments_mut()
+            .expect("homeserver is checked to be an http(s) URL in new()")
+            .pop_if_empty()
+            .extend(["_matrix", "client", "v3"])
+            .extend(segments);
+        url
+    }
+
+    async fn request(&self, request: reqwest::RequestBuilder) -> Result<Value> {
+        let response = request.bearer_auth(&self.access_token).send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("Matrix {}: {}", status, body);
+        }
+        Ok(response.json().await?)
+    }
+
+    async fn sync(&self, since: Option<&str>) -> Result<Value> {
+        let mut url = self.endpoint(&["sync"]);
+        {
+            let mut query = url.query_pairs_mut();
+            query.append_pair("timeout", &SYNC_TIMEOUT_MS.to_string());
+            if let Some(since) = since {
+                query.append_pair("since", since);
+            }
+        }
+        self.request(self.client.get(url)).await
+    }
+
+    async fn send_event(&self, room_id: &str, content: Value) -> Result<String> {
+        let txn = self.txn.fetch_add(1, Ordering::Relaxed).to_string();
+        let url = self.endpoint(&["rooms", room_id, "send", "m.room.message", &txn]);
+        let response = self.request(self.client.put(url).json(&content)).await?;
+        Ok(response["event_id"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string())
+    }
+}
+
+#[async_trait]
+impl ChatBridge for MatrixBridge {
+    fn name(&self) -> &str {
+        "matrix"
+    }
+
+    fn max_message_len(&self) -> usize {
+        MAX_MESSAGE_LEN
+    }
+
+    async fn listen(&self, incoming: mpsc::Sender<IncomingMessage>) -> Result<()> {
+        // Skip history: only answer messages sent after the bridge started
+        let initial = self.sync(None).await?;
+        let mut since = parse_sync(&initial, &self.user_id).next_batch;
+        info!("Matrix bridge connected as {}", self.user_id);
+
+        loop {
+            let update = parse_sync(&self.sync(Some(&since)).await?, &self.user_id);
+            for (room_id, inviter) in update.invites {
+                if !is_allowed(&self.allowed_users, &inviter) {
+                    info!("Ignoring Matrix invite to {} from {}", room_id, inviter);
+                    continue;
+                }
+                let url = self.endpoint(&["join", &room_id]);
+                match self.request(self.client.post(url).json(&json!({}))).await {
+                    Ok(_) => info!("Joined Matrix room {} (invited by {})", room_id, inviter),
+                    Err(e) => warn!("Failed to join Matrix room {}: {}", room_id, e),
+                }
+            }
+            for message in update.messages {
+                incoming.send(message).await?;
+            }
+            if !update.next_batch.is_empty() {
+                since = update.next_batch;
+            }
+        }
+    }
+
+    async fn send(&self, chat_id: &str, text: &str) -> Result<String> {
+        self.send_event(chat_id, json!({ "msgtype": "m.text", "body": text }))
+            .await
+    }
+
+    async fn edit(&self, chat_id: &str, message_id: &str, text: &str) -> Result<()> {
+        let content = json!({
+            "msgtype": "m.text",
+            "body": format!("* {}", text),
+            "m.new_content": { "msgtype": "m.text", "body": text },
+            "m.relates_to": { "rel_type": "m.replace", "event_id": message_id },
+        });
+        self.send_event(chat_id, content).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sync() {
+        let body = json!({
+            "next_batch": "s42",
+            "rooms": {
+                "join": {
+                    "!kitchen:example.org": {
+                        "timeline": { "events": [
+                            {
+                                "type": "m.room.message",
+                                "sender": "@dad:example.org",
+                                "content": { "msgtype": "m.text", "body": "What's for dinner?" }
+                            },
+                            {
+                                "type": "m.room.message",
+                                "sender": "@homegpt:example.org",
+                                "content": { "msgtype": "m.text", "body": "Tacos" }
+                            },
+                            {
+                                "type": "m.room.message",
+                                "sender": "@dad:example.org",
+                                "content": {
+                                    "msgtype": "m.text",
+                                    "body": "* What's for lunch?",
+                                    "m.relates_to": { "rel_type": "m.replace", "event_id": "$1" }
+                                }
+                            },
+                            {
+                                "type": "m.reaction",
+                                "sender": "@dad:example.org",
+                                "content": {}
+                            }
+                        ]}
+                    }
+                },
+                "invite": {
+                    "!family:example.org": {
+                        "invite_state": { "events": [
+                            {
+                                "type": "m.room.member",
+                                "sender": "@mom:example.org",
+                                "state_key": "@homegpt:example.org",
+                                "content": { "membership": "invite" }
+                            }
+                        ]}
+                    }
+                }
+            }
+        });
+
+        let update = parse_sync(&body, "@homegpt:example.org");
+        assert_eq!(update.next_batch, "s42");
+        assert_eq!(
+            update.messages,
+            vec![IncomingMessage {
+                chat_id: "!kitchen:example.org".to_string(),
+                user_id: "@dad:example.org".to_string(),
+                text: "What's for dinner?".to_string(),
+            }]
+        );
+        assert_eq!(
+            update.invites,
+            vec![(
+                "!family:example.org".to_string(),
+                "@mom:example.org".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_homeserver_must_be_http() {
+        let config = |homeserver: &str| MatrixBridgeConfig {
+            homeserver: homeserver.to_string(),
+            user_id: "@homegpt:example.org".to_string(),
+            access_token: "token".to_string(),
+            allowed_users: Vec::new(),
+        };
+        assert!(MatrixBridge::new(&config("matrix.example.org:8448")).is_err());
+        assert!(MatrixBridge::new(&config("ftp://matrix.example.org")).is_err());
+
+        let bridge = MatrixBridge::new(&config("https://matrix.example.org:8448")).unwrap();
+        assert_eq!(
+            bridge.endpoint(&["sync"]).as_str(),
+            "https://matrix.example.org:8448/_matrix/client/v3/sync"
+        );
+    }
+}
@@ -0,0 +1,455 @@
+//! Chat bridges: talk to the assistant from Matrix or Discord
+//!
+//! Each platform implements [`ChatBridge`] (receive messages, send and edit
+//! replies). [`BridgeRunner`] does the rest the same way for every
+//! platform: only `allowed_users` get answers, each chat (room, channel or
+//! DM) keeps its own agent session across restarts, and the reply is one
+//! message edited as the turn progresses ("Using memory_search…", then the
+//! answer) instead of a burst of messages.
+
+mod discord;
+mod matrix;
+
+pub use discord::DiscordBridge;
+pub use matrix::MatrixBridge;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::StreamExt;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::agent::{get_state_dir, Agent, AgentConfig, Approver, RemoteApprover, StreamEvent};
+use crate::concurrency::{TurnGate, WorkspaceLock};
+use crate::config::Config;
+use crate::memory::MemoryManager;
+
+/// Chat → session id map (relative to the state dir)
+const SESSIONS_FILE: &str = "bridge-sessions.json";
+
+/// Held while updating the sessions file; every bridge's runner writes it
+static SESSIONS_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// Placeholder sent while the agent works
+const THINKING: &str = "…";
+
+/// Minimum time between edits of a streaming reply (platform rate limits)
+const EDIT_INTERVAL: Duration = Duration::from_millis(1500);
+
+/// Wait before reconnecting a bridge that dropped
+const RECONNECT_DELAY: Duration = Duration::from_secs(15);
+
+/// Messages that start a fresh session for the chat
+const RESET_COMMANDS: &[&str] = &["/new", "!new"];
+
+/// A message from a chat platform
+#[derive(Debug, Clone, PartialEq)]
+pub struct IncomingMessage {
+    /// Room, channel or DM the reply goes to
+    pub chat_id: String,
+    pub user_id: String,
+    pub text: String,
+}
+
+/// A chat platform the assistant can be reached on
+#[async_trait]
+pub trait ChatBridge: Send + Sync {
+    /// Platform name, e.g. "matrix"
+    fn name(&self) -> &str;
+
+    /// Longest message the platform accepts
+    fn max_message_len(&self) -> usize;
+
+    /// Receive messages into `incoming` until the connection fails
+    async fn listen(&self, incoming: mpsc::Sender<IncomingMessage>) -> Result<()>;
+
+    /// Send a message, returning its id for later edits
+    async fn send(&self, chat_id: &str, text: &str) -> Result<String>;
+
+    /// Replace the text of a message sent earlier
+    async fn edit(&self, chat_id: &str, message_id: &str, text: &str) -> Result<()>;
+}
+
+/// Whether a platform user id is in the allowlist
+pub fn is_allowed(allowed_users: &[String], user_id: &str) -> bool {
+    allowed_users
+        .iter()
+        .any(|allowed| allowed.eq_ignore_ascii_case(user_id))
+}
+
+/// Split a reply into messages of at most `max` characters, preferring
+/// line breaks
+pub fn split_message(text: &str, max: usize) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    for line in text.split_inclusive('\n') {
+        if current.chars().count() + line.chars().count() > max && !current.is_empty() {
+            parts.push(std::mem::take(&mut current));
+        }
+        if line.chars().count() > max {
+            let chars: Vec<char> = line.chars().collect();
+            for chunk in chars.chunks(max) {
+                parts.push(chunk.iter().collect());
+            }
+        } else {
+            current.push_str(line);
+        }
+    }
+    if !current.is_empty() {
+        parts.push(current);
+    }
+    parts
+        .into_iter()
+        .map(|p| p.trim_end().to_string())
+        .filter(|p| !p.is_empty())
+        .collect()
+}
+
+/// One reply message, edited as the turn streams in
+struct StreamingReply<'a> {
+    bridge: &'a dyn ChatBridge,
+    chat_id: String,
+    message_id: String,
+    shown: String,
+    last_edit: Instant,
+}
+
+impl<'a> StreamingReply<'a> {
+    async fn start(bridge: &'a dyn ChatBridge, chat_id: &str) -> Result<Self> {
+        let message_id = bridge.send(chat_id, THINKING).await?;
+        Ok(Self {
+            bridge,
+            chat_id: chat_id.to_string(),
+            message_id,
+            shown: THINKING.to_string(),
+            last_edit: Instant::now(),
+        })
+    }
+
+    /// Show interim text, at most once per [`EDIT_INTERVAL`]
+    async fn update(&mut self, text: &str) {
+        if text == self.shown || self.last_edit.elapsed() < EDIT_INTERVAL {
+            return;
+        }
+        let text: String = text.chars().take(self.bridge.max_message_len()).collect();
+        match self
+            .bridge
+            .edit(&self.chat_id, &self.message_id, &text)
+            .await
+        {
+            Ok(()) => {
+                self.shown = text;
+                self.last_edit = Instant::now();
+            }
+            Err(e) => debug!("{} edit failed: {}", self.bridge.name(), e),
+        }
+    }
+
+    /// Show the final text, continuing in new messages if it's too long
+    async fn finish(self, text: &str) -> Result<()> {
+        let mut parts = split_message(text, self.bridge.max_message_len()).into_iter();
+        let first = parts.next().unwrap_or_else(|| "(no reply)".to_string());
+        self.bridge
+            .edit(&self.chat_id, &self.message_id, &first)
+            .await?;
+        for part in parts {
+            self.bridge.send(&self.chat_id, &part).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Answers the messages from one bridge
+pub struct BridgeRunner {
+    bridge: Arc<dyn ChatBridge>,
+    config: Config,
+    memory: MemoryManager,
+    allowed_users: Vec<String>,
+    turn_gate: TurnGate,
+    workspace_lock: WorkspaceLock,
+    sessions_path: PathBuf,
+    /// Chat key → agent, created on a chat's first message
+    agents: HashMap<String, Agent>,
+    /// Asks on the phone before tool calls that need approval, or refuses
+    /// them without `approval.remote`
+    approver: Arc<dyn Approver>,
+}
+
+impl BridgeRunner {
+    pub fn new(
+        bridge: Arc<dyn ChatBridge>,
+        config: &Config,
+        allowed_users: Vec<String>,
+        turn_gate: TurnGate,
+        state_dir: &Path,
+    ) -> Result<Self> {
+        if allowed_users.is_empty() {
+            warn!(
+                "{} bridge has no allowed_users; all messages will be ignored",
+                bridge.name()
+            );
+        }
+        Ok(Self {
+            memory: MemoryManager::new_with_full_config(&config.memory, Some(config), "main")?,
+            bridge,
+            config: config.clone(),
+            allowed_users,
+            turn_gate,
+            workspace_lock: WorkspaceLock::new()?,
+            sessions_path: state_dir.join(SESSIONS_FILE),
+            agents: HashMap::new(),
+            approver: RemoteApprover::shared(config)?,
+        })
+    }
+
+    fn load_sessions(&self) -> BTreeMap<String, String> {
+        fs::read_to_string(&self.sessions_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save_session_id(&self, key: &str, session_id: Option<String>) -> Result<()> {
+        let _lock = SESSIONS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut sessions = self.load_sessions();
+        match session_id {
+            Some(id) => sessions.insert(key.to_string(), id),
+            None => sessions.remove(key),
+        };
+        fs::write(
+            &self.sessions_path,
+            serde_json::to_string_pretty(&sessions)?,
+        )?;
+        Ok(())
+    }
+
+    /// The chat's agent, resuming its saved session after a restart
+    async fn agent_for(&mut self, key: &str) -> Result<&mut Agent> {
+        if !self.agents.contains_key(key) {
+            let agent_config = AgentConfig {
+                model: self.config.agent.default_model.clone(),
+                context_window: self.config.agent.context_window,
+                reserve_tokens: self.config.agent.reserve_tokens,
+            };
+            let mut agent = Agent::new(agent_config, &self.config, self.memory.clone()).await?;
+            agent.set_approver(Arc::clone(&self.approver));
+            let resumed = match self.load_sessions().get(key) {
+                Some(id) => agent.resume_session(id).await.is_ok(),
+                None => false,
+            };
+            if !resumed {
+                agent.new_session().await?;
+                self.save_session_id(key, Some(agent.session_status().id))?;
+            }
+            self.agents.insert(key.to_string(), agent);
+        }
+        Ok(self.agents.get_mut(key).expect("agent inserted above"))
+    }
+
+    /// Receive and answer messages until the task is aborted
+    pub async fn run(mut self) {
+        let (tx, mut rx) = mpsc::channel(32);
+        let bridge = Arc::clone(&self.bridge);
+        let listener = tokio::spawn(async move {
+            loop {
+                if let Err(e) = bridge.listen(tx.clone()).await {
+                    warn!("{} bridge disconnected: {}", bridge.name(), e);
+                }
+                if tx.is_closed() {
+                    break;
+                }
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        });
+
+        while let Some(message) = rx.recv().await {
+            if !is_allowed(&self.allowed_users, &message.user_id) {
+                info!(
+                    "Ignoring {} message from {} (not in allowed_users)",
+                    self.bridge.name(),
+                    message.user_id
+                );
+                continue;
+            }
+            if let Err(e) = self.handle(&message).await {
+                warn!("{} reply failed: {}", self.bridge.name(), e);
+            }
+        }
+        listener.abort();
+    }
+
+    async fn handle(&mut self, message: &IncomingMessage) -> Result<()> {
+        let key = format!("{}:{}", self.bridge.name(), message.chat_id);
+        let bridge = Arc::clone(&self.bridge);
+
+        if RESET_COMMANDS.contains(&message.text.trim()) {
+            self.agents.remove(&key);
+            self.save_session_id(&key, None)?;
+            bridge
+                .send(&message.chat_id, "Started a new conversation.")
+                .await?;
+            return Ok(());
+        }
+
+        let mut reply = StreamingReply::start(bridge.as_ref(), &message.chat_id).await?;
+
+        // Same turn limit as the HTTP server
+        let _gate_permit = self.turn_gate.acquire().await;
+        let _ws_guard = self.turn_gate.lock_workspace(&self.workspace_lock).await?;
+
+        let agent = self.agent_for(&key).await?;
+        let result = stream_turn(agent, &message.text, &mut reply).await;
+        if let Err(e) = agent.save_session().await {
+            warn!("Failed to save bridge session {}: {}", key, e);
+        }
+        match result {
+            Ok(text) => reply.finish(&text).await,
+            Err(e) => {
+                reply
+                    .finish(&format!("Sorry, something went wrong: {}", e))
+                    .await
+            }
+        }
+    }
+}
+
+/// Run one agent turn, showing tool use in the reply as it happens
+async fn stream_turn(
+    agent: &mut Agent,
+    text: &str,
+    reply: &mut StreamingReply<'_>,
+) -> Result<String> {
+    let stream = agent.chat_stream_with_tools(text, Vec::new()).await?;
+    let mut stream = std::pin::pin!(stream);
+    let mut response = String::new();
+    while let Some(event) = stream.next().await {
+        match event? {
+            StreamEvent::Content(chunk) => {
+                response.push_str(&chunk);
+                reply.update(&response).await;
+            }
+            StreamEvent::ToolCallStart { name, .. } => {
+                let status = format!("{}\n\n_Using {}…_", response, name);
+                reply.update(status.trim_start()).await;
+            }
+            StreamEvent::ToolCallEnd { .. } => {}
+            StreamEvent::Done => break,
+        }
+    }
+    Ok(response)
+}
+
+/// Start the bridges in `config.bridges`, one task each
+pub fn spawn_bridges(config: &Config, turn_gate: &TurnGate) -> Result<Vec<JoinHandle<()>>> {
+    let state_dir = get_state_dir()?;
+    let mut bridges: Vec<(Arc<dyn ChatBridge>, Vec<String>)> = Vec::new();
+    if let Some(ref matrix) = config.bridges.matrix {
+        bridges.push((
+            Arc::new(MatrixBridge::new(matrix)?),
+            matrix.allowed_users.clone(),
+        ));
+    }
+    if let Some(ref discord) = config.bridges.discord {
+        bridges.push((
+            Arc::new(DiscordBridge::new(discord)),
+            discord.allowed_users.clone(),
+        ));
+    }
+
+    let mut handles = Vec::new();
+    for (bridge, allowed_users) in bridges {
+        info!("Starting {} bridge", bridge.name());
+        let runner =
+            BridgeRunner::new(bridge, config, allowed_users, turn_gate.clone(), &state_dir)?;
+        handles.push(tokio::spawn(runner.run()));
+    }
+    Ok(handles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_split_message() {
+        assert_eq!(split_message("short", 10), vec!["short"]);
+        assert_eq!(
+            split_message("line one\nline two\nthree", 12),
+            vec!["line one", "line two", "three"]
+        );
+        assert_eq!(split_message("abcdefghij", 4), vec!["abcd", "efgh", "ij"]);
+        assert!(split_message("", 10).is_empty());
+    }
+
+    #[test]
+    fn test_allowlist() {
+        let allowed = vec!["@dad:example.org".to_string()];
+        assert!(is_allowed(&allowed, "@Dad:example.org"));
+        assert!(!is_allowed(&allowed, "@stranger:example.org"));
+        assert!(!is_allowed(&[], "@dad:example.org"));
+    }
+
+    /// Records sends and edits
+    #[derive(Default)]
+    struct FakeBridge {
+        log: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl ChatBridge for FakeBridge {
+        fn name(&self) -> &str {
+            "fake"
+        }
+
+        fn max_message_len(&self) -> usize {
+            10
+        }
+
+        async fn listen(&self, _incoming: mpsc::Sender<IncomingMessage>) -> Result<()> {
+            Ok(())
+        }
+
+        async fn send(&self, chat_id: &str, text: &str) -> Result<String> {
+            let mut log = self.log.lock().unwrap();
+            log.push(format!("send {}: {}", chat_id, text));
+            Ok(format!("m{}", log.len()))
+        }
+
+        async fn edit(&self, _chat_id: &str, message_id: &str, text: &str) -> Result<()> {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("edit {}: {}", message_id, text));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_streaming_reply_throttles_and_splits() {
+        let bridge = FakeBridge::default();
+        let mut reply = StreamingReply::start(&bridge, "room").await.unwrap();
+
+        // Too soon after the placeholder
+        reply.update("partial").await;
+        reply.last_edit -= EDIT_INTERVAL;
+        reply.update("partial").await;
+        reply.finish("first line\nsecond").await.unwrap();
+
+        assert_eq!(
+            *bridge.log.lock().unwrap(),
+            vec![
+                "send room: …",
+                "edit m1: partial",
+                "edit m1: first line",
+                "send room: second",
+            ]
+        );
+    }
+}
@@ -0,0 +1,427 @@
+//! Business connectors for the heartbeat
+//!
+//! A connector fetches a typed status snapshot (pending reviews, flagged
+//! content, moderation queues) from a business backend. The heartbeat runs
+//! each configured connector once its interval has passed, writes
+//! `memory/business/<name>-status.md`, and compares the snapshot with the
+//! previous run so only changes (a newly flagged review, a growing queue)
+//! become alerts.
+
+mod pocketbase;
+
+pub use pocketbase::PocketBaseConnector;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+use crate::config::{parse_duration, BusinessConnectorConfig, Config};
+use crate::locale;
+
+/// Status files written to the workspace (relative to workspace)
+const BUSINESS_DIR: &str = "memory/business";
+
+/// A backend the heartbeat can check for things that need attention
+#[async_trait]
+pub trait BusinessConnector: Send + Sync {
+    /// Name used in alerts and file names, e.g. "ergotools"
+    fn name(&self) -> &str;
+
+    /// Fetch the current status
+    async fn fetch_status(&self) -> Result<BusinessStatus>;
+}
+
+/// Flagged content that needs a human
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FlaggedItem {
+    pub id: String,
+    pub summary: String,
+}
+
+/// Snapshot of a business backend
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BusinessStatus {
+    pub pending_reviews: u64,
+    pub flagged: Vec<FlaggedItem>,
+    pub pending_products: u64,
+    pub stale_announcements: u64,
+    pub upcoming_events: u64,
+    pub new_submissions: u64,
+}
+
+impl BusinessStatus {
+    /// Alerts for what changed since `previous` (everything that needs
+    /// attention on the first run)
+    pub fn alerts(&self, previous: Option<&BusinessStatus>, review_threshold: u64) -> Vec<String> {
+        let before = previous.cloned().unwrap_or_default();
+        let mut alerts = Vec::new();
+
+        let new_flags: Vec<&FlaggedItem> = self
+            .flagged
+            .iter()
+            .filter(|item| !before.flagged.iter().any(|b| b.id == item.id))
+            .collect();
+        if !new_flags.is_empty() {
+            let summaries: Vec<&str> = new_flags.iter().map(|i| i.summary.as_str()).collect();
+            alerts.push(format!(
+                "{} newly flagged review(s): {}",
+                new_flags.len(),
+                summaries.join("; ")
+            ));
+        }
+
+        if self.pending_reviews > review_threshold
+            && (previous.is_none() || self.pending_reviews > before.pending_reviews)
+        {
+            alerts.push(format!(
+                "{} reviews pending (threshold {})",
+                self.pending_reviews, review_threshold
+            ));
+        }
+
+        let queues = [
+            (
+                self.pending_products,
+                before.pending_products,
+                "products pending moderation",
+            ),
+            (
+                self.stale_announcements,
+                before.stale_announcements,
+                "expired announcements still active",
+            ),
+            (
+                self.new_submissions,
+                before.new_submissions,
+                "new product submissions",
+            ),
+        ];
+        for (now, was, label) in queues {
+            if now > was {
+                match previous {
+                    Some(_) => alerts.push(format!("{} {} (was {})", now, label, was)),
+                    None => alerts.push(format!("{} {}", now, label)),
+                }
+            }
+        }
+        alerts
+    }
+
+    /// Markdown written to `memory/business/<name>-status.md`
+    pub fn to_markdown(&self, title: &str, checked_at: DateTime<Utc>) -> String {
+        let count = |n: u64, label: &str, none: &str| {
+            if n > 0 {
+                format!("{} {}", n, label)
+            } else {
+                none.to_string()
+            }
+        };
+        let flagged = if self.flagged.is_empty() {
+            "None".to_string()
+        } else {
+            self.flagged
+                .iter()
+                .map(|item| format!("- **{}** ({})", item.summary, item.id))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        format!(
+            "---\ncategory: business\nlast_verified: \"{}\"\nsources: [heartbeat]\n---\n\
+             # {} Business Status\n\nLast updated: {}\n\n\
+             ## Pending Reviews\n{}\n\n## Flagged Content\n{}\n\n\
+             ## Pending Products\n{}\n\n## Stale Announcements\n{}\n\n\
+             ## Upcoming Events\n{}\n\n## Product Submissions\n{}\n",
+            locale::home_time(&checked_at).format("%Y-%m-%d"),
+            title,
+            locale::home_time(&checked_at).to_rfc3339(),
+            count(self.pending_reviews, "pending", "None"),
+            flagged,
+            count(self.pending_products, "awaiting moderation", "None"),
+            count(self.stale_announcements, "expired but still active", "None"),
+            count(self.upcoming_events, "upcoming", "None scheduled"),
+            count(self.new_submissions, "new submissions", "None"),
+        )
+    }
+}
+
+/// Last run of a connector, saved between heartbeats
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedState {
+    checked_at: DateTime<Utc>,
+    status: BusinessStatus,
+}
+
+/// A configured connector with its schedule
+struct Monitored {
+    connector: Box<dyn BusinessConnector>,
+    interval: chrono::Duration,
+    review_threshold: u64,
+}
+
+/// Runs the configured connectors from the heartbeat
+pub struct BusinessMonitor {
+    connectors: Vec<Monitored>,
+    workspace: PathBuf,
+    state_dir: PathBuf,
+}
+
+impl BusinessMonitor {
+    /// Monitor for `business.connectors`, or `None` if there are none
+    pub fn from_config(config: &Config, state_dir: &Path) -> Result<Option<Self>> {
+        let mut monitor = Self::new(&config.workspace_path(), &state_dir.join("business"));
+        for connector in &config.business.connectors {
+            let boxed: Box<dyn BusinessConnector> = match connector.kind.as_str() {
+                "pocketbase" => Box::new(PocketBaseConnector::new(connector)),
+                other => anyhow::bail!(
+                    "Unknown business connector kind '{}' for {}: expected pocketbase",
+                    other,
+                    connector.name
+                ),
+            };
+            monitor.add(boxed, connector)?;
+        }
+        Ok((!monitor.connectors.is_empty()).then_some(monitor))
+    }
+
+    pub fn new(workspace: &Path, state_dir: &Path) -> Self {
+        Self {
+            connectors: Vec::new(),
+            workspace: workspace.to_path_buf(),
+            state_dir: state_dir.to_path_buf(),
+        }
+    }
+
+    /// Add a connector with the schedule and threshold from its config
+    pub fn add(
+        &mut self,
+        connector: Box<dyn BusinessConnector>,
+        config: &BusinessConnectorConfig,
+    ) -> Result<()> {
+        let interval = parse_duration(&config.interval).map_err(|e| {
+            anyhow::anyhow!(
+                "Invalid interval for business connector {}: {}",
+                config.name,
+                e
+            )
+        })?;
+        self.connectors.push(Monitored {
+            connector,
+            interval: chrono::Duration::from_std(interval)?,
+            review_threshold: config.review_threshold,
+        });
+        Ok(())
+    }
+
+    fn state_path(&self, name: &str) -> PathBuf {
+        self.state_dir.join(format!("{}.json", name))
+    }
+
+    /// Status file for a connector (relative to workspace)
+    pub fn status_path(name: &str) -> String {
+        format!("{}/{}-status.md", BUSINESS_DIR, name)
+    }
+
+    fn load_state(&self, name: &str) -> Option<SavedState> {
+        let content = fs::read_to_string(self.state_path(name)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Check every connector that is due, returning "name: alert" lines for
+    /// changes since its last run. Failing connectors are logged and skipped.
+    pub async fn check_due(&self, now: DateTime<Utc>) -> Vec<String> {
+        let mut alerts = Vec::new();
+        for monitored in &self.connectors {
+            let name = monitored.connector.name();
+            let previous = self.load_state(name);
+            if let Some(ref state) = previous {
+                if now - state.checked_at < monitored.interval {
+                    debug!("Business connector {} not due yet", name);
+                    continue;
+                }
+            }
+
+            let status = match monitored.connector.fetch_status().await {
+                Ok(status) => status,
+                Err(e) => {
+                    warn!("Business connector {} failed: {}", name, e);
+                    continue;
+                }
+            };
+            let changes = status.alerts(
+                previous.as_ref().map(|s| &s.status),
+                monitored.review_threshold,
+            );
+            if let Err(e) = self.record(name, &status, now, &changes) {
+                warn!("Failed to save {} status: {}", name, e);
+            }
+            alerts.extend(changes.into_iter().map(|a| format!("{}: {}", name, a)));
+        }
+        alerts
+    }
+
+    /// Save the run state, write the status file and log alerts to today's
+    /// daily log
+    fn record(
+        &self,
+        name: &str,
+        status: &BusinessStatus,
+        now: DateTime<Utc>,
+        alerts: &[String],
+    ) -> Result<()> {
+        fs::create_dir_all(&self.state_dir)?;
+        let state = SavedState {
+            checked_at: now,
+            status: status.clone(),
+        };
+        fs::write(self.state_path(name), serde_json::to_string_pretty(&state)?)?;
+
+        let status_path = self.workspace.join(Self::status_path(name));
+        if let Some(parent) = status_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&status_path, status.to_markdown(&title_case(name), now))?;
+
+        if !alerts.is_empty() {
+            let local = locale::home_time(&now);
+            let log_path = self
+                .workspace
+                .join("memory")
+                .join(format!("{}.md", local.format("%Y-%m-%d")));
+            let mut log = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(log_path)?;
+            let lines: Vec<String> = alerts.iter().map(|a| format!("- {}", a)).collect();
+            write!(
+                log,
+                "\n## {} Alert ({})\n{}\n",
+                title_case(name),
+                local.format("%H:%M"),
+                lines.join("\n")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// "ergotools" -> "Ergotools"
+fn title_case(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use tempfile::TempDir;
+
+    fn flagged(id: &str) -> FlaggedItem {
+        FlaggedItem {
+            id: id.to_string(),
+            summary: format!("review {}", id),
+        }
+    }
+
+    #[test]
+    fn test_alerts_only_on_changes() {
+        let status = BusinessStatus {
+            pending_reviews: 7,
+            flagged: vec![flagged("a")],
+            pending_products: 2,
+            ..Default::default()
+        };
+
+        // First run reports everything that needs attention
+        let first = status.alerts(None, 5);
+        assert_eq!(first.len(), 3);
+
+        // Nothing changed: no alerts
+        assert!(status.alerts(Some(&status), 5).is_empty());
+
+        // A new flag and a growing queue; a shrinking review count is quiet
+        let next = BusinessStatus {
+            pending_reviews: 6,
+            flagged: vec![flagged("a"), flagged("b")],
+            pending_products: 3,
+            ..Default::default()
+        };
+        assert_eq!(
+            next.alerts(Some(&status), 5),
+            vec![
+                "1 newly flagged review(s): review b".to_string(),
+                "3 products pending moderation (was 2)".to_string(),
+            ]
+        );
+    }
+
+    struct FakeConnector {
+        statuses: Mutex<Vec<BusinessStatus>>,
+    }
+
+    #[async_trait]
+    impl BusinessConnector for FakeConnector {
+        fn name(&self) -> &str {
+            "shop"
+        }
+
+        async fn fetch_status(&self) -> Result<BusinessStatus> {
+            Ok(self.statuses.lock().unwrap().remove(0))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_monitor_respects_interval_and_diffs() -> Result<()> {
+        let tmp = TempDir::new()?;
+        let workspace = tmp.path().join("workspace");
+        let mut monitor = BusinessMonitor::new(&workspace, &tmp.path().join("state"));
+        let statuses = vec![
+            BusinessStatus {
+                flagged: vec![flagged("a")],
+                ..Default::default()
+            },
+            BusinessStatus {
+                flagged: vec![flagged("a")],
+                ..Default::default()
+            },
+        ];
+        let config = BusinessConnectorConfig {
+            name: "shop".to_string(),
+            interval: "2h".to_string(),
+            ..Default::default()
+        };
+        monitor.add(
+            Box::new(FakeConnector {
+                statuses: Mutex::new(statuses),
+            }),
+            &config,
+        )?;
+
+        let start = Utc::now();
+        let alerts = monitor.check_due(start).await;
+        assert_eq!(alerts, vec!["shop: 1 newly flagged review(s): review a"]);
+        assert!(workspace.join("memory/business/shop-status.md").exists());
+
+        // Not due yet: the connector is not called
+        assert!(monitor
+            .check_due(start + chrono::Duration::minutes(30))
+            .await
+            .is_empty());
+
+        // Due again, same flag: no alert
+        assert!(monitor
+            .check_due(start + chrono::Duration::hours(3))
+            .await
+            .is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+//! PocketBase connector (the ErgoTools backend)
+//!
+//! Counts come from `totalItems` of filtered record lists, so only one
+//! record per query is transferred except for flagged reviews, which are
+//! listed for the alert text.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{BusinessConnector, BusinessStatus, FlaggedItem};
+use crate::config::BusinessConnectorConfig;
+
+/// Longest flagged review summary kept for alerts
+const SUMMARY_CHARS: usize = 80;
+
+#[derive(Debug, Deserialize)]
+struct RecordList {
+    #[serde(rename = "totalItems")]
+    total_items: u64,
+    #[serde(default)]
+    items: Vec<Value>,
+}
+
+pub struct PocketBaseConnector {
+    name: String,
+    base_url: String,
+    token: String,
+    client: reqwest::Client,
+}
+
+impl PocketBaseConnector {
+    pub fn new(config: &BusinessConnectorConfig) -> Self {
+        Self {
+            name: config.name.clone(),
+            base_url: config.base_url.trim_end_matches('/').to_string(),
+            token: config.token.clone(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn list(
+        &self,
+        collection: &str,
+        filter: &str,
+        per_page: usize,
+        sort: Option<&str>,
+    ) -> Result<RecordList> {
+        let url = format!("{}/api/collections/{}/records", self.base_url, collection);
+        let per_page = per_page.to_string();
+        let mut query = vec![("perPage", per_page.as_str()), ("filter", filter)];
+        if let Some(sort) = sort {
+            query.push(("sort", sort));
+        }
+
+        let mut request = self.client.get(&url).query(&query);
+        if !self.token.is_empty() {
+            request = request.header("Authorization", &self.token);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            anyhow::bail!("PocketBase {}: {}", collection, status);
+        }
+        Ok(response.json().await?)
+    }
+
+    async fn count(&self, collection: &str, filter: &str) -> Result<u64> {
+        Ok(self.list(collection, filter, 1, None).await?.total_items)
+    }
+}
+
+#[async_trait]
+impl BusinessConnector for PocketBaseConnector {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn fetch_status(&self) -> Result<BusinessStatus> {
+        let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+        let now = chrono::Utc::now().to_rfc3339();
+
+        let flagged = self
+            .list("reviews", "status = 'flagged'", 50, Some("-created"))
+            .await?;
+
+        Ok(BusinessStatus {
+            pending_reviews: self.count("reviews", "status = 'pending'").await?,
+            flagged: flagged.items.iter().map(flagged_item).collect(),
+            pending_products: self.count("products", "status = 'pending'").await?,
+            stale_announcements: self
+                .count(
+                    "announcements",
+                    &format!("end_date < '{}' && status = 'active'", today),
+                )
+                .await?,
+            upcoming_events: self.count("events", &format!("date >= '{}'", now)).await?,
+            new_submissions: self
+                .count("product_submissions", "status = 'pending'")
+                .await?,
+        })
+    }
+}
+
+/// Id plus the first of title/comment/content as a short summary
+fn flagged_item(record: &Value) -> FlaggedItem {
+    let id = record["id"].as_str().unwrap_or_default().to_string();
+    let text = ["title", "comment", "content", "body"]
+        .iter()
+        .find_map(|field| record[field].as_str().filter(|s| !s.trim().is_empty()))
+        .unwrap_or("(no text)")
+        .trim();
+    let summary = match text.char_indices().nth(SUMMARY_CHARS) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    };
+    FlaggedItem { id, summary }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_flagged_item_summary() {
+        let item = flagged_item(&json!({"id": "r1", "title": "", "comment": " Spam link "}));
+        assert_eq!(item.id, "r1");
+        assert_eq!(item.summary, "Spam link");
+
+        let long = "x".repeat(100);
+        let item = flagged_item(&json!({"id": "r2", "content": long}));
+        assert_eq!(item.summary.len(), SUMMARY_CHARS + 3);
+    }
+}
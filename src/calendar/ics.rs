@@ -0,0 +1,514 @@
+//! Minimal iCalendar (RFC 5545) reading and writing
+//!
+//! Reads the VEVENTs that school and team calendars publish, including
+//! simple RRULE recurrences (DAILY/WEEKLY/MONTHLY/YEARLY with INTERVAL,
+//! COUNT, UNTIL, BYDAY), EXDATE and moved instances (RECURRENCE-ID).
+//! Times with a TZID are taken as local time.
+
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, NaiveDateTime, Utc, Weekday};
+use std::collections::HashSet;
+
+use super::{CalEvent, When};
+
+use crate::locale;
+
+/// Stop expanding a recurrence after this many candidates
+const MAX_EXPANSION: usize = 5000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Freq {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Recurrence {
+    freq: Freq,
+    interval: u32,
+    count: Option<usize>,
+    until: Option<NaiveDate>,
+    /// Weekdays, with an optional ordinal for MONTHLY ("2TU", "-1FR")
+    by_day: Vec<(Option<i32>, Weekday)>,
+}
+
+/// A VEVENT as read, before recurrences are expanded
+#[derive(Debug, Clone)]
+struct RawEvent {
+    event: CalEvent,
+    rrule: Option<Recurrence>,
+    exdates: HashSet<NaiveDate>,
+    /// Set on a moved instance of a recurring event
+    recurrence_id: Option<NaiveDate>,
+}
+
+/// Join folded lines (continuations start with a space or tab)
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match line.strip_prefix([' ', '\t']) {
+            Some(rest) if !lines.is_empty() => lines.last_mut().unwrap().push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// Property parameters, e.g. ("TZID", "America/Chicago")
+type Params = Vec<(String, String)>;
+
+/// Split "NAME;PARAM=x:VALUE" into (NAME, params, VALUE)
+fn split_line(line: &str) -> Option<(String, Params, String)> {
+    let mut in_quotes = false;
+    let colon = line.char_indices().find_map(|(i, c)| match c {
+        '"' => {
+            in_quotes = !in_quotes;
+            None
+        }
+        ':' if !in_quotes => Some(i),
+        _ => None,
+    })?;
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+    let mut parts = head.split(';');
+    let name = parts.next()?.to_ascii_uppercase();
+    let params = parts
+        .filter_map(|p| p.split_once('='))
+        .map(|(k, v)| (k.to_ascii_uppercase(), v.trim_matches('"').to_string()))
+        .collect();
+    Some((name, params, value.to_string()))
+}
+
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// A DATE or DATE-TIME value; UTC times are converted to local time
+fn parse_when(value: &str, params: &[(String, String)]) -> Option<When> {
+    let value = value.trim();
+    let is_date = params.iter().any(|(k, v)| k == "VALUE" && v == "DATE") || value.len() == 8;
+    if is_date {
+        return NaiveDate::parse_from_str(&value[..8.min(value.len())], "%Y%m%d")
+            .ok()
+            .map(When::Date);
+    }
+    if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        let local = locale::home_time(&DateTime::<Utc>::from_naive_utc_and_offset(naive, Utc));
+        return Some(When::DateTime(local.naive_local()));
+    }
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .ok()
+        .map(When::DateTime)
+}
+
+fn parse_weekday(s: &str) -> Option<Weekday> {
+    Some(match s {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+fn parse_rrule(value: &str) -> Option<Recurrence> {
+    let mut rule = Recurrence {
+        freq: Freq::Daily,
+        interval: 1,
+        count: None,
+        until: None,
+        by_day: Vec::new(),
+    };
+    let mut freq = None;
+    for part in value.split(';') {
+        let (key, val) = part.split_once('=')?;
+        match key.to_ascii_uppercase().as_str() {
+            "FREQ" => {
+                freq = Some(match val {
+                    "DAILY" => Freq::Daily,
+                    "WEEKLY" => Freq::Weekly,
+                    "MONTHLY" => Freq::Monthly,
+                    "YEARLY" => Freq::Yearly,
+                    // HOURLY etc. aren't used by family calendars
+                    _ => return None,
+                })
+            }
+            "INTERVAL" => rule.interval = val.parse().ok().filter(|i| *i > 0)?,
+            "COUNT" => rule.count = val.parse().ok(),
+            "UNTIL" => rule.until = parse_when(val, &[]).map(|w| w.date()),
+            "BYDAY" => {
+                for day in val.split(',') {
+                    let split = day.len().checked_sub(2)?;
+                    let ordinal = day[..split].parse::<i32>().ok();
+                    rule.by_day.push((ordinal, parse_weekday(&day[split..])?));
+                }
+            }
+            _ => {}
+        }
+    }
+    rule.freq = freq?;
+    Some(rule)
+}
+
+fn parse_events(text: &str) -> Vec<RawEvent> {
+    let mut events = Vec::new();
+    let mut current: Option<RawEvent> = None;
+    // Depth inside components nested in a VEVENT (VALARM), which are skipped
+    let mut nested = 0;
+
+    for line in unfold(text) {
+        let Some((name, params, value)) = split_line(&line) else {
+            continue;
+        };
+        if current.is_some() {
+            match name.as_str() {
+                "BEGIN" => nested += 1,
+                "END" if nested > 0 => nested -= 1,
+                _ => {}
+            }
+            if nested > 0 || (name == "END" && !value.eq_ignore_ascii_case("VEVENT")) {
+                continue;
+            }
+        }
+        match (name.as_str(), current.as_mut()) {
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VEVENT") => {
+                current = Some(RawEvent {
+                    event: CalEvent {
+                        uid: String::new(),
+                        summary: String::new(),
+                        start: When::Date(NaiveDate::MIN),
+                        end: None,
+                        location: None,
+                        description: None,
+                    },
+                    rrule: None,
+                    exdates: HashSet::new(),
+                    recurrence_id: None,
+                });
+            }
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VEVENT") => {
+                let raw = current.take().unwrap();
+                if raw.event.start != When::Date(NaiveDate::MIN) {
+                    events.push(raw);
+                }
+            }
+            ("UID", Some(raw)) => raw.event.uid = value,
+            ("SUMMARY", Some(raw)) => raw.event.summary = unescape(&value),
+            ("LOCATION", Some(raw)) if !value.is_empty() => {
+                raw.event.location = Some(unescape(&value))
+            }
+            ("DESCRIPTION", Some(raw)) if !value.is_empty() => {
+                raw.event.description = Some(unescape(&value))
+            }
+            ("DTSTART", Some(raw)) => {
+                if let Some(when) = parse_when(&value, &params) {
+                    raw.event.start = when;
+                }
+            }
+            ("DTEND", Some(raw)) => raw.event.end = parse_when(&value, &params),
+            ("RRULE", Some(raw)) => raw.rrule = parse_rrule(&value),
+            ("EXDATE", Some(raw)) => raw.exdates.extend(
+                value
+                    .split(',')
+                    .filter_map(|v| parse_when(v, &params))
+                    .map(|w| w.date()),
+            ),
+            ("RECURRENCE-ID", Some(raw)) => {
+                raw.recurrence_id = parse_when(&value, &params).map(|w| w.date())
+            }
+            _ => {}
+        }
+    }
+    events
+}
+
+/// The `n`th `weekday` of a month (negative counts from the end)
+fn nth_weekday(year: i32, month: u32, weekday: Weekday, n: i32) -> Option<NaiveDate> {
+    if n > 0 {
+        NaiveDate::from_weekday_of_month_opt(year, month, weekday, n as u8)
+    } else {
+        let first_next = NaiveDate::from_ymd_opt(year, month, 1)? + Months::new(1);
+        let mut day = first_next.pred_opt()?;
+        while day.weekday() != weekday {
+            day = day.pred_opt()?;
+        }
+        let back = Duration::weeks((-n - 1) as i64);
+        Some(day - back).filter(|d| d.month() == month)
+    }
+}
+
+/// Candidate dates of a recurrence, in order, starting at `start`
+fn recurrence_dates(rule: &Recurrence, start: NaiveDate) -> Vec<NaiveDate> {
+    let mut dates = Vec::new();
+    let interval = rule.interval;
+    for period in 0..MAX_EXPANSION as u32 {
+        let step = period * interval;
+        let candidates: Vec<NaiveDate> = match rule.freq {
+            Freq::Daily => vec![start + Duration::days(step as i64)],
+            Freq::Weekly => {
+                let week = start.week(Weekday::Mon).first_day() + Duration::weeks(step as i64);
+                if rule.by_day.is_empty() {
+                    vec![week + Duration::days(start.weekday().num_days_from_monday() as i64)]
+                } else {
+                    let mut days: Vec<NaiveDate> = rule
+                        .by_day
+                        .iter()
+                        .map(|(_, wd)| week + Duration::days(wd.num_days_from_monday() as i64))
+                        .collect();
+                    days.sort();
+                    days
+                }
+            }
+            Freq::Monthly => {
+                let Some(month) = start
+                    .with_day(1)
+                    .and_then(|d| d.checked_add_months(Months::new(step)))
+                else {
+                    break;
+                };
+                if rule.by_day.is_empty() {
+                    month.with_day(start.day()).into_iter().collect()
+                } else {
+                    let mut days: Vec<NaiveDate> = rule
+                        .by_day
+                        .iter()
+                        .filter_map(|(n, wd)| {
+                            nth_weekday(month.year(), month.month(), *wd, n.unwrap_or(1))
+                        })
+                        .collect();
+                    days.sort();
+                    days
+                }
+            }
+            Freq::Yearly => start
+                .checked_add_months(Months::new(12 * step))
+                .into_iter()
+                .collect(),
+        };
+        for date in candidates {
+            if date < start {
+                continue;
+            }
+            if rule.until.is_some_and(|until| date > until)
+                || rule.count.is_some_and(|count| dates.len() >= count)
+                || dates.len() >= MAX_EXPANSION
+            {
+                return dates;
+            }
+            dates.push(date);
+        }
+    }
+    dates
+}
+
+/// Events overlapping `from..=to` (dates), with recurrences expanded
+pub fn events_between(text: &str, from: NaiveDate, to: NaiveDate) -> Vec<CalEvent> {
+    let raw = parse_events(text);
+    let moved: HashSet<(String, NaiveDate)> = raw
+        .iter()
+        .filter_map(|r| r.recurrence_id.map(|d| (r.event.uid.clone(), d)))
+        .collect();
+
+    let mut events = Vec::new();
+    for r in raw {
+        let rule = match r.rrule {
+            Some(ref rule) if r.recurrence_id.is_none() => rule,
+            _ => {
+                if r.event.end_date() >= from && r.event.start.date() <= to {
+                    events.push(r.event);
+                }
+                continue;
+            }
+        };
+        let start_date = r.event.start.date();
+        for date in recurrence_dates(rule, start_date) {
+            if date > to {
+                break;
+            }
+            if r.exdates.contains(&date) || moved.contains(&(r.event.uid.clone(), date)) {
+                continue;
+            }
+            let occurrence = r.event.shifted((date - start_date).num_days());
+            if occurrence.end_date() >= from {
+                events.push(occurrence);
+            }
+        }
+    }
+    events.sort_by_key(|e| e.start);
+    events
+}
+
+fn format_when(name: &str, when: When) -> String {
+    match when {
+        When::Date(date) => format!("{};VALUE=DATE:{}", name, date.format("%Y%m%d")),
+        // Floating time: shown at the same wall-clock time on every phone
+        When::DateTime(dt) => format!("{}:{}", name, dt.format("%Y%m%dT%H%M%S")),
+    }
+}
+
+/// Fold a content line to 75 octets per line
+fn fold(line: &str) -> String {
+    let mut out = String::with_capacity(line.len() + 8);
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+    out
+}
+
+/// An iCalendar feed of `events`
+pub fn write_calendar(name: &str, events: &[CalEvent], now: DateTime<Utc>) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//HomeGPT//Calendar//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        format!("X-WR-CALNAME:{}", escape(name)),
+    ];
+    for event in events {
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}", event.uid));
+        lines.push(format!("DTSTAMP:{}", now.format("%Y%m%dT%H%M%SZ")));
+        lines.push(format_when("DTSTART", event.start));
+        if let Some(end) = event.end {
+            lines.push(format_when("DTEND", end));
+        }
+        lines.push(format!("SUMMARY:{}", escape(&event.summary)));
+        if let Some(ref location) = event.location {
+            lines.push(format!("LOCATION:{}", escape(location)));
+        }
+        if let Some(ref description) = event.description {
+            lines.push(format!("DESCRIPTION:{}", escape(description)));
+        }
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn at(s: &str) -> When {
+        When::DateTime(NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap())
+    }
+
+    const SCHOOL: &str = "BEGIN:VCALENDAR\r\n\
+VERSION:2.0\r\n\
+BEGIN:VEVENT\r\n\
+UID:picture-day@school\r\n\
+DTSTART;VALUE=DATE:20261020\r\n\
+DTEND;VALUE=DATE:20261021\r\n\
+SUMMARY:Picture day\\, bring\r\n \x20a comb\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+UID:soccer@team\r\n\
+DTSTART;TZID=America/Chicago:20261006T170000\r\n\
+DTEND;TZID=America/Chicago:20261006T183000\r\n\
+RRULE:FREQ=WEEKLY;BYDAY=TU,TH;UNTIL=20261105T000000Z\r\n\
+EXDATE;TZID=America/Chicago:20261022T170000\r\n\
+SUMMARY:Soccer practice\r\n\
+LOCATION:Field 3\r\n\
+BEGIN:VALARM\r\n\
+DESCRIPTION:Reminder\r\n\
+END:VALARM\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+UID:soccer@team\r\n\
+RECURRENCE-ID;TZID=America/Chicago:20261027T170000\r\n\
+DTSTART;TZID=America/Chicago:20261028T170000\r\n\
+DTEND;TZID=America/Chicago:20261028T183000\r\n\
+SUMMARY:Soccer practice (moved)\r\n\
+END:VEVENT\r\n\
+END:VCALENDAR\r\n";
+
+    #[test]
+    fn test_events_between() {
+        let events = events_between(SCHOOL, date("2026-10-19"), date("2026-10-29"));
+        let summary: Vec<(When, &str)> = events
+            .iter()
+            .map(|e| (e.start, e.summary.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (When::Date(date("2026-10-20")), "Picture day, bring a comb"),
+                (at("2026-10-20 17:00"), "Soccer practice"),
+                // 22nd excluded, 27th moved to the 28th
+                (at("2026-10-28 17:00"), "Soccer practice (moved)"),
+                (at("2026-10-29 17:00"), "Soccer practice"),
+            ]
+        );
+        assert_eq!(events[1].location.as_deref(), Some("Field 3"));
+        assert_eq!(events[1].description, None);
+        assert_eq!(events[1].end, Some(at("2026-10-20 18:30")));
+    }
+
+    #[test]
+    fn test_recurrence_rules() {
+        let monthly = parse_rrule("FREQ=MONTHLY;BYDAY=-1FR;COUNT=3").unwrap();
+        assert_eq!(
+            recurrence_dates(&monthly, date("2026-10-30")),
+            vec![date("2026-10-30"), date("2026-11-27"), date("2026-12-25")]
+        );
+
+        let fortnightly = parse_rrule("FREQ=WEEKLY;INTERVAL=2;COUNT=3").unwrap();
+        assert_eq!(
+            recurrence_dates(&fortnightly, date("2026-10-17")),
+            vec![date("2026-10-17"), date("2026-10-31"), date("2026-11-14")]
+        );
+        assert!(parse_rrule("FREQ=HOURLY").is_none());
+    }
+
+    #[test]
+    fn test_write_round_trip() {
+        let event = CalEvent {
+            uid: "1@homegpt".to_string(),
+            summary: "Dentist; bring forms, insurance card".to_string(),
+            start: at("2026-11-03 09:15"),
+            end: Some(at("2026-11-03 10:00")),
+            location: None,
+            description: Some("x".repeat(100)),
+        };
+        let feed = write_calendar("HomeGPT", std::slice::from_ref(&event), Utc::now());
+        assert!(feed.lines().all(|line| line.len() <= 76));
+        let read = events_between(&feed, date("2026-11-01"), date("2026-11-30"));
+        assert_eq!(read, vec![event]);
+    }
+}
@@ -0,0 +1,508 @@
+//! Calendar: subscribed .ics feeds and events the assistant creates
+//!
+//! Subscriptions (`[[calendar.subscriptions]]`: school calendar, sports
+//! team) are fetched by the heartbeat every `calendar.refresh` and cached in
+//! `~/.homegpt/calendars/`. Their upcoming events, plus the events created
+//! with the `calendar_add_event` tool, are written to a marked section of
+//! `memory/calendar/upcoming.md` so the rest of the file (e.g. Google
+//! Calendar events the heartbeat copies in) is left alone. With
+//! `calendar.publish`, the created events are also served as an .ics feed
+//! the family's phones can subscribe to.
+
+mod ics;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+use crate::config::{parse_duration, CalendarSubscriptionConfig, Config};
+use crate::locale;
+
+/// Events created by the assistant (relative to the state dir)
+const EVENTS_FILE: &str = "calendar-events.json";
+
+/// Last good copy of each subscription (relative to the state dir)
+const CACHE_DIR: &str = "calendars";
+
+/// Workspace file the events are merged into
+pub const UPCOMING_PATH: &str = "memory/calendar/upcoming.md";
+
+const BLOCK_START: &str =
+    "<!-- calendar-sync:start (updated automatically; edits here are overwritten) -->";
+const BLOCK_END: &str = "<!-- calendar-sync:end -->";
+
+/// Calendar name for the assistant's own events
+const OWN_CALENDAR: &str = "HomeGPT";
+
+/// An all-day date or a local date and time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum When {
+    Date(NaiveDate),
+    DateTime(NaiveDateTime),
+}
+
+impl When {
+    /// "2026-10-20" (all day) or "2026-10-20 17:00"
+    pub fn parse(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+            return Ok(Self::Date(date));
+        }
+        ["%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M", "%Y-%m-%dT%H:%M:%S"]
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+            .map(Self::DateTime)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Invalid time '{}', expected YYYY-MM-DD or YYYY-MM-DD HH:MM",
+                    s
+                )
+            })
+    }
+
+    pub fn date(&self) -> NaiveDate {
+        match self {
+            Self::Date(date) => *date,
+            Self::DateTime(dt) => dt.date(),
+        }
+    }
+
+    fn time(&self) -> Option<NaiveTime> {
+        match self {
+            Self::Date(_) => None,
+            Self::DateTime(dt) => Some(dt.time()),
+        }
+    }
+
+    fn shifted(self, days: i64) -> Self {
+        let delta = chrono::Duration::days(days);
+        match self {
+            Self::Date(date) => Self::Date(date + delta),
+            Self::DateTime(dt) => Self::DateTime(dt + delta),
+        }
+    }
+}
+
+/// Chronological, with all-day events first on their day
+impl Ord for When {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.date(), self.time()).cmp(&(other.date(), other.time()))
+    }
+}
+
+impl PartialOrd for When {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CalEvent {
+    pub uid: String,
+    pub summary: String,
+    pub start: When,
+    /// Exclusive for all-day events, as in iCalendar
+    pub end: Option<When>,
+    pub location: Option<String>,
+    pub description: Option<String>,
+}
+
+impl CalEvent {
+    /// Last day the event covers
+    pub fn end_date(&self) -> NaiveDate {
+        match self.end {
+            Some(When::Date(end)) => end.pred_opt().unwrap_or(end).max(self.start.date()),
+            Some(When::DateTime(end)) => end.date(),
+            None => self.start.date(),
+        }
+    }
+
+    fn shifted(&self, days: i64) -> Self {
+        Self {
+            start: self.start.shifted(days),
+            end: self.end.map(|end| end.shifted(days)),
+            ..self.clone()
+        }
+    }
+
+    /// "Tue 20 Oct 17:00–18:30: Soccer practice @ Field 3"
+    pub fn describe(&self) -> String {
+        let mut text = format!(
+            "{} {}: {}",
+            locale::current().short_date(self.start.date()),
+            self.times(),
+            self.summary
+        );
+        if let Some(ref location) = self.location {
+            text.push_str(&format!(" @ {}", location));
+        }
+        text
+    }
+
+    /// "17:00–18:30", "all day" or "all day, until Wed 21 Oct"
+    fn times(&self) -> String {
+        match (self.start, self.end) {
+            (When::DateTime(start), Some(When::DateTime(end))) if end.date() == start.date() => {
+                format!("{}–{}", start.format("%H:%M"), end.format("%H:%M"))
+            }
+            (When::DateTime(start), _) => start.format("%H:%M").to_string(),
+            (When::Date(_), _) if self.end_date() > self.start.date() => {
+                let locale = locale::current();
+                locale.format(
+                    "calendar.all_day_until",
+                    &[("date", &locale.short_date(self.end_date()))],
+                )
+            }
+            (When::Date(_), _) => locale::current().text("calendar.all_day").to_string(),
+        }
+    }
+}
+
+fn load_events(path: &Path) -> Vec<CalEvent> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// File name for a subscription's cached copy
+fn cache_name(name: &str) -> String {
+    let slug: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    format!("{}.ics", slug)
+}
+
+/// The markdown section for upcoming.md, events grouped by day
+fn render_upcoming(
+    events: &[(String, CalEvent)],
+    today: NaiveDate,
+    days_ahead: u32,
+    now: DateTime<FixedOffset>,
+) -> String {
+    let mut text = format!(
+        "{}\n## Subscribed Calendars\n\nNext {} days, updated {}.\n",
+        BLOCK_START,
+        days_ahead,
+        now.format("%Y-%m-%d %H:%M")
+    );
+    if events.is_empty() {
+        text.push_str("\nNothing scheduled.\n");
+    }
+    let mut current_day = None;
+    for (calendar, event) in events {
+        // Events that started earlier but are still going are listed today
+        let day = event.start.date().max(today);
+        if current_day != Some(day) {
+            text.push_str(&format!("\n### {}\n", locale::current().short_date(day)));
+            current_day = Some(day);
+        }
+        text.push_str(&format!("- {}: {}", event.times(), event.summary));
+        if let Some(ref location) = event.location {
+            text.push_str(&format!(" @ {}", location));
+        }
+        text.push_str(&format!(" ({})\n", calendar));
+    }
+    text.push_str(BLOCK_END);
+    text.push('\n');
+    text
+}
+
+/// Replace the synced section of `existing`, or append it
+fn merge_block(existing: &str, block: &str) -> String {
+    if let (Some(start), Some(end)) = (existing.find(BLOCK_START), existing.find(BLOCK_END)) {
+        if start < end {
+            let after = &existing[end + BLOCK_END.len()..];
+            return format!("{}{}{}", &existing[..start], block.trim_end(), after);
+        }
+    }
+    let mut merged = existing.trim_end().to_string();
+    if !merged.is_empty() {
+        merged.push_str("\n\n");
+    }
+    merged.push_str(block);
+    merged
+}
+
+pub struct Calendar {
+    subscriptions: Vec<CalendarSubscriptionConfig>,
+    days_ahead: u32,
+    refresh: Duration,
+    workspace: PathBuf,
+    events_path: PathBuf,
+    cache_dir: PathBuf,
+    client: reqwest::Client,
+    last_refresh: Mutex<Option<Instant>>,
+}
+
+impl Calendar {
+    pub fn from_config(config: &Config, state_dir: &Path) -> Result<Self> {
+        let refresh = parse_duration(&config.calendar.refresh)
+            .map_err(|e| anyhow::anyhow!("Invalid calendar.refresh: {}", e))?;
+        Ok(Self {
+            subscriptions: config.calendar.subscriptions.clone(),
+            days_ahead: config.calendar.days_ahead,
+            refresh,
+            workspace: config.workspace_path(),
+            events_path: state_dir.join(EVENTS_FILE),
+            cache_dir: state_dir.join(CACHE_DIR),
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()?,
+            last_refresh: Mutex::new(None),
+        })
+    }
+
+    /// Events created by the assistant
+    pub fn events(&self) -> Vec<CalEvent> {
+        load_events(&self.events_path)
+    }
+
+    fn save_events(&self, events: &[CalEvent]) -> Result<()> {
+        if let Some(parent) = self.events_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.events_path, serde_json::to_string_pretty(events)?)?;
+        Ok(())
+    }
+
+    /// Create an event, returning it with its new uid
+    pub fn add_event(
+        &self,
+        summary: &str,
+        start: When,
+        end: Option<When>,
+        location: Option<String>,
+        description: Option<String>,
+    ) -> Result<CalEvent> {
+        if end.is_some_and(|end| end < start) {
+            anyhow::bail!("The event ends before it starts");
+        }
+        let event = CalEvent {
+            uid: format!("{}@homegpt", uuid::Uuid::new_v4()),
+            summary: summary.to_string(),
+            start,
+            // iCalendar all-day ends are exclusive
+            end: match end {
+                Some(When::Date(date)) => Some(When::Date(date + chrono::Duration::days(1))),
+                other => other,
+            },
+            location,
+            description,
+        };
+        let mut events = self.events();
+        events.push(event.clone());
+        events.sort_by_key(|e| e.start);
+        self.save_events(&events)?;
+        Ok(event)
+    }
+
+    /// Delete the created event whose uid starts with `query` or whose
+    /// title matches it
+    pub fn remove_event(&self, query: &str) -> Result<CalEvent> {
+        let mut events = self.events();
+        let matches: Vec<usize> = events
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.uid.starts_with(query) || e.summary.eq_ignore_ascii_case(query))
+            .map(|(i, _)| i)
+            .collect();
+        match matches.as_slice() {
+            [] => anyhow::bail!("No HomeGPT calendar event matches '{}'", query),
+            [index] => {
+                let removed = events.remove(*index);
+                self.save_events(&events)?;
+                Ok(removed)
+            }
+            _ => anyhow::bail!(
+                "'{}' matches {} events; use the id instead: {}",
+                query,
+                matches.len(),
+                matches
+                    .iter()
+                    .map(|&i| format!("{} ({})", events[i].describe(), short_id(&events[i].uid)))
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            ),
+        }
+    }
+
+    /// Fetch every subscription into the cache. A failed fetch keeps the
+    /// last good copy.
+    pub async fn refresh(&self) {
+        for subscription in &self.subscriptions {
+            if let Err(e) = self.fetch(subscription).await {
+                warn!("Calendar {} not refreshed: {}", subscription.name, e);
+            }
+        }
+        *self.last_refresh.lock().unwrap() = Some(Instant::now());
+    }
+
+    async fn fetch(&self, subscription: &CalendarSubscriptionConfig) -> Result<()> {
+        let url = match subscription.url.strip_prefix("webcal://") {
+            Some(rest) => format!("https://{}", rest),
+            None => subscription.url.clone(),
+        };
+        let response = self.client.get(&url).send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            anyhow::bail!("{}", status);
+        }
+        let text = response.text().await?;
+        if !text.contains("BEGIN:VCALENDAR") {
+            anyhow::bail!("not an iCalendar file");
+        }
+        fs::create_dir_all(&self.cache_dir)?;
+        fs::write(self.cache_dir.join(cache_name(&subscription.name)), text)?;
+        debug!("Fetched calendar {}", subscription.name);
+        Ok(())
+    }
+
+    /// Events from `today` through `days_ahead`, with the calendar each is from
+    pub fn upcoming(&self, today: NaiveDate) -> Vec<(String, CalEvent)> {
+        let to = today + chrono::Duration::days(self.days_ahead as i64 - 1);
+        let mut events = Vec::new();
+        for subscription in &self.subscriptions {
+            let path = self.cache_dir.join(cache_name(&subscription.name));
+            let Ok(text) = fs::read_to_string(&path) else {
+                continue;
+            };
+            for event in ics::events_between(&text, today, to) {
+                events.push((subscription.name.clone(), event));
+            }
+        }
+        for event in self.events() {
+            if event.end_date() >= today && event.start.date() <= to {
+                events.push((OWN_CALENDAR.to_string(), event));
+            }
+        }
+        events.sort_by_key(|(_, e)| (e.start.date().max(today), e.start));
+        events
+    }
+
+    /// Rewrite the synced section of upcoming.md, returning its path
+    pub fn write_upcoming(&self, now: DateTime<FixedOffset>) -> Result<PathBuf> {
+        let today = now.date_naive();
+        let block = render_upcoming(&self.upcoming(today), today, self.days_ahead, now);
+        let path = self.workspace.join(UPCOMING_PATH);
+        let existing = fs::read_to_string(&path).unwrap_or_default();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, merge_block(&existing, &block))
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+
+    /// Refetch subscriptions and rewrite upcoming.md when `calendar.refresh`
+    /// has passed, returning the path when it was rewritten
+    pub async fn sync_if_due(&self, now: DateTime<FixedOffset>) -> Result<Option<PathBuf>> {
+        if self.subscriptions.is_empty() && self.events().is_empty() {
+            return Ok(None);
+        }
+        let due = self
+            .last_refresh
+            .lock()
+            .unwrap()
+            .is_none_or(|last| last.elapsed() >= self.refresh);
+        if !due {
+            return Ok(None);
+        }
+        self.refresh().await;
+        self.write_upcoming(now).map(Some)
+    }
+
+    /// The created events as an iCalendar feed
+    pub fn feed(&self) -> String {
+        ics::write_calendar(OWN_CALENDAR, &self.events(), Utc::now())
+    }
+}
+
+/// First part of a uid, enough to identify an event in a tool call
+pub fn short_id(uid: &str) -> &str {
+    &uid[..uid.len().min(8)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn event(summary: &str, start: &str, end: Option<&str>) -> CalEvent {
+        CalEvent {
+            uid: format!("{}@test", summary),
+            summary: summary.to_string(),
+            start: When::parse(start).unwrap(),
+            end: end.map(|e| When::parse(e).unwrap()),
+            location: None,
+            description: None,
+        }
+    }
+
+    #[test]
+    fn test_when_ordering() {
+        let mut times = vec![
+            When::parse("2026-10-20 08:00").unwrap(),
+            When::parse("2026-10-21").unwrap(),
+            When::parse("2026-10-20").unwrap(),
+        ];
+        times.sort();
+        assert_eq!(
+            times,
+            vec![
+                When::parse("2026-10-20").unwrap(),
+                When::parse("2026-10-20 08:00").unwrap(),
+                When::parse("2026-10-21").unwrap(),
+            ]
+        );
+        assert!(When::parse("next tuesday").is_err());
+    }
+
+    #[test]
+    fn test_render_and_merge() {
+        let now = FixedOffset::east_opt(0)
+            .unwrap()
+            .with_ymd_and_hms(2026, 10, 19, 7, 0, 0)
+            .unwrap();
+        let events = vec![
+            (
+                "School".to_string(),
+                event("Fall break", "2026-10-17", Some("2026-10-21")),
+            ),
+            (
+                "Team".to_string(),
+                event("Soccer", "2026-10-20 17:00", Some("2026-10-20 18:30")),
+            ),
+        ];
+        let block = render_upcoming(&events, date("2026-10-19"), 14, now);
+        assert!(block.contains(
+            "### Mon 19 Oct\n- all day, until Tue 20 Oct: Fall break (School)\n\n\
+             ### Tue 20 Oct\n- 17:00–18:30: Soccer (Team)\n"
+        ));
+
+        let existing = "# Upcoming\n\n- Dentist Thursday\n";
+        let merged = merge_block(existing, &block);
+        assert!(merged.starts_with("# Upcoming\n\n- Dentist Thursday\n\n<!-- calendar-sync:start"));
+
+        // A second sync replaces the section and keeps the rest
+        let empty = render_upcoming(&[], date("2026-10-19"), 14, now);
+        let remerged = merge_block(&format!("{}\nNotes after\n", merged), &empty);
+        assert!(!remerged.contains("Soccer"));
+        assert!(remerged.contains("Nothing scheduled."));
+        assert!(remerged.starts_with("# Upcoming\n\n- Dentist Thursday\n"));
+        assert!(remerged.ends_with("<!-- calendar-sync:end -->\n\nNotes after\n"));
+    }
+}
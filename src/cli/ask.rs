@@ -1,10 +1,14 @@
 use anyhow::Result;
 use clap::Args;
+use futures::StreamExt;
+use serde::Serialize;
+use serde_json::Value;
 
-use homegpt::agent::{Agent, AgentConfig};
+use homegpt::agent::{Agent, AgentConfig, Role};
+use homegpt::client::{DaemonClient, DaemonEvent};
 use homegpt::concurrency::WorkspaceLock;
 use homegpt::config::Config;
-use homegpt::memory::MemoryManager;
+use homegpt::memory::{cited_hashes, MemoryManager};
 
 #[derive(Args)]
 pub struct AskArgs {
@@ -18,14 +22,73 @@ pub struct AskArgs {
     /// Output format: text (default) or json
     #[arg(short, long, default_value = "text")]
     pub format: String,
+
+    /// Print the answer, citations, tool calls and token usage as JSON
+    /// (same as --format json)
+    #[arg(long)]
+    pub json: bool,
+
+    /// Run in this process even if the daemon is running
+    #[arg(long)]
+    pub local: bool,
+}
+
+/// Everything a one-shot question produced, for `--json`
+#[derive(Serialize)]
+struct Answer {
+    question: String,
+    response: String,
+    model: String,
+    citations: Vec<Citation>,
+    tool_calls: Vec<ToolCallRecord>,
+    usage: Usage,
+}
+
+#[derive(Serialize)]
+struct Citation {
+    hash: String,
+    /// `path:start-end` of the cited chunk, null if it's no longer indexed
+    source: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ToolCallRecord {
+    name: String,
+    /// Arguments as the model sent them (in-process only)
+    #[serde(skip_serializing_if = "Value::is_null")]
+    arguments: Value,
+    /// Short description of the call (from the daemon)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+    /// Tool output; the daemon sends the first 500 characters
+    output: String,
+}
+
+#[derive(Serialize, Default)]
+struct Usage {
+    input_tokens: u64,
+    output_tokens: u64,
+    cache_read_tokens: u64,
+    cache_write_tokens: u64,
 }
 
 pub async fn run(args: AskArgs, agent_id: &str) -> Result<()> {
     let config = Config::load()?;
+    // The daemon's agents don't know about this process's --dry-run
+    if !args.local && !homegpt::agent::dry_run_default() {
+        if let Some(client) = DaemonClient::connect(&config).await {
+            let answer = ask_daemon(&client, &args).await?;
+            return print_answer(&args, &answer);
+        }
+    }
+
     let memory = MemoryManager::new_with_full_config(&config.memory, Some(&config), agent_id)?;
 
     let agent_config = AgentConfig {
-        model: args.model.unwrap_or(config.agent.default_model.clone()),
+        model: args
+            .model
+            .clone()
+            .unwrap_or(config.agent.default_model.clone()),
         context_window: config.agent.context_window,
         reserve_tokens: config.agent.reserve_tokens,
     };
@@ -35,21 +98,140 @@ pub async fn run(args: AskArgs, agent_id: &str) -> Result<()> {
 
     let workspace_lock = WorkspaceLock::new()?;
     let _lock_guard = workspace_lock.acquire()?;
+    let before = agent.raw_session_messages().len();
     let response = agent.chat(&args.question).await?;
 
-    match args.format.as_str() {
-        "json" => {
-            let output = serde_json::json!({
-                "question": args.question,
-                "response": response,
-                "model": agent.model(),
-            });
-            println!("{}", serde_json::to_string_pretty(&output)?);
-        }
-        _ => {
-            println!("{}", response);
+    // Tool calls made this turn, paired with their results
+    let turn = agent
+        .raw_session_messages()
+        .get(before..)
+        .unwrap_or_default();
+    let tool_calls = turn
+        .iter()
+        .filter(|m| m.message.role == Role::Assistant)
+        .flat_map(|m| m.message.tool_calls.iter().flatten())
+        .map(|call| ToolCallRecord {
+            name: call.name.clone(),
+            arguments: serde_json::from_str(&call.arguments)
+                .unwrap_or_else(|_| Value::String(call.arguments.clone())),
+            detail: None,
+            output: turn
+                .iter()
+                .find(|m| {
+                    m.message.role == Role::Tool
+                        && m.message.tool_call_id.as_deref() == Some(call.id.as_str())
+                })
+                .map(|m| m.message.content.clone())
+                .unwrap_or_default(),
+        })
+        .collect();
+
+    // The session is new, so its totals are this question's usage
+    let status = agent.session_status();
+    let answer = Answer {
+        question: args.question.clone(),
+        citations: cited_hashes(&response)
+            .into_iter()
+            .map(|hash| Citation {
+                source: agent.citation_source(&hash),
+                hash,
+            })
+            .collect(),
+        response,
+        model: agent.model().to_string(),
+        tool_calls,
+        usage: Usage {
+            input_tokens: status.api_input_tokens,
+            output_tokens: status.api_output_tokens,
+            cache_read_tokens: status.api_cache_read_tokens,
+            cache_write_tokens: status.api_cache_write_tokens,
+        },
+    };
+    print_answer(&args, &answer)
+}
+
+/// Ask in a throwaway session on the daemon
+async fn ask_daemon(client: &DaemonClient, args: &AskArgs) -> Result<Answer> {
+    let session = client.open_session(None).await?;
+    if let Some(model) = &args.model {
+        client.set_model(&session.session_id, model).await?;
+    }
+
+    let mut response = String::new();
+    let mut tool_calls: Vec<(String, ToolCallRecord)> = Vec::new();
+    let mut error = None;
+    let mut stream = std::pin::pin!(
+        client
+            .chat_stream(&session.session_id, &args.question, None, false)
+            .await?
+    );
+    while let Some(event) = stream.next().await {
+        match event {
+            DaemonEvent::Content(delta) => response.push_str(&delta),
+            DaemonEvent::ToolStart {
+                name,
+                id,
+                detail,
+                arguments,
+            } => tool_calls.push((
+                id,
+                ToolCallRecord {
+                    name,
+                    arguments: serde_json::from_str(&arguments).unwrap_or(Value::Null),
+                    detail,
+                    output: String::new(),
+                },
+            )),
+            DaemonEvent::ToolEnd { id, output, .. } => {
+                if let Some((_, call)) = tool_calls.iter_mut().find(|(call_id, _)| *call_id == id) {
+                    call.output = output;
+                }
+            }
+            DaemonEvent::Error(e) => {
+                error = Some(e);
+                break;
+            }
+            // Not asked for: nobody answers a one-shot question's approvals
+            DaemonEvent::Approval(_) | DaemonEvent::Done => {}
         }
     }
+    let status = client.session_status(&session.session_id).await.ok();
+    client.close_session(&session.session_id).await.ok();
+    if let Some(e) = error {
+        anyhow::bail!(e);
+    }
+
+    let mut citations = Vec::new();
+    for hash in cited_hashes(&response) {
+        let source = client.citation_source(&hash).await.unwrap_or(None);
+        citations.push(Citation { hash, source });
+    }
+    let usage = status
+        .as_ref()
+        .map(|status| Usage {
+            input_tokens: status.api_input_tokens,
+            output_tokens: status.api_output_tokens,
+            cache_read_tokens: status.api_cache_read_tokens,
+            cache_write_tokens: status.api_cache_write_tokens,
+        })
+        .unwrap_or_default();
+
+    Ok(Answer {
+        question: args.question.clone(),
+        response,
+        model: status.map(|status| status.model).unwrap_or(session.model),
+        citations,
+        tool_calls: tool_calls.into_iter().map(|(_, call)| call).collect(),
+        usage,
+    })
+}
+
+fn print_answer(args: &AskArgs, answer: &Answer) -> Result<()> {
+    if args.json || args.format == "json" {
+        println!("{}", serde_json::to_string_pretty(answer)?);
+    } else {
+        println!("{}", answer.response);
+    }
 
     Ok(())
 }
@@ -0,0 +1,127 @@
+use anyhow::Result;
+use clap::{Args, Subcommand};
+use std::path::PathBuf;
+
+use homegpt::backup::{generate_key, key_path, Backups};
+use homegpt::config::Config;
+
+use super::daemon::{get_pid_file, is_process_running};
+
+#[derive(Args)]
+pub struct BackupArgs {
+    #[command(subcommand)]
+    pub command: BackupCommands,
+}
+
+#[derive(Subcommand)]
+pub enum BackupCommands {
+    /// Back up the state dir and workspace now
+    Now,
+
+    /// List backups at the destination, newest first
+    List,
+
+    /// Restore a backup over the current state dir and workspace
+    Restore {
+        /// Backup name (or a unique prefix, e.g. 20260314), or "latest"
+        name: String,
+
+        /// age identity file to decrypt with (default: the keygen key, then
+        /// backup.passphrase)
+        #[arg(long)]
+        identity: Option<PathBuf>,
+
+        /// Restore into this directory instead, leaving everything in place
+        #[arg(long)]
+        to: Option<PathBuf>,
+    },
+
+    /// Create an age key to encrypt backups to
+    Keygen {
+        /// Where to write the key (default: ~/.homegpt/backup-key.txt)
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+}
+
+pub async fn run(args: BackupArgs, agent_id: &str) -> Result<()> {
+    let config = Config::load()?;
+
+    match args.command {
+        BackupCommands::Now => {
+            let backups = Backups::new(&config, agent_id)?;
+            let backup = backups.create().await?;
+            println!(
+                "Backed up to {} ({})",
+                backup.name,
+                format_size(backup.size)
+            );
+        }
+        BackupCommands::List => {
+            let backups = Backups::new(&config, agent_id)?.list().await?;
+            if backups.is_empty() {
+                println!("No backups yet");
+            }
+            for backup in backups {
+                println!(
+                    "{}  {}  {}",
+                    backup.created.format("%Y-%m-%d %H:%M"),
+                    backup.name,
+                    format_size(backup.size)
+                );
+            }
+        }
+        BackupCommands::Restore { name, identity, to } => {
+            if to.is_none() {
+                let pid_file = get_pid_file()?;
+                if pid_file.exists() && is_process_running(&std::fs::read_to_string(&pid_file)?) {
+                    anyhow::bail!(
+                        "The daemon is running; stop it first (`homegpt daemon stop`) or restore with --to"
+                    );
+                }
+                // Restoring overwrites the memory index, so keep a way back
+                homegpt::memory::MemoryManager::snapshot_closed(
+                    &config.memory,
+                    agent_id,
+                    "backup restore",
+                )?;
+            }
+            let backups = Backups::new(&config, agent_id)?;
+            let report = backups
+                .restore(&name, identity.as_deref(), to.as_deref())
+                .await?;
+            println!(
+                "Restored {} ({} files, backed up {})",
+                report.name, report.files, report.created_at
+            );
+            for root in report.roots {
+                println!("  {}: {}", root.name, root.path.display());
+            }
+        }
+        BackupCommands::Keygen { output } => {
+            let path = match output {
+                Some(path) => path,
+                None => key_path()?,
+            };
+            let recipient = generate_key(&path)?;
+            println!("Wrote {}", path.display());
+            println!();
+            println!("Add the public key to your config:");
+            println!();
+            println!("  [backup]");
+            println!("  recipients = [\"{}\"]", recipient);
+            println!();
+            println!("Keep a copy of the key somewhere other than this machine:");
+            println!("without it, backups can't be restored.");
+        }
+    }
+    Ok(())
+}
+
+fn format_size(bytes: u64) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    } else {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    }
+}
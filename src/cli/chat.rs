@@ -3,16 +3,24 @@ use clap::Args;
 use futures::StreamExt;
 use rustyline::error::ReadlineError;
 use rustyline::DefaultEditor;
-use std::io::{self, Write};
+use std::collections::HashMap;
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
 
 use homegpt::agent::{
-    extract_tool_detail, get_last_session_id_for_agent, get_skills_summary,
-    list_sessions_for_agent, load_skills, parse_skill_command, search_sessions_for_agent, Agent,
-    AgentConfig, ImageAttachment, Skill,
+    attach_to_message, dry_run_default, extract_tool_detail, extract_tool_preview,
+    get_last_session_id_for_agent, get_skills_summary, get_state_dir, list_sessions_for_agent,
+    load_agent_skills, parse_skill_command, search_sessions_for_agent, skill_prompt, Agent,
+    AgentConfig, Approval, ApprovalOutcome, Attachment, Skill,
 };
+use homegpt::client::{DaemonClient, DaemonEvent, DaemonSession};
 use homegpt::concurrency::WorkspaceLock;
-use homegpt::config::Config;
-use homegpt::memory::MemoryManager;
+use homegpt::config::{load_agent, Config};
+use homegpt::locale;
+use homegpt::memory::{parse_citation, MemoryManager, VerifiedChunk, CITATION_PREFIX};
+
+/// Readline history, kept across chats
+const HISTORY_FILE: &str = "chat_history";
 
 /// Adjust a byte index to the nearest valid UTF-8 char boundary (searching forward).
 fn floor_char_boundary(s: &str, index: usize) -> usize {
@@ -99,6 +107,165 @@ fn extract_snippet(content: &str, query: &str, max_len: usize) -> String {
     }
 }
 
+/// `/memory` results, one card each: where the chunk is, its citation,
+/// how far to trust it, and the text around the match
+fn print_memory_results(query: &str, results: &[VerifiedChunk]) {
+    if results.is_empty() {
+        println!(
+            "\nNo results found for '{}'. Try /reindex to rebuild memory index.\n",
+            query
+        );
+        return;
+    }
+    println!("\nMemory search results for '{}':\n", query);
+    for (i, chunk) in results.iter().enumerate() {
+        let citation = if chunk.verified {
+            format!("[VERIFIED:{}]", chunk.hash_prefix)
+        } else {
+            "[UNVERIFIED]".to_string()
+        };
+        println!(
+            "{}. {}:{}-{} {}",
+            i + 1,
+            chunk.file,
+            chunk.line_start,
+            chunk.line_end,
+            citation
+        );
+        let mut details = vec![
+            format!("Score: {:.3}", chunk.score),
+            format!("Confidence: {}", chunk.confidence),
+            format!("Provenance: {}", chunk.provenance),
+        ];
+        if let Some(ref date) = chunk.last_verified {
+            let stale = if chunk.stale { " (STALE)" } else { "" };
+            details.push(format!("Last verified: {}{}", date, stale));
+        }
+        println!("   {}", details.join(" | "));
+        println!("   {}\n", extract_snippet(&chunk.content, query, 200));
+    }
+}
+
+/// Open the line editor with history from earlier chats
+fn open_editor() -> Result<(DefaultEditor, Option<PathBuf>)> {
+    let mut rl = DefaultEditor::new()?;
+    let path = get_state_dir().ok().map(|dir| dir.join(HISTORY_FILE));
+    if let Some(ref path) = path {
+        // Missing on the first run
+        let _ = rl.load_history(path);
+    }
+    Ok((rl, path))
+}
+
+fn save_history(rl: &mut DefaultEditor, path: Option<&PathBuf>) {
+    if let Some(path) = path {
+        if let Err(e) = rl.save_history(path) {
+            eprintln!("Warning: Failed to save history: {}", e);
+        }
+    }
+}
+
+/// Renders streamed replies, replacing `[VERIFIED:<hash>]` citations with
+/// numbered markers that [`CitationRenderer::sources`] lists after the reply.
+/// A citation split across deltas is held back until it's complete.
+struct CitationRenderer {
+    pending: String,
+    cited: Vec<String>,
+    color: bool,
+}
+
+impl CitationRenderer {
+    fn new() -> Self {
+        Self {
+            pending: String::new(),
+            cited: Vec::new(),
+            color: io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none(),
+        }
+    }
+
+    /// Text of `delta` ready to print
+    fn push(&mut self, delta: &str) -> String {
+        self.pending.push_str(delta);
+        let mut out = String::new();
+        while let Some(start) = self.pending.find('[') {
+            out.push_str(&self.pending[..start]);
+            let rest = self.pending[start..].to_string();
+            if let Some((hash, len)) = parse_citation(&rest) {
+                let marker = self.cite(hash);
+                out.push_str(&marker);
+                self.pending = rest[len..].to_string();
+            } else if is_partial_citation(&rest) {
+                self.pending = rest;
+                return out;
+            } else {
+                out.push('[');
+                self.pending = rest[1..].to_string();
+            }
+        }
+        out.push_str(&self.pending);
+        self.pending.clear();
+        out
+    }
+
+    /// Whatever is still held back, at the end of a reply
+    fn finish(&mut self) -> String {
+        std::mem::take(&mut self.pending)
+    }
+
+    /// Hash prefixes cited so far in this reply
+    fn cited(&self) -> &[String] {
+        &self.cited
+    }
+
+    fn cite(&mut self, hash: &str) -> String {
+        let hash = hash.to_lowercase();
+        let n = match self.cited.iter().position(|h| *h == hash) {
+            Some(i) => i + 1,
+            None => {
+                self.cited.push(hash);
+                self.cited.len()
+            }
+        };
+        self.paint(&format!("[{}]", n))
+    }
+
+    fn paint(&self, text: &str) -> String {
+        if self.color {
+            format!("\x1b[36m{}\x1b[0m", text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// Footer listing the reply's citations, resolving each hash with
+    /// `locate`; empty when nothing was cited. Resets for the next reply.
+    fn sources(&mut self, locate: impl Fn(&str) -> Option<String>) -> String {
+        let mut out = String::new();
+        if self.cited.is_empty() {
+            return out;
+        }
+        out.push_str("\nSources:");
+        for (i, hash) in std::mem::take(&mut self.cited).iter().enumerate() {
+            let marker = self.paint(&format!("[{}]", i + 1));
+            match locate(hash) {
+                Some(location) => out.push_str(&format!("\n  {} {} ({})", marker, location, hash)),
+                None => out.push_str(&format!("\n  {} {}", marker, hash)),
+            }
+        }
+        out
+    }
+}
+
+/// Whether `text` (starting at a `[`) could still become a citation
+fn is_partial_citation(text: &str) -> bool {
+    if text.len() <= CITATION_PREFIX.len() {
+        return CITATION_PREFIX.starts_with(text);
+    }
+    // Hash prefixes are 8 hex chars; allow for a full hash before giving up
+    text.strip_prefix(CITATION_PREFIX)
+        .is_some_and(|hash| hash.len() <= 64 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
 #[derive(Args)]
 pub struct ChatArgs {
     /// Model to use (overrides config)
@@ -112,10 +279,21 @@ pub struct ChatArgs {
     /// Resume the most recent session
     #[arg(long)]
     pub resume: bool,
+
+    /// Run in this process even if the daemon is running
+    #[arg(long)]
+    pub local: bool,
 }
 
 pub async fn run(args: ChatArgs, agent_id: &str) -> Result<()> {
     let config = Config::load()?;
+    // The daemon's agents don't know about this process's --dry-run
+    if !args.local && !dry_run_default() {
+        if let Some(client) = DaemonClient::connect(&config).await {
+            return run_attached(client, args).await;
+        }
+    }
+
     // Embedding provider is automatically created based on config.memory.embedding_provider
     let memory = MemoryManager::new_with_full_config(&config.memory, Some(&config), agent_id)?;
 
@@ -157,9 +335,10 @@ pub async fn run(args: ChatArgs, agent_id: &str) -> Result<()> {
         agent.new_session().await?;
     }
 
-    // Load skills from workspace
+    // Load skills from workspace, as many as this agent's persona allows
     let workspace = config.workspace_path();
-    let skills = load_skills(&workspace).unwrap_or_default();
+    let persona = load_agent(&config.memory, agent_id)?;
+    let skills = load_agent_skills(&workspace, persona.as_ref()).unwrap_or_default();
     let skills_count = skills.iter().filter(|s| s.eligibility.is_ready()).count();
 
     let embedding_status = if agent.has_embeddings() {
@@ -186,14 +365,11 @@ pub async fn run(args: ChatArgs, agent_id: &str) -> Result<()> {
     // Store agent_id for command handling
     let agent_id = agent_id.to_string();
 
-    let mut rl = DefaultEditor::new()?;
+    let (mut rl, history) = open_editor()?;
     let mut stdout = io::stdout();
+    let mut citations = CitationRenderer::new();
 
     // Track pending file attachments (text and images)
-    enum Attachment {
-        Text { name: String, content: String },
-        Image { name: String, data: ImageAttachment },
-    }
     let mut pending_attachments: Vec<Attachment> = Vec::new();
 
     loop {
@@ -233,66 +409,14 @@ pub async fn run(args: ChatArgs, agent_id: &str) -> Result<()> {
                 }
                 let file_path = parts[1..].join(" ");
                 let expanded = shellexpand::tilde(&file_path).to_string();
-                let path = std::path::Path::new(&expanded);
-                let filename = path
-                    .file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or(&file_path)
-                    .to_string();
-
-                // Check if it's an image file
-                let ext = path
-                    .extension()
-                    .and_then(|e| e.to_str())
-                    .map(|e| e.to_lowercase());
-
-                let is_image = matches!(
-                    ext.as_deref(),
-                    Some("png") | Some("jpg") | Some("jpeg") | Some("gif") | Some("webp")
-                );
-
-                if is_image {
-                    // Read as binary and encode as base64
-                    match std::fs::read(&expanded) {
-                        Ok(bytes) => {
-                            use base64::{engine::general_purpose::STANDARD, Engine as _};
-                            let data = STANDARD.encode(&bytes);
-                            let media_type = match ext.as_deref() {
-                                Some("png") => "image/png",
-                                Some("jpg") | Some("jpeg") => "image/jpeg",
-                                Some("gif") => "image/gif",
-                                Some("webp") => "image/webp",
-                                _ => "application/octet-stream",
-                            }
-                            .to_string();
-
-                            let size = bytes.len();
-                            pending_attachments.push(Attachment::Image {
-                                name: filename.clone(),
-                                data: ImageAttachment { data, media_type },
-                            });
-                            println!("Attached image: {} ({} bytes)", filename, size);
-                            println!("Type your message to send with attachment(s), or /attachments to list.\n");
-                        }
-                        Err(e) => {
-                            eprintln!("Failed to read image file: {}", e);
-                        }
+                match Attachment::load(std::path::Path::new(&expanded)) {
+                    Ok(attachment) => {
+                        println!("Attached: {}", attachment.describe());
+                        println!("Type your message to send with attachment(s), or /attachments to list.\n");
+                        pending_attachments.push(attachment);
                     }
-                } else {
-                    // Read as text
-                    match std::fs::read_to_string(&expanded) {
-                        Ok(content) => {
-                            let size = content.len();
-                            pending_attachments.push(Attachment::Text {
-                                name: filename.clone(),
-                                content,
-                            });
-                            println!("Attached: {} ({} bytes)", filename, size);
-                            println!("Type your message to send with attachment(s), or /attachments to list.\n");
-                        }
-                        Err(e) => {
-                            eprintln!("Failed to read file: {}", e);
-                        }
+                    Err(e) => {
+                        eprintln!("Failed to attach file: {:#}", e);
                     }
                 }
                 continue;
@@ -305,20 +429,7 @@ pub async fn run(args: ChatArgs, agent_id: &str) -> Result<()> {
                 } else {
                     println!("\nPending attachments:");
                     for (i, att) in pending_attachments.iter().enumerate() {
-                        match att {
-                            Attachment::Text { name, content } => {
-                                println!("  {}. [text] {} ({} bytes)", i + 1, name, content.len());
-                            }
-                            Attachment::Image { name, data } => {
-                                println!(
-                                    "  {}. [image] {} ({}, {} bytes encoded)",
-                                    i + 1,
-                                    name,
-                                    data.media_type,
-                                    data.data.len()
-                                );
-                            }
-                        }
+                        println!("  {}. {}", i + 1, att.describe());
                     }
                     println!("\nType your message to send, or /clear-attachments to remove.\n");
                 }
@@ -343,7 +454,9 @@ pub async fn run(args: ChatArgs, agent_id: &str) -> Result<()> {
                     let _lock_guard = workspace_lock.acquire()?;
                     match agent.chat(&msg).await {
                         Ok(response) => {
-                            println!("{}\n", response);
+                            let text = citations.push(&response) + &citations.finish();
+                            let sources = citations.sources(|hash| agent.citation_source(hash));
+                            println!("{}{}\n", text, sources);
                             if let Err(e) = agent.auto_save_session() {
                                 eprintln!("Warning: Failed to auto-save session: {}", e);
                             }
@@ -362,32 +475,7 @@ pub async fn run(args: ChatArgs, agent_id: &str) -> Result<()> {
         }
 
         // Build message with attachments
-        let mut message = input.to_string();
-        let mut images: Vec<ImageAttachment> = Vec::new();
-
-        if !pending_attachments.is_empty() {
-            let mut text_attachments = Vec::new();
-
-            // Separate text and image attachments
-            for att in pending_attachments.drain(..) {
-                match att {
-                    Attachment::Text { name, content } => {
-                        text_attachments.push((name, content));
-                    }
-                    Attachment::Image { data, .. } => {
-                        images.push(data);
-                    }
-                }
-            }
-
-            // Add text attachments to message
-            if !text_attachments.is_empty() {
-                message.push_str("\n\n---\n\n**Attached files:**\n");
-                for (name, content) in &text_attachments {
-                    message.push_str(&format!("\n### {}\n```\n{}\n```\n", name, content));
-                }
-            }
-        }
+        let (message, images) = attach_to_message(input, std::mem::take(&mut pending_attachments));
 
         // Send message to agent with streaming
         print!("\nHomeGPT: ");
@@ -402,7 +490,7 @@ pub async fn run(args: ChatArgs, agent_id: &str) -> Result<()> {
                 while let Some(result) = stream.next().await {
                     match result {
                         Ok(chunk) => {
-                            print!("{}", chunk.delta);
+                            print!("{}", citations.push(&chunk.delta));
                             stdout.flush()?;
                             full_response.push_str(&chunk.delta);
 
@@ -418,6 +506,8 @@ pub async fn run(args: ChatArgs, agent_id: &str) -> Result<()> {
                     }
                 }
 
+                print!("{}", citations.finish());
+
                 // Handle tool calls if any
                 if let Some(tool_calls) = pending_tool_calls {
                     // Check for tools requiring approval
@@ -432,9 +522,13 @@ pub async fn run(args: ChatArgs, agent_id: &str) -> Result<()> {
                             println!("\n[{}]", tc.name);
                         }
 
-                        if agent.requires_approval(&tc.name) {
+                        let approval = agent.check_approval(&tc.name, &tc.arguments);
+                        if let Approval::Ask(ref reason) = approval {
+                            if let Some(preview) = extract_tool_preview(&tc.name, &tc.arguments) {
+                                println!("{}", preview);
+                            }
                             // Prompt for approval
-                            print!("Execute {}? [y/N]: ", tc.name);
+                            print!("Execute {}? ({}) [y/N]: ", tc.name, reason);
                             stdout.flush()?;
 
                             let mut input = String::new();
@@ -442,12 +536,30 @@ pub async fn run(args: ChatArgs, agent_id: &str) -> Result<()> {
                             let input = input.trim().to_lowercase();
 
                             if input == "y" || input == "yes" {
+                                agent.record_approval(
+                                    &tc.name,
+                                    &tc.arguments,
+                                    &approval,
+                                    ApprovalOutcome::Approved,
+                                );
                                 approved_calls.push(tc);
                             } else {
+                                agent.record_approval(
+                                    &tc.name,
+                                    &tc.arguments,
+                                    &approval,
+                                    ApprovalOutcome::Denied,
+                                );
                                 println!("Skipped: {}", tc.name);
                                 any_denied = true;
                             }
                         } else {
+                            agent.record_approval(
+                                &tc.name,
+                                &tc.arguments,
+                                &approval,
+                                ApprovalOutcome::Allowed,
+                            );
                             approved_calls.push(tc);
                         }
                     }
@@ -459,7 +571,7 @@ pub async fn run(args: ChatArgs, agent_id: &str) -> Result<()> {
                             .await
                         {
                             Ok(follow_up) => {
-                                print!("{}", follow_up);
+                                print!("{}", citations.push(&follow_up) + &citations.finish());
                                 stdout.flush()?;
                             }
                             Err(e) => {
@@ -476,6 +588,7 @@ pub async fn run(args: ChatArgs, agent_id: &str) -> Result<()> {
                     agent.finish_chat_stream(&full_response);
                 }
 
+                print!("{}", citations.sources(|hash| agent.citation_source(hash)));
                 if let Err(e) = agent.auto_save_session() {
                     eprintln!("Warning: Failed to auto-save session: {}", e);
                 }
@@ -487,10 +600,48 @@ pub async fn run(args: ChatArgs, agent_id: &str) -> Result<()> {
         }
     }
 
+    save_history(&mut rl, history.as_ref());
+    propose_facts(&agent).await;
     println!("Goodbye!");
     Ok(())
 }
 
+/// Propose facts from the session that's ending (`memory.extract_facts`),
+/// and track what the assistant promised in it (`memory.track_commitments`)
+async fn propose_facts(agent: &Agent) {
+    match agent.extract_memories().await {
+        Ok(0) => {}
+        Ok(n) => println!(
+            "{} new facts to remember; review them with `homegpt memory review`",
+            n
+        ),
+        Err(e) => eprintln!("Warning: Failed to propose facts from the session: {}", e),
+    }
+    if let Err(e) = agent.track_commitments().await {
+        eprintln!(
+            "Warning: Failed to track commitments from the session: {}",
+            e
+        );
+    }
+}
+
+/// Print (name, description, requires approval) for each tool
+fn print_tools(tools: &[(String, String, bool)]) {
+    println!("\nTools ({}):", tools.len());
+    for (name, description, approval) in tools {
+        // First sentence is enough for a listing
+        let summary = description.split(". ").next().unwrap_or(description);
+        let approval = if *approval { " (asks first)" } else { "" };
+        println!(
+            "  {:<18} {}{}",
+            name,
+            summary.trim_end_matches('.'),
+            approval
+        );
+    }
+    println!();
+}
+
 enum CommandResult {
     Continue,
     Quit,
@@ -516,21 +667,25 @@ async fn handle_command(
             println!("  /quit, /exit, /q  - Exit chat");
             println!("  /new              - Start a fresh session (reloads memory context)");
             println!("  /skills           - List available skills");
+            println!("  /tools            - List the tools the agent can call");
             println!("  /sessions         - List available sessions");
             println!("  /search <query>   - Search across all sessions");
             println!("  /resume <id>      - Resume a specific session");
             println!("  /model [name]     - Show or switch model (e.g., /model gpt-4o)");
             println!("  /models           - List available model prefixes");
             println!("  /context          - Show context window usage");
+            println!("  /prompt [full]    - Show the system prompt's sections and their tokens");
             println!("  /export [file]    - Export session as markdown");
             println!("  /attach <file>    - Attach file to next message");
             println!("  /attachments      - List pending attachments");
             println!("  /compact          - Compact session history");
             println!("  /clear            - Clear session history (keeps context)");
             println!("  /memory <query>   - Search memory");
+            println!("  /plan <task>      - Plan a multi-step task, then run each step");
             println!("  /reindex          - Rebuild memory index");
             println!("  /save             - Save current session");
             println!("  /status           - Show session status and API token usage");
+            println!("  /dryrun [on|off]  - Log changing tool calls instead of running them");
 
             // Show skill commands if any
             let invocable: Vec<&Skill> = skills.iter().filter(|s| s.can_invoke()).collect();
@@ -557,6 +712,19 @@ async fn handle_command(
             CommandResult::Continue
         }
 
+        "/tools" => {
+            let tools: Vec<(String, String, bool)> = agent
+                .tool_schemas()
+                .into_iter()
+                .map(|t| {
+                    let approval = agent.requires_approval(&t.name);
+                    (t.name, t.description, approval)
+                })
+                .collect();
+            print_tools(&tools);
+            CommandResult::Continue
+        }
+
         "/sessions" => match list_sessions_for_agent(agent_id) {
             Ok(sessions) => {
                 if sessions.is_empty() {
@@ -569,7 +737,7 @@ async fn handle_command(
                             i + 1,
                             &session.id[..8],
                             session.message_count,
-                            session.created_at.format("%Y-%m-%d %H:%M")
+                            locale::home_time(&session.created_at).format("%Y-%m-%d %H:%M")
                         );
                     }
                     if sessions.len() > 10 {
@@ -600,7 +768,7 @@ async fn handle_command(
                                 i + 1,
                                 &result.session_id[..8.min(result.session_id.len())],
                                 result.match_count,
-                                result.created_at.format("%Y-%m-%d")
+                                locale::home_time(&result.created_at).format("%Y-%m-%d")
                             );
                             if !result.message_preview.is_empty() {
                                 println!("     \"{}\"", result.message_preview);
@@ -617,6 +785,22 @@ async fn handle_command(
             }
         }
 
+        "/plan" => {
+            if parts.len() < 2 {
+                return CommandResult::Error("Usage: /plan <task>".into());
+            }
+            let task = parts[1..].join(" ");
+
+            println!("\nPlanning...");
+            match agent.run_plan(&task).await {
+                Ok(report) => {
+                    println!("\n{}", report.to_markdown());
+                    CommandResult::Continue
+                }
+                Err(e) => CommandResult::Error(format!("Plan failed: {}", e)),
+            }
+        }
+
         "/resume" => {
             if parts.len() < 2 {
                 return CommandResult::Error("Usage: /resume <session-id>".into());
@@ -684,6 +868,22 @@ async fn handle_command(
             Err(e) => CommandResult::Error(format!("Failed to compact: {}", e)),
         },
 
+        "/dryrun" => {
+            let on = match parts.get(1).copied() {
+                None => !agent.dry_run(),
+                Some("on") => true,
+                Some("off") => false,
+                Some(_) => return CommandResult::Error("Usage: /dryrun [on|off]".to_string()),
+            };
+            agent.set_dry_run(on);
+            if on {
+                println!("\nDry run on: file writes, commands and messages are logged, not run.\n");
+            } else {
+                println!("\nDry run off.\n");
+            }
+            CommandResult::Continue
+        }
+
         "/clear" => {
             agent.clear_session();
             println!("\nSession cleared.\n");
@@ -702,6 +902,8 @@ async fn handle_command(
                 }
             }
 
+            propose_facts(agent).await;
+
             match agent.new_session().await {
                 Ok(()) => {
                     println!("New session started. Memory context reloaded.\n");
@@ -718,25 +920,7 @@ async fn handle_command(
             let query = parts[1..].join(" ");
             match agent.search_memory(&query).await {
                 Ok(results) => {
-                    if results.is_empty() {
-                        println!(
-                            "\nNo results found for '{}'. Try /reindex to rebuild memory index.\n",
-                            query
-                        );
-                    } else {
-                        println!("\nMemory search results for '{}':", query);
-                        for (i, result) in results.iter().enumerate() {
-                            let snippet = extract_snippet(&result.content, &query, 120);
-                            println!(
-                                "{}. [{}:{}] {}",
-                                i + 1,
-                                result.file,
-                                result.line_start,
-                                snippet
-                            );
-                        }
-                        println!();
-                    }
+                    print_memory_results(&query, &results);
                     CommandResult::Continue
                 }
                 Err(e) => CommandResult::Error(format!("Memory search failed: {}", e)),
@@ -777,6 +961,9 @@ async fn handle_command(
             println!("  Messages: {}", status.message_count);
             println!("  Context tokens: ~{}", status.token_count);
             println!("  Compactions: {}", status.compaction_count);
+            if agent.dry_run() {
+                println!("  Dry run: on (changing tool calls are skipped)");
+            }
 
             println!("\nMemory:");
             println!("  Chunks: {}", agent.memory_chunk_count());
@@ -792,6 +979,14 @@ async fn handle_command(
                     "  Total tokens: {}",
                     status.api_input_tokens + status.api_output_tokens
                 );
+                if status.api_cache_read_tokens > 0 || status.api_cache_write_tokens > 0 {
+                    println!(
+                        "  Prompt cache: {} read ({:.0}% of input), {} written",
+                        status.api_cache_read_tokens,
+                        status.cache_hit_ratio() * 100.0,
+                        status.api_cache_write_tokens
+                    );
+                }
             }
             println!();
             CommandResult::Continue
@@ -827,6 +1022,16 @@ async fn handle_command(
             CommandResult::Continue
         }
 
+        "/prompt" => {
+            let inspection = agent.inspect_prompt();
+            println!();
+            if parts.get(1) == Some(&"full") {
+                println!("{}", inspection.format_sections());
+            }
+            println!("{}", inspection.format());
+            CommandResult::Continue
+        }
+
         "/export" => {
             let markdown = agent.export_markdown();
             if parts.len() >= 2 {
@@ -851,24 +1056,16 @@ async fn handle_command(
             if let Some(invocation) = parse_skill_command(input, skills) {
                 // Find the skill to get its path
                 if let Some(skill) = skills.iter().find(|s| s.name == invocation.skill_name) {
-                    let skill_prompt = if invocation.args.is_empty() {
-                        format!(
-                            "Use the skill at {}. Read it first, then follow its instructions.",
-                            skill.path.display()
-                        )
-                    } else {
-                        format!(
-                            "Use the skill at {} with this request: {}\n\nRead the skill file first, then follow its instructions.",
-                            skill.path.display(),
-                            invocation.args
-                        )
-                    };
                     println!(
                         "\nInvoking skill: {} {}",
                         skill.name,
                         skill.emoji.as_deref().unwrap_or("")
                     );
-                    return CommandResult::SendMessage(skill_prompt);
+                    return CommandResult::SendMessage(skill_prompt(
+                        skill,
+                        &invocation.args,
+                        &agent.prompt_vars(),
+                    ));
                 }
             }
 
@@ -879,3 +1076,237 @@ async fn handle_command(
         }
     }
 }
+
+/// Chat through the running daemon, which owns the agent and memory
+async fn run_attached(client: DaemonClient, args: ChatArgs) -> Result<()> {
+    if args.resume {
+        eprintln!("--resume is ignored while attached to the daemon; use --session <id>\n");
+    }
+    let mut session = client.open_session(args.session.as_deref()).await?;
+    if let Some(model) = &args.model {
+        client.set_model(&session.session_id, model).await?;
+        session.model = model.clone();
+    }
+
+    let status = client.status().await?;
+    println!(
+        "HomeGPT v{} | Attached to daemon at {} | Model: {} | Memory: {} chunks\n",
+        status.version,
+        client.base_url(),
+        session.model,
+        status.memory_chunks
+    );
+    println!("Type /help for commands, /quit to exit\n");
+
+    let (mut rl, history) = open_editor()?;
+    let mut stdout = io::stdout();
+    let mut citations = CitationRenderer::new();
+
+    loop {
+        let input = match rl.readline("You: ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => {
+                println!("^C");
+                continue;
+            }
+            Err(ReadlineError::Eof) => break,
+            Err(err) => {
+                eprintln!("Error: {:?}", err);
+                break;
+            }
+        };
+
+        let input = input.trim();
+        if input.is_empty() {
+            continue;
+        }
+        let _ = rl.add_history_entry(input);
+
+        if input.starts_with('/') {
+            match handle_attached_command(input, &client, &mut session).await {
+                Ok(true) => continue,
+                Ok(false) => break,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    continue;
+                }
+            }
+        }
+
+        print!("\nHomeGPT: ");
+        stdout.flush()?;
+        match client
+            .chat_stream(&session.session_id, input, None, true)
+            .await
+        {
+            Ok(stream) => {
+                let mut stream = std::pin::pin!(stream);
+                while let Some(event) = stream.next().await {
+                    match event {
+                        DaemonEvent::Content(delta) => {
+                            print!("{}", citations.push(&delta));
+                            stdout.flush()?;
+                        }
+                        DaemonEvent::ToolStart { name, detail, .. } => match detail {
+                            Some(detail) => println!("\n[{}: {}]", name, detail),
+                            None => println!("\n[{}]", name),
+                        },
+                        DaemonEvent::Approval(approval) => {
+                            if let Some(preview) =
+                                extract_tool_preview(&approval.name, &approval.arguments)
+                            {
+                                println!("{}", preview);
+                            }
+                            print!("Execute {}? ({}) [y/N]: ", approval.name, approval.reason);
+                            stdout.flush()?;
+
+                            let mut input = String::new();
+                            std::io::stdin().read_line(&mut input)?;
+                            let input = input.trim().to_lowercase();
+                            let approved = input == "y" || input == "yes";
+                            if !approved {
+                                println!("Skipped: {}", approval.name);
+                            }
+                            if let Err(e) = client.answer_approval(&approval, approved).await {
+                                eprintln!("\nError: {}", e);
+                            }
+                        }
+                        DaemonEvent::Error(e) => {
+                            eprintln!("\nError: {}", e);
+                            break;
+                        }
+                        DaemonEvent::ToolEnd { .. } | DaemonEvent::Done => {}
+                    }
+                }
+                print!("{}", citations.finish());
+                let mut located = HashMap::new();
+                for hash in citations.cited() {
+                    if let Ok(Some(location)) = client.citation_source(hash).await {
+                        located.insert(hash.clone(), location);
+                    }
+                }
+                print!("{}", citations.sources(|hash| located.get(hash).cloned()));
+                println!("\n");
+            }
+            Err(e) => {
+                eprintln!("Error: {}\n", e);
+            }
+        }
+    }
+
+    save_history(&mut rl, history.as_ref());
+    println!("Goodbye!");
+    Ok(())
+}
+
+/// Run a slash command against the daemon session. Returns false to quit.
+async fn handle_attached_command(
+    input: &str,
+    client: &DaemonClient,
+    session: &mut DaemonSession,
+) -> Result<bool> {
+    let parts: Vec<&str> = input.split_whitespace().collect();
+    let id = session.session_id.clone();
+
+    match parts[0] {
+        "/quit" | "/exit" | "/q" => return Ok(false),
+
+        "/help" | "/h" | "/?" => {
+            println!("\nCommands (attached to the daemon):");
+            println!("  /help, /h, /?     - Show this help");
+            println!("  /quit, /exit, /q  - Exit chat");
+            println!("  /new              - Start a fresh session");
+            println!("  /model [name]     - Show or switch model");
+            println!("  /compact          - Compact session history");
+            println!("  /clear            - Clear session history");
+            println!("  /memory <query>   - Search memory");
+            println!("  /tools            - List the tools the agent can call");
+            println!("  /reindex          - Update the memory index");
+            println!("  /status           - Show session status and API token usage");
+            println!("  /prompt [full]    - Show the system prompt's sections and their tokens");
+            println!("\nRun `homegpt chat --local` for the full command set.\n");
+        }
+
+        "/new" => {
+            *session = client.open_session(None).await?;
+            println!("\nNew session started.\n");
+        }
+
+        "/model" => match parts.get(1) {
+            Some(model) => {
+                client.set_model(&id, model).await?;
+                session.model = model.to_string();
+                println!("\nSwitched to model: {}\n", model);
+            }
+            None => println!("\nCurrent model: {}\n", session.model),
+        },
+
+        "/compact" => {
+            let (before, after) = client.compact(&id).await?;
+            println!("\nSession compacted. Token count: {} → {}\n", before, after);
+        }
+
+        "/clear" => {
+            client.clear_session(&id).await?;
+            println!("\nSession cleared.\n");
+        }
+
+        "/memory" => {
+            if parts.len() < 2 {
+                anyhow::bail!("Usage: /memory <query>");
+            }
+            let query = parts[1..].join(" ");
+            let results = client.search_memory(&query, 10).await?;
+            print_memory_results(&query, &results);
+        }
+
+        "/tools" => {
+            let tools: Vec<(String, String, bool)> = client
+                .tools(&id)
+                .await?
+                .into_iter()
+                .map(|t| (t.name, t.description, t.requires_approval))
+                .collect();
+            print_tools(&tools);
+        }
+
+        "/reindex" => {
+            let (files, chunks) = client.reindex().await?;
+            println!(
+                "\nMemory index updated: {} files changed, {} chunks\n",
+                files, chunks
+            );
+        }
+
+        "/status" => {
+            let status = client.session_status(&id).await?;
+            println!("\nSession Status:");
+            println!("  ID: {}", status.session_id);
+            println!("  Model: {}", status.model);
+            println!("  Messages: {}", status.message_count);
+            println!("  Context tokens: ~{}", status.token_count);
+            println!(
+                "  API tokens: {} in / {} out",
+                status.api_input_tokens, status.api_output_tokens
+            );
+            println!();
+        }
+
+        "/prompt" => {
+            let inspection = client.session_prompt(&id).await?;
+            println!();
+            if parts.get(1) == Some(&"full") {
+                println!("{}", inspection.format_sections());
+            }
+            println!("{}", inspection.format());
+        }
+
+        other => {
+            anyhow::bail!(
+                "{} isn't available while attached to the daemon (use `homegpt chat --local`)",
+                other
+            );
+        }
+    }
+    Ok(true)
+}
@@ -6,11 +6,17 @@ use std::path::PathBuf;
 #[cfg(unix)]
 use daemonize::Daemonize;
 
+use homegpt::backup::run_backups;
+use homegpt::bridges::spawn_bridges;
 use homegpt::concurrency::TurnGate;
-use homegpt::config::Config;
+use homegpt::config::{parse_duration, Config};
 use homegpt::heartbeat::HeartbeatRunner;
-use homegpt::memory::MemoryManager;
+use homegpt::locale;
+use homegpt::memory::{MemoryManager, WorkspaceSync};
+use homegpt::presence::run_owntracks;
+use homegpt::reminders::run_reminders;
 use homegpt::server::Server;
+use homegpt::timers::run_timers;
 
 /// Synchronously stop the daemon (for use before Tokio runtime starts)
 pub fn stop_sync() -> Result<()> {
@@ -126,10 +132,8 @@ pub fn daemonize_and_run(agent_id: &str) -> Result<()> {
 async fn run_daemon_server(config: Config, agent_id: &str) -> Result<()> {
     // Initialize logging in the daemon process
     // Disable ANSI colors since we're writing to a file
-    tracing_subscriber::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::new("info"))
-        .with_ansi(false)
-        .init();
+    homegpt::logging::init(&config.logging, "info", false);
+    homegpt::locale::init(&config.locale);
 
     let memory = MemoryManager::new_with_full_config(&config.memory, Some(&config), agent_id)?;
     let _watcher = memory.start_watcher()?;
@@ -148,7 +152,7 @@ async fn run_daemon_server(config: Config, agent_id: &str) -> Result<()> {
 /// Run daemon services (server and/or heartbeat)
 async fn run_daemon_services(config: &Config, agent_id: &str) -> Result<()> {
     // Create shared turn gate for heartbeat + HTTP concurrency control
-    let turn_gate = TurnGate::new();
+    let turn_gate = TurnGate::with_limit(config.server.max_concurrent_turns);
 
     // Spawn heartbeat in background if enabled
     let heartbeat_handle = if config.heartbeat.enabled {
@@ -179,6 +183,100 @@ async fn run_daemon_services(config: &Config, agent_id: &str) -> Result<()> {
         None
     };
 
+    // Spawn workspace sync loop if enabled
+    let sync_handle = if config.memory.sync.enabled {
+        let interval = parse_duration(&config.memory.sync.interval)
+            .map_err(|e| anyhow::anyhow!("Invalid sync interval: {}", e))?;
+        let sync = WorkspaceSync::new(&config.workspace_path(), &config.memory.sync);
+        println!(
+            "  Workspace sync: enabled (interval: {})",
+            config.memory.sync.interval
+        );
+        Some(tokio::spawn(async move {
+            let sync = std::sync::Arc::new(sync);
+            loop {
+                let task_sync = sync.clone();
+                match tokio::task::spawn_blocking(move || task_sync.sync()).await {
+                    Ok(Ok(_)) => {}
+                    Ok(Err(e)) => tracing::warn!("Workspace sync failed: {}", e),
+                    Err(e) => tracing::error!("Workspace sync task panicked: {}", e),
+                }
+                tokio::time::sleep(interval).await;
+            }
+        }))
+    } else {
+        None
+    };
+
+    // Spawn scheduled backups if enabled
+    let backup_handle = if config.backup.enabled {
+        parse_duration(&config.backup.interval)
+            .map_err(|e| anyhow::anyhow!("Invalid backup interval: {}", e))?;
+        let backup_config = config.clone();
+        let backup_agent_id = agent_id.to_string();
+        println!(
+            "  Backups: enabled (interval: {}, keep: {})",
+            config.backup.interval, config.backup.keep
+        );
+        Some(tokio::spawn(async move {
+            if let Err(e) = run_backups(&backup_config, &backup_agent_id).await {
+                tracing::error!("Backups error: {}", e);
+            }
+        }))
+    } else {
+        None
+    };
+
+    // Spawn OwnTracks listener if configured
+    let presence_handle = if let Some(ref owntracks) = config.presence.owntracks {
+        let presence_config = config.clone();
+        let state_dir = homegpt::agent::get_state_dir()?;
+        println!(
+            "  Presence: OwnTracks ({}:{})",
+            owntracks.host, owntracks.port
+        );
+        Some(tokio::spawn(async move {
+            if let Err(e) = run_owntracks(&presence_config, &state_dir).await {
+                tracing::error!("OwnTracks listener error: {}", e);
+            }
+        }))
+    } else {
+        None
+    };
+
+    // Send reminders if there is anywhere to send them
+    let reminders_handle =
+        if config.notifications.channels.is_empty() && config.webhooks.outbound.is_empty() {
+            None
+        } else {
+            let reminders_config = config.clone();
+            let state_dir = homegpt::agent::get_state_dir()?;
+            println!("  Reminders: enabled");
+            Some(tokio::spawn(async move {
+                if let Err(e) = run_reminders(&reminders_config, &state_dir).await {
+                    tracing::error!("Reminders error: {}", e);
+                }
+            }))
+        };
+
+    // Fire kitchen timers (spoken and/or pushed)
+    let timers_config = config.clone();
+    let timers_state_dir = homegpt::agent::get_state_dir()?;
+    let timers_handle = tokio::spawn(async move {
+        if let Err(e) = run_timers(&timers_config, &timers_state_dir).await {
+            tracing::error!("Timers error: {}", e);
+        }
+    });
+
+    // Spawn chat bridges (Matrix, Discord) if configured
+    let bridge_handles = spawn_bridges(config, &turn_gate)?;
+    if config.bridges.matrix.is_some() {
+        println!("  Matrix bridge: enabled");
+    }
+    if config.bridges.discord.is_some() {
+        println!("  Discord bridge: enabled");
+    }
+
     // Run server or wait for shutdown
     if config.server.enabled {
         println!(
@@ -196,10 +294,26 @@ async fn run_daemon_services(config: &Config, agent_id: &str) -> Result<()> {
         tokio::signal::ctrl_c().await?;
     }
 
-    // Abort heartbeat task on shutdown
+    // Abort background tasks on shutdown
     if let Some(handle) = heartbeat_handle {
         handle.abort();
     }
+    if let Some(handle) = sync_handle {
+        handle.abort();
+    }
+    if let Some(handle) = backup_handle {
+        handle.abort();
+    }
+    if let Some(handle) = presence_handle {
+        handle.abort();
+    }
+    if let Some(handle) = reminders_handle {
+        handle.abort();
+    }
+    timers_handle.abort();
+    for handle in bridge_handles {
+        handle.abort();
+    }
 
     Ok(())
 }
@@ -217,6 +331,11 @@ pub enum DaemonCommands {
         /// Run in foreground (don't daemonize)
         #[arg(short, long)]
         foreground: bool,
+
+        /// Log to the dated daemon log instead of the console, as the
+        /// service does
+        #[arg(long, requires = "foreground")]
+        log_file: bool,
     },
 
     /// Stop the daemon
@@ -238,7 +357,7 @@ pub enum DaemonCommands {
 
 pub async fn run(args: DaemonArgs, agent_id: &str) -> Result<()> {
     match args.command {
-        DaemonCommands::Start { foreground } => start_daemon(foreground, agent_id).await,
+        DaemonCommands::Start { foreground, .. } => start_daemon(foreground, agent_id).await,
         DaemonCommands::Stop => stop_daemon().await,
         DaemonCommands::Restart { foreground } => restart_daemon(foreground, agent_id).await,
         DaemonCommands::Status => show_status().await,
@@ -406,6 +525,7 @@ async fn show_status() -> Result<()> {
     if config.heartbeat.enabled {
         println!("  Heartbeat interval: {}", config.heartbeat.interval);
     }
+    println!("  Workspace sync enabled: {}", config.memory.sync.enabled);
     println!("  Server enabled: {}", config.server.enabled);
     if config.server.enabled {
         println!(
@@ -433,53 +553,23 @@ async fn run_heartbeat_once(agent_id: &str) -> Result<()> {
     Ok(())
 }
 
-fn get_pid_file() -> Result<PathBuf> {
+pub fn get_pid_file() -> Result<PathBuf> {
     // Put PID file in state dir (~/.homegpt/), not workspace
     let state_dir = homegpt::agent::get_state_dir()?;
     Ok(state_dir.join("daemon.pid"))
 }
 
 fn get_log_file(retention_days: u32) -> Result<PathBuf> {
-    let state_dir = homegpt::agent::get_state_dir()?;
-    let logs_dir = state_dir.join("logs");
+    let logs_dir = homegpt::logging::logs_dir()?;
     fs::create_dir_all(&logs_dir)?;
-
-    // Prune old logs only if retention_days > 0
-    if retention_days > 0 {
-        prune_old_logs(&logs_dir, retention_days as i64);
-    }
+    homegpt::logging::prune_logs(&logs_dir, "homegpt-", ".log", retention_days);
 
     // Use date-based log files (like OpenClaw)
-    let date = chrono::Local::now().format("%Y-%m-%d");
+    let date = locale::now().format("%Y-%m-%d");
     Ok(logs_dir.join(format!("homegpt-{}.log", date)))
 }
 
-/// Prune log files older than `keep_days` days
-fn prune_old_logs(logs_dir: &std::path::Path, keep_days: i64) {
-    let cutoff = chrono::Local::now() - chrono::Duration::days(keep_days);
-    let cutoff_date = cutoff.format("%Y-%m-%d").to_string();
-
-    if let Ok(entries) = fs::read_dir(logs_dir) {
-        for entry in entries.flatten() {
-            let name = entry.file_name();
-            let name_str = name.to_string_lossy();
-
-            // Match homegpt-YYYY-MM-DD.log pattern
-            if name_str.starts_with("homegpt-") && name_str.ends_with(".log") {
-                if let Some(date_part) = name_str
-                    .strip_prefix("homegpt-")
-                    .and_then(|s| s.strip_suffix(".log"))
-                {
-                    if date_part < cutoff_date.as_str() {
-                        let _ = fs::remove_file(entry.path());
-                    }
-                }
-            }
-        }
-    }
-}
-
-fn is_process_running(pid: &str) -> bool {
+pub fn is_process_running(pid: &str) -> bool {
     let pid = pid.trim();
 
     #[cfg(unix)]
@@ -5,11 +5,16 @@ use clap::Args;
 
 #[derive(Args)]
 pub struct DesktopArgs {
-    // No additional args needed for now
-    // Could add --theme, --size, etc. in future
+    /// Run the agent in this process even if the daemon is running
+    #[arg(long)]
+    pub local: bool,
+
+    /// Full screen, chat only, with large buttons (e.g. a kitchen tablet)
+    #[arg(long)]
+    pub kiosk: bool,
 }
 
-pub fn run(_args: DesktopArgs, agent_id: &str) -> Result<()> {
+pub fn run(args: DesktopArgs, agent_id: &str) -> Result<()> {
     use homegpt::desktop::DesktopApp;
 
     let native_options = eframe::NativeOptions {
@@ -21,11 +26,21 @@ pub fn run(_args: DesktopArgs, agent_id: &str) -> Result<()> {
     };
 
     let agent_id = agent_id.to_string();
+    // The daemon's agents don't know about this process's --dry-run
+    let local = args.local || homegpt::agent::dry_run_default();
+    let kiosk = args.kiosk;
 
     eframe::run_native(
         "HomeGPT",
         native_options,
-        Box::new(move |cc| Ok(Box::new(DesktopApp::new(cc, Some(agent_id.clone()))))),
+        Box::new(move |cc| {
+            Ok(Box::new(DesktopApp::new(
+                cc,
+                Some(agent_id.clone()),
+                local,
+                kiosk,
+            )))
+        }),
     )
     .map_err(|e| anyhow::anyhow!("Failed to run desktop app: {}", e))
 }
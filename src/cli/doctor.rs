@@ -0,0 +1,682 @@
+use anyhow::Result;
+use clap::Args;
+use reqwest::StatusCode;
+use rusqlite::{Connection, OpenFlags};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use homegpt::agent::{
+    cached_capabilities, create_provider, get_state_dir, ApprovalPolicy, Redactor, RemoteApprover,
+    SafetyFilter,
+};
+use homegpt::client::DaemonClient;
+use homegpt::config::{parse_duration, Config};
+use homegpt::locale::{parse_timezone, Language};
+use homegpt::memory::{ChunkOptions, EmbeddingStorage, MemoryIndex, MemoryManager};
+use homegpt::quiet::QuietPolicy;
+
+use super::daemon::{get_pid_file, is_process_running};
+
+/// How long each provider API gets to answer
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Workspace files the agent reads on every turn
+const WORKSPACE_FILES: [&str; 3] = ["MEMORY.md", "SOUL.md", "HEARTBEAT.md"];
+
+#[derive(Args)]
+pub struct DoctorArgs {
+    /// Skip checks that call provider APIs or load the embedding model
+    #[arg(long)]
+    pub offline: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Status {
+    Ok,
+    Warn,
+    Fail,
+}
+
+struct Check {
+    name: String,
+    status: Status,
+    detail: String,
+    /// How to fix it (may span several lines)
+    hint: Option<String>,
+}
+
+impl Check {
+    fn ok(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: Status::Ok,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn warn(name: impl Into<String>, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: Status::Warn,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn fail(name: impl Into<String>, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: Status::Fail,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn print(&self) {
+        let label = match self.status {
+            Status::Ok => "ok",
+            Status::Warn => "warn",
+            Status::Fail => "FAIL",
+        };
+        println!("[{:<4}] {}: {}", label, self.name, self.detail);
+        if let Some(ref hint) = self.hint {
+            for line in hint.lines() {
+                println!("       {}", line);
+            }
+        }
+    }
+}
+
+pub async fn run(args: DoctorArgs, agent_id: &str) -> Result<()> {
+    println!("HomeGPT v{} doctor\n", env!("CARGO_PKG_VERSION"));
+
+    let mut checks = Vec::new();
+    if let Some(config) = check_config(&mut checks)? {
+        check_models(&config, &mut checks);
+        if !args.offline {
+            check_providers(&config, &mut checks).await;
+        }
+        let (workspace, db_path) = MemoryManager::storage_paths(&config.memory, agent_id)?;
+        check_workspace(&workspace, &mut checks);
+        check_databases(&db_path, &mut checks)?;
+        if args.offline {
+            checks.push(Check::ok("Embeddings", "skipped (--offline)"));
+        } else {
+            check_embeddings(&config, &workspace, &db_path, &mut checks).await;
+        }
+        check_daemon(&config, &mut checks).await?;
+    }
+
+    for check in &checks {
+        check.print();
+    }
+
+    let failures = checks.iter().filter(|c| c.status == Status::Fail).count();
+    let warnings = checks.iter().filter(|c| c.status == Status::Warn).count();
+    println!();
+    if failures == 0 && warnings == 0 {
+        println!("All checks passed");
+        return Ok(());
+    }
+    println!("{} problem(s), {} warning(s)", failures, warnings);
+    if failures > 0 {
+        anyhow::bail!("homegpt doctor found {} problem(s)", failures);
+    }
+    Ok(())
+}
+
+/// Parse the config file without creating it, then check values that are
+/// only parsed when used
+fn check_config(checks: &mut Vec<Check>) -> Result<Option<Config>> {
+    let path = Config::config_path()?;
+    if !path.exists() {
+        checks.push(Check::warn(
+            "Config",
+            format!("no config file at {}; using defaults", path.display()),
+            "Run `homegpt config init` to create one",
+        ));
+        return Ok(Some(Config::default()));
+    }
+
+    let content = fs::read_to_string(&path)?;
+    if let Err(e) = toml::from_str::<Config>(&content) {
+        checks.push(Check::fail(
+            "Config",
+            format!("{} is invalid", path.display()),
+            format!(
+                "{}\nFix the file, or move it aside and run `homegpt config init`",
+                e.to_string().trim_end()
+            ),
+        ));
+        return Ok(None);
+    }
+    let config = Config::load()?;
+
+    let mut problems = Vec::new();
+    if let Err(e) = parse_duration(&config.heartbeat.interval) {
+        problems.push(format!("heartbeat.interval: {}", e));
+    }
+    if let Err(e) = parse_duration(&config.memory.sync.interval) {
+        problems.push(format!("memory.sync.interval: {}", e));
+    }
+    if let Err(e) = ChunkOptions::from_config(&config.memory) {
+        problems.push(format!("memory chunking: {}", e));
+    }
+    if let Err(e) = config.memory.embedding_storage.parse::<EmbeddingStorage>() {
+        problems.push(format!("memory.embedding_storage: {}", e));
+    }
+    for language in std::iter::once(&config.locale.language).chain(&config.locale.languages) {
+        if let Err(e) = language.parse::<Language>() {
+            problems.push(format!("locale: {}", e));
+        }
+    }
+    if let Err(e) = parse_timezone(&config.locale.timezone) {
+        problems.push(format!("locale.timezone: {}", e));
+    }
+    if let Err(e) = QuietPolicy::from_config(&config) {
+        problems.push(e.to_string());
+    }
+    if let Err(e) = ApprovalPolicy::from_config(&config) {
+        problems.push(e.to_string());
+    }
+    if let Err(e) = RemoteApprover::from_config(&config) {
+        problems.push(e.to_string());
+    }
+    if let Err(e) = Redactor::from_config(&config.redaction) {
+        problems.push(format!("redaction: {}", e));
+    }
+    if let Err(e) = SafetyFilter::from_config(&config) {
+        problems.push(e.to_string());
+    }
+    for (setting, key) in api_keys(&config) {
+        // Left as written when the variable isn't set
+        if key.starts_with('$') {
+            let var = key
+                .trim_start_matches('$')
+                .trim_matches(|c| c == '{' || c == '}');
+            problems.push(format!("{}: {} is not set", setting, var));
+        } else if key.is_empty() {
+            problems.push(format!("{} is empty", setting));
+        }
+    }
+
+    if problems.is_empty() {
+        checks.push(Check::ok("Config", path.display().to_string()));
+    }
+    for problem in problems {
+        checks.push(Check::fail(
+            "Config",
+            problem,
+            format!("Edit {}", path.display()),
+        ));
+    }
+    Ok(Some(config))
+}
+
+/// (setting, value) of each configured provider API key
+fn api_keys(config: &Config) -> Vec<(&'static str, &str)> {
+    let providers = &config.providers;
+    let mut keys = Vec::new();
+    if let Some(ref openai) = providers.openai {
+        keys.push(("providers.openai.api_key", openai.api_key.as_str()));
+    }
+    if let Some(ref anthropic) = providers.anthropic {
+        keys.push(("providers.anthropic.api_key", anthropic.api_key.as_str()));
+    }
+    if let Some(ref openrouter) = providers.openrouter {
+        keys.push(("providers.openrouter.api_key", openrouter.api_key.as_str()));
+    }
+    if let Some(ref gemini) = providers.gemini {
+        keys.push(("providers.gemini.api_key", gemini.api_key.as_str()));
+    }
+    if let Some(ref groq) = providers.groq {
+        keys.push(("providers.groq.api_key", groq.api_key.as_str()));
+    }
+    if let Some(ref voyage) = providers.voyage {
+        keys.push(("providers.voyage.api_key", voyage.api_key.as_str()));
+    }
+    keys
+}
+
+/// Each configured model must resolve to a configured provider
+fn check_models(config: &Config, checks: &mut Vec<Check>) {
+    let agent = &config.agent;
+    let mut models = vec![("agent.default_model", agent.default_model.as_str())];
+    if config.heartbeat.enabled {
+        models.push(("agent.heartbeat_model", agent.heartbeat_model()));
+    }
+    if let Some(ref model) = agent.summary_model {
+        models.push(("agent.summary_model", model));
+    }
+    if let Some(ref model) = agent.vision_model {
+        models.push(("agent.vision_model", model));
+    }
+    models.dedup_by_key(|(_, model)| *model);
+
+    for (setting, model) in models {
+        match create_provider(model, config) {
+            Ok(_) => {
+                // What an earlier turn found the model can't do
+                let limits = cached_capabilities(model)
+                    .map(|c| c.limits())
+                    .filter(|limits| !limits.is_empty())
+                    .map(|limits| format!("; {}", limits.join(", ")))
+                    .unwrap_or_default();
+                checks.push(Check::ok(
+                    "Model",
+                    format!("{} ({}{})", model, setting, limits),
+                ));
+            }
+            Err(e) => {
+                let message = e.to_string();
+                let (first, rest) = message.split_once('\n').unwrap_or((&message, ""));
+                let hint = match rest.trim() {
+                    "" => format!("Set {} to a supported model", setting),
+                    rest => rest.to_string(),
+                };
+                checks.push(Check::fail("Model", format!("{}: {}", model, first), hint));
+            }
+        }
+    }
+}
+
+/// Call a cheap authenticated endpoint of each configured provider
+async fn check_providers(config: &Config, checks: &mut Vec<Check>) {
+    let http = reqwest::Client::new();
+    let providers = &config.providers;
+    let usable = |key: &str| !key.is_empty() && !key.starts_with('$');
+
+    if let Some(ref openai) = providers.openai {
+        if usable(&openai.api_key) {
+            let request = http
+                .get(format!("{}/models", openai.base_url))
+                .bearer_auth(&openai.api_key);
+            checks.push(probe("OpenAI", request, "providers.openai").await);
+        }
+    }
+    if let Some(ref anthropic) = providers.anthropic {
+        if usable(&anthropic.api_key) {
+            let request = http
+                .get(format!("{}/v1/models", anthropic.base_url))
+                .header("x-api-key", &anthropic.api_key)
+                .header("anthropic-version", "2023-06-01");
+            checks.push(probe("Anthropic", request, "providers.anthropic").await);
+        }
+    }
+    if let Some(ref openrouter) = providers.openrouter {
+        if usable(&openrouter.api_key) {
+            let request = http
+                .get(format!("{}/key", openrouter.base_url))
+                .bearer_auth(&openrouter.api_key);
+            checks.push(probe("OpenRouter", request, "providers.openrouter").await);
+        }
+    }
+    if let Some(ref gemini) = providers.gemini {
+        if usable(&gemini.api_key) {
+            let request = http
+                .get(format!("{}/models", gemini.base_url))
+                .query(&[("key", &gemini.api_key)]);
+            checks.push(probe("Gemini", request, "providers.gemini").await);
+        }
+    }
+    if let Some(ref groq) = providers.groq {
+        if usable(&groq.api_key) {
+            let request = http
+                .get(format!("{}/models", groq.base_url))
+                .bearer_auth(&groq.api_key);
+            checks.push(probe("Groq", request, "providers.groq").await);
+        }
+    }
+    if let Some(ref ollama) = providers.ollama {
+        checks.push(check_ollama(&http, config, &ollama.endpoint).await);
+    }
+    if let Some(ref claude_cli) = providers.claude_cli {
+        let version = tokio::process::Command::new(&claude_cli.command)
+            .arg("--version")
+            .output()
+            .await;
+        checks.push(match version {
+            Ok(output) if output.status.success() => Check::ok(
+                "Claude CLI",
+                String::from_utf8_lossy(&output.stdout).trim().to_string(),
+            ),
+            Ok(output) => Check::fail(
+                "Claude CLI",
+                format!(
+                    "`{} --version` exited with {}",
+                    claude_cli.command, output.status
+                ),
+                "Run `claude` once to log in, or check providers.claude_cli.command",
+            ),
+            Err(e) => Check::fail(
+                "Claude CLI",
+                format!("`{}` not found: {}", claude_cli.command, e),
+                "Install Claude Code, or set providers.claude_cli.command to its path",
+            ),
+        });
+    }
+}
+
+async fn probe(name: &str, request: reqwest::RequestBuilder, section: &str) -> Check {
+    match request.timeout(PROBE_TIMEOUT).send().await {
+        Ok(response) if response.status().is_success() => {
+            Check::ok(name, "reachable, API key accepted")
+        }
+        Ok(response)
+            if response.status() == StatusCode::UNAUTHORIZED
+                || response.status() == StatusCode::FORBIDDEN =>
+        {
+            Check::fail(
+                name,
+                format!("API key rejected ({})", response.status()),
+                format!("Check {}.api_key", section),
+            )
+        }
+        Ok(response) => Check::warn(
+            name,
+            format!("API returned {}", response.status()),
+            format!("Check {}.base_url, or try again later", section),
+        ),
+        Err(e) => Check::fail(
+            name,
+            format!("unreachable: {}", e),
+            format!("Check the network connection and {}.base_url", section),
+        ),
+    }
+}
+
+/// Ollama must be running, with the default model pulled if it's an
+/// Ollama model
+async fn check_ollama(http: &reqwest::Client, config: &Config, endpoint: &str) -> Check {
+    let response = http
+        .get(format!("{}/api/tags", endpoint))
+        .timeout(PROBE_TIMEOUT)
+        .send()
+        .await;
+    let tags: serde_json::Value = match response {
+        Ok(response) if response.status().is_success() => response.json().await.unwrap_or_default(),
+        Ok(response) => {
+            return Check::fail(
+                "Ollama",
+                format!("{} returned {}", endpoint, response.status()),
+                "Check providers.ollama.endpoint",
+            )
+        }
+        Err(e) => {
+            return Check::fail(
+                "Ollama",
+                format!("not reachable at {}: {}", endpoint, e),
+                "Start Ollama (`ollama serve`), or check providers.ollama.endpoint",
+            )
+        }
+    };
+
+    let pulled: Vec<&str> = tags["models"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|m| m["name"].as_str())
+        .collect();
+    if let Some(model) = config.agent.default_model.strip_prefix("ollama/") {
+        // `llama3` is pulled as `llama3:latest`
+        let found = pulled
+            .iter()
+            .any(|name| *name == model || name.strip_suffix(":latest") == Some(model));
+        if !found {
+            return Check::fail(
+                "Ollama",
+                format!("model {} is not pulled", model),
+                format!("Run `ollama pull {}`", model),
+            );
+        }
+    }
+    Check::ok("Ollama", format!("{} models at {}", pulled.len(), endpoint))
+}
+
+fn check_workspace(workspace: &Path, checks: &mut Vec<Check>) {
+    let metadata = match fs::metadata(workspace) {
+        Ok(metadata) => metadata,
+        Err(_) => {
+            checks.push(Check::fail(
+                "Workspace",
+                format!("{} does not exist", workspace.display()),
+                "It's created the next time HomeGPT starts; check memory.workspace if it should already exist",
+            ));
+            return;
+        }
+    };
+    if metadata.permissions().readonly() {
+        checks.push(Check::fail(
+            "Workspace",
+            format!("{} is read-only", workspace.display()),
+            "The agent writes memory there; fix the directory permissions",
+        ));
+        return;
+    }
+
+    let missing: Vec<&str> = WORKSPACE_FILES
+        .into_iter()
+        .filter(|file| !workspace.join(file).exists())
+        .collect();
+    if missing.is_empty() {
+        checks.push(Check::ok("Workspace", workspace.display().to_string()));
+    } else {
+        checks.push(Check::warn(
+            "Workspace",
+            format!(
+                "{} missing from {}",
+                missing.join(", "),
+                workspace.display()
+            ),
+            "Missing files are recreated from memory.template_pack the next time HomeGPT starts",
+        ));
+    }
+}
+
+/// Run SQLite's quick_check on the index and the stores in the state dir
+fn check_databases(index_path: &Path, checks: &mut Vec<Check>) -> Result<()> {
+    if !index_path.exists() {
+        checks.push(Check::warn(
+            "Memory index",
+            format!("{} does not exist yet", index_path.display()),
+            "Run `homegpt memory reindex`",
+        ));
+    } else {
+        checks.push(match quick_check(index_path) {
+            Ok(()) => Check::ok("Memory index", index_path.display().to_string()),
+            Err(e) => Check::fail(
+                "Memory index",
+                format!("{}: {}", index_path.display(), e),
+                format!(
+                    "The index is rebuilt from the workspace: stop the daemon, move {} aside and run `homegpt memory reindex`",
+                    index_path.display()
+                ),
+            ),
+        });
+    }
+
+    let state_dir = get_state_dir()?;
+    let mut stores: Vec<PathBuf> = fs::read_dir(&state_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "db"))
+        .collect();
+    stores.sort();
+    for path in stores {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        checks.push(match quick_check(&path) {
+            Ok(()) => Check::ok("Database", name),
+            Err(e) => Check::fail(
+                "Database",
+                format!("{}: {}", path.display(), e),
+                format!(
+                    "Restore {} from a backup, or move it aside to start empty",
+                    name
+                ),
+            ),
+        });
+    }
+    Ok(())
+}
+
+fn quick_check(path: &Path) -> Result<()> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut stmt = conn.prepare("PRAGMA quick_check")?;
+    let errors: Vec<String> = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+        .into_iter()
+        .filter(|line| line != "ok")
+        .collect();
+    match errors.first() {
+        None => Ok(()),
+        Some(first) if errors.len() == 1 => anyhow::bail!("corrupt ({})", first),
+        Some(first) => anyhow::bail!("corrupt ({} and {} more)", first, errors.len() - 1),
+    }
+}
+
+/// Load the embedding provider, embed a probe text, and compare its
+/// dimensions with the embeddings already in the index
+async fn check_embeddings(
+    config: &Config,
+    workspace: &Path,
+    index_path: &Path,
+    checks: &mut Vec<Check>,
+) {
+    let setting = &config.memory.embedding_provider;
+    if setting == "none" {
+        checks.push(Check::ok("Embeddings", "disabled; keyword search only"));
+        return;
+    }
+    let Some(provider) = MemoryManager::create_embedding_provider(&config.memory, Some(config))
+    else {
+        checks.push(Check::fail(
+            "Embeddings",
+            format!("could not load the {} embedding provider", setting),
+            "See the warning above. Check memory.embedding_model, or set memory.embedding_provider = \"none\"",
+        ));
+        return;
+    };
+
+    let model = provider.model().to_string();
+    let dims = match provider.embed_batch(&["homegpt doctor".to_string()]).await {
+        Ok(embeddings) => embeddings.first().map_or(0, |e| e.len()),
+        Err(e) => {
+            checks.push(Check::fail(
+                "Embeddings",
+                format!("{} failed: {}", model, e),
+                "Check the provider's API key and memory.embedding_model",
+            ));
+            return;
+        }
+    };
+    if dims != provider.dimensions() {
+        checks.push(Check::warn(
+            "Embeddings",
+            format!(
+                "{} returned {} dimensions, expected {}",
+                model,
+                dims,
+                provider.dimensions()
+            ),
+            "Check memory.embedding_dimensions",
+        ));
+    } else {
+        checks.push(Check::ok(
+            "Embeddings",
+            format!("{} ({} dimensions)", model, dims),
+        ));
+    }
+
+    if !index_path.exists() {
+        return;
+    }
+    let stored = MemoryIndex::new_with_db_path(workspace, index_path)
+        .and_then(|index| index.embedding_dimensions());
+    match stored {
+        Ok(stored) => {
+            for (stored_model, stored_dims, chunks) in stored {
+                if stored_model != model || stored_dims != dims {
+                    checks.push(Check::fail(
+                        "Embeddings",
+                        format!(
+                            "{} chunks in the index are from {} ({} dimensions)",
+                            chunks, stored_model, stored_dims
+                        ),
+                        "Semantic search skips them. Run `homegpt memory reindex --force` to re-embed",
+                    ));
+                }
+            }
+        }
+        Err(e) => checks.push(Check::fail(
+            "Embeddings",
+            format!("could not read index embeddings: {}", e),
+            "See the memory index check above",
+        )),
+    }
+}
+
+async fn check_daemon(config: &Config, checks: &mut Vec<Check>) -> Result<()> {
+    let pid_file = get_pid_file()?;
+    let pid = fs::read_to_string(&pid_file)
+        .ok()
+        .map(|pid| pid.trim().to_string());
+    let running = pid.as_deref().is_some_and(is_process_running);
+
+    let address = format!("{}:{}", config.server.bind, config.server.port);
+    let check = match DaemonClient::connect(config).await {
+        Some(client) => match client.status().await {
+            Ok(status) if status.version != env!("CARGO_PKG_VERSION") => Check::warn(
+                "Daemon",
+                format!(
+                    "running v{}, this binary is v{}",
+                    status.version,
+                    env!("CARGO_PKG_VERSION")
+                ),
+                "Run `homegpt daemon restart` to pick up the new version",
+            ),
+            Ok(status) => Check::ok(
+                "Daemon",
+                format!(
+                    "running at {} ({}, {} sessions)",
+                    address, status.model, status.active_sessions
+                ),
+            ),
+            Err(e) => Check::warn(
+                "Daemon",
+                format!("answering at {} but status failed: {}", address, e),
+                "Check the daemon log in ~/.homegpt/logs",
+            ),
+        },
+        None if running && config.server.enabled => Check::warn(
+            "Daemon",
+            format!(
+                "running (PID {}) but not answering at {}",
+                pid.unwrap_or_default(),
+                address
+            ),
+            "Commands run in-process meanwhile. Check [server] bind/port and the daemon log in ~/.homegpt/logs",
+        ),
+        None if running => Check::ok(
+            "Daemon",
+            "running with the HTTP API disabled; commands run in-process",
+        ),
+        None if pid.is_some() => Check::warn(
+            "Daemon",
+            "not running, but a PID file was left behind",
+            format!(
+                "Remove {} or run `homegpt daemon start`",
+                pid_file.display()
+            ),
+        ),
+        None => Check::ok("Daemon", "not running; commands run in-process"),
+    };
+    checks.push(check);
+    Ok(())
+}
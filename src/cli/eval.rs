@@ -0,0 +1,88 @@
+use anyhow::{Context, Result};
+use clap::Args;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use homegpt::config::Config;
+use homegpt::memory::eval::{EvalReport, EvalSet, DEFAULT_K};
+use homegpt::memory::MemoryManager;
+
+#[derive(Args)]
+pub struct EvalArgs {
+    /// YAML file of questions and the results they should find
+    pub file: PathBuf,
+
+    /// Results per question to score (default: the file's `k`, or 5)
+    #[arg(short, long)]
+    pub k: Option<usize>,
+
+    /// Save the report as JSON, to compare a later run against
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Show the change from a report saved with --output
+    #[arg(short, long)]
+    pub baseline: Option<PathBuf>,
+
+    /// List questions that missed expected results
+    #[arg(long)]
+    pub misses: bool,
+}
+
+pub async fn run(args: EvalArgs, agent_id: &str) -> Result<()> {
+    let config = Config::load()?;
+    let set = EvalSet::load(&args.file)?;
+    let k = args.k.or(set.k).unwrap_or(DEFAULT_K).max(1);
+
+    let baseline: Option<EvalReport> = match args.baseline {
+        Some(ref path) => {
+            let content = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read baseline {}", path.display()))?;
+            Some(serde_json::from_str(&content).context("Invalid baseline report")?)
+        }
+        None => None,
+    };
+
+    let memory = MemoryManager::new_with_full_config(&config.memory, Some(&config), agent_id)?;
+    // Score with semantic search, not FTS alone
+    while memory.embeddings_loading() {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    if !memory.has_embeddings() {
+        println!("No embedding provider; scoring keyword search only.\n");
+    }
+
+    let report = memory.evaluate(&set, k)?;
+    if let Some(ref baseline) = baseline {
+        if baseline.k != report.k {
+            println!(
+                "Note: baseline was scored at k={}, this run at k={}\n",
+                baseline.k, report.k
+            );
+        }
+    }
+    print!("{}", report.summary(baseline.as_ref()));
+
+    if args.misses {
+        let misses: Vec<_> = report
+            .results
+            .iter()
+            .filter(|r| !r.missed.is_empty())
+            .collect();
+        if !misses.is_empty() {
+            println!("\nMissed:");
+            for result in misses {
+                println!("  [{}] {}", result.category, result.question);
+                for expected in &result.missed {
+                    println!("      {}", expected);
+                }
+            }
+        }
+    }
+
+    if let Some(path) = args.output {
+        std::fs::write(&path, serde_json::to_string_pretty(&report)?)?;
+        println!("\nSaved report to {}", path.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,112 @@
+use anyhow::Result;
+use clap::{Args, Subcommand};
+use std::path::PathBuf;
+
+use homegpt::config::Config;
+use homegpt::finance::{Categorizer, FinanceLedger, ImportOptions};
+use homegpt::locale;
+use homegpt::memory::MemoryManager;
+
+#[derive(Args)]
+pub struct FinanceArgs {
+    #[command(subcommand)]
+    pub command: FinanceCommands,
+}
+
+#[derive(Subcommand)]
+pub enum FinanceCommands {
+    /// Import a bank or credit card CSV export
+    Import {
+        /// CSV file(s) to import
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+
+        /// Account name stored with the transactions (default: file name)
+        #[arg(short, long)]
+        account: Option<String>,
+
+        /// Flip amount signs (card exports that list purchases as positive)
+        #[arg(short, long)]
+        invert: bool,
+
+        /// Only use finance.rules, not the LLM, to categorize
+        #[arg(long)]
+        no_llm: bool,
+    },
+
+    /// Show spending against the budgets for a month
+    Status {
+        /// Month as YYYY-MM (default: current month)
+        #[arg(short, long)]
+        month: Option<String>,
+    },
+
+    /// Print the report for a month (default: last month)
+    Report {
+        /// Month as YYYY-MM
+        #[arg(short, long)]
+        month: Option<String>,
+    },
+}
+
+pub async fn run(args: FinanceArgs, agent_id: &str) -> Result<()> {
+    let config = Config::load()?;
+    let ledger = FinanceLedger::new(&config.workspace_path(), &config.finance);
+
+    match args.command {
+        FinanceCommands::Import {
+            files,
+            account,
+            invert,
+            no_llm,
+        } => {
+            let categorizer = if no_llm {
+                Categorizer::rules_only(&config.finance.rules)
+            } else {
+                Categorizer::from_config(&config)
+            };
+            let memory =
+                MemoryManager::new_with_full_config(&config.memory, Some(&config), agent_id)?;
+
+            for file in files {
+                let options = ImportOptions {
+                    account: account.clone().unwrap_or_else(|| {
+                        file.file_stem()
+                            .map(|s| s.to_string_lossy().to_string())
+                            .unwrap_or_default()
+                    }),
+                    invert,
+                    date_format: None,
+                };
+                let summary = ledger.import(&file, &options, &categorizer).await?;
+                println!("{}: {}", file.display(), summary.summary());
+
+                for month in &summary.months {
+                    memory.index_written_file(&ledger.summary_path(month), "finance import")?;
+                }
+            }
+            Ok(())
+        }
+        FinanceCommands::Status { month } => {
+            let month = month.unwrap_or_else(|| locale::now().format("%Y-%m").to_string());
+            let summary = ledger.summary(&month)?;
+            if summary.transactions == 0 {
+                println!("No transactions imported for {}", month);
+            } else {
+                println!("{}", summary.status_text(&config.finance));
+            }
+            Ok(())
+        }
+        FinanceCommands::Report { month } => {
+            let month = match month {
+                Some(month) => month,
+                None => {
+                    homegpt::finance::previous_month(&locale::now().format("%Y-%m").to_string())
+                        .unwrap_or_default()
+                }
+            };
+            println!("{}", ledger.report(&month)?);
+            Ok(())
+        }
+    }
+}
@@ -1,8 +1,16 @@
 use anyhow::Result;
 use clap::{Args, Subcommand};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
+use homegpt::concurrency::WorkspaceLock;
 use homegpt::config::Config;
-use homegpt::memory::MemoryManager;
+use homegpt::locale;
+use homegpt::memory::frontmatter::split_frontmatter;
+use homegpt::memory::{
+    AuditFilter, MemoryChunk, MemoryManager, OpenClawReport, Provenance, SearchMode, VerifiedChunk,
+    WorkspaceSync, REMEMBERED_PATH,
+};
 
 #[derive(Args)]
 pub struct MemoryArgs {
@@ -20,6 +28,33 @@ pub enum MemoryCommands {
         /// Maximum number of results
         #[arg(short, long, default_value = "10")]
         limit: usize,
+
+        /// Only search files with this frontmatter category (repeatable).
+        /// Equivalent to adding `category:<name>` to the query.
+        #[arg(short, long)]
+        category: Vec<String>,
+
+        /// Vector search only (needs an embedding provider)
+        #[arg(long, group = "mode")]
+        semantic: bool,
+
+        /// Full-text search only
+        #[arg(long, group = "mode")]
+        keyword: bool,
+
+        /// Full-text and vector search combined (default)
+        #[arg(long, group = "mode")]
+        hybrid: bool,
+    },
+
+    /// Index memory files, all of them or only those under PATH
+    Index {
+        /// File or directory to index (default: everything)
+        path: Option<PathBuf>,
+
+        /// Reindex files even if unchanged
+        #[arg(short, long)]
+        force: bool,
     },
 
     /// Reindex all memory files
@@ -29,6 +64,12 @@ pub enum MemoryCommands {
         force: bool,
     },
 
+    /// Check chunks against their verification hashes, or show one citation
+    Verify {
+        /// Hash prefix or [VERIFIED:...] citation to check (default: all chunks)
+        citation: Option<String>,
+    },
+
     /// Show memory statistics
     Stats,
 
@@ -38,54 +79,365 @@ pub enum MemoryCommands {
         #[arg(short, long, default_value = "10")]
         count: usize,
     },
+
+    /// Sync the workspace with the configured git remote
+    Sync,
+
+    /// Find near-duplicate chunks and optionally merge them
+    Consolidate {
+        /// Rewrite files (default is a dry run that only reports)
+        #[arg(long)]
+        apply: bool,
+
+        /// Cosine similarity threshold (default: memory.consolidation_threshold)
+        #[arg(short, long)]
+        threshold: Option<f32>,
+    },
+
+    /// Permanently delete memory matching a pattern or chunk hash prefix
+    Forget {
+        /// Text to forget, or a hash prefix from a [VERIFIED:...] citation
+        target: String,
+
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Export the workspace and index (with embeddings) to a .tar.zst archive
+    Export {
+        /// Output file (e.g. memory.tar.zst)
+        output: PathBuf,
+    },
+
+    /// Import an archive created by `memory export`
+    Import {
+        /// Archive file
+        archive: PathBuf,
+
+        /// Overwrite workspace files that differ from the archive
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Snapshot the index and workspace, or list snapshots
+    Snapshot {
+        /// Why it's taken, shown in the list
+        #[arg(default_value = "manual")]
+        reason: String,
+
+        /// List snapshots instead of taking one
+        #[arg(short, long)]
+        list: bool,
+    },
+
+    /// Put back the index and workspace from a snapshot (the current state
+    /// is snapshotted first)
+    Restore {
+        /// Snapshot id, or a unique prefix of one
+        snapshot: String,
+
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Copy the workspace and index into an OpenClaw state directory
+    ExportOpenclaw {
+        /// OpenClaw state directory
+        #[arg(short, long, default_value = "~/.openclaw")]
+        dir: String,
+
+        /// Overwrite OpenClaw workspace files that differ
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Import the workspace and index from an OpenClaw state directory
+    ImportOpenclaw {
+        /// OpenClaw state directory
+        #[arg(short, long, default_value = "~/.openclaw")]
+        dir: String,
+
+        /// Overwrite workspace files that differ from OpenClaw's
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Check memory health and write memory/health-report.md
+    Doctor,
+
+    /// Show the agent's file changes waiting for review (memory.staging),
+    /// or approve / reject them
+    Changes {
+        /// Write change ID to its file (repeatable)
+        #[arg(short, long)]
+        approve: Vec<i64>,
+
+        /// Drop change ID (repeatable)
+        #[arg(short, long)]
+        reject: Vec<i64>,
+    },
+
+    /// Approve or reject facts proposed from conversations
+    /// (memory.extract_facts)
+    Review {
+        /// Only list the pending facts
+        #[arg(short, long)]
+        list: bool,
+    },
+
+    /// Show the memory audit log (searches, writes, indexing, forgets)
+    Audit {
+        /// Number of entries to show
+        #[arg(short = 'n', long, default_value = "20")]
+        limit: usize,
+
+        /// Only show this action (search, write, index, delete, forget)
+        #[arg(short, long)]
+        action: Option<String>,
+
+        /// Only show entries from this session
+        #[arg(short, long)]
+        session: Option<String>,
+
+        /// Only show entries for files under this path
+        #[arg(short, long)]
+        path: Option<String>,
+
+        /// Trace a chunk by content hash or [VERIFIED:...] citation hash
+        #[arg(short, long)]
+        chunk: Option<String>,
+    },
 }
 
 pub async fn run(args: MemoryArgs, agent_id: &str) -> Result<()> {
     let config = Config::load()?;
+
+    // Import replaces the index database, so it runs before the index is opened
+    match args.command {
+        MemoryCommands::Import { archive, force } => {
+            return import_memory(&config, agent_id, &archive, force).await;
+        }
+        MemoryCommands::ImportOpenclaw { dir, force } => {
+            return import_openclaw(&config, agent_id, &expand_dir(&dir), force).await;
+        }
+        MemoryCommands::Restore { snapshot, yes } => {
+            return restore_snapshot(&config, agent_id, &snapshot, yes).await;
+        }
+        _ => {}
+    }
+
     let memory = MemoryManager::new_with_full_config(&config.memory, Some(&config), agent_id)?;
 
     match args.command {
-        MemoryCommands::Search { query, limit } => search_memory(&memory, &query, limit).await,
-        MemoryCommands::Reindex { force } => reindex_memory(&memory, force).await,
+        MemoryCommands::Search {
+            query,
+            limit,
+            category,
+            semantic,
+            keyword,
+            hybrid: _,
+        } => {
+            let query = if category.is_empty() {
+                query
+            } else {
+                format!("{} category:{}", query, category.join(","))
+            };
+            let mode = if semantic {
+                SearchMode::Semantic
+            } else if keyword {
+                SearchMode::Keyword
+            } else {
+                SearchMode::Hybrid
+            };
+            search_memory(&memory, &query, mode, limit).await
+        }
+        MemoryCommands::Index { path: None, force } | MemoryCommands::Reindex { force } => {
+            reindex_memory(&memory, force).await
+        }
+        MemoryCommands::Index {
+            path: Some(path),
+            force,
+        } => index_path(&memory, &path, force).await,
+        MemoryCommands::Verify { citation } => verify_memory(&memory, citation.as_deref()).await,
         MemoryCommands::Stats => show_stats(&memory).await,
         MemoryCommands::Recent { count } => show_recent(&memory, count).await,
+        MemoryCommands::Sync => sync_workspace(&config, &memory).await,
+        MemoryCommands::Consolidate { apply, threshold } => {
+            let threshold = threshold.unwrap_or(config.memory.consolidation_threshold);
+            consolidate_memory(&memory, threshold, apply).await
+        }
+        MemoryCommands::Forget { target, yes } => forget_memory(&memory, &target, yes).await,
+        MemoryCommands::Export { output } => export_memory(&memory, &output).await,
+        MemoryCommands::Snapshot { reason, list } => snapshot_memory(&memory, &reason, list).await,
+        MemoryCommands::ExportOpenclaw { dir, force } => {
+            export_openclaw(&memory, agent_id, &expand_dir(&dir), force).await
+        }
+        MemoryCommands::Doctor => memory_doctor(&memory).await,
+        MemoryCommands::Changes { approve, reject } => {
+            review_changes(&memory, &approve, &reject).await
+        }
+        MemoryCommands::Review { list } => review_proposals(&memory, list).await,
+        MemoryCommands::Import { .. }
+        | MemoryCommands::ImportOpenclaw { .. }
+        | MemoryCommands::Restore { .. } => {
+            unreachable!("handled above")
+        }
+        MemoryCommands::Audit {
+            limit,
+            action,
+            session,
+            path,
+            chunk,
+        } => {
+            let filter = AuditFilter {
+                action,
+                session,
+                path,
+                chunk: chunk.map(|c| {
+                    c.trim_matches(|ch| ch == '[' || ch == ']')
+                        .trim_start_matches("VERIFIED:")
+                        .to_string()
+                }),
+            };
+            show_audit(&memory, &filter, limit).await
+        }
     }
 }
 
-async fn search_memory(memory: &MemoryManager, query: &str, limit: usize) -> Result<()> {
-    let results = memory.search(query, limit)?;
+async fn search_memory(
+    memory: &MemoryManager,
+    query: &str,
+    mode: SearchMode,
+    limit: usize,
+) -> Result<()> {
+    if mode != SearchMode::Keyword {
+        while memory.embeddings_loading() {
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+    }
+    let results = memory.search_with_mode(query, mode, limit)?;
 
     if results.is_empty() {
         println!("No results found for '{}'", query);
         return Ok(());
     }
 
-    println!("Found {} results for '{}':\n", results.len(), query);
+    let mode = match mode {
+        SearchMode::Hybrid if memory.has_embeddings() => "hybrid",
+        SearchMode::Hybrid | SearchMode::Keyword => "keyword",
+        SearchMode::Semantic => "semantic",
+    };
+    println!(
+        "Found {} results for '{}' ({}):\n",
+        results.len(),
+        query,
+        mode
+    );
 
-    for (i, result) in results.iter().enumerate() {
-        println!(
-            "{}. {} (lines {}-{})",
-            i + 1,
-            result.file,
-            result.line_start,
-            result.line_end
-        );
-        println!("   Score: {:.3}", result.score);
+    let verified = memory.verify_results(&results);
+    for (i, (result, chunk)) in results.iter().zip(&verified).enumerate() {
+        print_verified_chunk(i + 1, chunk, Some(result));
+    }
 
-        // Show preview (first 200 chars)
-        let preview: String = result.content.chars().take(200).collect();
-        let preview = preview.replace('\n', " ");
-        println!(
-            "   {}{}\n",
-            preview,
-            if result.content.len() > 200 {
-                "..."
-            } else {
-                ""
-            }
-        );
+    Ok(())
+}
+
+/// Print a search result or looked-up chunk with its verification details
+fn print_verified_chunk(n: usize, chunk: &VerifiedChunk, result: Option<&MemoryChunk>) {
+    let citation = if chunk.verified {
+        format!("[VERIFIED:{}]", chunk.hash_prefix)
+    } else {
+        "[UNVERIFIED]".to_string()
+    };
+    println!(
+        "{}. {}:{}-{} {}",
+        n, chunk.file, chunk.line_start, chunk.line_end, citation
+    );
+
+    let mut details = Vec::new();
+    if result.is_some() {
+        details.push(format!("Score: {:.3}", chunk.score));
+    }
+    details.push(format!("Confidence: {}", chunk.confidence));
+    details.push(format!("Provenance: {}", chunk.provenance));
+    if let Some(category) = result.and_then(|r| r.category.as_ref()) {
+        details.push(format!("Category: {}", category));
+    }
+    if let Some(ref date) = chunk.last_verified {
+        let stale = if chunk.stale { " (STALE)" } else { "" };
+        details.push(format!("Last verified: {}{}", date, stale));
     }
+    println!("   {}", details.join(" | "));
+    if !chunk.hash.is_empty() {
+        println!("   Hash: {}", chunk.hash);
+    }
+
+    // Show preview (first 200 chars)
+    let preview: String = chunk.content.chars().take(200).collect();
+    let preview = preview.replace('\n', " ");
+    println!(
+        "   {}{}\n",
+        preview,
+        if chunk.content.len() > 200 { "..." } else { "" }
+    );
+}
+
+async fn index_path(memory: &MemoryManager, path: &Path, force: bool) -> Result<()> {
+    println!("Indexing {}...", path.display());
+
+    let stats = memory.index_path(path, force)?;
+
+    println!("Index complete:");
+    println!("  Files processed: {}", stats.files_processed);
+    println!("  Files updated: {}", stats.files_updated);
+    println!("  Chunks in index: {}", stats.chunks_indexed);
+    println!("  Duration: {:?}", stats.duration);
 
+    generate_embeddings(memory).await
+}
+
+async fn verify_memory(memory: &MemoryManager, citation: Option<&str>) -> Result<()> {
+    if let Some(citation) = citation {
+        let prefix = citation
+            .trim_matches(|ch| ch == '[' || ch == ']')
+            .trim_start_matches("VERIFIED:");
+        let chunks = memory.verify_citation(prefix)?;
+        if chunks.is_empty() {
+            anyhow::bail!("No indexed chunk has a hash starting with {}", prefix);
+        }
+        for (i, chunk) in chunks.iter().enumerate() {
+            print_verified_chunk(i + 1, chunk, None);
+        }
+        if chunks.iter().any(|c| !c.verified) {
+            anyhow::bail!("Verification failed; run `homegpt memory reindex --force`");
+        }
+        return Ok(());
+    }
+
+    let chunks = memory.chunk_count()?;
+    let failed = memory.unverifiable_chunks()?;
+    let orphaned = memory.orphaned_hashes()?;
+    println!(
+        "Checked {} chunks: {} verified, {} failed",
+        chunks,
+        chunks.saturating_sub(failed.len()),
+        failed.len()
+    );
+    for (location, reason) in &failed {
+        println!("  {} ({})", location, reason);
+    }
+    if !orphaned.is_empty() {
+        println!("{} hashes without a chunk:", orphaned.len());
+        for (path, chunk_id) in &orphaned {
+            println!("  {} (chunk {})", path, chunk_id);
+        }
+    }
+
+    if !failed.is_empty() || !orphaned.is_empty() {
+        anyhow::bail!("Verification failed; run `homegpt memory reindex --force`");
+    }
     Ok(())
 }
 
@@ -103,12 +455,23 @@ async fn reindex_memory(memory: &MemoryManager, force: bool) -> Result<()> {
     println!("  Chunks indexed: {}", stats.chunks_indexed);
     println!("  Duration: {:?}", stats.duration);
 
-    // Generate embeddings if provider is configured
+    generate_embeddings(memory).await
+}
+
+/// Embed new chunks if an embedding provider is configured
+async fn generate_embeddings(memory: &MemoryManager) -> Result<()> {
     if memory.has_embeddings() {
         println!("\nGenerating embeddings...");
-        let (processed, embedded) = memory.generate_embeddings(50).await?;
+        let (processed, embedded) = memory
+            .generate_embeddings_with_progress(|p| {
+                if p.total > 0 {
+                    print!("\r  Embedded {}/{} chunks", p.embedded, p.total);
+                    let _ = std::io::stdout().flush();
+                }
+            })
+            .await?;
         if processed > 0 {
-            println!("  Chunks processed: {}", processed);
+            println!("\n  Chunks processed: {}", processed);
             println!("  Embeddings generated: {}", embedded);
         } else {
             println!("  All chunks already have embeddings");
@@ -127,6 +490,37 @@ async fn show_stats(memory: &MemoryManager) -> Result<()> {
     println!("Total files: {}", stats.total_files);
     println!("Total chunks: {}", stats.total_chunks);
     println!("Index size: {} KB", stats.index_size_kb);
+    if let Some(model) = memory.embedding_model() {
+        println!(
+            "Embedded chunks: {} ({}, {} pending)",
+            memory.embedded_chunk_count()?,
+            model,
+            memory.pending_embedding_count()?
+        );
+    }
+
+    // Provenance is stored per chunk, so group by kind (user-stated, file, ...)
+    let verification = memory.verification_stats()?;
+    let mut by_kind: Vec<(String, i64)> = Vec::new();
+    for (provenance, count) in verification.by_provenance {
+        let provenance: Provenance =
+            serde_json::from_str(&provenance).unwrap_or(Provenance::Unknown);
+        let kind = provenance.to_string();
+        let kind = kind.split(':').next().unwrap_or_default().to_string();
+        match by_kind.iter_mut().find(|(k, _)| *k == kind) {
+            Some((_, total)) => *total += count,
+            None => by_kind.push((kind, count)),
+        }
+    }
+    let by_kind: Vec<String> = by_kind
+        .iter()
+        .map(|(kind, count)| format!("{} {}", count, kind))
+        .collect();
+    println!(
+        "Verification hashes: {} ({})",
+        verification.total_hashes,
+        by_kind.join(", ")
+    );
     println!("\nFiles:");
     for file in &stats.files {
         println!(
@@ -155,3 +549,418 @@ async fn show_recent(memory: &MemoryManager, count: usize) -> Result<()> {
 
     Ok(())
 }
+
+async fn sync_workspace(config: &Config, memory: &MemoryManager) -> Result<()> {
+    let sync = &config.memory.sync;
+    if sync.remote.is_empty() {
+        println!("No sync remote configured (memory.sync.remote); committing locally only");
+    } else {
+        println!(
+            "Syncing workspace with {} ({})...",
+            sync.remote, sync.branch
+        );
+    }
+
+    let report = WorkspaceSync::new(memory.workspace(), sync).sync()?;
+
+    println!("Sync complete:");
+    println!("  Local changes committed: {}", report.committed);
+    println!("  Remote changes merged: {}", report.merged);
+    println!("  Pushed: {}", report.pushed);
+    for path in &report.conflicts_resolved {
+        println!("  Conflict resolved: {}", path);
+    }
+
+    if report.merged {
+        let stats = memory.reindex(false)?;
+        println!("  Files reindexed: {}", stats.files_updated);
+    }
+
+    Ok(())
+}
+
+async fn consolidate_memory(memory: &MemoryManager, threshold: f32, apply: bool) -> Result<()> {
+    if !memory.has_embeddings() {
+        anyhow::bail!("Consolidation needs embeddings; configure memory.embedding_provider");
+    }
+
+    // Make sure every chunk has an embedding before comparing
+    memory.reindex(false)?;
+    memory.generate_embeddings().await?;
+
+    let report = memory.consolidate(threshold, apply)?;
+    print!("{}", report.summary());
+
+    if !apply && !report.groups.is_empty() {
+        println!("\nRun with --apply to merge these duplicates.");
+    }
+
+    Ok(())
+}
+
+async fn forget_memory(memory: &MemoryManager, target: &str, yes: bool) -> Result<()> {
+    let preview = memory.forget(target, false)?;
+    print!("{}", preview.summary());
+    if preview.matches.is_empty() {
+        return Ok(());
+    }
+
+    if !yes {
+        print!("\nDelete these lines from the workspace and index? [y/N]: ");
+        std::io::stdout().flush()?;
+
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+        let input = input.trim().to_lowercase();
+        if input != "y" && input != "yes" {
+            println!("Nothing deleted");
+            return Ok(());
+        }
+    }
+
+    let report = memory.forget(target, true)?;
+    print!("{}", report.summary());
+    if let Some(id) = report.audit_id {
+        println!("Audit log entry: #{}", id);
+    }
+
+    Ok(())
+}
+
+async fn show_audit(memory: &MemoryManager, filter: &AuditFilter, limit: usize) -> Result<()> {
+    let entries = memory.audit_entries(filter, limit)?;
+
+    if entries.is_empty() {
+        println!("No audit entries found");
+        return Ok(());
+    }
+
+    // Oldest first so a chunk can be traced from write to index to search
+    for entry in entries.iter().rev() {
+        println!("{}\n", entry.format());
+    }
+
+    Ok(())
+}
+
+async fn review_changes(memory: &MemoryManager, approve: &[i64], reject: &[i64]) -> Result<()> {
+    if approve.is_empty() && reject.is_empty() {
+        let changes = memory.pending_changes()?;
+        if changes.is_empty() {
+            println!("No changes waiting for review");
+        }
+        for change in &changes {
+            println!(
+                "#{} {} ({}, {}, {})",
+                change.id,
+                change.path,
+                change.tool,
+                change.line_counts(),
+                change.created
+            );
+            println!("{}", change.diff);
+        }
+        if !changes.is_empty() {
+            println!("Approve or reject with `homegpt memory changes --approve ID --reject ID`");
+        }
+        return Ok(());
+    }
+
+    for &id in approve {
+        let _lock = WorkspaceLock::new()?.acquire()?;
+        match memory.approve_change(id) {
+            Ok(change) => println!("Approved #{}: wrote {}", id, change.path),
+            Err(e) => eprintln!("Change #{} not approved: {}", id, e),
+        }
+    }
+    for &id in reject {
+        match memory.reject_change(id) {
+            Ok(change) => println!("Rejected #{} to {}", id, change.path),
+            Err(e) => eprintln!("Change #{} not rejected: {}", id, e),
+        }
+    }
+    Ok(())
+}
+
+async fn review_proposals(memory: &MemoryManager, list: bool) -> Result<()> {
+    let proposals = memory.proposals()?;
+    if proposals.is_empty() {
+        println!("No facts waiting for review");
+        return Ok(());
+    }
+
+    if list {
+        for proposal in &proposals {
+            println!(
+                "{}  {}  {}",
+                proposal.id,
+                locale::home_time(&proposal.created).format("%Y-%m-%d"),
+                proposal.fact
+            );
+        }
+        return Ok(());
+    }
+
+    println!(
+        "{} facts proposed from conversations. Approved facts go to {}.\n",
+        proposals.len(),
+        REMEMBERED_PATH
+    );
+    let (mut approved, mut rejected) = (0, 0);
+    for proposal in &proposals {
+        println!(
+            "{}  (session {}, {})",
+            proposal.fact,
+            &proposal.session_id[..proposal.session_id.len().min(8)],
+            locale::home_time(&proposal.created).format("%Y-%m-%d %H:%M")
+        );
+        print!("Remember this? [y]es / [n]o / [s]kip / [q]uit: ");
+        std::io::stdout().flush()?;
+
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+        match input.trim().to_lowercase().as_str() {
+            "y" | "yes" => {
+                let _lock = WorkspaceLock::new()?.acquire()?;
+                memory.approve_proposal(&proposal.id)?;
+                approved += 1;
+            }
+            "n" | "no" => {
+                memory.reject_proposal(&proposal.id)?;
+                rejected += 1;
+            }
+            "q" | "quit" => break,
+            _ => {}
+        }
+        println!();
+    }
+
+    println!(
+        "{} approved, {} rejected, {} still waiting",
+        approved,
+        rejected,
+        memory.proposals()?.len()
+    );
+    Ok(())
+}
+
+async fn memory_doctor(memory: &MemoryManager) -> Result<()> {
+    let report = memory.health_report()?;
+    let path = {
+        let _lock = WorkspaceLock::new()?.acquire()?;
+        memory.write_health_report(&report)?
+    };
+
+    let markdown = report.to_markdown();
+    let (_, body) = split_frontmatter(&markdown);
+    println!("{}", body.trim_end());
+    println!("\nReport written to {}", path.display());
+
+    Ok(())
+}
+
+async fn export_memory(memory: &MemoryManager, output: &Path) -> Result<()> {
+    let _lock = WorkspaceLock::new()?.acquire()?;
+    let manifest = memory.export(output)?;
+
+    println!("Exported memory to {}", output.display());
+    println!("  Files: {}", manifest.files.len());
+    println!(
+        "  Chunks: {} ({} with embeddings)",
+        manifest.chunks, manifest.embedded_chunks
+    );
+    if let Some(ref model) = manifest.embedding_model {
+        println!("  Embedding model: {}", model);
+    }
+
+    Ok(())
+}
+
+async fn import_memory(config: &Config, agent_id: &str, archive: &Path, force: bool) -> Result<()> {
+    let report = {
+        let _lock = WorkspaceLock::new()?.acquire()?;
+        MemoryManager::import(&config.memory, agent_id, archive, force)?
+    };
+    let manifest = &report.manifest;
+
+    println!(
+        "Imported {} (homegpt {}, {})",
+        archive.display(),
+        manifest.homegpt_version,
+        manifest.created_at
+    );
+    println!(
+        "  Files written: {} ({} already up to date)",
+        report.files_written, report.files_unchanged
+    );
+    println!(
+        "  Chunks verified: {} ({} with embeddings)",
+        report.chunks_verified, manifest.embedded_chunks
+    );
+    if let Some(ref backup) = report.index_backup {
+        println!("  Previous index saved to {}", backup.display());
+    }
+
+    let memory = MemoryManager::new_with_full_config(&config.memory, Some(config), agent_id)?;
+    if let Some(ref model) = manifest.embedding_model {
+        let current = memory.embedding_model();
+        if current.as_deref() != Some(model.as_str()) {
+            println!(
+                "  Note: archive embeddings use {}, current config uses {}; run `homegpt memory reindex --force` to re-embed",
+                model,
+                current.as_deref().unwrap_or("none")
+            );
+        }
+    }
+
+    // Pick up anything that changed on disk since the export
+    let stats = memory.reindex(false)?;
+    println!("  Files reindexed: {}", stats.files_updated);
+
+    Ok(())
+}
+
+async fn snapshot_memory(memory: &MemoryManager, reason: &str, list: bool) -> Result<()> {
+    if !list {
+        let snapshot = {
+            let _lock = WorkspaceLock::new()?.acquire()?;
+            memory.snapshot(reason)?
+        };
+        println!(
+            "Snapshot {} ({} chunks{})",
+            snapshot.id,
+            snapshot.chunks,
+            if snapshot.workspace_tag.is_some() {
+                ", workspace tagged"
+            } else {
+                ", index only: the workspace couldn't be committed"
+            }
+        );
+        println!("Restore it with `homegpt memory restore {}`", snapshot.id);
+        return Ok(());
+    }
+
+    let snapshots = memory.snapshots()?;
+    if snapshots.is_empty() {
+        println!("No memory snapshots");
+        return Ok(());
+    }
+    for snapshot in snapshots {
+        println!(
+            "{}  {:>6} chunks  {}{}{}",
+            snapshot.id,
+            snapshot.chunks,
+            snapshot.reason,
+            if snapshot.auto { " (auto)" } else { "" },
+            if snapshot.workspace_tag.is_none() {
+                " [index only]"
+            } else {
+                ""
+            }
+        );
+    }
+    Ok(())
+}
+
+async fn restore_snapshot(config: &Config, agent_id: &str, id: &str, yes: bool) -> Result<()> {
+    if !yes {
+        print!(
+            "Replace the memory index and workspace files with snapshot {}? [y/N]: ",
+            id
+        );
+        std::io::stdout().flush()?;
+
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+        let input = input.trim().to_lowercase();
+        if input != "y" && input != "yes" {
+            println!("Nothing restored");
+            return Ok(());
+        }
+    }
+
+    let report = {
+        let _lock = WorkspaceLock::new()?.acquire()?;
+        MemoryManager::restore_snapshot(&config.memory, agent_id, id)?
+    };
+    let restored = &report.restored;
+    println!(
+        "Restored snapshot {} ({}, {} chunks)",
+        restored.id, restored.reason, restored.chunks
+    );
+    if !report.workspace_restored {
+        println!("  The snapshot has the index only; workspace files were left as they are");
+    }
+    if let Some(ref undo) = report.undo {
+        println!("  To undo, run `homegpt memory restore {}`", undo.id);
+    }
+
+    // Files the restored index doesn't match are picked up again
+    let memory = MemoryManager::new_with_full_config(&config.memory, Some(config), agent_id)?;
+    let stats = memory.reindex(false)?;
+    println!("  Files reindexed: {}", stats.files_updated);
+
+    Ok(())
+}
+
+fn expand_dir(dir: &str) -> PathBuf {
+    PathBuf::from(shellexpand::tilde(dir).to_string())
+}
+
+async fn export_openclaw(
+    memory: &MemoryManager,
+    agent_id: &str,
+    state_dir: &Path,
+    force: bool,
+) -> Result<()> {
+    let _lock = WorkspaceLock::new()?.acquire()?;
+    let report = memory.export_openclaw(state_dir, agent_id, force)?;
+
+    println!("Exported memory to {}", state_dir.display());
+    print_openclaw_report(&report);
+
+    Ok(())
+}
+
+async fn import_openclaw(
+    config: &Config,
+    agent_id: &str,
+    state_dir: &Path,
+    force: bool,
+) -> Result<()> {
+    let report = {
+        let _lock = WorkspaceLock::new()?.acquire()?;
+        MemoryManager::import_openclaw(&config.memory, agent_id, state_dir, force)?
+    };
+
+    println!("Imported memory from {}", state_dir.display());
+    print_openclaw_report(&report);
+    if report.hashes_recorded > 0 {
+        println!(
+            "  New verification hashes: {} (chunks written or changed by OpenClaw)",
+            report.hashes_recorded
+        );
+    }
+
+    // Re-chunk files whose chunks didn't match their hashes
+    let memory = MemoryManager::new_with_full_config(&config.memory, Some(config), agent_id)?;
+    let stats = memory.reindex(false)?;
+    println!("  Files reindexed: {}", stats.files_updated);
+
+    Ok(())
+}
+
+fn print_openclaw_report(report: &OpenClawReport) {
+    println!(
+        "  Files written: {} ({} already up to date)",
+        report.files_written, report.files_unchanged
+    );
+    println!(
+        "  Chunks: {} ({} verification hashes carried over)",
+        report.chunks, report.hashes_restored
+    );
+    println!("  Index: {}", report.index_path.display());
+    if let Some(ref backup) = report.index_backup {
+        println!("  Previous index saved to {}", backup.display());
+    }
+}
@@ -1,10 +1,16 @@
 pub mod ask;
+pub mod backup;
 pub mod chat;
 pub mod config;
 pub mod daemon;
 #[cfg(feature = "desktop")]
 pub mod desktop;
+pub mod doctor;
+pub mod eval;
+pub mod finance;
 pub mod memory;
+pub mod service;
+pub mod session;
 
 use clap::{Parser, Subcommand};
 
@@ -33,6 +39,11 @@ pub struct Cli {
         env = "HOMEGPT_AGENT"
     )]
     pub agent: String,
+
+    /// Log tool calls that would change files, run commands or reach out
+    /// (messages, calendars) instead of running them
+    #[arg(long, global = true, env = "HOMEGPT_DRY_RUN")]
+    pub dry_run: bool,
 }
 
 #[derive(Subcommand)]
@@ -53,6 +64,24 @@ pub enum Commands {
     /// Memory operations
     Memory(memory::MemoryArgs),
 
+    /// Inspect saved sessions
+    Session(session::SessionArgs),
+
+    /// Score memory retrieval against a set of questions
+    Eval(eval::EvalArgs),
+
+    /// Check the config, providers, memory index and daemon for problems
+    Doctor(doctor::DoctorArgs),
+
+    /// Import bank exports and check budgets
+    Finance(finance::FinanceArgs),
+
+    /// Encrypted backups of the state dir and workspace
+    Backup(backup::BackupArgs),
+
+    /// Run the daemon in the background at login, restarted if it fails
+    Service(service::ServiceArgs),
+
     /// Configuration management
     Config(config::ConfigArgs),
 }
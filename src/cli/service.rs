@@ -0,0 +1,454 @@
+//! `homegpt service`: run the daemon in the background from login
+//!
+//! Installs a systemd user unit (Linux), a launchd agent (macOS) or a Task
+//! Scheduler task (Windows) running `homegpt daemon start --foreground
+//! --log-file` from the state dir. The daemon logs to the dated daemon log
+//! (pruned after `logging.retention_days`) and is restarted if it fails.
+
+use anyhow::{Context, Result};
+use clap::{Args, Subcommand};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use homegpt::agent::get_state_dir;
+
+use super::daemon::{get_pid_file, is_process_running};
+
+/// Name of the systemd unit and the Windows task
+const SERVICE_NAME: &str = "homegpt";
+
+/// launchd label; `com.homegpt` is the start script's daemon
+const LAUNCHD_LABEL: &str = "com.homegpt.daemon";
+
+/// Seconds to wait before restarting a daemon that failed
+const RESTART_SECONDS: u32 = 10;
+
+#[derive(Args)]
+pub struct ServiceArgs {
+    #[command(subcommand)]
+    pub command: ServiceCommands,
+}
+
+#[derive(Subcommand)]
+pub enum ServiceCommands {
+    /// Install the service and start it
+    Install {
+        /// Install without starting it (it starts at the next login)
+        #[arg(long)]
+        no_start: bool,
+
+        /// Print the service file instead of installing it
+        #[arg(long)]
+        print: bool,
+    },
+
+    /// Stop the service and remove it
+    Uninstall,
+
+    /// Show what the service manager says about it
+    Status,
+}
+
+pub async fn run(args: ServiceArgs, agent_id: &str) -> Result<()> {
+    let platform = Platform::current()?;
+    match args.command {
+        ServiceCommands::Install { no_start, print } => {
+            let service = Service::new(agent_id)?;
+            let contents = platform.service_file(&service);
+            if print {
+                print!("{}", contents);
+                return Ok(());
+            }
+            install(platform, &service, &contents, !no_start)
+        }
+        ServiceCommands::Uninstall => uninstall(platform),
+        ServiceCommands::Status => platform.status(),
+    }
+}
+
+/// What the service runs, and where
+struct Service {
+    exe: PathBuf,
+    args: Vec<String>,
+    /// `~/.homegpt`, the working directory
+    state_dir: PathBuf,
+    logs_dir: PathBuf,
+    /// PATH at install time; service managers start with a minimal one
+    path: String,
+}
+
+impl Service {
+    fn new(agent_id: &str) -> Result<Self> {
+        let exe = std::env::current_exe()
+            .and_then(|exe| exe.canonicalize())
+            .context("Could not find the homegpt executable")?;
+        let state_dir = get_state_dir()?;
+        Ok(Self {
+            exe,
+            args: [
+                "--agent",
+                agent_id,
+                "daemon",
+                "start",
+                "--foreground",
+                "--log-file",
+            ]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect(),
+            logs_dir: state_dir.join("logs"),
+            state_dir,
+            path: std::env::var("PATH").unwrap_or_default(),
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Platform {
+    Systemd,
+    Launchd,
+    TaskScheduler,
+}
+
+impl Platform {
+    fn current() -> Result<Self> {
+        if cfg!(target_os = "linux") {
+            Ok(Self::Systemd)
+        } else if cfg!(target_os = "macos") {
+            Ok(Self::Launchd)
+        } else if cfg!(windows) {
+            Ok(Self::TaskScheduler)
+        } else {
+            anyhow::bail!("No service manager is supported on this platform")
+        }
+    }
+
+    /// Where the service file is installed. The Windows task is imported
+    /// from this file and it's kept for reference.
+    fn service_path(self) -> Result<PathBuf> {
+        let base = directories::BaseDirs::new()
+            .ok_or_else(|| anyhow::anyhow!("Could not determine home directory"))?;
+        Ok(match self {
+            Self::Systemd => base
+                .config_dir()
+                .join("systemd/user")
+                .join(format!("{}.service", SERVICE_NAME)),
+            Self::Launchd => base
+                .home_dir()
+                .join("Library/LaunchAgents")
+                .join(format!("{}.plist", LAUNCHD_LABEL)),
+            Self::TaskScheduler => get_state_dir()?.join(format!("{}-task.xml", SERVICE_NAME)),
+        })
+    }
+
+    fn service_file(self, service: &Service) -> String {
+        match self {
+            Self::Systemd => systemd_unit(service),
+            Self::Launchd => launchd_plist(service),
+            Self::TaskScheduler => task_xml(service),
+        }
+    }
+
+    fn status(self) -> Result<()> {
+        let unit = format!("{}.service", SERVICE_NAME);
+        match self {
+            // Exits non-zero when the service isn't running
+            Self::Systemd => {
+                Command::new("systemctl")
+                    .args(["--user", "status", &unit, "--no-pager"])
+                    .status()
+                    .context("Failed to run systemctl")?;
+                Ok(())
+            }
+            Self::Launchd => run_command("launchctl", &["list", LAUNCHD_LABEL]),
+            Self::TaskScheduler => run_command(
+                "schtasks",
+                &["/Query", "/TN", SERVICE_NAME, "/V", "/FO", "LIST"],
+            ),
+        }
+    }
+}
+
+fn install(platform: Platform, service: &Service, contents: &str, start: bool) -> Result<()> {
+    // A daemon started by hand would make the service's daemon exit at once
+    if start {
+        let pid_file = get_pid_file()?;
+        if let Ok(pid) = fs::read_to_string(&pid_file) {
+            if is_process_running(&pid) {
+                anyhow::bail!(
+                    "The daemon is already running (PID: {}); stop it with `homegpt daemon stop` first",
+                    pid.trim()
+                );
+            }
+        }
+    }
+
+    let path = platform.service_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::create_dir_all(&service.logs_dir)?;
+    match platform {
+        // schtasks reads the task XML as UTF-16
+        Platform::TaskScheduler => fs::write(&path, utf16_with_bom(contents))?,
+        _ => fs::write(&path, contents)?,
+    }
+    let path_arg = path.to_string_lossy();
+    let unit = format!("{}.service", SERVICE_NAME);
+
+    match platform {
+        Platform::Systemd => {
+            run_command("systemctl", &["--user", "daemon-reload"])?;
+            if start {
+                run_command("systemctl", &["--user", "enable", "--now", &unit])?;
+            } else {
+                run_command("systemctl", &["--user", "enable", &unit])?;
+            }
+        }
+        Platform::Launchd => {
+            if start {
+                run_command("launchctl", &["load", "-w", &path_arg])?;
+            }
+        }
+        Platform::TaskScheduler => {
+            run_command(
+                "schtasks",
+                &["/Create", "/TN", SERVICE_NAME, "/XML", &path_arg, "/F"],
+            )
+            .context("Failed to create the task (try again from an administrator prompt)")?;
+            if start {
+                run_command("schtasks", &["/Run", "/TN", SERVICE_NAME])?;
+            }
+        }
+    }
+
+    println!("Installed {}", path.display());
+    println!(
+        "  Runs: {} {}",
+        service.exe.display(),
+        service.args.join(" ")
+    );
+    println!("  Logs: {}", service.logs_dir.display());
+    if !start {
+        println!("It starts at the next login.");
+    }
+    if let Platform::Systemd = platform {
+        println!("\nUser services stop at logout; to keep it running, run:");
+        println!("  loginctl enable-linger $USER");
+    }
+    println!("\nRemove it with `homegpt service uninstall`.");
+    Ok(())
+}
+
+fn uninstall(platform: Platform) -> Result<()> {
+    let path = platform.service_path()?;
+    let path_arg = path.to_string_lossy();
+    let unit = format!("{}.service", SERVICE_NAME);
+
+    // Stopping fails if it isn't running, which is fine
+    match platform {
+        Platform::Systemd => {
+            let _ = run_command("systemctl", &["--user", "disable", "--now", &unit]);
+        }
+        Platform::Launchd => {
+            let _ = run_command("launchctl", &["unload", "-w", &path_arg]);
+        }
+        Platform::TaskScheduler => {
+            let _ = run_command("schtasks", &["/End", "/TN", SERVICE_NAME]);
+            run_command("schtasks", &["/Delete", "/TN", SERVICE_NAME, "/F"])?;
+        }
+    }
+
+    if path.exists() {
+        fs::remove_file(&path)?;
+        println!("Removed {}", path.display());
+    } else {
+        println!("No service file at {}", path.display());
+    }
+    if let Platform::Systemd = platform {
+        run_command("systemctl", &["--user", "daemon-reload"])?;
+    }
+    Ok(())
+}
+
+/// Run a service manager command, failing on non-zero exit
+fn run_command(program: &str, args: &[&str]) -> Result<()> {
+    let status = Command::new(program)
+        .args(args)
+        .status()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !status.success() {
+        anyhow::bail!("{} {} failed ({})", program, args.join(" "), status);
+    }
+    Ok(())
+}
+
+fn systemd_unit(service: &Service) -> String {
+    // `%` starts a specifier in unit files
+    let escape = |s: &str| s.replace('%', "%%");
+    let exec: Vec<String> = std::iter::once(service.exe.to_string_lossy().to_string())
+        .chain(service.args.iter().cloned())
+        .map(|arg| format!("\"{}\"", escape(&arg)))
+        .collect();
+    format!(
+        "\
+[Unit]
+Description=HomeGPT daemon
+After=network-online.target
+Wants=network-online.target
+
+[Service]
+Type=simple
+ExecStart={exec}
+WorkingDirectory={dir}
+Environment=\"PATH={path}\"
+Restart=on-failure
+RestartSec={restart}
+# The daemon shuts down cleanly on Ctrl+C
+KillSignal=SIGINT
+TimeoutStopSec=30
+
+[Install]
+WantedBy=default.target
+",
+        exec = exec.join(" "),
+        dir = escape(&service.state_dir.to_string_lossy()),
+        path = escape(&service.path),
+        restart = RESTART_SECONDS,
+    )
+}
+
+fn launchd_plist(service: &Service) -> String {
+    let args: String = std::iter::once(service.exe.to_string_lossy().to_string())
+        .chain(service.args.iter().cloned())
+        .map(|arg| format!("        <string>{}</string>\n", xml_escape(&arg)))
+        .collect();
+    let output = xml_escape(&service.logs_dir.join("service.log").to_string_lossy());
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+
+    <key>ProgramArguments</key>
+    <array>
+{args}    </array>
+
+    <key>WorkingDirectory</key>
+    <string>{dir}</string>
+
+    <key>EnvironmentVariables</key>
+    <dict>
+        <key>PATH</key>
+        <string>{path}</string>
+    </dict>
+
+    <key>RunAtLoad</key>
+    <true/>
+
+    <!-- Restart if it exits with an error or crashes -->
+    <key>KeepAlive</key>
+    <dict>
+        <key>SuccessfulExit</key>
+        <false/>
+    </dict>
+    <key>ThrottleInterval</key>
+    <integer>{restart}</integer>
+
+    <!-- Startup messages and panics; the log itself is in the daemon log -->
+    <key>StandardOutPath</key>
+    <string>{output}</string>
+    <key>StandardErrorPath</key>
+    <string>{output}</string>
+</dict>
+</plist>
+"#,
+        label = LAUNCHD_LABEL,
+        dir = xml_escape(&service.state_dir.to_string_lossy()),
+        path = xml_escape(&service.path),
+        restart = RESTART_SECONDS,
+    )
+}
+
+fn task_xml(service: &Service) -> String {
+    // Runs whether or not anyone is signed in, so no console window opens
+    let user = match (std::env::var("USERDOMAIN"), std::env::var("USERNAME")) {
+        (Ok(domain), Ok(name)) => format!("{}\\{}", domain, name),
+        (_, Ok(name)) => name,
+        _ => String::new(),
+    };
+    let args: Vec<String> = service
+        .args
+        .iter()
+        .map(|arg| xml_escape(&quote_windows_arg(arg)))
+        .collect();
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-16"?>
+<Task version="1.2" xmlns="http://schemas.microsoft.com/windows/2004/02/mit/task">
+  <RegistrationInfo>
+    <Description>HomeGPT daemon</Description>
+  </RegistrationInfo>
+  <Triggers>
+    <LogonTrigger>
+      <Enabled>true</Enabled>
+      <UserId>{user}</UserId>
+    </LogonTrigger>
+  </Triggers>
+  <Principals>
+    <Principal id="Author">
+      <UserId>{user}</UserId>
+      <LogonType>S4U</LogonType>
+      <RunLevel>LeastPrivilege</RunLevel>
+    </Principal>
+  </Principals>
+  <Settings>
+    <MultipleInstancesPolicy>IgnoreNew</MultipleInstancesPolicy>
+    <DisallowStartIfOnBatteries>false</DisallowStartIfOnBatteries>
+    <StopIfGoingOnBatteries>false</StopIfGoingOnBatteries>
+    <StartWhenAvailable>true</StartWhenAvailable>
+    <ExecutionTimeLimit>PT0S</ExecutionTimeLimit>
+    <RestartOnFailure>
+      <Interval>PT1M</Interval>
+      <Count>999</Count>
+    </RestartOnFailure>
+  </Settings>
+  <Actions Context="Author">
+    <Exec>
+      <Command>{exe}</Command>
+      <Arguments>{args}</Arguments>
+      <WorkingDirectory>{dir}</WorkingDirectory>
+    </Exec>
+  </Actions>
+</Task>
+"#,
+        user = xml_escape(&user),
+        exe = xml_escape(&service.exe.to_string_lossy()),
+        args = args.join(" "),
+        dir = xml_escape(&service.state_dir.to_string_lossy()),
+    )
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn quote_windows_arg(arg: &str) -> String {
+    if arg.contains(' ') {
+        format!("\"{}\"", arg)
+    } else {
+        arg.to_string()
+    }
+}
+
+fn utf16_with_bom(s: &str) -> Vec<u8> {
+    std::iter::once(0xFEFF)
+        .chain(s.encode_utf16())
+        .flat_map(|unit: u16| unit.to_le_bytes())
+        .collect()
+}
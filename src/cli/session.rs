@@ -0,0 +1,140 @@
+use anyhow::Result;
+use clap::{Args, Subcommand};
+
+use homegpt::agent::{find_turn, get_sessions_dir_for_agent, load_traces, Agent, AgentConfig};
+use homegpt::config::Config;
+use homegpt::memory::MemoryManager;
+
+#[derive(Args)]
+pub struct SessionArgs {
+    #[command(subcommand)]
+    pub command: SessionCommands,
+}
+
+#[derive(Subcommand)]
+pub enum SessionCommands {
+    /// Show which memory a turn retrieved and what went into the prompt
+    Trace {
+        /// Session ID (or a unique prefix)
+        session: String,
+
+        /// Turn number, "last", or the turn ID from the logs (default: all turns)
+        turn: Option<String>,
+
+        /// Print the trace as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show the system prompt the next turn sends, with tokens per section
+    Prompt {
+        /// Session ID (or a unique prefix) (default: a new session)
+        session: Option<String>,
+
+        /// Print each section's text too
+        #[arg(long)]
+        full: bool,
+
+        /// Print the sections as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+pub async fn run(args: SessionArgs, agent_id: &str) -> Result<()> {
+    match args.command {
+        SessionCommands::Trace {
+            session,
+            turn,
+            json,
+        } => {
+            let dir = get_sessions_dir_for_agent(agent_id)?;
+            let session_id = resolve_session(&dir, &session)?;
+            let traces = load_traces(&dir, &session_id)?;
+            if traces.is_empty() {
+                anyhow::bail!(
+                    "No retrieval traces for session {} (agent '{}'; web UI sessions are under `--agent http`)",
+                    session_id,
+                    agent_id
+                );
+            }
+
+            let shown: Vec<_> = match turn {
+                Some(ref turn) => match find_turn(&traces, turn) {
+                    Some(trace) => vec![trace.clone()],
+                    None => anyhow::bail!(
+                        "No turn '{}' in session {} (it has {})",
+                        turn,
+                        session_id,
+                        traces.len()
+                    ),
+                },
+                None => traces,
+            };
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&shown)?);
+            } else {
+                let text: Vec<String> = shown.iter().map(|t| t.format()).collect();
+                print!("{}", text.join("\n"));
+            }
+        }
+        SessionCommands::Prompt {
+            session,
+            full,
+            json,
+        } => {
+            let config = Config::load()?;
+            let memory =
+                MemoryManager::new_with_full_config(&config.memory, Some(&config), agent_id)?;
+            let agent_config = AgentConfig {
+                model: config.agent.default_model.clone(),
+                context_window: config.agent.context_window,
+                reserve_tokens: config.agent.reserve_tokens,
+            };
+            let mut agent = Agent::new(agent_config, &config, memory).await?;
+            match session {
+                Some(ref id) => {
+                    let dir = get_sessions_dir_for_agent(agent_id)?;
+                    agent.resume_session(&resolve_session(&dir, id)?).await?;
+                }
+                None => agent.new_session().await?,
+            }
+
+            let inspection = agent.inspect_prompt();
+            if json {
+                println!("{}", serde_json::to_string_pretty(&inspection)?);
+            } else {
+                if full {
+                    println!("{}", inspection.format_sections());
+                }
+                print!("{}", inspection.format());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The saved session `id` names, or the only one it's a prefix of
+fn resolve_session(dir: &std::path::Path, id: &str) -> Result<String> {
+    if dir.join(format!("{}.jsonl", id)).exists() {
+        return Ok(id.to_string());
+    }
+    let matches: Vec<String> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter_map(|e| {
+                    let name = e.file_name().to_string_lossy().to_string();
+                    name.strip_suffix(".jsonl").map(|s| s.to_string())
+                })
+                .filter(|name| name.starts_with(id))
+                .collect()
+        })
+        .unwrap_or_default();
+    match matches.len() {
+        1 => Ok(matches.into_iter().next().unwrap()),
+        0 => anyhow::bail!("Session not found: {}", id),
+        n => anyhow::bail!("'{}' matches {} sessions; give more of the ID", id, n),
+    }
+}
@@ -0,0 +1,578 @@
+//! Client for a running daemon
+//!
+//! While `homegpt daemon start` is running it owns the agent, the memory
+//! index and the heartbeat. `homegpt ask`, `homegpt chat` and the desktop app
+//! attach to its HTTP API on the loopback interface instead of building their
+//! own `Agent`, so only one process writes the SQLite index. When no daemon
+//! answers they fall back to running in-process.
+
+use anyhow::{Context, Result};
+use futures::{Stream, StreamExt};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+use crate::agent::{PromptInspection, ToolCall};
+use crate::config::Config;
+use crate::memory::{FileWrite, VerifiedChunk};
+
+/// How long to wait for the daemon to answer the health check
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Streamed reply to a chat message
+#[derive(Debug, Clone, PartialEq)]
+pub enum DaemonEvent {
+    Content(String),
+    ToolStart {
+        name: String,
+        id: String,
+        detail: Option<String>,
+        /// The call's JSON arguments
+        arguments: String,
+    },
+    ToolEnd {
+        name: String,
+        id: String,
+        output: String,
+    },
+    /// A tool call waits until this is answered with `answer_approval`
+    Approval(DaemonApproval),
+    Done,
+    Error(String),
+}
+
+/// A tool call the approval policy asks about, from a stream opened with
+/// `approvals`
+#[derive(Debug, Clone, PartialEq)]
+pub struct DaemonApproval {
+    pub id: String,
+    /// Secret the answer is sent with
+    pub token: String,
+    pub name: String,
+    pub detail: Option<String>,
+    /// Why the policy asked
+    pub reason: String,
+    /// The call's JSON arguments
+    pub arguments: String,
+}
+
+/// A daemon-held chat session
+#[derive(Debug, Clone, Deserialize)]
+pub struct DaemonSession {
+    pub session_id: String,
+    pub model: String,
+}
+
+/// Status of a daemon-held session
+#[derive(Debug, Clone, Deserialize)]
+pub struct DaemonSessionStatus {
+    pub session_id: String,
+    pub model: String,
+    pub message_count: usize,
+    pub token_count: usize,
+    #[serde(default)]
+    pub api_input_tokens: u64,
+    #[serde(default)]
+    pub api_output_tokens: u64,
+    #[serde(default)]
+    pub api_cache_read_tokens: u64,
+    #[serde(default)]
+    pub api_cache_write_tokens: u64,
+}
+
+/// A message of a daemon-held session
+#[derive(Debug, Clone, Deserialize)]
+pub struct DaemonSessionMessage {
+    /// "user", "assistant", "system" or "toolResult"
+    pub role: String,
+    pub content: Option<String>,
+    pub tool_calls: Option<Vec<ToolCall>>,
+    pub tool_call_id: Option<String>,
+}
+
+/// A session listed by `GET /api/sessions`
+#[derive(Debug, Clone, Deserialize)]
+pub struct DaemonSessionInfo {
+    pub session_id: String,
+    pub idle_seconds: u64,
+    #[serde(default)]
+    pub message_count: usize,
+}
+
+/// A tool available to a daemon-held session
+#[derive(Debug, Clone, Deserialize)]
+pub struct DaemonTool {
+    pub name: String,
+    pub description: String,
+    #[serde(default)]
+    pub requires_approval: bool,
+}
+
+/// The chunk a `[VERIFIED:<hash>]` citation points at, from
+/// `GET /api/memory/citations/<hash>`
+#[derive(Debug, Clone, Deserialize)]
+pub struct DaemonCitation {
+    pub file: String,
+    pub line_start: i32,
+    pub line_end: i32,
+    /// `path:start-end`
+    pub location: String,
+    /// Whether the chunk still matches its stored hash
+    #[serde(default)]
+    pub verified: bool,
+    #[serde(default)]
+    pub provenance: Option<String>,
+    #[serde(default)]
+    pub confidence: Option<String>,
+}
+
+/// The daemon's memory index, from `GET /api/memory/stats`
+#[derive(Debug, Clone, Deserialize)]
+pub struct DaemonMemoryStats {
+    pub total_chunks: usize,
+    /// Chunks per category, largest first
+    #[serde(default)]
+    pub categories: Vec<(String, usize)>,
+    /// Verification hashes per provenance
+    #[serde(default)]
+    pub provenance: Vec<(String, i64)>,
+    #[serde(default)]
+    pub embedding_model: Option<String>,
+}
+
+/// The heartbeat's last run, from `GET /api/heartbeat/status`
+#[derive(Debug, Clone, Deserialize)]
+pub struct DaemonHeartbeat {
+    pub enabled: bool,
+    pub interval: String,
+    pub last_event: Option<DaemonHeartbeatEvent>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DaemonHeartbeatEvent {
+    /// "sent", "ok", "skipped" or "failed"
+    pub status: String,
+    pub duration_ms: u64,
+    pub preview: Option<String>,
+    pub reason: Option<String>,
+    pub age_seconds: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DaemonStatus {
+    pub version: String,
+    pub model: String,
+    pub memory_chunks: usize,
+    #[serde(default)]
+    pub has_embeddings: bool,
+    pub active_sessions: usize,
+}
+
+pub struct DaemonClient {
+    http: reqwest::Client,
+    base_url: String,
+}
+
+impl DaemonClient {
+    /// Connect to the daemon if one is serving the API configured in
+    /// `[server]`; `None` if the server is disabled or nothing answers
+    pub async fn connect(config: &Config) -> Option<Self> {
+        if !config.server.enabled {
+            return None;
+        }
+        let client = Self::new(&config.server.bind, config.server.port);
+        let health = client
+            .http
+            .get(client.url("/health"))
+            .timeout(CONNECT_TIMEOUT)
+            .send()
+            .await;
+        match health {
+            Ok(response) if response.status().is_success() => Some(client),
+            _ => None,
+        }
+    }
+
+    fn new(bind: &str, port: u16) -> Self {
+        // A wildcard bind is reachable on loopback
+        let host = match bind {
+            "0.0.0.0" | "" => "127.0.0.1",
+            "::" | "[::]" => "[::1]",
+            other => other,
+        };
+        Self {
+            http: reqwest::Client::new(),
+            base_url: format!("http://{}:{}", host, port),
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let response = self.http.get(self.url(path)).send().await?;
+        Self::parse(response).await
+    }
+
+    async fn post<T: serde::de::DeserializeOwned>(&self, path: &str, body: Value) -> Result<T> {
+        let response = self.http.post(self.url(path)).json(&body).send().await?;
+        Self::parse(response).await
+    }
+
+    async fn parse<T: serde::de::DeserializeOwned>(response: reqwest::Response) -> Result<T> {
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            let message = serde_json::from_str::<Value>(&body)
+                .ok()
+                .and_then(|v| v["error"].as_str().map(str::to_string))
+                .unwrap_or(body);
+            anyhow::bail!("Daemon returned {}: {}", status, message);
+        }
+        response
+            .json()
+            .await
+            .context("Unexpected response from daemon")
+    }
+
+    pub async fn status(&self) -> Result<DaemonStatus> {
+        self.get("/api/status").await
+    }
+
+    /// Start a session, or attach to the daemon's session `session_id`
+    pub async fn open_session(&self, session_id: Option<&str>) -> Result<DaemonSession> {
+        self.post("/api/sessions", json!({ "session_id": session_id }))
+            .await
+    }
+
+    pub async fn close_session(&self, session_id: &str) -> Result<()> {
+        let _: Value = Self::parse(
+            self.http
+                .delete(self.url(&format!("/api/sessions/{}", session_id)))
+                .send()
+                .await?,
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn sessions(&self) -> Result<Vec<DaemonSessionInfo>> {
+        #[derive(Deserialize)]
+        struct Sessions {
+            sessions: Vec<DaemonSessionInfo>,
+        }
+        let list: Sessions = self.get("/api/sessions").await?;
+        Ok(list.sessions)
+    }
+
+    pub async fn session_status(&self, session_id: &str) -> Result<DaemonSessionStatus> {
+        self.get(&format!("/api/sessions/{}", session_id)).await
+    }
+
+    /// The session's messages, tool calls and results included
+    pub async fn session_messages(&self, session_id: &str) -> Result<Vec<DaemonSessionMessage>> {
+        #[derive(Deserialize)]
+        struct Messages {
+            messages: Vec<DaemonSessionMessage>,
+        }
+        let path = format!("/api/sessions/{}/messages", session_id);
+        let found: Messages = self.get(&path).await?;
+        Ok(found.messages)
+    }
+
+    /// The system prompt the session's next turn sends, by section
+    pub async fn session_prompt(&self, session_id: &str) -> Result<PromptInspection> {
+        let path = format!("/api/sessions/{}/prompt", session_id);
+        self.get(&path).await
+    }
+
+    pub async fn set_model(&self, session_id: &str, model: &str) -> Result<()> {
+        let path = format!("/api/sessions/{}/model", session_id);
+        let _: Value = self.post(&path, json!({ "model": model })).await?;
+        Ok(())
+    }
+
+    pub async fn clear_session(&self, session_id: &str) -> Result<()> {
+        let path = format!("/api/sessions/{}/clear", session_id);
+        let _: Value = self.post(&path, json!({})).await?;
+        Ok(())
+    }
+
+    pub async fn tools(&self, session_id: &str) -> Result<Vec<DaemonTool>> {
+        #[derive(Deserialize)]
+        struct Tools {
+            tools: Vec<DaemonTool>,
+        }
+        let list: Tools = self
+            .get(&format!("/api/sessions/{}/tools", session_id))
+            .await?;
+        Ok(list.tools)
+    }
+
+    /// Reindex memory in the daemon, returning (files updated, chunks indexed)
+    pub async fn reindex(&self) -> Result<(usize, usize)> {
+        let result: Value = self
+            .post("/api/memory/reindex", json!({ "force": false }))
+            .await?;
+        let count = |key: &str| result[key].as_u64().unwrap_or(0) as usize;
+        Ok((count("files_updated"), count("chunks_indexed")))
+    }
+
+    /// Compact the session history, returning the token counts before and after
+    pub async fn compact(&self, session_id: &str) -> Result<(usize, usize)> {
+        let path = format!("/api/sessions/{}/compact", session_id);
+        let result: Value = self.post(&path, json!({})).await?;
+        let count = |key: &str| result[key].as_u64().unwrap_or(0) as usize;
+        Ok((count("token_count_before"), count("token_count_after")))
+    }
+
+    /// Log the tutoring session to the tutor notes; `None` if nothing was
+    /// said in it
+    pub async fn log_tutor_session(
+        &self,
+        session_id: &str,
+        student: Option<&str>,
+    ) -> Result<Option<FileWrite>> {
+        let path = format!("/api/sessions/{}/tutor-notes", session_id);
+        let result: Value = self.post(&path, json!({ "student": student })).await?;
+        if !result["logged"].as_bool().unwrap_or(false) {
+            return Ok(None);
+        }
+        Ok(Some(match result["staged_change"].as_i64() {
+            Some(id) => FileWrite::Staged(id),
+            None => FileWrite::Written,
+        }))
+    }
+
+    pub async fn search_memory(&self, query: &str, limit: usize) -> Result<Vec<VerifiedChunk>> {
+        #[derive(Deserialize)]
+        struct Results {
+            results: Vec<VerifiedChunk>,
+        }
+        let response = self
+            .http
+            .get(self.url("/api/memory/search"))
+            .query(&[("q", query), ("limit", &limit.to_string())])
+            .send()
+            .await?;
+        let results: Results = Self::parse(response).await?;
+        Ok(results.results)
+    }
+
+    pub async fn memory_stats(&self) -> Result<DaemonMemoryStats> {
+        self.get("/api/memory/stats").await
+    }
+
+    pub async fn heartbeat_status(&self) -> Result<DaemonHeartbeat> {
+        self.get("/api/heartbeat/status").await
+    }
+
+    /// The chunk a citation points at, or `None` if it's no longer indexed
+    pub async fn citation(&self, hash: &str) -> Result<Option<DaemonCitation>> {
+        let path = format!("/api/memory/citations/{}", hash);
+        let response = self.http.get(self.url(&path)).send().await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(Some(Self::parse(response).await?))
+    }
+
+    /// Location (`path:start-end`) of the chunk a citation points at, or
+    /// `None` if it's no longer indexed
+    pub async fn citation_source(&self, hash: &str) -> Result<Option<String>> {
+        Ok(self.citation(hash).await?.map(|found| found.location))
+    }
+
+    /// Send a message in a session and stream the reply. `context` is added
+    /// to the session's system prompt (an empty one clears it). With
+    /// `approvals`, calls the approval policy asks about come as
+    /// [`DaemonEvent::Approval`] and wait for `answer_approval`; without,
+    /// the daemon asks on the phone or doesn't run them. Dropping the
+    /// stream closes the connection, which stops the turn in the daemon.
+    pub async fn chat_stream(
+        &self,
+        session_id: &str,
+        message: &str,
+        context: Option<&str>,
+        approvals: bool,
+    ) -> Result<impl Stream<Item = DaemonEvent>> {
+        let response = self
+            .http
+            .post(self.url("/api/chat/stream"))
+            .json(&json!({
+                "session_id": session_id,
+                "message": message,
+                "context": context,
+                "approvals": approvals,
+            }))
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("Daemon returned {}: {}", status, body);
+        }
+
+        let mut bytes = response.bytes_stream();
+        Ok(async_stream::stream! {
+            let mut buffer = String::new();
+            loop {
+                let chunk = match bytes.next().await {
+                    Some(Ok(chunk)) => chunk,
+                    Some(Err(e)) => {
+                        yield DaemonEvent::Error(format!("Lost connection to daemon: {}", e));
+                        return;
+                    }
+                    None => return,
+                };
+                buffer.push_str(&String::from_utf8_lossy(&chunk));
+                while let Some(end) = buffer.find("\n\n") {
+                    let frame: String = buffer.drain(..end + 2).collect();
+                    match parse_frame(&frame) {
+                        Some(Frame::Event(event)) => yield event,
+                        Some(Frame::End) => return,
+                        None => {}
+                    }
+                }
+            }
+        })
+    }
+
+    /// Approve or deny a call waiting in a streamed turn
+    pub async fn answer_approval(&self, approval: &DaemonApproval, approved: bool) -> Result<()> {
+        let answer = if approved { "approve" } else { "deny" };
+        let response = self
+            .http
+            .post(self.url(&format!("/approvals/{}/{}", approval.id, answer)))
+            .query(&[("token", &approval.token)])
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("Daemon returned {}: {}", status, body);
+        }
+        Ok(())
+    }
+}
+
+enum Frame {
+    Event(DaemonEvent),
+    /// The `[DONE]` sentinel after the last event
+    End,
+}
+
+/// Parse one server-sent event from `/api/chat/stream`
+fn parse_frame(frame: &str) -> Option<Frame> {
+    let data: String = frame
+        .lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(|data| data.strip_prefix(' ').unwrap_or(data))
+        .collect::<Vec<_>>()
+        .join("\n");
+    if data.is_empty() {
+        return None;
+    }
+    if data == "[DONE]" {
+        return Some(Frame::End);
+    }
+
+    let value: Value = serde_json::from_str(&data).ok()?;
+    if let Some(error) = value["error"].as_str() {
+        return Some(Frame::Event(DaemonEvent::Error(error.to_string())));
+    }
+    let text = |key: &str| value[key].as_str().unwrap_or_default().to_string();
+    let event = match value["type"].as_str()? {
+        "content" => DaemonEvent::Content(text("delta")),
+        "tool_start" => DaemonEvent::ToolStart {
+            name: text("name"),
+            id: text("id"),
+            detail: value["detail"].as_str().map(str::to_string),
+            arguments: text("arguments"),
+        },
+        "tool_end" => DaemonEvent::ToolEnd {
+            name: text("name"),
+            id: text("id"),
+            output: text("output"),
+        },
+        "approval" => DaemonEvent::Approval(DaemonApproval {
+            id: text("id"),
+            token: text("token"),
+            name: text("name"),
+            detail: value["detail"].as_str().map(str::to_string),
+            reason: text("reason"),
+            arguments: text("arguments"),
+        }),
+        "done" => DaemonEvent::Done,
+        _ => return None,
+    };
+    Some(Frame::Event(event))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(frame: &str) -> Option<DaemonEvent> {
+        match parse_frame(frame)? {
+            Frame::Event(event) => Some(event),
+            Frame::End => None,
+        }
+    }
+
+    #[test]
+    fn test_parse_frames() {
+        assert_eq!(
+            event("data: {\"type\":\"content\",\"delta\":\"Hi\"}\n\n"),
+            Some(DaemonEvent::Content("Hi".to_string()))
+        );
+        assert_eq!(
+            event("data: {\"type\":\"tool_start\",\"name\":\"set_timer\",\"id\":\"t1\",\"detail\":\"10m\"}\n\n"),
+            Some(DaemonEvent::ToolStart {
+                name: "set_timer".to_string(),
+                id: "t1".to_string(),
+                detail: Some("10m".to_string()),
+                arguments: String::new(),
+            })
+        );
+        assert_eq!(
+            event("data:{\"error\":\"Session not found\"}\n\n"),
+            Some(DaemonEvent::Error("Session not found".to_string()))
+        );
+        // The session announcement isn't surfaced
+        assert_eq!(
+            event("data: {\"type\":\"session\",\"session_id\":\"abc\"}\n\n"),
+            None
+        );
+        assert_eq!(
+            event("data: {\"type\":\"approval\",\"id\":\"a1\",\"token\":\"s3cret\",\"name\":\"bash\",\"detail\":\"ls\",\"reason\":\"bash is in approval.tools\",\"arguments\":\"{}\"}\n\n"),
+            Some(DaemonEvent::Approval(DaemonApproval {
+                id: "a1".to_string(),
+                token: "s3cret".to_string(),
+                name: "bash".to_string(),
+                detail: Some("ls".to_string()),
+                reason: "bash is in approval.tools".to_string(),
+                arguments: "{}".to_string(),
+            }))
+        );
+        assert!(matches!(parse_frame("data: [DONE]\n\n"), Some(Frame::End)));
+        assert!(parse_frame(": keep-alive\n\n").is_none());
+    }
+
+    #[test]
+    fn test_wildcard_bind_uses_loopback() {
+        assert_eq!(
+            DaemonClient::new("0.0.0.0", 31327).base_url(),
+            "http://127.0.0.1:31327"
+        );
+        assert_eq!(
+            DaemonClient::new("127.0.0.1", 8080).base_url(),
+            "http://127.0.0.1:8080"
+        );
+    }
+}
@@ -0,0 +1,458 @@
+//! Commitments: things the assistant said it would do, tracked until done
+//!
+//! With `memory.track_commitments` on, a finished conversation in which the
+//! assistant promised something for later ("I'll check the library hours
+//! tomorrow", "I'll add that to the shopping list") is read by the summary
+//! model, and each promise is stored in `~/.homegpt/commitments.db`. The
+//! heartbeat is given the ones that are due, checks them against memory,
+//! reminders and lists, and marks each done or broken with the
+//! `resolve_commitment` tool. Broken ones are flagged in the next digest.
+
+use anyhow::Result;
+use chrono::{NaiveDate, NaiveDateTime, Timelike};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use rusqlite::{params, Connection, OptionalExtension};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::locale;
+
+/// Commitment database (relative to the state dir)
+const DB_FILE: &str = "commitments.db";
+
+const TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Phrases that promise something; conversations without them aren't sent
+/// to the model
+static PROMISE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)\b(I['’]ll|I will|I['’]m going to|I am going to|let me get back to you)\b")
+        .unwrap()
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Open,
+    Done,
+    Broken,
+}
+
+impl Status {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Status::Open => "open",
+            Status::Done => "done",
+            Status::Broken => "broken",
+        }
+    }
+
+    /// "open", "done" or "broken"
+    pub fn parse(s: &str) -> Result<Self> {
+        match s {
+            "open" => Ok(Status::Open),
+            "done" => Ok(Status::Done),
+            "broken" => Ok(Status::Broken),
+            _ => anyhow::bail!("Unknown commitment status '{}' (open, done or broken)", s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Commitment {
+    pub id: i64,
+    /// What the assistant said it would do
+    pub promise: String,
+    /// When it said it would be done, if it said
+    pub due: Option<NaiveDate>,
+    /// Session it was made in
+    pub session_id: String,
+    pub created: NaiveDateTime,
+    pub status: Status,
+    /// How it was checked, or why it's broken
+    pub note: Option<String>,
+}
+
+impl Commitment {
+    /// "#3 Check the library's Saturday hours (by Tue 20 Oct)"
+    pub fn summary(&self) -> String {
+        let mut text = format!("#{} {}", self.id, self.promise);
+        if let Some(due) = self.due {
+            text.push_str(&format!(" (by {})", locale::current().short_date(due)));
+        }
+        text
+    }
+
+    /// Whether the heartbeat should check on it `today`: its day has come,
+    /// or, with no day given, it was made before today
+    pub fn is_due(&self, today: NaiveDate) -> bool {
+        match self.due {
+            Some(due) => due <= today,
+            None => self.created.date() < today,
+        }
+    }
+}
+
+/// Promises found by the model
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ExtractedCommitments {
+    /// Things the assistant said it would do later and hasn't done in the
+    /// conversation. Empty if there are none.
+    pub commitments: Vec<ExtractedCommitment>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ExtractedCommitment {
+    /// What the assistant will do, as a short instruction to itself, e.g.
+    /// "Check the library's Saturday hours"
+    pub promise: String,
+    /// The day it should be done by, as YYYY-MM-DD, if one was said or
+    /// implied ("tomorrow")
+    pub due: Option<String>,
+}
+
+/// Whether any of `replies` (the assistant's) sounds like a promise
+pub fn mentions_promise<'a>(mut replies: impl Iterator<Item = &'a str>) -> bool {
+    replies.any(|reply| PROMISE.is_match(reply))
+}
+
+pub fn extraction_prompt(transcript: &str, today: &str) -> String {
+    format!(
+        "Below is a conversation with a household assistant (today is {}). List what the \
+         assistant said it would do later: check something, follow up, add something to a \
+         list, remind someone. Leave out what it already did in the conversation (a tool \
+         call it made, an answer it gave), offers the user didn't take up, and things the \
+         user said they would do. Give each a due date if the assistant said when \
+         (\"tomorrow\" is the day after today). Return none if there are none.\
+         \n\nConversation:\n{}",
+        today, transcript
+    )
+}
+
+/// Commitments in SQLite, shared by the agent, its tools and the heartbeat
+pub struct CommitmentStore {
+    conn: Mutex<Connection>,
+}
+
+impl CommitmentStore {
+    /// Open (or create) the store in `state_dir`
+    pub fn open(state_dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(state_dir)?;
+        let conn = Connection::open(state_dir.join(DB_FILE))?;
+        Self::with_connection(conn)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self> {
+        // The daemon and a chat session may both have it open
+        conn.busy_timeout(Duration::from_secs(5))?;
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS commitments (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                promise TEXT NOT NULL,
+                due TEXT,
+                session_id TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                -- open, done or broken
+                status TEXT NOT NULL DEFAULT 'open',
+                note TEXT,
+                resolved_at TEXT,
+                -- A broken one has been in a digest
+                reported INTEGER NOT NULL DEFAULT 0
+            );
+            CREATE INDEX IF NOT EXISTS idx_commitments_status ON commitments(status);
+            -- Messages of each session already read for commitments
+            CREATE TABLE IF NOT EXISTS commitment_reads (
+                session_id TEXT PRIMARY KEY,
+                read INTEGER NOT NULL
+            );
+            "#,
+        )?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Track `promise` from `session_id`, unless the same one is already
+    /// open. Returns it if it was added.
+    pub fn add(
+        &self,
+        promise: &str,
+        due: Option<NaiveDate>,
+        session_id: &str,
+    ) -> Result<Option<Commitment>> {
+        let promise = promise.trim();
+        if promise.is_empty() {
+            return Ok(None);
+        }
+        let open = self.list(Status::Open)?;
+        if open.iter().any(|c| c.promise.eq_ignore_ascii_case(promise)) {
+            return Ok(None);
+        }
+        let created = locale::now().naive_local().with_nanosecond(0).unwrap();
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO commitments (promise, due, session_id, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                promise,
+                due.map(|d| d.format(DATE_FORMAT).to_string()),
+                session_id,
+                created.format(TIME_FORMAT).to_string(),
+            ],
+        )?;
+        Ok(Some(Commitment {
+            id: conn.last_insert_rowid(),
+            promise: promise.to_string(),
+            due,
+            session_id: session_id.to_string(),
+            created,
+            status: Status::Open,
+            note: None,
+        }))
+    }
+
+    /// Commitments with `status`, oldest first
+    pub fn list(&self, status: Status) -> Result<Vec<Commitment>> {
+        self.query(
+            "SELECT id, promise, due, session_id, created_at, status, note FROM commitments
+             WHERE status = ?1 ORDER BY id",
+            [status.as_str()],
+        )
+    }
+
+    /// Open commitments the heartbeat should check on `today`
+    pub fn due(&self, today: NaiveDate) -> Result<Vec<Commitment>> {
+        let mut open = self.list(Status::Open)?;
+        open.retain(|c| c.is_due(today));
+        Ok(open)
+    }
+
+    fn get(&self, id: i64) -> Result<Option<Commitment>> {
+        Ok(self
+            .query(
+                "SELECT id, promise, due, session_id, created_at, status, note FROM commitments
+                 WHERE id = ?1",
+                [id],
+            )?
+            .pop())
+    }
+
+    fn query<P: rusqlite::Params>(&self, sql: &str, params: P) -> Result<Vec<Commitment>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt.query_map(params, |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, Option<String>>(6)?,
+            ))
+        })?;
+        let mut commitments = Vec::new();
+        for row in rows {
+            let (id, promise, due, session_id, created, status, note) = row?;
+            commitments.push(Commitment {
+                id,
+                promise,
+                due: due.and_then(|d| NaiveDate::parse_from_str(&d, DATE_FORMAT).ok()),
+                session_id,
+                created: NaiveDateTime::parse_from_str(&created, TIME_FORMAT).unwrap_or_default(),
+                status: Status::parse(&status).unwrap_or(Status::Open),
+                note,
+            });
+        }
+        Ok(commitments)
+    }
+
+    /// Mark commitment `id` done, broken, or open again, returning it
+    pub fn resolve(
+        &self,
+        id: i64,
+        status: Status,
+        note: Option<&str>,
+    ) -> Result<Option<Commitment>> {
+        let resolved_at = (status != Status::Open)
+            .then(|| locale::now().naive_local().format(TIME_FORMAT).to_string());
+        let changed = self.conn.lock().unwrap().execute(
+            "UPDATE commitments SET status = ?1, note = ?2, resolved_at = ?3, reported = 0
+             WHERE id = ?4",
+            params![status.as_str(), note, resolved_at, id],
+        )?;
+        if changed == 0 {
+            return Ok(None);
+        }
+        self.get(id)
+    }
+
+    /// Broken commitments not in a digest yet, marked as reported
+    pub fn take_broken(&self) -> Result<Vec<Commitment>> {
+        let broken = self.query(
+            "SELECT id, promise, due, session_id, created_at, status, note FROM commitments
+             WHERE status = 'broken' AND reported = 0 ORDER BY id",
+            [],
+        )?;
+        let conn = self.conn.lock().unwrap();
+        for commitment in &broken {
+            conn.execute(
+                "UPDATE commitments SET reported = 1 WHERE id = ?1",
+                [commitment.id],
+            )?;
+        }
+        Ok(broken)
+    }
+
+    /// Messages of `session_id` already read for commitments
+    pub fn read_count(&self, session_id: &str) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        let read: Option<i64> = conn
+            .query_row(
+                "SELECT read FROM commitment_reads WHERE session_id = ?1",
+                [session_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(read.unwrap_or(0) as usize)
+    }
+
+    /// Record that the first `read` messages of `session_id` have been read
+    pub fn set_read(&self, session_id: &str, read: usize) -> Result<()> {
+        self.conn.lock().unwrap().execute(
+            "INSERT INTO commitment_reads (session_id, read) VALUES (?1, ?2)
+             ON CONFLICT(session_id) DO UPDATE SET read = excluded.read",
+            params![session_id, read as i64],
+        )?;
+        Ok(())
+    }
+}
+
+/// Commitments found in a conversation, with their due dates parsed; a
+/// date that doesn't parse leaves it for the next day's check
+pub fn parse_extracted(found: ExtractedCommitments) -> Vec<(String, Option<NaiveDate>)> {
+    found
+        .commitments
+        .into_iter()
+        .map(|c| {
+            let due = c
+                .due
+                .as_deref()
+                .and_then(|d| NaiveDate::parse_from_str(d.trim(), DATE_FORMAT).ok());
+            (c.promise, due)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> CommitmentStore {
+        CommitmentStore::with_connection(Connection::open_in_memory().unwrap()).unwrap()
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, day).unwrap()
+    }
+
+    #[test]
+    fn test_store_resolve_and_report() {
+        let store = store();
+        let hours = store
+            .add("Check the library's Saturday hours", Some(date(20)), "s1")
+            .unwrap()
+            .unwrap();
+        let list = store
+            .add("Add oat milk to the list", None, "s1")
+            .unwrap()
+            .unwrap();
+        // The same promise again isn't tracked twice
+        assert!(store
+            .add("add oat milk to the list", None, "s2")
+            .unwrap()
+            .is_none());
+
+        assert_eq!(
+            store.due(NaiveDate::MAX).unwrap(),
+            vec![hours.clone(), list.clone()]
+        );
+
+        store.resolve(list.id, Status::Done, None).unwrap();
+        let broken = store
+            .resolve(hours.id, Status::Broken, Some("never looked them up"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(broken.note.as_deref(), Some("never looked them up"));
+        assert!(store.list(Status::Open).unwrap().is_empty());
+
+        assert_eq!(store.take_broken().unwrap(), vec![broken]);
+        assert!(store.take_broken().unwrap().is_empty());
+        assert!(store.resolve(99, Status::Done, None).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_is_due() {
+        let mut commitment = Commitment {
+            id: 1,
+            promise: "Check the library hours".to_string(),
+            due: Some(date(20)),
+            session_id: "s1".to_string(),
+            created: date(18).and_hms_opt(19, 30, 0).unwrap(),
+            status: Status::Open,
+            note: None,
+        };
+        assert!(!commitment.is_due(date(19)));
+        assert!(commitment.is_due(date(20)));
+        // With no day given, it's checked from the next day
+        commitment.due = None;
+        assert!(!commitment.is_due(date(18)));
+        assert!(commitment.is_due(date(19)));
+    }
+
+    #[test]
+    fn test_read_count() {
+        let store = store();
+        assert_eq!(store.read_count("s1").unwrap(), 0);
+        store.set_read("s1", 4).unwrap();
+        store.set_read("s1", 9).unwrap();
+        assert_eq!(store.read_count("s1").unwrap(), 9);
+    }
+
+    #[test]
+    fn test_mentions_promise() {
+        assert!(mentions_promise(
+            ["Sure. I'll check the hours tomorrow and let you know."].into_iter()
+        ));
+        assert!(mentions_promise(["I will add it to the list."].into_iter()));
+        assert!(!mentions_promise(
+            ["The library opens at 10 on Saturdays."].into_iter()
+        ));
+    }
+
+    #[test]
+    fn test_parse_extracted() {
+        let found = ExtractedCommitments {
+            commitments: vec![
+                ExtractedCommitment {
+                    promise: "Check the library hours".to_string(),
+                    due: Some("2026-10-20".to_string()),
+                },
+                ExtractedCommitment {
+                    promise: "Follow up on the plumber".to_string(),
+                    due: Some("next week".to_string()),
+                },
+            ],
+        };
+        assert_eq!(
+            parse_extracted(found),
+            vec![
+                ("Check the library hours".to_string(), Some(date(20))),
+                ("Follow up on the plumber".to_string(), None),
+            ]
+        );
+    }
+}
@@ -1,24 +1,37 @@
 //! In-process turn gate using a tokio Semaphore.
 //!
-//! Prevents heartbeat and HTTP sessions from running agent turns
-//! simultaneously within the same daemon process.
+//! Limits how many agent turns (HTTP sessions, chat bridges, heartbeat)
+//! run at once within the same daemon process, and shares the
+//! cross-process workspace lock between them.
 
-use std::sync::Arc;
-use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use anyhow::Result;
+use std::sync::{Arc, Weak};
+use tokio::sync::{Mutex, OwnedSemaphorePermit, Semaphore};
 
-/// A single-permit gate that serializes agent turns within a process.
+use super::{WorkspaceLock, WorkspaceLockGuard};
+
+/// A gate that admits a fixed number of agent turns within a process.
 ///
-/// HTTP handlers call `acquire()` (async, waits for the permit).
+/// HTTP handlers call `acquire()` (async, waits for a permit).
 /// Heartbeat calls `try_acquire()` and skips if busy.
 #[derive(Clone)]
 pub struct TurnGate {
     semaphore: Arc<Semaphore>,
+    /// The workspace lock, while any turn admitted here holds it
+    workspace: Arc<Mutex<Weak<WorkspaceLockGuard>>>,
 }
 
 impl TurnGate {
+    /// A gate that runs one turn at a time
     pub fn new() -> Self {
+        Self::with_limit(1)
+    }
+
+    /// A gate that lets `limit` turns run at once (each in its own session)
+    pub fn with_limit(limit: usize) -> Self {
         Self {
-            semaphore: Arc::new(Semaphore::new(1)),
+            semaphore: Arc::new(Semaphore::new(limit.max(1))),
+            workspace: Arc::new(Mutex::new(Weak::new())),
         }
     }
 
@@ -36,10 +49,42 @@ impl TurnGate {
         self.semaphore.clone().try_acquire_owned().ok()
     }
 
-    /// Returns `true` if an agent turn is currently in progress.
+    /// Returns `true` if no more agent turns can start right now.
     pub fn is_busy(&self) -> bool {
         self.semaphore.available_permits() == 0
     }
+
+    /// Take the cross-process workspace lock for a turn. Turns admitted by
+    /// this gate share one hold on it: the first takes the file lock and
+    /// the last to drop its guard releases it, so they run side by side
+    /// while other processes (CLI, desktop) still wait.
+    pub async fn lock_workspace(&self, lock: &WorkspaceLock) -> Result<Arc<WorkspaceLockGuard>> {
+        let mut held = self.workspace.lock().await;
+        if let Some(guard) = held.upgrade() {
+            return Ok(guard);
+        }
+        let lock = lock.clone();
+        let guard = Arc::new(tokio::task::spawn_blocking(move || lock.acquire()).await??);
+        *held = Arc::downgrade(&guard);
+        Ok(guard)
+    }
+
+    /// Like `lock_workspace`, but `None` if another process holds the lock
+    pub async fn try_lock_workspace(
+        &self,
+        lock: &WorkspaceLock,
+    ) -> Result<Option<Arc<WorkspaceLockGuard>>> {
+        let mut held = self.workspace.lock().await;
+        if let Some(guard) = held.upgrade() {
+            return Ok(Some(guard));
+        }
+        let Some(guard) = lock.try_acquire()? else {
+            return Ok(None);
+        };
+        let guard = Arc::new(guard);
+        *held = Arc::downgrade(&guard);
+        Ok(Some(guard))
+    }
 }
 
 impl Default for TurnGate {
@@ -79,6 +124,36 @@ mod tests {
         assert!(permit.is_some());
     }
 
+    #[tokio::test]
+    async fn with_limit_admits_that_many_turns() {
+        let gate = TurnGate::with_limit(2);
+
+        let _first = gate.acquire().await;
+        assert!(!gate.is_busy());
+        let _second = gate.try_acquire().expect("second turn should start");
+        assert!(gate.is_busy());
+        assert!(gate.try_acquire().is_none());
+    }
+
+    #[tokio::test]
+    async fn turns_share_the_workspace_lock() {
+        let tmp = tempfile::tempdir().unwrap();
+        let lock = WorkspaceLock::at(tmp.path().join("test.lock"));
+        let gate = TurnGate::with_limit(2);
+
+        let first = gate.lock_workspace(&lock).await.unwrap();
+        // A second turn joins the hold instead of waiting on the file lock
+        let second = gate.try_lock_workspace(&lock).await.unwrap();
+        assert!(second.is_some());
+        // Another process (here: an unrelated lock holder) can't get in
+        assert!(lock.try_acquire().unwrap().is_none());
+
+        drop(first);
+        assert!(lock.try_acquire().unwrap().is_none());
+        drop(second);
+        assert!(lock.try_acquire().unwrap().is_some());
+    }
+
     #[tokio::test]
     async fn clone_shares_state() {
         let gate1 = TurnGate::new();
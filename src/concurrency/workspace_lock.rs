@@ -43,6 +43,12 @@ impl WorkspaceLock {
         Ok(Self { path })
     }
 
+    /// A lock on the file at `path`
+    #[cfg(test)]
+    pub(super) fn at(path: PathBuf) -> Self {
+        Self { path }
+    }
+
     /// Blocking acquire — waits until the lock is available.
     ///
     /// Returns an RAII guard that releases the lock on drop.
@@ -0,0 +1,164 @@
+//! Household agents from `~/.homegpt/agents.toml`
+//!
+//! An agent defined there is a persona over another agent's memory: it
+//! searches and writes the same index and workspace, but loads its own SOUL
+//! file and only the skills it lists. Agents that aren't defined keep their
+//! own index, as before.
+//!
+//! ```toml
+//! [agents.tutor]
+//! description = "Homework help for the kids"
+//! soul = "personas/tutor/SOUL.md"
+//! skills = ["tutor", "flashcards"]
+//!
+//! [agents.house]
+//! description = "House manager"
+//! memory = "main"
+//! soul = "personas/house/SOUL.md"
+//! ```
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::MemoryConfig;
+
+pub const AGENTS_FILE: &str = "agents.toml";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AgentsFile {
+    pub agents: BTreeMap<String, AgentDefinition>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AgentDefinition {
+    /// What the agent is for
+    pub description: String,
+
+    /// Agent whose memory index this one shares
+    pub memory: String,
+
+    /// Workspace-relative SOUL file to load instead of SOUL.md; translated
+    /// the same way (`SOUL.es.md`)
+    pub soul: Option<String>,
+
+    /// Skill names this agent loads; all of them if unset
+    pub skills: Option<Vec<String>>,
+}
+
+impl Default for AgentDefinition {
+    fn default() -> Self {
+        Self {
+            description: String::new(),
+            memory: "main".to_string(),
+            soul: None,
+            skills: None,
+        }
+    }
+}
+
+impl AgentDefinition {
+    pub fn allows_skill(&self, name: &str) -> bool {
+        self.skills
+            .as_ref()
+            .is_none_or(|skills| skills.iter().any(|s| s.eq_ignore_ascii_case(name)))
+    }
+}
+
+impl AgentsFile {
+    /// Read `agents.toml` in `state_dir`; empty if there isn't one
+    pub fn load(state_dir: &Path) -> Result<Self> {
+        let path = state_dir.join(AGENTS_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)?;
+        let file: Self =
+            toml::from_str(&content).with_context(|| format!("Invalid {}", path.display()))?;
+        file.validate()?;
+        Ok(file)
+    }
+
+    fn validate(&self) -> Result<()> {
+        for (id, agent) in &self.agents {
+            if let Some(shared) = self.agents.get(&agent.memory) {
+                // One level only, so an index is always named by a real id
+                if shared.memory != agent.memory {
+                    anyhow::bail!(
+                        "Agent '{}' shares the memory of '{}', which shares '{}'; point it at '{}'",
+                        id,
+                        agent.memory,
+                        shared.memory,
+                        shared.memory
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, agent_id: &str) -> Option<&AgentDefinition> {
+        self.agents.get(agent_id)
+    }
+}
+
+/// The state dir `agents.toml` lives in: the workspace's parent, like the
+/// memory index
+pub fn agents_dir(memory_config: &MemoryConfig) -> Result<PathBuf> {
+    let workspace = PathBuf::from(shellexpand::tilde(&memory_config.workspace).to_string());
+    workspace
+        .parent()
+        .map(Path::to_path_buf)
+        .ok_or_else(|| anyhow::anyhow!("Workspace has no parent directory"))
+}
+
+/// `agent_id`'s definition in `agents.toml`, if it has one
+pub fn load_agent(memory_config: &MemoryConfig, agent_id: &str) -> Result<Option<AgentDefinition>> {
+    let file = AgentsFile::load(&agents_dir(memory_config)?)?;
+    Ok(file.get(agent_id).cloned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load_agents() -> Result<()> {
+        let dir = TempDir::new()?;
+        assert!(AgentsFile::load(dir.path())?.agents.is_empty());
+
+        fs::write(
+            dir.path().join(AGENTS_FILE),
+            r#"
+[agents.tutor]
+soul = "personas/tutor/SOUL.md"
+skills = ["tutor", "Flashcards"]
+
+[agents.house]
+memory = "family"
+"#,
+        )?;
+        let file = AgentsFile::load(dir.path())?;
+        let tutor = file.get("tutor").unwrap();
+        assert_eq!(tutor.memory, "main");
+        assert!(tutor.allows_skill("flashcards"));
+        assert!(!tutor.allows_skill("groceries"));
+        let house = file.get("house").unwrap();
+        assert_eq!(house.memory, "family");
+        assert!(house.allows_skill("groceries"));
+        assert!(file.get("main").is_none());
+
+        // A chain of shared memories is rejected
+        fs::write(
+            dir.path().join(AGENTS_FILE),
+            "[agents.a]\nmemory = \"b\"\n[agents.b]\nmemory = \"main\"\n",
+        )?;
+        assert!(AgentsFile::load(dir.path()).is_err());
+        Ok(())
+    }
+}
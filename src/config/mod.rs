@@ -1,11 +1,14 @@
+mod agents;
 mod migrate;
 mod schema;
 
+pub use agents::{agents_dir, load_agent, AgentDefinition, AgentsFile, AGENTS_FILE};
 pub use migrate::{has_openclaw_workspace, openclaw_config_path, try_migrate_openclaw_config};
 pub use schema::*;
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
 
@@ -14,6 +17,9 @@ pub struct Config {
     #[serde(default)]
     pub agent: AgentConfig,
 
+    #[serde(default)]
+    pub locale: LocaleConfig,
+
     #[serde(default)]
     pub providers: ProvidersConfig,
 
@@ -31,6 +37,63 @@ pub struct Config {
 
     #[serde(default)]
     pub tools: ToolsConfig,
+
+    #[serde(default)]
+    pub approval: ApprovalConfig,
+
+    #[serde(default)]
+    pub files: FilesConfig,
+
+    #[serde(default)]
+    pub limits: LimitsConfig,
+
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+
+    #[serde(default)]
+    pub safety: SafetyConfig,
+
+    #[serde(default)]
+    pub finance: FinanceConfig,
+
+    #[serde(default)]
+    pub business: BusinessConfig,
+
+    #[serde(default)]
+    pub weather: WeatherConfig,
+
+    #[serde(default)]
+    pub home_assistant: Option<HomeAssistantConfig>,
+
+    #[serde(default)]
+    pub presence: PresenceConfig,
+
+    #[serde(default)]
+    pub announce: AnnounceConfig,
+
+    #[serde(default)]
+    pub stt: SttConfig,
+
+    #[serde(default)]
+    pub bridges: BridgesConfig,
+
+    #[serde(default)]
+    pub webhooks: WebhooksConfig,
+
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+
+    #[serde(default)]
+    pub quiet_hours: QuietHoursConfig,
+
+    #[serde(default)]
+    pub calendar: CalendarConfig,
+
+    #[serde(default)]
+    pub desktop: DesktopConfig,
+
+    #[serde(default)]
+    pub backup: BackupConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,6 +110,39 @@ pub struct AgentConfig {
     /// Maximum tokens for LLM response
     #[serde(default = "default_max_tokens")]
     pub max_tokens: usize,
+
+    /// Model for heartbeat runs (default: default_model)
+    #[serde(default)]
+    pub heartbeat_model: Option<String>,
+
+    /// Model for session compaction summaries (default: the session's model)
+    #[serde(default)]
+    pub summary_model: Option<String>,
+
+    /// Model for turns whose context includes images, for when the chat
+    /// model can't see them (default: the session's model)
+    #[serde(default)]
+    pub vision_model: Option<String>,
+
+    /// Check what a model can do (tool calls, images, JSON, context size)
+    /// the first time it's used, and work around what it can't
+    #[serde(default = "default_true")]
+    pub probe_capabilities: bool,
+
+    /// Check replies that cite memory against the cited chunks with the
+    /// summary model: "off", "flag" (note unsupported claims) or "revise"
+    /// (leave them out)
+    #[serde(default = "default_self_check")]
+    pub self_check: String,
+}
+
+impl AgentConfig {
+    /// Model used by the heartbeat runner
+    pub fn heartbeat_model(&self) -> &str {
+        self.heartbeat_model
+            .as_deref()
+            .unwrap_or(&self.default_model)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -60,7 +156,7 @@ pub struct ToolsConfig {
     pub web_fetch_max_bytes: usize,
 
     /// Tools that require user approval before execution
-    /// e.g., ["bash", "write_file", "edit_file"]
+    /// e.g., ["bash", "write_file", "edit_file"] (same as `approval.tools`)
     #[serde(default)]
     pub require_approval: Vec<String>,
 
@@ -90,6 +186,18 @@ pub struct ProvidersConfig {
 
     #[serde(default)]
     pub claude_cli: Option<ClaudeCliConfig>,
+
+    #[serde(default)]
+    pub openrouter: Option<OpenRouterConfig>,
+
+    #[serde(default)]
+    pub gemini: Option<GeminiConfig>,
+
+    #[serde(default)]
+    pub groq: Option<GroqConfig>,
+
+    #[serde(default)]
+    pub voyage: Option<VoyageConfig>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -100,6 +208,59 @@ pub struct OpenAIConfig {
     pub base_url: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenRouterConfig {
+    pub api_key: String,
+
+    #[serde(default = "default_openrouter_base_url")]
+    pub base_url: String,
+
+    /// Sent as `X-Title` for OpenRouter's app attribution
+    #[serde(default = "default_openrouter_app_name")]
+    pub app_name: String,
+
+    /// Sent as `HTTP-Referer` for OpenRouter's app attribution
+    #[serde(default)]
+    pub app_url: Option<String>,
+
+    /// Models tried in order if the requested model fails
+    #[serde(default)]
+    pub fallback_models: Vec<String>,
+
+    /// Upstream providers to try first, in order
+    #[serde(default)]
+    pub provider_order: Vec<String>,
+
+    /// Upstream provider ranking: "price", "throughput" or "latency"
+    #[serde(default)]
+    pub sort: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeminiConfig {
+    pub api_key: String,
+
+    #[serde(default = "default_gemini_base_url")]
+    pub base_url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroqConfig {
+    pub api_key: String,
+
+    #[serde(default = "default_groq_base_url")]
+    pub base_url: String,
+}
+
+/// Voyage AI (embeddings only)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoyageConfig {
+    pub api_key: String,
+
+    #[serde(default = "default_voyage_base_url")]
+    pub base_url: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnthropicConfig {
     pub api_key: String,
@@ -127,71 +288,1092 @@ pub struct ClaudeCliConfig {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct HeartbeatConfig {
-    #[serde(default = "default_true")]
-    pub enabled: bool,
+pub struct HeartbeatConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    #[serde(default = "default_interval")]
+    pub interval: String,
+
+    #[serde(default)]
+    pub active_hours: Option<ActiveHours>,
+
+    #[serde(default)]
+    pub timezone: Option<String>,
+
+    /// Plan the HEARTBEAT.md checklist first, then run each item as its own
+    /// turn and write a run report to memory/heartbeat-report.md
+    #[serde(default)]
+    pub planning: bool,
+
+    /// Run each `## ` section of HEARTBEAT.md in its own session, with its
+    /// own token budget and tools, logging the outcomes to
+    /// memory/heartbeat-log.md
+    #[serde(default)]
+    pub isolate_tasks: bool,
+
+    /// Tokens one task's session may use before it's stopped (0 = no cap)
+    #[serde(default = "default_task_token_budget")]
+    pub task_token_budget: u64,
+
+    /// Tools a task's session may use (empty = all)
+    #[serde(default)]
+    pub task_tools: Vec<String>,
+
+    /// Budgets and tools for particular tasks, by section title
+    #[serde(default)]
+    pub tasks: Vec<HeartbeatTaskConfig>,
+
+    /// What to do with a task whose time passed while the heartbeat wasn't
+    /// running (asleep, daemon down): "run" it late or "skip" to its next
+    /// time, noting it in the daily digest
+    #[serde(default = "default_catch_up")]
+    pub catch_up: String,
+}
+
+impl HeartbeatConfig {
+    fn task(&self, title: &str) -> Option<&HeartbeatTaskConfig> {
+        self.tasks
+            .iter()
+            .find(|task| task.title.eq_ignore_ascii_case(title))
+    }
+
+    /// Token budget of the task titled `title` (None = no cap)
+    pub fn token_budget_for(&self, title: &str) -> Option<u64> {
+        let budget = self
+            .task(title)
+            .and_then(|task| task.token_budget)
+            .unwrap_or(self.task_token_budget);
+        (budget > 0).then_some(budget)
+    }
+
+    /// Catch-up policy of the task titled `title`
+    pub fn catch_up_for(&self, title: &str) -> &str {
+        self.task(title)
+            .and_then(|task| task.catch_up.as_deref())
+            .unwrap_or(&self.catch_up)
+    }
+
+    /// Tools the task titled `title` may use (empty = all)
+    pub fn tools_for(&self, title: &str) -> &[String] {
+        self.task(title)
+            .and_then(|task| task.tools.as_deref())
+            .unwrap_or(&self.task_tools)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeartbeatTaskConfig {
+    /// Section title in HEARTBEAT.md, e.g. "ErgoTools Check"
+    pub title: String,
+
+    /// Overrides heartbeat.task_token_budget
+    #[serde(default)]
+    pub token_budget: Option<u64>,
+
+    /// Overrides heartbeat.task_tools
+    #[serde(default)]
+    pub tools: Option<Vec<String>>,
+
+    /// Overrides heartbeat.catch_up
+    #[serde(default)]
+    pub catch_up: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveHours {
+    pub start: String,
+    pub end: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryConfig {
+    #[serde(default = "default_workspace")]
+    pub workspace: String,
+
+    /// Template pack for new workspace files: "home" (default), "minimal",
+    /// or a path to a directory of templates. Files in ~/.homegpt/templates/
+    /// override pack files with the same relative path.
+    #[serde(default = "default_template_pack")]
+    pub template_pack: String,
+
+    /// Embedding provider: "local" (fastembed, default), "openai", or "none"
+    #[serde(default = "default_embedding_provider")]
+    pub embedding_provider: String,
+
+    #[serde(default = "default_embedding_model")]
+    pub embedding_model: String,
+
+    /// Reduced embedding size for Matryoshka models (e.g. 512 for
+    /// text-embedding-3-large; 0 = the model's full size)
+    #[serde(default)]
+    pub embedding_dimensions: usize,
+
+    /// How chunk embeddings are stored: "float" (JSON, default), "int8" or "binary"
+    #[serde(default = "default_embedding_storage")]
+    pub embedding_storage: String,
+
+    /// Re-rank quantized search results with exact vectors from the embedding cache
+    #[serde(default = "default_true")]
+    pub embedding_rescore: bool,
+
+    /// Most chunks sent per embedding request
+    #[serde(default = "default_embedding_batch_size")]
+    pub embedding_batch_size: usize,
+
+    /// Most estimated tokens sent per embedding request (0 = no cap)
+    #[serde(default = "default_embedding_batch_tokens")]
+    pub embedding_batch_tokens: usize,
+
+    /// Embedding requests per minute (0 = unlimited)
+    #[serde(default)]
+    pub embedding_rpm: u32,
+
+    /// Embedding tokens per minute (0 = unlimited)
+    #[serde(default)]
+    pub embedding_tpm: u32,
+
+    /// Cache directory for local embedding models (optional)
+    /// Default: ~/.cache/homegpt/models
+    /// Can also be set via FASTEMBED_CACHE_DIR environment variable
+    #[serde(default = "default_embedding_cache_dir")]
+    pub embedding_cache_dir: String,
+
+    /// ONNX Runtime execution provider for local embeddings: "cpu" (default),
+    /// "auto", "cuda", "coreml" or "directml"
+    #[serde(default = "default_embedding_execution_provider")]
+    pub embedding_execution_provider: String,
+
+    /// Threads for local embeddings (0 = one per core)
+    #[serde(default)]
+    pub embedding_threads: usize,
+
+    #[serde(default = "default_chunk_size")]
+    pub chunk_size: usize,
+
+    #[serde(default = "default_chunk_overlap")]
+    pub chunk_overlap: usize,
+
+    /// Chunking strategy: "lines" (default), "headings", "sentences" or "table"
+    #[serde(default = "default_chunk_strategy")]
+    pub chunk_strategy: String,
+
+    /// Hard cap on tokens per chunk (0 = no cap)
+    #[serde(default)]
+    pub chunk_max_tokens: usize,
+
+    /// Per-directory chunking overrides (longest matching path wins)
+    #[serde(default)]
+    pub chunking: Vec<ChunkingRule>,
+
+    /// Ask the LLM for alternative phrasings of memory_search queries
+    #[serde(default)]
+    pub query_rewrite: bool,
+
+    /// Embed an LLM-written hypothetical answer alongside the query (HyDE)
+    #[serde(default)]
+    pub hyde: bool,
+
+    /// Model used for query expansion (default: agent.default_model)
+    #[serde(default)]
+    pub query_expansion_model: Option<String>,
+
+    /// Tokens of retrieved chunks memory_search returns per call (0 = no limit)
+    #[serde(default = "default_context_budget_tokens")]
+    pub context_budget_tokens: usize,
+
+    /// Chunks memory_search returns from any one file (0 = no limit)
+    #[serde(default = "default_max_chunks_per_file")]
+    pub max_chunks_per_file: usize,
+
+    /// Condense chunks that don't fit the budget with the summary model
+    /// instead of dropping them
+    #[serde(default)]
+    pub summarize_overflow: bool,
+
+    /// Record memory searches and writes in the audit log (`homegpt memory audit`)
+    #[serde(default = "default_true")]
+    pub audit_log: bool,
+
+    /// Link people, places, appliances and vendors found in chunks at index
+    /// time, for the graph_neighbors tool
+    #[serde(default = "default_true")]
+    pub entity_graph: bool,
+
+    /// Record the dates written in chunks at index time, for the
+    /// upcoming_events and what_happened_around tools
+    #[serde(default = "default_true")]
+    pub time_index: bool,
+
+    /// Lowest verified-chunk confidence a grounded answer may rest on:
+    /// "none", "low", "medium" or "high" (below it, the answer abstains)
+    #[serde(default = "default_answer_min_confidence")]
+    pub answer_min_confidence: String,
+
+    /// After a session ends (or idles out in the daemon), propose facts
+    /// from it for `homegpt memory review`
+    #[serde(default)]
+    pub extract_facts: bool,
+
+    /// After a session ends, track what the assistant said it would do
+    /// later, for the heartbeat to check on
+    #[serde(default)]
+    pub track_commitments: bool,
+
+    /// On the first heartbeat of a week, sum up the week before's sessions
+    /// and heartbeat log in `memory/journal/YYYY-Www.md`
+    #[serde(default)]
+    pub weekly_journal: bool,
+
+    /// Cosine similarity above which chunks are merged by `memory consolidate`
+    #[serde(default = "default_consolidation_threshold")]
+    pub consolidation_threshold: f32,
+
+    /// How often the heartbeat merges near-duplicate chunks like
+    /// `memory consolidate --apply` (e.g. "7d"; empty = only by hand)
+    #[serde(default)]
+    pub consolidation_interval: String,
+
+    /// Snapshot the index and workspace before schema migrations,
+    /// consolidation and imports
+    #[serde(default = "default_true")]
+    pub auto_snapshot: bool,
+
+    /// Automatic snapshots kept (snapshots taken by hand are never pruned)
+    #[serde(default = "default_keep_snapshots")]
+    pub keep_snapshots: usize,
+
+    /// How often the heartbeat refreshes memory/health-report.md (e.g. "7d";
+    /// empty = only via `homegpt memory doctor`)
+    #[serde(default = "default_health_report_interval")]
+    pub health_report_interval: String,
+
+    /// Days after which a file's `last_verified` frontmatter counts as stale
+    #[serde(default = "default_stale_after_days")]
+    pub stale_after_days: u32,
+
+    /// Days before `last_verified` counts as stale for files in a category,
+    /// instead of `stale_after_days` (e.g. `kids = 90`)
+    #[serde(default)]
+    pub max_age_days: BTreeMap<String, u32>,
+
+    /// Have the heartbeat ask the family to re-confirm stale files
+    #[serde(default = "default_true")]
+    pub reconfirm_stale: bool,
+
+    /// Directory whose PDF, DOCX, .txt and spreadsheet (CSV/TSV/XLSX) files
+    /// are indexed alongside the markdown files (relative to workspace or
+    /// absolute)
+    #[serde(default = "default_documents_dir")]
+    pub documents_dir: String,
+
+    /// Additional paths to index (relative to workspace or absolute)
+    /// Each path uses a glob pattern for file matching
+    #[serde(default = "default_index_paths")]
+    pub paths: Vec<MemoryIndexPath>,
+
+    /// Maximum messages to save in session memory files (0 = unlimited)
+    /// Similar to OpenClaw's hooks.session-memory.messages (default: 15)
+    #[serde(default = "default_session_max_messages")]
+    pub session_max_messages: usize,
+
+    /// Maximum characters per message in session memory (0 = unlimited)
+    /// Set to 0 to preserve full message content like OpenClaw
+    #[serde(default)]
+    pub session_max_chars: usize,
+
+    /// Optional workspace sync to a git remote
+    #[serde(default)]
+    pub sync: SyncConfig,
+
+    /// Review queue for the agent's edits to workspace files
+    #[serde(default)]
+    pub staging: StagingConfig,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StagingConfig {
+    /// Stage the agent's file writes inside the workspace as pending
+    /// changes (`homegpt memory changes`) instead of writing them
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Changes written right away without review
+    #[serde(default)]
+    pub auto_approve: Vec<AutoApproveRule>,
+}
+
+/// Changes to files under a workspace-relative path that skip review
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoApproveRule {
+    pub path: String,
+
+    /// Only changes that add to the end of the file (e.g. tutor notes)
+    #[serde(default = "default_true")]
+    pub append_only: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Git remote URL (e.g. "git@homeserver:homegpt-workspace.git").
+    /// Empty = commit locally only (useful with Syncthing hooks)
+    #[serde(default)]
+    pub remote: String,
+
+    #[serde(default = "default_sync_branch")]
+    pub branch: String,
+
+    /// How often the daemon syncs (e.g. "15m")
+    #[serde(default = "default_sync_interval")]
+    pub interval: String,
+
+    /// Shell command run in the workspace before each sync
+    #[serde(default)]
+    pub pre_sync: Option<String>,
+
+    /// Shell command run in the workspace after each sync
+    #[serde(default)]
+    pub post_sync: Option<String>,
+}
+
+/// Chunking override for files under a workspace-relative directory.
+/// Unset fields fall back to the global memory chunk settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkingRule {
+    pub path: String,
+
+    #[serde(default)]
+    pub strategy: Option<String>,
+
+    #[serde(default)]
+    pub chunk_size: Option<usize>,
+
+    #[serde(default)]
+    pub chunk_overlap: Option<usize>,
+
+    #[serde(default)]
+    pub max_tokens: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryIndexPath {
+    pub path: String,
+    #[serde(default = "default_pattern")]
+    pub pattern: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FinanceConfig {
+    /// Monthly budget per category, e.g. `groceries = 600`
+    #[serde(default)]
+    pub budgets: BTreeMap<String, f64>,
+
+    /// Categorization rules, checked in order before the LLM
+    #[serde(default)]
+    pub rules: Vec<CategoryRule>,
+
+    /// Ask an LLM to categorize transactions no rule matches
+    #[serde(default = "default_true")]
+    pub llm_categorize: bool,
+
+    /// Model for LLM categorization (default: agent.default_model)
+    #[serde(default)]
+    pub categorize_model: Option<String>,
+
+    /// chrono format of the date column in bank exports (default: try
+    /// common formats, month before day)
+    #[serde(default)]
+    pub date_format: Option<String>,
+
+    /// Currency symbol used in summaries and reports
+    #[serde(default = "default_currency")]
+    pub currency: String,
+
+    /// Write a report for the previous month on the first heartbeat of a
+    /// new month
+    #[serde(default = "default_true")]
+    pub monthly_report: bool,
+}
+
+/// Transactions whose description contains `pattern` (case-insensitive)
+/// get `category`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryRule {
+    pub pattern: String,
+    pub category: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BusinessConfig {
+    /// Backends checked by the heartbeat
+    #[serde(default)]
+    pub connectors: Vec<BusinessConnectorConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BusinessConnectorConfig {
+    /// Name used in alerts and `memory/business/<name>-status.md`
+    pub name: String,
+
+    /// Connector implementation: "pocketbase"
+    #[serde(default = "default_connector_kind")]
+    pub kind: String,
+
+    pub base_url: String,
+
+    /// API token sent as the Authorization header (supports ${ENV_VAR})
+    #[serde(default)]
+    pub token: String,
+
+    /// Minimum time between checks
+    #[serde(default = "default_connector_interval")]
+    pub interval: String,
+
+    /// Alert when more reviews than this are pending
+    #[serde(default = "default_review_threshold")]
+    pub review_threshold: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeatherConfig {
+    /// Place name, e.g. "Portland, OR" (weather is off when this and the
+    /// coordinates are unset)
+    #[serde(default)]
+    pub location: String,
+
+    /// Coordinates; looked up from `location` when not set
+    #[serde(default)]
+    pub latitude: Option<f64>,
+
+    #[serde(default)]
+    pub longitude: Option<f64>,
+
+    /// "metric" or "imperial"
+    #[serde(default = "default_weather_units")]
+    pub units: String,
+
+    /// How long a fetched forecast is reused before asking Open-Meteo again
+    #[serde(default = "default_weather_cache_ttl")]
+    pub cache_ttl: String,
+
+    /// Local time (HH:MM) after which the first heartbeat of the day gets
+    /// today's forecast; empty to disable
+    #[serde(default = "default_weather_digest_time")]
+    pub digest_time: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HomeAssistantConfig {
+    /// e.g. "http://homeassistant.local:8123"
+    pub url: String,
+
+    /// Long-lived access token (supports ${ENV_VAR})
+    pub token: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PresenceConfig {
+    /// People whose phones report whether they're home
+    #[serde(default)]
+    pub people: Vec<PersonConfig>,
+
+    /// OwnTracks phones publishing to an MQTT broker
+    #[serde(default)]
+    pub owntracks: Option<OwnTracksConfig>,
+
+    /// While a kid is home during these hours, spoken announcements are
+    /// suppressed
+    #[serde(default)]
+    pub kids_bedtime: Option<ActiveHours>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersonConfig {
+    pub name: String,
+
+    #[serde(default)]
+    pub kid: bool,
+
+    /// Home Assistant entity, e.g. "person.ella" or "device_tracker.ella_phone"
+    #[serde(default)]
+    pub entity: Option<String>,
+
+    /// OwnTracks "user/device" (or just "user") from the MQTT topic
+    #[serde(default)]
+    pub owntracks: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OwnTracksConfig {
+    pub host: String,
+
+    #[serde(default = "default_mqtt_port")]
+    pub port: u16,
+
+    #[serde(default)]
+    pub username: String,
+
+    /// Supports ${ENV_VAR}
+    #[serde(default)]
+    pub password: String,
+
+    #[serde(default = "default_owntracks_topic")]
+    pub topic: String,
+
+    /// OwnTracks region (set up in the app) that counts as home
+    #[serde(default = "default_home_region")]
+    pub home_region: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AnnounceConfig {
+    /// Speakers for spoken announcements
+    #[serde(default)]
+    pub devices: Vec<AnnounceDeviceConfig>,
+
+    /// No spoken announcements during these hours (default: the household's
+    /// `[quiet_hours]`)
+    #[serde(default)]
+    pub quiet_hours: Option<ActiveHours>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnounceDeviceConfig {
+    /// e.g. "kitchen"; the announce tool can target a device by name
+    pub name: String,
+
+    /// "piper" (local speaker), "homeassistant" (a media_player, e.g. Sonos)
+    /// or "openai" (the OpenAI speech API, played on this machine)
+    #[serde(default = "default_announce_kind")]
+    pub kind: String,
+
+    /// Piper voice model (.onnx) for kind = "piper"; speech model for kind =
+    /// "openai" (default "tts-1")
+    #[serde(default)]
+    pub model: String,
+
+    /// Voice for kind = "openai" (default "alloy")
+    #[serde(default)]
+    pub voice: Option<String>,
+
+    /// Piper binary
+    #[serde(default = "default_piper_command")]
+    pub piper_command: String,
+
+    /// Command that plays a WAV file (default: afplay on macOS, aplay elsewhere)
+    #[serde(default)]
+    pub player: Option<String>,
+
+    /// Home Assistant media_player entity for kind = "homeassistant"
+    #[serde(default)]
+    pub media_player: String,
+
+    /// Home Assistant TTS entity used with tts.speak
+    #[serde(default = "default_tts_entity")]
+    pub tts_entity: String,
+}
+
+/// Speech to text, for the desktop app's mic
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SttConfig {
+    /// "whisper" (a Whisper server with the OpenAI API, like the voice
+    /// bridge's) or "openai" (the OpenAI API, using `[providers.openai]`)
+    #[serde(default = "default_stt_kind")]
+    pub kind: String,
+
+    /// Whisper server for kind = "whisper" (its /v1/audio/transcriptions)
+    #[serde(default = "default_stt_url")]
+    pub url: String,
+
+    /// Transcription model (default: "base" for whisper, "whisper-1" for
+    /// openai)
+    #[serde(default)]
+    pub model: String,
+}
+
+/// Language of messages, dates and new workspace files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocaleConfig {
+    /// "en" (default) or "es"
+    #[serde(default = "default_language")]
+    pub language: String,
+
+    /// Other languages the household speaks; the agent answers in the one
+    /// it's addressed in
+    #[serde(default)]
+    pub languages: Vec<String>,
+
+    /// Home timezone (IANA name, e.g. "America/Chicago") for schedules,
+    /// reminders, digests and displayed times; empty = the system's
+    #[serde(default)]
+    pub timezone: String,
+}
+
+/// Calendar subscriptions (.ics URLs) and the published feed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalendarConfig {
+    /// External calendars merged into memory/calendar/upcoming.md
+    #[serde(default)]
+    pub subscriptions: Vec<CalendarSubscriptionConfig>,
+
+    /// How far ahead upcoming.md lists events
+    #[serde(default = "default_calendar_days_ahead")]
+    pub days_ahead: u32,
+
+    /// How often the heartbeat refetches subscriptions
+    #[serde(default = "default_calendar_refresh")]
+    pub refresh: String,
+
+    /// Serve assistant-created events at GET /calendar.ics
+    #[serde(default)]
+    pub publish: bool,
+
+    /// Required as `?token=` on the feed URL (supports ${ENV_VAR})
+    #[serde(default)]
+    pub feed_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalendarSubscriptionConfig {
+    /// Shown next to each event, e.g. "School"
+    pub name: String,
+
+    /// .ics URL (https:// or webcal://, supports ${ENV_VAR})
+    pub url: String,
+}
+
+/// How the desktop app looks until it's changed from its View menu
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DesktopConfig {
+    /// "system" (default), "light" or "dark"
+    #[serde(default = "default_desktop_theme")]
+    pub theme: String,
+
+    /// Size of text and controls (1.0 = normal)
+    #[serde(default = "default_desktop_ui_scale")]
+    pub ui_scale: f32,
+
+    /// Full screen, chat only, with large buttons (a kitchen tablet)
+    #[serde(default)]
+    pub kiosk: bool,
+
+    /// Leaving kiosk mode asks for this (supports ${ENV_VAR})
+    #[serde(default)]
+    pub parent_pin: Option<String>,
+
+    /// Who kiosk mode offers to chat as; picking one locks the app to them
+    #[serde(default)]
+    pub people: Vec<KioskPersonConfig>,
+
+    /// Command that records 16 kHz mono 16-bit PCM to stdout until stopped
+    /// (default: sox on macOS, arecord elsewhere)
+    #[serde(default)]
+    pub mic_command: Option<String>,
+
+    /// Send what the mic heard right away, rather than putting it in the
+    /// input box to check first
+    #[serde(default)]
+    pub send_speech: bool,
+
+    /// Hands-free tutoring waits for this phrase (e.g. "hey tutor") before
+    /// a question, except right after a reply (default: none, everything
+    /// said is sent)
+    #[serde(default)]
+    pub wake_word: Option<String>,
+
+    /// Hands-free tutoring ends after this long without anyone talking
+    #[serde(default = "default_tutor_timeout")]
+    pub tutor_timeout: String,
+
+    /// Mic level in dBFS that counts as speech in hands-free tutoring; raise
+    /// it in a noisy room
+    #[serde(default = "default_mic_threshold")]
+    pub mic_threshold: f32,
+
+    /// How closely a voice must match someone's enrolled voiceprint (0 to
+    /// 1) for voice mode to switch to them; raise it if it mixes people up
+    #[serde(default = "default_speaker_threshold")]
+    pub speaker_threshold: f32,
+
+    /// Keyboard shortcuts by action, e.g. `palette = "Ctrl+K"`, replacing
+    /// the defaults; "" turns one off
+    #[serde(default)]
+    pub shortcuts: BTreeMap<String, String>,
+}
+
+/// Encrypted backups of the state dir (sessions, memory index, config) and
+/// the workspace
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupConfig {
+    /// The daemon backs up every `interval`
+    #[serde(default)]
+    pub enabled: bool,
+
+    #[serde(default = "default_backup_interval")]
+    pub interval: String,
+
+    /// A directory (another disk, or a mounted SMB share) or
+    /// "s3://bucket/prefix" with [backup.s3]
+    #[serde(default)]
+    pub destination: Option<String>,
+
+    /// Newest backups to keep (0 = keep all)
+    #[serde(default = "default_backup_keep")]
+    pub keep: usize,
+
+    /// age public keys ("age1...") backups are encrypted to; restoring
+    /// needs one of their identities (`homegpt backup keygen`)
+    #[serde(default)]
+    pub recipients: Vec<String>,
+
+    /// Used instead of recipients when there are none (supports ${ENV_VAR})
+    #[serde(default)]
+    pub passphrase: Option<String>,
+
+    #[serde(default)]
+    pub s3: Option<BackupS3Config>,
+}
+
+/// An S3-compatible bucket (AWS, Backblaze B2, MinIO, Cloudflare R2, ...)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupS3Config {
+    /// e.g. "https://s3.us-west-004.backblazeb2.com"
+    pub endpoint: String,
+
+    #[serde(default = "default_s3_region")]
+    pub region: String,
+
+    /// Supports ${ENV_VAR}
+    pub access_key: String,
+
+    /// Supports ${ENV_VAR}
+    pub secret_key: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KioskPersonConfig {
+    pub name: String,
+
+    /// Profile their sessions run under, e.g. "kids" (default: none)
+    #[serde(default)]
+    pub profile: Option<String>,
+
+    /// Shown on their button, e.g. an emoji (default: their initial)
+    #[serde(default)]
+    pub avatar: Option<String>,
+
+    /// Told to the assistant in their sessions, e.g. "Mia is 8 and loves
+    /// horses; keep answers short"
+    #[serde(default)]
+    pub persona: Option<String>,
+}
+
+/// Chat platforms the daemon answers on
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BridgesConfig {
+    #[serde(default)]
+    pub matrix: Option<MatrixBridgeConfig>,
+
+    #[serde(default)]
+    pub discord: Option<DiscordBridgeConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatrixBridgeConfig {
+    /// e.g. "https://matrix.example.org"
+    pub homeserver: String,
+
+    /// The bot account, e.g. "@homegpt:example.org"
+    pub user_id: String,
+
+    /// Supports ${ENV_VAR}
+    pub access_token: String,
+
+    /// Matrix IDs allowed to chat (and invite the bot to rooms)
+    #[serde(default)]
+    pub allowed_users: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscordBridgeConfig {
+    /// Bot token (supports ${ENV_VAR}); needs the Message Content intent
+    pub token: String,
+
+    /// Discord user IDs allowed to chat
+    #[serde(default)]
+    pub allowed_users: Vec<String>,
+
+    /// Answer in server channels when mentioned (DMs always work)
+    #[serde(default = "default_true")]
+    pub mentions: bool,
+}
+
+/// Inbound triggers (`POST /hook/<name>`) and outbound event notifications
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WebhooksConfig {
+    #[serde(default)]
+    pub inbound: Vec<InboundWebhookConfig>,
+
+    #[serde(default)]
+    pub outbound: Vec<OutboundWebhookConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InboundWebhookConfig {
+    /// URL path segment: `POST /hook/<name>`
+    pub name: String,
+
+    /// Prompt to run; `{{payload}}` is replaced with the request body
+    #[serde(default)]
+    pub prompt: String,
+
+    /// Run a heartbeat now instead of a prompt
+    #[serde(default)]
+    pub heartbeat: bool,
+
+    /// Required in the X-Webhook-Secret header or `?secret=` (supports ${ENV_VAR})
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboundWebhookConfig {
+    /// Supports ${ENV_VAR}
+    pub url: String,
+
+    /// alert, digest, heartbeat_failed, approval_requested, reminder, timer,
+    /// emergency (empty = all; alert subscribers also get emergencies)
+    #[serde(default)]
+    pub events: Vec<String>,
+
+    /// "json" ({"event", "message", "timestamp"}) or "text" (message only)
+    #[serde(default = "default_webhook_format")]
+    pub format: String,
+}
+
+/// Push notifications for heartbeat alerts, digests and failures
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotificationsConfig {
+    #[serde(default)]
+    pub channels: Vec<NotificationChannelConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationChannelConfig {
+    /// e.g. "phones"
+    pub name: String,
+
+    /// "ntfy" or "pushover"
+    pub kind: String,
+
+    /// alert, digest, heartbeat_failed, approval_requested, reminder, timer
+    /// (empty = all)
+    #[serde(default)]
+    pub events: Vec<String>,
+
+    /// ntfy server
+    #[serde(default = "default_ntfy_server")]
+    pub server: String,
+
+    /// ntfy topic
+    #[serde(default)]
+    pub topic: String,
+
+    /// ntfy access token or Pushover application token (supports ${ENV_VAR})
+    #[serde(default)]
+    pub token: String,
+
+    /// Pushover user or group key (supports ${ENV_VAR})
+    #[serde(default)]
+    pub user_key: String,
+}
+
+/// Which tool calls ask for the user's approval before they run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalConfig {
+    /// Tools that always ask, e.g. ["bash"]
+    #[serde(default)]
+    pub tools: Vec<String>,
+
+    /// Tools that ask under a profile (HOMEGPT_PROFILE); "*" = every tool
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Vec<String>>,
+
+    /// Rules on a call's arguments, checked in order; the first that
+    /// matches decides
+    #[serde(default)]
+    pub rules: Vec<ApprovalRuleConfig>,
+
+    /// Record decisions in ~/.homegpt/approvals.jsonl
+    #[serde(default = "default_true")]
+    pub audit: bool,
+
+    /// Ask on the phone for calls in the daemon's sessions and bridges
+    #[serde(default)]
+    pub remote: Option<RemoteApprovalConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteApprovalConfig {
+    /// Where phones reach the HTTP server, e.g. "https://homegpt.example.org"
+    pub public_url: String,
+
+    /// How long a turn waits for an answer; then the call isn't run
+    #[serde(default = "default_remote_approval_timeout")]
+    pub timeout: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalRuleConfig {
+    /// Tools the rule applies to
+    pub tools: Vec<String>,
+
+    /// "ask" or "allow"
+    pub action: String,
+
+    /// Argument the conditions look at
+    #[serde(default = "default_approval_argument")]
+    pub argument: String,
+
+    /// Matches a path under one of these (relative to the workspace)
+    #[serde(default)]
+    pub paths: Vec<String>,
+
+    /// Matches a path outside the workspace
+    #[serde(default)]
+    pub outside_workspace: bool,
+
+    /// Matches an argument containing one of these, e.g. ["sudo", "rm "]
+    #[serde(default)]
+    pub contains: Vec<String>,
+}
+
+/// Which paths the file tools may read and write
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilesConfig {
+    /// Globs or directories read_file, memory_get and finance_import may
+    /// read; relative ones are under the workspace
+    #[serde(default = "default_files_allowed")]
+    pub read: Vec<String>,
+
+    /// Globs or directories write_file, edit_file and patch_file may write
+    #[serde(default = "default_files_allowed")]
+    pub write: Vec<String>,
+
+    /// Globs or directories no file tool may touch, whatever read and
+    /// write allow
+    #[serde(default)]
+    pub deny: Vec<String>,
 
-    #[serde(default = "default_interval")]
-    pub interval: String,
+    /// "deny" refuses a call outside these; "ask" asks for approval instead
+    #[serde(default = "default_files_on_violation")]
+    pub on_violation: String,
+}
 
+/// Usage quotas, so one profile can't spend the month's API budget
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LimitsConfig {
+    /// Quotas by profile (HOMEGPT_PROFILE); "default" = no profile set
     #[serde(default)]
-    pub active_hours: Option<ActiveHours>,
+    pub profiles: BTreeMap<String, ProfileLimitsConfig>,
+}
 
+/// Quotas for one profile; unset = unlimited
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProfileLimitsConfig {
+    /// Messages sent to the agent in any hour
     #[serde(default)]
-    pub timezone: Option<String>,
+    pub messages_per_hour: Option<u32>,
+
+    /// Input plus output tokens per day
+    #[serde(default)]
+    pub tokens_per_day: Option<u64>,
+
+    /// web_fetch calls per day
+    #[serde(default)]
+    pub web_fetches_per_day: Option<u32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ActiveHours {
-    pub start: String,
-    pub end: String,
+/// Sensitive text cloud models see stand-ins for
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RedactionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Text to hide → its stand-in, e.g. "Emma Johnson" = "CHILD_A"
+    #[serde(default)]
+    pub terms: BTreeMap<String, String>,
+
+    /// Built-in patterns to hide: "phone", "ssn", "email"
+    #[serde(default)]
+    pub patterns: Vec<String>,
 }
 
+/// Checks replies before kids see or hear them
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct MemoryConfig {
-    #[serde(default = "default_workspace")]
-    pub workspace: String,
+pub struct SafetyConfig {
+    /// Profiles (HOMEGPT_PROFILE) whose replies are checked, e.g. ["kids"]
+    #[serde(default)]
+    pub profiles: Vec<String>,
 
-    /// Embedding provider: "local" (fastembed, default), "openai", or "none"
-    #[serde(default = "default_embedding_provider")]
-    pub embedding_provider: String,
+    /// Words and phrases a reply mustn't contain (whole words, any case)
+    #[serde(default)]
+    pub words: Vec<String>,
 
-    #[serde(default = "default_embedding_model")]
-    pub embedding_model: String,
+    /// Cheap model asked whether each reply suits a child
+    #[serde(default)]
+    pub model: Option<String>,
 
-    /// Cache directory for local embedding models (optional)
-    /// Default: ~/.cache/homegpt/models
-    /// Can also be set via FASTEMBED_CACHE_DIR environment variable
-    #[serde(default = "default_embedding_cache_dir")]
-    pub embedding_cache_dir: String,
+    /// "block" (replace the reply with `message`) or "rephrase" (have the
+    /// model rewrite it for a child)
+    #[serde(default = "default_safety_action")]
+    pub action: String,
 
-    #[serde(default = "default_chunk_size")]
-    pub chunk_size: usize,
+    /// Replaces a blocked reply; defaults to a localized one
+    #[serde(default)]
+    pub message: Option<String>,
+}
 
-    #[serde(default = "default_chunk_overlap")]
-    pub chunk_overlap: usize,
+/// When notifications and spoken announcements wait until morning
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuietHoursConfig {
+    /// Every night, e.g. { start = "21:30", end = "07:00" }
+    #[serde(default)]
+    pub hours: Option<ActiveHours>,
 
-    /// Additional paths to index (relative to workspace or absolute)
-    /// Each path uses a glob pattern for file matching
-    #[serde(default = "default_index_paths")]
-    pub paths: Vec<MemoryIndexPath>,
+    /// Nights that differ, keyed by the day they start ("fri", "saturday")
+    #[serde(default)]
+    pub days: BTreeMap<String, ActiveHours>,
 
-    /// Maximum messages to save in session memory files (0 = unlimited)
-    /// Similar to OpenClaw's hooks.session-memory.messages (default: 15)
-    #[serde(default = "default_session_max_messages")]
-    pub session_max_messages: usize,
+    /// Events sent anyway; emergencies always are
+    #[serde(default = "default_quiet_bypass")]
+    pub bypass: Vec<String>,
 
-    /// Maximum characters per message in session memory (0 = unlimited)
-    /// Set to 0 to preserve full message content like OpenClaw
+    /// People whose own channels keep different hours
     #[serde(default)]
-    pub session_max_chars: usize,
+    pub people: Vec<QuietPersonConfig>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct MemoryIndexPath {
-    pub path: String,
-    #[serde(default = "default_pattern")]
-    pub pattern: String,
+pub struct QuietPersonConfig {
+    pub name: String,
+
+    /// Their notification channels (by name, or URL for webhooks)
+    pub channels: Vec<String>,
+
+    /// Their nights; with neither `hours` nor `days` they have none
+    #[serde(default)]
+    pub hours: Option<ActiveHours>,
+
+    #[serde(default)]
+    pub days: BTreeMap<String, ActiveHours>,
+
+    /// Events sent to them anyway (default: the household's `bypass`)
+    #[serde(default)]
+    pub bypass: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -204,6 +1386,15 @@ pub struct ServerConfig {
 
     #[serde(default = "default_bind")]
     pub bind: String,
+
+    /// Serve Prometheus metrics on /metrics
+    #[serde(default = "default_true")]
+    pub metrics: bool,
+
+    /// Agent turns the daemon runs at once, each in its own session (web UI,
+    /// desktop, chat bridges, heartbeat); 1 runs them one at a time
+    #[serde(default = "default_max_concurrent_turns")]
+    pub max_concurrent_turns: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -245,6 +1436,21 @@ fn default_tool_output_max_chars() -> usize {
 fn default_openai_base_url() -> String {
     "https://api.openai.com/v1".to_string()
 }
+fn default_openrouter_base_url() -> String {
+    "https://openrouter.ai/api/v1".to_string()
+}
+fn default_openrouter_app_name() -> String {
+    "HomeGPT".to_string()
+}
+fn default_gemini_base_url() -> String {
+    "https://generativelanguage.googleapis.com/v1beta".to_string()
+}
+fn default_groq_base_url() -> String {
+    "https://api.groq.com/openai/v1".to_string()
+}
+fn default_voyage_base_url() -> String {
+    "https://api.voyageai.com/v1".to_string()
+}
 fn default_anthropic_base_url() -> String {
     "https://api.anthropic.com".to_string()
 }
@@ -266,9 +1472,18 @@ fn default_true() -> bool {
 fn default_interval() -> String {
     "30m".to_string()
 }
+fn default_task_token_budget() -> u64 {
+    50_000
+}
+fn default_catch_up() -> String {
+    "run".to_string()
+}
 fn default_workspace() -> String {
     "~/.homegpt/workspace".to_string()
 }
+fn default_template_pack() -> String {
+    "home".to_string()
+}
 fn default_embedding_provider() -> String {
     "local".to_string() // Local embeddings via fastembed (no API key needed)
 }
@@ -284,6 +1499,45 @@ fn default_chunk_size() -> usize {
 fn default_chunk_overlap() -> usize {
     80
 }
+fn default_embedding_execution_provider() -> String {
+    "cpu".to_string()
+}
+fn default_embedding_storage() -> String {
+    "float".to_string()
+}
+fn default_embedding_batch_size() -> usize {
+    128
+}
+fn default_embedding_batch_tokens() -> usize {
+    100_000
+}
+fn default_consolidation_threshold() -> f32 {
+    0.92
+}
+fn default_keep_snapshots() -> usize {
+    10
+}
+fn default_context_budget_tokens() -> usize {
+    1500
+}
+fn default_max_chunks_per_file() -> usize {
+    2
+}
+fn default_answer_min_confidence() -> String {
+    "medium".to_string()
+}
+fn default_health_report_interval() -> String {
+    "7d".to_string()
+}
+fn default_stale_after_days() -> u32 {
+    180
+}
+fn default_chunk_strategy() -> String {
+    "lines".to_string()
+}
+fn default_documents_dir() -> String {
+    "memory/documents".to_string()
+}
 fn default_index_paths() -> Vec<MemoryIndexPath> {
     vec![MemoryIndexPath {
         path: "knowledge".to_string(),
@@ -293,18 +1547,141 @@ fn default_index_paths() -> Vec<MemoryIndexPath> {
 fn default_pattern() -> String {
     "**/*.md".to_string()
 }
+fn default_sync_branch() -> String {
+    "main".to_string()
+}
+fn default_sync_interval() -> String {
+    "15m".to_string()
+}
 fn default_session_max_messages() -> usize {
     15 // Match OpenClaw's default
 }
+fn default_currency() -> String {
+    "$".to_string()
+}
+fn default_connector_kind() -> String {
+    "pocketbase".to_string()
+}
+fn default_connector_interval() -> String {
+    "2h".to_string()
+}
+fn default_review_threshold() -> u64 {
+    5
+}
+fn default_mqtt_port() -> u16 {
+    1883
+}
+fn default_owntracks_topic() -> String {
+    "owntracks/#".to_string()
+}
+fn default_home_region() -> String {
+    "home".to_string()
+}
+fn default_safety_action() -> String {
+    "block".to_string()
+}
+fn default_remote_approval_timeout() -> String {
+    "5m".to_string()
+}
+fn default_approval_argument() -> String {
+    "path".to_string()
+}
+fn default_files_allowed() -> Vec<String> {
+    vec!["**".to_string()]
+}
+fn default_files_on_violation() -> String {
+    "deny".to_string()
+}
+fn default_quiet_bypass() -> Vec<String> {
+    vec!["reminder".to_string(), "timer".to_string()]
+}
+fn default_announce_kind() -> String {
+    "piper".to_string()
+}
+fn default_piper_command() -> String {
+    "piper".to_string()
+}
+fn default_tts_entity() -> String {
+    "tts.piper".to_string()
+}
+fn default_stt_kind() -> String {
+    "whisper".to_string()
+}
+fn default_stt_url() -> String {
+    "http://localhost:8001".to_string()
+}
+fn default_webhook_format() -> String {
+    "json".to_string()
+}
+fn default_ntfy_server() -> String {
+    "https://ntfy.sh".to_string()
+}
+fn default_calendar_days_ahead() -> u32 {
+    14
+}
+fn default_calendar_refresh() -> String {
+    "1h".to_string()
+}
+fn default_language() -> String {
+    "en".to_string()
+}
+fn default_weather_units() -> String {
+    "metric".to_string()
+}
+fn default_weather_cache_ttl() -> String {
+    "30m".to_string()
+}
+fn default_weather_digest_time() -> String {
+    "07:00".to_string()
+}
+fn default_desktop_theme() -> String {
+    "system".to_string()
+}
+
+fn default_desktop_ui_scale() -> f32 {
+    1.0
+}
+
+fn default_tutor_timeout() -> String {
+    "5m".to_string()
+}
+
+fn default_backup_interval() -> String {
+    "24h".to_string()
+}
+
+fn default_backup_keep() -> usize {
+    14
+}
+
+fn default_s3_region() -> String {
+    "us-east-1".to_string()
+}
+
+fn default_mic_threshold() -> f32 {
+    -45.0
+}
+
+fn default_speaker_threshold() -> f32 {
+    0.85
+}
+
 fn default_port() -> u16 {
     31327
 }
 fn default_bind() -> String {
     "127.0.0.1".to_string()
 }
+fn default_max_concurrent_turns() -> usize {
+    4
+}
 fn default_log_level() -> String {
     "info".to_string()
 }
+fn default_self_check() -> String {
+    "off".to_string()
+}
+
 fn default_log_file() -> String {
     "~/.homegpt/logs/agent.log".to_string()
 }
@@ -316,6 +1693,11 @@ impl Default for AgentConfig {
             context_window: default_context_window(),
             reserve_tokens: default_reserve_tokens(),
             max_tokens: default_max_tokens(),
+            heartbeat_model: None,
+            summary_model: None,
+            vision_model: None,
+            probe_capabilities: true,
+            self_check: default_self_check(),
         }
     }
 }
@@ -340,6 +1722,12 @@ impl Default for HeartbeatConfig {
             interval: default_interval(),
             active_hours: None,
             timezone: None,
+            planning: false,
+            isolate_tasks: false,
+            task_token_budget: default_task_token_budget(),
+            task_tools: Vec::new(),
+            tasks: Vec::new(),
+            catch_up: default_catch_up(),
         }
     }
 }
@@ -348,14 +1736,230 @@ impl Default for MemoryConfig {
     fn default() -> Self {
         Self {
             workspace: default_workspace(),
+            template_pack: default_template_pack(),
             embedding_provider: default_embedding_provider(),
             embedding_model: default_embedding_model(),
+            embedding_dimensions: 0,
+            embedding_storage: default_embedding_storage(),
+            embedding_rescore: true,
+            embedding_batch_size: default_embedding_batch_size(),
+            embedding_batch_tokens: default_embedding_batch_tokens(),
+            embedding_rpm: 0,
+            embedding_tpm: 0,
             embedding_cache_dir: default_embedding_cache_dir(),
+            embedding_execution_provider: default_embedding_execution_provider(),
+            embedding_threads: 0,
             chunk_size: default_chunk_size(),
             chunk_overlap: default_chunk_overlap(),
+            chunk_strategy: default_chunk_strategy(),
+            chunk_max_tokens: 0,
+            chunking: Vec::new(),
+            query_rewrite: false,
+            hyde: false,
+            query_expansion_model: None,
+            context_budget_tokens: default_context_budget_tokens(),
+            max_chunks_per_file: default_max_chunks_per_file(),
+            summarize_overflow: false,
+            audit_log: true,
+            entity_graph: true,
+            time_index: true,
+            answer_min_confidence: default_answer_min_confidence(),
+            extract_facts: false,
+            track_commitments: false,
+            weekly_journal: false,
+            consolidation_threshold: default_consolidation_threshold(),
+            consolidation_interval: String::new(),
+            auto_snapshot: true,
+            keep_snapshots: default_keep_snapshots(),
+            health_report_interval: default_health_report_interval(),
+            stale_after_days: default_stale_after_days(),
+            max_age_days: BTreeMap::new(),
+            reconfirm_stale: true,
+            documents_dir: default_documents_dir(),
             paths: default_index_paths(),
             session_max_messages: default_session_max_messages(),
             session_max_chars: 0, // 0 = unlimited (preserve full content like OpenClaw)
+            sync: SyncConfig::default(),
+            staging: StagingConfig::default(),
+        }
+    }
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            remote: String::new(),
+            branch: default_sync_branch(),
+            interval: default_sync_interval(),
+            pre_sync: None,
+            post_sync: None,
+        }
+    }
+}
+
+impl Default for FinanceConfig {
+    fn default() -> Self {
+        Self {
+            budgets: BTreeMap::new(),
+            rules: Vec::new(),
+            llm_categorize: true,
+            categorize_model: None,
+            date_format: None,
+            currency: default_currency(),
+            monthly_report: true,
+        }
+    }
+}
+
+impl Default for BusinessConnectorConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            kind: default_connector_kind(),
+            base_url: String::new(),
+            token: String::new(),
+            interval: default_connector_interval(),
+            review_threshold: default_review_threshold(),
+        }
+    }
+}
+
+impl Default for WeatherConfig {
+    fn default() -> Self {
+        Self {
+            location: String::new(),
+            latitude: None,
+            longitude: None,
+            units: default_weather_units(),
+            cache_ttl: default_weather_cache_ttl(),
+            digest_time: default_weather_digest_time(),
+        }
+    }
+}
+
+impl Default for LocaleConfig {
+    fn default() -> Self {
+        Self {
+            language: default_language(),
+            languages: Vec::new(),
+            timezone: String::new(),
+        }
+    }
+}
+
+impl Default for DesktopConfig {
+    fn default() -> Self {
+        Self {
+            theme: default_desktop_theme(),
+            ui_scale: default_desktop_ui_scale(),
+            kiosk: false,
+            parent_pin: None,
+            people: Vec::new(),
+            mic_command: None,
+            send_speech: false,
+            wake_word: None,
+            tutor_timeout: default_tutor_timeout(),
+            mic_threshold: default_mic_threshold(),
+            speaker_threshold: default_speaker_threshold(),
+            shortcuts: BTreeMap::new(),
+        }
+    }
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval: default_backup_interval(),
+            destination: None,
+            keep: default_backup_keep(),
+            recipients: Vec::new(),
+            passphrase: None,
+            s3: None,
+        }
+    }
+}
+
+impl Default for SttConfig {
+    fn default() -> Self {
+        Self {
+            kind: default_stt_kind(),
+            url: default_stt_url(),
+            model: String::new(),
+        }
+    }
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        Self {
+            subscriptions: Vec::new(),
+            days_ahead: default_calendar_days_ahead(),
+            refresh: default_calendar_refresh(),
+            publish: false,
+            feed_token: None,
+        }
+    }
+}
+
+impl Default for ApprovalConfig {
+    fn default() -> Self {
+        Self {
+            tools: Vec::new(),
+            profiles: BTreeMap::new(),
+            rules: Vec::new(),
+            audit: true,
+            remote: None,
+        }
+    }
+}
+
+impl Default for FilesConfig {
+    fn default() -> Self {
+        Self {
+            read: default_files_allowed(),
+            write: default_files_allowed(),
+            deny: Vec::new(),
+            on_violation: default_files_on_violation(),
+        }
+    }
+}
+
+impl Default for SafetyConfig {
+    fn default() -> Self {
+        Self {
+            profiles: Vec::new(),
+            words: Vec::new(),
+            model: None,
+            action: default_safety_action(),
+            message: None,
+        }
+    }
+}
+
+impl Default for QuietHoursConfig {
+    fn default() -> Self {
+        Self {
+            hours: None,
+            days: BTreeMap::new(),
+            bypass: default_quiet_bypass(),
+            people: Vec::new(),
+        }
+    }
+}
+
+impl Default for AnnounceDeviceConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            kind: default_announce_kind(),
+            model: String::new(),
+            voice: None,
+            piper_command: default_piper_command(),
+            player: None,
+            media_player: String::new(),
+            tts_entity: default_tts_entity(),
         }
     }
 }
@@ -366,6 +1970,8 @@ impl Default for ServerConfig {
             enabled: default_true(),
             port: default_port(),
             bind: default_bind(),
+            metrics: default_true(),
+            max_concurrent_turns: default_max_concurrent_turns(),
         }
     }
 }
@@ -443,12 +2049,58 @@ impl Config {
     }
 
     fn expand_env_vars(&mut self) {
+        for connector in &mut self.business.connectors {
+            connector.token = expand_env(&connector.token);
+        }
+        if let Some(ref mut home_assistant) = self.home_assistant {
+            home_assistant.token = expand_env(&home_assistant.token);
+        }
+        if let Some(ref mut owntracks) = self.presence.owntracks {
+            owntracks.password = expand_env(&owntracks.password);
+        }
+        if let Some(ref mut matrix) = self.bridges.matrix {
+            matrix.access_token = expand_env(&matrix.access_token);
+        }
+        if let Some(ref mut discord) = self.bridges.discord {
+            discord.token = expand_env(&discord.token);
+        }
+        for hook in &mut self.webhooks.inbound {
+            if let Some(ref mut secret) = hook.secret {
+                *secret = expand_env(secret);
+            }
+        }
+        for hook in &mut self.webhooks.outbound {
+            hook.url = expand_env(&hook.url);
+        }
+        for subscription in &mut self.calendar.subscriptions {
+            subscription.url = expand_env(&subscription.url);
+        }
+        if let Some(ref mut token) = self.calendar.feed_token {
+            *token = expand_env(token);
+        }
+        if let Some(ref mut pin) = self.desktop.parent_pin {
+            *pin = expand_env(pin);
+        }
+        if let Some(ref mut passphrase) = self.backup.passphrase {
+            *passphrase = expand_env(passphrase);
+        }
+        if let Some(ref mut s3) = self.backup.s3 {
+            s3.access_key = expand_env(&s3.access_key);
+            s3.secret_key = expand_env(&s3.secret_key);
+        }
+        for channel in &mut self.notifications.channels {
+            channel.token = expand_env(&channel.token);
+            channel.user_key = expand_env(&channel.user_key);
+        }
         if let Some(ref mut openai) = self.providers.openai {
             openai.api_key = expand_env(&openai.api_key);
         }
         if let Some(ref mut anthropic) = self.providers.anthropic {
             anthropic.api_key = expand_env(&anthropic.api_key);
         }
+        if let Some(ref mut openrouter) = self.providers.openrouter {
+            openrouter.api_key = expand_env(&openrouter.api_key);
+        }
     }
 
     pub fn get_value(&self, key: &str) -> Result<String> {
@@ -458,12 +2110,30 @@ impl Config {
             ["agent", "default_model"] => Ok(self.agent.default_model.clone()),
             ["agent", "context_window"] => Ok(self.agent.context_window.to_string()),
             ["agent", "reserve_tokens"] => Ok(self.agent.reserve_tokens.to_string()),
+            ["agent", "heartbeat_model"] => Ok(self.agent.heartbeat_model().to_string()),
+            ["agent", "summary_model"] => Ok(self.agent.summary_model.clone().unwrap_or_default()),
+            ["agent", "vision_model"] => Ok(self.agent.vision_model.clone().unwrap_or_default()),
             ["heartbeat", "enabled"] => Ok(self.heartbeat.enabled.to_string()),
             ["heartbeat", "interval"] => Ok(self.heartbeat.interval.clone()),
             ["server", "enabled"] => Ok(self.server.enabled.to_string()),
             ["server", "port"] => Ok(self.server.port.to_string()),
             ["server", "bind"] => Ok(self.server.bind.clone()),
             ["memory", "workspace"] => Ok(self.memory.workspace.clone()),
+            ["memory", "sync", "enabled"] => Ok(self.memory.sync.enabled.to_string()),
+            ["memory", "sync", "remote"] => Ok(self.memory.sync.remote.clone()),
+            ["memory", "sync", "interval"] => Ok(self.memory.sync.interval.clone()),
+            ["finance", "currency"] => Ok(self.finance.currency.clone()),
+            ["finance", "llm_categorize"] => Ok(self.finance.llm_categorize.to_string()),
+            ["finance", "monthly_report"] => Ok(self.finance.monthly_report.to_string()),
+            ["finance", "budgets", category] => Ok(self
+                .finance
+                .budgets
+                .get(*category)
+                .map(|b| b.to_string())
+                .unwrap_or_default()),
+            ["weather", "location"] => Ok(self.weather.location.clone()),
+            ["weather", "units"] => Ok(self.weather.units.clone()),
+            ["weather", "digest_time"] => Ok(self.weather.digest_time.clone()),
             ["logging", "level"] => Ok(self.logging.level.clone()),
             _ => anyhow::bail!("Unknown config key: {}", key),
         }
@@ -476,12 +2146,44 @@ impl Config {
             ["agent", "default_model"] => self.agent.default_model = value.to_string(),
             ["agent", "context_window"] => self.agent.context_window = value.parse()?,
             ["agent", "reserve_tokens"] => self.agent.reserve_tokens = value.parse()?,
+            ["agent", "heartbeat_model"] => {
+                self.agent.heartbeat_model = Some(value.to_string()).filter(|v| !v.is_empty())
+            }
+            ["agent", "summary_model"] => {
+                self.agent.summary_model = Some(value.to_string()).filter(|v| !v.is_empty())
+            }
+            ["agent", "vision_model"] => {
+                self.agent.vision_model = Some(value.to_string()).filter(|v| !v.is_empty())
+            }
             ["heartbeat", "enabled"] => self.heartbeat.enabled = value.parse()?,
             ["heartbeat", "interval"] => self.heartbeat.interval = value.to_string(),
             ["server", "enabled"] => self.server.enabled = value.parse()?,
             ["server", "port"] => self.server.port = value.parse()?,
             ["server", "bind"] => self.server.bind = value.to_string(),
             ["memory", "workspace"] => self.memory.workspace = value.to_string(),
+            ["memory", "sync", "enabled"] => self.memory.sync.enabled = value.parse()?,
+            ["memory", "sync", "remote"] => self.memory.sync.remote = value.to_string(),
+            ["memory", "sync", "interval"] => self.memory.sync.interval = value.to_string(),
+            ["finance", "currency"] => self.finance.currency = value.to_string(),
+            ["finance", "llm_categorize"] => self.finance.llm_categorize = value.parse()?,
+            ["finance", "monthly_report"] => self.finance.monthly_report = value.parse()?,
+            ["finance", "budgets", category] => {
+                if value.is_empty() {
+                    self.finance.budgets.remove(*category);
+                } else {
+                    self.finance
+                        .budgets
+                        .insert(category.to_string(), value.parse()?);
+                }
+            }
+            ["weather", "location"] => {
+                self.weather.location = value.to_string();
+                // Coordinates belong to the old location
+                self.weather.latitude = None;
+                self.weather.longitude = None;
+            }
+            ["weather", "units"] => self.weather.units = value.to_string(),
+            ["weather", "digest_time"] => self.weather.digest_time = value.to_string(),
             ["logging", "level"] => self.logging.level = value.to_string(),
             _ => anyhow::bail!("Unknown config key: {}", key),
         }
@@ -489,6 +2191,18 @@ impl Config {
         Ok(())
     }
 
+    /// Profile from the HOMEGPT_PROFILE env var, lowercased; `None` for
+    /// the default profile
+    pub fn profile() -> Option<String> {
+        let profile = std::env::var("HOMEGPT_PROFILE").ok()?;
+        let trimmed = profile.trim().to_lowercase();
+        if trimmed.is_empty() || trimmed == "default" {
+            None
+        } else {
+            Some(trimmed)
+        }
+    }
+
     /// Get workspace path, expanded
     ///
     /// Resolution order (like OpenClaw):
@@ -507,16 +2221,13 @@ impl Config {
         }
 
         // Check for profile-based workspace (like OpenClaw's OPENCLAW_PROFILE)
-        if let Ok(profile) = std::env::var("HOMEGPT_PROFILE") {
-            let trimmed = profile.trim().to_lowercase();
-            if !trimmed.is_empty() && trimmed != "default" {
-                let base = directories::BaseDirs::new()
-                    .map(|b| b.home_dir().to_path_buf())
-                    .unwrap_or_else(|| PathBuf::from("~"));
-                return base
-                    .join(".homegpt")
-                    .join(format!("workspace-{}", trimmed));
-            }
+        if let Some(profile) = Self::profile() {
+            let base = directories::BaseDirs::new()
+                .map(|b| b.home_dir().to_path_buf())
+                .unwrap_or_else(|| PathBuf::from("~"));
+            return base
+                .join(".homegpt")
+                .join(format!("workspace-{}", profile));
         }
 
         // Use config value
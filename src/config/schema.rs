@@ -1,5 +1,6 @@
 //! Configuration schema validation and helpers
 
+use chrono::NaiveTime;
 use std::time::Duration;
 
 /// Parse a duration string like "30m", "1h", "2h30m"
@@ -57,6 +58,36 @@ pub fn parse_time(s: &str) -> Result<(u8, u8), String> {
     Ok((hour, minute))
 }
 
+/// Daily window like quiet hours; an `end` before `start` runs overnight
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl TimeWindow {
+    /// Parse "HH:MM" start and end times
+    pub fn parse(start: &str, end: &str) -> Result<Self, String> {
+        let time = |s: &str| {
+            let (hour, minute) = parse_time(s)?;
+            NaiveTime::from_hms_opt(hour as u32, minute as u32, 0)
+                .ok_or_else(|| format!("Invalid time: {}", s))
+        };
+        Ok(Self {
+            start: time(start)?,
+            end: time(end)?,
+        })
+    }
+
+    pub fn contains(&self, now: NaiveTime) -> bool {
+        if self.start <= self.end {
+            now >= self.start && now <= self.end
+        } else {
+            now >= self.start || now <= self.end
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -77,4 +108,14 @@ mod tests {
         assert_eq!(parse_time("00:00").unwrap(), (0, 0));
         assert_eq!(parse_time("23:59").unwrap(), (23, 59));
     }
+
+    #[test]
+    fn test_time_window_overnight() {
+        let window = TimeWindow::parse("21:00", "07:00").unwrap();
+        let time = |s| NaiveTime::parse_from_str(s, "%H:%M").unwrap();
+        assert!(window.contains(time("23:30")));
+        assert!(window.contains(time("06:00")));
+        assert!(!window.contains(time("12:00")));
+        assert!(TimeWindow::parse("21:00", "7am").is_err());
+    }
 }
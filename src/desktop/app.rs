@@ -1,57 +1,99 @@
 //! Main eframe application
 
 use eframe::egui;
-
-use super::state::{Panel, UiState};
-use super::views::{chat::show_toolbar, ChatView, SessionsView, StatusView};
+use std::time::Duration;
+
+use super::display::{DisplaySettings, STORAGE_KEY};
+use super::mic::Mic;
+use super::shortcuts::{Action, Shortcuts};
+use super::state::{Panel, UiMessage, UiState};
+use super::views::{
+    chat::show_toolbar, ChangesView, ChatView, CommandPalette, KioskView, LogsView, PromptView,
+    SessionsView, StatusView, TimersView,
+};
 use super::worker::WorkerHandle;
+use crate::config::Config;
 
 /// The main desktop application
 pub struct DesktopApp {
     state: UiState,
     worker: WorkerHandle,
+    timers: TimersView,
+    changes: ChangesView,
+    prompt: PromptView,
+    sessions: SessionsView,
+    logs: LogsView,
+    kiosk: KioskView,
+    mic: Mic,
+    display: DisplaySettings,
+    shortcuts: Shortcuts,
+    palette: CommandPalette,
 }
 
 impl DesktopApp {
-    /// Create a new desktop app
-    pub fn new(cc: &eframe::CreationContext<'_>, agent_id: Option<String>) -> Self {
+    /// Create a new desktop app. Chats go through the daemon when it's
+    /// running, unless `local` is set. `kiosk` starts in the kiosk layout.
+    pub fn new(
+        cc: &eframe::CreationContext<'_>,
+        agent_id: Option<String>,
+        local: bool,
+        kiosk: bool,
+    ) -> Self {
         // Configure fonts and visuals
         Self::configure_style(&cc.egui_ctx);
 
+        // The View menu's last settings, else config's
+        let config = Config::load().map(|c| c.desktop).unwrap_or_default();
+        let mut display = cc
+            .storage
+            .and_then(|storage| eframe::get_value::<DisplaySettings>(storage, STORAGE_KEY))
+            .unwrap_or_else(|| DisplaySettings::from_config(&config));
+        display.kiosk |= kiosk;
+        display.apply(&cc.egui_ctx);
+
         // Start the background worker
-        let worker = WorkerHandle::start(agent_id).expect("Failed to start worker");
+        let worker = WorkerHandle::start(agent_id, local).expect("Failed to start worker");
 
         Self {
             state: UiState::new(),
             worker,
+            timers: TimersView::new(),
+            changes: ChangesView::new(),
+            prompt: PromptView::new(),
+            sessions: SessionsView::new(),
+            logs: LogsView::new(),
+            kiosk: KioskView::new(),
+            mic: Mic::new(),
+            display,
+            shortcuts: Shortcuts::from_config(&config),
+            palette: CommandPalette::new(),
         }
     }
 
+    /// Applied to the light and dark styles alike
     fn configure_style(ctx: &egui::Context) {
-        let mut style = (*ctx.style()).clone();
-
-        // Use slightly larger text
-        style.text_styles.insert(
-            egui::TextStyle::Body,
-            egui::FontId::new(14.0, egui::FontFamily::Proportional),
-        );
-        style.text_styles.insert(
-            egui::TextStyle::Button,
-            egui::FontId::new(14.0, egui::FontFamily::Proportional),
-        );
-        style.text_styles.insert(
-            egui::TextStyle::Heading,
-            egui::FontId::new(20.0, egui::FontFamily::Proportional),
-        );
-
-        // Rounded corners
-        style.visuals.window_rounding = egui::Rounding::same(8.0);
-        style.visuals.widgets.noninteractive.rounding = egui::Rounding::same(4.0);
-        style.visuals.widgets.inactive.rounding = egui::Rounding::same(4.0);
-        style.visuals.widgets.hovered.rounding = egui::Rounding::same(4.0);
-        style.visuals.widgets.active.rounding = egui::Rounding::same(4.0);
-
-        ctx.set_style(style);
+        ctx.all_styles_mut(|style| {
+            // Use slightly larger text
+            style.text_styles.insert(
+                egui::TextStyle::Body,
+                egui::FontId::new(14.0, egui::FontFamily::Proportional),
+            );
+            style.text_styles.insert(
+                egui::TextStyle::Button,
+                egui::FontId::new(14.0, egui::FontFamily::Proportional),
+            );
+            style.text_styles.insert(
+                egui::TextStyle::Heading,
+                egui::FontId::new(20.0, egui::FontFamily::Proportional),
+            );
+
+            // Rounded corners
+            style.visuals.window_rounding = egui::Rounding::same(8.0);
+            style.visuals.widgets.noninteractive.rounding = egui::Rounding::same(4.0);
+            style.visuals.widgets.inactive.rounding = egui::Rounding::same(4.0);
+            style.visuals.widgets.hovered.rounding = egui::Rounding::same(4.0);
+            style.visuals.widgets.active.rounding = egui::Rounding::same(4.0);
+        });
     }
 
     /// Process all pending worker messages
@@ -60,6 +102,22 @@ impl DesktopApp {
             self.state.handle_worker_message(msg);
         }
     }
+
+    /// Send a UI message to the worker; leaving kiosk mode also restores
+    /// the normal layout, and switching panels stays in the app
+    fn send(&mut self, ctx: &egui::Context, msg: UiMessage) {
+        if let UiMessage::ShowPanel(panel) = msg {
+            self.state.active_panel = panel;
+            return;
+        }
+        if matches!(msg, UiMessage::LeaveKiosk) {
+            self.display.kiosk = false;
+            self.display.apply(ctx);
+        }
+        if let Err(e) = self.worker.send(msg) {
+            self.state.error = Some(format!("Failed to send to worker: {}", e));
+        }
+    }
 }
 
 impl eframe::App for DesktopApp {
@@ -67,34 +125,107 @@ impl eframe::App for DesktopApp {
         // Process worker messages
         self.process_worker_messages();
 
-        // Request repaint while loading or streaming
-        if self.state.is_loading || !self.state.streaming_content.is_empty() {
+        // Save the session and close the index before the window goes
+        if ctx.input(|i| i.viewport().close_requested()) {
+            self.worker.shutdown();
+        }
+
+        // Request repaint while loading, streaming or indexing
+        if self.state.is_loading
+            || !self.state.streaming_content.is_empty()
+            || self.state.indexing.is_some()
+        {
             ctx.request_repaint();
         }
 
-        // Top panel with toolbar
-        egui::TopBottomPanel::top("toolbar").show(ctx, |ui| {
-            show_toolbar(ui, &mut self.state);
-        });
+        // Top panel with toolbar; the kiosk layout only has the chat
+        let kiosk = self.display.kiosk;
+        self.state.locked = kiosk;
+        let mut display_changed = false;
+        let toolbar_msg = egui::TopBottomPanel::top("toolbar")
+            .show(ctx, |ui| {
+                if kiosk {
+                    self.state.active_panel = Panel::Chat;
+                    self.kiosk.show_bar(ui, &mut self.state)
+                } else {
+                    show_toolbar(ui, &mut self.state, &mut self.display, &mut display_changed);
+                    None
+                }
+            })
+            .inner;
+        if display_changed {
+            self.display.apply(ctx);
+        }
+        if let Some(msg) = toolbar_msg {
+            self.send(ctx, msg);
+        }
+        if let Some(msg) = self.kiosk.show_prompt(ctx) {
+            self.send(ctx, msg);
+        }
 
-        // Main content
-        egui::CentralPanel::default().show(ctx, |ui| {
-            let msg = match self.state.active_panel {
-                Panel::Chat => ChatView::show(ui, &mut self.state),
-                Panel::Sessions => SessionsView::show(ui, &mut self.state),
-                Panel::Status => StatusView::show(ui, &mut self.state),
-            };
-
-            // Send any UI messages to worker
-            if let Some(msg) = msg {
-                if let Err(e) = self.worker.send(msg) {
-                    self.state.error = Some(format!("Failed to send to worker: {}", e));
+        // Shortcuts and the command palette; not in the kiosk layout
+        if !kiosk {
+            match self.shortcuts.pressed(ctx) {
+                Some(Action::Palette) => self.palette.toggle(false, &self.state),
+                Some(Action::SearchMemory) => self.palette.toggle(true, &self.state),
+                Some(Action::NewSession) => self.send(ctx, UiMessage::NewSession),
+                Some(Action::Voice) => self.send(ctx, UiMessage::SetVoice(None)),
+                Some(Action::ShowPanel(panel)) => self.state.active_panel = panel,
+                None => {}
+            }
+            if let Some(msg) = self.palette.show(ctx, &self.state, &self.shortcuts) {
+                // What the other commands do shows up in the chat
+                if !matches!(msg, UiMessage::ShowPanel(_)) {
+                    self.state.active_panel = Panel::Chat;
                 }
+                self.send(ctx, msg);
             }
-        });
+        }
+
+        // Hands-free tutoring listens whichever panel is showing
+        if let Some(msg) = self.mic.converse(ctx, &mut self.state) {
+            self.send(ctx, msg);
+        }
+
+        // Running timers, counting down once a second
+        self.timers.refresh();
+        if !self.timers.is_empty() {
+            egui::TopBottomPanel::bottom("timers").show(ctx, |ui| {
+                self.timers.show(ui);
+            });
+        }
+        ctx.request_repaint_after(Duration::from_secs(1));
+
+        // Main content
+        let msg = egui::CentralPanel::default()
+            .show(ctx, |ui| {
+                if kiosk && self.kiosk.needs_pick(&self.state) {
+                    return self.kiosk.show_picker(ui);
+                }
+                match self.state.active_panel {
+                    Panel::Chat => ChatView::show(ui, &mut self.state, &mut self.mic),
+                    Panel::Sessions => self.sessions.show(ui, &mut self.state),
+                    Panel::Status => {
+                        StatusView::show(ui, &self.state);
+                        None
+                    }
+                    Panel::Changes => self.changes.show(ui),
+                    Panel::Prompt => self.prompt.show(ui, &self.state),
+                    Panel::Logs => {
+                        self.logs.show(ui);
+                        None
+                    }
+                }
+            })
+            .inner;
+
+        // Send any UI messages to worker
+        if let Some(msg) = msg {
+            self.send(ctx, msg);
+        }
     }
 
-    fn save(&mut self, _storage: &mut dyn eframe::Storage) {
-        // Could save window position, etc.
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, STORAGE_KEY, &self.display);
     }
 }
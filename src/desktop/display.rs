@@ -0,0 +1,139 @@
+//! Theme, scale and kiosk layout
+//!
+//! `[desktop]` in config gives the starting look; changes made from the View
+//! menu are kept in eframe's storage and win from then on.
+
+use anyhow::Result;
+use eframe::egui::{self, style::Spacing, ThemePreference};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::config::DesktopConfig;
+
+/// eframe storage key for the settings
+pub const STORAGE_KEY: &str = "display";
+
+/// Extra zoom in kiosk mode, on top of the scale, so kids can tap it
+const KIOSK_ZOOM: f32 = 1.5;
+
+const MIN_SCALE: f32 = 0.75;
+const MAX_SCALE: f32 = 2.5;
+const SCALE_STEP: f32 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Theme {
+    System,
+    Light,
+    Dark,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Self::System, Self::Light, Self::Dark];
+
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.trim() {
+            "system" => Ok(Self::System),
+            "light" => Ok(Self::Light),
+            "dark" => Ok(Self::Dark),
+            other => anyhow::bail!("Unknown theme '{}' (expected system, light or dark)", other),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::System => "System",
+            Self::Light => "Light",
+            Self::Dark => "Dark",
+        }
+    }
+
+    fn preference(self) -> ThemePreference {
+        match self {
+            Self::System => ThemePreference::System,
+            Self::Light => ThemePreference::Light,
+            Self::Dark => ThemePreference::Dark,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DisplaySettings {
+    pub theme: Theme,
+    /// Size of text and controls (1.0 = normal)
+    pub scale: f32,
+    /// Full screen, chat only, large buttons
+    pub kiosk: bool,
+}
+
+impl DisplaySettings {
+    pub fn from_config(config: &DesktopConfig) -> Self {
+        let theme = Theme::parse(&config.theme).unwrap_or_else(|e| {
+            warn!("Invalid desktop.theme: {}", e);
+            Theme::System
+        });
+        Self {
+            theme,
+            scale: config.ui_scale.clamp(MIN_SCALE, MAX_SCALE),
+            kiosk: config.kiosk,
+        }
+    }
+
+    /// Set up `ctx` for these settings; call again after any change
+    pub fn apply(&self, ctx: &egui::Context) {
+        ctx.set_theme(self.theme.preference());
+        let zoom = if self.kiosk {
+            self.scale * KIOSK_ZOOM
+        } else {
+            self.scale
+        };
+        ctx.set_zoom_factor(zoom);
+        let kiosk = self.kiosk;
+        ctx.all_styles_mut(|style| {
+            style.spacing = if kiosk {
+                kiosk_spacing()
+            } else {
+                Spacing::default()
+            };
+        });
+        ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(self.kiosk));
+    }
+
+    /// Contents of the View menu; returns whether anything changed
+    pub fn menu(&mut self, ui: &mut egui::Ui) -> bool {
+        let before = self.clone();
+        ui.label("Theme");
+        for theme in Theme::ALL {
+            ui.radio_value(&mut self.theme, theme, theme.label());
+        }
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label(format!("Size {:.0}%", self.scale * 100.0));
+            if ui.button("-").clicked() {
+                self.scale = (self.scale - SCALE_STEP).max(MIN_SCALE);
+            }
+            if ui.button("+").clicked() {
+                self.scale = (self.scale + SCALE_STEP).min(MAX_SCALE);
+            }
+            if ui.button("Reset").clicked() {
+                self.scale = 1.0;
+            }
+        });
+        ui.separator();
+        ui.checkbox(
+            &mut self.kiosk,
+            "Kiosk: full screen, chat only, big buttons",
+        );
+        *self != before
+    }
+}
+
+/// Roomier buttons and gaps, so a finger hits the right one
+fn kiosk_spacing() -> Spacing {
+    let default = Spacing::default();
+    Spacing {
+        item_spacing: egui::vec2(12.0, 10.0),
+        button_padding: egui::vec2(14.0, 8.0),
+        interact_size: egui::vec2(default.interact_size.x, 36.0),
+        ..default
+    }
+}
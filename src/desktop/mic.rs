@@ -0,0 +1,492 @@
+//! The mic: push-to-talk, and the open mic of hands-free tutoring
+//!
+//! Recording runs `desktop.mic_command`, which writes raw 16 kHz mono PCM to
+//! stdout until it's stopped. A reader thread keeps the samples (push-to-talk)
+//! or splits them into utterances (hands-free), and tracks the input level
+//! for the meter. Either way the worker transcribes what was said.
+
+use std::io::Read;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use eframe::egui::{self, Color32, Key, ProgressBar, RichText, Ui};
+use tracing::warn;
+
+use super::state::{UiMessage, UiState};
+use crate::config::{parse_duration, Config};
+use crate::stt::{after_wake_word, frame_db, VoiceDetector, SAMPLE_RATE};
+
+/// Recordings stop on their own after this long
+const MAX_RECORDING: Duration = Duration::from_secs(60);
+
+/// Anything shorter is a stray tap, not speech
+const MIN_RECORDING: Duration = Duration::from_millis(300);
+
+/// How often the level meter is redrawn while recording
+const METER_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The open mic waits this long after a reply is read out, so it doesn't
+/// hear the end of it
+const LISTEN_DELAY: Duration = Duration::from_millis(500);
+
+/// After a reply (or the wake word alone), a question needs no wake word
+/// for this long
+const FOLLOW_UP: Duration = Duration::from_secs(10);
+
+/// A running `mic_command`
+struct Recording {
+    child: Child,
+    reader: Option<JoinHandle<Vec<i16>>>,
+    /// Input level, 0.0 to 1.0, as `f32` bits
+    level: Arc<AtomicU32>,
+    started: Instant,
+    /// Hands-free: utterances heard
+    utterances: Receiver<Vec<i16>>,
+    /// Hands-free: nothing is heard while set
+    muted: Arc<AtomicBool>,
+    /// Hands-free: someone is talking
+    hearing: Arc<AtomicBool>,
+}
+
+impl Recording {
+    /// Record everything until stopped, or with `detector`, hand over each
+    /// utterance as it ends
+    fn start(command: &str, detector: Option<VoiceDetector>) -> Result<Self> {
+        let mut parts = command.split_whitespace();
+        let program = parts
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Empty mic command"))?;
+        let mut child = Command::new(program)
+            .args(parts)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .with_context(|| format!("Failed to run {}", program))?;
+        let mut stdout = child.stdout.take().context("No mic output")?;
+
+        let level = Arc::new(AtomicU32::new(0));
+        let muted = Arc::new(AtomicBool::new(false));
+        let hearing = Arc::new(AtomicBool::new(false));
+        let (heard, utterances) = mpsc::channel();
+        let meter = Arc::clone(&level);
+        let mute = Arc::clone(&muted);
+        let talking = Arc::clone(&hearing);
+        let mut detector = detector;
+        let reader = thread::spawn(move || {
+            let mut samples = Vec::new();
+            // A tenth of a second at a time
+            let mut buf = [0u8; 3200];
+            let mut odd_byte = None;
+            loop {
+                let n = match stdout.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => n,
+                };
+                let mut bytes: Vec<u8> = odd_byte.take().into_iter().collect();
+                bytes.extend_from_slice(&buf[..n]);
+                if bytes.len() % 2 == 1 {
+                    odd_byte = bytes.pop();
+                }
+                let block: Vec<i16> = bytes
+                    .chunks_exact(2)
+                    .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+                    .collect();
+                meter.store(input_level(&block).to_bits(), Ordering::Relaxed);
+                let Some(detector) = detector.as_mut() else {
+                    samples.extend(block);
+                    continue;
+                };
+                if mute.load(Ordering::Relaxed) {
+                    detector.reset();
+                } else {
+                    for utterance in detector.push(&block) {
+                        let _ = heard.send(utterance);
+                    }
+                }
+                talking.store(detector.in_speech(), Ordering::Relaxed);
+            }
+            samples
+        });
+
+        Ok(Self {
+            child,
+            reader: Some(reader),
+            level,
+            started: Instant::now(),
+            utterances,
+            muted,
+            hearing,
+        })
+    }
+
+    fn level(&self) -> f32 {
+        f32::from_bits(self.level.load(Ordering::Relaxed))
+    }
+
+    /// Whether `mic_command` is still recording
+    fn is_running(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+
+    /// Stop recording; returns what was recorded
+    fn stop(mut self) -> Vec<i16> {
+        self.kill();
+        self.reader
+            .take()
+            .and_then(|reader| reader.join().ok())
+            .unwrap_or_default()
+    }
+
+    fn kill(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl Drop for Recording {
+    fn drop(&mut self) {
+        self.kill();
+    }
+}
+
+/// Loudness of `samples` for the meter: -60 dBFS and below is 0, full scale 1
+fn input_level(samples: &[i16]) -> f32 {
+    ((frame_db(samples) + 60.0) / 60.0).clamp(0.0, 1.0)
+}
+
+/// The open mic of hands-free tutoring
+struct OpenMic {
+    recording: Recording,
+    session_id: String,
+    /// When someone last talked or a reply last ended
+    last_active: Instant,
+    /// Since when nothing's been in the way of listening
+    idle_since: Option<Instant>,
+    /// Until then a question needs no wake word
+    awake_until: Option<Instant>,
+    /// The utterance being transcribed started in the `awake_until` window
+    heard_awake: bool,
+    /// A question was sent; its reply opens the `awake_until` window
+    asked: bool,
+}
+
+impl OpenMic {
+    fn is_awake(&self) -> bool {
+        self.awake_until.is_some_and(|until| Instant::now() < until)
+    }
+
+    /// The question in `text`, if it was meant for the tutor
+    fn question(&mut self, text: String, wake_word: Option<&str>) -> Option<String> {
+        let question = match wake_word {
+            Some(wake_word) if !self.heard_awake => after_wake_word(&text, wake_word)?.to_string(),
+            _ => text,
+        };
+        if question.is_empty() {
+            // Only the wake word: the question comes next
+            self.awake_until = Some(Instant::now() + FOLLOW_UP);
+            return None;
+        }
+        self.awake_until = None;
+        self.asked = true;
+        Some(question)
+    }
+}
+
+/// The chat input's mic: a button, and holding Space while the input is
+/// empty; and the open mic while tutoring hands-free
+pub struct Mic {
+    command: String,
+    /// Transcripts are sent as messages rather than put in the input box
+    send: bool,
+    recording: Option<Recording>,
+    /// Started by holding Space, so letting go stops it
+    held: bool,
+    /// Hands-free questions start with this
+    wake_word: Option<String>,
+    /// Hands-free tutoring ends after this long without talk
+    tutor_timeout: Duration,
+    /// Speech level for the open mic, in dBFS
+    threshold: f32,
+    open: Option<OpenMic>,
+}
+
+impl Mic {
+    pub fn new() -> Self {
+        let desktop = match Config::load() {
+            Ok(config) => config.desktop,
+            Err(e) => {
+                warn!("Mic settings unavailable: {}", e);
+                Default::default()
+            }
+        };
+        let tutor_timeout = parse_duration(&desktop.tutor_timeout).unwrap_or_else(|e| {
+            warn!("Bad desktop.tutor_timeout: {}", e);
+            Duration::from_secs(300)
+        });
+        Self {
+            command: desktop.mic_command.unwrap_or_else(default_command),
+            send: desktop.send_speech,
+            recording: None,
+            held: false,
+            wake_word: desktop.wake_word.filter(|w| !w.trim().is_empty()),
+            tutor_timeout,
+            threshold: desktop.mic_threshold,
+            open: None,
+        }
+    }
+
+    /// Whether what was heard goes straight out as a message
+    pub fn sends(&self) -> bool {
+        self.send
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some() || self.open.is_some()
+    }
+
+    /// Hands-free tutoring, whichever panel is showing: the mic stays open
+    /// while `state.tutoring`, and each question heard is sent. It doesn't
+    /// listen while a reply is on its way or being read out, and tutoring
+    /// ends after `tutor_timeout` without talk.
+    pub fn converse(&mut self, ctx: &egui::Context, state: &mut UiState) -> Option<UiMessage> {
+        let Some(session_id) = state.tutoring.clone() else {
+            self.open = None;
+            return None;
+        };
+        if self
+            .open
+            .as_ref()
+            .is_none_or(|open| open.session_id != session_id)
+        {
+            // Push-to-talk gives way
+            self.recording = None;
+            self.held = false;
+            let detector = VoiceDetector::new(self.threshold);
+            match Recording::start(&self.command, Some(detector)) {
+                Ok(recording) => {
+                    self.open = Some(OpenMic {
+                        recording,
+                        session_id,
+                        last_active: Instant::now(),
+                        idle_since: None,
+                        awake_until: None,
+                        heard_awake: false,
+                        asked: false,
+                    });
+                }
+                Err(e) => return self.end_tutoring(state, format!("Mic unavailable: {}", e)),
+            }
+        }
+        let open = self.open.as_mut()?;
+        ctx.request_repaint_after(METER_INTERVAL);
+        if !open.recording.is_running() {
+            return self.end_tutoring(state, "The mic stopped recording".to_string());
+        }
+
+        if let Some(text) = state.heard.take() {
+            if let Some(question) = open.question(text, self.wake_word.as_deref()) {
+                state.add_user_message(question.clone());
+                state.is_loading = true;
+                return Some(UiMessage::Chat(question));
+            }
+        }
+
+        // Taking turns: quiet while a reply is on its way or being read out
+        let now = Instant::now();
+        let busy = state.is_loading || state.transcribing || state.speaking;
+        if busy {
+            open.idle_since = None;
+            open.last_active = now;
+        } else if open.idle_since.is_none() {
+            open.idle_since = Some(now);
+            if std::mem::take(&mut open.asked) {
+                open.awake_until = Some(now + LISTEN_DELAY + FOLLOW_UP);
+            }
+        }
+        let listening = open
+            .idle_since
+            .is_some_and(|since| since.elapsed() >= LISTEN_DELAY);
+        open.recording.muted.store(!listening, Ordering::Relaxed);
+        if open.recording.hearing.load(Ordering::Relaxed) {
+            open.last_active = now;
+        }
+
+        if let Ok(samples) = open.recording.utterances.try_recv() {
+            if listening {
+                // The answer to "Who's this?" needs no wake word
+                if !state.asking_who.is_empty() {
+                    open.awake_until = Some(now + FOLLOW_UP);
+                }
+                open.heard_awake = open.is_awake();
+                state.transcribing = true;
+                let wake_word = self.wake_word.clone().filter(|_| !open.heard_awake);
+                return Some(UiMessage::Transcribe { samples, wake_word });
+            }
+        }
+        if open.last_active.elapsed() >= self.tutor_timeout {
+            self.open = None;
+            state.tutoring = None;
+            return Some(UiMessage::Tutor(false));
+        }
+        None
+    }
+
+    /// Stop hands-free tutoring because the mic failed
+    fn end_tutoring(&mut self, state: &mut UiState, error: String) -> Option<UiMessage> {
+        self.open = None;
+        state.tutoring = None;
+        state.error = Some(error);
+        Some(UiMessage::Tutor(false))
+    }
+
+    /// What the open mic is doing, above the input
+    pub fn show_status(&self, ui: &mut Ui, state: &UiState) {
+        let Some(ref open) = self.open else {
+            return;
+        };
+        let status = if state.speaking {
+            "Reading the reply aloud...".to_string()
+        } else if state.is_loading {
+            return;
+        } else if state.transcribing {
+            "Working out what was said...".to_string()
+        } else if open.recording.hearing.load(Ordering::Relaxed) {
+            "Hearing you...".to_string()
+        } else {
+            match self.wake_word {
+                Some(ref wake_word) if !open.is_awake() => {
+                    format!("Listening for \"{}\"", wake_word)
+                }
+                _ => "Listening...".to_string(),
+            }
+        };
+        ui.label(
+            RichText::new(format!("🎓 {}", status))
+                .small()
+                .color(Color32::GRAY),
+        );
+    }
+
+    /// Hold-Space push-to-talk, while the chat input (`input_id`) is empty
+    /// and nothing else has focus. Call before the input is drawn, so the
+    /// space isn't typed.
+    pub fn push_to_talk(
+        &mut self,
+        ui: &Ui,
+        input_id: egui::Id,
+        state: &mut UiState,
+    ) -> Option<UiMessage> {
+        if self.open.is_some() {
+            return None;
+        }
+        if let Some(ref recording) = self.recording {
+            let released = self.held && !ui.input(|i| i.key_down(Key::Space));
+            if released || recording.started.elapsed() >= MAX_RECORDING {
+                return self.stop(state);
+            }
+            if self.held {
+                ui.input_mut(|i| i.events.retain(|event| !is_space(event)));
+            }
+            ui.ctx().request_repaint_after(METER_INTERVAL);
+            return None;
+        }
+
+        let focus = ui.memory(|m| m.focused());
+        if !state.input.is_empty() || state.is_loading || focus.is_some_and(|id| id != input_id) {
+            return None;
+        }
+        if !ui.input_mut(|i| i.consume_key(egui::Modifiers::NONE, Key::Space)) {
+            return None;
+        }
+        ui.input_mut(|i| i.events.retain(|event| !is_space(event)));
+        self.held = true;
+        self.start(state)
+    }
+
+    /// The mic button, or while recording the level meter and a stop button
+    pub fn show_button(&mut self, ui: &mut Ui, state: &mut UiState) -> Option<UiMessage> {
+        if let Some(ref open) = self.open {
+            ui.add(ProgressBar::new(open.recording.level()).desired_width(60.0))
+                .on_hover_text("The mic is open for hands-free tutoring");
+            return None;
+        }
+        if let Some(ref recording) = self.recording {
+            ui.add(ProgressBar::new(recording.level()).desired_width(60.0));
+            if ui
+                .button("⏹")
+                .on_hover_text("Stop and transcribe")
+                .clicked()
+            {
+                return self.stop(state);
+            }
+            return None;
+        }
+        let can_record = !state.is_loading && !state.transcribing;
+        if ui
+            .add_enabled(can_record, egui::Button::new("🎤"))
+            .on_hover_text("Talk instead of typing (or hold Space)")
+            .clicked()
+        {
+            self.held = false;
+            return self.start(state);
+        }
+        None
+    }
+
+    /// Start recording; a reply being read aloud stops, as if typed over
+    fn start(&mut self, state: &mut UiState) -> Option<UiMessage> {
+        match Recording::start(&self.command, None) {
+            Ok(recording) => {
+                self.recording = Some(recording);
+                Some(UiMessage::StopSpeaking)
+            }
+            Err(e) => {
+                self.held = false;
+                state.error = Some(format!("Mic unavailable: {}", e));
+                None
+            }
+        }
+    }
+
+    /// Stop recording and have it transcribed, unless it was too short
+    fn stop(&mut self, state: &mut UiState) -> Option<UiMessage> {
+        let recording = self.recording.take()?;
+        self.held = false;
+        let long_enough = recording.started.elapsed() >= MIN_RECORDING;
+        let samples = recording.stop();
+        if !long_enough || samples.is_empty() {
+            return None;
+        }
+        state.transcribing = true;
+        Some(UiMessage::Transcribe {
+            samples,
+            wake_word: None,
+        })
+    }
+}
+
+/// Space presses and the spaces they type
+fn is_space(event: &egui::Event) -> bool {
+    match event {
+        egui::Event::Key { key, .. } => *key == Key::Space,
+        egui::Event::Text(text) => text == " ",
+        _ => false,
+    }
+}
+
+fn default_command() -> String {
+    if cfg!(target_os = "macos") {
+        format!(
+            "sox -q -d -t raw -r {} -c 1 -b 16 -e signed-integer -",
+            SAMPLE_RATE
+        )
+    } else {
+        format!("arecord -q -f S16_LE -r {} -c 1 -t raw", SAMPLE_RATE)
+    }
+}
@@ -5,6 +5,11 @@
 //! and communicates with the UI via channels.
 
 mod app;
+mod display;
+mod mic;
+mod shortcuts;
+mod speaker_id;
+mod speech;
 mod state;
 mod views;
 mod worker;
@@ -0,0 +1,149 @@
+//! Keyboard shortcuts
+//!
+//! Each action has a default key, which `[desktop.shortcuts]` can change
+//! (`new_session = "Ctrl+Shift+N"`) or turn off (`""`). Ctrl is Cmd on
+//! macOS.
+
+use anyhow::Result;
+use eframe::egui::{self, Key, KeyboardShortcut, Modifiers};
+use std::cmp::Reverse;
+use tracing::warn;
+
+use super::state::Panel;
+use crate::config::DesktopConfig;
+
+/// Something a shortcut does
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    /// Open the command palette
+    Palette,
+    /// Open the command palette to search memory
+    SearchMemory,
+    NewSession,
+    /// Read this session's replies aloud, or stop
+    Voice,
+    ShowPanel(Panel),
+}
+
+impl Action {
+    pub const ALL: [Action; 10] = [
+        Self::Palette,
+        Self::SearchMemory,
+        Self::NewSession,
+        Self::Voice,
+        Self::ShowPanel(Panel::Chat),
+        Self::ShowPanel(Panel::Sessions),
+        Self::ShowPanel(Panel::Status),
+        Self::ShowPanel(Panel::Changes),
+        Self::ShowPanel(Panel::Prompt),
+        Self::ShowPanel(Panel::Logs),
+    ];
+
+    /// Key in `[desktop.shortcuts]`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Palette => "palette",
+            Self::SearchMemory => "search_memory",
+            Self::NewSession => "new_session",
+            Self::Voice => "voice",
+            Self::ShowPanel(Panel::Chat) => "chat",
+            Self::ShowPanel(Panel::Sessions) => "sessions",
+            Self::ShowPanel(Panel::Status) => "status",
+            Self::ShowPanel(Panel::Changes) => "changes",
+            Self::ShowPanel(Panel::Prompt) => "prompt",
+            Self::ShowPanel(Panel::Logs) => "logs",
+        }
+    }
+
+    fn default_key(&self) -> &'static str {
+        match self {
+            Self::Palette => "Ctrl+K",
+            Self::SearchMemory => "Ctrl+Shift+F",
+            Self::NewSession => "Ctrl+N",
+            Self::Voice => "Ctrl+Shift+V",
+            Self::ShowPanel(Panel::Chat) => "Ctrl+1",
+            Self::ShowPanel(Panel::Sessions) => "Ctrl+2",
+            Self::ShowPanel(Panel::Status) => "Ctrl+3",
+            Self::ShowPanel(Panel::Changes) => "Ctrl+4",
+            Self::ShowPanel(Panel::Prompt) => "Ctrl+5",
+            Self::ShowPanel(Panel::Logs) => "Ctrl+6",
+        }
+    }
+}
+
+/// The shortcut for each action that has one
+pub struct Shortcuts {
+    bindings: Vec<(Action, KeyboardShortcut)>,
+}
+
+impl Shortcuts {
+    pub fn from_config(config: &DesktopConfig) -> Self {
+        for name in config.shortcuts.keys() {
+            if !Action::ALL.iter().any(|action| action.name() == name) {
+                warn!("Unknown action in desktop.shortcuts: {}", name);
+            }
+        }
+        let mut bindings: Vec<(Action, KeyboardShortcut)> = Action::ALL
+            .into_iter()
+            .filter_map(|action| {
+                let key = config
+                    .shortcuts
+                    .get(action.name())
+                    .map_or(action.default_key(), String::as_str);
+                if key.trim().is_empty() {
+                    return None;
+                }
+                match parse_shortcut(key) {
+                    Ok(shortcut) => Some((action, shortcut)),
+                    Err(e) => {
+                        warn!("Invalid desktop.shortcuts.{}: {}", action.name(), e);
+                        None
+                    }
+                }
+            })
+            .collect();
+        // Ctrl+F would also take Ctrl+Shift+F, so the longer one goes first
+        bindings.sort_by_key(|(_, shortcut)| {
+            let m = shortcut.modifiers;
+            Reverse(m.shift as u8 + m.alt as u8)
+        });
+        Self { bindings }
+    }
+
+    /// The action whose shortcut was pressed this frame, if any
+    pub fn pressed(&self, ctx: &egui::Context) -> Option<Action> {
+        ctx.input_mut(|input| {
+            self.bindings
+                .iter()
+                .find(|(_, shortcut)| input.consume_shortcut(shortcut))
+                .map(|(action, _)| *action)
+        })
+    }
+
+    /// `action`'s shortcut as this platform writes it, e.g. "Ctrl+N" or "⌘N"
+    pub fn label(&self, ctx: &egui::Context, action: Action) -> Option<String> {
+        self.bindings
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map(|(_, shortcut)| ctx.format_shortcut(shortcut))
+    }
+}
+
+/// "Ctrl+Shift+K" as a shortcut
+fn parse_shortcut(text: &str) -> Result<KeyboardShortcut> {
+    let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+    let key = parts.pop().unwrap_or_default();
+    let mut modifiers = Modifiers::NONE;
+    for part in parts {
+        modifiers = modifiers
+            | match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "cmd" | "command" => Modifiers::COMMAND,
+                "alt" | "option" => Modifiers::ALT,
+                "shift" => Modifiers::SHIFT,
+                other => anyhow::bail!("unknown modifier '{}' in '{}'", other, text),
+            };
+    }
+    let key = Key::from_name(key)
+        .ok_or_else(|| anyhow::anyhow!("unknown key '{}' in '{}'", key, text))?;
+    Ok(KeyboardShortcut::new(modifiers, key))
+}
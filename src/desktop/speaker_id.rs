@@ -0,0 +1,283 @@
+//! Speaker identification in voice mode: a question said into the mic goes
+//! to the session of the family member whose voice it is
+//!
+//! People in `desktop.people` enroll with `/enroll <name>` and a few
+//! sentences into the mic. After that, a question in an enrolled voice
+//! starts that person's session, under their profile and persona, before
+//! it's sent. A voice that doesn't match well enough is asked "Who's this?"
+//! and the question waits for the answer. Voice only ever switches to
+//! someone with a profile: grown-ups still pick themselves (behind the
+//! parent PIN in kiosk mode).
+
+use std::path::PathBuf;
+use std::sync::mpsc::Sender;
+use tracing::warn;
+
+use crate::config::{Config, KioskPersonConfig};
+use crate::stt::{embed_voice, name_said, Speaker, Voiceprints};
+
+use super::state::WorkerMessage;
+
+/// Asked, and read aloud, when a voice isn't recognized
+pub const WHO_IS_THIS: &str = "Who's this?";
+
+/// What the mic heard, and whose session it goes to
+pub struct Heard {
+    pub text: String,
+    /// Start this person's session first
+    pub switch_to: Option<KioskPersonConfig>,
+    /// "Who's this?" was asked; `text` waits for the answer
+    pub asked: bool,
+}
+
+impl Heard {
+    fn text(text: String) -> Self {
+        Self {
+            text,
+            switch_to: None,
+            asked: false,
+        }
+    }
+}
+
+pub struct SpeakerId {
+    path: Option<PathBuf>,
+    voiceprints: Voiceprints,
+    threshold: f32,
+    /// Whose voice the next recording is, after `/enroll <name>`
+    enrolling: Option<String>,
+    /// The question held while "Who's this?" is asked
+    held: Option<String>,
+    /// Someone said who they are, so voices that aren't recognized stay in
+    /// the current session until a known one switches
+    settled: bool,
+}
+
+impl SpeakerId {
+    pub fn new(config: &Config) -> Self {
+        let path = Voiceprints::path()
+            .map_err(|e| warn!("Voiceprints unavailable: {}", e))
+            .ok();
+        let voiceprints = path.as_deref().map(Voiceprints::load).unwrap_or_default();
+        Self {
+            path,
+            voiceprints,
+            threshold: config.desktop.speaker_threshold,
+            enrolling: None,
+            held: None,
+            settled: false,
+        }
+    }
+
+    /// Whether the next recording is for `/enroll` rather than the chat
+    pub fn is_enrolling(&self) -> bool {
+        self.enrolling.is_some()
+    }
+
+    /// `/enroll <name>`, `/enroll forget <name>`, or `/enroll` to list
+    pub fn command(&mut self, arg: Option<String>, config: &Config, tx: &Sender<WorkerMessage>) {
+        let arg = arg.unwrap_or_default();
+        let arg = arg.trim();
+        if arg.is_empty() {
+            let enrolled: Vec<String> = self
+                .voiceprints
+                .enrolled()
+                .map(|(name, recordings)| format!("{} ({} recordings)", name, recordings))
+                .collect();
+            let text = if enrolled.is_empty() {
+                "No voices enrolled. /enroll <name> records someone from desktop.people."
+                    .to_string()
+            } else {
+                format!("Voices enrolled: {}", enrolled.join(", "))
+            };
+            let _ = tx.send(WorkerMessage::SystemMessage(text));
+            return;
+        }
+        if let Some(name) = arg.strip_prefix("forget ") {
+            let text = if self.voiceprints.forget(name.trim()) {
+                self.save(tx);
+                format!("Forgot {}'s voice.", name.trim())
+            } else {
+                format!("No voice enrolled for {}.", name.trim())
+            };
+            let _ = tx.send(WorkerMessage::SystemMessage(text));
+            return;
+        }
+
+        let Some(person) = find_person(config, arg) else {
+            let _ = tx.send(WorkerMessage::Error(format!(
+                "No one called '{}' in desktop.people",
+                arg
+            )));
+            return;
+        };
+        self.enrolling = Some(person.name.clone());
+        let _ = tx.send(WorkerMessage::SystemMessage(format!(
+            "{}: record a few sentences in your normal voice with the mic. \
+             Enrolling three or four times makes it more reliable.",
+            person.name
+        )));
+    }
+
+    /// The `/enroll` recording: add it to the person's voiceprint
+    pub fn enroll(&mut self, samples: &[i16], tx: &Sender<WorkerMessage>) -> Heard {
+        let Some(name) = self.enrolling.take() else {
+            return Heard::text(String::new());
+        };
+        let Some(embedding) = embed_voice(samples) else {
+            let _ = tx.send(WorkerMessage::Error(format!(
+                "Too little speech to enroll {}; try a longer recording",
+                name
+            )));
+            self.enrolling = Some(name);
+            return Heard::text(String::new());
+        };
+        let recordings = self.voiceprints.enroll(&name, &embedding);
+        if self.save(tx) {
+            let _ = tx.send(WorkerMessage::SystemMessage(format!(
+                "Enrolled {}'s voice ({} recordings).",
+                name, recordings
+            )));
+        }
+        Heard::text(String::new())
+    }
+
+    /// Who said `text`, recorded in `samples`. Only questions meant for the
+    /// assistant (`addressed`) are checked; `chatting_as` is whose session
+    /// is open.
+    pub fn heard(
+        &mut self,
+        samples: &[i16],
+        text: String,
+        addressed: bool,
+        chatting_as: Option<&str>,
+        config: &Config,
+        tx: &Sender<WorkerMessage>,
+    ) -> Heard {
+        if self.held.is_some() {
+            return self.answer(&text, chatting_as, config, tx);
+        }
+        if self.voiceprints.is_empty() || text.trim().is_empty() || !addressed {
+            return Heard::text(text);
+        }
+        let Some(embedding) = embed_voice(samples) else {
+            return Heard::text(text);
+        };
+        match self
+            .voiceprints
+            .identify(&embedding, self.threshold, chatting_as)
+        {
+            Speaker::Known(name) if Some(name.as_str()) == chatting_as => Heard::text(text),
+            Speaker::Known(name) => match find_person(config, &name) {
+                // Grown-ups aren't switched to by voice
+                Some(person) if person.profile.is_some() => {
+                    self.settled = false;
+                    Heard {
+                        text,
+                        switch_to: Some(person.clone()),
+                        asked: false,
+                    }
+                }
+                _ => Heard::text(text),
+            },
+            Speaker::Unsure if self.settled => Heard::text(text),
+            Speaker::Unsure => {
+                self.held = Some(text);
+                let names = config.desktop.people.iter().map(|p| p.name.clone());
+                let _ = tx.send(WorkerMessage::AskWho(names.collect()));
+                Heard {
+                    text: String::new(),
+                    switch_to: None,
+                    asked: true,
+                }
+            }
+        }
+    }
+
+    /// The answer to "Who's this?", from its buttons or said into the mic:
+    /// the held question, in the session of the person named
+    pub fn answer(
+        &mut self,
+        answer: &str,
+        chatting_as: Option<&str>,
+        config: &Config,
+        tx: &Sender<WorkerMessage>,
+    ) -> Heard {
+        let Some(text) = self.held.take() else {
+            return Heard::text(String::new());
+        };
+        let _ = tx.send(WorkerMessage::AskWho(Vec::new()));
+        self.settled = true;
+
+        let names: Vec<&str> = config
+            .desktop
+            .people
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        let person = name_said(answer, &names).and_then(|name| find_person(config, name));
+        let session = match chatting_as {
+            Some(name) => format!("{}'s session", name),
+            None => "this session".to_string(),
+        };
+        let switch_to = match person {
+            Some(person) if Some(person.name.as_str()) == chatting_as => None,
+            Some(person) if person.profile.is_some() => Some(person.clone()),
+            Some(person) => {
+                let _ = tx.send(WorkerMessage::SystemMessage(format!(
+                    "Voice only switches to people with a profile; pick {} to leave {}.",
+                    person.name, session
+                )));
+                None
+            }
+            None => {
+                let _ = tx.send(WorkerMessage::SystemMessage(format!(
+                    "Didn't catch a name, so that goes to {}.",
+                    session
+                )));
+                None
+            }
+        };
+        Heard {
+            text,
+            switch_to,
+            asked: false,
+        }
+    }
+
+    /// A typed message came first; the held question is dropped
+    pub fn drop_question(&mut self, tx: &Sender<WorkerMessage>) {
+        if self.held.take().is_some() {
+            let _ = tx.send(WorkerMessage::AskWho(Vec::new()));
+        }
+    }
+
+    /// Someone was picked by hand
+    pub fn picked(&mut self) {
+        self.settled = true;
+    }
+
+    fn save(&self, tx: &Sender<WorkerMessage>) -> bool {
+        let Some(ref path) = self.path else {
+            return false;
+        };
+        match self.voiceprints.save(path) {
+            Ok(()) => true,
+            Err(e) => {
+                let _ = tx.send(WorkerMessage::Error(format!(
+                    "Couldn't save voiceprints: {}",
+                    e
+                )));
+                false
+            }
+        }
+    }
+}
+
+fn find_person<'a>(config: &'a Config, name: &str) -> Option<&'a KioskPersonConfig> {
+    config
+        .desktop
+        .people
+        .iter()
+        .find(|person| person.name.eq_ignore_ascii_case(name))
+}
@@ -0,0 +1,190 @@
+//! Reading replies aloud while they stream
+//!
+//! Sentences are spoken one after another on their own thread, so the worker
+//! keeps streaming while the speaker talks. Barging in (the user starts
+//! typing, or stops the reply) drops what's queued and cuts off the sentence
+//! being spoken.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+
+use tokio::sync::Notify;
+
+use crate::announce::{Announcer, SentenceChunker};
+use crate::memory::{parse_citation, CITATION_PREFIX};
+
+use super::state::WorkerMessage;
+
+/// Added to the system prompt while a session's replies are read aloud, so
+/// SOUL.md's guidance for voice replies applies
+pub const VOICE_CONTEXT: &str = "Your replies in this session are read aloud \
+as you write them. Follow SOUL.md's guidance for voice replies, and write \
+plain spoken sentences: no markdown, lists, tables, code or emoji.";
+
+/// Stop request for speech, shared with the UI. Each stop starts a new
+/// generation; sentences from earlier ones are dropped.
+#[derive(Clone, Default)]
+pub struct BargeIn {
+    generation: Arc<AtomicU64>,
+    notify: Arc<Notify>,
+}
+
+impl BargeIn {
+    pub fn stop(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once `stop` has been called since `generation`
+    async fn stopped(&self, generation: u64) {
+        loop {
+            // Register before checking so a stop in between isn't missed
+            let notified = self.notify.notified();
+            if self.generation() != generation {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// The speaker replies are read aloud on
+pub struct Speech {
+    tx: Sender<(u64, String)>,
+    barge_in: BargeIn,
+    speaker: String,
+}
+
+impl Speech {
+    /// Start speaking on `speaker`, one of `announcer`'s devices. Errors are
+    /// shown in the chat, once per reply, and `ui` hears when speaking
+    /// starts and stops (hands-free tutoring listens in between).
+    pub fn start(
+        announcer: Announcer,
+        speaker: String,
+        barge_in: BargeIn,
+        ui: Sender<WorkerMessage>,
+    ) -> Self {
+        let (tx, rx) = mpsc::channel::<(u64, String)>();
+        let stop = barge_in.clone();
+        let device = speaker.clone();
+        thread::spawn(move || {
+            let rt = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("Failed to create tokio runtime");
+            let mut failed = None;
+            let mut speaking = false;
+            loop {
+                let (generation, text) = match rx.try_recv() {
+                    Ok(next) => next,
+                    Err(TryRecvError::Empty) => {
+                        if speaking {
+                            speaking = false;
+                            let _ = ui.send(WorkerMessage::Speaking(false));
+                        }
+                        // Ends once the worker drops the `Speech`
+                        match rx.recv() {
+                            Ok(next) => next,
+                            Err(_) => break,
+                        }
+                    }
+                    Err(TryRecvError::Disconnected) => break,
+                };
+                if generation != stop.generation() {
+                    continue;
+                }
+                if !speaking {
+                    speaking = true;
+                    let _ = ui.send(WorkerMessage::Speaking(true));
+                }
+                let result = rt.block_on(async {
+                    tokio::select! {
+                        result = announcer.say(&text, Some(&device)) => result.map(|_| ()),
+                        _ = stop.stopped(generation) => Ok(()),
+                    }
+                });
+                if let Err(e) = result {
+                    if failed != Some(generation) {
+                        failed = Some(generation);
+                        let _ = ui.send(WorkerMessage::Error(format!(
+                            "Reading the reply failed: {}",
+                            e
+                        )));
+                    }
+                }
+            }
+            if speaking {
+                let _ = ui.send(WorkerMessage::Speaking(false));
+            }
+        });
+        Self {
+            tx,
+            barge_in,
+            speaker,
+        }
+    }
+
+    pub fn speaker(&self) -> &str {
+        &self.speaker
+    }
+
+    /// Start reading a reply; feed it the streamed text
+    pub fn reply(&self) -> SpokenReply {
+        SpokenReply {
+            tx: self.tx.clone(),
+            generation: self.barge_in.generation(),
+            chunker: SentenceChunker::new(),
+        }
+    }
+}
+
+/// A reply being read aloud, a sentence at a time
+pub struct SpokenReply {
+    tx: Sender<(u64, String)>,
+    /// A barge-in after this reply started silences the rest of it
+    generation: u64,
+    chunker: SentenceChunker,
+}
+
+impl SpokenReply {
+    pub fn push(&mut self, text: &str) {
+        for sentence in self.chunker.push(text) {
+            self.say(&sentence);
+        }
+    }
+
+    /// The reply is complete; say what's left
+    pub fn finish(mut self) {
+        if let Some(rest) = self.chunker.finish() {
+            self.say(&rest);
+        }
+    }
+
+    fn say(&self, sentence: &str) {
+        let text = spoken_text(sentence);
+        if !text.is_empty() {
+            let _ = self.tx.send((self.generation, text));
+        }
+    }
+}
+
+/// `text` without citations and markdown marks, which speakers would read out
+fn spoken_text(text: &str) -> String {
+    let mut spoken = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(CITATION_PREFIX) {
+        spoken.push_str(&rest[..start]);
+        let len = parse_citation(&rest[start..]).map_or(CITATION_PREFIX.len(), |(_, len)| len);
+        rest = &rest[start + len..];
+    }
+    spoken.push_str(rest);
+    spoken.retain(|c| !matches!(c, '*' | '#' | '`' | '_' | '>' | '|'));
+    spoken.trim().to_string()
+}
@@ -1,12 +1,51 @@
 //! Application state shared between UI and worker
 
-use crate::agent::{SessionInfo, SessionStatus, ToolCall};
+use chrono::NaiveDateTime;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::agent::{
+    extract_tool_detail, Message, PromptInspection, Role, SessionInfo, SessionStatus, ToolCall,
+    INTERRUPTED_MARKER,
+};
+use crate::memory::IndexProgress;
 
 /// Message from UI to worker
 #[derive(Debug, Clone)]
 pub enum UiMessage {
     /// Send a chat message
     Chat(String),
+    /// Run a skill (`/skill <name> [request]`); the worker turns it into a
+    /// chat message
+    InvokeSkill { name: String, args: String },
+    /// Read the session's replies aloud: "on", "off", a speaker name, or
+    /// (`None`) over
+    SetVoice(Option<String>),
+    /// The user started typing; stop reading the reply aloud
+    StopSpeaking,
+    /// Turn a mic recording (16 kHz mono) into text for the input. The
+    /// open mic's `wake_word` is given while a question needs it.
+    Transcribe {
+        samples: Vec<i16>,
+        wake_word: Option<String>,
+    },
+    /// `/enroll`: the next recording is this person's voice (`None` lists
+    /// who's enrolled; "forget <name>" removes someone)
+    Enroll(Option<String>),
+    /// Answer to `AskWho`
+    ThisIs(String),
+    /// Start or end hands-free tutoring in the current session: the mic
+    /// stays open, replies are read aloud, and the session is logged to the
+    /// tutor notes when it ends
+    Tutor(bool),
+    /// Kiosk mode: start a session for a family member under their profile
+    ChatAs {
+        name: String,
+        profile: Option<String>,
+    },
+    /// Kiosk mode ended: back to the app's own profile, in a new session
+    LeaveKiosk,
     /// Create a new session
     NewSession,
     /// Resume a session by ID
@@ -15,22 +54,87 @@ pub enum UiMessage {
     ApproveTools(Vec<ToolCall>),
     /// Deny pending tool calls
     DenyTools,
+    /// Stop the response being generated, keeping what has streamed so far
+    CancelGeneration,
+    /// Attach files (dropped onto the window) to the next message
+    AttachFiles(Vec<PathBuf>),
+    /// Remove a pending attachment
+    RemoveAttachment(usize),
+    /// Save a pending text attachment into memory
+    SaveAttachment(usize),
+    /// Rewind to an earlier user message and send `content` in its place
+    /// (unchanged to regenerate the reply). `occurrence` counts later user
+    /// messages with the same text as `original`.
+    Resend {
+        original: String,
+        occurrence: usize,
+        content: String,
+    },
     /// Request session list refresh
     RefreshSessions,
-    /// Request status update
-    RefreshStatus,
+    /// Name a saved session (an empty label clears it)
+    RenameSession {
+        agent_id: String,
+        id: String,
+        label: String,
+    },
+    /// Pin a saved session to the top of the list, or unpin it
+    PinSession {
+        agent_id: String,
+        id: String,
+        pinned: bool,
+    },
+    /// Replace a saved session's tags
+    TagSession {
+        agent_id: String,
+        id: String,
+        tags: Vec<String>,
+    },
+    /// Delete a saved session's file and sessions.json entries
+    DeleteSession { agent_id: String, id: String },
     /// Set model
     SetModel(String),
     /// Compact current session
     Compact,
     /// Search memory
     SearchMemory(String),
+    /// Look up the chunk a `[VERIFIED:<hash>]` citation points at
+    LookupCitation(String),
+    /// Reindex memory in the background
+    Reindex,
+    /// Stop the background reindex
+    CancelIndexing,
     /// Save session to disk
     Save,
     /// Show help text
     ShowHelp,
     /// Show status info
     ShowStatus,
+    /// Show what the next turn sends the model, for the Prompt panel
+    InspectPrompt,
+    /// Switch to a panel (handled by the app)
+    ShowPanel(Panel),
+    /// Save the session and start over as another agent (handled by
+    /// `WorkerHandle::send`)
+    SwitchAgent(String),
+    /// Run a HEARTBEAT.md section now, by title; the worker turns it into
+    /// a chat message
+    RunTask(String),
+    /// Turn dry run on, off, or (`None`) over
+    SetDryRun(Option<bool>),
+    /// Write a staged change to its file
+    ApproveChange(i64),
+    /// Drop a staged change
+    RejectChange(i64),
+    /// Restore the session from the recovery offer
+    RestoreSession,
+    /// Decline the recovery offer
+    DismissRecovery,
+    /// Save and stop before the app exits (sent by `WorkerHandle::shutdown`)
+    Shutdown,
+    /// Restart the crashed worker now rather than after the backoff
+    /// (handled by `WorkerHandle::send`)
+    Reconnect,
 }
 
 /// Message from worker to UI
@@ -38,10 +142,18 @@ pub enum UiMessage {
 pub enum WorkerMessage {
     /// Agent is ready
     Ready {
+        /// Agent whose sessions this app resumes ("daemon" when attached)
+        agent_id: String,
         model: String,
         memory_chunks: usize,
         has_embeddings: bool,
+        /// Embedding model still loading; `EmbeddingsReady` follows
+        embeddings_loading: bool,
     },
+    /// Embedding model loaded; semantic search is available
+    EmbeddingsReady,
+    /// Command names of the skills `/skill` can run
+    Skills(Vec<String>),
     /// Streaming content chunk
     ContentChunk(String),
     /// Tool call started
@@ -49,6 +161,8 @@ pub enum WorkerMessage {
         name: String,
         id: String,
         detail: Option<String>,
+        /// JSON arguments
+        arguments: String,
     },
     /// Tool call completed
     ToolCallEnd {
@@ -60,16 +174,168 @@ pub enum WorkerMessage {
     ToolsPendingApproval(Vec<ToolCall>),
     /// Response complete
     Done,
+    /// Response stopped by `CancelGeneration`; the partial reply was saved
+    Interrupted,
     /// Error occurred
     Error(String),
     /// Session status update
     Status(SessionStatus),
+    /// Memory, usage and heartbeat health, pushed with `Status` every few
+    /// seconds
+    Overview(StatusOverview),
+    /// Answer to `InspectPrompt`
+    Prompt(PromptInspection),
+    /// Answer to `LookupCitation` (None = no longer indexed)
+    Citation {
+        hash: String,
+        card: Option<CitationCard>,
+    },
     /// Session list update
     Sessions(Vec<SessionInfo>),
     /// Session created/resumed
     SessionChanged { id: String, message_count: usize },
+    /// Transcript of a resumed session, tool calls included
+    History(Vec<ChatMessage>),
+    /// Answer to `ChatAs` (the person's name) or `LeaveKiosk` (None)
+    ChattingAs(Option<String>),
+    /// Sessions whose replies are read aloud
+    VoicedSessions(Vec<String>),
+    /// Answer to `Transcribe`; empty if nothing was made out
+    Heard(String),
+    /// The voice wasn't recognized: who's this, of these people? Empty
+    /// once answered.
+    AskWho(Vec<String>),
+    /// Session in hands-free tutoring, or `None` once it ended
+    Tutoring(Option<String>),
+    /// A reply started or finished being read aloud
+    Speaking(bool),
     /// System message for display (command output, help text, etc.)
     SystemMessage(String),
+    /// Answer to `SearchMemory`
+    MemoryResults { query: String, hits: Vec<MemoryHit> },
+    /// Pending attachments changed
+    Attachments(Vec<AttachmentInfo>),
+    /// Background reindex progressed or ended
+    IndexProgress(IndexProgress),
+    /// The app last stopped mid-reply; offer to restore that session
+    RecoveryAvailable(RecoveryOffer),
+    /// Interrupted session restored; shows the exchange that was cut short
+    Restored { message: String, partial: String },
+    /// Worker saved everything and exited after `Shutdown`
+    Stopped,
+    /// The worker thread crashed (sent by `WorkerHandle::try_recv`); it is
+    /// restarted after `retry_in`. `dropped` inputs crashed it twice and
+    /// won't be sent again.
+    WorkerDied {
+        reason: String,
+        retry_in: Duration,
+        dropped: usize,
+    },
+    /// The restarted worker is ready; `resent` chat inputs the crashed one
+    /// never answered were sent again
+    Reconnected { resent: usize },
+}
+
+/// The worker crashed and is waiting to be restarted
+#[derive(Debug, Clone)]
+pub struct WorkerDown {
+    pub reason: String,
+    pub retry_at: Instant,
+}
+
+/// What the status panel shows besides the session
+#[derive(Debug, Clone, Default)]
+pub struct StatusOverview {
+    pub memory_chunks: usize,
+    /// Chunks per category, largest first
+    pub categories: Vec<(String, usize)>,
+    /// Verification hashes per provenance
+    pub provenance: Vec<(String, i64)>,
+    /// `memory.embedding_provider`
+    pub embedding_provider: String,
+    pub embedding_model: Option<String>,
+    /// Tokens the profile has spent today, in every process
+    pub tokens_today: u64,
+    pub daemon: DaemonLink,
+    /// The daemon's last heartbeat (None = no daemon, or no run yet)
+    pub heartbeat: Option<HeartbeatRun>,
+    /// When each HEARTBEAT.md section last ran
+    pub task_runs: Vec<(String, NaiveDateTime)>,
+}
+
+/// How the app stands with the daemon
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DaemonLink {
+    /// Chat goes through the daemon
+    Attached,
+    /// Running, but this app has its own agent (`--local`)
+    Running,
+    #[default]
+    NotRunning,
+    /// Attached, but it stopped answering
+    Unreachable,
+}
+
+/// The outcome of a heartbeat run
+#[derive(Debug, Clone)]
+pub struct HeartbeatRun {
+    /// "sent", "ok", "skipped" or "failed"
+    pub status: String,
+    pub age_seconds: u64,
+    /// The reply, or why it was skipped or failed
+    pub detail: Option<String>,
+}
+
+/// Where a citation in a reply comes from, shown when its chip is hovered
+#[derive(Debug, Clone)]
+pub struct CitationCard {
+    /// Workspace-relative file
+    pub file: String,
+    pub line_start: i32,
+    pub line_end: i32,
+    /// The file on disk, for opening it
+    pub path: PathBuf,
+    /// Whether the chunk still matches its stored hash
+    pub verified: bool,
+    pub provenance: String,
+    pub confidence: String,
+}
+
+/// A `/memory` search result
+#[derive(Debug, Clone)]
+pub struct MemoryHit {
+    /// Citation hash prefix; empty if the chunk has no hash
+    pub hash: String,
+    pub card: CitationCard,
+    pub score: f64,
+    /// `last_verified` is past the max age for the file's category
+    pub stale: bool,
+    pub content: String,
+}
+
+/// A citation looked up (or being looked up) for the chat view
+#[derive(Debug, Clone)]
+pub enum Citation {
+    Loading,
+    Found(CitationCard),
+    /// The chunk was edited or forgotten since it was cited
+    Missing,
+}
+
+/// Reply that was cut short when the app last stopped
+#[derive(Debug, Clone)]
+pub struct RecoveryOffer {
+    pub session_id: String,
+    /// The message being answered
+    pub message: String,
+}
+
+/// Pending attachment shown above the input
+#[derive(Debug, Clone)]
+pub struct AttachmentInfo {
+    pub label: String,
+    /// Text attachments can be saved into memory
+    pub is_text: bool,
 }
 
 /// A chat message for display
@@ -78,6 +344,8 @@ pub struct ChatMessage {
     pub role: MessageRole,
     pub content: String,
     pub tool_info: Option<ToolInfo>,
+    /// `/memory` results, for `MessageRole::Memory`
+    pub memory: Vec<MemoryHit>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -86,12 +354,68 @@ pub enum MessageRole {
     User,
     Assistant,
     System,
+    /// A tool call the agent made; see `tool_info`
+    Tool,
+    /// `/memory` results, shown as cards; see `memory`
+    Memory,
+}
+
+impl ChatMessage {
+    /// The transcript of a saved session, with each tool call next to
+    /// its output
+    pub fn from_session(messages: &[Message]) -> Vec<Self> {
+        let output = |id: &str| {
+            messages
+                .iter()
+                .find(|m| m.role == Role::Tool && m.tool_call_id.as_deref() == Some(id))
+                .map(|m| m.content.clone())
+        };
+        let mut transcript = Vec::new();
+        for message in messages {
+            let role = match message.role {
+                Role::User => MessageRole::User,
+                Role::Assistant => MessageRole::Assistant,
+                Role::System | Role::Tool => continue,
+            };
+            for call in message.tool_calls.iter().flatten() {
+                let status = match output(&call.id) {
+                    Some(output) => ToolStatus::from_output(output),
+                    None => ToolStatus::Error("No output was saved".to_string()),
+                };
+                transcript.push(Self {
+                    role: MessageRole::Tool,
+                    content: String::new(),
+                    tool_info: Some(ToolInfo {
+                        id: call.id.clone(),
+                        name: call.name.clone(),
+                        detail: extract_tool_detail(&call.name, &call.arguments),
+                        arguments: call.arguments.clone(),
+                        status,
+                    }),
+                    memory: Vec::new(),
+                });
+            }
+            if !message.content.is_empty() {
+                transcript.push(Self {
+                    role,
+                    content: message.content.clone(),
+                    tool_info: None,
+                    memory: Vec::new(),
+                });
+            }
+        }
+        transcript
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct ToolInfo {
+    /// The call's ID, unique within the session
+    pub id: String,
     pub name: String,
     pub detail: Option<String>,
+    /// JSON arguments, as the model sent them
+    pub arguments: String,
     pub status: ToolStatus,
 }
 
@@ -99,10 +423,20 @@ pub struct ToolInfo {
 #[allow(dead_code)]
 pub enum ToolStatus {
     Running,
-    Completed(String), // output preview
+    Completed(String), // full output
     Error(String),
 }
 
+impl ToolStatus {
+    /// Status for a finished call; failed calls report "Error: ..."
+    pub fn from_output(output: String) -> Self {
+        match output.strip_prefix("Error: ") {
+            Some(error) => ToolStatus::Error(error.to_string()),
+            None => ToolStatus::Completed(output),
+        }
+    }
+}
+
 /// UI state
 #[derive(Default)]
 pub struct UiState {
@@ -110,6 +444,26 @@ pub struct UiState {
     pub messages: Vec<ChatMessage>,
     /// Current input text
     pub input: String,
+    /// Messages sent, for Up / Down in the input
+    pub input_history: InputHistory,
+    /// Skill command names, for `/skill` completion
+    pub skills: Vec<String>,
+    /// Family member the kiosk session belongs to
+    pub chatting_as: Option<String>,
+    /// Kiosk mode: slash commands that reach outside the chat are refused
+    pub locked: bool,
+    /// Sessions whose replies are read aloud
+    pub voiced_sessions: Vec<String>,
+    /// A mic recording is being transcribed
+    pub transcribing: bool,
+    /// What the mic heard, for the chat view to put in the input or send
+    pub heard: Option<String>,
+    /// Session in hands-free tutoring
+    pub tutoring: Option<String>,
+    /// People to pick from while "Who's this?" is asked
+    pub asking_who: Vec<String>,
+    /// A reply is being read aloud
+    pub speaking: bool,
     /// Whether the agent is processing
     pub is_loading: bool,
     /// Current streaming response (being built)
@@ -124,18 +478,89 @@ pub struct UiState {
     pub sessions: Vec<SessionInfo>,
     /// Current session info
     pub current_session: Option<SessionInfo>,
+    /// Agent whose sessions can be resumed
+    pub agent_id: String,
     /// Model name
     pub model: String,
     /// Memory chunk count
     pub memory_chunks: usize,
     /// Whether embeddings are enabled
     pub has_embeddings: bool,
+    /// Whether the embedding model is still loading
+    pub embeddings_loading: bool,
+    /// Progress of the background reindex, while one runs
+    pub indexing: Option<IndexProgress>,
+    /// Interrupted session offered for restore
+    pub recovery: Option<RecoveryOffer>,
+    /// The worker crashed; shown as a reconnect banner until it's back
+    pub worker_down: Option<WorkerDown>,
     /// Session status
     pub status: Option<SessionStatus>,
+    /// Memory, usage and heartbeat health
+    pub overview: Option<StatusOverview>,
     /// Which panel is active
     pub active_panel: Panel,
     /// Scroll to bottom on next frame
     pub scroll_to_bottom: bool,
+    /// User message being edited: (index, draft)
+    pub editing: Option<(usize, String)>,
+    /// Files attached to the next message
+    pub attachments: Vec<AttachmentInfo>,
+    /// Citations looked up so far, by lowercased hash prefix
+    pub citations: HashMap<String, Citation>,
+    /// The Prompt panel's last answer to `InspectPrompt`
+    pub prompt: Option<PromptInspection>,
+}
+
+/// Earlier inputs, stepped through with Up and Down
+#[derive(Debug, Default)]
+pub struct InputHistory {
+    entries: Vec<String>,
+    /// Entry being shown; `None` while editing a new message
+    position: Option<usize>,
+    /// The new message, kept while stepping through history
+    draft: String,
+}
+
+impl InputHistory {
+    /// Remember a sent input (not twice in a row) and stop browsing
+    pub fn push(&mut self, input: &str) {
+        if self.entries.last().is_none_or(|last| last != input) {
+            self.entries.push(input.to_string());
+        }
+        self.position = None;
+    }
+
+    /// The entry before the one shown, or `None` at the oldest
+    pub fn previous(&mut self, current: &str) -> Option<String> {
+        let position = match self.position {
+            None if self.entries.is_empty() => return None,
+            None => {
+                self.draft = current.to_string();
+                self.entries.len() - 1
+            }
+            Some(0) => return None,
+            Some(position) => position - 1,
+        };
+        self.position = Some(position);
+        Some(self.entries[position].clone())
+    }
+
+    /// The entry after the one shown, then the draft; `None` if not browsing
+    pub fn next(&mut self) -> Option<String> {
+        let position = self.position?;
+        if position + 1 < self.entries.len() {
+            self.position = Some(position + 1);
+            Some(self.entries[position + 1].clone())
+        } else {
+            self.position = None;
+            Some(std::mem::take(&mut self.draft))
+        }
+    }
+
+    pub fn is_browsing(&self) -> bool {
+        self.position.is_some()
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
@@ -144,6 +569,31 @@ pub enum Panel {
     Chat,
     Sessions,
     Status,
+    Changes,
+    Prompt,
+    Logs,
+}
+
+impl Panel {
+    pub const ALL: [Panel; 6] = [
+        Self::Chat,
+        Self::Sessions,
+        Self::Status,
+        Self::Changes,
+        Self::Prompt,
+        Self::Logs,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Chat => "Chat",
+            Self::Sessions => "Sessions",
+            Self::Status => "Status",
+            Self::Changes => "Changes",
+            Self::Prompt => "Prompt",
+            Self::Logs => "Logs",
+        }
+    }
 }
 
 impl UiState {
@@ -151,47 +601,63 @@ impl UiState {
         Self::default()
     }
 
+    /// Whether the current session's replies are read aloud
+    pub fn voice_on(&self) -> bool {
+        let id = match (&self.current_session, &self.status) {
+            (Some(session), _) => &session.id,
+            (None, Some(status)) => &status.id,
+            (None, None) => return false,
+        };
+        self.voiced_sessions.contains(id)
+    }
+
     /// Process a message from the worker
     pub fn handle_worker_message(&mut self, msg: WorkerMessage) {
         match msg {
             WorkerMessage::Ready {
+                agent_id,
                 model,
                 memory_chunks,
                 has_embeddings,
+                embeddings_loading,
             } => {
+                self.agent_id = agent_id;
                 self.model = model;
                 self.memory_chunks = memory_chunks;
                 self.has_embeddings = has_embeddings;
+                self.embeddings_loading = embeddings_loading;
                 self.is_loading = false;
             }
+            WorkerMessage::EmbeddingsReady => {
+                self.has_embeddings = true;
+                self.embeddings_loading = false;
+            }
             WorkerMessage::ContentChunk(content) => {
                 self.streaming_content.push_str(&content);
                 self.scroll_to_bottom = true;
             }
             WorkerMessage::ToolCallStart {
                 name,
-                id: _,
+                id,
                 detail,
+                arguments,
             } => {
                 self.active_tools.push(ToolInfo {
+                    id,
                     name,
                     detail,
+                    arguments,
                     status: ToolStatus::Running,
                 });
             }
             WorkerMessage::ToolCallEnd {
-                name,
+                name: _,
                 output,
-                id: _,
+                id,
             } => {
                 // Update tool status
-                if let Some(tool) = self.active_tools.iter_mut().find(|t| t.name == name) {
-                    let preview = if output.len() > 100 {
-                        format!("{}...", &output[..100])
-                    } else {
-                        output
-                    };
-                    tool.status = ToolStatus::Completed(preview);
+                if let Some(tool) = self.active_tools.iter_mut().find(|t| t.id == id) {
+                    tool.status = ToolStatus::from_output(output);
                 }
             }
             WorkerMessage::ToolsPendingApproval(calls) => {
@@ -199,15 +665,30 @@ impl UiState {
                 self.is_loading = false;
             }
             WorkerMessage::Done => {
+                // The turn's tool calls stay in the transcript, above the reply
+                self.finish_tools();
                 // Finalize streaming content as assistant message
                 if !self.streaming_content.is_empty() {
                     self.messages.push(ChatMessage {
                         role: MessageRole::Assistant,
                         content: std::mem::take(&mut self.streaming_content),
                         tool_info: None,
+                        memory: Vec::new(),
                     });
                 }
-                self.active_tools.clear();
+                self.is_loading = false;
+                self.scroll_to_bottom = true;
+            }
+            WorkerMessage::Interrupted => {
+                self.finish_tools();
+                let partial = std::mem::take(&mut self.streaming_content);
+                self.messages.push(ChatMessage {
+                    role: MessageRole::Assistant,
+                    content: interrupted_reply(&partial),
+                    tool_info: None,
+                    memory: Vec::new(),
+                });
+                self.pending_approval = None;
                 self.is_loading = false;
                 self.scroll_to_bottom = true;
             }
@@ -219,6 +700,17 @@ impl UiState {
             WorkerMessage::Status(status) => {
                 self.status = Some(status);
             }
+            WorkerMessage::Overview(overview) => {
+                self.memory_chunks = overview.memory_chunks;
+                self.overview = Some(overview);
+            }
+            WorkerMessage::Citation { hash, card } => {
+                let citation = card.map_or(Citation::Missing, Citation::Found);
+                self.citations.insert(hash, citation);
+            }
+            WorkerMessage::Prompt(inspection) => {
+                self.prompt = Some(inspection);
+            }
             WorkerMessage::Sessions(sessions) => {
                 self.sessions = sessions;
             }
@@ -227,17 +719,138 @@ impl UiState {
                     id,
                     message_count,
                     created_at: chrono::Utc::now(),
-                    file_size: 0,
+                    ..SessionInfo::default()
                 });
                 // Clear chat on session change
                 self.messages.clear();
                 self.streaming_content.clear();
             }
+            WorkerMessage::ChattingAs(name) => {
+                self.chatting_as = name;
+            }
+            WorkerMessage::VoicedSessions(sessions) => {
+                self.voiced_sessions = sessions;
+            }
+            WorkerMessage::Heard(text) => {
+                self.transcribing = false;
+                if !text.is_empty() {
+                    self.heard = Some(text);
+                }
+            }
+            WorkerMessage::AskWho(names) => self.asking_who = names,
+            WorkerMessage::Tutoring(session) => self.tutoring = session,
+            WorkerMessage::Speaking(speaking) => self.speaking = speaking,
+            WorkerMessage::History(messages) => {
+                self.messages = messages;
+                self.scroll_to_bottom = true;
+            }
+            WorkerMessage::Attachments(attachments) => {
+                self.attachments = attachments;
+            }
+            WorkerMessage::Skills(skills) => {
+                self.skills = skills;
+            }
             WorkerMessage::SystemMessage(text) => {
                 self.messages.push(ChatMessage {
                     role: MessageRole::System,
                     content: text,
                     tool_info: None,
+                    memory: Vec::new(),
+                });
+                self.scroll_to_bottom = true;
+            }
+            WorkerMessage::MemoryResults { query, hits } => {
+                // The cards' citation chips need no lookup
+                for hit in hits.iter().filter(|hit| !hit.hash.is_empty()) {
+                    self.citations
+                        .insert(hit.hash.clone(), Citation::Found(hit.card.clone()));
+                }
+                self.messages.push(ChatMessage {
+                    role: MessageRole::Memory,
+                    content: query,
+                    tool_info: None,
+                    memory: hits,
+                });
+                self.scroll_to_bottom = true;
+            }
+            WorkerMessage::IndexProgress(progress) => {
+                let summary = match progress {
+                    IndexProgress::Running { .. } => {
+                        self.indexing = Some(progress);
+                        return;
+                    }
+                    IndexProgress::Finished {
+                        files_updated,
+                        chunks_indexed,
+                        chunks_embedded,
+                    } => {
+                        self.memory_chunks = chunks_indexed;
+                        // Line ranges may have moved
+                        self.citations.clear();
+                        format!(
+                            "Memory reindexed: {} files updated, {} chunks ({} newly embedded)",
+                            files_updated, chunks_indexed, chunks_embedded
+                        )
+                    }
+                    IndexProgress::Cancelled => "Reindex cancelled".to_string(),
+                    IndexProgress::Failed(e) => format!("Reindex failed: {}", e),
+                };
+                self.indexing = None;
+                self.messages.push(ChatMessage {
+                    role: MessageRole::System,
+                    content: summary,
+                    tool_info: None,
+                    memory: Vec::new(),
+                });
+                self.scroll_to_bottom = true;
+            }
+            WorkerMessage::RecoveryAvailable(offer) => {
+                self.recovery = Some(offer);
+            }
+            WorkerMessage::Restored { message, partial } => {
+                self.recovery = None;
+                self.add_user_message(message);
+                self.messages.push(ChatMessage {
+                    role: MessageRole::Assistant,
+                    content: interrupted_reply(&partial),
+                    tool_info: None,
+                    memory: Vec::new(),
+                });
+            }
+            WorkerMessage::Stopped => {}
+            WorkerMessage::WorkerDied {
+                reason,
+                retry_in,
+                dropped,
+            } => {
+                // The turn in flight died with the worker
+                self.active_tools.clear();
+                self.streaming_content.clear();
+                self.pending_approval = None;
+                self.is_loading = false;
+                self.worker_down = Some(WorkerDown {
+                    reason,
+                    retry_at: Instant::now() + retry_in,
+                });
+                if dropped > 0 {
+                    self.error = Some(format!(
+                        "The assistant crashed twice on your last message, so it wasn't sent again ({} dropped)",
+                        dropped
+                    ));
+                }
+            }
+            WorkerMessage::Reconnected { resent } => {
+                self.worker_down = None;
+                let mut text = "Reconnected to the assistant.".to_string();
+                if resent > 0 {
+                    self.is_loading = true;
+                    text.push_str(" Sending your last message again.");
+                }
+                self.messages.push(ChatMessage {
+                    role: MessageRole::System,
+                    content: text,
+                    tool_info: None,
+                    memory: Vec::new(),
                 });
                 self.scroll_to_bottom = true;
             }
@@ -245,17 +858,58 @@ impl UiState {
     }
 
     /// Add a user message
+    /// Move the turn's tool calls into the transcript
+    fn finish_tools(&mut self) {
+        for tool in self.active_tools.drain(..) {
+            self.messages.push(ChatMessage {
+                role: MessageRole::Tool,
+                content: String::new(),
+                tool_info: Some(tool),
+                memory: Vec::new(),
+            });
+        }
+    }
+
     pub fn add_user_message(&mut self, content: String) {
         self.messages.push(ChatMessage {
             role: MessageRole::User,
             content,
             tool_info: None,
+            memory: Vec::new(),
         });
         self.scroll_to_bottom = true;
     }
 
+    /// Remove the user message at or before `index` and everything after it.
+    /// Returns its text and how many later user messages share that text, so
+    /// the worker can find the same message in the session.
+    pub fn rewind_to(&mut self, index: usize) -> Option<(String, usize)> {
+        let start = self
+            .messages
+            .get(..=index)?
+            .iter()
+            .rposition(|m| m.role == MessageRole::User)?;
+        let original = self.messages[start].content.clone();
+        let occurrence = self.messages[start + 1..]
+            .iter()
+            .filter(|m| m.role == MessageRole::User && m.content == original)
+            .count();
+        self.messages.truncate(start);
+        self.editing = None;
+        Some((original, occurrence))
+    }
+
     /// Clear error
     pub fn clear_error(&mut self) {
         self.error = None;
     }
 }
+
+/// A partial reply as it's kept in the session after being cut short
+fn interrupted_reply(partial: &str) -> String {
+    if partial.trim().is_empty() {
+        INTERRUPTED_MARKER.to_string()
+    } else {
+        format!("{}\n\n{}", partial.trim_end(), INTERRUPTED_MARKER)
+    }
+}
@@ -0,0 +1,134 @@
+//! Changes view - the agent's file edits waiting for review
+
+use eframe::egui::{Color32, RichText, ScrollArea, Ui};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::config::Config;
+use crate::desktop::state::UiMessage;
+use crate::memory::{ChangeQueue, PendingChange};
+
+/// How often the queue is reread (changes are staged by other processes
+/// too, e.g. the daemon's chat bridges)
+const RELOAD_INTERVAL: Duration = Duration::from_secs(2);
+
+pub struct ChangesView {
+    queue: Option<ChangeQueue>,
+    changes: Vec<PendingChange>,
+    loaded_at: Option<Instant>,
+}
+
+impl ChangesView {
+    pub fn new() -> Self {
+        let queue =
+            Config::load().and_then(|config| ChangeQueue::for_workspace(&config.workspace_path()));
+        let queue = match queue {
+            Ok(queue) => Some(queue),
+            Err(e) => {
+                warn!("Change review unavailable: {}", e);
+                None
+            }
+        };
+        Self {
+            queue,
+            changes: Vec::new(),
+            loaded_at: None,
+        }
+    }
+
+    /// Reread the queue if it's stale
+    fn refresh(&mut self) {
+        let Some(ref queue) = self.queue else {
+            return;
+        };
+        if self
+            .loaded_at
+            .is_some_and(|at| at.elapsed() < RELOAD_INTERVAL)
+        {
+            return;
+        }
+        match queue.pending() {
+            Ok(changes) => self.changes = changes,
+            Err(e) => warn!("Failed to load pending changes: {}", e),
+        }
+        self.loaded_at = Some(Instant::now());
+    }
+
+    /// Approving and rejecting go through the worker, which writes and
+    /// indexes the file
+    pub fn show(&mut self, ui: &mut Ui) -> Option<UiMessage> {
+        self.refresh();
+
+        ui.heading("Changes");
+        ui.add_space(10.0);
+        if self.queue.is_none() {
+            ui.label("The change queue couldn't be opened; see the logs.");
+            return None;
+        }
+        if self.changes.is_empty() {
+            ui.label(RichText::new("No changes waiting.").color(Color32::GRAY));
+            ui.label(
+                RichText::new("With memory.staging on, the agent's file edits wait here.")
+                    .color(Color32::GRAY),
+            );
+            return None;
+        }
+
+        let mut decision = None;
+        ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for change in &self.changes {
+                    ui.group(|ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(
+                                RichText::new(format!("#{} {}", change.id, change.path)).strong(),
+                            );
+                            ui.label(
+                                RichText::new(format!(
+                                    "{} · {} · {}",
+                                    change.tool,
+                                    change.line_counts(),
+                                    change.created.get(..16).unwrap_or(&change.created)
+                                ))
+                                .small()
+                                .color(Color32::GRAY),
+                            );
+                        });
+                        for line in change.diff.lines().skip(2) {
+                            ui.label(diff_line(line));
+                        }
+                        ui.horizontal(|ui| {
+                            if ui.button("Approve").clicked() {
+                                decision = Some(UiMessage::ApproveChange(change.id));
+                            }
+                            if ui.button("Reject").clicked() {
+                                decision = Some(UiMessage::RejectChange(change.id));
+                            }
+                        });
+                    });
+                    ui.add_space(5.0);
+                }
+            });
+
+        if decision.is_some() {
+            // Reread once the worker has applied it
+            self.loaded_at = None;
+        }
+        decision
+    }
+}
+
+/// A line of a unified diff, coloured by whether it's added or removed
+pub(crate) fn diff_line(line: &str) -> RichText {
+    let text = RichText::new(line).monospace();
+    if line.starts_with('+') {
+        text.color(Color32::from_rgb(46, 204, 113))
+    } else if line.starts_with('-') {
+        text.color(Color32::from_rgb(231, 76, 60))
+    } else if line.starts_with("@@") {
+        text.color(Color32::GRAY)
+    } else {
+        text
+    }
+}
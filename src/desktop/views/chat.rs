@@ -1,17 +1,142 @@
 //! Chat view - message display and input
 
-use eframe::egui::{self, Color32, RichText, ScrollArea, TextEdit, Ui};
+use std::collections::HashMap;
+use std::path::PathBuf;
 
-use crate::desktop::state::{ChatMessage, MessageRole, Panel, ToolStatus, UiMessage, UiState};
+use eframe::egui::collapsing_header::CollapsingState;
+use eframe::egui::{self, Color32, Grid, PopupCloseBehavior, RichText, ScrollArea, TextEdit, Ui};
+
+use super::changes::diff_line;
+use crate::agent::extract_tool_preview;
+use crate::desktop::display::DisplaySettings;
+use crate::desktop::mic::Mic;
+use crate::desktop::speaker_id::WHO_IS_THIS;
+use crate::desktop::state::{
+    ChatMessage, Citation, MemoryHit, MessageRole, Panel, ToolInfo, ToolStatus, UiMessage, UiState,
+};
+use crate::memory::{parse_citation, IndexPhase, IndexProgress, CITATION_PREFIX};
+
+/// Slash commands offered while typing, with what they do
+const COMMANDS: &[(&str, &str)] = &[
+    ("/new", "Start a new session"),
+    ("/model", "Show or set the current model"),
+    ("/compact", "Compact session history"),
+    ("/memory", "Search memory, with citations"),
+    ("/reindex", "Reindex memory files"),
+    ("/save", "Save the session"),
+    ("/status", "Show session status"),
+    ("/dryrun", "Log changing tool calls instead of running them"),
+    ("/sessions", "Show saved sessions"),
+    ("/resume", "Resume a session by ID"),
+    ("/skill", "Run a skill"),
+    ("/voice", "Read replies aloud"),
+    ("/tutor", "Hands-free tutoring"),
+    ("/enroll", "Record someone's voice"),
+    ("/help", "Show all commands"),
+];
+
+/// Commands kiosk mode allows
+const KIOSK_COMMANDS: &[&str] = &["/new", "/skill", "/voice", "/tutor", "/help"];
+
+/// Rows the input grows to before it scrolls
+const MAX_INPUT_ROWS: usize = 6;
 
 pub struct ChatView;
 
+/// Edit / regenerate action picked on a message, by index into `messages`
+enum MessageAction {
+    StartEdit(usize, String),
+    CancelEdit,
+    SendEdit(usize, String),
+    Regenerate(usize),
+}
+
 impl ChatView {
-    pub fn show(ui: &mut Ui, state: &mut UiState) -> Option<UiMessage> {
+    pub fn show(ui: &mut Ui, state: &mut UiState, mic: &mut Mic) -> Option<UiMessage> {
         let mut message_to_send = None;
 
-        // Main chat area
-        let available_height = ui.available_height() - 60.0; // Reserve space for input
+        // Files dropped onto the window are attached to the next message
+        let dropped: Vec<PathBuf> = ui.ctx().input(|i| {
+            i.raw
+                .dropped_files
+                .iter()
+                .filter_map(|f| f.path.clone())
+                .collect()
+        });
+        if !dropped.is_empty() {
+            message_to_send = Some(UiMessage::AttachFiles(dropped));
+        }
+
+        // The worker crashed; it's restarted in the same session
+        if let Some(down) = &state.worker_down {
+            let wait = down
+                .retry_at
+                .saturating_duration_since(std::time::Instant::now())
+                .as_secs();
+            let retry = if wait > 0 {
+                format!("Reconnecting in {}s.", wait)
+            } else {
+                "Reconnecting...".to_string()
+            };
+            egui::Frame::none()
+                .fill(Color32::from_rgb(80, 24, 20))
+                .rounding(4.0)
+                .inner_margin(8.0)
+                .show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(
+                            RichText::new(format!(
+                                "The assistant stopped unexpectedly ({}). {}",
+                                down.reason, retry
+                            ))
+                            .color(Color32::from_rgb(255, 205, 200)),
+                        );
+                        if wait > 0 && ui.button("Reconnect now").clicked() {
+                            message_to_send = Some(UiMessage::Reconnect);
+                        }
+                    });
+                });
+            ui.add_space(5.0);
+        }
+
+        // Offer back a reply cut short when the app last stopped
+        if let Some(offer) = &state.recovery {
+            let preview: String = offer
+                .message
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .chars()
+                .take(60)
+                .collect();
+            let mut choice = None;
+            ui.horizontal(|ui| {
+                ui.label(
+                    RichText::new(format!("The app closed while replying to \"{}\".", preview))
+                        .color(Color32::GRAY),
+                );
+                if ui.button("Restore").clicked() {
+                    choice = Some(UiMessage::RestoreSession);
+                }
+                if ui.button("Dismiss").clicked() {
+                    choice = Some(UiMessage::DismissRecovery);
+                }
+            });
+            if choice.is_some() {
+                state.recovery = None;
+                message_to_send = choice;
+            }
+            ui.separator();
+        }
+
+        // Main chat area; the input grows with its lines and completions
+        let completions = completions(&state.input, &state.skills, state.locked);
+        let input_rows = state.input.lines().count().clamp(1, MAX_INPUT_ROWS);
+        let mut reserved = 60.0 + (input_rows - 1) as f32 * 18.0;
+        if !completions.is_empty() {
+            reserved += 28.0;
+        }
+        let available_height = ui.available_height() - reserved;
 
         // Messages scroll area
         ScrollArea::vertical()
@@ -22,11 +147,32 @@ impl ChatView {
             .show(ui, |ui| {
                 ui.set_min_width(ui.available_width());
 
-                // Show messages
-                for msg in &state.messages {
-                    Self::render_message(ui, msg);
+                // Show messages, with edit / regenerate actions when idle
+                let mut editing = state.editing.take();
+                let mut action = None;
+                let mut lookup = None;
+                for (index, msg) in state.messages.iter().enumerate() {
+                    match editing.as_mut() {
+                        Some((i, draft)) if *i == index => {
+                            action = Self::render_editor(ui, index, draft).or(action);
+                        }
+                        _ => {
+                            lookup = Self::render_message(ui, msg, &state.citations).or(lookup);
+                            if !state.is_loading && editing.is_none() {
+                                action = Self::render_actions(ui, index, msg).or(action);
+                            }
+                        }
+                    }
                     ui.add_space(8.0);
                 }
+                state.editing = editing;
+                if let Some(action) = action {
+                    message_to_send = Self::apply_action(state, action);
+                }
+                if let Some(hash) = lookup.filter(|_| message_to_send.is_none()) {
+                    state.citations.insert(hash.clone(), Citation::Loading);
+                    message_to_send = Some(UiMessage::LookupCitation(hash));
+                }
 
                 // Show streaming content if any
                 if !state.streaming_content.is_empty() {
@@ -43,22 +189,16 @@ impl ChatView {
 
                 // Show active tools
                 for tool in &state.active_tools {
-                    ui.horizontal(|ui| match &tool.status {
-                        ToolStatus::Running => {
-                            ui.spinner();
-                            if let Some(ref detail) = tool.detail {
-                                ui.label(format!("Running: {}: {}", tool.name, detail));
-                            } else {
-                                ui.label(format!("Running: {}", tool.name));
-                            }
-                        }
-                        ToolStatus::Completed(preview) => {
-                            ui.label(RichText::new("Done").color(Color32::from_rgb(46, 204, 113)));
-                            ui.label(format!("{}: {}", tool.name, preview));
-                        }
-                        ToolStatus::Error(err) => {
-                            ui.label(RichText::new("Error").color(Color32::from_rgb(231, 76, 60)));
-                            ui.label(format!("{}: {}", tool.name, err));
+                    if tool.status != ToolStatus::Running {
+                        render_tool(ui, tool);
+                        continue;
+                    }
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        if let Some(ref detail) = tool.detail {
+                            ui.label(format!("Running: {}: {}", tool.name, detail));
+                        } else {
+                            ui.label(format!("Running: {}", tool.name));
                         }
                     });
                 }
@@ -71,15 +211,29 @@ impl ChatView {
                         ui.label(RichText::new("Tools pending approval:").strong());
                         for tool in &tools {
                             ui.label(format!("  - {}", tool.name));
+                            if let Some(preview) = extract_tool_preview(&tool.name, &tool.arguments)
+                            {
+                                ScrollArea::vertical()
+                                    .id_salt(&tool.id)
+                                    .max_height(240.0)
+                                    .show(ui, |ui| {
+                                        for line in preview.lines() {
+                                            ui.label(diff_line(line));
+                                        }
+                                    });
+                            }
                         }
                         ui.horizontal(|ui| {
+                            // A turn in the daemon carries on once answered
                             if ui.button("Approve").clicked() {
                                 message_to_send = Some(UiMessage::ApproveTools(tools.clone()));
                                 state.pending_approval = None;
+                                state.is_loading = true;
                             }
                             if ui.button("Deny").clicked() {
                                 message_to_send = Some(UiMessage::DenyTools);
                                 state.pending_approval = None;
+                                state.is_loading = true;
                             }
                         });
                     });
@@ -107,35 +261,171 @@ impl ChatView {
 
         ui.add_space(10.0);
 
+        // Pending attachments
+        if ui.ctx().input(|i| !i.raw.hovered_files.is_empty()) {
+            ui.label(RichText::new("Drop files to attach them").color(Color32::GRAY));
+        }
+        if !state.attachments.is_empty() {
+            ui.horizontal_wrapped(|ui| {
+                for (index, attachment) in state.attachments.iter().enumerate() {
+                    ui.label(RichText::new(&attachment.label).small());
+                    if attachment.is_text && ui.small_button("Save to memory").clicked() {
+                        message_to_send = Some(UiMessage::SaveAttachment(index));
+                    }
+                    if ui.small_button("Remove").clicked() {
+                        message_to_send = Some(UiMessage::RemoveAttachment(index));
+                    }
+                    ui.add_space(8.0);
+                }
+            });
+        }
+
+        let input_id = egui::Id::new("chat_input");
+
+        // What the mic heard goes out right away, or into the input to check
+        if let Some(text) = state.heard.take() {
+            if mic.sends() && state.input.trim().is_empty() && !state.is_loading {
+                message_to_send = Self::submit(text, state);
+            } else {
+                if !state.input.is_empty() && !state.input.ends_with(char::is_whitespace) {
+                    state.input.push(' ');
+                }
+                state.input.push_str(&text);
+                move_cursor_to_end(ui.ctx(), input_id, &state.input);
+                ui.memory_mut(|m| m.request_focus(input_id));
+            }
+        }
+        if let Some(message) = mic.push_to_talk(ui, input_id, state) {
+            message_to_send = Some(message);
+        }
+
+        // Slash command completions; Tab takes the first
+        let focused = ui.memory(|m| m.has_focus(input_id));
+        let mut completed = None;
+        if !completions.is_empty() {
+            ui.horizontal_wrapped(|ui| {
+                for (text, description) in &completions {
+                    if ui
+                        .small_button(text.trim_end())
+                        .on_hover_text(*description)
+                        .clicked()
+                    {
+                        completed = Some(text.clone());
+                    }
+                }
+                ui.label(
+                    RichText::new("Tab to complete")
+                        .small()
+                        .color(Color32::GRAY),
+                );
+            });
+            if focused && ui.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Tab)) {
+                completed = completions.first().map(|(text, _)| text.clone());
+            }
+        }
+
+        // Up / Down step through sent messages while the input is one line
+        if focused && completed.is_none() {
+            let one_line = !state.input.contains('\n');
+            if one_line
+                && ui.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp))
+            {
+                completed = state.input_history.previous(&state.input);
+            } else if state.input_history.is_browsing()
+                && ui.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown))
+            {
+                completed = state.input_history.next();
+            }
+        }
+        if let Some(text) = completed {
+            state.input = text;
+            move_cursor_to_end(ui.ctx(), input_id, &state.input);
+            ui.memory_mut(|m| m.request_focus(input_id));
+        }
+
         // Input area
+        mic.show_status(ui, state);
+        if !state.asking_who.is_empty() {
+            ui.horizontal_wrapped(|ui| {
+                ui.label(RichText::new(WHO_IS_THIS).strong());
+                for name in &state.asking_who {
+                    if ui.button(name).clicked() {
+                        message_to_send = Some(UiMessage::ThisIs(name.clone()));
+                    }
+                }
+            });
+        }
+        let input_buttons_width = if mic.is_recording() { 260.0 } else { 180.0 };
         ui.horizontal(|ui| {
-            let input_response = ui.add_sized(
-                [ui.available_width() - 70.0, 35.0],
-                TextEdit::singleline(&mut state.input)
-                    .hint_text("Type a message or /help for commands...")
-                    .frame(true),
-            );
+            let input_response = ScrollArea::vertical()
+                .id_salt("chat_input_scroll")
+                .max_height(18.0 * MAX_INPUT_ROWS as f32 + 12.0)
+                .show(ui, |ui| {
+                    ui.add(
+                        TextEdit::multiline(&mut state.input)
+                            .id(input_id)
+                            .hint_text(
+                                "Type a message or /help for commands (Shift+Enter for a new line)",
+                            )
+                            .desired_rows(1)
+                            .desired_width(ui.available_width() - input_buttons_width)
+                            // Plain Enter sends
+                            .return_key(egui::KeyboardShortcut::new(
+                                egui::Modifiers::SHIFT,
+                                egui::Key::Enter,
+                            ))
+                            .frame(true),
+                    )
+                })
+                .inner;
+
+            // Typing over a reply being read aloud stops it
+            let voice_on = state.voice_on();
+            if input_response.changed() && voice_on {
+                message_to_send = Some(UiMessage::StopSpeaking);
+            }
+            if let Some(message) = mic.show_button(ui, state) {
+                message_to_send = Some(message);
+            }
+            let speaker = if voice_on { "🔊" } else { "🔇" };
+            if ui
+                .selectable_label(voice_on, speaker)
+                .on_hover_text("Read this session's replies aloud")
+                .clicked()
+            {
+                let arg = if voice_on { "off" } else { "on" };
+                message_to_send = Some(UiMessage::SetVoice(Some(arg.to_string())));
+            }
+            let tutoring = state.tutoring.is_some();
+            if ui
+                .selectable_label(tutoring, "🎓")
+                .on_hover_text("Hands-free tutoring: talk, and hear the replies")
+                .clicked()
+            {
+                message_to_send = Some(UiMessage::Tutor(!tutoring));
+            }
+
+            // While a response is in progress the button stops it instead
+            if state.is_loading {
+                let escape_pressed = ui.input(|i| i.key_pressed(egui::Key::Escape));
+                if ui.button("Stop").clicked() || escape_pressed {
+                    message_to_send = Some(UiMessage::CancelGeneration);
+                }
+                return;
+            }
 
-            let can_send = !state.input.trim().is_empty() && !state.is_loading;
+            let can_send = !state.input.trim().is_empty();
             let send_clicked = ui
                 .add_enabled(can_send, egui::Button::new("Send"))
                 .clicked();
 
             // Send on Enter or button click
-            let enter_pressed =
-                input_response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            let enter_pressed = input_response.has_focus()
+                && ui.input(|i| i.key_pressed(egui::Key::Enter) && !i.modifiers.shift);
 
             if (send_clicked || enter_pressed) && can_send {
-                let content = state.input.trim().to_string();
-                state.input.clear();
-
-                if let Some(cmd) = Self::parse_slash_command(&content, state) {
-                    message_to_send = Some(cmd);
-                } else {
-                    state.add_user_message(content.clone());
-                    state.is_loading = true;
-                    message_to_send = Some(UiMessage::Chat(content));
-                }
+                let content = std::mem::take(&mut state.input);
+                message_to_send = Self::submit(content, state);
             }
         });
 
@@ -147,9 +437,46 @@ impl ChatView {
             });
         }
 
+        // Background reindex; chat carries on while it runs
+        if let Some(IndexProgress::Running {
+            phase,
+            done,
+            total,
+            paused,
+        }) = &state.indexing
+        {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                let what = match phase {
+                    IndexPhase::Files => "files",
+                    IndexPhase::Embeddings => "chunks embedded",
+                };
+                let mut text = format!("Indexing memory: {}/{} {}", done, total, what);
+                if *paused {
+                    text.push_str(" (paused for chat)");
+                }
+                ui.label(RichText::new(text).color(Color32::GRAY).small());
+                if ui.small_button("Cancel").clicked() {
+                    message_to_send = Some(UiMessage::CancelIndexing);
+                }
+            });
+        }
+
         message_to_send
     }
 
+    /// Send `content` as typed: a slash command, or a chat message
+    fn submit(content: String, state: &mut UiState) -> Option<UiMessage> {
+        let content = content.trim().to_string();
+        state.input_history.push(&content);
+        if let Some(cmd) = Self::parse_slash_command(&content, state) {
+            return Some(cmd);
+        }
+        state.add_user_message(content.clone());
+        state.is_loading = true;
+        Some(UiMessage::Chat(content))
+    }
+
     /// Parse a slash command from user input.
     /// Returns `Some(UiMessage)` if a command was recognized, `None` if it should be sent as chat.
     fn parse_slash_command(input: &str, state: &mut UiState) -> Option<UiMessage> {
@@ -161,6 +488,18 @@ impl ChatView {
         let cmd = parts[0];
         let arg = parts.get(1).map(|s| s.trim()).unwrap_or("");
 
+        // Other sessions, models and the index stay out of reach in kiosk mode
+        if state.locked && !KIOSK_COMMANDS.contains(&cmd) {
+            state.messages.push(ChatMessage {
+                role: MessageRole::System,
+                content: format!("{} isn't available in kiosk mode.", cmd),
+                tool_info: None,
+                memory: Vec::new(),
+            });
+            state.scroll_to_bottom = true;
+            return None;
+        }
+
         match cmd {
             "/new" => Some(UiMessage::NewSession),
             "/model" => {
@@ -170,6 +509,7 @@ impl ChatView {
                         role: MessageRole::System,
                         content: format!("Current model: {}", state.model),
                         tool_info: None,
+                        memory: Vec::new(),
                     });
                     state.scroll_to_bottom = true;
                     None // No message to send to worker
@@ -184,6 +524,7 @@ impl ChatView {
                         role: MessageRole::System,
                         content: "Usage: /memory <query>".to_string(),
                         tool_info: None,
+                        memory: Vec::new(),
                     });
                     state.scroll_to_bottom = true;
                     None
@@ -191,6 +532,22 @@ impl ChatView {
                     Some(UiMessage::SearchMemory(arg.to_string()))
                 }
             }
+            "/reindex" => Some(UiMessage::Reindex),
+            "/dryrun" => match arg {
+                "" => Some(UiMessage::SetDryRun(None)),
+                "on" => Some(UiMessage::SetDryRun(Some(true))),
+                "off" => Some(UiMessage::SetDryRun(Some(false))),
+                _ => {
+                    state.messages.push(ChatMessage {
+                        role: MessageRole::System,
+                        content: "Usage: /dryrun [on|off]".to_string(),
+                        tool_info: None,
+                        memory: Vec::new(),
+                    });
+                    state.scroll_to_bottom = true;
+                    None
+                }
+            },
             "/save" => Some(UiMessage::Save),
             "/help" => Some(UiMessage::ShowHelp),
             "/status" => Some(UiMessage::ShowStatus),
@@ -200,6 +557,7 @@ impl ChatView {
                         role: MessageRole::System,
                         content: "Usage: /resume <session-id>".to_string(),
                         tool_info: None,
+                        memory: Vec::new(),
                     });
                     state.scroll_to_bottom = true;
                     None
@@ -211,6 +569,52 @@ impl ChatView {
                 state.active_panel = Panel::Sessions;
                 Some(UiMessage::RefreshSessions)
             }
+            "/skill" => {
+                let (name, args) = arg.split_once(char::is_whitespace).unwrap_or((arg, ""));
+                if name.is_empty() {
+                    let skills = if state.skills.is_empty() {
+                        "none found".to_string()
+                    } else {
+                        state.skills.join(", ")
+                    };
+                    state.messages.push(ChatMessage {
+                        role: MessageRole::System,
+                        content: format!("Usage: /skill <name> [request]\nSkills: {}", skills),
+                        tool_info: None,
+                        memory: Vec::new(),
+                    });
+                    state.scroll_to_bottom = true;
+                    None
+                } else {
+                    state.add_user_message(input.to_string());
+                    state.is_loading = true;
+                    Some(UiMessage::InvokeSkill {
+                        name: name.to_string(),
+                        args: args.trim().to_string(),
+                    })
+                }
+            }
+            "/voice" => Some(UiMessage::SetVoice(
+                Some(arg.to_string()).filter(|arg| !arg.is_empty()),
+            )),
+            "/enroll" => Some(UiMessage::Enroll(
+                Some(arg.to_string()).filter(|arg| !arg.is_empty()),
+            )),
+            "/tutor" => match arg {
+                "" => Some(UiMessage::Tutor(state.tutoring.is_none())),
+                "on" => Some(UiMessage::Tutor(true)),
+                "off" => Some(UiMessage::Tutor(false)),
+                _ => {
+                    state.messages.push(ChatMessage {
+                        role: MessageRole::System,
+                        content: "Usage: /tutor [on|off]".to_string(),
+                        tool_info: None,
+                        memory: Vec::new(),
+                    });
+                    state.scroll_to_bottom = true;
+                    None
+                }
+            },
             _ => {
                 state.messages.push(ChatMessage {
                     role: MessageRole::System,
@@ -219,6 +623,7 @@ impl ChatView {
                         cmd
                     ),
                     tool_info: None,
+                    memory: Vec::new(),
                 });
                 state.scroll_to_bottom = true;
                 None
@@ -226,11 +631,88 @@ impl ChatView {
         }
     }
 
-    fn render_message(ui: &mut Ui, msg: &ChatMessage) {
+    /// Edit button under user messages, regenerate under assistant replies
+    fn render_actions(ui: &mut Ui, index: usize, msg: &ChatMessage) -> Option<MessageAction> {
+        let (label, action) = match msg.role {
+            MessageRole::User => ("Edit", MessageAction::StartEdit(index, msg.content.clone())),
+            MessageRole::Assistant => ("Regenerate", MessageAction::Regenerate(index)),
+            MessageRole::System | MessageRole::Tool | MessageRole::Memory => return None,
+        };
+        ui.small_button(label).clicked().then_some(action)
+    }
+
+    /// Inline editor replacing a user message
+    fn render_editor(ui: &mut Ui, index: usize, draft: &mut String) -> Option<MessageAction> {
+        ui.add(
+            TextEdit::multiline(draft)
+                .desired_rows(2)
+                .desired_width(f32::INFINITY),
+        );
+        let mut action = None;
+        ui.horizontal(|ui| {
+            let can_send = !draft.trim().is_empty();
+            if ui
+                .add_enabled(can_send, egui::Button::new("Save & send"))
+                .clicked()
+            {
+                action = Some(MessageAction::SendEdit(index, draft.trim().to_string()));
+            }
+            if ui.button("Cancel").clicked() {
+                action = Some(MessageAction::CancelEdit);
+            }
+        });
+        action
+    }
+
+    /// Rewind the transcript for an edit / regenerate and build the message
+    /// that makes the worker do the same to the session
+    fn apply_action(state: &mut UiState, action: MessageAction) -> Option<UiMessage> {
+        let (index, content) = match action {
+            MessageAction::StartEdit(index, content) => {
+                state.editing = Some((index, content));
+                return None;
+            }
+            MessageAction::CancelEdit => {
+                state.editing = None;
+                return None;
+            }
+            MessageAction::SendEdit(index, content) => (index, Some(content)),
+            MessageAction::Regenerate(index) => (index, None),
+        };
+
+        let (original, occurrence) = state.rewind_to(index)?;
+        let content = content.unwrap_or_else(|| original.clone());
+        state.add_user_message(content.clone());
+        state.clear_error();
+        state.is_loading = true;
+        Some(UiMessage::Resend {
+            original,
+            occurrence,
+            content,
+        })
+    }
+
+    /// Returns a citation hash to look up, when one is hovered for the first
+    /// time
+    fn render_message(
+        ui: &mut Ui,
+        msg: &ChatMessage,
+        citations: &HashMap<String, Citation>,
+    ) -> Option<String> {
         let (label, color) = match msg.role {
             MessageRole::User => ("You", Color32::from_rgb(52, 152, 219)),
             MessageRole::Assistant => ("Assistant", Color32::from_rgb(100, 149, 237)),
             MessageRole::System => ("System", Color32::from_rgb(149, 165, 166)),
+            MessageRole::Tool => {
+                if let Some(ref tool) = msg.tool_info {
+                    render_tool(ui, tool);
+                }
+                return None;
+            }
+            MessageRole::Memory => {
+                render_memory(ui, &msg.content, &msg.memory, citations);
+                return None;
+            }
         };
 
         ui.horizontal(|ui| {
@@ -238,7 +720,12 @@ impl ChatView {
         });
 
         // Render content with basic markdown-like formatting
-        ui.label(&msg.content);
+        let mut lookup = None;
+        if msg.role == MessageRole::Assistant && msg.content.contains(CITATION_PREFIX) {
+            lookup = render_cited(ui, &msg.content, citations);
+        } else {
+            ui.label(&msg.content);
+        }
 
         // Show tool info if any
         if let Some(ref tool_info) = msg.tool_info {
@@ -250,17 +737,264 @@ impl ChatView {
                 );
             });
         }
+        lookup
+    }
+}
+
+/// A finished tool call on one line, expanding to its full arguments and
+/// output
+fn render_tool(ui: &mut Ui, tool: &ToolInfo) {
+    let (status, color, output) = match tool.status {
+        ToolStatus::Running => ("Running", Color32::GRAY, ""),
+        ToolStatus::Completed(ref output) => {
+            ("Done", Color32::from_rgb(46, 204, 113), output.as_str())
+        }
+        ToolStatus::Error(ref error) => ("Error", Color32::from_rgb(231, 76, 60), error.as_str()),
+    };
+    let summary = match tool.detail {
+        Some(ref detail) => format!("{}: {}", tool.name, detail),
+        None => {
+            let preview: String = output
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .chars()
+                .take(100)
+                .collect();
+            format!("{}: {}", tool.name, preview)
+        }
+    };
+
+    let id = ui.make_persistent_id(("tool_call", &tool.id));
+    CollapsingState::load_with_default_open(ui.ctx(), id, false)
+        .show_header(ui, |ui| {
+            ui.label(RichText::new(status).color(color));
+            ui.label(summary);
+        })
+        .body(|ui| {
+            let arguments = serde_json::from_str::<serde_json::Value>(&tool.arguments)
+                .and_then(|value| serde_json::to_string_pretty(&value))
+                .unwrap_or_else(|_| tool.arguments.clone());
+            tool_section(ui, id.with("arguments"), "Arguments", &arguments);
+            tool_section(ui, id.with("output"), "Output", output);
+        });
+}
+
+/// `/memory` results as cards: the chunk's citation chip, where it is,
+/// how far to trust it and the start of its text
+fn render_memory(
+    ui: &mut Ui,
+    query: &str,
+    hits: &[MemoryHit],
+    citations: &HashMap<String, Citation>,
+) {
+    ui.horizontal(|ui| {
+        ui.label(
+            RichText::new("Memory")
+                .strong()
+                .color(Color32::from_rgb(155, 89, 182)),
+        );
+        ui.label(RichText::new(format!("\"{}\"", query)).color(Color32::GRAY));
+    });
+    if hits.is_empty() {
+        ui.label("Nothing found. Try /reindex to rebuild the memory index.");
+        return;
+    }
+    for hit in hits {
+        ui.group(|ui| {
+            ui.set_width(ui.available_width());
+            ui.horizontal(|ui| {
+                if hit.hash.is_empty() {
+                    ui.label(
+                        RichText::new("unverified")
+                            .small()
+                            .color(Color32::from_rgb(231, 76, 60)),
+                    );
+                } else {
+                    citation_chip(ui, &hit.hash, citations.get(&hit.hash));
+                }
+                ui.label(
+                    RichText::new(format!(
+                        "{}:{}-{}",
+                        hit.card.file, hit.card.line_start, hit.card.line_end
+                    ))
+                    .monospace(),
+                );
+            });
+            let mut details = format!(
+                "score {:.3} · {} confidence · {}",
+                hit.score, hit.card.confidence, hit.card.provenance
+            );
+            if hit.stale {
+                details.push_str(" · stale");
+            }
+            ui.label(RichText::new(details).small().color(Color32::GRAY));
+            let text = hit.content.split_whitespace().collect::<Vec<_>>().join(" ");
+            let preview: String = text.chars().take(300).collect();
+            if preview.len() < text.len() {
+                ui.label(format!("{}...", preview));
+            } else {
+                ui.label(preview);
+            }
+        });
+    }
+}
+
+/// A titled, scrolling block of monospace text with a Copy button
+fn tool_section(ui: &mut Ui, id: egui::Id, title: &str, text: &str) {
+    ui.horizontal(|ui| {
+        ui.label(RichText::new(title).strong());
+        if ui.small_button("Copy").clicked() {
+            ui.ctx().copy_text(text.to_string());
+        }
+    });
+    ScrollArea::vertical()
+        .id_salt(id)
+        .max_height(240.0)
+        .show(ui, |ui| {
+            if text.is_empty() {
+                ui.label(RichText::new("(empty)").color(Color32::GRAY));
+            } else {
+                ui.label(RichText::new(text).monospace());
+            }
+        });
+}
+
+/// Reply text with each `[VERIFIED:<hash>]` citation drawn as a chip.
+/// Hovering a chip shows where the chunk comes from; clicking pins that
+/// card with a button to open the file.
+fn render_cited(ui: &mut Ui, text: &str, citations: &HashMap<String, Citation>) -> Option<String> {
+    let mut lookup = None;
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing.x = 0.0;
+        let mut rest = text;
+        while let Some(start) = rest.find(CITATION_PREFIX) {
+            let Some((hash, len)) = parse_citation(&rest[start..]) else {
+                let end = start + CITATION_PREFIX.len();
+                ui.label(&rest[..end]);
+                rest = &rest[end..];
+                continue;
+            };
+            if start > 0 {
+                ui.label(&rest[..start]);
+            }
+            let hash = hash.to_lowercase();
+            if citation_chip(ui, &hash, citations.get(&hash)) {
+                lookup = Some(hash);
+            }
+            rest = &rest[start + len..];
+        }
+        if !rest.is_empty() {
+            ui.label(rest);
+        }
+    });
+    lookup
+}
+
+/// A citation chip; true if it was just hovered and needs looking up
+fn citation_chip(ui: &mut Ui, hash: &str, citation: Option<&Citation>) -> bool {
+    let color = match citation {
+        Some(Citation::Found(card)) if card.verified => Color32::from_rgb(46, 204, 113),
+        Some(Citation::Found(_)) | Some(Citation::Missing) => Color32::from_rgb(231, 76, 60),
+        _ => Color32::GRAY,
+    };
+    let label = RichText::new(format!("✔ {}", hash)).small().color(color);
+    let chip = ui.add(egui::Button::new(label).small());
+    let card_id = chip.id.with("card");
+    if chip.clicked() {
+        ui.memory_mut(|m| m.toggle_popup(card_id));
+    }
+    let needs_lookup = citation.is_none() && (chip.hovered() || chip.clicked());
+
+    if ui.memory(|m| m.is_popup_open(card_id)) {
+        egui::popup_below_widget(
+            ui,
+            card_id,
+            &chip,
+            PopupCloseBehavior::CloseOnClickOutside,
+            |ui| citation_card(ui, citation, true),
+        );
+    } else {
+        chip.on_hover_ui(|ui| citation_card(ui, citation, false));
+    }
+    needs_lookup
+}
+
+/// File, lines, provenance and confidence of a cited chunk
+fn citation_card(ui: &mut Ui, citation: Option<&Citation>, pinned: bool) {
+    ui.set_min_width(240.0);
+    let card = match citation {
+        None | Some(Citation::Loading) => {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Looking up the source...");
+            });
+            return;
+        }
+        Some(Citation::Missing) => {
+            ui.label("Not in the index any more: the file was edited or the memory forgotten.");
+            return;
+        }
+        Some(Citation::Found(card)) => card,
+    };
+
+    ui.label(
+        RichText::new(format!(
+            "{}:{}-{}",
+            card.file, card.line_start, card.line_end
+        ))
+        .monospace()
+        .strong(),
+    );
+    Grid::new(ui.id().with("citation_card"))
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Provenance");
+            ui.label(&card.provenance);
+            ui.end_row();
+            ui.label("Confidence");
+            ui.label(&card.confidence);
+            ui.end_row();
+            ui.label("Hash");
+            if card.verified {
+                ui.label(RichText::new("matches").color(Color32::from_rgb(46, 204, 113)));
+            } else {
+                ui.label(
+                    RichText::new("changed since indexing").color(Color32::from_rgb(231, 76, 60)),
+                );
+            }
+            ui.end_row();
+        });
+    if pinned {
+        ui.add_space(4.0);
+        if ui.button("Open file").clicked() {
+            let url = format!("file://{}", card.path.display());
+            ui.ctx().open_url(egui::OpenUrl::new_tab(url));
+        }
+    } else {
+        ui.label(
+            RichText::new("Click to pin and open the file")
+                .small()
+                .color(Color32::GRAY),
+        );
     }
 }
 
 /// Top toolbar with panel tabs
-pub fn show_toolbar(ui: &mut Ui, state: &mut UiState) {
+/// Panel tabs, the View menu (sets `display_changed`) and the model
+pub fn show_toolbar(
+    ui: &mut Ui,
+    state: &mut UiState,
+    display: &mut DisplaySettings,
+    display_changed: &mut bool,
+) {
     ui.horizontal(|ui| {
-        ui.selectable_value(&mut state.active_panel, Panel::Chat, "Chat");
-        ui.selectable_value(&mut state.active_panel, Panel::Sessions, "Sessions");
-        ui.selectable_value(&mut state.active_panel, Panel::Status, "Status");
+        for panel in Panel::ALL {
+            ui.selectable_value(&mut state.active_panel, panel, panel.label());
+        }
 
         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            ui.menu_button("View", |ui| *display_changed = display.menu(ui));
             if !state.model.is_empty() {
                 ui.label(RichText::new(&state.model).small().color(Color32::GRAY));
             }
@@ -268,3 +1002,37 @@ pub fn show_toolbar(ui: &mut Ui, state: &mut UiState) {
     });
     ui.separator();
 }
+
+/// What `input` can be completed to, with a description: the slash commands
+/// it starts, or the skills after `/skill `
+fn completions(input: &str, skills: &[String], locked: bool) -> Vec<(String, &'static str)> {
+    if let Some(partial) = input.strip_prefix("/skill ") {
+        if partial.contains(char::is_whitespace) {
+            return Vec::new();
+        }
+        return skills
+            .iter()
+            .filter(|skill| skill.starts_with(partial) && skill.as_str() != partial)
+            .map(|skill| (format!("/skill {} ", skill), "Skill"))
+            .collect();
+    }
+    if !input.starts_with('/') || input.contains(char::is_whitespace) {
+        return Vec::new();
+    }
+    COMMANDS
+        .iter()
+        .filter(|(command, _)| !locked || KIOSK_COMMANDS.contains(command))
+        .filter(|(command, _)| command.starts_with(input) && *command != input)
+        .map(|(command, description)| (format!("{} ", command), *description))
+        .collect()
+}
+
+/// Put the input's cursor after `text`, e.g. once history or a completion
+/// replaced it
+fn move_cursor_to_end(ctx: &egui::Context, id: egui::Id, text: &str) {
+    let mut edit = egui::text_edit::TextEditState::load(ctx, id).unwrap_or_default();
+    let end = egui::text::CCursor::new(text.chars().count());
+    edit.cursor
+        .set_char_range(Some(egui::text::CCursorRange::one(end)));
+    edit.store(ctx, id);
+}
@@ -0,0 +1,230 @@
+//! Kiosk view - the kitchen tablet's person picker, top bar and parent PIN
+//!
+//! With `desktop.people` set, kiosk mode opens on a picker and each person
+//! chats under their own profile. Leaving kiosk mode, and picking someone
+//! without a profile (a grown-up), asks for `desktop.parent_pin`.
+
+use eframe::egui::{self, Align2, Color32, RichText, TextEdit, Ui};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::config::{Config, KioskPersonConfig};
+use crate::desktop::state::{UiMessage, UiState};
+
+/// Wrong PINs in a row before the prompt stops taking guesses for a while
+const MAX_PIN_FAILURES: u32 = 5;
+const PIN_LOCKOUT: Duration = Duration::from_secs(60);
+
+/// What the PIN unlocks
+#[derive(Debug, Clone, Copy)]
+enum Unlock {
+    Person(usize),
+    Exit,
+}
+
+struct PinPrompt {
+    unlock: Unlock,
+    entry: String,
+    wrong: bool,
+}
+
+pub struct KioskView {
+    people: Vec<KioskPersonConfig>,
+    parent_pin: Option<String>,
+    prompt: Option<PinPrompt>,
+    failures: u32,
+    locked_until: Option<Instant>,
+}
+
+impl KioskView {
+    pub fn new() -> Self {
+        let desktop = match Config::load() {
+            Ok(config) => config.desktop,
+            Err(e) => {
+                warn!("Kiosk settings unavailable: {}", e);
+                Default::default()
+            }
+        };
+        Self {
+            people: desktop.people,
+            parent_pin: desktop.parent_pin.filter(|pin| !pin.is_empty()),
+            prompt: None,
+            failures: 0,
+            locked_until: None,
+        }
+    }
+
+    /// Whether the picker stands in for the chat
+    pub fn needs_pick(&self, state: &UiState) -> bool {
+        !self.people.is_empty() && state.chatting_as.is_none()
+    }
+
+    /// Top bar: who's chatting, New chat, Switch and Exit
+    pub fn show_bar(&mut self, ui: &mut Ui, state: &mut UiState) -> Option<UiMessage> {
+        let mut message = None;
+        ui.horizontal(|ui| {
+            if let Some(ref name) = state.chatting_as {
+                ui.label(RichText::new(name).heading().strong());
+            }
+            if state.chatting_as.is_some() || self.people.is_empty() {
+                let can_start = !state.is_loading;
+                if ui
+                    .add_enabled(can_start, egui::Button::new("New chat"))
+                    .clicked()
+                {
+                    message = Some(UiMessage::NewSession);
+                }
+            }
+            if state.chatting_as.is_some() && ui.button("Switch").clicked() {
+                // Back to the picker; the next pick starts a new session
+                state.chatting_as = None;
+            }
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.button("Exit kiosk").clicked() {
+                    message = self.unlock(Unlock::Exit);
+                }
+            });
+        });
+        ui.separator();
+        message
+    }
+
+    /// "Who's chatting?" with a big button per person
+    pub fn show_picker(&mut self, ui: &mut Ui) -> Option<UiMessage> {
+        let mut picked = None;
+        ui.vertical_centered(|ui| {
+            ui.add_space(20.0);
+            ui.heading("Who's chatting?");
+            ui.add_space(20.0);
+        });
+        ui.horizontal_wrapped(|ui| {
+            for (index, person) in self.people.iter().enumerate() {
+                let avatar = person.avatar.clone().unwrap_or_else(|| {
+                    person
+                        .name
+                        .chars()
+                        .next()
+                        .map(|c| c.to_uppercase().to_string())
+                        .unwrap_or_default()
+                });
+                let text = RichText::new(format!("{}\n{}", avatar, person.name)).size(28.0);
+                if ui
+                    .add(egui::Button::new(text).min_size(egui::vec2(160.0, 140.0)))
+                    .clicked()
+                {
+                    picked = Some(index);
+                }
+            }
+        });
+        let index = picked?;
+        let person = &self.people[index];
+        if person.profile.is_none() {
+            self.unlock(Unlock::Person(index))
+        } else {
+            Some(self.chat_as(index))
+        }
+    }
+
+    /// The PIN pad, while one is asked for
+    pub fn show_prompt(&mut self, ctx: &egui::Context) -> Option<UiMessage> {
+        let mut submitted = false;
+        let mut cancelled = false;
+        let locked_out = self
+            .locked_until
+            .is_some_and(|until| Instant::now() < until);
+        let prompt = self.prompt.as_mut()?;
+        egui::Window::new("Parent PIN")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                let response = ui.add(
+                    TextEdit::singleline(&mut prompt.entry)
+                        .password(true)
+                        .desired_width(180.0),
+                );
+                response.request_focus();
+                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    submitted = true;
+                }
+                if locked_out {
+                    ui.label(RichText::new("Too many tries; wait a minute").color(Color32::RED));
+                } else if prompt.wrong {
+                    ui.label(RichText::new("Wrong PIN").color(Color32::RED));
+                }
+                egui::Grid::new("pin_pad").show(ui, |ui| {
+                    for row in [["1", "2", "3"], ["4", "5", "6"], ["7", "8", "9"]] {
+                        for digit in row {
+                            if ui.button(RichText::new(digit).size(24.0)).clicked() {
+                                prompt.entry.push_str(digit);
+                            }
+                        }
+                        ui.end_row();
+                    }
+                    if ui.button(RichText::new("⌫").size(24.0)).clicked() {
+                        prompt.entry.pop();
+                    }
+                    if ui.button(RichText::new("0").size(24.0)).clicked() {
+                        prompt.entry.push('0');
+                    }
+                    if ui.button(RichText::new("OK").size(24.0)).clicked() {
+                        submitted = true;
+                    }
+                    ui.end_row();
+                });
+                if ui.button("Cancel").clicked() {
+                    cancelled = true;
+                }
+            });
+
+        if cancelled {
+            self.prompt = None;
+            return None;
+        }
+        if !submitted || locked_out {
+            return None;
+        }
+        if self.parent_pin.as_deref() != Some(prompt.entry.as_str()) {
+            prompt.entry.clear();
+            prompt.wrong = true;
+            self.failures += 1;
+            if self.failures >= MAX_PIN_FAILURES {
+                self.failures = 0;
+                self.locked_until = Some(Instant::now() + PIN_LOCKOUT);
+            }
+            return None;
+        }
+        let unlock = prompt.unlock;
+        self.prompt = None;
+        self.failures = 0;
+        Some(self.unlocked(unlock))
+    }
+
+    /// Ask for the PIN if there is one, else go ahead
+    fn unlock(&mut self, unlock: Unlock) -> Option<UiMessage> {
+        if self.parent_pin.is_none() {
+            return Some(self.unlocked(unlock));
+        }
+        self.prompt = Some(PinPrompt {
+            unlock,
+            entry: String::new(),
+            wrong: false,
+        });
+        None
+    }
+
+    fn unlocked(&self, unlock: Unlock) -> UiMessage {
+        match unlock {
+            Unlock::Person(index) => self.chat_as(index),
+            Unlock::Exit => UiMessage::LeaveKiosk,
+        }
+    }
+
+    fn chat_as(&self, index: usize) -> UiMessage {
+        let person = &self.people[index];
+        UiMessage::ChatAs {
+            name: person.name.clone(),
+            profile: person.profile.clone(),
+        }
+    }
+}
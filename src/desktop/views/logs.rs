@@ -0,0 +1,150 @@
+//! Logs view - recent trace file lines, filtered by level and text
+
+use eframe::egui::{self, Color32, RichText, ScrollArea, Ui};
+use std::time::{Duration, Instant};
+use tracing::{warn, Level};
+
+use crate::logging::{self, LogRecord};
+
+/// How often the trace files are reread while the panel is open
+const RELOAD_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Lines read back from the trace files
+const MAX_RECORDS: usize = 500;
+
+const LEVELS: [Level; 5] = [
+    Level::ERROR,
+    Level::WARN,
+    Level::INFO,
+    Level::DEBUG,
+    Level::TRACE,
+];
+
+pub struct LogsView {
+    records: Vec<LogRecord>,
+    loaded_at: Option<Instant>,
+    /// Most verbose level shown
+    level: Level,
+    /// Matched against the message, target, session and turn IDs
+    filter: String,
+}
+
+impl LogsView {
+    pub fn new() -> Self {
+        Self {
+            records: Vec::new(),
+            loaded_at: None,
+            level: Level::INFO,
+            filter: String::new(),
+        }
+    }
+
+    /// Reread the trace files if they're stale
+    fn refresh(&mut self) {
+        if self
+            .loaded_at
+            .is_some_and(|at| at.elapsed() < RELOAD_INTERVAL)
+        {
+            return;
+        }
+        match logging::read_recent(MAX_RECORDS) {
+            Ok(records) => self.records = records,
+            Err(e) => warn!("Failed to read trace logs: {}", e),
+        }
+        self.loaded_at = Some(Instant::now());
+    }
+
+    fn matches(&self, record: &LogRecord) -> bool {
+        if record.level > self.level {
+            return false;
+        }
+        let filter = self.filter.trim().to_lowercase();
+        filter.is_empty()
+            || record.message.to_lowercase().contains(&filter)
+            || record.target.to_lowercase().contains(&filter)
+            || record
+                .session_id
+                .as_deref()
+                .is_some_and(|id| id.starts_with(&filter))
+            || record
+                .turn_id
+                .as_deref()
+                .is_some_and(|id| id.starts_with(&filter))
+    }
+
+    pub fn show(&mut self, ui: &mut Ui) {
+        self.refresh();
+
+        ui.heading("Logs");
+        ui.add_space(10.0);
+
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_label("Level")
+                .selected_text(self.level.as_str())
+                .show_ui(ui, |ui| {
+                    for level in LEVELS {
+                        ui.selectable_value(&mut self.level, level, level.as_str());
+                    }
+                });
+            ui.label("Filter:");
+            ui.text_edit_singleline(&mut self.filter);
+            if ui.button("Clear").clicked() {
+                self.filter.clear();
+            }
+            if ui.button("Refresh").clicked() {
+                self.loaded_at = None;
+            }
+        });
+        ui.label(
+            RichText::new("Click a turn ID to show only that turn")
+                .small()
+                .color(Color32::GRAY),
+        );
+        ui.add_space(5.0);
+
+        let mut select_turn = None;
+        ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                for record in self.records.iter().filter(|r| self.matches(r)) {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.label(
+                            RichText::new(&record.timestamp)
+                                .small()
+                                .color(Color32::GRAY),
+                        );
+                        ui.label(
+                            RichText::new(record.level.as_str()).color(level_color(record.level)),
+                        );
+                        if let Some(ref turn_id) = record.turn_id {
+                            if ui.small_button(turn_id).clicked() {
+                                select_turn = Some(turn_id.clone());
+                            }
+                        }
+                        if !record.spans.is_empty() {
+                            ui.label(
+                                RichText::new(record.spans.join(":"))
+                                    .small()
+                                    .color(Color32::GRAY),
+                            );
+                        }
+                        ui.label(&record.message);
+                    });
+                }
+            });
+
+        if let Some(turn_id) = select_turn {
+            self.filter = turn_id;
+        }
+    }
+}
+
+fn level_color(level: Level) -> Color32 {
+    match level {
+        Level::ERROR => Color32::from_rgb(231, 76, 60),
+        Level::WARN => Color32::from_rgb(243, 156, 18),
+        Level::INFO => Color32::from_rgb(46, 204, 113),
+        _ => Color32::GRAY,
+    }
+}
@@ -1,9 +1,21 @@
 //! UI views
 
+mod changes;
 pub mod chat;
+mod kiosk;
+mod logs;
+mod palette;
+mod prompt;
 mod sessions;
 mod status;
+mod timers;
 
+pub use changes::ChangesView;
 pub use chat::ChatView;
+pub use kiosk::KioskView;
+pub use logs::LogsView;
+pub use palette::CommandPalette;
+pub use prompt::PromptView;
 pub use sessions::SessionsView;
 pub use status::StatusView;
+pub use timers::TimersView;
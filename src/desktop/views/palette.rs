@@ -0,0 +1,240 @@
+//! Command palette (Ctrl+K): type to filter, Up and Down to pick, Enter to
+//! run. Each command is a `UiMessage`, sent the same way a button sends it.
+
+use eframe::egui::{self, Align2, Color32, Key, Modifiers, RichText, TextEdit};
+use std::fs;
+
+use crate::agent::DEFAULT_AGENT_ID;
+use crate::config::{agents_dir, AgentsFile, Config};
+use crate::desktop::shortcuts::{Action, Shortcuts};
+use crate::desktop::state::{Panel, UiMessage, UiState};
+use crate::desktop::worker::DAEMON_AGENT_ID;
+use crate::heartbeat::{parse_tasks, HEARTBEAT_PATH};
+
+/// Most commands listed at once
+const MAX_SHOWN: usize = 12;
+
+struct Command {
+    label: String,
+    /// The command's shortcut, shown next to it
+    shortcut: Option<String>,
+    message: UiMessage,
+}
+
+#[derive(Default)]
+pub struct CommandPalette {
+    open: bool,
+    /// Opened to search memory: the query is the only command
+    search_only: bool,
+    query: String,
+    selected: usize,
+    /// Agents to switch to, read when the palette opens
+    agents: Vec<String>,
+    /// HEARTBEAT.md section titles, read when the palette opens
+    tasks: Vec<String>,
+}
+
+impl CommandPalette {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open the palette, or close it if it's open
+    pub fn toggle(&mut self, search_only: bool, state: &UiState) {
+        if self.open && self.search_only == search_only {
+            self.open = false;
+            return;
+        }
+        let config = Config::load().unwrap_or_default();
+        self.open = true;
+        self.search_only = search_only;
+        self.query.clear();
+        self.selected = 0;
+        self.tasks = fs::read_to_string(config.workspace_path().join(HEARTBEAT_PATH))
+            .map(|content| parse_tasks(&content).into_iter().map(|t| t.title).collect())
+            .unwrap_or_default();
+        self.agents = other_agents(&config, state);
+    }
+
+    /// Draw the palette if it's open; the command picked, if one was
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        state: &UiState,
+        shortcuts: &Shortcuts,
+    ) -> Option<UiMessage> {
+        if !self.open {
+            return None;
+        }
+        let commands = self.commands(ctx, state, shortcuts);
+        let (up, down, enter, escape) = ctx.input_mut(|input| {
+            (
+                input.consume_key(Modifiers::NONE, Key::ArrowUp),
+                input.consume_key(Modifiers::NONE, Key::ArrowDown),
+                input.consume_key(Modifiers::NONE, Key::Enter),
+                input.consume_key(Modifiers::NONE, Key::Escape),
+            )
+        });
+        if escape {
+            self.open = false;
+            return None;
+        }
+        let last = commands.len().min(MAX_SHOWN).saturating_sub(1);
+        if down {
+            self.selected = (self.selected + 1).min(last);
+        }
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        self.selected = self.selected.min(last);
+        let mut chosen = enter
+            .then(|| commands.get(self.selected).map(|c| c.message.clone()))
+            .flatten();
+
+        let window = egui::Window::new("Command palette")
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_TOP, [0.0, 60.0])
+            .fixed_size([420.0, 0.0])
+            .show(ctx, |ui| {
+                let hint = if self.search_only {
+                    "Search memory..."
+                } else {
+                    "Type a command..."
+                };
+                let input = ui.add(
+                    TextEdit::singleline(&mut self.query)
+                        .hint_text(hint)
+                        .desired_width(f32::INFINITY),
+                );
+                input.request_focus();
+                if input.changed() {
+                    self.selected = 0;
+                }
+                ui.separator();
+                if commands.is_empty() {
+                    ui.label(RichText::new("No matching commands").color(Color32::GRAY));
+                }
+                for (i, command) in commands.iter().take(MAX_SHOWN).enumerate() {
+                    ui.horizontal(|ui| {
+                        if ui
+                            .selectable_label(i == self.selected, &command.label)
+                            .clicked()
+                        {
+                            chosen = Some(command.message.clone());
+                        }
+                        if let Some(ref shortcut) = command.shortcut {
+                            ui.with_layout(
+                                egui::Layout::right_to_left(egui::Align::Center),
+                                |ui| {
+                                    ui.label(RichText::new(shortcut).small().color(Color32::GRAY));
+                                },
+                            );
+                        }
+                    });
+                }
+            });
+        if window.is_some_and(|window| window.response.clicked_elsewhere()) {
+            self.open = false;
+        }
+        if chosen.is_some() {
+            self.open = false;
+        }
+        chosen
+    }
+
+    /// The commands matching the query, in order
+    fn commands(
+        &self,
+        ctx: &egui::Context,
+        state: &UiState,
+        shortcuts: &Shortcuts,
+    ) -> Vec<Command> {
+        let query = self.query.trim();
+        let command = |label: String, action: Option<Action>, message: UiMessage| Command {
+            label,
+            shortcut: action.and_then(|action| shortcuts.label(ctx, action)),
+            message,
+        };
+        let mut commands = Vec::new();
+        if !self.search_only {
+            commands.push(command(
+                "New session".to_string(),
+                Some(Action::NewSession),
+                UiMessage::NewSession,
+            ));
+            for panel in Panel::ALL {
+                commands.push(command(
+                    format!("Show {}", panel.label()),
+                    Some(Action::ShowPanel(panel)),
+                    UiMessage::ShowPanel(panel),
+                ));
+            }
+            let voice = if state.voice_on() {
+                "Stop reading replies aloud"
+            } else {
+                "Read replies aloud"
+            };
+            commands.push(command(
+                voice.to_string(),
+                Some(Action::Voice),
+                UiMessage::SetVoice(None),
+            ));
+            for agent in &self.agents {
+                commands.push(command(
+                    format!("Switch to agent {}", agent),
+                    None,
+                    UiMessage::SwitchAgent(agent.clone()),
+                ));
+            }
+            for task in &self.tasks {
+                commands.push(command(
+                    format!("Run heartbeat task: {}", task),
+                    None,
+                    UiMessage::RunTask(task.clone()),
+                ));
+            }
+            for skill in &state.skills {
+                commands.push(command(
+                    format!("Run skill: {}", skill),
+                    None,
+                    UiMessage::InvokeSkill {
+                        name: skill.clone(),
+                        args: String::new(),
+                    },
+                ));
+            }
+            let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+            commands.retain(|command| {
+                let label = command.label.to_lowercase();
+                words.iter().all(|word| label.contains(word.as_str()))
+            });
+        }
+        if !query.is_empty() {
+            commands.push(command(
+                format!("Search memory for \"{}\"", query),
+                Some(Action::SearchMemory),
+                UiMessage::SearchMemory(query.to_string()),
+            ));
+        }
+        commands
+    }
+}
+
+/// Agents defined in agents.toml or with saved sessions, other than the
+/// app's own. None when attached to the daemon, which serves one agent.
+fn other_agents(config: &Config, state: &UiState) -> Vec<String> {
+    if state.agent_id == DAEMON_AGENT_ID {
+        return Vec::new();
+    }
+    let mut agents = vec![DEFAULT_AGENT_ID.to_string()];
+    if let Ok(file) = agents_dir(&config.memory).and_then(|dir| AgentsFile::load(&dir)) {
+        agents.extend(file.agents.into_keys());
+    }
+    agents.extend(state.sessions.iter().map(|s| s.agent_id.clone()));
+    agents.sort();
+    agents.dedup();
+    agents.retain(|agent| *agent != state.agent_id && agent != DAEMON_AGENT_ID);
+    agents
+}
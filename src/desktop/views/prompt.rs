@@ -0,0 +1,97 @@
+//! Prompt view - the system prompt the next turn sends, section by section
+//! with token counts
+
+use eframe::egui::{CollapsingHeader, Color32, Grid, RichText, ScrollArea, Ui};
+
+use crate::agent::PromptInspection;
+use crate::desktop::state::{UiMessage, UiState};
+
+pub struct PromptView {
+    /// Session the last `InspectPrompt` was sent for; asked again when the
+    /// panel shows another one
+    asked_for: Option<Option<String>>,
+}
+
+impl PromptView {
+    pub fn new() -> Self {
+        Self { asked_for: None }
+    }
+
+    pub fn show(&mut self, ui: &mut Ui, state: &UiState) -> Option<UiMessage> {
+        let session = state.current_session.as_ref().map(|s| s.id.clone());
+        let mut msg = None;
+        if self.asked_for.as_ref() != Some(&session) {
+            self.asked_for = Some(session);
+            msg = Some(UiMessage::InspectPrompt);
+        }
+
+        ui.horizontal(|ui| {
+            ui.heading("Prompt");
+            if ui.button("Refresh").clicked() {
+                msg = Some(UiMessage::InspectPrompt);
+            }
+        });
+        ui.label(
+            RichText::new("What the model receives before your next message")
+                .small()
+                .color(Color32::GRAY),
+        );
+        ui.add_space(10.0);
+
+        let Some(ref inspection) = state.prompt else {
+            ui.label(RichText::new("Loading...").color(Color32::GRAY));
+            return msg;
+        };
+
+        ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                show_totals(ui, inspection);
+                ui.add_space(10.0);
+                for (i, section) in inspection.sections.iter().enumerate() {
+                    CollapsingHeader::new(format!("{} ({} tokens)", section.name, section.tokens))
+                        .id_salt(i)
+                        .show(ui, |ui| {
+                            ui.label(RichText::new(section.text.trim()).monospace());
+                        });
+                }
+            });
+        msg
+    }
+}
+
+/// Tokens per section and in total, against the context window
+fn show_totals(ui: &mut Ui, inspection: &PromptInspection) {
+    let window = inspection.context_window.max(1) as f64;
+    Grid::new("prompt_totals")
+        .num_columns(3)
+        .striped(true)
+        .show(ui, |ui| {
+            let mut row = |name: &str, tokens: usize| {
+                ui.label(name);
+                ui.label(tokens.to_string());
+                ui.label(
+                    RichText::new(format!("{:.1}%", tokens as f64 * 100.0 / window))
+                        .color(Color32::GRAY),
+                );
+                ui.end_row();
+            };
+            for section in &inspection.sections {
+                row(&section.name, section.tokens);
+            }
+            row("Tool definitions", inspection.tool_tokens);
+            row(
+                &format!("Conversation ({} messages)", inspection.history_messages),
+                inspection.history_tokens,
+            );
+            row("Total", inspection.total_tokens());
+        });
+    ui.label(
+        RichText::new(format!(
+            "{} · {} token context window",
+            inspection.model, inspection.context_window
+        ))
+        .small()
+        .color(Color32::GRAY),
+    );
+}
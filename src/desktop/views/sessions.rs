@@ -1,25 +1,59 @@
-//! Sessions view - list and manage sessions
+//! Sessions view - list, group and manage sessions
+//!
+//! Names, pins and tags live in each agent's sessions.json, so they show up
+//! wherever the session is listed.
 
-use eframe::egui::{Color32, RichText, ScrollArea, Ui};
+use std::collections::BTreeMap;
 
+use chrono::{Duration, NaiveDate};
+use eframe::egui::{CollapsingHeader, Color32, Key, RichText, ScrollArea, TextEdit, Ui};
+
+use crate::agent::SessionInfo;
 use crate::desktop::state::{UiMessage, UiState};
+use crate::locale;
+
+const GREEN: Color32 = Color32::from_rgb(46, 204, 113);
+
+/// How unpinned sessions are grouped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Grouping {
+    Day,
+    Agent,
+    Tag,
+}
 
-pub struct SessionsView;
+/// The row being edited, in place of its buttons
+enum RowEdit {
+    Rename(String),
+    /// Comma-separated
+    Tags(String),
+    ConfirmDelete,
+}
+
+pub struct SessionsView {
+    grouping: Grouping,
+    /// (agent, session) of the row being edited
+    editing: Option<(String, String, RowEdit)>,
+}
 
 impl SessionsView {
-    pub fn show(ui: &mut Ui, state: &mut UiState) -> Option<UiMessage> {
+    pub fn new() -> Self {
+        Self {
+            grouping: Grouping::Day,
+            editing: None,
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut Ui, state: &mut UiState) -> Option<UiMessage> {
         let mut message_to_send = None;
 
         ui.heading("Sessions");
         ui.add_space(10.0);
 
-        // New session button
-        if ui.button("New Session").clicked() {
-            message_to_send = Some(UiMessage::NewSession);
-        }
-
-        // Refresh button
         ui.horizontal(|ui| {
+            if ui.button("New Session").clicked() {
+                message_to_send = Some(UiMessage::NewSession);
+            }
             if ui.button("Refresh").clicked() {
                 message_to_send = Some(UiMessage::RefreshSessions);
             }
@@ -31,58 +65,244 @@ impl SessionsView {
         if let Some(ref current) = state.current_session {
             ui.group(|ui| {
                 ui.label(RichText::new("Current Session").strong());
-                ui.label(format!("ID: {}...", &current.id[..8.min(current.id.len())]));
+                ui.label(format!("ID: {}...", short_id(&current.id)));
                 ui.label(format!("Messages: {}", current.message_count));
             });
             ui.add_space(10.0);
         }
 
-        // Session list
-        ui.label(RichText::new("Available Sessions").strong());
+        ui.horizontal(|ui| {
+            ui.label("Group by:");
+            ui.selectable_value(&mut self.grouping, Grouping::Day, "Day");
+            ui.selectable_value(&mut self.grouping, Grouping::Agent, "Agent");
+            ui.selectable_value(&mut self.grouping, Grouping::Tag, "Tag");
+        });
         ui.add_space(5.0);
 
         if state.sessions.is_empty() {
             ui.label(RichText::new("No saved sessions").color(Color32::GRAY));
-        } else {
-            ScrollArea::vertical()
-                .id_salt("sessions_list")
-                .auto_shrink([false, false])
-                .show(ui, |ui| {
-                    for session in &state.sessions {
-                        let is_current = state
-                            .current_session
-                            .as_ref()
-                            .map(|c| c.id == session.id)
-                            .unwrap_or(false);
-
-                        ui.horizontal(|ui| {
-                            let short_id = &session.id[..8.min(session.id.len())];
-                            let date = session.created_at.format("%Y-%m-%d %H:%M");
-
-                            if is_current {
-                                ui.label(
-                                    RichText::new(format!(
-                                        "{} ({} msgs, {})",
-                                        short_id, session.message_count, date
-                                    ))
-                                    .color(Color32::from_rgb(46, 204, 113)),
-                                );
-                                ui.label(RichText::new("(current)").small().color(Color32::GRAY));
-                            } else {
-                                ui.label(format!(
-                                    "{} ({} msgs, {})",
-                                    short_id, session.message_count, date
-                                ));
-                                if ui.small_button("Resume").clicked() {
-                                    message_to_send =
-                                        Some(UiMessage::ResumeSession(session.id.clone()));
+            return message_to_send;
+        }
+
+        let pinned: Vec<&SessionInfo> = state.sessions.iter().filter(|s| s.pinned).collect();
+        let unpinned: Vec<&SessionInfo> = state.sessions.iter().filter(|s| !s.pinned).collect();
+        let mut groups = group_sessions(&unpinned, self.grouping);
+        if !pinned.is_empty() {
+            groups.insert(0, ("Pinned".to_string(), pinned));
+        }
+
+        let current_id = state.current_session.as_ref().map(|c| c.id.as_str());
+        ScrollArea::vertical()
+            .id_salt("sessions_list")
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for (title, sessions) in &groups {
+                    CollapsingHeader::new(format!("{} ({})", title, sessions.len()))
+                        .id_salt(("session_group", title))
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            for session in sessions {
+                                let is_current = current_id == Some(session.id.as_str());
+                                let can_resume = !is_current && session.agent_id == state.agent_id;
+                                if let Some(message) =
+                                    self.show_row(ui, session, is_current, can_resume)
+                                {
+                                    message_to_send = Some(message);
                                 }
                             }
                         });
-                    }
-                });
-        }
+                }
+            });
 
         message_to_send
     }
+
+    fn show_row(
+        &mut self,
+        ui: &mut Ui,
+        session: &SessionInfo,
+        is_current: bool,
+        can_resume: bool,
+    ) -> Option<UiMessage> {
+        let mut message = None;
+        let editing = self
+            .editing
+            .as_mut()
+            .filter(|(agent, id, _)| *agent == session.agent_id && *id == session.id)
+            .map(|(_, _, edit)| edit);
+
+        ui.horizontal_wrapped(|ui| {
+            let name = session
+                .label
+                .clone()
+                .unwrap_or_else(|| short_id(&session.id).to_string());
+            let text = RichText::new(name).strong();
+            ui.label(if is_current { text.color(GREEN) } else { text });
+            let date = locale::home_time(&session.created_at).format("%Y-%m-%d %H:%M");
+            ui.label(
+                RichText::new(format!("{} msgs, {}", session.message_count, date))
+                    .small()
+                    .color(Color32::GRAY),
+            );
+            for tag in &session.tags {
+                ui.label(RichText::new(format!("#{}", tag)).small());
+            }
+            if is_current {
+                ui.label(RichText::new("(current)").small().color(Color32::GRAY));
+            }
+        });
+
+        let mut close_edit = false;
+        let mut edit = None;
+        ui.horizontal(|ui| match editing {
+            Some(RowEdit::Rename(label)) => {
+                let response = ui.add(TextEdit::singleline(label).hint_text("Name"));
+                let enter = response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+                if ui.small_button("Save").clicked() || enter {
+                    message = Some(UiMessage::RenameSession {
+                        agent_id: session.agent_id.clone(),
+                        id: session.id.clone(),
+                        label: label.clone(),
+                    });
+                    close_edit = true;
+                }
+                if ui.small_button("Cancel").clicked() {
+                    close_edit = true;
+                }
+            }
+            Some(RowEdit::Tags(tags)) => {
+                let response = ui.add(TextEdit::singleline(tags).hint_text("work, trip"));
+                let enter = response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+                if ui.small_button("Save").clicked() || enter {
+                    message = Some(UiMessage::TagSession {
+                        agent_id: session.agent_id.clone(),
+                        id: session.id.clone(),
+                        tags: parse_tags(tags),
+                    });
+                    close_edit = true;
+                }
+                if ui.small_button("Cancel").clicked() {
+                    close_edit = true;
+                }
+            }
+            Some(RowEdit::ConfirmDelete) => {
+                ui.label(RichText::new("Delete this session for good?").color(Color32::RED));
+                if ui.small_button("Delete").clicked() {
+                    message = Some(UiMessage::DeleteSession {
+                        agent_id: session.agent_id.clone(),
+                        id: session.id.clone(),
+                    });
+                    close_edit = true;
+                }
+                if ui.small_button("Cancel").clicked() {
+                    close_edit = true;
+                }
+            }
+            None => {
+                if can_resume && ui.small_button("Resume").clicked() {
+                    message = Some(UiMessage::ResumeSession(session.id.clone()));
+                }
+                let pin = if session.pinned { "Unpin" } else { "Pin" };
+                if ui.small_button(pin).clicked() {
+                    message = Some(UiMessage::PinSession {
+                        agent_id: session.agent_id.clone(),
+                        id: session.id.clone(),
+                        pinned: !session.pinned,
+                    });
+                }
+                if ui.small_button("Rename").clicked() {
+                    edit = Some(RowEdit::Rename(session.label.clone().unwrap_or_default()));
+                }
+                if ui.small_button("Tags").clicked() {
+                    edit = Some(RowEdit::Tags(session.tags.join(", ")));
+                }
+                if !is_current && ui.small_button("Delete").clicked() {
+                    edit = Some(RowEdit::ConfirmDelete);
+                }
+            }
+        });
+        if close_edit {
+            self.editing = None;
+        }
+        if let Some(edit) = edit {
+            self.editing = Some((session.agent_id.clone(), session.id.clone(), edit));
+        }
+        ui.add_space(4.0);
+
+        message
+    }
+}
+
+fn short_id(id: &str) -> &str {
+    &id[..8.min(id.len())]
+}
+
+/// "work, Trip ,," is ["work", "Trip"]
+fn parse_tags(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in text.split(',').map(|t| t.trim().trim_start_matches('#')) {
+        if !tag.is_empty() && !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+/// Sessions under their group titles, in display order. By day the newest
+/// day comes first; by tag a session shows under each of its tags, with
+/// untagged ones last.
+fn group_sessions<'a>(
+    sessions: &[&'a SessionInfo],
+    grouping: Grouping,
+) -> Vec<(String, Vec<&'a SessionInfo>)> {
+    match grouping {
+        Grouping::Day => {
+            let mut days: BTreeMap<NaiveDate, Vec<&SessionInfo>> = BTreeMap::new();
+            for session in sessions {
+                let day = locale::home_time(&session.created_at).date_naive();
+                days.entry(day).or_default().push(session);
+            }
+            let today = locale::now().date_naive();
+            days.into_iter()
+                .rev()
+                .map(|(day, sessions)| {
+                    let title = if day == today {
+                        "Today".to_string()
+                    } else if day == today - Duration::days(1) {
+                        "Yesterday".to_string()
+                    } else {
+                        day.format("%A %Y-%m-%d").to_string()
+                    };
+                    (title, sessions)
+                })
+                .collect()
+        }
+        Grouping::Agent => {
+            let mut agents: BTreeMap<String, Vec<&SessionInfo>> = BTreeMap::new();
+            for session in sessions {
+                agents
+                    .entry(session.agent_id.clone())
+                    .or_default()
+                    .push(session);
+            }
+            agents.into_iter().collect()
+        }
+        Grouping::Tag => {
+            let mut tags: BTreeMap<String, Vec<&SessionInfo>> = BTreeMap::new();
+            let mut untagged = Vec::new();
+            for session in sessions {
+                if session.tags.is_empty() {
+                    untagged.push(*session);
+                }
+                for tag in &session.tags {
+                    tags.entry(tag.clone()).or_default().push(session);
+                }
+            }
+            let mut groups: Vec<_> = tags.into_iter().collect();
+            if !untagged.is_empty() {
+                groups.push(("Untagged".to_string(), untagged));
+            }
+            groups
+        }
+    }
 }
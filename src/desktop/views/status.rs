@@ -1,91 +1,218 @@
-//! Status view - show model, memory, and session stats
+//! Status view - show model, memory, usage, heartbeat and session stats
+//!
+//! The worker pushes fresh numbers every few seconds, so there's nothing to
+//! refresh by hand.
 
-use eframe::egui::{Color32, ProgressBar, RichText, Ui};
+use eframe::egui::{Color32, Grid, ProgressBar, RichText, ScrollArea, Ui};
 
-use crate::desktop::state::{UiMessage, UiState};
+use crate::desktop::state::{DaemonLink, StatusOverview, UiState};
+use crate::locale;
+
+const GREEN: Color32 = Color32::from_rgb(46, 204, 113);
+const RED: Color32 = Color32::from_rgb(231, 76, 60);
 
 pub struct StatusView;
 
 impl StatusView {
-    pub fn show(ui: &mut Ui, state: &mut UiState) -> Option<UiMessage> {
-        let mut message_to_send = None;
-
+    pub fn show(ui: &mut Ui, state: &UiState) {
         ui.heading("Status");
         ui.add_space(10.0);
 
-        // Refresh button
-        if ui.button("Refresh").clicked() {
-            message_to_send = Some(UiMessage::RefreshStatus);
-        }
+        ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                // Model info
+                ui.group(|ui| {
+                    ui.label(RichText::new("Model").strong());
+                    ui.label(&state.model);
+                });
 
-        ui.add_space(10.0);
+                ui.add_space(10.0);
 
-        // Model info
-        ui.group(|ui| {
-            ui.label(RichText::new("Model").strong());
-            ui.label(&state.model);
-        });
+                if let Some(ref overview) = state.overview {
+                    show_daemon(ui, overview);
+                    ui.add_space(10.0);
+                }
 
-        ui.add_space(10.0);
+                show_memory(ui, state);
+
+                ui.add_space(10.0);
 
-        // Memory info
-        ui.group(|ui| {
-            ui.label(RichText::new("Memory").strong());
-            ui.label(format!("Chunks: {}", state.memory_chunks));
-            ui.horizontal(|ui| {
-                ui.label("Embeddings:");
-                if state.has_embeddings {
-                    ui.label(RichText::new("enabled").color(Color32::from_rgb(46, 204, 113)));
-                } else {
-                    ui.label(RichText::new("disabled").color(Color32::GRAY));
+                if let Some(ref overview) = state.overview {
+                    show_heartbeat(ui, overview);
+                    ui.add_space(10.0);
                 }
+
+                show_session(ui, state);
             });
+    }
+}
+
+fn show_daemon(ui: &mut Ui, overview: &StatusOverview) {
+    ui.group(|ui| {
+        ui.label(RichText::new("Daemon").strong());
+        let (text, color) = match overview.daemon {
+            DaemonLink::Attached => ("connected; chat goes through the daemon", GREEN),
+            DaemonLink::Running => ("running; this window has its own agent", GREEN),
+            DaemonLink::NotRunning => {
+                ("not running (heartbeat and bridges are off)", Color32::GRAY)
+            }
+            DaemonLink::Unreachable => ("not answering", RED),
+        };
+        ui.label(RichText::new(text).color(color));
+    });
+}
+
+fn show_memory(ui: &mut Ui, state: &UiState) {
+    ui.group(|ui| {
+        ui.label(RichText::new("Memory").strong());
+        ui.label(format!("Chunks: {}", state.memory_chunks));
+        ui.horizontal(|ui| {
+            ui.label("Embeddings:");
+            if state.has_embeddings {
+                ui.label(RichText::new("enabled").color(GREEN));
+            } else if state.embeddings_loading {
+                ui.label(RichText::new("loading...").color(Color32::GRAY));
+            } else {
+                ui.label(RichText::new("disabled").color(Color32::GRAY));
+            }
         });
+        let Some(ref overview) = state.overview else {
+            return;
+        };
+        let model = overview.embedding_model.as_deref().unwrap_or("none loaded");
+        ui.label(
+            RichText::new(format!("{} · {}", overview.embedding_provider, model))
+                .small()
+                .color(Color32::GRAY),
+        );
+
+        if !overview.categories.is_empty() {
+            ui.add_space(5.0);
+            ui.label("By category:");
+            Grid::new("status_categories")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    for (category, count) in &overview.categories {
+                        ui.label(category);
+                        ui.label(count.to_string());
+                        ui.end_row();
+                    }
+                });
+        }
 
-        ui.add_space(10.0);
+        if !overview.provenance.is_empty() {
+            ui.add_space(5.0);
+            let verified: i64 = overview.provenance.iter().map(|(_, n)| n).sum();
+            ui.label(format!("Verified chunks: {}", verified));
+            Grid::new("status_provenance")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    for (provenance, count) in &overview.provenance {
+                        ui.label(provenance);
+                        ui.label(count.to_string());
+                        ui.end_row();
+                    }
+                });
+        }
+    });
+}
+
+fn show_heartbeat(ui: &mut Ui, overview: &StatusOverview) {
+    ui.group(|ui| {
+        ui.label(RichText::new("Heartbeat").strong());
+        match overview.heartbeat {
+            Some(ref run) => {
+                let color = match run.status.as_str() {
+                    "failed" => RED,
+                    "skipped" => Color32::GRAY,
+                    _ => GREEN,
+                };
+                ui.horizontal(|ui| {
+                    ui.label("Last run:");
+                    ui.label(RichText::new(&run.status).color(color));
+                    ui.label(RichText::new(ago(run.age_seconds)).color(Color32::GRAY));
+                });
+                if let Some(ref detail) = run.detail {
+                    let detail: String = detail.chars().take(200).collect();
+                    ui.label(RichText::new(detail).small());
+                }
+            }
+            None => {
+                ui.label(RichText::new("No run since the daemon started").color(Color32::GRAY));
+            }
+        }
 
-        // Session info
-        if let Some(ref status) = state.status {
-            ui.group(|ui| {
-                ui.label(RichText::new("Session").strong());
-                ui.label(format!("ID: {}...", &status.id[..8.min(status.id.len())]));
-                ui.label(format!("Messages: {}", status.message_count));
-                ui.label(format!("Compactions: {}", status.compaction_count));
-
-                // Token usage bar
-                ui.add_space(5.0);
-                ui.label("Context usage:");
-                let token_pct = status.token_count as f32 / 128000.0; // Assume 128k context
-                ui.add(
-                    ProgressBar::new(token_pct.min(1.0))
-                        .text(format!("~{} tokens", status.token_count)),
-                );
-
-                if token_pct > 0.8 {
-                    ui.label(
-                        RichText::new("Context nearly full. Consider starting a new session.")
-                            .color(Color32::from_rgb(231, 76, 60))
-                            .small(),
-                    );
+        if !overview.task_runs.is_empty() {
+            ui.add_space(5.0);
+            let now = locale::now().naive_local();
+            Grid::new("status_task_runs").num_columns(2).show(ui, |ui| {
+                for (title, at) in &overview.task_runs {
+                    let seconds = (now - *at).num_seconds().max(0) as u64;
+                    ui.label(title);
+                    ui.label(RichText::new(ago(seconds)).color(Color32::GRAY));
+                    ui.end_row();
                 }
             });
+        }
+    });
+}
 
-            ui.add_space(10.0);
+fn show_session(ui: &mut Ui, state: &UiState) {
+    let Some(ref status) = state.status else {
+        return;
+    };
+    ui.group(|ui| {
+        ui.label(RichText::new("Session").strong());
+        ui.label(format!("ID: {}...", &status.id[..8.min(status.id.len())]));
+        ui.label(format!("Messages: {}", status.message_count));
+        ui.label(format!("Compactions: {}", status.compaction_count));
+
+        // Token usage bar
+        ui.add_space(5.0);
+        ui.label("Context usage:");
+        let token_pct = status.token_count as f32 / 128000.0; // Assume 128k context
+        ui.add(
+            ProgressBar::new(token_pct.min(1.0)).text(format!("~{} tokens", status.token_count)),
+        );
+
+        if token_pct > 0.8 {
+            ui.label(
+                RichText::new("Context nearly full. Consider starting a new session.")
+                    .color(RED)
+                    .small(),
+            );
+        }
+    });
 
-            // API usage
-            if status.api_input_tokens > 0 || status.api_output_tokens > 0 {
-                ui.group(|ui| {
-                    ui.label(RichText::new("API Usage (Session)").strong());
-                    ui.label(format!("Input: {} tokens", status.api_input_tokens));
-                    ui.label(format!("Output: {} tokens", status.api_output_tokens));
-                    ui.label(format!(
-                        "Total: {} tokens",
-                        status.api_input_tokens + status.api_output_tokens
-                    ));
-                });
-            }
+    ui.add_space(10.0);
+
+    // API usage
+    ui.group(|ui| {
+        ui.label(RichText::new("API Usage").strong());
+        if let Some(ref overview) = state.overview {
+            ui.label(format!("Today: {} tokens", overview.tokens_today));
         }
+        ui.label(format!(
+            "This session: {} in / {} out",
+            status.api_input_tokens, status.api_output_tokens
+        ));
+        if status.api_cache_read_tokens > 0 {
+            ui.label(format!(
+                "Cached: {} tokens ({:.0}% of input)",
+                status.api_cache_read_tokens,
+                status.cache_hit_ratio() * 100.0
+            ));
+        }
+    });
+}
 
-        message_to_send
+/// "just now", "12 min ago", "3 h ago", "2 days ago"
+fn ago(seconds: u64) -> String {
+    match seconds {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{} min ago", seconds / 60),
+        3600..=86399 => format!("{} h ago", seconds / 3600),
+        _ => format!("{} days ago", seconds / 86400),
     }
 }
@@ -0,0 +1,87 @@
+//! Timers strip - running kitchen timers with a countdown
+
+use chrono::Utc;
+use eframe::egui::{RichText, Ui};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::agent::get_state_dir;
+use crate::timers::{format_countdown, Timer, TimerStore};
+
+/// How often the timer list is reread (timers can be set from other
+/// processes, e.g. the daemon's chat bridges)
+const RELOAD_INTERVAL: Duration = Duration::from_secs(1);
+
+pub struct TimersView {
+    store: Option<TimerStore>,
+    timers: Vec<Timer>,
+    loaded_at: Option<Instant>,
+}
+
+impl TimersView {
+    pub fn new() -> Self {
+        let store = match get_state_dir().and_then(|dir| TimerStore::open(&dir)) {
+            Ok(store) => Some(store),
+            Err(e) => {
+                warn!("Timers unavailable: {}", e);
+                None
+            }
+        };
+        Self {
+            store,
+            timers: Vec::new(),
+            loaded_at: None,
+        }
+    }
+
+    /// Reread the timers if they're stale
+    pub fn refresh(&mut self) {
+        let Some(ref store) = self.store else {
+            return;
+        };
+        if self
+            .loaded_at
+            .is_some_and(|at| at.elapsed() < RELOAD_INTERVAL)
+        {
+            return;
+        }
+        match store.list() {
+            Ok(timers) => self.timers = timers,
+            Err(e) => warn!("Failed to load timers: {}", e),
+        }
+        self.loaded_at = Some(Instant::now());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.timers.is_empty()
+    }
+
+    pub fn show(&mut self, ui: &mut Ui) {
+        let now = Utc::now();
+        let mut cancel = None;
+        ui.horizontal_wrapped(|ui| {
+            ui.label(RichText::new("Timers").strong());
+            for timer in &self.timers {
+                ui.group(|ui| {
+                    let remaining = timer.remaining(now);
+                    let countdown = if remaining.is_zero() {
+                        "done".to_string()
+                    } else {
+                        format_countdown(remaining)
+                    };
+                    ui.label(format!("{} {}", timer.name(), countdown));
+                    if ui.small_button("Cancel").clicked() {
+                        cancel = Some(timer.id);
+                    }
+                });
+            }
+        });
+
+        if let (Some(id), Some(ref store)) = (cancel, &self.store) {
+            if let Err(e) = store.cancel(&id.to_string()) {
+                warn!("Failed to cancel timer: {}", e);
+            }
+            self.loaded_at = None;
+        }
+    }
+}
@@ -3,21 +3,84 @@
 //! The worker runs in a separate thread with its own tokio runtime.
 //! It receives commands from the UI and sends back status updates.
 
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
 use std::pin::pin;
-use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::sync::Arc;
 use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
 use futures::StreamExt;
+use tokio::sync::Notify;
 
 use crate::agent::{
-    extract_tool_detail, list_sessions_for_agent, Agent, AgentConfig, StreamEvent, ToolCall,
-    DEFAULT_AGENT_ID,
+    attach_to_message, delete_session_for_agent, extract_tool_detail, get_state_dir,
+    list_all_sessions, load_agent_skills, load_skills, parse_skill_command, render_template,
+    skill_prompt, split_attachments, Agent, AgentConfig, ApprovalOutcome, ApprovalPolicy,
+    Attachment, ImageAttachment, Message, PromptVars, RecoveryMarker, Role, SessionEntry,
+    SessionInfo, SessionStatus, SessionStore, Skill, StreamEvent, ToolCall, UsageLimits,
+    DEFAULT_AGENT_ID, TUTOR_NOTES_PATH,
 };
-use crate::config::Config;
-use crate::memory::MemoryManager;
+use crate::announce::Announcer;
+use crate::client::{DaemonClient, DaemonEvent, DaemonSessionMessage, DaemonSessionStatus};
+use crate::config::{load_agent, Config};
+use crate::heartbeat::{parse_tasks, RunLog, HEARTBEAT_PATH};
+use crate::locale;
+use crate::memory::{ChangeQueue, FileWrite, IndexQueue, MemoryManager, Priority, VerifiedChunk};
+use crate::notifications::{Event, Notifier};
+use crate::stt::{after_wake_word, Transcriber};
 
-use super::state::{UiMessage, WorkerMessage};
+use super::speaker_id::{Heard, SpeakerId, WHO_IS_THIS};
+use super::speech::{BargeIn, Speech, SpokenReply, VOICE_CONTEXT};
+use super::state::{
+    AttachmentInfo, ChatMessage, CitationCard, DaemonLink, HeartbeatRun, MemoryHit, RecoveryOffer,
+    StatusOverview, UiMessage, WorkerMessage,
+};
+
+/// Agent ID the sessions panel shows for the daemon's sessions
+pub(super) const DAEMON_AGENT_ID: &str = "daemon";
+
+const HELP_TEXT: &str = "\
+Available commands:
+  /new              Start a new session
+  /model [name]     Show or set the current model
+  /compact          Compact session history
+  /memory <query>   Search memory files
+  /reindex          Reindex memory files in the background
+  /save             Save current session to disk
+  /status           Show session status
+  /dryrun [on|off]  Log changing tool calls instead of running them
+  /sessions         Show saved sessions
+  /resume <id>      Resume a session by ID
+  /skill <name>     Run a skill, with an optional request after the name
+  /voice [on|off]   Read this session's replies aloud (or /voice <speaker>)
+  /tutor [on|off]   Hands-free tutoring: talk, and hear the replies
+  /enroll [name]    Record someone's voice, so voice mode knows who's talking
+  /help             Show this help text
+
+Enter sends, Shift+Enter starts a new line, Up and Down recall sent messages.
+Ctrl+K opens the command palette.";
+
+/// How long closing the app waits for the worker to save
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often the status panel's session and health details are pushed
+const STATUS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often the recovery marker is rewritten while a reply streams
+const RECOVERY_WRITE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Wait before restarting a crashed worker, doubled for each crash in a row
+const RESTART_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest wait between restarts
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(30);
+
+/// How often a turn waiting for an approval answer checks for a cancel
+const APPROVAL_POLL_INTERVAL: Duration = Duration::from_millis(200);
 
 /// Handle to the background worker
 pub struct WorkerHandle {
@@ -25,59 +88,473 @@ pub struct WorkerHandle {
     pub tx: Sender<UiMessage>,
     /// Receive updates from the worker
     pub rx: Receiver<WorkerMessage>,
-    /// Thread handle
-    _thread: JoinHandle<()>,
+    /// Cancels the in-flight response
+    cancel: CancelSignal,
+    /// Stops replies being read aloud
+    barge_in: BargeIn,
+    /// Thread handle; taken when the worker dies
+    thread: Option<JoinHandle<()>>,
+    agent_id: String,
+    local: bool,
+    /// What's needed to bring a crashed worker back
+    supervisor: Supervisor,
 }
 
-impl WorkerHandle {
-    /// Start the background worker
-    pub fn start(agent_id: Option<String>) -> Result<Self> {
-        let (ui_tx, ui_rx) = mpsc::channel::<UiMessage>();
-        let (worker_tx, worker_rx) = mpsc::channel::<WorkerMessage>();
+/// Watches the worker's traffic so that if the thread dies it can be
+/// restarted in the same session, with input it never answered sent again
+#[derive(Default)]
+struct Supervisor {
+    /// Session the worker has open, from its status updates
+    session_id: Option<String>,
+    /// Chat input sent but not answered yet, oldest first, and whether it
+    /// is already a replay
+    unanswered: VecDeque<(UiMessage, bool)>,
+    /// Input to send once the restarted worker is ready
+    replay: Vec<(UiMessage, bool)>,
+    /// Input sent while the worker was down
+    unsent: Vec<UiMessage>,
+    /// When the dead worker is restarted; `None` while it runs
+    restart_at: Option<Instant>,
+    /// Restarted and waiting for `Ready`
+    restarting: bool,
+    /// Crashes since a worker last came up
+    crashes: u32,
+    /// Messages for the UI ahead of the worker's own
+    pending: VecDeque<WorkerMessage>,
+}
 
-        let agent_id = agent_id.unwrap_or_else(|| DEFAULT_AGENT_ID.to_string());
+/// Stop request for the turn being streamed. The worker doesn't read its
+/// command channel until a turn finishes, so this is shared directly.
+#[derive(Clone, Default)]
+struct CancelSignal {
+    requested: Arc<AtomicBool>,
+    /// The app is closing; every turn from now on stops immediately
+    shutting_down: Arc<AtomicBool>,
+    notify: Arc<Notify>,
+}
 
-        let thread = thread::spawn(move || {
-            // Create tokio runtime for this thread
-            let rt = tokio::runtime::Builder::new_current_thread()
-                .enable_all()
-                .build()
-                .expect("Failed to create tokio runtime");
+impl CancelSignal {
+    fn cancel(&self) {
+        self.requested.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
 
-            rt.block_on(async {
-                if let Err(e) = worker_loop(agent_id, ui_rx, worker_tx).await {
-                    eprintln!("Worker error: {}", e);
-                }
-            });
-        });
+    fn shut_down(&self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+        self.cancel();
+    }
+
+    fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    fn reset(&self) {
+        if !self.is_shutting_down() {
+            self.requested.store(false, Ordering::SeqCst);
+        }
+    }
+
+    /// Resolves once `cancel` has been called
+    async fn cancelled(&self) {
+        loop {
+            // Register before checking the flag so a cancel in between isn't missed
+            let notified = self.notify.notified();
+            if self.requested.load(Ordering::SeqCst) {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+impl WorkerHandle {
+    /// Start the background worker. It attaches to the daemon if one is
+    /// running, unless `local` is set.
+    pub fn start(agent_id: Option<String>, local: bool) -> Result<Self> {
+        let agent_id = agent_id.unwrap_or_else(|| DEFAULT_AGENT_ID.to_string());
+        let cancel = CancelSignal::default();
+        let barge_in = BargeIn::default();
+        let (tx, rx, thread) = spawn_worker(&agent_id, local, None, &cancel, &barge_in);
 
         Ok(Self {
-            tx: ui_tx,
-            rx: worker_rx,
-            _thread: thread,
+            tx,
+            rx,
+            cancel,
+            barge_in,
+            thread: Some(thread),
+            agent_id,
+            local,
+            supervisor: Supervisor::default(),
         })
     }
 
-    /// Send a message to the worker
-    pub fn send(&self, msg: UiMessage) -> Result<()> {
-        self.tx.send(msg)?;
+    /// Send a message to the worker. Stopping a reply or its speech takes
+    /// effect right away, even mid-turn. While the worker is down, input
+    /// is kept and sent once it's back.
+    pub fn send(&mut self, msg: UiMessage) -> Result<()> {
+        match msg {
+            UiMessage::Reconnect => {
+                if self.supervisor.restart_at.is_some() {
+                    self.supervisor.restart_at = Some(Instant::now());
+                }
+                return Ok(());
+            }
+            UiMessage::CancelGeneration => {
+                self.cancel.cancel();
+                self.barge_in.stop();
+                return Ok(());
+            }
+            UiMessage::StopSpeaking => {
+                self.barge_in.stop();
+                return Ok(());
+            }
+            UiMessage::SwitchAgent(agent_id) => {
+                self.switch_agent(agent_id);
+                return Ok(());
+            }
+            UiMessage::SetVoice(Some(ref arg)) if arg == "off" => self.barge_in.stop(),
+            _ => {}
+        }
+        if self.supervisor.restart_at.is_some() || self.supervisor.restarting {
+            self.supervisor.unsent.push(msg);
+            return Ok(());
+        }
+        let input = is_chat_input(&msg).then(|| msg.clone());
+        match self.tx.send(msg) {
+            Ok(()) => self
+                .supervisor
+                .unanswered
+                .extend(input.map(|msg| (msg, false))),
+            // A dead worker is noticed by `try_recv`; the input waits for it
+            Err(mpsc::SendError(msg)) => self.supervisor.unsent.push(msg),
+        }
         Ok(())
     }
 
-    /// Try to receive a message from the worker (non-blocking)
-    pub fn try_recv(&self) -> Option<WorkerMessage> {
-        self.rx.try_recv().ok()
+    /// Try to receive a message from the worker (non-blocking). If the
+    /// worker has died this reports it once with `WorkerDied`, then
+    /// restarts it when the backoff is up.
+    pub fn try_recv(&mut self) -> Option<WorkerMessage> {
+        if let Some(msg) = self.supervisor.pending.pop_front() {
+            return Some(msg);
+        }
+        match self.rx.try_recv() {
+            Ok(msg) => {
+                let ready = matches!(msg, WorkerMessage::Ready { .. });
+                self.supervisor.observe(&msg);
+                if ready && self.supervisor.restarting {
+                    self.replay();
+                }
+                Some(msg)
+            }
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) if self.cancel.is_shutting_down() => None,
+            Err(TryRecvError::Disconnected) => match self.supervisor.restart_at {
+                Some(at) if Instant::now() >= at => {
+                    self.restart();
+                    None
+                }
+                Some(_) => None,
+                None => Some(self.worker_died()),
+            },
+        }
+    }
+
+    /// The worker thread is gone: find out why and schedule a restart
+    fn worker_died(&mut self) -> WorkerMessage {
+        let reason = match self.thread.take().map(JoinHandle::join) {
+            Some(Err(panic)) => panic
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "the worker panicked".to_string()),
+            _ => "the worker stopped".to_string(),
+        };
+        eprintln!("Worker died: {}", reason);
+
+        let supervisor = &mut self.supervisor;
+        supervisor.restarting = false;
+        supervisor.crashes += 1;
+        let delay = RESTART_DELAY
+            .saturating_mul(1 << (supervisor.crashes - 1).min(5))
+            .min(MAX_RESTART_DELAY);
+        supervisor.restart_at = Some(Instant::now() + delay);
+
+        // Input that was in flight goes again, unless it already crashed a
+        // restarted worker
+        let mut dropped = 0;
+        while let Some((msg, replayed)) = supervisor.unanswered.pop_front() {
+            if replayed {
+                dropped += 1;
+            } else {
+                supervisor.replay.push((msg, true));
+            }
+        }
+        WorkerMessage::WorkerDied {
+            reason,
+            retry_in: delay,
+            dropped,
+        }
+    }
+
+    /// Start a new worker in the session the dead one had open
+    fn restart(&mut self) {
+        self.cancel.reset();
+        let (tx, rx, thread) = spawn_worker(
+            &self.agent_id,
+            self.local,
+            self.supervisor.session_id.clone(),
+            &self.cancel,
+            &self.barge_in,
+        );
+        self.tx = tx;
+        self.rx = rx;
+        self.thread = Some(thread);
+        self.supervisor.restart_at = None;
+        self.supervisor.restarting = true;
+    }
+
+    /// Send the restarted worker what the dead one never answered, then
+    /// what was typed while it was down
+    fn replay(&mut self) {
+        let supervisor = &mut self.supervisor;
+        supervisor.restarting = false;
+        let replay: Vec<(UiMessage, bool)> = supervisor
+            .replay
+            .drain(..)
+            .chain(supervisor.unsent.drain(..).map(|msg| (msg, false)))
+            .collect();
+        let resent = replay.iter().filter(|(msg, _)| is_chat_input(msg)).count();
+        for (msg, replayed) in replay {
+            let input = is_chat_input(&msg).then(|| (msg.clone(), replayed));
+            if self.tx.send(msg).is_err() {
+                break;
+            }
+            self.supervisor.unanswered.extend(input);
+        }
+        self.supervisor
+            .pending
+            .push_back(WorkerMessage::Reconnected { resent });
+    }
+
+    /// Save the session and stop the worker, then start one for `agent_id`
+    /// in a new session
+    fn switch_agent(&mut self, agent_id: String) {
+        if agent_id == self.agent_id {
+            return;
+        }
+        self.cancel.cancel();
+        self.barge_in.stop();
+        self.wait_stopped();
+        self.cancel.reset();
+        let (tx, rx, thread) =
+            spawn_worker(&agent_id, self.local, None, &self.cancel, &self.barge_in);
+        self.tx = tx;
+        self.rx = rx;
+        self.thread = Some(thread);
+        self.agent_id = agent_id;
+        self.supervisor = Supervisor::default();
+    }
+
+    /// Stop the worker before the app exits. A reply in progress is cut
+    /// short and saved to its session, then the memory index is closed.
+    /// Waits up to `SHUTDOWN_TIMEOUT`.
+    pub fn shutdown(&self) {
+        if self.cancel.is_shutting_down() {
+            return;
+        }
+        self.cancel.shut_down();
+        self.barge_in.stop();
+        self.wait_stopped();
+    }
+
+    /// Ask the worker to save and exit, waiting up to `SHUTDOWN_TIMEOUT`
+    fn wait_stopped(&self) {
+        if self.tx.send(UiMessage::Shutdown).is_err() {
+            return;
+        }
+        let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            match self.rx.recv_timeout(remaining) {
+                Ok(WorkerMessage::Stopped) | Err(_) => return,
+                Ok(_) => {}
+            }
+        }
+    }
+}
+
+impl Supervisor {
+    /// Note a message from the worker
+    fn observe(&mut self, msg: &WorkerMessage) {
+        match msg {
+            WorkerMessage::Ready { .. } => self.crashes = 0,
+            WorkerMessage::Status(status) => self.session_id = Some(status.id.clone()),
+            WorkerMessage::SessionChanged { id, .. } => self.session_id = Some(id.clone()),
+            WorkerMessage::Done | WorkerMessage::Interrupted | WorkerMessage::Error(_) => {
+                self.unanswered.pop_front();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Input that starts a turn, and is lost if the worker dies before it's
+/// answered
+fn is_chat_input(msg: &UiMessage) -> bool {
+    matches!(
+        msg,
+        UiMessage::Chat(_)
+            | UiMessage::InvokeSkill { .. }
+            | UiMessage::RunTask(_)
+            | UiMessage::Resend { .. }
+    )
+}
+
+/// Spawn the worker thread with its own runtime. `resume` is the session
+/// to carry on in after a crash.
+fn spawn_worker(
+    agent_id: &str,
+    local: bool,
+    resume: Option<String>,
+    cancel: &CancelSignal,
+    barge_in: &BargeIn,
+) -> (Sender<UiMessage>, Receiver<WorkerMessage>, JoinHandle<()>) {
+    let (ui_tx, ui_rx) = mpsc::channel::<UiMessage>();
+    let (worker_tx, worker_rx) = mpsc::channel::<WorkerMessage>();
+
+    let agent_id = agent_id.to_string();
+    let worker_cancel = cancel.clone();
+    let worker_barge_in = barge_in.clone();
+
+    let thread = thread::spawn(move || {
+        // Create tokio runtime for this thread
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("Failed to create tokio runtime");
+
+        rt.block_on(async {
+            let voice = Voice::new(worker_barge_in);
+            if let Err(e) = worker_loop(
+                agent_id,
+                local,
+                resume,
+                ui_rx,
+                worker_tx,
+                worker_cancel,
+                voice,
+            )
+            .await
+            {
+                eprintln!("Worker error: {}", e);
+            }
+        });
+    });
+    (ui_tx, worker_rx, thread)
+}
+
+/// A turn being streamed, mirrored to the recovery marker so a reply cut
+/// short can be restored on the next start
+struct InFlightTurn {
+    path: Option<PathBuf>,
+    marker: RecoveryMarker,
+    written: Instant,
+    interrupted: bool,
+    /// Read aloud as it streams, if the session has voice on
+    spoken: Option<SpokenReply>,
+}
+
+impl InFlightTurn {
+    fn begin(agent_id: &str, session_id: &str, message: &str) -> Self {
+        let path = RecoveryMarker::path(agent_id)
+            .map_err(|e| eprintln!("Warning: No recovery marker: {}", e))
+            .ok();
+        let mut turn = Self {
+            path,
+            marker: RecoveryMarker::new(session_id, message),
+            written: Instant::now(),
+            interrupted: false,
+            spoken: None,
+        };
+        turn.write();
+        turn
+    }
+
+    fn message(&self) -> &str {
+        &self.marker.message
+    }
+
+    fn partial(&self) -> &str {
+        &self.marker.partial
+    }
+
+    /// Record streamed text, rewriting the marker at most once per
+    /// `RECOVERY_WRITE_INTERVAL`
+    fn push(&mut self, text: &str) {
+        if let Some(spoken) = &mut self.spoken {
+            spoken.push(text);
+        }
+        self.marker.partial.push_str(text);
+        if self.written.elapsed() >= RECOVERY_WRITE_INTERVAL {
+            self.write();
+        }
+    }
+
+    /// Once the session is saved: keep the marker, now pointing at the saved
+    /// session, if closing the app cut the reply short; otherwise remove it
+    fn finish(mut self, shutting_down: bool) {
+        if self.interrupted && shutting_down {
+            self.marker.saved = true;
+            self.write();
+        } else if let Some(path) = &self.path {
+            RecoveryMarker::clear(path);
+        }
+    }
+
+    fn write(&mut self) {
+        if let Some(path) = &self.path {
+            if let Err(e) = self.marker.save(path) {
+                eprintln!("Warning: Failed to write recovery marker: {}", e);
+            }
+        }
+        self.written = Instant::now();
     }
 }
 
 async fn worker_loop(
     agent_id: String,
+    local: bool,
+    resume: Option<String>,
     rx: Receiver<UiMessage>,
     tx: Sender<WorkerMessage>,
+    cancel: CancelSignal,
+    mut voice: Voice,
 ) -> Result<()> {
     // Initialize agent
     let config = Config::load()?;
-    let memory = MemoryManager::new_with_full_config(&config.memory, Some(&config), &agent_id)?;
+    if !local {
+        if let Some(client) = DaemonClient::connect(&config).await {
+            return attached_loop(client, config, resume, rx, tx, cancel, voice).await;
+        }
+    }
+
+    // Load the embedding model in the background; keyword search works
+    // until it is ready
+    let ready_tx = tx.clone();
+    let memory = MemoryManager::new_with_background_embeddings(
+        &config.memory,
+        Some(&config),
+        &agent_id,
+        move |model| {
+            if model.is_some() {
+                let _ = ready_tx.send(WorkerMessage::EmbeddingsReady);
+            }
+        },
+    )?;
 
     let agent_config = AgentConfig {
         model: config.agent.default_model.clone(),
@@ -85,98 +562,157 @@ async fn worker_loop(
         reserve_tokens: config.agent.reserve_tokens,
     };
 
-    let mut agent = Agent::new(agent_config, &config, memory).await?;
+    // Reindexing runs on its own thread and pauses while a turn is streaming
+    let progress_tx = tx.clone();
+    let index_queue = IndexQueue::start(memory.clone(), move |progress| {
+        let _ = progress_tx.send(WorkerMessage::IndexProgress(progress));
+    })?;
+
+    let mut agent = Agent::new(agent_config, &config, memory.clone()).await?;
     agent.new_session().await?;
+    // Restarted after a crash: carry on in the session the app has open
+    if let Some(ref id) = resume {
+        if let Err(e) = agent.resume_session(id).await {
+            eprintln!(
+                "Warning: Could not resume session {} after restart: {}",
+                id, e
+            );
+        }
+    }
 
     // Send ready message
     let _ = tx.send(WorkerMessage::Ready {
+        agent_id: agent_id.clone(),
         model: agent.model().to_string(),
         memory_chunks: agent.memory_chunk_count(),
         has_embeddings: agent.has_embeddings(),
+        embeddings_loading: agent.embeddings_loading(),
     });
 
     // Send initial session list
-    if let Ok(sessions) = list_sessions_for_agent(&agent_id) {
-        let _ = tx.send(WorkerMessage::Sessions(sessions));
-    }
+    send_saved_sessions(&tx);
+    send_skills(&config, &agent_id, &tx);
 
     // Send initial status
     let _ = tx.send(WorkerMessage::Status(agent.session_status()));
+    let _ = tx.send(WorkerMessage::Overview(
+        local_overview(&config, &memory).await,
+    ));
 
-    // Track tools requiring approval
-    let approval_tools: Vec<String> = agent.approval_required_tools().to_vec();
+    // A reply cut short last time is offered for restore. After a crash
+    // the app sends that message again instead.
+    let recovery_path = RecoveryMarker::path(&agent_id)?;
+    let mut recovery = match resume {
+        Some(_) => {
+            RecoveryMarker::clear(&recovery_path);
+            None
+        }
+        None => RecoveryMarker::load(&recovery_path),
+    };
+    if let Some(marker) = &recovery {
+        let _ = tx.send(WorkerMessage::RecoveryAvailable(RecoveryOffer {
+            session_id: marker.session_id.clone(),
+            message: marker.message.clone(),
+        }));
+    }
+
+    // Checked as tool calls stream in, while the turn borrows the agent
+    let mut approval = agent.approval_policy().clone();
+    let notifier = Notifier::from_config(&config)?;
 
-    // Main loop
-    while let Ok(msg) = rx.recv() {
+    // Files attached to the next chat message
+    let mut attachments: Vec<Attachment> = Vec::new();
+
+    // Kiosk mode's person, for the tutor notes and their persona
+    let mut chatting_as: Option<String> = None;
+    let mut speaker_id = SpeakerId::new(&config);
+
+    // Main loop; the status panel is refreshed while it waits
+    let mut status_sent = Instant::now();
+    loop {
+        let msg = match rx.recv_timeout(STATUS_INTERVAL.saturating_sub(status_sent.elapsed())) {
+            Ok(msg) => msg,
+            Err(RecvTimeoutError::Timeout) => {
+                let _ = tx.send(WorkerMessage::Status(agent.session_status()));
+                let _ = tx.send(WorkerMessage::Overview(
+                    local_overview(&config, &memory).await,
+                ));
+                status_sent = Instant::now();
+                continue;
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        let Some(msg) = resolve_skill(msg, &config, &agent_id, &agent.prompt_vars(), &tx)
+            .and_then(|msg| resolve_task(msg, &config, &tx))
+        else {
+            continue;
+        };
+        // Logged while the tutored session is still the agent's
+        if ends_tutoring(&msg) {
+            if let Some(tutor) = voice.take_tutor(&tx) {
+                let logged = agent.log_tutor_session(tutor.student.as_deref()).await;
+                voice.end_tutor(tutor, logged, &config, &tx);
+            }
+        }
         let mut should_auto_save = false;
+        let mut finished_turn = None;
 
         match msg {
-            UiMessage::Chat(message) => {
-                // Stream response with tool support
-                match agent.chat_stream_with_tools(&message).await {
-                    Ok(stream) => {
-                        let mut stream = pin!(stream);
-                        let mut pending_tools: Vec<ToolCall> = Vec::new();
-
-                        while let Some(result) = stream.next().await {
-                            match result {
-                                Ok(event) => match event {
-                                    StreamEvent::Content(text) => {
-                                        let _ = tx.send(WorkerMessage::ContentChunk(text));
-                                    }
-                                    StreamEvent::ToolCallStart {
-                                        name,
-                                        id,
-                                        arguments,
-                                    } => {
-                                        // Check if this tool requires approval
-                                        if approval_tools.contains(&name) {
-                                            // Collect for approval
-                                            pending_tools.push(ToolCall {
-                                                id,
-                                                name,
-                                                arguments: String::new(),
-                                            });
-                                        } else {
-                                            let detail = extract_tool_detail(&name, &arguments);
-                                            let _ = tx.send(WorkerMessage::ToolCallStart {
-                                                name,
-                                                id,
-                                                detail,
-                                            });
-                                        }
-                                    }
-                                    StreamEvent::ToolCallEnd { name, id, output } => {
-                                        let _ = tx.send(WorkerMessage::ToolCallEnd {
-                                            name,
-                                            id,
-                                            output,
-                                        });
-                                    }
-                                    StreamEvent::Done => {
-                                        if !pending_tools.is_empty() {
-                                            let _ = tx.send(WorkerMessage::ToolsPendingApproval(
-                                                pending_tools.clone(),
-                                            ));
-                                            pending_tools.clear();
-                                        } else {
-                                            let _ = tx.send(WorkerMessage::Done);
-                                        }
-                                        should_auto_save = true;
-                                    }
-                                },
-                                Err(e) => {
-                                    let _ = tx.send(WorkerMessage::Error(e.to_string()));
-                                    break;
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        let _ = tx.send(WorkerMessage::Error(e.to_string()));
-                    }
-                }
+            UiMessage::Chat(mut message) => {
+                let images = if attachments.is_empty() {
+                    Vec::new()
+                } else {
+                    let _ = tx.send(WorkerMessage::Attachments(Vec::new()));
+                    let (with_files, images) =
+                        attach_to_message(&message, std::mem::take(&mut attachments));
+                    message = with_files;
+                    images
+                };
+                speaker_id.drop_question(&tx);
+                let _chat = memory.foreground(Priority::Chat);
+                let mut turn = InFlightTurn::begin(&agent_id, &agent.session_status().id, &message);
+                turn.spoken = spoken_reply(&mut agent, &voice, &config, chatting_as.as_deref());
+                should_auto_save = stream_turn(
+                    &mut agent,
+                    &mut turn,
+                    images,
+                    &tx,
+                    &cancel,
+                    &approval,
+                    notifier.as_ref(),
+                )
+                .await;
+                finished_turn = Some(turn);
             }
+            UiMessage::Resend {
+                original,
+                occurrence,
+                content,
+            } => match agent.rewind_to_user_message(&original, occurrence) {
+                Ok(removed) => {
+                    // Keep the original message's attachments
+                    let (_, attached) = split_attachments(&removed.content);
+                    let message = format!("{}{}", content, attached);
+                    let _chat = memory.foreground(Priority::Chat);
+                    let mut turn =
+                        InFlightTurn::begin(&agent_id, &agent.session_status().id, &message);
+                    turn.spoken = spoken_reply(&mut agent, &voice, &config, chatting_as.as_deref());
+                    should_auto_save = stream_turn(
+                        &mut agent,
+                        &mut turn,
+                        removed.images,
+                        &tx,
+                        &cancel,
+                        &approval,
+                        notifier.as_ref(),
+                    )
+                    .await;
+                    finished_turn = Some(turn);
+                }
+                Err(e) => {
+                    let _ = tx.send(WorkerMessage::Error(e.to_string()));
+                }
+            },
             UiMessage::NewSession => match agent.new_session().await {
                 Ok(()) => {
                     let status = agent.session_status();
@@ -197,6 +733,12 @@ async fn worker_loop(
                         id: status.id.clone(),
                         message_count: status.message_count,
                     });
+                    let messages: Vec<Message> = agent
+                        .raw_session_messages()
+                        .iter()
+                        .map(|m| m.message.clone())
+                        .collect();
+                    let _ = tx.send(WorkerMessage::History(ChatMessage::from_session(&messages)));
                     let _ = tx.send(WorkerMessage::Status(status));
                 }
                 Err(e) => {
@@ -211,13 +753,76 @@ async fn worker_loop(
             UiMessage::DenyTools => {
                 let _ = tx.send(WorkerMessage::Done);
             }
-            UiMessage::RefreshSessions => {
-                if let Ok(sessions) = list_sessions_for_agent(&agent_id) {
-                    let _ = tx.send(WorkerMessage::Sessions(sessions));
+            UiMessage::AttachFiles(paths) => {
+                for path in paths {
+                    match Attachment::load(&path) {
+                        Ok(attachment) => attachments.push(attachment),
+                        Err(e) => {
+                            let _ = tx.send(WorkerMessage::Error(format!("{:#}", e)));
+                        }
+                    }
                 }
+                let _ = tx.send(WorkerMessage::Attachments(attachment_infos(&attachments)));
             }
-            UiMessage::RefreshStatus => {
-                let _ = tx.send(WorkerMessage::Status(agent.session_status()));
+            UiMessage::RemoveAttachment(index) => {
+                if index < attachments.len() {
+                    attachments.remove(index);
+                }
+                let _ = tx.send(WorkerMessage::Attachments(attachment_infos(&attachments)));
+            }
+            UiMessage::SaveAttachment(index) => {
+                let Some(attachment) = attachments.get(index) else {
+                    continue;
+                };
+                match agent.save_attachment(attachment) {
+                    Ok(path) => {
+                        let _ = tx.send(WorkerMessage::SystemMessage(format!(
+                            "Saved {} to memory ({})",
+                            attachment.name(),
+                            path.display()
+                        )));
+                    }
+                    Err(e) => {
+                        let _ = tx.send(WorkerMessage::Error(format!("{:#}", e)));
+                    }
+                }
+            }
+            // Handled by `WorkerHandle::send` and the app; nothing is in
+            // flight here
+            UiMessage::CancelGeneration
+            | UiMessage::StopSpeaking
+            | UiMessage::Reconnect
+            | UiMessage::SwitchAgent(_)
+            | UiMessage::ShowPanel(_) => {}
+            UiMessage::RefreshSessions => send_saved_sessions(&tx),
+            UiMessage::RenameSession {
+                agent_id,
+                id,
+                label,
+            } => {
+                let label = Some(label.trim().to_string()).filter(|l| !l.is_empty());
+                update_saved_session(&agent_id, &id, |entry| entry.label = label, &tx);
+            }
+            UiMessage::PinSession {
+                agent_id,
+                id,
+                pinned,
+            } => update_saved_session(&agent_id, &id, |entry| entry.pinned = pinned, &tx),
+            UiMessage::TagSession { agent_id, id, tags } => {
+                update_saved_session(&agent_id, &id, |entry| entry.tags = tags, &tx);
+            }
+            UiMessage::DeleteSession { agent_id, id } => {
+                if id == agent.session_status().id {
+                    let _ = tx.send(WorkerMessage::Error(
+                        "That's the open session; start a new one before deleting it".to_string(),
+                    ));
+                    continue;
+                }
+                let _ = tx.send(match delete_session_for_agent(&agent_id, &id) {
+                    Ok(()) => WorkerMessage::SystemMessage(format!("Deleted session {}", id)),
+                    Err(e) => WorkerMessage::Error(format!("Deleting session failed: {}", e)),
+                });
+                send_saved_sessions(&tx);
             }
             UiMessage::SetModel(name) => match agent.set_model(&name) {
                 Ok(()) => {
@@ -250,34 +855,8 @@ async fn worker_loop(
             },
             UiMessage::SearchMemory(query) => match agent.search_memory(&query).await {
                 Ok(results) => {
-                    if results.is_empty() {
-                        let _ = tx.send(WorkerMessage::SystemMessage(
-                            "No memory results found.".to_string(),
-                        ));
-                    } else {
-                        let text = results
-                            .iter()
-                            .enumerate()
-                            .map(|(i, chunk)| {
-                                let preview: String = chunk.content.chars().take(150).collect();
-                                let preview = preview.replace('\n', " ");
-                                format!(
-                                    "{}. {} (lines {}-{}, score: {:.3})\n   {}",
-                                    i + 1,
-                                    chunk.file,
-                                    chunk.line_start,
-                                    chunk.line_end,
-                                    chunk.score,
-                                    preview,
-                                )
-                            })
-                            .collect::<Vec<_>>()
-                            .join("\n\n");
-                        let _ = tx.send(WorkerMessage::SystemMessage(format!(
-                            "Memory search results for \"{}\":\n{}",
-                            query, text
-                        )));
-                    }
+                    let hits = memory_hits(results, &config.workspace_path());
+                    let _ = tx.send(WorkerMessage::MemoryResults { query, hits });
                 }
                 Err(e) => {
                     let _ = tx.send(WorkerMessage::SystemMessage(format!(
@@ -286,6 +865,32 @@ async fn worker_loop(
                     )));
                 }
             },
+            UiMessage::LookupCitation(hash) => {
+                let found = memory
+                    .verify_citation(&hash)
+                    .map(|chunks| chunks.into_iter().next());
+                let card = match found {
+                    Ok(chunk) => chunk.map(|chunk| CitationCard {
+                        path: config.workspace_path().join(&chunk.file),
+                        file: chunk.file,
+                        line_start: chunk.line_start,
+                        line_end: chunk.line_end,
+                        verified: chunk.verified,
+                        provenance: chunk.provenance.to_string(),
+                        confidence: chunk.confidence.to_string(),
+                    }),
+                    Err(e) => {
+                        let _ = tx.send(WorkerMessage::Error(format!(
+                            "Citation lookup failed: {}",
+                            e
+                        )));
+                        None
+                    }
+                };
+                let _ = tx.send(WorkerMessage::Citation { hash, card });
+            }
+            UiMessage::Reindex => index_queue.reindex(true),
+            UiMessage::CancelIndexing => index_queue.cancel(),
             UiMessage::Save => match agent.save_session().await {
                 Ok(path) => {
                     let _ = tx.send(WorkerMessage::SystemMessage(format!(
@@ -298,33 +903,143 @@ async fn worker_loop(
                 }
             },
             UiMessage::ShowHelp => {
-                let help_text = "\
-Available commands:
-  /new              Start a new session
-  /model [name]     Show or set the current model
-  /compact          Compact session history
-  /memory <query>   Search memory files
-  /save             Save current session to disk
-  /status           Show session status
-  /sessions         Show saved sessions
-  /resume <id>      Resume a session by ID
-  /help             Show this help text";
-                let _ = tx.send(WorkerMessage::SystemMessage(help_text.to_string()));
+                let _ = tx.send(WorkerMessage::SystemMessage(HELP_TEXT.to_string()));
+            }
+            UiMessage::SetVoice(arg) => {
+                voice.set(&agent.session_status().id, arg, &config, &tx);
+            }
+            UiMessage::Transcribe { samples, wake_word } => {
+                let heard = if speaker_id.is_enrolling() {
+                    speaker_id.enroll(&samples, &tx)
+                } else {
+                    let text = transcribe(&samples, &config, &tx).await;
+                    let addressed = wake_word
+                        .is_none_or(|wake_word| after_wake_word(&text, &wake_word).is_some());
+                    let current = chatting_as.as_deref();
+                    speaker_id.heard(&samples, text, addressed, current, &config, &tx)
+                };
+                if let Some(name) = hear(heard, &mut agent, &mut voice, &config, &tx).await {
+                    approval = agent.approval_policy().clone();
+                    chatting_as = Some(name);
+                }
+            }
+            UiMessage::ThisIs(name) => {
+                let heard = speaker_id.answer(&name, chatting_as.as_deref(), &config, &tx);
+                if let Some(name) = hear(heard, &mut agent, &mut voice, &config, &tx).await {
+                    approval = agent.approval_policy().clone();
+                    chatting_as = Some(name);
+                }
+            }
+            UiMessage::Enroll(arg) => speaker_id.command(arg, &config, &tx),
+            UiMessage::Tutor(true) => {
+                let student = chatting_as.clone();
+                voice.start_tutor(&agent.session_status().id, student, &config, &tx);
+            }
+            // Ended above
+            UiMessage::Tutor(false) => {}
+            // Turned into `Chat` by `resolve_skill` and `resolve_task`
+            UiMessage::InvokeSkill { .. } | UiMessage::RunTask(_) => {}
+            UiMessage::ChatAs { name, profile } => {
+                match start_profile_session(&mut agent, profile.as_deref(), &tx).await {
+                    Ok(()) => {
+                        approval = agent.approval_policy().clone();
+                        chatting_as = Some(name.clone());
+                        speaker_id.picked();
+                        let _ = tx.send(WorkerMessage::ChattingAs(Some(name)));
+                    }
+                    Err(e) => {
+                        let _ = tx.send(WorkerMessage::Error(format!(
+                            "Couldn't start {}'s session: {}",
+                            name, e
+                        )));
+                    }
+                }
+            }
+            UiMessage::LeaveKiosk => {
+                let profile = Config::profile();
+                match start_profile_session(&mut agent, profile.as_deref(), &tx).await {
+                    Ok(()) => {
+                        approval = agent.approval_policy().clone();
+                        chatting_as = None;
+                        let _ = tx.send(WorkerMessage::ChattingAs(None));
+                    }
+                    Err(e) => {
+                        let _ = tx.send(WorkerMessage::Error(e.to_string()));
+                    }
+                }
             }
             UiMessage::ShowStatus => {
                 let status = agent.session_status();
-                let text = format!(
-                    "Session: {}\nMessages: {}\nTokens: {} context / {} API in / {} API out\nCompactions: {}",
-                    &status.id[..8.min(status.id.len())],
-                    status.message_count,
-                    status.token_count,
-                    status.api_input_tokens,
-                    status.api_output_tokens,
-                    status.compaction_count,
-                );
-                let _ = tx.send(WorkerMessage::SystemMessage(text));
+                let _ = tx.send(WorkerMessage::SystemMessage(status_text(&status)));
                 let _ = tx.send(WorkerMessage::Status(status));
             }
+            UiMessage::InspectPrompt => {
+                let _ = tx.send(WorkerMessage::Prompt(agent.inspect_prompt()));
+            }
+            UiMessage::RestoreSession => {
+                let Some(marker) = recovery.take() else {
+                    continue;
+                };
+                match agent.restore_session(&marker).await {
+                    Ok(()) => {
+                        RecoveryMarker::clear(&recovery_path);
+                        let status = agent.session_status();
+                        let _ = tx.send(WorkerMessage::SessionChanged {
+                            id: status.id.clone(),
+                            message_count: status.message_count,
+                        });
+                        let _ = tx.send(WorkerMessage::Restored {
+                            message: marker.message,
+                            partial: marker.partial,
+                        });
+                        let _ = tx.send(WorkerMessage::Status(status));
+                    }
+                    Err(e) => {
+                        let _ = tx.send(WorkerMessage::Error(format!(
+                            "Failed to restore session: {:#}",
+                            e
+                        )));
+                    }
+                }
+            }
+            UiMessage::SetDryRun(on) => {
+                let on = on.unwrap_or(!agent.dry_run());
+                agent.set_dry_run(on);
+                let text = if on {
+                    "Dry run on: file writes, commands and messages are logged, not run."
+                } else {
+                    "Dry run off."
+                };
+                let _ = tx.send(WorkerMessage::SystemMessage(text.to_string()));
+            }
+            UiMessage::ApproveChange(id) => {
+                let _ = tx.send(match memory.approve_change(id) {
+                    Ok(change) => WorkerMessage::SystemMessage(format!(
+                        "Approved change #{} to {}",
+                        id, change.path
+                    )),
+                    Err(e) => {
+                        WorkerMessage::Error(format!("Approving change #{} failed: {}", id, e))
+                    }
+                });
+            }
+            UiMessage::RejectChange(id) => {
+                let _ = tx.send(match memory.reject_change(id) {
+                    Ok(change) => WorkerMessage::SystemMessage(format!(
+                        "Rejected change #{} to {}",
+                        id, change.path
+                    )),
+                    Err(e) => {
+                        WorkerMessage::Error(format!("Rejecting change #{} failed: {}", id, e))
+                    }
+                });
+            }
+            UiMessage::DismissRecovery => {
+                if recovery.take().is_some() {
+                    RecoveryMarker::clear(&recovery_path);
+                }
+            }
+            UiMessage::Shutdown => break,
         }
 
         // Auto-save session after chat completes
@@ -333,7 +1048,1282 @@ Available commands:
                 eprintln!("Warning: Failed to auto-save session: {}", e);
             }
         }
+        if let Some(turn) = finished_turn {
+            turn.finish(cancel.is_shutting_down());
+        }
+    }
+
+    // The app is closing: stop indexing and fold the index's WAL back into
+    // the database so nothing is left half-written
+    index_queue.cancel();
+    drop(index_queue);
+    if let Err(e) = agent.auto_save_session() {
+        eprintln!("Warning: Failed to save session: {}", e);
+    }
+    if let Err(e) = memory.checkpoint() {
+        eprintln!("Warning: Failed to checkpoint memory index: {}", e);
+    }
+    let _ = tx.send(WorkerMessage::Stopped);
+
+    Ok(())
+}
+
+/// Send a chat message and forward the streamed response to the UI. Returns
+/// whether the session changed and should be saved.
+async fn stream_turn(
+    agent: &mut Agent,
+    turn: &mut InFlightTurn,
+    images: Vec<ImageAttachment>,
+    tx: &Sender<WorkerMessage>,
+    cancel: &CancelSignal,
+    approval: &ApprovalPolicy,
+    notifier: Option<&Notifier>,
+) -> bool {
+    cancel.reset();
+    let mut should_auto_save = false;
+    let session_id = agent.session_status().id;
+
+    // Stream response with tool support
+    let message = turn.message().to_string();
+    match agent.chat_stream_with_tools(&message, images).await {
+        Ok(stream) => {
+            let mut stream = pin!(stream);
+            let mut pending_tools: Vec<ToolCall> = Vec::new();
+            let mut done = false;
+
+            loop {
+                // Dropping the stream aborts the request and any
+                // running tool calls
+                let result = tokio::select! {
+                    biased;
+                    result = stream.next() => match result {
+                        Some(result) => result,
+                        None => break,
+                    },
+                    _ = cancel.cancelled(), if !done => {
+                        turn.interrupted = true;
+                        break;
+                    }
+                };
+                match result {
+                    Ok(event) => match event {
+                        StreamEvent::Content(text) => {
+                            turn.push(&text);
+                            let _ = tx.send(WorkerMessage::ContentChunk(text));
+                        }
+                        StreamEvent::ToolCallStart {
+                            name,
+                            id,
+                            arguments,
+                        } => {
+                            // Check if this call requires approval
+                            let decision = approval.check(&name, &arguments);
+                            let outcome = if decision.asks() {
+                                ApprovalOutcome::Requested
+                            } else {
+                                ApprovalOutcome::Allowed
+                            };
+                            approval.record(&session_id, &name, &arguments, &decision, outcome);
+                            if decision.asks() {
+                                // Collect for approval
+                                pending_tools.push(ToolCall {
+                                    id,
+                                    name,
+                                    arguments: String::new(),
+                                });
+                            } else {
+                                let detail = extract_tool_detail(&name, &arguments);
+                                let _ = tx.send(WorkerMessage::ToolCallStart {
+                                    name,
+                                    id,
+                                    detail,
+                                    arguments,
+                                });
+                            }
+                        }
+                        StreamEvent::ToolCallEnd { name, id, output } => {
+                            let _ = tx.send(WorkerMessage::ToolCallEnd { name, id, output });
+                        }
+                        StreamEvent::Done => {
+                            if !pending_tools.is_empty() {
+                                let _ = tx.send(WorkerMessage::ToolsPendingApproval(
+                                    pending_tools.clone(),
+                                ));
+                                if let Some(notifier) = notifier {
+                                    let names: Vec<&str> =
+                                        pending_tools.iter().map(|t| t.name.as_str()).collect();
+                                    let message =
+                                        format!("Approval needed to run: {}", names.join(", "));
+                                    notifier.notify(Event::ApprovalRequested, &message).await;
+                                }
+                                pending_tools.clear();
+                            } else {
+                                let _ = tx.send(WorkerMessage::Done);
+                            }
+                            done = true;
+                            should_auto_save = true;
+                        }
+                    },
+                    Err(e) => {
+                        let _ = tx.send(WorkerMessage::Error(e.to_string()));
+                        break;
+                    }
+                }
+            }
+        }
+        Err(e) => {
+            let _ = tx.send(WorkerMessage::Error(e.to_string()));
+        }
+    }
+
+    if let Some(spoken) = turn.spoken.take() {
+        if !turn.interrupted {
+            spoken.finish();
+        }
+    }
+    if turn.interrupted {
+        agent.interrupt_turn(turn.partial());
+        let _ = tx.send(WorkerMessage::Interrupted);
+        should_auto_save = true;
+    }
+
+    should_auto_save
+}
+
+/// UI commands for the attached loop. Those that arrive while a turn waits
+/// for an approval answer are kept for after it.
+struct Inbox {
+    rx: Receiver<UiMessage>,
+    deferred: VecDeque<UiMessage>,
+}
+
+impl Inbox {
+    fn recv_timeout(&mut self, timeout: Duration) -> Result<UiMessage, RecvTimeoutError> {
+        match self.deferred.pop_front() {
+            Some(msg) => Ok(msg),
+            None => self.rx.recv_timeout(timeout),
+        }
+    }
+
+    /// Whether the user approved the pending call; `None` if the turn was
+    /// stopped or the app is closing
+    fn wait_for_answer(&mut self, cancel: &CancelSignal) -> Option<bool> {
+        loop {
+            if cancel.is_cancelled() {
+                return None;
+            }
+            match self.rx.recv_timeout(APPROVAL_POLL_INTERVAL) {
+                Ok(UiMessage::ApproveTools(_)) => return Some(true),
+                Ok(UiMessage::DenyTools) => return Some(false),
+                Ok(msg) => self.deferred.push_back(msg),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return None,
+            }
+        }
     }
+}
+
+/// Forward UI commands to the daemon, which owns the agent and memory
+async fn attached_loop(
+    client: DaemonClient,
+    config: Config,
+    resume: Option<String>,
+    rx: Receiver<UiMessage>,
+    tx: Sender<WorkerMessage>,
+    cancel: CancelSignal,
+    mut voice: Voice,
+) -> Result<()> {
+    let status = client.status().await?;
+    let resumed = match resume {
+        Some(ref id) => client.open_session(Some(id)).await.ok(),
+        None => None,
+    };
+    let mut session = match resumed {
+        Some(session) => session,
+        None => client.open_session(None).await?,
+    };
+
+    let _ = tx.send(WorkerMessage::Ready {
+        agent_id: DAEMON_AGENT_ID.to_string(),
+        model: session.model.clone(),
+        memory_chunks: status.memory_chunks,
+        has_embeddings: status.has_embeddings,
+        embeddings_loading: false,
+    });
+    let _ = tx.send(WorkerMessage::SystemMessage(format!(
+        "Attached to the HomeGPT daemon at {}",
+        client.base_url()
+    )));
+    send_daemon_sessions(&client, &tx).await;
+    // The daemon doesn't say which agent it serves
+    send_skills(&config, DEFAULT_AGENT_ID, &tx);
+    send_daemon_status(&client, &session.session_id, &tx).await;
+    let _ = tx.send(WorkerMessage::Overview(
+        attached_overview(&config, &client).await,
+    ));
+
+    let workspace = config.workspace_path();
+    let mut attachments: Vec<Attachment> = Vec::new();
+    let mut chatting_as: Option<String> = None;
+    let mut inbox = Inbox {
+        rx,
+        deferred: VecDeque::new(),
+    };
 
+    let mut status_sent = Instant::now();
+    loop {
+        let msg = match inbox.recv_timeout(STATUS_INTERVAL.saturating_sub(status_sent.elapsed())) {
+            Ok(msg) => msg,
+            Err(RecvTimeoutError::Timeout) => {
+                send_daemon_status(&client, &session.session_id, &tx).await;
+                let _ = tx.send(WorkerMessage::Overview(
+                    attached_overview(&config, &client).await,
+                ));
+                status_sent = Instant::now();
+                continue;
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        let Some(msg) = resolve_skill(
+            msg,
+            &config,
+            DEFAULT_AGENT_ID,
+            &PromptVars::new(&config, None, None),
+            &tx,
+        )
+        .and_then(|msg| resolve_task(msg, &config, &tx)) else {
+            continue;
+        };
+        if ends_tutoring(&msg) {
+            if let Some(tutor) = voice.take_tutor(&tx) {
+                let student = tutor.student.as_deref();
+                let logged = client.log_tutor_session(&tutor.session_id, student).await;
+                voice.end_tutor(tutor, logged, &config, &tx);
+            }
+        }
+        let id = session.session_id.clone();
+        match msg {
+            UiMessage::Chat(mut message) => {
+                if !attachments.is_empty() {
+                    let _ = tx.send(WorkerMessage::Attachments(Vec::new()));
+                    let (with_files, images) =
+                        attach_to_message(&message, std::mem::take(&mut attachments));
+                    if !images.is_empty() {
+                        let _ = tx.send(WorkerMessage::SystemMessage(
+                            "Images can't be sent through the daemon; sending the text only"
+                                .to_string(),
+                        ));
+                    }
+                    message = with_files;
+                }
+                let spoken = voice.reply(&id);
+                // Always sent, so turning voice off clears it
+                let context = turn_context(&config, chatting_as.as_deref(), &voice, &id);
+                let context = context.unwrap_or_default();
+                stream_daemon_turn(
+                    &client, &id, &message, spoken, &context, &mut inbox, &tx, &cancel,
+                )
+                .await;
+                send_daemon_status(&client, &id, &tx).await;
+            }
+            UiMessage::NewSession => match client.open_session(None).await {
+                Ok(opened) => {
+                    session = opened;
+                    let _ = tx.send(WorkerMessage::SessionChanged {
+                        id: session.session_id.clone(),
+                        message_count: 0,
+                    });
+                    send_daemon_status(&client, &session.session_id, &tx).await;
+                }
+                Err(e) => {
+                    let _ = tx.send(WorkerMessage::Error(e.to_string()));
+                }
+            },
+            UiMessage::ResumeSession(session_id) => {
+                match client.open_session(Some(&session_id)).await {
+                    Ok(opened) => {
+                        session = opened;
+                        let message_count = client
+                            .session_status(&session.session_id)
+                            .await
+                            .map(|s| s.message_count)
+                            .unwrap_or(0);
+                        let _ = tx.send(WorkerMessage::SessionChanged {
+                            id: session.session_id.clone(),
+                            message_count,
+                        });
+                        if let Ok(messages) = client.session_messages(&session.session_id).await {
+                            let history = ChatMessage::from_session(&daemon_messages(messages));
+                            let _ = tx.send(WorkerMessage::History(history));
+                        }
+                        send_daemon_status(&client, &session.session_id, &tx).await;
+                    }
+                    Err(e) => {
+                        let _ = tx.send(WorkerMessage::Error(e.to_string()));
+                    }
+                }
+            }
+            // Answers are taken by the turn waiting for them; this one is
+            // for a turn that has ended
+            UiMessage::ApproveTools(_) | UiMessage::DenyTools => {
+                let _ = tx.send(WorkerMessage::Done);
+            }
+            UiMessage::AttachFiles(paths) => {
+                for path in paths {
+                    match Attachment::load(&path) {
+                        Ok(attachment) => attachments.push(attachment),
+                        Err(e) => {
+                            let _ = tx.send(WorkerMessage::Error(format!("{:#}", e)));
+                        }
+                    }
+                }
+                let _ = tx.send(WorkerMessage::Attachments(attachment_infos(&attachments)));
+            }
+            UiMessage::RemoveAttachment(index) => {
+                if index < attachments.len() {
+                    attachments.remove(index);
+                }
+                let _ = tx.send(WorkerMessage::Attachments(attachment_infos(&attachments)));
+            }
+            UiMessage::SaveAttachment(_) | UiMessage::Resend { .. } => {
+                let _ = tx.send(WorkerMessage::SystemMessage(
+                    "Not available while attached to the daemon (run `homegpt desktop --local`)"
+                        .to_string(),
+                ));
+            }
+            UiMessage::Save => {
+                let _ = tx.send(WorkerMessage::SystemMessage(
+                    "The daemon saves sessions automatically.".to_string(),
+                ));
+            }
+            // Handled by `WorkerHandle::send` and the app; nothing is in
+            // flight here
+            UiMessage::CancelGeneration
+            | UiMessage::StopSpeaking
+            | UiMessage::Reconnect
+            | UiMessage::SwitchAgent(_)
+            | UiMessage::ShowPanel(_) => {}
+            UiMessage::RefreshSessions => send_daemon_sessions(&client, &tx).await,
+            UiMessage::SetModel(name) => {
+                let text = match client.set_model(&id, &name).await {
+                    Ok(()) => {
+                        session.model = name;
+                        format!("Model set to: {}", session.model)
+                    }
+                    Err(e) => format!("Failed to set model: {}", e),
+                };
+                let _ = tx.send(WorkerMessage::SystemMessage(text));
+            }
+            UiMessage::Compact => {
+                let text = match client.compact(&id).await {
+                    Ok((before, after)) => {
+                        format!("Session compacted: {} -> {} tokens", before, after)
+                    }
+                    Err(e) => format!("Compact failed: {}", e),
+                };
+                let _ = tx.send(WorkerMessage::SystemMessage(text));
+                send_daemon_status(&client, &id, &tx).await;
+            }
+            UiMessage::SearchMemory(query) => match client.search_memory(&query, 10).await {
+                Ok(results) => {
+                    let hits = memory_hits(results, &workspace);
+                    let _ = tx.send(WorkerMessage::MemoryResults { query, hits });
+                }
+                Err(e) => {
+                    let _ = tx.send(WorkerMessage::SystemMessage(format!(
+                        "Memory search failed: {}",
+                        e
+                    )));
+                }
+            },
+            UiMessage::LookupCitation(hash) => {
+                let card = match client.citation(&hash).await {
+                    Ok(found) => found.map(|found| CitationCard {
+                        path: workspace.join(&found.file),
+                        file: found.file,
+                        line_start: found.line_start,
+                        line_end: found.line_end,
+                        verified: found.verified,
+                        provenance: found.provenance.unwrap_or_else(|| "unknown".to_string()),
+                        confidence: found.confidence.unwrap_or_else(|| "unknown".to_string()),
+                    }),
+                    Err(e) => {
+                        let _ = tx.send(WorkerMessage::Error(format!(
+                            "Citation lookup failed: {}",
+                            e
+                        )));
+                        None
+                    }
+                };
+                let _ = tx.send(WorkerMessage::Citation { hash, card });
+            }
+            UiMessage::Reindex => {
+                let text = match client.reindex().await {
+                    Ok((files, chunks)) => format!(
+                        "Memory index updated: {} files changed, {} chunks",
+                        files, chunks
+                    ),
+                    Err(e) => format!("Reindex failed: {}", e),
+                };
+                let _ = tx.send(WorkerMessage::SystemMessage(text));
+            }
+            // The daemon's reindex finishes before `Reindex` returns
+            UiMessage::CancelIndexing => {}
+            UiMessage::ShowHelp => {
+                let _ = tx.send(WorkerMessage::SystemMessage(HELP_TEXT.to_string()));
+            }
+            UiMessage::SetVoice(arg) => voice.set(&id, arg, &config, &tx),
+            UiMessage::Transcribe { samples, .. } => {
+                let text = transcribe(&samples, &config, &tx).await;
+                let _ = tx.send(WorkerMessage::Heard(text));
+            }
+            // Voice only switches to people with a profile, which the
+            // daemon's sessions can't have
+            UiMessage::Enroll(_) => {
+                let _ = tx.send(WorkerMessage::Error(
+                    "Speaker identification needs the app to run its own agent (use --local)"
+                        .to_string(),
+                ));
+            }
+            UiMessage::ThisIs(_) => {}
+            UiMessage::Tutor(true) => voice.start_tutor(&id, chatting_as.clone(), &config, &tx),
+            UiMessage::Tutor(false) => {}
+            // Turned into `Chat` by `resolve_skill` and `resolve_task`
+            UiMessage::InvokeSkill { .. } | UiMessage::RunTask(_) => {}
+            // The daemon's sessions all run under its own profile
+            UiMessage::ChatAs {
+                profile: Some(_), ..
+            } => {
+                let _ = tx.send(WorkerMessage::Error(
+                    "Kiosk profiles need the app to run its own agent (use --local)".to_string(),
+                ));
+            }
+            UiMessage::ChatAs {
+                name,
+                profile: None,
+            } => match client.open_session(None).await {
+                Ok(opened) => {
+                    session = opened;
+                    let _ = tx.send(WorkerMessage::SessionChanged {
+                        id: session.session_id.clone(),
+                        message_count: 0,
+                    });
+                    chatting_as = Some(name.clone());
+                    let _ = tx.send(WorkerMessage::ChattingAs(Some(name)));
+                    send_daemon_status(&client, &session.session_id, &tx).await;
+                }
+                Err(e) => {
+                    let _ = tx.send(WorkerMessage::Error(e.to_string()));
+                }
+            },
+            UiMessage::LeaveKiosk => {
+                chatting_as = None;
+                let _ = tx.send(WorkerMessage::ChattingAs(None));
+            }
+            UiMessage::ShowStatus => match client.session_status(&id).await {
+                Ok(status) => {
+                    let status = session_status(status);
+                    let _ = tx.send(WorkerMessage::SystemMessage(status_text(&status)));
+                    let _ = tx.send(WorkerMessage::Status(status));
+                }
+                Err(e) => {
+                    let _ = tx.send(WorkerMessage::Error(e.to_string()));
+                }
+            },
+            UiMessage::InspectPrompt => match client.session_prompt(&id).await {
+                Ok(inspection) => {
+                    let _ = tx.send(WorkerMessage::Prompt(inspection));
+                }
+                Err(e) => {
+                    let _ = tx.send(WorkerMessage::Error(e.to_string()));
+                }
+            },
+            // Applied straight to the workspace; the daemon's file watcher
+            // reindexes it
+            UiMessage::ApproveChange(id) => {
+                let result = ChangeQueue::for_workspace(&workspace).and_then(|q| q.approve(id));
+                let _ = tx.send(match result {
+                    Ok(change) => WorkerMessage::SystemMessage(format!(
+                        "Approved change #{} to {}",
+                        id, change.path
+                    )),
+                    Err(e) => {
+                        WorkerMessage::Error(format!("Approving change #{} failed: {}", id, e))
+                    }
+                });
+            }
+            UiMessage::RejectChange(id) => {
+                let result = ChangeQueue::for_workspace(&workspace).and_then(|q| q.reject(id));
+                let _ = tx.send(match result {
+                    Ok(change) => WorkerMessage::SystemMessage(format!(
+                        "Rejected change #{} to {}",
+                        id, change.path
+                    )),
+                    Err(e) => {
+                        WorkerMessage::Error(format!("Rejecting change #{} failed: {}", id, e))
+                    }
+                });
+            }
+            UiMessage::SetDryRun(_) => {
+                let _ = tx.send(WorkerMessage::Error(
+                    "Dry run isn't available while attached to the daemon (use --local)"
+                        .to_string(),
+                ));
+            }
+            UiMessage::RenameSession { .. }
+            | UiMessage::PinSession { .. }
+            | UiMessage::TagSession { .. }
+            | UiMessage::DeleteSession { .. } => {
+                let _ = tx.send(WorkerMessage::Error(
+                    "Saved sessions can only be changed when running locally (use --local)"
+                        .to_string(),
+                ));
+            }
+            // Only offered when running locally; the daemon saves its sessions
+            UiMessage::RestoreSession | UiMessage::DismissRecovery => {}
+            UiMessage::Shutdown => break,
+        }
+    }
+
+    let _ = tx.send(WorkerMessage::Stopped);
     Ok(())
 }
+
+/// Send a message through the daemon and forward the streamed reply,
+/// reading it aloud if `spoken` is given. `context` replaces the session's
+/// system prompt addition.
+#[allow(clippy::too_many_arguments)]
+async fn stream_daemon_turn(
+    client: &DaemonClient,
+    session_id: &str,
+    message: &str,
+    mut spoken: Option<SpokenReply>,
+    context: &str,
+    inbox: &mut Inbox,
+    tx: &Sender<WorkerMessage>,
+    cancel: &CancelSignal,
+) {
+    cancel.reset();
+    let stream = match client
+        .chat_stream(session_id, message, Some(context), true)
+        .await
+    {
+        Ok(stream) => stream,
+        Err(e) => {
+            let _ = tx.send(WorkerMessage::Error(e.to_string()));
+            return;
+        }
+    };
+    let mut stream = pin!(stream);
+
+    loop {
+        // Dropping the stream closes the connection, which stops the turn
+        let event = tokio::select! {
+            biased;
+            event = stream.next() => match event {
+                Some(event) => event,
+                None => break,
+            },
+            _ = cancel.cancelled() => {
+                let _ = tx.send(WorkerMessage::Interrupted);
+                return;
+            }
+        };
+        let message = match event {
+            DaemonEvent::Content(text) => {
+                if let Some(spoken) = &mut spoken {
+                    spoken.push(&text);
+                }
+                WorkerMessage::ContentChunk(text)
+            }
+            DaemonEvent::ToolStart {
+                name,
+                id,
+                detail,
+                arguments,
+            } => WorkerMessage::ToolCallStart {
+                name,
+                id,
+                detail,
+                arguments,
+            },
+            DaemonEvent::ToolEnd { name, id, output } => {
+                WorkerMessage::ToolCallEnd { name, id, output }
+            }
+            DaemonEvent::Approval(approval) => {
+                let _ = tx.send(WorkerMessage::ToolsPendingApproval(vec![ToolCall {
+                    id: approval.id.clone(),
+                    name: approval.name.clone(),
+                    arguments: approval.arguments.clone(),
+                }]));
+                // The turn waits in the daemon until this is answered
+                let Some(approved) = inbox.wait_for_answer(cancel) else {
+                    let _ = tx.send(WorkerMessage::Interrupted);
+                    return;
+                };
+                if let Err(e) = client.answer_approval(&approval, approved).await {
+                    let _ = tx.send(WorkerMessage::Error(e.to_string()));
+                    return;
+                }
+                continue;
+            }
+            DaemonEvent::Done => {
+                if let Some(spoken) = spoken {
+                    spoken.finish();
+                }
+                let _ = tx.send(WorkerMessage::Done);
+                return;
+            }
+            DaemonEvent::Error(e) => {
+                let _ = tx.send(WorkerMessage::Error(e));
+                return;
+            }
+        };
+        let _ = tx.send(message);
+    }
+}
+
+/// Start a new session under `profile`'s approvals, quotas and reply checks
+async fn start_profile_session(
+    agent: &mut Agent,
+    profile: Option<&str>,
+    tx: &Sender<WorkerMessage>,
+) -> Result<()> {
+    agent.set_profile(profile)?;
+    agent.new_session().await?;
+    let status = agent.session_status();
+    let _ = tx.send(WorkerMessage::SessionChanged {
+        id: status.id.clone(),
+        message_count: status.message_count,
+    });
+    let _ = tx.send(WorkerMessage::Status(status));
+    Ok(())
+}
+
+/// Skills `/skill` can run, for completion in the input
+fn send_skills(config: &Config, agent_id: &str, tx: &Sender<WorkerMessage>) {
+    let skills = agent_skills(config, agent_id);
+    let names = skills
+        .into_iter()
+        .filter(|skill| skill.can_invoke())
+        .map(|skill| skill.command_name)
+        .collect();
+    let _ = tx.send(WorkerMessage::Skills(names));
+}
+
+/// `InvokeSkill` as the chat message that runs the skill; `None` (with an
+/// error shown) if there's no such skill. Other messages pass through.
+fn resolve_skill(
+    msg: UiMessage,
+    config: &Config,
+    agent_id: &str,
+    vars: &PromptVars,
+    tx: &Sender<WorkerMessage>,
+) -> Option<UiMessage> {
+    let UiMessage::InvokeSkill { name, args } = msg else {
+        return Some(msg);
+    };
+    let skills = agent_skills(config, agent_id);
+    let command = format!("/{} {}", name, args);
+    let found = parse_skill_command(&command, &skills)
+        .and_then(|invocation| skills.iter().find(|s| s.name == invocation.skill_name));
+    match found {
+        Some(skill) => Some(UiMessage::Chat(skill_prompt(skill, &args, vars))),
+        None => {
+            let _ = tx.send(WorkerMessage::Error(format!(
+                "No skill called '{}'; /skill lists them",
+                name
+            )));
+            None
+        }
+    }
+}
+
+/// `RunTask` as a chat message asking for that HEARTBEAT.md section to be
+/// done now; `None` (with an error shown) if there's no such section.
+/// Other messages pass through.
+fn resolve_task(msg: UiMessage, config: &Config, tx: &Sender<WorkerMessage>) -> Option<UiMessage> {
+    let UiMessage::RunTask(title) = msg else {
+        return Some(msg);
+    };
+    let content =
+        std::fs::read_to_string(config.workspace_path().join(HEARTBEAT_PATH)).unwrap_or_default();
+    match parse_tasks(&content)
+        .into_iter()
+        .find(|task| task.title == title)
+    {
+        Some(task) => Some(UiMessage::Chat(format!(
+            "Do this task from {} now, rather than waiting for its schedule:\n\n{}",
+            HEARTBEAT_PATH,
+            task.to_markdown()
+        ))),
+        None => {
+            let _ = tx.send(WorkerMessage::Error(format!(
+                "{} has no task called '{}'",
+                HEARTBEAT_PATH, title
+            )));
+            None
+        }
+    }
+}
+
+/// The skills `agent_id` loads, as many as its persona in agents.toml allows
+fn agent_skills(config: &Config, agent_id: &str) -> Vec<Skill> {
+    let persona = load_agent(&config.memory, agent_id).ok().flatten();
+    load_agent_skills(&config.workspace_path(), persona.as_ref()).unwrap_or_default()
+}
+
+/// Replies read aloud: the speaker, the sessions that have it on, and the
+/// one being tutored hands-free
+struct Voice {
+    speech: Option<Speech>,
+    sessions: HashSet<String>,
+    barge_in: BargeIn,
+    tutor: Option<Tutor>,
+}
+
+/// Hands-free tutoring in one session
+struct Tutor {
+    session_id: String,
+    /// Who's being tutored (kiosk mode's person), for the notes
+    student: Option<String>,
+    /// Added to the system prompt instead of `VOICE_CONTEXT`
+    context: String,
+    /// Voice was already on, so it stays on afterwards
+    voiced: bool,
+}
+
+impl Voice {
+    fn new(barge_in: BargeIn) -> Self {
+        Self {
+            speech: None,
+            sessions: HashSet::new(),
+            barge_in,
+            tutor: None,
+        }
+    }
+
+    /// Added to the system prompt for a turn in `session_id`
+    fn context(&self, session_id: &str) -> Option<&str> {
+        match self.tutor {
+            Some(ref tutor) if tutor.session_id == session_id => Some(&tutor.context),
+            _ if self.sessions.contains(session_id) => Some(VOICE_CONTEXT),
+            _ => None,
+        }
+    }
+
+    /// Start hands-free tutoring in `session_id`, reading its replies aloud
+    fn start_tutor(
+        &mut self,
+        session_id: &str,
+        student: Option<String>,
+        config: &Config,
+        tx: &Sender<WorkerMessage>,
+    ) {
+        if self
+            .tutor
+            .as_ref()
+            .is_some_and(|tutor| tutor.session_id == session_id)
+        {
+            return;
+        }
+        let voiced = self.sessions.contains(session_id);
+        if !voiced {
+            self.set(session_id, Some("on".to_string()), config, tx);
+        }
+        self.tutor = Some(Tutor {
+            session_id: session_id.to_string(),
+            context: tutor_context(config, student.as_deref()),
+            student,
+            voiced,
+        });
+        let _ = tx.send(WorkerMessage::Tutoring(Some(session_id.to_string())));
+        let how = match config.desktop.wake_word {
+            Some(ref wake_word) => format!("say \"{}\" and ask a question", wake_word),
+            None => "just talk".to_string(),
+        };
+        let _ = tx.send(WorkerMessage::SystemMessage(format!(
+            "Hands-free tutoring on: {}. The session is logged to {} when it ends.",
+            how, TUTOR_NOTES_PATH
+        )));
+    }
+
+    /// End hands-free tutoring; the mic closes right away, and the session
+    /// is logged before `end_tutor`
+    fn take_tutor(&mut self, tx: &Sender<WorkerMessage>) -> Option<Tutor> {
+        let tutor = self.tutor.take()?;
+        let _ = tx.send(WorkerMessage::Tutoring(None));
+        Some(tutor)
+    }
+
+    fn end_tutor(
+        &mut self,
+        tutor: Tutor,
+        logged: Result<Option<FileWrite>>,
+        config: &Config,
+        tx: &Sender<WorkerMessage>,
+    ) {
+        if !tutor.voiced {
+            self.set(&tutor.session_id, Some("off".to_string()), config, tx);
+        }
+        let message = match logged {
+            Ok(Some(FileWrite::Written)) => format!(
+                "Hands-free tutoring off. Notes on the session were added to {}.",
+                TUTOR_NOTES_PATH
+            ),
+            Ok(Some(FileWrite::Staged(id))) => format!(
+                "Hands-free tutoring off. Notes on the session are waiting for review (change #{}).",
+                id
+            ),
+            Ok(None) => "Hands-free tutoring off.".to_string(),
+            Err(e) => {
+                let _ = tx.send(WorkerMessage::Error(format!(
+                    "Couldn't log the tutoring session: {}",
+                    e
+                )));
+                return;
+            }
+        };
+        let _ = tx.send(WorkerMessage::SystemMessage(message));
+    }
+
+    /// Say `text` if `session_id`'s replies are read aloud
+    fn say(&self, session_id: &str, text: &str) {
+        if let Some(mut reply) = self.reply(session_id) {
+            reply.push(text);
+            reply.finish();
+        }
+    }
+
+    /// Reader for a reply in `session_id`, if its replies are read aloud
+    fn reply(&self, session_id: &str) -> Option<SpokenReply> {
+        if !self.sessions.contains(session_id) {
+            return None;
+        }
+        self.speech.as_ref().map(Speech::reply)
+    }
+
+    /// `/voice` for `session_id`: "on", "off", a speaker name, or nothing
+    /// to switch it over
+    fn set(
+        &mut self,
+        session_id: &str,
+        arg: Option<String>,
+        config: &Config,
+        tx: &Sender<WorkerMessage>,
+    ) {
+        let (on, device) = match arg.as_deref() {
+            None => (!self.sessions.contains(session_id), None),
+            Some("on") => (true, None),
+            Some("off") => (false, None),
+            Some(name) => (true, Some(name.to_string())),
+        };
+        if !on {
+            self.sessions.remove(session_id);
+            self.barge_in.stop();
+            let _ = tx.send(WorkerMessage::SystemMessage("Voice off.".to_string()));
+            self.send_sessions(tx);
+            return;
+        }
+        // Start on the first speaker, or switch to the one named
+        let switch = device.as_deref().is_some_and(|name| {
+            self.speech
+                .as_ref()
+                .is_none_or(|speech| !speech.speaker().eq_ignore_ascii_case(name))
+        });
+        if self.speech.is_none() || switch {
+            let Some(speech) = self.start(device, config, tx) else {
+                return;
+            };
+            self.speech = Some(speech);
+        }
+        self.sessions.insert(session_id.to_string());
+        let speaker = self
+            .speech
+            .as_ref()
+            .map(Speech::speaker)
+            .unwrap_or_default();
+        let _ = tx.send(WorkerMessage::SystemMessage(format!(
+            "Voice on: replies in this session are read aloud on {}.",
+            speaker
+        )));
+        self.send_sessions(tx);
+    }
+
+    /// Speech on `device`, or the first speaker in `announce.devices`
+    fn start(
+        &self,
+        device: Option<String>,
+        config: &Config,
+        tx: &Sender<WorkerMessage>,
+    ) -> Option<Speech> {
+        let announcer = get_state_dir().and_then(|dir| Announcer::from_config(config, &dir));
+        let announcer = match announcer {
+            Ok(Some(announcer)) => announcer,
+            Ok(None) => {
+                let _ = tx.send(WorkerMessage::Error(
+                    "No speakers configured; add one under [[announce.devices]]".to_string(),
+                ));
+                return None;
+            }
+            Err(e) => {
+                let _ = tx.send(WorkerMessage::Error(format!("Voice unavailable: {}", e)));
+                return None;
+            }
+        };
+        let names = announcer.device_names();
+        let speaker = match device {
+            Some(ref name) => names.iter().find(|n| n.eq_ignore_ascii_case(name)).copied(),
+            None => names.first().copied(),
+        };
+        let Some(speaker) = speaker.map(String::from) else {
+            let _ = tx.send(WorkerMessage::Error(format!(
+                "No speaker called '{}' (have: {})",
+                device.unwrap_or_default(),
+                names.join(", ")
+            )));
+            return None;
+        };
+        Some(Speech::start(
+            announcer,
+            speaker,
+            self.barge_in.clone(),
+            tx.clone(),
+        ))
+    }
+
+    fn send_sessions(&self, tx: &Sender<WorkerMessage>) {
+        let sessions = self.sessions.iter().cloned().collect();
+        let _ = tx.send(WorkerMessage::VoicedSessions(sessions));
+    }
+}
+
+/// What was said into the mic; empty (with the error shown) if it
+/// couldn't be made out
+async fn transcribe(samples: &[i16], config: &Config, tx: &Sender<WorkerMessage>) -> String {
+    let heard = match Transcriber::from_config(config) {
+        Ok(transcriber) => transcriber.transcribe(samples).await,
+        Err(e) => Err(e),
+    };
+    heard.unwrap_or_else(|e| {
+        let _ = tx.send(WorkerMessage::Error(format!(
+            "Couldn't transcribe the recording: {}",
+            e
+        )));
+        String::new()
+    })
+}
+
+/// Pass on what the mic heard, first starting the session of the person
+/// who said it. Tutoring and voice carry over to their session. Returns
+/// their name if it switched.
+async fn hear(
+    heard: Heard,
+    agent: &mut Agent,
+    voice: &mut Voice,
+    config: &Config,
+    tx: &Sender<WorkerMessage>,
+) -> Option<String> {
+    let session_id = agent.session_status().id;
+    if heard.asked {
+        voice.say(&session_id, WHO_IS_THIS);
+    }
+    let mut switched = None;
+    if let Some(person) = heard.switch_to {
+        let tutored = match voice.take_tutor(tx) {
+            Some(tutor) => {
+                let logged = agent.log_tutor_session(tutor.student.as_deref()).await;
+                voice.end_tutor(tutor, logged, config, tx);
+                true
+            }
+            None => false,
+        };
+        let voiced = voice.sessions.contains(&session_id);
+        match start_profile_session(agent, person.profile.as_deref(), tx).await {
+            Ok(()) => {
+                let id = agent.session_status().id;
+                let _ = tx.send(WorkerMessage::ChattingAs(Some(person.name.clone())));
+                if voiced {
+                    voice.set(&id, Some("on".to_string()), config, tx);
+                }
+                if tutored {
+                    voice.start_tutor(&id, Some(person.name.clone()), config, tx);
+                }
+                switched = Some(person.name);
+            }
+            Err(e) => {
+                let _ = tx.send(WorkerMessage::Error(format!(
+                    "Couldn't start {}'s session: {}",
+                    person.name, e
+                )));
+            }
+        }
+    }
+    let _ = tx.send(WorkerMessage::Heard(heard.text));
+    switched
+}
+
+/// Reader for this turn's reply if the session has voice on, telling the
+/// agent its reply will be heard and who it's talking with
+fn spoken_reply(
+    agent: &mut Agent,
+    voice: &Voice,
+    config: &Config,
+    chatting_as: Option<&str>,
+) -> Option<SpokenReply> {
+    let session_id = agent.session_status().id;
+    agent.set_additional_context(turn_context(config, chatting_as, voice, &session_id));
+    voice.reply(&session_id)
+}
+
+/// Added to the system prompt for a turn in `session_id`: who's chatting
+/// (kiosk mode's person, and their persona), then voice or tutoring
+fn turn_context(
+    config: &Config,
+    chatting_as: Option<&str>,
+    voice: &Voice,
+    session_id: &str,
+) -> Option<String> {
+    let person = chatting_as.map(|name| {
+        let persona = config
+            .desktop
+            .people
+            .iter()
+            .find(|person| person.name == name)
+            .and_then(|person| person.persona.as_deref());
+        match persona {
+            Some(persona) => format!("You're talking with {}. {}", name, persona.trim()),
+            None => format!("You're talking with {}.", name),
+        }
+    });
+    let parts: Vec<&str> = person
+        .as_deref()
+        .into_iter()
+        .chain(voice.context(session_id))
+        .collect();
+    (!parts.is_empty()).then(|| parts.join("\n\n"))
+}
+
+/// Hands-free tutoring's addition to the system prompt: spoken replies, who
+/// is being tutored, and the workspace's tutor skill
+fn tutor_context(config: &Config, student: Option<&str>) -> String {
+    let mut context = VOICE_CONTEXT.to_string();
+    if let Some(student) = student {
+        context.push_str(&format!(" You're tutoring {}.", student));
+    }
+    let skills = load_skills(&config.workspace_path()).unwrap_or_default();
+    let skill = skills
+        .iter()
+        .find(|skill| skill.name == "tutor")
+        .and_then(|skill| std::fs::read_to_string(&skill.path).ok())
+        .map(|skill| render_template("tutor", &skill, &PromptVars::new(config, None, None)));
+    if let Some(skill) = skill {
+        context.push_str("\n\n");
+        context.push_str(skill.trim());
+    }
+    context
+}
+
+/// Messages that end hands-free tutoring (and so the tutored session)
+fn ends_tutoring(msg: &UiMessage) -> bool {
+    matches!(
+        msg,
+        UiMessage::Tutor(false)
+            | UiMessage::NewSession
+            | UiMessage::ResumeSession(_)
+            | UiMessage::RestoreSession
+            | UiMessage::ChatAs { .. }
+            | UiMessage::LeaveKiosk
+            | UiMessage::Shutdown
+    )
+}
+
+/// Saved sessions of every agent, for the sessions panel
+fn send_saved_sessions(tx: &Sender<WorkerMessage>) {
+    if let Ok(sessions) = list_all_sessions() {
+        let _ = tx.send(WorkerMessage::Sessions(sessions));
+    }
+}
+
+/// Change a saved session's name, pin or tags, and resend the list
+fn update_saved_session(
+    agent_id: &str,
+    session_id: &str,
+    f: impl FnOnce(&mut SessionEntry),
+    tx: &Sender<WorkerMessage>,
+) {
+    let result = SessionStore::load_for_agent(agent_id)
+        .and_then(|mut store| store.update_session(session_id, f));
+    if let Err(e) = result {
+        let _ = tx.send(WorkerMessage::Error(format!(
+            "Updating session failed: {}",
+            e
+        )));
+    }
+    send_saved_sessions(tx);
+}
+
+/// The daemon's active sessions, for the sessions panel
+async fn send_daemon_sessions(client: &DaemonClient, tx: &Sender<WorkerMessage>) {
+    if let Ok(sessions) = client.sessions().await {
+        let now = chrono::Utc::now();
+        let sessions = sessions
+            .into_iter()
+            .map(|s| SessionInfo {
+                id: s.session_id,
+                // Shows when the session was last used
+                created_at: now - chrono::Duration::seconds(s.idle_seconds as i64),
+                agent_id: DAEMON_AGENT_ID.to_string(),
+                message_count: s.message_count,
+                ..SessionInfo::default()
+            })
+            .collect();
+        let _ = tx.send(WorkerMessage::Sessions(sessions));
+    }
+}
+
+/// A daemon session's messages as the agent keeps them
+fn daemon_messages(messages: Vec<DaemonSessionMessage>) -> Vec<Message> {
+    messages
+        .into_iter()
+        .filter_map(|m| {
+            let role = match m.role.as_str() {
+                "user" => Role::User,
+                "assistant" => Role::Assistant,
+                "toolResult" => Role::Tool,
+                _ => return None,
+            };
+            Some(Message {
+                role,
+                content: m.content.unwrap_or_default(),
+                tool_calls: m.tool_calls,
+                tool_call_id: m.tool_call_id,
+                images: Vec::new(),
+            })
+        })
+        .collect()
+}
+
+async fn send_daemon_status(client: &DaemonClient, session_id: &str, tx: &Sender<WorkerMessage>) {
+    if let Ok(status) = client.session_status(session_id).await {
+        let _ = tx.send(WorkerMessage::Status(session_status(status)));
+    }
+}
+
+/// Status panel details both modes read the same way: today's tokens from
+/// the shared usage counts and task runs from the heartbeat's run log
+fn base_overview(config: &Config) -> StatusOverview {
+    let now = locale::now().naive_local();
+    StatusOverview {
+        embedding_provider: config.memory.embedding_provider.clone(),
+        tokens_today: UsageLimits::from_config(config)
+            .map(|limits| limits.tokens_today(now))
+            .unwrap_or(0),
+        task_runs: get_state_dir()
+            .map(|dir| RunLog::open(&dir).last_runs())
+            .unwrap_or_default(),
+        ..StatusOverview::default()
+    }
+}
+
+/// Status panel details from this process's memory index, and the
+/// daemon's heartbeat if one is running
+async fn local_overview(config: &Config, memory: &MemoryManager) -> StatusOverview {
+    let mut overview = base_overview(config);
+    overview.memory_chunks = memory.chunk_count().unwrap_or(0);
+    overview.categories = memory.category_counts().unwrap_or_default();
+    overview.provenance = memory
+        .verification_stats()
+        .map(|stats| stats.by_provenance)
+        .unwrap_or_default();
+    overview.embedding_model = memory.embedding_model();
+    if let Some(client) = DaemonClient::connect(config).await {
+        overview.daemon = DaemonLink::Running;
+        overview.heartbeat = daemon_heartbeat(&client).await;
+    }
+    overview
+}
+
+/// Status panel details from the daemon
+async fn attached_overview(config: &Config, client: &DaemonClient) -> StatusOverview {
+    let mut overview = base_overview(config);
+    match client.memory_stats().await {
+        Ok(stats) => {
+            overview.daemon = DaemonLink::Attached;
+            overview.memory_chunks = stats.total_chunks;
+            overview.categories = stats.categories;
+            overview.provenance = stats.provenance;
+            overview.embedding_model = stats.embedding_model;
+            overview.heartbeat = daemon_heartbeat(client).await;
+        }
+        Err(_) => overview.daemon = DaemonLink::Unreachable,
+    }
+    overview
+}
+
+async fn daemon_heartbeat(client: &DaemonClient) -> Option<HeartbeatRun> {
+    let event = client.heartbeat_status().await.ok()?.last_event?;
+    Some(HeartbeatRun {
+        status: event.status,
+        age_seconds: event.age_seconds,
+        detail: event.preview.or(event.reason),
+    })
+}
+
+fn session_status(status: DaemonSessionStatus) -> SessionStatus {
+    SessionStatus {
+        id: status.session_id,
+        message_count: status.message_count,
+        token_count: status.token_count,
+        compaction_count: 0,
+        api_input_tokens: status.api_input_tokens,
+        api_output_tokens: status.api_output_tokens,
+        api_cache_read_tokens: status.api_cache_read_tokens,
+        api_cache_write_tokens: status.api_cache_write_tokens,
+    }
+}
+
+fn status_text(status: &SessionStatus) -> String {
+    format!(
+        "Session: {}\nMessages: {}\nTokens: {} context / {} API in / {} API out\nCompactions: {}",
+        &status.id[..8.min(status.id.len())],
+        status.message_count,
+        status.token_count,
+        status.api_input_tokens,
+        status.api_output_tokens,
+        status.compaction_count,
+    )
+}
+
+/// Search results as the chat view's cards, from the local index or the
+/// daemon
+fn memory_hits(results: Vec<VerifiedChunk>, workspace: &Path) -> Vec<MemoryHit> {
+    results
+        .into_iter()
+        .map(|chunk| MemoryHit {
+            hash: chunk.hash_prefix,
+            card: CitationCard {
+                path: workspace.join(&chunk.file),
+                file: chunk.file,
+                line_start: chunk.line_start,
+                line_end: chunk.line_end,
+                verified: chunk.verified,
+                provenance: chunk.provenance.to_string(),
+                confidence: chunk.confidence.to_string(),
+            },
+            score: chunk.score,
+            stale: chunk.stale,
+            content: chunk.content,
+        })
+        .collect()
+}
+
+fn attachment_infos(attachments: &[Attachment]) -> Vec<AttachmentInfo> {
+    attachments
+        .iter()
+        .map(|a| AttachmentInfo {
+            label: a.describe(),
+            is_text: matches!(a, Attachment::Text { .. }),
+        })
+        .collect()
+}
@@ -0,0 +1,254 @@
+//! Transaction categorization: `finance.rules` first, then an LLM for
+//! whatever no rule matched
+//!
+//! Rules are case-insensitive substrings of the description, checked in
+//! config order. Unmatched money received is income; unmatched spending is
+//! sent to the LLM in batches with the list of allowed categories, so the
+//! summaries don't fill up with near-synonyms ("food", "grocery").
+
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use tracing::{debug, warn};
+
+use super::{Transaction, INCOME, TRANSFER, UNCATEGORIZED};
+use crate::agent::{chat_structured, create_provider, ChatProvider, Message, Role};
+use crate::config::{CategoryRule, Config};
+
+/// Categories offered to the LLM besides those in budgets and rules
+pub const DEFAULT_CATEGORIES: &[&str] = &[
+    "groceries",
+    "dining",
+    "fuel",
+    "transport",
+    "housing",
+    "utilities",
+    "insurance",
+    "healthcare",
+    "kids",
+    "school",
+    "shopping",
+    "entertainment",
+    "subscriptions",
+    "travel",
+    "gifts",
+    "fees",
+    TRANSFER,
+];
+
+/// Transactions per LLM request
+const LLM_BATCH_SIZE: usize = 40;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct Assignments {
+    assignments: Vec<Assignment>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct Assignment {
+    /// Number of the transaction in the list
+    index: usize,
+    /// One of the allowed categories
+    category: String,
+}
+
+pub struct Categorizer {
+    rules: Vec<CategoryRule>,
+    /// Allowed categories, lowercase
+    categories: BTreeSet<String>,
+    provider: Option<Box<dyn ChatProvider>>,
+}
+
+impl Categorizer {
+    /// Categorizer from `finance` config, with the LLM fallback unless
+    /// `finance.llm_categorize` is off or the model is unavailable
+    pub fn from_config(config: &Config) -> Self {
+        let finance = &config.finance;
+        let mut categorizer = Self::rules_only(&finance.rules);
+        categorizer
+            .categories
+            .extend(finance.budgets.keys().map(|c| c.to_lowercase()));
+
+        if finance.llm_categorize {
+            let model = finance
+                .categorize_model
+                .as_deref()
+                .unwrap_or(&config.agent.default_model);
+            match create_provider(model, config) {
+                Ok(provider) => categorizer.provider = Some(provider),
+                Err(e) => warn!(
+                    "Categorization model {} unavailable, using rules only: {}",
+                    model, e
+                ),
+            }
+        }
+        categorizer
+    }
+
+    /// Categorizer that only applies `rules`
+    pub fn rules_only(rules: &[CategoryRule]) -> Self {
+        let mut categories: BTreeSet<String> =
+            DEFAULT_CATEGORIES.iter().map(|c| c.to_string()).collect();
+        categories.extend(rules.iter().map(|r| r.category.to_lowercase()));
+        Self {
+            rules: rules.to_vec(),
+            categories,
+            provider: None,
+        }
+    }
+
+    fn rule_category(&self, description: &str) -> Option<String> {
+        let description = description.to_lowercase();
+        self.rules
+            .iter()
+            .find(|r| description.contains(&r.pattern.to_lowercase()))
+            .map(|r| r.category.to_lowercase())
+    }
+
+    /// Set the category of every transaction still [`UNCATEGORIZED`]
+    pub async fn categorize(&self, transactions: &mut [Transaction]) {
+        let mut unmatched = Vec::new();
+        for (i, t) in transactions.iter_mut().enumerate() {
+            if t.category != UNCATEGORIZED {
+                continue;
+            }
+            if let Some(category) = self.rule_category(&t.description) {
+                t.category = category;
+            } else if t.amount > 0.0 {
+                t.category = INCOME.to_string();
+            } else {
+                unmatched.push(i);
+            }
+        }
+
+        let Some(ref provider) = self.provider else {
+            return;
+        };
+        for batch in unmatched.chunks(LLM_BATCH_SIZE) {
+            let lines: Vec<String> = batch
+                .iter()
+                .enumerate()
+                .map(|(n, &i)| {
+                    let t = &transactions[i];
+                    format!("{}. {} ({:.2})", n + 1, t.description, -t.amount)
+                })
+                .collect();
+
+            match self.ask(provider.as_ref(), &lines).await {
+                Ok(assigned) => {
+                    for (n, category) in assigned {
+                        if let Some(&i) = batch.get(n) {
+                            transactions[i].category = category;
+                        }
+                    }
+                }
+                Err(e) => warn!("LLM categorization failed: {}", e),
+            }
+        }
+    }
+
+    /// Ask the LLM for categories, returning (batch position, category)
+    /// pairs for answers in the allowed list
+    async fn ask(
+        &self,
+        provider: &dyn ChatProvider,
+        lines: &[String],
+    ) -> Result<Vec<(usize, String)>> {
+        let categories: Vec<&str> = self.categories.iter().map(String::as_str).collect();
+        let prompt = format!(
+            "Categorize these household bank transactions (description and amount spent). \
+             Use only these categories: {}. Use \"{}\" if none fits.\n\n{}",
+            categories.join(", "),
+            UNCATEGORIZED,
+            lines.join("\n")
+        );
+        let messages = [Message {
+            role: Role::User,
+            content: prompt,
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+        }];
+
+        let response: Assignments = chat_structured(provider, &messages).await?;
+        let assigned = self.accept(response, lines.len());
+        debug!(
+            "LLM categorized {} of {} transactions",
+            assigned.len(),
+            lines.len()
+        );
+        Ok(assigned)
+    }
+
+    /// Keep assignments with a valid index and an allowed category
+    fn accept(&self, response: Assignments, count: usize) -> Vec<(usize, String)> {
+        response
+            .assignments
+            .into_iter()
+            .filter(|a| a.index >= 1 && a.index <= count)
+            .map(|a| (a.index - 1, a.category.trim().to_lowercase()))
+            .filter(|(_, category)| self.categories.contains(category))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn transaction(description: &str, amount: f64) -> Transaction {
+        Transaction {
+            date: NaiveDate::from_ymd_opt(2026, 3, 2).unwrap(),
+            description: description.to_string(),
+            amount,
+            category: UNCATEGORIZED.to_string(),
+            account: String::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rules_then_income() {
+        let rules = vec![CategoryRule {
+            pattern: "costco".to_string(),
+            category: "Groceries".to_string(),
+        }];
+        let categorizer = Categorizer::rules_only(&rules);
+        let mut transactions = vec![
+            transaction("COSTCO WHSE #123", -184.10),
+            transaction("PAYROLL ACME", 3200.0),
+            transaction("MYSTERY SHOP", -12.0),
+        ];
+        categorizer.categorize(&mut transactions).await;
+
+        assert_eq!(transactions[0].category, "groceries");
+        assert_eq!(transactions[1].category, INCOME);
+        assert_eq!(transactions[2].category, UNCATEGORIZED);
+    }
+
+    #[test]
+    fn test_accept_filters_unknown_categories() {
+        let categorizer = Categorizer::rules_only(&[]);
+        let response = Assignments {
+            assignments: vec![
+                Assignment {
+                    index: 1,
+                    category: "Dining".to_string(),
+                },
+                Assignment {
+                    index: 2,
+                    category: "snacks".to_string(),
+                },
+                Assignment {
+                    index: 9,
+                    category: "fuel".to_string(),
+                },
+            ],
+        };
+        assert_eq!(
+            categorizer.accept(response, 2),
+            vec![(0, "dining".to_string())]
+        );
+    }
+}
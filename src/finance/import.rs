@@ -0,0 +1,208 @@
+//! Bank and credit card CSV export parsing
+//!
+//! Exports differ per bank, so columns are found by header name: a date
+//! column, a description (payee, merchant, memo...) and either a signed
+//! amount or separate debit/credit columns. Preamble rows above the header
+//! (account number, statement period) are skipped.
+
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+
+use super::{Transaction, UNCATEGORIZED};
+
+/// Date formats tried when `finance.date_format` is not set (US month/day
+/// order first, as most bank exports use it)
+const DATE_FORMATS: &[&str] = &[
+    "%Y-%m-%d",
+    "%m/%d/%Y",
+    "%m/%d/%y",
+    "%Y/%m/%d",
+    "%d.%m.%Y",
+    "%d %b %Y",
+    "%b %d, %Y",
+];
+
+/// Description columns, in order of preference
+const DESCRIPTION_HEADERS: &[&str] = &[
+    "description",
+    "payee",
+    "merchant",
+    "name",
+    "details",
+    "narrative",
+    "memo",
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct ImportOptions {
+    /// Account name stored with each transaction (e.g. "visa")
+    pub account: String,
+    /// Flip the sign of amounts (card exports that list purchases as positive)
+    pub invert: bool,
+    /// chrono format for the date column (default: try common formats)
+    pub date_format: Option<String>,
+}
+
+/// Column positions found in the header row
+struct Columns {
+    date: usize,
+    description: usize,
+    amount: Amount,
+}
+
+enum Amount {
+    Signed(usize),
+    DebitCredit {
+        debit: Option<usize>,
+        credit: Option<usize>,
+    },
+}
+
+impl Columns {
+    fn from_header(header: &[String]) -> Option<Self> {
+        let find = |wanted: &dyn Fn(&str) -> bool| header.iter().position(|h| wanted(h.as_str()));
+
+        let date = find(&|h| h == "date" || h == "transaction date")
+            .or_else(|| find(&|h| h.contains("date")))?;
+        let description = DESCRIPTION_HEADERS
+            .iter()
+            .find_map(|wanted| find(&|h| h == *wanted))
+            .or_else(|| {
+                DESCRIPTION_HEADERS
+                    .iter()
+                    .find_map(|wanted| find(&|h| h.contains(wanted)))
+            })?;
+
+        let amount = match find(&|h| h.contains("amount") && !h.contains("balance")) {
+            Some(column) => Amount::Signed(column),
+            None => {
+                let debit = find(&|h| h.contains("debit") || h.contains("withdrawal"));
+                let credit = find(&|h| h.contains("credit") || h.contains("deposit"));
+                if debit.is_none() && credit.is_none() {
+                    return None;
+                }
+                Amount::DebitCredit { debit, credit }
+            }
+        };
+
+        Some(Self {
+            date,
+            description,
+            amount,
+        })
+    }
+}
+
+/// Parse a CSV export into uncategorized transactions
+pub fn parse_statement(data: &[u8], options: &ImportOptions) -> Result<Vec<Transaction>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(data);
+
+    let mut columns: Option<Columns> = None;
+    let mut transactions = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let fields: Vec<&str> = record.iter().map(str::trim).collect();
+
+        let Some(ref cols) = columns else {
+            let header: Vec<String> = fields.iter().map(|f| f.to_lowercase()).collect();
+            columns = Columns::from_header(&header);
+            continue;
+        };
+
+        let field = |index: usize| fields.get(index).copied().unwrap_or("");
+        if field(cols.date).is_empty() {
+            continue;
+        }
+
+        let line = record.position().map_or(0, |p| p.line());
+        let date = parse_date(field(cols.date), options.date_format.as_deref())
+            .with_context(|| format!("Line {}: unrecognized date '{}'", line, field(cols.date)))?;
+        let amount = match cols.amount {
+            Amount::Signed(column) => parse_amount(field(column)),
+            Amount::DebitCredit { debit, credit } => {
+                let debit = debit.and_then(|c| parse_amount(field(c))).unwrap_or(0.0);
+                let credit = credit.and_then(|c| parse_amount(field(c))).unwrap_or(0.0);
+                Some(credit - debit.abs())
+            }
+        }
+        .with_context(|| format!("Line {}: no amount", line))?;
+
+        transactions.push(Transaction {
+            date,
+            description: field(cols.description).to_string(),
+            amount: if options.invert { -amount } else { amount },
+            category: UNCATEGORIZED.to_string(),
+            account: options.account.clone(),
+        });
+    }
+
+    if columns.is_none() {
+        anyhow::bail!("No header row with date, description and amount columns found");
+    }
+    Ok(transactions)
+}
+
+fn parse_date(value: &str, format: Option<&str>) -> Option<NaiveDate> {
+    match format {
+        Some(format) => NaiveDate::parse_from_str(value, format).ok(),
+        None => DATE_FORMATS
+            .iter()
+            .find_map(|format| NaiveDate::parse_from_str(value, format).ok()),
+    }
+}
+
+/// "$1,204.50", "-12.00", "(12.00)" (accounting negative)
+fn parse_amount(value: &str) -> Option<f64> {
+    let negative = value.starts_with('(') && value.ends_with(')');
+    let cleaned: String = value
+        .chars()
+        .filter(|c| c.is_ascii_digit() || *c == '.' || *c == '-')
+        .collect();
+    if cleaned.is_empty() {
+        return None;
+    }
+    let amount: f64 = cleaned.parse().ok()?;
+    Some(if negative { -amount } else { amount })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_signed_amounts_with_preamble() {
+        let csv = "Account,****1234\n\nDate,Description,Amount,Balance\n\
+                   03/02/2026,COSTCO WHSE #123,\"-$1,184.10\",900.00\n\
+                   03/05/2026,PAYROLL ACME,3200.00,4100.00\n";
+        let options = ImportOptions {
+            account: "checking".to_string(),
+            ..Default::default()
+        };
+        let transactions = parse_statement(csv.as_bytes(), &options).unwrap();
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0].date.to_string(), "2026-03-02");
+        assert_eq!(transactions[0].description, "COSTCO WHSE #123");
+        assert_eq!(transactions[0].amount, -1184.10);
+        assert_eq!(transactions[1].amount, 3200.0);
+        assert_eq!(transactions[1].account, "checking");
+    }
+
+    #[test]
+    fn test_parse_debit_credit_columns() {
+        let csv =
+            "Posted Date,Payee,Debit,Credit\n2026-03-04,Shell,48.00,\n2026-03-06,Refund,,5.00\n";
+        let transactions = parse_statement(csv.as_bytes(), &ImportOptions::default()).unwrap();
+        assert_eq!(transactions[0].amount, -48.0);
+        assert_eq!(transactions[1].amount, 5.0);
+
+        let options = ImportOptions {
+            date_format: Some("%d/%m/%Y".to_string()),
+            ..Default::default()
+        };
+        assert!(parse_statement(csv.as_bytes(), &options).is_err());
+        assert!(parse_statement(b"foo,bar\n1,2\n", &ImportOptions::default()).is_err());
+    }
+}
@@ -0,0 +1,594 @@
+//! Household finance tracking
+//!
+//! Bank and credit card CSV exports are imported into one ledger CSV per
+//! month under `memory/finance/transactions/` (overlapping exports are
+//! deduplicated), categorized by `finance.rules` with an LLM fallback, and
+//! summarized into `memory/finance/<YYYY-MM>.md` so spending questions can
+//! be answered from memory. Budgets come from `finance.budgets`; the
+//! heartbeat writes a report for each finished month to
+//! `memory/finance/reports/`.
+
+mod categorize;
+mod import;
+
+pub use categorize::{Categorizer, DEFAULT_CATEGORIES};
+pub use import::{parse_statement, ImportOptions};
+
+use anyhow::{Context, Result};
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::FinanceConfig;
+use crate::locale;
+
+/// Monthly summaries (relative to workspace)
+pub const FINANCE_DIR: &str = "memory/finance";
+
+/// Per-month transaction ledgers (relative to workspace)
+const TRANSACTIONS_DIR: &str = "memory/finance/transactions";
+
+/// Monthly reports written by the heartbeat (relative to workspace)
+const REPORTS_DIR: &str = "memory/finance/reports";
+
+/// Category for money received
+pub const INCOME: &str = "income";
+
+/// Category for moves between the household's own accounts (not spending)
+pub const TRANSFER: &str = "transfer";
+
+/// Category when neither a rule nor the LLM could place a transaction
+pub const UNCATEGORIZED: &str = "uncategorized";
+
+/// A single bank or card transaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Transaction {
+    pub date: NaiveDate,
+    pub description: String,
+    /// Negative for money spent, positive for money received
+    pub amount: f64,
+    pub category: String,
+    pub account: String,
+}
+
+impl Transaction {
+    /// Key for spotting the same transaction in overlapping exports
+    fn key(&self) -> String {
+        let key = format!(
+            "{}|{}|{:.2}|{}",
+            self.date,
+            self.description.to_lowercase(),
+            self.amount,
+            self.account
+        );
+        format!("{:x}", Sha256::digest(key.as_bytes()))
+    }
+
+    /// Ledger month, e.g. "2026-03"
+    pub fn month(&self) -> String {
+        self.date.format("%Y-%m").to_string()
+    }
+}
+
+/// Result of adding transactions to the ledger
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    pub added: usize,
+    /// Already in the ledger from an earlier (overlapping) import
+    pub duplicates: usize,
+    pub uncategorized: usize,
+    /// Months whose ledger and summary were rewritten
+    pub months: Vec<String>,
+}
+
+impl ImportSummary {
+    pub fn summary(&self) -> String {
+        let mut out = format!(
+            "Imported {} transaction(s), skipped {} already in the ledger",
+            self.added, self.duplicates
+        );
+        if !self.months.is_empty() {
+            out.push_str(&format!("\nUpdated months: {}", self.months.join(", ")));
+        }
+        if self.uncategorized > 0 {
+            out.push_str(&format!(
+                "\n{} transaction(s) are uncategorized; add finance.rules to place them",
+                self.uncategorized
+            ));
+        }
+        out
+    }
+}
+
+/// Totals for one month
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MonthSummary {
+    /// "YYYY-MM"
+    pub month: String,
+    pub income: f64,
+    /// Total spending (refunds subtract from their category)
+    pub spending: f64,
+    /// Spending per category
+    pub by_category: BTreeMap<String, f64>,
+    pub transactions: usize,
+}
+
+/// Spending against the budget for one category
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetLine {
+    pub category: String,
+    pub spent: f64,
+    /// `None` for categories without a budget
+    pub budget: Option<f64>,
+}
+
+impl BudgetLine {
+    pub fn over_budget(&self) -> bool {
+        self.budget.is_some_and(|budget| self.spent > budget)
+    }
+}
+
+impl MonthSummary {
+    pub fn from_transactions(month: &str, transactions: &[Transaction]) -> Self {
+        let mut summary = Self {
+            month: month.to_string(),
+            transactions: transactions.len(),
+            ..Default::default()
+        };
+        for t in transactions {
+            match t.category.as_str() {
+                INCOME => summary.income += t.amount,
+                TRANSFER => {}
+                category => {
+                    *summary.by_category.entry(category.to_string()).or_default() -= t.amount
+                }
+            }
+        }
+        summary.spending = summary.by_category.values().sum();
+        summary
+    }
+
+    /// Budgeted categories first (in config order), then other spending
+    pub fn budget_lines(&self, budgets: &BTreeMap<String, f64>) -> Vec<BudgetLine> {
+        let mut lines: Vec<BudgetLine> = budgets
+            .iter()
+            .map(|(category, budget)| BudgetLine {
+                category: category.clone(),
+                spent: self.by_category.get(category).copied().unwrap_or(0.0),
+                budget: Some(*budget),
+            })
+            .collect();
+        lines.extend(
+            self.by_category
+                .iter()
+                .filter(|(category, _)| !budgets.contains_key(*category))
+                .map(|(category, spent)| BudgetLine {
+                    category: category.clone(),
+                    spent: *spent,
+                    budget: None,
+                }),
+        );
+        lines
+    }
+
+    /// One line for the heartbeat, e.g. "March 2026: spent $2310.40 of
+    /// $2500.00 budgeted; over budget: dining"
+    pub fn headline(&self, config: &FinanceConfig) -> String {
+        let mut line = format!(
+            "{}: spent {}",
+            month_title(&self.month),
+            money(&config.currency, self.spending)
+        );
+        if !config.budgets.is_empty() {
+            let budgeted: f64 = config.budgets.values().sum();
+            line.push_str(&format!(
+                " of {} budgeted",
+                money(&config.currency, budgeted)
+            ));
+        }
+        let over: Vec<String> = self
+            .budget_lines(&config.budgets)
+            .into_iter()
+            .filter(BudgetLine::over_budget)
+            .map(|l| l.category)
+            .collect();
+        if !over.is_empty() {
+            line.push_str(&format!("; over budget: {}", over.join(", ")));
+        }
+        line
+    }
+
+    /// Budget status as plain text (for the `budget_status` tool and CLI)
+    pub fn status_text(&self, config: &FinanceConfig) -> String {
+        let currency = &config.currency;
+        let mut out = format!(
+            "{} ({} transactions)\nIncome: {}\nSpending: {}\n",
+            month_title(&self.month),
+            self.transactions,
+            money(currency, self.income),
+            money(currency, self.spending)
+        );
+        for line in self.budget_lines(&config.budgets) {
+            out.push_str(&format!(
+                "\n{}: {}",
+                line.category,
+                money(currency, line.spent)
+            ));
+            if let Some(budget) = line.budget {
+                out.push_str(&format!(
+                    " of {} ({} {})",
+                    money(currency, budget),
+                    money(currency, (budget - line.spent).abs()),
+                    if line.over_budget() { "over" } else { "left" }
+                ));
+            }
+        }
+        out
+    }
+
+    /// Markdown summary written to `memory/finance/<YYYY-MM>.md`
+    pub fn to_markdown(&self, config: &FinanceConfig) -> String {
+        let currency = &config.currency;
+        let mut out = format!(
+            "---\ncategory: finance\n---\n\n# Finances: {}\n\n\
+             - Income: {}\n- Spending: {}\n- Net: {}\n- Transactions: {}\n\n\
+             | Category | Spent | Budget | Remaining |\n|---|---|---|---|\n",
+            month_title(&self.month),
+            money(currency, self.income),
+            money(currency, self.spending),
+            money(currency, self.income - self.spending),
+            self.transactions
+        );
+        for line in self.budget_lines(&config.budgets) {
+            let (budget, remaining) = match line.budget {
+                Some(budget) => (
+                    money(currency, budget),
+                    money(currency, budget - line.spent),
+                ),
+                None => ("-".to_string(), "-".to_string()),
+            };
+            out.push_str(&format!(
+                "| {} | {} | {} | {} |\n",
+                line.category,
+                money(currency, line.spent),
+                budget,
+                remaining
+            ));
+        }
+        out
+    }
+}
+
+/// Per-month ledgers and summaries under [`FINANCE_DIR`]
+pub struct FinanceLedger {
+    workspace: PathBuf,
+    config: FinanceConfig,
+}
+
+impl FinanceLedger {
+    pub fn new(workspace: &Path, config: &FinanceConfig) -> Self {
+        Self {
+            workspace: workspace.to_path_buf(),
+            config: config.clone(),
+        }
+    }
+
+    pub fn config(&self) -> &FinanceConfig {
+        &self.config
+    }
+
+    fn ledger_path(&self, month: &str) -> PathBuf {
+        self.workspace
+            .join(TRANSACTIONS_DIR)
+            .join(format!("{}.csv", month))
+    }
+
+    /// Markdown summary for `month`
+    pub fn summary_path(&self, month: &str) -> PathBuf {
+        self.workspace
+            .join(FINANCE_DIR)
+            .join(format!("{}.md", month))
+    }
+
+    /// Heartbeat report for `month`
+    pub fn report_path(&self, month: &str) -> PathBuf {
+        self.workspace
+            .join(REPORTS_DIR)
+            .join(format!("{}.md", month))
+    }
+
+    /// Transactions recorded for `month` ("YYYY-MM"), oldest first
+    pub fn transactions(&self, month: &str) -> Result<Vec<Transaction>> {
+        let path = self.ledger_path(month);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let mut reader = csv::Reader::from_path(&path)?;
+        let transactions = reader.deserialize().collect::<Result<Vec<_>, _>>()?;
+        Ok(transactions)
+    }
+
+    fn write_transactions(&self, month: &str, transactions: &[Transaction]) -> Result<()> {
+        let path = self.ledger_path(month);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut writer = csv::Writer::from_path(&path)?;
+        for t in transactions {
+            writer.serialize(t)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Parse a bank export, categorize it and add it to the ledger
+    pub async fn import(
+        &self,
+        path: &Path,
+        options: &ImportOptions,
+        categorizer: &Categorizer,
+    ) -> Result<ImportSummary> {
+        let mut options = options.clone();
+        if options.date_format.is_none() {
+            options.date_format = self.config.date_format.clone();
+        }
+        let data = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let mut transactions = parse_statement(&data, &options)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        categorizer.categorize(&mut transactions).await;
+        self.add(transactions)
+    }
+
+    /// Add categorized transactions, skipping ones already in the ledger,
+    /// and rewrite the summaries of the months that changed
+    pub fn add(&self, transactions: Vec<Transaction>) -> Result<ImportSummary> {
+        let mut by_month: BTreeMap<String, Vec<Transaction>> = BTreeMap::new();
+        for t in transactions {
+            by_month.entry(t.month()).or_default().push(t);
+        }
+
+        let mut summary = ImportSummary::default();
+        for (month, incoming) in by_month {
+            let mut ledger = self.transactions(&month)?;
+            let (added, duplicates) = merge_transactions(&mut ledger, incoming);
+            summary.duplicates += duplicates.len();
+            if added.is_empty() {
+                continue;
+            }
+            summary.added += added.len();
+            summary.uncategorized += added.iter().filter(|t| t.category == UNCATEGORIZED).count();
+
+            ledger.sort_by_key(|t| t.date);
+            self.write_transactions(&month, &ledger)?;
+            self.write_summary(&month)?;
+            summary.months.push(month);
+        }
+        Ok(summary)
+    }
+
+    pub fn summary(&self, month: &str) -> Result<MonthSummary> {
+        Ok(MonthSummary::from_transactions(
+            month,
+            &self.transactions(month)?,
+        ))
+    }
+
+    /// Rewrite `memory/finance/<month>.md` from the ledger
+    pub fn write_summary(&self, month: &str) -> Result<PathBuf> {
+        let path = self.summary_path(month);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, self.summary(month)?.to_markdown(&self.config))?;
+        Ok(path)
+    }
+
+    /// Report for a finished month: the summary plus the change against the
+    /// month before and the largest transactions
+    pub fn report(&self, month: &str) -> Result<String> {
+        let transactions = self.transactions(month)?;
+        let summary = MonthSummary::from_transactions(month, &transactions);
+        let currency = &self.config.currency;
+
+        let mut out =
+            summary
+                .to_markdown(&self.config)
+                .replacen("# Finances:", "# Finance report:", 1);
+
+        if let Some(previous) = previous_month(month) {
+            let before = self.summary(&previous)?;
+            if before.transactions > 0 {
+                out.push_str(&format!(
+                    "\n## Compared with {}\n\n- Spending: {} ({:+.2})\n",
+                    month_title(&previous),
+                    money(currency, summary.spending),
+                    summary.spending - before.spending
+                ));
+                for (category, spent) in &summary.by_category {
+                    let was = before.by_category.get(category).copied().unwrap_or(0.0);
+                    if (spent - was).abs() >= 0.01 {
+                        out.push_str(&format!("- {}: {:+.2}\n", category, spent - was));
+                    }
+                }
+            }
+        }
+
+        let mut largest: Vec<&Transaction> = transactions
+            .iter()
+            .filter(|t| t.amount < 0.0 && t.category != TRANSFER)
+            .collect();
+        largest.sort_by(|a, b| a.amount.total_cmp(&b.amount));
+        if !largest.is_empty() {
+            out.push_str("\n## Largest purchases\n\n");
+            for t in largest.iter().take(5) {
+                out.push_str(&format!(
+                    "- {} {} ({}): {}\n",
+                    t.date,
+                    t.description,
+                    t.category,
+                    money(currency, -t.amount)
+                ));
+            }
+        }
+        Ok(out)
+    }
+
+    /// Write the report for the month before `today` unless it exists or
+    /// there were no transactions, returning its headline and path
+    pub fn write_monthly_report(&self, today: NaiveDate) -> Result<Option<(String, PathBuf)>> {
+        let Some(month) = previous_month(&today.format("%Y-%m").to_string()) else {
+            return Ok(None);
+        };
+        let path = self.report_path(&month);
+        if path.exists() {
+            return Ok(None);
+        }
+        let summary = self.summary(&month)?;
+        if summary.transactions == 0 {
+            return Ok(None);
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, self.report(&month)?)?;
+        Ok(Some((summary.headline(&self.config), path)))
+    }
+}
+
+/// Add `incoming` to `ledger`, returning the added and duplicate
+/// transactions. Identical transactions (two coffees on the same day) are
+/// matched by count, so re-importing an overlapping export adds nothing.
+fn merge_transactions(
+    ledger: &mut Vec<Transaction>,
+    incoming: Vec<Transaction>,
+) -> (Vec<Transaction>, Vec<Transaction>) {
+    let mut existing: HashMap<String, usize> = HashMap::new();
+    for t in ledger.iter() {
+        *existing.entry(t.key()).or_default() += 1;
+    }
+
+    let mut added = Vec::new();
+    let mut duplicates = Vec::new();
+    for t in incoming {
+        match existing.get_mut(&t.key()) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                duplicates.push(t);
+            }
+            _ => added.push(t),
+        }
+    }
+    ledger.extend(added.iter().cloned());
+    (added, duplicates)
+}
+
+/// "2026-03" -> "2026-02"
+pub fn previous_month(month: &str) -> Option<String> {
+    let first = NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d").ok()?;
+    let last_of_previous = first.pred_opt()?;
+    Some(format!(
+        "{}-{:02}",
+        last_of_previous.year(),
+        last_of_previous.month()
+    ))
+}
+
+/// "2026-03" -> "March 2026"
+fn month_title(month: &str) -> String {
+    NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d")
+        .map(|d| locale::current().month_year(d))
+        .unwrap_or_else(|_| month.to_string())
+}
+
+fn money(currency: &str, amount: f64) -> String {
+    if amount < 0.0 {
+        format!("-{}{:.2}", currency, -amount)
+    } else {
+        format!("{}{:.2}", currency, amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn transaction(date: &str, description: &str, amount: f64, category: &str) -> Transaction {
+        Transaction {
+            date: NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap(),
+            description: description.to_string(),
+            amount,
+            category: category.to_string(),
+            account: "checking".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_month_summary_and_budgets() {
+        let transactions = vec![
+            transaction("2026-03-01", "Payroll", 3200.0, INCOME),
+            transaction("2026-03-02", "Costco", -184.10, "groceries"),
+            transaction("2026-03-05", "Costco refund", 20.0, "groceries"),
+            transaction("2026-03-07", "Savings", -500.0, TRANSFER),
+            transaction("2026-03-09", "Pizza", -62.50, "dining"),
+        ];
+        let summary = MonthSummary::from_transactions("2026-03", &transactions);
+        assert_eq!(summary.income, 3200.0);
+        assert!((summary.spending - 226.60).abs() < 1e-9);
+        assert!((summary.by_category["groceries"] - 164.10).abs() < 1e-9);
+
+        let config = FinanceConfig {
+            budgets: BTreeMap::from([
+                ("dining".to_string(), 50.0),
+                ("groceries".to_string(), 600.0),
+            ]),
+            ..Default::default()
+        };
+        assert_eq!(
+            summary.headline(&config),
+            "March 2026: spent $226.60 of $650.00 budgeted; over budget: dining"
+        );
+        assert!(summary
+            .to_markdown(&config)
+            .contains("| groceries | $164.10 | $600.00 | $435.90 |"));
+    }
+
+    #[test]
+    fn test_ledger_skips_overlapping_imports() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let ledger = FinanceLedger::new(temp_dir.path(), &FinanceConfig::default());
+
+        let coffee = transaction("2026-03-03", "Cafe", -4.50, "dining");
+        let first = ledger.add(vec![coffee.clone(), coffee.clone()])?;
+        assert_eq!(first.added, 2);
+        assert_eq!(first.months, vec!["2026-03"]);
+
+        // The next export repeats both coffees and adds one new transaction
+        let fuel = transaction("2026-04-01", "Shell", -48.0, "fuel");
+        let second = ledger.add(vec![coffee.clone(), coffee, fuel])?;
+        assert_eq!(second.added, 1);
+        assert_eq!(second.duplicates, 2);
+
+        assert_eq!(ledger.transactions("2026-03")?.len(), 2);
+        assert!(ledger.summary_path("2026-04").exists());
+
+        // The March report is written once, in April
+        let april = NaiveDate::from_ymd_opt(2026, 4, 2).unwrap();
+        let (headline, path) = ledger.write_monthly_report(april)?.unwrap();
+        assert!(headline.starts_with("March 2026: spent $9.00"));
+        assert!(fs::read_to_string(path)?.contains("# Finance report: March 2026"));
+        assert!(ledger.write_monthly_report(april)?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_previous_month() {
+        assert_eq!(previous_month("2026-03").as_deref(), Some("2026-02"));
+        assert_eq!(previous_month("2026-01").as_deref(), Some("2025-12"));
+        assert_eq!(previous_month("bogus"), None);
+    }
+}
@@ -0,0 +1,432 @@
+//! Catching up on heartbeat tasks after downtime
+//!
+//! Laptops sleep and daemons get restarted, and a `## School Summary
+//! (daily, 8pm)` section whose time passed while nothing was running would
+//! otherwise just be dropped. The runner keeps when each section last ran
+//! in `~/.homegpt/heartbeat.json`. A section whose schedule came due since
+//! then, longer ago than a heartbeat or two, missed its run: depending on
+//! `heartbeat.catch_up` it's run late or skipped until its next time.
+//! Skipped runs are held for the daily digest.
+
+use anyhow::Result;
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::tasks::HeartbeatTask;
+
+const STATE_FILE: &str = "heartbeat.json";
+
+/// When a section that names a day but no time is due
+const DEFAULT_HOUR: u32 = 9;
+
+/// How far back a missed run is looked for
+const MAX_LOOKBACK_DAYS: i64 = 400;
+
+/// What to do with a run missed while the heartbeat wasn't running
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatchUp {
+    /// Run it now, late
+    Run,
+    /// Wait for its next scheduled time
+    Skip,
+}
+
+impl CatchUp {
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.trim() {
+            "run" => Ok(Self::Run),
+            "skip" => Ok(Self::Skip),
+            other => anyhow::bail!("Unknown catch-up '{}' (expected run or skip)", other),
+        }
+    }
+}
+
+/// A section whose schedule came due while the heartbeat wasn't running
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissedRun {
+    pub title: String,
+    pub schedule: String,
+    /// When it last ran
+    pub last_run: NaiveDateTime,
+    /// The latest time it was due
+    pub due: NaiveDateTime,
+    pub catch_up: CatchUp,
+}
+
+impl MissedRun {
+    /// "School Summary (daily, 8pm) was due Sat 20:00; nothing ran from
+    /// Sat 19:55 to Sun 08:10"
+    fn describe(&self, now: NaiveDateTime) -> String {
+        format!(
+            "{} ({}) was due {}; nothing ran from {} to {}",
+            self.title,
+            self.schedule,
+            self.due.format("%a %H:%M"),
+            self.last_run.format("%a %H:%M"),
+            now.format("%a %H:%M")
+        )
+    }
+}
+
+/// The heartbeat prompt's note on missed runs, if there are any
+pub fn missed_note(missed: &[MissedRun]) -> Option<String> {
+    if missed.is_empty() {
+        return None;
+    }
+    let mut note = "\n\nThe heartbeat didn't run for a while, so these sections missed their \
+                    time:"
+        .to_string();
+    for run in missed {
+        let action = match run.catch_up {
+            CatchUp::Run => "do it now, late",
+            CatchUp::Skip => "skip it this time; it runs again at its next scheduled time",
+        };
+        note.push_str(&format!(
+            "\n- {} ({}), due {}: {}.",
+            run.title,
+            run.schedule,
+            run.due.format("%a %H:%M"),
+            action
+        ));
+    }
+    Some(note)
+}
+
+/// Saved between runs in `~/.homegpt/heartbeat.json`
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RunState {
+    /// When each section last ran, by title
+    #[serde(default)]
+    last_runs: BTreeMap<String, NaiveDateTime>,
+    /// Skipped runs not yet in a digest
+    #[serde(default)]
+    skipped: Vec<String>,
+}
+
+/// When each HEARTBEAT.md section last ran
+pub struct RunLog {
+    path: PathBuf,
+}
+
+impl RunLog {
+    pub fn open(state_dir: &Path) -> Self {
+        Self {
+            path: state_dir.join(STATE_FILE),
+        }
+    }
+
+    fn load(&self) -> RunState {
+        fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, state: &RunState) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(state)?)?;
+        Ok(())
+    }
+
+    /// Sections of `tasks` that came due since they last ran and more than
+    /// `grace` before `now`, with what `catch_up` says to do about each.
+    /// Sections that never ran or have no schedule can't miss a run.
+    pub fn missed(
+        &self,
+        tasks: &[HeartbeatTask],
+        now: NaiveDateTime,
+        grace: Duration,
+        catch_up: impl Fn(&str) -> CatchUp,
+    ) -> Vec<MissedRun> {
+        let state = self.load();
+        tasks
+            .iter()
+            .filter_map(|task| {
+                let schedule = task.schedule.as_deref()?;
+                let last_run = *state.last_runs.get(&task.title)?;
+                let due = last_due(schedule, last_run, now - grace)?;
+                Some(MissedRun {
+                    title: task.title.clone(),
+                    schedule: schedule.to_string(),
+                    last_run,
+                    due,
+                    catch_up: catch_up(&task.title),
+                })
+            })
+            .collect()
+    }
+
+    /// Record that `tasks` ran at `now`, holding the `missed` runs that
+    /// were skipped for the digest
+    pub fn record(
+        &self,
+        tasks: &[HeartbeatTask],
+        now: NaiveDateTime,
+        missed: &[MissedRun],
+    ) -> Result<()> {
+        let mut state = self.load();
+        // Sections removed from HEARTBEAT.md are forgotten
+        state.last_runs = tasks.iter().map(|task| (task.title.clone(), now)).collect();
+        state.skipped.extend(
+            missed
+                .iter()
+                .filter(|run| run.catch_up == CatchUp::Skip)
+                .map(|run| run.describe(now)),
+        );
+        self.save(&state)
+    }
+
+    /// When each section last ran, by title
+    pub fn last_runs(&self) -> Vec<(String, NaiveDateTime)> {
+        self.load().last_runs.into_iter().collect()
+    }
+
+    /// Skipped runs not yet in a digest; they won't be returned again
+    pub fn take_skipped(&self) -> Result<Vec<String>> {
+        let mut state = self.load();
+        if state.skipped.is_empty() {
+            return Ok(Vec::new());
+        }
+        let skipped = std::mem::take(&mut state.skipped);
+        self.save(&state)?;
+        Ok(skipped)
+    }
+}
+
+/// How a section's schedule picks its days
+enum Days {
+    /// Every `n` days, counted from the date if given
+    Every(u32, Option<NaiveDate>),
+    Weekdays,
+    /// The weekday every `n` weeks, counted from the date if given
+    Weekly(Weekday, u32, Option<NaiveDate>),
+    /// The day of the month
+    Monthly(u32),
+}
+
+impl Days {
+    fn matches(&self, date: NaiveDate) -> bool {
+        let counted = |n: u32, from: Option<NaiveDate>, unit: i64| {
+            n <= 1
+                || from
+                    .is_none_or(|from| ((date - from).num_days() / unit).rem_euclid(n as i64) == 0)
+        };
+        match *self {
+            Self::Every(n, from) => counted(n, from, 1),
+            Self::Weekdays => !matches!(date.weekday(), Weekday::Sat | Weekday::Sun),
+            Self::Weekly(weekday, n, from) => date.weekday() == weekday && counted(n, from, 7),
+            Self::Monthly(day) => date.day() == day,
+        }
+    }
+}
+
+/// A schedule: a fixed period after the last run, or a time on some days
+enum Cadence {
+    Period(Duration),
+    At(Days, NaiveTime),
+}
+
+/// Read a section heading's schedule: "every hour", "every 2 hours",
+/// "daily, 8pm", "weekdays, 7am", "weekly, Sunday", "monthly, 1st 9am",
+/// "every other week, Saturday 10am, from 2026-10-24"
+fn cadence(schedule: &str) -> Option<Cadence> {
+    let lowered = schedule.to_lowercase();
+    let mut parts = lowered.split(',').map(str::trim);
+    let words: Vec<&str> = parts.next()?.split_whitespace().collect();
+
+    let mut weekday = None;
+    let mut day_of_month = None;
+    let mut time = None;
+    let mut from = None;
+    for part in parts {
+        if let Some(date) = part.strip_prefix("from ") {
+            from = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").ok();
+            continue;
+        }
+        for word in part.split_whitespace() {
+            if let Ok(day) = word.parse::<Weekday>() {
+                weekday = Some(day);
+            } else if let Some(t) = clock(word) {
+                time = Some(t);
+            } else if let Some(day) = ordinal(word) {
+                day_of_month = Some(day);
+            }
+        }
+    }
+    let at = |days| {
+        let time = time.unwrap_or_else(|| NaiveTime::from_hms_opt(DEFAULT_HOUR, 0, 0).unwrap());
+        Some(Cadence::At(days, time))
+    };
+    let period = |duration| Some(Cadence::Period(duration));
+
+    let count = |n: &str| n.parse::<u32>().ok().filter(|&n| n > 0);
+    match words.as_slice() {
+        ["hourly"] | ["every", "hour"] => period(Duration::hours(1)),
+        ["every", n, unit] if unit.starts_with("hour") => period(Duration::hours(count(n)? as i64)),
+        ["every", n, unit] if unit.starts_with("min") => {
+            period(Duration::minutes(count(n)? as i64))
+        }
+        ["daily"] | ["every", "day"] if time.is_some() => at(Days::Every(1, None)),
+        ["daily"] | ["every", "day"] => period(Duration::days(1)),
+        ["every", n, "days"] if time.is_some() => at(Days::Every(count(n)?, from)),
+        ["every", n, "days"] => period(Duration::days(count(n)? as i64)),
+        ["weekdays"] | ["every", "weekday"] => at(Days::Weekdays),
+        ["weekly"] | ["every", "week"] => match weekday {
+            Some(day) => at(Days::Weekly(day, 1, from)),
+            None => period(Duration::weeks(1)),
+        },
+        ["every", "other", "week"] => at(Days::Weekly(weekday?, 2, from)),
+        ["every", n, "weeks"] => at(Days::Weekly(weekday?, count(n)?, from)),
+        ["monthly"] | ["every", "month"] => match day_of_month {
+            Some(day) => at(Days::Monthly(day)),
+            None => period(Duration::days(30)),
+        },
+        _ => None,
+    }
+}
+
+/// "9am", "8:30pm", "20:00"
+fn clock(word: &str) -> Option<NaiveTime> {
+    let word = word.to_uppercase();
+    // "9AM" -> "9:00AM"
+    let word = match word.find(['A', 'P']) {
+        Some(i) if !word.contains(':') => format!("{}:00{}", &word[..i], &word[i..]),
+        _ => word,
+    };
+    ["%I:%M%p", "%H:%M"]
+        .iter()
+        .find_map(|format| NaiveTime::parse_from_str(&word, format).ok())
+}
+
+/// "1st", "22nd"
+fn ordinal(word: &str) -> Option<u32> {
+    let digits = word.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    if !matches!(&word[digits.len()..], "st" | "nd" | "rd" | "th") {
+        return None;
+    }
+    digits.parse().ok().filter(|day| (1..=31).contains(day))
+}
+
+/// The latest time in (`since`, `until`] the section with `schedule` was
+/// due, if it was
+pub fn last_due(
+    schedule: &str,
+    since: NaiveDateTime,
+    until: NaiveDateTime,
+) -> Option<NaiveDateTime> {
+    if until <= since {
+        return None;
+    }
+    match cadence(schedule)? {
+        Cadence::Period(period) => {
+            let periods = (until - since).num_seconds() / period.num_seconds().max(1);
+            (periods > 0).then(|| since + period * periods as i32)
+        }
+        Cadence::At(days, time) => {
+            let oldest = since
+                .date()
+                .max(until.date() - Duration::days(MAX_LOOKBACK_DAYS));
+            until
+                .date()
+                .iter_days()
+                .rev()
+                .take_while(|date| *date >= oldest)
+                .filter(|date| days.matches(*date))
+                .map(|date| date.and_time(time))
+                .find(|due| *due > since && *due <= until)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        // October 2026; the 17th is a Saturday
+        NaiveDate::from_ymd_opt(2026, 10, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_last_due() {
+        let due = |schedule| last_due(schedule, at(17, 19, 55), at(18, 8, 10));
+        assert_eq!(due("daily, 8pm"), Some(at(17, 20, 0)));
+        assert_eq!(due("every hour"), Some(at(18, 7, 55)));
+        assert_eq!(due("weekly, Sunday"), None);
+        assert_eq!(due("weekly, Sunday 7am"), Some(at(18, 7, 0)));
+        assert_eq!(due("weekdays, 7am"), None);
+        assert_eq!(due("every other week, Sunday 7am, from 2026-10-25"), None);
+        assert_eq!(
+            due("every other week, Sunday 7am, from 2026-10-04"),
+            Some(at(18, 7, 0))
+        );
+        assert_eq!(
+            last_due("monthly, 1st 9am", at(17, 0, 0), at(31, 0, 0)),
+            None
+        );
+        assert_eq!(due("whenever"), None);
+    }
+
+    #[test]
+    fn test_missed_and_skipped() {
+        let dir = TempDir::new().unwrap();
+        let log = RunLog::open(dir.path());
+        let task = |title: &str, schedule: &str| HeartbeatTask {
+            title: title.to_string(),
+            schedule: Some(schedule.to_string()),
+            body: vec!["- [ ] Something".to_string()],
+        };
+        let tasks = vec![
+            task("School Summary", "daily, 8pm"),
+            task("Calendar Sync", "every hour"),
+        ];
+        let catch_up = |title: &str| {
+            if title == "Calendar Sync" {
+                CatchUp::Skip
+            } else {
+                CatchUp::Run
+            }
+        };
+        let grace = Duration::minutes(30);
+
+        // Never ran: nothing missed
+        assert!(log
+            .missed(&tasks, at(18, 8, 10), grace, catch_up)
+            .is_empty());
+        log.record(&tasks, at(17, 19, 55), &[]).unwrap();
+        // Ran on time
+        assert!(log
+            .missed(&tasks, at(17, 20, 25), grace, catch_up)
+            .is_empty());
+
+        // Asleep overnight
+        let now = at(18, 8, 10);
+        let missed = log.missed(&tasks, now, grace, catch_up);
+        assert_eq!(missed.len(), 2);
+        assert_eq!(missed[0].due, at(17, 20, 0));
+        let note = missed_note(&missed).unwrap();
+        assert!(note.contains("School Summary (daily, 8pm), due Sat 20:00: do it now, late."));
+
+        log.record(&tasks, now, &missed).unwrap();
+        assert!(log
+            .missed(&tasks, at(18, 8, 40), grace, catch_up)
+            .is_empty());
+        assert_eq!(
+            log.take_skipped().unwrap(),
+            vec![
+                "Calendar Sync (every hour) was due Sun 06:55; nothing ran from Sat 19:55 to \
+                 Sun 08:10"
+            ]
+        );
+        assert!(log.take_skipped().unwrap().is_empty());
+    }
+}
@@ -2,6 +2,7 @@
 
 use serde::Serialize;
 use std::sync::RwLock;
+use std::time::Duration;
 
 /// Heartbeat event status
 #[derive(Debug, Clone, Serialize, PartialEq)]
@@ -17,6 +18,17 @@ pub enum HeartbeatStatus {
     Failed,
 }
 
+impl HeartbeatStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sent => "sent",
+            Self::Ok => "ok",
+            Self::Skipped => "skipped",
+            Self::Failed => "failed",
+        }
+    }
+}
+
 /// A heartbeat event for tracking/display
 #[derive(Debug, Clone, Serialize)]
 pub struct HeartbeatEvent {
@@ -37,8 +49,13 @@ pub struct HeartbeatEvent {
 /// Global state for last heartbeat event
 static LAST_HEARTBEAT: RwLock<Option<HeartbeatEvent>> = RwLock::new(None);
 
-/// Emit a heartbeat event (stores it for later retrieval)
+/// Emit a heartbeat event (stores it for later retrieval and counts it in
+/// the metrics)
 pub fn emit_heartbeat_event(event: HeartbeatEvent) {
+    let elapsed = (event.status != HeartbeatStatus::Skipped)
+        .then(|| Duration::from_millis(event.duration_ms));
+    crate::metrics::record_heartbeat(event.status.as_str(), elapsed);
+
     if let Ok(mut guard) = LAST_HEARTBEAT.write() {
         *guard = Some(event);
     }
@@ -1,5 +1,11 @@
+mod catchup;
 mod events;
 mod runner;
+mod tasks;
 
+pub use catchup::RunLog;
 pub use events::{emit_heartbeat_event, get_last_heartbeat_event, HeartbeatEvent, HeartbeatStatus};
 pub use runner::HeartbeatRunner;
+pub use tasks::{
+    add_task, parse_tasks, remove_task, schedule_label, HeartbeatTask, HEARTBEAT_PATH,
+};
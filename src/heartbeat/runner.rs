@@ -1,20 +1,43 @@
 //! Heartbeat runner for continuous autonomous operation
 
 use anyhow::Result;
-use chrono::{Local, NaiveTime};
+use chrono::{Datelike, IsoWeek, NaiveTime};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use std::time::{Duration, Instant};
 use tokio::time::sleep;
 use tracing::{debug, info, warn};
 
+use super::catchup::{missed_note, CatchUp, RunLog};
 use super::events::{emit_heartbeat_event, now_ms, HeartbeatEvent, HeartbeatStatus};
+use super::tasks::{parse_tasks, HeartbeatTask};
 use crate::agent::{
-    build_heartbeat_prompt, is_heartbeat_ok, Agent, AgentConfig, SessionStore, HEARTBEAT_OK_TOKEN,
+    build_heartbeat_prompt, dry_run_default, get_state_dir, is_heartbeat_ok, journal_path,
+    strip_emergency, week_label, Agent, AgentConfig, SessionStore, HEARTBEAT_OK_TOKEN,
 };
+use crate::announce::Announcer;
+use crate::business::BusinessMonitor;
+use crate::calendar::Calendar;
+use crate::commitments::CommitmentStore;
 use crate::concurrency::{TurnGate, WorkspaceLock};
 use crate::config::{parse_duration, parse_time, Config};
-use crate::memory::MemoryManager;
+use crate::finance::FinanceLedger;
+use crate::locale;
+use crate::memory::{FileWrite, MemoryManager, ReconfirmLog, HEALTH_REPORT_PATH};
+use crate::notifications::{Event, Notifier};
+use crate::presence::Presence;
+use crate::weather::WeatherService;
+
+/// Run report written by planning-mode heartbeats (relative to workspace)
+const PLAN_REPORT_PATH: &str = "memory/heartbeat-report.md";
+
+/// One line per task run when `heartbeat.isolate_tasks` is on (relative to
+/// workspace)
+const TASK_LOG_PATH: &str = "memory/heartbeat-log.md";
+
+/// Task runs kept in the log; older lines are dropped
+const TASK_LOG_ENTRIES: usize = 500;
 
 pub struct HeartbeatRunner {
     config: Config,
@@ -24,10 +47,43 @@ pub struct HeartbeatRunner {
     agent_id: String,
     /// Cached MemoryManager to avoid reinitializing embedding provider on every heartbeat
     memory: MemoryManager,
+    /// How often to refresh the memory health report (None = never)
+    health_report_interval: Option<Duration>,
     /// In-process turn gate (shared with HTTP server when running in daemon)
     turn_gate: Option<TurnGate>,
     /// Cross-process workspace lock
     workspace_lock: WorkspaceLock,
+    /// Business connectors checked on each heartbeat (None = none configured)
+    business: Option<BusinessMonitor>,
+    /// Forecast for the daily weather digest (None = no location configured)
+    weather: Option<WeatherService>,
+    /// Subscribed calendars merged into memory/calendar/upcoming.md
+    calendar: Calendar,
+    /// Who's home, to vary announcements (None = no people configured)
+    presence: Option<Presence>,
+    /// Spoken announcements (None = no devices configured)
+    announcer: Option<Announcer>,
+    /// Push notifications and webhooks for alerts, digests and failures
+    /// (None = none configured)
+    notifier: Option<Notifier>,
+    /// When each HEARTBEAT.md section last ran, to catch up after downtime
+    run_log: RunLog,
+    /// How long past its time a section can wait for a heartbeat before
+    /// it counts as missed
+    catch_up_grace: chrono::Duration,
+    /// What the assistant said it would do, checked once due (None =
+    /// `memory.track_commitments` is off)
+    commitments: Option<CommitmentStore>,
+    /// Which stale memory files the family was asked about (None =
+    /// `memory.reconfirm_stale` is off)
+    reconfirm: Option<ReconfirmLog>,
+    /// Week the journal was last tried for, so a quiet week isn't read
+    /// again on every heartbeat
+    journal_week: Mutex<Option<IsoWeek>>,
+    /// How often near-duplicate chunks are merged (None = only by hand)
+    consolidation_interval: Option<Duration>,
+    /// When they were last merged, since the daemon started
+    consolidated_at: Mutex<Option<Instant>>,
 }
 
 impl HeartbeatRunner {
@@ -44,7 +100,8 @@ impl HeartbeatRunner {
     /// Create a new HeartbeatRunner with an optional in-process TurnGate.
     ///
     /// When running inside the daemon alongside the HTTP server, pass a
-    /// shared `TurnGate` so heartbeat skips when an HTTP agent turn is active.
+    /// shared `TurnGate` so heartbeat skips when the daemon is already
+    /// running as many agent turns as it allows.
     pub fn new_with_gate(
         config: &Config,
         agent_id: &str,
@@ -67,11 +124,58 @@ impl HeartbeatRunner {
             None
         };
 
+        let health_report_interval = match config.memory.health_report_interval.as_str() {
+            "" => None,
+            interval => Some(
+                parse_duration(interval)
+                    .map_err(|e| anyhow::anyhow!("Invalid memory.health_report_interval: {}", e))?,
+            ),
+        };
+
+        let consolidation_interval = match config.memory.consolidation_interval.as_str() {
+            "" => None,
+            interval => Some(
+                parse_duration(interval)
+                    .map_err(|e| anyhow::anyhow!("Invalid memory.consolidation_interval: {}", e))?,
+            ),
+        };
+
         let workspace = config.workspace_path();
 
         // Create MemoryManager once and reuse it to avoid reinitializing embedding provider
         let memory = MemoryManager::new_with_full_config(&config.memory, Some(config), agent_id)?;
         let workspace_lock = WorkspaceLock::new()?;
+        let state_dir = get_state_dir()?;
+        let business = BusinessMonitor::from_config(config, &state_dir)?;
+        let weather = WeatherService::from_config(config, &state_dir)?;
+        let calendar = Calendar::from_config(config, &state_dir)?;
+        let presence = Presence::from_config(config, &state_dir)?;
+        let announcer = Announcer::from_config(config, &state_dir)?;
+        let notifier = Notifier::from_config(config)?;
+        let run_log = RunLog::open(&state_dir);
+        CatchUp::parse(&config.heartbeat.catch_up)
+            .map_err(|e| anyhow::anyhow!("Invalid heartbeat.catch_up: {}", e))?;
+        for task in &config.heartbeat.tasks {
+            if let Some(ref catch_up) = task.catch_up {
+                CatchUp::parse(catch_up).map_err(|e| {
+                    anyhow::anyhow!(
+                        "Invalid catch_up for heartbeat task '{}': {}",
+                        task.title,
+                        e
+                    )
+                })?;
+            }
+        }
+        let catch_up_grace = chrono::Duration::from_std(interval * 2)?;
+        let commitments = if config.memory.track_commitments {
+            Some(CommitmentStore::open(&state_dir)?)
+        } else {
+            None
+        };
+        let reconfirm = config
+            .memory
+            .reconfirm_stale
+            .then(|| ReconfirmLog::open(&state_dir));
 
         Ok(Self {
             config: config.clone(),
@@ -80,8 +184,22 @@ impl HeartbeatRunner {
             workspace,
             agent_id: agent_id.to_string(),
             memory,
+            health_report_interval,
             turn_gate,
             workspace_lock,
+            business,
+            weather,
+            calendar,
+            presence,
+            announcer,
+            notifier,
+            run_log,
+            catch_up_grace,
+            commitments,
+            reconfirm,
+            journal_week: Mutex::new(None),
+            consolidation_interval,
+            consolidated_at: Mutex::new(None),
         })
     }
 
@@ -96,6 +214,11 @@ impl HeartbeatRunner {
             // Sleep until next interval
             sleep(self.interval).await;
 
+            // Send alerts held overnight, even outside active hours
+            if let Some(ref notifier) = self.notifier {
+                notifier.send_held().await;
+            }
+
             // Check active hours
             if !self.in_active_hours() {
                 debug!("Outside active hours, skipping heartbeat");
@@ -144,6 +267,7 @@ impl HeartbeatRunner {
                         reason: Some(e.to_string()),
                     });
                     warn!("Heartbeat error: {}", e);
+                    self.notify(Event::HeartbeatFailed, &e.to_string()).await;
                 }
             }
         }
@@ -181,6 +305,7 @@ impl HeartbeatRunner {
                     preview: None,
                     reason: Some(e.to_string()),
                 });
+                self.notify(Event::HeartbeatFailed, &e.to_string()).await;
                 Err(e)
             }
         }
@@ -188,16 +313,21 @@ impl HeartbeatRunner {
 
     /// Internal heartbeat execution (returns response and status)
     async fn run_once_internal(&self) -> Result<(String, HeartbeatStatus)> {
-        // Skip if an in-process agent turn is already in flight
+        // Skip if the daemon is running as many agent turns as it allows
         if let Some(ref gate) = self.turn_gate {
             if gate.is_busy() {
-                debug!("Skipping heartbeat: agent turn in flight (TurnGate busy)");
+                debug!("Skipping heartbeat: agent turns in flight (TurnGate busy)");
                 return Ok((HEARTBEAT_OK_TOKEN.to_string(), HeartbeatStatus::Skipped));
             }
         }
 
-        // Try to acquire the cross-process workspace lock (non-blocking)
-        let _ws_guard = match self.workspace_lock.try_acquire()? {
+        // Try to acquire the cross-process workspace lock (non-blocking),
+        // sharing the daemon's hold on it with the other turns in flight
+        let ws_guard = match self.turn_gate {
+            Some(ref gate) => gate.try_lock_workspace(&self.workspace_lock).await?,
+            None => self.workspace_lock.try_acquire()?.map(std::sync::Arc::new),
+        };
+        let _ws_guard = match ws_guard {
             Some(guard) => guard,
             None => {
                 debug!("Skipping heartbeat: workspace locked by another process");
@@ -219,6 +349,18 @@ impl HeartbeatRunner {
             None
         };
 
+        let health_note = self.refresh_health_report();
+        let finance_note = self.write_finance_report().await;
+        let business_note = self.check_business().await;
+        let weather_note = self.weather_digest().await;
+        let commitments_note = self.commitments_note();
+        let stale_note = self.stale_note();
+        self.sync_calendar().await;
+        self.write_journal().await;
+        self.consolidate_memory().await;
+        let presence_note = self.presence_note().await;
+        let announce_note = self.announce_note().await;
+
         // Check if HEARTBEAT.md exists and has content
         let heartbeat_path = self.workspace.join("HEARTBEAT.md");
 
@@ -233,22 +375,76 @@ impl HeartbeatRunner {
             return Ok((HEARTBEAT_OK_TOKEN.to_string(), HeartbeatStatus::Skipped));
         }
 
-        // Create agent for heartbeat (clone the cached MemoryManager to share the embedding provider)
-        let agent_config = AgentConfig {
-            model: self.config.agent.default_model.clone(),
-            context_window: self.config.agent.context_window,
-            reserve_tokens: self.config.agent.reserve_tokens,
-        };
-
-        let mut agent = Agent::new(agent_config, &self.config, self.memory.clone()).await?;
-        agent.new_session().await?;
-
         // Check if workspace is a git repo
         let workspace_is_git = self.workspace.join(".git").exists();
 
-        // Send heartbeat prompt
-        let heartbeat_prompt = build_heartbeat_prompt(workspace_is_git);
-        let response = agent.chat(&heartbeat_prompt).await?;
+        let mut heartbeat_prompt = build_heartbeat_prompt(workspace_is_git);
+        // Things to tell the user, then context for every task
+        let alerts: String = [
+            health_note,
+            finance_note,
+            business_note,
+            weather_note,
+            commitments_note,
+            stale_note,
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        let mut context: String = [presence_note, announce_note]
+            .into_iter()
+            .flatten()
+            .collect();
+        // Sections like "(daily, 8pm)" go by the home clock
+        let now = locale::now();
+        context.push_str(&format!(
+            "\n\nIt is now {} ({}); use this time for tasks scheduled at a time of day.",
+            now.format("%A %Y-%m-%d %H:%M"),
+            now.format("%Z")
+        ));
+
+        let tasks = parse_tasks(&content);
+        // Sections whose time passed while nothing was running
+        let missed = self
+            .run_log
+            .missed(&tasks, now.naive_local(), self.catch_up_grace, |title| {
+                self.catch_up_for(title)
+            });
+        if let Some(note) = missed_note(&missed) {
+            info!("Heartbeat missed {} scheduled task run(s)", missed.len());
+            context.push_str(&note);
+        }
+
+        let (response, session_id) = if self.config.heartbeat.isolate_tasks && !tasks.is_empty() {
+            let skipped = |task: &HeartbeatTask| {
+                missed
+                    .iter()
+                    .any(|run| run.title == task.title && run.catch_up == CatchUp::Skip)
+            };
+            let due: Vec<HeartbeatTask> = tasks
+                .iter()
+                .filter(|task| !skipped(task))
+                .cloned()
+                .collect();
+            self.run_tasks(&heartbeat_prompt, &alerts, &context, &due)
+                .await?
+        } else {
+            heartbeat_prompt.push_str(&alerts);
+            heartbeat_prompt.push_str(&context);
+            self.run_heartbeat(&heartbeat_prompt, &content).await?
+        };
+        // A failed run is caught up on next time
+        if let Err(e) = self.run_log.record(&tasks, now.naive_local(), &missed) {
+            warn!("Failed to record heartbeat task runs: {}", e);
+        }
+        if !self.has_daily_digest() {
+            for note in [self.take_skipped(), self.take_broken()]
+                .into_iter()
+                .flatten()
+            {
+                self.notify(Event::Digest, &note).await;
+            }
+        }
 
         // Determine status based on response
         if is_heartbeat_ok(&response) {
@@ -271,7 +467,6 @@ impl HeartbeatRunner {
             }
 
             // Record the heartbeat (re-read from disk to avoid clobbering)
-            let session_id = agent.session_status().id;
             if let Err(e) = store.load_and_update(session_key, &session_id, |entry| {
                 entry.record_heartbeat(&response);
             }) {
@@ -279,15 +474,481 @@ impl HeartbeatRunner {
             }
         }
 
+        match strip_emergency(&response) {
+            Some(alert) => self.notify(Event::Emergency, alert).await,
+            None => self.notify(Event::Alert, &response).await,
+        }
         Ok((response, HeartbeatStatus::Sent))
     }
 
+    /// Agent for a heartbeat session (clones the cached MemoryManager to
+    /// share the embedding provider)
+    async fn heartbeat_agent(&self) -> Result<Agent> {
+        let agent_config = AgentConfig {
+            model: self.config.agent.heartbeat_model().to_string(),
+            context_window: self.config.agent.context_window,
+            reserve_tokens: self.config.agent.reserve_tokens,
+        };
+        Agent::new(agent_config, &self.config, self.memory.clone()).await
+    }
+
+    /// Run the whole of HEARTBEAT.md in one session, returning the reply
+    /// and the session's id
+    async fn run_heartbeat(
+        &self,
+        heartbeat_prompt: &str,
+        content: &str,
+    ) -> Result<(String, String)> {
+        let mut agent = self.heartbeat_agent().await?;
+        agent.new_session().await?;
+
+        let response = if self.config.heartbeat.planning {
+            let instruction = format!("{}\n\nHEARTBEAT.md:\n\n{}", heartbeat_prompt, content);
+            let report = agent.run_plan(&instruction).await?;
+            if report.steps.is_empty() {
+                HEARTBEAT_OK_TOKEN.to_string()
+            } else {
+                info!("Heartbeat plan finished: {}", report.summary());
+                let report_path = self.workspace.join(PLAN_REPORT_PATH);
+                let written = report_path
+                    .parent()
+                    .map_or(Ok(()), fs::create_dir_all)
+                    .and_then(|_| fs::write(&report_path, report.to_markdown()));
+                if let Err(e) = written {
+                    warn!("Failed to write {}: {}", report_path.display(), e);
+                }
+                report.reply
+            }
+        } else {
+            agent.chat(heartbeat_prompt).await?
+        };
+        Ok((response, agent.session_status().id))
+    }
+
+    /// Run each HEARTBEAT.md section in its own session with its own token
+    /// budget and tools, so one runaway task can't starve the rest. Alerts
+    /// get a session of their own. Each outcome is logged to
+    /// [`TASK_LOG_PATH`]; the replies that aren't HEARTBEAT_OK are joined,
+    /// emergencies first, with the id of the last session.
+    async fn run_tasks(
+        &self,
+        heartbeat_prompt: &str,
+        alerts: &str,
+        context: &str,
+        tasks: &[HeartbeatTask],
+    ) -> Result<(String, String)> {
+        let mut runs: Vec<(String, String)> = tasks
+            .iter()
+            .map(|task| {
+                let section = format!(
+                    "\n\nThis run covers only the \"{}\" section of HEARTBEAT.md; the other \
+                     sections have runs of their own. The section:\n\n{}",
+                    task.title,
+                    task.to_markdown()
+                );
+                (task.title.clone(), section)
+            })
+            .collect();
+        if !alerts.is_empty() {
+            let prompt = format!(
+                "\n\nThis run is only for the alerts below; the HEARTBEAT.md sections have runs \
+                 of their own.{}",
+                alerts
+            );
+            runs.insert(0, ("Alerts".to_string(), prompt));
+        }
+
+        let mut replies = Vec::new();
+        let mut log = Vec::new();
+        let mut session_id = String::new();
+        for (title, task_prompt) in runs {
+            let config = &self.config.heartbeat;
+            let mut agent = self.heartbeat_agent().await?;
+            let tools = config.tools_for(&title);
+            if !tools.is_empty() {
+                agent.restrict_tools(tools);
+            }
+            agent.set_token_budget(config.token_budget_for(&title));
+            agent.new_session().await?;
+            session_id = agent.session_status().id;
+
+            let prompt = format!("{}{}{}", heartbeat_prompt, context, task_prompt);
+            let result = agent.chat(&prompt).await;
+            let usage = agent.usage();
+            let tokens = usage.input_tokens + usage.output_tokens;
+            let outcome = match result {
+                Ok(reply) if is_heartbeat_ok(&reply) => "OK".to_string(),
+                Ok(reply) => {
+                    let outcome = first_line(&reply);
+                    replies.push(reply);
+                    outcome
+                }
+                Err(e) => {
+                    warn!("Heartbeat task '{}' failed: {}", title, e);
+                    let message = format!("Heartbeat task '{}' failed: {}", title, e);
+                    self.notify(Event::HeartbeatFailed, &message).await;
+                    format!("failed: {}", first_line(&e.to_string()))
+                }
+            };
+            debug!(
+                "Heartbeat task '{}': {} ({} tokens)",
+                title, outcome, tokens
+            );
+            log.push(format!(
+                "- {} **{}** ({} tokens): {}",
+                locale::now().format("%Y-%m-%d %H:%M"),
+                title,
+                tokens,
+                outcome
+            ));
+        }
+        if let Err(e) = append_task_log(&self.workspace.join(TASK_LOG_PATH), &log) {
+            warn!("Failed to write {}: {}", TASK_LOG_PATH, e);
+        }
+
+        if replies.is_empty() {
+            return Ok((HEARTBEAT_OK_TOKEN.to_string(), session_id));
+        }
+        // strip_emergency reads the start of the reply
+        replies.sort_by_key(|reply| strip_emergency(reply).is_none());
+        Ok((replies.join("\n\n"), session_id))
+    }
+
+    /// Send `event` to the notification channels subscribed to it
+    async fn notify(&self, event: Event, message: &str) {
+        if dry_run_default() {
+            info!("Dry run: not sending {:?} notification: {}", event, message);
+            return;
+        }
+        if let Some(ref notifier) = self.notifier {
+            notifier.notify(event, message).await;
+        }
+    }
+
+    /// Regenerate the memory health report when due, returning a note for
+    /// the heartbeat prompt if it found problems
+    fn refresh_health_report(&self) -> Option<String> {
+        let interval = self.health_report_interval?;
+        match self.memory.refresh_health_report(interval) {
+            Ok(Some(report)) if !report.is_healthy() => Some(format!(
+                "\n\n{} (details in {}). Tell the user, briefly.",
+                report.summary(),
+                HEALTH_REPORT_PATH
+            )),
+            Ok(_) => None,
+            Err(e) => {
+                warn!("Memory health report failed: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Run the business connectors that are due, returning a note for the
+    /// heartbeat prompt if anything changed
+    async fn check_business(&self) -> Option<String> {
+        let alerts = self.business.as_ref()?.check_due(chrono::Utc::now()).await;
+        if alerts.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "\n\nBusiness alerts (changes since the last check):\n- {}\nTell the user, briefly.",
+            alerts.join("\n- ")
+        ))
+    }
+
+    /// Today's forecast on the first heartbeat after `weather.digest_time`
+    async fn weather_digest(&self) -> Option<String> {
+        match self.weather.as_ref()?.daily_digest(locale::now()).await {
+            Ok(Some(digest)) => {
+                let mut message = format!("Today's weather: {}", digest);
+                for note in [self.take_skipped(), self.take_broken()]
+                    .into_iter()
+                    .flatten()
+                {
+                    message.push_str(&format!("\n\n{}", note));
+                }
+                self.notify(Event::Digest, &message).await;
+                Some(format!(
+                    "\n\nToday's weather: {}. Tell the user, briefly, including anything \
+                     to do about it (e.g. bring the bikes in before the rain).",
+                    digest
+                ))
+            }
+            Ok(None) => None,
+            Err(e) => {
+                warn!("Weather forecast failed: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Whether a daily digest goes out (the weather one), to carry
+    /// skipped task runs
+    fn has_daily_digest(&self) -> bool {
+        self.weather.is_some() && !self.config.weather.digest_time.is_empty()
+    }
+
+    /// Task runs skipped after downtime that haven't been in a digest yet
+    fn take_skipped(&self) -> Option<String> {
+        let skipped = match self.run_log.take_skipped() {
+            Ok(skipped) => skipped,
+            Err(e) => {
+                warn!("Failed to read skipped heartbeat tasks: {}", e);
+                return None;
+            }
+        };
+        if skipped.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "Skipped while the heartbeat wasn't running:\n- {}",
+            skipped.join("\n- ")
+        ))
+    }
+
+    /// The assistant's promises that are due, for the heartbeat to check
+    fn commitments_note(&self) -> Option<String> {
+        let today = locale::now().date_naive();
+        let due = match self.commitments.as_ref()?.due(today) {
+            Ok(due) => due,
+            Err(e) => {
+                warn!("Failed to read commitments: {}", e);
+                return None;
+            }
+        };
+        if due.is_empty() {
+            return None;
+        }
+        let list: Vec<String> = due.iter().map(|c| c.summary()).collect();
+        Some(format!(
+            "\n\nYou told the family you would do these, and they're due:\n- {}\nFor each, \
+             check whether it was done (memory, reminders, lists, HEARTBEAT.md) and call \
+             resolve_commitment: done if it was, broken if its time has passed and it wasn't. \
+             If you can do one now, do it first and mark it done.",
+            list.join("\n- ")
+        ))
+    }
+
+    /// A few stale memory files for the heartbeat to ask the family about
+    fn stale_note(&self) -> Option<String> {
+        let log = self.reconfirm.as_ref()?;
+        let stale = match self.memory.stale_files() {
+            Ok(stale) => stale,
+            Err(e) => {
+                warn!("Failed to check for stale memory: {}", e);
+                return None;
+            }
+        };
+        if stale.is_empty() {
+            return None;
+        }
+        let due = match log.take_due(stale, locale::now().date_naive()) {
+            Ok(due) => due,
+            Err(e) => {
+                warn!("Failed to record stale memory questions: {}", e);
+                return None;
+            }
+        };
+        if due.is_empty() {
+            return None;
+        }
+        let list: Vec<String> = due.iter().map(|f| f.summary()).collect();
+        Some(format!(
+            "\n\nThese memory files haven't been confirmed in a while and may be out of \
+             date:\n- {}\nRead each and ask the family, in one short question per file, \
+             whether the key facts still hold (e.g. \"Is Leo still a size 13 shoe?\"). When \
+             they answer, update the file and set its `last_verified` frontmatter to today.",
+            list.join("\n- ")
+        ))
+    }
+
+    /// Promises found broken that haven't been in a digest yet
+    fn take_broken(&self) -> Option<String> {
+        let broken = match self.commitments.as_ref()?.take_broken() {
+            Ok(broken) => broken,
+            Err(e) => {
+                warn!("Failed to read broken commitments: {}", e);
+                return None;
+            }
+        };
+        if broken.is_empty() {
+            return None;
+        }
+        let list: Vec<String> = broken
+            .iter()
+            .map(|c| match c.note {
+                Some(ref note) => format!("{} ({})", c.summary(), note),
+                None => c.summary(),
+            })
+            .collect();
+        Some(format!(
+            "Promises the assistant didn't keep:\n- {}",
+            list.join("\n- ")
+        ))
+    }
+
+    /// Catch-up policy for the section titled `title`
+    fn catch_up_for(&self, title: &str) -> CatchUp {
+        // Checked in new_with_gate
+        CatchUp::parse(self.config.heartbeat.catch_up_for(title)).unwrap_or(CatchUp::Run)
+    }
+
+    /// Refetch subscribed calendars into upcoming.md when they're due
+    async fn sync_calendar(&self) {
+        match self.calendar.sync_if_due(locale::now()).await {
+            Ok(Some(path)) => {
+                if let Err(e) = self.memory.index_written_file(&path, "heartbeat") {
+                    warn!("Failed to index {}: {}", path.display(), e);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Calendar sync failed: {}", e),
+        }
+    }
+
+    /// Who's home
+    async fn presence_note(&self) -> Option<String> {
+        let snapshot = self.presence.as_ref()?.snapshot().await;
+        Some(format!("\n\nPresence: {}", snapshot.summary()))
+    }
+
+    /// Whether the announce tool may be used right now
+    async fn announce_note(&self) -> Option<String> {
+        let announcer = self.announcer.as_ref()?;
+        let now = locale::now().naive_local();
+        Some(match announcer.hold_reason(Event::Alert, now).await {
+            Some(reason) => format!(
+                "\n\nSpoken announcements are off right now ({}); use the announce tool only \
+                 for an emergency (emergency: true).",
+                reason
+            ),
+            None => "\n\nUse the announce tool for anything people at home need to hear now \
+                     (\"the dryer finished\", \"leave in 10 minutes for piano\")."
+                .to_string(),
+        })
+    }
+
+    /// Write last month's finance report on the first heartbeat of a month,
+    /// returning a note for the heartbeat prompt
+    async fn write_finance_report(&self) -> Option<String> {
+        if !self.config.finance.monthly_report {
+            return None;
+        }
+        let ledger = FinanceLedger::new(&self.workspace, &self.config.finance);
+        match ledger.write_monthly_report(locale::now().date_naive()) {
+            Ok(Some((headline, path))) => {
+                if let Err(e) = self.memory.index_written_file(&path, "heartbeat") {
+                    warn!("Failed to index {}: {}", path.display(), e);
+                }
+                let relative = path.strip_prefix(&self.workspace).unwrap_or(&path);
+                let digest = format!("Monthly finance report: {}", headline);
+                self.notify(Event::Digest, &digest).await;
+                Some(format!(
+                    "\n\nMonthly finance report written to {}: {}. Tell the user, briefly.",
+                    relative.display(),
+                    headline
+                ))
+            }
+            Ok(None) => None,
+            Err(e) => {
+                warn!("Finance report failed: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Write last week's journal on the first heartbeat of a week, unless
+    /// it's written or waiting for review already
+    async fn write_journal(&self) {
+        if !self.config.memory.weekly_journal {
+            return;
+        }
+        let week = (locale::now().date_naive() - chrono::Duration::days(7)).iso_week();
+        {
+            let mut tried = self.journal_week.lock().unwrap();
+            if *tried == Some(week) {
+                return;
+            }
+            *tried = Some(week);
+        }
+        let relative = journal_path(week);
+        let pending = self
+            .memory
+            .pending_changes()
+            .is_ok_and(|changes| changes.iter().any(|c| c.path == relative));
+        if pending || self.workspace.join(&relative).exists() {
+            return;
+        }
+
+        let log = fs::read_to_string(self.workspace.join(TASK_LOG_PATH)).unwrap_or_default();
+        let written = match self.heartbeat_agent().await {
+            Ok(agent) => agent.write_journal(week, &log).await,
+            Err(e) => Err(e),
+        };
+        match written {
+            Ok(Some((path, FileWrite::Written))) => {
+                if let Err(e) = self.memory.index_path(&path, false) {
+                    warn!("Failed to index {}: {}", path.display(), e);
+                }
+            }
+            Ok(Some((_, FileWrite::Staged(id)))) => {
+                info!(
+                    "Journal for {} staged for review as #{}",
+                    week_label(week),
+                    id
+                );
+            }
+            Ok(None) => debug!("Nothing for the {} journal", week_label(week)),
+            Err(e) => {
+                warn!("Journal for {} failed: {}", week_label(week), e);
+                // Tried again on the next heartbeat
+                *self.journal_week.lock().unwrap() = None;
+            }
+        }
+    }
+
+    /// Merge near-duplicate chunks once `memory.consolidation_interval`
+    /// has passed, like `memory consolidate --apply`
+    async fn consolidate_memory(&self) {
+        let Some(interval) = self.consolidation_interval else {
+            return;
+        };
+        {
+            let mut last = self.consolidated_at.lock().unwrap();
+            if last.is_some_and(|at| at.elapsed() < interval) {
+                return;
+            }
+            *last = Some(Instant::now());
+        }
+        if !self.memory.has_embeddings() {
+            debug!("Skipping memory consolidation: no embedding provider");
+            return;
+        }
+        if let Err(e) = self.memory.generate_embeddings().await {
+            warn!("Memory consolidation failed: {}", e);
+            return;
+        }
+
+        let _writes = self.memory.lock_writes().await;
+        match self
+            .memory
+            .consolidate(self.config.memory.consolidation_threshold, true)
+        {
+            Ok(report) if report.applied => info!(
+                "Merged {} near-duplicate memory chunks",
+                report.duplicate_count()
+            ),
+            Ok(_) => debug!("No near-duplicate memory chunks"),
+            Err(e) => warn!("Memory consolidation failed: {}", e),
+        }
+    }
+
     fn in_active_hours(&self) -> bool {
         let Some((start, end)) = self.active_hours else {
             return true; // No active hours configured, always active
         };
 
-        let now = Local::now().time();
+        let now = locale::now().time();
 
         if start <= end {
             // Normal range (e.g., 09:00 to 22:00)
@@ -299,13 +960,50 @@ impl HeartbeatRunner {
     }
 }
 
+/// The first line of `text`, cut to a log line's length
+fn first_line(text: &str) -> String {
+    let line = text.trim().lines().next().unwrap_or_default();
+    match line.char_indices().nth(160) {
+        Some((end, _)) => format!("{}...", &line[..end]),
+        None => line.to_string(),
+    }
+}
+
+/// Add `entries` to the task log at `path`, keeping the newest
+/// [`TASK_LOG_ENTRIES`]
+fn append_task_log(path: &Path, entries: &[String]) -> Result<()> {
+    let existing = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let mut lines: Vec<&str> = existing
+        .lines()
+        .filter(|line| line.starts_with("- "))
+        .collect();
+    lines.extend(entries.iter().map(String::as_str));
+    let keep = lines.len().saturating_sub(TASK_LOG_ENTRIES);
+
+    let mut content =
+        "# Heartbeat Log\n\nOne line per heartbeat task run, newest last.\n\n".to_string();
+    for line in &lines[keep..] {
+        content.push_str(line);
+        content.push('\n');
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, content)?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_active_hours_normal_range() {
-        // This test would require mocking Local::now()
+        // This test would require mocking locale::now()
         // For now, just verify the logic pattern
         let start = NaiveTime::from_hms_opt(9, 0, 0).unwrap();
         let end = NaiveTime::from_hms_opt(22, 0, 0).unwrap();
@@ -316,4 +1014,27 @@ mod tests {
         assert!(noon >= start && noon <= end);
         assert!(!(midnight >= start && midnight <= end));
     }
+
+    #[test]
+    fn test_append_task_log() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(TASK_LOG_PATH);
+        let entry = |i: usize| format!("- 2026-10-18 09:00 **Calendar Sync** ({} tokens): OK", i);
+
+        append_task_log(&path, &[entry(0)]).unwrap();
+        let entries: Vec<String> = (1..=TASK_LOG_ENTRIES).map(entry).collect();
+        append_task_log(&path, &entries).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("# Heartbeat Log\n"));
+        assert_eq!(
+            content.lines().filter(|l| l.starts_with("- ")).count(),
+            TASK_LOG_ENTRIES
+        );
+        assert!(!content.contains("(0 tokens)"));
+        assert!(content.ends_with(&format!("{}\n", entry(TASK_LOG_ENTRIES))));
+
+        let long = format!("{}\nsecond line", "a".repeat(200));
+        assert_eq!(first_line(&long), format!("{}...", "a".repeat(160)));
+    }
 }
@@ -0,0 +1,301 @@
+//! Heartbeat tasks: the `## Title (schedule)` sections of HEARTBEAT.md
+//!
+//! The agent's `schedule_task` tool turns "check the propane level page
+//! every Monday morning" into a section like
+//!
+//! ```markdown
+//! ## Propane Check (weekly, Monday 9am)
+//! - [ ] Check the propane level page
+//! ```
+//!
+//! with the schedule parsed into the same wording as the hand-written
+//! sections, and `list_tasks` and `remove_task` read and remove them.
+
+use anyhow::Result;
+use chrono::{NaiveDateTime, NaiveTime, Timelike};
+
+use crate::reminders::{parse_schedule, Repeat};
+
+/// The task file, relative to the workspace
+pub const HEARTBEAT_PATH: &str = "HEARTBEAT.md";
+
+/// One section of HEARTBEAT.md
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeartbeatTask {
+    pub title: String,
+    /// The heading's parenthesised schedule, e.g. "daily, 8pm"
+    pub schedule: Option<String>,
+    /// Lines under the heading, up to the next one
+    pub body: Vec<String>,
+}
+
+impl HeartbeatTask {
+    /// The section's checklist items
+    pub fn items(&self) -> impl Iterator<Item = &str> {
+        self.body
+            .iter()
+            .map(|line| line.trim())
+            .filter(|line| line.starts_with("- "))
+    }
+
+    /// The section as it reads in HEARTBEAT.md
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = match self.schedule {
+            Some(ref schedule) => format!("## {} ({})\n", self.title, schedule),
+            None => format!("## {}\n", self.title),
+        };
+        for line in &self.body {
+            markdown.push_str(line);
+            markdown.push('\n');
+        }
+        markdown
+    }
+}
+
+/// Heading text of a section ("Calendar Sync (every hour)"), if `line` is one
+fn heading(line: &str) -> Option<&str> {
+    line.strip_prefix("## ").map(str::trim)
+}
+
+fn split_heading(heading: &str) -> (String, Option<String>) {
+    if let Some(open) = heading.rfind(" (").filter(|_| heading.ends_with(')')) {
+        let schedule = &heading[open + 2..heading.len() - 1];
+        return (
+            heading[..open].trim().to_string(),
+            Some(schedule.to_string()),
+        );
+    }
+    (heading.to_string(), None)
+}
+
+/// The sections of HEARTBEAT.md `content`, in order
+pub fn parse_tasks(content: &str) -> Vec<HeartbeatTask> {
+    let mut tasks: Vec<HeartbeatTask> = Vec::new();
+    let mut in_task = false;
+    for line in content.lines() {
+        if let Some(heading) = heading(line) {
+            let (title, schedule) = split_heading(heading);
+            tasks.push(HeartbeatTask {
+                title,
+                schedule,
+                body: Vec::new(),
+            });
+            in_task = true;
+        } else if line.starts_with("# ") {
+            in_task = false;
+        } else if in_task {
+            if let Some(task) = tasks.last_mut() {
+                task.body.push(line.to_string());
+            }
+        }
+    }
+    for task in &mut tasks {
+        while task.body.last().is_some_and(|line| line.trim().is_empty()) {
+            task.body.pop();
+        }
+    }
+    tasks
+}
+
+/// `content` with a section for `title` appended, each line of `items` a
+/// checklist item. Fails if a section with that title exists.
+pub fn add_task(content: &str, title: &str, schedule: &str, items: &str) -> Result<String> {
+    let title = title.trim().trim_start_matches('#').trim();
+    if title.is_empty() {
+        anyhow::bail!("The task needs a title");
+    }
+    if find(content, title).is_some() {
+        anyhow::bail!(
+            "HEARTBEAT.md already has a task called '{}'; remove it first or pick another title",
+            title
+        );
+    }
+    let items: Vec<String> = items
+        .lines()
+        .map(|line| {
+            let line = line.trim();
+            let line = line.strip_prefix("- [ ]").unwrap_or(line);
+            line.strip_prefix("- ").unwrap_or(line).trim().to_string()
+        })
+        .filter(|line| !line.is_empty())
+        .collect();
+    if items.is_empty() {
+        anyhow::bail!("The task needs something to do");
+    }
+
+    let mut content = content.trim_end().to_string();
+    if !content.is_empty() {
+        content.push_str("\n\n");
+    }
+    content.push_str(&format!("## {} ({})\n", title, schedule));
+    for item in items {
+        content.push_str(&format!("- [ ] {}\n", item));
+    }
+    Ok(content)
+}
+
+/// `content` without the section titled `title` (any case), and the
+/// section removed
+pub fn remove_task(content: &str, title: &str) -> Result<(String, HeartbeatTask)> {
+    let title = title.trim();
+    let Some(task) = parse_tasks(content)
+        .into_iter()
+        .find(|task| task.title.eq_ignore_ascii_case(title))
+    else {
+        anyhow::bail!("HEARTBEAT.md has no task called '{}'", title);
+    };
+    let start = find(content, title).unwrap_or_default();
+    let lines: Vec<&str> = content.lines().collect();
+    let end = lines[start + 1..]
+        .iter()
+        .position(|line| line.starts_with("## ") || line.starts_with("# "))
+        .map_or(lines.len(), |i| start + 1 + i);
+
+    let mut kept: Vec<&str> = lines[..start].to_vec();
+    while kept.last().is_some_and(|line| line.trim().is_empty()) {
+        kept.pop();
+    }
+    if end < lines.len() && !kept.is_empty() {
+        kept.push("");
+    }
+    kept.extend(&lines[end..]);
+    let mut content = kept.join("\n");
+    content.push('\n');
+    Ok((content, task))
+}
+
+/// Line index of the heading of the section titled `title`
+fn find(content: &str, title: &str) -> Option<usize> {
+    content.lines().position(|line| {
+        heading(line).is_some_and(|h| split_heading(h).0.eq_ignore_ascii_case(title))
+    })
+}
+
+/// The heading schedule for the recurring `text` ("every Monday morning"
+/// is "weekly, Monday 9am"), worded like the hand-written sections
+pub fn schedule_label(text: &str, now: NaiveDateTime) -> Result<String> {
+    let lowered = text.trim().to_lowercase();
+    let words: Vec<&str> = lowered.split_whitespace().collect();
+    match words.as_slice() {
+        ["hourly"] | ["every", "hour"] => return Ok("every hour".to_string()),
+        ["every", n, unit] if n.parse::<u32>().is_ok_and(|n| n > 0) => {
+            match unit.trim_end_matches('s') {
+                "hour" | "hr" => return Ok(format!("every {} hours", n)),
+                "minute" | "min" => return Ok(format!("every {} minutes", n)),
+                _ => {}
+            }
+        }
+        _ => {}
+    }
+
+    let schedule = parse_schedule(text, now)?;
+    let Some(repeat) = schedule.repeat else {
+        anyhow::bail!(
+            "'{}' doesn't repeat; heartbeat tasks are for recurring work (use set_reminder \
+             for one-off things). Try e.g. 'every Monday morning' or 'daily at 8pm'",
+            text
+        );
+    };
+    let due = schedule.due;
+    let time = clock(due.time());
+    let start = || due.format("from %Y-%m-%d").to_string();
+    Ok(match repeat {
+        Repeat::Days(1) => format!("daily, {}", time),
+        Repeat::Weekdays => format!("weekdays, {}", time),
+        Repeat::Weeks(1) => format!("weekly, {} {}", due.format("%A"), time),
+        Repeat::Months { every: 1, day } => format!("monthly, {} {}", ordinal(day), time),
+        Repeat::Days(_) => format!("{}, {}, {}", repeat, time, start()),
+        Repeat::Weeks(_) => format!("{}, {} {}, {}", repeat, due.format("%A"), time, start()),
+        Repeat::Months { day, .. } => format!("{}, {} {}, {}", repeat, ordinal(day), time, start()),
+    })
+}
+
+/// "9am", "8:30pm"
+fn clock(time: NaiveTime) -> String {
+    let (pm, hour) = time.hour12();
+    let suffix = if pm { "pm" } else { "am" };
+    if time.minute() == 0 {
+        format!("{}{}", hour, suffix)
+    } else {
+        format!("{}:{:02}{}", hour, time.minute(), suffix)
+    }
+}
+
+/// "1st", "22nd"
+fn ordinal(day: u32) -> String {
+    let suffix = match (day % 10, day % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", day, suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    const HEARTBEAT: &str = "# HEARTBEAT.md - Recurring Tasks\n\n\
+        Tasks listed here run during heartbeat cycles.\n\n\
+        ## Calendar Sync (every hour)\n\
+        - [ ] Fetch today's events\n\n\
+        ## Home Maintenance (weekly, Sunday)\n\
+        - [ ] Check memory/home/maintenance.md\n\
+        - [x] Flag any overdue items\n";
+
+    #[test]
+    fn test_schedule_label() {
+        // Saturday
+        let now = NaiveDate::from_ymd_opt(2026, 10, 17)
+            .unwrap()
+            .and_hms_opt(14, 0, 0)
+            .unwrap();
+        let label = |text| schedule_label(text, now).unwrap();
+        assert_eq!(label("every Monday morning"), "weekly, Monday 9am");
+        assert_eq!(label("daily at 8:30pm"), "daily, 8:30pm");
+        assert_eq!(label("every weekday at 7am"), "weekdays, 7am");
+        assert_eq!(label("every 2 hours"), "every 2 hours");
+        assert_eq!(
+            label("every other Saturday at 10am"),
+            "every other week, Saturday 10am, from 2026-10-24"
+        );
+        assert_eq!(label("monthly on the 1st of november"), "monthly, 1st 9am");
+        assert!(schedule_label("tomorrow at 9am", now).is_err());
+    }
+
+    #[test]
+    fn test_add_and_remove_task() {
+        let tasks = parse_tasks(HEARTBEAT);
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].schedule.as_deref(), Some("every hour"));
+        assert_eq!(tasks[1].items().count(), 2);
+        assert_eq!(
+            tasks[0].to_markdown(),
+            "## Calendar Sync (every hour)\n- [ ] Fetch today's events\n"
+        );
+
+        let content = add_task(
+            HEARTBEAT,
+            "Propane Check",
+            "weekly, Monday 9am",
+            "- Check the propane level page\nTell us if it's under 20%",
+        )
+        .unwrap();
+        assert!(content.ends_with(
+            "- [x] Flag any overdue items\n\n## Propane Check (weekly, Monday 9am)\n\
+             - [ ] Check the propane level page\n- [ ] Tell us if it's under 20%\n"
+        ));
+        assert!(add_task(&content, "propane check", "daily, 9am", "Again").is_err());
+
+        // From the middle: the sections around it stay apart
+        let (content, removed) = remove_task(&content, "home maintenance").unwrap();
+        assert_eq!(removed.title, "Home Maintenance");
+        assert!(content.contains("- [ ] Fetch today's events\n\n## Propane Check"));
+        let (content, _) = remove_task(&content, "Propane Check").unwrap();
+        assert!(content.ends_with("- [ ] Fetch today's events\n"));
+        assert!(remove_task(&content, "Propane Check").is_err());
+    }
+}
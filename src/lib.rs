@@ -2,18 +2,55 @@
 //!
 //! This crate provides the core functionality for HomeGPT, including:
 //! - Agent core with LLM provider abstraction
+//! - Encrypted backups to a directory or S3 bucket, with restore
 //! - Memory system with markdown files and SQLite index
 //! - Heartbeat runner for continuous operation
-//! - HTTP server for UI integration
+//! - Finance tracking from bank CSV exports
+//! - Business connectors checked by the heartbeat
+//! - Weather forecasts (Open-Meteo)
+//! - Calendar subscriptions (.ics) and a published feed of created events
+//! - Presence (who's home) from Home Assistant or OwnTracks
+//! - Spoken announcements through Piper or Home Assistant speakers
+//! - Speech to text (Whisper) for the desktop mic
+//! - Matrix and Discord chat bridges
+//! - Webhooks: inbound triggers and outbound event notifications
+//! - Push notifications (ntfy, Pushover) per event type
+//! - Quiet hours per night and per person, with an emergency bypass
+//! - Reminders set in plain language ("every other Saturday")
+//! - Kitchen timers, spoken and pushed when they go off
+//! - The assistant's own promises, checked by the heartbeat until kept
+//! - HTTP server for UI integration, and a client that attaches to it
+//! - English and Spanish messages, dates and workspace templates
+//! - Logging to daily JSON trace files, correlated by session and turn
+//! - Prometheus metrics for the daemon
 //! - Desktop GUI (egui-based)
 
 pub mod agent;
+pub mod announce;
+pub mod backup;
+pub mod bridges;
+pub mod business;
+pub mod calendar;
+pub mod client;
+pub mod commitments;
 pub mod concurrency;
 pub mod config;
 #[cfg(feature = "desktop")]
 pub mod desktop;
+pub mod finance;
 pub mod heartbeat;
+pub mod locale;
+pub mod logging;
 pub mod memory;
+pub mod metrics;
+pub mod notifications;
+pub mod presence;
+pub mod quiet;
+pub mod reminders;
 pub mod server;
+pub mod stt;
+pub mod timers;
+pub mod weather;
+pub mod webhooks;
 
 pub use config::Config;
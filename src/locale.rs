@@ -0,0 +1,529 @@
+//! Languages: UI strings, date formatting and localized workspace files
+//!
+//! `[locale] language` picks the language of notification titles, timer and
+//! reminder messages, dates, and new workspace files; `languages` lists
+//! every language the household speaks, so the agent answers in whichever
+//! one it's addressed in. Built-in strings can be overridden per language
+//! in `~/.homegpt/locales/<code>.toml` (flat `key = "text"` pairs).
+//!
+//! Files can be localized by adding the language code before the
+//! extension: `SOUL.es.md` is read instead of `SOUL.md`, and
+//! `skills/tutor/SKILL.es.md` instead of `SKILL.md`, when the language is
+//! Spanish.
+//!
+//! The locale is process-wide: [`init`] sets it from the config at startup
+//! and [`current`] falls back to English until then.
+//!
+//! `[locale] timezone` is the home timezone, set process-wide by
+//! [`set_timezone`]. Heartbeat schedules, active hours, reminders, digests
+//! and displayed times go by [`now`] and [`home_time`], so they follow it
+//! (DST included) even when the host runs on UTC.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use once_cell::sync::{Lazy, OnceCell};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tracing::warn;
+
+use crate::agent::get_state_dir;
+use crate::config::LocaleConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    #[default]
+    English,
+    Spanish,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::Spanish];
+
+    /// ISO 639-1 code, as used in file names (`SOUL.es.md`)
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::English => "en",
+            Self::Spanish => "es",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::English => "English",
+            Self::Spanish => "Spanish",
+        }
+    }
+
+    fn strings(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::English => EN_STRINGS,
+            Self::Spanish => ES_STRINGS,
+        }
+    }
+
+    fn weekdays(self) -> [&'static str; 7] {
+        match self {
+            Self::English => ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"],
+            Self::Spanish => ["lun", "mar", "mié", "jue", "vie", "sáb", "dom"],
+        }
+    }
+
+    fn short_months(self) -> [&'static str; 12] {
+        match self {
+            Self::English => [
+                "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+            ],
+            Self::Spanish => [
+                "ene", "feb", "mar", "abr", "may", "jun", "jul", "ago", "sept", "oct", "nov", "dic",
+            ],
+        }
+    }
+
+    fn months(self) -> [&'static str; 12] {
+        match self {
+            Self::English => [
+                "January",
+                "February",
+                "March",
+                "April",
+                "May",
+                "June",
+                "July",
+                "August",
+                "September",
+                "October",
+                "November",
+                "December",
+            ],
+            Self::Spanish => [
+                "enero",
+                "febrero",
+                "marzo",
+                "abril",
+                "mayo",
+                "junio",
+                "julio",
+                "agosto",
+                "septiembre",
+                "octubre",
+                "noviembre",
+                "diciembre",
+            ],
+        }
+    }
+}
+
+impl FromStr for Language {
+    type Err = anyhow::Error;
+
+    /// Accepts codes ("es", "es-MX", "es_MX.UTF-8") and names ("Spanish",
+    /// "español")
+    fn from_str(s: &str) -> Result<Self> {
+        let lower = s.trim().to_lowercase();
+        let code = lower.split(['-', '_', '.']).next().unwrap_or("");
+        match code {
+            "en" | "english" => Ok(Self::English),
+            "es" | "spanish" | "español" | "espanol" => Ok(Self::Spanish),
+            _ => anyhow::bail!(
+                "Unsupported language '{}': expected one of {}",
+                s,
+                Self::ALL.map(|l| l.code()).join(", ")
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+pub struct Locale {
+    language: Language,
+    /// Other languages the household speaks
+    also_speaks: Vec<Language>,
+    strings: BTreeMap<String, String>,
+}
+
+static CURRENT: OnceCell<Locale> = OnceCell::new();
+static ENGLISH: Lazy<Locale> = Lazy::new(|| Locale::builtin(Language::English));
+
+/// Set the process-wide locale from the config. Problems are logged and
+/// leave the built-in English strings in place; only the first call counts.
+pub fn init(config: &LocaleConfig) {
+    match Locale::load(config) {
+        Ok(locale) => {
+            let _ = CURRENT.set(locale);
+        }
+        Err(e) => warn!("Using English: {:#}", e),
+    }
+}
+
+/// The locale set by [`init`], or English
+pub fn current() -> &'static Locale {
+    CURRENT.get().unwrap_or(&ENGLISH)
+}
+
+static TIMEZONE: OnceCell<Tz> = OnceCell::new();
+
+/// Use `name` (e.g. "America/Chicago") as the home timezone; empty keeps
+/// the system's. Only the first call counts.
+pub fn set_timezone(name: &str) -> Result<()> {
+    if let Some(tz) = parse_timezone(name)? {
+        let _ = TIMEZONE.set(tz);
+    }
+    Ok(())
+}
+
+/// `name` as a timezone from the built-in tz database (`None` when empty)
+pub fn parse_timezone(name: &str) -> Result<Option<Tz>> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Ok(None);
+    }
+    name.parse().map(Some).map_err(|_| {
+        anyhow::anyhow!(
+            "Unknown timezone '{}': expected an IANA name like \"America/Chicago\"",
+            name
+        )
+    })
+}
+
+/// The home timezone's name, if one is set
+pub fn timezone_name() -> Option<&'static str> {
+    TIMEZONE.get().map(|tz| tz.name())
+}
+
+/// The time at home: in the home timezone, or the system's if none is set
+pub fn now() -> DateTime<FixedOffset> {
+    home_time(&Utc::now())
+}
+
+/// `time` on the home clock
+pub fn home_time<Z: TimeZone>(time: &DateTime<Z>) -> DateTime<FixedOffset> {
+    match TIMEZONE.get() {
+        Some(tz) => time.with_timezone(tz).fixed_offset(),
+        None => time.with_timezone(&Local).fixed_offset(),
+    }
+}
+
+impl Locale {
+    /// Built-in strings for `language`
+    pub fn builtin(language: Language) -> Self {
+        let strings = language
+            .strings()
+            .iter()
+            .map(|(key, text)| (key.to_string(), text.to_string()))
+            .collect();
+        Self {
+            language,
+            also_speaks: Vec::new(),
+            strings,
+        }
+    }
+
+    /// Built-in strings with overrides from `~/.homegpt/locales/<code>.toml`
+    pub fn load(config: &LocaleConfig) -> Result<Self> {
+        let language: Language = config.language.parse()?;
+        let mut locale = Self::builtin(language);
+        for name in &config.languages {
+            let other: Language = name.parse()?;
+            if other != language && !locale.also_speaks.contains(&other) {
+                locale.also_speaks.push(other);
+            }
+        }
+
+        let path = get_state_dir()?
+            .join("locales")
+            .join(format!("{}.toml", language.code()));
+        if path.exists() {
+            let content = fs::read_to_string(&path)?;
+            let overrides: BTreeMap<String, String> = toml::from_str(&content)
+                .with_context(|| format!("Invalid strings in {}", path.display()))?;
+            locale.apply_overrides(overrides);
+        }
+        Ok(locale)
+    }
+
+    fn apply_overrides(&mut self, overrides: BTreeMap<String, String>) {
+        for (key, text) in overrides {
+            if !self.strings.contains_key(&key) {
+                warn!("Ignoring unknown string '{}'", key);
+                continue;
+            }
+            self.strings.insert(key, text);
+        }
+    }
+
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    /// Every language the household speaks, the configured one first
+    pub fn languages(&self) -> Vec<Language> {
+        let mut languages = vec![self.language];
+        languages.extend(&self.also_speaks);
+        languages
+    }
+
+    /// The string for `key`, or the key itself if there's none
+    pub fn text<'a>(&'a self, key: &'a str) -> &'a str {
+        self.strings.get(key).map(String::as_str).unwrap_or(key)
+    }
+
+    /// The string for `key` with `{name}` placeholders filled in
+    pub fn format(&self, key: &str, args: &[(&str, &str)]) -> String {
+        let mut text = self.text(key).to_string();
+        for (name, value) in args {
+            text = text.replace(&format!("{{{}}}", name), value);
+        }
+        text
+    }
+
+    /// "Tue 20 Oct" / "mar 20 oct"
+    pub fn short_date(&self, date: NaiveDate) -> String {
+        format!(
+            "{} {} {}",
+            self.language.weekdays()[date.weekday().num_days_from_monday() as usize],
+            date.day(),
+            self.language.short_months()[date.month0() as usize]
+        )
+    }
+
+    /// "Tue 20 Oct 16:00" / "mar 20 oct 16:00"
+    pub fn short_datetime(&self, time: NaiveDateTime) -> String {
+        format!("{} {}", self.short_date(time.date()), time.format("%H:%M"))
+    }
+
+    /// "October 2026" / "octubre de 2026"
+    pub fn month_year(&self, date: NaiveDate) -> String {
+        let month = self.language.months()[date.month0() as usize];
+        match self.language {
+            Language::English => format!("{} {}", month, date.year()),
+            Language::Spanish => format!("{} de {}", month, date.year()),
+        }
+    }
+
+    /// `dir/NAME.<code>.ext` if it exists, otherwise `path`
+    pub fn localized_path(&self, path: &Path) -> PathBuf {
+        let (Some(stem), Some(ext)) = (
+            path.file_stem().and_then(|s| s.to_str()),
+            path.extension().and_then(|e| e.to_str()),
+        ) else {
+            return path.to_path_buf();
+        };
+        let localized = path.with_file_name(format!("{}.{}.{}", stem, self.language.code(), ext));
+        if localized.exists() {
+            localized
+        } else {
+            path.to_path_buf()
+        }
+    }
+}
+
+/// Split `NAME.<code>.ext` into (`NAME.ext`, language) for a known code
+pub fn split_localized_name(name: &str) -> Option<(String, Language)> {
+    let (rest, ext) = name.rsplit_once('.')?;
+    let (stem, code) = rest.rsplit_once('.')?;
+    let language = Language::ALL.into_iter().find(|l| l.code() == code)?;
+    Some((format!("{}.{}", stem, ext), language))
+}
+
+/// Built-in English strings; every key must also be in [`ES_STRINGS`]
+const EN_STRINGS: &[(&str, &str)] = &[
+    ("notification.alert", "HomeGPT"),
+    ("notification.digest", "HomeGPT digest"),
+    ("notification.heartbeat_failed", "HomeGPT heartbeat failed"),
+    ("notification.approval_requested", "HomeGPT needs approval"),
+    ("notification.reminder", "Reminder"),
+    ("notification.timer", "Timer done"),
+    ("notification.emergency", "HomeGPT emergency"),
+    (
+        "safety.blocked",
+        "Let's talk about something else. That one is a question for a grown-up!",
+    ),
+    ("reminder.late", "{message} (was due {due})"),
+    ("timer.unlabeled", "{duration} timer"),
+    ("timer.left", "#{id} {name}: {time} left"),
+    ("timer.done", "Your {duration} timer is done."),
+    ("timer.done_labeled", "The {label} timer is done."),
+    ("timer.finished_at", "It finished at {time}."),
+    ("calendar.all_day", "all day"),
+    ("calendar.all_day_until", "all day, until {date}"),
+    // "a 12 minute timer": singular either way
+    ("duration.hour", "{n} hour"),
+    ("duration.hours", "{n} hour"),
+    ("duration.minute", "{n} minute"),
+    ("duration.minutes", "{n} minute"),
+    ("duration.second", "{n} second"),
+    ("duration.seconds", "{n} second"),
+    ("tutor.struggled", "Struggled with"),
+    ("tutor.got_right", "Got right"),
+    ("journal.title", "Week {week} ({from} to {to})"),
+    ("journal.happened", "What happened"),
+    ("journal.decisions", "Decisions"),
+    ("journal.follow_ups", "Open follow-ups"),
+    (
+        "answer.not_sure",
+        "I'm not sure. I couldn't find that in my notes.",
+    ),
+    (
+        "self_check.unsupported",
+        "I couldn't confirm this part in my notes:",
+    ),
+];
+
+const ES_STRINGS: &[(&str, &str)] = &[
+    ("notification.alert", "HomeGPT"),
+    ("notification.digest", "Resumen de HomeGPT"),
+    (
+        "notification.heartbeat_failed",
+        "Falló el heartbeat de HomeGPT",
+    ),
+    (
+        "notification.approval_requested",
+        "HomeGPT necesita aprobación",
+    ),
+    ("notification.reminder", "Recordatorio"),
+    ("notification.timer", "Temporizador terminado"),
+    ("notification.emergency", "Emergencia de HomeGPT"),
+    (
+        "safety.blocked",
+        "Hablemos de otra cosa. ¡Esa pregunta es para un adulto!",
+    ),
+    ("reminder.late", "{message} (era para el {due})"),
+    ("timer.unlabeled", "temporizador de {duration}"),
+    ("timer.left", "#{id} {name}: quedan {time}"),
+    ("timer.done", "Tu temporizador de {duration} ha terminado."),
+    (
+        "timer.done_labeled",
+        "El temporizador de {label} ha terminado.",
+    ),
+    ("timer.finished_at", "Terminó a las {time}."),
+    ("calendar.all_day", "todo el día"),
+    ("calendar.all_day_until", "todo el día, hasta el {date}"),
+    ("duration.hour", "{n} hora"),
+    ("duration.hours", "{n} horas"),
+    ("duration.minute", "{n} minuto"),
+    ("duration.minutes", "{n} minutos"),
+    ("duration.second", "{n} segundo"),
+    ("duration.seconds", "{n} segundos"),
+    ("tutor.struggled", "Le costó"),
+    ("tutor.got_right", "Le salió bien"),
+    ("journal.title", "Semana {week} ({from} al {to})"),
+    ("journal.happened", "Qué pasó"),
+    ("journal.decisions", "Decisiones"),
+    ("journal.follow_ups", "Pendientes"),
+    (
+        "answer.not_sure",
+        "No estoy seguro. No encontré eso en mis notas.",
+    ),
+    (
+        "self_check.unsupported",
+        "No pude confirmar esto en mis notas:",
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_language() {
+        assert_eq!("es-MX".parse::<Language>().unwrap(), Language::Spanish);
+        assert_eq!(
+            "en_US.UTF-8".parse::<Language>().unwrap(),
+            Language::English
+        );
+        assert_eq!("Español".parse::<Language>().unwrap(), Language::Spanish);
+        assert!("fr".parse::<Language>().is_err());
+    }
+
+    #[test]
+    fn test_every_language_has_every_string() {
+        for language in Language::ALL {
+            let keys: Vec<&str> = language.strings().iter().map(|(key, _)| *key).collect();
+            let english: Vec<&str> = EN_STRINGS.iter().map(|(key, _)| *key).collect();
+            assert_eq!(keys, english, "{} strings", language);
+        }
+    }
+
+    #[test]
+    fn test_format_and_overrides() {
+        let mut locale = Locale::builtin(Language::Spanish);
+        assert_eq!(
+            locale.format("timer.done_labeled", &[("label", "pasta")]),
+            "El temporizador de pasta ha terminado."
+        );
+        locale.apply_overrides(BTreeMap::from([
+            ("notification.timer".to_string(), "¡Listo!".to_string()),
+            ("no.such.key".to_string(), "x".to_string()),
+        ]));
+        assert_eq!(locale.text("notification.timer"), "¡Listo!");
+        assert_eq!(locale.text("no.such.key"), "no.such.key");
+    }
+
+    #[test]
+    fn test_dates() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 20).unwrap();
+        let english = Locale::builtin(Language::English);
+        let spanish = Locale::builtin(Language::Spanish);
+        assert_eq!(
+            english.short_date(date),
+            date.format("%a %-d %b").to_string()
+        );
+        assert_eq!(spanish.short_date(date), "mar 20 oct");
+        assert_eq!(english.month_year(date), "October 2026");
+        assert_eq!(spanish.month_year(date), "octubre de 2026");
+        assert_eq!(
+            spanish.short_datetime(date.and_hms_opt(16, 0, 0).unwrap()),
+            "mar 20 oct 16:00"
+        );
+    }
+
+    #[test]
+    fn test_parse_timezone() {
+        assert!(parse_timezone("Mars/Olympus_Mons").is_err());
+        assert!(parse_timezone("../../etc/passwd").is_err());
+        assert_eq!(parse_timezone(" ").unwrap(), None);
+        let chicago = parse_timezone("America/Chicago").unwrap().unwrap();
+
+        // 13:00 UTC is 08:00 in Chicago in summer, 07:00 in winter
+        let summer = Utc.with_ymd_and_hms(2026, 7, 1, 13, 0, 0).unwrap();
+        let winter = Utc.with_ymd_and_hms(2026, 12, 1, 13, 0, 0).unwrap();
+        assert_eq!(
+            summer.with_timezone(&chicago).format("%H:%M").to_string(),
+            "08:00"
+        );
+        assert_eq!(
+            winter.with_timezone(&chicago).format("%H:%M").to_string(),
+            "07:00"
+        );
+    }
+
+    #[test]
+    fn test_localized_names() {
+        assert_eq!(
+            split_localized_name("SOUL.es.md"),
+            Some(("SOUL.md".to_string(), Language::Spanish))
+        );
+        assert_eq!(split_localized_name("SOUL.md"), None);
+        assert_eq!(split_localized_name("notes.v2.md"), None);
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let soul = tmp.path().join("SOUL.md");
+        let spanish = Locale::builtin(Language::Spanish);
+        assert_eq!(spanish.localized_path(&soul), soul);
+        fs::write(tmp.path().join("SOUL.es.md"), "").unwrap();
+        assert_eq!(spanish.localized_path(&soul), tmp.path().join("SOUL.es.md"));
+        assert_eq!(
+            Locale::builtin(Language::English).localized_path(&soul),
+            soul
+        );
+    }
+}
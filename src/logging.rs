@@ -0,0 +1,335 @@
+//! Logging setup and the trace files
+//!
+//! Every process logs to the console as before and, as JSON lines, to
+//! `~/.homegpt/logs/trace-YYYY-MM-DD.jsonl`, starting a new file at midnight
+//! and pruning files older than `logging.retention_days`. The agent wraps
+//! each reply in a `turn` span carrying the session and turn IDs; LLM calls,
+//! tool calls and memory searches run in spans inside it. Every line in the
+//! file lists its enclosing spans, so a log line can be traced back to the
+//! turn that caused it. The desktop log panel reads the files back with
+//! [`read_recent`].
+
+use anyhow::Result;
+use serde_json::Value;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::Level;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter, Layer};
+
+use crate::agent::get_state_dir;
+use crate::config::LoggingConfig;
+use crate::locale;
+
+const TRACE_PREFIX: &str = "trace-";
+const TRACE_SUFFIX: &str = ".jsonl";
+
+/// The daemon's output log, `homegpt-YYYY-MM-DD.log`
+const DAEMON_LOG_PREFIX: &str = "homegpt-";
+const DAEMON_LOG_SUFFIX: &str = ".log";
+
+/// Directory holding the trace files and the daemon's output log
+pub fn logs_dir() -> Result<PathBuf> {
+    Ok(get_state_dir()?.join("logs"))
+}
+
+/// Install the global subscriber: console output at `console_level`
+/// (`RUST_LOG` overrides it) and the trace file at `config.level`
+pub fn init(config: &LoggingConfig, console_level: &str, ansi: bool) {
+    install(config, console_level, ansi, io::stdout);
+}
+
+/// Like [`init`], but the console output goes to the dated daemon log
+/// instead, a new file each day. For the daemon run as a service, where
+/// nothing would rotate captured output.
+pub fn init_to_daemon_log(config: &LoggingConfig, console_level: &str) {
+    let log = logs_dir().and_then(|dir| {
+        Ok(RollingFile::open(
+            dir,
+            DAEMON_LOG_PREFIX,
+            DAEMON_LOG_SUFFIX,
+            config.retention_days,
+        )?)
+    });
+    match log {
+        Ok(writer) => install(config, console_level, false, Mutex::new(writer)),
+        Err(e) => {
+            eprintln!("Warning: Not writing the daemon log: {}", e);
+            install(config, console_level, false, io::stdout);
+        }
+    }
+}
+
+fn install<W>(config: &LoggingConfig, console_level: &str, ansi: bool, console_writer: W)
+where
+    W: for<'w> fmt::MakeWriter<'w> + Send + Sync + 'static,
+{
+    let console_filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(console_level));
+    let console = fmt::layer()
+        .with_ansi(ansi)
+        .with_writer(console_writer)
+        .with_filter(console_filter);
+
+    let trace = logs_dir().and_then(|dir| {
+        Ok(RollingFile::open(
+            dir,
+            TRACE_PREFIX,
+            TRACE_SUFFIX,
+            config.retention_days,
+        )?)
+    });
+    let file = match trace {
+        Ok(writer) => Some(
+            fmt::layer()
+                .json()
+                .with_current_span(false)
+                .with_span_list(true)
+                .with_writer(Mutex::new(writer))
+                .with_filter(EnvFilter::new(&config.level)),
+        ),
+        Err(e) => {
+            eprintln!("Warning: Not writing trace logs: {}", e);
+            None
+        }
+    };
+
+    tracing_subscriber::registry()
+        .with(console)
+        .with(file)
+        .init();
+}
+
+/// Delete `<prefix>YYYY-MM-DD<suffix>` files in `dir` older than
+/// `keep_days` days (0 keeps everything)
+pub fn prune_logs(dir: &Path, prefix: &str, suffix: &str, keep_days: u32) {
+    if keep_days == 0 {
+        return;
+    }
+    let cutoff = locale::now() - chrono::Duration::days(keep_days as i64);
+    let cutoff_date = cutoff.format("%Y-%m-%d").to_string();
+
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let date = name
+            .to_str()
+            .and_then(|name| name.strip_prefix(prefix))
+            .and_then(|name| name.strip_suffix(suffix));
+        if date.is_some_and(|date| date < cutoff_date.as_str()) {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// Appends to one log file per day, `<prefix>YYYY-MM-DD<suffix>`
+struct RollingFile {
+    dir: PathBuf,
+    prefix: &'static str,
+    suffix: &'static str,
+    retention_days: u32,
+    date: String,
+    file: File,
+}
+
+impl RollingFile {
+    fn open(
+        dir: PathBuf,
+        prefix: &'static str,
+        suffix: &'static str,
+        retention_days: u32,
+    ) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        let date = today();
+        let file = open_day(&dir.join(format!("{}{}{}", prefix, date, suffix)))?;
+        prune_logs(&dir, prefix, suffix, retention_days);
+        Ok(Self {
+            dir,
+            prefix,
+            suffix,
+            retention_days,
+            date,
+            file,
+        })
+    }
+
+    /// Write a line logged on `date`, switching files when the date changed
+    fn write_on(&mut self, date: &str, buf: &[u8]) -> io::Result<usize> {
+        if date != self.date {
+            let name = format!("{}{}{}", self.prefix, date, self.suffix);
+            self.file = open_day(&self.dir.join(name))?;
+            self.date = date.to_string();
+            prune_logs(&self.dir, self.prefix, self.suffix, self.retention_days);
+        }
+        self.file.write(buf)
+    }
+}
+
+impl Write for RollingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_on(&today(), buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn today() -> String {
+    locale::now().format("%Y-%m-%d").to_string()
+}
+
+fn open_day(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// One line of a trace file
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogRecord {
+    pub timestamp: String,
+    pub level: Level,
+    pub target: String,
+    /// The message followed by the event's other fields as `key=value`
+    pub message: String,
+    pub session_id: Option<String>,
+    pub turn_id: Option<String>,
+    /// Enclosing spans, outermost first
+    pub spans: Vec<String>,
+}
+
+impl LogRecord {
+    /// Parse a line written by the trace file layer
+    pub fn parse(line: &str) -> Option<Self> {
+        let value: Value = serde_json::from_str(line).ok()?;
+        let level = value["level"].as_str()?.parse().ok()?;
+        let text = |v: &Value| match v {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+
+        let mut message = value["fields"]["message"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        if let Some(fields) = value["fields"].as_object() {
+            for (key, field) in fields.iter().filter(|(key, _)| *key != "message") {
+                message.push_str(&format!(" {}={}", key, text(field)));
+            }
+        }
+
+        let mut record = Self {
+            timestamp: value["timestamp"].as_str().unwrap_or_default().to_string(),
+            level,
+            target: value["target"].as_str().unwrap_or_default().to_string(),
+            message,
+            session_id: None,
+            turn_id: None,
+            spans: Vec::new(),
+        };
+        for span in value["spans"].as_array().into_iter().flatten() {
+            record
+                .spans
+                .push(span["name"].as_str().unwrap_or("?").to_string());
+            if let Some(id) = span.get("session_id") {
+                record.session_id = Some(text(id));
+            }
+            if let Some(id) = span.get("turn_id") {
+                record.turn_id = Some(text(id));
+            }
+        }
+        Some(record)
+    }
+}
+
+/// The last `limit` records from the most recent trace files, oldest first
+pub fn read_recent(limit: usize) -> Result<Vec<LogRecord>> {
+    read_recent_in(&logs_dir()?, limit)
+}
+
+fn read_recent_in(dir: &Path, limit: usize) -> Result<Vec<LogRecord>> {
+    let mut files: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| {
+                        name.starts_with(TRACE_PREFIX) && name.ends_with(TRACE_SUFFIX)
+                    })
+            })
+            .collect(),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    // Newest day first
+    files.sort_by(|a, b| b.cmp(a));
+
+    let mut records = Vec::new();
+    for path in files {
+        let content = fs::read_to_string(&path)?;
+        let mut day: Vec<LogRecord> = content.lines().filter_map(LogRecord::parse).collect();
+        let keep = limit - records.len();
+        if day.len() > keep {
+            day.drain(..day.len() - keep);
+        }
+        day.append(&mut records);
+        records = day;
+        if records.len() >= limit {
+            break;
+        }
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const LINE: &str = r#"{"timestamp":"2026-10-18T09:15:02.120Z","level":"INFO","fields":{"message":"Executing tool","tool":"set_timer"},"target":"homegpt::agent","spans":[{"name":"turn","session_id":"4f1c","turn_id":"a9e0b2c4"},{"call_id":"t1","name":"tool_call","tool":"set_timer"}]}"#;
+
+    #[test]
+    fn test_parse_record() {
+        let record = LogRecord::parse(LINE).unwrap();
+        assert_eq!(record.level, Level::INFO);
+        assert_eq!(record.target, "homegpt::agent");
+        assert_eq!(record.message, "Executing tool tool=set_timer");
+        assert_eq!(record.session_id.as_deref(), Some("4f1c"));
+        assert_eq!(record.turn_id.as_deref(), Some("a9e0b2c4"));
+        assert_eq!(record.spans, vec!["turn", "tool_call"]);
+        assert!(LogRecord::parse("not json").is_none());
+    }
+
+    #[test]
+    fn test_rolls_over_and_prunes() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("trace-2000-01-01.jsonl"), LINE).unwrap();
+        fs::write(dir.path().join("homegpt-2000-01-01.log"), "daemon").unwrap();
+
+        let mut file =
+            RollingFile::open(dir.path().to_path_buf(), TRACE_PREFIX, TRACE_SUFFIX, 30).unwrap();
+        assert!(!dir.path().join("trace-2000-01-01.jsonl").exists());
+        assert!(dir.path().join("homegpt-2000-01-01.log").exists());
+
+        let first = file.date.clone();
+        file.write_on(&first, format!("{}\n", LINE).as_bytes())
+            .unwrap();
+        file.write_on("2999-12-31", format!("{}\n", LINE).as_bytes())
+            .unwrap();
+        assert!(dir.path().join(format!("trace-{}.jsonl", first)).exists());
+        assert!(dir.path().join("trace-2999-12-31.jsonl").exists());
+
+        // The newest file is read last, and the limit keeps the latest lines
+        assert_eq!(read_recent_in(dir.path(), 10).unwrap().len(), 2);
+        assert_eq!(read_recent_in(dir.path(), 1).unwrap().len(), 1);
+        assert!(read_recent_in(&dir.path().join("missing"), 5)
+            .unwrap()
+            .is_empty());
+    }
+}
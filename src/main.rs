@@ -8,11 +8,30 @@ use cli::{Cli, Commands};
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
+    // The config isn't created here if it doesn't exist yet; `config init`
+    // and first runs do that.
+    let config = homegpt::Config::config_path()
+        .ok()
+        .filter(|path| path.exists())
+        .and_then(|_| homegpt::Config::load().ok())
+        .unwrap_or_default();
+
+    // Schedules and displayed times follow the home timezone from here on
+    // (and in the forked daemon)
+    if let Err(e) = homegpt::locale::set_timezone(&config.locale.timezone) {
+        eprintln!("Warning: {}", e);
+    }
+
+    // Every agent (and the forked daemon's) starts in dry run
+    homegpt::agent::set_dry_run_default(cli.dry_run);
+
     // Handle daemon start/restart specially - must fork BEFORE starting Tokio runtime
     #[cfg(unix)]
     if let Commands::Daemon(ref args) = cli.command {
         match args.command {
-            cli::daemon::DaemonCommands::Start { foreground: false } => {
+            cli::daemon::DaemonCommands::Start {
+                foreground: false, ..
+            } => {
                 // Do the fork synchronously, then start Tokio in the child
                 return cli::daemon::daemonize_and_run(&cli.agent);
             }
@@ -29,18 +48,24 @@ fn main() -> Result<()> {
     tokio::runtime::Builder::new_multi_thread()
         .enable_all()
         .build()?
-        .block_on(async_main(cli))
+        .block_on(async_main(cli, config))
 }
 
-async fn async_main(cli: Cli) -> Result<()> {
-    // Initialize logging
+async fn async_main(cli: Cli, config: homegpt::Config) -> Result<()> {
+    // Initialize logging and the locale
     let log_level = if cli.verbose { "debug" } else { "info" };
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(log_level)),
-        )
-        .init();
+    let log_file = matches!(
+        cli.command,
+        Commands::Daemon(cli::daemon::DaemonArgs {
+            command: cli::daemon::DaemonCommands::Start { log_file: true, .. },
+        })
+    );
+    if log_file {
+        homegpt::logging::init_to_daemon_log(&config.logging, log_level);
+    } else {
+        homegpt::logging::init(&config.logging, log_level, true);
+    }
+    homegpt::locale::init(&config.locale);
 
     match cli.command {
         Commands::Chat(args) => cli::chat::run(args, &cli.agent).await,
@@ -49,6 +74,12 @@ async fn async_main(cli: Cli) -> Result<()> {
         Commands::Desktop(args) => cli::desktop::run(args, &cli.agent),
         Commands::Daemon(args) => cli::daemon::run(args, &cli.agent).await,
         Commands::Memory(args) => cli::memory::run(args, &cli.agent).await,
+        Commands::Session(args) => cli::session::run(args, &cli.agent).await,
+        Commands::Eval(args) => cli::eval::run(args, &cli.agent).await,
+        Commands::Doctor(args) => cli::doctor::run(args, &cli.agent).await,
+        Commands::Finance(args) => cli::finance::run(args, &cli.agent).await,
+        Commands::Backup(args) => cli::backup::run(args, &cli.agent).await,
+        Commands::Service(args) => cli::service::run(args, &cli.agent).await,
         Commands::Config(args) => cli::config::run(args).await,
     }
 }
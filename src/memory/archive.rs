@@ -0,0 +1,464 @@
+//! Export and import of the whole memory: workspace files plus the SQLite
+//! index (chunks, verification hashes, embeddings and embedding cache).
+//!
+//! Archives are zstd-compressed tarballs:
+//!
+//! ```text
+//! manifest.json        format, version, SHA-256 of every file
+//! index.sqlite         consistent snapshot of the index (VACUUM INTO)
+//! workspace/...        workspace files (.git and SQLite files excluded)
+//! ```
+//!
+//! Import verifies every file against the manifest and every chunk against
+//! its content and verification hashes before touching the destination, so
+//! a machine can be migrated without re-embedding. Callers should hold the
+//! workspace lock while exporting or importing.
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use super::db::rename_database;
+use super::embeddings::hash_text;
+use super::index::MemoryIndex;
+use super::verification::compute_chunk_hash;
+
+/// `format` field of every manifest
+pub const ARCHIVE_FORMAT: &str = "homegpt-memory";
+/// Newest archive version this build can import
+pub const ARCHIVE_VERSION: u32 = 1;
+
+const MANIFEST_NAME: &str = "manifest.json";
+const INDEX_NAME: &str = "index.sqlite";
+const WORKSPACE_DIR: &str = "workspace";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    pub format: String,
+    pub version: u32,
+    /// homegpt version that wrote the archive
+    pub homegpt_version: String,
+    pub created_at: String,
+    /// Embedding model of the stored vectors, if any
+    #[serde(default)]
+    pub embedding_model: Option<String>,
+    pub chunks: usize,
+    pub embedded_chunks: usize,
+    pub index_sha256: String,
+    pub files: Vec<ArchiveFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveFile {
+    /// Path relative to the workspace (forward slashes)
+    pub path: String,
+    pub sha256: String,
+    pub size: u64,
+}
+
+#[derive(Debug)]
+pub struct ImportReport {
+    pub manifest: ArchiveManifest,
+    pub files_written: usize,
+    pub files_unchanged: usize,
+    pub chunks_verified: usize,
+    /// Previous index database, if one was replaced
+    pub index_backup: Option<PathBuf>,
+}
+
+/// Write the workspace and a snapshot of the index to `output`
+pub fn export(
+    workspace: &Path,
+    index: &MemoryIndex,
+    embedding_model: Option<&str>,
+    output: &Path,
+) -> Result<ArchiveManifest> {
+    let snapshot = output.with_extension("index.tmp");
+    let _ = fs::remove_file(&snapshot);
+    index.snapshot_to(&snapshot)?;
+    let result = write_archive(workspace, &snapshot, embedding_model, output);
+    let _ = fs::remove_file(&snapshot);
+    result
+}
+
+fn write_archive(
+    workspace: &Path,
+    snapshot: &Path,
+    embedding_model: Option<&str>,
+    output: &Path,
+) -> Result<ArchiveManifest> {
+    let files = workspace_files(workspace)?;
+    let mut manifest_files = Vec::with_capacity(files.len());
+    for relative in &files {
+        let content = fs::read(workspace.join(relative))?;
+        manifest_files.push(ArchiveFile {
+            path: relative.clone(),
+            sha256: sha256_bytes(&content),
+            size: content.len() as u64,
+        });
+    }
+
+    let check = check_index(snapshot)?;
+    let manifest = ArchiveManifest {
+        format: ARCHIVE_FORMAT.to_string(),
+        version: ARCHIVE_VERSION,
+        homegpt_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        embedding_model: embedding_model.map(|m| m.to_string()),
+        chunks: check.chunks,
+        embedded_chunks: check.embedded,
+        index_sha256: sha256_bytes(&fs::read(snapshot)?),
+        files: manifest_files,
+    };
+
+    let file =
+        File::create(output).with_context(|| format!("Failed to create {}", output.display()))?;
+    let encoder = zstd::Encoder::new(BufWriter::new(file), 0)?;
+    let mut tar = tar::Builder::new(encoder);
+
+    let manifest_json = serde_json::to_vec_pretty(&manifest)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest_json.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(chrono::Utc::now().timestamp() as u64);
+    tar.append_data(&mut header, MANIFEST_NAME, manifest_json.as_slice())?;
+
+    tar.append_path_with_name(snapshot, INDEX_NAME)?;
+    for relative in &files {
+        tar.append_path_with_name(
+            workspace.join(relative),
+            format!("{}/{}", WORKSPACE_DIR, relative),
+        )?;
+    }
+
+    tar.into_inner()?.finish()?;
+
+    info!(
+        "Exported {} files and {} chunks to {}",
+        manifest.files.len(),
+        manifest.chunks,
+        output.display()
+    );
+    Ok(manifest)
+}
+
+/// Verify an archive and install it into `workspace` and `db_path`.
+///
+/// Existing workspace files that differ from the archive are only
+/// overwritten with `force`. A replaced index is kept as `<db>.bak`.
+pub fn import(
+    archive: &Path,
+    workspace: &Path,
+    db_path: &Path,
+    force: bool,
+) -> Result<ImportReport> {
+    let staging = std::env::temp_dir().join(format!("homegpt-import-{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&staging)?;
+    let result =
+        unpack(archive, &staging).and_then(|_| install(&staging, workspace, db_path, force));
+    if let Err(e) = fs::remove_dir_all(&staging) {
+        warn!("Failed to remove import staging dir: {}", e);
+    }
+    result
+}
+
+fn unpack(archive: &Path, staging: &Path) -> Result<()> {
+    let file =
+        File::open(archive).with_context(|| format!("Failed to open {}", archive.display()))?;
+    let decoder = zstd::Decoder::new(BufReader::new(file))?;
+    // Entries with absolute or `..` paths are skipped by unpack
+    tar::Archive::new(decoder)
+        .unpack(staging)
+        .context("Failed to unpack memory archive")
+}
+
+fn install(staging: &Path, workspace: &Path, db_path: &Path, force: bool) -> Result<ImportReport> {
+    let manifest = verify_staging(staging)?;
+    let chunks_verified = manifest.chunks;
+
+    // Refuse to clobber local changes unless forced
+    let mut files_unchanged = 0;
+    let mut to_write = Vec::new();
+    let mut conflicts = Vec::new();
+    for file in &manifest.files {
+        let dest = workspace.join(&file.path);
+        match fs::read(&dest) {
+            Ok(existing) if sha256_bytes(&existing) == file.sha256 => files_unchanged += 1,
+            Ok(_) => {
+                conflicts.push(file.path.clone());
+                to_write.push(file);
+            }
+            Err(_) => to_write.push(file),
+        }
+    }
+    if !conflicts.is_empty() && !force {
+        anyhow::bail!(
+            "{} workspace files differ from the archive (e.g. {}); re-run with --force to overwrite",
+            conflicts.len(),
+            conflicts[..conflicts.len().min(3)].join(", ")
+        );
+    }
+
+    let files_written = to_write.len();
+    for file in to_write {
+        let dest = workspace.join(&file.path);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(staging.join(WORKSPACE_DIR).join(&file.path), &dest)?;
+    }
+
+    let index_backup = if db_path.exists() {
+        let backup = db_path.with_extension("sqlite.bak");
+        rename_database(db_path, &backup)?;
+        Some(backup)
+    } else {
+        if let Some(parent) = db_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        None
+    };
+    fs::copy(staging.join(INDEX_NAME), db_path)?;
+
+    info!(
+        "Imported {} files ({} unchanged) and {} verified chunks",
+        files_written, files_unchanged, chunks_verified
+    );
+
+    Ok(ImportReport {
+        manifest,
+        files_written,
+        files_unchanged,
+        chunks_verified,
+        index_backup,
+    })
+}
+
+/// Check the manifest, file hashes and chunk hashes of an unpacked archive
+fn verify_staging(staging: &Path) -> Result<ArchiveManifest> {
+    let manifest: ArchiveManifest = serde_json::from_slice(
+        &fs::read(staging.join(MANIFEST_NAME)).context("Archive has no manifest.json")?,
+    )
+    .context("Invalid archive manifest")?;
+
+    if manifest.format != ARCHIVE_FORMAT {
+        anyhow::bail!(
+            "Not a homegpt memory archive (format '{}')",
+            manifest.format
+        );
+    }
+    if manifest.version > ARCHIVE_VERSION {
+        anyhow::bail!(
+            "Archive version {} is newer than supported ({}); upgrade homegpt",
+            manifest.version,
+            ARCHIVE_VERSION
+        );
+    }
+
+    for file in &manifest.files {
+        if file.path.split('/').any(|part| part == "..") || Path::new(&file.path).is_absolute() {
+            anyhow::bail!("Archive contains an unsafe path: {}", file.path);
+        }
+        let content = fs::read(staging.join(WORKSPACE_DIR).join(&file.path))
+            .with_context(|| format!("Archive is missing {}", file.path))?;
+        if sha256_bytes(&content) != file.sha256 {
+            anyhow::bail!("Hash mismatch for {}; archive is corrupt", file.path);
+        }
+    }
+
+    let index_path = staging.join(INDEX_NAME);
+    if sha256_bytes(&fs::read(&index_path).context("Archive has no index")?)
+        != manifest.index_sha256
+    {
+        anyhow::bail!("Hash mismatch for the index; archive is corrupt");
+    }
+
+    let check = check_index(&index_path)?;
+    if let Some(failure) = check.failures.first() {
+        anyhow::bail!(
+            "{} chunks failed hash verification (first: {})",
+            check.failures.len(),
+            failure
+        );
+    }
+    if check.chunks != manifest.chunks || check.embedded != manifest.embedded_chunks {
+        anyhow::bail!(
+            "Index has {} chunks ({} embedded), manifest says {} ({})",
+            check.chunks,
+            check.embedded,
+            manifest.chunks,
+            manifest.embedded_chunks
+        );
+    }
+
+    Ok(manifest)
+}
+
+struct IndexCheck {
+    chunks: usize,
+    embedded: usize,
+    /// Locations of chunks whose content or verification hash doesn't match
+    failures: Vec<String>,
+}
+
+/// Recompute content and verification hashes for every chunk in a database
+fn check_index(db_path: &Path) -> Result<IndexCheck> {
+    let conn = Connection::open(db_path)?;
+    let mut stmt = conn.prepare(
+        r#"SELECT c.path, c.start_line, c.end_line, c.text, c.hash, c.embedding != '', h.hash, h.timestamp
+           FROM chunks c
+           LEFT JOIN chunk_hashes h ON h.chunk_id = c.id"#,
+    )?;
+    let rows = stmt.query_map(params![], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, i32>(1)?,
+            row.get::<_, i32>(2)?,
+            row.get::<_, String>(3)?,
+            row.get::<_, String>(4)?,
+            row.get::<_, bool>(5)?,
+            row.get::<_, Option<String>>(6)?,
+            row.get::<_, Option<String>>(7)?,
+        ))
+    })?;
+
+    let mut check = IndexCheck {
+        chunks: 0,
+        embedded: 0,
+        failures: Vec::new(),
+    };
+    for row in rows {
+        let (path, start, end, text, content_hash, embedded, verify_hash, timestamp) = row?;
+        check.chunks += 1;
+        if embedded {
+            check.embedded += 1;
+        }
+
+        let content_ok = hash_text(&text) == content_hash;
+        let verify_ok = match (verify_hash, timestamp) {
+            (Some(hash), Some(ts)) => compute_chunk_hash(&path, &text, &ts) == hash,
+            _ => true,
+        };
+        if !content_ok || !verify_ok {
+            check.failures.push(format!("{}:{}-{}", path, start, end));
+        }
+    }
+
+    Ok(check)
+}
+
+/// Workspace-relative paths of all files to export, sorted
+pub(super) fn workspace_files(workspace: &Path) -> Result<Vec<String>> {
+    let mut files = Vec::new();
+    collect_files(workspace, workspace, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<String>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        let file_type = entry.file_type()?;
+
+        if file_type.is_dir() {
+            // Git history is synced separately (memory.sync)
+            if name != ".git" {
+                collect_files(root, &path, files)?;
+            }
+        } else if file_type.is_file() && !name.contains(".sqlite") {
+            let relative = path.strip_prefix(root)?;
+            let parts: Vec<String> = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect();
+            files.push(parts.join("/"));
+        }
+    }
+    Ok(())
+}
+
+pub(super) fn sha256_bytes(bytes: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_export_import_roundtrip() -> Result<()> {
+        let source = TempDir::new()?;
+        let workspace = source.path().join("workspace");
+        fs::create_dir_all(workspace.join("memory"))?;
+        fs::create_dir_all(workspace.join(".git"))?;
+        fs::write(
+            workspace.join("MEMORY.md"),
+            "# Memory\nWifi is guest-net.\n",
+        )?;
+        fs::write(
+            workspace.join("memory/2026-01-02.md"),
+            "Bought a new kettle.\n",
+        )?;
+        fs::write(workspace.join(".git/HEAD"), "ref: refs/heads/main\n")?;
+
+        let index = MemoryIndex::new_with_db_path(&workspace, &source.path().join("db.sqlite"))?;
+        index.index_file(&workspace.join("MEMORY.md"), false)?;
+        index.index_file(&workspace.join("memory/2026-01-02.md"), false)?;
+
+        let archive = source.path().join("memory.tar.zst");
+        let manifest = export(&workspace, &index, None, &archive)?;
+        let paths: Vec<&str> = manifest.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["MEMORY.md", "memory/2026-01-02.md"]);
+        assert_eq!(manifest.chunks, 2);
+
+        let dest = TempDir::new()?;
+        let dest_workspace = dest.path().join("workspace");
+        let dest_db = dest.path().join("memory/main.sqlite");
+        let report = import(&archive, &dest_workspace, &dest_db, false)?;
+        assert_eq!(report.files_written, 2);
+        assert_eq!(report.chunks_verified, 2);
+        assert_eq!(
+            fs::read_to_string(dest_workspace.join("memory/2026-01-02.md"))?,
+            "Bought a new kettle.\n"
+        );
+
+        let imported = MemoryIndex::new_with_db_path(&dest_workspace, &dest_db)?;
+        assert!(!imported.search("kettle", 5)?.is_empty());
+
+        // Local edits are protected unless forced
+        fs::write(dest_workspace.join("MEMORY.md"), "# Changed\n")?;
+        assert!(import(&archive, &dest_workspace, &dest_db, false).is_err());
+        let report = import(&archive, &dest_workspace, &dest_db, true)?;
+        assert_eq!((report.files_written, report.files_unchanged), (1, 1));
+        assert!(report.index_backup.is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tampered_chunk_fails_verification() -> Result<()> {
+        let dir = TempDir::new()?;
+        let workspace = dir.path().join("workspace");
+        fs::create_dir_all(&workspace)?;
+        fs::write(workspace.join("notes.md"), "Bins go out Thursday.\n")?;
+
+        let db_path = dir.path().join("db.sqlite");
+        let index = MemoryIndex::new_with_db_path(&workspace, &db_path)?;
+        index.index_file(&workspace.join("notes.md"), false)?;
+        assert!(check_index(&db_path)?.failures.is_empty());
+
+        Connection::open(&db_path)?
+            .execute("UPDATE chunks SET text = 'Bins go out Friday.'", [])?;
+        assert_eq!(check_index(&db_path)?.failures, vec!["notes.md:1-1"]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+//! Append-only audit log of memory reads and writes.
+//!
+//! Every search (query and returned chunks), every file write made by the
+//! agent, every (re)index of a file and every forget is recorded in the
+//! `memory_audit` table, so an odd answer can be traced back to the chunk it
+//! came from and the session that wrote it.
+//!
+//! The initiating session is carried in a task-local set by the agent around
+//! tool calls; writes picked up later by the watcher inherit the session of
+//! the last audited write to the same file.
+
+use serde_json::Value;
+use std::future::Future;
+
+tokio::task_local! {
+    static SESSION: String;
+}
+
+/// Run `f` with `session_id` attributed to any audit entries it records
+pub async fn with_session<F: Future>(session_id: &str, f: F) -> F::Output {
+    SESSION.scope(session_id.to_string(), f).await
+}
+
+/// Session of the current task, if set with [`with_session`]
+pub fn current_session() -> Option<String> {
+    SESSION.try_with(|s| s.clone()).ok()
+}
+
+/// One row of the memory audit log
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub timestamp: String,
+    /// search, write, index, delete, forget
+    pub action: String,
+    pub session: Option<String>,
+    pub path: Option<String>,
+    /// JSON details (never the forgotten content itself)
+    pub detail: String,
+}
+
+impl AuditEntry {
+    /// Single-entry rendering for `homegpt memory audit`
+    pub fn format(&self) -> String {
+        let mut out = format!("#{} {} {}", self.id, self.timestamp, self.action);
+        if let Some(ref path) = self.path {
+            out.push_str(&format!(" {}", path));
+        }
+        if let Some(ref session) = self.session {
+            out.push_str(&format!(" (session {})", session));
+        }
+
+        let detail: Value = serde_json::from_str(&self.detail).unwrap_or(Value::Null);
+        if let Some(query) = detail["query"].as_str() {
+            out.push_str(&format!("\n  query: {}", query));
+        }
+        if let Some(tool) = detail["tool"].as_str() {
+            out.push_str(&format!("\n  tool: {}", tool));
+        }
+        if let Some(provenance) = detail["provenance"].as_str() {
+            out.push_str(&format!("\n  provenance: {}", provenance));
+        }
+        for chunk in detail["chunks"].as_array().into_iter().flatten() {
+            out.push_str(&format!(
+                "\n  {}{} hash {}{}",
+                chunk["path"]
+                    .as_str()
+                    .map(|p| format!("{}:", p))
+                    .unwrap_or_default(),
+                chunk["lines"].as_str().unwrap_or("?"),
+                chunk["hash"].as_str().unwrap_or("?"),
+                chunk["citation"]
+                    .as_str()
+                    .map(|c| format!(" [VERIFIED:{}]", c))
+                    .unwrap_or_default()
+            ));
+        }
+        if detail["chunks"].is_number() {
+            out.push_str(&format!(
+                "\n  chunks: {}, verified: {}",
+                detail["chunks"], detail["verified"]
+            ));
+        }
+
+        out
+    }
+}
+
+/// Filters for reading the audit log (all optional, combined with AND)
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub action: Option<String>,
+    pub session: Option<String>,
+    /// Path prefix
+    pub path: Option<String>,
+    /// Chunk content hash or citation hash prefix
+    pub chunk: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_session_scope() {
+        assert_eq!(current_session(), None);
+        let inner = with_session("abc", async { current_session() }).await;
+        assert_eq!(inner.as_deref(), Some("abc"));
+        assert_eq!(current_session(), None);
+    }
+
+    #[test]
+    fn test_format_search_entry() {
+        let entry = AuditEntry {
+            id: 7,
+            timestamp: "2026-03-01T10:00:00Z".into(),
+            action: "search".into(),
+            session: Some("s1".into()),
+            path: None,
+            detail: r#"{"query":"dentist","chunks":[{"path":"memory/ella.md","lines":"1-4","hash":"0123456789ab","citation":"deadbeef"}]}"#.into(),
+        };
+        let text = entry.format();
+        assert!(text.starts_with("#7 2026-03-01T10:00:00Z search (session s1)"));
+        assert!(text.contains("query: dentist"));
+        assert!(text.contains("memory/ella.md:1-4 hash 0123456789ab [VERIFIED:deadbeef]"));
+    }
+}
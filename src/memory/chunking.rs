@@ -0,0 +1,546 @@
+//! Chunking strategies for the memory index.
+//!
+//! - `lines`: fixed-size line windows with overlap (original behaviour)
+//! - `headings`: markdown sections split on headings, small sections merged,
+//!   long sections split into line windows
+//! - `sentences`: sentence windows with sentence-level overlap
+//! - `table`: groups of CSV rows, each chunk prefixed with the header row
+//!   (and sheet name) so retrieved rows keep their column names
+//!
+//! All sizes are in tokens, estimated at ~4 characters per token. An optional
+//! `max_tokens` cap hard-splits any chunk that is still too long (e.g. a
+//! single enormous line).
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::config::MemoryConfig;
+
+/// Rough estimate: 4 chars per token
+pub(super) const CHARS_PER_TOKEN: usize = 4;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChunkStrategy {
+    #[default]
+    Lines,
+    Headings,
+    Sentences,
+    Table,
+}
+
+impl std::str::FromStr for ChunkStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "lines" | "fixed" => Ok(Self::Lines),
+            "headings" | "markdown" => Ok(Self::Headings),
+            "sentences" | "sentence" => Ok(Self::Sentences),
+            "table" | "csv" => Ok(Self::Table),
+            other => anyhow::bail!(
+                "Unknown chunk strategy '{}': expected lines, headings, sentences or table",
+                other
+            ),
+        }
+    }
+}
+
+/// Resolved chunking parameters for a single file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkOptions {
+    pub strategy: ChunkStrategy,
+    /// Target tokens per chunk
+    pub chunk_size: usize,
+    /// Overlap between consecutive chunks (tokens)
+    pub chunk_overlap: usize,
+    /// Hard cap on tokens per chunk (0 = no cap)
+    pub max_tokens: usize,
+}
+
+impl Default for ChunkOptions {
+    fn default() -> Self {
+        Self {
+            strategy: ChunkStrategy::Lines,
+            chunk_size: 400,
+            chunk_overlap: 80,
+            max_tokens: 0,
+        }
+    }
+}
+
+impl ChunkOptions {
+    /// Resolve the default options and per-directory overrides from config
+    pub fn from_config(config: &MemoryConfig) -> Result<(Self, Vec<(String, Self)>)> {
+        let defaults = Self {
+            strategy: config.chunk_strategy.parse()?,
+            chunk_size: config.chunk_size,
+            chunk_overlap: config.chunk_overlap,
+            max_tokens: config.chunk_max_tokens,
+        };
+
+        let mut rules = Vec::with_capacity(config.chunking.len());
+        for rule in &config.chunking {
+            let options = Self {
+                strategy: match rule.strategy {
+                    Some(ref s) => s.parse()?,
+                    None => defaults.strategy,
+                },
+                chunk_size: rule.chunk_size.unwrap_or(defaults.chunk_size),
+                chunk_overlap: rule.chunk_overlap.unwrap_or(defaults.chunk_overlap),
+                max_tokens: rule.max_tokens.unwrap_or(defaults.max_tokens),
+            };
+            rules.push((rule.path.trim_start_matches("./").to_string(), options));
+        }
+
+        Ok((defaults, rules))
+    }
+}
+
+pub(crate) struct ChunkInfo {
+    pub line_start: i32,
+    pub line_end: i32,
+    pub content: String,
+}
+
+/// Chunk `text` using the given options
+pub(crate) fn chunk_with(text: &str, options: &ChunkOptions) -> Vec<ChunkInfo> {
+    let chunks = match options.strategy {
+        ChunkStrategy::Lines => chunk_text(text, options.chunk_size, options.chunk_overlap),
+        ChunkStrategy::Headings => {
+            chunk_by_headings(text, options.chunk_size, options.chunk_overlap)
+        }
+        ChunkStrategy::Sentences => {
+            chunk_by_sentences(text, options.chunk_size, options.chunk_overlap)
+        }
+        ChunkStrategy::Table => chunk_table(text, options.chunk_size),
+    };
+
+    if options.max_tokens == 0 {
+        chunks
+    } else {
+        cap_chunks(chunks, options.max_tokens)
+    }
+}
+
+pub(crate) fn chunk_text(
+    text: &str,
+    target_tokens: usize,
+    overlap_tokens: usize,
+) -> Vec<ChunkInfo> {
+    let lines: Vec<&str> = text.lines().collect();
+    chunk_lines(&lines, 0, target_tokens, overlap_tokens)
+}
+
+/// Line-window chunking over `lines`, numbering from `line_offset + 1`
+fn chunk_lines(
+    lines: &[&str],
+    line_offset: usize,
+    target_tokens: usize,
+    overlap_tokens: usize,
+) -> Vec<ChunkInfo> {
+    let mut chunks = Vec::new();
+
+    if lines.is_empty() {
+        return chunks;
+    }
+
+    let target_chars = target_tokens * CHARS_PER_TOKEN;
+    let overlap_chars = overlap_tokens * CHARS_PER_TOKEN;
+
+    let mut start_line = 0;
+    let mut current_chars = 0;
+    let mut chunk_lines = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        chunk_lines.push(*line);
+        current_chars += line.len() + 1; // +1 for newline
+
+        if current_chars >= target_chars || i == lines.len() - 1 {
+            // Create chunk
+            chunks.push(ChunkInfo {
+                line_start: (line_offset + start_line + 1) as i32,
+                line_end: (line_offset + i + 1) as i32,
+                content: chunk_lines.join("\n"),
+            });
+
+            // Calculate overlap for next chunk
+            let mut overlap_len = 0;
+            let mut overlap_start = chunk_lines.len();
+
+            for (j, line) in chunk_lines.iter().enumerate().rev() {
+                overlap_len += line.len() + 1;
+                if overlap_len >= overlap_chars {
+                    overlap_start = j;
+                    break;
+                }
+            }
+
+            // Prepare for next chunk
+            if overlap_start < chunk_lines.len() {
+                start_line += overlap_start;
+                chunk_lines = chunk_lines[overlap_start..].to_vec();
+                current_chars = chunk_lines.iter().map(|l| l.len() + 1).sum();
+            } else {
+                start_line = i + 1;
+                chunk_lines.clear();
+                current_chars = 0;
+            }
+        }
+    }
+
+    chunks
+}
+
+/// Split markdown on headings (ignoring `#` inside code fences).
+///
+/// Adjacent small sections are merged up to the target size so terse files
+/// don't produce one tiny chunk per heading; sections larger than the target
+/// fall back to line windows with overlap.
+fn chunk_by_headings(text: &str, target_tokens: usize, overlap_tokens: usize) -> Vec<ChunkInfo> {
+    let lines: Vec<&str> = text.lines().collect();
+    let target_chars = target_tokens * CHARS_PER_TOKEN;
+
+    // Section boundaries as [start, end) line indices
+    let mut sections: Vec<(usize, usize)> = Vec::new();
+    let mut in_fence = false;
+    let mut start = 0;
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+        if !in_fence && is_heading(trimmed) && i > start {
+            sections.push((start, i));
+            start = i;
+        }
+    }
+    if start < lines.len() {
+        sections.push((start, lines.len()));
+    }
+
+    let section_chars =
+        |(s, e): (usize, usize)| -> usize { lines[s..e].iter().map(|l| l.len() + 1).sum() };
+
+    let mut chunks = Vec::new();
+    let mut pending: Option<(usize, usize)> = None;
+
+    let flush = |range: (usize, usize), chunks: &mut Vec<ChunkInfo>| {
+        let content = lines[range.0..range.1].join("\n");
+        if !content.trim().is_empty() {
+            chunks.push(ChunkInfo {
+                line_start: (range.0 + 1) as i32,
+                line_end: range.1 as i32,
+                content,
+            });
+        }
+    };
+
+    for section in sections {
+        if section_chars(section) > target_chars {
+            if let Some(p) = pending.take() {
+                flush(p, &mut chunks);
+            }
+            chunks.extend(chunk_lines(
+                &lines[section.0..section.1],
+                section.0,
+                target_tokens,
+                overlap_tokens,
+            ));
+            continue;
+        }
+
+        pending = match pending {
+            Some(p) if section_chars((p.0, section.1)) <= target_chars => Some((p.0, section.1)),
+            Some(p) => {
+                flush(p, &mut chunks);
+                Some(section)
+            }
+            None => Some(section),
+        };
+    }
+    if let Some(p) = pending {
+        flush(p, &mut chunks);
+    }
+
+    chunks
+}
+
+fn is_heading(line: &str) -> bool {
+    let hashes = line.chars().take_while(|c| *c == '#').count();
+    (1..=6).contains(&hashes) && line[hashes..].starts_with(' ')
+}
+
+/// Sentence windows: pack whole sentences up to the target size, then start
+/// the next window far enough back to cover `overlap_tokens`.
+fn chunk_by_sentences(text: &str, target_tokens: usize, overlap_tokens: usize) -> Vec<ChunkInfo> {
+    let sentences = split_sentences(text);
+    let target_chars = target_tokens * CHARS_PER_TOKEN;
+    let overlap_chars = overlap_tokens * CHARS_PER_TOKEN;
+    let line_of = |offset: usize| text[..offset].matches('\n').count() + 1;
+
+    let mut chunks = Vec::new();
+    let mut first = 0;
+    while first < sentences.len() {
+        // Extend the window until it reaches the target size
+        let mut last = first;
+        while last + 1 < sentences.len()
+            && sentences[last + 1].1 - sentences[first].0 <= target_chars
+        {
+            last += 1;
+        }
+
+        let (start, end) = (sentences[first].0, sentences[last].1);
+        chunks.push(ChunkInfo {
+            line_start: line_of(start) as i32,
+            line_end: line_of(end.saturating_sub(1).max(start)) as i32,
+            content: text[start..end].to_string(),
+        });
+
+        if last + 1 >= sentences.len() {
+            break;
+        }
+
+        // Step back from the end of the window to cover the overlap
+        let mut next = last + 1;
+        while next > first + 1 && end - sentences[next - 1].0 <= overlap_chars {
+            next -= 1;
+        }
+        first = next;
+    }
+
+    chunks
+}
+
+/// Byte ranges of sentences; blank lines and markdown line breaks also end a sentence
+fn split_sentences(text: &str) -> Vec<(usize, usize)> {
+    let mut sentences = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        if start.is_none() {
+            if c.is_whitespace() {
+                continue;
+            }
+            start = Some(i);
+        }
+
+        let end = i + c.len_utf8();
+        let next = chars.peek().map(|(_, n)| *n);
+        let boundary = match c {
+            '.' | '!' | '?' => next.is_none_or(char::is_whitespace),
+            '\n' => next == Some('\n') || next.is_none(),
+            _ => next.is_none(),
+        };
+
+        if boundary {
+            if let Some(s) = start.take() {
+                let trimmed_end = s + text[s..end].trim_end().len();
+                if trimmed_end > s {
+                    sentences.push((s, trimmed_end));
+                }
+            }
+        }
+    }
+
+    sentences
+}
+
+/// Row-group chunking for CSV text. The first row (and the first row after
+/// each `# Sheet` line) is the header and is repeated at the top of every
+/// chunk; rows are never split or overlapped.
+fn chunk_table(text: &str, target_tokens: usize) -> Vec<ChunkInfo> {
+    let max_chars = target_tokens * CHARS_PER_TOKEN;
+    let mut chunks = Vec::new();
+    // Sheet line and header row repeated in each chunk
+    let mut context = String::new();
+    let mut has_header = false;
+    // (line number, row) pending for the current chunk
+    let mut rows: Vec<(usize, &str)> = Vec::new();
+    let mut row_chars = 0;
+
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if line.starts_with("# ") {
+            push_table_chunk(&mut chunks, &context, &mut rows);
+            context = format!("{}\n", line);
+            has_header = false;
+            continue;
+        }
+        if !has_header {
+            context.push_str(line);
+            context.push('\n');
+            has_header = true;
+            continue;
+        }
+
+        if !rows.is_empty() && context.len() + row_chars + line.len() > max_chars {
+            push_table_chunk(&mut chunks, &context, &mut rows);
+        }
+        if rows.is_empty() {
+            row_chars = 0;
+        }
+        rows.push((i + 1, line));
+        row_chars += line.len() + 1;
+    }
+    push_table_chunk(&mut chunks, &context, &mut rows);
+
+    // A header without rows is still indexed
+    if chunks.is_empty() && !context.is_empty() {
+        chunks.push(ChunkInfo {
+            line_start: 1,
+            line_end: text.lines().count() as i32,
+            content: context.trim_end().to_string(),
+        });
+    }
+
+    chunks
+}
+
+fn push_table_chunk(chunks: &mut Vec<ChunkInfo>, context: &str, rows: &mut Vec<(usize, &str)>) {
+    let (Some(first), Some(last)) = (rows.first(), rows.last()) else {
+        return;
+    };
+    let body: Vec<&str> = rows.iter().map(|(_, row)| *row).collect();
+    chunks.push(ChunkInfo {
+        line_start: first.0 as i32,
+        line_end: last.0 as i32,
+        content: format!("{}{}", context, body.join("\n")),
+    });
+    rows.clear();
+}
+
+/// Hard-split chunks longer than `max_tokens`, on char boundaries
+fn cap_chunks(chunks: Vec<ChunkInfo>, max_tokens: usize) -> Vec<ChunkInfo> {
+    let max_chars = max_tokens * CHARS_PER_TOKEN;
+    let mut capped = Vec::with_capacity(chunks.len());
+
+    for chunk in chunks {
+        if chunk.content.len() <= max_chars {
+            capped.push(chunk);
+            continue;
+        }
+
+        let mut rest = chunk.content.as_str();
+        let mut line = chunk.line_start;
+        while !rest.is_empty() {
+            let mut cut = max_chars.min(rest.len());
+            while !rest.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            let piece = &rest[..cut];
+            let newlines = piece.matches('\n').count() as i32;
+            capped.push(ChunkInfo {
+                line_start: line,
+                line_end: (line + newlines).min(chunk.line_end),
+                content: piece.to_string(),
+            });
+            line = (line + newlines).min(chunk.line_end);
+            rest = &rest[cut..];
+        }
+    }
+
+    capped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_text() {
+        let text = "Line 1\nLine 2\nLine 3\nLine 4\nLine 5";
+        let chunks = chunk_text(text, 10, 2); // Small chunks for testing
+
+        assert!(!chunks.is_empty());
+        assert_eq!(chunks[0].line_start, 1);
+    }
+
+    #[test]
+    fn test_headings_merge_small_sections() {
+        let text = "# Family\n## Alice\nLikes cats.\n## Bob\nLikes dogs.\n# Home\nAddress here.";
+        let chunks = chunk_by_headings(text, 8, 0);
+
+        // Each chunk starts on a heading
+        assert!(chunks.iter().all(|c| c.content.starts_with('#')));
+        assert_eq!(chunks[0].line_start, 1);
+        assert_eq!(chunks.last().unwrap().line_end, 7);
+
+        // Everything fits in one chunk with a large target
+        let merged = chunk_by_headings(text, 1000, 0);
+        assert_eq!(merged.len(), 1);
+    }
+
+    #[test]
+    fn test_headings_ignore_code_fences() {
+        let text = "# Notes\n```sh\n# not a heading\n```\n# Next";
+        let chunks = chunk_by_headings(text, 9, 0);
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks[0].content.contains("# not a heading"));
+    }
+
+    #[test]
+    fn test_sentence_windows_overlap() {
+        let text = "One fish. Two fish.\nRed fish. Blue fish.";
+        let sentences = split_sentences(text);
+        assert_eq!(sentences.len(), 4);
+
+        let chunks = chunk_by_sentences(text, 5, 3);
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks[0].content, "One fish. Two fish.");
+        // Overlap repeats the last sentence of the previous window
+        assert!(chunks[1].content.starts_with("Two fish."));
+        assert_eq!(chunks.last().unwrap().line_end, 2);
+    }
+
+    #[test]
+    fn test_max_tokens_cap() {
+        let text = "x".repeat(100);
+        let options = ChunkOptions {
+            max_tokens: 10,
+            ..Default::default()
+        };
+        let chunks = chunk_with(&text, &options);
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks.iter().all(|c| c.content.len() <= 40));
+    }
+
+    #[test]
+    fn test_table_chunks_repeat_header() {
+        let text = "date,category,amount\n2026-03-02,groceries,84.10\n2026-03-09,fuel,52.00\n2026-03-16,groceries,91.45\n\n# Budget\ncategory,limit\ngroceries,400";
+        let chunks = chunk_table(text, 12);
+
+        assert_eq!(chunks.len(), 4);
+        assert!(chunks[..3]
+            .iter()
+            .all(|c| c.content.starts_with("date,category,amount\n")));
+        assert_eq!(chunks[0].line_start, 2);
+        assert_eq!(chunks[2].line_end, 4);
+        // Rows after a sheet heading get that sheet's header
+        assert_eq!(chunks[3].content, "# Budget\ncategory,limit\ngroceries,400");
+        assert_eq!(chunks[3].line_start, 8);
+
+        // Everything fits in one chunk with a large target
+        assert_eq!(chunk_table(text, 1000).len(), 2);
+    }
+
+    #[test]
+    fn test_strategy_from_str() {
+        assert_eq!(
+            "headings".parse::<ChunkStrategy>().unwrap(),
+            ChunkStrategy::Headings
+        );
+        assert_eq!(
+            "Sentences".parse::<ChunkStrategy>().unwrap(),
+            ChunkStrategy::Sentences
+        );
+        assert_eq!(
+            "csv".parse::<ChunkStrategy>().unwrap(),
+            ChunkStrategy::Table
+        );
+        assert!("bogus".parse::<ChunkStrategy>().is_err());
+    }
+}
@@ -0,0 +1,417 @@
+//! Memory consolidation: find and merge near-duplicate chunks.
+//!
+//! Repeated notes ("we're out of milk") bloat the index and crowd out other
+//! results. Consolidation groups chunks whose embeddings are nearly identical,
+//! keeps the newest one as canonical, records where the duplicates came from
+//! (`merged_from` in the canonical file's frontmatter, or an HTML comment if
+//! it has none) and removes the duplicate lines from their source files.
+//!
+//! Always run as a dry run first: `find_duplicate_groups` only reports.
+
+use anyhow::Result;
+use serde::Serialize;
+use serde_yaml::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+use tracing::{info, warn};
+
+use super::embeddings::cosine_similarity;
+use super::frontmatter::{render_frontmatter, split_frontmatter};
+use super::index::{EmbeddedChunk, MemoryIndex};
+
+/// A chunk taking part in a duplicate group
+#[derive(Debug, Clone, Serialize)]
+pub struct ChunkRef {
+    pub chunk_id: String,
+    pub file: String,
+    pub line_start: i32,
+    pub line_end: i32,
+    pub content: String,
+    /// Similarity to the canonical chunk (1.0 for the canonical chunk itself)
+    pub similarity: f32,
+}
+
+impl ChunkRef {
+    fn from_chunk(chunk: &EmbeddedChunk, similarity: f32) -> Self {
+        Self {
+            chunk_id: chunk.chunk_id.clone(),
+            file: chunk.file.clone(),
+            line_start: chunk.line_start,
+            line_end: chunk.line_end,
+            content: chunk.content.clone(),
+            similarity,
+        }
+    }
+
+    pub fn location(&self) -> String {
+        format!("{}:{}-{}", self.file, self.line_start, self.line_end)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateGroup {
+    pub canonical: ChunkRef,
+    pub duplicates: Vec<ChunkRef>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ConsolidationReport {
+    pub threshold: f32,
+    pub chunks_scanned: usize,
+    pub groups: Vec<DuplicateGroup>,
+    /// False for a dry run
+    pub applied: bool,
+    pub files_changed: Vec<String>,
+    pub files_removed: Vec<String>,
+}
+
+impl ConsolidationReport {
+    pub fn duplicate_count(&self) -> usize {
+        self.groups.iter().map(|g| g.duplicates.len()).sum()
+    }
+
+    /// Human-readable summary (used by the CLI and the agent tool)
+    pub fn summary(&self) -> String {
+        let mut out = format!(
+            "Scanned {} chunks (threshold {:.2}): {} duplicate groups, {} duplicate chunks{}\n",
+            self.chunks_scanned,
+            self.threshold,
+            self.groups.len(),
+            self.duplicate_count(),
+            if self.applied { "" } else { " (dry run)" }
+        );
+
+        for (i, group) in self.groups.iter().enumerate() {
+            let preview: String = group.canonical.content.chars().take(80).collect();
+            out.push_str(&format!(
+                "\n{}. Keep {}\n   {}\n",
+                i + 1,
+                group.canonical.location(),
+                preview.replace('\n', " ")
+            ));
+            for dup in &group.duplicates {
+                out.push_str(&format!(
+                    "   - merge {} (similarity {:.3})\n",
+                    dup.location(),
+                    dup.similarity
+                ));
+            }
+        }
+
+        if self.applied {
+            out.push_str(&format!(
+                "\nFiles changed: {}, files removed: {}\n",
+                self.files_changed.len(),
+                self.files_removed.len()
+            ));
+        }
+
+        out
+    }
+}
+
+/// Group near-duplicate chunks. The newest chunk (by file mtime) of each
+/// group is canonical. Overlapping chunks of the same file are never
+/// grouped, and chunks outside the workspace (absolute paths) are skipped.
+pub fn find_duplicate_groups(chunks: &[EmbeddedChunk], threshold: f32) -> Vec<DuplicateGroup> {
+    let mut order: Vec<&EmbeddedChunk> = chunks
+        .iter()
+        .filter(|c| !Path::new(&c.file).is_absolute() && !c.embedding.is_empty())
+        .collect();
+    order.sort_by(|a, b| b.mtime.cmp(&a.mtime).then_with(|| b.file.cmp(&a.file)));
+
+    let mut assigned = vec![false; order.len()];
+    let mut groups = Vec::new();
+
+    for i in 0..order.len() {
+        if assigned[i] {
+            continue;
+        }
+        let canonical = order[i];
+        let mut duplicates = Vec::new();
+
+        for j in (i + 1)..order.len() {
+            if assigned[j] {
+                continue;
+            }
+            let other = order[j];
+            if other.file == canonical.file
+                && other.line_start <= canonical.line_end
+                && canonical.line_start <= other.line_end
+            {
+                continue;
+            }
+            if canonical.embedding.len() != other.embedding.len() {
+                continue;
+            }
+
+            let similarity = cosine_similarity(&canonical.embedding, &other.embedding);
+            if similarity >= threshold {
+                assigned[j] = true;
+                duplicates.push(ChunkRef::from_chunk(other, similarity));
+            }
+        }
+
+        if !duplicates.is_empty() {
+            assigned[i] = true;
+            groups.push(DuplicateGroup {
+                canonical: ChunkRef::from_chunk(canonical, 1.0),
+                duplicates,
+            });
+        }
+    }
+
+    groups
+}
+
+/// Pending edits for one workspace file
+#[derive(Default)]
+struct FileEdit {
+    /// Duplicate chunks to remove (id, start line, end line, content)
+    remove: Vec<(String, i32, i32, String)>,
+    /// Provenance notes anchored after a line (1-indexed)
+    notes: Vec<(i32, String)>,
+}
+
+/// Rewrite the source markdown for the given groups.
+///
+/// Returns (files changed, files removed). The index is not updated; the
+/// caller should reindex afterwards.
+pub fn apply_groups(
+    workspace: &Path,
+    index: &MemoryIndex,
+    groups: &[DuplicateGroup],
+) -> Result<(Vec<String>, Vec<String>)> {
+    let mut edits: BTreeMap<String, FileEdit> = BTreeMap::new();
+
+    for group in groups {
+        let sources: Vec<String> = group.duplicates.iter().map(|d| d.location()).collect();
+        edits
+            .entry(group.canonical.file.clone())
+            .or_default()
+            .notes
+            .push((group.canonical.line_end, sources.join(", ")));
+
+        for dup in &group.duplicates {
+            edits.entry(dup.file.clone()).or_default().remove.push((
+                dup.chunk_id.clone(),
+                dup.line_start,
+                dup.line_end,
+                dup.content.clone(),
+            ));
+        }
+    }
+
+    let mut changed = Vec::new();
+    let mut removed = Vec::new();
+
+    for (file, edit) in edits {
+        let path = workspace.join(&file);
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                warn!("Skipping {}: {}", file, e);
+                continue;
+            }
+        };
+
+        // Lines still covered by chunks we keep must not be removed
+        let removed_ids: HashSet<&str> = edit.remove.iter().map(|r| r.0.as_str()).collect();
+        let kept_ranges: Vec<(i32, i32)> = index
+            .chunk_ranges(&file)?
+            .into_iter()
+            .filter(|(id, _, _)| !removed_ids.contains(id.as_str()))
+            .map(|(_, start, end)| (start, end))
+            .collect();
+
+        match rewrite_file(&content, &edit, &kept_ranges)? {
+            Some(new_content) if new_content.trim().is_empty() => {
+                fs::remove_file(&path)?;
+                info!("Consolidation removed {}", file);
+                removed.push(file);
+            }
+            Some(new_content) => {
+                fs::write(&path, new_content)?;
+                info!("Consolidation rewrote {}", file);
+                changed.push(file);
+            }
+            None => {}
+        }
+    }
+
+    Ok((changed, removed))
+}
+
+/// Apply removals and provenance notes to one file's content.
+/// Returns `None` if nothing changed. An empty result means the file is
+/// only left with frontmatter and should be deleted.
+fn rewrite_file(
+    content: &str,
+    edit: &FileEdit,
+    kept_ranges: &[(i32, i32)],
+) -> Result<Option<String>> {
+    let lines: Vec<&str> = content.lines().collect();
+    let (frontmatter, body) = split_frontmatter(content);
+    let fm_lines = lines.len() - body.lines().count();
+
+    let mut remove = vec![false; lines.len()];
+    for (_, start, end, chunk_content) in &edit.remove {
+        let (s, e) = (
+            (*start - 1).max(0) as usize,
+            (*end as usize).min(lines.len()),
+        );
+        if s >= e || lines[s..e].join("\n") != *chunk_content {
+            warn!(
+                "Chunk at lines {}-{} changed since indexing, skipping",
+                start, end
+            );
+            continue;
+        }
+        for (i, flag) in remove.iter_mut().enumerate().take(e).skip(s) {
+            let line_no = i as i32 + 1;
+            let kept = kept_ranges
+                .iter()
+                .any(|(ks, ke)| *ks <= line_no && line_no <= *ke);
+            if i >= fm_lines && !kept {
+                *flag = true;
+            }
+        }
+    }
+
+    let use_frontmatter = frontmatter.is_some() && !edit.notes.is_empty();
+    if !remove.iter().any(|r| *r) && edit.notes.is_empty() {
+        return Ok(None);
+    }
+
+    // Rebuild the body, inserting comment notes when there's no frontmatter
+    let mut body_lines: Vec<String> = Vec::new();
+    for (i, line) in lines.iter().enumerate().skip(fm_lines) {
+        if !remove[i] {
+            body_lines.push(line.to_string());
+        }
+        if !use_frontmatter {
+            for (anchor, note) in &edit.notes {
+                if *anchor as usize == i + 1 {
+                    body_lines.push(format!("<!-- merged from: {} -->", note));
+                }
+            }
+        }
+    }
+
+    let mut new_body = body_lines.join("\n");
+    if content.ends_with('\n') && !new_body.is_empty() {
+        new_body.push('\n');
+    }
+
+    // A file left with only frontmatter is deleted by the caller
+    if new_body.trim().is_empty() && frontmatter.is_some() {
+        return Ok(Some(String::new()));
+    }
+
+    match frontmatter {
+        Some(mut map) => {
+            if use_frontmatter {
+                let key = Value::String("merged_from".to_string());
+                let mut merged = match map.remove(&key) {
+                    Some(Value::Sequence(seq)) => seq,
+                    _ => Vec::new(),
+                };
+                for (_, note) in &edit.notes {
+                    for source in note.split(", ") {
+                        merged.push(Value::String(source.to_string()));
+                    }
+                }
+                map.insert(key, Value::Sequence(merged));
+            }
+            Ok(Some(render_frontmatter(&map, &new_body)?))
+        }
+        None => Ok(Some(new_body)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(
+        id: &str,
+        file: &str,
+        lines: (i32, i32),
+        embedding: Vec<f32>,
+        mtime: i64,
+    ) -> EmbeddedChunk {
+        EmbeddedChunk {
+            chunk_id: id.to_string(),
+            file: file.to_string(),
+            line_start: lines.0,
+            line_end: lines.1,
+            content: format!("content {}", id),
+            embedding,
+            mtime,
+        }
+    }
+
+    #[test]
+    fn test_find_duplicate_groups_prefers_newest() {
+        let chunks = vec![
+            chunk("a", "memory/2026-01-01.md", (1, 2), vec![1.0, 0.0], 100),
+            chunk("b", "memory/2026-01-05.md", (3, 4), vec![0.999, 0.04], 500),
+            chunk("c", "memory/other.md", (1, 1), vec![0.0, 1.0], 900),
+            // Overlapping chunk of the same file is never a duplicate
+            chunk("d", "memory/2026-01-05.md", (4, 6), vec![1.0, 0.0], 500),
+        ];
+
+        let groups = find_duplicate_groups(&chunks, 0.95);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].canonical.file, "memory/2026-01-05.md");
+        let dup_ids: Vec<&str> = groups[0]
+            .duplicates
+            .iter()
+            .map(|d| d.chunk_id.as_str())
+            .collect();
+        assert_eq!(dup_ids, vec!["a"]);
+    }
+
+    #[test]
+    fn test_rewrite_file_removes_lines_and_notes_provenance() {
+        let content = "# Log\nWe're out of milk.\nCalled the plumber.\n";
+        let edit = FileEdit {
+            remove: vec![("x".into(), 2, 2, "We're out of milk.".into())],
+            notes: vec![(3, "memory/a.md:1-1".into())],
+        };
+        let out = rewrite_file(content, &edit, &[(1, 1), (3, 3)])
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            out,
+            "# Log\nCalled the plumber.\n<!-- merged from: memory/a.md:1-1 -->\n"
+        );
+
+        // Lines shared with a kept chunk are preserved
+        let out = rewrite_file(content, &edit, &[(1, 3)]).unwrap().unwrap();
+        assert!(out.contains("We're out of milk."));
+    }
+
+    #[test]
+    fn test_rewrite_file_frontmatter() {
+        let content = "---\ncategory: food\n---\nOut of milk\n";
+        let remove_all = FileEdit {
+            remove: vec![("x".into(), 1, 4, content.trim_end().into())],
+            notes: Vec::new(),
+        };
+        // Only frontmatter left: the file should be deleted
+        assert_eq!(
+            rewrite_file(content, &remove_all, &[]).unwrap().unwrap(),
+            ""
+        );
+
+        let note = FileEdit {
+            remove: Vec::new(),
+            notes: vec![(4, "memory/a.md:1-2".into())],
+        };
+        let out = rewrite_file(content, &note, &[(1, 4)]).unwrap().unwrap();
+        assert!(out.contains("merged_from:\n- memory/a.md:1-2\n"));
+        assert!(out.ends_with("---\nOut of milk\n"));
+    }
+}
@@ -0,0 +1,253 @@
+//! SQLite access for the memory index.
+//!
+//! The index is opened in WAL mode with one writer connection and one
+//! reader connection. Searches read a consistent snapshot while the heartbeat
+//! or the file watcher is indexing, instead of queueing behind the write.
+//! Every connection waits on `busy_timeout` when another process (a CLI
+//! command next to the daemon) holds the write lock, and [`Database::write_with`]
+//! retries a whole write if SQLite still reports the database busy.
+//!
+//! A panic while a connection is borrowed doesn't disable the index: the
+//! connection is taken back from the poisoned lock, since SQLite rolls back
+//! any transaction the panicking code left open.
+
+use anyhow::Result;
+use rusqlite::{Connection, ErrorCode};
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tracing::debug;
+
+/// How long a statement waits for another connection's lock
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Attempts for a write that keeps failing with SQLITE_BUSY
+const WRITE_ATTEMPTS: u32 = 4;
+
+/// First pause between attempts (doubles each time)
+const RETRY_DELAY: Duration = Duration::from_millis(50);
+
+pub type DbGuard<'a> = MutexGuard<'a, Connection>;
+
+/// Shared handle to the index database
+#[derive(Clone)]
+pub struct Database {
+    writer: Arc<Mutex<Connection>>,
+    /// Separate read connection (file databases only)
+    reader: Option<Arc<Mutex<Connection>>>,
+}
+
+impl Database {
+    /// Wrap the connection the schema was created on. For a file database
+    /// this switches to WAL and opens the read connection, running `setup`
+    /// on it (to load extensions the writer has).
+    pub fn new(writer: Connection, setup: impl Fn(&Connection)) -> Result<Self> {
+        configure(&writer)?;
+        let reader = match writer.path().filter(|p| !p.is_empty()) {
+            Some(path) => {
+                let mode: String =
+                    writer
+                        .pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get(0))?;
+                if !mode.eq_ignore_ascii_case("wal") {
+                    debug!("Index journal mode is {}, not WAL", mode);
+                }
+                writer.pragma_update(None, "synchronous", "NORMAL")?;
+                let reader = Connection::open(path)?;
+                configure(&reader)?;
+                reader.pragma_update(None, "query_only", true)?;
+                setup(&reader);
+                Some(Arc::new(Mutex::new(reader)))
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            writer: Arc::new(Mutex::new(writer)),
+            reader,
+        })
+    }
+
+    /// Wrap a connection as-is: no WAL and no separate reader. For one-off
+    /// work on a staged copy that is renamed into place afterwards.
+    pub fn single(conn: Connection) -> Result<Self> {
+        configure(&conn)?;
+        Ok(Self {
+            writer: Arc::new(Mutex::new(conn)),
+            reader: None,
+        })
+    }
+
+    /// The writer connection. Hold it only for the statements that need it.
+    pub fn write(&self) -> DbGuard<'_> {
+        lock(&self.writer)
+    }
+
+    /// A connection for queries; doesn't wait for writes in progress
+    pub fn read(&self) -> DbGuard<'_> {
+        match &self.reader {
+            Some(reader) => lock(reader),
+            None => lock(&self.writer),
+        }
+    }
+
+    /// Run `write` on the writer connection, retrying with backoff while the
+    /// database is busy. `write` runs again from the start on each attempt,
+    /// so it should do its work in one transaction.
+    pub fn write_with<T>(&self, mut write: impl FnMut(&mut Connection) -> Result<T>) -> Result<T> {
+        let mut delay = RETRY_DELAY;
+        let mut attempt = 1;
+        loop {
+            // Released before sleeping so other writers can finish
+            let result = write(&mut self.write());
+            match result {
+                Err(e) if attempt < WRITE_ATTEMPTS && is_busy(&e) => {
+                    debug!("Index busy, retrying write in {:?}: {}", delay, e);
+                    std::thread::sleep(delay);
+                    delay *= 2;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Copy the WAL back into the database file and truncate it, leaving
+    /// the index self-contained. Used on shutdown; a checkpoint blocked by
+    /// another connection is skipped.
+    pub fn checkpoint(&self) -> Result<()> {
+        if self.reader.is_some() {
+            let busy: i64 =
+                self.write()
+                    .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| row.get(0))?;
+            if busy != 0 {
+                debug!("Index checkpoint skipped; database in use");
+            }
+        }
+        Ok(())
+    }
+}
+
+fn configure(conn: &Connection) -> Result<()> {
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    Ok(())
+}
+
+fn lock(conn: &Mutex<Connection>) -> DbGuard<'_> {
+    conn.lock().unwrap_or_else(|poisoned| {
+        debug!("Recovering index connection after a panic");
+        poisoned.into_inner()
+    })
+}
+
+/// Whether an error is SQLite reporting the database busy or locked
+pub fn is_busy(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<rusqlite::Error>(),
+            Some(rusqlite::Error::SqliteFailure(e, _))
+                if matches!(e.code, ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked)
+        )
+    })
+}
+
+/// Rename a database along with its WAL and shared-memory files, so a
+/// leftover WAL is never applied to a different database
+pub fn rename_database(from: &Path, to: &Path) -> Result<()> {
+    std::fs::rename(from, to)?;
+    for suffix in ["-wal", "-shm"] {
+        let companion = sidecar(from, suffix);
+        if companion.exists() {
+            std::fs::rename(&companion, sidecar(to, suffix))?;
+        }
+    }
+    Ok(())
+}
+
+fn sidecar(path: &Path, suffix: &str) -> std::path::PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    name.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn open(path: &Path) -> Database {
+        let conn = Connection::open(path).unwrap();
+        conn.execute_batch("CREATE TABLE IF NOT EXISTS t (n INTEGER)")
+            .unwrap();
+        Database::new(conn, |_| {}).unwrap()
+    }
+
+    #[test]
+    fn test_reads_while_writing() {
+        let dir = TempDir::new().unwrap();
+        let db = open(&dir.path().join("db.sqlite"));
+        let mode: String = db
+            .read()
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(mode, "wal");
+
+        // A search isn't blocked by an open write transaction
+        let mut writer = db.write();
+        let tx = writer.transaction().unwrap();
+        tx.execute("INSERT INTO t VALUES (1)", []).unwrap();
+        let count: i64 = db
+            .read()
+            .query_row("SELECT COUNT(*) FROM t", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 0);
+        tx.commit().unwrap();
+        drop(writer);
+
+        let count: i64 = db
+            .read()
+            .query_row("SELECT COUNT(*) FROM t", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+        assert!(db.read().execute("INSERT INTO t VALUES (2)", []).is_err());
+
+        db.checkpoint().unwrap();
+        let wal = sidecar(&dir.path().join("db.sqlite"), "-wal");
+        assert_eq!(std::fs::metadata(wal).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_write_retries_while_busy() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("db.sqlite");
+        let db = open(&path);
+
+        // Another process holds the write lock past the first attempt
+        let other = Connection::open(&path).unwrap();
+        other.execute_batch("BEGIN IMMEDIATE").unwrap();
+        let mut attempts = 0;
+        let result = db.write_with(|conn| {
+            attempts += 1;
+            if attempts == 2 {
+                other.execute_batch("COMMIT").unwrap();
+            }
+            conn.pragma_update(None, "busy_timeout", 0)?;
+            conn.execute("INSERT INTO t VALUES (1)", [])?;
+            Ok(())
+        });
+        assert!(result.is_ok());
+        assert_eq!(attempts, 2);
+    }
+
+    #[test]
+    fn test_survives_panic_while_locked() {
+        let dir = TempDir::new().unwrap();
+        let db = open(&dir.path().join("db.sqlite"));
+        let shared = db.clone();
+        let _ = std::thread::spawn(move || {
+            let _conn = shared.write();
+            panic!("indexing failed");
+        })
+        .join();
+        assert!(db.write().execute("INSERT INTO t VALUES (1)", []).is_ok());
+    }
+}
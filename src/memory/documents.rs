@@ -0,0 +1,225 @@
+//! Text extraction for non-markdown documents (PDF, DOCX, spreadsheets,
+//! plain text).
+//!
+//! Files under `memory.documents_dir` are indexed like markdown files once
+//! their text is extracted: PDFs via pdf-extract, DOCX by reading the
+//! paragraphs of `word/document.xml`. Scanned PDFs have no text layer and
+//! index as empty. CSV, TSV and XLSX files become one CSV line per row, with
+//! a `# <sheet>` line before each worksheet, and are chunked by row groups.
+
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use calamine::Reader as _;
+use quick_xml::escape::resolve_predefined_entity;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+use crate::config::MemoryConfig;
+
+/// Extensions indexed from the documents directory
+pub const DOCUMENT_EXTENSIONS: &[&str] = &["pdf", "docx", "txt", "csv", "tsv", "xlsx"];
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+}
+
+/// The configured documents directory (workspace-relative or absolute)
+pub fn documents_dir(workspace: &Path, config: &MemoryConfig) -> PathBuf {
+    let dir = &config.documents_dir;
+    if dir.starts_with('~') || dir.starts_with('/') {
+        PathBuf::from(shellexpand::tilde(dir).to_string())
+    } else {
+        workspace.join(dir)
+    }
+}
+
+/// Whether `path` is a document type indexed from the documents directory
+pub fn is_document(path: &Path) -> bool {
+    extension(path).is_some_and(|ext| DOCUMENT_EXTENSIONS.contains(&ext.as_str()))
+}
+
+/// Whether the file is binary and its text has to be extracted (slow for
+/// large PDFs, so unchanged files are skipped before extracting)
+pub fn needs_extraction(path: &Path) -> bool {
+    matches!(
+        extension(path).as_deref(),
+        Some("pdf") | Some("docx") | Some("xlsx")
+    )
+}
+
+/// Whether the file is a table (CSV/TSV/XLSX) chunked by row groups
+pub fn is_table(path: &Path) -> bool {
+    matches!(
+        extension(path).as_deref(),
+        Some("csv") | Some("tsv") | Some("xlsx")
+    )
+}
+
+/// Text content of a file: extracted for PDF/DOCX, one CSV line per row for
+/// spreadsheets, read as-is otherwise
+pub fn read_text(path: &Path) -> Result<String> {
+    match extension(path).as_deref() {
+        Some("pdf") => pdf_extract::extract_text(path)
+            .with_context(|| format!("Failed to extract text from {}", path.display())),
+        Some("docx") => {
+            docx_text(path).with_context(|| format!("Failed to read {}", path.display()))
+        }
+        Some("csv") => csv_text(&fs::read(path)?, b','),
+        Some("tsv") => csv_text(&fs::read(path)?, b'\t'),
+        Some("xlsx") => {
+            xlsx_text(path).with_context(|| format!("Failed to read {}", path.display()))
+        }
+        _ => Ok(fs::read_to_string(path)?),
+    }
+}
+
+/// Paragraph text of a .docx file, one paragraph per line
+fn docx_text(path: &Path) -> Result<String> {
+    let mut archive = zip::ZipArchive::new(fs::File::open(path)?)?;
+    let mut xml = String::new();
+    archive
+        .by_name("word/document.xml")?
+        .read_to_string(&mut xml)?;
+    docx_xml_text(&xml)
+}
+
+fn docx_xml_text(xml: &str) -> Result<String> {
+    let mut reader = Reader::from_str(xml);
+    let mut text = String::new();
+    let mut in_text = false;
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) if e.name().as_ref() == b"w:t" => in_text = true,
+            Event::End(e) => match e.name().as_ref() {
+                b"w:t" => in_text = false,
+                b"w:p" => text.push('\n'),
+                _ => {}
+            },
+            Event::Empty(e) => match e.name().as_ref() {
+                b"w:tab" => text.push('\t'),
+                b"w:br" => text.push('\n'),
+                _ => {}
+            },
+            Event::Text(t) if in_text => text.push_str(&t.decode()?),
+            Event::GeneralRef(r) if in_text => {
+                if let Some(c) = r.resolve_char_ref()? {
+                    text.push(c);
+                } else if let Some(s) = resolve_predefined_entity(&r.decode()?) {
+                    text.push_str(s);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(text)
+}
+
+/// Re-encode delimited text as CSV with one line per row, so quoted fields
+/// with embedded newlines don't break row-group chunking
+fn csv_text(data: &[u8], delimiter: u8) -> Result<String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(delimiter)
+        .from_reader(data);
+    let mut writer = csv_writer();
+    for record in reader.records() {
+        let record = record?;
+        write_row(&mut writer, record.iter().map(str::to_string))?;
+    }
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+/// Every non-empty worksheet as a `# <sheet>` line followed by CSV rows
+fn xlsx_text(path: &Path) -> Result<String> {
+    let mut workbook = calamine::open_workbook_auto(path)?;
+    let mut text = String::new();
+    for name in workbook.sheet_names() {
+        let range = workbook.worksheet_range(&name)?;
+        if range.is_empty() {
+            continue;
+        }
+        let mut writer = csv_writer();
+        for row in range.rows() {
+            write_row(&mut writer, row.iter().map(cell_text))?;
+        }
+        text.push_str(&format!("# {}\n", name));
+        text.push_str(&String::from_utf8(writer.into_inner()?)?);
+    }
+    Ok(text)
+}
+
+fn csv_writer() -> csv::Writer<Vec<u8>> {
+    csv::WriterBuilder::new()
+        .flexible(true)
+        .terminator(csv::Terminator::Any(b'\n'))
+        .from_writer(Vec::new())
+}
+
+fn cell_text(cell: &calamine::Data) -> String {
+    match cell {
+        calamine::Data::DateTime(dt) => match dt.as_datetime() {
+            Some(dt) if dt.time() == chrono::NaiveTime::MIN => dt.date().to_string(),
+            Some(dt) => dt.to_string(),
+            None => cell.to_string(),
+        },
+        _ => cell.to_string(),
+    }
+}
+
+/// Write one row with newlines flattened, skipping rows with no values
+fn write_row<W: std::io::Write>(
+    writer: &mut csv::Writer<W>,
+    fields: impl Iterator<Item = String>,
+) -> Result<()> {
+    let fields: Vec<String> = fields
+        .map(|f| f.replace(['\r', '\n'], " ").trim().to_string())
+        .collect();
+    if fields.iter().any(|f| !f.is_empty()) {
+        writer.write_record(&fields)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_docx_xml_text() {
+        let xml = r#"<w:document><w:body>
+            <w:p><w:r><w:t>Furnace warranty</w:t></w:r></w:p>
+            <w:p><w:r><w:t>Expires:</w:t><w:tab/><w:t xml:space="preserve">2027 &amp; later</w:t></w:r></w:p>
+        </w:body></w:document>"#;
+        assert_eq!(
+            docx_xml_text(xml).unwrap(),
+            "Furnace warranty\nExpires:\t2027 & later\n"
+        );
+    }
+
+    #[test]
+    fn test_is_document() {
+        assert!(is_document(Path::new("memory/documents/Warranty.PDF")));
+        assert!(is_document(Path::new("curriculum.docx")));
+        assert!(!is_document(Path::new("notes.md")));
+        assert!(needs_extraction(Path::new("a.pdf")));
+        assert!(!needs_extraction(Path::new("a.txt")));
+        assert!(is_table(Path::new("budget-2026.XLSX")));
+        assert!(!is_table(Path::new("a.pdf")));
+    }
+
+    #[test]
+    fn test_csv_text_one_line_per_row() {
+        let tsv = "date\tpayee\tamount\n2026-03-02\t\"Corner\nMarket\"\t84.10\n\t\t\n";
+        assert_eq!(
+            csv_text(tsv.as_bytes(), b'\t').unwrap(),
+            "date,payee,amount\n2026-03-02,Corner Market,84.10\n"
+        );
+    }
+}
@@ -0,0 +1,309 @@
+//! Batched, rate-limited embedding for bulk indexing.
+//!
+//! Pending chunks are packed into requests of at most `embedding_batch_size`
+//! texts and `embedding_batch_tokens` estimated tokens. Before each request
+//! the queue waits until it fits in the configured requests-per-minute and
+//! tokens-per-minute budget (a sliding 60 second window shared by every clone
+//! of the queue). Rate limits (429) and server errors are retried with
+//! exponential backoff and jitter, honouring `Retry-After` when the API sends
+//! one.
+
+use anyhow::Result;
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+use super::chunking::CHARS_PER_TOKEN;
+use super::embeddings::{EmbeddingApiError, EmbeddingProvider};
+use crate::config::MemoryConfig;
+
+/// Attempts per batch before giving up
+const MAX_ATTEMPTS: u32 = 6;
+const BASE_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Progress of a bulk embedding run
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EmbedProgress {
+    /// Chunks that now have embeddings (including cache hits)
+    pub embedded: usize,
+    /// Chunks served from the embedding cache
+    pub cached: usize,
+    /// Chunks that were pending when the run started
+    pub total: usize,
+}
+
+/// Requests/tokens per minute; 0 = unlimited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RateLimits {
+    pub rpm: u32,
+    pub tpm: u32,
+}
+
+/// Sliding-window limiter over the last minute of requests
+struct RateLimiter {
+    limits: RateLimits,
+    /// (sent at, estimated tokens)
+    sent: Mutex<VecDeque<(Instant, usize)>>,
+}
+
+impl RateLimiter {
+    /// Wait until a request of `tokens` fits the budget, then record it
+    async fn acquire(&self, tokens: usize) {
+        loop {
+            let wait = {
+                let mut sent = self.sent.lock().await;
+                self.limits.reserve(&mut sent, Instant::now(), tokens)
+            };
+            match wait {
+                Some(wait) => {
+                    debug!("Embedding rate limit reached, waiting {:?}", wait);
+                    tokio::time::sleep(wait).await;
+                }
+                None => return,
+            }
+        }
+    }
+}
+
+impl RateLimits {
+    /// Record a request of `tokens` sent at `now` if it fits the window,
+    /// otherwise return how long until the oldest request expires
+    fn reserve(
+        &self,
+        sent: &mut VecDeque<(Instant, usize)>,
+        now: Instant,
+        tokens: usize,
+    ) -> Option<Duration> {
+        while sent.front().is_some_and(|(at, _)| now - *at >= WINDOW) {
+            sent.pop_front();
+        }
+
+        let used: usize = sent.iter().map(|(_, t)| t).sum();
+        let rpm_ok = self.rpm == 0 || sent.len() < self.rpm as usize;
+        // An oversized request still goes through on an empty window
+        let tpm_ok = self.tpm == 0 || sent.is_empty() || used + tokens <= self.tpm as usize;
+        if rpm_ok && tpm_ok {
+            sent.push_back((now, tokens));
+            return None;
+        }
+        sent.front().map(|(at, _)| WINDOW - (now - *at))
+    }
+}
+
+/// Embeds texts in token-limited batches under a shared rate limit
+#[derive(Clone)]
+pub struct EmbeddingQueue {
+    provider: Arc<dyn EmbeddingProvider>,
+    max_batch_size: usize,
+    max_batch_tokens: usize,
+    limiter: Arc<RateLimiter>,
+}
+
+impl EmbeddingQueue {
+    pub fn new(provider: Arc<dyn EmbeddingProvider>, config: &MemoryConfig) -> Self {
+        Self {
+            provider,
+            max_batch_size: config.embedding_batch_size.max(1),
+            max_batch_tokens: config.embedding_batch_tokens,
+            limiter: Arc::new(RateLimiter {
+                limits: RateLimits {
+                    rpm: config.embedding_rpm,
+                    tpm: config.embedding_tpm,
+                },
+                sent: Mutex::new(VecDeque::new()),
+            }),
+        }
+    }
+
+    pub fn provider(&self) -> &Arc<dyn EmbeddingProvider> {
+        &self.provider
+    }
+
+    /// Texts loaded from the index per round (a few requests' worth)
+    pub fn page_size(&self) -> usize {
+        self.max_batch_size * 4
+    }
+
+    /// Embed `texts` in batches, calling `on_batch` with the number of texts
+    /// after each request. Returns the embeddings for the leading batches
+    /// that succeeded; the error, if any, is for the first batch that failed.
+    pub async fn embed(
+        &self,
+        texts: &[String],
+        mut on_batch: impl FnMut(usize) + Send,
+    ) -> (Vec<Vec<f32>>, Option<anyhow::Error>) {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for range in pack_batches(texts, self.max_batch_size, self.max_batch_tokens) {
+            let batch = &texts[range];
+            match self.embed_with_retry(batch).await {
+                Ok(batch_embeddings) => {
+                    embeddings.extend(batch_embeddings);
+                    on_batch(batch.len());
+                }
+                Err(e) => return (embeddings, Some(e)),
+            }
+        }
+        (embeddings, None)
+    }
+
+    async fn embed_with_retry(&self, batch: &[String]) -> Result<Vec<Vec<f32>>> {
+        let tokens = batch.iter().map(|t| estimate_tokens(t)).sum();
+        let mut attempt = 0;
+        loop {
+            self.limiter.acquire(tokens).await;
+            let error = match self.provider.embed_batch(batch).await {
+                Ok(embeddings) if embeddings.len() == batch.len() => return Ok(embeddings),
+                Ok(embeddings) => anyhow::anyhow!(
+                    "Embedding provider returned {} embeddings for {} texts",
+                    embeddings.len(),
+                    batch.len()
+                ),
+                Err(e) => e,
+            };
+
+            attempt += 1;
+            let delay = match retry_delay(&error, attempt) {
+                Some(delay) if attempt < MAX_ATTEMPTS => delay,
+                _ => return Err(error),
+            };
+            warn!(
+                "Embedding batch of {} failed (attempt {}/{}), retrying in {:?}: {}",
+                batch.len(),
+                attempt,
+                MAX_ATTEMPTS,
+                delay,
+                error
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+fn estimate_tokens(text: &str) -> usize {
+    text.len().div_ceil(CHARS_PER_TOKEN)
+}
+
+/// Split `texts` into consecutive batches of at most `max_size` texts and
+/// `max_tokens` estimated tokens (0 = no token cap). A text over the token
+/// cap gets a batch of its own.
+fn pack_batches(texts: &[String], max_size: usize, max_tokens: usize) -> Vec<Range<usize>> {
+    let mut batches = Vec::new();
+    let mut start = 0;
+    let mut tokens = 0;
+    for (i, text) in texts.iter().enumerate() {
+        let t = estimate_tokens(text);
+        let full = i - start >= max_size || (max_tokens > 0 && tokens + t > max_tokens);
+        if i > start && full {
+            batches.push(start..i);
+            start = i;
+            tokens = 0;
+        }
+        tokens += t;
+    }
+    if start < texts.len() {
+        batches.push(start..texts.len());
+    }
+    batches
+}
+
+/// Backoff before retry `attempt` (1-based), or None if the error is final
+fn retry_delay(error: &anyhow::Error, attempt: u32) -> Option<Duration> {
+    let retry_after = if let Some(api) = error.downcast_ref::<EmbeddingApiError>() {
+        if !api.is_retryable() {
+            return None;
+        }
+        api.retry_after
+    } else if error
+        .downcast_ref::<reqwest::Error>()
+        .is_some_and(|e| e.is_timeout() || e.is_connect())
+    {
+        None
+    } else {
+        return None;
+    };
+
+    let backoff = BASE_BACKOFF
+        .saturating_mul(1u32 << attempt.min(6))
+        .min(MAX_BACKOFF);
+    // Equal jitter: half fixed, half random, so parallel clients spread out
+    let jittered = backoff / 2 + backoff.mul_f64(jitter() / 2.0);
+    Some(retry_after.map_or(jittered, |after| after.max(jittered)))
+}
+
+/// Pseudo-random fraction in [0, 1)
+fn jitter() -> f64 {
+    (uuid::Uuid::new_v4().as_u128() % 10_000) as f64 / 10_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_batches() {
+        let texts = vec![
+            "a".repeat(40),
+            "b".repeat(40),
+            "c".repeat(400),
+            "d".repeat(8),
+        ];
+
+        // 10 + 10 tokens fit, 100 is over the cap alone, 2 follows it
+        assert_eq!(pack_batches(&texts, 10, 50), vec![0..2, 2..3, 3..4]);
+        assert_eq!(pack_batches(&texts, 3, 0), vec![0..3, 3..4]);
+        assert_eq!(pack_batches(&texts, 1, 0).len(), 4);
+        assert!(pack_batches(&[], 10, 50).is_empty());
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let api = |status| {
+            anyhow::Error::new(EmbeddingApiError {
+                status,
+                retry_after: None,
+                message: String::new(),
+            })
+        };
+
+        assert!(retry_delay(&api(400), 1).is_none());
+        assert!(retry_delay(&anyhow::anyhow!("bad input"), 1).is_none());
+
+        let delay = retry_delay(&api(429), 1).unwrap();
+        assert!(delay >= Duration::from_secs(1) && delay <= Duration::from_secs(2));
+        assert!(retry_delay(&api(503), 10).unwrap() <= MAX_BACKOFF);
+
+        let limited = anyhow::Error::new(EmbeddingApiError {
+            status: 429,
+            retry_after: Some(Duration::from_secs(30)),
+            message: String::new(),
+        });
+        assert!(retry_delay(&limited, 1).unwrap() >= Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_rate_limits() {
+        let limits = RateLimits { rpm: 2, tpm: 100 };
+        let mut sent = VecDeque::new();
+        let start = Instant::now();
+
+        assert_eq!(limits.reserve(&mut sent, start, 10), None);
+        // Over the token budget: wait for the first request to age out
+        assert_eq!(
+            limits.reserve(&mut sent, start + Duration::from_secs(20), 95),
+            Some(Duration::from_secs(40))
+        );
+        assert_eq!(limits.reserve(&mut sent, start, 10), None);
+        // Over the request budget
+        assert!(limits.reserve(&mut sent, start, 1).is_some());
+        assert_eq!(limits.reserve(&mut sent, start + WINDOW, 1), None);
+
+        // A single oversized request is not blocked forever
+        let mut sent = VecDeque::new();
+        assert_eq!(limits.reserve(&mut sent, start, 500), None);
+    }
+}
@@ -8,7 +8,8 @@ use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use tracing::debug;
+use std::time::Duration;
+use tracing::{debug, info, warn};
 
 /// Embedding provider trait
 #[async_trait]
@@ -34,27 +35,37 @@ pub struct OpenAIEmbeddingProvider {
     client: Client,
     api_key: String,
     base_url: String,
+    /// Model name sent to the API
+    api_model: String,
+    /// Model name plus reduced dimensions, as stored with embeddings
     model: String,
     dimensions: usize,
+    /// Requested via the API `dimensions` parameter
+    reduced_dimensions: Option<usize>,
 }
 
 impl OpenAIEmbeddingProvider {
-    pub fn new(api_key: &str, base_url: &str, model: &str) -> Result<Self> {
+    /// `dimensions` asks `text-embedding-3-*` models for shorter embeddings
+    /// (0 = the model's full size)
+    pub fn new(api_key: &str, base_url: &str, model: &str, dimensions: usize) -> Result<Self> {
         // text-embedding-3-small has 1536 dimensions by default
         // text-embedding-3-large has 3072 dimensions by default
-        let dimensions = match model {
+        let native = match model {
             "text-embedding-3-small" => 1536,
             "text-embedding-3-large" => 3072,
             "text-embedding-ada-002" => 1536,
             _ => 1536, // default
         };
+        let reduced_dimensions = reduced_dimensions(model, native, dimensions)?;
 
         Ok(Self {
             client: Client::new(),
             api_key: api_key.to_string(),
             base_url: base_url.to_string(),
-            model: model.to_string(),
-            dimensions,
+            api_model: model.to_string(),
+            model: model_id(model, reduced_dimensions),
+            dimensions: reduced_dimensions.unwrap_or(native),
+            reduced_dimensions,
         })
     }
 }
@@ -63,6 +74,8 @@ impl OpenAIEmbeddingProvider {
 struct EmbeddingRequest {
     model: String,
     input: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dimensions: Option<usize>,
 }
 
 #[derive(Deserialize)]
@@ -103,8 +116,9 @@ impl EmbeddingProvider for OpenAIEmbeddingProvider {
         }
 
         let request = EmbeddingRequest {
-            model: self.model.clone(),
+            model: self.api_model.clone(),
             input: texts.to_vec(),
+            dimensions: self.reduced_dimensions,
         };
 
         debug!("Embedding {} texts with {}", texts.len(), self.model);
@@ -119,24 +133,320 @@ impl EmbeddingProvider for OpenAIEmbeddingProvider {
             .await?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            anyhow::bail!("OpenAI API error {}: {}", status, body);
+            return Err(api_error("OpenAI", response).await);
         }
 
         let response: EmbeddingResponse = response.json().await?;
 
-        // Normalize embeddings to unit vectors
+        // Normalize embeddings to unit vectors (truncating for compatible
+        // servers that ignore `dimensions`)
         let embeddings: Vec<Vec<f32>> = response
             .data
             .into_iter()
-            .map(|d| normalize_embedding(d.embedding))
+            .map(|d| truncate_embedding(d.embedding, self.dimensions))
             .collect();
 
         Ok(embeddings)
     }
 }
 
+/// Google Gemini embedding provider (`gemini-embedding-001`, `text-embedding-004`)
+pub struct GeminiEmbeddingProvider {
+    client: Client,
+    api_key: String,
+    base_url: String,
+    api_model: String,
+    model: String,
+    dimensions: usize,
+    /// Requested via `outputDimensionality`
+    reduced_dimensions: Option<usize>,
+}
+
+/// Texts per `batchEmbedContents` request (API limit)
+const GEMINI_MAX_BATCH: usize = 100;
+
+impl GeminiEmbeddingProvider {
+    pub const DEFAULT_MODEL: &'static str = "gemini-embedding-001";
+
+    /// `dimensions` asks `gemini-embedding-001` for shorter embeddings
+    /// (0 = the model's full size; 768 or 1536 recommended)
+    pub fn new(api_key: &str, base_url: &str, model: &str, dimensions: usize) -> Result<Self> {
+        let api_model = model.trim_start_matches("models/");
+        let native = match api_model {
+            "text-embedding-004" | "embedding-001" => 768,
+            _ => 3072, // gemini-embedding-001
+        };
+        let reduced_dimensions = reduced_dimensions(api_model, native, dimensions)?;
+
+        Ok(Self {
+            client: Client::new(),
+            api_key: api_key.to_string(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_model: api_model.to_string(),
+            model: model_id(api_model, reduced_dimensions),
+            dimensions: reduced_dimensions.unwrap_or(native),
+            reduced_dimensions,
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct GeminiBatchRequest<'a> {
+    requests: Vec<GeminiEmbedRequest<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiEmbedRequest<'a> {
+    model: String,
+    content: GeminiContent<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output_dimensionality: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct GeminiContent<'a> {
+    parts: [GeminiPart<'a>; 1],
+}
+
+#[derive(Serialize)]
+struct GeminiPart<'a> {
+    text: &'a str,
+}
+
+#[derive(Deserialize)]
+struct GeminiBatchResponse {
+    embeddings: Vec<GeminiEmbedding>,
+}
+
+#[derive(Deserialize)]
+struct GeminiEmbedding {
+    values: Vec<f32>,
+}
+
+#[async_trait]
+impl EmbeddingProvider for GeminiEmbeddingProvider {
+    fn id(&self) -> &str {
+        "gemini"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let results = self.embed_batch(&[text.to_string()]).await?;
+        results
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("No embedding returned"))
+    }
+
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        debug!("Embedding {} texts with {}", texts.len(), self.model);
+
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(GEMINI_MAX_BATCH) {
+            let request = GeminiBatchRequest {
+                requests: batch
+                    .iter()
+                    .map(|text| GeminiEmbedRequest {
+                        model: format!("models/{}", self.api_model),
+                        content: GeminiContent {
+                            parts: [GeminiPart { text }],
+                        },
+                        output_dimensionality: self.reduced_dimensions,
+                    })
+                    .collect(),
+            };
+
+            let response = self
+                .client
+                .post(format!(
+                    "{}/models/{}:batchEmbedContents",
+                    self.base_url, self.api_model
+                ))
+                .header("x-goog-api-key", &self.api_key)
+                .json(&request)
+                .send()
+                .await?;
+
+            if !response.status().is_success() {
+                return Err(api_error("Gemini", response).await);
+            }
+
+            let response: GeminiBatchResponse = response.json().await?;
+            // Reduced Gemini embeddings are not unit length
+            embeddings.extend(
+                response
+                    .embeddings
+                    .into_iter()
+                    .map(|e| truncate_embedding(e.values, self.dimensions)),
+            );
+        }
+
+        Ok(embeddings)
+    }
+}
+
+/// Voyage AI embedding provider (`voyage-3.5`, `voyage-3-large`, ...)
+pub struct VoyageEmbeddingProvider {
+    client: Client,
+    api_key: String,
+    base_url: String,
+    api_model: String,
+    model: String,
+    dimensions: usize,
+    /// Requested via `output_dimension`
+    reduced_dimensions: Option<usize>,
+}
+
+impl VoyageEmbeddingProvider {
+    pub const DEFAULT_MODEL: &'static str = "voyage-3.5";
+
+    /// `dimensions` asks voyage-3.5, voyage-3-large and voyage-code-3 for
+    /// 256 or 512 dimensions (0 = the model's full size)
+    pub fn new(api_key: &str, base_url: &str, model: &str, dimensions: usize) -> Result<Self> {
+        let native = match model {
+            "voyage-3-lite" => 512,
+            "voyage-code-2" => 1536,
+            _ => 1024,
+        };
+        let reduced_dimensions = reduced_dimensions(model, native, dimensions)?;
+        if let Some(dims) = reduced_dimensions {
+            if ![256, 512].contains(&dims) {
+                anyhow::bail!(
+                    "{} supports 256 or 512 reduced dimensions, not {}",
+                    model,
+                    dims
+                );
+            }
+        }
+
+        Ok(Self {
+            client: Client::new(),
+            api_key: api_key.to_string(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_model: model.to_string(),
+            model: model_id(model, reduced_dimensions),
+            dimensions: reduced_dimensions.unwrap_or(native),
+            reduced_dimensions,
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct VoyageRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output_dimension: Option<usize>,
+}
+
+#[async_trait]
+impl EmbeddingProvider for VoyageEmbeddingProvider {
+    fn id(&self) -> &str {
+        "voyage"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let results = self.embed_batch(&[text.to_string()]).await?;
+        results
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("No embedding returned"))
+    }
+
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let request = VoyageRequest {
+            model: &self.api_model,
+            input: texts,
+            output_dimension: self.reduced_dimensions,
+        };
+
+        debug!("Embedding {} texts with {}", texts.len(), self.model);
+
+        let response = self
+            .client
+            .post(format!("{}/embeddings", self.base_url))
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .json(&request)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(api_error("Voyage", response).await);
+        }
+
+        // Same response shape as OpenAI
+        let response: EmbeddingResponse = response.json().await?;
+        Ok(response
+            .data
+            .into_iter()
+            .map(|d| normalize_embedding(d.embedding))
+            .collect())
+    }
+}
+
+/// Turn an unsuccessful API response into an [`EmbeddingApiError`]
+async fn api_error(api: &str, response: reqwest::Response) -> anyhow::Error {
+    let status = response.status();
+    let retry_after = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<f64>().ok())
+        .map(Duration::from_secs_f64);
+    let body = response.text().await.unwrap_or_default();
+    EmbeddingApiError {
+        status: status.as_u16(),
+        retry_after,
+        message: format!("{} API error {}: {}", api, status, body),
+    }
+    .into()
+}
+
+/// HTTP error from an embedding API, kept typed so the embedding queue can
+/// retry rate limits and server errors
+#[derive(Debug)]
+pub struct EmbeddingApiError {
+    pub status: u16,
+    /// Server-requested delay from the `Retry-After` header
+    pub retry_after: Option<Duration>,
+    pub message: String,
+}
+
+impl EmbeddingApiError {
+    /// Rate limited (429) or a server error worth retrying
+    pub fn is_retryable(&self) -> bool {
+        self.status == 429 || self.status >= 500
+    }
+}
+
+impl std::fmt::Display for EmbeddingApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for EmbeddingApiError {}
+
 /// Normalize embedding to unit vector
 pub fn normalize_embedding(mut vec: Vec<f32>) -> Vec<f32> {
     let magnitude: f32 = vec.iter().map(|x| x * x).sum::<f32>().sqrt();
@@ -148,6 +458,65 @@ pub fn normalize_embedding(mut vec: Vec<f32>) -> Vec<f32> {
     vec
 }
 
+/// Cut an embedding to its first `dims` components and renormalize.
+///
+/// Only meaningful for Matryoshka (MRL) models, whose leading components
+/// carry most of the information.
+pub fn truncate_embedding(mut vec: Vec<f32>, dims: usize) -> Vec<f32> {
+    vec.truncate(dims);
+    normalize_embedding(vec)
+}
+
+/// Whether a model was trained with Matryoshka representation learning, so
+/// its embeddings can be truncated with little quality loss
+pub fn supports_matryoshka(model: &str) -> bool {
+    let model = model.to_lowercase();
+    [
+        "text-embedding-3-",
+        "gemini-embedding-",
+        "voyage-3.5",
+        "voyage-3-large",
+        "voyage-code-3",
+        "nomic-embed-text-v1.5",
+        "embeddinggemma",
+        "mxbai-embed-large",
+    ]
+    .iter()
+    .any(|name| model.contains(name))
+}
+
+/// Validate a requested dimension count (0 = native) for a model, returning
+/// `Some(dims)` if embeddings should be reduced
+fn reduced_dimensions(model: &str, native: usize, requested: usize) -> Result<Option<usize>> {
+    if requested == 0 || requested == native {
+        return Ok(None);
+    }
+    if !supports_matryoshka(model) {
+        anyhow::bail!(
+            "{} does not support reduced embedding dimensions (Matryoshka models only: text-embedding-3-*, gemini-embedding-001, voyage-3.5, voyage-3-large, voyage-code-3, nomic-embed-text-v1.5, embeddinggemma, mxbai-embed-large)",
+            model
+        );
+    }
+    if requested > native {
+        anyhow::bail!(
+            "{} has {} dimensions; cannot request {}",
+            model,
+            native,
+            requested
+        );
+    }
+    Ok(Some(requested))
+}
+
+/// Model name stored with embeddings, so vectors of different sizes from the
+/// same model are never compared
+fn model_id(model: &str, reduced_dimensions: Option<usize>) -> String {
+    match reduced_dimensions {
+        Some(dims) => format!("{}@{}", model, dims),
+        None => model.to_string(),
+    }
+}
+
 /// Hash text for embedding cache lookup
 pub fn hash_text(text: &str) -> String {
     let mut hasher = Sha256::new();
@@ -165,6 +534,101 @@ pub struct FastEmbedProvider {
     model: Arc<StdMutex<fastembed::TextEmbedding>>,
     model_name: String,
     dimensions: usize,
+    /// Matryoshka truncation applied to every embedding
+    reduced_dimensions: Option<usize>,
+}
+
+/// ONNX Runtime execution provider for local embeddings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExecutionProvider {
+    /// CPU only (default)
+    #[default]
+    Cpu,
+    /// Every accelerator compiled in, falling back to CPU
+    Auto,
+    /// NVIDIA GPUs (`--features cuda`)
+    Cuda,
+    /// Apple Neural Engine / GPU (`--features coreml`)
+    CoreMl,
+    /// Windows GPUs via DirectX 12 (`--features directml`)
+    DirectMl,
+}
+
+impl std::str::FromStr for ExecutionProvider {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "cpu" | "" => Ok(Self::Cpu),
+            "auto" => Ok(Self::Auto),
+            "cuda" => Ok(Self::Cuda),
+            "coreml" => Ok(Self::CoreMl),
+            "directml" => Ok(Self::DirectMl),
+            other => anyhow::bail!(
+                "Unknown execution provider '{}': expected cpu, auto, cuda, coreml or directml",
+                other
+            ),
+        }
+    }
+}
+
+impl ExecutionProvider {
+    /// Config and cargo feature name
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Cpu => "cpu",
+            Self::Auto => "auto",
+            Self::Cuda => "cuda",
+            Self::CoreMl => "coreml",
+            Self::DirectMl => "directml",
+        }
+    }
+
+    /// Execution providers to register, in order of preference. An explicitly
+    /// chosen accelerator must register; `Auto` falls back to CPU silently.
+    fn dispatch(self) -> Result<Vec<fastembed::ExecutionProviderDispatch>> {
+        #[allow(unused_mut)]
+        let mut available: Vec<(Self, fastembed::ExecutionProviderDispatch)> = Vec::new();
+        #[cfg(feature = "cuda")]
+        available.push((Self::Cuda, ort::ep::CUDA::default().build()));
+        #[cfg(feature = "coreml")]
+        available.push((Self::CoreMl, ort::ep::CoreML::default().build()));
+        #[cfg(feature = "directml")]
+        available.push((Self::DirectMl, ort::ep::DirectML::default().build()));
+
+        match self {
+            Self::Cpu => Ok(Vec::new()),
+            Self::Auto => Ok(available.into_iter().map(|(_, ep)| ep).collect()),
+            wanted => match available.into_iter().find(|(ep, _)| *ep == wanted) {
+                Some((_, ep)) => Ok(vec![ep.error_on_failure()]),
+                None => anyhow::bail!(
+                    "homegpt was built without {} support; rebuild with `--features {}`",
+                    wanted.name(),
+                    wanted.name()
+                ),
+            },
+        }
+    }
+}
+
+/// Hardware options for [`FastEmbedProvider`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExecutionOptions {
+    pub provider: ExecutionProvider,
+    /// ONNX Runtime intra-op threads (0 = one per core)
+    pub threads: usize,
+}
+
+/// Size ONNX Runtime's shared thread pool. Must run before the first model
+/// is loaded; later calls have no effect.
+fn configure_ort_threads(threads: usize) -> Result<()> {
+    use ort::environment::GlobalThreadPoolOptions;
+
+    let pool = GlobalThreadPoolOptions::default().with_intra_threads(threads)?;
+    if !ort::init().with_global_thread_pool(pool).commit() {
+        warn!("ONNX Runtime already initialised; ignoring embedding_threads");
+    }
+    Ok(())
 }
 
 impl FastEmbedProvider {
@@ -173,6 +637,15 @@ impl FastEmbedProvider {
     }
 
     pub fn new_with_cache_dir(model_name: Option<&str>, cache_dir: Option<&str>) -> Result<Self> {
+        Self::new_with_options(model_name, cache_dir, ExecutionOptions::default())
+    }
+
+    /// Load a model on the given execution provider and thread count
+    pub fn new_with_options(
+        model_name: Option<&str>,
+        cache_dir: Option<&str>,
+        options: ExecutionOptions,
+    ) -> Result<Self> {
         use fastembed::{EmbeddingModel, InitOptions, TextEmbedding};
 
         // Set cache directory via environment variable if provided
@@ -231,14 +704,59 @@ impl FastEmbedProvider {
             }
         };
 
-        debug!("Loading local embedding model: {}", name);
-        let model = TextEmbedding::try_new(InitOptions::new(model_enum))?;
+        if options.threads > 0 {
+            configure_ort_threads(options.threads)?;
+        }
 
-        Ok(Self {
+        debug!(
+            "Loading local embedding model: {} ({})",
+            name,
+            options.provider.name()
+        );
+        let model = TextEmbedding::try_new(
+            InitOptions::new(model_enum).with_execution_providers(options.provider.dispatch()?),
+        )?;
+
+        let provider = Self {
             model: Arc::new(StdMutex::new(model)),
             model_name: name.to_string(),
             dimensions: dims,
-        })
+            reduced_dimensions: None,
+        };
+
+        // Accelerators compile kernels on first use; pay that at startup
+        // rather than on the first search
+        if options.provider != ExecutionProvider::Cpu {
+            provider.warm_up()?;
+        }
+
+        Ok(provider)
+    }
+
+    /// Run one embedding so the session is fully initialised
+    pub fn warm_up(&self) -> Result<()> {
+        let start = std::time::Instant::now();
+        self.model
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?
+            .embed(vec!["warm up"], None)
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        info!(
+            "Warmed up embedding model {} in {:?}",
+            self.model_name,
+            start.elapsed()
+        );
+        Ok(())
+    }
+
+    /// Truncate embeddings to `dimensions` (0 = full size; MRL models only)
+    pub fn with_dimensions(mut self, dimensions: usize) -> Result<Self> {
+        if let Some(dims) = reduced_dimensions(&self.model_name, self.dimensions, dimensions)? {
+            self.model_name = model_id(&self.model_name, Some(dims));
+            self.dimensions = dims;
+            self.reduced_dimensions = Some(dims);
+        }
+        Ok(self)
     }
 }
 
@@ -290,7 +808,13 @@ impl EmbeddingProvider for FastEmbedProvider {
         .await??;
 
         // Normalize all embeddings
-        Ok(embeddings.into_iter().map(normalize_embedding).collect())
+        Ok(embeddings
+            .into_iter()
+            .map(|e| match self.reduced_dimensions {
+                Some(dims) => truncate_embedding(e, dims),
+                None => normalize_embedding(e),
+            })
+            .collect())
     }
 }
 
@@ -304,6 +828,8 @@ pub struct LlamaCppProvider {
     backend: Arc<llama_cpp_2::llama_backend::LlamaBackend>,
     model_name: String,
     dimensions: usize,
+    /// Matryoshka truncation applied to every embedding
+    reduced_dimensions: Option<usize>,
     #[allow(dead_code)] // Reserved for future HuggingFace download support
     cache_dir: Option<String>,
 }
@@ -352,10 +878,21 @@ impl LlamaCppProvider {
             backend: Arc::new(backend),
             model_name,
             dimensions,
+            reduced_dimensions: None,
             cache_dir: cache_dir.map(|s| s.to_string()),
         })
     }
 
+    /// Truncate embeddings to `dimensions` (0 = full size; MRL models only)
+    pub fn with_dimensions(mut self, dimensions: usize) -> Result<Self> {
+        if let Some(dims) = reduced_dimensions(&self.model_name, self.dimensions, dimensions)? {
+            self.model_name = model_id(&self.model_name, Some(dims));
+            self.dimensions = dims;
+            self.reduced_dimensions = Some(dims);
+        }
+        Ok(self)
+    }
+
     /// Resolve model path - download from HuggingFace if needed
     fn resolve_model_path(model_path: &str, cache_dir: Option<&str>) -> Result<String> {
         // If it's already a file path, use it directly
@@ -422,6 +959,7 @@ impl EmbeddingProvider for LlamaCppProvider {
         let model = Arc::clone(&self.model);
         let backend = Arc::clone(&self.backend);
         let model_name = self.model_name.clone();
+        let reduced_dimensions = self.reduced_dimensions;
 
         // llama.cpp is synchronous, run in blocking task
         tokio::task::spawn_blocking(move || {
@@ -459,7 +997,10 @@ impl EmbeddingProvider for LlamaCppProvider {
                 text.len()
             );
 
-            Ok(normalize_embedding(embeddings.to_vec()))
+            Ok(match reduced_dimensions {
+                Some(dims) => truncate_embedding(embeddings.to_vec(), dims),
+                None => normalize_embedding(embeddings.to_vec()),
+            })
         })
         .await?
     }
@@ -495,6 +1036,26 @@ pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
     a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
 }
 
+/// Normalized mean of several embeddings (e.g. a query and its HyDE answer).
+/// Returns `None` if there are no embeddings or their dimensions differ.
+pub fn mean_embedding(embeddings: &[Vec<f32>]) -> Option<Vec<f32>> {
+    let first = embeddings.first()?;
+    if embeddings.iter().any(|e| e.len() != first.len()) {
+        return None;
+    }
+    if embeddings.len() == 1 {
+        return Some(first.clone());
+    }
+
+    let mut mean = vec![0.0f32; first.len()];
+    for embedding in embeddings {
+        for (m, x) in mean.iter_mut().zip(embedding) {
+            *m += x;
+        }
+    }
+    Some(normalize_embedding(mean))
+}
+
 /// Serialize embedding to JSON string for storage
 pub fn serialize_embedding(embedding: &[f32]) -> String {
     serde_json::to_string(embedding).unwrap_or_else(|_| "[]".to_string())
@@ -509,6 +1070,16 @@ pub fn deserialize_embedding(json: &str) -> Vec<f32> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_mean_embedding() {
+        let mean = mean_embedding(&[vec![1.0, 0.0], vec![0.0, 1.0]]).unwrap();
+        assert!((mean[0] - mean[1]).abs() < 1e-6);
+        assert!((mean[0] * mean[0] + mean[1] * mean[1] - 1.0).abs() < 1e-6);
+
+        assert!(mean_embedding(&[]).is_none());
+        assert!(mean_embedding(&[vec![1.0], vec![1.0, 0.0]]).is_none());
+    }
+
     #[test]
     fn test_normalize_embedding() {
         let vec = vec![3.0, 4.0];
@@ -517,6 +1088,64 @@ mod tests {
         assert!((normalized[1] - 0.8).abs() < 1e-6);
     }
 
+    #[test]
+    fn test_matryoshka_truncation() {
+        let truncated = truncate_embedding(vec![3.0, 4.0, 12.0], 2);
+        assert_eq!(truncated.len(), 2);
+        assert!((truncated[0] - 0.6).abs() < 1e-6);
+
+        assert_eq!(
+            reduced_dimensions("text-embedding-3-large", 3072, 512).unwrap(),
+            Some(512)
+        );
+        assert_eq!(
+            reduced_dimensions("all-MiniLM-L6-v2", 384, 0).unwrap(),
+            None
+        );
+        assert!(reduced_dimensions("all-MiniLM-L6-v2", 384, 128).is_err());
+        assert!(reduced_dimensions("text-embedding-3-small", 1536, 2048).is_err());
+        assert!(reduced_dimensions("text-embedding-004", 768, 256).is_err());
+
+        let gemini = GeminiEmbeddingProvider::new(
+            "key",
+            "https://example.com/",
+            "models/gemini-embedding-001",
+            768,
+        )
+        .unwrap();
+        assert_eq!(gemini.model(), "gemini-embedding-001@768");
+        assert_eq!(gemini.dimensions(), 768);
+        assert_eq!(gemini.base_url, "https://example.com");
+        let voyage = VoyageEmbeddingProvider::new("key", "", "voyage-3.5", 0).unwrap();
+        assert_eq!((voyage.model(), voyage.dimensions()), ("voyage-3.5", 1024));
+        assert!(VoyageEmbeddingProvider::new("key", "", "voyage-3.5", 300).is_err());
+        assert_eq!(
+            model_id("text-embedding-3-large", Some(512)),
+            "text-embedding-3-large@512"
+        );
+    }
+
+    #[test]
+    fn test_execution_provider() {
+        assert_eq!(
+            "CUDA".parse::<ExecutionProvider>().unwrap(),
+            ExecutionProvider::Cuda
+        );
+        assert_eq!(
+            "".parse::<ExecutionProvider>().unwrap(),
+            ExecutionProvider::Cpu
+        );
+        assert!("tpu".parse::<ExecutionProvider>().is_err());
+
+        assert!(ExecutionProvider::Cpu.dispatch().unwrap().is_empty());
+        #[cfg(not(feature = "coreml"))]
+        assert!(ExecutionProvider::CoreMl
+            .dispatch()
+            .unwrap_err()
+            .to_string()
+            .contains("--features coreml"));
+    }
+
     #[test]
     fn test_cosine_similarity() {
         let a = vec![1.0, 0.0];
@@ -0,0 +1,306 @@
+//! Retrieval evaluation (`homegpt eval`).
+//!
+//! An eval set is a YAML file of questions, each with the files (and
+//! optionally the text inside them) a good search should return. Every
+//! question is searched against the live index and scored by recall@k (the
+//! share of expected results found in the top k) and reciprocal rank (1 over
+//! the rank of the first expected result, 0 if none is in the top k).
+//! Scores are averaged per category and overall, and a report saved as JSON
+//! can be compared with a later run after changing chunking or the
+//! embedding model.
+//!
+//! ```yaml
+//! k: 5
+//! questions:
+//!   - question: What's the garage door code?
+//!     category: household
+//!     expected:
+//!       - file: MEMORY.md
+//!         contains: garage
+//! ```
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use super::MemoryChunk;
+
+/// k used when neither the eval set nor the command line sets one
+pub const DEFAULT_K: usize = 5;
+
+/// Category for questions without one
+const UNCATEGORIZED: &str = "uncategorized";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EvalSet {
+    #[serde(default)]
+    pub k: Option<usize>,
+    pub questions: Vec<EvalQuestion>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EvalQuestion {
+    pub question: String,
+    #[serde(default)]
+    pub category: Option<String>,
+    pub expected: Vec<Expected>,
+}
+
+/// A result the search should return: a chunk from `file`, containing
+/// `contains` when set
+#[derive(Debug, Clone, Deserialize)]
+pub struct Expected {
+    pub file: String,
+    #[serde(default)]
+    pub contains: Option<String>,
+}
+
+impl Expected {
+    fn matches(&self, chunk: &MemoryChunk) -> bool {
+        let file = self.file.trim_start_matches("./");
+        chunk.file.trim_start_matches("./") == file
+            && self
+                .contains
+                .as_ref()
+                .is_none_or(|text| chunk.content.to_lowercase().contains(&text.to_lowercase()))
+    }
+}
+
+impl EvalSet {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read eval set {}", path.display()))?;
+        let set: Self = serde_yaml::from_str(&content)
+            .with_context(|| format!("Invalid eval set {}", path.display()))?;
+
+        if set.questions.is_empty() {
+            bail!("Eval set {} has no questions", path.display());
+        }
+        if let Some(question) = set.questions.iter().find(|q| q.expected.is_empty()) {
+            bail!("Question {:?} has no expected results", question.question);
+        }
+        if set.k == Some(0) {
+            bail!("k must be at least 1");
+        }
+        Ok(set)
+    }
+}
+
+/// Score of one question
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuestionResult {
+    pub question: String,
+    pub category: String,
+    pub recall: f64,
+    pub reciprocal_rank: f64,
+    /// Expected results not in the top k, as `file` or `file: contains`
+    pub missed: Vec<String>,
+}
+
+/// Mean scores over a group of questions
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Score {
+    pub questions: usize,
+    pub recall: f64,
+    pub mrr: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalReport {
+    pub k: usize,
+    pub overall: Score,
+    pub categories: BTreeMap<String, Score>,
+    pub results: Vec<QuestionResult>,
+}
+
+/// Score a question against its top-k search results
+pub fn score_question(
+    question: &EvalQuestion,
+    results: &[MemoryChunk],
+    k: usize,
+) -> QuestionResult {
+    let top = &results[..results.len().min(k)];
+
+    let missed: Vec<String> = question
+        .expected
+        .iter()
+        .filter(|expected| !top.iter().any(|chunk| expected.matches(chunk)))
+        .map(|expected| match expected.contains {
+            Some(ref text) => format!("{}: {}", expected.file, text),
+            None => expected.file.clone(),
+        })
+        .collect();
+    let found = question.expected.len() - missed.len();
+
+    let first_hit = top
+        .iter()
+        .position(|chunk| question.expected.iter().any(|e| e.matches(chunk)));
+
+    QuestionResult {
+        question: question.question.clone(),
+        category: question
+            .category
+            .clone()
+            .unwrap_or_else(|| UNCATEGORIZED.to_string()),
+        recall: found as f64 / question.expected.len() as f64,
+        reciprocal_rank: first_hit.map_or(0.0, |rank| 1.0 / (rank + 1) as f64),
+        missed,
+    }
+}
+
+impl EvalReport {
+    pub fn new(k: usize, results: Vec<QuestionResult>) -> Self {
+        let mut by_category: BTreeMap<String, Vec<&QuestionResult>> = BTreeMap::new();
+        for result in &results {
+            by_category
+                .entry(result.category.clone())
+                .or_default()
+                .push(result);
+        }
+        let categories = by_category
+            .into_iter()
+            .map(|(category, results)| (category, Score::of(&results)))
+            .collect();
+
+        Self {
+            k,
+            overall: Score::of(&results.iter().collect::<Vec<_>>()),
+            categories,
+            results,
+        }
+    }
+
+    /// Table of scores per category, with the change from `baseline` when
+    /// given
+    pub fn summary(&self, baseline: Option<&EvalReport>) -> String {
+        let mut out = format!(
+            "{:<20} {:>9} {:>10} {:>8}\n",
+            "Category",
+            "Questions",
+            format!("Recall@{}", self.k),
+            "MRR"
+        );
+        let mut row = |name: &str, score: &Score, before: Option<&Score>| {
+            out.push_str(&format!(
+                "{:<20} {:>9} {:>10.3} {:>8.3}",
+                name, score.questions, score.recall, score.mrr
+            ));
+            if let Some(before) = before {
+                out.push_str(&format!(
+                    "  ({:+.3} recall, {:+.3} MRR)",
+                    score.recall - before.recall,
+                    score.mrr - before.mrr
+                ));
+            }
+            out.push('\n');
+        };
+        for (category, score) in &self.categories {
+            row(
+                category,
+                score,
+                baseline.and_then(|b| b.categories.get(category)),
+            );
+        }
+        row("overall", &self.overall, baseline.map(|b| &b.overall));
+        out
+    }
+}
+
+impl Score {
+    fn of(results: &[&QuestionResult]) -> Self {
+        let n = results.len().max(1) as f64;
+        Self {
+            questions: results.len(),
+            recall: results.iter().map(|r| r.recall).sum::<f64>() / n,
+            mrr: results.iter().map(|r| r.reciprocal_rank).sum::<f64>() / n,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(file: &str, content: &str) -> MemoryChunk {
+        MemoryChunk {
+            chunk_id: None,
+            file: file.to_string(),
+            line_start: 1,
+            line_end: 1,
+            content: content.to_string(),
+            score: 1.0,
+            category: None,
+            last_verified: None,
+        }
+    }
+
+    #[test]
+    fn test_score_question() {
+        let set: EvalSet = serde_yaml::from_str(
+            r#"
+questions:
+  - question: What's the garage code?
+    category: household
+    expected:
+      - file: MEMORY.md
+        contains: Garage
+      - file: memory/house.md
+"#,
+        )
+        .unwrap();
+        let question = &set.questions[0];
+
+        let results = vec![
+            chunk("memory/2026-10-01.md", "Went to the garage sale"),
+            chunk("MEMORY.md", "The garage code is 4821"),
+            chunk("memory/house.md", "Roof redone in 2019"),
+        ];
+        let scored = score_question(question, &results, 3);
+        assert_eq!(scored.recall, 1.0);
+        assert_eq!(scored.reciprocal_rank, 0.5);
+
+        // Only the top k count
+        let scored = score_question(question, &results, 2);
+        assert_eq!(scored.recall, 0.5);
+        assert_eq!(scored.missed, vec!["memory/house.md"]);
+
+        let scored = score_question(question, &results[..1], 5);
+        assert_eq!(scored.reciprocal_rank, 0.0);
+        assert_eq!(scored.category, "household");
+    }
+
+    #[test]
+    fn test_report_by_category() {
+        let result = |category: &str, recall, reciprocal_rank| QuestionResult {
+            question: String::new(),
+            category: category.to_string(),
+            recall,
+            reciprocal_rank,
+            missed: Vec::new(),
+        };
+        let report = EvalReport::new(
+            5,
+            vec![
+                result("school", 1.0, 1.0),
+                result("school", 0.0, 0.0),
+                result("finance", 1.0, 0.5),
+            ],
+        );
+        assert_eq!(
+            report.categories["school"],
+            Score {
+                questions: 2,
+                recall: 0.5,
+                mrr: 0.5
+            }
+        );
+        assert_eq!(report.overall.questions, 3);
+        assert!((report.overall.mrr - 0.5).abs() < 1e-9);
+
+        let summary = report.summary(Some(&report));
+        assert!(summary.contains("Recall@5"));
+        assert!(summary.contains("(+0.000 recall, +0.000 MRR)"));
+    }
+}
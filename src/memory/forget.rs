@@ -0,0 +1,398 @@
+//! Forgetting: verified deletion of memory content.
+//!
+//! `forget` removes everything matching a text pattern or a chunk hash
+//! prefix (as shown in `[VERIFIED:abcd1234]` citations): the lines in the
+//! source markdown, the indexed chunks, their FTS rows, vectors, cached
+//! embeddings and verification hashes. The index is then re-checked to make
+//! sure nothing matching is left, and the deletion is recorded in the audit
+//! log without the forgotten content itself.
+
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+use tracing::{info, warn};
+
+use super::embeddings::hash_text;
+use super::frontmatter::split_frontmatter;
+use super::index::{IndexedChunk, MemoryIndex};
+
+/// What to forget
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ForgetTarget {
+    /// Prefix of a chunk's verification or content hash
+    Hash(String),
+    /// Case-insensitive text to remove wherever it appears
+    Pattern(String),
+}
+
+impl ForgetTarget {
+    /// Interpret user input: `[VERIFIED:abcd1234]`, a bare hex hash prefix
+    /// (6+ chars) or otherwise a text pattern
+    pub fn parse(input: &str) -> Self {
+        let trimmed = input.trim();
+        let hash = trimmed
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .trim_start_matches("VERIFIED:");
+
+        if (6..=64).contains(&hash.len()) && hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            ForgetTarget::Hash(hash.to_lowercase())
+        } else {
+            ForgetTarget::Pattern(trimmed.to_string())
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            ForgetTarget::Hash(_) => "hash",
+            ForgetTarget::Pattern(_) => "pattern",
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ForgetReport {
+    /// False when only previewing matches
+    pub applied: bool,
+    pub chunks_removed: usize,
+    pub lines_removed: usize,
+    pub files_edited: Vec<String>,
+    pub files_deleted: Vec<String>,
+    /// Matching chunks still in the index after deletion (should be 0)
+    pub remaining: usize,
+    /// Audit log entry id for the deletion
+    pub audit_id: Option<i64>,
+    /// Matched chunk locations (path:start-end)
+    pub matches: Vec<String>,
+}
+
+impl ForgetReport {
+    pub fn verified(&self) -> bool {
+        self.applied && self.remaining == 0
+    }
+
+    pub fn summary(&self) -> String {
+        if self.matches.is_empty() {
+            return "Nothing in memory matches.".to_string();
+        }
+
+        if !self.applied {
+            let mut out = format!("{} matching chunks:\n", self.matches.len());
+            for location in &self.matches {
+                out.push_str(&format!("  {}\n", location));
+            }
+            return out;
+        }
+
+        let mut out = format!(
+            "Forgot {} chunks ({} lines) from {} files",
+            self.chunks_removed,
+            self.lines_removed,
+            self.files_edited.len() + self.files_deleted.len()
+        );
+        if !self.files_deleted.is_empty() {
+            out.push_str(&format!(
+                ", deleted empty files: {}",
+                self.files_deleted.join(", ")
+            ));
+        }
+        out.push('\n');
+        if self.verified() {
+            out.push_str("Verified: no matching content remains in the index.\n");
+        } else {
+            out.push_str(&format!(
+                "WARNING: {} matching chunks remain in the index.\n",
+                self.remaining
+            ));
+        }
+        out
+    }
+}
+
+/// Find indexed chunks for a target. A hash prefix that matches nothing is
+/// retried as a text pattern (it may just be a hex-looking word).
+pub fn find_matches(
+    index: &MemoryIndex,
+    target: &ForgetTarget,
+) -> Result<(ForgetTarget, Vec<IndexedChunk>)> {
+    match target {
+        ForgetTarget::Hash(prefix) => {
+            let chunks = index.chunks_by_hash_prefix(prefix)?;
+            if !chunks.is_empty() {
+                return Ok((target.clone(), chunks));
+            }
+            let chunks = index.chunks_containing(prefix)?;
+            Ok((ForgetTarget::Pattern(prefix.clone()), chunks))
+        }
+        ForgetTarget::Pattern(pattern) => {
+            if pattern.trim().is_empty() {
+                anyhow::bail!("Refusing to forget an empty pattern");
+            }
+            Ok((target.clone(), index.chunks_containing(pattern)?))
+        }
+    }
+}
+
+/// Delete the matched content from disk and index, verify and audit it
+pub fn forget(
+    workspace: &Path,
+    index: &MemoryIndex,
+    target: &ForgetTarget,
+    chunks: &[IndexedChunk],
+) -> Result<ForgetReport> {
+    let mut report = ForgetReport {
+        applied: true,
+        matches: chunks.iter().map(|c| c.location()).collect(),
+        ..Default::default()
+    };
+
+    // Distinctive removed lines, used to verify hash-targeted deletes
+    let mut removed_text: BTreeSet<String> = BTreeSet::new();
+
+    let mut by_file: BTreeMap<&str, Vec<&IndexedChunk>> = BTreeMap::new();
+    for chunk in chunks {
+        by_file.entry(chunk.path.as_str()).or_default().push(chunk);
+    }
+
+    // Remove the content from the source markdown
+    for (file, file_chunks) in &by_file {
+        let path = workspace.join(file);
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                warn!("Cannot edit {} while forgetting: {}", file, e);
+                continue;
+            }
+        };
+
+        let remove = match target {
+            ForgetTarget::Pattern(pattern) => matching_lines(&content, pattern),
+            ForgetTarget::Hash(_) => {
+                let ranges: Vec<(i32, i32)> = file_chunks
+                    .iter()
+                    .map(|c| (c.line_start, c.line_end))
+                    .collect();
+                range_lines(&content, &ranges)
+            }
+        };
+        if remove.is_empty() {
+            continue;
+        }
+
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if remove.contains(&(i + 1)) && line.len() >= 12 && !line.starts_with('#') {
+                removed_text.insert(line.to_string());
+            }
+        }
+
+        let new_content = remove_lines(&content, &remove);
+        report.lines_removed += remove.len();
+
+        if split_frontmatter(&new_content).1.trim().is_empty() {
+            fs::remove_file(&path)?;
+            report.files_deleted.push(file.to_string());
+        } else {
+            fs::write(&path, new_content)?;
+            report.files_edited.push(file.to_string());
+        }
+    }
+
+    // Purge the index rows, then re-chunk what is left of edited files
+    let ids: Vec<&str> = chunks.iter().map(|c| c.id.as_str()).collect();
+    let hashes: Vec<String> = chunks.iter().map(|c| hash_text(&c.text)).collect();
+    report.chunks_removed = index.purge_chunks(&ids, &hashes)?;
+
+    for file in &report.files_deleted {
+        index.remove_file(file)?;
+    }
+    for file in &report.files_edited {
+        index.index_file(&workspace.join(file), true)?;
+    }
+
+    // Verify nothing matching survived
+    report.remaining = match target {
+        ForgetTarget::Pattern(pattern) => index.chunks_containing(pattern)?.len(),
+        ForgetTarget::Hash(prefix) => {
+            let mut remaining: BTreeSet<String> = index
+                .chunks_by_hash_prefix(prefix)?
+                .into_iter()
+                .map(|c| c.id)
+                .collect();
+            for line in &removed_text {
+                remaining.extend(index.chunks_containing(line)?.into_iter().map(|c| c.id));
+            }
+            remaining.len()
+        }
+    };
+
+    // Past searches for the forgotten text would otherwise keep it in the audit log
+    if let ForgetTarget::Pattern(pattern) = target {
+        index.redact_audit_queries(pattern)?;
+    }
+
+    index.compact()?;
+
+    let target_digest = match target {
+        // Never store the forgotten text itself
+        ForgetTarget::Pattern(pattern) => hash_text(&pattern.to_lowercase())[..16].to_string(),
+        ForgetTarget::Hash(prefix) => prefix.clone(),
+    };
+    let detail = json!({
+        "target_kind": target.kind(),
+        "target": target_digest,
+        "chunks": report.chunks_removed,
+        "lines": report.lines_removed,
+        "files_edited": report.files_edited,
+        "files_deleted": report.files_deleted,
+        "verified": report.verified(),
+    });
+    report.audit_id = Some(index.record_audit("forget", None, &detail)?);
+
+    info!(
+        "Forgot {} chunks from {} files (verified: {})",
+        report.chunks_removed,
+        report.files_edited.len() + report.files_deleted.len(),
+        report.verified()
+    );
+
+    Ok(report)
+}
+
+/// 1-indexed lines containing `pattern` (case-insensitive), excluding the
+/// frontmatter delimiters
+fn matching_lines(content: &str, pattern: &str) -> BTreeSet<usize> {
+    let pattern = pattern.to_lowercase();
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.trim() != "---" && line.to_lowercase().contains(&pattern))
+        .map(|(i, _)| i + 1)
+        .collect()
+}
+
+/// 1-indexed lines covered by `ranges`, excluding the frontmatter block
+fn range_lines(content: &str, ranges: &[(i32, i32)]) -> BTreeSet<usize> {
+    let total = content.lines().count();
+    let body_start = total - split_frontmatter(content).1.lines().count() + 1;
+
+    ranges
+        .iter()
+        .flat_map(|(start, end)| (*start).max(1) as usize..=(*end).max(0) as usize)
+        .filter(|line| *line >= body_start && *line <= total)
+        .collect()
+}
+
+fn remove_lines(content: &str, remove: &BTreeSet<usize>) -> String {
+    let mut out: Vec<&str> = content
+        .lines()
+        .enumerate()
+        .filter(|(i, _)| !remove.contains(&(i + 1)))
+        .map(|(_, line)| line)
+        .collect();
+
+    // Don't leave a trailing run of blank lines behind
+    while out.last().is_some_and(|l| l.trim().is_empty()) {
+        out.pop();
+    }
+
+    let mut result = out.join("\n");
+    if !result.is_empty() && content.ends_with('\n') {
+        result.push('\n');
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::audit::AuditFilter;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(
+            ForgetTarget::parse("[VERIFIED:ABCD1234]"),
+            ForgetTarget::Hash("abcd1234".into())
+        );
+        assert_eq!(
+            ForgetTarget::parse("deadbeef99"),
+            ForgetTarget::Hash("deadbeef99".into())
+        );
+        assert_eq!(
+            ForgetTarget::parse(" door code "),
+            ForgetTarget::Pattern("door code".into())
+        );
+        assert_eq!(
+            ForgetTarget::parse("cafe"),
+            ForgetTarget::Pattern("cafe".into())
+        );
+    }
+
+    #[test]
+    fn test_line_selection() {
+        let content = "---\nnote: door code\n---\n# Home\nDoor code is 4321\nWifi is guest\n";
+        let lines = matching_lines(content, "DOOR CODE");
+        assert_eq!(lines, BTreeSet::from([2, 5]));
+
+        // Frontmatter is never removed for range deletes
+        let lines = range_lines(content, &[(1, 5)]);
+        assert_eq!(lines, BTreeSet::from([4, 5]));
+
+        assert_eq!(
+            remove_lines(content, &BTreeSet::from([5, 6])),
+            "---\nnote: door code\n---\n# Home\n"
+        );
+    }
+
+    #[test]
+    fn test_forget_pattern_is_verified() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let workspace = temp_dir.path();
+        fs::write(
+            workspace.join("notes.md"),
+            "# Notes\nThe garage code is 8812.\nBins go out Thursday.\n",
+        )?;
+        fs::write(workspace.join("secret.md"), "Garage code: 8812\n")?;
+
+        let index = MemoryIndex::new(workspace)?;
+        index.index_file(&workspace.join("notes.md"), false)?;
+        index.index_file(&workspace.join("secret.md"), false)?;
+
+        index.record_search("what is the Garage Code?", &[])?;
+
+        let (target, chunks) = find_matches(&index, &ForgetTarget::parse("garage code"))?;
+        assert_eq!(chunks.len(), 2);
+
+        let report = forget(workspace, &index, &target, &chunks)?;
+        assert!(report.verified());
+        assert_eq!(report.files_deleted, vec!["secret.md"]);
+        assert_eq!(
+            fs::read_to_string(workspace.join("notes.md"))?,
+            "# Notes\nBins go out Thursday.\n"
+        );
+        assert!(index.search("8812", 10)?.is_empty());
+        assert!(!index.search("Thursday", 10)?.is_empty());
+
+        let filter = AuditFilter {
+            action: Some("forget".into()),
+            ..Default::default()
+        };
+        let entries = index.audit_entries(&filter, 10)?;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].action, "forget");
+        assert!(!entries[0].detail.contains("garage"));
+
+        let filter = AuditFilter {
+            action: Some("search".into()),
+            ..Default::default()
+        };
+        let searches = index.audit_entries(&filter, 10)?;
+        assert!(searches[0].detail.contains("[redacted]"));
+
+        Ok(())
+    }
+}
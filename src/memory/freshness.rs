@@ -0,0 +1,246 @@
+//! Freshness: facts the family should confirm again.
+//!
+//! A file's `last_verified` frontmatter says when someone last checked it.
+//! Past `memory.stale_after_days`, or the age set for its category in
+//! `memory.max_age_days` (kids' shoe sizes change faster than the boiler's
+//! model number), its chunks are marked stale in search results, listed in
+//! the health report, and the heartbeat asks the family to re-confirm them.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::frontmatter::parse_timestamp;
+use crate::config::MemoryConfig;
+
+/// Re-confirmation state (relative to the state dir)
+const STATE_FILE: &str = "reconfirm.json";
+
+/// Days before the heartbeat asks about the same file again
+const REASK_DAYS: i64 = 7;
+
+/// Files the heartbeat asks about per day, so it doesn't read out a list
+const MAX_ASKED_PER_DAY: usize = 3;
+
+/// How old a file's `last_verified` may get, by category
+#[derive(Debug, Clone)]
+pub struct Freshness {
+    default_days: u32,
+    /// Lowercased category -> days
+    by_category: BTreeMap<String, u32>,
+}
+
+impl Freshness {
+    pub fn from_config(config: &MemoryConfig) -> Self {
+        Self::new(config.stale_after_days, &config.max_age_days)
+    }
+
+    pub fn new(default_days: u32, by_category: &BTreeMap<String, u32>) -> Self {
+        Self {
+            default_days,
+            by_category: by_category
+                .iter()
+                .map(|(category, days)| (category.trim().to_lowercase(), *days))
+                .collect(),
+        }
+    }
+
+    /// Max age for `category` (comma-separated, as in frontmatter). A file
+    /// in several categories goes stale with the quickest of them.
+    pub fn max_age_days(&self, category: Option<&str>) -> u32 {
+        category
+            .into_iter()
+            .flat_map(|c| c.split(','))
+            .filter_map(|c| self.by_category.get(&c.trim().to_lowercase()))
+            .copied()
+            .min()
+            .unwrap_or(self.default_days)
+    }
+
+    /// Whether a file last verified at `last_verified` is stale at `now`.
+    /// Dates that don't parse aren't.
+    pub fn is_stale(
+        &self,
+        category: Option<&str>,
+        last_verified: &str,
+        now: DateTime<Utc>,
+    ) -> bool {
+        let max_age = Duration::days(self.max_age_days(category) as i64);
+        parse_timestamp(last_verified).is_some_and(|verified| verified < now - max_age)
+    }
+
+    /// Which of `files` (path, category, last_verified) are stale at `now`
+    pub fn stale_files(
+        &self,
+        files: Vec<(String, Option<String>, String)>,
+        now: DateTime<Utc>,
+    ) -> Vec<StaleFile> {
+        files
+            .into_iter()
+            .filter(|(_, category, verified)| self.is_stale(category.as_deref(), verified, now))
+            .map(|(path, category, last_verified)| StaleFile {
+                max_age_days: self.max_age_days(category.as_deref()),
+                path,
+                category,
+                last_verified,
+            })
+            .collect()
+    }
+}
+
+/// A file past its max age
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaleFile {
+    pub path: String,
+    pub category: Option<String>,
+    pub last_verified: String,
+    pub max_age_days: u32,
+}
+
+impl StaleFile {
+    pub fn summary(&self) -> String {
+        let category = match self.category {
+            Some(ref category) => format!(", {}", category),
+            None => String::new(),
+        };
+        format!(
+            "{} (last verified {}, checked every {} days{})",
+            self.path, self.last_verified, self.max_age_days, category
+        )
+    }
+}
+
+/// When the heartbeat last asked about each stale file
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ReconfirmState {
+    asked: BTreeMap<String, NaiveDate>,
+}
+
+/// Picks the stale files the heartbeat asks the family about, a few a day
+/// and each at most once a week
+pub struct ReconfirmLog {
+    path: PathBuf,
+}
+
+impl ReconfirmLog {
+    pub fn open(state_dir: &Path) -> Self {
+        Self {
+            path: state_dir.join(STATE_FILE),
+        }
+    }
+
+    fn load(&self) -> ReconfirmState {
+        fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, state: &ReconfirmState) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string(state)?)?;
+        Ok(())
+    }
+
+    /// Up to a few of `stale` to ask about today, recorded as asked. Files
+    /// no longer stale are forgotten, so they're asked about as soon as
+    /// they go stale again.
+    pub fn take_due(&self, stale: Vec<StaleFile>, today: NaiveDate) -> Result<Vec<StaleFile>> {
+        let mut state = self.load();
+        state
+            .asked
+            .retain(|path, _| stale.iter().any(|f| f.path == *path));
+
+        let asked_today = state.asked.values().filter(|d| **d == today).count();
+        let due: Vec<StaleFile> = stale
+            .into_iter()
+            .filter(|f| match state.asked.get(&f.path) {
+                Some(asked) => today - *asked >= Duration::days(REASK_DAYS),
+                None => true,
+            })
+            .take(MAX_ASKED_PER_DAY.saturating_sub(asked_today))
+            .collect();
+        for file in &due {
+            state.asked.insert(file.path.clone(), today);
+        }
+        self.save(&state)?;
+        Ok(due)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn freshness() -> Freshness {
+        let by_category = BTreeMap::from([("Kids".to_string(), 90), ("contacts".to_string(), 365)]);
+        Freshness::new(180, &by_category)
+    }
+
+    fn file(path: &str) -> StaleFile {
+        StaleFile {
+            path: path.to_string(),
+            category: None,
+            last_verified: "2020-01-01".to_string(),
+            max_age_days: 180,
+        }
+    }
+
+    #[test]
+    fn test_max_age_by_category() {
+        let freshness = freshness();
+        assert_eq!(freshness.max_age_days(None), 180);
+        assert_eq!(freshness.max_age_days(Some("kids")), 90);
+        assert_eq!(freshness.max_age_days(Some("contacts, kids")), 90);
+        assert_eq!(freshness.max_age_days(Some("food")), 180);
+    }
+
+    #[test]
+    fn test_stale_files() {
+        let now = parse_timestamp("2026-10-18").unwrap();
+        let files = vec![
+            ("kids.md".into(), Some("kids".into()), "2026-06-01".into()),
+            ("house.md".into(), None, "2026-06-01".into()),
+            (
+                "contacts.md".into(),
+                Some("contacts".into()),
+                "2025-06-01".into(),
+            ),
+            ("odd.md".into(), None, "sometime".into()),
+        ];
+        let stale = freshness().stale_files(files, now);
+        let paths: Vec<&str> = stale.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["kids.md", "contacts.md"]);
+        assert_eq!(stale[0].max_age_days, 90);
+    }
+
+    #[test]
+    fn test_reconfirm_take_due() -> Result<()> {
+        let dir = TempDir::new()?;
+        let log = ReconfirmLog::open(dir.path());
+        let day = |d: u32| NaiveDate::from_ymd_opt(2026, 10, d).unwrap();
+        let stale: Vec<StaleFile> = ["a.md", "b.md", "c.md", "d.md"]
+            .iter()
+            .map(|p| file(p))
+            .collect();
+
+        // A few a day, then the rest, then nothing until a week has passed
+        assert_eq!(log.take_due(stale.clone(), day(1))?.len(), 3);
+        assert!(log.take_due(stale.clone(), day(1))?.is_empty());
+        let next = log.take_due(stale.clone(), day(2))?;
+        assert_eq!(next, vec![file("d.md")]);
+        assert!(log.take_due(stale.clone(), day(7))?.is_empty());
+        assert_eq!(log.take_due(stale.clone(), day(8))?.len(), 3);
+
+        // Re-confirmed files are forgotten
+        log.take_due(vec![file("d.md")], day(9))?;
+        assert_eq!(log.take_due(stale, day(9))?.len(), 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+//! YAML frontmatter helpers for workspace markdown files.
+//!
+//! Memory files may start with a `---` delimited YAML block carrying
+//! metadata such as `category`, `last_verified` and `sources`. These helpers
+//! split that block from the body without caring about the exact schema.
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde_yaml::{Mapping, Value};
+
+/// Frontmatter keys checked (in order) when looking for a file's timestamp
+const TIMESTAMP_KEYS: &[&str] = &["updated", "last_verified", "modified", "created", "date"];
+
+/// Split markdown content into its YAML frontmatter mapping and body.
+///
+/// Returns `None` for the mapping if there is no frontmatter block or it
+/// fails to parse; the body is then the full content.
+pub fn split_frontmatter(content: &str) -> (Option<Mapping>, &str) {
+    let Some(rest) = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))
+    else {
+        return (None, content);
+    };
+
+    // Find the closing delimiter on its own line
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            let yaml = &rest[..offset];
+            let body = &rest[offset + line.len()..];
+            return match serde_yaml::from_str::<Value>(yaml) {
+                Ok(Value::Mapping(map)) => (Some(map), body),
+                Ok(Value::Null) => (Some(Mapping::new()), body),
+                _ => (None, content),
+            };
+        }
+        offset += line.len();
+    }
+
+    (None, content)
+}
+
+/// Render a frontmatter mapping followed by a markdown body
+pub fn render_frontmatter(map: &Mapping, body: &str) -> Result<String, serde_yaml::Error> {
+    let yaml = serde_yaml::to_string(map)?;
+    Ok(format!("---\n{}---\n{}", yaml, body))
+}
+
+/// Get a frontmatter value as a string (scalars only)
+pub fn get_str(map: &Mapping, key: &str) -> Option<String> {
+    match map.get(key)? {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Parse a frontmatter date value (RFC 3339, `YYYY-MM-DD HH:MM:SS` or `YYYY-MM-DD`)
+pub fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    for fmt in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(value, fmt) {
+            return Some(Utc.from_utc_datetime(&dt));
+        }
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|dt| Utc.from_utc_datetime(&dt))
+}
+
+/// Most recent timestamp found in the frontmatter of `content`, if any
+pub fn frontmatter_timestamp(content: &str) -> Option<DateTime<Utc>> {
+    let (map, _) = split_frontmatter(content);
+    let map = map?;
+    TIMESTAMP_KEYS
+        .iter()
+        .filter_map(|key| get_str(&map, key))
+        .filter_map(|v| parse_timestamp(&v))
+        .max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_frontmatter() {
+        let content = "---\ncategory: facts\nlast_verified: 2026-01-02\n---\n# Title\nbody\n";
+        let (map, body) = split_frontmatter(content);
+        let map = map.unwrap();
+        assert_eq!(get_str(&map, "category").as_deref(), Some("facts"));
+        assert_eq!(body, "# Title\nbody\n");
+    }
+
+    #[test]
+    fn test_split_without_frontmatter() {
+        let content = "# Just markdown\n";
+        let (map, body) = split_frontmatter(content);
+        assert!(map.is_none());
+        assert_eq!(body, content);
+
+        let unterminated = "---\ncategory: facts\n# no closing\n";
+        let (map, body) = split_frontmatter(unterminated);
+        assert!(map.is_none());
+        assert_eq!(body, unterminated);
+    }
+
+    #[test]
+    fn test_render_frontmatter_roundtrip() {
+        let content = "---\ncategory: facts\n---\nbody\n";
+        let (map, body) = split_frontmatter(content);
+        let rendered = render_frontmatter(&map.unwrap(), body).unwrap();
+        assert_eq!(rendered, content);
+    }
+
+    #[test]
+    fn test_frontmatter_timestamp_picks_newest() {
+        let content = "---\ncreated: 2025-01-01\nlast_verified: 2026-03-04T10:00:00Z\n---\nbody";
+        let ts = frontmatter_timestamp(content).unwrap();
+        assert_eq!(ts.to_rfc3339(), "2026-03-04T10:00:00+00:00");
+
+        assert!(frontmatter_timestamp("no frontmatter").is_none());
+    }
+}
@@ -0,0 +1,440 @@
+//! Entity graph over indexed chunks.
+//!
+//! Each chunk is scanned for people, places, appliances and vendors when it
+//! is indexed. Entities in the same chunk are linked, with the verb between
+//! them as the relation when they share a sentence ("Acme Plumbing installed
+//! the water heater"). The links are stored as (entity, relation, other,
+//! chunk hash) rows, so a question can hop from an appliance to the
+//! contractor who serviced it and on to the file with their phone number.
+//!
+//! Extraction is pattern-based and cheap enough to run on every reindex; it
+//! trades recall for needing no model.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashSet;
+
+/// Relation for entities that only share a chunk
+pub const MENTIONED_WITH: &str = "mentioned with";
+
+const APPLIANCES: &[&str] = &[
+    "water heater",
+    "furnace",
+    "boiler",
+    "heat pump",
+    "air conditioner",
+    "dishwasher",
+    "washing machine",
+    "washer",
+    "dryer",
+    "refrigerator",
+    "fridge",
+    "freezer",
+    "oven",
+    "stove",
+    "microwave",
+    "garbage disposal",
+    "sump pump",
+    "water softener",
+    "thermostat",
+    "garage door opener",
+    "generator",
+    "smoke detector",
+    "septic tank",
+    "roof",
+    "gutters",
+    "solar panels",
+    "ev charger",
+    "lawn mower",
+];
+
+const ROOMS: &[&str] = &[
+    "basement",
+    "attic",
+    "garage",
+    "kitchen",
+    "laundry room",
+    "bathroom",
+    "backyard",
+    "crawl space",
+];
+
+const VENDOR_SUFFIXES: &str = "Plumbing|Heating|Cooling|HVAC|Electric|Electrical|Roofing|\
+    Landscaping|Cleaning|Services?|Repairs?|Appliances?|Pest Control|Construction|\
+    Contractors?|Hardware|Motors|Insurance|Bank|Pharmacy|Clinic|Dental|Company|Co|Inc|LLC|Ltd";
+
+const PLACE_SUFFIXES: &str = "Street|St|Avenue|Ave|Road|Rd|Lane|Drive|Park|School|\
+    Elementary|Library|Hospital|Church|Airport|Mall|Market|Beach|Lake|Station";
+
+/// Capitalized words that start sentences or name dates, not people
+const NOT_NAMES: &[&str] = &[
+    "I",
+    "The",
+    "A",
+    "An",
+    "We",
+    "Our",
+    "My",
+    "He",
+    "She",
+    "They",
+    "It",
+    "This",
+    "That",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+    "Christmas",
+    "Thanksgiving",
+    "Easter",
+    "Note",
+    "Notes",
+    "Todo",
+];
+
+/// Verbs kept as relations when they sit between two entities
+const VERBS: &str = "installed|replaced|repaired|fixed|serviced|inspected|cleaned|built|\
+    sold|delivered|maintains|maintained|recommended|owns|bought|teaches|lives";
+
+static APPLIANCE: Lazy<Regex> = Lazy::new(|| vocabulary(APPLIANCES));
+static ROOM: Lazy<Regex> = Lazy::new(|| vocabulary(ROOMS));
+static VENDOR: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(
+        r"\b(?:[A-Z][\w'&-]*\s+){{1,3}}(?:{})\b",
+        VENDOR_SUFFIXES
+    ))
+    .unwrap()
+});
+static PLACE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(
+        r"\b(?:[A-Z][\w'-]*\s+){{1,3}}(?:{})\b",
+        PLACE_SUFFIXES
+    ))
+    .unwrap()
+});
+static PERSON: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b(?:(?:Dr|Mr|Mrs|Ms|Mx)\.?\s+)?[A-Z][a-z]+(?:\s+[A-Z][a-z]+)?\b").unwrap()
+});
+static VERB: Lazy<Regex> = Lazy::new(|| Regex::new(&format!(r"(?i)\b({})\b", VERBS)).unwrap());
+static SENTENCE_END: Lazy<Regex> = Lazy::new(|| Regex::new(r"[.!?](\s|$)|\n").unwrap());
+
+/// Case-insensitive whole-word match of any term, longest first
+fn vocabulary(terms: &[&str]) -> Regex {
+    let mut terms = terms.to_vec();
+    terms.sort_by_key(|t| std::cmp::Reverse(t.len()));
+    Regex::new(&format!(r"(?i)\b(?:{})\b", terms.join("|"))).unwrap()
+}
+
+/// Words dropped from the front of a vendor or place match
+const LEADING: &[&str] = &[
+    "The",
+    "A",
+    "An",
+    "Our",
+    "We",
+    "Called",
+    "Call",
+    "Ask",
+    "From",
+    "At",
+    "By",
+    "Then",
+    "Later",
+    "Today",
+    "Yesterday",
+    "Tomorrow",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntityKind {
+    Person,
+    Place,
+    Appliance,
+    Vendor,
+}
+
+impl EntityKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntityKind::Person => "person",
+            EntityKind::Place => "place",
+            EntityKind::Appliance => "appliance",
+            EntityKind::Vendor => "vendor",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entity {
+    pub name: String,
+    pub kind: EntityKind,
+}
+
+/// Two entities in the same chunk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    pub from: Entity,
+    pub relation: String,
+    pub to: Entity,
+}
+
+/// One edge found by `graph_neighbors`
+#[derive(Debug, Clone)]
+pub struct GraphEdge {
+    pub from: String,
+    pub from_kind: String,
+    pub relation: String,
+    pub to: String,
+    pub to_kind: String,
+    /// path:start-end of the chunk the link came from
+    pub location: String,
+    /// Hops from the entity that was asked about
+    pub depth: usize,
+}
+
+impl GraphEdge {
+    pub fn summary(&self) -> String {
+        format!(
+            "{} ({}) {} {} ({}) [{}]",
+            self.from, self.from_kind, self.relation, self.to, self.to_kind, self.location
+        )
+    }
+}
+
+/// An entity with its byte span in the chunk
+struct Mention {
+    start: usize,
+    end: usize,
+    entity: Entity,
+}
+
+/// Entities in `text` in order of appearance (overlapping matches keep the
+/// longest, then the most specific kind)
+fn mentions(text: &str) -> Vec<Mention> {
+    let mut found = Vec::new();
+    for (regex, kind) in [
+        (&*APPLIANCE, EntityKind::Appliance),
+        (&*VENDOR, EntityKind::Vendor),
+        (&*PLACE, EntityKind::Place),
+        (&*ROOM, EntityKind::Place),
+    ] {
+        for m in regex.find_iter(text) {
+            let (start, name) = strip_leading(m.start(), m.as_str());
+            found.push(Mention {
+                start,
+                end: m.end(),
+                entity: Entity {
+                    name: name.to_string(),
+                    kind,
+                },
+            });
+        }
+    }
+    for m in PERSON.find_iter(text) {
+        let first = m.as_str().split_whitespace().next().unwrap_or_default();
+        if NOT_NAMES.contains(&first) || LEADING.contains(&first) {
+            continue;
+        }
+        // A single capitalized word at the start of a sentence says nothing,
+        // unless it's someone's ("Leo's piano")
+        if starts_sentence(text, m.start())
+            && !m.as_str().contains(' ')
+            && !text[m.end()..].starts_with("'s")
+        {
+            continue;
+        }
+        found.push(Mention {
+            start: m.start(),
+            end: m.end(),
+            entity: Entity {
+                name: m.as_str().to_string(),
+                kind: EntityKind::Person,
+            },
+        });
+    }
+
+    // Earlier first, then longer; a tie keeps the first kind pushed
+    found.sort_by_key(|m| (m.start, std::cmp::Reverse(m.end)));
+    let mut kept: Vec<Mention> = Vec::new();
+    for mention in found {
+        if kept.last().is_some_and(|last| mention.start < last.end) {
+            continue;
+        }
+        kept.push(mention);
+    }
+    kept
+}
+
+fn strip_leading(mut start: usize, mut name: &str) -> (usize, &str) {
+    while let Some((first, rest)) = name.split_once(char::is_whitespace) {
+        if !LEADING.contains(&first) {
+            break;
+        }
+        let rest_trimmed = rest.trim_start();
+        start += name.len() - rest_trimmed.len();
+        name = rest_trimmed;
+    }
+    (start, name)
+}
+
+fn starts_sentence(text: &str, at: usize) -> bool {
+    match text[..at].trim_end_matches([' ', '\t']).chars().last() {
+        None => true,
+        Some(c) => matches!(c, '.' | '!' | '?' | '\n' | '-' | '*' | '#' | '>' | '|'),
+    }
+}
+
+/// Links between the entities of a chunk. Entities in the same sentence get
+/// the verb between them as the relation (passive "installed by" is turned
+/// around); the rest are `MENTIONED_WITH`.
+pub fn links(text: &str) -> Vec<Link> {
+    let found = mentions(text);
+    let sentences: Vec<usize> = found
+        .iter()
+        .map(|m| SENTENCE_END.find_iter(&text[..m.start]).count())
+        .collect();
+
+    let mut links = Vec::new();
+    let mut linked = HashSet::new();
+    for (i, a) in found.iter().enumerate() {
+        let key_a = a.entity.name.to_lowercase();
+        for (j, b) in found.iter().enumerate().skip(i + 1) {
+            let key_b = b.entity.name.to_lowercase();
+            if key_a == key_b {
+                continue;
+            }
+            let pair = if key_a < key_b {
+                (key_a.clone(), key_b.clone())
+            } else {
+                (key_b.clone(), key_a.clone())
+            };
+
+            let between = &text[a.end..b.start];
+            let verb = (sentences[i] == sentences[j])
+                .then(|| VERB.captures(between))
+                .flatten();
+            let link = match verb {
+                Some(caps) => {
+                    let verb = caps.get(1).unwrap();
+                    let relation = verb.as_str().to_lowercase();
+                    let passive = between[verb.end()..].trim_start().starts_with("by ");
+                    let (from, to) = if passive { (b, a) } else { (a, b) };
+                    Link {
+                        from: from.entity.clone(),
+                        relation,
+                        to: to.entity.clone(),
+                    }
+                }
+                None => Link {
+                    from: a.entity.clone(),
+                    relation: MENTIONED_WITH.to_string(),
+                    to: b.entity.clone(),
+                },
+            };
+
+            // A verb link replaces a plain co-mention of the same pair
+            if linked.contains(&pair) {
+                if link.relation != MENTIONED_WITH {
+                    if let Some(existing) = links
+                        .iter_mut()
+                        .find(|l: &&mut Link| l.relation == MENTIONED_WITH && same_pair(l, &link))
+                    {
+                        *existing = link;
+                    }
+                }
+                continue;
+            }
+            linked.insert(pair);
+            links.push(link);
+        }
+    }
+    links
+}
+
+fn same_pair(a: &Link, b: &Link) -> bool {
+    let key = |e: &Entity| e.name.to_lowercase();
+    (key(&a.from) == key(&b.from) && key(&a.to) == key(&b.to))
+        || (key(&a.from) == key(&b.to) && key(&a.to) == key(&b.from))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(text: &str) -> Vec<(String, EntityKind)> {
+        mentions(text)
+            .into_iter()
+            .map(|m| (m.entity.name, m.entity.kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_extract_entities() {
+        let found = names(
+            "We called Acme Plumbing about the water heater in the basement. \
+             Dave Ortiz came out on Monday. Leo's school is Lincoln Elementary.",
+        );
+        let found: Vec<(&str, EntityKind)> = found.iter().map(|(n, k)| (n.as_str(), *k)).collect();
+        assert_eq!(
+            found,
+            vec![
+                ("Acme Plumbing", EntityKind::Vendor),
+                ("water heater", EntityKind::Appliance),
+                ("basement", EntityKind::Place),
+                ("Dave Ortiz", EntityKind::Person),
+                ("Leo", EntityKind::Person),
+                ("Lincoln Elementary", EntityKind::Place),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sentence_start_is_not_a_name() {
+        assert_eq!(
+            names("Remember to check the furnace filter.\nCheck it monthly."),
+            vec![("furnace".to_string(), EntityKind::Appliance)]
+        );
+    }
+
+    #[test]
+    fn test_verb_relations() {
+        let links = super::links("The water heater was installed by Acme Plumbing in 2021.");
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].from.name, "Acme Plumbing");
+        assert_eq!(links[0].relation, "installed");
+        assert_eq!(links[0].to.name, "water heater");
+
+        let links = super::links("Acme Plumbing serviced the furnace. Dave Ortiz was the tech.");
+        let serviced = links.iter().find(|l| l.relation == "serviced").unwrap();
+        assert_eq!(serviced.from.name, "Acme Plumbing");
+        assert_eq!(serviced.to.name, "furnace");
+        assert!(links
+            .iter()
+            .any(|l| l.relation == MENTIONED_WITH && l.to.name == "Dave Ortiz"));
+    }
+
+    #[test]
+    fn test_repeated_pair_keeps_verb() {
+        let links = super::links(
+            "Acme Plumbing quoted the water heater. Later Acme Plumbing replaced the water heater.",
+        );
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].relation, "replaced");
+    }
+}
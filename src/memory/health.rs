@@ -0,0 +1,322 @@
+//! Memory health report (`homegpt memory doctor`).
+//!
+//! Checks the index against the workspace for problems that quietly degrade
+//! recall or citations: chunks that fail hash verification, verification
+//! hashes left behind by deleted chunks, embeddings from another model or
+//! with the wrong dimension, stale `last_verified` frontmatter, and files
+//! that were never indexed or changed since. The heartbeat refreshes the
+//! report under `memory/` on a schedule and surfaces the summary.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde_yaml::{Mapping, Value};
+use std::fs;
+use std::path::Path;
+
+use super::embeddings::hash_text;
+use super::freshness::{Freshness, StaleFile};
+use super::frontmatter::{frontmatter_timestamp, render_frontmatter};
+use super::index::MemoryIndex;
+
+use crate::locale;
+
+/// Workspace-relative path of the written report
+pub const HEALTH_REPORT_PATH: &str = "memory/health-report.md";
+
+/// Items listed per section before the rest are summarised
+const MAX_LISTED: usize = 50;
+
+#[derive(Debug, Clone)]
+pub struct HealthReport {
+    pub generated_at: DateTime<Utc>,
+    pub chunks: usize,
+    pub files_indexed: usize,
+    /// (chunk location, reason)
+    pub unverifiable: Vec<(String, String)>,
+    /// (path, chunk id) of hashes without a chunk
+    pub orphaned_hashes: Vec<(String, String)>,
+    /// (model, dims, chunks) of embeddings that don't match the provider
+    pub dimension_mismatches: Vec<(String, usize, usize)>,
+    /// Provider the embeddings were checked against, as (model, dims)
+    pub expected_embedding: Option<(String, usize)>,
+    /// Files whose `last_verified` is past their category's max age
+    pub stale: Vec<StaleFile>,
+    /// (path, "not indexed" or "changed since indexing")
+    pub unindexed: Vec<(String, String)>,
+}
+
+impl HealthReport {
+    pub fn issue_count(&self) -> usize {
+        self.unverifiable.len()
+            + self.orphaned_hashes.len()
+            + self.dimension_mismatches.len()
+            + self.stale.len()
+            + self.unindexed.len()
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.issue_count() == 0
+    }
+
+    /// One line for the heartbeat and CLI
+    pub fn summary(&self) -> String {
+        if self.is_healthy() {
+            return format!(
+                "Memory health: OK ({} chunks in {} files)",
+                self.chunks, self.files_indexed
+            );
+        }
+
+        let mut parts = Vec::new();
+        let mut push = |count: usize, what: &str| {
+            if count > 0 {
+                parts.push(format!("{} {}", count, what));
+            }
+        };
+        push(self.unverifiable.len(), "unverifiable chunks");
+        push(self.orphaned_hashes.len(), "orphaned hashes");
+        push(
+            self.dimension_mismatches.iter().map(|(_, _, n)| n).sum(),
+            "chunks with mismatched embeddings",
+        );
+        push(self.stale.len(), "stale files");
+        push(self.unindexed.len(), "files not indexed");
+        format!("Memory health: {}", parts.join(", "))
+    }
+
+    /// Markdown report with frontmatter, as written to [`HEALTH_REPORT_PATH`]
+    pub fn to_markdown(&self) -> String {
+        let mut body = String::from("# Memory Health Report\n\n");
+        body.push_str(&format!(
+            "Generated {} for {} chunks in {} files.\n\n{}\n",
+            locale::home_time(&self.generated_at).format("%Y-%m-%d %H:%M %Z"),
+            self.chunks,
+            self.files_indexed,
+            self.summary()
+        ));
+
+        section(
+            &mut body,
+            "Unverifiable chunks",
+            self.unverifiable
+                .iter()
+                .map(|(location, reason)| format!("`{}`: {}", location, reason)),
+            "Run `homegpt memory reindex --force` to re-hash chunks from the files on disk.",
+        );
+        section(
+            &mut body,
+            "Orphaned hashes",
+            self.orphaned_hashes
+                .iter()
+                .map(|(path, id)| format!("`{}` (chunk {})", path, &id[..id.len().min(8)])),
+            "Run `homegpt memory reindex --force` to drop hashes of deleted chunks.",
+        );
+        let expected = match self.expected_embedding {
+            Some((ref model, dims)) => format!("{} ({} dims)", model, dims),
+            None => "no embedding provider".to_string(),
+        };
+        section(
+            &mut body,
+            "Embedding mismatches",
+            self.dimension_mismatches.iter().map(|(model, dims, count)| {
+                format!(
+                    "{} chunks embedded with {} ({} dims), expected {}",
+                    count, model, dims, expected
+                )
+            }),
+            "These chunks are skipped by semantic search. Run `homegpt memory reindex --force` to re-embed.",
+        );
+        section(
+            &mut body,
+            "Stale files",
+            self.stale.iter().map(|f| {
+                format!(
+                    "`{}`: last verified {}, checked every {} days",
+                    f.path, f.last_verified, f.max_age_days
+                )
+            }),
+            "Check these files are still correct and update `last_verified` in their frontmatter.",
+        );
+        section(
+            &mut body,
+            "Files not indexed",
+            self.unindexed
+                .iter()
+                .map(|(path, reason)| format!("`{}`: {}", path, reason)),
+            "Run `homegpt memory reindex`, or check the file watcher is running.",
+        );
+
+        let mut frontmatter = Mapping::new();
+        frontmatter.insert("category".into(), "health".into());
+        frontmatter.insert(
+            "updated".into(),
+            Value::String(self.generated_at.to_rfc3339()),
+        );
+        frontmatter.insert("issues".into(), (self.issue_count() as u64).into());
+        render_frontmatter(&frontmatter, &body).unwrap_or(body)
+    }
+}
+
+fn section(out: &mut String, title: &str, items: impl Iterator<Item = String>, fix: &str) {
+    let items: Vec<String> = items.collect();
+    out.push_str(&format!("\n## {} ({})\n\n", title, items.len()));
+    if items.is_empty() {
+        out.push_str("None.\n");
+        return;
+    }
+    for item in items.iter().take(MAX_LISTED) {
+        out.push_str(&format!("- {}\n", item));
+    }
+    if items.len() > MAX_LISTED {
+        out.push_str(&format!("- ... and {} more\n", items.len() - MAX_LISTED));
+    }
+    out.push_str(&format!("\n{}\n", fix));
+}
+
+/// Run every check against the index and the workspace on disk.
+///
+/// `expected_embedding` is the configured provider's (model, dims); without
+/// one, any stored embedding counts as a mismatch.
+pub fn check(
+    workspace: &Path,
+    index: &MemoryIndex,
+    expected_embedding: Option<(&str, usize)>,
+    freshness: &Freshness,
+) -> Result<HealthReport> {
+    let now = Utc::now();
+    let file_hashes = index.file_hashes()?;
+
+    let dimension_mismatches = index
+        .embedding_dimensions()?
+        .into_iter()
+        .filter(|(model, dims, _)| expected_embedding != Some((model.as_str(), *dims)))
+        .collect();
+
+    let stale = freshness.stale_files(index.last_verified_dates()?, now);
+
+    let mut unindexed = Vec::new();
+    let pattern = format!("{}/**/*.md", workspace.display());
+    for entry in glob::glob(&pattern)
+        .into_iter()
+        .flatten()
+        .filter_map(|r| r.ok())
+        .filter(|p| p.is_file())
+    {
+        let relative = entry
+            .strip_prefix(workspace)
+            .unwrap_or(&entry)
+            .to_string_lossy()
+            .to_string();
+        let content = fs::read_to_string(&entry)?;
+        match file_hashes.get(&relative) {
+            None => unindexed.push((relative, "not indexed".to_string())),
+            Some(hash) if *hash != hash_text(&content) => {
+                unindexed.push((relative, "changed since indexing".to_string()))
+            }
+            Some(_) => {}
+        }
+    }
+
+    Ok(HealthReport {
+        generated_at: now,
+        chunks: index.chunk_count()?,
+        files_indexed: file_hashes.len(),
+        unverifiable: index.verifier().unverifiable_chunks()?,
+        orphaned_hashes: index.verifier().orphaned_hashes()?,
+        dimension_mismatches,
+        expected_embedding: expected_embedding.map(|(m, d)| (m.to_string(), d)),
+        stale,
+        unindexed,
+    })
+}
+
+/// When the report at `path` was last generated, if it exists
+pub fn last_generated(path: &Path) -> Option<DateTime<Utc>> {
+    frontmatter_timestamp(&fs::read_to_string(path).ok()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_health_checks() -> Result<()> {
+        let dir = TempDir::new()?;
+        let workspace = dir.path().join("workspace");
+        fs::create_dir_all(workspace.join("memory"))?;
+        fs::write(
+            workspace.join("MEMORY.md"),
+            "---\nlast_verified: 2020-01-01\n---\nThe boiler was serviced.\n",
+        )?;
+        fs::write(
+            workspace.join("memory/notes.md"),
+            "Spare key is with Sam.\n",
+        )?;
+
+        let index = MemoryIndex::new_with_db_path(&workspace, &dir.path().join("db.sqlite"))?;
+        index.index_file(&workspace.join("MEMORY.md"), false)?;
+        index.index_file(&workspace.join("memory/notes.md"), false)?;
+
+        let freshness = Freshness::new(180, &Default::default());
+        let report = check(&workspace, &index, None, &freshness)?;
+        assert_eq!(report.chunks, 2);
+        let stale: Vec<(&str, &str)> = report
+            .stale
+            .iter()
+            .map(|f| (f.path.as_str(), f.last_verified.as_str()))
+            .collect();
+        assert_eq!(stale, vec![("MEMORY.md", "2020-01-01")]);
+        assert!(report.unverifiable.is_empty() && report.unindexed.is_empty());
+
+        // Unindexed and edited files, a tampered chunk and a foreign embedding
+        fs::write(workspace.join("memory/new.md"), "Not indexed yet.\n")?;
+        fs::write(workspace.join("memory/notes.md"), "Spare key moved.\n")?;
+        let conn = rusqlite::Connection::open(dir.path().join("db.sqlite"))?;
+        conn.execute(
+            "UPDATE chunks SET text = 'The boiler exploded.' WHERE path = 'MEMORY.md'",
+            [],
+        )?;
+        conn.execute(
+            "UPDATE chunks SET embedding = '[0.1,0.2,0.3]', model = 'other'
+             WHERE path = 'memory/notes.md'",
+            [],
+        )?;
+        conn.execute(
+            "INSERT INTO chunk_hashes (chunk_id, path, hash, timestamp, created_at)
+             VALUES ('gone', 'old.md', 'x', 'x', 'x')",
+            [],
+        )?;
+
+        let report = check(&workspace, &index, Some(("model", 384)), &freshness)?;
+        assert_eq!(
+            report.unverifiable,
+            vec![("MEMORY.md:1-4".into(), "content hash mismatch".into())]
+        );
+        assert_eq!(
+            report.orphaned_hashes,
+            vec![("old.md".into(), "gone".into())]
+        );
+        assert_eq!(report.dimension_mismatches, vec![("other".into(), 3, 1)]);
+        assert_eq!(
+            report.unindexed,
+            vec![
+                ("memory/new.md".into(), "not indexed".into()),
+                ("memory/notes.md".into(), "changed since indexing".into()),
+            ]
+        );
+        assert_eq!(report.issue_count(), 6);
+        assert!(report.summary().contains("1 unverifiable chunks"));
+
+        let markdown = report.to_markdown();
+        assert!(markdown.starts_with("---\ncategory: health\n"));
+        assert!(markdown.contains("## Files not indexed (2)"));
+        fs::write(workspace.join(HEALTH_REPORT_PATH), &markdown)?;
+        assert_eq!(
+            last_generated(&workspace.join(HEALTH_REPORT_PATH)).map(|t| t.timestamp()),
+            Some(report.generated_at.timestamp())
+        );
+
+        Ok(())
+    }
+}
@@ -1,30 +1,80 @@
-use anyhow::{anyhow, Result};
+use anyhow::Result;
+use chrono::NaiveDate;
 use rusqlite::{params, Connection};
+use serde_json::json;
 use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
-use super::embeddings::{cosine_similarity, deserialize_embedding, serialize_embedding};
-use super::search::MemoryChunk;
+use super::audit::{current_session, AuditEntry, AuditFilter};
+use super::chunking::{chunk_with, ChunkOptions, ChunkStrategy};
+use super::db::Database;
+use super::documents;
+use super::embeddings::cosine_similarity;
+use super::frontmatter::{get_str, split_frontmatter};
+use super::graph::{self, GraphEdge};
+use super::quantize::{decode_embedding, embedding_dimensions, EmbeddingStorage, RESCORE_FACTOR};
+use super::search::{MemoryChunk, SearchFilter};
+use super::snapshot::{SnapshotInfo, Snapshots};
+use super::timeline::{self, DatedEntry};
 use super::verification::{ChunkVerifier, Provenance};
 
 #[derive(Clone)]
 pub struct MemoryIndex {
-    conn: Arc<Mutex<Connection>>,
+    db: Database,
     workspace: PathBuf,
     db_path: PathBuf,
     /// Whether sqlite-vec extension is loaded for fast vector search
     has_vec_extension: bool,
-    /// Token count per chunk (default: 400)
-    chunk_size: usize,
-    /// Token overlap between chunks (default: 80)
-    chunk_overlap: usize,
+    /// Default chunking (400 tokens, 80 overlap, line windows)
+    chunk_options: ChunkOptions,
+    /// Per-directory chunking overrides as (path prefix, options)
+    chunk_rules: Vec<(String, ChunkOptions)>,
     /// Verification layer for anti-hallucination
     verifier: ChunkVerifier,
+    /// Record searches and indexed writes in the audit log
+    audit: bool,
+    /// Format of stored chunk embeddings
+    embedding_storage: EmbeddingStorage,
+    /// Re-rank quantized search results with exact cached vectors
+    rescore: bool,
+    /// Link the people, places, appliances and vendors in each chunk
+    entity_graph: bool,
+    /// Record the dates written in each chunk
+    time_index: bool,
+}
+
+/// A stored chunk with its embedding (for similarity jobs like consolidation)
+#[derive(Debug, Clone)]
+pub struct EmbeddedChunk {
+    pub chunk_id: String,
+    pub file: String,
+    pub line_start: i32,
+    pub line_end: i32,
+    pub content: String,
+    pub embedding: Vec<f32>,
+    /// Modification time of the source file (unix seconds)
+    pub mtime: i64,
+}
+
+/// A stored chunk located by id (for forget and other targeted edits)
+#[derive(Debug, Clone)]
+pub struct IndexedChunk {
+    pub id: String,
+    pub path: String,
+    pub line_start: i32,
+    pub line_end: i32,
+    pub text: String,
+}
+
+impl IndexedChunk {
+    pub fn location(&self) -> String {
+        format!("{}:{}-{}", self.path, self.line_start, self.line_end)
+    }
 }
 
 #[derive(Debug)]
@@ -48,10 +98,22 @@ impl MemoryIndex {
         // Check if we need to migrate from old schema
         let needs_migration = Self::needs_schema_migration(&conn)?;
         if needs_migration {
+            // An index that can't be snapshotted still has to open
+            if let Err(e) = Snapshots::new(workspace, db_path).take(&conn, "schema migration", true)
+            {
+                warn!("Failed to snapshot memory before schema migration: {}", e);
+            }
             info!("Migrating database schema to OpenClaw-compatible format");
             Self::migrate_to_openclaw_schema(&conn)?;
         }
 
+        // Tables derived from chunk text; files indexed before they existed
+        // are re-chunked on the next reindex
+        let had_derived = conn
+            .prepare("SELECT entity FROM entity_graph LIMIT 0")
+            .is_ok()
+            && conn.prepare("SELECT date FROM chunk_dates LIMIT 0").is_ok();
+
         // Initialize OpenClaw-compatible schema
         conn.execute_batch(
             r#"
@@ -96,13 +158,53 @@ impl MemoryIndex {
                 PRIMARY KEY (provider, model, provider_key, hash)
             );
 
+            -- Append-only log of destructive memory operations (forget, ...)
+            CREATE TABLE IF NOT EXISTS memory_audit (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp TEXT NOT NULL,
+                action TEXT NOT NULL,
+                detail TEXT NOT NULL DEFAULT '{}'
+            );
+
+            -- Entities linked within a chunk (see graph.rs)
+            CREATE TABLE IF NOT EXISTS entity_graph (
+                entity TEXT NOT NULL COLLATE NOCASE,
+                kind TEXT NOT NULL,
+                relation TEXT NOT NULL,
+                other TEXT NOT NULL COLLATE NOCASE,
+                other_kind TEXT NOT NULL,
+                chunk_hash TEXT NOT NULL,
+                path TEXT NOT NULL
+            );
+
+            -- Dates written in chunks (see timeline.rs); `date` is NULL for
+            -- ones written without a year, which recur on `month_day`
+            CREATE TABLE IF NOT EXISTS chunk_dates (
+                date TEXT,
+                month_day TEXT NOT NULL,
+                log INTEGER NOT NULL DEFAULT 0,
+                text TEXT NOT NULL,
+                path TEXT NOT NULL,
+                line INTEGER NOT NULL,
+                chunk_hash TEXT NOT NULL
+            );
+
             -- Indexes
             CREATE INDEX IF NOT EXISTS idx_chunks_path ON chunks(path);
             CREATE INDEX IF NOT EXISTS idx_chunks_source ON chunks(source);
             CREATE INDEX IF NOT EXISTS idx_embedding_cache_updated_at ON embedding_cache(updated_at);
+            CREATE INDEX IF NOT EXISTS idx_entity_graph_entity ON entity_graph(entity);
+            CREATE INDEX IF NOT EXISTS idx_entity_graph_other ON entity_graph(other);
+            CREATE INDEX IF NOT EXISTS idx_entity_graph_path ON entity_graph(path);
+            CREATE INDEX IF NOT EXISTS idx_chunk_dates_date ON chunk_dates(date);
+            CREATE INDEX IF NOT EXISTS idx_chunk_dates_path ON chunk_dates(path);
             "#,
         )?;
 
+        if !had_derived {
+            conn.execute("UPDATE files SET hash = ''", [])?;
+        }
+
         // Create FTS5 table (OpenClaw-compatible with UNINDEXED columns)
         Self::ensure_fts_table(&conn)?;
 
@@ -110,6 +212,25 @@ impl MemoryIndex {
         Self::ensure_column(&conn, "files", "source", "TEXT NOT NULL DEFAULT 'memory'")?;
         Self::ensure_column(&conn, "chunks", "source", "TEXT NOT NULL DEFAULT 'memory'")?;
 
+        // Frontmatter metadata on chunks; files indexed before these columns
+        // existed are re-chunked on the next reindex
+        let added_category = Self::ensure_column(&conn, "chunks", "category", "TEXT")?;
+        let added_verified = Self::ensure_column(&conn, "chunks", "last_verified", "TEXT")?;
+        if added_category || added_verified {
+            conn.execute("UPDATE files SET hash = ''", [])?;
+        }
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_chunks_category ON chunks(category)",
+            [],
+        )?;
+
+        Self::ensure_column(&conn, "memory_audit", "session", "TEXT")?;
+        Self::ensure_column(&conn, "memory_audit", "path", "TEXT")?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_memory_audit_path ON memory_audit(path)",
+            [],
+        )?;
+
         // Try to load sqlite-vec extension for fast vector search
         let has_vec_extension = Self::try_load_sqlite_vec(&conn);
         if has_vec_extension {
@@ -119,30 +240,91 @@ impl MemoryIndex {
             debug!("sqlite-vec extension not available, using in-memory vector search");
         }
 
-        let conn = Arc::new(Mutex::new(conn));
-        let verifier = ChunkVerifier::new(conn.clone())?;
+        // Searches read through a second connection, which needs sqlite-vec too
+        let db = Database::new(conn, |reader| {
+            if has_vec_extension {
+                Self::try_load_sqlite_vec(reader);
+            }
+        })?;
+        let verifier = ChunkVerifier::new(db.clone())?;
 
         Ok(Self {
-            conn,
+            db,
             workspace: workspace.to_path_buf(),
             db_path: db_path.to_path_buf(),
             has_vec_extension,
-            chunk_size: 400,
-            chunk_overlap: 80,
+            chunk_options: ChunkOptions::default(),
+            chunk_rules: Vec::new(),
             verifier,
+            audit: true,
+            embedding_storage: EmbeddingStorage::Float,
+            rescore: true,
+            entity_graph: true,
+            time_index: true,
         })
     }
 
     /// Set chunk size and overlap (builder pattern)
     pub fn with_chunk_config(mut self, chunk_size: usize, chunk_overlap: usize) -> Self {
-        self.chunk_size = chunk_size;
-        self.chunk_overlap = chunk_overlap;
+        self.chunk_options.chunk_size = chunk_size;
+        self.chunk_options.chunk_overlap = chunk_overlap;
+        self
+    }
+
+    /// Set default chunking options and per-directory overrides (builder pattern)
+    pub fn with_chunking(
+        mut self,
+        options: ChunkOptions,
+        rules: Vec<(String, ChunkOptions)>,
+    ) -> Self {
+        self.chunk_options = options;
+        self.chunk_rules = rules;
+        self
+    }
+
+    /// Enable or disable audit logging of searches and indexed writes (builder pattern)
+    pub fn with_audit(mut self, enabled: bool) -> Self {
+        self.audit = enabled;
+        self
+    }
+
+    /// Set how embeddings are stored and whether quantized results are
+    /// rescored with exact vectors (builder pattern)
+    pub fn with_embedding_storage(mut self, storage: EmbeddingStorage, rescore: bool) -> Self {
+        self.embedding_storage = storage;
+        self.rescore = rescore;
+        self
+    }
+
+    /// Enable or disable entity extraction at index time (builder pattern)
+    pub fn with_entity_graph(mut self, enabled: bool) -> Self {
+        self.entity_graph = enabled;
         self
     }
 
+    /// Enable or disable recording the dates in chunks (builder pattern)
+    pub fn with_time_index(mut self, enabled: bool) -> Self {
+        self.time_index = enabled;
+        self
+    }
+
+    /// Chunking options for a workspace-relative path (longest matching prefix wins)
+    fn chunk_options_for(&self, relative_path: &str) -> ChunkOptions {
+        let relative_path = relative_path.replace('\\', "/");
+        self.chunk_rules
+            .iter()
+            .filter(|(prefix, _)| {
+                let prefix = prefix.trim_end_matches('/');
+                relative_path == prefix || relative_path.starts_with(&format!("{}/", prefix))
+            })
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, options)| *options)
+            .unwrap_or(self.chunk_options)
+    }
+
     /// Try to load sqlite-vec extension
     #[allow(unsafe_code)]
-    fn try_load_sqlite_vec(conn: &Connection) -> bool {
+    pub(super) fn try_load_sqlite_vec(conn: &Connection) -> bool {
         // sqlite-vec provides the extension as a loadable module
         // Try to load it - this requires the extension to be installed on the system
 
@@ -194,8 +376,6 @@ impl MemoryIndex {
 
     /// Index a file, returning true if it was updated
     pub fn index_file(&self, path: &Path, force: bool) -> Result<bool> {
-        let content = fs::read_to_string(path)?;
-        let file_hash = hash_content(&content);
         let metadata = fs::metadata(path)?;
         let mtime = metadata
             .modified()?
@@ -209,12 +389,22 @@ impl MemoryIndex {
             .to_string_lossy()
             .to_string();
 
+        // Extracting PDF/DOCX text is slow, so skip unchanged documents by
+        // mtime and size before reading them
+        if !force
+            && documents::needs_extraction(path)
+            && self.stored_mtime_size(&relative_path)? == Some((mtime, size))
+        {
+            debug!("Document unchanged, skipping: {}", relative_path);
+            return Ok(false);
+        }
+
+        let content = documents::read_text(path)?;
+        let file_hash = hash_content(&content);
+
         // Check if file has changed (separate lock scope to avoid deadlock with verifier)
         if !force {
-            let conn = self
-                .conn
-                .lock()
-                .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+            let conn = self.db.read();
 
             let existing: Option<String> = conn
                 .query_row(
@@ -241,39 +431,64 @@ impl MemoryIndex {
             .duration_since(std::time::UNIX_EPOCH)?
             .as_secs() as i64;
 
-        let chunks = chunk_text(&content, self.chunk_size, self.chunk_overlap);
-        let mut chunk_records: Vec<(String, String)> = Vec::new();
-
-        // Insert file record and chunks (lock scope)
-        {
-            let conn = self
-                .conn
-                .lock()
-                .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+        // Spreadsheets always chunk by row groups so each chunk keeps its header
+        let mut options = self.chunk_options_for(&relative_path);
+        if documents::is_table(path) {
+            options.strategy = ChunkStrategy::Table;
+        }
+        let chunks = chunk_with(&content, &options);
+
+        // Frontmatter metadata is stored on every chunk of the file
+        let (frontmatter, _) = split_frontmatter(&content);
+        let category = frontmatter
+            .as_ref()
+            .and_then(|fm| get_str(fm, "category"))
+            .map(|c| c.trim().to_lowercase())
+            .filter(|c| !c.is_empty());
+        let last_verified = frontmatter
+            .as_ref()
+            .and_then(|fm| get_str(fm, "last_verified"))
+            .filter(|v| !v.trim().is_empty());
+        // Files marked `provenance: user-stated` hold facts someone approved
+        // (see memory review), so their chunks carry that provenance; weekly
+        // journal files are marked `provenance: journal`
+        let marked = frontmatter
+            .as_ref()
+            .and_then(|fm| get_str(fm, "provenance"))
+            .map(|p| p.trim().to_lowercase());
+        let journal_week = frontmatter
+            .as_ref()
+            .and_then(|fm| get_str(fm, "week"))
+            .map(|w| w.trim().to_string());
+        // Insert file record and chunks in one transaction, so searches
+        // never see the file half-indexed
+        let chunk_records: Vec<(String, String)> = self.db.write_with(|conn| {
+            let tx = conn.transaction()?;
 
             // Update file record (OpenClaw-compatible columns)
-            conn.execute(
+            tx.execute(
                 "INSERT OR REPLACE INTO files (path, source, hash, mtime, size) VALUES (?1, 'memory', ?2, ?3, ?4)",
                 params![&relative_path, &file_hash, mtime, size],
             )?;
 
             // Delete existing chunks and their FTS entries
-            Self::delete_chunks_for_path(&conn, &relative_path)?;
+            Self::delete_chunks_for_path(&tx, &relative_path)?;
 
             // Create new chunks (OpenClaw-compatible)
+            let mut records = Vec::new();
             for chunk in chunks.iter() {
                 let chunk_id = Uuid::new_v4().to_string();
                 let chunk_hash = hash_content(&chunk.content);
 
-                conn.execute(
-                    r#"INSERT INTO chunks (id, path, source, start_line, end_line, hash, model, text, embedding, updated_at)
-                       VALUES (?1, ?2, 'memory', ?3, ?4, ?5, '', ?6, '', ?7)"#,
-                    params![&chunk_id, &relative_path, chunk.line_start, chunk.line_end, &chunk_hash, &chunk.content, now],
+                tx.execute(
+                    r#"INSERT INTO chunks (id, path, source, start_line, end_line, hash, model, text, embedding, updated_at, category, last_verified)
+                       VALUES (?1, ?2, 'memory', ?3, ?4, ?5, '', ?6, '', ?7, ?8, ?9)"#,
+                    params![&chunk_id, &relative_path, chunk.line_start, chunk.line_end, &chunk_hash, &chunk.content, now, &category, &last_verified],
                 )?;
 
                 // Insert into FTS
                 Self::insert_fts(
-                    &conn,
+                    &tx,
                     &chunk_id,
                     &relative_path,
                     "memory",
@@ -283,24 +498,66 @@ impl MemoryIndex {
                     &chunk.content,
                 )?;
 
-                chunk_records.push((chunk_id, chunk.content.clone()));
+                if self.entity_graph {
+                    Self::insert_links(&tx, &relative_path, &chunk_hash, &chunk.content)?;
+                }
+                if self.time_index {
+                    Self::insert_dates(
+                        &tx,
+                        &relative_path,
+                        &chunk_hash,
+                        chunk.line_start,
+                        &chunk.content,
+                    )?;
+                }
+
+                records.push((chunk_id, chunk.content.clone()));
             }
-        }
-        // conn lock dropped here
+            tx.commit()?;
+            Ok(records)
+        })?;
 
         // Record verification hashes for new chunks (verifier handles its own locking)
-        let provenance = Provenance::FileContent {
-            path: relative_path.clone(),
+        let provenance = match marked.as_deref() {
+            Some("user-stated") => Provenance::UserStated,
+            Some("journal") => Provenance::Journal {
+                week: journal_week.unwrap_or_default(),
+            },
+            _ => Provenance::FileContent {
+                path: relative_path.clone(),
+            },
         };
-        for (chunk_id, chunk_content) in &chunk_records {
-            if let Err(e) =
-                self.verifier
-                    .record_hash(chunk_id, &relative_path, chunk_content, &provenance)
-            {
-                warn!(
-                    "Failed to record verification hash for chunk {}: {}",
-                    chunk_id, e
-                );
+        let mut audit_chunks = Vec::new();
+        for ((chunk_id, chunk_content), chunk) in chunk_records.iter().zip(&chunks) {
+            let citation = match self.verifier.record_hash(
+                chunk_id,
+                &relative_path,
+                chunk_content,
+                &provenance,
+            ) {
+                Ok(hash) => Some(hash[..8].to_string()),
+                Err(e) => {
+                    warn!(
+                        "Failed to record verification hash for chunk {}: {}",
+                        chunk_id, e
+                    );
+                    None
+                }
+            };
+            audit_chunks.push(json!({
+                "lines": format!("{}-{}", chunk.line_start, chunk.line_end),
+                "hash": &hash_content(chunk_content)[..12],
+                "citation": citation,
+            }));
+        }
+
+        if self.audit {
+            let detail = json!({
+                "provenance": provenance.to_string(),
+                "chunks": audit_chunks,
+            });
+            if let Err(e) = self.record_audit("index", Some(&relative_path), &detail) {
+                warn!("Failed to audit index of {}: {}", relative_path, e);
             }
         }
 
@@ -322,36 +579,253 @@ impl MemoryIndex {
 
         // Delete chunks
         conn.execute("DELETE FROM chunks WHERE path = ?1", params![path])?;
+        conn.execute("DELETE FROM entity_graph WHERE path = ?1", params![path])?;
+        conn.execute("DELETE FROM chunk_dates WHERE path = ?1", params![path])?;
         Ok(())
     }
 
-    /// Remove a file and its chunks from the index (for deleted files)
-    pub fn remove_file(&self, relative_path: &str) -> Result<()> {
-        {
-            let conn = self
-                .conn
-                .lock()
-                .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+    /// Store the dates written in a chunk (and a daily log's own date)
+    fn insert_dates(
+        conn: &Connection,
+        path: &str,
+        chunk_hash: &str,
+        line_start: i32,
+        text: &str,
+    ) -> Result<()> {
+        let mut stmt = conn.prepare_cached(
+            r#"INSERT INTO chunk_dates (date, month_day, log, text, path, line, chunk_hash)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"#,
+        )?;
+        let log_date = timeline::log_date(path);
+        if let (Some(date), Some((line, heading))) = (log_date, timeline::log_heading(text)) {
+            stmt.execute(params![
+                date.to_string(),
+                date.format("%m-%d").to_string(),
+                true,
+                heading,
+                path,
+                line_start + line as i32,
+                chunk_hash
+            ])?;
+        }
+        for mention in timeline::dates(text) {
+            // Without a year, a daily log means the nearest one
+            let year = mention.year.or_else(|| {
+                log_date.map(|log| timeline::year_near(mention.month, mention.day, log))
+            });
+            let date = year.and_then(|y| NaiveDate::from_ymd_opt(y, mention.month, mention.day));
+            stmt.execute(params![
+                date.map(|d| d.to_string()),
+                format!("{:02}-{:02}", mention.month, mention.day),
+                false,
+                &mention.text,
+                path,
+                line_start + mention.line as i32,
+                chunk_hash
+            ])?;
+        }
+        Ok(())
+    }
 
-            Self::delete_chunks_for_path(&conn, relative_path)?;
-            conn.execute("DELETE FROM files WHERE path = ?1", params![relative_path])?;
+    /// Dates between `from` and `to` (inclusive) written in indexed chunks,
+    /// with yearly ones at their occurrence in that range, in date order.
+    /// Daily logs' own dates are included with `logs`.
+    pub fn dates_between(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        logs: bool,
+    ) -> Result<Vec<DatedEntry>> {
+        let conn = self.db.read();
+        let mut stmt = conn.prepare(
+            r#"SELECT d.date, d.month_day, d.log, d.text, d.path, d.line
+               FROM chunk_dates d
+               WHERE ((d.date BETWEEN ?1 AND ?2) OR d.date IS NULL) AND (?3 OR d.log = 0)
+                 AND EXISTS (SELECT 1 FROM chunks c WHERE c.hash = d.chunk_hash AND c.path = d.path)"#,
+        )?;
+        let rows = stmt.query_map(params![from.to_string(), to.to_string(), logs], |row| {
+            Ok((
+                row.get::<_, Option<String>>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, bool>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, i32>(5)?,
+            ))
+        })?;
+
+        let mut seen = HashSet::new();
+        let mut entries = Vec::new();
+        for row in rows {
+            let (date, month_day, log, text, path, line) = row?;
+            let yearly = date.is_none();
+            let date = match date {
+                Some(date) => NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok(),
+                None => {
+                    let (month, day) = month_day.split_once('-').unwrap_or_default();
+                    let (month, day) = (month.parse().unwrap_or(0), day.parse().unwrap_or(0));
+                    NaiveDate::from_ymd_opt(2000, month, day)
+                        .map(|_| timeline::next_occurrence(month, day, from))
+                }
+            };
+            let Some(date) = date.filter(|d| *d <= to) else {
+                continue;
+            };
+            // Chunks overlap, so a line can be stored more than once
+            let location = format!("{}:{}", path, line);
+            if !seen.insert((date, location.clone())) {
+                continue;
+            }
+            entries.push(DatedEntry {
+                date,
+                yearly,
+                log,
+                text,
+                location,
+            });
+        }
+        entries.sort_by(|a, b| a.date.cmp(&b.date).then(a.location.cmp(&b.location)));
+        Ok(entries)
+    }
+
+    /// Store the entity links found in a chunk
+    fn insert_links(conn: &Connection, path: &str, chunk_hash: &str, text: &str) -> Result<()> {
+        let mut stmt = conn.prepare_cached(
+            r#"INSERT INTO entity_graph (entity, kind, relation, other, other_kind, chunk_hash, path)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"#,
+        )?;
+        for link in graph::links(text) {
+            stmt.execute(params![
+                &link.from.name,
+                link.from.kind.as_str(),
+                &link.relation,
+                &link.to.name,
+                link.to.kind.as_str(),
+                chunk_hash,
+                path
+            ])?;
+        }
+        Ok(())
+    }
+
+    /// Entities linked to `entity`, following links up to `depth` hops.
+    /// The name is matched case-insensitively, falling back to entities that
+    /// contain it ("acme" finds "Acme Plumbing"). Nearest links come first.
+    pub fn graph_neighbors(
+        &self,
+        entity: &str,
+        depth: usize,
+        limit: usize,
+    ) -> Result<Vec<GraphEdge>> {
+        let conn = self.db.read();
+        let entity = entity.trim();
+
+        let names = |sql: &str, arg: String| -> Result<Vec<String>> {
+            let mut stmt = conn.prepare(sql)?;
+            let rows = stmt.query_map(params![arg], |row| row.get(0))?;
+            Ok(rows.collect::<rusqlite::Result<_>>()?)
+        };
+        let mut frontier = names(
+            "SELECT entity FROM entity_graph WHERE entity = ?1
+             UNION SELECT other FROM entity_graph WHERE other = ?1 LIMIT 1",
+            entity.to_string(),
+        )?;
+        if frontier.is_empty() {
+            frontier = names(
+                "SELECT name FROM (
+                     SELECT entity AS name FROM entity_graph UNION ALL SELECT other FROM entity_graph
+                 ) WHERE instr(lower(name), lower(?1)) > 0
+                 GROUP BY lower(name) ORDER BY count(*) DESC LIMIT 5",
+                entity.to_string(),
+            )?;
+        }
+
+        let mut stmt = conn.prepare(
+            r#"SELECT g.entity, g.kind, g.relation, g.other, g.other_kind,
+                      c.path, c.start_line, c.end_line
+               FROM entity_graph g JOIN chunks c ON c.hash = g.chunk_hash AND c.path = g.path
+               WHERE g.entity = ?1 OR g.other = ?1
+               ORDER BY c.updated_at DESC"#,
+        )?;
+        let mut seen: HashSet<String> = frontier.iter().map(|n| n.to_lowercase()).collect();
+        let mut seen_edges = HashSet::new();
+        let mut edges = Vec::new();
+        for hop in 1..=depth {
+            let mut next = Vec::new();
+            for name in &frontier {
+                let rows = stmt.query_map(params![name], |row| {
+                    Ok(GraphEdge {
+                        from: row.get(0)?,
+                        from_kind: row.get(1)?,
+                        relation: row.get(2)?,
+                        to: row.get(3)?,
+                        to_kind: row.get(4)?,
+                        location: format!(
+                            "{}:{}-{}",
+                            row.get::<_, String>(5)?,
+                            row.get::<_, i32>(6)?,
+                            row.get::<_, i32>(7)?
+                        ),
+                        depth: hop,
+                    })
+                })?;
+                for edge in rows {
+                    let edge = edge?;
+                    let key = (
+                        edge.from.to_lowercase(),
+                        edge.relation.clone(),
+                        edge.to.to_lowercase(),
+                    );
+                    if !seen_edges.insert(key) {
+                        continue;
+                    }
+                    let neighbor = if edge.from.eq_ignore_ascii_case(name) {
+                        &edge.to
+                    } else {
+                        &edge.from
+                    };
+                    if seen.insert(neighbor.to_lowercase()) {
+                        next.push(neighbor.clone());
+                    }
+                    edges.push(edge);
+                    if edges.len() >= limit {
+                        return Ok(edges);
+                    }
+                }
+            }
+            frontier = next;
         }
+        Ok(edges)
+    }
+
+    /// Remove a file and its chunks from the index (for deleted files)
+    pub fn remove_file(&self, relative_path: &str) -> Result<()> {
+        self.db.write_with(|conn| {
+            let tx = conn.transaction()?;
+            Self::delete_chunks_for_path(&tx, relative_path)?;
+            tx.execute("DELETE FROM files WHERE path = ?1", params![relative_path])?;
+            tx.commit()?;
+            Ok(())
+        })?;
 
         // Remove verification hashes (verifier handles its own locking)
         if let Err(e) = self.verifier.remove_hashes_for_path(relative_path) {
             warn!("Failed to remove verification hashes: {}", e);
         }
 
+        if self.audit {
+            if let Err(e) = self.record_audit("delete", Some(relative_path), &json!({})) {
+                warn!("Failed to audit removal of {}: {}", relative_path, e);
+            }
+        }
+
         debug!("Removed deleted file from index: {}", relative_path);
         Ok(())
     }
 
     /// Get all indexed file paths
     pub fn indexed_files(&self) -> Result<Vec<String>> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+        let conn = self.db.read();
 
         let mut stmt = conn.prepare("SELECT path FROM files")?;
         let rows = stmt.query_map([], |row| row.get(0))?;
@@ -363,6 +837,18 @@ impl MemoryIndex {
         Ok(paths)
     }
 
+    /// Modification time and size recorded when a file was last indexed
+    fn stored_mtime_size(&self, relative_path: &str) -> Result<Option<(i64, i64)>> {
+        let conn = self.db.read();
+        Ok(conn
+            .query_row(
+                "SELECT mtime, size FROM files WHERE path = ?1",
+                params![relative_path],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .ok())
+    }
+
     /// Insert into FTS table
     #[allow(clippy::too_many_arguments)]
     fn insert_fts(
@@ -384,27 +870,38 @@ impl MemoryIndex {
 
     /// Search using FTS5
     pub fn search(&self, query: &str, limit: usize) -> Result<Vec<MemoryChunk>> {
+        self.search_filtered(query, &SearchFilter::default(), limit)
+    }
+
+    /// Search using FTS5, restricted to chunks matching `filter`
+    pub fn search_filtered(
+        &self,
+        query: &str,
+        filter: &SearchFilter,
+        limit: usize,
+    ) -> Result<Vec<MemoryChunk>> {
         let fts_query = match build_fts_query(query) {
             Some(q) => q,
             None => return Ok(Vec::new()),
         };
 
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+        let conn = self.db.read();
 
         let mut stmt = conn.prepare(
             r#"
-            SELECT fts.id, fts.path, fts.start_line, fts.end_line, fts.text, bm25(chunks_fts) as score
+            SELECT fts.id, fts.path, fts.start_line, fts.end_line, fts.text, bm25(chunks_fts) as score,
+                   c.category, c.last_verified
             FROM chunks_fts fts
+            LEFT JOIN chunks c ON c.id = fts.id
             WHERE chunks_fts MATCH ?1
+              AND (?3 = '[]' OR c.category IN (SELECT value FROM json_each(?3)))
             ORDER BY score
             LIMIT ?2
             "#,
         )?;
 
-        let rows = stmt.query_map(params![&fts_query, limit as i64], |row| {
+        let categories = filter.categories_json();
+        let rows = stmt.query_map(params![&fts_query, limit as i64, &categories], |row| {
             Ok(MemoryChunk {
                 chunk_id: Some(row.get(0)?),
                 file: row.get(1)?,
@@ -412,6 +909,8 @@ impl MemoryIndex {
                 line_end: row.get(3)?,
                 content: row.get(4)?,
                 score: row.get::<_, f64>(5)?.abs(), // BM25 returns negative scores
+                category: row.get(6)?,
+                last_verified: row.get(7)?,
             })
         })?;
 
@@ -425,14 +924,28 @@ impl MemoryIndex {
 
     /// Get total chunk count
     pub fn chunk_count(&self) -> Result<usize> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+        let conn = self.db.read();
         let count: i64 = conn.query_row("SELECT COUNT(*) FROM chunks", [], |row| row.get(0))?;
         Ok(count as usize)
     }
 
+    /// Chunk counts per frontmatter category, largest first
+    pub fn category_counts(&self) -> Result<Vec<(Option<String>, usize)>> {
+        let conn = self.db.read();
+        let mut stmt = conn.prepare(
+            "SELECT category, COUNT(*) FROM chunks GROUP BY category ORDER BY COUNT(*) DESC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let count: i64 = row.get(1)?;
+            Ok((row.get(0)?, count as usize))
+        })?;
+        let mut counts = Vec::new();
+        for row in rows {
+            counts.push(row?);
+        }
+        Ok(counts)
+    }
+
     /// Get chunk count for a specific file
     pub fn file_chunk_count(&self, path: &Path) -> Result<usize> {
         let relative_path = path
@@ -441,10 +954,7 @@ impl MemoryIndex {
             .to_string_lossy()
             .to_string();
 
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+        let conn = self.db.read();
         let count: i64 = conn.query_row(
             "SELECT COUNT(*) FROM chunks WHERE path = ?1",
             params![&relative_path],
@@ -462,6 +972,12 @@ impl MemoryIndex {
         }
     }
 
+    /// Fold the write-ahead log into the database file (see
+    /// [`Database::checkpoint`])
+    pub fn checkpoint(&self) -> Result<()> {
+        self.db.checkpoint()
+    }
+
     /// Get the database path
     pub fn db_path(&self) -> &Path {
         &self.db_path
@@ -658,22 +1174,26 @@ impl MemoryIndex {
     }
 
     /// Ensure a column exists on a table (for migrations)
-    fn ensure_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    /// Add a column if missing, returning true if it was added
+    fn ensure_column(
+        conn: &Connection,
+        table: &str,
+        column: &str,
+        definition: &str,
+    ) -> Result<bool> {
         let sql = format!("SELECT {} FROM {} LIMIT 0", column, table);
         if conn.prepare(&sql).is_err() {
             let alter = format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition);
             conn.execute(&alter, [])?;
             debug!("Added column {} to table {}", column, table);
+            return Ok(true);
         }
-        Ok(())
+        Ok(false)
     }
 
     /// Get chunks that need embeddings (OpenClaw-compatible: id is TEXT, text column)
     pub fn chunks_without_embeddings(&self, limit: usize) -> Result<Vec<(String, String)>> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+        let conn = self.db.read();
 
         let mut stmt = conn.prepare(
             "SELECT id, text FROM chunks WHERE embedding = '' OR embedding IS NULL LIMIT ?1",
@@ -691,21 +1211,300 @@ impl MemoryIndex {
         Ok(results)
     }
 
+    /// Number of chunks still waiting for an embedding
+    pub fn pending_embedding_count(&self) -> Result<usize> {
+        let conn = self.db.read();
+
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM chunks WHERE embedding = '' OR embedding IS NULL",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    /// Get all chunks embedded with `model`, with their file mtime
+    pub fn embedded_chunks(&self, model: &str) -> Result<Vec<EmbeddedChunk>> {
+        let conn = self.db.read();
+
+        let mut stmt = conn.prepare(
+            r#"SELECT c.id, c.path, c.start_line, c.end_line, c.text, c.embedding, COALESCE(f.mtime, 0)
+               FROM chunks c
+               LEFT JOIN files f ON f.path = c.path
+               WHERE c.embedding != '' AND c.embedding IS NOT NULL AND c.model = ?1
+               ORDER BY c.path, c.start_line"#,
+        )?;
+
+        let rows = stmt.query_map(params![model], |row| {
+            Ok(EmbeddedChunk {
+                chunk_id: row.get(0)?,
+                file: row.get(1)?,
+                line_start: row.get(2)?,
+                line_end: row.get(3)?,
+                content: row.get(4)?,
+                embedding: decode_embedding(row.get_ref(5)?),
+                mtime: row.get(6)?,
+            })
+        })?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row?);
+        }
+        Ok(results)
+    }
+
+    /// Get (chunk id, start line, end line) for every chunk of a file
+    pub fn chunk_ranges(&self, path: &str) -> Result<Vec<(String, i32, i32)>> {
+        let conn = self.db.read();
+
+        let mut stmt = conn.prepare(
+            "SELECT id, start_line, end_line FROM chunks WHERE path = ?1 ORDER BY start_line",
+        )?;
+        let rows = stmt.query_map(params![path], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row?);
+        }
+        Ok(results)
+    }
+
+    /// Find chunks whose verification or content hash starts with `prefix`
+    pub fn chunks_by_hash_prefix(&self, prefix: &str) -> Result<Vec<IndexedChunk>> {
+        let pattern = format!("{}%", prefix.to_lowercase());
+        self.query_chunks(
+            r#"SELECT c.id, c.path, c.start_line, c.end_line, c.text
+               FROM chunks c
+               LEFT JOIN chunk_hashes h ON h.chunk_id = c.id
+               WHERE c.hash LIKE ?1 OR h.hash LIKE ?1
+               ORDER BY c.path, c.start_line"#,
+            &pattern,
+        )
+    }
+
+    /// Find chunks containing `needle` (case-insensitive for ASCII)
+    pub fn chunks_containing(&self, needle: &str) -> Result<Vec<IndexedChunk>> {
+        self.query_chunks(
+            r#"SELECT id, path, start_line, end_line, text FROM chunks
+               WHERE instr(lower(text), lower(?1)) > 0
+               ORDER BY path, start_line"#,
+            needle,
+        )
+    }
+
+    fn query_chunks(&self, sql: &str, param: &str) -> Result<Vec<IndexedChunk>> {
+        let conn = self.db.read();
+
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt.query_map(params![param], |row| {
+            Ok(IndexedChunk {
+                id: row.get(0)?,
+                path: row.get(1)?,
+                line_start: row.get(2)?,
+                line_end: row.get(3)?,
+                text: row.get(4)?,
+            })
+        })?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row?);
+        }
+        Ok(results)
+    }
+
+    /// Delete chunks with their FTS rows, vectors, verification hashes and
+    /// cached embeddings (by content hash). Deleted pages are zeroed.
+    pub fn purge_chunks(&self, ids: &[&str], content_hashes: &[String]) -> Result<usize> {
+        self.db.write_with(|conn| {
+            conn.execute_batch("PRAGMA secure_delete = ON")?;
+            let tx = conn.transaction()?;
+            let mut removed = 0;
+            for id in ids {
+                let _ = tx.execute("DELETE FROM chunks_fts WHERE id = ?1", params![id]);
+                if self.has_vec_extension {
+                    let _ = tx.execute("DELETE FROM chunks_vec WHERE id = ?1", params![id]);
+                }
+                tx.execute("DELETE FROM chunk_hashes WHERE chunk_id = ?1", params![id])?;
+                removed += tx.execute("DELETE FROM chunks WHERE id = ?1", params![id])?;
+            }
+            for hash in content_hashes {
+                tx.execute("DELETE FROM embedding_cache WHERE hash = ?1", params![hash])?;
+                tx.execute(
+                    "DELETE FROM entity_graph WHERE chunk_hash = ?1",
+                    params![hash],
+                )?;
+                tx.execute(
+                    "DELETE FROM chunk_dates WHERE chunk_hash = ?1",
+                    params![hash],
+                )?;
+            }
+            tx.commit()?;
+            conn.execute_batch("PRAGMA secure_delete = OFF")?;
+
+            Ok(removed)
+        })
+    }
+
+    /// Merge FTS segments and vacuum so deleted text is not left in free pages
+    pub fn compact(&self) -> Result<()> {
+        let conn = self.db.write();
+
+        let _ = conn.execute("INSERT INTO chunks_fts(chunks_fts) VALUES('optimize')", []);
+        conn.execute_batch("VACUUM")?;
+        Ok(())
+    }
+
+    /// Take a snapshot of the index and workspace (see [`Snapshots`])
+    pub fn take_snapshot(
+        &self,
+        snapshots: &Snapshots,
+        reason: &str,
+        auto: bool,
+    ) -> Result<SnapshotInfo> {
+        let conn = self.db.write();
+        snapshots.take(&conn, reason, auto)
+    }
+
+    /// Write a consistent copy of the database to `path` (must not exist)
+    pub fn snapshot_to(&self, path: &Path) -> Result<()> {
+        let conn = self.db.write();
+
+        conn.execute("VACUUM INTO ?1", params![path.to_string_lossy()])?;
+        Ok(())
+    }
+
+    /// Append an entry to the audit log, returning its id.
+    ///
+    /// The session comes from the current task (see `audit::with_session`);
+    /// index entries without one inherit the last audited write to the path.
+    pub fn record_audit(
+        &self,
+        action: &str,
+        path: Option<&str>,
+        detail: &serde_json::Value,
+    ) -> Result<i64> {
+        let conn = self.db.write();
+
+        let session = current_session().or_else(|| {
+            let path = path.filter(|_| action == "index")?;
+            conn.query_row(
+                "SELECT session FROM memory_audit WHERE path = ?1 AND action = 'write' ORDER BY id DESC LIMIT 1",
+                params![path],
+                |row| row.get::<_, Option<String>>(0),
+            )
+            .ok()
+            .flatten()
+        });
+
+        conn.execute(
+            "INSERT INTO memory_audit (timestamp, action, session, path, detail) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                chrono::Utc::now().to_rfc3339(),
+                action,
+                session,
+                path,
+                detail.to_string()
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Redact audited search queries containing `pattern` (used by forget).
+    /// Returns the number of entries redacted.
+    pub fn redact_audit_queries(&self, pattern: &str) -> Result<usize> {
+        let conn = self.db.write();
+
+        let redacted = conn.execute(
+            r#"UPDATE memory_audit SET detail = json_set(detail, '$.query', '[redacted]')
+               WHERE action = 'search'
+                 AND instr(lower(json_extract(detail, '$.query')), lower(?1)) > 0"#,
+            params![pattern],
+        )?;
+        Ok(redacted)
+    }
+
+    /// Audit a search and the chunks it returned
+    pub fn record_search(&self, query: &str, results: &[MemoryChunk]) -> Result<()> {
+        if !self.audit {
+            return Ok(());
+        }
+
+        let chunks: Vec<serde_json::Value> = results
+            .iter()
+            .map(|chunk| {
+                let citation = chunk
+                    .chunk_id
+                    .as_ref()
+                    .and_then(|id| self.verifier.get_chunk_info(id).ok().flatten())
+                    .map(|(hash, ..)| hash[..8].to_string());
+                json!({
+                    "path": chunk.file,
+                    "lines": format!("{}-{}", chunk.line_start, chunk.line_end),
+                    "hash": &hash_content(&chunk.content)[..12],
+                    "citation": citation,
+                })
+            })
+            .collect();
+
+        self.record_audit("search", None, &json!({ "query": query, "chunks": chunks }))?;
+        Ok(())
+    }
+
+    /// Most recent audit log entries matching `filter`, newest first
+    pub fn audit_entries(&self, filter: &AuditFilter, limit: usize) -> Result<Vec<AuditEntry>> {
+        let conn = self.db.read();
+
+        let mut stmt = conn.prepare(
+            r#"SELECT id, timestamp, action, session, path, detail FROM memory_audit
+               WHERE (?1 IS NULL OR action = ?1)
+                 AND (?2 IS NULL OR session = ?2)
+                 AND (?3 IS NULL OR path LIKE ?3 || '%')
+                 AND (?4 IS NULL OR instr(detail, lower(?4)) > 0)
+               ORDER BY id DESC LIMIT ?5"#,
+        )?;
+        let rows = stmt.query_map(
+            params![
+                filter.action,
+                filter.session,
+                filter.path,
+                filter.chunk,
+                limit as i64
+            ],
+            |row| {
+                Ok(AuditEntry {
+                    id: row.get(0)?,
+                    timestamp: row.get(1)?,
+                    action: row.get(2)?,
+                    session: row.get(3)?,
+                    path: row.get(4)?,
+                    detail: row.get(5)?,
+                })
+            },
+        )?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row?);
+        }
+        Ok(results)
+    }
+
     /// Store embedding for a chunk (OpenClaw-compatible: id is TEXT, model column)
     pub fn store_embedding(&self, chunk_id: &str, embedding: &[f32], model: &str) -> Result<()> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+        let conn = self.db.write();
 
-        let embedding_json = serialize_embedding(embedding);
+        let stored = self.embedding_storage.encode(embedding);
         let now = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)?
             .as_secs() as i64;
 
         conn.execute(
             "UPDATE chunks SET embedding = ?1, model = ?2, updated_at = ?3 WHERE id = ?4",
-            params![&embedding_json, model, now, chunk_id],
+            params![&stored, model, now, chunk_id],
         )?;
 
         // Also store in vec table if sqlite-vec is available
@@ -731,20 +1530,17 @@ impl MemoryIndex {
         model: &str,
         text_hash: &str,
     ) -> Result<Option<Vec<f32>>> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+        let conn = self.db.read();
 
-        let result: Option<String> = conn
+        let result: Option<Vec<f32>> = conn
             .query_row(
                 "SELECT embedding FROM embedding_cache WHERE provider = ?1 AND model = ?2 AND hash = ?3",
                 params![provider, model, text_hash],
-                |row| row.get(0),
+                |row| Ok(decode_embedding(row.get_ref(0)?)),
             )
             .ok();
 
-        Ok(result.map(|json| deserialize_embedding(&json)))
+        Ok(result)
     }
 
     /// Store embedding in cache
@@ -756,12 +1552,15 @@ impl MemoryIndex {
         text_hash: &str,
         embedding: &[f32],
     ) -> Result<()> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+        let conn = self.db.write();
 
-        let embedding_json = serialize_embedding(embedding);
+        // Exact vectors are kept for rescoring; otherwise the cache is
+        // quantized like the chunks
+        let stored = if self.rescore {
+            EmbeddingStorage::Float.encode(embedding)
+        } else {
+            self.embedding_storage.encode(embedding)
+        };
         let dims = embedding.len() as i32;
         let now = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)?
@@ -770,7 +1569,7 @@ impl MemoryIndex {
         conn.execute(
             "INSERT OR REPLACE INTO embedding_cache (provider, model, provider_key, hash, embedding, dims, updated_at)
              VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            params![provider, model, provider_key, text_hash, &embedding_json, dims, now],
+            params![provider, model, provider_key, text_hash, &stored, dims, now],
         )?;
 
         Ok(())
@@ -789,21 +1588,31 @@ impl MemoryIndex {
         model: &str,
         limit: usize,
     ) -> Result<Vec<MemoryChunk>> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+        self.search_vector_filtered(query_embedding, model, &SearchFilter::default(), limit)
+    }
+
+    /// Vector search restricted to chunks matching `filter`
+    pub fn search_vector_filtered(
+        &self,
+        query_embedding: &[f32],
+        model: &str,
+        filter: &SearchFilter,
+        limit: usize,
+    ) -> Result<Vec<MemoryChunk>> {
+        let conn = self.db.read();
 
         // Try sqlite-vec fast path if available
         if self.has_vec_extension {
-            if let Ok(results) = self.search_vector_fast(&conn, query_embedding, model, limit) {
+            if let Ok(results) =
+                self.search_vector_fast(&conn, query_embedding, model, filter, limit)
+            {
                 return Ok(results);
             }
             warn!("sqlite-vec search failed, falling back to in-memory scan");
         }
 
         // Fallback: in-memory scan (slower but always works)
-        self.search_vector_scan(&conn, query_embedding, model, limit)
+        self.search_vector_scan(&conn, query_embedding, model, filter, limit)
     }
 
     /// Fast vector search using sqlite-vec extension
@@ -812,6 +1621,7 @@ impl MemoryIndex {
         conn: &Connection,
         query_embedding: &[f32],
         model: &str,
+        filter: &SearchFilter,
         limit: usize,
     ) -> Result<Vec<MemoryChunk>> {
         let query_blob = embedding_to_blob(query_embedding);
@@ -820,25 +1630,33 @@ impl MemoryIndex {
         let mut stmt = conn.prepare(
             r#"
             SELECT c.id, c.path, c.start_line, c.end_line, c.text,
-                   1.0 - vec_distance_cosine(v.embedding, ?1) AS score
+                   1.0 - vec_distance_cosine(v.embedding, ?1) AS score,
+                   c.category, c.last_verified
             FROM chunks_vec v
             JOIN chunks c ON c.id = v.id
             WHERE c.model = ?2
+              AND (?4 = '[]' OR c.category IN (SELECT value FROM json_each(?4)))
             ORDER BY score DESC
             LIMIT ?3
             "#,
         )?;
 
-        let rows = stmt.query_map(params![&query_blob, model, limit as i64], |row| {
-            Ok(MemoryChunk {
-                chunk_id: Some(row.get(0)?),
-                file: row.get(1)?,
-                line_start: row.get(2)?,
-                line_end: row.get(3)?,
-                content: row.get(4)?,
-                score: row.get(5)?,
-            })
-        })?;
+        let categories = filter.categories_json();
+        let rows = stmt.query_map(
+            params![&query_blob, model, limit as i64, &categories],
+            |row| {
+                Ok(MemoryChunk {
+                    chunk_id: Some(row.get(0)?),
+                    file: row.get(1)?,
+                    line_start: row.get(2)?,
+                    line_end: row.get(3)?,
+                    content: row.get(4)?,
+                    score: row.get(5)?,
+                    category: row.get(6)?,
+                    last_verified: row.get(7)?,
+                })
+            },
+        )?;
 
         let mut results = Vec::new();
         for row in rows {
@@ -853,33 +1671,47 @@ impl MemoryIndex {
         conn: &Connection,
         query_embedding: &[f32],
         model: &str,
+        filter: &SearchFilter,
         limit: usize,
     ) -> Result<Vec<MemoryChunk>> {
         let mut stmt = conn.prepare(
-            "SELECT id, path, start_line, end_line, text, embedding
+            "SELECT id, path, start_line, end_line, text, embedding, category, last_verified, hash
              FROM chunks
-             WHERE embedding != '' AND embedding IS NOT NULL AND model = ?1",
+             WHERE embedding != '' AND embedding IS NOT NULL AND model = ?1
+               AND (?2 = '[]' OR category IN (SELECT value FROM json_each(?2)))",
         )?;
 
-        let rows = stmt.query_map(params![model], |row| {
+        let categories = filter.categories_json();
+        let rows = stmt.query_map(params![model, &categories], |row| {
             Ok((
-                row.get::<_, String>(0)?,
-                row.get::<_, String>(1)?,
-                row.get::<_, i32>(2)?,
-                row.get::<_, i32>(3)?,
-                row.get::<_, String>(4)?,
-                row.get::<_, String>(5)?,
+                (
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i32>(2)?,
+                    row.get::<_, i32>(3)?,
+                    row.get::<_, String>(4)?,
+                    decode_embedding(row.get_ref(5)?),
+                ),
+                (
+                    row.get::<_, Option<String>>(6)?,
+                    row.get::<_, Option<String>>(7)?,
+                    row.get::<_, String>(8)?,
+                ),
             ))
         })?;
 
         // Compute similarities and sort
         let mut scored: Vec<(f32, MemoryChunk)> = Vec::new();
+        let mut hashes: HashMap<String, String> = HashMap::new();
 
         for row in rows {
-            let (id, path, start_line, end_line, text, embedding_json) = row?;
-            let embedding = deserialize_embedding(&embedding_json);
+            let (
+                (id, path, start_line, end_line, text, embedding),
+                (category, last_verified, hash),
+            ) = row?;
 
             if embedding.len() == query_embedding.len() {
+                hashes.insert(id.clone(), hash);
                 let similarity = cosine_similarity(query_embedding, &embedding);
                 scored.push((
                     similarity,
@@ -890,6 +1722,8 @@ impl MemoryIndex {
                         line_end: end_line,
                         content: text,
                         score: similarity as f64,
+                        category,
+                        last_verified,
                     },
                 ));
             }
@@ -898,6 +1732,30 @@ impl MemoryIndex {
         // Sort by similarity (descending)
         scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
 
+        // Quantized scores are approximate: re-rank the top candidates
+        // against exact vectors from the embedding cache
+        if self.rescore && self.embedding_storage != EmbeddingStorage::Float {
+            scored.truncate(limit * RESCORE_FACTOR);
+            let mut stmt = conn.prepare_cached(
+                "SELECT embedding FROM embedding_cache WHERE model = ?1 AND hash = ?2 LIMIT 1",
+            )?;
+            for (score, chunk) in scored.iter_mut() {
+                let Some(hash) = chunk.chunk_id.as_ref().and_then(|id| hashes.get(id)) else {
+                    continue;
+                };
+                let exact: Option<Vec<f32>> = stmt
+                    .query_row(params![model, hash], |row| {
+                        Ok(decode_embedding(row.get_ref(0)?))
+                    })
+                    .ok();
+                if let Some(exact) = exact.filter(|e| e.len() == query_embedding.len()) {
+                    *score = cosine_similarity(query_embedding, &exact);
+                    chunk.score = *score as f64;
+                }
+            }
+            scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        }
+
         // Take top results
         Ok(scored
             .into_iter()
@@ -907,21 +1765,68 @@ impl MemoryIndex {
     }
 
     /// Hybrid search: combine FTS and vector results
+    #[allow(clippy::too_many_arguments)]
     pub fn search_hybrid(
         &self,
         query: &str,
         query_embedding: Option<&[f32]>,
         model: &str,
+        filter: &SearchFilter,
+        limit: usize,
+        text_weight: f32,
+        vector_weight: f32,
+    ) -> Result<Vec<MemoryChunk>> {
+        self.search_hybrid_multi(
+            &[query],
+            query_embedding,
+            model,
+            filter,
+            limit,
+            text_weight,
+            vector_weight,
+        )
+    }
+
+    /// Hybrid search over several query phrasings (e.g. rewrites of a short query).
+    ///
+    /// Each phrasing runs its own FTS search; a chunk's text score is its best
+    /// rank across phrasings, so expansion improves recall without letting
+    /// chunks that match every variant crowd out the rest.
+    #[allow(clippy::too_many_arguments)]
+    pub fn search_hybrid_multi(
+        &self,
+        queries: &[&str],
+        query_embedding: Option<&[f32]>,
+        model: &str,
+        filter: &SearchFilter,
         limit: usize,
         text_weight: f32,
         vector_weight: f32,
     ) -> Result<Vec<MemoryChunk>> {
-        // Get FTS results
-        let fts_results = self.search(query, limit * 2)?;
+        // Get FTS results, keeping the best rank per chunk across phrasings
+        let mut best_rank: std::collections::HashMap<String, (usize, MemoryChunk)> =
+            std::collections::HashMap::new();
+        for query in queries {
+            for (rank, result) in self
+                .search_filtered(query, filter, limit * 2)?
+                .into_iter()
+                .enumerate()
+            {
+                let key = format!("{}:{}:{}", result.file, result.line_start, result.line_end);
+                match best_rank.get(&key) {
+                    Some((existing, _)) if *existing <= rank => {}
+                    _ => {
+                        best_rank.insert(key, (rank, result));
+                    }
+                }
+            }
+        }
+        let mut fts_results: Vec<(usize, MemoryChunk)> = best_rank.into_values().collect();
+        fts_results.sort_by_key(|(rank, _)| *rank);
 
         // Get vector results if embedding provided
         let vector_results = if let Some(embedding) = query_embedding {
-            self.search_vector(embedding, model, limit * 2)?
+            self.search_vector_filtered(embedding, model, filter, limit * 2)?
         } else {
             Vec::new()
         };
@@ -932,7 +1837,7 @@ impl MemoryIndex {
 
         // Add FTS results using rank-based scoring (OpenClaw-compatible)
         // BM25 results are already ordered by relevance (best first)
-        for (rank, result) in fts_results.into_iter().enumerate() {
+        for (rank, result) in fts_results {
             let key = format!("{}:{}:{}", result.file, result.line_start, result.line_end);
             let rank_score = 1.0 / (1.0 + rank as f32); // rank 0 → 1.0, rank 1 → 0.5, rank 9 → 0.1
             let weighted_score = rank_score * text_weight;
@@ -968,10 +1873,7 @@ impl MemoryIndex {
 
     /// Count chunks with embeddings (OpenClaw-compatible: model column)
     pub fn embedded_chunk_count(&self, model: &str) -> Result<usize> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+        let conn = self.db.read();
 
         let count: i64 = conn.query_row(
             "SELECT COUNT(*) FROM chunks WHERE embedding != '' AND embedding IS NOT NULL AND model = ?1",
@@ -981,6 +1883,54 @@ impl MemoryIndex {
 
         Ok(count as usize)
     }
+
+    /// Stored embeddings grouped by model and dimension: (model, dims, chunks)
+    pub fn embedding_dimensions(&self) -> Result<Vec<(String, usize, usize)>> {
+        let conn = self.db.read();
+
+        let mut stmt = conn.prepare(
+            "SELECT model, embedding FROM chunks
+             WHERE embedding != '' AND embedding IS NOT NULL",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                embedding_dimensions(row.get_ref(1)?),
+            ))
+        })?;
+
+        let mut counts: std::collections::BTreeMap<(String, usize), usize> =
+            std::collections::BTreeMap::new();
+        for row in rows {
+            *counts.entry(row?).or_default() += 1;
+        }
+        Ok(counts
+            .into_iter()
+            .map(|((model, dims), count)| (model, dims, count))
+            .collect())
+    }
+
+    /// `category` and `last_verified` frontmatter of each indexed file that
+    /// has `last_verified`, as (path, category, last_verified)
+    pub fn last_verified_dates(&self) -> Result<Vec<(String, Option<String>, String)>> {
+        let conn = self.db.read();
+
+        let mut stmt = conn.prepare(
+            "SELECT DISTINCT path, category, last_verified FROM chunks
+             WHERE last_verified IS NOT NULL ORDER BY path",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Content hash of every indexed file, by workspace-relative path
+    pub fn file_hashes(&self) -> Result<HashMap<String, String>> {
+        let conn = self.db.read();
+
+        let mut stmt = conn.prepare("SELECT path, hash FROM files")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
 }
 
 fn hash_content(content: &str) -> String {
@@ -1020,101 +1970,344 @@ fn build_fts_query(raw: &str) -> Option<String> {
     Some(quoted.join(" AND "))
 }
 
-struct ChunkInfo {
-    line_start: i32,
-    line_end: i32,
-    content: String,
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_memory_index() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let workspace = temp_dir.path();
+
+        // Create a test file
+        let test_file = workspace.join("test.md");
+        fs::write(
+            &test_file,
+            "# Test\n\nThis is a test document.\n\nWith multiple lines.",
+        )?;
 
-fn chunk_text(text: &str, target_tokens: usize, overlap_tokens: usize) -> Vec<ChunkInfo> {
-    let lines: Vec<&str> = text.lines().collect();
-    let mut chunks = Vec::new();
+        let index = MemoryIndex::new(workspace)?;
+        index.index_file(&test_file, false)?;
 
-    if lines.is_empty() {
-        return chunks;
+        assert!(index.chunk_count()? > 0);
+
+        let results = index.search("test document", 10)?;
+        assert!(!results.is_empty());
+
+        Ok(())
     }
 
-    // Rough estimate: 4 chars per token
-    let target_chars = target_tokens * 4;
-    let overlap_chars = overlap_tokens * 4;
+    #[test]
+    fn test_category_filter() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let workspace = temp_dir.path();
 
-    let mut start_line = 0;
-    let mut current_chars = 0;
-    let mut chunk_lines = Vec::new();
+        let food = workspace.join("food.md");
+        fs::write(
+            &food,
+            "---\ncategory: Food\nlast_verified: 2026-01-05\n---\n# Meals\nTaco night on Tuesday.",
+        )?;
+        let school = workspace.join("school.md");
+        fs::write(
+            &school,
+            "---\ncategory: school\n---\n# Plan\nTuesday is math day.",
+        )?;
 
-    for (i, line) in lines.iter().enumerate() {
-        chunk_lines.push(*line);
-        current_chars += line.len() + 1; // +1 for newline
+        let index = MemoryIndex::new(workspace)?;
+        index.index_file(&food, false)?;
+        index.index_file(&school, false)?;
 
-        if current_chars >= target_chars || i == lines.len() - 1 {
-            // Create chunk
-            chunks.push(ChunkInfo {
-                line_start: (start_line + 1) as i32,
-                line_end: (i + 1) as i32,
-                content: chunk_lines.join("\n"),
-            });
+        assert_eq!(index.search("Tuesday", 10)?.len(), 2);
 
-            // Calculate overlap for next chunk
-            let mut overlap_len = 0;
-            let mut overlap_start = chunk_lines.len();
+        let (query, filter) = SearchFilter::parse("Tuesday category:food");
+        let results = index.search_filtered(&query, &filter, 10)?;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].file, "food.md");
+        assert_eq!(results[0].category.as_deref(), Some("food"));
+        assert_eq!(results[0].last_verified.as_deref(), Some("2026-01-05"));
 
-            for (j, line) in chunk_lines.iter().enumerate().rev() {
-                overlap_len += line.len() + 1;
-                if overlap_len >= overlap_chars {
-                    overlap_start = j;
-                    break;
-                }
-            }
+        let counts = index.category_counts()?;
+        assert_eq!(counts.len(), 2);
+        assert!(counts.contains(&(Some("school".to_string()), 1)));
 
-            // Prepare for next chunk
-            if overlap_start < chunk_lines.len() {
-                start_line += overlap_start;
-                chunk_lines = chunk_lines[overlap_start..].to_vec();
-                current_chars = chunk_lines.iter().map(|l| l.len() + 1).sum();
-            } else {
-                start_line = i + 1;
-                chunk_lines.clear();
-                current_chars = 0;
-            }
-        }
+        Ok(())
     }
 
-    chunks
-}
+    #[test]
+    fn test_frontmatter_provenance() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let workspace = temp_dir.path();
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::TempDir;
+        let journal = workspace.join("2026-W42.md");
+        fs::write(
+            &journal,
+            "---\nprovenance: journal\nweek: 2026-W42\n---\n# Week\nLeo started piano.",
+        )?;
+        let notes = workspace.join("notes.md");
+        fs::write(&notes, "# Notes\nMia started swimming.")?;
+
+        let index = MemoryIndex::new(workspace)?;
+        index.index_file(&journal, false)?;
+        index.index_file(&notes, false)?;
+
+        let provenance = |query: &str| -> Result<Provenance> {
+            let results = index.search(query, 1)?;
+            let chunk_id = results[0].chunk_id.clone().unwrap();
+            Ok(index.verifier().get_chunk_info(&chunk_id)?.unwrap().1)
+        };
+        assert_eq!(
+            provenance("piano")?,
+            Provenance::Journal {
+                week: "2026-W42".to_string()
+            }
+        );
+        assert_eq!(
+            provenance("swimming")?,
+            Provenance::FileContent {
+                path: "notes.md".to_string()
+            }
+        );
+
+        Ok(())
+    }
 
     #[test]
-    fn test_chunk_text() {
-        let text = "Line 1\nLine 2\nLine 3\nLine 4\nLine 5";
-        let chunks = chunk_text(text, 10, 2); // Small chunks for testing
+    fn test_graph_neighbors_across_files() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let workspace = temp_dir.path();
+
+        let house = workspace.join("house.md");
+        fs::write(
+            &house,
+            "# House\n\nThe water heater was installed by Acme Plumbing in 2021.\n",
+        )?;
+        let contacts = workspace.join("contacts.md");
+        fs::write(
+            &contacts,
+            "# Contacts\n\nOur plumber is Dave Ortiz at Acme Plumbing, 555-0142.\n",
+        )?;
 
-        assert!(!chunks.is_empty());
-        assert_eq!(chunks[0].line_start, 1);
+        let index = MemoryIndex::new(workspace)?;
+        index.index_file(&house, false)?;
+        index.index_file(&contacts, false)?;
+
+        let edges = index.graph_neighbors("Water Heater", 2, 10)?;
+        assert_eq!(edges.len(), 2);
+        assert_eq!(edges[0].from, "Acme Plumbing");
+        assert_eq!(edges[0].relation, "installed");
+        assert_eq!(edges[0].depth, 1);
+        assert_eq!(edges[1].from, "Dave Ortiz");
+        assert_eq!(edges[1].depth, 2);
+        assert!(edges[1].location.starts_with("contacts.md:"));
+
+        // A partial name finds the entity that contains it
+        assert_eq!(index.graph_neighbors("acme", 1, 10)?.len(), 2);
+
+        // Reindexing a file replaces its links
+        fs::write(&contacts, "# Contacts\n\nNothing here yet.\n")?;
+        index.index_file(&contacts, false)?;
+        assert_eq!(index.graph_neighbors("water heater", 2, 10)?.len(), 1);
+
+        Ok(())
     }
 
     #[test]
-    fn test_memory_index() -> Result<()> {
+    fn test_dates_between() -> Result<()> {
         let temp_dir = TempDir::new()?;
         let workspace = temp_dir.path();
+        fs::create_dir_all(workspace.join("memory"))?;
 
-        // Create a test file
-        let test_file = workspace.join("test.md");
+        let family = workspace.join("memory/family.md");
         fs::write(
-            &test_file,
-            "# Test\n\nThis is a test document.\n\nWith multiple lines.",
+            &family,
+            "# Family\n\n- Mia's birthday: March 3\n- Dishwasher warranty expires 2027-06-30\n",
+        )?;
+        let log = workspace.join("memory/2027-02-27.md");
+        fs::write(
+            &log,
+            "## Plumber visit\n\nBooked the furnace service for Mar 2.\n",
         )?;
 
         let index = MemoryIndex::new(workspace)?;
-        index.index_file(&test_file, false)?;
+        index.index_file(&family, false)?;
+        index.index_file(&log, false)?;
+
+        let from = NaiveDate::from_ymd_opt(2027, 2, 25).unwrap();
+        let to = NaiveDate::from_ymd_opt(2027, 3, 5).unwrap();
+        let entries = index.dates_between(from, to, false)?;
+        let found: Vec<(String, &str)> = entries
+            .iter()
+            .map(|e| (e.date.to_string(), e.location.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("2027-03-02".to_string(), "memory/2027-02-27.md:3"),
+                ("2027-03-03".to_string(), "memory/family.md:3"),
+            ]
+        );
+        assert!(!entries[0].yearly);
+        assert!(entries[1].yearly);
 
-        assert!(index.chunk_count()? > 0);
+        // Daily logs are filed under their own date too
+        let with_logs = index.dates_between(from, to, true)?;
+        assert_eq!(with_logs.len(), 3);
+        assert!(with_logs[0].log);
+        assert_eq!(with_logs[0].text, "Plumber visit");
 
-        let results = index.search("test document", 10)?;
-        assert!(!results.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_index_docx_document() -> Result<()> {
+        use std::io::Write;
+
+        let temp_dir = TempDir::new()?;
+        let workspace = temp_dir.path();
+        let documents = workspace.join("memory/documents");
+        fs::create_dir_all(&documents)?;
+
+        let path = documents.join("furnace.docx");
+        let mut docx = zip::ZipWriter::new(fs::File::create(&path)?);
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        docx.start_file("word/document.xml", options)?;
+        docx.write_all(
+            b"<w:document><w:body><w:p><w:r><w:t>Furnace warranty expires in 2027.</w:t>\
+              </w:r></w:p></w:body></w:document>",
+        )?;
+        docx.finish()?;
+
+        let index = MemoryIndex::new(workspace)?;
+        assert!(index.index_file(&path, false)?);
+        // Unchanged documents are skipped without extracting them again
+        assert!(!index.index_file(&path, false)?);
+
+        let results = index.search("furnace warranty", 5)?;
+        assert_eq!(results[0].file, "memory/documents/furnace.docx");
+        Ok(())
+    }
+
+    #[test]
+    fn test_hybrid_multi_merges_phrasings() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let workspace = temp_dir.path();
+
+        let file = workspace.join("calendar.md");
+        fs::write(&file, "Ella has an orthodontist appointment on March 3.")?;
+
+        let index = MemoryIndex::new(workspace)?;
+        index.index_file(&file, false)?;
+
+        let filter = SearchFilter::default();
+        assert!(index
+            .search_filtered("ella dentist", &filter, 5)?
+            .is_empty());
+
+        let results = index.search_hybrid_multi(
+            &["ella dentist", "ella orthodontist appointment"],
+            None,
+            "",
+            &filter,
+            5,
+            1.0,
+            0.0,
+        )?;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].file, "calendar.md");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_audit_traces_chunk_to_session() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let workspace = temp_dir.path();
+        let notes = workspace.join("notes.md");
+        fs::write(&notes, "Ella's dentist is on March 3.")?;
+
+        let index = MemoryIndex::new(workspace)?;
+        crate::memory::audit::with_session("s1", async {
+            index.record_audit("write", Some("notes.md"), &json!({ "tool": "write_file" }))
+        })
+        .await?;
+
+        // Indexed later (e.g. by the watcher): inherits the writing session
+        index.index_file(&notes, false)?;
+        let results = index.search("dentist", 5)?;
+        index.record_search("dentist", &results)?;
+
+        let indexed = index.audit_entries(
+            &AuditFilter {
+                action: Some("index".into()),
+                ..Default::default()
+            },
+            10,
+        )?;
+        assert_eq!(indexed[0].session.as_deref(), Some("s1"));
+
+        let detail: serde_json::Value = serde_json::from_str(&indexed[0].detail)?;
+        let citation = detail["chunks"][0]["citation"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let trace = index.audit_entries(
+            &AuditFilter {
+                chunk: Some(citation),
+                ..Default::default()
+            },
+            10,
+        )?;
+        let actions: Vec<&str> = trace.iter().map(|e| e.action.as_str()).collect();
+        assert_eq!(actions, vec!["search", "index"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_quantized_rescore() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let workspace = temp_dir.path();
+        fs::write(workspace.join("a.md"), "Bins go out on Thursday.")?;
+        fs::write(
+            workspace.join("b.md"),
+            "Recycling is collected fortnightly.",
+        )?;
+
+        let mut index =
+            MemoryIndex::new(workspace)?.with_embedding_storage(EmbeddingStorage::Binary, true);
+        index.index_file(&workspace.join("a.md"), false)?;
+        index.index_file(&workspace.join("b.md"), false)?;
+
+        // Sign bits rank b first; the exact vectors rank a first
+        let normalize = crate::memory::embeddings::normalize_embedding;
+        let vectors = [
+            ("a.md", normalize(vec![0.9, 0.1, -0.05])),
+            ("b.md", normalize(vec![0.2, 0.6, 0.7])),
+        ];
+        let chunks: Vec<(String, String)> = {
+            let conn = index.db.read();
+            let mut stmt = conn.prepare("SELECT id, hash FROM chunks ORDER BY path")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+        for ((id, hash), (_, embedding)) in chunks.iter().zip(vectors.iter()) {
+            index.store_embedding(id, embedding, "test")?;
+            index.cache_embedding("test", "test", "", hash, embedding)?;
+        }
+
+        let query = normalize(vec![1.0, 0.5, 0.5]);
+        let top = |index: &MemoryIndex| -> Result<String> {
+            let conn = index.db.read();
+            let results =
+                index.search_vector_scan(&conn, &query, "test", &SearchFilter::default(), 1)?;
+            Ok(results[0].file.clone())
+        };
+        assert_eq!(top(&index)?, "a.md");
+
+        index.rescore = false;
+        assert_eq!(top(&index)?, "b.md");
 
         Ok(())
     }
@@ -0,0 +1,347 @@
+//! Background indexing queue
+//!
+//! Reindexing and embedding run on a dedicated thread, one file or one
+//! embedding page per step, so the loop that asked for them stays free for
+//! chat. Memory work is prioritized as chat turns, then searches, then
+//! indexing: before each step the queue waits while a chat turn or a search
+//! is in progress (see [`MemoryManager::foreground`]). Progress is reported
+//! after every step, and [`IndexQueue::cancel`] stops the running job at its
+//! next step.
+
+use anyhow::Result;
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+use tracing::{debug, info};
+
+use super::MemoryManager;
+
+/// How often a paused job checks whether it can continue
+const PAUSE_POLL: Duration = Duration::from_millis(100);
+
+/// Who memory work is for, lowest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    /// Background reindexing and embedding
+    Indexing,
+    /// Memory searches
+    Retrieval,
+    /// A chat turn in progress
+    Chat,
+}
+
+/// Foreground work in progress, counted per priority
+#[derive(Debug, Default)]
+pub(super) struct Activity {
+    active: [AtomicUsize; 3],
+}
+
+impl Activity {
+    /// Whether anything above `priority` is in progress
+    pub(super) fn busy_above(&self, priority: Priority) -> bool {
+        self.active[priority as usize + 1..]
+            .iter()
+            .any(|count| count.load(Ordering::SeqCst) > 0)
+    }
+}
+
+/// Marks foreground work in progress until dropped
+pub struct ForegroundGuard {
+    activity: Arc<Activity>,
+    priority: Priority,
+}
+
+impl ForegroundGuard {
+    pub(super) fn new(activity: Arc<Activity>, priority: Priority) -> Self {
+        activity.active[priority as usize].fetch_add(1, Ordering::SeqCst);
+        Self { activity, priority }
+    }
+}
+
+impl Drop for ForegroundGuard {
+    fn drop(&mut self) {
+        self.activity.active[self.priority as usize].fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Which part of a reindex is running
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexPhase {
+    /// Chunking files into the index
+    Files,
+    /// Embedding chunks for semantic search
+    Embeddings,
+}
+
+/// Progress of the job the queue is running
+#[derive(Debug, Clone, PartialEq)]
+pub enum IndexProgress {
+    Running {
+        phase: IndexPhase,
+        done: usize,
+        total: usize,
+        /// Waiting for a chat turn or search to finish
+        paused: bool,
+    },
+    Finished {
+        files_updated: usize,
+        chunks_indexed: usize,
+        chunks_embedded: usize,
+    },
+    Cancelled,
+    Failed(String),
+}
+
+/// Handle to the indexing thread. Dropping it stops the thread after its
+/// current step.
+pub struct IndexQueue {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    state: Mutex<QueueState>,
+    wake: Condvar,
+}
+
+#[derive(Default)]
+struct QueueState {
+    /// Waiting reindex, `Some(force)`. A request made while one is waiting
+    /// is merged into it.
+    pending: Option<bool>,
+    /// Bumped by `cancel`; a job stops once this differs from its own
+    generation: u64,
+    shutdown: bool,
+}
+
+impl IndexQueue {
+    /// Start the indexing thread for `memory`, calling `on_progress` from
+    /// that thread as jobs run
+    pub fn start(
+        memory: MemoryManager,
+        on_progress: impl Fn(IndexProgress) + Send + Sync + 'static,
+    ) -> Result<Self> {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(QueueState::default()),
+            wake: Condvar::new(),
+        });
+        let runner = Runner {
+            shared: shared.clone(),
+            memory,
+            on_progress: Box::new(on_progress),
+        };
+        std::thread::Builder::new()
+            .name("index-queue".to_string())
+            .spawn(move || runner.run())?;
+        Ok(Self { shared })
+    }
+
+    /// Queue a reindex of the workspace followed by embedding new chunks
+    pub fn reindex(&self, force: bool) {
+        let mut state = self.shared.lock();
+        state.pending = Some(state.pending.unwrap_or(false) || force);
+        self.shared.wake.notify_all();
+    }
+
+    /// Drop the waiting job and stop the running one at its next step
+    pub fn cancel(&self) {
+        let mut state = self.shared.lock();
+        state.pending = None;
+        state.generation += 1;
+    }
+}
+
+impl Drop for IndexQueue {
+    fn drop(&mut self) {
+        self.shared.lock().shutdown = true;
+        self.shared.wake.notify_all();
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> std::sync::MutexGuard<'_, QueueState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Wait for the next job, returning it with the generation it runs
+    /// under; `None` once the queue is dropped
+    fn next(&self) -> Option<(bool, u64)> {
+        let mut state = self.lock();
+        loop {
+            if state.shutdown {
+                return None;
+            }
+            if let Some(force) = state.pending.take() {
+                return Some((force, state.generation));
+            }
+            state = self
+                .wake
+                .wait(state)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+    }
+
+    fn is_cancelled(&self, generation: u64) -> bool {
+        let state = self.lock();
+        state.shutdown || state.generation != generation
+    }
+}
+
+struct Runner {
+    shared: Arc<Shared>,
+    memory: MemoryManager,
+    on_progress: Box<dyn Fn(IndexProgress) + Send + Sync>,
+}
+
+impl Runner {
+    fn run(self) {
+        let rt = match tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+        {
+            Ok(rt) => rt,
+            Err(e) => {
+                (self.on_progress)(IndexProgress::Failed(format!(
+                    "Failed to start indexing: {}",
+                    e
+                )));
+                return;
+            }
+        };
+
+        while let Some((force, generation)) = self.shared.next() {
+            let progress = match self.reindex(&rt, force, generation) {
+                Ok(Some(progress)) => progress,
+                Ok(None) => {
+                    info!("Background reindex cancelled");
+                    IndexProgress::Cancelled
+                }
+                Err(e) => IndexProgress::Failed(format!("{:#}", e)),
+            };
+            (self.on_progress)(progress);
+        }
+        debug!("Index queue stopped");
+    }
+
+    /// Run one reindex; `None` if it was cancelled
+    fn reindex(
+        &self,
+        rt: &tokio::runtime::Runtime,
+        force: bool,
+        generation: u64,
+    ) -> Result<Option<IndexProgress>> {
+        let stats = self.memory.reindex_with(force, |done, total| {
+            self.checkpoint(IndexPhase::Files, done, total, generation)
+        })?;
+        if self.shared.is_cancelled(generation) {
+            return Ok(None);
+        }
+
+        let (_, chunks_embedded) =
+            rt.block_on(self.memory.generate_embeddings_with(|progress| {
+                self.checkpoint(
+                    IndexPhase::Embeddings,
+                    progress.embedded,
+                    progress.total,
+                    generation,
+                )
+            }))?;
+        if self.shared.is_cancelled(generation) {
+            return Ok(None);
+        }
+
+        Ok(Some(IndexProgress::Finished {
+            files_updated: stats.files_updated,
+            chunks_indexed: stats.chunks_indexed,
+            chunks_embedded,
+        }))
+    }
+
+    /// Between steps: wait out foreground work, then report progress.
+    /// Breaks if the job was cancelled.
+    fn checkpoint(
+        &self,
+        phase: IndexPhase,
+        done: usize,
+        total: usize,
+        generation: u64,
+    ) -> ControlFlow<()> {
+        let mut paused = false;
+        loop {
+            if self.shared.is_cancelled(generation) {
+                return ControlFlow::Break(());
+            }
+            if !self.memory.activity.busy_above(Priority::Indexing) {
+                break;
+            }
+            if !paused {
+                paused = true;
+                (self.on_progress)(IndexProgress::Running {
+                    phase,
+                    done,
+                    total,
+                    paused,
+                });
+            }
+            std::thread::sleep(PAUSE_POLL);
+        }
+        (self.on_progress)(IndexProgress::Running {
+            phase,
+            done,
+            total,
+            paused: false,
+        });
+        ControlFlow::Continue(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_indexing_yields_to_chat_and_search() {
+        let activity = Arc::new(Activity::default());
+        assert!(!activity.busy_above(Priority::Indexing));
+
+        let search = ForegroundGuard::new(activity.clone(), Priority::Retrieval);
+        assert!(activity.busy_above(Priority::Indexing));
+        assert!(!activity.busy_above(Priority::Retrieval));
+
+        let chat = ForegroundGuard::new(activity.clone(), Priority::Chat);
+        assert!(activity.busy_above(Priority::Retrieval));
+        drop(search);
+        assert!(activity.busy_above(Priority::Indexing));
+        drop(chat);
+        assert!(!activity.busy_above(Priority::Indexing));
+    }
+
+    #[test]
+    fn test_requests_merge_and_cancel() {
+        let shared = Shared {
+            state: Mutex::new(QueueState::default()),
+            wake: Condvar::new(),
+        };
+        let queue = IndexQueue {
+            shared: Arc::new(shared),
+        };
+
+        // A forced reindex requested while another waits wins
+        queue.reindex(false);
+        queue.reindex(true);
+        queue.reindex(false);
+        let (force, generation) = queue.shared.next().unwrap();
+        assert!(force);
+        assert!(!queue.shared.is_cancelled(generation));
+
+        // Cancelling stops the running job and drops the waiting one
+        queue.reindex(false);
+        queue.cancel();
+        assert!(queue.shared.is_cancelled(generation));
+        assert!(queue.shared.lock().pending.is_none());
+
+        drop(queue);
+    }
+}
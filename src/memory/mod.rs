@@ -1,31 +1,90 @@
+mod archive;
+pub mod audit;
+mod chunking;
+mod consolidate;
+mod db;
+pub mod documents;
+mod embed_queue;
 mod embeddings;
+pub mod eval;
+mod forget;
+mod freshness;
+pub mod frontmatter;
+mod graph;
+mod health;
 mod index;
+mod index_queue;
+mod openclaw;
+mod patch;
+mod proposals;
+mod quantize;
 mod search;
+mod snapshot;
+mod staging;
+mod sync;
+mod timeline;
 pub mod verification;
 mod watcher;
 mod workspace;
 
+pub use archive::{ArchiveFile, ArchiveManifest, ImportReport};
+pub use audit::{AuditEntry, AuditFilter};
+pub use chunking::{ChunkOptions, ChunkStrategy};
+pub use consolidate::{ChunkRef, ConsolidationReport, DuplicateGroup};
+pub use embed_queue::EmbedProgress;
 #[cfg(feature = "gguf")]
 pub use embeddings::LlamaCppProvider;
-pub use embeddings::{hash_text, EmbeddingProvider, FastEmbedProvider, OpenAIEmbeddingProvider};
-pub use index::{MemoryIndex, ReindexStats};
-pub use search::MemoryChunk;
+pub use embeddings::{
+    hash_text, EmbeddingProvider, ExecutionOptions, ExecutionProvider, FastEmbedProvider,
+    GeminiEmbeddingProvider, OpenAIEmbeddingProvider, VoyageEmbeddingProvider,
+};
+pub use forget::{ForgetReport, ForgetTarget};
+pub use freshness::{Freshness, ReconfirmLog, StaleFile};
+pub use graph::GraphEdge;
+pub use health::{HealthReport, HEALTH_REPORT_PATH};
+pub use index::{EmbeddedChunk, IndexedChunk, MemoryIndex, ReindexStats};
+pub use index_queue::{ForegroundGuard, IndexPhase, IndexProgress, IndexQueue, Priority};
+pub use openclaw::OpenClawReport;
+pub use patch::{apply_patch, replace_file};
+pub use proposals::{Proposal, REMEMBERED_PATH};
+pub use quantize::EmbeddingStorage;
+pub use search::{MemoryChunk, QueryExpansion, SearchFilter, SearchMode};
+pub use snapshot::{RestoreReport, SnapshotInfo, Snapshots};
+pub use staging::{ChangeQueue, FileWrite, PendingChange};
+pub use sync::{ConflictSide, SyncReport, WorkspaceSync};
+pub use timeline::DatedEntry;
 pub use verification::{
-    ChunkVerifier, Confidence, Provenance, VerifiedChunk, VerificationStats,
+    cited_hashes, parse_citation, ChunkVerifier, Confidence, Provenance, VerificationStats,
+    VerifiedChunk, CITATION_PREFIX,
 };
 pub use watcher::MemoryWatcher;
-pub use workspace::{init_state_dir, init_workspace};
+pub use workspace::{
+    init_state_dir, init_workspace, init_workspace_with_templates, DEFAULT_TEMPLATE_PACK,
+};
 
-use anyhow::Result;
-use chrono::Local;
+use anyhow::{Context, Result};
 use std::fs;
+use std::ops::ControlFlow;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
 use tokio::runtime::Handle;
 use tracing::{debug, info, warn};
 
-use crate::config::{Config, MemoryConfig};
+use crate::config::{load_agent, AgentDefinition, Config, MemoryConfig};
+use crate::locale;
+
+/// Workspace-relative directory for documents saved from attachments
+pub const DOCUMENTS_DIR: &str = "memory/files";
+use embed_queue::EmbeddingQueue;
+use embeddings::mean_embedding;
+use index_queue::Activity;
+
+/// Held from reading a memory file to writing it back, so agent turns
+/// running at the same time (in different sessions) don't lose each
+/// other's edits
+static FILE_WRITES: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
 
 #[derive(Clone)]
 pub struct MemoryManager {
@@ -33,8 +92,17 @@ pub struct MemoryManager {
     db_path: PathBuf,
     index: MemoryIndex,
     config: MemoryConfig,
-    /// Optional embedding provider for semantic search
-    embedding_provider: Option<Arc<dyn EmbeddingProvider>>,
+    /// Persona from agents.toml, for an agent sharing another's memory
+    persona: Option<AgentDefinition>,
+    /// Embedding provider for semantic search, wrapped in the queue that
+    /// batches and rate-limits bulk embedding. Shared by clones so a model
+    /// loaded in the background reaches every holder.
+    embeddings: Arc<RwLock<Option<EmbeddingQueue>>>,
+    /// True while the provider is loading in the background
+    embeddings_loading: Arc<AtomicBool>,
+    /// Chat turns and searches in progress, shared by clones; background
+    /// indexing waits for them
+    activity: Arc<Activity>,
     /// True if this was a brand new workspace (first run)
     is_brand_new: bool,
 }
@@ -79,28 +147,89 @@ impl MemoryManager {
         app_config: Option<&Config>,
         agent_id: &str,
     ) -> Result<Self> {
-        let workspace = shellexpand::tilde(&memory_config.workspace).to_string();
-        let workspace = PathBuf::from(workspace);
+        let manager = Self::open(memory_config, agent_id)?;
+        if let Some(provider) = Self::create_embedding_provider(memory_config, app_config) {
+            manager.set_embedding_provider(provider);
+        }
+        Ok(manager)
+    }
 
-        // Initialize workspace with templates if needed, returns true if brand new
-        let is_brand_new = init_workspace(&workspace)?;
+    /// Like [`Self::new_with_full_config`], but load the embedding provider
+    /// on a background thread so startup doesn't wait for model downloads.
+    /// Keyword search works meanwhile. `on_ready` runs on that thread with
+    /// the model name once semantic search is available, or `None` if no
+    /// provider could be loaded.
+    pub fn new_with_background_embeddings(
+        memory_config: &MemoryConfig,
+        app_config: Option<&Config>,
+        agent_id: &str,
+        on_ready: impl FnOnce(Option<String>) + Send + 'static,
+    ) -> Result<Self> {
+        let manager = Self::open(memory_config, agent_id)?;
+        manager.embeddings_loading.store(true, Ordering::SeqCst);
+
+        let loader = manager.clone();
+        let memory_config = memory_config.clone();
+        let app_config = app_config.cloned();
+        std::thread::Builder::new()
+            .name("embedding-loader".to_string())
+            .spawn(move || {
+                let start = std::time::Instant::now();
+                let provider = Self::create_embedding_provider(&memory_config, app_config.as_ref());
+                let model = provider.map(|provider| {
+                    let model = provider.model().to_string();
+                    loader.set_embedding_provider(provider);
+                    info!("Semantic search ready after {:?}", start.elapsed());
+                    model
+                });
+                loader.embeddings_loading.store(false, Ordering::SeqCst);
+                on_ready(model);
+            })?;
 
-        // Database goes in state_dir/memory/{agentId}.sqlite (OpenClaw-compatible)
-        let state_dir = workspace
-            .parent()
-            .ok_or_else(|| anyhow::anyhow!("Workspace has no parent directory"))?;
-        let memory_dir = state_dir.join("memory");
-        std::fs::create_dir_all(&memory_dir)?;
-        let db_path = memory_dir.join(format!("{}.sqlite", agent_id));
+        Ok(manager)
+    }
 
+    /// Open the workspace and index without an embedding provider
+    fn open(memory_config: &MemoryConfig, agent_id: &str) -> Result<Self> {
+        let (workspace, db_path) = Self::storage_paths(memory_config, agent_id)?;
+        let persona = load_agent(memory_config, agent_id)?;
+
+        // Initialize workspace with templates if needed, returns true if brand new
+        let is_brand_new = init_workspace_with_templates(
+            &workspace,
+            &memory_config.template_pack,
+            locale::current().language(),
+        )?;
+
+        let (chunk_options, chunk_rules) = ChunkOptions::from_config(memory_config)?;
+        let embedding_storage: EmbeddingStorage = memory_config.embedding_storage.parse()?;
         let index = MemoryIndex::new_with_db_path(&workspace, &db_path)?
-            .with_chunk_config(memory_config.chunk_size, memory_config.chunk_overlap);
+            .with_chunking(chunk_options, chunk_rules)
+            .with_audit(memory_config.audit_log)
+            .with_entity_graph(memory_config.entity_graph)
+            .with_time_index(memory_config.time_index)
+            .with_embedding_storage(embedding_storage, memory_config.embedding_rescore);
 
-        // Create embedding provider based on config
-        let embedding_provider: Option<Arc<dyn EmbeddingProvider>> = match memory_config
-            .embedding_provider
-            .as_str()
-        {
+        Ok(Self {
+            workspace,
+            db_path,
+            index,
+            config: memory_config.clone(),
+            persona,
+            embeddings: Arc::new(RwLock::new(None)),
+            embeddings_loading: Arc::new(AtomicBool::new(false)),
+            activity: Arc::new(Activity::default()),
+            is_brand_new,
+        })
+    }
+
+    /// Create the configured embedding provider. Failures are logged and
+    /// leave memory on keyword-only search.
+    pub fn create_embedding_provider(
+        memory_config: &MemoryConfig,
+        app_config: Option<&Config>,
+    ) -> Option<Arc<dyn EmbeddingProvider>> {
+        match memory_config.embedding_provider.as_str() {
             "local" => {
                 let model_name = if memory_config.embedding_model.is_empty()
                     || memory_config.embedding_model == "text-embedding-3-small"
@@ -114,7 +243,17 @@ impl MemoryManager {
                 } else {
                     Some(memory_config.embedding_cache_dir.as_str())
                 };
-                match FastEmbedProvider::new_with_cache_dir(model_name, cache_dir) {
+                let execution = memory_config.embedding_execution_provider.parse();
+                match execution
+                    .and_then(|provider| {
+                        let options = ExecutionOptions {
+                            provider,
+                            threads: memory_config.embedding_threads,
+                        };
+                        FastEmbedProvider::new_with_options(model_name, cache_dir, options)
+                    })
+                    .and_then(|p| p.with_dimensions(memory_config.embedding_dimensions))
+                {
                     Ok(provider) => {
                         info!("Using local embedding provider: {}", provider.model());
                         Some(Arc::new(provider))
@@ -133,6 +272,7 @@ impl MemoryManager {
                             &openai.api_key,
                             &openai.base_url,
                             &memory_config.embedding_model,
+                            memory_config.embedding_dimensions,
                         ) {
                             Ok(provider) => {
                                 info!("Using OpenAI embedding provider: {}", provider.model());
@@ -152,6 +292,48 @@ impl MemoryManager {
                     None
                 }
             }
+            "gemini" => {
+                let Some(gemini) = app_config.and_then(|c| c.providers.gemini.as_ref()) else {
+                    warn!("Gemini embedding provider requested but no [providers.gemini] config found. Falling back to FTS-only search.");
+                    return None;
+                };
+                match GeminiEmbeddingProvider::new(
+                    &gemini.api_key,
+                    &gemini.base_url,
+                    api_embedding_model(memory_config, GeminiEmbeddingProvider::DEFAULT_MODEL),
+                    memory_config.embedding_dimensions,
+                ) {
+                    Ok(provider) => {
+                        info!("Using Gemini embedding provider: {}", provider.model());
+                        Some(Arc::new(provider))
+                    }
+                    Err(e) => {
+                        warn!("Failed to initialize Gemini embeddings: {}. Falling back to FTS-only search.", e);
+                        None
+                    }
+                }
+            }
+            "voyage" => {
+                let Some(voyage) = app_config.and_then(|c| c.providers.voyage.as_ref()) else {
+                    warn!("Voyage embedding provider requested but no [providers.voyage] config found. Falling back to FTS-only search.");
+                    return None;
+                };
+                match VoyageEmbeddingProvider::new(
+                    &voyage.api_key,
+                    &voyage.base_url,
+                    api_embedding_model(memory_config, VoyageEmbeddingProvider::DEFAULT_MODEL),
+                    memory_config.embedding_dimensions,
+                ) {
+                    Ok(provider) => {
+                        info!("Using Voyage embedding provider: {}", provider.model());
+                        Some(Arc::new(provider))
+                    }
+                    Err(e) => {
+                        warn!("Failed to initialize Voyage embeddings: {}. Falling back to FTS-only search.", e);
+                        None
+                    }
+                }
+            }
             #[cfg(feature = "gguf")]
             "gguf" => {
                 let cache_dir = if memory_config.embedding_cache_dir.is_empty() {
@@ -159,7 +341,9 @@ impl MemoryManager {
                 } else {
                     Some(memory_config.embedding_cache_dir.as_str())
                 };
-                match LlamaCppProvider::new(&memory_config.embedding_model, cache_dir) {
+                match LlamaCppProvider::new(&memory_config.embedding_model, cache_dir)
+                    .and_then(|p| p.with_dimensions(memory_config.embedding_dimensions))
+                {
                     Ok(provider) => {
                         info!("Using GGUF embedding provider: {}", provider.model());
                         Some(Arc::new(provider))
@@ -186,33 +370,87 @@ impl MemoryManager {
                 );
                 None
             }
+        }
+    }
+
+    /// Workspace directory and index database path for an agent.
+    ///
+    /// The database goes in state_dir/memory/{agentId}.sqlite (OpenClaw-compatible),
+    /// where an agent defined in agents.toml uses the id whose memory it shares.
+    pub fn storage_paths(
+        memory_config: &MemoryConfig,
+        agent_id: &str,
+    ) -> Result<(PathBuf, PathBuf)> {
+        let workspace = shellexpand::tilde(&memory_config.workspace).to_string();
+        let workspace = PathBuf::from(workspace);
+
+        let state_dir = workspace
+            .parent()
+            .ok_or_else(|| anyhow::anyhow!("Workspace has no parent directory"))?;
+        let index_id = match load_agent(memory_config, agent_id)? {
+            Some(persona) => persona.memory,
+            None => agent_id.to_string(),
         };
+        let db_path = state_dir
+            .join("memory")
+            .join(format!("{}.sqlite", index_id));
 
-        Ok(Self {
-            workspace,
-            db_path,
-            index,
-            config: memory_config.clone(),
-            embedding_provider,
-            is_brand_new,
-        })
+        Ok((workspace, db_path))
     }
 
     /// Set embedding provider for semantic search (requires OpenAI API key)
     pub fn with_embedding_provider(mut self, provider: Arc<dyn EmbeddingProvider>) -> Self {
-        self.embedding_provider = Some(provider);
+        self.embeddings = Arc::new(RwLock::new(None));
+        self.set_embedding_provider(provider);
         self
     }
 
+    fn set_embedding_provider(&self, provider: Arc<dyn EmbeddingProvider>) {
+        let queue = EmbeddingQueue::new(provider, &self.config);
+        if let Ok(mut embeddings) = self.embeddings.write() {
+            *embeddings = Some(queue);
+        }
+    }
+
+    fn embedding_queue(&self) -> Option<EmbeddingQueue> {
+        self.embeddings.read().ok().and_then(|e| e.clone())
+    }
+
+    fn embedding_provider(&self) -> Option<Arc<dyn EmbeddingProvider>> {
+        self.embedding_queue().map(|q| q.provider().clone())
+    }
+
+    /// Model name of the configured embedding provider, if any
+    pub fn embedding_model(&self) -> Option<String> {
+        self.embedding_provider().map(|p| p.model().to_string())
+    }
+
     /// Check if semantic search is available
     pub fn has_embeddings(&self) -> bool {
-        self.embedding_provider.is_some()
+        self.embedding_provider().is_some()
+    }
+
+    /// True while the embedding provider is still loading in the background
+    pub fn embeddings_loading(&self) -> bool {
+        self.embeddings_loading.load(Ordering::SeqCst)
+    }
+
+    /// Mark foreground work (such as a chat turn) until the guard is
+    /// dropped. Background indexing pauses for work above its priority;
+    /// searches mark themselves.
+    pub fn foreground(&self, priority: Priority) -> ForegroundGuard {
+        ForegroundGuard::new(self.activity.clone(), priority)
     }
 
     pub fn workspace(&self) -> &PathBuf {
         &self.workspace
     }
 
+    /// This agent's definition in agents.toml, if it has one
+    pub fn persona(&self) -> Option<&AgentDefinition> {
+        self.persona.as_ref()
+    }
+
     /// Read the main MEMORY.md file
     pub fn read_memory_file(&self) -> Result<String> {
         let path = self.workspace.join("MEMORY.md");
@@ -233,9 +471,16 @@ impl MemoryManager {
         }
     }
 
-    /// Read the SOUL.md file (persona/tone guidance)
+    /// Read the SOUL.md file (persona/tone guidance), or its translation
+    /// for the configured language (`SOUL.es.md`). A persona from agents.toml
+    /// reads its own soul file instead.
     pub fn read_soul_file(&self) -> Result<String> {
-        let path = self.workspace.join("SOUL.md");
+        let soul = self
+            .persona
+            .as_ref()
+            .and_then(|p| p.soul.as_deref())
+            .unwrap_or("SOUL.md");
+        let path = locale::current().localized_path(&self.workspace.join(soul));
         if path.exists() {
             Ok(fs::read_to_string(&path)?)
         } else {
@@ -295,7 +540,7 @@ impl MemoryManager {
             return Ok(String::new());
         }
 
-        let today = Local::now().date_naive();
+        let today = locale::now().date_naive();
         let mut content = String::new();
 
         for i in 0..days {
@@ -317,44 +562,174 @@ impl MemoryManager {
         Ok(content)
     }
 
-    /// Search memory using hybrid search (FTS + semantic if available)
+    /// Search memory using hybrid search (FTS + semantic if available).
+    ///
+    /// Supports `category:<name>` filter terms (comma-separated for several),
+    /// matched against the `category` frontmatter of indexed files.
     pub fn search(&self, query: &str, limit: usize) -> Result<Vec<MemoryChunk>> {
+        self.search_expanded(query, &QueryExpansion::default(), limit)
+    }
+
+    /// Run an eval set against the index, searching each question for its
+    /// top `k` results. Eval searches aren't written to the audit log.
+    pub fn evaluate(&self, set: &eval::EvalSet, k: usize) -> Result<eval::EvalReport> {
+        let mut results = Vec::with_capacity(set.questions.len());
+        for question in &set.questions {
+            let chunks = self.run_search(&question.question, &QueryExpansion::default(), k)?;
+            results.push(eval::score_question(question, &chunks, k));
+        }
+        Ok(eval::EvalReport::new(k, results))
+    }
+
+    /// Search memory with query rewrites and/or a hypothetical answer (HyDE).
+    ///
+    /// Rewrites are searched with FTS alongside the original query; the
+    /// hypothetical answer is embedded with the query and the normalized mean
+    /// is used for vector search.
+    pub fn search_expanded(
+        &self,
+        query: &str,
+        expansion: &QueryExpansion,
+        limit: usize,
+    ) -> Result<Vec<MemoryChunk>> {
+        let _retrieval = self.foreground(Priority::Retrieval);
+        let _span = tracing::info_span!("memory_search", limit).entered();
+        let results = self.run_search(query, expansion, limit)?;
+        debug!("Memory search returned {} results", results.len());
+        self.audit_search(query, &results);
+        Ok(results)
+    }
+
+    fn run_search(
+        &self,
+        query: &str,
+        expansion: &QueryExpansion,
+        limit: usize,
+    ) -> Result<Vec<MemoryChunk>> {
+        let (query, filter) = SearchFilter::parse(query);
+        let query = query.as_str();
+
+        let mut queries = vec![query];
+        queries.extend(expansion.rewrites.iter().map(|r| r.as_str()));
+
         // If we have an embedding provider, try hybrid search
-        if let Some(ref provider) = self.embedding_provider {
-            // Try to get query embedding (may fail if no API key, rate limited, etc.)
-            if let Ok(handle) = Handle::try_current() {
-                let provider = provider.clone();
-                let query_string = query.to_string();
-                let model = provider.model().to_string();
-
-                // Run embedding in blocking context
-                let embedding_result = std::thread::spawn(move || {
-                    handle.block_on(async { provider.embed(&query_string).await })
-                })
-                .join()
-                .map_err(|_| anyhow::anyhow!("Thread panicked"))?;
-
-                if let Ok(embedding) = embedding_result {
-                    debug!("Using hybrid search with {} dimensions", embedding.len());
-                    return self.index.search_hybrid(
-                        query,
-                        Some(&embedding),
-                        &model,
-                        limit,
-                        0.3, // FTS weight
-                        0.7, // Vector weight
-                    );
-                }
-            }
+        let mut texts = vec![query.to_string()];
+        texts.extend(expansion.hypothetical.clone());
+        if let Some((embedding, model)) = self.embed_query(texts)? {
+            debug!(
+                "Using hybrid search with {} dimensions ({} phrasings, hyde: {})",
+                embedding.len(),
+                queries.len(),
+                expansion.hypothetical.is_some()
+            );
+            return self.index.search_hybrid_multi(
+                &queries,
+                Some(&embedding),
+                &model,
+                &filter,
+                limit,
+                0.3, // FTS weight
+                0.7, // Vector weight
+            );
         }
 
         // Fallback to FTS-only search
-        self.index.search(query, limit)
+        if queries.len() > 1 {
+            self.index
+                .search_hybrid_multi(&queries, None, "", &filter, limit, 1.0, 0.0)
+        } else {
+            self.index.search_filtered(query, &filter, limit)
+        }
+    }
+
+    /// Embed `texts` with the configured provider and average them, returning
+    /// the embedding and its model. `None` without a provider, or if the
+    /// embedding failed (no API key, rate limited, etc.).
+    fn embed_query(&self, texts: Vec<String>) -> Result<Option<(Vec<f32>, String)>> {
+        let Some(provider) = self.embedding_provider() else {
+            return Ok(None);
+        };
+        let Ok(handle) = Handle::try_current() else {
+            return Ok(None);
+        };
+        let model = provider.model().to_string();
+
+        // Run embedding in blocking context
+        let embedding_result = std::thread::spawn(move || {
+            handle.block_on(async { provider.embed_batch(&texts).await })
+        })
+        .join()
+        .map_err(|_| anyhow::anyhow!("Thread panicked"))?;
+
+        Ok(embedding_result
+            .ok()
+            .and_then(|embeddings| mean_embedding(&embeddings))
+            .map(|embedding| (embedding, model)))
+    }
+
+    /// Search with a single retrieval method. `Hybrid` is the same as
+    /// [`search`](Self::search); `Semantic` fails without a working
+    /// embedding provider.
+    pub fn search_with_mode(
+        &self,
+        query: &str,
+        mode: SearchMode,
+        limit: usize,
+    ) -> Result<Vec<MemoryChunk>> {
+        match mode {
+            SearchMode::Hybrid => self.search(query, limit),
+            SearchMode::Keyword => self.search_fts(query, limit),
+            SearchMode::Semantic => {
+                let _retrieval = self.foreground(Priority::Retrieval);
+                let (text, filter) = SearchFilter::parse(query);
+                let Some((embedding, model)) = self.embed_query(vec![text])? else {
+                    anyhow::bail!(
+                        "Semantic search needs an embedding provider (memory.embedding_provider)"
+                    );
+                };
+                let results = self
+                    .index
+                    .search_vector_filtered(&embedding, &model, &filter, limit)?;
+                self.audit_search(query, &results);
+                Ok(results)
+            }
+        }
     }
 
     /// Search memory using FTS only (faster, no API calls)
     pub fn search_fts(&self, query: &str, limit: usize) -> Result<Vec<MemoryChunk>> {
-        self.index.search(query, limit)
+        let (text, filter) = SearchFilter::parse(query);
+        let results = self.index.search_filtered(&text, &filter, limit)?;
+        self.audit_search(query, &results);
+        Ok(results)
+    }
+
+    fn audit_search(&self, query: &str, results: &[MemoryChunk]) {
+        if let Err(e) = self.index.record_search(query, results) {
+            warn!("Failed to audit memory search: {}", e);
+        }
+    }
+
+    /// Audit a write to a memory file by the agent (`tool` is the tool name).
+    /// Paths outside the workspace are ignored.
+    pub fn record_write(&self, path: &std::path::Path, tool: &str, provenance: &str) {
+        if !self.config.audit_log {
+            return;
+        }
+        let Ok(relative) = path.strip_prefix(&self.workspace) else {
+            return;
+        };
+
+        let detail = serde_json::json!({ "tool": tool, "provenance": provenance });
+        let relative = relative.to_string_lossy();
+        if let Err(e) = self.index.record_audit("write", Some(&relative), &detail) {
+            warn!("Failed to audit write to {}: {}", relative, e);
+        }
+    }
+
+    /// Recent memory audit log entries matching `filter`, newest first
+    pub fn audit_entries(&self, filter: &AuditFilter, limit: usize) -> Result<Vec<AuditEntry>> {
+        self.index.audit_entries(filter, limit)
     }
 
     /// Search memory and verify results with hash verification
@@ -363,13 +738,29 @@ impl MemoryManager {
         Ok(self.verify_results(&results))
     }
 
+    /// Expanded search (see [`Self::search_expanded`]) with hash verification
+    pub fn search_verified_expanded(
+        &self,
+        query: &str,
+        expansion: &QueryExpansion,
+        limit: usize,
+    ) -> Result<Vec<VerifiedChunk>> {
+        let results = self.search_expanded(query, expansion, limit)?;
+        Ok(self.verify_results(&results))
+    }
+
     /// Verify a list of search results against stored hashes
     pub fn verify_results(&self, results: &[MemoryChunk]) -> Vec<VerifiedChunk> {
         let verifier = self.index.verifier();
+        let freshness = Freshness::from_config(&self.config);
+        let now = chrono::Utc::now();
 
         results
             .iter()
             .map(|chunk| {
+                let stale = chunk.last_verified.as_deref().is_some_and(|verified| {
+                    freshness.is_stale(chunk.category.as_deref(), verified, now)
+                });
                 if let Some(ref chunk_id) = chunk.chunk_id {
                     let verified = verifier
                         .verify_chunk(chunk_id, &chunk.file, &chunk.content)
@@ -395,6 +786,8 @@ impl MemoryManager {
                             hash,
                             provenance,
                             confidence,
+                            last_verified: chunk.last_verified.clone(),
+                            stale,
                         };
                     }
                 }
@@ -411,6 +804,8 @@ impl MemoryManager {
                     hash: String::new(),
                     provenance: Provenance::Unknown,
                     confidence: Confidence::None,
+                    last_verified: chunk.last_verified.clone(),
+                    stale,
                 }
             })
             .collect()
@@ -421,8 +816,92 @@ impl MemoryManager {
         self.index.chunk_count()
     }
 
+    /// Chunk counts per category, largest first; chunks without one are
+    /// "uncategorized"
+    pub fn category_counts(&self) -> Result<Vec<(String, usize)>> {
+        Ok(self
+            .index
+            .category_counts()?
+            .into_iter()
+            .map(|(category, count)| (category.unwrap_or_else(|| "uncategorized".into()), count))
+            .collect())
+    }
+
+    /// Size of the index database in bytes
+    pub fn index_size_bytes(&self) -> Result<u64> {
+        self.index.size_bytes()
+    }
+
+    /// Number of indexed files
+    pub fn indexed_file_count(&self) -> Result<usize> {
+        Ok(self.index.indexed_files()?.len())
+    }
+
+    /// Chunks still waiting for an embedding (0 without an embedding provider)
+    pub fn pending_embedding_count(&self) -> Result<usize> {
+        if !self.has_embeddings() {
+            return Ok(0);
+        }
+        self.index.pending_embedding_count()
+    }
+
+    /// Write pending index changes into the database file before exiting
+    pub fn checkpoint(&self) -> Result<()> {
+        self.index.checkpoint()
+    }
+
     /// Reindex all memory files
     pub fn reindex(&self, force: bool) -> Result<ReindexStats> {
+        self.reindex_with(force, |_, _| ControlFlow::Continue(()))
+    }
+
+    /// Like [`reindex`](Self::reindex), calling `step` with (files done,
+    /// total) before each file. Returning `Break` stops the reindex, keeping
+    /// the files indexed so far.
+    pub fn reindex_with(
+        &self,
+        force: bool,
+        step: impl FnMut(usize, usize) -> ControlFlow<()>,
+    ) -> Result<ReindexStats> {
+        // First, clean up deleted files from the index
+        let files_removed = self.cleanup_deleted_files()?;
+        if files_removed > 0 {
+            info!("Removed {} deleted files from index", files_removed);
+        }
+
+        let files = self.indexable_files();
+        self.index_files(&files, force, step)
+    }
+
+    /// Index the files a reindex covers under `path` (a file or directory),
+    /// leaving the rest of the index alone
+    pub fn index_path(&self, path: &std::path::Path, force: bool) -> Result<ReindexStats> {
+        let target = path
+            .canonicalize()
+            .with_context(|| format!("{} not found", path.display()))?;
+        let files: Vec<(PathBuf, bool)> = self
+            .indexable_files()
+            .into_iter()
+            .filter(|(file, _)| {
+                file.canonicalize()
+                    .is_ok_and(|file| file.starts_with(&target))
+            })
+            .collect();
+        if files.is_empty() {
+            anyhow::bail!(
+                "Nothing to index under {} (Markdown in the workspace, documents, or memory.paths)",
+                path.display()
+            );
+        }
+        self.index_files(&files, force, |_, _| ControlFlow::Continue(()))
+    }
+
+    fn index_files(
+        &self,
+        files: &[(PathBuf, bool)],
+        force: bool,
+        mut step: impl FnMut(usize, usize) -> ControlFlow<()>,
+    ) -> Result<ReindexStats> {
         let start = std::time::Instant::now();
         let mut stats = ReindexStats {
             files_processed: 0,
@@ -431,12 +910,43 @@ impl MemoryManager {
             duration: Duration::default(),
         };
 
-        // First, clean up deleted files from the index
-        let files_removed = self.cleanup_deleted_files()?;
-        if files_removed > 0 {
-            info!("Removed {} deleted files from index", files_removed);
+        let mut stopped = false;
+        for (done, (path, is_document)) in files.iter().enumerate() {
+            if step(done, files.len()).is_break() {
+                stopped = true;
+                break;
+            }
+            stats.files_processed += 1;
+            match self.index.index_file(path, force) {
+                Ok(true) => stats.files_updated += 1,
+                Ok(false) => {}
+                // One unreadable document shouldn't stop the reindex
+                Err(e) if *is_document => warn!("Failed to index {}: {:#}", path.display(), e),
+                Err(e) => return Err(e),
+            }
         }
 
+        stats.chunks_indexed = self.index.chunk_count()?;
+        stats.duration = start.elapsed();
+
+        if stopped {
+            info!(
+                "Reindex stopped after {} of {} files",
+                stats.files_processed,
+                files.len()
+            );
+        } else {
+            info!("Reindex complete: {:?}", stats);
+        }
+        Ok(stats)
+    }
+
+    /// Files a reindex covers: Markdown under the workspace, documents in
+    /// the documents directory, and configured external paths. The flag
+    /// marks documents.
+    fn indexable_files(&self) -> Vec<(PathBuf, bool)> {
+        let mut files = Vec::new();
+
         // Index all .md files recursively under workspace
         let pattern = format!("{}/**/*.md", self.workspace.display());
         for entry in glob::glob(&pattern)
@@ -445,10 +955,20 @@ impl MemoryManager {
             .filter_map(|r| r.ok())
         {
             if entry.is_file() {
-                stats.files_processed += 1;
-                if self.index.index_file(&entry, force)? {
-                    stats.files_updated += 1;
-                }
+                files.push((entry, false));
+            }
+        }
+
+        // Index PDF, DOCX and text files in the documents directory
+        let documents_dir = documents::documents_dir(&self.workspace, &self.config);
+        let pattern = format!("{}/**/*", documents_dir.display());
+        for entry in glob::glob(&pattern)
+            .into_iter()
+            .flatten()
+            .filter_map(|r| r.ok())
+        {
+            if entry.is_file() && documents::is_document(&entry) {
+                files.push((entry, true));
             }
         }
 
@@ -480,19 +1000,12 @@ impl MemoryManager {
                 .filter_map(|r| r.ok())
             {
                 if entry.is_file() {
-                    stats.files_processed += 1;
-                    if self.index.index_file(&entry, force)? {
-                        stats.files_updated += 1;
-                    }
+                    files.push((entry, false));
                 }
             }
         }
 
-        stats.chunks_indexed = self.index.chunk_count()?;
-        stats.duration = start.elapsed();
-
-        info!("Reindex complete: {:?}", stats);
-        Ok(stats)
+        files
     }
 
     /// Remove files from index that no longer exist on disk
@@ -655,24 +1168,50 @@ impl MemoryManager {
     /// Generate embeddings for chunks that don't have them
     /// Returns (chunks_processed, chunks_embedded)
     /// Uses embedding cache to avoid regenerating identical content
-    pub async fn generate_embeddings(&self, batch_size: usize) -> Result<(usize, usize)> {
-        let provider = match &self.embedding_provider {
-            Some(p) => p,
+    pub async fn generate_embeddings(&self) -> Result<(usize, usize)> {
+        self.generate_embeddings_with_progress(|_| {}).await
+    }
+
+    /// Like [`generate_embeddings`](Self::generate_embeddings), calling
+    /// `on_progress` after each cache pass and API batch
+    pub async fn generate_embeddings_with_progress(
+        &self,
+        mut on_progress: impl FnMut(EmbedProgress) + Send,
+    ) -> Result<(usize, usize)> {
+        self.generate_embeddings_with(|progress| {
+            on_progress(progress);
+            ControlFlow::Continue(())
+        })
+        .await
+    }
+
+    /// Like [`generate_embeddings_with_progress`](Self::generate_embeddings_with_progress),
+    /// but `on_progress` can stop the run: returning `Break` after a cache
+    /// pass skips embedding the rest
+    pub async fn generate_embeddings_with(
+        &self,
+        mut on_progress: impl FnMut(EmbedProgress) -> ControlFlow<()> + Send,
+    ) -> Result<(usize, usize)> {
+        let queue = match self.embedding_queue() {
+            Some(q) => q,
             None => {
                 debug!("No embedding provider configured, skipping embedding generation");
                 return Ok((0, 0));
             }
         };
 
-        let provider_id = provider.id().to_string();
-        let model = provider.model().to_string();
+        let provider_id = queue.provider().id().to_string();
+        let model = queue.provider().model().to_string();
+        let page_size = queue.page_size();
+        let mut progress = EmbedProgress {
+            total: self.index.pending_embedding_count()?,
+            ..Default::default()
+        };
         let mut total_processed = 0;
-        let mut total_embedded = 0;
-        let mut cache_hits = 0;
 
         loop {
             // Get chunks without embeddings
-            let chunks = self.index.chunks_without_embeddings(batch_size)?;
+            let chunks = self.index.chunks_without_embeddings(page_size)?;
             if chunks.is_empty() {
                 break;
             }
@@ -680,98 +1219,594 @@ impl MemoryManager {
             total_processed += chunks.len();
 
             // Separate chunks into cached and uncached
-            let mut to_embed: Vec<(String, String, String)> = Vec::new(); // (id, text, hash)
-            let mut from_cache: Vec<(String, Vec<f32>)> = Vec::new(); // (id, embedding)
+            let mut to_embed: Vec<(String, String)> = Vec::new(); // (id, hash)
+            let mut texts: Vec<String> = Vec::new();
 
-            for (chunk_id, text) in &chunks {
+            for (chunk_id, text) in chunks.iter() {
                 let text_hash = hash_text(text);
 
                 // Check cache first
-                if let Ok(Some(cached)) =
-                    self.index
-                        .get_cached_embedding(&provider_id, &model, &text_hash)
+                match self
+                    .index
+                    .get_cached_embedding(&provider_id, &model, &text_hash)
                 {
-                    from_cache.push((chunk_id.clone(), cached));
-                    cache_hits += 1;
-                } else {
-                    to_embed.push((chunk_id.clone(), text.clone(), text_hash));
+                    Ok(Some(cached)) => {
+                        if let Err(e) = self.index.store_embedding(chunk_id, &cached, &model) {
+                            warn!(
+                                "Failed to store cached embedding for chunk {}: {}",
+                                chunk_id, e
+                            );
+                        } else {
+                            progress.embedded += 1;
+                            progress.cached += 1;
+                        }
+                    }
+                    _ => {
+                        to_embed.push((chunk_id.clone(), text_hash));
+                        texts.push(text.clone());
+                    }
                 }
             }
-
-            // Store cached embeddings
-            for (chunk_id, embedding) in from_cache {
-                if let Err(e) = self.index.store_embedding(&chunk_id, &embedding, &model) {
-                    warn!(
-                        "Failed to store cached embedding for chunk {}: {}",
-                        chunk_id, e
-                    );
-                } else {
-                    total_embedded += 1;
-                }
+            if on_progress(progress).is_break() {
+                break;
             }
 
-            // Generate new embeddings for uncached chunks
-            if !to_embed.is_empty() {
-                let texts: Vec<String> = to_embed.iter().map(|(_, text, _)| text.clone()).collect();
-
-                match provider.embed_batch(&texts).await {
-                    Ok(embeddings) => {
-                        for ((chunk_id, _text, text_hash), embedding) in
-                            to_embed.iter().zip(embeddings.iter())
-                        {
-                            // Store in chunk
-                            if let Err(e) = self.index.store_embedding(chunk_id, embedding, &model)
-                            {
-                                warn!("Failed to store embedding for chunk {}: {}", chunk_id, e);
-                            } else {
-                                total_embedded += 1;
-                            }
+            // Generate new embeddings for uncached chunks in batches
+            let (embeddings, error) = queue
+                .embed(&texts, |done| {
+                    progress.embedded += done;
+                    let _ = on_progress(progress);
+                })
+                .await;
 
-                            // Store in cache for future reuse
-                            if let Err(e) = self.index.cache_embedding(
-                                &provider_id,
-                                &model,
-                                "", // provider_key (API key identifier, can be empty)
-                                text_hash,
-                                embedding,
-                            ) {
-                                debug!("Failed to cache embedding: {}", e);
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        warn!("Failed to generate embeddings: {}", e);
-                        break;
-                    }
+            for ((chunk_id, text_hash), embedding) in to_embed.iter().zip(embeddings.iter()) {
+                // Store in chunk
+                if let Err(e) = self.index.store_embedding(chunk_id, embedding, &model) {
+                    warn!("Failed to store embedding for chunk {}: {}", chunk_id, e);
+                    progress.embedded -= 1;
                 }
+
+                // Store in cache for future reuse
+                if let Err(e) = self.index.cache_embedding(
+                    &provider_id,
+                    &model,
+                    "", // provider_key (API key identifier, can be empty)
+                    text_hash,
+                    embedding,
+                ) {
+                    debug!("Failed to cache embedding: {}", e);
+                }
+            }
+
+            if let Some(e) = error {
+                warn!("Failed to generate embeddings: {}", e);
+                break;
             }
 
             debug!(
                 "Generated embeddings: {}/{} chunks ({} from cache)",
-                total_embedded, total_processed, cache_hits
+                progress.embedded, progress.total, progress.cached
             );
 
-            // Break if we processed fewer than batch_size (last batch)
-            if chunks.len() < batch_size {
+            // Break if we processed fewer than a page (last page)
+            if chunks.len() < page_size {
                 break;
             }
         }
 
         info!(
             "Embedding generation complete: {} chunks, {} embedded, {} cache hits",
-            total_processed, total_embedded, cache_hits
+            total_processed, progress.embedded, progress.cached
         );
 
-        Ok((total_processed, total_embedded))
+        Ok((total_processed, progress.embedded))
     }
 
     /// Get count of chunks with embeddings
     pub fn embedded_chunk_count(&self) -> Result<usize> {
-        let model = self
-            .embedding_provider
-            .as_ref()
-            .map(|p| p.model().to_string())
-            .unwrap_or_default();
+        let model = self.embedding_model().unwrap_or_default();
         self.index.embedded_chunk_count(&model)
     }
+
+    /// Export the workspace and index (with embeddings) to a `.tar.zst` archive
+    pub fn export(&self, output: &std::path::Path) -> Result<ArchiveManifest> {
+        let model = self.embedding_model();
+        archive::export(&self.workspace, &self.index, model.as_deref(), output)
+    }
+
+    /// Verify and install an archive written by [`Self::export`].
+    ///
+    /// Takes paths rather than a manager because the index database is
+    /// replaced and must not be open.
+    pub fn import(
+        memory_config: &MemoryConfig,
+        agent_id: &str,
+        archive_path: &std::path::Path,
+        force: bool,
+    ) -> Result<ImportReport> {
+        let (workspace, db_path) = Self::storage_paths(memory_config, agent_id)?;
+        auto_snapshot_closed(memory_config, &workspace, &db_path, "import")?;
+        archive::import(archive_path, &workspace, &db_path, force)
+    }
+
+    /// Copy the workspace and index into an OpenClaw state directory
+    /// (e.g. `~/.openclaw`) so OpenClaw can use the same memory.
+    pub fn export_openclaw(
+        &self,
+        state_dir: &std::path::Path,
+        agent_id: &str,
+        force: bool,
+    ) -> Result<OpenClawReport> {
+        openclaw::export(&self.workspace, &self.index, state_dir, agent_id, force)
+    }
+
+    /// Copy OpenClaw's workspace and index from `state_dir`, restoring
+    /// homegpt verification hashes for chunks that round-tripped unchanged.
+    ///
+    /// Like [`Self::import`], runs before the index database is opened.
+    pub fn import_openclaw(
+        memory_config: &MemoryConfig,
+        agent_id: &str,
+        state_dir: &std::path::Path,
+        force: bool,
+    ) -> Result<OpenClawReport> {
+        let (workspace, db_path) = Self::storage_paths(memory_config, agent_id)?;
+        auto_snapshot_closed(memory_config, &workspace, &db_path, "OpenClaw import")?;
+        openclaw::import(state_dir, agent_id, &workspace, &db_path, force)
+    }
+
+    /// Check the index and workspace for verification, embedding,
+    /// staleness and indexing problems
+    pub fn health_report(&self) -> Result<HealthReport> {
+        let provider = self.embedding_provider();
+        let expected = provider.as_ref().map(|p| (p.model(), p.dimensions()));
+        health::check(
+            &self.workspace,
+            &self.index,
+            expected,
+            &Freshness::from_config(&self.config),
+        )
+    }
+
+    /// Files whose `last_verified` frontmatter is past their category's
+    /// max age
+    pub fn stale_files(&self) -> Result<Vec<StaleFile>> {
+        let freshness = Freshness::from_config(&self.config);
+        Ok(freshness.stale_files(self.index.last_verified_dates()?, chrono::Utc::now()))
+    }
+
+    /// Write a report to [`HEALTH_REPORT_PATH`] and index it
+    pub fn write_health_report(&self, report: &HealthReport) -> Result<PathBuf> {
+        let path = self.workspace.join(HEALTH_REPORT_PATH);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, report.to_markdown())?;
+        self.index.index_file(&path, false)?;
+        Ok(path)
+    }
+
+    /// Index a file written outside the memory tools (e.g. finance
+    /// summaries), auditing the write as `tool`
+    pub fn index_written_file(&self, path: &std::path::Path, tool: &str) -> Result<bool> {
+        self.record_write(path, tool, "generated");
+        self.index.index_file(path, false)
+    }
+
+    /// Write `content` to `path` for the agent's `tool`. With
+    /// `memory.staging` on, a file inside the workspace is staged for review
+    /// instead, unless an auto-approve rule covers the change. A workspace
+    /// file's previous version is kept under `~/.homegpt/backups/`.
+    pub fn write_file(
+        &self,
+        path: &std::path::Path,
+        content: &str,
+        tool: &str,
+        provenance: &str,
+    ) -> Result<FileWrite> {
+        let path = std::path::absolute(path)?;
+        if let Some(relative) = self.staged_path(&path) {
+            let queue = ChangeQueue::for_workspace(&self.workspace)?;
+            if !queue.auto_approved(&self.config.staging.auto_approve, &relative, content)? {
+                let id = queue.stage(&relative, content, tool)?;
+                info!("Staged change #{} to {} for review", id, relative);
+                return Ok(FileWrite::Staged(id));
+            }
+        }
+
+        let backup = path
+            .strip_prefix(&self.workspace)
+            .ok()
+            .and_then(|relative| patch::backup_path(&self.workspace, &relative.to_string_lossy()));
+        replace_file(&path, content, backup.as_deref())?;
+        self.record_write(&path, tool, provenance);
+        Ok(FileWrite::Written)
+    }
+
+    /// Wait for other turns' edits of memory files, then keep theirs out
+    /// until the guard is dropped. Hold it from reading a file until the
+    /// new content is written.
+    pub async fn lock_writes(&self) -> tokio::sync::MutexGuard<'static, ()> {
+        FILE_WRITES.lock().await
+    }
+
+    /// `path`, or if a file is already there, the first of `name-2.md`,
+    /// `name-3.md`, ... that isn't. For new files named by the time or a
+    /// slug, which two sessions can pick at once; call it under
+    /// `lock_writes`.
+    pub fn unused_path(path: &std::path::Path) -> PathBuf {
+        if !path.exists() {
+            return path.to_path_buf();
+        }
+        let stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let extension = path
+            .extension()
+            .map(|e| format!(".{}", e.to_string_lossy()))
+            .unwrap_or_default();
+        (2..)
+            .map(|n| path.with_file_name(format!("{}-{}{}", stem, n, extension)))
+            .find(|candidate| !candidate.exists())
+            .expect("some suffix is free")
+    }
+
+    /// Content of `path` as the agent's writes left it: with changes
+    /// waiting for review, the newest of them
+    pub fn read_file(&self, path: &std::path::Path) -> Result<String> {
+        let path = std::path::absolute(path)?;
+        if let Some(relative) = self.staged_path(&path) {
+            let queue = ChangeQueue::for_workspace(&self.workspace)?;
+            return queue
+                .current(&relative)?
+                .ok_or_else(|| anyhow::anyhow!("{} does not exist", path.display()));
+        }
+        Ok(fs::read_to_string(&path)?)
+    }
+
+    /// Workspace-relative form of `path`, if writes to it are staged
+    fn staged_path(&self, path: &std::path::Path) -> Option<String> {
+        if !self.config.staging.enabled {
+            return None;
+        }
+        let relative = path.strip_prefix(&self.workspace).ok()?;
+        Some(relative.to_string_lossy().replace('\\', "/"))
+    }
+
+    /// Changes waiting for review, oldest first
+    pub fn pending_changes(&self) -> Result<Vec<PendingChange>> {
+        ChangeQueue::for_workspace(&self.workspace)?.pending()
+    }
+
+    /// Write staged change `id` to its file and index it
+    pub fn approve_change(&self, id: i64) -> Result<PendingChange> {
+        let change = ChangeQueue::for_workspace(&self.workspace)?.approve(id)?;
+        let path = self.workspace.join(&change.path);
+        self.record_write(&path, &change.tool, "approved");
+        if path.extension().is_some_and(|ext| ext == "md") {
+            self.index.index_file(&path, false)?;
+        }
+        Ok(change)
+    }
+
+    /// Drop staged change `id`
+    pub fn reject_change(&self, id: i64) -> Result<PendingChange> {
+        ChangeQueue::for_workspace(&self.workspace)?.reject(id)
+    }
+
+    fn proposal_store(&self) -> proposals::ProposalStore {
+        proposals::ProposalStore::for_index(&self.db_path)
+    }
+
+    /// Messages of `session_id` already read for facts to propose
+    pub fn proposals_read(&self, session_id: &str) -> Result<usize> {
+        self.proposal_store().read_count(session_id)
+    }
+
+    /// Queue facts from a conversation for review, recording that the first
+    /// `read` messages of `session_id` have been read. Returns the new ones.
+    pub fn propose_facts(
+        &self,
+        session_id: &str,
+        read: usize,
+        facts: &[String],
+    ) -> Result<Vec<Proposal>> {
+        self.proposal_store().propose(session_id, read, facts)
+    }
+
+    /// Facts waiting for review, oldest first
+    pub fn proposals(&self) -> Result<Vec<Proposal>> {
+        self.proposal_store().pending()
+    }
+
+    /// Write the proposal `id` (or a unique prefix) to [`REMEMBERED_PATH`]
+    /// and index it as user-stated. `None` if no proposal matches.
+    pub fn approve_proposal(&self, id: &str) -> Result<Option<Proposal>> {
+        let Some(proposal) = self.proposal_store().take(id)? else {
+            return Ok(None);
+        };
+        let path = proposals::remember(&self.workspace, &proposal.fact, proposal.created)?;
+        self.record_write(&path, "memory_review", "user-stated");
+        self.index.index_file(&path, false)?;
+        Ok(Some(proposal))
+    }
+
+    /// Drop the proposal `id` (or a unique prefix) without writing it
+    pub fn reject_proposal(&self, id: &str) -> Result<Option<Proposal>> {
+        self.proposal_store().take(id)
+    }
+
+    /// Save a document's text (e.g. an extracted PDF attachment) under
+    /// [`DOCUMENTS_DIR`] and index it, so its chunks cite that file
+    /// (`Provenance::FileContent`). `source` is the original file.
+    pub fn save_document(
+        &self,
+        name: &str,
+        source: &std::path::Path,
+        text: &str,
+    ) -> Result<PathBuf> {
+        let stem = std::path::Path::new(name)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let slug = stem
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("-");
+        let slug = if slug.is_empty() {
+            "document".to_string()
+        } else {
+            slug
+        };
+        let path = self
+            .workspace
+            .join(DOCUMENTS_DIR)
+            .join(format!("{}.md", slug));
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let source = format!("file:{}", source.display());
+        let mut meta = serde_yaml::Mapping::new();
+        meta.insert("source".into(), source.clone().into());
+        meta.insert("imported".into(), locale::now().to_rfc3339().into());
+        let body = format!("\n# {}\n\n{}\n", name, text.trim());
+        fs::write(&path, frontmatter::render_frontmatter(&meta, &body)?)?;
+
+        self.record_write(&path, "attachment", &source);
+        self.index.index_file(&path, false)?;
+        Ok(path)
+    }
+
+    /// Regenerate the health report if it is older than `interval`,
+    /// returning the new report
+    pub fn refresh_health_report(&self, interval: Duration) -> Result<Option<HealthReport>> {
+        let path = self.workspace.join(HEALTH_REPORT_PATH);
+        if let Some(generated) = health::last_generated(&path) {
+            let age = chrono::Utc::now().signed_duration_since(generated);
+            if age.to_std().unwrap_or_default() < interval {
+                return Ok(None);
+            }
+        }
+
+        let report = self.health_report()?;
+        self.write_health_report(&report)?;
+        info!("{}", report.summary());
+        Ok(Some(report))
+    }
+
+    /// Entities linked to `entity` in the graph built at index time, up to
+    /// `depth` hops away
+    pub fn graph_neighbors(
+        &self,
+        entity: &str,
+        depth: usize,
+        limit: usize,
+    ) -> Result<Vec<GraphEdge>> {
+        self.index.graph_neighbors(entity, depth, limit)
+    }
+
+    /// Dates written in memory between `from` and `to`, plus daily logs
+    /// from those days with `logs`
+    pub fn dates_between(
+        &self,
+        from: chrono::NaiveDate,
+        to: chrono::NaiveDate,
+        logs: bool,
+    ) -> Result<Vec<DatedEntry>> {
+        self.index.dates_between(from, to, logs)
+    }
+
+    /// Chunk a `[VERIFIED:<hash>]` citation points at, if it's still indexed
+    pub fn cited_chunk(&self, hash_prefix: &str) -> Result<Option<IndexedChunk>> {
+        Ok(self
+            .index
+            .chunks_by_hash_prefix(hash_prefix)?
+            .into_iter()
+            .next())
+    }
+
+    /// Verify the chunks a citation hash prefix points at against their
+    /// stored hashes
+    pub fn verify_citation(&self, hash_prefix: &str) -> Result<Vec<VerifiedChunk>> {
+        let chunks: Vec<MemoryChunk> = self
+            .index
+            .chunks_by_hash_prefix(hash_prefix)?
+            .into_iter()
+            .map(|chunk| MemoryChunk {
+                chunk_id: Some(chunk.id),
+                file: chunk.path,
+                line_start: chunk.line_start,
+                line_end: chunk.line_end,
+                content: chunk.text,
+                score: 1.0,
+                category: None,
+                last_verified: None,
+            })
+            .collect();
+        Ok(self.verify_results(&chunks))
+    }
+
+    /// Chunks that fail hash verification, as (location, reason)
+    pub fn unverifiable_chunks(&self) -> Result<Vec<(String, String)>> {
+        self.index.verifier().unverifiable_chunks()
+    }
+
+    /// Verification hashes left without a chunk, as (path, chunk id)
+    pub fn orphaned_hashes(&self) -> Result<Vec<(String, String)>> {
+        self.index.verifier().orphaned_hashes()
+    }
+
+    /// Verification hash counts, by provenance
+    pub fn verification_stats(&self) -> Result<VerificationStats> {
+        self.index.verifier().stats()
+    }
+
+    /// Forget everything matching a text pattern or chunk hash prefix.
+    ///
+    /// Without `apply` only the matching chunks are reported. With it the
+    /// source lines, chunks, embeddings and hashes are deleted, the index is
+    /// checked for leftovers and the deletion is written to the audit log.
+    pub fn forget(&self, target: &str, apply: bool) -> Result<ForgetReport> {
+        // Line ranges must match what is on disk before editing files
+        self.reindex(false)?;
+
+        let (target, chunks) = forget::find_matches(&self.index, &ForgetTarget::parse(target))?;
+        if !apply || chunks.is_empty() {
+            return Ok(ForgetReport {
+                matches: chunks.iter().map(|c| c.location()).collect(),
+                ..Default::default()
+            });
+        }
+
+        forget::forget(&self.workspace, &self.index, &target, &chunks)
+    }
+
+    /// Snapshot the index and workspace by hand
+    pub fn snapshot(&self, reason: &str) -> Result<SnapshotInfo> {
+        let snapshots = Snapshots::new(&self.workspace, &self.db_path);
+        self.index.take_snapshot(&snapshots, reason, false)
+    }
+
+    /// Every snapshot of this memory, newest first
+    pub fn snapshots(&self) -> Result<Vec<SnapshotInfo>> {
+        Snapshots::new(&self.workspace, &self.db_path).list()
+    }
+
+    /// Snapshot before `operation`, if `memory.auto_snapshot` is on, and
+    /// prune old automatic snapshots. Fails if the snapshot can't be taken,
+    /// so the operation doesn't run without one.
+    fn auto_snapshot(&self, operation: &str) -> Result<()> {
+        if !self.config.auto_snapshot {
+            return Ok(());
+        }
+        let snapshots = Snapshots::new(&self.workspace, &self.db_path);
+        self.index
+            .take_snapshot(&snapshots, operation, true)
+            .with_context(|| {
+                format!(
+                    "Failed to snapshot memory before {} (set memory.auto_snapshot = false to skip)",
+                    operation
+                )
+            })?;
+        snapshots.prune(self.config.keep_snapshots)?;
+        Ok(())
+    }
+
+    /// Put back the index and workspace from a snapshot. Like
+    /// [`Self::import`], runs before the index database is opened.
+    pub fn restore_snapshot(
+        memory_config: &MemoryConfig,
+        agent_id: &str,
+        id: &str,
+    ) -> Result<RestoreReport> {
+        let (workspace, db_path) = Self::storage_paths(memory_config, agent_id)?;
+        Snapshots::new(&workspace, &db_path).restore(id)
+    }
+
+    /// Snapshot the closed index and workspace before `operation` on them
+    /// from outside (e.g. restoring a backup), if `memory.auto_snapshot`
+    /// is on
+    pub fn snapshot_closed(
+        memory_config: &MemoryConfig,
+        agent_id: &str,
+        operation: &str,
+    ) -> Result<()> {
+        let (workspace, db_path) = Self::storage_paths(memory_config, agent_id)?;
+        auto_snapshot_closed(memory_config, &workspace, &db_path, operation)
+    }
+
+    /// Find near-duplicate chunks and, if `apply` is set, merge them.
+    ///
+    /// Requires embeddings; call `generate_embeddings` first so new chunks
+    /// are included. Without `apply` this is a dry run that only reports.
+    pub fn consolidate(&self, threshold: f32, apply: bool) -> Result<ConsolidationReport> {
+        let Some(provider) = self.embedding_provider() else {
+            anyhow::bail!("Memory consolidation requires an embedding provider");
+        };
+
+        let chunks = self.index.embedded_chunks(provider.model())?;
+        let groups = consolidate::find_duplicate_groups(&chunks, threshold);
+
+        let mut report = ConsolidationReport {
+            threshold,
+            chunks_scanned: chunks.len(),
+            groups,
+            ..Default::default()
+        };
+
+        if apply && !report.groups.is_empty() {
+            self.auto_snapshot("consolidate")?;
+            let (changed, removed) =
+                consolidate::apply_groups(&self.workspace, &self.index, &report.groups)?;
+            report.files_changed = changed;
+            report.files_removed = removed;
+            report.applied = true;
+            self.reindex(false)?;
+        }
+
+        info!(
+            "Consolidation: {} groups, {} duplicates (applied: {})",
+            report.groups.len(),
+            report.duplicate_count(),
+            report.applied
+        );
+
+        Ok(report)
+    }
+}
+
+/// Snapshot a closed index before `operation`, if `memory.auto_snapshot` is
+/// on (see [`MemoryManager::auto_snapshot`])
+fn auto_snapshot_closed(
+    memory_config: &MemoryConfig,
+    workspace: &std::path::Path,
+    db_path: &std::path::Path,
+    operation: &str,
+) -> Result<()> {
+    if !memory_config.auto_snapshot {
+        return Ok(());
+    }
+    let snapshots = Snapshots::new(workspace, db_path);
+    snapshots.take_closed(operation, true).with_context(|| {
+        format!(
+            "Failed to snapshot memory before {} (set memory.auto_snapshot = false to skip)",
+            operation
+        )
+    })?;
+    snapshots.prune(memory_config.keep_snapshots)?;
+    Ok(())
+}
+
+/// `memory.embedding_model` for an API provider, or the provider's default
+/// when it is unset or still the local default
+fn api_embedding_model<'a>(memory_config: &'a MemoryConfig, default: &'a str) -> &'a str {
+    let model = memory_config.embedding_model.as_str();
+    if model.is_empty() || model == MemoryConfig::default().embedding_model {
+        default
+    } else {
+        model
+    }
 }
@@ -0,0 +1,514 @@
+//! Import and export of OpenClaw's on-disk memory layout.
+//!
+//! OpenClaw keeps markdown in `<state>/workspace` and its index in
+//! `<state>/memory/<agent>.sqlite`, using the same `meta`, `files`, `chunks`,
+//! `chunks_fts` and `embedding_cache` tables as homegpt. Chunks, embeddings
+//! and file hashes therefore copy across as-is; what needs adapting is:
+//!
+//! - `chunk_hashes` (verification hashes and provenance) is homegpt-only. It
+//!   is exported as `homegpt_chunk_hashes`, which OpenClaw ignores, and
+//!   restored on import for every chunk whose text still matches its hash.
+//!   Chunks OpenClaw created or changed get a fresh file-content hash.
+//! - `memory_audit` stays local, and `chunks_vec` is dropped because each
+//!   side sizes its vector table for its own embedding model.
+//!
+//! Callers should hold the workspace lock, and OpenClaw should not be
+//! running while its index is replaced.
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use super::archive::{sha256_bytes, workspace_files};
+use super::db::{rename_database, Database};
+use super::embeddings::hash_text;
+use super::frontmatter::{get_str, split_frontmatter};
+use super::index::MemoryIndex;
+use super::quantize::{decode_embedding, EmbeddingStorage};
+use super::verification::{compute_chunk_hash, ChunkVerifier, Provenance};
+
+/// Name of the exported verification table inside an OpenClaw index
+const HASHES_TABLE: &str = "homegpt_chunk_hashes";
+/// `meta` key describing the homegpt export that wrote an index
+const EXPORT_META_KEY: &str = "homegpt_export";
+
+#[derive(Debug, Default)]
+pub struct OpenClawReport {
+    /// Index database that was written
+    pub index_path: PathBuf,
+    pub files_written: usize,
+    pub files_unchanged: usize,
+    pub chunks: usize,
+    /// Chunks whose homegpt verification hash and provenance round-tripped
+    pub hashes_restored: usize,
+    /// Chunks given a new file-content verification hash (import only)
+    pub hashes_recorded: usize,
+    /// Files whose chunk hashes didn't match and will be re-chunked on the
+    /// next reindex (import only)
+    pub stale_files: usize,
+    /// Previous index database, if one was replaced
+    pub index_backup: Option<PathBuf>,
+}
+
+/// Index database of `agent_id` inside an OpenClaw state directory
+pub fn index_path(state_dir: &Path, agent_id: &str) -> PathBuf {
+    state_dir
+        .join("memory")
+        .join(format!("{}.sqlite", agent_id))
+}
+
+/// Copy the workspace and index into an OpenClaw state directory
+pub fn export(
+    workspace: &Path,
+    index: &MemoryIndex,
+    state_dir: &Path,
+    agent_id: &str,
+    force: bool,
+) -> Result<OpenClawReport> {
+    let dest_db = index_path(state_dir, agent_id);
+    ensure_distinct(index.db_path(), &dest_db)?;
+    if let Some(parent) = dest_db.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let staged = dest_db.with_extension("sqlite.tmp");
+    let _ = fs::remove_file(&staged);
+    index.snapshot_to(&staged)?;
+
+    let result = prepare_export(&staged).and_then(|(chunks, hashes)| {
+        let (files_written, files_unchanged) =
+            copy_tree(workspace, &state_dir.join("workspace"), force)?;
+        let index_backup = install_db(&staged, &dest_db)?;
+        Ok(OpenClawReport {
+            index_path: dest_db.clone(),
+            files_written,
+            files_unchanged,
+            chunks,
+            hashes_restored: hashes,
+            index_backup,
+            ..Default::default()
+        })
+    });
+    let _ = fs::remove_file(&staged);
+
+    if let Ok(ref report) = result {
+        info!(
+            "Exported {} files and {} chunks to {}",
+            report.files_written + report.files_unchanged,
+            report.chunks,
+            state_dir.display()
+        );
+    }
+    result
+}
+
+/// Turn a snapshot of the homegpt index into an OpenClaw index, returning
+/// the number of chunks and verification hashes it carries
+fn prepare_export(db: &Path) -> Result<(usize, usize)> {
+    let conn = Connection::open(db)?;
+
+    if table_exists(&conn, "chunks_vec")? {
+        MemoryIndex::try_load_sqlite_vec(&conn);
+        conn.execute("DROP TABLE chunks_vec", [])
+            .context("sqlite-vec must be loadable to drop the vector table")?;
+    }
+    conn.execute_batch(&format!(
+        "DROP TABLE IF EXISTS memory_audit;
+         DROP TABLE IF EXISTS {table};
+         ALTER TABLE chunk_hashes RENAME TO {table};",
+        table = HASHES_TABLE
+    ))?;
+
+    // OpenClaw only reads JSON float arrays
+    for table in ["chunks", "embedding_cache"] {
+        dequantize_embeddings(&conn, table)?;
+    }
+
+    let chunks: i64 = conn.query_row("SELECT COUNT(*) FROM chunks", [], |row| row.get(0))?;
+    let hashes: i64 = conn.query_row(
+        &format!("SELECT COUNT(*) FROM {}", HASHES_TABLE),
+        [],
+        |row| row.get(0),
+    )?;
+    conn.execute(
+        "INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)",
+        params![
+            EXPORT_META_KEY,
+            json!({
+                "homegpt_version": env!("CARGO_PKG_VERSION"),
+                "exported_at": chrono::Utc::now().to_rfc3339(),
+            })
+            .to_string()
+        ],
+    )?;
+    conn.execute_batch("VACUUM")?;
+
+    Ok((chunks as usize, hashes as usize))
+}
+
+/// Rewrite quantized blob embeddings in `table` as JSON float arrays
+fn dequantize_embeddings(conn: &Connection, table: &str) -> Result<()> {
+    let rows: Vec<(i64, Vec<f32>)> = {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT rowid, embedding FROM {} WHERE typeof(embedding) = 'blob'",
+            table
+        ))?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get(0)?, decode_embedding(row.get_ref(1)?)))
+        })?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+    for (rowid, embedding) in rows {
+        conn.execute(
+            &format!("UPDATE {} SET embedding = ?1 WHERE rowid = ?2", table),
+            params![EmbeddingStorage::Float.encode(&embedding), rowid],
+        )?;
+    }
+    Ok(())
+}
+
+/// Copy an OpenClaw workspace and index into `workspace` and `db_path`.
+///
+/// Existing workspace files that differ are only overwritten with `force`.
+/// A replaced index is kept as `<db>.bak`.
+pub fn import(
+    state_dir: &Path,
+    agent_id: &str,
+    workspace: &Path,
+    db_path: &Path,
+    force: bool,
+) -> Result<OpenClawReport> {
+    let source_db = index_path(state_dir, agent_id);
+    if !source_db.exists() {
+        anyhow::bail!("No OpenClaw index at {}", source_db.display());
+    }
+    ensure_distinct(&source_db, db_path)?;
+    if let Some(parent) = db_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let staged = db_path.with_extension("sqlite.tmp");
+    let _ = fs::remove_file(&staged);
+    let source_workspace = state_dir.join("workspace");
+
+    let result = snapshot_readonly(&source_db, &staged)
+        .and_then(|_| prepare_import(&staged, &source_workspace))
+        .and_then(|mut report| {
+            let (written, unchanged) = copy_tree(&source_workspace, workspace, force)?;
+            report.files_written = written;
+            report.files_unchanged = unchanged;
+            report.index_backup = install_db(&staged, db_path)?;
+            report.index_path = db_path.to_path_buf();
+            Ok(report)
+        });
+    let _ = fs::remove_file(&staged);
+
+    if let Ok(ref report) = result {
+        info!(
+            "Imported {} chunks from {} ({} hashes restored, {} recorded)",
+            report.chunks,
+            state_dir.display(),
+            report.hashes_restored,
+            report.hashes_recorded
+        );
+    }
+    result
+}
+
+/// Copy a database that may be open elsewhere without writing to it
+fn snapshot_readonly(source: &Path, dest: &Path) -> Result<()> {
+    let conn = Connection::open_with_flags(source, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .with_context(|| format!("Failed to open {}", source.display()))?;
+    MemoryIndex::try_load_sqlite_vec(&conn);
+    conn.execute("VACUUM INTO ?1", params![dest.to_string_lossy()])?;
+    Ok(())
+}
+
+/// Turn a copy of an OpenClaw index into a homegpt index.
+///
+/// `source_workspace` is read for frontmatter so existing chunks don't have
+/// to be re-chunked just to fill in category and last_verified.
+fn prepare_import(db: &Path, source_workspace: &Path) -> Result<OpenClawReport> {
+    let conn = Connection::open(db)?;
+
+    // OpenClaw's vector table is sized for its model; homegpt recreates its own
+    if table_exists(&conn, "chunks_vec")? && MemoryIndex::try_load_sqlite_vec(&conn) {
+        conn.execute("DROP TABLE chunks_vec", [])?;
+    }
+
+    let mut report = OpenClawReport::default();
+    let paths: Vec<String> = conn
+        .prepare("SELECT DISTINCT path FROM chunks")?
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    add_frontmatter_columns(&conn, source_workspace, &paths)?;
+
+    let has_hashes = table_exists(&conn, HASHES_TABLE)?;
+    let sql = if has_hashes {
+        format!(
+            "SELECT c.id, c.path, c.text, c.hash, h.path, h.hash, h.timestamp
+             FROM chunks c LEFT JOIN {} h ON h.chunk_id = c.id",
+            HASHES_TABLE
+        )
+    } else {
+        "SELECT id, path, text, hash, NULL, NULL, NULL FROM chunks".to_string()
+    };
+    #[allow(clippy::type_complexity)]
+    let rows: Vec<(
+        String,
+        String,
+        String,
+        String,
+        Option<String>,
+        Option<String>,
+        Option<String>,
+    )> = conn
+        .prepare(&sql)?
+        .query_map([], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+                row.get(5)?,
+                row.get(6)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+
+    let db = Database::single(conn)?;
+    let verifier = ChunkVerifier::new(db.clone())?;
+    let mut stale_paths = Vec::new();
+    for (id, path, text, content_hash, hash_path, hash, timestamp) in rows {
+        report.chunks += 1;
+
+        // Chunks that don't match their content hash are re-chunked later
+        if hash_text(&text) != content_hash {
+            if !stale_paths.contains(&path) {
+                stale_paths.push(path);
+            }
+            continue;
+        }
+
+        let verified = match (hash_path, hash, timestamp) {
+            (Some(hash_path), Some(hash), Some(ts)) => {
+                hash_path == path && compute_chunk_hash(&path, &text, &ts) == hash
+            }
+            _ => false,
+        };
+        if verified {
+            let conn = db.write();
+            conn.execute(
+                &format!(
+                    r#"INSERT OR REPLACE INTO chunk_hashes
+                       (chunk_id, path, hash, timestamp, provenance, access_count, last_accessed, created_at)
+                       SELECT chunk_id, path, hash, timestamp, provenance, access_count, last_accessed, created_at
+                       FROM {} WHERE chunk_id = ?1"#,
+                    HASHES_TABLE
+                ),
+                params![id],
+            )?;
+            report.hashes_restored += 1;
+        } else {
+            let provenance = Provenance::FileContent { path: path.clone() };
+            verifier.record_hash(&id, &path, &text, &provenance)?;
+            report.hashes_recorded += 1;
+        }
+    }
+
+    let conn = db.write();
+    for path in &stale_paths {
+        conn.execute("UPDATE files SET hash = '' WHERE path = ?1", params![path])?;
+    }
+    report.stale_files = stale_paths.len();
+    conn.execute(&format!("DROP TABLE IF EXISTS {}", HASHES_TABLE), [])?;
+    conn.execute("DELETE FROM meta WHERE key = ?1", params![EXPORT_META_KEY])?;
+
+    Ok(report)
+}
+
+/// Add homegpt's frontmatter columns to an OpenClaw chunks table, filling
+/// them from the files on disk
+fn add_frontmatter_columns(conn: &Connection, workspace: &Path, paths: &[String]) -> Result<()> {
+    for column in ["category", "last_verified"] {
+        let sql = format!("SELECT {} FROM chunks LIMIT 0", column);
+        if conn.prepare(&sql).is_err() {
+            conn.execute(
+                &format!("ALTER TABLE chunks ADD COLUMN {} TEXT", column),
+                [],
+            )?;
+        }
+    }
+
+    for path in paths {
+        let Ok(content) = fs::read_to_string(workspace.join(path)) else {
+            continue;
+        };
+        let (Some(frontmatter), _) = split_frontmatter(&content) else {
+            continue;
+        };
+        let category = get_str(&frontmatter, "category")
+            .map(|c| c.trim().to_lowercase())
+            .filter(|c| !c.is_empty());
+        let last_verified = get_str(&frontmatter, "last_verified").filter(|v| !v.trim().is_empty());
+        conn.execute(
+            "UPDATE chunks SET category = ?1, last_verified = ?2 WHERE path = ?3",
+            params![category, last_verified, path],
+        )?;
+    }
+    Ok(())
+}
+
+/// Copy every workspace file from `source` to `dest`, returning
+/// (written, unchanged). Nothing is written if a differing file would be
+/// overwritten without `force`.
+fn copy_tree(source: &Path, dest: &Path, force: bool) -> Result<(usize, usize)> {
+    if !source.is_dir() {
+        return Ok((0, 0));
+    }
+
+    let mut unchanged = 0;
+    let mut to_write = Vec::new();
+    let mut conflicts = Vec::new();
+    for relative in workspace_files(source)? {
+        let content = fs::read(source.join(&relative))?;
+        match fs::read(dest.join(&relative)) {
+            Ok(existing) if sha256_bytes(&existing) == sha256_bytes(&content) => unchanged += 1,
+            Ok(_) => {
+                conflicts.push(relative.clone());
+                to_write.push(relative);
+            }
+            Err(_) => to_write.push(relative),
+        }
+    }
+    if !conflicts.is_empty() && !force {
+        anyhow::bail!(
+            "{} files in {} would be overwritten (e.g. {}); re-run with --force",
+            conflicts.len(),
+            dest.display(),
+            conflicts[..conflicts.len().min(3)].join(", ")
+        );
+    }
+
+    for relative in &to_write {
+        let target = dest.join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(source.join(relative), &target)?;
+    }
+    Ok((to_write.len(), unchanged))
+}
+
+/// Move a staged database into place, keeping any previous one as `.bak`
+fn install_db(staged: &Path, db_path: &Path) -> Result<Option<PathBuf>> {
+    let backup = if db_path.exists() {
+        let backup = db_path.with_extension("sqlite.bak");
+        rename_database(db_path, &backup)?;
+        Some(backup)
+    } else {
+        None
+    };
+    fs::rename(staged, db_path)?;
+    Ok(backup)
+}
+
+/// Refuse to copy an index onto itself (workspace already in OpenClaw's location)
+fn ensure_distinct(a: &Path, b: &Path) -> Result<()> {
+    let same = match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    };
+    if same {
+        anyhow::bail!(
+            "{} is already shared with OpenClaw; nothing to copy",
+            a.display()
+        );
+    }
+    Ok(())
+}
+
+fn table_exists(conn: &Connection, name: &str) -> Result<bool> {
+    Ok(conn
+        .query_row(
+            "SELECT 1 FROM sqlite_master WHERE name = ?1",
+            params![name],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_openclaw_roundtrip_keeps_hashes() -> Result<()> {
+        let home = TempDir::new()?;
+        let workspace = home.path().join("homegpt/workspace");
+        fs::create_dir_all(workspace.join("memory"))?;
+        fs::write(
+            workspace.join("MEMORY.md"),
+            "---\ncategory: Household\n---\nRecycling goes out on Mondays.\n",
+        )?;
+        fs::write(workspace.join("memory/2026-02-01.md"), "Fixed the gate.\n")?;
+
+        let db_path = home.path().join("homegpt/memory/main.sqlite");
+        let index = MemoryIndex::new_with_db_path(&workspace, &db_path)?;
+        index.index_file(&workspace.join("MEMORY.md"), false)?;
+        index.index_file(&workspace.join("memory/2026-02-01.md"), false)?;
+        index.record_audit("search", None, &json!({}))?;
+
+        let state_dir = home.path().join(".openclaw");
+        let report = export(&workspace, &index, &state_dir, "main", false)?;
+        assert_eq!((report.files_written, report.chunks), (2, 2));
+        assert_eq!(report.hashes_restored, 2);
+
+        // The exported index only has tables OpenClaw knows, plus ours
+        let exported = Connection::open(index_path(&state_dir, "main"))?;
+        assert!(!table_exists(&exported, "chunk_hashes")?);
+        assert!(!table_exists(&exported, "memory_audit")?);
+        assert!(table_exists(&exported, HASHES_TABLE)?);
+
+        // OpenClaw edits one chunk in place
+        exported.execute(
+            "UPDATE chunks SET text = 'Fixed the gate and the fence.', hash = ?1
+             WHERE path = 'memory/2026-02-01.md'",
+            params![hash_text("Fixed the gate and the fence.")],
+        )?;
+        drop(exported);
+
+        let dest = TempDir::new()?;
+        let dest_workspace = dest.path().join("workspace");
+        let dest_db = dest.path().join("memory/main.sqlite");
+        let report = import(&state_dir, "main", &dest_workspace, &dest_db, false)?;
+        assert_eq!(report.files_written, 2);
+        assert_eq!((report.hashes_restored, report.hashes_recorded), (1, 1));
+        assert_eq!(report.stale_files, 0);
+
+        let imported = MemoryIndex::new_with_db_path(&dest_workspace, &dest_db)?;
+        let results = imported.search("recycling", 5)?;
+        assert_eq!(results[0].category.as_deref(), Some("household"));
+        let chunk_id = results[0].chunk_id.clone().unwrap();
+        let original = index.verifier().get_chunk_info(&chunk_id)?.unwrap();
+        let restored = imported.verifier().get_chunk_info(&chunk_id)?.unwrap();
+        assert_eq!(
+            (original.0, original.1),
+            (restored.0, restored.1),
+            "verification hash and provenance should survive the round trip"
+        );
+
+        // Importing again is a no-op for files but keeps a backup of the index
+        let report = import(&state_dir, "main", &dest_workspace, &dest_db, false)?;
+        assert_eq!((report.files_written, report.files_unchanged), (0, 2));
+        assert!(report.index_backup.is_some());
+
+        Ok(())
+    }
+}
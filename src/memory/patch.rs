@@ -0,0 +1,186 @@
+//! Patches: the agent changes files with unified diffs, not whole rewrites.
+//!
+//! A rewrite of MEMORY.md from the model's memory of it silently drops
+//! whatever was edited by hand since it last read the file. A patch only
+//! touches the lines it names, and fails to apply if they've changed.
+//! Files are replaced atomically, and the agent's writes to workspace
+//! files keep the previous version under `~/.homegpt/backups/`.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where the previous version of a workspace file is kept, relative to the
+/// state directory
+const BACKUP_DIR: &str = "backups";
+
+/// Apply the unified diff `diff` to `original`. The hunk line counts may be
+/// off (models miscount them); the context and removed lines must match.
+pub fn apply_patch(original: &str, diff: &str) -> Result<String> {
+    let diff = normalize(diff);
+    let patch = diffy::Patch::from_str(&diff).context("Invalid unified diff")?;
+    if patch.hunks().is_empty() {
+        anyhow::bail!("The diff has no hunks (@@ ... @@ sections)");
+    }
+    diffy::apply(original, &patch).map_err(|e| {
+        anyhow::anyhow!(
+            "The diff doesn't apply ({}): its context and '-' lines must match the \
+             file exactly. Read the file again and make a new diff.",
+            e
+        )
+    })
+}
+
+/// Rewrite the hunk headers with the line counts of their hunks, and read
+/// blank lines inside a hunk as blank context lines
+fn normalize(diff: &str) -> String {
+    let mut out = Vec::new();
+    // The current hunk: its header's index in `out`, with the header's
+    // starts and the lines counted so far
+    let mut hunk: Option<Hunk> = None;
+
+    for line in diff.lines() {
+        if let Some(header) = line.strip_prefix("@@ ") {
+            if let Some(hunk) = hunk.take() {
+                hunk.finish(&mut out);
+            }
+            let mut ranges = header.split_whitespace();
+            let start = |range: Option<&str>, sign: char| {
+                range
+                    .and_then(|r| r.strip_prefix(sign))
+                    .and_then(|r| r.split(',').next())
+                    .filter(|start| start.parse::<usize>().is_ok())
+                    .unwrap_or("1")
+                    .to_string()
+            };
+            let old = start(ranges.next(), '-');
+            let new = start(ranges.next(), '+');
+            hunk = Some(Hunk {
+                header: out.len(),
+                old,
+                new,
+                removed: 0,
+                added: 0,
+            });
+            out.push(String::new());
+            continue;
+        }
+        let Some(ref mut hunk) = hunk else {
+            out.push(line.to_string());
+            continue;
+        };
+        let line = if line.is_empty() { " " } else { line };
+        match line.chars().next() {
+            Some(' ') => {
+                hunk.removed += 1;
+                hunk.added += 1;
+            }
+            Some('-') => hunk.removed += 1,
+            Some('+') => hunk.added += 1,
+            _ => {}
+        }
+        out.push(line.to_string());
+    }
+    if let Some(hunk) = hunk {
+        hunk.finish(&mut out);
+    }
+
+    let mut diff = out.join("\n");
+    diff.push('\n');
+    diff
+}
+
+struct Hunk {
+    header: usize,
+    old: String,
+    new: String,
+    removed: usize,
+    added: usize,
+}
+
+impl Hunk {
+    fn finish(self, out: &mut [String]) {
+        out[self.header] = format!(
+            "@@ -{},{} +{},{} @@",
+            self.old, self.removed, self.new, self.added
+        );
+    }
+}
+
+/// Where the previous version of the workspace file `relative` is kept
+pub(crate) fn backup_path(workspace: &Path, relative: &str) -> Option<PathBuf> {
+    Some(workspace.parent()?.join(BACKUP_DIR).join(relative))
+}
+
+/// Replace `path` with `content` without ever leaving half a file, first
+/// copying the current file to `backup` if given
+pub fn replace_file(path: &Path, content: &str, backup: Option<&Path>) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if let Some(backup) = backup.filter(|_| path.is_file()) {
+        if let Some(parent) = backup.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(path, backup)?;
+    }
+    // Written beside the file and renamed over it
+    let tmp = path.with_file_name(format!(
+        ".{}.tmp",
+        path.file_name().unwrap_or_default().to_string_lossy()
+    ));
+    fs::write(&tmp, content)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const MEMORY: &str = "# Family\n\n- Leo: 10\n- Mia: 7\n\n## Food\n- Mia: no peanuts\n";
+
+    #[test]
+    fn test_apply_patch() {
+        // Counts are wrong, the blank context line lost its space and the
+        // hunk is two lines off
+        let diff =
+            "--- a/MEMORY.md\n+++ b/MEMORY.md\n@@ -6,2 +6,2 @@\n-- Mia: 7\n+- Mia: 8\n\n ## Food";
+        let patched = apply_patch(MEMORY, diff).unwrap();
+        assert_eq!(
+            patched,
+            "# Family\n\n- Leo: 10\n- Mia: 8\n\n## Food\n- Mia: no peanuts\n"
+        );
+
+        // A line edited by hand since
+        let stale = "@@ -3,2 +3,2 @@\n-- Leo: 9\n+- Leo: 11\n - Mia: 7\n";
+        assert!(apply_patch(MEMORY, stale)
+            .unwrap_err()
+            .to_string()
+            .contains("doesn't apply"));
+        assert!(apply_patch(MEMORY, "just rewrite it").is_err());
+
+        // A new file
+        let new = "--- /dev/null\n+++ b/notes.md\n@@ -0,0 +1 @@\n+Tacos on Tuesday\n";
+        assert_eq!(apply_patch("", new).unwrap(), "Tacos on Tuesday\n");
+    }
+
+    #[test]
+    fn test_replace_file_with_backup() {
+        let dir = TempDir::new().unwrap();
+        let workspace = dir.path().join("workspace");
+        let path = workspace.join("MEMORY.md");
+        let backup = backup_path(&workspace, "MEMORY.md").unwrap();
+        assert_eq!(backup, dir.path().join("backups/MEMORY.md"));
+
+        // Nothing to back up yet
+        replace_file(&path, "one\n", Some(&backup)).unwrap();
+        assert!(!backup.exists());
+
+        replace_file(&path, "two\n", Some(&backup)).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "two\n");
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "one\n");
+        assert_eq!(fs::read_dir(&workspace).unwrap().count(), 1);
+    }
+}
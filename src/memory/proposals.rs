@@ -0,0 +1,225 @@
+//! Memory proposals: facts pulled from finished conversations, waiting for
+//! someone to review them.
+//!
+//! With `memory.extract_facts` on, the agent reads a session once it ends
+//! and proposes the durable facts in it ("Leo's piano recital moved to the
+//! 21st"). Proposals wait in `memory/{agentId}.proposals.json` next to the
+//! index until `homegpt memory review` approves or rejects them. Approved
+//! facts are appended to [`REMEMBERED_PATH`], whose frontmatter marks its
+//! chunks `Provenance::UserStated`.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::frontmatter::render_frontmatter;
+
+use crate::locale;
+
+/// Where approved facts go, relative to the workspace
+pub const REMEMBERED_PATH: &str = "memory/remembered.md";
+
+/// A fact waiting for review
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Proposal {
+    /// Short id for `memory review`
+    pub id: String,
+    pub fact: String,
+    /// Session the fact came from
+    pub session_id: String,
+    pub created: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Proposals {
+    #[serde(default)]
+    pending: Vec<Proposal>,
+    /// Messages already read, by session, so a session that carries on is
+    /// only read from where it left off
+    #[serde(default)]
+    read: BTreeMap<String, usize>,
+}
+
+/// Pending proposals for one agent
+pub struct ProposalStore {
+    path: PathBuf,
+}
+
+impl ProposalStore {
+    /// Store beside the index database `db_path`
+    pub fn for_index(db_path: &Path) -> Self {
+        Self {
+            path: db_path.with_extension("proposals.json"),
+        }
+    }
+
+    fn load(&self) -> Result<Proposals> {
+        match fs::read_to_string(&self.path) {
+            Ok(content) => Ok(serde_json::from_str(&content)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Proposals::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save(&self, proposals: &Proposals) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(proposals)?)?;
+        Ok(())
+    }
+
+    pub fn pending(&self) -> Result<Vec<Proposal>> {
+        Ok(self.load()?.pending)
+    }
+
+    /// Messages of `session_id` already read for facts
+    pub fn read_count(&self, session_id: &str) -> Result<usize> {
+        Ok(self.load()?.read.get(session_id).copied().unwrap_or(0))
+    }
+
+    /// Queue `facts` from `session_id`, whose first `read` messages have
+    /// now been read. Facts already pending are skipped.
+    pub fn propose(
+        &self,
+        session_id: &str,
+        read: usize,
+        facts: &[String],
+    ) -> Result<Vec<Proposal>> {
+        let mut proposals = self.load()?;
+        proposals.read.insert(session_id.to_string(), read);
+        let mut added = Vec::new();
+        for fact in facts.iter().map(|f| f.trim()).filter(|f| !f.is_empty()) {
+            let known = proposals
+                .pending
+                .iter()
+                .any(|p| p.fact.eq_ignore_ascii_case(fact));
+            if known {
+                continue;
+            }
+            let proposal = Proposal {
+                id: uuid::Uuid::new_v4().simple().to_string()[..8].to_string(),
+                fact: fact.to_string(),
+                session_id: session_id.to_string(),
+                created: Utc::now(),
+            };
+            proposals.pending.push(proposal.clone());
+            added.push(proposal);
+        }
+        self.save(&proposals)?;
+        Ok(added)
+    }
+
+    /// Remove the proposal `id` (or a unique prefix of it) from the queue
+    pub fn take(&self, id: &str) -> Result<Option<Proposal>> {
+        let mut proposals = self.load()?;
+        let matches: Vec<usize> = proposals
+            .pending
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p.id.starts_with(id))
+            .map(|(i, _)| i)
+            .collect();
+        match matches.as_slice() {
+            [] => Ok(None),
+            [i] => {
+                let proposal = proposals.pending.remove(*i);
+                self.save(&proposals)?;
+                Ok(Some(proposal))
+            }
+            _ => anyhow::bail!("'{}' matches more than one proposal", id),
+        }
+    }
+}
+
+/// Append `fact` to the remembered-facts file in `workspace`, creating it
+/// with the frontmatter that marks it user-stated. Returns the file.
+pub fn remember(workspace: &Path, fact: &str, created: DateTime<Utc>) -> Result<PathBuf> {
+    let path = workspace.join(REMEMBERED_PATH);
+    let line = format!(
+        "- {} _({})_\n",
+        fact.trim(),
+        locale::home_time(&created).format("%Y-%m-%d")
+    );
+    let mut content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let mut meta = serde_yaml::Mapping::new();
+            meta.insert("category".into(), "remembered".into());
+            meta.insert("provenance".into(), "user-stated".into());
+            render_frontmatter(
+                &meta,
+                "# Remembered\n\nFacts from conversations, approved with `homegpt memory review`.\n\n",
+            )?
+        }
+        Err(e) => return Err(e.into()),
+    };
+    if !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(&line);
+    fs::write(&path, content)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::frontmatter::{get_str, split_frontmatter};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_propose_and_take() {
+        let dir = TempDir::new().unwrap();
+        let store = ProposalStore::for_index(&dir.path().join("main.sqlite"));
+        let facts = vec![
+            "Leo's piano recital moved to the 21st".to_string(),
+            " ".to_string(),
+        ];
+
+        let added = store.propose("s1", 6, &facts).unwrap();
+        assert_eq!(added.len(), 1);
+        // Already pending
+        assert!(store
+            .propose(
+                "s1",
+                8,
+                &["leo's piano recital moved to the 21st".to_string()]
+            )
+            .unwrap()
+            .is_empty());
+        assert_eq!(store.read_count("s1").unwrap(), 8);
+        assert_eq!(store.read_count("s2").unwrap(), 0);
+
+        let id = &added[0].id;
+        assert_eq!(store.take(&id[..4]).unwrap().as_ref(), Some(&added[0]));
+        assert_eq!(store.take(id).unwrap(), None);
+        assert!(store.pending().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_remember() {
+        let dir = TempDir::new().unwrap();
+        let created = Utc::now();
+        remember(dir.path(), "Leo's recital is on the 21st", created).unwrap();
+        let path = remember(dir.path(), "Mia is allergic to peanuts", created).unwrap();
+
+        let content = fs::read_to_string(path).unwrap();
+        let (meta, body) = split_frontmatter(&content);
+        assert_eq!(
+            get_str(&meta.unwrap(), "provenance").as_deref(),
+            Some("user-stated")
+        );
+        assert!(body.contains("- Leo's recital is on the 21st _("));
+        assert!(body.trim_end().ends_with(&format!(
+            "- Mia is allergic to peanuts _({})_",
+            locale::home_time(&created).format("%Y-%m-%d")
+        )));
+    }
+}
@@ -0,0 +1,187 @@
+//! Compact storage for chunk embeddings.
+//!
+//! By default embeddings are stored as JSON float arrays (the format
+//! OpenClaw reads). With `memory.embedding_storage = "int8"` or `"binary"`
+//! they are stored as tagged blobs instead:
+//!
+//! ```text
+//! int8:   0x01 | dims (u32 LE) | scale (f32 LE) | dims x i8
+//! binary: 0x02 | dims (u32 LE) | ceil(dims / 8) bytes, one sign bit per dim
+//! ```
+//!
+//! Search decodes either form, so an index can hold a mix while it is being
+//! re-embedded. Quantized scores are approximate; with rescoring enabled the
+//! top candidates are re-ranked against exact vectors kept in the embedding
+//! cache.
+
+use rusqlite::types::{Value, ValueRef};
+
+use super::embeddings::{deserialize_embedding, normalize_embedding, serialize_embedding};
+
+/// Candidates re-scored exactly per requested result
+pub const RESCORE_FACTOR: usize = 4;
+
+const TAG_INT8: u8 = 1;
+const TAG_BINARY: u8 = 2;
+const HEADER_LEN: usize = 5;
+
+/// How chunk embeddings are stored in the index
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmbeddingStorage {
+    /// JSON float arrays (OpenClaw-compatible)
+    #[default]
+    Float,
+    /// One signed byte per dimension plus a scale (~10x smaller than JSON)
+    Int8,
+    /// One sign bit per dimension (~80x smaller than JSON)
+    Binary,
+}
+
+impl std::str::FromStr for EmbeddingStorage {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "float" | "json" | "" => Ok(Self::Float),
+            "int8" => Ok(Self::Int8),
+            "binary" | "bit" => Ok(Self::Binary),
+            other => anyhow::bail!(
+                "Unknown embedding storage '{}': expected float, int8 or binary",
+                other
+            ),
+        }
+    }
+}
+
+impl EmbeddingStorage {
+    /// Encode an embedding for the `embedding` column
+    pub fn encode(self, embedding: &[f32]) -> Value {
+        match self {
+            Self::Float => Value::Text(serialize_embedding(embedding)),
+            Self::Int8 => {
+                let max = embedding.iter().fold(0.0f32, |m, x| m.max(x.abs()));
+                let scale = if max > 0.0 { max / 127.0 } else { 1.0 };
+                let mut blob = header(TAG_INT8, embedding.len());
+                blob.extend_from_slice(&scale.to_le_bytes());
+                blob.extend(
+                    embedding
+                        .iter()
+                        .map(|x| (x / scale).round().clamp(-127.0, 127.0) as i8 as u8),
+                );
+                Value::Blob(blob)
+            }
+            Self::Binary => {
+                let mut blob = header(TAG_BINARY, embedding.len());
+                for bits in embedding.chunks(8) {
+                    let byte = bits
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, x)| **x > 0.0)
+                        .fold(0u8, |b, (i, _)| b | (1 << i));
+                    blob.push(byte);
+                }
+                Value::Blob(blob)
+            }
+        }
+    }
+}
+
+fn header(tag: u8, dims: usize) -> Vec<u8> {
+    let mut blob = Vec::with_capacity(HEADER_LEN + 4 + dims);
+    blob.push(tag);
+    blob.extend_from_slice(&(dims as u32).to_le_bytes());
+    blob
+}
+
+/// Decode a stored embedding (JSON or quantized blob) to a unit vector.
+/// Returns an empty vector for anything unreadable.
+pub fn decode_embedding(value: ValueRef<'_>) -> Vec<f32> {
+    match value {
+        ValueRef::Text(text) => deserialize_embedding(&String::from_utf8_lossy(text)),
+        ValueRef::Blob(blob) if blob.len() >= HEADER_LEN => {
+            let dims = stored_dims(blob);
+            let data = &blob[HEADER_LEN..];
+            match blob[0] {
+                TAG_INT8 if data.len() == 4 + dims => {
+                    let scale = f32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+                    normalize_embedding(data[4..].iter().map(|&b| b as i8 as f32 * scale).collect())
+                }
+                TAG_BINARY if data.len() == dims.div_ceil(8) => {
+                    let magnitude = 1.0 / (dims as f32).sqrt();
+                    (0..dims)
+                        .map(|i| {
+                            if data[i / 8] & (1 << (i % 8)) != 0 {
+                                magnitude
+                            } else {
+                                -magnitude
+                            }
+                        })
+                        .collect()
+                }
+                _ => Vec::new(),
+            }
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Dimension count of a stored embedding without fully decoding it
+pub fn embedding_dimensions(value: ValueRef<'_>) -> usize {
+    match value {
+        ValueRef::Blob(blob) if blob.len() >= HEADER_LEN => stored_dims(blob),
+        other => decode_embedding(other).len(),
+    }
+}
+
+fn stored_dims(blob: &[u8]) -> usize {
+    u32::from_le_bytes([blob[1], blob[2], blob[3], blob[4]]) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::embeddings::cosine_similarity;
+
+    fn decode(value: &Value) -> Vec<f32> {
+        decode_embedding(ValueRef::from(value))
+    }
+
+    #[test]
+    fn test_quantized_roundtrip() {
+        let embedding = normalize_embedding(vec![0.9, -0.3, 0.05, -0.6, 0.2, 0.0, 0.4, -0.1, 0.7]);
+
+        let float = EmbeddingStorage::Float.encode(&embedding);
+        assert!(matches!(float, Value::Text(_)));
+        assert_eq!(decode(&float), embedding);
+
+        let int8 = EmbeddingStorage::Int8.encode(&embedding);
+        let decoded = decode(&int8);
+        assert_eq!(decoded.len(), 9);
+        assert!(cosine_similarity(&embedding, &decoded) > 0.999);
+        assert_eq!(embedding_dimensions(ValueRef::from(&int8)), 9);
+
+        let binary = EmbeddingStorage::Binary.encode(&embedding);
+        let Value::Blob(ref blob) = binary else {
+            panic!("binary storage should be a blob");
+        };
+        assert_eq!(blob.len(), HEADER_LEN + 2);
+        let decoded = decode(&binary);
+        assert_eq!(decoded.len(), 9);
+        assert!(decoded[0] > 0.0 && decoded[1] < 0.0);
+        assert!(cosine_similarity(&embedding, &decoded) > 0.7);
+    }
+
+    #[test]
+    fn test_storage_from_str() {
+        assert_eq!(
+            "INT8".parse::<EmbeddingStorage>().unwrap(),
+            EmbeddingStorage::Int8
+        );
+        assert_eq!(
+            "float".parse::<EmbeddingStorage>().unwrap(),
+            EmbeddingStorage::Float
+        );
+        assert!("fp16".parse::<EmbeddingStorage>().is_err());
+        assert!(decode_embedding(ValueRef::Blob(&[9, 0])).is_empty());
+    }
+}
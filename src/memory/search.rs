@@ -22,6 +22,79 @@ pub struct MemoryChunk {
 
     /// Relevance score (higher is better)
     pub score: f64,
+
+    /// `category` from the file's frontmatter
+    #[serde(default)]
+    pub category: Option<String>,
+
+    /// `last_verified` from the file's frontmatter
+    #[serde(default)]
+    pub last_verified: Option<String>,
+}
+
+/// Retrieval expansion produced for a query before search
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryExpansion {
+    /// Alternative phrasings / synonyms, each searched with FTS alongside the original
+    pub rewrites: Vec<String>,
+    /// Hypothetical answer (HyDE), embedded together with the query for vector search
+    pub hypothetical: Option<String>,
+}
+
+impl QueryExpansion {
+    pub fn is_empty(&self) -> bool {
+        self.rewrites.is_empty() && self.hypothetical.is_none()
+    }
+}
+
+/// Retrieval method for a search
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SearchMode {
+    /// FTS and vector search combined, FTS only without embeddings
+    #[default]
+    Hybrid,
+    /// Vector search only (needs an embedding provider)
+    Semantic,
+    /// FTS only
+    Keyword,
+}
+
+/// Structured filters parsed out of a search query (e.g. `category:food`)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchFilter {
+    /// Match chunks in any of these categories (empty = no filter)
+    pub categories: Vec<String>,
+}
+
+impl SearchFilter {
+    /// Split `key:value` filter terms from a query, returning the remaining
+    /// free-text query and the parsed filter. Unknown keys stay in the query.
+    pub fn parse(query: &str) -> (String, Self) {
+        let mut filter = Self::default();
+        let mut terms = Vec::new();
+
+        for term in query.split_whitespace() {
+            match term.split_once(':') {
+                Some((key, value)) if key.eq_ignore_ascii_case("category") && !value.is_empty() => {
+                    filter
+                        .categories
+                        .extend(value.split(',').map(|c| c.trim().to_lowercase()));
+                }
+                _ => terms.push(term),
+            }
+        }
+
+        (terms.join(" "), filter)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.categories.is_empty()
+    }
+
+    /// JSON array of categories, bound as a SQL parameter for `json_each`
+    pub(crate) fn categories_json(&self) -> String {
+        serde_json::to_string(&self.categories).unwrap_or_else(|_| "[]".to_string())
+    }
 }
 
 impl MemoryChunk {
@@ -34,6 +107,8 @@ impl MemoryChunk {
             line_end,
             content,
             score,
+            category: None,
+            last_verified: None,
         }
     }
 
@@ -86,4 +161,20 @@ mod tests {
 
         assert_eq!(chunk.location(), "test.md:10");
     }
+
+    #[test]
+    fn test_search_filter_parse() {
+        let (query, filter) = SearchFilter::parse("pizza night category:Food");
+        assert_eq!(query, "pizza night");
+        assert_eq!(filter.categories, vec!["food"]);
+        assert_eq!(filter.categories_json(), r#"["food"]"#);
+
+        let (query, filter) = SearchFilter::parse("category:food,school http://x.y");
+        assert_eq!(query, "http://x.y");
+        assert_eq!(filter.categories, vec!["food", "school"]);
+
+        let (query, filter) = SearchFilter::parse("plain query");
+        assert_eq!(query, "plain query");
+        assert!(filter.is_empty());
+    }
 }
@@ -0,0 +1,345 @@
+//! Snapshots of the whole memory, to roll back a bad import or merge.
+//!
+//! A snapshot is a copy of the index database taken with SQLite's backup API
+//! plus a git tag on the workspace (committing any uncommitted changes
+//! first). They live next to the index:
+//!
+//! ```text
+//! memory/snapshots/<agent>/<id>/index.sqlite
+//! memory/snapshots/<agent>/<id>/snapshot.json    reason, time, tag, chunk count
+//! ```
+//!
+//! One is taken before every risky operation (schema migration,
+//! `memory consolidate --apply`, `memory import`) unless
+//! `memory.auto_snapshot` is off, and only the newest `memory.keep_snapshots`
+//! of those are kept. Restoring takes a snapshot of the current state first,
+//! so a restore can be undone too.
+
+use anyhow::{Context, Result};
+use rusqlite::{Connection, DatabaseName};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::{info, warn};
+
+use super::db::rename_database;
+
+use crate::locale;
+
+const SNAPSHOTS_DIR: &str = "snapshots";
+const INDEX_NAME: &str = "index.sqlite";
+const INFO_NAME: &str = "snapshot.json";
+
+/// Prefix of the workspace git tags
+const TAG_PREFIX: &str = "homegpt-snapshot-";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotInfo {
+    /// Time-based id, e.g. `20261018-143005`
+    pub id: String,
+    pub created_at: String,
+    /// Why it was taken ("manual", "consolidate", ...)
+    pub reason: String,
+    /// Taken before an operation rather than by hand; only these are pruned
+    pub auto: bool,
+    /// Git tag of the workspace, if it could be tagged
+    pub workspace_tag: Option<String>,
+    pub chunks: usize,
+}
+
+#[derive(Debug)]
+pub struct RestoreReport {
+    pub restored: SnapshotInfo,
+    /// Snapshot of the state before the restore
+    pub undo: Option<SnapshotInfo>,
+    /// The workspace was checked out from the snapshot's tag
+    pub workspace_restored: bool,
+}
+
+/// Snapshots of one agent's index and its workspace
+pub struct Snapshots {
+    dir: PathBuf,
+    workspace: PathBuf,
+    db_path: PathBuf,
+}
+
+impl Snapshots {
+    pub fn new(workspace: &Path, db_path: &Path) -> Self {
+        let agent = db_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| "main".to_string());
+        let dir = db_path
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join(SNAPSHOTS_DIR)
+            .join(agent);
+        Self {
+            dir,
+            workspace: workspace.to_path_buf(),
+            db_path: db_path.to_path_buf(),
+        }
+    }
+
+    /// Snapshot the index through `conn` and tag the workspace
+    pub fn take(&self, conn: &Connection, reason: &str, auto: bool) -> Result<SnapshotInfo> {
+        let now = locale::now();
+        let mut id = now.format("%Y%m%d-%H%M%S").to_string();
+        // Two snapshots in the same second (e.g. the one before a restore)
+        let mut n = 1;
+        while self.dir.join(&id).exists() {
+            n += 1;
+            id = format!("{}-{}", now.format("%Y%m%d-%H%M%S"), n);
+        }
+        let dir = self.dir.join(&id);
+        fs::create_dir_all(&dir)?;
+
+        conn.backup(DatabaseName::Main, dir.join(INDEX_NAME), None)
+            .context("Failed to back up the memory index")?;
+        let chunks: i64 = Connection::open(dir.join(INDEX_NAME))?
+            .query_row("SELECT COUNT(*) FROM chunks", [], |row| row.get(0))
+            .unwrap_or(0);
+
+        let tag = format!("{}{}", TAG_PREFIX, id);
+        let workspace_tag = match self.tag_workspace(&tag, reason) {
+            Ok(()) => Some(tag),
+            Err(e) => {
+                warn!("Snapshot {} has the index only: {}", id, e);
+                None
+            }
+        };
+
+        let info = SnapshotInfo {
+            id,
+            created_at: now.to_rfc3339(),
+            reason: reason.to_string(),
+            auto,
+            workspace_tag,
+            chunks: chunks as usize,
+        };
+        fs::write(dir.join(INFO_NAME), serde_json::to_string_pretty(&info)?)?;
+        info!("Took memory snapshot {} ({})", info.id, reason);
+        Ok(info)
+    }
+
+    /// Snapshot the index at `db_path` while it isn't open, if it exists
+    pub fn take_closed(&self, reason: &str, auto: bool) -> Result<Option<SnapshotInfo>> {
+        if !self.db_path.exists() {
+            return Ok(None);
+        }
+        let conn = Connection::open(&self.db_path)?;
+        self.take(&conn, reason, auto).map(Some)
+    }
+
+    /// Every snapshot, newest first
+    pub fn list(&self) -> Result<Vec<SnapshotInfo>> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Ok(Vec::new());
+        };
+        let mut snapshots: Vec<SnapshotInfo> = entries
+            .filter_map(|e| e.ok())
+            .filter_map(|e| fs::read_to_string(e.path().join(INFO_NAME)).ok())
+            .filter_map(|content| serde_json::from_str(&content).ok())
+            .collect();
+        snapshots.sort_by(|a, b| b.id.cmp(&a.id));
+        Ok(snapshots)
+    }
+
+    /// The snapshot `id` names, or the only one it's a prefix of
+    pub fn find(&self, id: &str) -> Result<SnapshotInfo> {
+        let matches: Vec<SnapshotInfo> = self
+            .list()?
+            .into_iter()
+            .filter(|s| s.id.starts_with(id))
+            .collect();
+        if let Some(exact) = matches.iter().find(|s| s.id == id) {
+            return Ok(exact.clone());
+        }
+        match matches.len() {
+            0 => anyhow::bail!(
+                "No memory snapshot '{}' (see `homegpt memory snapshot --list`)",
+                id
+            ),
+            1 => Ok(matches.into_iter().next().unwrap()),
+            n => anyhow::bail!("'{}' matches {} snapshots; give more of the id", id, n),
+        }
+    }
+
+    /// Put back the index and workspace from snapshot `id`. The index must
+    /// not be open; the current state is snapshotted first.
+    pub fn restore(&self, id: &str) -> Result<RestoreReport> {
+        let snapshot = self.find(id)?;
+        let undo = self.take_closed(&format!("before restoring {}", snapshot.id), true)?;
+        if snapshot.workspace_tag.is_some()
+            && undo.as_ref().is_none_or(|u| u.workspace_tag.is_none())
+        {
+            // Changes that aren't committed would be lost for good
+            commit_all(&self.workspace, "homegpt snapshot: before restore")
+                .context("Failed to commit the workspace before restoring it")?;
+        }
+
+        let workspace_restored = match snapshot.workspace_tag {
+            Some(ref tag) => {
+                // Tracked files the snapshot didn't have are removed too
+                git(
+                    &self.workspace,
+                    &[
+                        "restore",
+                        "--source",
+                        tag,
+                        "--staged",
+                        "--worktree",
+                        "--",
+                        ".",
+                    ],
+                )?;
+                commit_all(
+                    &self.workspace,
+                    &format!("homegpt restore: {}", snapshot.id),
+                )?;
+                true
+            }
+            None => false,
+        };
+
+        let index = self.dir.join(&snapshot.id).join(INDEX_NAME);
+        let staged = self.db_path.with_extension("sqlite.restore");
+        fs::copy(&index, &staged)?;
+        if self.db_path.exists() {
+            rename_database(&self.db_path, &self.db_path.with_extension("sqlite.bak"))?;
+        }
+        fs::rename(&staged, &self.db_path)?;
+
+        info!("Restored memory snapshot {}", snapshot.id);
+        Ok(RestoreReport {
+            restored: snapshot,
+            undo,
+            workspace_restored,
+        })
+    }
+
+    /// Delete all but the newest `keep` automatic snapshots, returning how
+    /// many were deleted. Snapshots taken by hand are kept.
+    pub fn prune(&self, keep: usize) -> Result<usize> {
+        let old: Vec<SnapshotInfo> = self
+            .list()?
+            .into_iter()
+            .filter(|s| s.auto)
+            .skip(keep)
+            .collect();
+        for snapshot in &old {
+            fs::remove_dir_all(self.dir.join(&snapshot.id))?;
+            if let Some(ref tag) = snapshot.workspace_tag {
+                if let Err(e) = git(&self.workspace, &["tag", "-d", tag]) {
+                    warn!("Failed to delete snapshot tag {}: {}", tag, e);
+                }
+            }
+        }
+        Ok(old.len())
+    }
+
+    /// Commit uncommitted workspace changes and tag the result
+    fn tag_workspace(&self, tag: &str, reason: &str) -> Result<()> {
+        if !self.workspace.join(".git").exists() {
+            git(&self.workspace, &["init", "--quiet"])?;
+        }
+        commit_all(&self.workspace, &format!("homegpt snapshot: {}", reason))?;
+        git(&self.workspace, &["tag", tag])?;
+        Ok(())
+    }
+}
+
+/// Stage and commit everything in the workspace, if anything changed
+fn commit_all(workspace: &Path, message: &str) -> Result<()> {
+    git(workspace, &["add", "-A"])?;
+    if git(workspace, &["status", "--porcelain"])?
+        .trim()
+        .is_empty()
+    {
+        return Ok(());
+    }
+    git(workspace, &["commit", "--quiet", "-m", message])?;
+    Ok(())
+}
+
+/// Run git in `workspace` and return stdout, failing on non-zero exit
+fn git(workspace: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(workspace)
+        .output()
+        .context("Failed to run git (is it installed?)")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn chunk_count(db_path: &Path) -> i64 {
+        Connection::open(db_path)
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM chunks", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_snapshot_and_restore() -> Result<()> {
+        let dir = TempDir::new()?;
+        let workspace = dir.path().join("workspace");
+        fs::create_dir_all(&workspace)?;
+        let db_path = dir.path().join("memory/main.sqlite");
+        fs::create_dir_all(db_path.parent().unwrap())?;
+        git(&workspace, &["init", "--quiet"])?;
+        git(&workspace, &["config", "user.email", "test@example.com"])?;
+        git(&workspace, &["config", "user.name", "Test"])?;
+
+        fs::write(workspace.join("MEMORY.md"), "Spare key is with Sam.\n")?;
+        {
+            let conn = Connection::open(&db_path)?;
+            conn.execute_batch("CREATE TABLE chunks (id TEXT); INSERT INTO chunks VALUES ('a');")?;
+        }
+
+        let snapshots = Snapshots::new(&workspace, &db_path);
+        let snapshot = snapshots.take_closed("manual", false)?.unwrap();
+        assert_eq!(snapshot.chunks, 1);
+        assert!(snapshot.workspace_tag.is_some());
+
+        // A bad import: a file rewritten, one added, the index changed
+        fs::write(workspace.join("MEMORY.md"), "Spare key is lost.\n")?;
+        fs::write(workspace.join("junk.md"), "Imported junk.\n")?;
+        Connection::open(&db_path)?.execute("INSERT INTO chunks VALUES ('b')", [])?;
+
+        let report = snapshots.restore(&snapshot.id[..8])?;
+        assert!(report.workspace_restored);
+        assert_eq!(
+            fs::read_to_string(workspace.join("MEMORY.md"))?,
+            "Spare key is with Sam.\n"
+        );
+        assert!(!workspace.join("junk.md").exists());
+        assert_eq!(chunk_count(&db_path), 1);
+
+        // The restore can be undone
+        let undo = report.undo.unwrap();
+        assert!(undo.auto);
+        assert_eq!(undo.chunks, 2);
+        snapshots.restore(&undo.id)?;
+        assert!(workspace.join("junk.md").exists());
+        assert_eq!(chunk_count(&db_path), 2);
+
+        // Only automatic snapshots are pruned
+        assert_eq!(snapshots.list()?.len(), 3);
+        assert_eq!(snapshots.prune(0)?, 2);
+        assert_eq!(snapshots.list()?.len(), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,374 @@
+//! Staged changes: the agent's edits to workspace files, held for review.
+//!
+//! With `memory.staging.enabled`, write_file, edit_file, patch_file and
+//! memory_store don't write inside the workspace. The new content and a
+//! unified diff go to the `pending_changes` table of `memory/changes.sqlite`
+//! (next to the index databases, so every agent and process shares one
+//! queue) until someone approves or rejects them with `homegpt memory
+//! changes` or the desktop app's Changes panel. Changes matching an
+//! auto-approve rule are written right away.
+//!
+//! Each change records the hash of the content it was made against: the
+//! file, or the change staged before it for the same file. Approving it
+//! after the file changed some other way fails instead of clobbering that
+//! edit.
+
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::audit::current_session;
+use super::db::Database;
+use super::embeddings::hash_text;
+use super::patch::{backup_path, replace_file};
+use crate::config::AutoApproveRule;
+
+/// What became of a file write
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileWrite {
+    Written,
+    /// Waiting for review as this change
+    Staged(i64),
+}
+
+/// A change waiting for review
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingChange {
+    pub id: i64,
+    /// Workspace-relative file
+    pub path: String,
+    /// Tool that made the change
+    pub tool: String,
+    pub session: Option<String>,
+    /// RFC 3339
+    pub created: String,
+    /// Unified diff against the content the change was made on
+    pub diff: String,
+}
+
+impl PendingChange {
+    /// e.g. "+3 -1"
+    pub fn line_counts(&self) -> String {
+        let count = |prefix: char, header: &str| {
+            self.diff
+                .lines()
+                .filter(|l| l.starts_with(prefix) && !l.starts_with(header))
+                .count()
+        };
+        format!("+{} -{}", count('+', "+++"), count('-', "---"))
+    }
+}
+
+/// The queue of changes waiting for review
+#[derive(Clone)]
+pub struct ChangeQueue {
+    db: Database,
+    workspace: PathBuf,
+}
+
+impl ChangeQueue {
+    /// Queue for `workspace`, in `memory/changes.sqlite` beside it
+    pub fn for_workspace(workspace: &Path) -> Result<Self> {
+        let state_dir = workspace
+            .parent()
+            .ok_or_else(|| anyhow::anyhow!("Workspace has no parent directory"))?;
+        let dir = state_dir.join("memory");
+        fs::create_dir_all(&dir)?;
+        Self::open(workspace, &dir.join("changes.sqlite"))
+    }
+
+    fn open(workspace: &Path, db_path: &Path) -> Result<Self> {
+        let conn = Connection::open(db_path)?;
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS pending_changes (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                created TEXT NOT NULL,
+                path TEXT NOT NULL,
+                tool TEXT NOT NULL,
+                session TEXT,
+                -- Hash of the content the change was made on ('' = no file)
+                base_hash TEXT NOT NULL,
+                content TEXT NOT NULL,
+                diff TEXT NOT NULL,
+                -- pending, approved or rejected
+                status TEXT NOT NULL DEFAULT 'pending',
+                decided TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_pending_changes_path ON pending_changes(path, status);
+            "#,
+        )?;
+        Ok(Self {
+            db: Database::single(conn)?,
+            workspace: workspace.to_path_buf(),
+        })
+    }
+
+    /// Content of the workspace file `path` as the agent last left it: its
+    /// newest pending change, otherwise the file (`None` if there's none)
+    pub fn current(&self, path: &str) -> Result<Option<String>> {
+        if let Some(content) = self.staged_content(path)? {
+            return Ok(Some(content));
+        }
+        read_optional(&self.workspace.join(path))
+    }
+
+    /// Newest pending content for `path`
+    fn staged_content(&self, path: &str) -> Result<Option<String>> {
+        let conn = self.db.read();
+        Ok(conn
+            .query_row(
+                "SELECT content FROM pending_changes
+                 WHERE path = ?1 AND status = 'pending' ORDER BY id DESC LIMIT 1",
+                params![path],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Whether `content` for `path` may be written without review
+    pub fn auto_approved(
+        &self,
+        rules: &[AutoApproveRule],
+        path: &str,
+        content: &str,
+    ) -> Result<bool> {
+        // Changes already waiting for the file go first
+        if self.staged_content(path)?.is_some() {
+            return Ok(false);
+        }
+        let current = read_optional(&self.workspace.join(path))?.unwrap_or_default();
+        Ok(auto_approve_matches(rules, path, &current, content))
+    }
+
+    /// Stage `content` for the workspace file `path`, returning the change id
+    pub fn stage(&self, path: &str, content: &str, tool: &str) -> Result<i64> {
+        let base = self.current(path)?;
+        let base_text = base.as_deref().unwrap_or("");
+        let diff = diffy::DiffOptions::new()
+            .set_original_filename(format!("a/{}", path))
+            .set_modified_filename(format!("b/{}", path))
+            .create_patch(base_text, content)
+            .to_string();
+        let base_hash = base.as_deref().map(hash_text).unwrap_or_default();
+
+        self.db.write_with(|conn| {
+            conn.execute(
+                "INSERT INTO pending_changes (created, path, tool, session, base_hash, content, diff)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    chrono::Utc::now().to_rfc3339(),
+                    path,
+                    tool,
+                    current_session(),
+                    base_hash,
+                    content,
+                    diff
+                ],
+            )?;
+            Ok(conn.last_insert_rowid())
+        })
+    }
+
+    /// Changes waiting for review, oldest first
+    pub fn pending(&self) -> Result<Vec<PendingChange>> {
+        let conn = self.db.read();
+        let mut stmt = conn.prepare(
+            "SELECT id, path, tool, session, created, diff FROM pending_changes
+             WHERE status = 'pending' ORDER BY id",
+        )?;
+        let changes = stmt
+            .query_map([], |row| {
+                Ok(PendingChange {
+                    id: row.get(0)?,
+                    path: row.get(1)?,
+                    tool: row.get(2)?,
+                    session: row.get(3)?,
+                    created: row.get(4)?,
+                    diff: row.get(5)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(changes)
+    }
+
+    fn get(&self, id: i64) -> Result<(PendingChange, String, String)> {
+        let conn = self.db.read();
+        conn.query_row(
+            "SELECT id, path, tool, session, created, diff, base_hash, content
+             FROM pending_changes WHERE id = ?1 AND status = 'pending'",
+            params![id],
+            |row| {
+                let change = PendingChange {
+                    id: row.get(0)?,
+                    path: row.get(1)?,
+                    tool: row.get(2)?,
+                    session: row.get(3)?,
+                    created: row.get(4)?,
+                    diff: row.get(5)?,
+                };
+                Ok((change, row.get(6)?, row.get(7)?))
+            },
+        )
+        .optional()?
+        .ok_or_else(|| anyhow::anyhow!("No pending change #{}", id))
+    }
+
+    /// Write change `id` to its file. Fails if the file no longer has the
+    /// content the change was made on.
+    pub fn approve(&self, id: i64) -> Result<PendingChange> {
+        let (change, base_hash, content) = self.get(id)?;
+        let path = self.workspace.join(&change.path);
+        let on_disk = read_optional(&path)?
+            .as_deref()
+            .map(hash_text)
+            .unwrap_or_default();
+        if on_disk != base_hash {
+            anyhow::bail!(
+                "{} changed since change #{} was made (by hand, or an earlier change \
+                 is still waiting); reject it, or approve the earlier change first",
+                change.path,
+                id
+            );
+        }
+
+        let backup = backup_path(&self.workspace, &change.path);
+        replace_file(&path, &content, backup.as_deref())?;
+
+        self.decide(id, "approved")?;
+        Ok(change)
+    }
+
+    /// Drop change `id` without writing it
+    pub fn reject(&self, id: i64) -> Result<PendingChange> {
+        let (change, ..) = self.get(id)?;
+        self.decide(id, "rejected")?;
+        Ok(change)
+    }
+
+    fn decide(&self, id: i64, status: &str) -> Result<()> {
+        self.db.write_with(|conn| {
+            conn.execute(
+                "UPDATE pending_changes SET status = ?1, decided = ?2 WHERE id = ?3",
+                params![status, chrono::Utc::now().to_rfc3339(), id],
+            )?;
+            Ok(())
+        })
+    }
+}
+
+/// Whether a rule lets `current` become `content` for `path` unreviewed
+fn auto_approve_matches(
+    rules: &[AutoApproveRule],
+    path: &str,
+    current: &str,
+    content: &str,
+) -> bool {
+    rules.iter().any(|rule| {
+        let prefix = rule.path.trim_matches('/');
+        let under = path == prefix
+            || path
+                .strip_prefix(prefix)
+                .is_some_and(|rest| rest.starts_with('/'));
+        under && (!rule.append_only || content.starts_with(current))
+    })
+}
+
+fn read_optional(path: &Path) -> Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn queue(dir: &TempDir) -> ChangeQueue {
+        let workspace = dir.path().join("workspace");
+        fs::create_dir_all(&workspace).unwrap();
+        ChangeQueue::for_workspace(&workspace).unwrap()
+    }
+
+    #[test]
+    fn test_stage_and_approve() -> Result<()> {
+        let dir = TempDir::new()?;
+        let queue = queue(&dir);
+        let file = dir.path().join("workspace/MEMORY.md");
+        fs::write(&file, "# Family\n- Leo: 9\n")?;
+
+        let first = queue.stage("MEMORY.md", "# Family\n- Leo: 10\n", "edit_file")?;
+        // Stacks on the first change
+        assert_eq!(
+            queue.current("MEMORY.md")?.as_deref(),
+            Some("# Family\n- Leo: 10\n")
+        );
+        let second = queue.stage("MEMORY.md", "# Family\n- Leo: 10\n- Mia: 7\n", "edit_file")?;
+        assert_eq!(fs::read_to_string(&file)?, "# Family\n- Leo: 9\n");
+
+        let pending = queue.pending()?;
+        assert_eq!(pending.len(), 2);
+        assert!(pending[0].diff.contains("--- a/MEMORY.md"));
+        assert!(pending[0].diff.contains("-- Leo: 9\n+- Leo: 10\n"));
+        assert_eq!(pending[1].line_counts(), "+1 -0");
+
+        // Out of order: the file doesn't have what the second change was made on
+        assert!(queue.approve(second).is_err());
+        queue.approve(first)?;
+        queue.approve(second)?;
+        assert_eq!(
+            fs::read_to_string(&file)?,
+            "# Family\n- Leo: 10\n- Mia: 7\n"
+        );
+        assert!(queue.pending()?.is_empty());
+        assert!(queue.approve(first).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_and_manual_edit() -> Result<()> {
+        let dir = TempDir::new()?;
+        let queue = queue(&dir);
+        let file = dir.path().join("workspace/memory/food/meal-plans.md");
+
+        let new_file = queue.stage("memory/food/meal-plans.md", "Tacos\n", "write_file")?;
+        queue.reject(new_file)?;
+        assert!(!file.exists());
+
+        let id = queue.stage("memory/food/meal-plans.md", "Pizza\n", "write_file")?;
+        fs::create_dir_all(file.parent().unwrap())?;
+        fs::write(&file, "Soup\n")?;
+        // Not clobbered
+        assert!(queue.approve(id).is_err());
+        assert_eq!(fs::read_to_string(&file)?, "Soup\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_auto_approve_rules() {
+        let rules = vec![AutoApproveRule {
+            path: "memory/school/tutor-notes.md".to_string(),
+            append_only: true,
+        }];
+        let path = "memory/school/tutor-notes.md";
+        assert!(auto_approve_matches(&rules, path, "a\n", "a\nb\n"));
+        assert!(!auto_approve_matches(&rules, path, "a\n", "b\n"));
+        assert!(!auto_approve_matches(&rules, "MEMORY.md", "", "a"));
+
+        let rules = vec![AutoApproveRule {
+            path: "memory/food/".to_string(),
+            append_only: false,
+        }];
+        assert!(auto_approve_matches(
+            &rules,
+            "memory/food/meal-plans.md",
+            "a",
+            "b"
+        ));
+        assert!(!auto_approve_matches(&rules, "memory/foodie.md", "a", "b"));
+    }
+}
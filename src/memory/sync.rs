@@ -0,0 +1,318 @@
+//! Workspace sync to a git remote.
+//!
+//! Commits local workspace changes, merges the configured remote branch and
+//! pushes the result, so the same memory is available on several machines
+//! (e.g. the desktop and a headless home server). Merge conflicts are
+//! resolved per file by keeping whichever side has the newest frontmatter
+//! timestamp (`updated`, `last_verified`, ...); ties keep the local copy.
+//!
+//! Optional `pre_sync` / `post_sync` shell hooks allow other sync tools
+//! (e.g. Syncthing `rescan`) to run around each sync.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use tracing::{debug, info, warn};
+
+use super::frontmatter::frontmatter_timestamp;
+use crate::concurrency::WorkspaceLock;
+use crate::config::SyncConfig;
+use crate::locale;
+
+/// Which side of a conflicting merge to keep
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictSide {
+    Ours,
+    Theirs,
+}
+
+/// Result of a single sync run
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    /// Local changes were committed before merging
+    pub committed: bool,
+    /// Remote changes were merged into the workspace
+    pub merged: bool,
+    /// Local commits were pushed to the remote
+    pub pushed: bool,
+    /// Files whose merge conflicts were resolved automatically
+    pub conflicts_resolved: Vec<String>,
+}
+
+pub struct WorkspaceSync {
+    workspace: PathBuf,
+    config: SyncConfig,
+}
+
+impl WorkspaceSync {
+    pub fn new(workspace: &Path, config: &SyncConfig) -> Self {
+        Self {
+            workspace: workspace.to_path_buf(),
+            config: config.clone(),
+        }
+    }
+
+    /// Commit, merge and push the workspace.
+    ///
+    /// Holds the workspace lock for the duration so agent turns never write
+    /// files mid-merge.
+    pub fn sync(&self) -> Result<SyncReport> {
+        let _lock = WorkspaceLock::new()?.acquire()?;
+
+        if let Some(ref hook) = self.config.pre_sync {
+            self.run_hook("pre_sync", hook)?;
+        }
+
+        self.ensure_repo()?;
+
+        let mut report = SyncReport {
+            committed: self.commit_local_changes()?,
+            ..Default::default()
+        };
+
+        if !self.config.remote.is_empty() {
+            let branch = &self.config.branch;
+            self.git(&["fetch", "--quiet", "origin"])
+                .context("Failed to fetch from sync remote")?;
+
+            // The remote branch may not exist yet (first push from this machine)
+            let remote_ref = format!("origin/{}", branch);
+            let remote_exists = self.git_ok(&["rev-parse", "--verify", "--quiet", &remote_ref])?;
+
+            if remote_exists && self.count_commits(&format!("HEAD..{}", remote_ref))? > 0 {
+                report.conflicts_resolved = self.merge(&remote_ref)?;
+                report.merged = true;
+            }
+
+            if !remote_exists || self.count_commits(&format!("{}..HEAD", remote_ref))? > 0 {
+                self.git(&["push", "origin", &format!("HEAD:{}", branch)])
+                    .with_context(|| format!("Failed to push to origin/{}", branch))?;
+                report.pushed = true;
+            }
+        }
+
+        if let Some(ref hook) = self.config.post_sync {
+            self.run_hook("post_sync", hook)?;
+        }
+
+        info!(
+            "Workspace sync complete (committed: {}, merged: {}, pushed: {}, conflicts: {})",
+            report.committed,
+            report.merged,
+            report.pushed,
+            report.conflicts_resolved.len()
+        );
+
+        Ok(report)
+    }
+
+    /// Initialize the workspace as a git repo and point `origin` at the remote
+    fn ensure_repo(&self) -> Result<()> {
+        if !self.workspace.join(".git").exists() {
+            info!("Initializing git repo in {}", self.workspace.display());
+            self.git(&["init", "--quiet"])?;
+            self.git(&["checkout", "--quiet", "-B", &self.config.branch])?;
+        }
+
+        if self.config.remote.is_empty() {
+            return Ok(());
+        }
+
+        match self.git(&["remote", "get-url", "origin"]) {
+            Ok(url) if url.trim() == self.config.remote => {}
+            Ok(_) => {
+                self.git(&["remote", "set-url", "origin", &self.config.remote])?;
+            }
+            Err(_) => {
+                self.git(&["remote", "add", "origin", &self.config.remote])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Stage and commit all local changes. Returns true if a commit was made.
+    fn commit_local_changes(&self) -> Result<bool> {
+        self.git(&["add", "-A"])?;
+        if self.git(&["status", "--porcelain"])?.trim().is_empty() {
+            debug!("No local workspace changes to commit");
+            return Ok(false);
+        }
+
+        let message = format!(
+            "homegpt sync: {} ({})",
+            locale::now().format("%Y-%m-%d %H:%M:%S"),
+            hostname()
+        );
+        self.git(&["commit", "--quiet", "-m", &message])?;
+        Ok(true)
+    }
+
+    /// Merge `remote_ref` into HEAD, resolving conflicts by newest frontmatter
+    fn merge(&self, remote_ref: &str) -> Result<Vec<String>> {
+        let output = self.git_output(&[
+            "merge",
+            "--no-edit",
+            "--quiet",
+            // A second machine starts with its own initial commit
+            "--allow-unrelated-histories",
+            remote_ref,
+        ])?;
+        if output.status.success() {
+            return Ok(Vec::new());
+        }
+
+        let conflicted = self.git(&["diff", "--name-only", "--diff-filter=U"])?;
+        let conflicted: Vec<String> = conflicted
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| l.to_string())
+            .collect();
+
+        if conflicted.is_empty() {
+            let _ = self.git(&["merge", "--abort"]);
+            anyhow::bail!(
+                "git merge failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        for path in &conflicted {
+            // Stage 2 is our version, stage 3 is theirs; either may be missing on delete
+            // Read as bytes so binary files are written back untouched
+            let ours = self.git_bytes(&["show", &format!(":2:{}", path)]).ok();
+            let theirs = self.git_bytes(&["show", &format!(":3:{}", path)]).ok();
+
+            let side = match (&ours, &theirs) {
+                (Some(o), Some(t)) => match (std::str::from_utf8(o), std::str::from_utf8(t)) {
+                    (Ok(o), Ok(t)) => pick_newest(o, t),
+                    // No frontmatter to compare in binary files
+                    _ => ConflictSide::Ours,
+                },
+                (Some(_), None) => ConflictSide::Ours,
+                (None, Some(_)) => ConflictSide::Theirs,
+                (None, None) => ConflictSide::Ours,
+            };
+            let chosen = match side {
+                ConflictSide::Ours => ours,
+                ConflictSide::Theirs => theirs,
+            };
+
+            match chosen {
+                Some(content) => {
+                    fs::write(self.workspace.join(path), content)?;
+                    self.git(&["add", "--", path])?;
+                }
+                None => {
+                    self.git(&["rm", "--quiet", "--", path])?;
+                }
+            }
+
+            warn!("Resolved sync conflict in {} (kept {:?})", path, side);
+        }
+
+        self.git(&["commit", "--quiet", "--no-edit"])?;
+        Ok(conflicted)
+    }
+
+    fn count_commits(&self, range: &str) -> Result<usize> {
+        Ok(self
+            .git(&["rev-list", "--count", range])?
+            .trim()
+            .parse()
+            .unwrap_or(0))
+    }
+
+    fn run_hook(&self, name: &str, command: &str) -> Result<()> {
+        debug!("Running {} hook: {}", name, command);
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .current_dir(&self.workspace)
+            .status()
+            .with_context(|| format!("Failed to run {} hook", name))?;
+        if !status.success() {
+            anyhow::bail!("{} hook exited with {}", name, status);
+        }
+        Ok(())
+    }
+
+    fn git_output(&self, args: &[&str]) -> Result<Output> {
+        Command::new("git")
+            .args(args)
+            .current_dir(&self.workspace)
+            .output()
+            .context("Failed to run git (is it installed?)")
+    }
+
+    /// Run git and return stdout, failing on non-zero exit
+    fn git(&self, args: &[&str]) -> Result<String> {
+        Ok(String::from_utf8_lossy(&self.git_bytes(args)?).to_string())
+    }
+
+    /// Run git and return stdout as raw bytes, failing on non-zero exit
+    fn git_bytes(&self, args: &[&str]) -> Result<Vec<u8>> {
+        let output = self.git_output(args)?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "git {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(output.stdout)
+    }
+
+    /// Run git and report whether it exited successfully
+    fn git_ok(&self, args: &[&str]) -> Result<bool> {
+        Ok(self.git_output(args)?.status.success())
+    }
+}
+
+/// Pick the version of a conflicting file with the newest frontmatter timestamp.
+///
+/// Local content wins ties and files without a timestamp on either side.
+pub fn pick_newest(ours: &str, theirs: &str) -> ConflictSide {
+    match (frontmatter_timestamp(ours), frontmatter_timestamp(theirs)) {
+        (Some(o), Some(t)) if t > o => ConflictSide::Theirs,
+        (None, Some(_)) => ConflictSide::Theirs,
+        _ => ConflictSide::Ours,
+    }
+}
+
+fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| {
+            Command::new("hostname")
+                .output()
+                .ok()
+                .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        })
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_newest_prefers_newer_frontmatter() {
+        let old = "---\nlast_verified: 2026-01-01\n---\nold";
+        let new = "---\nlast_verified: 2026-02-01\n---\nnew";
+
+        assert_eq!(pick_newest(old, new), ConflictSide::Theirs);
+        assert_eq!(pick_newest(new, old), ConflictSide::Ours);
+    }
+
+    #[test]
+    fn test_pick_newest_ties_keep_ours() {
+        let a = "---\nupdated: 2026-01-01\n---\na";
+        let b = "---\nupdated: 2026-01-01\n---\nb";
+        assert_eq!(pick_newest(a, b), ConflictSide::Ours);
+        assert_eq!(pick_newest("plain", "also plain"), ConflictSide::Ours);
+        assert_eq!(pick_newest("plain", a), ConflictSide::Theirs);
+    }
+}
@@ -0,0 +1,283 @@
+//! Time index over indexed chunks.
+//!
+//! Dates written in memory files ("Mia's birthday: March 3", "warranty
+//! expires 2027-06-30", "dentist 11/4/2026") are pulled out when a chunk is
+//! indexed and stored with the line they came from. Questions like "what's
+//! due in the next two weeks" are then a range query instead of a vector
+//! search. A date written without a year counts as every year, except in a
+//! daily log, where it's taken as the nearest one to the log's own date.
+//! Daily logs are also indexed under their file date, so "what happened
+//! around March 3rd" finds them.
+
+use chrono::{Datelike, Duration, NaiveDate};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::path::Path;
+
+use crate::locale;
+
+/// Longest line kept as an entry's text
+const MAX_TEXT_CHARS: usize = 160;
+
+const MONTHS: &str = "january|february|march|april|may|june|july|august|september|sept|\
+    october|november|december|jan|feb|mar|apr|jun|jul|aug|sep|oct|nov|dec|\
+    enero|febrero|marzo|abril|mayo|junio|julio|agosto|septiembre|setiembre|octubre|\
+    noviembre|diciembre|ene|abr|ago|dic";
+
+static ISO: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b(\d{4})-(\d{2})-(\d{2})\b").unwrap());
+static NUMERIC: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b(\d{1,2})/(\d{1,2})/(\d{4}|\d{2})\b").unwrap());
+/// "March 3", "Mar 3rd, 2027"
+static MONTH_DAY: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(
+        r"(?i)\b({})\.?\s+(\d{{1,2}})(?:st|nd|rd|th)?\b(?:,?\s+(\d{{4}})\b)?",
+        MONTHS
+    ))
+    .unwrap()
+});
+/// "3 March", "3rd of March 2027", "3 de marzo de 2027"
+static DAY_MONTH: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(
+        r"(?i)\b(\d{{1,2}})(?:st|nd|rd|th)?\s+(?:of\s+|de\s+)?({})\b\.?(?:,?\s+(?:de\s+)?(\d{{4}})\b)?",
+        MONTHS
+    ))
+    .unwrap()
+});
+
+fn month(word: &str) -> Option<u32> {
+    let word = word.to_lowercase();
+    let month = match word.get(..3)? {
+        "jan" | "ene" => 1,
+        "feb" => 2,
+        "mar" => 3,
+        "apr" | "abr" => 4,
+        "may" => 5,
+        "jun" => 6,
+        "jul" => 7,
+        "aug" | "ago" => 8,
+        "sep" | "set" => 9,
+        "oct" => 10,
+        "nov" => 11,
+        "dec" | "dic" => 12,
+        _ => return None,
+    };
+    Some(month)
+}
+
+/// A date written in a chunk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DateMention {
+    pub month: u32,
+    pub day: u32,
+    /// None when the text didn't give one
+    pub year: Option<i32>,
+    /// Line within the chunk (0-based)
+    pub line: usize,
+    /// The line it was on, trimmed
+    pub text: String,
+}
+
+/// Dates in `text`, one per distinct date per line
+pub fn dates(text: &str) -> Vec<DateMention> {
+    let mut found = Vec::new();
+    for (line, content) in text.lines().enumerate() {
+        let mut on_line: Vec<(u32, u32, Option<i32>)> = Vec::new();
+        for caps in ISO.captures_iter(content) {
+            on_line.push((
+                caps[2].parse().unwrap_or(0),
+                caps[3].parse().unwrap_or(0),
+                caps[1].parse().ok(),
+            ));
+        }
+        for caps in NUMERIC.captures_iter(content) {
+            let year: i32 = caps[3].parse().unwrap_or(0);
+            let year = if year < 100 { 2000 + year } else { year };
+            on_line.push((
+                caps[1].parse().unwrap_or(0),
+                caps[2].parse().unwrap_or(0),
+                Some(year),
+            ));
+        }
+        for caps in MONTH_DAY.captures_iter(content) {
+            if let Some(m) = month(&caps[1]) {
+                let year = caps.get(3).and_then(|y| y.as_str().parse().ok());
+                on_line.push((m, caps[2].parse().unwrap_or(0), year));
+            }
+        }
+        for caps in DAY_MONTH.captures_iter(content) {
+            if let Some(m) = month(&caps[2]) {
+                let year = caps.get(3).and_then(|y| y.as_str().parse().ok());
+                on_line.push((m, caps[1].parse().unwrap_or(0), year));
+            }
+        }
+
+        for (month, day, year) in on_line {
+            // Validate against a leap year so Feb 29 without a year is kept
+            if NaiveDate::from_ymd_opt(year.unwrap_or(2000), month, day).is_none() {
+                continue;
+            }
+            let duplicate = found.iter().any(|d: &DateMention| {
+                d.line == line && d.month == month && d.day == day && d.year == year
+            });
+            if !duplicate {
+                found.push(DateMention {
+                    month,
+                    day,
+                    year,
+                    line,
+                    text: entry_text(content),
+                });
+            }
+        }
+    }
+    found
+}
+
+fn entry_text(line: &str) -> String {
+    let text = line.trim().trim_start_matches(['#', '-', '*', '>', ' ']);
+    match text.char_indices().nth(MAX_TEXT_CHARS) {
+        Some((cut, _)) => format!("{}...", &text[..cut]),
+        None => text.to_string(),
+    }
+}
+
+/// Date of a daily log (`memory/2026-03-03.md`) from its file name
+pub fn log_date(path: &str) -> Option<NaiveDate> {
+    let stem = Path::new(path).file_stem()?.to_str()?;
+    NaiveDate::parse_from_str(stem, "%Y-%m-%d").ok()
+}
+
+/// First line of a log chunk worth showing, as (line, text)
+pub fn log_heading(text: &str) -> Option<(usize, String)> {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i, entry_text(line)))
+        .find(|(_, line)| !line.is_empty())
+}
+
+/// The first `month`/`day` on or after `from` (Feb 29 falls back to Feb 28
+/// in other years)
+pub fn next_occurrence(month: u32, day: u32, from: NaiveDate) -> NaiveDate {
+    let in_year = |year: i32| {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .or_else(|| NaiveDate::from_ymd_opt(year, month, day - 1))
+            .unwrap_or(from)
+    };
+    let this_year = in_year(from.year());
+    if this_year >= from {
+        this_year
+    } else {
+        in_year(from.year() + 1)
+    }
+}
+
+/// Year for a date written without one in a daily log: the occurrence
+/// nearest the log's date, looking up to a month back
+pub fn year_near(month: u32, day: u32, log: NaiveDate) -> i32 {
+    next_occurrence(month, day, log - Duration::days(30)).year()
+}
+
+/// A date found by a time query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatedEntry {
+    pub date: NaiveDate,
+    /// Written without a year, so it comes back every year
+    pub yearly: bool,
+    /// The date of a daily log rather than one written in it
+    pub log: bool,
+    pub text: String,
+    /// path:line
+    pub location: String,
+}
+
+impl DatedEntry {
+    pub fn summary(&self, today: NaiveDate) -> String {
+        let days = (self.date - today).num_days();
+        let when = match days {
+            0 => "today".to_string(),
+            1 => "tomorrow".to_string(),
+            -1 => "yesterday".to_string(),
+            d if d > 0 => format!("in {} days", d),
+            d => format!("{} days ago", -d),
+        };
+        let kind = if self.log {
+            " (daily log)"
+        } else if self.yearly {
+            " (every year)"
+        } else {
+            ""
+        };
+        format!(
+            "{} {} ({}){}: {} [{}]",
+            locale::current().short_date(self.date),
+            self.date.year(),
+            when,
+            kind,
+            self.text,
+            self.location
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ymd(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_dates_formats() {
+        let found = dates(
+            "# Family\n\
+             - Mia's birthday: March 3\n\
+             - Dishwasher warranty expires 2027-06-30\n\
+             - Dentist 11/4/2026 at 3pm\n\
+             - Leo's recital is on the 21st of October, 2026\n\
+             - Cumpleaños de la abuela: 9 de julio\n\
+             - Nothing on May the fourth",
+        );
+        let summary: Vec<(u32, u32, Option<i32>, usize)> = found
+            .iter()
+            .map(|d| (d.month, d.day, d.year, d.line))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (3, 3, None, 1),
+                (6, 30, Some(2027), 2),
+                (11, 4, Some(2026), 3),
+                (10, 21, Some(2026), 4),
+                (7, 9, None, 5),
+            ]
+        );
+        assert_eq!(found[0].text, "Mia's birthday: March 3");
+    }
+
+    #[test]
+    fn test_invalid_dates_skipped() {
+        assert!(dates("Version 13/45/2026 and 2026-02-30").is_empty());
+        assert_eq!(dates("Leap day Feb 29").len(), 1);
+    }
+
+    #[test]
+    fn test_next_occurrence() {
+        let today = ymd(2026, 10, 18);
+        assert_eq!(next_occurrence(10, 18, today), today);
+        assert_eq!(next_occurrence(3, 3, today), ymd(2027, 3, 3));
+        assert_eq!(next_occurrence(2, 29, today), ymd(2027, 2, 28));
+        assert_eq!(year_near(10, 10, today), 2026);
+        assert_eq!(year_near(1, 5, today), 2027);
+    }
+
+    #[test]
+    fn test_log_date() {
+        assert_eq!(log_date("memory/2026-03-03.md"), Some(ymd(2026, 3, 3)));
+        assert_eq!(log_date("memory/house.md"), None);
+        assert_eq!(
+            log_heading("\n## 14:02 Plumber visit\nFixed the leak"),
+            Some((1, "14:02 Plumber visit".to_string()))
+        );
+    }
+}
@@ -4,14 +4,16 @@
 //! Every memory chunk gets a SHA-256 hash at index time, and search results
 //! are verified before returning to ensure data integrity.
 
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use chrono::Utc;
-use rusqlite::{params, Connection};
+use rusqlite::params;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::sync::{Arc, Mutex};
 use tracing::{debug, warn};
 
+use super::db::Database;
+use super::embeddings::hash_text;
+
 /// A verified chunk result with cryptographic proof
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VerifiedChunk {
@@ -32,6 +34,10 @@ pub struct VerifiedChunk {
     pub provenance: Provenance,
     /// Confidence level
     pub confidence: Confidence,
+    /// `last_verified` from the file's frontmatter
+    pub last_verified: Option<String>,
+    /// `last_verified` is past the max age for the file's category
+    pub stale: bool,
 }
 
 impl VerifiedChunk {
@@ -56,6 +62,8 @@ pub enum Provenance {
     FileContent { path: String },
     /// Discovered during autonomous heartbeat task
     HeartbeatDiscovery { task: String },
+    /// Summed up from a week's sessions in the weekly journal ("2026-W42")
+    Journal { week: String },
     /// Unknown / legacy data without provenance
     Unknown,
 }
@@ -67,6 +75,7 @@ impl std::fmt::Display for Provenance {
             Provenance::WebSearch { url, .. } => write!(f, "web-search:{}", url),
             Provenance::FileContent { path } => write!(f, "file:{}", path),
             Provenance::HeartbeatDiscovery { task } => write!(f, "heartbeat:{}", task),
+            Provenance::Journal { week } => write!(f, "journal:{}", week),
             Provenance::Unknown => write!(f, "unknown"),
         }
     }
@@ -96,6 +105,54 @@ impl std::fmt::Display for Confidence {
     }
 }
 
+impl std::str::FromStr for Confidence {
+    type Err = anyhow::Error;
+
+    /// "none", "low", "medium" or "high" (as in `memory.answer_min_confidence`)
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "none" => Ok(Confidence::None),
+            "low" => Ok(Confidence::Low),
+            "medium" => Ok(Confidence::Medium),
+            "high" => Ok(Confidence::High),
+            _ => anyhow::bail!(
+                "Unknown confidence '{}' (expected none, low, medium or high)",
+                s
+            ),
+        }
+    }
+}
+
+/// Opening of a citation the agent writes for a verified chunk
+pub const CITATION_PREFIX: &str = "[VERIFIED:";
+
+/// Parse a citation at the start of `text`, returning its hash prefix and
+/// the citation's length in bytes
+pub fn parse_citation(text: &str) -> Option<(&str, usize)> {
+    let rest = text.strip_prefix(CITATION_PREFIX)?;
+    let end = rest.find(']')?;
+    let hash = &rest[..end];
+    if hash.is_empty() || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some((hash, CITATION_PREFIX.len() + end + 1))
+}
+
+/// Hash prefixes cited as `[VERIFIED:<hash>]` in `text`, lowercased, in
+/// order of first citation
+pub fn cited_hashes(text: &str) -> Vec<String> {
+    let mut hashes: Vec<String> = Vec::new();
+    for (start, _) in text.match_indices(CITATION_PREFIX) {
+        if let Some((hash, _)) = parse_citation(&text[start..]) {
+            let hash = hash.to_lowercase();
+            if !hashes.contains(&hash) {
+                hashes.push(hash);
+            }
+        }
+    }
+    hashes
+}
+
 /// Compute SHA-256 hash of a chunk's content + metadata
 pub fn compute_chunk_hash(path: &str, content: &str, timestamp: &str) -> String {
     let mut hasher = Sha256::new();
@@ -110,14 +167,14 @@ pub fn compute_chunk_hash(path: &str, content: &str, timestamp: &str) -> String
 /// Manages chunk verification hashes in a SQLite table alongside the chunks
 #[derive(Clone)]
 pub struct ChunkVerifier {
-    conn: Arc<Mutex<Connection>>,
+    db: Database,
 }
 
 impl ChunkVerifier {
-    /// Create a new ChunkVerifier using the same connection as MemoryIndex
-    pub fn new(conn: Arc<Mutex<Connection>>) -> Result<Self> {
+    /// Create a new ChunkVerifier using the same database as MemoryIndex
+    pub fn new(db: Database) -> Result<Self> {
         {
-            let conn = conn.lock().map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+            let conn = db.write();
 
             // Create the verification table
             conn.execute_batch(
@@ -139,7 +196,7 @@ impl ChunkVerifier {
             )?;
         }
 
-        Ok(Self { conn })
+        Ok(Self { db })
     }
 
     /// Record a hash for a chunk at index time
@@ -154,10 +211,7 @@ impl ChunkVerifier {
         let hash = compute_chunk_hash(path, content, &now);
         let provenance_str = serde_json::to_string(provenance)?;
 
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+        let conn = self.db.write();
 
         conn.execute(
             r#"INSERT OR REPLACE INTO chunk_hashes
@@ -172,10 +226,7 @@ impl ChunkVerifier {
 
     /// Verify a chunk's content against its stored hash
     pub fn verify_chunk(&self, chunk_id: &str, path: &str, content: &str) -> Result<bool> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+        let conn = self.db.write();
 
         let result: Option<(String, String)> = conn
             .query_row(
@@ -220,10 +271,7 @@ impl ChunkVerifier {
         &self,
         chunk_id: &str,
     ) -> Result<Option<(String, Provenance, i64, Option<String>)>> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+        let conn = self.db.read();
 
         let result: Option<(String, String, i64, Option<String>)> = conn
             .query_row(
@@ -272,6 +320,7 @@ impl ChunkVerifier {
             }
             Provenance::WebSearch { .. } => Confidence::Medium,
             Provenance::HeartbeatDiscovery { .. } => Confidence::Medium,
+            Provenance::Journal { .. } => Confidence::Medium,
             Provenance::Unknown => {
                 if access_count > 10 {
                     Confidence::Medium
@@ -284,10 +333,7 @@ impl ChunkVerifier {
 
     /// Remove hashes for chunks belonging to a path (called when file is re-indexed)
     pub fn remove_hashes_for_path(&self, path: &str) -> Result<usize> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+        let conn = self.db.write();
 
         let count = conn.execute(
             "DELETE FROM chunk_hashes WHERE path = ?1",
@@ -299,10 +345,7 @@ impl ChunkVerifier {
 
     /// Get verification stats
     pub fn stats(&self) -> Result<VerificationStats> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+        let conn = self.db.read();
 
         let total: i64 =
             conn.query_row("SELECT COUNT(*) FROM chunk_hashes", [], |row| row.get(0))?;
@@ -320,6 +363,59 @@ impl ChunkVerifier {
             by_provenance,
         })
     }
+
+    /// Chunks without a verification hash, or whose text no longer matches
+    /// their content or verification hash, as (location, reason)
+    pub fn unverifiable_chunks(&self) -> Result<Vec<(String, String)>> {
+        let conn = self.db.read();
+
+        let mut stmt = conn.prepare(
+            r#"SELECT c.path, c.start_line, c.end_line, c.text, c.hash, h.hash, h.timestamp
+               FROM chunks c
+               LEFT JOIN chunk_hashes h ON h.chunk_id = c.id
+               ORDER BY c.path, c.start_line"#,
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i32>(1)?,
+                row.get::<_, i32>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, Option<String>>(5)?,
+                row.get::<_, Option<String>>(6)?,
+            ))
+        })?;
+
+        let mut failures = Vec::new();
+        for row in rows {
+            let (path, start, end, text, content_hash, hash, timestamp) = row?;
+            let reason = match (hash, timestamp) {
+                _ if hash_text(&text) != content_hash => "content hash mismatch",
+                (Some(hash), Some(ts)) if compute_chunk_hash(&path, &text, &ts) != hash => {
+                    "verification hash mismatch"
+                }
+                (Some(_), Some(_)) => continue,
+                _ => "no verification hash",
+            };
+            failures.push((format!("{}:{}-{}", path, start, end), reason.to_string()));
+        }
+
+        Ok(failures)
+    }
+
+    /// Verification hashes whose chunk no longer exists, as (path, chunk id)
+    pub fn orphaned_hashes(&self) -> Result<Vec<(String, String)>> {
+        let conn = self.db.read();
+
+        let mut stmt = conn.prepare(
+            "SELECT path, chunk_id FROM chunk_hashes
+             WHERE chunk_id NOT IN (SELECT id FROM chunks)
+             ORDER BY path",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
 }
 
 #[derive(Debug)]
@@ -333,9 +429,9 @@ mod tests {
     use super::*;
     use rusqlite::Connection;
 
-    fn setup_test_db() -> Arc<Mutex<Connection>> {
+    fn setup_test_db() -> Database {
         let conn = Connection::open_in_memory().unwrap();
-        Arc::new(Mutex::new(conn))
+        Database::new(conn, |_| {}).unwrap()
     }
 
     #[test]
@@ -349,6 +445,18 @@ mod tests {
         assert_eq!(hash1.len(), 64); // SHA-256 hex
     }
 
+    #[test]
+    fn test_cited_hashes() {
+        let text = "Code is 4821 [VERIFIED:ab12CD34], set in May [VERIFIED:ab12cd34] \
+                    [VERIFIED:] [VERIFIED:xyz] [VERIFIED:0f0f";
+        assert_eq!(cited_hashes(text), vec!["ab12cd34"]);
+        assert_eq!(
+            parse_citation("[VERIFIED:0f0f] rest"),
+            Some(("0f0f", "[VERIFIED:0f0f]".len()))
+        );
+        assert_eq!(parse_citation("[UNVERIFIED] x"), None);
+    }
+
     #[test]
     fn test_record_and_verify() {
         let conn = setup_test_db();
@@ -380,6 +488,13 @@ mod tests {
             .to_string(),
             "file:test.md"
         );
+        assert_eq!(
+            Provenance::Journal {
+                week: "2026-W42".into()
+            }
+            .to_string(),
+            "journal:2026-W42"
+        );
     }
 
     #[test]
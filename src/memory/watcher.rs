@@ -7,7 +7,7 @@ use std::sync::mpsc;
 use std::time::Duration;
 use tracing::{debug, info, warn};
 
-use super::MemoryIndex;
+use super::{documents, ChunkOptions, MemoryIndex};
 use crate::config::MemoryConfig;
 
 pub struct MemoryWatcher {
@@ -23,16 +23,23 @@ impl MemoryWatcher {
     pub fn new(workspace: PathBuf, db_path: PathBuf, config: MemoryConfig) -> Result<Self> {
         // Create a channel for receiving events
         let (tx, rx) = mpsc::channel();
+        let documents_dir = documents::documents_dir(&workspace, &config);
+        let watched_documents_dir = documents_dir.clone();
 
         // Create watcher with debounce
         let mut watcher = notify::recommended_watcher(move |res: Result<Event, notify::Error>| {
             match res {
                 Ok(event) => {
-                    // Filter for modify/create events on .md files
+                    // Filter for modify/create events on .md files and on
+                    // documents in the documents directory
                     match event.kind {
                         EventKind::Modify(_) | EventKind::Create(_) => {
                             for path in event.paths {
-                                if path.extension().map(|e| e == "md").unwrap_or(false) {
+                                let is_markdown =
+                                    path.extension().map(|e| e == "md").unwrap_or(false);
+                                let is_document = path.starts_with(&watched_documents_dir)
+                                    && documents::is_document(&path);
+                                if is_markdown || is_document {
                                     if let Err(e) = tx.send(path.clone()) {
                                         warn!("Failed to send event: {}", e);
                                     }
@@ -77,15 +84,23 @@ impl MemoryWatcher {
             }
         }
 
+        if !documents_dir.starts_with(&workspace) && documents_dir.exists() {
+            if let Err(e) = watcher.watch(&documents_dir, RecursiveMode::Recursive) {
+                warn!("Failed to watch {}: {}", documents_dir.display(), e);
+            } else {
+                info!("Watching documents: {}", documents_dir.display());
+                watched_paths.push(documents_dir);
+            }
+        }
+
         // Spawn background task to handle events
         let workspace_for_task = workspace.clone();
         let db_path_for_task = db_path.clone();
-        let chunk_size = config.chunk_size;
-        let chunk_overlap = config.chunk_overlap;
+        let (chunk_options, chunk_rules) = ChunkOptions::from_config(&config)?;
         std::thread::spawn(move || {
             let index = match MemoryIndex::new_with_db_path(&workspace_for_task, &db_path_for_task)
             {
-                Ok(idx) => idx.with_chunk_config(chunk_size, chunk_overlap),
+                Ok(idx) => idx.with_chunking(chunk_options, chunk_rules),
                 Err(e) => {
                     warn!("Failed to create memory index for watcher: {}", e);
                     return;
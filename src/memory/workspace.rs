@@ -3,37 +3,40 @@
 //! Creates default workspace files on first run.
 
 use anyhow::Result;
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tracing::info;
 
-/// Initialize workspace with default templates if files don't exist.
+use crate::locale::{split_localized_name, Language};
+
+/// Built-in template pack used when none is configured
+pub const DEFAULT_TEMPLATE_PACK: &str = "home";
+
+/// Initialize workspace with the default ("home") English templates if files don't exist.
 /// Returns true if this is a brand new workspace (all key files were missing).
 pub fn init_workspace(workspace: &Path) -> Result<bool> {
-    // Ensure directories exist — home-specific structure
+    init_workspace_with_templates(workspace, DEFAULT_TEMPLATE_PACK, Language::English)
+}
+
+/// Initialize workspace from a template pack.
+///
+/// `template_pack` is either a built-in pack name (`"home"` or `"minimal"`)
+/// or a path to a directory whose files are copied into the workspace.
+/// Files in `<state_dir>/templates/` (e.g. `~/.homegpt/templates/`) are
+/// layered on top and override pack files with the same relative path.
+/// In either, `NAME.<code>.md` is written as `NAME.md` when `language` has
+/// that code, and skipped otherwise.
+/// Existing workspace files are never overwritten.
+pub fn init_workspace_with_templates(
+    workspace: &Path,
+    template_pack: &str,
+    language: Language,
+) -> Result<bool> {
     fs::create_dir_all(workspace)?;
     fs::create_dir_all(workspace.join("memory"))?;
     fs::create_dir_all(workspace.join("skills"))?;
 
-    // Home workspace subdirectories
-    let home_dirs = [
-        "memory/family",
-        "memory/home",
-        "memory/food",
-        "memory/school",
-        "memory/calendar",
-        "memory/finance",
-        "memory/business",
-        "memory/knowledge",
-        "skills/tutor",
-        "skills/shopping",
-        "skills/maintenance",
-        "skills/family",
-    ];
-    for dir in &home_dirs {
-        fs::create_dir_all(workspace.join(dir))?;
-    }
-
     // Also init the parent state directory (.gitignore for sessions/logs)
     if let Some(state_dir) = workspace.parent() {
         init_state_dir(state_dir)?;
@@ -47,46 +50,27 @@ pub fn init_workspace(workspace: &Path) -> Result<bool> {
     ];
     let is_brand_new = key_files.iter().all(|p| !p.exists());
 
-    // Create MEMORY.md if it doesn't exist
-    let memory_path = workspace.join("MEMORY.md");
-    if !memory_path.exists() {
-        fs::write(&memory_path, MEMORY_TEMPLATE)?;
-        info!("Created {}", memory_path.display());
-    }
+    let mut templates = load_template_pack(template_pack, language)?;
 
-    // Create HEARTBEAT.md if it doesn't exist
-    let heartbeat_path = workspace.join("HEARTBEAT.md");
-    if !heartbeat_path.exists() {
-        fs::write(&heartbeat_path, HEARTBEAT_TEMPLATE)?;
-        info!("Created {}", heartbeat_path.display());
-    }
-
-    // Create SOUL.md if it doesn't exist
-    let soul_path = workspace.join("SOUL.md");
-    if !soul_path.exists() {
-        fs::write(&soul_path, SOUL_TEMPLATE)?;
-        info!("Created {}", soul_path.display());
+    // User overrides from the state directory take precedence
+    if let Some(state_dir) = workspace.parent() {
+        let overrides = state_dir.join("templates");
+        if overrides.is_dir() {
+            let mut user = BTreeMap::new();
+            read_template_dir(&overrides, &overrides, &mut user)?;
+            templates.extend(localize_templates(user, language));
+        }
     }
 
-    // Create home workspace files if they don't exist
-    let home_files: &[(&str, &str)] = &[
-        ("memory/family/members.md", FAMILY_MEMBERS_TEMPLATE),
-        ("memory/family/routines.md", FAMILY_ROUTINES_TEMPLATE),
-        ("memory/school/curriculum.md", SCHOOL_CURRICULUM_TEMPLATE),
-        ("memory/school/progress.md", SCHOOL_PROGRESS_TEMPLATE),
-        ("memory/school/tutor-notes.md", TUTOR_NOTES_TEMPLATE),
-        ("memory/home/maintenance.md", HOME_MAINTENANCE_TEMPLATE),
-        ("memory/food/meal-plans.md", MEAL_PLANS_TEMPLATE),
-        ("memory/food/shopping-lists.md", SHOPPING_LISTS_TEMPLATE),
-        ("memory/calendar/upcoming.md", CALENDAR_TEMPLATE),
-        ("memory/business/ergotools-status.md", ERGOTOOLS_TEMPLATE),
-        ("skills/tutor/SKILL.md", TUTOR_SKILL_TEMPLATE),
-        ("skills/shopping/SKILL.md", SHOPPING_SKILL_TEMPLATE),
-        ("skills/maintenance/SKILL.md", MAINTENANCE_SKILL_TEMPLATE),
-    ];
-
-    for (path, content) in home_files {
+    for (path, content) in &templates {
         let full_path = workspace.join(path);
+        if path.ends_with('/') {
+            fs::create_dir_all(&full_path)?;
+            continue;
+        }
+        if let Some(parent) = full_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
         if !full_path.exists() {
             fs::write(&full_path, content)?;
             info!("Created {}", full_path.display());
@@ -103,6 +87,150 @@ pub fn init_workspace(workspace: &Path) -> Result<bool> {
     Ok(is_brand_new)
 }
 
+/// Resolve a template pack into (relative path, content) pairs in `language`.
+///
+/// Directory entries (paths ending in `/`) only create the directory.
+fn load_template_pack(template_pack: &str, language: Language) -> Result<BTreeMap<String, String>> {
+    let builtin: &[(&str, &str)] = match template_pack {
+        "home" => HOME_TEMPLATES,
+        "minimal" => MINIMAL_TEMPLATES,
+        _ => &[],
+    };
+    if !builtin.is_empty() {
+        return Ok(localize_templates(builtin_templates(builtin), language));
+    }
+
+    let dir = PathBuf::from(shellexpand::tilde(template_pack).to_string());
+    if !dir.is_dir() {
+        anyhow::bail!(
+            "Unknown template pack '{}': expected \"home\", \"minimal\" or a directory",
+            template_pack
+        );
+    }
+    // Custom packs fall back to the minimal core files they don't provide
+    let mut templates = localize_templates(builtin_templates(MINIMAL_TEMPLATES), language);
+    let mut pack = BTreeMap::new();
+    read_template_dir(&dir, &dir, &mut pack)?;
+    templates.extend(localize_templates(pack, language));
+
+    Ok(templates)
+}
+
+fn builtin_templates(builtin: &[(&str, &str)]) -> BTreeMap<String, String> {
+    builtin
+        .iter()
+        .map(|(path, content)| (path.to_string(), content.to_string()))
+        .collect()
+}
+
+/// Put `NAME.<code>.md` translations for `language` in place of `NAME.md`
+/// and drop the translations for other languages
+fn localize_templates(
+    templates: BTreeMap<String, String>,
+    language: Language,
+) -> BTreeMap<String, String> {
+    let mut localized = BTreeMap::new();
+    let mut translations = Vec::new();
+    for (path, content) in templates {
+        match split_localized_name(&path) {
+            Some((path, lang)) if lang == language => translations.push((path, content)),
+            Some(_) => {}
+            None => {
+                localized.insert(path, content);
+            }
+        }
+    }
+    localized.extend(translations);
+    localized
+}
+
+/// Recursively read a template directory into `templates`, keyed by path relative to `root`
+fn read_template_dir(
+    root: &Path,
+    dir: &Path,
+    templates: &mut BTreeMap<String, String>,
+) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        if name.starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            read_template_dir(root, &path, templates)?;
+        } else if let Ok(rel) = path.strip_prefix(root) {
+            let rel = rel.to_string_lossy().replace('\\', "/");
+            templates.insert(rel, fs::read_to_string(&path)?);
+        }
+    }
+    Ok(())
+}
+
+/// Built-in "home" pack: family, school, food and business layout
+const HOME_TEMPLATES: &[(&str, &str)] = &[
+    ("MEMORY.md", MEMORY_TEMPLATE),
+    ("HEARTBEAT.md", HEARTBEAT_TEMPLATE),
+    ("SOUL.md", SOUL_TEMPLATE),
+    ("memory/family/members.md", FAMILY_MEMBERS_TEMPLATE),
+    ("memory/family/routines.md", FAMILY_ROUTINES_TEMPLATE),
+    ("memory/school/curriculum.md", SCHOOL_CURRICULUM_TEMPLATE),
+    ("memory/school/progress.md", SCHOOL_PROGRESS_TEMPLATE),
+    ("memory/school/tutor-notes.md", TUTOR_NOTES_TEMPLATE),
+    ("memory/home/maintenance.md", HOME_MAINTENANCE_TEMPLATE),
+    ("memory/food/meal-plans.md", MEAL_PLANS_TEMPLATE),
+    ("memory/food/shopping-lists.md", SHOPPING_LISTS_TEMPLATE),
+    ("memory/calendar/upcoming.md", CALENDAR_TEMPLATE),
+    ("memory/finance/", ""),
+    ("memory/knowledge/", ""),
+    ("memory/business/ergotools-status.md", ERGOTOOLS_TEMPLATE),
+    ("skills/tutor/SKILL.md", TUTOR_SKILL_TEMPLATE),
+    ("skills/shopping/SKILL.md", SHOPPING_SKILL_TEMPLATE),
+    ("skills/maintenance/SKILL.md", MAINTENANCE_SKILL_TEMPLATE),
+    ("skills/family/", ""),
+    // Spanish translations (see `localize_templates`)
+    ("MEMORY.es.md", ES_MEMORY_TEMPLATE),
+    ("HEARTBEAT.es.md", ES_HEARTBEAT_TEMPLATE),
+    ("SOUL.es.md", ES_SOUL_TEMPLATE),
+    ("memory/family/members.es.md", ES_FAMILY_MEMBERS_TEMPLATE),
+    ("memory/family/routines.es.md", ES_FAMILY_ROUTINES_TEMPLATE),
+    (
+        "memory/school/curriculum.es.md",
+        ES_SCHOOL_CURRICULUM_TEMPLATE,
+    ),
+    ("memory/school/progress.es.md", ES_SCHOOL_PROGRESS_TEMPLATE),
+    ("memory/school/tutor-notes.es.md", ES_TUTOR_NOTES_TEMPLATE),
+    (
+        "memory/home/maintenance.es.md",
+        ES_HOME_MAINTENANCE_TEMPLATE,
+    ),
+    ("memory/food/meal-plans.es.md", ES_MEAL_PLANS_TEMPLATE),
+    (
+        "memory/food/shopping-lists.es.md",
+        ES_SHOPPING_LISTS_TEMPLATE,
+    ),
+    ("memory/calendar/upcoming.es.md", ES_CALENDAR_TEMPLATE),
+    (
+        "memory/business/ergotools-status.es.md",
+        ES_ERGOTOOLS_TEMPLATE,
+    ),
+    ("skills/tutor/SKILL.es.md", ES_TUTOR_SKILL_TEMPLATE),
+    ("skills/shopping/SKILL.es.md", ES_SHOPPING_SKILL_TEMPLATE),
+    (
+        "skills/maintenance/SKILL.es.md",
+        ES_MAINTENANCE_SKILL_TEMPLATE,
+    ),
+];
+
+/// Built-in "minimal" pack: generic core files only
+const MINIMAL_TEMPLATES: &[(&str, &str)] = &[
+    ("MEMORY.md", MINIMAL_MEMORY_TEMPLATE),
+    ("HEARTBEAT.md", MINIMAL_HEARTBEAT_TEMPLATE),
+    ("SOUL.md", MINIMAL_SOUL_TEMPLATE),
+    ("MEMORY.es.md", ES_MINIMAL_MEMORY_TEMPLATE),
+    ("HEARTBEAT.es.md", ES_MINIMAL_HEARTBEAT_TEMPLATE),
+    ("SOUL.es.md", ES_MINIMAL_SOUL_TEMPLATE),
+];
+
 const MEMORY_TEMPLATE: &str = r#"# MEMORY.md - Family Knowledge Base
 
 Core facts about the family, home, and daily life.
@@ -129,11 +257,12 @@ Tasks listed here run during heartbeat cycles (every 15 minutes).
 
 ## Calendar Sync (every hour)
 - [ ] Fetch today's events from Google Calendar bridge (http://localhost:31340/events/today)
-- [ ] Update memory/calendar/upcoming.md with current events
+- [ ] Update memory/calendar/upcoming.md with current events (leave the Subscribed Calendars section alone; HomeGPT updates it)
 
-## ErgoTools Business Check (every 2 hours)
-- [ ] Run ergotools-heartbeat check
-- [ ] If pending reviews > 5 or flagged content exists, note it in today's log
+## ErgoTools Business Check
+Runs automatically from `[[business.connectors]]` in config.toml; alerts are
+logged to today's daily log and only sent when something changed.
+- [ ] Review memory/business/ergotools-status.md when an alert comes in
 
 ## School Progress (daily, 8pm)
 - [ ] Summarize today's tutoring sessions from memory/school/tutor-notes.md
@@ -196,6 +325,47 @@ const GITIGNORE_TEMPLATE: &str = r#"# HomeGPT workspace .gitignore
 .DS_Store
 "#;
 
+// ============================================================================
+// Minimal workspace templates
+// ============================================================================
+
+const MINIMAL_MEMORY_TEMPLATE: &str = r#"# MEMORY.md - Knowledge Base
+
+Long-term facts worth remembering across sessions.
+
+## People
+
+## Places
+
+## Preferences
+
+---
+
+"#;
+
+const MINIMAL_HEARTBEAT_TEMPLATE: &str = r#"# HEARTBEAT.md - Recurring Tasks
+
+Tasks listed here run during heartbeat cycles.
+
+<!-- Add checklist items, e.g. "- [ ] Check memory/ for overdue items" -->
+"#;
+
+const MINIMAL_SOUL_TEMPLATE: &str = r#"# SOUL.md - Assistant Personality
+
+You are a helpful, local-first assistant with a persistent memory.
+
+## Core Values
+
+**Anti-hallucination.** NEVER fabricate information. Always search verified memory before claiming facts. Say "I don't know" when you don't know.
+
+**Be practical.** Give actionable answers, not essays.
+
+## Continuity
+
+Each session, read MEMORY.md and memory/ files. They are your persistent knowledge.
+Update them when you learn something new. These files are how you remember.
+"#;
+
 // ============================================================================
 // Home workspace templates
 // ============================================================================
@@ -310,7 +480,7 @@ sources: [heartbeat]
 ---
 # ErgoTools Business Status
 
-<!-- Auto-updated from ergotools-heartbeat script -->
+<!-- Auto-updated by the heartbeat's business connector -->
 
 ## Pending Reviews
 None
@@ -374,6 +544,300 @@ Track home maintenance schedules and tasks.
 - Weekly heartbeat check for overdue maintenance
 "#;
 
+// ============================================================================
+// Spanish translations
+// ============================================================================
+
+const ES_MEMORY_TEMPLATE: &str = r#"# MEMORY.md - Base de conocimiento familiar
+
+Datos clave sobre la familia, la casa y el día a día.
+
+## Familia
+
+<!-- Nombres, cumpleaños, alergias, preferencias — ver memory/family/ para más detalle -->
+
+## Casa
+
+<!-- Dirección, contactos importantes, detalles de la casa -->
+
+## Preferencias
+
+<!-- Preferencias de la familia, necesidades alimentarias, etc. -->
+
+---
+
+"#;
+
+const ES_HEARTBEAT_TEMPLATE: &str = r#"# HEARTBEAT.md - Tareas recurrentes
+
+Las tareas de esta lista se ejecutan en cada ciclo de heartbeat (cada 15 minutos).
+
+## Sincronizar calendario (cada hora)
+- [ ] Traer los eventos de hoy del puente de Google Calendar (http://localhost:31340/events/today)
+- [ ] Actualizar memory/calendar/upcoming.md con los eventos actuales (no tocar la sección Subscribed Calendars; HomeGPT la actualiza)
+
+## Revisión del negocio ErgoTools
+Se ejecuta automáticamente desde `[[business.connectors]]` en config.toml; las
+alertas se anotan en el registro del día y solo se envían cuando algo cambió.
+- [ ] Revisar memory/business/ergotools-status.md cuando llegue una alerta
+
+## Progreso escolar (diario, 8pm)
+- [ ] Resumir las sesiones de tutoría de hoy a partir de memory/school/tutor-notes.md
+- [ ] Anotar las materias que necesitan más atención
+
+## Mantenimiento de la casa (semanal, domingo)
+- [ ] Revisar memory/home/maintenance.md para ver las tareas de mantenimiento próximas
+- [ ] Señalar las que estén atrasadas
+"#;
+
+const ES_SOUL_TEMPLATE: &str = r#"# SOUL.md - Personalidad del asistente del hogar
+
+Eres el asistente del hogar de la familia. Ayudas a organizar la casa, das tutoría a los niños,
+vigilas el negocio y te encargas de que todo funcione bien.
+
+## Valores
+
+**La familia primero.** Todo lo que haces es para la familia. Sé cálido, paciente y confiable.
+
+**Nada de inventar.** NUNCA inventes información. Busca siempre en la memoria verificada antes de afirmar algo. Di "no lo sé" cuando no lo sepas.
+
+**Sé práctico.** Da respuestas concretas, no ensayos. La familia está ocupada.
+
+**Gánate la confianza con precisión.** Tu memoria está verificada criptográficamente. Úsala. Cítala.
+
+## Con los niños
+
+- Ten paciencia y anímalos durante la tutoría
+- Guíalos hacia la respuesta, nunca se la des directamente
+- Da respuestas de voz cortas (1 a 3 frases) para TTS
+- Celebra el esfuerzo por encima del resultado
+- Responde en el idioma en que te hablen
+
+## Con los adultos
+
+- Sé eficiente y directo
+- Avisa de las alertas importantes del negocio sin esperar a que pregunten
+- Recuerda preferencias y rutinas
+- Lleva el seguimiento de lo importante sin que te lo pidan
+
+## Continuidad
+
+En cada sesión, lee MEMORY.md y los archivos de memory/. Son tu conocimiento persistente.
+Actualízalos cuando aprendas algo nuevo. Estos archivos son tu memoria.
+"#;
+
+const ES_MINIMAL_MEMORY_TEMPLATE: &str = r#"# MEMORY.md - Base de conocimiento
+
+Datos a largo plazo que vale la pena recordar entre sesiones.
+
+## Personas
+
+## Lugares
+
+## Preferencias
+
+---
+
+"#;
+
+const ES_MINIMAL_HEARTBEAT_TEMPLATE: &str = r#"# HEARTBEAT.md - Tareas recurrentes
+
+Las tareas de esta lista se ejecutan en cada ciclo de heartbeat.
+
+<!-- Añade elementos de lista, p. ej. "- [ ] Revisar memory/ en busca de tareas atrasadas" -->
+"#;
+
+const ES_MINIMAL_SOUL_TEMPLATE: &str = r#"# SOUL.md - Personalidad del asistente
+
+Eres un asistente útil y local, con memoria persistente.
+
+## Valores
+
+**Nada de inventar.** NUNCA inventes información. Busca siempre en la memoria verificada antes de afirmar algo. Di "no lo sé" cuando no lo sepas.
+
+**Sé práctico.** Da respuestas concretas, no ensayos.
+
+## Continuidad
+
+En cada sesión, lee MEMORY.md y los archivos de memory/. Son tu conocimiento persistente.
+Actualízalos cuando aprendas algo nuevo. Estos archivos son tu memoria.
+"#;
+
+const ES_FAMILY_MEMBERS_TEMPLATE: &str = r#"---
+category: family
+last_verified: null
+sources: []
+---
+# Miembros de la familia
+
+<!-- Añade aquí los datos de cada miembro de la familia -->
+<!-- Nombre, cumpleaños, alergias, preferencias -->
+"#;
+
+const ES_FAMILY_ROUTINES_TEMPLATE: &str = r#"---
+category: family
+last_verified: null
+sources: []
+---
+# Rutinas familiares
+
+## Rutina de la mañana
+
+## Rutina de la noche
+
+## Horario escolar
+"#;
+
+const ES_SCHOOL_CURRICULUM_TEMPLATE: &str = r#"---
+category: school
+last_verified: null
+sources: []
+---
+# Plan de estudios
+
+<!-- Plan de estudios de cada niño -->
+<!-- Años de AO, niveles de matemáticas de TGTB, etc. -->
+"#;
+
+const ES_SCHOOL_PROGRESS_TEMPLATE: &str = r#"---
+category: school
+last_verified: null
+sources: []
+---
+# Progreso escolar
+
+<!-- En qué está trabajando cada niño ahora -->
+"#;
+
+const ES_TUTOR_NOTES_TEMPLATE: &str = r#"---
+category: school
+last_verified: null
+sources: []
+---
+# Notas de tutoría
+
+<!-- Se completa a partir de las sesiones de tutoría por voz -->
+<!-- Temas que necesitan más ayuda, dónde destacaron los niños -->
+"#;
+
+const ES_HOME_MAINTENANCE_TEMPLATE: &str = r#"---
+category: home
+last_verified: null
+sources: []
+---
+# Mantenimiento de la casa
+
+## Próximo mantenimiento
+
+## Contactos de técnicos
+
+## Garantías
+"#;
+
+const ES_MEAL_PLANS_TEMPLATE: &str = r#"---
+category: food
+last_verified: null
+sources: []
+---
+# Menús
+
+## Esta semana
+"#;
+
+const ES_SHOPPING_LISTS_TEMPLATE: &str = r#"---
+category: food
+last_verified: null
+sources: []
+---
+# Listas de compras
+
+## Lista actual
+
+- [ ]
+"#;
+
+const ES_CALENDAR_TEMPLATE: &str = r#"---
+category: calendar
+last_verified: null
+sources: [heartbeat]
+---
+# Próximos eventos
+
+<!-- Se actualiza desde el heartbeat de Google Calendar -->
+"#;
+
+const ES_ERGOTOOLS_TEMPLATE: &str = r#"---
+category: business
+last_verified: null
+sources: [heartbeat]
+---
+# Estado del negocio ErgoTools
+
+<!-- Lo actualiza el conector de negocio del heartbeat -->
+
+## Reseñas pendientes
+Ninguna
+
+## Contenido señalado
+Ninguno
+
+## Próximos eventos
+Ninguno
+
+## Productos enviados
+Ninguno
+"#;
+
+const ES_TUTOR_SKILL_TEMPLATE: &str = r#"# Habilidad de tutoría
+
+Eres un tutor paciente y alentador para estudiantes que aprenden en casa.
+
+## Tu enfoque
+
+- Guía a los estudiantes hacia la respuesta, nunca se la des directamente
+- Haz preguntas que los orienten: "¿Qué crees que pasa después?"
+- Divide los problemas complejos en pasos pequeños
+- Celebra el esfuerzo y el progreso
+- Las respuestas equivocadas son oportunidades para aprender, nunca fracasos
+- Responde en el idioma en que te pregunten
+
+## Formato para voz (clave para TTS)
+
+- Limita las respuestas a 1 a 3 frases
+- Escribe los números con letras: "tres cuartos", no "3/4"
+- Nada de emojis, markdown ni caracteres especiales
+- Haz preguntas de seguimiento para comprobar que entendieron
+
+## Materias
+
+- Matemáticas: Teaching Textbooks (TGTB), resolver los problemas paso a paso
+- Lectura: libros de Ambleside Online, narración y comprensión
+- Biblia: lectura de las Escrituras y conversación
+- Ciencias/Historia: exploración guiada y conexiones
+"#;
+
+const ES_SHOPPING_SKILL_TEMPLATE: &str = r#"# Habilidad de compras
+
+Gestiona las listas de compras y la planificación de comidas.
+
+## Capacidades
+
+- Añadir/quitar artículos de memory/food/shopping-lists.md
+- Sugerir comidas a partir de memory/food/meal-plans.md
+- Llevar el inventario de la despensa
+"#;
+
+const ES_MAINTENANCE_SKILL_TEMPLATE: &str = r#"# Habilidad de mantenimiento
+
+Lleva el calendario y las tareas de mantenimiento de la casa.
+
+## Capacidades
+
+- Revisar memory/home/maintenance.md para ver las tareas próximas
+- Llevar los contactos de técnicos y las garantías
+- Revisión semanal en el heartbeat del mantenimiento atrasado
+"#;
+
 /// Initialize state directory with .gitignore
 pub fn init_state_dir(state_dir: &Path) -> Result<()> {
     fs::create_dir_all(state_dir)?;
@@ -420,3 +884,92 @@ memory/*.sqlite-shm
 *~
 .DS_Store
 "#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_minimal_pack_skips_home_files() {
+        let tmp = TempDir::new().unwrap();
+        let workspace = tmp.path().join("workspace");
+
+        assert!(init_workspace_with_templates(&workspace, "minimal", Language::English).unwrap());
+        assert!(workspace.join("SOUL.md").exists());
+        assert!(!workspace
+            .join("memory/business/ergotools-status.md")
+            .exists());
+    }
+
+    #[test]
+    fn test_state_dir_templates_override_pack() {
+        let tmp = TempDir::new().unwrap();
+        let overrides = tmp.path().join("templates/memory/garden");
+        fs::create_dir_all(&overrides).unwrap();
+        fs::write(overrides.join("plants.md"), "# Plants\n").unwrap();
+        fs::write(tmp.path().join("templates/SOUL.md"), "# Custom soul\n").unwrap();
+
+        let workspace = tmp.path().join("workspace");
+        init_workspace_with_templates(&workspace, "home", Language::English).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(workspace.join("SOUL.md")).unwrap(),
+            "# Custom soul\n"
+        );
+        assert!(workspace.join("memory/garden/plants.md").exists());
+        assert!(workspace.join("skills/family").is_dir());
+    }
+
+    #[test]
+    fn test_directory_pack_and_existing_files() {
+        let tmp = TempDir::new().unwrap();
+        let pack = tmp.path().join("pack");
+        fs::create_dir_all(&pack).unwrap();
+        fs::write(pack.join("MEMORY.md"), "# Pack memory\n").unwrap();
+
+        let workspace = tmp.path().join("workspace");
+        fs::create_dir_all(&workspace).unwrap();
+        fs::write(workspace.join("MEMORY.md"), "# Mine\n").unwrap();
+
+        let is_new =
+            init_workspace_with_templates(&workspace, pack.to_str().unwrap(), Language::English)
+                .unwrap();
+        assert!(!is_new);
+        // Existing files are never overwritten
+        assert_eq!(
+            fs::read_to_string(workspace.join("MEMORY.md")).unwrap(),
+            "# Mine\n"
+        );
+        // Core files missing from the pack come from the minimal pack
+        assert!(workspace.join("HEARTBEAT.md").exists());
+
+        assert!(
+            init_workspace_with_templates(&workspace, "no-such-pack", Language::English).is_err()
+        );
+    }
+
+    #[test]
+    fn test_spanish_templates() {
+        let tmp = TempDir::new().unwrap();
+        // A user override in English still beats the built-in translation
+        fs::create_dir_all(tmp.path().join("templates")).unwrap();
+        fs::write(tmp.path().join("templates/MEMORY.md"), "# Ours\n").unwrap();
+
+        let workspace = tmp.path().join("workspace");
+        init_workspace_with_templates(&workspace, "home", Language::Spanish).unwrap();
+
+        let soul = fs::read_to_string(workspace.join("SOUL.md")).unwrap();
+        assert!(soul.contains("Personalidad del asistente del hogar"));
+        assert!(fs::read_to_string(workspace.join("skills/tutor/SKILL.md"))
+            .unwrap()
+            .contains("Habilidad de tutoría"));
+        assert_eq!(
+            fs::read_to_string(workspace.join("MEMORY.md")).unwrap(),
+            "# Ours\n"
+        );
+        // Translations aren't copied under their own names
+        assert!(!workspace.join("SOUL.es.md").exists());
+        assert!(!workspace.join("memory/family/members.es.md").exists());
+    }
+}
@@ -0,0 +1,314 @@
+//! Prometheus metrics
+//!
+//! Counters and histograms are kept per process and updated where the work
+//! happens: agent turns, LLM calls and tool calls in the agent, heartbeat
+//! runs when their event is emitted. Memory index gauges are read when the
+//! metrics are scraped. The daemon serves everything in the Prometheus text
+//! format on `/metrics` (see `server.metrics`).
+
+use once_cell::sync::Lazy;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Histogram bucket bounds in seconds, from a quick tool call to a long
+/// local-model turn
+const BUCKETS: [f64; 12] = [
+    0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0,
+];
+
+static REGISTRY: Lazy<Mutex<Registry>> = Lazy::new(Default::default);
+
+/// Memory index gauges, read at scrape time
+#[derive(Debug, Clone, Default)]
+pub struct IndexGauges {
+    pub files: usize,
+    pub chunks: usize,
+    pub size_bytes: u64,
+    /// Chunks waiting for an embedding
+    pub pending_embeddings: usize,
+}
+
+/// Record a finished agent turn (a message answered, tool calls included)
+pub fn record_turn(elapsed: Duration) {
+    with_registry(|r| r.turns.observe(elapsed));
+}
+
+/// Record a completed LLM request and the tokens it used
+pub fn record_llm_call(elapsed: Duration, input_tokens: u64, output_tokens: u64) {
+    with_registry(|r| {
+        r.llm_calls.observe(elapsed);
+        r.input_tokens += input_tokens;
+        r.output_tokens += output_tokens;
+    });
+}
+
+/// Record an LLM request that returned an error
+pub fn record_llm_failure() {
+    with_registry(|r| r.llm_failures += 1);
+}
+
+/// Record a tool call; `ok` is false when the tool returned an error
+pub fn record_tool_call(tool: &str, ok: bool) {
+    with_registry(|r| {
+        let counts = r.tools.entry(tool.to_string()).or_default();
+        counts.calls += 1;
+        if !ok {
+            counts.failures += 1;
+        }
+    });
+}
+
+/// Record a heartbeat run by status; `elapsed` is `None` for skipped runs
+pub fn record_heartbeat(status: &'static str, elapsed: Option<Duration>) {
+    with_registry(|r| {
+        *r.heartbeat_runs.entry(status).or_default() += 1;
+        if let Some(elapsed) = elapsed {
+            r.heartbeats.observe(elapsed);
+        }
+    });
+}
+
+/// All metrics in the Prometheus text exposition format
+pub fn render(index: Option<&IndexGauges>) -> String {
+    let registry = REGISTRY.lock().unwrap_or_else(|p| p.into_inner());
+    registry.render(index)
+}
+
+fn with_registry(update: impl FnOnce(&mut Registry)) {
+    let mut registry = REGISTRY.lock().unwrap_or_else(|p| p.into_inner());
+    update(&mut registry);
+}
+
+#[derive(Default)]
+struct Registry {
+    turns: Histogram,
+    llm_calls: Histogram,
+    llm_failures: u64,
+    input_tokens: u64,
+    output_tokens: u64,
+    tools: BTreeMap<String, ToolCounts>,
+    heartbeats: Histogram,
+    heartbeat_runs: BTreeMap<&'static str, u64>,
+}
+
+#[derive(Default)]
+struct ToolCounts {
+    calls: u64,
+    failures: u64,
+}
+
+/// Cumulative bucket counts, as Prometheus expects them
+#[derive(Default)]
+struct Histogram {
+    buckets: [u64; BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        for (bucket, bound) in self.buckets.iter_mut().zip(BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+
+    fn render(&self, out: &mut String, name: &str, help: &str) {
+        header(out, name, help, "histogram");
+        for (count, bound) in self.buckets.iter().zip(BUCKETS) {
+            let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bound, count);
+        }
+        let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, self.count);
+        let _ = writeln!(out, "{}_sum {}", name, self.sum);
+        let _ = writeln!(out, "{}_count {}", name, self.count);
+    }
+}
+
+impl Registry {
+    fn render(&self, index: Option<&IndexGauges>) -> String {
+        let mut out = String::new();
+
+        self.turns.render(
+            &mut out,
+            "homegpt_turn_duration_seconds",
+            "Time to answer a message, tool calls included",
+        );
+        self.llm_calls.render(
+            &mut out,
+            "homegpt_llm_request_duration_seconds",
+            "LLM request latency",
+        );
+        counter(
+            &mut out,
+            "homegpt_llm_request_failures_total",
+            "LLM requests that returned an error",
+            self.llm_failures,
+        );
+        header(
+            &mut out,
+            "homegpt_llm_tokens_total",
+            "Tokens used by LLM requests",
+            "counter",
+        );
+        let _ = writeln!(
+            out,
+            "homegpt_llm_tokens_total{{kind=\"input\"}} {}",
+            self.input_tokens
+        );
+        let _ = writeln!(
+            out,
+            "homegpt_llm_tokens_total{{kind=\"output\"}} {}",
+            self.output_tokens
+        );
+
+        header(
+            &mut out,
+            "homegpt_tool_calls_total",
+            "Tool calls by tool",
+            "counter",
+        );
+        for (tool, counts) in &self.tools {
+            let _ = writeln!(
+                out,
+                "homegpt_tool_calls_total{{tool=\"{}\"}} {}",
+                escape(tool),
+                counts.calls
+            );
+        }
+        header(
+            &mut out,
+            "homegpt_tool_call_failures_total",
+            "Tool calls that returned an error, by tool",
+            "counter",
+        );
+        for (tool, counts) in &self.tools {
+            let _ = writeln!(
+                out,
+                "homegpt_tool_call_failures_total{{tool=\"{}\"}} {}",
+                escape(tool),
+                counts.failures
+            );
+        }
+
+        self.heartbeats.render(
+            &mut out,
+            "homegpt_heartbeat_duration_seconds",
+            "Heartbeat run duration (skipped runs excluded)",
+        );
+        header(
+            &mut out,
+            "homegpt_heartbeat_runs_total",
+            "Heartbeat runs by status",
+            "counter",
+        );
+        for (status, count) in &self.heartbeat_runs {
+            let _ = writeln!(
+                out,
+                "homegpt_heartbeat_runs_total{{status=\"{}\"}} {}",
+                status, count
+            );
+        }
+
+        if let Some(index) = index {
+            gauge(
+                &mut out,
+                "homegpt_memory_index_files",
+                "Files in the memory index",
+                index.files as u64,
+            );
+            gauge(
+                &mut out,
+                "homegpt_memory_index_chunks",
+                "Chunks in the memory index",
+                index.chunks as u64,
+            );
+            gauge(
+                &mut out,
+                "homegpt_memory_index_size_bytes",
+                "Size of the memory index database",
+                index.size_bytes,
+            );
+            gauge(
+                &mut out,
+                "homegpt_embedding_queue_depth",
+                "Chunks waiting for an embedding",
+                index.pending_embeddings as u64,
+            );
+        }
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn counter(out: &mut String, name: &str, help: &str, value: u64) {
+    header(out, name, help, "counter");
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, value: u64) {
+    header(out, name, help, "gauge");
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+/// Escape a label value
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let mut registry = Registry::default();
+        registry.turns.observe(Duration::from_millis(300));
+        registry.turns.observe(Duration::from_secs(20));
+        registry.tools.insert(
+            "set_timer".to_string(),
+            ToolCounts {
+                calls: 3,
+                failures: 1,
+            },
+        );
+        registry.heartbeat_runs.insert("ok", 2);
+
+        let text = registry.render(Some(&IndexGauges {
+            files: 4,
+            chunks: 120,
+            size_bytes: 65536,
+            pending_embeddings: 7,
+        }));
+        assert!(text.contains("# TYPE homegpt_turn_duration_seconds histogram\n"));
+        assert!(text.contains("homegpt_turn_duration_seconds_bucket{le=\"0.25\"} 0\n"));
+        assert!(text.contains("homegpt_turn_duration_seconds_bucket{le=\"0.5\"} 1\n"));
+        assert!(text.contains("homegpt_turn_duration_seconds_bucket{le=\"30\"} 2\n"));
+        assert!(text.contains("homegpt_turn_duration_seconds_bucket{le=\"+Inf\"} 2\n"));
+        assert!(text.contains("homegpt_turn_duration_seconds_count 2\n"));
+        assert!(text.contains("homegpt_tool_calls_total{tool=\"set_timer\"} 3\n"));
+        assert!(text.contains("homegpt_tool_call_failures_total{tool=\"set_timer\"} 1\n"));
+        assert!(text.contains("homegpt_heartbeat_runs_total{status=\"ok\"} 2\n"));
+        assert!(text.contains("homegpt_embedding_queue_depth 7\n"));
+
+        // Without the index only the process metrics are exported
+        assert!(!registry.render(None).contains("homegpt_memory_index"));
+    }
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
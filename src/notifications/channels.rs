@@ -0,0 +1,167 @@
+//! Push channels: ntfy and Pushover
+
+use anyhow::Result;
+use async_trait::async_trait;
+use std::time::Duration;
+
+use super::{Actions, Channel, Event};
+use crate::config::NotificationChannelConfig;
+
+const PUSHOVER_URL: &str = "https://api.pushover.net/1/messages.json";
+
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn client() -> Result<reqwest::Client> {
+    Ok(reqwest::Client::builder().timeout(SEND_TIMEOUT).build()?)
+}
+
+async fn check(response: reqwest::Response, service: &str) -> Result<()> {
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("{} {}: {}", service, status, body);
+    }
+    Ok(())
+}
+
+/// Publishes to an ntfy topic (ntfy.sh or self-hosted)
+pub struct NtfyChannel {
+    name: String,
+    url: String,
+    token: String,
+    client: reqwest::Client,
+}
+
+impl NtfyChannel {
+    pub fn new(config: &NotificationChannelConfig) -> Result<Self> {
+        if config.topic.is_empty() {
+            anyhow::bail!("Notification channel {} needs an ntfy topic", config.name);
+        }
+        Ok(Self {
+            name: config.name.clone(),
+            url: format!("{}/{}", config.server.trim_end_matches('/'), config.topic),
+            token: config.token.clone(),
+            client: client()?,
+        })
+    }
+
+    fn request(&self, event: Event, message: &str) -> reqwest::RequestBuilder {
+        let priority = match event {
+            Event::Emergency => "max",
+            event if event.is_urgent() => "high",
+            _ => "default",
+        };
+        let request = self
+            .client
+            .post(&self.url)
+            .header("Title", event.title())
+            .header("Priority", priority)
+            .header("Tags", event.as_str())
+            .body(message.to_string());
+        if self.token.is_empty() {
+            request
+        } else {
+            request.bearer_auth(&self.token)
+        }
+    }
+}
+
+#[async_trait]
+impl Channel for NtfyChannel {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn send(&self, event: Event, message: &str) -> Result<()> {
+        check(self.request(event, message).send().await?, "ntfy").await
+    }
+
+    /// ntfy action buttons, each POSTing to its URL; tapping the
+    /// notification itself opens the link
+    async fn send_with_actions(
+        &self,
+        event: Event,
+        message: &str,
+        actions: &Actions,
+    ) -> Result<()> {
+        let buttons: Vec<String> = actions
+            .buttons
+            .iter()
+            .map(|(label, url)| format!("http, {}, {}, method=POST, clear=true", label, url))
+            .collect();
+        let request = self
+            .request(event, message)
+            .header("Actions", buttons.join("; "))
+            .header("Click", &actions.link);
+        check(request.send().await?, "ntfy").await
+    }
+}
+
+/// Sends through the Pushover API
+pub struct PushoverChannel {
+    name: String,
+    token: String,
+    user_key: String,
+    client: reqwest::Client,
+}
+
+impl PushoverChannel {
+    pub fn new(config: &NotificationChannelConfig) -> Result<Self> {
+        if config.token.is_empty() || config.user_key.is_empty() {
+            anyhow::bail!(
+                "Notification channel {} needs a Pushover token and user_key",
+                config.name
+            );
+        }
+        Ok(Self {
+            name: config.name.clone(),
+            token: config.token.clone(),
+            user_key: config.user_key.clone(),
+            client: client()?,
+        })
+    }
+
+    async fn post(&self, event: Event, message: &str, url: Option<&str>) -> Result<()> {
+        // High priority (bypasses the phone's own quiet hours) for
+        // emergencies, normal for urgent events, quiet (no sound) otherwise
+        let priority = match event {
+            Event::Emergency => "1",
+            event if event.is_urgent() => "0",
+            _ => "-1",
+        };
+        let mut form = vec![
+            ("token", self.token.as_str()),
+            ("user", self.user_key.as_str()),
+            ("title", event.title()),
+            ("message", message),
+            ("priority", priority),
+        ];
+        if let Some(url) = url {
+            form.push(("url", url));
+            form.push(("url_title", "Answer"));
+        }
+        let response = self.client.post(PUSHOVER_URL).form(&form).send().await?;
+        check(response, "Pushover").await
+    }
+}
+
+#[async_trait]
+impl Channel for PushoverChannel {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn send(&self, event: Event, message: &str) -> Result<()> {
+        self.post(event, message, None).await
+    }
+
+    /// Pushover has no buttons; the link is attached as the message's URL
+    async fn send_with_actions(
+        &self,
+        event: Event,
+        message: &str,
+        actions: &Actions,
+    ) -> Result<()> {
+        self.post(event, message, Some(&actions.link)).await
+    }
+}
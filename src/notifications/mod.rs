@@ -0,0 +1,428 @@
+//! Notifications: heartbeat alerts, digests and failures pushed to phones
+//!
+//! Each channel in `[[notifications.channels]]` (ntfy, Pushover) and each
+//! `[[webhooks.outbound]]` entry subscribes to some events, so alerts can go
+//! to everyone's phone while digests go somewhere quieter. Reminders and
+//! timers go out the same way (see [`crate::reminders`], [`crate::timers`]).
+//! During quiet hours (see [`crate::quiet`]) events are held and sent once
+//! they end.
+
+mod channels;
+
+pub use channels::{NtfyChannel, PushoverChannel};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use std::fmt;
+use std::sync::Mutex;
+use tracing::{debug, info, warn};
+
+use crate::config::Config;
+use crate::locale;
+use crate::quiet::QuietPolicy;
+use crate::webhooks::WebhookChannel;
+
+/// Something a channel can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// The heartbeat has something to tell the user
+    Alert,
+    /// A daily or monthly digest is ready (weather, finance report)
+    Digest,
+    HeartbeatFailed,
+    /// Tool calls are waiting for the user's approval
+    ApprovalRequested,
+    /// A reminder set with the set_reminder tool is due
+    Reminder,
+    /// A kitchen timer went off
+    Timer,
+    /// Something that can't wait until morning (smoke, a leak); sent even
+    /// during quiet hours
+    Emergency,
+}
+
+impl Event {
+    pub const ALL: [Event; 7] = [
+        Self::Alert,
+        Self::Digest,
+        Self::HeartbeatFailed,
+        Self::ApprovalRequested,
+        Self::Reminder,
+        Self::Timer,
+        Self::Emergency,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Alert => "alert",
+            Self::Digest => "digest",
+            Self::HeartbeatFailed => "heartbeat_failed",
+            Self::ApprovalRequested => "approval_requested",
+            Self::Reminder => "reminder",
+            Self::Timer => "timer",
+            Self::Emergency => "emergency",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|event| event.as_str() == name)
+    }
+
+    /// Parse a channel's `events` list (empty = every event)
+    pub fn parse_list(names: &[String], channel: &str) -> Result<Vec<Self>> {
+        names
+            .iter()
+            .map(|name| {
+                Self::parse(name).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Unknown event '{}' for {}: expected one of {}",
+                        name,
+                        channel,
+                        Self::ALL.map(|e| e.as_str()).join(", ")
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Notification title, in the configured language
+    pub fn title(&self) -> &'static str {
+        let key = match self {
+            Self::Alert => "notification.alert",
+            Self::Digest => "notification.digest",
+            Self::HeartbeatFailed => "notification.heartbeat_failed",
+            Self::ApprovalRequested => "notification.approval_requested",
+            Self::Reminder => "notification.reminder",
+            Self::Timer => "notification.timer",
+            Self::Emergency => "notification.emergency",
+        };
+        locale::current().text(key)
+    }
+
+    /// Worth interrupting someone for
+    pub fn is_urgent(&self) -> bool {
+        matches!(
+            self,
+            Self::Alert | Self::ApprovalRequested | Self::Reminder | Self::Timer | Self::Emergency
+        )
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Buttons for a notification, such as Approve and Deny
+#[derive(Debug, Clone, PartialEq)]
+pub struct Actions {
+    /// (label, URL POSTed when tapped)
+    pub buttons: Vec<(String, String)>,
+    /// Page with the same choices, for channels without buttons
+    pub link: String,
+}
+
+/// Somewhere notifications can be sent
+#[async_trait]
+pub trait Channel: Send + Sync {
+    /// Name from config (or the URL, for webhooks)
+    fn name(&self) -> &str;
+
+    async fn send(&self, event: Event, message: &str) -> Result<()>;
+
+    /// Send with buttons; channels without them send `actions.link`
+    async fn send_with_actions(
+        &self,
+        event: Event,
+        message: &str,
+        actions: &Actions,
+    ) -> Result<()> {
+        self.send(event, &format!("{}\n{}", message, actions.link))
+            .await
+    }
+}
+
+struct Subscription {
+    channel: Box<dyn Channel>,
+    /// Empty = every event
+    events: Vec<Event>,
+}
+
+impl Subscription {
+    /// Channels that take alerts take emergencies too
+    fn wants(&self, event: Event) -> bool {
+        self.events.is_empty()
+            || self.events.contains(&event)
+            || (event == Event::Emergency && self.events.contains(&Event::Alert))
+    }
+}
+
+/// A notification waiting for quiet hours to end
+struct Held {
+    /// Index into `subscriptions`
+    subscription: usize,
+    event: Event,
+    message: String,
+}
+
+/// Sends each event to the channels subscribed to it
+#[derive(Default)]
+pub struct Notifier {
+    subscriptions: Vec<Subscription>,
+    policy: QuietPolicy,
+    held: Mutex<Vec<Held>>,
+}
+
+impl Notifier {
+    /// Notifier for `notifications.channels` and `webhooks.outbound`, or
+    /// `None` if neither has entries
+    pub fn from_config(config: &Config) -> Result<Option<Self>> {
+        let mut notifier = Self::default();
+        for channel in &config.notifications.channels {
+            let boxed: Box<dyn Channel> = match channel.kind.as_str() {
+                "ntfy" => Box::new(NtfyChannel::new(channel)?),
+                "pushover" => Box::new(PushoverChannel::new(channel)?),
+                other => anyhow::bail!(
+                    "Unknown notification channel kind '{}' for {}: expected ntfy or pushover",
+                    other,
+                    channel.name
+                ),
+            };
+            notifier.add(boxed, Event::parse_list(&channel.events, &channel.name)?);
+        }
+        for hook in &config.webhooks.outbound {
+            let events = Event::parse_list(&hook.events, &hook.url)?;
+            notifier.add(Box::new(WebhookChannel::new(hook)?), events);
+        }
+
+        if notifier.subscriptions.is_empty() {
+            return Ok(None);
+        }
+        notifier.policy = QuietPolicy::from_config(config)?;
+        Ok(Some(notifier))
+    }
+
+    /// Hold events back according to `policy`
+    pub fn with_policy(mut self, policy: QuietPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Send `events` (empty = all) to `channel`
+    pub fn add(&mut self, channel: Box<dyn Channel>, events: Vec<Event>) {
+        self.subscriptions.push(Subscription { channel, events });
+    }
+
+    /// Whether any channel subscribes to `event`
+    pub fn wants(&self, event: Event) -> bool {
+        self.subscriptions.iter().any(|s| s.wants(event))
+    }
+
+    /// Send `message` with `actions` to the channels subscribed to `event`,
+    /// returning the channels it reached. Nothing is held for quiet hours:
+    /// the answer would come too late.
+    pub async fn notify_with_actions(
+        &self,
+        event: Event,
+        message: &str,
+        actions: &Actions,
+    ) -> Vec<String> {
+        let now = locale::now().naive_local();
+        let mut reached = Vec::new();
+        for subscription in self.subscriptions.iter().filter(|s| s.wants(event)) {
+            let channel = &subscription.channel;
+            if let Some(reason) = self.policy.channel_hold(event, channel.name(), now) {
+                info!("Not sending {} to {} ({})", event, channel.name(), reason);
+                continue;
+            }
+            match channel.send_with_actions(event, message, actions).await {
+                Ok(()) => reached.push(channel.name().to_string()),
+                Err(e) => warn!("{} notification to {} failed: {}", event, channel.name(), e),
+            }
+        }
+        reached
+    }
+
+    /// Send `message` to every channel subscribed to `event`, returning the
+    /// channels it reached. Channels in quiet hours get it when they end.
+    /// Failures are logged, not returned: a dead channel shouldn't break the
+    /// caller.
+    pub async fn notify(&self, event: Event, message: &str) -> Vec<String> {
+        self.notify_at(event, message, locale::now().naive_local())
+            .await
+    }
+
+    pub async fn notify_at(&self, event: Event, message: &str, now: NaiveDateTime) -> Vec<String> {
+        let mut reached = Vec::new();
+        for (index, subscription) in self.subscriptions.iter().enumerate() {
+            if !subscription.wants(event) {
+                continue;
+            }
+            let channel = &subscription.channel;
+            if let Some(reason) = self.policy.channel_hold(event, channel.name(), now) {
+                info!(
+                    "Holding {} notification to {} ({})",
+                    event,
+                    channel.name(),
+                    reason
+                );
+                self.held.lock().unwrap().push(Held {
+                    subscription: index,
+                    event,
+                    message: message.to_string(),
+                });
+                continue;
+            }
+            if send(subscription, event, message).await {
+                reached.push(channel.name().to_string());
+            }
+        }
+        reached
+    }
+
+    /// Send notifications held for quiet hours that have since ended,
+    /// returning how many went out. Called by the daemon's loops.
+    pub async fn send_held(&self) -> usize {
+        self.send_held_at(locale::now().naive_local()).await
+    }
+
+    pub async fn send_held_at(&self, now: NaiveDateTime) -> usize {
+        let due: Vec<Held> = {
+            let mut held = self.held.lock().unwrap();
+            let (due, waiting) = held.drain(..).partition(|h| {
+                let channel = self.subscriptions[h.subscription].channel.name();
+                self.policy.channel_hold(h.event, channel, now).is_none()
+            });
+            *held = waiting;
+            due
+        };
+
+        let mut sent = 0;
+        for held in due {
+            if send(
+                &self.subscriptions[held.subscription],
+                held.event,
+                &held.message,
+            )
+            .await
+            {
+                sent += 1;
+            }
+        }
+        sent
+    }
+}
+
+async fn send(subscription: &Subscription, event: Event, message: &str) -> bool {
+    let channel = &subscription.channel;
+    match channel.send(event, message).await {
+        Ok(()) => {
+            debug!("Sent {} notification to {}", event, channel.name());
+            true
+        }
+        Err(e) => {
+            warn!("{} notification to {} failed: {}", event, channel.name(), e);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    struct FakeChannel {
+        name: String,
+        sent: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl Channel for FakeChannel {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        async fn send(&self, event: Event, message: &str) -> Result<()> {
+            self.sent
+                .lock()
+                .unwrap()
+                .push(format!("{} {}: {}", self.name, event, message));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_notify_routes_by_event() {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let mut notifier = Notifier::default();
+        for (name, events) in [
+            ("phones", vec![Event::Alert, Event::HeartbeatFailed]),
+            ("everything", vec![]),
+        ] {
+            let channel = FakeChannel {
+                name: name.to_string(),
+                sent: Arc::clone(&sent),
+            };
+            notifier.add(Box::new(channel), events);
+        }
+
+        let reached = notifier.notify(Event::Alert, "Rain at 3pm").await;
+        assert_eq!(reached, vec!["phones", "everything"]);
+        notifier.notify(Event::Digest, "Sunny all day").await;
+        assert_eq!(
+            *sent.lock().unwrap(),
+            vec![
+                "phones alert: Rain at 3pm",
+                "everything alert: Rain at 3pm",
+                "everything digest: Sunny all day",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_quiet_hours_hold_until_morning() {
+        use crate::config::TimeWindow;
+        use crate::quiet::Schedule;
+
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let quiet = Schedule::every_night(TimeWindow::parse("21:30", "07:00").unwrap());
+        let mut notifier = Notifier::default().with_policy(QuietPolicy::new(quiet, vec![]));
+        let channel = FakeChannel {
+            name: "phones".to_string(),
+            sent: Arc::clone(&sent),
+        };
+        notifier.add(Box::new(channel), vec![Event::Alert]);
+        let at = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+
+        let reached = notifier
+            .notify_at(Event::Alert, "Garage door open", at("2026-10-15 02:00"))
+            .await;
+        assert!(reached.is_empty());
+        notifier
+            .notify_at(Event::Emergency, "Water leak", at("2026-10-15 02:05"))
+            .await;
+        assert_eq!(notifier.send_held_at(at("2026-10-15 06:30")).await, 0);
+        assert_eq!(notifier.send_held_at(at("2026-10-15 07:00")).await, 1);
+        assert_eq!(
+            *sent.lock().unwrap(),
+            vec![
+                "phones emergency: Water leak",
+                "phones alert: Garage door open",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_events() {
+        let names = vec!["alert".to_string(), "digest".to_string()];
+        assert_eq!(
+            Event::parse_list(&names, "phones").unwrap(),
+            vec![Event::Alert, Event::Digest]
+        );
+        let err = Event::parse_list(&["digest_ready".to_string()], "phones").unwrap_err();
+        assert!(err.to_string().contains("Unknown event 'digest_ready'"));
+    }
+}
@@ -0,0 +1,71 @@
+//! Home Assistant REST API client
+
+use anyhow::Result;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::config::HomeAssistantConfig;
+
+#[derive(Debug, Deserialize)]
+struct EntityState {
+    state: String,
+}
+
+pub struct HomeAssistant {
+    url: String,
+    token: String,
+    client: reqwest::Client,
+}
+
+impl HomeAssistant {
+    pub fn new(config: &HomeAssistantConfig) -> Self {
+        Self {
+            url: config.url.trim_end_matches('/').to_string(),
+            token: config.token.clone(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Current state of an entity, e.g. "home" for `person.ella`
+    pub async fn state(&self, entity: &str) -> Result<String> {
+        let response = self
+            .client
+            .get(format!("{}/api/states/{}", self.url, entity))
+            .bearer_auth(&self.token)
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            anyhow::bail!("Home Assistant {}: {}", entity, status);
+        }
+        let state: EntityState = response.json().await?;
+        Ok(state.state)
+    }
+
+    /// Call a service, e.g. `tts.speak`
+    pub async fn call_service(&self, domain: &str, service: &str, data: &Value) -> Result<()> {
+        let response = self
+            .client
+            .post(format!("{}/api/services/{}/{}", self.url, domain, service))
+            .bearer_auth(&self.token)
+            .json(data)
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("Home Assistant {}.{}: {} {}", domain, service, status, body);
+        }
+        Ok(())
+    }
+}
+
+/// Whether a person/device_tracker state means home (None if unknown)
+pub fn is_home(state: &str) -> Option<bool> {
+    match state {
+        "home" => Some(true),
+        "unknown" | "unavailable" | "" => None,
+        // "not_home" or the name of another zone
+        _ => Some(false),
+    }
+}
@@ -0,0 +1,263 @@
+//! Who's home, from the family's phones
+//!
+//! Each person in `presence.people` is tracked through Home Assistant (a
+//! `person` or `device_tracker` entity, polled when asked) and/or OwnTracks
+//! (region events over MQTT, received by the daemon and saved to
+//! `~/.homegpt/presence.json`). The heartbeat uses this to hold back spoken
+//! announcements when nobody is home or the kids are asleep.
+
+mod home_assistant;
+mod owntracks;
+
+pub use home_assistant::HomeAssistant;
+pub use owntracks::run_owntracks;
+
+use anyhow::Result;
+use chrono::{DateTime, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use crate::config::{Config, PersonConfig, TimeWindow};
+
+/// OwnTracks sightings saved by the daemon (relative to the state dir)
+const STATE_FILE: &str = "presence.json";
+
+/// Last OwnTracks report for a person
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sighting {
+    pub home: bool,
+    pub updated_at: DateTime<Utc>,
+}
+
+fn load_sightings(path: &Path) -> BTreeMap<String, Sighting> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_sighting(path: &Path, name: &str, home: bool, now: DateTime<Utc>) -> Result<()> {
+    let mut sightings = load_sightings(path);
+    sightings.insert(
+        name.to_string(),
+        Sighting {
+            home,
+            updated_at: now,
+        },
+    );
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(&sightings)?)?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PersonStatus {
+    pub name: String,
+    pub kid: bool,
+    /// None when no source knows
+    pub home: Option<bool>,
+}
+
+/// Everyone's presence at one moment
+#[derive(Debug, Clone, Default)]
+pub struct PresenceSnapshot {
+    pub people: Vec<PersonStatus>,
+}
+
+impl PresenceSnapshot {
+    fn names(&self, home: Option<bool>) -> Vec<&str> {
+        self.people
+            .iter()
+            .filter(|p| p.home == home)
+            .map(|p| p.name.as_str())
+            .collect()
+    }
+
+    /// Everyone's whereabouts are known and nobody is home
+    pub fn house_empty(&self) -> bool {
+        !self.people.is_empty() && self.people.iter().all(|p| p.home == Some(false))
+    }
+
+    pub fn kids_home(&self) -> bool {
+        self.people.iter().any(|p| p.kid && p.home == Some(true))
+    }
+
+    /// "Home: Dad, Ella. Away: Mom. Unknown: Sam."
+    pub fn summary(&self) -> String {
+        let groups = [
+            ("Home", self.names(Some(true))),
+            ("Away", self.names(Some(false))),
+            ("Unknown", self.names(None)),
+        ];
+        groups
+            .iter()
+            .filter(|(_, names)| !names.is_empty())
+            .map(|(label, names)| format!("{}: {}.", label, names.join(", ")))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Presence for the configured people
+pub struct Presence {
+    people: Vec<PersonConfig>,
+    home_assistant: Option<HomeAssistant>,
+    kids_bedtime: Option<TimeWindow>,
+    state_path: PathBuf,
+}
+
+impl Presence {
+    /// Presence for `presence.people`, or `None` if nobody is configured
+    pub fn from_config(config: &Config, state_dir: &Path) -> Result<Option<Self>> {
+        let presence = &config.presence;
+        if presence.people.is_empty() {
+            return Ok(None);
+        }
+
+        let kids_bedtime = match presence.kids_bedtime {
+            Some(ref hours) => Some(
+                TimeWindow::parse(&hours.start, &hours.end)
+                    .map_err(|e| anyhow::anyhow!("Invalid presence.kids_bedtime: {}", e))?,
+            ),
+            None => None,
+        };
+
+        if config.home_assistant.is_none() && presence.people.iter().any(|p| p.entity.is_some()) {
+            warn!("presence.people use Home Assistant entities but [home_assistant] is not set");
+        }
+
+        Ok(Some(Self {
+            people: presence.people.clone(),
+            home_assistant: config.home_assistant.as_ref().map(HomeAssistant::new),
+            kids_bedtime,
+            state_path: state_dir.join(STATE_FILE),
+        }))
+    }
+
+    /// Who is home now. Home Assistant is asked first; OwnTracks sightings
+    /// fill in for people it doesn't know about.
+    pub async fn snapshot(&self) -> PresenceSnapshot {
+        let sightings = load_sightings(&self.state_path);
+        let mut people = Vec::with_capacity(self.people.len());
+        for person in &self.people {
+            let mut home = None;
+            if let (Some(ha), Some(entity)) = (&self.home_assistant, &person.entity) {
+                match ha.state(entity).await {
+                    Ok(state) => home = home_assistant::is_home(&state),
+                    Err(e) => warn!("Presence for {} unavailable: {}", person.name, e),
+                }
+            }
+            if home.is_none() && person.owntracks.is_some() {
+                home = sightings.get(&person.name).map(|s| s.home);
+            }
+            people.push(PersonStatus {
+                name: person.name.clone(),
+                kid: person.kid,
+                home,
+            });
+        }
+        PresenceSnapshot { people }
+    }
+
+    fn is_bedtime(&self, now: NaiveTime) -> bool {
+        self.kids_bedtime.is_some_and(|window| window.contains(now))
+    }
+
+    /// Why spoken announcements should be held back right now, if they should
+    pub fn quiet_reason(
+        &self,
+        snapshot: &PresenceSnapshot,
+        now: NaiveTime,
+    ) -> Option<&'static str> {
+        if snapshot.house_empty() {
+            Some("nobody is home")
+        } else if snapshot.kids_home() && self.is_bedtime(now) {
+            Some("the kids are asleep")
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ActiveHours, PresenceConfig};
+    use tempfile::TempDir;
+
+    fn person(name: &str, kid: bool) -> PersonConfig {
+        PersonConfig {
+            name: name.to_string(),
+            kid,
+            entity: None,
+            owntracks: Some(name.to_lowercase()),
+        }
+    }
+
+    fn presence(temp: &TempDir) -> Presence {
+        let config = Config {
+            presence: PresenceConfig {
+                people: vec![person("Dad", false), person("Ella", true)],
+                owntracks: None,
+                kids_bedtime: Some(ActiveHours {
+                    start: "20:00".to_string(),
+                    end: "07:00".to_string(),
+                }),
+            },
+            ..Default::default()
+        };
+        Presence::from_config(&config, temp.path())
+            .unwrap()
+            .unwrap()
+    }
+
+    fn time(s: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(s, "%H:%M").unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_from_sightings() {
+        let temp = TempDir::new().unwrap();
+        let presence = presence(&temp);
+        let path = temp.path().join(STATE_FILE);
+
+        let snapshot = presence.snapshot().await;
+        assert_eq!(snapshot.summary(), "Unknown: Dad, Ella.");
+        assert!(!snapshot.house_empty());
+
+        save_sighting(&path, "Dad", false, Utc::now()).unwrap();
+        save_sighting(&path, "Ella", true, Utc::now()).unwrap();
+        let snapshot = presence.snapshot().await;
+        assert_eq!(snapshot.summary(), "Home: Ella. Away: Dad.");
+        assert!(snapshot.kids_home());
+    }
+
+    #[tokio::test]
+    async fn test_quiet_reason() {
+        let temp = TempDir::new().unwrap();
+        let presence = presence(&temp);
+        let path = temp.path().join(STATE_FILE);
+
+        save_sighting(&path, "Dad", true, Utc::now()).unwrap();
+        save_sighting(&path, "Ella", true, Utc::now()).unwrap();
+        let snapshot = presence.snapshot().await;
+        assert_eq!(presence.quiet_reason(&snapshot, time("12:00")), None);
+        assert_eq!(
+            presence.quiet_reason(&snapshot, time("21:30")),
+            Some("the kids are asleep")
+        );
+
+        save_sighting(&path, "Dad", false, Utc::now()).unwrap();
+        save_sighting(&path, "Ella", false, Utc::now()).unwrap();
+        let snapshot = presence.snapshot().await;
+        assert_eq!(
+            presence.quiet_reason(&snapshot, time("12:00")),
+            Some("nobody is home")
+        );
+    }
+}
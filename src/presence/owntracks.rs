@@ -0,0 +1,159 @@
+//! OwnTracks over MQTT
+//!
+//! Phones publish to `owntracks/<user>/<device>`: location messages list the
+//! regions the phone is in (`inregions`), and `.../event` transition
+//! messages report entering or leaving one. Either is enough to tell whether
+//! the phone is in the home region.
+
+use anyhow::Result;
+use chrono::Utc;
+use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS};
+use serde::Deserialize;
+use std::path::Path;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+use super::{save_sighting, STATE_FILE};
+use crate::config::{Config, PersonConfig};
+
+/// Wait before reconnecting after a broker error
+const RECONNECT_DELAY: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Deserialize)]
+struct Message {
+    #[serde(rename = "_type")]
+    kind: String,
+    #[serde(default)]
+    inregions: Vec<String>,
+    #[serde(default)]
+    event: Option<String>,
+    #[serde(default)]
+    desc: Option<String>,
+}
+
+/// "user/device" from an OwnTracks topic
+fn device(topic: &str) -> Option<String> {
+    let mut parts = topic.split('/').skip(1);
+    let user = parts.next()?;
+    let device = parts.next()?;
+    Some(format!("{}/{}", user, device))
+}
+
+/// Whether a message says the phone is home (None if it doesn't say)
+fn parse_home(payload: &[u8], home_region: &str) -> Option<bool> {
+    let message: Message = serde_json::from_slice(payload).ok()?;
+    let is_home_region = |region: &str| region.eq_ignore_ascii_case(home_region);
+    match message.kind.as_str() {
+        "location" => Some(message.inregions.iter().any(|r| is_home_region(r))),
+        "transition" if message.desc.as_deref().is_some_and(is_home_region) => {
+            match message.event.as_deref() {
+                Some("enter") => Some(true),
+                Some("leave") => Some(false),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Person configured for a "user/device" (matching "user/device" or "user")
+fn person_for<'a>(people: &'a [PersonConfig], device: &str) -> Option<&'a PersonConfig> {
+    let user = device.split('/').next().unwrap_or(device);
+    people.iter().find(|p| {
+        p.owntracks
+            .as_deref()
+            .is_some_and(|id| id.eq_ignore_ascii_case(device) || id.eq_ignore_ascii_case(user))
+    })
+}
+
+/// Listen for OwnTracks messages and save who is home to the state dir.
+/// Runs until the task is aborted, reconnecting after broker errors.
+pub async fn run_owntracks(config: &Config, state_dir: &Path) -> Result<()> {
+    let Some(ref owntracks) = config.presence.owntracks else {
+        return Ok(());
+    };
+    let people = &config.presence.people;
+    let state_path = state_dir.join(STATE_FILE);
+
+    let mut options = MqttOptions::new(
+        format!("homegpt-{}", std::process::id()),
+        owntracks.host.clone(),
+        owntracks.port,
+    );
+    options.set_keep_alive(Duration::from_secs(60));
+    if !owntracks.username.is_empty() {
+        options.set_credentials(owntracks.username.clone(), owntracks.password.clone());
+    }
+    let (client, mut eventloop) = AsyncClient::new(options, 10);
+
+    loop {
+        match eventloop.poll().await {
+            // Subscribe on every (re)connect; the session isn't persistent
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                info!(
+                    "Connected to OwnTracks broker {}:{}",
+                    owntracks.host, owntracks.port
+                );
+                client
+                    .subscribe(owntracks.topic.clone(), QoS::AtLeastOnce)
+                    .await?;
+            }
+            Ok(Event::Incoming(Packet::Publish(publish))) => {
+                let Some(device) = device(&publish.topic) else {
+                    continue;
+                };
+                let Some(home) = parse_home(&publish.payload, &owntracks.home_region) else {
+                    continue;
+                };
+                let Some(person) = person_for(people, &device) else {
+                    debug!("OwnTracks message from unconfigured device {}", device);
+                    continue;
+                };
+                debug!("OwnTracks: {} home={}", person.name, home);
+                if let Err(e) = save_sighting(&state_path, &person.name, home, Utc::now()) {
+                    warn!("Failed to save presence: {}", e);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => {
+                warn!("OwnTracks MQTT error: {}", e);
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_home() {
+        let location = br#"{"_type":"location","lat":45.5,"lon":-122.6,"inregions":["Home"]}"#;
+        assert_eq!(parse_home(location, "home"), Some(true));
+
+        let away = br#"{"_type":"location","lat":45.6,"lon":-122.7}"#;
+        assert_eq!(parse_home(away, "home"), Some(false));
+
+        let leave = br#"{"_type":"transition","event":"leave","desc":"home"}"#;
+        assert_eq!(parse_home(leave, "home"), Some(false));
+
+        let other = br#"{"_type":"transition","event":"enter","desc":"school"}"#;
+        assert_eq!(parse_home(other, "home"), None);
+        assert_eq!(parse_home(b"not json", "home"), None);
+    }
+
+    #[test]
+    fn test_person_for_device() {
+        let people = vec![PersonConfig {
+            name: "Ella".to_string(),
+            kid: true,
+            entity: None,
+            owntracks: Some("ella".to_string()),
+        }];
+        let topic_device = device("owntracks/ella/pixel/event").unwrap();
+        assert_eq!(topic_device, "ella/pixel");
+        assert_eq!(person_for(&people, &topic_device).unwrap().name, "Ella");
+        assert!(person_for(&people, "dad/iphone").is_none());
+    }
+}
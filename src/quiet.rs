@@ -0,0 +1,265 @@
+//! Quiet hours: when notifications and spoken announcements wait
+//!
+//! `[quiet_hours]` sets the household's nights, with different hours for
+//! some weekdays (later on Fridays), and the events that go out anyway.
+//! `[[quiet_hours.people]]` gives someone their own nights for their own
+//! notification channels, e.g. a parent on call who wants alerts at 2am.
+//! Emergencies always go through. [`crate::notifications::Notifier`] holds
+//! back what it can't send and sends it once quiet hours end;
+//! [`crate::announce::Announcer`] drops held announcements.
+
+use anyhow::Result;
+use chrono::{Datelike, NaiveDateTime, Weekday};
+use std::collections::BTreeMap;
+
+use crate::config::{ActiveHours, Config, TimeWindow};
+use crate::notifications::Event;
+
+/// Quiet hours for each night of the week
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Schedule {
+    /// By the weekday the night starts, Monday first
+    nights: [Option<TimeWindow>; 7],
+}
+
+impl Schedule {
+    pub fn every_night(window: TimeWindow) -> Self {
+        Self {
+            nights: [Some(window); 7],
+        }
+    }
+
+    /// Schedule from `hours` (every night) and `days` (by weekday name)
+    pub fn parse(
+        hours: Option<&ActiveHours>,
+        days: &BTreeMap<String, ActiveHours>,
+        what: &str,
+    ) -> Result<Self> {
+        let window = |hours: &ActiveHours| {
+            TimeWindow::parse(&hours.start, &hours.end)
+                .map_err(|e| anyhow::anyhow!("Invalid {}: {}", what, e))
+        };
+        let mut schedule = match hours {
+            Some(hours) => Self::every_night(window(hours)?),
+            None => Self::default(),
+        };
+        for (day, hours) in days {
+            let weekday: Weekday = day.parse().map_err(|_| {
+                anyhow::anyhow!(
+                    "Invalid {}: unknown day '{}' (expected mon..sun)",
+                    what,
+                    day
+                )
+            })?;
+            schedule.nights[weekday.num_days_from_monday() as usize] = Some(window(hours)?);
+        }
+        Ok(schedule)
+    }
+
+    fn night(&self, day: Weekday) -> Option<TimeWindow> {
+        self.nights[day.num_days_from_monday() as usize]
+    }
+
+    /// The quiet hours `now` falls in, if any. A night that runs past
+    /// midnight belongs to the day it started; it ends at `end`.
+    pub fn active(&self, now: NaiveDateTime) -> Option<TimeWindow> {
+        let time = now.time();
+        let today = self.night(now.weekday()).filter(|w| {
+            if w.start <= w.end {
+                time >= w.start && time < w.end
+            } else {
+                time >= w.start
+            }
+        });
+        today.or_else(|| {
+            self.night(now.weekday().pred())
+                .filter(|w| w.start > w.end && time < w.end)
+        })
+    }
+}
+
+/// Someone with their own quiet hours on their own channels
+struct Person {
+    name: String,
+    channels: Vec<String>,
+    schedule: Schedule,
+    /// `None` = the household's
+    bypass: Option<Vec<Event>>,
+}
+
+/// Decides whether an event may interrupt someone right now
+#[derive(Default)]
+pub struct QuietPolicy {
+    house: Schedule,
+    /// For spoken announcements; `announce.quiet_hours` or the house's
+    speakers: Schedule,
+    bypass: Vec<Event>,
+    people: Vec<Person>,
+}
+
+impl QuietPolicy {
+    pub fn new(house: Schedule, bypass: Vec<Event>) -> Self {
+        Self {
+            speakers: house.clone(),
+            house,
+            bypass,
+            people: Vec::new(),
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let quiet = &config.quiet_hours;
+        let house = Schedule::parse(quiet.hours.as_ref(), &quiet.days, "quiet_hours")?;
+        let mut policy = Self::new(
+            house,
+            Event::parse_list(&quiet.bypass, "quiet_hours.bypass")?,
+        );
+        if let Some(ref hours) = config.announce.quiet_hours {
+            policy.speakers =
+                Schedule::parse(Some(hours), &BTreeMap::new(), "announce.quiet_hours")?;
+        }
+
+        for person in &quiet.people {
+            let what = format!("quiet_hours for {}", person.name);
+            let bypass = match person.bypass {
+                Some(ref names) => Some(Event::parse_list(names, &what)?),
+                None => None,
+            };
+            policy.add_person(
+                &person.name,
+                person.channels.clone(),
+                Schedule::parse(person.hours.as_ref(), &person.days, &what)?,
+                bypass,
+            );
+        }
+        Ok(policy)
+    }
+
+    /// Give `channels` `name`'s own schedule and, if set, bypass list
+    pub fn add_person(
+        &mut self,
+        name: &str,
+        channels: Vec<String>,
+        schedule: Schedule,
+        bypass: Option<Vec<Event>>,
+    ) {
+        self.people.push(Person {
+            name: name.to_string(),
+            channels,
+            schedule,
+            bypass,
+        });
+    }
+
+    /// Why `event` shouldn't go to notification `channel` at `now`, if it
+    /// shouldn't
+    pub fn channel_hold(&self, event: Event, channel: &str, now: NaiveDateTime) -> Option<String> {
+        let person = self
+            .people
+            .iter()
+            .find(|p| p.channels.iter().any(|c| c == channel));
+        match person {
+            Some(person) => {
+                let bypass = person.bypass.as_ref().unwrap_or(&self.bypass);
+                hold(event, bypass, &person.schedule, now)
+                    .map(|reason| format!("{}'s {}", person.name, reason))
+            }
+            None => hold(event, &self.bypass, &self.house, now),
+        }
+    }
+
+    /// Why `event` shouldn't be spoken on the house speakers at `now`, if
+    /// it shouldn't
+    pub fn speaker_hold(&self, event: Event, now: NaiveDateTime) -> Option<String> {
+        hold(event, &self.bypass, &self.speakers, now)
+    }
+}
+
+fn hold(event: Event, bypass: &[Event], schedule: &Schedule, now: NaiveDateTime) -> Option<String> {
+    if event == Event::Emergency || bypass.contains(&event) {
+        return None;
+    }
+    let window = schedule.active(now)?;
+    Some(format!(
+        "quiet hours ({} to {})",
+        window.start.format("%H:%M"),
+        window.end.format("%H:%M")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hours(start: &str, end: &str) -> ActiveHours {
+        ActiveHours {
+            start: start.to_string(),
+            end: end.to_string(),
+        }
+    }
+
+    /// 2026-10-16 is a Friday
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn schedule() -> Schedule {
+        let days = BTreeMap::from([("fri".to_string(), hours("23:00", "09:00"))]);
+        Schedule::parse(Some(&hours("21:30", "07:00")), &days, "quiet_hours").unwrap()
+    }
+
+    #[test]
+    fn test_schedule_nights() {
+        let schedule = schedule();
+        let active = |s: &str| {
+            schedule
+                .active(at(s))
+                .map(|w| w.start.format("%H:%M").to_string())
+        };
+
+        assert_eq!(active("2026-10-15 21:45").as_deref(), Some("21:30"));
+        assert_eq!(active("2026-10-16 06:59").as_deref(), Some("21:30"));
+        assert_eq!(active("2026-10-16 07:00"), None);
+        // Friday night starts later and runs into Saturday morning
+        assert_eq!(active("2026-10-16 22:00"), None);
+        assert_eq!(active("2026-10-17 08:30").as_deref(), Some("23:00"));
+        assert_eq!(active("2026-10-17 09:00"), None);
+
+        let err = Schedule::parse(
+            None,
+            &BTreeMap::from([("funday".to_string(), hours("21:00", "07:00"))]),
+            "quiet_hours",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("unknown day 'funday'"));
+    }
+
+    #[test]
+    fn test_policy_holds() {
+        let mut policy = QuietPolicy::new(schedule(), vec![Event::Reminder]);
+        let on_call = Schedule::every_night(TimeWindow::parse("23:00", "05:00").unwrap());
+        policy.add_person("Mom", vec!["mom".to_string()], on_call, None);
+        policy.add_person("Dad", vec!["dad".to_string()], Schedule::default(), None);
+        let night = at("2026-10-15 02:00");
+
+        assert_eq!(
+            policy
+                .channel_hold(Event::Alert, "phones", night)
+                .as_deref(),
+            Some("quiet hours (21:30 to 07:00)")
+        );
+        assert_eq!(
+            policy.channel_hold(Event::Alert, "mom", night).as_deref(),
+            Some("Mom's quiet hours (23:00 to 05:00)")
+        );
+        assert_eq!(
+            policy.channel_hold(Event::Alert, "mom", at("2026-10-15 06:00")),
+            None
+        );
+        assert_eq!(policy.channel_hold(Event::Alert, "dad", night), None);
+        assert_eq!(policy.channel_hold(Event::Reminder, "phones", night), None);
+        assert_eq!(policy.channel_hold(Event::Emergency, "phones", night), None);
+        assert!(policy.speaker_hold(Event::Timer, night).is_some());
+        assert_eq!(policy.speaker_hold(Event::Emergency, night), None);
+    }
+}
@@ -0,0 +1,314 @@
+//! Reminders: one-off and repeating reminders set in plain language
+//!
+//! The `set_reminder` tool parses times like "next Tuesday at 4" or "every
+//! other Saturday" (see [`parse_schedule`]) and stores the reminder in
+//! `~/.homegpt/reminders.db`. The daemon checks for due reminders every few
+//! seconds and sends them to the notification channels subscribed to the
+//! `reminder` event. Reminders that came due while the daemon was stopped
+//! go out when it starts, marked late.
+
+mod parse;
+
+pub use parse::{parse_schedule, Repeat, Schedule};
+
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+use crate::config::Config;
+use crate::locale;
+use crate::notifications::{Event, Notifier};
+
+/// Reminder database (relative to the state dir)
+const DB_FILE: &str = "reminders.db";
+
+/// How often the daemon checks for due reminders
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Reminders sent later than this say when they were due
+const LATE_AFTER_MINUTES: i64 = 5;
+
+const TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reminder {
+    pub id: i64,
+    pub message: String,
+    /// Next time it goes off (local)
+    pub due: NaiveDateTime,
+    pub repeat: Option<Repeat>,
+}
+
+impl Reminder {
+    /// "#3 Tue 20 Oct 16:00: Call the plumber (every week)"
+    pub fn summary(&self) -> String {
+        let mut text = format!(
+            "#{} {}: {}",
+            self.id,
+            locale::current().short_datetime(self.due),
+            self.message
+        );
+        if let Some(repeat) = self.repeat {
+            text.push_str(&format!(" ({})", repeat));
+        }
+        text
+    }
+
+    /// The notification text, noting when it was due if it's late
+    pub fn notification(&self, now: NaiveDateTime) -> String {
+        if (now - self.due).num_minutes() > LATE_AFTER_MINUTES {
+            let locale = locale::current();
+            locale.format(
+                "reminder.late",
+                &[
+                    ("message", &self.message),
+                    ("due", &locale.short_datetime(self.due)),
+                ],
+            )
+        } else {
+            self.message.clone()
+        }
+    }
+}
+
+/// Reminders in SQLite, shared by the tools and the daemon
+pub struct ReminderStore {
+    conn: Mutex<Connection>,
+}
+
+impl ReminderStore {
+    /// Open (or create) the store in `state_dir`
+    pub fn open(state_dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(state_dir)?;
+        let conn = Connection::open(state_dir.join(DB_FILE))?;
+        Self::with_connection(conn)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self> {
+        // The daemon and a chat session may both have it open
+        conn.busy_timeout(Duration::from_secs(5))?;
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS reminders (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                message TEXT NOT NULL,
+                due TEXT NOT NULL,
+                repeat TEXT,
+                created_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_reminders_due ON reminders(due);
+            "#,
+        )?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    pub fn add(&self, message: &str, schedule: &Schedule) -> Result<Reminder> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO reminders (message, due, repeat, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                message,
+                schedule.due.format(TIME_FORMAT).to_string(),
+                schedule.repeat.map(|r| r.encode()),
+                locale::now().naive_local().format(TIME_FORMAT).to_string(),
+            ],
+        )?;
+        Ok(Reminder {
+            id: conn.last_insert_rowid(),
+            message: message.to_string(),
+            due: schedule.due,
+            repeat: schedule.repeat,
+        })
+    }
+
+    /// Every pending reminder, soonest first
+    pub fn list(&self) -> Result<Vec<Reminder>> {
+        self.query(
+            "SELECT id, message, due, repeat FROM reminders ORDER BY due, id",
+            [],
+        )
+    }
+
+    /// Reminders due at or before `now`
+    pub fn due(&self, now: NaiveDateTime) -> Result<Vec<Reminder>> {
+        self.query(
+            "SELECT id, message, due, repeat FROM reminders WHERE due <= ?1 ORDER BY due, id",
+            [now.format(TIME_FORMAT).to_string()],
+        )
+    }
+
+    fn query<P: rusqlite::Params>(&self, sql: &str, params: P) -> Result<Vec<Reminder>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt.query_map(params, |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?,
+            ))
+        })?;
+        let mut reminders = Vec::new();
+        for row in rows {
+            let (id, message, due, repeat) = row?;
+            let Ok(due) = NaiveDateTime::parse_from_str(&due, TIME_FORMAT) else {
+                warn!("Skipping reminder #{} with invalid time '{}'", id, due);
+                continue;
+            };
+            reminders.push(Reminder {
+                id,
+                message,
+                due,
+                repeat: repeat.as_deref().and_then(Repeat::decode),
+            });
+        }
+        Ok(reminders)
+    }
+
+    /// Delete a reminder, returning it if it existed
+    pub fn cancel(&self, id: i64) -> Result<Option<Reminder>> {
+        let reminder = self
+            .query(
+                "SELECT id, message, due, repeat FROM reminders WHERE id = ?1",
+                [id],
+            )?
+            .pop();
+        if reminder.is_some() {
+            let conn = self.conn.lock().unwrap();
+            conn.execute("DELETE FROM reminders WHERE id = ?1", [id])?;
+        }
+        Ok(reminder)
+    }
+
+    /// After a reminder went off: delete it, or move a repeating one to its
+    /// next time after `now` (occurrences missed while stopped are skipped;
+    /// one with no next time left on the calendar is deleted too)
+    pub fn complete(&self, reminder: &Reminder, now: NaiveDateTime) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let mut next = reminder.repeat.and_then(|r| r.next(reminder.due));
+        while let Some(due) = next.filter(|due| *due <= now) {
+            next = reminder.repeat.and_then(|r| r.next(due));
+        }
+        match next {
+            Some(next) => {
+                conn.execute(
+                    "UPDATE reminders SET due = ?1 WHERE id = ?2",
+                    params![next.format(TIME_FORMAT).to_string(), reminder.id],
+                )?;
+            }
+            None => {
+                conn.execute("DELETE FROM reminders WHERE id = ?1", [reminder.id])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The next reminder to go off
+    pub fn next_due(&self) -> Result<Option<NaiveDateTime>> {
+        let conn = self.conn.lock().unwrap();
+        let due: Option<String> = conn
+            .query_row("SELECT MIN(due) FROM reminders", [], |row| row.get(0))
+            .optional()?
+            .flatten();
+        Ok(due.and_then(|d| NaiveDateTime::parse_from_str(&d, TIME_FORMAT).ok()))
+    }
+}
+
+/// Send due reminders until the task is aborted (run by the daemon)
+pub async fn run_reminders(config: &Config, state_dir: &Path) -> Result<()> {
+    let store = ReminderStore::open(state_dir)?;
+    let Some(notifier) = Notifier::from_config(config)? else {
+        anyhow::bail!("No notification channels configured to send reminders to");
+    };
+    if let Some(next) = store.next_due()? {
+        info!("Reminders: next due {}", next.format("%Y-%m-%d %H:%M"));
+    }
+
+    loop {
+        let now = locale::now().naive_local();
+        for reminder in store.due(now)? {
+            let reached = notifier
+                .notify(Event::Reminder, &reminder.notification(now))
+                .await;
+            if reached.is_empty() {
+                warn!(
+                    "Reminder #{} reached no channel (does one subscribe to \"reminder\"?)",
+                    reminder.id
+                );
+            } else {
+                debug!("Reminder #{} sent to {}", reminder.id, reached.join(", "));
+            }
+            store.complete(&reminder, now)?;
+        }
+        notifier.send_held().await;
+        tokio::time::sleep(CHECK_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn time(day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_store_due_and_complete() {
+        let store = ReminderStore::with_connection(Connection::open_in_memory().unwrap()).unwrap();
+        let once = store
+            .add(
+                "Call the plumber",
+                &Schedule {
+                    due: time(19, 9),
+                    repeat: None,
+                },
+            )
+            .unwrap();
+        let weekly = store
+            .add(
+                "Take the bins out",
+                &Schedule {
+                    due: time(18, 19),
+                    repeat: Some(Repeat::Weeks(1)),
+                },
+            )
+            .unwrap();
+        assert_eq!(store.list().unwrap(), vec![weekly.clone(), once.clone()]);
+        assert_eq!(store.next_due().unwrap(), Some(time(18, 19)));
+
+        // The daemon was stopped for ten days: each goes off once, late
+        let now = time(28, 12);
+        let due = store.due(now).unwrap();
+        assert_eq!(due.len(), 2);
+        assert_eq!(
+            due[0].notification(now),
+            "Take the bins out (was due Sun 18 Oct 19:00)"
+        );
+        for reminder in &due {
+            store.complete(reminder, now).unwrap();
+        }
+        let left = store.list().unwrap();
+        assert_eq!(left.len(), 1);
+        let november = NaiveDate::from_ymd_opt(2026, 11, 1).unwrap();
+        assert_eq!(left[0].due, november.and_hms_opt(19, 0, 0).unwrap());
+        assert_eq!(
+            left[0].summary(),
+            "#2 Sun 1 Nov 19:00: Take the bins out (every week)"
+        );
+
+        assert_eq!(store.cancel(weekly.id).unwrap(), Some(left[0].clone()));
+        assert_eq!(store.cancel(weekly.id).unwrap(), None);
+        assert!(store.list().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,634 @@
+//! Natural-language reminder times: "in 20 minutes", "next Tuesday at 4",
+//! "every other Saturday", "nov 3 at 9:30am"
+
+use anyhow::Result;
+use chrono::{Datelike, Months, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Weekday};
+use std::fmt;
+
+/// When a reminder goes off if only a day is given
+const DEFAULT_HOUR: u32 = 9;
+
+/// How a reminder repeats after it goes off
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Repeat {
+    Days(u32),
+    Weeks(u32),
+    /// On `day` of the month, or the month's last day if it's shorter
+    Months {
+        every: u32,
+        day: u32,
+    },
+    /// Monday to Friday
+    Weekdays,
+}
+
+impl Repeat {
+    /// The first occurrence after `due` (`None` past the end of the calendar)
+    pub fn next(&self, due: NaiveDateTime) -> Option<NaiveDateTime> {
+        match *self {
+            Self::Days(n) => due.checked_add_signed(chrono::Duration::days(n as i64)),
+            Self::Weeks(n) => due.checked_add_signed(chrono::Duration::weeks(n as i64)),
+            Self::Months { every, day } => {
+                // From the first of the month, so the 31st comes back after February
+                let month = due
+                    .date()
+                    .with_day(1)?
+                    .checked_add_months(Months::new(every))?;
+                let last = month.checked_add_months(Months::new(1))?.pred_opt()?.day();
+                Some(month.with_day(day.min(last))?.and_time(due.time()))
+            }
+            Self::Weekdays => {
+                let mut next = due.checked_add_signed(chrono::Duration::days(1))?;
+                while is_weekend(next.date()) {
+                    next = next.checked_add_signed(chrono::Duration::days(1))?;
+                }
+                Some(next)
+            }
+        }
+    }
+
+    /// Stored form: "3d", "2w", "1m31" (every month on the 31st) or
+    /// "weekdays"
+    pub fn encode(&self) -> String {
+        match self {
+            Self::Days(n) => format!("{}d", n),
+            Self::Weeks(n) => format!("{}w", n),
+            Self::Months { every, day } => format!("{}m{}", every, day),
+            Self::Weekdays => "weekdays".to_string(),
+        }
+    }
+
+    pub fn decode(s: &str) -> Option<Self> {
+        if s == "weekdays" {
+            return Some(Self::Weekdays);
+        }
+        if let Some((every, day)) = s.split_once('m') {
+            return Some(Self::Months {
+                every: every.parse().ok().filter(|&n| n > 0)?,
+                day: day.parse().ok().filter(|d| (1..=31).contains(d))?,
+            });
+        }
+        let unit = s.chars().last()?;
+        let n: u32 = s[..s.len() - 1].parse().ok().filter(|&n| n > 0)?;
+        match unit {
+            'd' => Some(Self::Days(n)),
+            'w' => Some(Self::Weeks(n)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Repeat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Days(1) => write!(f, "every day"),
+            Self::Weeks(1) => write!(f, "every week"),
+            Self::Weeks(2) => write!(f, "every other week"),
+            Self::Months { every: 1, .. } => write!(f, "every month"),
+            Self::Days(n) => write!(f, "every {} days", n),
+            Self::Weeks(n) => write!(f, "every {} weeks", n),
+            Self::Months { every, .. } => write!(f, "every {} months", every),
+            Self::Weekdays => write!(f, "every weekday"),
+        }
+    }
+}
+
+/// When a reminder first goes off, and how it repeats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Schedule {
+    pub due: NaiveDateTime,
+    pub repeat: Option<Repeat>,
+}
+
+fn is_weekend(date: NaiveDate) -> bool {
+    matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+}
+
+fn weekday(word: &str) -> Option<Weekday> {
+    let weekday = match word.trim_end_matches('s') {
+        "mon" | "monday" => Weekday::Mon,
+        "tue" | "tuesday" => Weekday::Tue,
+        "wed" | "wednesday" => Weekday::Wed,
+        "thu" | "thur" | "thursday" => Weekday::Thu,
+        "fri" | "friday" => Weekday::Fri,
+        "sat" | "saturday" => Weekday::Sat,
+        "sun" | "sunday" => Weekday::Sun,
+        _ => return None,
+    };
+    Some(weekday)
+}
+
+fn month(word: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+    if word.len() < 3 {
+        return None;
+    }
+    let index = MONTHS.iter().position(|m| word.starts_with(m))?;
+    let full = NaiveDate::from_ymd_opt(2000, index as u32 + 1, 1)?
+        .format("%B")
+        .to_string()
+        .to_lowercase();
+    // "mar", "march" and "sept" but not "market"
+    (full.starts_with(word) || word == "sept").then_some(index as u32 + 1)
+}
+
+/// The first `day` of a month from `today` on
+fn next_day_of_month(today: NaiveDate, day: u32) -> Option<NaiveDate> {
+    (0..12).find_map(|n| {
+        let month = today.with_day(1)?.checked_add_months(Months::new(n))?;
+        month.with_day(day).filter(|date| *date >= today)
+    })
+}
+
+/// "3", "3rd", "21st"
+fn day_of_month(word: &str) -> Option<u32> {
+    let digits = word.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let suffix = &word[digits.len()..];
+    if !matches!(suffix, "" | "st" | "nd" | "rd" | "th") {
+        return None;
+    }
+    digits.parse().ok().filter(|d| (1..=31).contains(d))
+}
+
+fn number(word: &str) -> Option<u32> {
+    let n = match word {
+        "a" | "an" | "one" => 1,
+        "two" => 2,
+        "three" => 3,
+        "four" => 4,
+        "five" => 5,
+        "six" => 6,
+        "ten" => 10,
+        "fifteen" => 15,
+        "twenty" => 20,
+        "thirty" => 30,
+        _ => return word.parse().ok(),
+    };
+    Some(n)
+}
+
+/// A clock time, with whether it said am/pm or used 24-hour form
+fn clock(word: &str) -> Option<(u32, u32, bool)> {
+    let (digits, meridiem) = if let Some(d) = word.strip_suffix("am") {
+        (d, Some(false))
+    } else if let Some(d) = word.strip_suffix("pm") {
+        (d, Some(true))
+    } else {
+        (word, None)
+    };
+    let (hour, minute) = match digits.split_once([':', '.']) {
+        Some((h, m)) if m.len() == 2 => (h.parse::<u32>().ok()?, m.parse::<u32>().ok()?),
+        Some(_) => return None,
+        None => (digits.parse::<u32>().ok()?, 0),
+    };
+    if minute > 59 {
+        return None;
+    }
+    match meridiem {
+        Some(pm) if (1..=12).contains(&hour) => {
+            Some((hour % 12 + if pm { 12 } else { 0 }, minute, true))
+        }
+        Some(_) => None,
+        // "16:30" and "0:15" are unambiguous
+        None if hour <= 23 => Some((hour, minute, hour == 0 || hour > 12)),
+        None => None,
+    }
+}
+
+#[derive(Default)]
+struct Parsed {
+    offset: Option<chrono::Duration>,
+    date: Option<NaiveDate>,
+    weekday: Option<Weekday>,
+    /// "next tuesday": not today even if it's Tuesday
+    skip_today: bool,
+    time: Option<NaiveTime>,
+    /// The time said am/pm (or was 24-hour or a part of the day)
+    time_exact: bool,
+    repeat: Option<Repeat>,
+}
+
+impl Parsed {
+    fn set_time(&mut self, hour: u32, minute: u32, exact: bool) {
+        self.time = NaiveTime::from_hms_opt(hour, minute, 0);
+        self.time_exact = exact;
+    }
+
+    /// "morning", "tonight" and the like
+    fn part_of_day(&mut self, word: &str) -> bool {
+        let Some(hour) = part_of_day_hour(word) else {
+            return false;
+        };
+        match self.time {
+            None => self.set_time(hour, 0, true),
+            // "at 4 in the afternoon", "at 8 in the morning"
+            Some(time) if !self.time_exact => {
+                let pm = hour >= 12 && time.hour() < 12;
+                self.set_time(time.hour() + if pm { 12 } else { 0 }, time.minute(), true);
+            }
+            Some(_) => {}
+        }
+        true
+    }
+}
+
+fn part_of_day_hour(word: &str) -> Option<u32> {
+    let hour = match word {
+        "morning" => DEFAULT_HOUR,
+        "noon" | "lunchtime" => 12,
+        "afternoon" => 15,
+        "evening" => 18,
+        "night" | "tonight" => 20,
+        _ => return None,
+    };
+    Some(hour)
+}
+
+fn tokenize(text: &str) -> Vec<String> {
+    let lowered = text.to_lowercase().replace([',', '!', '?'], " ");
+    let mut tokens: Vec<String> = Vec::new();
+    for word in lowered.split_whitespace() {
+        let word = word.trim_end_matches('.');
+        // "4 pm" -> "4pm"
+        if matches!(word, "am" | "pm" | "a.m" | "p.m") {
+            if let Some(last) = tokens.last_mut() {
+                if clock(last).is_some() {
+                    last.push_str(&word.replace('.', ""));
+                    continue;
+                }
+            }
+        }
+        tokens.push(word.to_string());
+    }
+    tokens
+}
+
+/// Parse `text` relative to `now` (local time)
+pub fn parse_schedule(text: &str, now: NaiveDateTime) -> Result<Schedule> {
+    let tokens = tokenize(text);
+    let today = now.date();
+    let mut parsed = Parsed::default();
+    let not_understood = |word: &str| {
+        anyhow::anyhow!(
+            "Couldn't understand '{}' in '{}'. Try e.g. 'tomorrow at 9am', 'in 20 minutes', \
+             'next Tuesday at 4pm', 'every other Saturday' or '2026-11-03 18:30'",
+            word,
+            text
+        )
+    };
+
+    let mut i = 0;
+    while i < tokens.len() {
+        let word = tokens[i].as_str();
+        let next = tokens.get(i + 1).map(String::as_str).unwrap_or("");
+        i += 1;
+
+        match word {
+            "at" | "on" | "the" | "of" | "this" | "starting" | "from" | "by" => {}
+            // "tomorrow in the morning"
+            "in" if next == "the"
+                && tokens
+                    .get(i + 1)
+                    .is_some_and(|w| part_of_day_hour(w).is_some()) => {}
+            "in" => {
+                let (n, unit) = match (number(next), tokens.get(i + 1)) {
+                    (Some(n), Some(unit)) => (n, unit.as_str()),
+                    _ => return Err(not_understood(word)),
+                };
+                let n = n as i64;
+                let offset = match unit.trim_end_matches('s') {
+                    "sec" | "second" => chrono::Duration::seconds(n),
+                    "min" | "minute" => chrono::Duration::minutes(n),
+                    "hr" | "hour" => chrono::Duration::hours(n),
+                    "day" => chrono::Duration::days(n),
+                    "week" => chrono::Duration::weeks(n),
+                    _ => return Err(not_understood(unit)),
+                };
+                parsed.offset = Some(offset);
+                i += 2;
+            }
+            "every" | "each" => {
+                let interval = match next {
+                    "other" => Some(2),
+                    _ => next.parse::<u32>().ok().filter(|&n| n > 0),
+                };
+                let unit = match interval {
+                    Some(_) => tokens.get(i + 1).map(String::as_str).unwrap_or(""),
+                    None => next,
+                };
+                i += if interval.is_some() { 2 } else { 1 };
+                let interval = interval.unwrap_or(1);
+                parsed.repeat = Some(match unit {
+                    "day" | "days" => Repeat::Days(interval),
+                    "weekday" | "weekdays" if interval == 1 => Repeat::Weekdays,
+                    "week" | "weeks" => Repeat::Weeks(interval),
+                    // Its day is the first occurrence's, set in resolve
+                    "month" | "months" => Repeat::Months {
+                        every: interval,
+                        day: 0,
+                    },
+                    _ if parsed.part_of_day(unit) => Repeat::Days(interval),
+                    _ => match weekday(unit) {
+                        Some(day) => {
+                            parsed.weekday = Some(day);
+                            Repeat::Weeks(interval)
+                        }
+                        None => return Err(not_understood(unit)),
+                    },
+                });
+            }
+            "daily" => parsed.repeat = Some(Repeat::Days(1)),
+            "weekly" => parsed.repeat = Some(Repeat::Weeks(1)),
+            "fortnightly" | "biweekly" => parsed.repeat = Some(Repeat::Weeks(2)),
+            "monthly" => parsed.repeat = Some(Repeat::Months { every: 1, day: 0 }),
+            "today" => parsed.date = Some(today),
+            "tonight" => {
+                parsed.date = Some(today);
+                parsed.part_of_day(word);
+            }
+            "tomorrow" => parsed.date = Some(today + chrono::Duration::days(1)),
+            "midnight" => {
+                parsed.set_time(0, 0, true);
+                parsed.skip_today = true;
+            }
+            "next" => match (weekday(next), next) {
+                (Some(day), _) => {
+                    parsed.weekday = Some(day);
+                    parsed.skip_today = true;
+                    i += 1;
+                }
+                (None, "week") => {
+                    parsed.date = Some(today + chrono::Duration::weeks(1));
+                    i += 1;
+                }
+                (None, "month") => {
+                    parsed.date = today.checked_add_months(Months::new(1));
+                    i += 1;
+                }
+                _ => return Err(not_understood(word)),
+            },
+            _ if parsed.part_of_day(word) => {}
+            _ => {
+                if let Some(day) = weekday(word) {
+                    parsed.weekday = Some(day);
+                } else if let Ok(date) = NaiveDate::parse_from_str(word, "%Y-%m-%d") {
+                    parsed.date = Some(date);
+                } else if let Some(m) = month(word) {
+                    // "nov 3"
+                    let day = day_of_month(next).ok_or_else(|| not_understood(word))?;
+                    parsed.date =
+                        Some(month_day(today, m, day).ok_or_else(|| not_understood(word))?);
+                    i += 1;
+                } else if let Some(day) = day_of_month(word).filter(|_| {
+                    month(next).is_some()
+                        || (next == "of" && tokens.get(i + 1).and_then(|w| month(w)).is_some())
+                }) {
+                    // "3 nov", "3rd of november"
+                    if next == "of" {
+                        i += 1;
+                    }
+                    let m = month(&tokens[i]).unwrap_or_default();
+                    parsed.date =
+                        Some(month_day(today, m, day).ok_or_else(|| not_understood(word))?);
+                    i += 1;
+                } else if let Some(day) =
+                    day_of_month(word).filter(|_| !word.ends_with(|c: char| c.is_ascii_digit()))
+                {
+                    // "the 31st", this month's or a later one's
+                    parsed.date =
+                        Some(next_day_of_month(today, day).ok_or_else(|| not_understood(word))?);
+                } else if let Some((hour, minute, exact)) = clock(word) {
+                    parsed.set_time(hour, minute, exact);
+                } else {
+                    return Err(not_understood(word));
+                }
+            }
+        }
+    }
+
+    if let Some(offset) = parsed.offset {
+        let due = now
+            .checked_add_signed(offset)
+            .ok_or_else(|| not_understood("in"))?;
+        return Ok(Schedule {
+            due,
+            repeat: anchor_month_day(parsed.repeat, due),
+        });
+    }
+    let Some(schedule) = resolve(parsed, now) else {
+        anyhow::bail!(
+            "'{}' has no day or time. Try e.g. 'tomorrow at 9am' or 'in 20 minutes'",
+            text
+        );
+    };
+    if schedule.due <= now {
+        anyhow::bail!(
+            "'{}' is in the past ({})",
+            text,
+            schedule.due.format("%a %-d %b %H:%M")
+        );
+    }
+    Ok(schedule)
+}
+
+/// This year's `month`/`day`, or next year's once it has passed
+fn month_day(today: NaiveDate, month: u32, day: u32) -> Option<NaiveDate> {
+    let date = NaiveDate::from_ymd_opt(today.year(), month, day)?;
+    if date < today {
+        NaiveDate::from_ymd_opt(today.year() + 1, month, day)
+    } else {
+        Some(date)
+    }
+}
+
+/// A monthly repeat comes back on the day of the month it first falls on
+fn anchor_month_day(repeat: Option<Repeat>, due: NaiveDateTime) -> Option<Repeat> {
+    repeat.map(|repeat| match repeat {
+        Repeat::Months { every, .. } => Repeat::Months {
+            every,
+            day: due.day(),
+        },
+        other => other,
+    })
+}
+
+fn resolve(parsed: Parsed, now: NaiveDateTime) -> Option<Schedule> {
+    let today = now.date();
+    let has_day = parsed.date.is_some() || parsed.weekday.is_some() || parsed.repeat.is_some();
+    if !has_day && parsed.time.is_none() {
+        return None;
+    }
+
+    let mut time = parsed
+        .time
+        .unwrap_or_else(|| NaiveTime::from_hms_opt(DEFAULT_HOUR, 0, 0).unwrap());
+    if parsed.time.is_some() && !parsed.time_exact && (1..=11).contains(&time.hour()) {
+        let pm = time + chrono::Duration::hours(12);
+        if has_day {
+            // "tuesday at 4" is the afternoon, "tuesday at 8" the morning
+            if time.hour() <= 6 {
+                time = pm;
+            }
+        } else if today.and_time(time) <= now && today.and_time(pm) > now {
+            // "at 4" at noon is 16:00 today
+            time = pm;
+        }
+    }
+
+    let first_day = match (parsed.date, parsed.weekday) {
+        (Some(date), _) => date,
+        (None, Some(day)) => {
+            let mut date = today;
+            if parsed.skip_today {
+                date += chrono::Duration::days(1);
+            }
+            while date.weekday() != day || date.and_time(time) <= now {
+                date += chrono::Duration::days(1);
+            }
+            date
+        }
+        (None, None) => {
+            let mut date = today;
+            if parsed.skip_today {
+                date += chrono::Duration::days(1);
+            }
+            while date.and_time(time) <= now
+                || (parsed.repeat == Some(Repeat::Weekdays) && is_weekend(date))
+            {
+                date += chrono::Duration::days(1);
+            }
+            date
+        }
+    };
+
+    let mut due = first_day.and_time(time);
+    let repeat = anchor_month_day(parsed.repeat, due);
+    if let Some(repeat) = repeat {
+        // Stays in the past, and is rejected, if it runs off the calendar
+        while due <= now {
+            match repeat.next(due) {
+                Some(next) => due = next,
+                None => break,
+            }
+        }
+    }
+    Some(Schedule { due, repeat })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sunday 18 October 2026, 12:00
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 18)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+    }
+
+    fn at(text: &str) -> (String, Option<Repeat>) {
+        let schedule = parse_schedule(text, now()).unwrap();
+        (
+            schedule.due.format("%a %Y-%m-%d %H:%M").to_string(),
+            schedule.repeat,
+        )
+    }
+
+    #[test]
+    fn test_one_off() {
+        assert_eq!(at("in 20 minutes").0, "Sun 2026-10-18 12:20");
+        assert_eq!(at("in an hour").0, "Sun 2026-10-18 13:00");
+        assert_eq!(at("at 4").0, "Sun 2026-10-18 16:00");
+        assert_eq!(at("at 11am").0, "Mon 2026-10-19 11:00");
+        assert_eq!(at("tonight").0, "Sun 2026-10-18 20:00");
+        assert_eq!(at("tomorrow").0, "Mon 2026-10-19 09:00");
+        assert_eq!(at("tomorrow at 7:30 pm").0, "Mon 2026-10-19 19:30");
+        assert_eq!(at("tomorrow evening").0, "Mon 2026-10-19 18:00");
+        assert_eq!(at("tomorrow in the morning").0, "Mon 2026-10-19 09:00");
+        assert_eq!(at("tomorrow at 7 in the morning").0, "Mon 2026-10-19 07:00");
+        assert_eq!(at("at 4 in the afternoon").0, "Sun 2026-10-18 16:00");
+        assert_eq!(at("at 8 in the evening").0, "Sun 2026-10-18 20:00");
+        assert_eq!(at("next Tuesday at 4").0, "Tue 2026-10-20 16:00");
+        assert_eq!(at("Sunday at 8").0, "Sun 2026-10-25 08:00");
+        assert_eq!(at("Sunday at 17:00").0, "Sun 2026-10-18 17:00");
+        assert_eq!(at("nov 3rd at 9:30am").0, "Tue 2026-11-03 09:30");
+        assert_eq!(at("3 March").0, "Wed 2027-03-03 09:00");
+        assert_eq!(at("2026-12-24 18:00").0, "Thu 2026-12-24 18:00");
+    }
+
+    #[test]
+    fn test_repeating() {
+        assert_eq!(
+            at("every other Saturday"),
+            ("Sat 2026-10-24 09:00".to_string(), Some(Repeat::Weeks(2)))
+        );
+        assert_eq!(
+            at("every weekday at 7am"),
+            ("Mon 2026-10-19 07:00".to_string(), Some(Repeat::Weekdays))
+        );
+        assert_eq!(
+            at("every evening"),
+            ("Sun 2026-10-18 18:00".to_string(), Some(Repeat::Days(1)))
+        );
+        assert_eq!(
+            at("every 3 days at noon"),
+            ("Mon 2026-10-19 12:00".to_string(), Some(Repeat::Days(3)))
+        );
+        assert_eq!(
+            at("monthly on the 1st of november").1,
+            Some(Repeat::Months { every: 1, day: 1 })
+        );
+
+        assert_eq!(
+            at("monthly, in 3 days"),
+            (
+                "Wed 2026-10-21 12:00".to_string(),
+                Some(Repeat::Months { every: 1, day: 21 })
+            )
+        );
+
+        // The 31st comes back after shorter months
+        let (due, repeat) = at("monthly on the 31st");
+        assert_eq!(due, "Sat 2026-10-31 09:00");
+        let repeat = repeat.unwrap();
+        assert_eq!(repeat, Repeat::Months { every: 1, day: 31 });
+        let mut due = parse_schedule("monthly on the 31st", now()).unwrap().due;
+        let mut dates = Vec::new();
+        for _ in 0..5 {
+            due = repeat.next(due).unwrap();
+            dates.push(due.format("%m-%d").to_string());
+        }
+        assert_eq!(dates, ["11-30", "12-31", "01-31", "02-28", "03-31"]);
+
+        let every = Repeat::Weekdays;
+        let friday = NaiveDate::from_ymd_opt(2026, 10, 23)
+            .unwrap()
+            .and_hms_opt(7, 0, 0)
+            .unwrap();
+        assert_eq!(every.next(friday).unwrap().weekday(), Weekday::Mon);
+        assert_eq!(Repeat::Days(1).next(NaiveDateTime::MAX), None);
+        for repeat in [
+            Repeat::Days(3),
+            Repeat::Weeks(2),
+            Repeat::Months { every: 1, day: 31 },
+            every,
+        ] {
+            assert_eq!(Repeat::decode(&repeat.encode()), Some(repeat));
+        }
+    }
+
+    #[test]
+    fn test_unparseable() {
+        let err = parse_schedule("when the moon is full", now()).unwrap_err();
+        assert!(err.to_string().contains("Couldn't understand 'when'"));
+        assert!(parse_schedule("", now()).is_err());
+        assert!(parse_schedule("at 25", now()).is_err());
+        let err = parse_schedule("in 100000000 days", now()).unwrap_err();
+        assert!(err.to_string().contains("Couldn't understand 'in'"));
+        let err = parse_schedule("today at 9am", now()).unwrap_err();
+        assert!(err.to_string().contains("is in the past"));
+    }
+}
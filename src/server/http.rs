@@ -9,10 +9,10 @@ use axum::{
         ws::{Message as WsMessage, WebSocket, WebSocketUpgrade},
         Path, Query, State,
     },
-    http::{header, StatusCode},
+    http::{header, HeaderMap, StatusCode},
     response::{
         sse::{Event, Sse},
-        IntoResponse, Json, Response,
+        Html, IntoResponse, Json, Response,
     },
     routing::{delete, get, post},
     Router,
@@ -28,13 +28,21 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 use tower_http::cors::{Any, CorsLayer};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
-use crate::agent::{extract_tool_detail, Agent, AgentConfig, StreamEvent};
+use crate::agent::{
+    answer_approval, extract_tool_detail, get_sessions_dir_for_agent, get_state_dir, load_traces,
+    pending_approval, Agent, AgentConfig, Approver, GroundedAnswer, RemoteApprover, StreamApproval,
+    StreamApprover, StreamEvent,
+};
+use crate::calendar::Calendar;
 use crate::concurrency::{TurnGate, WorkspaceLock};
 use crate::config::Config;
-use crate::heartbeat::{get_last_heartbeat_event, HeartbeatStatus};
-use crate::memory::MemoryManager;
+use crate::heartbeat::{get_last_heartbeat_event, HeartbeatRunner, HeartbeatStatus};
+use crate::locale;
+use crate::memory::{FileWrite, MemoryManager, VerifiedChunk};
+use crate::metrics::{self, IndexGauges};
+use crate::webhooks;
 
 /// Embedded UI assets
 #[derive(RustEmbed)]
@@ -50,6 +58,9 @@ const MAX_SESSIONS: usize = 100;
 /// Agent ID for HTTP sessions
 const HTTP_AGENT_ID: &str = "http";
 
+/// How long a streamed turn waits for its client to answer an approval
+const STREAM_APPROVAL_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
 pub struct Server {
     config: Config,
     turn_gate: TurnGate,
@@ -64,20 +75,25 @@ struct SessionEntry {
 
 struct AppState {
     config: Config,
-    sessions: Mutex<HashMap<String, SessionEntry>>,
+    /// Each session behind its own lock: a turn holds its session, not the
+    /// map, so turns in different sessions run side by side
+    sessions: Mutex<HashMap<String, Arc<Mutex<SessionEntry>>>>,
     /// Shared MemoryManager to avoid reinitializing embedding provider
     memory: MemoryManager,
     /// In-process turn gate shared with heartbeat runner
     turn_gate: TurnGate,
     /// Cross-process workspace lock
     workspace_lock: WorkspaceLock,
+    /// Asks on the phone before tool calls that need approval, or refuses
+    /// them without `approval.remote`
+    approver: Arc<dyn Approver>,
 }
 
 impl Server {
     pub fn new(config: &Config) -> Result<Self> {
         Ok(Self {
             config: config.clone(),
-            turn_gate: TurnGate::new(),
+            turn_gate: TurnGate::with_limit(config.server.max_concurrent_turns),
         })
     }
 
@@ -96,6 +112,7 @@ impl Server {
             MemoryManager::new_with_full_config(&self.config.memory, Some(&self.config), "main")?;
 
         let workspace_lock = WorkspaceLock::new()?;
+        let approver = RemoteApprover::shared(&self.config)?;
 
         let state = Arc::new(AppState {
             config: self.config.clone(),
@@ -103,6 +120,7 @@ impl Server {
             memory,
             turn_gate: self.turn_gate.clone(),
             workspace_lock,
+            approver,
         });
 
         // Load persisted sessions on startup
@@ -141,6 +159,7 @@ impl Server {
             .route("/ui/{*path}", get(serve_ui_file))
             // API routes
             .route("/health", get(health_check))
+            .route("/metrics", get(prometheus_metrics))
             .route("/api/sessions", post(create_session))
             .route("/api/sessions", get(list_sessions))
             .route("/api/sessions/{session_id}", delete(delete_session))
@@ -149,18 +168,31 @@ impl Server {
                 "/api/sessions/{session_id}/messages",
                 get(get_session_messages),
             )
+            .route("/api/sessions/{session_id}/tools", get(get_session_tools))
+            .route("/api/sessions/{session_id}/trace", get(get_session_trace))
+            .route("/api/sessions/{session_id}/prompt", get(get_session_prompt))
             .route("/api/sessions/{session_id}/compact", post(compact_session))
             .route("/api/sessions/{session_id}/clear", post(clear_session))
             .route("/api/sessions/{session_id}/model", post(set_session_model))
+            .route(
+                "/api/sessions/{session_id}/tutor-notes",
+                post(log_tutor_session),
+            )
             .route("/api/chat", post(chat))
             .route("/api/chat/stream", post(chat_stream))
+            .route("/api/answer", post(answer))
             .route("/api/ws", get(websocket_handler))
             .route("/api/memory/search", get(memory_search))
             .route("/api/memory/stats", get(memory_stats))
+            .route("/api/memory/citations/{hash}", get(memory_citation))
             .route("/api/memory/reindex", post(memory_reindex))
             .route("/api/status", get(status))
             .route("/api/config", get(get_config))
             .route("/api/heartbeat/status", get(heartbeat_status))
+            .route("/hook/{name}", post(inbound_webhook))
+            .route("/approvals/{id}", get(approval_page))
+            .route("/approvals/{id}/{answer}", post(answer_approval_request))
+            .route("/calendar.ics", get(calendar_feed))
             .route("/api/saved-sessions", get(list_saved_sessions))
             .route("/api/saved-sessions/{session_id}", get(get_saved_session))
             .route("/api/logs/daemon", get(get_daemon_logs))
@@ -191,19 +223,40 @@ impl IntoResponse for AppError {
 // Session cleanup task
 async fn cleanup_expired_sessions(state: &Arc<AppState>) {
     let mut sessions = state.sessions.lock().await;
-    let before_count = sessions.len();
-
-    sessions.retain(|id, entry| {
-        let expired = entry.last_accessed.elapsed() > SESSION_TIMEOUT;
-        if expired {
+    // A session in the middle of a turn isn't idle
+    let expired_ids: Vec<String> = sessions
+        .iter()
+        .filter(|(_, entry)| {
+            entry
+                .try_lock()
+                .is_ok_and(|entry| entry.last_accessed.elapsed() > SESSION_TIMEOUT)
+        })
+        .map(|(id, _)| id.clone())
+        .collect();
+    let expired: Vec<(String, Arc<Mutex<SessionEntry>>)> = expired_ids
+        .into_iter()
+        .filter_map(|id| {
             debug!("Expiring session: {}", id);
-        }
-        !expired
-    });
+            let entry = sessions.remove(&id)?;
+            Some((id, entry))
+        })
+        .collect();
+    drop(sessions);
 
-    let removed = before_count - sessions.len();
-    if removed > 0 {
-        info!("Cleaned up {} expired sessions", removed);
+    if !expired.is_empty() {
+        info!("Cleaned up {} expired sessions", expired.len());
+    }
+
+    // Idle sessions have ended: propose facts from them for review, and
+    // track what the assistant promised in them
+    for (id, entry) in expired {
+        let entry = entry.lock().await;
+        if let Err(e) = entry.agent.extract_memories().await {
+            warn!("Failed to propose facts from session {}: {}", id, e);
+        }
+        if let Err(e) = entry.agent.track_commitments().await {
+            warn!("Failed to track commitments from session {}: {}", id, e);
+        }
     }
 }
 
@@ -222,17 +275,18 @@ async fn load_persisted_sessions(state: &Arc<AppState>) -> Result<(), anyhow::Er
         };
 
         let mut agent = Agent::new(agent_config, &state.config, state.memory.clone()).await?;
+        attach_approver(state, &mut agent);
 
         // Try to resume the session
         if agent.resume_session(&session_info.id).await.is_ok() {
             let mut sessions = state.sessions.lock().await;
             sessions.insert(
                 session_info.id.clone(),
-                SessionEntry {
+                Arc::new(Mutex::new(SessionEntry {
                     agent,
                     last_accessed: Instant::now(),
                     dirty: false,
-                },
+                })),
             );
             loaded += 1;
         }
@@ -247,10 +301,14 @@ async fn load_persisted_sessions(state: &Arc<AppState>) -> Result<(), anyhow::Er
 
 // Save dirty sessions to disk
 async fn save_dirty_sessions(state: &Arc<AppState>) {
-    let mut sessions = state.sessions.lock().await;
+    let sessions = all_sessions(state).await;
     let mut saved = 0;
 
-    for (id, entry) in sessions.iter_mut() {
+    for (id, entry) in sessions {
+        // A session in the middle of a turn is saved next time
+        let Ok(mut entry) = entry.try_lock() else {
+            continue;
+        };
         if entry.dirty {
             if let Err(e) = entry.agent.save_session_for_agent(HTTP_AGENT_ID).await {
                 debug!("Failed to save session {}: {}", id, e);
@@ -266,38 +324,44 @@ async fn save_dirty_sessions(state: &Arc<AppState>) {
     }
 }
 
+/// Have the agent ask on the phone before calls that need approval (or
+/// refuse them, without `approval.remote`)
+fn attach_approver(state: &AppState, agent: &mut Agent) {
+    agent.set_approver(Arc::clone(&state.approver));
+}
+
+/// A session, cloned out of the map so the map isn't held while it's used
+async fn find_session(state: &AppState, session_id: &str) -> Option<Arc<Mutex<SessionEntry>>> {
+    state.sessions.lock().await.get(session_id).cloned()
+}
+
+/// Every session, cloned out of the map
+async fn all_sessions(state: &AppState) -> Vec<(String, Arc<Mutex<SessionEntry>>)> {
+    let sessions = state.sessions.lock().await;
+    sessions
+        .iter()
+        .map(|(id, entry)| (id.clone(), Arc::clone(entry)))
+        .collect()
+}
+
 // Get or create a session
 async fn get_or_create_session(
     state: &Arc<AppState>,
     session_id: Option<String>,
 ) -> Result<String, AppError> {
-    let mut sessions = state.sessions.lock().await;
-
     // If session_id provided, try to use existing session
     if let Some(ref id) = session_id {
-        if sessions.contains_key(id) {
-            // Update last accessed time
-            if let Some(entry) = sessions.get_mut(id) {
+        if let Some(entry) = find_session(state, id).await {
+            // A session in the middle of a turn was just used anyway
+            if let Ok(mut entry) = entry.try_lock() {
                 entry.last_accessed = Instant::now();
             }
             return Ok(id.clone());
         }
     }
 
-    // Check session limit
-    if sessions.len() >= MAX_SESSIONS {
-        // Try to remove oldest session
-        if let Some(oldest_id) = sessions
-            .iter()
-            .min_by_key(|(_, e)| e.last_accessed)
-            .map(|(id, _)| id.clone())
-        {
-            sessions.remove(&oldest_id);
-            info!("Removed oldest session {} to make room", oldest_id);
-        }
-    }
-
-    // Create new session
+    // Create new session (before taking the map, so other sessions aren't
+    // held up while the agent starts)
     let new_id = session_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
 
     let agent_config = AgentConfig {
@@ -309,19 +373,40 @@ async fn get_or_create_session(
     let mut agent = Agent::new(agent_config, &state.config, state.memory.clone())
         .await
         .map_err(|e| AppError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    attach_approver(state, &mut agent);
 
     agent
         .new_session()
         .await
         .map_err(|e| AppError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
+    let mut sessions = state.sessions.lock().await;
+    if sessions.contains_key(&new_id) {
+        // Created by another request for the same ID meanwhile
+        return Ok(new_id);
+    }
+
+    // Check session limit
+    if sessions.len() >= MAX_SESSIONS {
+        // Try to remove the oldest session that isn't in a turn
+        if let Some(oldest_id) = sessions
+            .iter()
+            .filter_map(|(id, e)| Some((id, e.try_lock().ok()?.last_accessed)))
+            .min_by_key(|(_, last_accessed)| *last_accessed)
+            .map(|(id, _)| id.clone())
+        {
+            sessions.remove(&oldest_id);
+            info!("Removed oldest session {} to make room", oldest_id);
+        }
+    }
+
     sessions.insert(
         new_id.clone(),
-        SessionEntry {
+        Arc::new(Mutex::new(SessionEntry {
             agent,
             last_accessed: Instant::now(),
             dirty: true, // New sessions should be saved
-        },
+        })),
     );
 
     info!("Created new session: {}", new_id);
@@ -333,6 +418,32 @@ async fn health_check() -> &'static str {
     "OK"
 }
 
+// Prometheus metrics endpoint
+async fn prometheus_metrics(State(state): State<Arc<AppState>>) -> Response {
+    if !state.config.server.metrics {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    // Index gauges are left out if the index can't be read
+    let index = index_gauges(&state.memory)
+        .map_err(|e| debug!("Metrics: memory index unavailable: {}", e))
+        .ok();
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics::render(index.as_ref()),
+    )
+        .into_response()
+}
+
+fn index_gauges(memory: &MemoryManager) -> Result<IndexGauges> {
+    Ok(IndexGauges {
+        files: memory.indexed_file_count()?,
+        chunks: memory.chunk_count()?,
+        size_bytes: memory.index_size_bytes()?,
+        pending_embeddings: memory.pending_embedding_count()?,
+    })
+}
+
 // Serve UI index.html at root
 async fn serve_ui_index() -> Response {
     serve_ui_asset("index.html")
@@ -364,6 +475,7 @@ struct StatusResponse {
     version: String,
     model: String,
     memory_chunks: usize,
+    has_embeddings: bool,
     active_sessions: usize,
 }
 
@@ -374,6 +486,7 @@ async fn status(State(state): State<Arc<AppState>>) -> Json<StatusResponse> {
         version: env!("CARGO_PKG_VERSION").to_string(),
         model: state.config.agent.default_model.clone(),
         memory_chunks: state.memory.chunk_count().unwrap_or(0),
+        has_embeddings: state.memory.has_embeddings(),
         active_sessions: sessions.len(),
     })
 }
@@ -408,6 +521,11 @@ async fn create_session(
 struct SessionInfo {
     session_id: String,
     idle_seconds: u64,
+    /// Left out while the session is in the middle of a turn
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message_count: Option<usize>,
+    /// In the middle of a turn
+    busy: bool,
 }
 
 #[derive(Serialize)]
@@ -416,13 +534,23 @@ struct ListSessionsResponse {
 }
 
 async fn list_sessions(State(state): State<Arc<AppState>>) -> Json<ListSessionsResponse> {
-    let sessions = state.sessions.lock().await;
+    let sessions = all_sessions(&state).await;
 
     let session_list: Vec<SessionInfo> = sessions
-        .iter()
-        .map(|(id, entry)| SessionInfo {
-            session_id: id.clone(),
-            idle_seconds: entry.last_accessed.elapsed().as_secs(),
+        .into_iter()
+        .map(|(id, entry)| match entry.try_lock() {
+            Ok(entry) => SessionInfo {
+                session_id: id,
+                idle_seconds: entry.last_accessed.elapsed().as_secs(),
+                message_count: Some(entry.agent.session_status().message_count),
+                busy: false,
+            },
+            Err(_) => SessionInfo {
+                session_id: id,
+                idle_seconds: 0,
+                message_count: None,
+                busy: true,
+            },
         })
         .collect();
 
@@ -456,16 +584,17 @@ struct SessionStatusResponse {
     idle_seconds: u64,
     api_input_tokens: u64,
     api_output_tokens: u64,
+    api_cache_read_tokens: u64,
+    api_cache_write_tokens: u64,
 }
 
 async fn get_session_status(
     State(state): State<Arc<AppState>>,
     Path(session_id): Path<String>,
 ) -> Response {
-    let sessions = state.sessions.lock().await;
-
-    match sessions.get(&session_id) {
+    match find_session(&state, &session_id).await {
         Some(entry) => {
+            let entry = entry.lock().await;
             let status = entry.agent.session_status();
             Json(SessionStatusResponse {
                 session_id,
@@ -475,6 +604,8 @@ async fn get_session_status(
                 idle_seconds: entry.last_accessed.elapsed().as_secs(),
                 api_input_tokens: status.api_input_tokens,
                 api_output_tokens: status.api_output_tokens,
+                api_cache_read_tokens: status.api_cache_read_tokens,
+                api_cache_write_tokens: status.api_cache_write_tokens,
             })
             .into_response()
         }
@@ -482,6 +613,37 @@ async fn get_session_status(
     }
 }
 
+// Get session tools - the tools the session's agent can call
+#[derive(Serialize)]
+struct SessionTool {
+    name: String,
+    description: String,
+    requires_approval: bool,
+}
+
+async fn get_session_tools(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+) -> Response {
+    match find_session(&state, &session_id).await {
+        Some(entry) => {
+            let entry = entry.lock().await;
+            let tools: Vec<SessionTool> = entry
+                .agent
+                .tool_schemas()
+                .into_iter()
+                .map(|schema| SessionTool {
+                    requires_approval: entry.agent.requires_approval(&schema.name),
+                    name: schema.name,
+                    description: schema.description,
+                })
+                .collect();
+            Json(json!({ "tools": tools })).into_response()
+        }
+        None => AppError(StatusCode::NOT_FOUND, "Session not found".to_string()).into_response(),
+    }
+}
+
 // Get session messages - returns message history for an active session
 #[derive(Serialize)]
 struct ActiveSessionMessage {
@@ -502,10 +664,9 @@ async fn get_session_messages(
     State(state): State<Arc<AppState>>,
     Path(session_id): Path<String>,
 ) -> Response {
-    let mut sessions = state.sessions.lock().await;
-
-    match sessions.get_mut(&session_id) {
+    match find_session(&state, &session_id).await {
         Some(entry) => {
+            let mut entry = entry.lock().await;
             entry.last_accessed = Instant::now();
 
             let messages: Vec<ActiveSessionMessage> = entry
@@ -557,15 +718,53 @@ async fn get_session_messages(
     }
 }
 
+// Retrieval trace of each turn, for the UI's "why did it say that?" view
+async fn get_session_trace(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+) -> Response {
+    let traces = match find_session(&state, &session_id).await {
+        Some(entry) => entry.lock().await.agent.retrieval_traces(HTTP_AGENT_ID),
+        None => {
+            get_sessions_dir_for_agent(HTTP_AGENT_ID).and_then(|dir| load_traces(&dir, &session_id))
+        }
+    };
+
+    match traces {
+        Ok(traces) => {
+            let turns: Vec<serde_json::Value> = traces
+                .iter()
+                .map(|trace| {
+                    let mut turn = json!(trace);
+                    turn["text"] = json!(trace.format());
+                    turn
+                })
+                .collect();
+            Json(json!({ "session_id": session_id, "turns": turns })).into_response()
+        }
+        Err(e) => AppError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+// System prompt the session's next turn sends, by section with token counts
+async fn get_session_prompt(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+) -> Response {
+    match find_session(&state, &session_id).await {
+        Some(entry) => Json(entry.lock().await.agent.inspect_prompt()).into_response(),
+        None => AppError(StatusCode::NOT_FOUND, "Session not found".to_string()).into_response(),
+    }
+}
+
 // Compact session history
 async fn compact_session(
     State(state): State<Arc<AppState>>,
     Path(session_id): Path<String>,
 ) -> Response {
-    let mut sessions = state.sessions.lock().await;
-
-    match sessions.get_mut(&session_id) {
+    match find_session(&state, &session_id).await {
         Some(entry) => {
+            let mut entry = entry.lock().await;
             entry.last_accessed = Instant::now();
 
             match entry.agent.compact_session().await {
@@ -589,10 +788,9 @@ async fn clear_session(
     State(state): State<Arc<AppState>>,
     Path(session_id): Path<String>,
 ) -> Response {
-    let mut sessions = state.sessions.lock().await;
-
-    match sessions.get_mut(&session_id) {
+    match find_session(&state, &session_id).await {
         Some(entry) => {
+            let mut entry = entry.lock().await;
             entry.last_accessed = Instant::now();
             entry.agent.clear_session();
             Json(json!({"session_id": session_id, "cleared": true})).into_response()
@@ -612,10 +810,9 @@ async fn set_session_model(
     Path(session_id): Path<String>,
     Json(request): Json<SetModelRequest>,
 ) -> Response {
-    let mut sessions = state.sessions.lock().await;
-
-    match sessions.get_mut(&session_id) {
+    match find_session(&state, &session_id).await {
         Some(entry) => {
+            let mut entry = entry.lock().await;
             entry.last_accessed = Instant::now();
 
             match entry.agent.set_model(&request.model) {
@@ -631,6 +828,46 @@ async fn set_session_model(
     }
 }
 
+// Log a finished tutoring session to the tutor notes
+#[derive(Deserialize)]
+struct TutorNotesRequest {
+    /// The student's name, if the frontend knows it
+    student: Option<String>,
+}
+
+async fn log_tutor_session(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+    Json(request): Json<TutorNotesRequest>,
+) -> Response {
+    match find_session(&state, &session_id).await {
+        Some(entry) => {
+            let mut entry = entry.lock().await;
+            entry.last_accessed = Instant::now();
+
+            match entry
+                .agent
+                .log_tutor_session(request.student.as_deref())
+                .await
+            {
+                Ok(write) => Json(json!({
+                    "session_id": session_id,
+                    "logged": write.is_some(),
+                    "staged_change": match write {
+                        Some(FileWrite::Staged(id)) => Some(id),
+                        _ => None,
+                    },
+                }))
+                .into_response(),
+                Err(e) => {
+                    AppError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+                }
+            }
+        }
+        None => AppError(StatusCode::NOT_FOUND, "Session not found".to_string()).into_response(),
+    }
+}
+
 // Chat endpoint
 #[derive(Deserialize)]
 struct ChatRequest {
@@ -640,7 +877,12 @@ struct ChatRequest {
     model: Option<String>,
     /// Optional additional context to append to the system prompt for this session.
     /// Used by frontends to inject persona/role instructions (e.g., tutor mode).
+    /// An empty string clears it.
     context: Option<String>,
+    /// The client answers approval requests sent in the stream (`homegpt
+    /// chat`, the desktop app); others are asked as `approval.remote` says
+    #[serde(default)]
+    approvals: bool,
 }
 
 #[derive(Serialize)]
@@ -650,6 +892,39 @@ struct ChatResponse {
     model: String,
 }
 
+#[derive(Deserialize)]
+struct AnswerRequest {
+    question: String,
+    session_id: Option<String>,
+}
+
+#[derive(Serialize)]
+struct AnswerResponse {
+    session_id: String,
+    #[serde(flatten)]
+    answer: GroundedAnswer,
+}
+
+// Grounded answer - from verified memory only, or an abstention
+async fn answer(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<AnswerRequest>,
+) -> Response {
+    let session_id = match get_or_create_session(&state, request.session_id).await {
+        Ok(id) => id,
+        Err(e) => return e.into_response(),
+    };
+
+    let Some(entry) = find_session(&state, &session_id).await else {
+        return AppError(StatusCode::NOT_FOUND, "Session not found".to_string()).into_response();
+    };
+    let entry = entry.lock().await;
+    match entry.agent.answer_grounded(&request.question).await {
+        Ok(answer) => Json(AnswerResponse { session_id, answer }).into_response(),
+        Err(e) => AppError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
 async fn chat(State(state): State<Arc<AppState>>, Json(request): Json<ChatRequest>) -> Response {
     // Get or create session
     let session_id = match get_or_create_session(&state, request.session_id).await {
@@ -657,39 +932,30 @@ async fn chat(State(state): State<Arc<AppState>>, Json(request): Json<ChatReques
         Err(e) => return e.into_response(),
     };
 
-    // Acquire in-process turn gate (waits for other turns to finish)
+    // Get agent from session; a turn already running in it finishes first
+    let Some(session) = find_session(&state, &session_id).await else {
+        return AppError(StatusCode::NOT_FOUND, "Session not found".to_string()).into_response();
+    };
+    let mut entry = session.lock().await;
+
+    // Acquire in-process turn gate (waits if too many turns are running)
     let _gate_permit = state.turn_gate.acquire().await;
 
-    // Acquire cross-process workspace lock (blocking, so use spawn_blocking)
-    let ws_lock_path = state.workspace_lock.clone();
-    let ws_guard = match tokio::task::spawn_blocking(move || ws_lock_path.acquire()).await {
-        Ok(Ok(guard)) => guard,
-        Ok(Err(e)) => {
-            return AppError(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to acquire workspace lock: {}", e),
-            )
-            .into_response()
-        }
+    // Acquire cross-process workspace lock, shared with the other turns
+    let ws_guard = match state.turn_gate.lock_workspace(&state.workspace_lock).await {
+        Ok(guard) => guard,
         Err(e) => {
             return AppError(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Lock task error: {}", e),
+                format!("Failed to acquire workspace lock: {}", e),
             )
             .into_response()
         }
     };
 
-    // Get agent from session
-    let mut sessions = state.sessions.lock().await;
-    let entry = match sessions.get_mut(&session_id) {
-        Some(e) => e,
-        None => {
-            return AppError(StatusCode::NOT_FOUND, "Session not found".to_string()).into_response()
-        }
-    };
-
     entry.last_accessed = Instant::now();
+    // A stream that was dropped mid-turn leaves its client's approver behind
+    attach_approver(&state, &mut entry.agent);
 
     // Switch model if requested
     if let Some(ref model) = request.model {
@@ -700,8 +966,10 @@ async fn chat(State(state): State<Arc<AppState>>, Json(request): Json<ChatReques
     }
 
     // Inject additional context into system prompt if provided
-    if let Some(ref context) = request.context {
-        entry.agent.set_additional_context(Some(context.clone()));
+    if let Some(context) = request.context {
+        entry
+            .agent
+            .set_additional_context(Some(context).filter(|c| !c.is_empty()));
     }
 
     let result = entry.agent.chat(&request.message).await;
@@ -736,33 +1004,28 @@ async fn chat_stream(
 
     let state_clone = state.clone();
     let message = request.message.clone();
+    let context = request.context;
+    let answers_approvals = request.approvals;
 
     let stream = async_stream::stream! {
         // Send session_id first
         yield Ok::<Event, Infallible>(Event::default().data(json!({"type": "session", "session_id": session_id}).to_string()));
 
+        // Only this session is held, so other sessions stream alongside
+        let Some(session) = find_session(&state_clone, &session_id).await else {
+            yield Ok(Event::default().data(json!({"error": "Session not found"}).to_string()));
+            return;
+        };
+        let mut entry = session.lock().await;
+
         // Acquire in-process turn gate
         let _gate_permit = state_clone.turn_gate.acquire().await;
 
-        // Acquire cross-process workspace lock
-        let ws_lock = state_clone.workspace_lock.clone();
-        let _ws_guard = match tokio::task::spawn_blocking(move || ws_lock.acquire()).await {
-            Ok(Ok(guard)) => Some(guard),
-            Ok(Err(e)) => {
-                yield Ok(Event::default().data(json!({"error": format!("Workspace lock error: {}", e)}).to_string()));
-                return;
-            }
+        // Acquire cross-process workspace lock, shared with the other turns
+        let _ws_guard = match state_clone.turn_gate.lock_workspace(&state_clone.workspace_lock).await {
+            Ok(guard) => guard,
             Err(e) => {
-                yield Ok(Event::default().data(json!({"error": format!("Lock task error: {}", e)}).to_string()));
-                return;
-            }
-        };
-
-        let mut sessions = state_clone.sessions.lock().await;
-        let entry = match sessions.get_mut(&session_id) {
-            Some(e) => e,
-            None => {
-                yield Ok(Event::default().data(json!({"error": "Session not found"}).to_string()));
+                yield Ok(Event::default().data(json!({"error": format!("Workspace lock error: {}", e)}).to_string()));
                 return;
             }
         };
@@ -770,15 +1033,44 @@ async fn chat_stream(
         entry.last_accessed = Instant::now();
         entry.dirty = true;
 
+        if let Some(context) = context {
+            entry.agent.set_additional_context(Some(context).filter(|c| !c.is_empty()));
+        }
+
+        // Approval requests go to the client, if it answers them
+        let mut approvals = None;
+        if answers_approvals {
+            let (approver, requests) = StreamApprover::new(STREAM_APPROVAL_TIMEOUT);
+            entry.agent.set_approver(Arc::new(approver));
+            approvals = Some(requests);
+        } else {
+            attach_approver(&state_clone, &mut entry.agent);
+        }
+
         // Use streaming with tools
-        match entry.agent.chat_stream_with_tools(&message).await {
+        match entry.agent.chat_stream_with_tools(&message, Vec::new()).await {
             Ok(event_stream) => {
+                use futures::future::Either;
                 use futures::StreamExt;
 
                 // Pin the stream to iterate over it
                 let mut pinned_stream = std::pin::pin!(event_stream);
 
-                while let Some(event) = pinned_stream.next().await {
+                loop {
+                    // A tool call waiting for approval holds up the turn's
+                    // stream, so its request is sent alongside
+                    let next = tokio::select! {
+                        Some(request) = async { approvals.as_mut()?.recv().await } => Either::Left(request),
+                        event = pinned_stream.next() => Either::Right(event),
+                    };
+                    let event = match next {
+                        Either::Left(request) => {
+                            yield Ok(Event::default().data(approval_event(&request).to_string()));
+                            continue;
+                        }
+                        Either::Right(Some(event)) => event,
+                        Either::Right(None) => break,
+                    };
                     match event {
                         Ok(StreamEvent::Content(content)) => {
                             let data = json!({"type": "content", "delta": content});
@@ -786,7 +1078,7 @@ async fn chat_stream(
                         }
                         Ok(StreamEvent::ToolCallStart { name, id, arguments }) => {
                             let detail = extract_tool_detail(&name, &arguments);
-                            let data = json!({"type": "tool_start", "name": name, "id": id, "detail": detail});
+                            let data = json!({"type": "tool_start", "name": name, "id": id, "detail": detail, "arguments": arguments});
                             yield Ok(Event::default().data(data.to_string()));
                         }
                         Ok(StreamEvent::ToolCallEnd { name, id, output }) => {
@@ -794,7 +1086,7 @@ async fn chat_stream(
                                 "type": "tool_end",
                                 "name": name,
                                 "id": id,
-                                "output": output.chars().take(500).collect::<String>()
+                                "output": output
                             });
                             yield Ok(Event::default().data(data.to_string()));
                         }
@@ -813,6 +1105,7 @@ async fn chat_stream(
                 yield Ok(Event::default().data(json!({"error": e.to_string()}).to_string()));
             }
         }
+        attach_approver(&state_clone, &mut entry.agent);
 
         yield Ok(Event::default().data("[DONE]"));
     };
@@ -820,6 +1113,19 @@ async fn chat_stream(
     Sse::new(stream).into_response()
 }
 
+/// The `approval` event asking a streaming client about a tool call
+fn approval_event(approval: &StreamApproval) -> serde_json::Value {
+    json!({
+        "type": "approval",
+        "id": approval.id,
+        "token": approval.token,
+        "name": approval.request.tool,
+        "detail": approval.request.detail,
+        "reason": approval.request.reason,
+        "arguments": approval.arguments,
+    })
+}
+
 // Memory search endpoint
 #[derive(Deserialize)]
 struct SearchQuery {
@@ -827,18 +1133,9 @@ struct SearchQuery {
     limit: Option<usize>,
 }
 
-#[derive(Serialize)]
-struct SearchResult {
-    file: String,
-    line_start: i32,
-    line_end: i32,
-    content: String,
-    score: f64,
-}
-
 #[derive(Serialize)]
 struct SearchResponse {
-    results: Vec<SearchResult>,
+    results: Vec<VerifiedChunk>,
     query: String,
 }
 
@@ -858,18 +1155,7 @@ fn memory_search_inner(
     limit: Option<usize>,
 ) -> Result<SearchResponse, anyhow::Error> {
     let limit = limit.unwrap_or(10);
-    let results = memory.search(query, limit)?;
-
-    let results: Vec<SearchResult> = results
-        .into_iter()
-        .map(|r| SearchResult {
-            file: r.file,
-            line_start: r.line_start,
-            line_end: r.line_end,
-            content: r.content,
-            score: r.score,
-        })
-        .collect();
+    let results = memory.search_verified(query, limit)?;
 
     Ok(SearchResponse {
         results,
@@ -877,6 +1163,28 @@ fn memory_search_inner(
     })
 }
 
+// Citation lookup - the chunk a [VERIFIED:<hash>] citation points at
+async fn memory_citation(State(state): State<Arc<AppState>>, Path(hash): Path<String>) -> Response {
+    let found = state.memory.verify_citation(&hash);
+    match found.map(|chunks| chunks.into_iter().next()) {
+        Ok(Some(chunk)) => Json(json!({
+            "hash": hash,
+            "location": format!("{}:{}-{}", chunk.file, chunk.line_start, chunk.line_end),
+            "file": chunk.file,
+            "line_start": chunk.line_start,
+            "line_end": chunk.line_end,
+            "verified": chunk.verified,
+            "provenance": chunk.provenance.to_string(),
+            "confidence": chunk.confidence.to_string(),
+        }))
+        .into_response(),
+        Ok(None) => {
+            AppError(StatusCode::NOT_FOUND, "Citation not found".to_string()).into_response()
+        }
+        Err(e) => AppError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
 // Memory stats endpoint
 #[derive(Serialize)]
 struct StatsResponse {
@@ -884,6 +1192,11 @@ struct StatsResponse {
     total_files: usize,
     total_chunks: usize,
     index_size_kb: u64,
+    /// Chunks per category, largest first
+    categories: Vec<(String, usize)>,
+    /// Verification hashes per provenance
+    provenance: Vec<(String, i64)>,
+    embedding_model: Option<String>,
 }
 
 async fn memory_stats(State(state): State<Arc<AppState>>) -> Response {
@@ -901,6 +1214,9 @@ fn memory_stats_inner(memory: &MemoryManager) -> Result<StatsResponse, anyhow::E
         total_files: stats.total_files,
         total_chunks: stats.total_chunks,
         index_size_kb: stats.index_size_kb,
+        categories: memory.category_counts()?,
+        provenance: memory.verification_stats()?.by_provenance,
+        embedding_model: memory.embedding_model(),
     })
 }
 
@@ -1062,6 +1378,162 @@ async fn heartbeat_status(State(state): State<Arc<AppState>>) -> Json<HeartbeatS
     })
 }
 
+// Inbound webhook endpoint: runs the hook's prompt (or a heartbeat) and
+// returns the reply
+#[derive(Serialize)]
+struct WebhookResponse {
+    hook: String,
+    reply: String,
+}
+
+async fn inbound_webhook(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+    body: String,
+) -> Response {
+    let Some(hook) = webhooks::find_inbound(&state.config, &name) else {
+        return AppError(StatusCode::NOT_FOUND, format!("Unknown hook: {}", name)).into_response();
+    };
+
+    let provided = headers
+        .get("x-webhook-secret")
+        .and_then(|value| value.to_str().ok())
+        .or(params.get("secret").map(String::as_str));
+    if !webhooks::is_authorized(hook, provided) {
+        return AppError(StatusCode::UNAUTHORIZED, "Invalid secret".to_string()).into_response();
+    }
+
+    info!("Webhook triggered: {}", name);
+    let result = if hook.heartbeat {
+        // The runner takes the turn gate and workspace lock itself
+        match HeartbeatRunner::new_with_gate(&state.config, "main", Some(state.turn_gate.clone())) {
+            Ok(runner) => runner.run_once().await,
+            Err(e) => Err(e),
+        }
+    } else {
+        run_webhook_prompt(&state, &webhooks::render_prompt(&hook.prompt, &body)).await
+    };
+
+    match result {
+        Ok(reply) => Json(WebhookResponse { hook: name, reply }).into_response(),
+        Err(e) => AppError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Run a hook prompt in a fresh session
+async fn run_webhook_prompt(state: &Arc<AppState>, prompt: &str) -> Result<String> {
+    let agent_config = AgentConfig {
+        model: state.config.agent.default_model.clone(),
+        context_window: state.config.agent.context_window,
+        reserve_tokens: state.config.agent.reserve_tokens,
+    };
+    let mut agent = Agent::new(agent_config, &state.config, state.memory.clone()).await?;
+    attach_approver(state, &mut agent);
+    agent.new_session().await?;
+
+    let _gate_permit = state.turn_gate.acquire().await;
+    let _ws_guard = state
+        .turn_gate
+        .lock_workspace(&state.workspace_lock)
+        .await?;
+
+    agent.chat(prompt).await
+}
+
+#[derive(Deserialize)]
+struct ApprovalQuery {
+    token: String,
+}
+
+// Approval page - linked from approval requests on channels without buttons
+async fn approval_page(Path(id): Path<String>, Query(query): Query<ApprovalQuery>) -> Response {
+    let Some(request) = pending_approval(&id, &query.token) else {
+        return approval_gone();
+    };
+    let form = |answer: &str, label: &str| {
+        format!(
+            "<form method=\"post\" action=\"/approvals/{}/{}?token={}\" style=\"display:inline\">\
+             <button>{}</button></form>",
+            id, answer, query.token, label
+        )
+    };
+    Html(format!(
+        "<!doctype html><meta name=\"viewport\" content=\"width=device-width\">\
+         <title>HomeGPT approval</title><p>{}</p>{} {}",
+        html_escape(&request.summary()),
+        form("approve", "Approve"),
+        form("deny", "Deny")
+    ))
+    .into_response()
+}
+
+// Answer an approval request (ntfy action buttons and the approval page)
+async fn answer_approval_request(
+    Path((id, answer)): Path<(String, String)>,
+    Query(query): Query<ApprovalQuery>,
+) -> Response {
+    let approved = match answer.as_str() {
+        "approve" => true,
+        "deny" => false,
+        _ => return AppError(StatusCode::NOT_FOUND, "Not found".to_string()).into_response(),
+    };
+    match answer_approval(&id, &query.token, approved) {
+        Some(request) => {
+            info!(
+                "Approval {} {}: {}",
+                id,
+                if approved { "approved" } else { "denied" },
+                request.tool
+            );
+            let verb = if approved { "Approved" } else { "Denied" };
+            format!("{}: {}", verb, request.summary()).into_response()
+        }
+        None => approval_gone(),
+    }
+}
+
+fn approval_gone() -> Response {
+    AppError(
+        StatusCode::NOT_FOUND,
+        "This request was already answered or has expired.".to_string(),
+    )
+    .into_response()
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Calendar feed - events created with calendar_add_event, for phones to subscribe to
+async fn calendar_feed(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    let calendar_config = &state.config.calendar;
+    if !calendar_config.publish {
+        return AppError(StatusCode::NOT_FOUND, "Not published".to_string()).into_response();
+    }
+    let token = calendar_config.feed_token.as_deref().unwrap_or_default();
+    if !token.is_empty() && params.get("token").map(String::as_str) != Some(token) {
+        return AppError(StatusCode::UNAUTHORIZED, "Invalid token".to_string()).into_response();
+    }
+
+    let calendar = get_state_dir().and_then(|dir| Calendar::from_config(&state.config, &dir));
+    match calendar {
+        Ok(calendar) => (
+            [(header::CONTENT_TYPE, "text/calendar; charset=utf-8")],
+            calendar.feed(),
+        )
+            .into_response(),
+        Err(e) => AppError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
 // Saved sessions endpoint - list sessions from file store
 #[derive(Serialize)]
 struct SavedSessionInfo {
@@ -1244,7 +1716,7 @@ async fn get_daemon_logs(Query(query): Query<LogsQuery>) -> Response {
     };
 
     // Use date-based log file (matches daemon.rs)
-    let date = chrono::Local::now().format("%Y-%m-%d");
+    let date = locale::now().format("%Y-%m-%d");
     let log_path = state_dir
         .join("logs")
         .join(format!("homegpt-{}.log", date));
@@ -1413,26 +1885,32 @@ async fn handle_websocket(socket: WebSocket, state: Arc<AppState>) {
 
                         debug!("WebSocket chat [{}]: {}", session_id, message);
 
+                        // Process chat, holding only this session
+                        let session = match find_session(&state, &session_id).await {
+                            Some(session) => session,
+                            None => {
+                                let error = WsOutgoing::Error {
+                                    message: "Session not found".to_string(),
+                                };
+                                if let Ok(json) = serde_json::to_string(&error) {
+                                    let _ = sender.send(WsMessage::Text(json.into())).await;
+                                }
+                                current_session_id = None;
+                                continue;
+                            }
+                        };
+                        let mut entry = session.lock().await;
+
                         // Acquire in-process turn gate
                         let _gate_permit = state.turn_gate.acquire().await;
 
-                        // Acquire cross-process workspace lock
-                        let ws_lock = state.workspace_lock.clone();
+                        // Acquire cross-process workspace lock, shared with the other turns
                         let _ws_guard =
-                            match tokio::task::spawn_blocking(move || ws_lock.acquire()).await {
-                                Ok(Ok(guard)) => guard,
-                                Ok(Err(e)) => {
-                                    let error = WsOutgoing::Error {
-                                        message: format!("Workspace lock error: {}", e),
-                                    };
-                                    if let Ok(json) = serde_json::to_string(&error) {
-                                        let _ = sender.send(WsMessage::Text(json.into())).await;
-                                    }
-                                    continue;
-                                }
+                            match state.turn_gate.lock_workspace(&state.workspace_lock).await {
+                                Ok(guard) => guard,
                                 Err(e) => {
                                     let error = WsOutgoing::Error {
-                                        message: format!("Lock task error: {}", e),
+                                        message: format!("Workspace lock error: {}", e),
                                     };
                                     if let Ok(json) = serde_json::to_string(&error) {
                                         let _ = sender.send(WsMessage::Text(json.into())).await;
@@ -1441,22 +1919,6 @@ async fn handle_websocket(socket: WebSocket, state: Arc<AppState>) {
                                 }
                             };
 
-                        // Process chat
-                        let mut sessions = state.sessions.lock().await;
-                        let entry = match sessions.get_mut(&session_id) {
-                            Some(e) => e,
-                            None => {
-                                let error = WsOutgoing::Error {
-                                    message: "Session not found".to_string(),
-                                };
-                                if let Ok(json) = serde_json::to_string(&error) {
-                                    let _ = sender.send(WsMessage::Text(json.into())).await;
-                                }
-                                current_session_id = None;
-                                continue;
-                            }
-                        };
-
                         entry.last_accessed = Instant::now();
 
                         match entry.agent.chat(&message).await {
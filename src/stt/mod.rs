@@ -0,0 +1,195 @@
+//! Speech to text, for the desktop app's mic
+//!
+//! Recorded audio is sent as a WAV file to a Whisper server with the OpenAI
+//! API (the one the voice bridge uses), or to OpenAI itself.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::config::Config;
+
+mod vad;
+mod voiceprint;
+pub use vad::{frame_db, VoiceDetector};
+pub use voiceprint::{embed_voice, name_said, Speaker, Voiceprints};
+
+/// Sample rate the mic records at
+pub const SAMPLE_RATE: u32 = 16_000;
+
+/// Turns recorded speech into text
+pub struct Transcriber {
+    client: reqwest::Client,
+    /// API base; `/audio/transcriptions` is added
+    base_url: String,
+    api_key: Option<String>,
+    model: String,
+}
+
+#[derive(Deserialize)]
+struct Transcription {
+    text: String,
+}
+
+impl Transcriber {
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let stt = &config.stt;
+        let (base_url, api_key, default_model) = match stt.kind.as_str() {
+            "whisper" => (
+                format!("{}/v1", stt.url.trim_end_matches('/')),
+                None,
+                "base",
+            ),
+            "openai" => {
+                let openai = config.providers.openai.as_ref().ok_or_else(|| {
+                    anyhow::anyhow!("stt.kind = \"openai\" needs [providers.openai] in config")
+                })?;
+                (
+                    openai.base_url.trim_end_matches('/').to_string(),
+                    Some(openai.api_key.clone()),
+                    "whisper-1",
+                )
+            }
+            other => anyhow::bail!("Unknown stt.kind '{}': expected whisper or openai", other),
+        };
+        let model = if stt.model.is_empty() {
+            default_model.to_string()
+        } else {
+            stt.model.clone()
+        };
+        Ok(Self {
+            client: reqwest::Client::new(),
+            base_url,
+            api_key,
+            model,
+        })
+    }
+
+    /// What was said in `samples` (mono, at `SAMPLE_RATE`)
+    pub async fn transcribe(&self, samples: &[i16]) -> Result<String> {
+        let file = reqwest::multipart::Part::bytes(wav_bytes(samples, SAMPLE_RATE))
+            .file_name("speech.wav")
+            .mime_str("audio/wav")?;
+        let form = reqwest::multipart::Form::new()
+            .text("model", self.model.clone())
+            .part("file", file);
+        let mut request = self
+            .client
+            .post(format!("{}/audio/transcriptions", self.base_url))
+            .multipart(form);
+        if let Some(ref key) = self.api_key {
+            request = request.bearer_auth(key);
+        }
+        let response = request
+            .send()
+            .await
+            .context("Transcription request failed")?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("Transcription returned {}: {}", status, body);
+        }
+        let transcription: Transcription = response.json().await?;
+        Ok(transcription.text.trim().to_string())
+    }
+}
+
+/// `samples` as a 16-bit mono WAV file
+pub fn wav_bytes(samples: &[i16], sample_rate: u32) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    // PCM, one channel
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+    wav
+}
+
+/// What was asked after `wake_word` in `text`, or `None` if it wasn't
+/// said. Case, spacing and punctuation don't matter, since transcripts
+/// vary ("Hey, Home GPT!" matches "hey homegpt"). The rest may be empty.
+pub fn after_wake_word<'a>(text: &'a str, wake_word: &str) -> Option<&'a str> {
+    let wanted: Vec<char> = wake_word
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect();
+    if wanted.is_empty() {
+        return Some(text.trim());
+    }
+    // Letters of `text`, with where each ends
+    let letters: Vec<(char, usize)> = text
+        .char_indices()
+        .filter(|(_, c)| c.is_alphanumeric())
+        .flat_map(|(i, c)| c.to_lowercase().map(move |l| (l, i + c.len_utf8())))
+        .collect();
+    let end = letters
+        .windows(wanted.len())
+        .find(|window| window.iter().map(|(c, _)| *c).eq(wanted.iter().copied()))
+        .map(|window| window[window.len() - 1].1)?;
+    Some(
+        text[end..]
+            .trim_start_matches(|c: char| !c.is_alphanumeric())
+            .trim_end(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wav_bytes() {
+        let wav = wav_bytes(&[0, 1, -1], SAMPLE_RATE);
+        assert_eq!(wav.len(), 44 + 6);
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(u32::from_le_bytes(wav[4..8].try_into().unwrap()), 36 + 6);
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        assert_eq!(u32::from_le_bytes(wav[24..28].try_into().unwrap()), 16_000);
+        assert_eq!(u32::from_le_bytes(wav[28..32].try_into().unwrap()), 32_000);
+        assert_eq!(&wav[36..40], b"data");
+        assert_eq!(u32::from_le_bytes(wav[40..44].try_into().unwrap()), 6);
+        assert_eq!(&wav[44..], &[0, 0, 1, 0, 0xff, 0xff]);
+    }
+
+    #[test]
+    fn test_after_wake_word() {
+        let wake = "hey tutor";
+        assert_eq!(
+            after_wake_word("Hey, Tutor! What's seven times eight?", wake),
+            Some("What's seven times eight?")
+        );
+        assert_eq!(after_wake_word("Um, hey tutor.", wake), Some(""));
+        assert_eq!(after_wake_word("Hey tooter, help", wake), None);
+        assert_eq!(
+            after_wake_word("Hey Home GPT what's for dinner", "hey homegpt"),
+            Some("what's for dinner")
+        );
+        assert_eq!(after_wake_word(" Hello ", ""), Some("Hello"));
+    }
+
+    #[test]
+    fn test_from_config() {
+        let mut config = Config::default();
+        let transcriber = Transcriber::from_config(&config).unwrap();
+        assert_eq!(transcriber.base_url, "http://localhost:8001/v1");
+        assert_eq!(transcriber.model, "base");
+        assert!(transcriber.api_key.is_none());
+
+        config.stt.kind = "openai".to_string();
+        assert!(Transcriber::from_config(&config).is_err());
+        config.stt.kind = "vosk".to_string();
+        assert!(Transcriber::from_config(&config).is_err());
+    }
+}
@@ -0,0 +1,195 @@
+//! Voice activity detection for the open mic: splits a stream of samples
+//! into utterances by loudness
+//!
+//! Audio is judged in 30 ms frames. An utterance starts at the first frame
+//! above the threshold (with a little of what came before, so the first
+//! word isn't clipped) and ends after `END_OF_TURN` of quiet.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use super::SAMPLE_RATE;
+
+/// Samples per frame: 30 ms
+const FRAME: usize = SAMPLE_RATE as usize * 30 / 1000;
+
+/// Quiet that ends an utterance
+const END_OF_TURN: Duration = Duration::from_millis(800);
+
+/// Audio kept from before speech starts
+const PRE_ROLL: Duration = Duration::from_millis(300);
+
+/// Less loud audio than this is a cough or a door, not speech
+const MIN_SPEECH: Duration = Duration::from_millis(250);
+
+/// Utterances are cut off at this length
+const MAX_UTTERANCE: Duration = Duration::from_secs(30);
+
+fn samples(duration: Duration) -> usize {
+    (duration.as_millis() as usize) * SAMPLE_RATE as usize / 1000
+}
+
+/// Loudness of `frame` in dBFS
+pub fn frame_db(frame: &[i16]) -> f32 {
+    if frame.is_empty() {
+        return f32::NEG_INFINITY;
+    }
+    let sum: f64 = frame.iter().map(|&s| (s as f64) * (s as f64)).sum();
+    let rms = (sum / frame.len() as f64).sqrt() / i16::MAX as f64;
+    (20.0 * rms.log10()) as f32
+}
+
+/// Finds utterances in mic audio, one frame at a time
+#[derive(Debug)]
+pub struct VoiceDetector {
+    /// Frames at or above this (dBFS) are speech
+    threshold: f32,
+    /// Samples not yet making up a whole frame
+    partial: Vec<i16>,
+    /// Recent quiet frames, while waiting for speech
+    pre_roll: VecDeque<Vec<i16>>,
+    /// The utterance so far, once speech started
+    utterance: Option<Vec<i16>>,
+    speech_samples: usize,
+    quiet_samples: usize,
+}
+
+impl VoiceDetector {
+    pub fn new(threshold: f32) -> Self {
+        Self {
+            threshold,
+            partial: Vec::new(),
+            pre_roll: VecDeque::new(),
+            utterance: None,
+            speech_samples: 0,
+            quiet_samples: 0,
+        }
+    }
+
+    /// Whether someone is talking right now
+    pub fn in_speech(&self) -> bool {
+        self.utterance.is_some()
+    }
+
+    /// Forget what was heard so far (the mic was muted)
+    pub fn reset(&mut self) {
+        self.partial.clear();
+        self.pre_roll.clear();
+        self.utterance = None;
+        self.speech_samples = 0;
+        self.quiet_samples = 0;
+    }
+
+    /// Add mic `samples`; returns the utterances they finished
+    pub fn push(&mut self, samples: &[i16]) -> Vec<Vec<i16>> {
+        self.partial.extend_from_slice(samples);
+        let mut finished = Vec::new();
+        let whole = self.partial.len() - self.partial.len() % FRAME;
+        let frames: Vec<i16> = self.partial.drain(..whole).collect();
+        for frame in frames.chunks_exact(FRAME) {
+            if let Some(utterance) = self.push_frame(frame) {
+                finished.push(utterance);
+            }
+        }
+        finished
+    }
+
+    fn push_frame(&mut self, frame: &[i16]) -> Option<Vec<i16>> {
+        let loud = frame_db(frame) >= self.threshold;
+        let Some(utterance) = self.utterance.as_mut() else {
+            if loud {
+                let mut utterance: Vec<i16> = self.pre_roll.drain(..).flatten().collect();
+                utterance.extend_from_slice(frame);
+                self.utterance = Some(utterance);
+                self.speech_samples = frame.len();
+                self.quiet_samples = 0;
+            } else {
+                self.pre_roll.push_back(frame.to_vec());
+                if self.pre_roll.len() * FRAME > samples(PRE_ROLL) {
+                    self.pre_roll.pop_front();
+                }
+            }
+            return None;
+        };
+
+        utterance.extend_from_slice(frame);
+        if loud {
+            self.speech_samples += frame.len();
+            self.quiet_samples = 0;
+        } else {
+            self.quiet_samples += frame.len();
+        }
+        let ended = self.quiet_samples >= samples(END_OF_TURN);
+        if !ended && utterance.len() < samples(MAX_UTTERANCE) {
+            return None;
+        }
+
+        let mut utterance = self.utterance.take()?;
+        // Keep a little of the trailing quiet, not all of it
+        let trailing = self.quiet_samples.saturating_sub(samples(PRE_ROLL));
+        utterance.truncate(utterance.len() - trailing);
+        let speech = std::mem::take(&mut self.speech_samples);
+        self.quiet_samples = 0;
+        (speech >= samples(MIN_SPEECH)).then_some(utterance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(duration: Duration, amplitude: i16) -> Vec<i16> {
+        (0..samples(duration))
+            .map(|i| if i % 2 == 0 { amplitude } else { -amplitude })
+            .collect()
+    }
+
+    fn quiet(duration: Duration) -> Vec<i16> {
+        vec![0; samples(duration)]
+    }
+
+    #[test]
+    fn test_frame_db() {
+        assert_eq!(frame_db(&tone(Duration::from_millis(30), i16::MAX)), 0.0);
+        let db = frame_db(&tone(Duration::from_millis(30), 327));
+        assert!((-41.0..-39.0).contains(&db), "{}", db);
+        assert_eq!(frame_db(&[]), f32::NEG_INFINITY);
+    }
+
+    #[test]
+    fn test_utterances() {
+        let mut detector = VoiceDetector::new(-40.0);
+        assert!(detector.push(&quiet(Duration::from_secs(1))).is_empty());
+
+        // Speech, a pause too short to end the turn, more speech
+        assert!(detector
+            .push(&tone(Duration::from_millis(600), 8000))
+            .is_empty());
+        assert!(detector.in_speech());
+        assert!(detector.push(&quiet(Duration::from_millis(400))).is_empty());
+        assert!(detector
+            .push(&tone(Duration::from_millis(500), 8000))
+            .is_empty());
+
+        let finished = detector.push(&quiet(Duration::from_secs(1)));
+        assert_eq!(finished.len(), 1);
+        assert!(!detector.in_speech());
+        // Pre-roll, 1.5 s of the utterance, and a little quiet after
+        let length = finished[0].len();
+        assert!(length >= samples(Duration::from_millis(1800)), "{}", length);
+        assert!(length <= samples(Duration::from_millis(2200)), "{}", length);
+    }
+
+    #[test]
+    fn test_short_noise_dropped() {
+        let mut detector = VoiceDetector::new(-40.0);
+        let mut audio = tone(Duration::from_millis(90), 8000);
+        audio.extend(quiet(Duration::from_secs(1)));
+        assert!(detector.push(&audio).is_empty());
+        assert!(!detector.in_speech());
+
+        // Too quiet to count
+        assert!(detector.push(&tone(Duration::from_secs(1), 100)).is_empty());
+        assert!(!detector.in_speech());
+    }
+}
@@ -0,0 +1,447 @@
+//! Speaker identification: a voiceprint per family member, from a few
+//! enrollment recordings
+//!
+//! A recording's voiceprint is the mean and spread of its MFCCs (the shape
+//! of the voice's spectrum, which differs with vocal tract size) over the
+//! frames with speech in them. Voiceprints are compared by cosine
+//! similarity. It's lightweight, not forensic: good enough to tell a
+//! family's voices apart on one mic, and it says when it isn't sure.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::f32::consts::PI;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use super::{frame_db, SAMPLE_RATE};
+use crate::agent::get_state_dir;
+
+/// Enrolled voiceprints (relative to the state dir)
+const STATE_FILE: &str = "voiceprints.json";
+
+/// Samples per analysis frame (25 ms) and between frames (10 ms)
+const FRAME_LEN: usize = 400;
+const HOP: usize = 160;
+const FFT_LEN: usize = 512;
+
+/// Mel filters between these frequencies
+const MEL_BANDS: usize = 26;
+const LOW_HZ: f32 = 60.0;
+const HIGH_HZ: f32 = 8000.0;
+
+/// MFCCs kept, after the first (which is only loudness)
+const COEFFICIENTS: usize = 12;
+
+/// Frames this far below the loudest, or quieter than `SILENCE`, aren't
+/// speech
+const SPEECH_RANGE: f32 = 30.0;
+const SILENCE: f32 = -60.0;
+
+/// Half a second of speech, at least, to tell a voice by
+const MIN_FRAMES: usize = 50;
+
+/// How far ahead of everyone else a voice must be to switch to them
+const MARGIN: f32 = 0.03;
+
+/// The voiceprint of `samples` (mono, at `SAMPLE_RATE`), or `None` if
+/// there's too little speech in them
+pub fn embed_voice(samples: &[i16]) -> Option<Vec<f32>> {
+    if samples.len() < FRAME_LEN {
+        return None;
+    }
+    let frames: Vec<&[i16]> = (0..=samples.len() - FRAME_LEN)
+        .step_by(HOP)
+        .map(|start| &samples[start..start + FRAME_LEN])
+        .collect();
+    let loudest = frames
+        .iter()
+        .map(|frame| frame_db(frame))
+        .fold(f32::NEG_INFINITY, f32::max);
+    let quietest = (loudest - SPEECH_RANGE).max(SILENCE);
+
+    let analysis = Analysis::new();
+    let cepstra: Vec<[f32; COEFFICIENTS]> = frames
+        .iter()
+        .filter(|frame| frame_db(frame) >= quietest)
+        .map(|frame| analysis.cepstrum(frame))
+        .collect();
+    if cepstra.len() < MIN_FRAMES {
+        return None;
+    }
+
+    let count = cepstra.len() as f32;
+    let mut mean = [0.0f32; COEFFICIENTS];
+    for cepstrum in &cepstra {
+        for (sum, c) in mean.iter_mut().zip(cepstrum) {
+            *sum += c / count;
+        }
+    }
+    let mut spread = [0.0f32; COEFFICIENTS];
+    for cepstrum in &cepstra {
+        for ((sum, c), m) in spread.iter_mut().zip(cepstrum).zip(&mean) {
+            *sum += (c - m).powi(2) / count;
+        }
+    }
+    let mut embedding = mean.to_vec();
+    embedding.extend(spread.iter().map(|variance| variance.sqrt()));
+    Some(embedding)
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 {
+        0.0
+    } else {
+        dot / norms
+    }
+}
+
+/// Window and mel filters, shared by a recording's frames
+struct Analysis {
+    window: Vec<f32>,
+    /// Weight of each FFT bin in each mel band
+    filters: Vec<Vec<f32>>,
+}
+
+impl Analysis {
+    fn new() -> Self {
+        let window = (0..FRAME_LEN)
+            .map(|n| 0.54 - 0.46 * (2.0 * PI * n as f32 / (FRAME_LEN - 1) as f32).cos())
+            .collect();
+
+        let mel = |hz: f32| 2595.0 * (1.0 + hz / 700.0).log10();
+        let hz = |mel: f32| 700.0 * (10f32.powf(mel / 2595.0) - 1.0);
+        let (low, high) = (mel(LOW_HZ), mel(HIGH_HZ));
+        let edges: Vec<f32> = (0..MEL_BANDS + 2)
+            .map(|i| hz(low + (high - low) * i as f32 / (MEL_BANDS + 1) as f32))
+            .collect();
+        let bin_hz = SAMPLE_RATE as f32 / FFT_LEN as f32;
+        let filters = edges
+            .windows(3)
+            .map(|edge| {
+                (0..=FFT_LEN / 2)
+                    .map(|bin| {
+                        let f = bin as f32 * bin_hz;
+                        let rising = (f - edge[0]) / (edge[1] - edge[0]);
+                        let falling = (edge[2] - f) / (edge[2] - edge[1]);
+                        rising.min(falling).max(0.0)
+                    })
+                    .collect()
+            })
+            .collect();
+        Self { window, filters }
+    }
+
+    /// MFCCs 1 to `COEFFICIENTS` of one frame
+    fn cepstrum(&self, frame: &[i16]) -> [f32; COEFFICIENTS] {
+        let mut re = vec![0.0f32; FFT_LEN];
+        let mut im = vec![0.0f32; FFT_LEN];
+        // Pre-emphasis lifts the higher formants
+        let mut previous = 0.0;
+        for ((out, &sample), w) in re.iter_mut().zip(frame).zip(&self.window) {
+            let sample = sample as f32 / i16::MAX as f32;
+            *out = (sample - 0.97 * previous) * w;
+            previous = sample;
+        }
+        fft(&mut re, &mut im);
+        let power: Vec<f32> = re
+            .iter()
+            .zip(&im)
+            .take(FFT_LEN / 2 + 1)
+            .map(|(r, i)| r * r + i * i)
+            .collect();
+        let bands: Vec<f32> = self
+            .filters
+            .iter()
+            .map(|filter| {
+                let energy: f32 = filter.iter().zip(&power).map(|(w, p)| w * p).sum();
+                (energy + 1e-10).ln()
+            })
+            .collect();
+
+        let mut cepstrum = [0.0f32; COEFFICIENTS];
+        for (k, c) in cepstrum.iter_mut().enumerate() {
+            let k = (k + 1) as f32;
+            *c = bands
+                .iter()
+                .enumerate()
+                .map(|(m, band)| band * (PI * k * (m as f32 + 0.5) / MEL_BANDS as f32).cos())
+                .sum();
+        }
+        cepstrum
+    }
+}
+
+/// In-place radix-2 FFT; the length must be a power of two
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let tr = re[b] * cos - im[b] * sin;
+                let ti = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - tr;
+                im[b] = im[a] - ti;
+                re[a] += tr;
+                im[a] += ti;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// Who a voice belongs to
+#[derive(Debug, Clone, PartialEq)]
+pub enum Speaker {
+    Known(String),
+    /// Nobody's voiceprint matches well enough, or two match about as well
+    Unsure,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Voiceprint {
+    /// Average of the enrollment recordings' voiceprints
+    embedding: Vec<f32>,
+    recordings: u32,
+}
+
+/// Everyone's enrolled voiceprints, by name
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Voiceprints {
+    people: BTreeMap<String, Voiceprint>,
+}
+
+impl Voiceprints {
+    /// Where voiceprints are kept
+    pub fn path() -> Result<PathBuf> {
+        Ok(get_state_dir()?.join(STATE_FILE))
+    }
+
+    /// Voiceprints saved at `path`; none if there aren't any yet
+    pub fn load(path: &Path) -> Self {
+        let Ok(content) = fs::read_to_string(path) else {
+            return Self::default();
+        };
+        serde_json::from_str(&content).unwrap_or_else(|e| {
+            warn!("Ignoring unreadable voiceprints {}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.people.is_empty()
+    }
+
+    /// Enrolled names, with how many recordings each
+    pub fn enrolled(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.people
+            .iter()
+            .map(|(name, print)| (name.as_str(), print.recordings))
+    }
+
+    /// Add a recording's voiceprint to `name`'s; returns their recording count
+    pub fn enroll(&mut self, name: &str, embedding: &[f32]) -> u32 {
+        let print = self
+            .people
+            .entry(name.to_string())
+            .or_insert_with(|| Voiceprint {
+                embedding: vec![0.0; embedding.len()],
+                recordings: 0,
+            });
+        if print.embedding.len() != embedding.len() {
+            // Enrolled by an older version; start over
+            print.embedding = vec![0.0; embedding.len()];
+            print.recordings = 0;
+        }
+        print.recordings += 1;
+        let weight = 1.0 / print.recordings as f32;
+        for (average, value) in print.embedding.iter_mut().zip(embedding) {
+            *average += (value - *average) * weight;
+        }
+        print.recordings
+    }
+
+    /// Remove `name`'s voiceprint; false if they had none
+    pub fn forget(&mut self, name: &str) -> bool {
+        self.people.remove(name).is_some()
+    }
+
+    /// Whose voice `embedding` is. A match needs at least `threshold`
+    /// similarity; someone other than `current` (who's chatting now) must
+    /// also be clearly ahead of everyone else.
+    pub fn identify(&self, embedding: &[f32], threshold: f32, current: Option<&str>) -> Speaker {
+        let mut scores: Vec<(&str, f32)> = self
+            .people
+            .iter()
+            .map(|(name, print)| (name.as_str(), cosine(&print.embedding, embedding)))
+            .collect();
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let Some(&(best, score)) = scores.first() else {
+            return Speaker::Unsure;
+        };
+
+        let current_score = scores.iter().find(|(name, _)| Some(*name) == current);
+        if let Some(&(name, current_score)) = current_score {
+            if current_score >= threshold && score - current_score < MARGIN {
+                return Speaker::Known(name.to_string());
+            }
+        }
+        let runner_up = scores.get(1).map_or(f32::NEG_INFINITY, |(_, s)| *s);
+        if score >= threshold && score - runner_up >= MARGIN {
+            Speaker::Known(best.to_string())
+        } else {
+            Speaker::Unsure
+        }
+    }
+}
+
+/// Which of `names` is said in `text` ("It's Mia"), matching whole words
+/// regardless of case
+pub fn name_said<'a>(text: &str, names: &[&'a str]) -> Option<&'a str> {
+    let words = |s: &str| -> Vec<String> {
+        s.split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect()
+    };
+    let said = words(text);
+    names.iter().copied().find(|name| {
+        let name = words(name);
+        !name.is_empty() && said.windows(name.len()).any(|window| window == name)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A vowel-ish sound: harmonics of `pitch` shaped by two formants
+    fn voice(pitch: f32, formants: (f32, f32), seconds: f32) -> Vec<i16> {
+        let count = (SAMPLE_RATE as f32 * seconds) as usize;
+        let gain = |f: f32, formant: f32| 1.0 / (1.0 + ((f - formant) / 150.0).powi(2));
+        (0..count)
+            .map(|n| {
+                let t = n as f32 / SAMPLE_RATE as f32;
+                let mut sample = 0.0;
+                let mut harmonic = pitch;
+                while harmonic < 4000.0 {
+                    let amplitude = gain(harmonic, formants.0) + 0.5 * gain(harmonic, formants.1);
+                    sample += amplitude * (2.0 * PI * harmonic * t).sin();
+                    harmonic += pitch;
+                }
+                (sample * 3000.0).clamp(-32767.0, 32767.0) as i16
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fft() {
+        // A cosine at bin 4 puts half its energy in bin 4 and half in 12
+        let mut re: Vec<f32> = (0..16)
+            .map(|n| (2.0 * PI * 4.0 * n as f32 / 16.0).cos())
+            .collect();
+        let mut im = vec![0.0; 16];
+        fft(&mut re, &mut im);
+        assert!((re[4] - 8.0).abs() < 1e-3, "{}", re[4]);
+        assert!((re[12] - 8.0).abs() < 1e-3, "{}", re[12]);
+        assert!(re[1].abs() < 1e-3 && im[4].abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_too_little_speech() {
+        assert!(embed_voice(&[]).is_none());
+        assert!(embed_voice(&vec![0; SAMPLE_RATE as usize]).is_none());
+        assert!(embed_voice(&voice(120.0, (700.0, 1200.0), 0.2)).is_none());
+        assert!(embed_voice(&voice(120.0, (700.0, 1200.0), 1.0)).is_some());
+    }
+
+    #[test]
+    fn test_identify() {
+        let low = voice(110.0, (600.0, 1000.0), 1.5);
+        let high = voice(260.0, (900.0, 2300.0), 1.5);
+        let mut prints = Voiceprints::default();
+        assert_eq!(
+            prints.identify(&embed_voice(&low).unwrap(), 0.8, None),
+            Speaker::Unsure
+        );
+        prints.enroll("Dad", &embed_voice(&low).unwrap());
+        assert_eq!(prints.enroll("Mia", &embed_voice(&high).unwrap()), 1);
+
+        // Another take, a little different
+        let dad = embed_voice(&voice(115.0, (620.0, 1020.0), 1.2)).unwrap();
+        let mia = embed_voice(&voice(250.0, (880.0, 2250.0), 1.2)).unwrap();
+        assert_eq!(
+            prints.identify(&dad, 0.8, None),
+            Speaker::Known("Dad".to_string())
+        );
+        assert_eq!(
+            prints.identify(&mia, 0.8, Some("Dad")),
+            Speaker::Known("Mia".to_string())
+        );
+        assert_eq!(prints.identify(&mia, 0.9999, None), Speaker::Unsure);
+
+        assert_eq!(prints.enroll("Mia", &mia), 2);
+        assert!(prints.forget("Dad"));
+        assert!(!prints.forget("Dad"));
+        assert_eq!(prints.enrolled().collect::<Vec<_>>(), vec![("Mia", 2)]);
+    }
+
+    #[test]
+    fn test_identify_close_call() {
+        let mut prints = Voiceprints::default();
+        prints.enroll("Leo", &[1.0, 0.0, 0.2]);
+        prints.enroll("Mia", &[1.0, 0.05, 0.2]);
+        // Too close to call, unless one of them is already chatting
+        assert_eq!(
+            prints.identify(&[1.0, 0.02, 0.2], 0.8, None),
+            Speaker::Unsure
+        );
+        assert_eq!(
+            prints.identify(&[1.0, 0.02, 0.2], 0.8, Some("Mia")),
+            Speaker::Known("Mia".to_string())
+        );
+        assert_eq!(
+            prints.identify(&[1.0, 0.02, 0.2], 0.8, Some("Dad")),
+            Speaker::Unsure
+        );
+    }
+
+    #[test]
+    fn test_name_said() {
+        let names = ["Mia", "Leo", "Mary Ann"];
+        assert_eq!(name_said("It's Mia!", &names), Some("Mia"));
+        assert_eq!(name_said("leo", &names), Some("Leo"));
+        assert_eq!(name_said("this is mary ann", &names), Some("Mary Ann"));
+        assert_eq!(name_said("Mary", &names), None);
+        assert_eq!(name_said("Amelia", &names), None);
+    }
+}
@@ -0,0 +1,450 @@
+//! Kitchen timers: "set a 12 minute pasta timer"
+//!
+//! Unlike [reminders](crate::reminders), timers are short and need to go
+//! off on the second. They are stored in `~/.homegpt/timers.db` with their
+//! end time, so they survive restarts of the daemon and the desktop app.
+//! The daemon fires them: spoken on the house speakers (the timer's device,
+//! or all of them) and sent to the notification channels subscribed to the
+//! `timer` event. The desktop app shows the active ones with a countdown.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::announce::Announcer;
+use crate::config::Config;
+use crate::locale;
+use crate::notifications::{Event, Notifier};
+
+/// Timer database (relative to the state dir)
+const DB_FILE: &str = "timers.db";
+
+/// Longest timer; anything longer is a reminder
+const MAX_DURATION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Longest the daemon sleeps between checks, so timers set by another
+/// process are picked up
+const MAX_POLL: Duration = Duration::from_secs(1);
+
+/// Timers fired later than this (the daemon was down) say when they ended
+const LATE_AFTER_SECS: i64 = 60;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timer {
+    pub id: i64,
+    /// What it's for, e.g. "pasta" (may be empty)
+    pub label: String,
+    /// Speaker to announce on (None = all)
+    pub device: Option<String>,
+    pub duration: Duration,
+    pub ends_at: DateTime<Utc>,
+}
+
+impl Timer {
+    /// Time left at `now` (zero once done)
+    pub fn remaining(&self, now: DateTime<Utc>) -> Duration {
+        (self.ends_at - now).to_std().unwrap_or_default()
+    }
+
+    /// "pasta" or "12 minute timer" for unlabeled ones
+    pub fn name(&self) -> String {
+        if self.label.is_empty() {
+            locale::current().format(
+                "timer.unlabeled",
+                &[("duration", &describe_duration(self.duration))],
+            )
+        } else {
+            self.label.clone()
+        }
+    }
+
+    /// "#2 pasta: 11:42 left"
+    pub fn summary(&self, now: DateTime<Utc>) -> String {
+        locale::current().format(
+            "timer.left",
+            &[
+                ("id", &self.id.to_string()),
+                ("name", &self.name()),
+                ("time", &format_countdown(self.remaining(now))),
+            ],
+        )
+    }
+
+    /// What gets spoken and pushed when it goes off
+    pub fn done_message(&self, now: DateTime<Utc>) -> String {
+        let locale = locale::current();
+        let mut message = if self.label.is_empty() {
+            locale.format(
+                "timer.done",
+                &[("duration", &describe_duration(self.duration))],
+            )
+        } else {
+            locale.format("timer.done_labeled", &[("label", &self.label)])
+        };
+        if (now - self.ends_at).num_seconds() > LATE_AFTER_SECS {
+            let ended = locale::home_time(&self.ends_at).format("%H:%M");
+            message.push(' ');
+            message.push_str(&locale.format("timer.finished_at", &[("time", &ended.to_string())]));
+        }
+        message
+    }
+}
+
+/// "11:42" or "1:05:09", rounded up like a kitchen timer
+pub fn format_countdown(remaining: Duration) -> String {
+    let secs = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+/// "12 minute", "1 hour 30 minute" (as in "a 12 minute timer")
+fn describe_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let locale = locale::current();
+    [
+        (secs / 3600, "duration.hour", "duration.hours"),
+        (secs / 60 % 60, "duration.minute", "duration.minutes"),
+        (secs % 60, "duration.second", "duration.seconds"),
+    ]
+    .iter()
+    .filter(|(n, _, _)| *n > 0)
+    .map(|(n, one, many)| {
+        let key = if *n == 1 { one } else { many };
+        locale.format(key, &[("n", &n.to_string())])
+    })
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+/// Parse "12 minutes", "1h30m", "90 seconds", "an hour and a half" or
+/// "2:30" (minutes:seconds)
+pub fn parse_timer_duration(text: &str) -> Result<Duration> {
+    let lowered = text.trim().to_lowercase();
+    let invalid = || {
+        anyhow::anyhow!(
+            "Couldn't understand the duration '{}'. Try e.g. '12 minutes', '1h30m' or '90 seconds'",
+            text
+        )
+    };
+
+    let secs = if lowered.contains(':') {
+        // "2:30" or "1:02:30"
+        let parts: Vec<u64> = lowered
+            .split(':')
+            .map(|p| p.trim().parse().map_err(|_| invalid()))
+            .collect::<Result<_>>()?;
+        match parts.as_slice() {
+            [m, s] if *s < 60 => (m * 60 + s) as f64,
+            [h, m, s] if *m < 60 && *s < 60 => (h * 3600 + m * 60 + s) as f64,
+            _ => return Err(invalid()),
+        }
+    } else {
+        // Split "1h30m" into "1", "h", "30", "m"
+        let mut tokens: Vec<String> = Vec::new();
+        for word in lowered.split(|c: char| c.is_whitespace() || c == ',' || c == '-') {
+            let mut current = String::new();
+            for c in word.chars() {
+                let numeric = c.is_ascii_digit() || c == '.';
+                if !current.is_empty()
+                    && numeric != current.starts_with(|p: char| p.is_ascii_digit() || p == '.')
+                {
+                    tokens.push(std::mem::take(&mut current));
+                }
+                current.push(c);
+            }
+            if !current.is_empty() {
+                tokens.push(current);
+            }
+        }
+
+        let mut total = 0.0;
+        let mut pending: Option<f64> = None;
+        let mut last_unit: Option<f64> = None;
+        // The pending 1 came from "a"/"an"
+        let mut article = false;
+        for token in &tokens {
+            let unit = match token.as_str() {
+                "s" | "sec" | "secs" | "second" | "seconds" => Some(1.0),
+                "m" | "min" | "mins" | "minute" | "minutes" => Some(60.0),
+                "h" | "hr" | "hrs" | "hour" | "hours" => Some(3600.0),
+                _ => None,
+            };
+            if let Some(unit) = unit {
+                total += pending.take().ok_or_else(invalid)? * unit;
+                last_unit = Some(unit);
+                article = false;
+                continue;
+            }
+            match token.as_str() {
+                "and" | "for" | "timer" => {}
+                // "half an hour" keeps the half
+                "a" | "an" => {
+                    if pending.is_none() {
+                        pending = Some(1.0);
+                        article = true;
+                    }
+                }
+                "half" => match (pending, last_unit) {
+                    // "an hour and a half"
+                    (Some(_), Some(unit)) if article => {
+                        total += unit / 2.0;
+                        pending = None;
+                    }
+                    (None, _) => pending = Some(0.5),
+                    _ => return Err(invalid()),
+                },
+                number => {
+                    pending = Some(number.parse().map_err(|_| invalid())?);
+                    article = false;
+                }
+            }
+        }
+        // A bare number is minutes
+        total + pending.map_or(0.0, |n| n * 60.0)
+    };
+
+    if secs < 1.0 {
+        return Err(invalid());
+    }
+    let duration = Duration::from_secs(secs.round() as u64);
+    if duration > MAX_DURATION {
+        anyhow::bail!("Timers can run for up to 24 hours; set a reminder instead");
+    }
+    Ok(duration)
+}
+
+/// Timers in SQLite, shared by the tools, the daemon and the desktop app
+pub struct TimerStore {
+    conn: Mutex<Connection>,
+}
+
+impl TimerStore {
+    /// Open (or create) the store in `state_dir`
+    pub fn open(state_dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(state_dir)?;
+        let conn = Connection::open(state_dir.join(DB_FILE))?;
+        Self::with_connection(conn)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self> {
+        conn.busy_timeout(Duration::from_secs(5))?;
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS timers (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                label TEXT NOT NULL,
+                device TEXT,
+                duration_secs INTEGER NOT NULL,
+                ends_at_ms INTEGER NOT NULL
+            );
+            "#,
+        )?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Start a timer running from `now`
+    pub fn add(
+        &self,
+        label: &str,
+        duration: Duration,
+        device: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<Timer> {
+        // Stored to the millisecond
+        let ends_at = now + chrono::Duration::from_std(duration)?;
+        let ends_at =
+            DateTime::from_timestamp_millis(ends_at.timestamp_millis()).unwrap_or(ends_at);
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO timers (label, device, duration_secs, ends_at_ms) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                label,
+                device,
+                duration.as_secs() as i64,
+                ends_at.timestamp_millis()
+            ],
+        )?;
+        Ok(Timer {
+            id: conn.last_insert_rowid(),
+            label: label.to_string(),
+            device: device.map(String::from),
+            duration,
+            ends_at,
+        })
+    }
+
+    /// Every timer, soonest to end first
+    pub fn list(&self) -> Result<Vec<Timer>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, label, device, duration_secs, ends_at_ms FROM timers ORDER BY ends_at_ms, id",
+        )?;
+        let timers = stmt
+            .query_map([], |row| {
+                Ok(Timer {
+                    id: row.get(0)?,
+                    label: row.get(1)?,
+                    device: row.get(2)?,
+                    duration: Duration::from_secs(row.get::<_, i64>(3)?.max(0) as u64),
+                    ends_at: DateTime::from_timestamp_millis(row.get(4)?).unwrap_or_default(),
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(timers)
+    }
+
+    /// Stop the timer with this id or label (case-insensitive), returning it
+    pub fn cancel(&self, query: &str) -> Result<Timer> {
+        let query = query.trim().trim_start_matches('#');
+        let timers = self.list()?;
+        let matches: Vec<&Timer> = timers
+            .iter()
+            .filter(|t| t.id.to_string() == query || t.label.eq_ignore_ascii_case(query))
+            .collect();
+        let timer = match matches.as_slice() {
+            [] => anyhow::bail!("No timer '{}'", query),
+            [timer] => (*timer).clone(),
+            _ => anyhow::bail!("More than one {} timer; cancel it by id", query),
+        };
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM timers WHERE id = ?1", [timer.id])?;
+        Ok(timer)
+    }
+
+    /// Remove and return the timers that have ended by `now`
+    pub fn take_finished(&self, now: DateTime<Utc>) -> Result<Vec<Timer>> {
+        let finished: Vec<Timer> = self
+            .list()?
+            .into_iter()
+            .filter(|t| t.ends_at <= now)
+            .collect();
+        let conn = self.conn.lock().unwrap();
+        for timer in &finished {
+            conn.execute("DELETE FROM timers WHERE id = ?1", [timer.id])?;
+        }
+        Ok(finished)
+    }
+
+    /// When the next timer ends
+    pub fn next_end(&self) -> Result<Option<DateTime<Utc>>> {
+        let conn = self.conn.lock().unwrap();
+        let ms: Option<i64> = conn
+            .query_row("SELECT MIN(ends_at_ms) FROM timers", [], |row| row.get(0))
+            .optional()?
+            .flatten();
+        Ok(ms.and_then(DateTime::from_timestamp_millis))
+    }
+}
+
+/// Fire timers as they end, until the task is aborted (run by the daemon)
+pub async fn run_timers(config: &Config, state_dir: &Path) -> Result<()> {
+    let store = TimerStore::open(state_dir)?;
+    let announcer = Announcer::from_config(config, state_dir)?;
+    let notifier = Notifier::from_config(config)?;
+    if announcer.is_none() && notifier.is_none() {
+        warn!("Timers: no announce devices or notification channels to fire them on");
+    }
+
+    loop {
+        let now = Utc::now();
+        for timer in store.take_finished(now)? {
+            let message = timer.done_message(now);
+            info!("Timer #{} done: {}", timer.id, timer.name());
+            if let Some(ref announcer) = announcer {
+                let time = locale::home_time(&now).naive_local();
+                match announcer
+                    .announce(&message, timer.device.as_deref(), Event::Timer, time)
+                    .await
+                {
+                    Ok(announcement) => info!("Timer #{}: {}", timer.id, announcement.summary()),
+                    Err(e) => warn!("Timer #{} announcement failed: {}", timer.id, e),
+                }
+            }
+            if let Some(ref notifier) = notifier {
+                notifier.notify(Event::Timer, &message).await;
+            }
+        }
+
+        if let Some(ref notifier) = notifier {
+            notifier.send_held().await;
+        }
+
+        // Wake right when the next timer ends
+        let sleep = match store.next_end()? {
+            Some(end) => (end - Utc::now())
+                .to_std()
+                .unwrap_or_default()
+                .min(MAX_POLL),
+            None => MAX_POLL,
+        };
+        tokio::time::sleep(sleep).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timer_duration() {
+        let secs = |s: &str| parse_timer_duration(s).unwrap().as_secs();
+        assert_eq!(secs("12 minutes"), 720);
+        assert_eq!(secs("12-minute"), 720);
+        assert_eq!(secs("1h30m"), 5400);
+        assert_eq!(secs("1 hour, 15 mins"), 4500);
+        assert_eq!(secs("an hour and a half"), 5400);
+        assert_eq!(secs("half an hour"), 1800);
+        assert_eq!(secs("90 seconds"), 90);
+        assert_eq!(secs("2:30"), 150);
+        assert_eq!(secs("1.5 min"), 90);
+        assert_eq!(secs("7"), 420);
+        assert!(parse_timer_duration("until the pasta is done").is_err());
+        assert!(parse_timer_duration("0 minutes").is_err());
+        assert!(parse_timer_duration("3 days").is_err());
+        assert!(parse_timer_duration("25 hours").is_err());
+    }
+
+    #[test]
+    fn test_store_and_fire() {
+        let store = TimerStore::with_connection(Connection::open_in_memory().unwrap()).unwrap();
+        let start = Utc::now();
+        let pasta = store
+            .add("pasta", Duration::from_secs(720), Some("kitchen"), start)
+            .unwrap();
+        let eggs = store.add("", Duration::from_secs(90), None, start).unwrap();
+        assert_eq!(store.list().unwrap(), vec![eggs.clone(), pasta.clone()]);
+        assert_eq!(store.next_end().unwrap(), Some(eggs.ends_at));
+
+        let later = start + chrono::Duration::seconds(100);
+        assert_eq!(
+            pasta.summary(later),
+            format!("#{} pasta: 10:20 left", pasta.id)
+        );
+        assert_eq!(store.take_finished(later).unwrap(), vec![eggs.clone()]);
+        assert_eq!(
+            eggs.done_message(later),
+            "Your 1 minute 30 second timer is done."
+        );
+        assert!(store.take_finished(later).unwrap().is_empty());
+
+        assert!(store.cancel("soup").is_err());
+        assert_eq!(store.cancel("Pasta").unwrap(), pasta);
+        assert!(store.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_format_countdown() {
+        assert_eq!(format_countdown(Duration::from_secs(702)), "11:42");
+        assert_eq!(format_countdown(Duration::from_secs(3909)), "1:05:09");
+        assert_eq!(format_countdown(Duration::ZERO), "0:00");
+    }
+}
@@ -0,0 +1,547 @@
+//! Weather forecasts for the agent and the heartbeat
+//!
+//! Forecasts come from Open-Meteo for `weather.location` and are cached in
+//! the state directory for `weather.cache_ttl`, so the `weather` tool and
+//! the heartbeat share requests. The first heartbeat after
+//! `weather.digest_time` each day gets today's forecast with highlights
+//! ("rain from 15:00 to 18:00") to pass on to the household.
+
+mod open_meteo;
+
+pub use open_meteo::FORECAST_DAYS;
+
+use anyhow::Result;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+use crate::config::{parse_duration, parse_time, Config, WeatherConfig};
+use crate::locale;
+use open_meteo::OpenMeteo;
+
+/// State file with the cached forecast (relative to the state dir)
+const STATE_FILE: &str = "weather.json";
+
+/// Precipitation probability (%) worth mentioning
+const WET_PROBABILITY: f64 = 50.0;
+
+/// Hours of the day considered for highlights (nobody needs the 3am rain)
+const DAYTIME: std::ops::RangeInclusive<u32> = 6..=21;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Units {
+    Metric,
+    Imperial,
+}
+
+impl Units {
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "metric" => Ok(Self::Metric),
+            "imperial" => Ok(Self::Imperial),
+            other => anyhow::bail!(
+                "Unknown weather.units '{}': expected metric or imperial",
+                other
+            ),
+        }
+    }
+
+    fn temperature(self) -> &'static str {
+        match self {
+            Self::Metric => "°C",
+            Self::Imperial => "°F",
+        }
+    }
+
+    fn precipitation(self) -> &'static str {
+        match self {
+            Self::Metric => "mm",
+            Self::Imperial => "in",
+        }
+    }
+
+    fn wind(self) -> &'static str {
+        match self {
+            Self::Metric => "km/h",
+            Self::Imperial => "mph",
+        }
+    }
+
+    /// (frost, heat, strong wind) thresholds
+    fn thresholds(self) -> (f64, f64, f64) {
+        match self {
+            Self::Metric => (0.0, 30.0, 40.0),
+            Self::Imperial => (32.0, 86.0, 25.0),
+        }
+    }
+}
+
+/// A place to forecast
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Place {
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hour {
+    /// Local time at the place
+    pub time: NaiveDateTime,
+    pub temperature: f64,
+    /// 0-100
+    pub precipitation_probability: f64,
+    pub precipitation: f64,
+    /// WMO weather code
+    pub weather_code: u8,
+    pub wind_speed: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Day {
+    pub date: NaiveDate,
+    pub temp_max: f64,
+    pub temp_min: f64,
+    pub precipitation: f64,
+    pub precipitation_probability: f64,
+    pub weather_code: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Forecast {
+    pub location: String,
+    pub units: Units,
+    /// Current conditions
+    pub temperature: Option<f64>,
+    pub weather_code: Option<u8>,
+    pub wind_speed: Option<f64>,
+    pub hours: Vec<Hour>,
+    pub days: Vec<Day>,
+}
+
+impl Forecast {
+    /// One line for a day, e.g. "Sun 18 Oct: 8 to 14°C, light rain, 4.2 mm (80%)"
+    pub fn day_summary(&self, day: &Day) -> String {
+        let mut line = format!(
+            "{}: {:.0} to {:.0}{}, {}",
+            locale::current().short_date(day.date),
+            day.temp_min,
+            day.temp_max,
+            self.units.temperature(),
+            describe(day.weather_code)
+        );
+        if day.precipitation > 0.0 {
+            line.push_str(&format!(
+                ", {:.1} {} ({:.0}%)",
+                day.precipitation,
+                self.units.precipitation(),
+                day.precipitation_probability
+            ));
+        }
+        line
+    }
+
+    /// Things worth acting on for a day: when rain/snow starts and stops,
+    /// frost, heat and strong wind
+    pub fn highlights(&self, date: NaiveDate) -> Vec<String> {
+        let mut highlights = Vec::new();
+        let hours: Vec<&Hour> = self
+            .hours
+            .iter()
+            .filter(|h| h.time.date() == date && DAYTIME.contains(&h.time.hour()))
+            .collect();
+
+        // First stretch of likely precipitation
+        if let Some(start) = hours
+            .iter()
+            .position(|h| h.precipitation_probability >= WET_PROBABILITY)
+        {
+            let wet: Vec<&Hour> = hours[start..]
+                .iter()
+                .take_while(|h| h.precipitation_probability >= WET_PROBABILITY)
+                .copied()
+                .collect();
+            let kind = wet
+                .iter()
+                .map(|h| precipitation_kind(h.weather_code))
+                .max_by_key(|(rank, _)| *rank)
+                .map(|(_, kind)| kind)
+                .unwrap_or("rain");
+            let peak = wet
+                .iter()
+                .map(|h| h.precipitation_probability)
+                .fold(0.0, f64::max);
+            let last = wet[wet.len() - 1].time.hour();
+            let span = if last + 1 > *DAYTIME.end() {
+                format!("from {}", wet[0].time.format("%H:%M"))
+            } else {
+                format!("from {} to {:02}:00", wet[0].time.format("%H:%M"), last + 1)
+            };
+            highlights.push(format!("{} {} ({:.0}%)", kind, span, peak));
+        }
+
+        let (frost, heat, wind) = self.units.thresholds();
+        let unit = self.units.temperature();
+        if let Some(day) = self.days.iter().find(|d| d.date == date) {
+            if day.temp_min <= frost {
+                highlights.push(format!("frost (low {:.0}{})", day.temp_min, unit));
+            }
+            if day.temp_max >= heat {
+                highlights.push(format!("hot (high {:.0}{})", day.temp_max, unit));
+            }
+        }
+        if let Some(windiest) = hours
+            .iter()
+            .filter(|h| h.wind_speed >= wind)
+            .max_by(|a, b| a.wind_speed.total_cmp(&b.wind_speed))
+        {
+            highlights.push(format!(
+                "windy, up to {:.0} {} around {}",
+                windiest.wind_speed,
+                self.units.wind(),
+                windiest.time.format("%H:%M")
+            ));
+        }
+        highlights
+    }
+
+    /// Tool output: current conditions and `days` days of forecast
+    pub fn to_text(&self, days: usize) -> String {
+        let mut lines = vec![format!("Weather for {}", self.location)];
+        if let (Some(temperature), Some(code)) = (self.temperature, self.weather_code) {
+            let mut now = format!(
+                "Now: {:.0}{}, {}",
+                temperature,
+                self.units.temperature(),
+                describe(code)
+            );
+            if let Some(wind) = self.wind_speed {
+                now.push_str(&format!(", wind {:.0} {}", wind, self.units.wind()));
+            }
+            lines.push(now);
+        }
+        for day in self.days.iter().take(days) {
+            lines.push(String::new());
+            lines.push(self.day_summary(day));
+            for highlight in self.highlights(day.date) {
+                lines.push(format!("- {}", highlight));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// Precipitation type of a WMO code, ranked so storms win over rain
+fn precipitation_kind(code: u8) -> (u8, &'static str) {
+    match code {
+        95..=99 => (3, "thunderstorms"),
+        71..=77 | 85 | 86 => (2, "snow"),
+        56 | 57 | 66 | 67 => (2, "freezing rain"),
+        _ => (1, "rain"),
+    }
+}
+
+/// Text for a WMO weather code
+pub fn describe(code: u8) -> &'static str {
+    match code {
+        0 => "clear",
+        1 => "mostly clear",
+        2 => "partly cloudy",
+        3 => "overcast",
+        45 | 48 => "fog",
+        51 | 53 | 55 => "drizzle",
+        56 | 57 => "freezing drizzle",
+        61 => "light rain",
+        63 => "rain",
+        65 => "heavy rain",
+        66 | 67 => "freezing rain",
+        71 => "light snow",
+        73 => "snow",
+        75 => "heavy snow",
+        77 => "snow grains",
+        80 | 81 => "rain showers",
+        82 => "heavy rain showers",
+        85 | 86 => "snow showers",
+        95 => "thunderstorms",
+        96 | 99 => "thunderstorms with hail",
+        _ => "unknown conditions",
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedForecast {
+    fetched_at: DateTime<Utc>,
+    place: Place,
+    forecast: Forecast,
+}
+
+/// Saved between runs in `~/.homegpt/weather.json`
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct WeatherState {
+    /// `weather.location` and the place it was geocoded to
+    #[serde(default)]
+    geocoded: Option<(String, Place)>,
+    #[serde(default)]
+    cached: Option<CachedForecast>,
+    /// Local date of the last heartbeat digest
+    #[serde(default)]
+    last_digest: Option<NaiveDate>,
+}
+
+impl WeatherState {
+    fn fresh_forecast(
+        &self,
+        place: &Place,
+        units: Units,
+        now: DateTime<Utc>,
+        ttl: chrono::Duration,
+    ) -> Option<&Forecast> {
+        let cached = self.cached.as_ref()?;
+        let fresh = cached.place == *place
+            && cached.forecast.units == units
+            && now - cached.fetched_at < ttl;
+        fresh.then_some(&cached.forecast)
+    }
+}
+
+/// Forecasts for the configured location, cached
+pub struct WeatherService {
+    location: String,
+    coordinates: Option<(f64, f64)>,
+    units: Units,
+    cache_ttl: chrono::Duration,
+    digest_time: Option<NaiveTime>,
+    state_path: PathBuf,
+    api: OpenMeteo,
+}
+
+impl WeatherService {
+    /// Service for the `weather` config, or `None` if no location is set
+    pub fn from_config(config: &Config, state_dir: &Path) -> Result<Option<Self>> {
+        let weather: &WeatherConfig = &config.weather;
+        let coordinates = weather.latitude.zip(weather.longitude);
+        if weather.location.trim().is_empty() && coordinates.is_none() {
+            return Ok(None);
+        }
+
+        let cache_ttl = parse_duration(&weather.cache_ttl)
+            .map_err(|e| anyhow::anyhow!("Invalid weather.cache_ttl: {}", e))?;
+        let digest_time = match weather.digest_time.as_str() {
+            "" => None,
+            time => {
+                let (hour, minute) = parse_time(time)
+                    .map_err(|e| anyhow::anyhow!("Invalid weather.digest_time: {}", e))?;
+                NaiveTime::from_hms_opt(hour as u32, minute as u32, 0)
+            }
+        };
+
+        Ok(Some(Self {
+            location: weather.location.trim().to_string(),
+            coordinates,
+            units: Units::parse(&weather.units)?,
+            cache_ttl: chrono::Duration::from_std(cache_ttl)?,
+            digest_time,
+            state_path: state_dir.join(STATE_FILE),
+            api: OpenMeteo::new(),
+        }))
+    }
+
+    fn load_state(&self) -> WeatherState {
+        fs::read_to_string(&self.state_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save_state(&self, state: &WeatherState) -> Result<()> {
+        if let Some(parent) = self.state_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.state_path, serde_json::to_string(state)?)?;
+        Ok(())
+    }
+
+    /// Configured coordinates, or the geocoded location (looked up once)
+    async fn place(&self, state: &mut WeatherState) -> Result<Place> {
+        if let Some((latitude, longitude)) = self.coordinates {
+            let name = if self.location.is_empty() {
+                format!("{:.2}, {:.2}", latitude, longitude)
+            } else {
+                self.location.clone()
+            };
+            return Ok(Place {
+                name,
+                latitude,
+                longitude,
+            });
+        }
+
+        if let Some((ref location, ref place)) = state.geocoded {
+            if *location == self.location {
+                return Ok(place.clone());
+            }
+        }
+        let place = self.api.geocode(&self.location).await?;
+        debug!(
+            "Geocoded {} to {} ({}, {})",
+            self.location, place.name, place.latitude, place.longitude
+        );
+        state.geocoded = Some((self.location.clone(), place.clone()));
+        Ok(place)
+    }
+
+    async fn load_forecast(&self, state: &mut WeatherState) -> Result<Forecast> {
+        let place = self.place(state).await?;
+        let now = Utc::now();
+        if let Some(forecast) = state.fresh_forecast(&place, self.units, now, self.cache_ttl) {
+            debug!("Using cached forecast for {}", place.name);
+            return Ok(forecast.clone());
+        }
+
+        let forecast = self.api.forecast(&place, self.units).await?;
+        state.cached = Some(CachedForecast {
+            fetched_at: now,
+            place,
+            forecast: forecast.clone(),
+        });
+        Ok(forecast)
+    }
+
+    /// Forecast for the configured location, from the cache when fresh
+    pub async fn forecast(&self) -> Result<Forecast> {
+        let mut state = self.load_state();
+        let forecast = self.load_forecast(&mut state).await?;
+        self.save_state(&state)?;
+        Ok(forecast)
+    }
+
+    /// Today's forecast and highlights, once a day after `digest_time`
+    pub async fn daily_digest(&self, now: DateTime<FixedOffset>) -> Result<Option<String>> {
+        let Some(digest_time) = self.digest_time else {
+            return Ok(None);
+        };
+        let today = now.date_naive();
+        let mut state = self.load_state();
+        if now.time() < digest_time || state.last_digest == Some(today) {
+            return Ok(None);
+        }
+
+        let forecast = self.load_forecast(&mut state).await?;
+        state.last_digest = Some(today);
+        self.save_state(&state)?;
+
+        let Some(day) = forecast.days.iter().find(|d| d.date == today) else {
+            return Ok(None);
+        };
+        let mut digest = format!("{} — {}", forecast.location, forecast.day_summary(day));
+        let highlights = forecast.highlights(today);
+        if !highlights.is_empty() {
+            digest.push_str(&format!("; {}", highlights.join("; ")));
+        }
+        Ok(Some(digest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hour(time: &str, probability: f64, code: u8, wind: f64) -> Hour {
+        Hour {
+            time: NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M").unwrap(),
+            temperature: 12.0,
+            precipitation_probability: probability,
+            precipitation: if probability > 0.0 { 0.5 } else { 0.0 },
+            weather_code: code,
+            wind_speed: wind,
+        }
+    }
+
+    fn forecast() -> Forecast {
+        Forecast {
+            location: "Home".to_string(),
+            units: Units::Metric,
+            temperature: Some(11.0),
+            weather_code: Some(3),
+            wind_speed: Some(8.0),
+            hours: vec![
+                hour("2026-10-18T03:00", 90.0, 63, 5.0),
+                hour("2026-10-18T14:00", 20.0, 3, 10.0),
+                hour("2026-10-18T15:00", 70.0, 61, 20.0),
+                hour("2026-10-18T16:00", 85.0, 95, 45.0),
+                hour("2026-10-18T17:00", 30.0, 3, 15.0),
+            ],
+            days: vec![Day {
+                date: NaiveDate::from_ymd_opt(2026, 10, 18).unwrap(),
+                temp_max: 14.0,
+                temp_min: -1.0,
+                precipitation: 4.2,
+                precipitation_probability: 85.0,
+                weather_code: 95,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_highlights() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 18).unwrap();
+        assert_eq!(
+            forecast().highlights(date),
+            vec![
+                "thunderstorms from 15:00 to 17:00 (85%)",
+                "frost (low -1°C)",
+                "windy, up to 45 km/h around 16:00",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_to_text() {
+        let text = forecast().to_text(1);
+        assert!(text.starts_with("Weather for Home\nNow: 11°C, overcast, wind 8 km/h"));
+        assert!(text.contains("Sun 18 Oct: -1 to 14°C, thunderstorms, 4.2 mm (85%)"));
+    }
+
+    #[test]
+    fn test_cached_forecast_freshness() {
+        let place = Place {
+            name: "Home".to_string(),
+            latitude: 45.5,
+            longitude: -122.7,
+        };
+        let fetched_at = Utc::now();
+        let state = WeatherState {
+            cached: Some(CachedForecast {
+                fetched_at,
+                place: place.clone(),
+                forecast: forecast(),
+            }),
+            ..Default::default()
+        };
+        let ttl = chrono::Duration::minutes(30);
+
+        let later = fetched_at + chrono::Duration::minutes(10);
+        assert!(state
+            .fresh_forecast(&place, Units::Metric, later, ttl)
+            .is_some());
+        assert!(state
+            .fresh_forecast(&place, Units::Imperial, later, ttl)
+            .is_none());
+
+        let expired = fetched_at + chrono::Duration::minutes(31);
+        assert!(state
+            .fresh_forecast(&place, Units::Metric, expired, ttl)
+            .is_none());
+
+        let moved = Place {
+            latitude: 40.0,
+            ..place
+        };
+        assert!(state
+            .fresh_forecast(&moved, Units::Metric, later, ttl)
+            .is_none());
+    }
+}
@@ -0,0 +1,268 @@
+//! Open-Meteo forecast and geocoding APIs (free, no key)
+
+use anyhow::Result;
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::Deserialize;
+
+use super::{Day, Forecast, Hour, Place, Units};
+
+const FORECAST_URL: &str = "https://api.open-meteo.com/v1/forecast";
+const GEOCODING_URL: &str = "https://geocoding-api.open-meteo.com/v1/search";
+
+/// Days of forecast requested (today included)
+pub const FORECAST_DAYS: usize = 3;
+
+#[derive(Debug, Deserialize)]
+struct ForecastResponse {
+    #[serde(default)]
+    current: Option<CurrentBlock>,
+    hourly: HourlyBlock,
+    daily: DailyBlock,
+}
+
+#[derive(Debug, Deserialize)]
+struct CurrentBlock {
+    temperature_2m: Option<f64>,
+    weather_code: Option<u8>,
+    wind_speed_10m: Option<f64>,
+}
+
+// Open-Meteo returns parallel arrays with nulls for missing values
+#[derive(Debug, Deserialize)]
+struct HourlyBlock {
+    time: Vec<String>,
+    temperature_2m: Vec<Option<f64>>,
+    precipitation_probability: Vec<Option<f64>>,
+    precipitation: Vec<Option<f64>>,
+    weather_code: Vec<Option<u8>>,
+    wind_speed_10m: Vec<Option<f64>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DailyBlock {
+    time: Vec<String>,
+    weather_code: Vec<Option<u8>>,
+    temperature_2m_max: Vec<Option<f64>>,
+    temperature_2m_min: Vec<Option<f64>>,
+    precipitation_sum: Vec<Option<f64>>,
+    precipitation_probability_max: Vec<Option<f64>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeocodingResponse {
+    #[serde(default)]
+    results: Vec<GeocodingResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeocodingResult {
+    name: String,
+    latitude: f64,
+    longitude: f64,
+    #[serde(default)]
+    admin1: Option<String>,
+    #[serde(default)]
+    country: Option<String>,
+    #[serde(default)]
+    country_code: Option<String>,
+}
+
+pub struct OpenMeteo {
+    client: reqwest::Client,
+}
+
+impl OpenMeteo {
+    pub fn new() -> Self {
+        Self {
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Forecast for a place, with times in the place's own timezone
+    pub async fn forecast(&self, place: &Place, units: Units) -> Result<Forecast> {
+        let mut query = vec![
+            ("latitude", place.latitude.to_string()),
+            ("longitude", place.longitude.to_string()),
+            (
+                "current",
+                "temperature_2m,weather_code,wind_speed_10m".to_string(),
+            ),
+            (
+                "hourly",
+                "temperature_2m,precipitation_probability,precipitation,weather_code,wind_speed_10m"
+                    .to_string(),
+            ),
+            (
+                "daily",
+                "weather_code,temperature_2m_max,temperature_2m_min,precipitation_sum,precipitation_probability_max"
+                    .to_string(),
+            ),
+            ("timezone", "auto".to_string()),
+            ("forecast_days", FORECAST_DAYS.to_string()),
+        ];
+        if units == Units::Imperial {
+            query.push(("temperature_unit", "fahrenheit".to_string()));
+            query.push(("wind_speed_unit", "mph".to_string()));
+            query.push(("precipitation_unit", "inch".to_string()));
+        }
+
+        let response = self.client.get(FORECAST_URL).query(&query).send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            anyhow::bail!("Open-Meteo forecast: {}", status);
+        }
+        let body: ForecastResponse = response.json().await?;
+        parse_forecast(body, &place.name, units)
+    }
+
+    /// Coordinates for a place name like "Portland, OR". Open-Meteo only
+    /// searches the name itself, so the part after the first comma is used
+    /// to choose between matches (region, country or country code).
+    pub async fn geocode(&self, location: &str) -> Result<Place> {
+        let (name, qualifier) = match location.split_once(',') {
+            Some((name, rest)) => (name.trim(), rest.trim().to_lowercase()),
+            None => (location.trim(), String::new()),
+        };
+
+        let response = self
+            .client
+            .get(GEOCODING_URL)
+            .query(&[("name", name), ("count", "10"), ("format", "json")])
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            anyhow::bail!("Open-Meteo geocoding: {}", status);
+        }
+        let body: GeocodingResponse = response.json().await?;
+        pick_place(body.results, &qualifier)
+            .ok_or_else(|| anyhow::anyhow!("Location not found: {}", location))
+    }
+}
+
+fn pick_place(results: Vec<GeocodingResult>, qualifier: &str) -> Option<Place> {
+    let matches = |r: &GeocodingResult| {
+        [&r.admin1, &r.country, &r.country_code]
+            .into_iter()
+            .flatten()
+            .any(|field| field.to_lowercase().starts_with(qualifier))
+    };
+    let index = results.iter().position(matches).unwrap_or(0);
+    let result = results.into_iter().nth(index)?;
+
+    let name = match result.admin1.as_deref().or(result.country.as_deref()) {
+        Some(region) => format!("{}, {}", result.name, region),
+        None => result.name,
+    };
+    Some(Place {
+        name,
+        latitude: result.latitude,
+        longitude: result.longitude,
+    })
+}
+
+fn parse_forecast(body: ForecastResponse, location: &str, units: Units) -> Result<Forecast> {
+    let value = |values: &[Option<f64>], i: usize| values.get(i).copied().flatten();
+
+    let hourly = &body.hourly;
+    let mut hours = Vec::with_capacity(hourly.time.len());
+    for (i, time) in hourly.time.iter().enumerate() {
+        let time = NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M")?;
+        let Some(temperature) = value(&hourly.temperature_2m, i) else {
+            continue;
+        };
+        hours.push(Hour {
+            time,
+            temperature,
+            precipitation_probability: value(&hourly.precipitation_probability, i).unwrap_or(0.0),
+            precipitation: value(&hourly.precipitation, i).unwrap_or(0.0),
+            weather_code: hourly.weather_code.get(i).copied().flatten().unwrap_or(0),
+            wind_speed: value(&hourly.wind_speed_10m, i).unwrap_or(0.0),
+        });
+    }
+
+    let daily = &body.daily;
+    let mut days = Vec::with_capacity(daily.time.len());
+    for (i, date) in daily.time.iter().enumerate() {
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")?;
+        let (Some(temp_max), Some(temp_min)) = (
+            value(&daily.temperature_2m_max, i),
+            value(&daily.temperature_2m_min, i),
+        ) else {
+            continue;
+        };
+        days.push(Day {
+            date,
+            temp_max,
+            temp_min,
+            precipitation: value(&daily.precipitation_sum, i).unwrap_or(0.0),
+            precipitation_probability: value(&daily.precipitation_probability_max, i)
+                .unwrap_or(0.0),
+            weather_code: daily.weather_code.get(i).copied().flatten().unwrap_or(0),
+        });
+    }
+
+    let current = body.current.as_ref();
+    Ok(Forecast {
+        location: location.to_string(),
+        units,
+        temperature: current.and_then(|c| c.temperature_2m),
+        weather_code: current.and_then(|c| c.weather_code),
+        wind_speed: current.and_then(|c| c.wind_speed_10m),
+        hours,
+        days,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_forecast_skips_missing_values() {
+        let body: ForecastResponse = serde_json::from_value(json!({
+            "current": {"temperature_2m": 12.5, "weather_code": 61, "wind_speed_10m": 9.0},
+            "hourly": {
+                "time": ["2026-10-18T14:00", "2026-10-18T15:00"],
+                "temperature_2m": [13.0, null],
+                "precipitation_probability": [40, 80],
+                "precipitation": [0.0, 1.2],
+                "weather_code": [3, 61],
+                "wind_speed_10m": [10.0, 12.0]
+            },
+            "daily": {
+                "time": ["2026-10-18"],
+                "weather_code": [61],
+                "temperature_2m_max": [14.0],
+                "temperature_2m_min": [8.0],
+                "precipitation_sum": [4.2],
+                "precipitation_probability_max": [80]
+            }
+        }))
+        .unwrap();
+
+        let forecast = parse_forecast(body, "Home", Units::Metric).unwrap();
+        assert_eq!(forecast.temperature, Some(12.5));
+        assert_eq!(forecast.hours.len(), 1);
+        assert_eq!(forecast.hours[0].precipitation_probability, 40.0);
+        assert_eq!(forecast.days[0].precipitation, 4.2);
+    }
+
+    #[test]
+    fn test_pick_place_uses_qualifier() {
+        let result = |admin1: &str, code: &str| GeocodingResult {
+            name: "Portland".to_string(),
+            latitude: 0.0,
+            longitude: 0.0,
+            admin1: Some(admin1.to_string()),
+            country: Some("United States".to_string()),
+            country_code: Some(code.to_string()),
+        };
+        let results = vec![result("Maine", "US"), result("Oregon", "US")];
+
+        let place = pick_place(results, "or").unwrap();
+        assert_eq!(place.name, "Portland, Oregon");
+        assert!(pick_place(Vec::new(), "").is_none());
+    }
+}
@@ -0,0 +1,179 @@
+//! Webhooks: inbound triggers and outbound event notifications
+//!
+//! Inbound hooks (`[[webhooks.inbound]]`) are served by the HTTP server at
+//! `POST /hook/<name>` and run a prompt or a heartbeat. Outbound hooks
+//! (`[[webhooks.outbound]]`) are a notification channel: they are POSTed
+//! the events they subscribe to, so IFTTT or Node-RED can pick them up.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::json;
+use std::time::Duration;
+
+use crate::config::{Config, InboundWebhookConfig, OutboundWebhookConfig};
+use crate::notifications::{Channel, Event};
+
+/// Replaced with the request body in inbound hook prompts
+const PAYLOAD_PLACEHOLDER: &str = "{{payload}}";
+
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An outbound webhook, sent events like any other notification channel
+pub struct WebhookChannel {
+    url: String,
+    /// Send the bare message instead of JSON
+    text: bool,
+    client: reqwest::Client,
+}
+
+impl WebhookChannel {
+    pub fn new(config: &OutboundWebhookConfig) -> Result<Self> {
+        let text = match config.format.as_str() {
+            "json" => false,
+            "text" => true,
+            other => anyhow::bail!(
+                "Unknown webhook format '{}' for {}: expected json or text",
+                other,
+                config.url
+            ),
+        };
+        Ok(Self {
+            url: config.url.clone(),
+            text,
+            client: reqwest::Client::builder().timeout(SEND_TIMEOUT).build()?,
+        })
+    }
+}
+
+#[async_trait]
+impl Channel for WebhookChannel {
+    fn name(&self) -> &str {
+        &self.url
+    }
+
+    async fn send(&self, event: Event, message: &str) -> Result<()> {
+        let request = self
+            .client
+            .post(&self.url)
+            .header("X-HomeGPT-Event", event.as_str());
+        let request = if self.text {
+            request.body(message.to_string())
+        } else {
+            request.json(&json!({
+                "event": event.as_str(),
+                "message": message,
+                "timestamp": chrono::Utc::now().to_rfc3339(),
+            }))
+        };
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            anyhow::bail!("Webhook {}: {}", self.url, response.status());
+        }
+        Ok(())
+    }
+}
+
+/// The inbound hook called `name`
+pub fn find_inbound<'a>(config: &'a Config, name: &str) -> Option<&'a InboundWebhookConfig> {
+    config
+        .webhooks
+        .inbound
+        .iter()
+        .find(|hook| hook.name == name)
+}
+
+/// Whether the caller supplied the hook's secret (always true without one)
+pub fn is_authorized(hook: &InboundWebhookConfig, provided: Option<&str>) -> bool {
+    match hook.secret.as_deref() {
+        None | Some("") => true,
+        Some(secret) => provided == Some(secret),
+    }
+}
+
+/// The hook's prompt with the request body filled in. Without a
+/// `{{payload}}` placeholder a non-empty body is appended; without a prompt
+/// the body is the prompt.
+pub fn render_prompt(template: &str, payload: &str) -> String {
+    let payload = payload.trim();
+    if template.contains(PAYLOAD_PLACEHOLDER) {
+        template.replace(PAYLOAD_PLACEHOLDER, payload)
+    } else if template.trim().is_empty() {
+        payload.to_string()
+    } else if payload.is_empty() {
+        template.to_string()
+    } else {
+        format!("{}\n\nPayload:\n{}", template, payload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifications::Notifier;
+
+    #[test]
+    fn test_render_prompt() {
+        assert_eq!(
+            render_prompt("Package delivered: {{payload}}", " front porch\n"),
+            "Package delivered: front porch"
+        );
+        assert_eq!(
+            render_prompt("The washer finished.", ""),
+            "The washer finished."
+        );
+        assert_eq!(
+            render_prompt("The washer finished.", "{\"cycle\": \"wool\"}"),
+            "The washer finished.\n\nPayload:\n{\"cycle\": \"wool\"}"
+        );
+        assert_eq!(
+            render_prompt("", "Remind me to water the plants"),
+            "Remind me to water the plants"
+        );
+    }
+
+    #[test]
+    fn test_is_authorized() {
+        let mut hook = InboundWebhookConfig {
+            name: "doorbell".to_string(),
+            prompt: "Someone is at the door.".to_string(),
+            heartbeat: false,
+            secret: None,
+        };
+        assert!(is_authorized(&hook, None));
+
+        hook.secret = Some("s3cret".to_string());
+        assert!(is_authorized(&hook, Some("s3cret")));
+        assert!(!is_authorized(&hook, Some("guess")));
+        assert!(!is_authorized(&hook, None));
+    }
+
+    #[test]
+    fn test_outbound_events() {
+        let mut config = Config::default();
+        config.webhooks.outbound.push(OutboundWebhookConfig {
+            url: "https://ntfy.sh/family".to_string(),
+            events: vec!["alert".to_string(), "heartbeat_failed".to_string()],
+            format: "text".to_string(),
+        });
+        let notifier = Notifier::from_config(&config).unwrap().unwrap();
+        assert!(notifier.wants(Event::Alert));
+        assert!(notifier.wants(Event::Emergency));
+        assert!(notifier.wants(Event::HeartbeatFailed));
+        assert!(!notifier.wants(Event::Digest));
+
+        config.webhooks.outbound[0].events = vec!["digest_ready".to_string()];
+        assert!(Notifier::from_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_outbound_format() {
+        let mut config = OutboundWebhookConfig {
+            url: "https://ntfy.sh/family".to_string(),
+            events: Vec::new(),
+            format: "text".to_string(),
+        };
+        assert!(WebhookChannel::new(&config).unwrap().text);
+        config.format = "xml".to_string();
+        assert!(WebhookChannel::new(&config).is_err());
+    }
+}
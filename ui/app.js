@@ -143,14 +143,17 @@ async function loadSessionMessages(sessionId) {
             return;
         }
 
-        // Render each message
+        // Render each message, keeping each turn's last reply for its trace
+        const turnReplies = [];
         for (const msg of data.messages) {
             if (msg.role === 'system') continue; // Skip system messages
 
             if (msg.role === 'user') {
                 appendMessage('user', msg.content || '');
+                turnReplies.push(null);
             } else if (msg.role === 'assistant') {
                 const div = appendMessage('assistant', msg.content || '');
+                if (turnReplies.length > 0) turnReplies[turnReplies.length - 1] = div;
 
                 // Render tool calls if present
                 if (msg.tool_calls && msg.tool_calls.length > 0) {
@@ -170,6 +173,7 @@ async function loadSessionMessages(sessionId) {
             }
         }
 
+        await attachTraces(sessionId, turnReplies);
         scrollToBottom();
     } catch (err) {
         console.error('Failed to load session messages:', err);
@@ -237,6 +241,9 @@ async function sendMessage() {
                 }
             }
         }
+
+        const traces = await fetchTraces(sessionId);
+        appendTrace(assistantDiv, traces[traces.length - 1]);
     } catch (err) {
         assistantDiv.classList.remove('loading');
         assistantDiv.classList.add('error');
@@ -292,6 +299,43 @@ function handleEvent(event, assistantDiv) {
     }
 }
 
+// Retrieval traces ("why did it say that?"), one per turn, oldest first
+async function fetchTraces(id) {
+    if (!id) return [];
+    try {
+        const res = await fetch(`${API}/sessions/${id}/trace`);
+        if (!res.ok) return [];
+        const data = await res.json();
+        return data.turns || [];
+    } catch (err) {
+        console.error('Failed to load retrieval traces:', err);
+        return [];
+    }
+}
+
+// Traces line up with the latest turns: earlier ones may predate tracing
+// or have been compacted away
+async function attachTraces(id, turnReplies) {
+    const traces = await fetchTraces(id);
+    const offset = turnReplies.length - traces.length;
+    traces.forEach((trace, i) => appendTrace(turnReplies[offset + i], trace));
+}
+
+function appendTrace(div, trace) {
+    if (!div || !trace) return;
+    const searches = trace.searches.length;
+    const details = document.createElement('details');
+    details.className = 'retrieval-trace';
+    const summary = document.createElement('summary');
+    summary.textContent = searches === 0
+        ? 'Retrieval: no memory searched'
+        : `Retrieval: ${searches} memory search${searches === 1 ? '' : 'es'}`;
+    const text = document.createElement('pre');
+    text.textContent = trace.text;
+    details.append(summary, text);
+    div.appendChild(details);
+}
+
 function updateSessionSelect(newSessionId) {
     const select = document.getElementById('session-select');
 